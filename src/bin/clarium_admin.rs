@@ -0,0 +1,96 @@
+//!
+//! clarium admin tool
+//! ------------------
+//! Offline maintenance against a database folder (the server should not be
+//! writing to it at the same time).
+//!
+//! USAGE:
+//!   clarium_admin verify <db/schema/table | --all> [--quarantine] [--db-folder PATH]
+//!
+//! `verify` checks every chunk against the checksum recorded at write time and
+//! decodes it, printing one line per chunk. Damaged chunks report their time
+//! range; with `--quarantine` they are moved to `<table>/_quarantine/`. The exit
+//! code is 2 when damage was found.
+
+use anyhow::Result;
+use std::path::Path;
+
+fn string_arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).cloned()
+}
+
+/// Positional arguments, skipping `--db-folder PATH` and boolean `--flags`.
+fn positional_args(args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "--db-folder" { i += 2; continue; }
+        if !args[i].starts_with("--") { out.push(args[i].clone()); }
+        i += 1;
+    }
+    out
+}
+
+/// Every table under `root` as `db/schema/<table dir>` (time tables keep their `.time` suffix).
+fn all_tables(root: &Path) -> Vec<String> {
+    let mut out = Vec::new();
+    let subdirs = |p: &Path| -> Vec<(String, std::path::PathBuf)> {
+        std::fs::read_dir(p).map(|rd| rd.flatten()
+            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+            .filter(|(n, _)| !n.starts_with('.') && !n.starts_with('_'))
+            .collect()).unwrap_or_default()
+    };
+    for (db, dbp) in subdirs(root) {
+        for (schema, sp) in subdirs(&dbp) {
+            for (table, tp) in subdirs(&sp) {
+                if tp.join("schema.json").exists() { out.push(format!("{}/{}/{}", db, schema, table)); }
+            }
+        }
+    }
+    out.sort();
+    out
+}
+
+fn run_verify(args: &[String], db_root: &str) -> Result<i32> {
+    let quarantine = args.iter().any(|a| a == "--quarantine");
+    let store = clarium::storage::Store::new(db_root)?;
+    let tables = if args.iter().any(|a| a == "--all") {
+        all_tables(Path::new(db_root))
+    } else {
+        match positional_args(args).into_iter().next() {
+            Some(t) => vec![t],
+            None => anyhow::bail!("usage: clarium_admin verify <db/schema/table | --all> [--quarantine] [--db-folder PATH]"),
+        }
+    };
+    let mut bad = 0usize;
+    for table in &tables {
+        for r in store.verify_table(table, quarantine)? {
+            let range = match (r.min_time, r.max_time) {
+                (Some(a), Some(b)) => format!(" time=[{}, {}]", a, b),
+                _ => String::new(),
+            };
+            println!("{} {} {}{}{}{}", table, r.file, r.status.as_str(), range,
+                r.detail.as_deref().map(|d| format!(" ({})", d)).unwrap_or_default(),
+                if r.quarantined { " -> quarantined" } else { "" });
+            if r.status.is_bad() { bad += 1; }
+        }
+    }
+    println!("verified {} table(s), {} damaged chunk(s)", tables.len(), bad);
+    Ok(if bad > 0 { 2 } else { 0 })
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let db_root = string_arg(&args, "--db-folder")
+        .or_else(|| std::env::var("CLARIUM_DB_FOLDER").ok())
+        .unwrap_or_else(|| "dbs".to_string());
+    let code = match args.get(1).map(|s| s.as_str()) {
+        Some("verify") => run_verify(&args[2..], &db_root)?,
+        _ => {
+            println!("clarium admin\n\nUSAGE:\n  clarium_admin verify <db/schema/table | --all> [--quarantine] [--db-folder PATH]\n");
+            0
+        }
+    };
+    std::process::exit(code);
+}
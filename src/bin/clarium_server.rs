@@ -2,18 +2,12 @@
 //! clarium server binary
 //! ----------------------
 //! Command-line entry point for starting the clarium HTTP server and optional
-//! pgwire endpoint. Supports configuration via CLI flags and environment variables.
+//! pgwire endpoint. Supports configuration via CLI flags, environment variables and
+//! clarium.toml (see `clarium::config`).
 
 use anyhow::Result;
 use std::env;
 
-fn parse_port_env(name: &str) -> Option<u16> {
-    match env::var(name) {
-        Ok(val) => val.parse::<u16>().ok(),
-        Err(_) => None,
-    }
-}
-
 fn parse_port_arg(args: &[String], flag: &str) -> Option<u16> {
     let mut i = 0;
     while i < args.len() {
@@ -26,20 +20,6 @@ fn parse_port_arg(args: &[String], flag: &str) -> Option<u16> {
     None
 }
 
-fn parse_bool_env(name: &str) -> Option<bool> {
-    match env::var(name) {
-        Ok(v) => {
-            let s = v.to_lowercase();
-            match s.as_str() {
-                "1" | "true" | "yes" | "on" => Some(true),
-                "0" | "false" | "no" | "off" => Some(false),
-                _ => None,
-            }
-        }
-        Err(_) => None,
-    }
-}
-
 fn parse_pgwire_arg(args: &[String]) -> Option<bool> {
     let mut i = 0;
     while i < args.len() {
@@ -71,6 +51,39 @@ fn has_flag(args: &[String], flag: &str) -> bool {
     args.iter().any(|a| a == flag)
 }
 
+/// Positional arguments after a subcommand, skipping `--flag value` pairs.
+fn positional_args(args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if args[i].starts_with("--") { i += 2; continue; }
+        out.push(args[i].clone());
+        i += 1;
+    }
+    out
+}
+
+fn string_arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).cloned()
+}
+
+/// `backup <db> <target>` and `restore <db> <source> [--as-of TS]` against the local db folder.
+async fn run_backup_restore(sub: &str, args: &[String], db_root: &str) -> Result<()> {
+    let pos = positional_args(args);
+    let (Some(db), Some(location)) = (pos.first(), pos.get(1)) else {
+        anyhow::bail!("usage: clarium_server {} <database> <path|s3://bucket/prefix> [--db-folder PATH]{}", sub, if sub == "restore" { " [--as-of TS]" } else { "" });
+    };
+    let store = clarium::storage::SharedStore::new(db_root)?;
+    let res = if sub == "backup" {
+        clarium::server::exec::exec_backup::handle_backup(&store, db, location).await?
+    } else {
+        let as_of = string_arg(args, "--as-of").map(|t| clarium::storage::backup::parse_as_of(&t)).transpose()?;
+        clarium::server::exec::exec_backup::handle_restore(&store, db, location, as_of).await?
+    };
+    println!("{}", serde_json::to_string_pretty(&res)?);
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     println!(r"   ________           _               
@@ -79,30 +92,17 @@ async fn main() -> Result<()> {
 / /___/ / /_/ / /  / / /_/ / / / / / /
 \____/_/\__,_/_/  /_/\__,_/_/ /_/ /_/  ");
 
-    // Initialize tracing subscriber with env filter if provided
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .try_init();
+    // Initialize tracing subscriber with env filter if provided (plus OTLP export under `otel`)
+    clarium::telemetry::init_tracing("error");
 
     let args: Vec<String> = env::args().collect();
 
     if has_flag(&args, "--help") || has_flag(&args, "-h") {
-        println!("clarium Server\n\nUSAGE:\n  clarium_server [--http-port N] [--pg-port N] [--db-folder PATH] [--pgwire|--no-pgwire]\n\nOPTIONS:\n  --http-port N       HTTP API port (env: clarium_HTTP_PORT, default 7878)\n  --pg-port N         pgwire port (env: clarium_PG_PORT, default 5433)\n  --db-folder PATH    Database root folder (env: clarium_DB_FOLDER, default dbs/clarium)\n  --pgwire [bool]     Enable pgwire (env: clarium_PGWIRE). Presence enables; or pass true/false.\n  --no-pgwire        Disable pgwire explicitly.\n");
+        println!("clarium Server\n\nUSAGE:\n  clarium_server [--http-port N] [--pg-port N] [--db-folder PATH] [--pgwire|--no-pgwire]\n\nOPTIONS:\n  --http-port N       HTTP API port (env: clarium_HTTP_PORT, default 7878)\n  --pg-port N         pgwire port (env: clarium_PG_PORT, default 5433)\n  --db-folder PATH    Database root folder (env: clarium_DB_FOLDER, default dbs/clarium)\n  --pgwire [bool]     Enable pgwire (env: clarium_PGWIRE). Presence enables; or pass true/false.\n  --no-pgwire        Disable pgwire explicitly.\n  --config PATH       Config file (env: CLARIUM_CONFIG, default ./clarium.toml if present)\n\nSUBCOMMANDS:\n  backup <db> <path|s3://bucket/prefix>                 Snapshot a database (same as BACKUP DATABASE)\n  restore <db> <path|s3://bucket/prefix> [--as-of TS]   Restore into a new database (same as RESTORE DATABASE)\n");
         return Ok(());
     }
 
-    // Defaults
-    let default_http: u16 = 7878;
-    let default_pg: u16 = 5433;
-    let default_root: &str = "dbs";
-
-    // Environment variables
-    let env_http = parse_port_env("clarium_HTTP_PORT");
-    let env_pg = parse_port_env("clarium_PG_PORT");
-    let env_root = std::env::var("clarium_DB_FOLDER").ok();
-    let env_pgwire = parse_bool_env("clarium_PGWIRE");
-
-    // CLI arguments override environment
+    // CLI arguments override clarium.toml and environment (see clarium::config)
     let arg_http = parse_port_arg(&args, "--http-port");
     let arg_pg = parse_port_arg(&args, "--pg-port");
     let arg_root = {
@@ -119,13 +119,24 @@ async fn main() -> Result<()> {
     };
     let arg_pgwire = parse_pgwire_arg(&args);
 
-    let http_port = arg_http.or(env_http).unwrap_or(default_http);
-    let pg_port = arg_pg.or(env_pg).unwrap_or(default_pg);
-    let db_root = arg_root.or(env_root).unwrap_or_else(|| default_root.to_string());
+    let mut overrides: Vec<(String, String)> = Vec::new();
+    if let Some(p) = arg_http { overrides.push(("server.http_port".into(), p.to_string())); }
+    if let Some(p) = arg_pg { overrides.push(("server.pg_port".into(), p.to_string())); }
+    if let Some(r) = arg_root { overrides.push(("server.db_root".into(), r)); }
+    if let Some(b) = arg_pgwire { overrides.push(("server.pgwire".into(), b.to_string())); }
+    let config_path = string_arg(&args, "--config").map(std::path::PathBuf::from);
+    let cfg = clarium::config::init(config_path.as_deref(), overrides)?;
+
+    let http_port = cfg.server.http_port;
+    let pg_port = cfg.server.pg_port;
+    let db_root = cfg.server.db_root.clone();
+
+    if let Some(sub) = args.get(1).filter(|s| *s == "backup" || *s == "restore") {
+        return run_backup_restore(sub, &args[2..], &db_root).await;
+    }
 
     // Default enable depends on compile-time feature
-    let default_enable = cfg!(feature = "pgwire");
-    let enable_pgwire = arg_pgwire.or(env_pgwire).unwrap_or(default_enable);
+    let enable_pgwire = cfg.server.pgwire;
 
     #[cfg(feature = "pgwire")]
     {
@@ -143,7 +154,9 @@ async fn main() -> Result<()> {
             );
             tracing::info!("pgwire disabled; Using port: http={}, db_root={}", http_port, db_root);
         }
-        return clarium::server::run_with_ports(http_port, pg_opt, &db_root).await;
+        let res = clarium::server::run_with_ports(http_port, pg_opt, &db_root).await;
+        clarium::telemetry::shutdown_tracing();
+        return res;
     }
 
     #[cfg(not(feature = "pgwire"))]
@@ -155,6 +168,8 @@ async fn main() -> Result<()> {
         println!("clarium starting using ports: http={}, db_root={}", http_port, db_root);
         tracing::info!("Using port: http={}, db_root={}", http_port, db_root);
         // Pass None for pgwire (disabled)
-        return clarium::server::run_with_ports(http_port, None, &db_root).await;
+        let res = clarium::server::run_with_ports(http_port, None, &db_root).await;
+        clarium::telemetry::shutdown_tracing();
+        return res;
     }
 }
@@ -0,0 +1,552 @@
+//!
+//! clarium configuration
+//! ---------------------
+//! Layered server configuration: built-in defaults, then `clarium.toml`, then
+//! environment variables, then command-line flags (later layers win).
+//!
+//! The file is taken from `--config PATH`, else `CLARIUM_CONFIG`, else `./clarium.toml`
+//! when present. It uses one table per section:
+//!
+//! ```toml
+//! [server]
+//! http_port = 7878
+//! pg_port = 5433
+//! pgwire = true
+//! db_root = "dbs"
+//! pgwire_tls_cert = "certs/server.crt"
+//! pgwire_tls_key = "certs/server.key"
+//!
+//! [limits]
+//! session_idle_secs = 1800
+//!
+//! [http]
+//! cors_origins = "https://app.example.com"
+//! body_limits = "/query=8"
+//!
+//! [filestore]
+//! git_branch = "main"
+//!
+//! [oidc]
+//! issuer = "https://login.example.com/realms/corp"
+//! audience = "clarium"
+//!
+//! [password]
+//! min_length = 12
+//! lockout_threshold = 5
+//! ```
+//!
+//! `ADMIN RELOAD CONFIG` (or SIGHUP on unix) re-reads the file and environment.
+//! Reloadable options take effect immediately; changes to the others (ports, db root,
+//! pgwire, background task intervals, OIDC) are reported as pending a restart and keep their
+//! running value. `pg_catalog.clarium_config` shows every effective option and its source.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ident::{DEFAULT_DB, DEFAULT_SCHEMA};
+use crate::server::exec::filestore::GlobalFilestoreConfig;
+
+pub const DEFAULT_CONFIG_FILE: &str = "clarium.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerSettings {
+    pub http_port: u16,
+    pub pg_port: u16,
+    pub pgwire: bool,
+    pub db_root: String,
+    pub default_db: String,
+    pub default_schema: String,
+    /// PEM certificate chain for pgwire TLS; TLS is offered when this and the key are set
+    pub pgwire_tls_cert: String,
+    /// PEM private key for pgwire TLS
+    pub pgwire_tls_key: String,
+    /// Reject pgwire clients that do not negotiate TLS
+    pub pgwire_tls_require: bool,
+    /// pgwire auth method per client address: comma-separated `<address> <method>` rules, first
+    /// match wins (e.g. `127.0.0.1 md5, all scram-sha-256`); no match uses `password`
+    pub pgwire_auth: String,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            http_port: 7878,
+            pg_port: 5433,
+            pgwire: cfg!(feature = "pgwire"),
+            db_root: "dbs".to_string(),
+            default_db: DEFAULT_DB.to_string(),
+            default_schema: DEFAULT_SCHEMA.to_string(),
+            pgwire_tls_cert: String::new(),
+            pgwire_tls_key: String::new(),
+            pgwire_tls_require: false,
+            pgwire_auth: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LimitSettings {
+    /// HTTP session idle timeout
+    pub session_idle_secs: u64,
+    /// HTTP session absolute lifetime
+    pub session_abs_secs: u64,
+    /// Sessions one user may hold at once; a new login ends the oldest; 0 = unlimited
+    pub max_sessions_per_user: u64,
+    /// GraphStore background GC interval; 0 disables the ticker
+    pub graph_gc_interval_sec: i64,
+    /// How often the background sweeper evicts expired KV keys; 0 disables it (keys still
+    /// expire when next read)
+    pub kv_sweep_interval_ms: u64,
+    /// Default per-query working memory before sorts/aggregations spill to disk; 0 = unlimited
+    pub work_mem_mb: u64,
+    /// Memory all running queries may hold together; 0 = unlimited
+    pub memory_budget_mb: u64,
+    /// How long a query waits for the memory budget before it is rejected; 0 rejects at once
+    pub memory_queue_ms: u64,
+    /// Analytical queries (SELECT, SLICE, CALCULATE, ...) running at once; 0 = unlimited
+    pub max_concurrent_queries: u64,
+    /// Analytical queries one user may run at once; 0 = unlimited
+    pub max_queries_per_user: u64,
+    /// How long a query waits for a free slot before it fails; 0 fails at once
+    pub queue_timeout_ms: u64,
+    /// Statements one user may start per minute; more are throttled, then rejected; 0 = unlimited
+    pub queries_per_minute: u64,
+    /// Rows one user may read from storage per day (UTC); 0 = unlimited
+    pub rows_scanned_per_day: u64,
+    /// Bytes one user may write with INSERT, COPY and `/write` per day (UTC); 0 = unlimited
+    pub ingest_bytes_per_day: u64,
+    /// Total size of cached query results (`SET enable_result_cache = on`); 0 disables the cache
+    pub result_cache_mb: u64,
+    /// Lifetime of a cached result
+    pub result_cache_ttl_secs: u64,
+    /// Largest request body `/v1/ingest` accepts, after decompression
+    pub ingest_max_body_mb: u64,
+    /// Rows `/v1/ingest` writes per batch
+    pub ingest_batch_rows: u64,
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self {
+            session_idle_secs: 30 * 60,
+            session_abs_secs: 24 * 60 * 60,
+            max_sessions_per_user: 0,
+            graph_gc_interval_sec: 60,
+            kv_sweep_interval_ms: 5_000,
+            work_mem_mb: 0,
+            memory_budget_mb: 0,
+            memory_queue_ms: 10_000,
+            max_concurrent_queries: 0,
+            max_queries_per_user: 0,
+            queue_timeout_ms: 30_000,
+            queries_per_minute: 0,
+            rows_scanned_per_day: 0,
+            ingest_bytes_per_day: 0,
+            result_cache_mb: 64,
+            result_cache_ttl_secs: 300,
+            ingest_max_body_mb: 256,
+            ingest_batch_rows: 50_000,
+        }
+    }
+}
+
+/// Browser-facing HTTP policy: CORS, request body limits and response compression.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HttpSettings {
+    /// Comma-separated origins allowed to call the API from a browser, or `*` for any; empty
+    /// disables CORS
+    pub cors_origins: String,
+    /// Let listed origins send credentials (cookies, Authorization); ignored with `*`
+    pub cors_allow_credentials: bool,
+    /// How long browsers may cache a preflight response
+    pub cors_max_age_secs: u64,
+    /// Largest request body of routes without their own limit (bulk routes use
+    /// `limits.ingest_max_body_mb`)
+    pub max_body_mb: u64,
+    /// Per-route limits as comma-separated `<route path>=<MB>` (e.g. `/query=8, /write=64`)
+    pub body_limits: String,
+    /// gzip/brotli responses for clients that send `Accept-Encoding`
+    pub compression: bool,
+    /// Smaller responses are sent uncompressed
+    pub compression_min_bytes: u64,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            cors_origins: String::new(),
+            cors_allow_credentials: false,
+            cors_max_age_secs: 600,
+            max_body_mb: 2,
+            body_limits: String::new(),
+            compression: true,
+            compression_min_bytes: 1024,
+        }
+    }
+}
+
+/// OpenID Connect bearer tokens for the HTTP API; disabled while `issuer` is empty.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OidcSettings {
+    /// Expected `iss` claim, e.g. `https://login.example.com/realms/corp`
+    pub issuer: String,
+    /// Expected `aud` claim (the client id); empty skips the audience check
+    pub audience: String,
+    /// JWKS endpoint; empty discovers it from `<issuer>/.well-known/openid-configuration`
+    pub jwks_uri: String,
+    /// How long fetched signing keys are used before they are fetched again
+    pub jwks_refresh_secs: u64,
+    /// Clock skew allowed when checking `exp` and `nbf`
+    pub leeway_secs: u64,
+    /// Claim holding the user name. Defaults to `sub`, which the issuer keeps unique; a
+    /// user-editable claim such as `preferred_username` lets one account claim another's name
+    pub username_claim: String,
+    /// Claim holding role names; a dotted path reaches nested claims (`realm_access.roles`)
+    pub roles_claim: String,
+    /// Comma-separated role names that make the token's user an admin
+    pub admin_roles: String,
+    /// Claims copied to the principal's org_id / tenant_id attributes
+    pub org_claim: String,
+    pub tenant_claim: String,
+    /// Comma-separated `<token user>=<local user>` pairs. Only a listed token user gets the
+    /// Clarium roles granted to its local user (`GRANT role TO user`); a matching name alone
+    /// grants nothing
+    pub local_users: String,
+}
+
+impl Default for OidcSettings {
+    fn default() -> Self {
+        Self {
+            issuer: String::new(),
+            audience: String::new(),
+            jwks_uri: String::new(),
+            jwks_refresh_secs: 3600,
+            leeway_secs: 60,
+            username_claim: "sub".to_string(),
+            roles_claim: "roles".to_string(),
+            admin_roles: "admin".to_string(),
+            org_claim: "org_id".to_string(),
+            tenant_claim: "tid".to_string(),
+            local_users: String::new(),
+        }
+    }
+}
+
+/// Password rules, hashing cost and login lockout for local users (see `identity::password`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PasswordSettings {
+    /// Minimum length of a new password; 0 = no minimum
+    pub min_length: u64,
+    /// Character classes a new password must contain
+    pub require_upper: bool,
+    pub require_lower: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Hash for new passwords: `argon2` (argon2id) or `bcrypt`; stored hashes of either kind keep working
+    pub hash_algorithm: String,
+    /// Argon2id memory (KiB), passes and lanes
+    pub argon2_memory_kib: u64,
+    pub argon2_iterations: u64,
+    pub argon2_parallelism: u64,
+    /// bcrypt cost factor (4-31)
+    pub bcrypt_cost: u64,
+    /// Failed logins in a row that lock the account; 0 disables lockout
+    pub lockout_threshold: u64,
+    /// First lockout period; each further failure doubles it, up to `lockout_max_secs`
+    pub lockout_base_secs: u64,
+    pub lockout_max_secs: u64,
+    /// Days after a password change before the user must set a new one; 0 = never
+    pub max_age_days: u64,
+}
+
+impl Default for PasswordSettings {
+    fn default() -> Self {
+        Self {
+            min_length: 0,
+            require_upper: false,
+            require_lower: false,
+            require_digit: false,
+            require_symbol: false,
+            hash_algorithm: "argon2".to_string(),
+            argon2_memory_kib: 19 * 1024,
+            argon2_iterations: 2,
+            argon2_parallelism: 1,
+            bcrypt_cost: 12,
+            lockout_threshold: 5,
+            lockout_base_secs: 30,
+            lockout_max_secs: 3600,
+            max_age_days: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ClariumConfig {
+    pub server: ServerSettings,
+    pub limits: LimitSettings,
+    /// CORS, body limits and compression of the HTTP API
+    pub http: HttpSettings,
+    /// Single sign-on for the HTTP API
+    pub oidc: OidcSettings,
+    /// Local password rules and lockout
+    pub password: PasswordSettings,
+    /// Global FILESTORE defaults (git remote/branch/mode, ACL cache TTLs, ...)
+    pub filestore: GlobalFilestoreConfig,
+}
+
+/// Options read only at startup; reloads report changes to them as pending a restart.
+const RESTART_KEYS: &[&str] = &[
+    "server.http_port", "server.pg_port", "server.pgwire", "server.db_root",
+    "server.pgwire_tls_cert", "server.pgwire_tls_key",
+    "limits.graph_gc_interval_sec", "limits.ingest_max_body_mb",
+    "http.cors_origins", "http.cors_allow_credentials", "http.cors_max_age_secs", "http.max_body_mb", "http.body_limits",
+    "http.compression", "http.compression_min_bytes",
+    "oidc.issuer", "oidc.audience", "oidc.jwks_uri", "oidc.jwks_refresh_secs", "oidc.leeway_secs",
+    "oidc.username_claim", "oidc.roles_claim", "oidc.admin_roles", "oidc.org_claim", "oidc.tenant_claim",
+    "oidc.local_users",
+];
+
+/// Environment variables per option, in priority order.
+const ENV_KEYS: &[(&str, &[&str])] = &[
+    ("server.http_port", &["CLARIUM_HTTP_PORT", "clarium_HTTP_PORT"]),
+    ("server.pg_port", &["CLARIUM_PG_PORT", "clarium_PG_PORT"]),
+    ("server.pgwire", &["CLARIUM_PGWIRE", "clarium_PGWIRE"]),
+    ("server.db_root", &["CLARIUM_DB_FOLDER", "clarium_DB_FOLDER"]),
+    ("server.default_db", &["CLARIUM_DEFAULT_DB"]),
+    ("server.default_schema", &["CLARIUM_DEFAULT_SCHEMA"]),
+    ("server.pgwire_tls_cert", &["CLARIUM_PGWIRE_TLS_CERT"]),
+    ("server.pgwire_tls_key", &["CLARIUM_PGWIRE_TLS_KEY"]),
+    ("server.pgwire_tls_require", &["CLARIUM_PGWIRE_TLS_REQUIRE"]),
+    ("server.pgwire_auth", &["CLARIUM_PGWIRE_AUTH"]),
+    ("limits.session_idle_secs", &["CLARIUM_SESSION_IDLE_SECS"]),
+    ("limits.session_abs_secs", &["CLARIUM_SESSION_ABS_SECS"]),
+    ("limits.max_sessions_per_user", &["CLARIUM_MAX_SESSIONS_PER_USER"]),
+    ("limits.graph_gc_interval_sec", &["CLARIUM_GRAPH_GC_INTERVAL_SEC"]),
+    ("limits.kv_sweep_interval_ms", &["CLARIUM_KV_SWEEP_INTERVAL_MS"]),
+    ("limits.work_mem_mb", &["CLARIUM_WORK_MEM_MB"]),
+    ("limits.memory_budget_mb", &["CLARIUM_MEMORY_BUDGET_MB"]),
+    ("limits.memory_queue_ms", &["CLARIUM_MEMORY_QUEUE_MS"]),
+    ("limits.max_concurrent_queries", &["CLARIUM_MAX_CONCURRENT_QUERIES"]),
+    ("limits.max_queries_per_user", &["CLARIUM_MAX_QUERIES_PER_USER"]),
+    ("limits.queue_timeout_ms", &["CLARIUM_QUEUE_TIMEOUT_MS"]),
+    ("limits.queries_per_minute", &["CLARIUM_QUERIES_PER_MINUTE"]),
+    ("limits.rows_scanned_per_day", &["CLARIUM_ROWS_SCANNED_PER_DAY"]),
+    ("limits.ingest_bytes_per_day", &["CLARIUM_INGEST_BYTES_PER_DAY"]),
+    ("limits.result_cache_mb", &["CLARIUM_RESULT_CACHE_MB"]),
+    ("limits.result_cache_ttl_secs", &["CLARIUM_RESULT_CACHE_TTL_SECS"]),
+    ("limits.ingest_max_body_mb", &["CLARIUM_INGEST_MAX_BODY_MB"]),
+    ("limits.ingest_batch_rows", &["CLARIUM_INGEST_BATCH_ROWS"]),
+    ("http.cors_origins", &["CLARIUM_CORS_ORIGINS"]),
+    ("http.max_body_mb", &["CLARIUM_HTTP_MAX_BODY_MB"]),
+    ("http.compression", &["CLARIUM_HTTP_COMPRESSION"]),
+    ("oidc.issuer", &["CLARIUM_OIDC_ISSUER"]),
+    ("oidc.audience", &["CLARIUM_OIDC_AUDIENCE"]),
+    ("oidc.jwks_uri", &["CLARIUM_OIDC_JWKS_URI"]),
+    ("password.min_length", &["CLARIUM_PASSWORD_MIN_LENGTH"]),
+    ("password.hash_algorithm", &["CLARIUM_PASSWORD_HASH"]),
+    ("password.lockout_threshold", &["CLARIUM_LOCKOUT_THRESHOLD"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource { Default, File, Env, Cli }
+
+impl ConfigSource {
+    pub fn as_str(&self) -> &'static str {
+        match self { ConfigSource::Default => "default", ConfigSource::File => "file", ConfigSource::Env => "env", ConfigSource::Cli => "cli" }
+    }
+}
+
+/// One effective option, as shown by `pg_catalog.clarium_config`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: Value,
+    pub source: ConfigSource,
+    pub reloadable: bool,
+    /// A reload saw a different value that only applies after a restart
+    pub pending_restart: bool,
+}
+
+impl ConfigEntry {
+    /// Value rendered as text (strings unquoted, unset options empty).
+    pub fn setting(&self) -> String {
+        match &self.value { Value::String(s) => s.clone(), Value::Null => String::new(), v => v.to_string() }
+    }
+}
+
+/// Outcome of `ADMIN RELOAD CONFIG`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReloadReport {
+    pub file: Option<String>,
+    pub applied: Vec<String>,
+    pub pending_restart: Vec<String>,
+}
+
+pub fn is_reloadable(key: &str) -> bool { !RESTART_KEYS.contains(&key) }
+
+fn flatten(prefix: &str, v: &Value, out: &mut BTreeMap<String, Value>) {
+    match v {
+        Value::Object(map) => {
+            for (k, child) in map {
+                let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                flatten(&key, child, out);
+            }
+        }
+        other => { out.insert(prefix.to_string(), other.clone()); }
+    }
+}
+
+fn unflatten(entries: &BTreeMap<String, ConfigEntry>) -> Value {
+    let mut root = serde_json::Map::new();
+    for (key, e) in entries {
+        let (section, name) = key.split_once('.').unwrap_or(("", key.as_str()));
+        let obj = root.entry(section.to_string()).or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if let Value::Object(m) = obj { m.insert(name.to_string(), e.value.clone()); }
+    }
+    Value::Object(root)
+}
+
+/// Convert a textual value (env var or CLI flag) to the type of the option's default.
+fn typed_value(key: &str, default: &Value, raw: &str) -> Result<Value> {
+    let raw = raw.trim();
+    Ok(match default {
+        Value::Bool(_) => match raw.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Value::Bool(true),
+            "0" | "false" | "no" | "off" => Value::Bool(false),
+            _ => bail!("config option '{}' expects a boolean, got '{}'", key, raw),
+        },
+        Value::Number(_) => {
+            let n: i64 = raw.parse().map_err(|_| anyhow!("config option '{}' expects a number, got '{}'", key, raw))?;
+            Value::from(n)
+        }
+        _ => Value::String(raw.to_string()),
+    })
+}
+
+fn env_value(names: &[&str]) -> Option<String> {
+    names.iter().find_map(|n| std::env::var(n).ok()).filter(|v| !v.trim().is_empty())
+}
+
+/// Config file to use: the explicit path, else `CLARIUM_CONFIG`, else `./clarium.toml` if present.
+pub fn resolve_file(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(p) = explicit { return Some(p.to_path_buf()); }
+    if let Some(p) = env_value(&["CLARIUM_CONFIG"]) { return Some(PathBuf::from(p)); }
+    let local = PathBuf::from(DEFAULT_CONFIG_FILE);
+    local.is_file().then_some(local)
+}
+
+/// Merge defaults, `file`, the environment and `cli` overrides (`(key, value)` pairs).
+pub fn build(file: Option<&Path>, cli: &[(String, String)]) -> Result<(ClariumConfig, Vec<ConfigEntry>)> {
+    let mut defaults = BTreeMap::new();
+    flatten("", &serde_json::to_value(ClariumConfig::default())?, &mut defaults);
+    let mut entries: BTreeMap<String, ConfigEntry> = defaults.iter()
+        .map(|(k, v)| (k.clone(), ConfigEntry { key: k.clone(), value: v.clone(), source: ConfigSource::Default, reloadable: is_reloadable(k), pending_restart: false }))
+        .collect();
+
+    if let Some(path) = file {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading config file {}", path.display()))?;
+        let parsed: toml::Value = toml::from_str(&text).with_context(|| format!("parsing config file {}", path.display()))?;
+        let mut flat = BTreeMap::new();
+        flatten("", &serde_json::to_value(parsed)?, &mut flat);
+        for (k, v) in flat {
+            let Some(e) = entries.get_mut(&k) else { bail!("unknown config option '{}' in {}", k, path.display()) };
+            e.value = v;
+            e.source = ConfigSource::File;
+        }
+    }
+    for (key, names) in ENV_KEYS {
+        if let Some(raw) = env_value(names) {
+            let e = entries.get_mut(*key).expect("ENV_KEYS names a known option");
+            e.value = typed_value(key, &defaults[*key], &raw)?;
+            e.source = ConfigSource::Env;
+        }
+    }
+    for (k, raw) in cli {
+        let Some(e) = entries.get_mut(k.as_str()) else { bail!("unknown config option '{}'", k) };
+        e.value = typed_value(k, &defaults[k.as_str()], raw)?;
+        e.source = ConfigSource::Cli;
+    }
+
+    let cfg: ClariumConfig = serde_json::from_value(unflatten(&entries)).context("invalid configuration")?;
+    Ok((cfg, entries.into_values().collect()))
+}
+
+struct State {
+    file: Option<PathBuf>,
+    cli: Vec<(String, String)>,
+    config: Arc<ClariumConfig>,
+    entries: Vec<ConfigEntry>,
+}
+
+static STATE: Lazy<RwLock<Option<State>>> = Lazy::new(|| RwLock::new(None));
+
+/// Load the configuration and make it current. `file` is the `--config` path, if given.
+pub fn init(file: Option<&Path>, cli: Vec<(String, String)>) -> Result<Arc<ClariumConfig>> {
+    let file = resolve_file(file);
+    let (cfg, entries) = build(file.as_deref(), &cli)?;
+    let cfg = Arc::new(cfg);
+    crate::tprintln!("[config] loaded file={:?} cli_overrides={}", file, cli.len());
+    *STATE.write() = Some(State { file, cli, config: cfg.clone(), entries });
+    Ok(cfg)
+}
+
+fn ensure_init() {
+    if STATE.read().is_some() { return; }
+    // Not initialised by a binary (tests, embedded use): defaults + file + env, falling back to defaults
+    if let Err(e) = init(None, Vec::new()) {
+        tracing::warn!(target: "clarium::config", "ignoring configuration: {}", e);
+        let (cfg, entries) = build(None, &[]).unwrap_or_else(|_| (ClariumConfig::default(), Vec::new()));
+        let mut st = STATE.write();
+        if st.is_none() { *st = Some(State { file: None, cli: Vec::new(), config: Arc::new(cfg), entries }); }
+    }
+}
+
+/// Current effective configuration.
+pub fn current() -> Arc<ClariumConfig> {
+    ensure_init();
+    STATE.read().as_ref().map(|s| s.config.clone()).unwrap_or_default()
+}
+
+/// Current options with their sources, ordered by key.
+pub fn entries() -> Vec<ConfigEntry> {
+    ensure_init();
+    STATE.read().as_ref().map(|s| s.entries.clone()).unwrap_or_default()
+}
+
+/// Re-read the config file and environment (CLI overrides are kept). Reloadable options
+/// are applied; other changed options keep their running value and are marked pending.
+pub fn reload() -> Result<ReloadReport> {
+    ensure_init();
+    let mut guard = STATE.write();
+    let st = guard.as_mut().ok_or_else(|| anyhow!("configuration not initialised"))?;
+    let (_, fresh) = build(st.file.as_deref(), &st.cli)?;
+    let old: BTreeMap<String, ConfigEntry> = st.entries.iter().map(|e| (e.key.clone(), e.clone())).collect();
+    let mut report = ReloadReport { file: st.file.as_ref().map(|p| p.display().to_string()), ..Default::default() };
+    let mut merged: BTreeMap<String, ConfigEntry> = BTreeMap::new();
+    for mut e in fresh {
+        if let Some(prev) = old.get(&e.key) {
+            if prev.value != e.value {
+                if e.reloadable {
+                    report.applied.push(e.key.clone());
+                } else {
+                    report.pending_restart.push(e.key.clone());
+                    e = ConfigEntry { pending_restart: true, ..prev.clone() };
+                }
+            }
+        }
+        merged.insert(e.key.clone(), e);
+    }
+    let cfg: ClariumConfig = serde_json::from_value(unflatten(&merged)).context("invalid configuration")?;
+    st.config = Arc::new(cfg);
+    st.entries = merged.into_values().collect();
+    tracing::info!(target: "clarium::config", "configuration reloaded: applied={:?} pending_restart={:?}", report.applied, report.pending_restart);
+    Ok(report)
+}
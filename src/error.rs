@@ -1,6 +1,12 @@
 //! Unified application error model and mapping helpers.
 //! This module provides a common error enum used across frontends (HTTP, WebSocket, pgwire)
 //! and exec modules, along with helper mappers to various protocols.
+//!
+//! Every error maps to a PostgreSQL SQLSTATE so clients can tell a syntax error (42601) from
+//! a permission error (42501) or a missing table (42P01). When the `code` of an error is a
+//! PostgreSQL condition name (`undefined_column`) it picks the SQLSTATE; otherwise the
+//! variant decides the class. Errors that are still plain anyhow strings are classified
+//! from their message by `AppError::classify`.
 
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
@@ -12,6 +18,14 @@ pub enum AppError {
     NotFound { code: String, message: String },
     Conflict { code: String, message: String },
     Auth { code: String, message: String },
+    Permission { code: String, message: String },
+    Syntax {
+        code: String,
+        message: String,
+        /// 1-based character offset into the statement, as in PostgreSQL's error position
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        position: Option<usize>,
+    },
     Csrf { code: String, message: String },
     Ddl { code: String, message: String },
     Exec { code: String, message: String },
@@ -26,6 +40,8 @@ impl AppError {
             | AppError::NotFound { code, .. }
             | AppError::Conflict { code, .. }
             | AppError::Auth { code, .. }
+            | AppError::Permission { code, .. }
+            | AppError::Syntax { code, .. }
             | AppError::Csrf { code, .. }
             | AppError::Ddl { code, .. }
             | AppError::Exec { code, .. }
@@ -40,6 +56,8 @@ impl AppError {
             | AppError::NotFound { message, .. }
             | AppError::Conflict { message, .. }
             | AppError::Auth { message, .. }
+            | AppError::Permission { message, .. }
+            | AppError::Syntax { message, .. }
             | AppError::Csrf { message, .. }
             | AppError::Ddl { message, .. }
             | AppError::Exec { message, .. }
@@ -52,6 +70,8 @@ impl AppError {
     pub fn not_found<S: Into<String>>(code: S, msg: S) -> Self { AppError::NotFound { code: code.into(), message: msg.into() } }
     pub fn conflict<S: Into<String>>(code: S, msg: S) -> Self { AppError::Conflict { code: code.into(), message: msg.into() } }
     pub fn auth<S: Into<String>>(code: S, msg: S) -> Self { AppError::Auth { code: code.into(), message: msg.into() } }
+    pub fn permission<S: Into<String>>(code: S, msg: S) -> Self { AppError::Permission { code: code.into(), message: msg.into() } }
+    pub fn syntax<S: Into<String>>(code: S, msg: S, position: Option<usize>) -> Self { AppError::Syntax { code: code.into(), message: msg.into(), position } }
     pub fn csrf<S: Into<String>>(code: S, msg: S) -> Self { AppError::Csrf { code: code.into(), message: msg.into() } }
     pub fn ddl<S: Into<String>>(code: S, msg: S) -> Self { AppError::Ddl { code: code.into(), message: msg.into() } }
     pub fn exec<S: Into<String>>(code: S, msg: S) -> Self { AppError::Exec { code: code.into(), message: msg.into() } }
@@ -65,6 +85,8 @@ impl AppError {
             AppError::NotFound { .. } => 404,
             AppError::Conflict { .. } => 409,
             AppError::Auth { .. } => 401,
+            AppError::Permission { .. } => 403,
+            AppError::Syntax { .. } => 400,
             AppError::Csrf { .. } => 403,
             AppError::Ddl { .. } => 400,
             AppError::Exec { .. } => 422,
@@ -73,19 +95,160 @@ impl AppError {
         }
     }
 
+    /// PostgreSQL SQLSTATE for this error: an explicit code when it names one, else the variant's class.
+    pub fn sqlstate(&self) -> &'static str {
+        if let Some(state) = sqlstate_for_code(self.code_str()) { return state; }
+        match self {
+            AppError::UserInput { .. } | AppError::Ddl { .. } => "22000", // data_exception
+            AppError::NotFound { .. } => "42P01",                       // undefined_table
+            AppError::Conflict { .. } => "23505",                       // unique_violation (best-effort)
+            AppError::Auth { .. } | AppError::Csrf { .. } => "28000",   // invalid_authorization_specification
+            AppError::Permission { .. } => "42501",                     // insufficient_privilege
+            AppError::Syntax { .. } => "42601",                         // syntax_error
+            AppError::Io { .. } => "08006",                             // connection_failure
+            AppError::Exec { .. } | AppError::Internal { .. } => "XX000", // internal_error
+        }
+    }
+
+    /// Error position within the statement (syntax errors only).
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            AppError::Syntax { position, .. } => *position,
+            _ => None,
+        }
+    }
+
     /// Pgwire mapping: return (sqlstate, severity, message)
     pub fn pgwire_fields(&self) -> (&'static str, &'static str, String) {
-        let msg = self.message().to_string();
-        match self {
-            AppError::UserInput { .. } | AppError::Ddl { .. } => ("22000", "ERROR", msg), // data exception
-            AppError::NotFound { .. } => ("42P01", "ERROR", msg),  // undefined_table
-            AppError::Conflict { .. } => ("23505", "ERROR", msg),  // unique_violation (best-effort)
-            AppError::Auth { .. } | AppError::Csrf { .. } => ("28000", "FATAL", msg), // invalid_authorization_specification
-            AppError::Exec { .. } => ("XX000", "ERROR", msg),      // internal_error
-            AppError::Io { .. } => ("08006", "FATAL", msg),        // connection_failure
-            AppError::Internal { .. } => ("XX000", "ERROR", msg),  // internal_error
+        let severity = match self {
+            AppError::Auth { .. } | AppError::Csrf { .. } | AppError::Io { .. } => "FATAL",
+            _ => "ERROR",
+        };
+        (self.sqlstate(), severity, self.message().to_string())
+    }
+
+    /// HTTP/WebSocket JSON error body.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "status": "error",
+            "code": self.code_str(),
+            "sqlstate": self.sqlstate(),
+            "message": self.message(),
+        });
+        if let Some(p) = self.position() { body["position"] = serde_json::json!(p); }
+        body
+    }
+
+    /// The AppError carried by `err`, or one classified from its message.
+    pub fn classify(err: &anyhow::Error) -> AppError {
+        match err.chain().find_map(|e| e.downcast_ref::<AppError>()) {
+            Some(app) => app.clone(),
+            None => AppError::from_message(&err.to_string()),
         }
     }
+
+    /// Classify a plain error message by the wording used across the engine.
+    pub fn from_message(msg: &str) -> AppError {
+        let low = msg.to_ascii_lowercase();
+        let has = |words: &[&str]| words.iter().any(|w| low.contains(w));
+        if has(&["permission denied", "unauthorized", "not authorized", "forbidden", "access denied"]) {
+            return AppError::permission("insufficient_privilege", msg);
+        }
+        if has(&["authentication failed", "invalid_credentials"]) { return AppError::auth("invalid_password", msg); }
+        if low.contains("syntax") { return AppError::syntax("syntax_error", msg, None); }
+        if has(&["not found", "does not exist", "unknown table"]) {
+            let code = if low.contains("cursor") { "invalid_cursor_name" }
+                else if low.contains("column") { "undefined_column" }
+                else if has(&["function", "udf", "script"]) { "undefined_function" }
+                else if low.contains("database") { "invalid_catalog_name" }
+                else if low.contains("schema") { "invalid_schema_name" }
+                else if has(&["table", "view", "relation"]) { "undefined_table" }
+                else { "undefined_object" };
+            return AppError::not_found(code, msg);
+        }
+        if low.contains("already exists") {
+            return AppError::conflict(if has(&["table", "view"]) { "duplicate_table" } else { "duplicate_object" }, msg);
+        }
+        if has(&["duplicate key", "unique constraint"]) { return AppError::conflict("unique_violation", msg); }
+        if low.contains("division by zero") { return AppError::user("division_by_zero", msg); }
+        let code = if has(&["not supported", "unsupported", "not implemented"]) { "feature_not_supported" }
+            else if has(&["read-only", "read only"]) { "read_only_sql_transaction" }
+            else if low.contains("canceling statement") { "query_canceled" }
+            else if low.contains("memory") && has(&["exceed", "budget"]) { "out_of_memory" }
+            else { "exec_error" };
+        AppError::exec(code, msg)
+    }
+
+    /// Wrap a parser failure as a syntax error, locating it in `sql` when the message quotes
+    /// the offending token or reports a truncated statement. AppErrors pass through unchanged.
+    pub fn from_parse_error(err: &anyhow::Error, sql: &str) -> AppError {
+        if let Some(app) = err.chain().find_map(|e| e.downcast_ref::<AppError>()) { return app.clone(); }
+        let msg = err.to_string();
+        let position = syntax_error_position(sql, &msg);
+        AppError::syntax("syntax_error", msg.as_str(), position)
+    }
+}
+
+/// SQLSTATE for a PostgreSQL condition name or one of the codes used across this crate.
+fn sqlstate_for_code(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "syntax_error" | "syntax" => "42601",
+        "insufficient_privilege" => "42501",
+        "undefined_table" | "table_not_found" | "not_found" => "42P01",
+        "undefined_column" => "42703",
+        "undefined_function" => "42883",
+        "undefined_object" => "42704",
+        "invalid_catalog_name" => "3D000",
+        "invalid_schema_name" => "3F000",
+        "invalid_cursor_name" => "34000",
+        "duplicate_table" | "name_conflict" => "42P07",
+        "duplicate_object" => "42710",
+        "duplicate_function" => "42723",
+        "invalid_grant_operation" => "0LP01",
+        "unique_violation" => "23505",
+        "not_null_violation" => "23502",
+        "division_by_zero" => "22012",
+        "invalid_text_representation" => "22P02",
+        "datatype_mismatch" => "42804",
+        "invalid_password" | "invalid_credentials" => "28P01",
+        "invalid_parameter_value" => "22023",
+        "undefined_parameter" => "42P02",
+        "feature_not_supported" => "0A000",
+        "triggered_action_exception" => "09000",
+        "raise_exception" => "P0001",
+        "assert_failure" => "P0004",
+        "read_only_sql_transaction" => "25006",
+        "serialization_failure" => "40001",
+        "query_canceled" => "57014",
+        "out_of_memory" => "53200",
+        "configuration_limit_exceeded" => "53400",
+        "statement_too_complex" => "54001",
+        "protocol_violation" => "08P01",
+        "admin_shutdown" => "57P01",
+        "idle_session_timeout" => "57P05",
+        _ => return None,
+    })
+}
+
+/// 1-based character position of a parse error in `sql`: the first token quoted in the message
+/// ('x' or "x"), or the end of the statement for unterminated input. Comments are skipped when
+/// searching; they keep their width so the offset still points into the original text.
+pub fn syntax_error_position(sql: &str, message: &str) -> Option<usize> {
+    let text = crate::server::query::strip_sql_comments(sql);
+    let quoted = ['\'', '"'].iter().find_map(|q| {
+        let start = message.find(*q)? + 1;
+        let len = message[start..].find(*q)?;
+        Some(&message[start..start + len])
+    });
+    if let Some(token) = quoted.filter(|t| !t.trim().is_empty()) {
+        let at = text.to_ascii_lowercase().find(&token.to_ascii_lowercase())?;
+        return Some(text[..at].chars().count() + 1);
+    }
+    let low = message.to_ascii_lowercase();
+    if low.contains("unterminated") || low.contains("end of input") {
+        return Some(text.trim_end().chars().count() + 1);
+    }
+    None
 }
 
 impl Display for AppError {
@@ -100,8 +263,7 @@ pub type AppResult<T> = Result<T, AppError>;
 
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
-        // Default mapping: treat as Exec unless downcasted elsewhere
-        AppError::Exec { code: "exec_error".into(), message: err.to_string() }
+        AppError::classify(&err)
     }
 }
 
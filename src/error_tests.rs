@@ -28,3 +28,53 @@ fn pgwire_fields_mapping() {
     assert_eq!(code, "XX000");
     assert_eq!(sev, "ERROR");
 }
+
+#[test]
+fn sqlstate_from_condition_names() {
+    assert_eq!(AppError::not_found("undefined_column", "no col").sqlstate(), "42703");
+    assert_eq!(AppError::conflict("name_conflict", "exists").sqlstate(), "42P07");
+    assert_eq!(AppError::user("division_by_zero", "x").sqlstate(), "22012");
+    assert_eq!(AppError::permission("insufficient_privilege", "no").sqlstate(), "42501");
+    assert_eq!(AppError::permission("insufficient_privilege", "no").http_status(), 403);
+    assert_eq!(AppError::syntax("syntax_error", "bad", Some(3)).sqlstate(), "42601");
+    // Unknown codes fall back to the variant's class
+    assert_eq!(AppError::ddl("vector_mode", "x").sqlstate(), "22000");
+}
+
+#[test]
+fn classify_plain_messages() {
+    let cases = [
+        ("unauthorized: user=bob action=Select", "42501"),
+        ("Table not found: clarium/public/t", "42P01"),
+        ("column 'x' does not exist", "42703"),
+        ("cursor \"c\" does not exist", "34000"),
+        ("Database not found: nope", "3D000"),
+        ("Table already exists: clarium/public/t", "42P07"),
+        ("Invalid RENAME syntax", "42601"),
+        ("canceling statement due to user request", "57014"),
+        ("something odd happened", "XX000"),
+    ];
+    for (msg, state) in cases {
+        assert_eq!(AppError::from_message(msg).sqlstate(), state, "{}", msg);
+    }
+    // An AppError inside an anyhow chain wins over message heuristics
+    let err = anyhow::Error::from(AppError::conflict("unique_violation", "not found anyway"));
+    assert_eq!(AppError::classify(&err).sqlstate(), "23505");
+}
+
+#[test]
+fn syntax_error_position_and_json() {
+    let sql = "/* tag */ SELECT a FROM t LEFT x";
+    assert_eq!(syntax_error_position(sql, "Expected JOIN after join type near 'x'."), Some(32));
+    assert_eq!(syntax_error_position("SELECT 'é' FRM t", "unexpected token 'FRM'"), Some(12));
+    assert_eq!(syntax_error_position("SELECT (1", "unterminated parenthesis"), Some(10));
+    assert_eq!(syntax_error_position("SELECT 1", "Invalid syntax"), None);
+
+    let err = AppError::from_parse_error(&anyhow::anyhow!("unexpected token 'FRM'"), "SELECT 1 FRM t");
+    assert_eq!(err.position(), Some(10));
+    let body = err.to_json();
+    assert_eq!(body["sqlstate"], "42601");
+    assert_eq!(body["position"], 10);
+    assert_eq!(body["message"], "unexpected token 'FRM'");
+    assert!(AppError::exec("exec_error", "x").to_json().get("position").is_none());
+}
@@ -27,17 +27,61 @@ pub fn normalize_identifier(ident: &str) -> String {
 pub struct QueryDefaults {
     pub current_database: String,
     pub current_schema: String,
+    /// Schemas probed in order for unqualified table names (`SET search_path`);
+    /// empty means the current schema only.
+    pub search_path: Vec<String>,
+    /// Storage root the search path is probed under; without it the current schema is used.
+    pub root: Option<PathBuf>,
 }
 
 impl QueryDefaults {
     pub fn new(db: impl Into<String>, schema: impl Into<String>) -> Self {
-        Self { current_database: db.into(), current_schema: schema.into() }
+        Self { current_database: db.into(), current_schema: schema.into(), search_path: Vec::new(), root: None }
     }
     pub fn from_options(db: Option<&str>, schema: Option<&str>) -> Self {
-        Self {
-            current_database: db.unwrap_or(DEFAULT_DB).to_string(),
-            current_schema: schema.unwrap_or(DEFAULT_SCHEMA).to_string(),
+        Self::new(db.unwrap_or(DEFAULT_DB), schema.unwrap_or(DEFAULT_SCHEMA))
+    }
+    /// Resolve unqualified names through `search_path`, probing for existing objects under `root`.
+    pub fn with_search_path(mut self, search_path: Vec<String>, root: Option<PathBuf>) -> Self {
+        self.search_path = search_path;
+        self.root = root;
+        self
+    }
+
+    /// Schema for an unqualified table (or view) name: the first search_path schema of the
+    /// current database holding it, otherwise the current schema.
+    fn schema_for(&self, table: &str) -> String {
+        let db = normalize_identifier(&self.current_database);
+        if let Some(root) = &self.root {
+            for schema in &self.search_path {
+                let base = to_local_path(root, &format!("{}/{}/{}", db, schema, table));
+                // A view `<name>.view` or a time table `<name>.time` answers to the bare name too
+                let with_ext = |ext: &str| { let mut p = base.clone().into_os_string(); p.push(ext); PathBuf::from(p) };
+                if base.exists() || with_ext(".view").exists() || with_ext(".time").exists() { return schema.clone(); }
+            }
         }
+        normalize_identifier(&self.current_schema)
+    }
+}
+
+/// Qualify a schema name into canonical `<db>/<schema>`; accepts `db.schema`, `db/schema` or `schema`.
+pub fn qualify_schema_ident(ident: &str, d: &QueryDefaults) -> String {
+    let s = ident.trim().replace('\\', "/");
+    let sep = if s.contains('/') { '/' } else { '.' };
+    match s.split_once(sep) {
+        Some((db, schema)) => format!("{}/{}", normalize_identifier(db), normalize_identifier(schema)),
+        None => format!("{}/{}", normalize_identifier(&d.current_database), normalize_identifier(&s)),
+    }
+}
+
+/// Split a database-scoped object name (filestore, KV store) into `(database, name)`;
+/// accepts `db.name`, `db/name` or `name`, which takes the current database.
+pub fn qualify_db_object(ident: &str, d: &QueryDefaults) -> (String, String) {
+    let s = ident.trim().replace('\\', "/");
+    let sep = if s.contains('/') { '/' } else { '.' };
+    match s.split_once(sep) {
+        Some((db, name)) if !db.is_empty() && !name.is_empty() => (normalize_identifier(db), name.to_string()),
+        _ => (normalize_identifier(&d.current_database), s),
     }
 }
 
@@ -67,7 +111,11 @@ pub fn qualify_table_ident(ident: &str, d: &QueryDefaults, require_time: bool) -
         tprintln!("[qualify_table_ident] current db [{}] current schema [{}] table parts {:?} ", db, schema, parts);
         let (dpart, spart, mut t): (String, String, String) = match parts.len() {
             0 => (normalize_identifier(db), normalize_identifier(schema), String::new()),
-            1 => (normalize_identifier(db), normalize_identifier(schema), normalize_identifier(parts[0])),
+            1 => {
+                let mut t = normalize_identifier(parts[0]);
+                if require_time && !t.to_lowercase().ends_with(".time") { t.push_str(".time"); }
+                (normalize_identifier(db), d.schema_for(&t), t)
+            }
             2 => (normalize_identifier(db), normalize_identifier(parts[0]), normalize_identifier(parts[1])),
             _ => (normalize_identifier(parts[0]), normalize_identifier(parts[1]), parts[2..].iter().map(|p| normalize_identifier(p)).collect::<Vec<_>>().join("/")),
         };
@@ -80,7 +128,7 @@ pub fn qualify_table_ident(ident: &str, d: &QueryDefaults, require_time: bool) -
     if require_time && parts.len() == 2 && parts[1].eq_ignore_ascii_case("time") {
         let base = normalize_identifier(parts[0]);
         let t = format!("{}.time", base);
-        return format!("{}/{}/{}", normalize_identifier(db), normalize_identifier(schema), t);
+        return format!("{}/{}/{}", normalize_identifier(db), d.schema_for(&t), t);
     }
     if require_time && parts.len() >= 3 && parts.last().map(|x| x.eq_ignore_ascii_case("time")).unwrap_or(false) {
         let dpart = normalize_identifier(parts[0]);
@@ -91,7 +139,11 @@ pub fn qualify_table_ident(ident: &str, d: &QueryDefaults, require_time: bool) -
     }
     let (dpart, spart, mut t): (String, String, String) = match parts.len() {
         0 => (normalize_identifier(db), normalize_identifier(schema), String::new()),
-        1 => (normalize_identifier(db), normalize_identifier(schema), normalize_identifier(parts[0])),
+        1 => {
+            let mut t = normalize_identifier(parts[0]);
+            if require_time && !t.to_lowercase().ends_with(".time") { t.push_str(".time"); }
+            (normalize_identifier(db), d.schema_for(&t), t)
+        }
         2 => (normalize_identifier(db), normalize_identifier(parts[0]), normalize_identifier(parts[1])),
         _ => (normalize_identifier(parts[0]), normalize_identifier(parts[1]), parts[2..].iter().map(|p| normalize_identifier(p)).collect::<Vec<_>>().join(".")),
     };
@@ -103,12 +155,16 @@ pub fn qualify_table_ident(ident: &str, d: &QueryDefaults, require_time: bool) -
 /// This also accepts partially qualified inputs and will sanitize path segments.
 pub fn to_local_path(root: &Path, qualified_or_raw: &str) -> PathBuf {
     // We tolerate either fully qualified (db/schema/table[.time]) or raw; callers should pass `qualify_*` first for consistency.
+    // The first segment is the database; attached databases live outside `root`.
     let s = qualified_or_raw.replace('\\', "/");
-    let mut out = root.to_path_buf();
+    let mut out: Option<PathBuf> = None;
     for part in s.split('/') {
         let p = part.trim();
         if p.is_empty() || p == "." || p == ".." { continue; }
-        out = out.join(p);
+        out = Some(match out {
+            None => crate::storage::attach::database_dir(root, p),
+            Some(o) => o.join(p),
+        });
     }
-    out
+    out.unwrap_or_else(|| root.to_path_buf())
 }
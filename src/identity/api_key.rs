@@ -0,0 +1,92 @@
+//! API keys for scripts and agents calling the HTTP API.
+//!
+//! A key is `clk_<id>_<secret>` and is shown once, when it is created. Only its SHA-256 is kept,
+//! in `<root>/api_keys.json`; a request sends it as `Authorization: Bearer <key>` and acts as the
+//! user that created it, without a session or CSRF token.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+use super::principal::{Attrs, Principal};
+
+pub const API_KEY_PREFIX: &str = "clk_";
+
+/// One stored key (without its secret).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub user: String,
+    pub name: String,
+    /// Hex SHA-256 of the full key
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub hash: String,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+fn keys_path(db_root: &str) -> PathBuf { Path::new(db_root).join("api_keys.json") }
+
+fn digest(key: &str) -> String { Sha256::digest(key.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect() }
+
+fn read_keys(db_root: &str) -> Result<Vec<ApiKey>> {
+    let p = keys_path(db_root);
+    if !p.exists() { return Ok(Vec::new()); }
+    Ok(serde_json::from_slice(&std::fs::read(&p)?)?)
+}
+
+fn write_keys(db_root: &str, keys: &[ApiKey]) -> Result<()> {
+    let p = keys_path(db_root);
+    if let Some(dir) = p.parent() { std::fs::create_dir_all(dir).ok(); }
+    let tmp = p.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(keys)?)?;
+    std::fs::rename(&tmp, &p)?;
+    Ok(())
+}
+
+/// Create a key for `user`; returns its record and the key itself.
+pub fn create_api_key(db_root: &str, user: &str, name: &str) -> Result<(ApiKey, String)> {
+    let mut id_bytes = [0u8; 8];
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut id_bytes).map_err(|e| anyhow::anyhow!("random source unavailable: {}", e))?;
+    getrandom::getrandom(&mut secret).map_err(|e| anyhow::anyhow!("random source unavailable: {}", e))?;
+    let id: String = id_bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let key = format!("{}{}_{}", API_KEY_PREFIX, id, base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(secret));
+    let record = ApiKey { id, user: user.to_string(), name: name.to_string(), hash: digest(&key), created_at: chrono::Utc::now().timestamp_millis() };
+    let mut keys = read_keys(db_root)?;
+    keys.push(record.clone());
+    write_keys(db_root, &keys)?;
+    tracing::info!(target: "clarium::audit", "api key create user={} id={} name={}", user, record.id, name);
+    Ok((ApiKey { hash: String::new(), ..record }, key))
+}
+
+/// Keys of `user`, or of every user when None; hashes are left out.
+pub fn list_api_keys(db_root: &str, user: Option<&str>) -> Result<Vec<ApiKey>> {
+    Ok(read_keys(db_root)?.into_iter()
+        .filter(|k| user.is_none_or(|u| k.user.eq_ignore_ascii_case(u)))
+        .map(|k| ApiKey { hash: String::new(), ..k })
+        .collect())
+}
+
+/// Delete key `id`, when it belongs to `user` (any user when None). Returns false if not found.
+pub fn revoke_api_key(db_root: &str, id: &str, user: Option<&str>) -> Result<bool> {
+    let mut keys = read_keys(db_root)?;
+    let Some(pos) = keys.iter().position(|k| k.id == id && user.is_none_or(|u| k.user.eq_ignore_ascii_case(u))) else { return Ok(false) };
+    let removed = keys.remove(pos);
+    write_keys(db_root, &keys)?;
+    tracing::info!(target: "clarium::audit", "api key revoke user={} id={}", removed.user, id);
+    Ok(true)
+}
+
+/// Principal of a valid key; None for unknown or revoked keys.
+pub fn resolve_api_key(db_root: &str, key: &str) -> Option<Principal> {
+    let id = key.strip_prefix(API_KEY_PREFIX)?.split_once('_')?.0;
+    let hash = digest(key);
+    let keys = read_keys(db_root).ok()?;
+    let found = keys.iter().find(|k| k.id == id)?;
+    // Compare every byte so the time taken does not depend on where the hashes differ
+    let same = found.hash.len() == hash.len() && found.hash.bytes().zip(hash.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+    same.then(|| Principal { user_id: found.user.clone(), roles: Vec::new(), attrs: Attrs::default(), password: None })
+}
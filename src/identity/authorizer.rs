@@ -19,6 +19,9 @@ pub async fn check_command_allowed_async(
     cmd: security::CommandKind,
     db: Option<&str>,
 ) -> bool {
+    // Admin shortcut: a SUPERUSER role granted with GRANT role TO user
+    let root = store.root_path().to_string_lossy().to_string();
+    if security::has_superuser_role(&root, username) { return true; }
     // Admin shortcut: membership in role 'admin'
     let q_admin = format!(
         "SELECT COUNT(1) AS c FROM security.role_memberships WHERE LOWER(user_id)=LOWER('{}') AND LOWER(role_id)='admin'",
@@ -99,7 +102,9 @@ pub enum Role {
 pub fn roles_for_user(db_root: &str, username: &str, db: Option<&str>) -> Vec<Role> {
     let mut roles = vec![Role::User];
     // Admin: any DDL/database privilege implies admin under the legacy model
-    if security::authorize(db_root, username, security::CommandKind::Schema, None).unwrap_or(false) {
+    if security::authorize(db_root, username, security::CommandKind::Schema, None).unwrap_or(false)
+        || security::has_superuser_role(db_root, username)
+    {
         roles.push(Role::Admin);
     }
     // Database-scoped roles (best-effort from legacy permissions)
@@ -4,14 +4,22 @@
 mod principal;
 mod session;
 mod provider;
+mod oidc;
 mod adapters;
 mod request_context;
 mod authorizer;
+mod scram;
+mod password;
+mod api_key;
 
-pub use principal::{Principal, Attrs};
-pub use session::{Session, SessionToken, SessionManager};
+pub use principal::{Principal, Attrs, PasswordStatus, ADMIN_ROLE, BUILTIN_ROLES};
+pub use session::{Session, SessionInfo, SessionToken, SessionManager};
 pub use provider::{AuthProvider, LocalAuthProvider, LoginRequest, LoginResponse};
 pub use provider::login_via_sql;
+pub use oidc::{OidcAuthProvider, Jwk};
+pub use password::{check_password_policy, hash_password, verify_password, check_lockout, record_login_failure, record_login_success, unlock_account, password_status};
+pub use scram::{ScramVerifier, ScramServer, SCRAM_ITERATIONS, md5_password_hash, md5_response_matches};
 pub use adapters::{to_filestore_legacy_user, to_filestore_v2_user};
 pub use request_context::RequestContext;
 pub use authorizer::{Role, check_command_allowed, check_command_allowed_async};
+pub use api_key::{ApiKey, API_KEY_PREFIX, create_api_key, list_api_keys, revoke_api_key, resolve_api_key};
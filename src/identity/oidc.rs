@@ -0,0 +1,316 @@
+//! OpenID Connect bearer-token authentication.
+//!
+//! [`OidcAuthProvider`] validates the JWTs an identity provider issues (an `Authorization:
+//! Bearer` header on the HTTP API): the signature against the issuer's JWKS, then `iss`,
+//! `aud`, `exp` and `nbf`. Signing keys are fetched from `jwks_uri` (or discovered from the
+//! issuer's `.well-known/openid-configuration`), kept for `jwks_refresh_secs` and fetched
+//! again early when a token names an unknown `kid`, so key rotation needs no restart.
+//!
+//! Claims become a [`Principal`]: `username_claim` is the user id, `roles_claim` lists roles
+//! (plus `admin` when one of them is in `admin_roles`), and `org_claim` / `tenant_claim` fill
+//! the org and tenant attributes. Roles granted inside Clarium reach a token user only through
+//! an explicit `local_users` entry, never by a matching name. RS256/384/512, PS256/384/512 and ES256/384 are accepted;
+//! `none` and HMAC algorithms are not.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::config::OidcSettings;
+use crate::tprintln;
+
+use super::principal::{Attrs, Principal, ADMIN_ROLE, BUILTIN_ROLES};
+use super::provider::{AuthProvider, LoginRequest, LoginResponse};
+use super::session::SessionManager;
+
+// A token naming an unknown kid refetches the JWKS at most this often
+const MIN_REFETCH: Duration = Duration::from_secs(30);
+// Validated tokens kept to skip signature checks on repeated requests
+const TOKEN_CACHE_MAX: usize = 1024;
+
+/// One key of a JWKS document (RSA or EC public key).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(default)]
+    pub kid: Option<String>,
+    #[serde(default)]
+    pub alg: Option<String>,
+    #[serde(default, rename = "use")]
+    pub use_: Option<String>,
+    #[serde(default)]
+    pub n: Option<String>,
+    #[serde(default)]
+    pub e: Option<String>,
+    #[serde(default)]
+    pub crv: Option<String>,
+    #[serde(default)]
+    pub x: Option<String>,
+    #[serde(default)]
+    pub y: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwkSet { keys: Vec<Jwk> }
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+#[derive(Default)]
+struct KeyCache {
+    keys: Vec<Jwk>,
+    fetched_at: Option<Instant>,
+}
+
+pub struct OidcAuthProvider {
+    settings: OidcSettings,
+    db_root: String,
+    sm: SessionManager,
+    keys: RwLock<KeyCache>,
+    tokens: RwLock<HashMap<String, (Principal, i64)>>,
+    http: reqwest::Client,
+}
+
+fn b64url(s: &str) -> Result<Vec<u8>> {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(s.trim_end_matches('=')).map_err(|e| anyhow!("invalid_token: bad base64url: {}", e))
+}
+
+fn now_secs() -> i64 { chrono::Utc::now().timestamp() }
+
+/// Value at a dotted claim path (`realm_access.roles`).
+fn claim<'a>(claims: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let mut cur = claims.get(parts.next()?)?;
+    for p in parts { cur = cur.get(p)?; }
+    Some(cur)
+}
+
+fn claim_str(claims: &Map<String, Value>, path: &str) -> Option<String> {
+    if path.is_empty() { return None; }
+    match claim(claims, path)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Verify a JWS signature over `message` with `key`.
+fn verify_signature(alg: &str, key: &Jwk, message: &[u8], signature: &[u8]) -> Result<()> {
+    use ring::signature as sig;
+    let ok = match alg {
+        "RS256" | "RS384" | "RS512" | "PS256" | "PS384" | "PS512" => {
+            if key.kty != "RSA" { bail!("invalid_token: key {} is not an RSA key", key.kid.as_deref().unwrap_or("?")); }
+            let n = b64url(key.n.as_deref().ok_or_else(|| anyhow!("invalid_token: RSA key without n"))?)?;
+            let e = b64url(key.e.as_deref().ok_or_else(|| anyhow!("invalid_token: RSA key without e"))?)?;
+            let params: &sig::RsaParameters = match alg {
+                "RS256" => &sig::RSA_PKCS1_2048_8192_SHA256,
+                "RS384" => &sig::RSA_PKCS1_2048_8192_SHA384,
+                "RS512" => &sig::RSA_PKCS1_2048_8192_SHA512,
+                "PS256" => &sig::RSA_PSS_2048_8192_SHA256,
+                "PS384" => &sig::RSA_PSS_2048_8192_SHA384,
+                _ => &sig::RSA_PSS_2048_8192_SHA512,
+            };
+            sig::RsaPublicKeyComponents { n: &n, e: &e }.verify(params, message, signature).is_ok()
+        }
+        "ES256" | "ES384" => {
+            let (crv, algorithm): (&str, &sig::EcdsaVerificationAlgorithm) =
+                if alg == "ES256" { ("P-256", &sig::ECDSA_P256_SHA256_FIXED) } else { ("P-384", &sig::ECDSA_P384_SHA384_FIXED) };
+            if key.kty != "EC" || key.crv.as_deref() != Some(crv) { bail!("invalid_token: key {} is not a {} key", key.kid.as_deref().unwrap_or("?"), crv); }
+            let mut point = vec![0x04u8];
+            point.extend(b64url(key.x.as_deref().ok_or_else(|| anyhow!("invalid_token: EC key without x"))?)?);
+            point.extend(b64url(key.y.as_deref().ok_or_else(|| anyhow!("invalid_token: EC key without y"))?)?);
+            sig::UnparsedPublicKey::new(algorithm, &point).verify(message, signature).is_ok()
+        }
+        other => bail!("invalid_token: unsupported alg {}", other),
+    };
+    if !ok { bail!("invalid_token: bad signature"); }
+    Ok(())
+}
+
+impl OidcAuthProvider {
+    pub fn new(settings: OidcSettings, db_root: String, sm: SessionManager) -> Self {
+        Self { settings, db_root, sm, keys: RwLock::new(KeyCache::default()), tokens: RwLock::new(HashMap::new()), http: reqwest::Client::new() }
+    }
+
+    /// Provider for the `[oidc]` settings, or None when no issuer is configured.
+    pub fn from_config(db_root: &str) -> Option<Self> {
+        let settings = crate::config::current().oidc.clone();
+        if settings.issuer.trim().is_empty() { return None; }
+        Some(Self::new(settings, db_root.to_string(), SessionManager::default()))
+    }
+
+    pub fn settings(&self) -> &OidcSettings { &self.settings }
+
+    /// Use `jwks` (a JWKS JSON document) as the signing keys, as if just fetched.
+    pub fn set_jwks(&self, jwks: &str) -> Result<()> {
+        let set: JwkSet = serde_json::from_str(jwks).map_err(|e| anyhow!("invalid JWKS: {}", e))?;
+        *self.keys.write() = KeyCache { keys: set.keys, fetched_at: Some(Instant::now()) };
+        Ok(())
+    }
+
+    async fn jwks_uri(&self) -> Result<String> {
+        if !self.settings.jwks_uri.trim().is_empty() { return Ok(self.settings.jwks_uri.trim().to_string()); }
+        let url = format!("{}/.well-known/openid-configuration", self.settings.issuer.trim_end_matches('/'));
+        let doc: Value = self.http.get(&url).send().await?.error_for_status()?.json().await?;
+        doc.get("jwks_uri").and_then(|v| v.as_str()).map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("OIDC discovery document at {} has no jwks_uri", url))
+    }
+
+    /// Fetch the issuer's signing keys.
+    pub async fn refresh_keys(&self) -> Result<()> {
+        let uri = self.jwks_uri().await?;
+        let body = self.http.get(&uri).send().await?.error_for_status()?.text().await?;
+        self.set_jwks(&body)?;
+        tprintln!("oidc.jwks refreshed uri={} keys={}", uri, self.keys.read().keys.len());
+        Ok(())
+    }
+
+    fn find_key(&self, kid: Option<&str>, alg: &str) -> Option<Jwk> {
+        let cache = self.keys.read();
+        let usable = |k: &&Jwk| k.use_.as_deref().map(|u| u == "sig").unwrap_or(true) && k.alg.as_deref().map(|a| a == alg).unwrap_or(true);
+        match kid {
+            Some(kid) => cache.keys.iter().filter(usable).find(|k| k.kid.as_deref() == Some(kid)).cloned(),
+            None => {
+                let mut it = cache.keys.iter().filter(usable);
+                let first = it.next().cloned();
+                if it.next().is_some() { None } else { first }
+            }
+        }
+    }
+
+    fn keys_stale(&self, min_age: Duration) -> bool {
+        match self.keys.read().fetched_at {
+            Some(at) => at.elapsed() >= min_age,
+            None => true,
+        }
+    }
+
+    /// Check the signature and registered claims of `token` against the cached keys; returns its claims.
+    pub fn validate_cached(&self, token: &str) -> Result<Map<String, Value>> {
+        let mut parts = token.trim().split('.');
+        let (Some(h), Some(p), Some(s), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            bail!("invalid_token: not a JWS compact token");
+        };
+        let header: JwtHeader = serde_json::from_slice(&b64url(h)?).map_err(|e| anyhow!("invalid_token: bad header: {}", e))?;
+        let key = self.find_key(header.kid.as_deref(), &header.alg)
+            .ok_or_else(|| anyhow!("invalid_token: no signing key for kid {:?}", header.kid))?;
+        verify_signature(&header.alg, &key, format!("{}.{}", h, p).as_bytes(), &b64url(s)?)?;
+        let claims: Map<String, Value> = serde_json::from_slice(&b64url(p)?).map_err(|e| anyhow!("invalid_token: bad payload: {}", e))?;
+        self.check_claims(&claims)?;
+        Ok(claims)
+    }
+
+    fn check_claims(&self, claims: &Map<String, Value>) -> Result<()> {
+        let iss = claims.get("iss").and_then(|v| v.as_str()).unwrap_or("");
+        if iss.trim_end_matches('/') != self.settings.issuer.trim().trim_end_matches('/') {
+            bail!("invalid_token: unexpected issuer '{}'", iss);
+        }
+        let audience = self.settings.audience.trim();
+        if !audience.is_empty() {
+            let ok = match claims.get("aud") {
+                Some(Value::String(a)) => a == audience,
+                Some(Value::Array(list)) => list.iter().any(|a| a.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !ok { bail!("invalid_token: token is not issued for audience '{}'", audience); }
+        }
+        let now = now_secs();
+        let leeway = self.settings.leeway_secs as i64;
+        let exp = claims.get("exp").and_then(|v| v.as_i64()).ok_or_else(|| anyhow!("invalid_token: missing exp"))?;
+        if now > exp + leeway { bail!("invalid_token: token expired"); }
+        if let Some(nbf) = claims.get("nbf").and_then(|v| v.as_i64()) {
+            if now + leeway < nbf { bail!("invalid_token: token not valid yet"); }
+        }
+        Ok(())
+    }
+
+    /// Local user `user_id` is mapped to in `local_users`, if any.
+    fn local_user<'a>(&'a self, user_id: &str) -> Option<&'a str> {
+        self.settings.local_users.split(',').find_map(|pair| {
+            let (token_user, local) = pair.split_once('=')?;
+            (token_user.trim() == user_id && !local.trim().is_empty()).then(|| local.trim())
+        })
+    }
+
+    /// Principal for validated `claims`.
+    pub fn principal(&self, claims: &Map<String, Value>, ip: Option<String>) -> Result<Principal> {
+        let user_id = claim_str(claims, &self.settings.username_claim)
+            .or_else(|| claim_str(claims, "sub"))
+            .ok_or_else(|| anyhow!("invalid_token: no '{}' or 'sub' claim", self.settings.username_claim))?;
+        let mut roles: Vec<String> = vec!["user".into()];
+        let claimed: Vec<String> = match claim(claims, &self.settings.roles_claim) {
+            Some(Value::Array(list)) => list.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect(),
+            Some(Value::String(s)) => s.split([',', ' ']).filter(|r| !r.is_empty()).map(|r| r.to_string()).collect(),
+            _ => Vec::new(),
+        };
+        let is_admin = claimed.iter().any(|r| self.settings.admin_roles.split(',').any(|a| a.trim().eq_ignore_ascii_case(r)));
+        // Roles granted inside Clarium (GRANT role TO user) apply only to mapped SSO users
+        let local: Vec<String> = match self.local_user(&user_id) {
+            Some(local) => crate::security::effective_roles(&self.db_root, local).into_iter().skip(1).collect(),
+            None => Vec::new(),
+        };
+        for r in claimed.into_iter().chain(local) {
+            // Built-in names such as `admin` only come from the mapping below, not from the issuer
+            if BUILTIN_ROLES.iter().any(|b| b.eq_ignore_ascii_case(&r)) { continue; }
+            if !roles.iter().any(|x| x.eq_ignore_ascii_case(&r)) { roles.push(r); }
+        }
+        if is_admin { roles.push(ADMIN_ROLE.into()); }
+        let attrs = Attrs {
+            org_id: claim_str(claims, &self.settings.org_claim),
+            tenant_id: claim_str(claims, &self.settings.tenant_claim),
+            ip,
+            ..Default::default()
+        };
+        Ok(Principal { user_id, roles, attrs, password: None })
+    }
+
+    /// Validate a bearer token, fetching the JWKS when the keys are stale or do not know the
+    /// token's key, and return its principal. Validated tokens are cached until they expire.
+    pub async fn authenticate(&self, token: &str, ip: Option<String>) -> Result<Principal> {
+        let now = now_secs();
+        if let Some((p, exp)) = self.tokens.read().get(token) {
+            if now <= *exp { return Ok(p.clone()); }
+        }
+        if self.keys_stale(Duration::from_secs(self.settings.jwks_refresh_secs.max(1))) {
+            if let Err(e) = self.refresh_keys().await { tracing::warn!(target: "clarium::auth", "OIDC JWKS refresh failed: {}", e); }
+        }
+        let claims = match self.validate_cached(token) {
+            Err(e) if e.to_string().contains("no signing key") && self.keys_stale(MIN_REFETCH) => {
+                // Key rotation: the issuer may have published a new key since the last fetch
+                self.refresh_keys().await?;
+                self.validate_cached(token)?
+            }
+            other => other?,
+        };
+        let principal = self.principal(&claims, ip)?;
+        let exp = claims.get("exp").and_then(|v| v.as_i64()).unwrap_or(now);
+        let mut cache = self.tokens.write();
+        if cache.len() >= TOKEN_CACHE_MAX { cache.retain(|_, (_, e)| *e >= now); }
+        if cache.len() < TOKEN_CACHE_MAX { cache.insert(token.to_string(), (principal.clone(), exp)); }
+        tprintln!("auth.oidc user={} roles={:?}", principal.user_id, principal.roles);
+        Ok(principal)
+    }
+}
+
+impl AuthProvider for OidcAuthProvider {
+    /// Exchange an ID token (passed as the password) for a session, using the cached keys.
+    fn login(&self, req: &LoginRequest) -> Result<LoginResponse> {
+        let claims = self.validate_cached(&req.password)?;
+        let principal = self.principal(&claims, req.ip.clone())?;
+        if !req.username.is_empty() && !req.username.eq_ignore_ascii_case(&principal.user_id) {
+            bail!("invalid_credentials");
+        }
+        let session = self.sm.issue(principal);
+        tprintln!("auth.login(oidc) user={} sid={}", req.username, session.session_id);
+        Ok(LoginResponse { session })
+    }
+}
@@ -0,0 +1,111 @@
+//! Password policy for local users, configured by the `[password]` section:
+//! - complexity rules checked whenever a password is set (`USER ADD`, `USER ALTER ... PASSWORD`);
+//! - the hash new passwords are stored with (argon2id or bcrypt, with their cost parameters);
+//! - lockout after repeated failed logins, doubling with every further failure;
+//! - rotation status (`max_age_days`, `USER ALTER <name> EXPIRE PASSWORD`) carried on the
+//!   principal of each login.
+//!
+//! Lockout state is kept in memory and starts empty after a restart.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier, Version};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use password_hash::{PasswordHash, SaltString};
+
+use super::principal::PasswordStatus;
+use crate::error::AppError;
+
+#[derive(Debug, Default)]
+struct Failures {
+    count: u32,
+    locked_until: Option<Instant>,
+}
+
+static FAILURES: Lazy<Mutex<HashMap<String, Failures>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Reject a new password that breaks the configured complexity rules.
+pub fn check_password_policy(password: &str) -> Result<()> {
+    let cfg = crate::config::current().password.clone();
+    let mut missing: Vec<String> = Vec::new();
+    if (password.chars().count() as u64) < cfg.min_length { missing.push(format!("at least {} characters", cfg.min_length)); }
+    if cfg.require_upper && !password.chars().any(|c| c.is_uppercase()) { missing.push("an uppercase letter".into()); }
+    if cfg.require_lower && !password.chars().any(|c| c.is_lowercase()) { missing.push("a lowercase letter".into()); }
+    if cfg.require_digit && !password.chars().any(|c| c.is_ascii_digit()) { missing.push("a digit".into()); }
+    if cfg.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) { missing.push("a symbol".into()); }
+    if missing.is_empty() { return Ok(()); }
+    Err(AppError::user("invalid_parameter_value".to_string(), format!("password must contain {}", missing.join(", "))).into())
+}
+
+/// Hash a password with the configured algorithm (PHC string for argon2id, `$2b$` for bcrypt).
+pub fn hash_password(password: &str) -> Result<String> {
+    let cfg = crate::config::current().password.clone();
+    match cfg.hash_algorithm.to_ascii_lowercase().as_str() {
+        "bcrypt" => bcrypt::hash(password, cfg.bcrypt_cost as u32).map_err(|e| anyhow!(e.to_string())),
+        "argon2" | "argon2id" | "" => {
+            let mut salt_bytes = [0u8; 16];
+            getrandom::getrandom(&mut salt_bytes).map_err(|e| anyhow!(e.to_string()))?;
+            let salt = SaltString::encode_b64(&salt_bytes).map_err(|e| anyhow!(e.to_string()))?;
+            let params = Params::new(cfg.argon2_memory_kib as u32, cfg.argon2_iterations as u32, cfg.argon2_parallelism as u32, None)
+                .map_err(|e| anyhow!("invalid argon2 parameters: {}", e))?;
+            let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+            Ok(argon2.hash_password(password.as_bytes(), &salt).map_err(|e| anyhow!(e.to_string()))?.to_string())
+        }
+        other => bail!("unknown password.hash_algorithm '{}' (expected argon2 or bcrypt)", other),
+    }
+}
+
+/// Check a password against a stored hash of either algorithm; the cost is read from the hash.
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    if hash.starts_with("$2") { return bcrypt::verify(password, hash).unwrap_or(false); }
+    match PasswordHash::new(hash) {
+        Ok(parsed) => Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Fail while `username` is locked out after too many failed logins.
+pub fn check_lockout(username: &str) -> Result<()> {
+    let map = FAILURES.lock();
+    let Some(until) = map.get(&username.to_lowercase()).and_then(|f| f.locked_until) else { return Ok(()) };
+    let now = Instant::now();
+    if until <= now { return Ok(()); }
+    let secs = until.duration_since(now).as_secs().max(1);
+    Err(AppError::auth("account_locked".to_string(), format!("account \"{}\" is locked after repeated failed logins; try again in {} seconds", username, secs)).into())
+}
+
+/// Count a failed login; past `lockout_threshold` failures the account is locked for
+/// `lockout_base_secs`, doubled for every further failure up to `lockout_max_secs`.
+pub fn record_login_failure(username: &str) {
+    let cfg = crate::config::current().password.clone();
+    let mut map = FAILURES.lock();
+    let f = map.entry(username.to_lowercase()).or_default();
+    f.count = f.count.saturating_add(1);
+    if cfg.lockout_threshold == 0 || (f.count as u64) < cfg.lockout_threshold { return; }
+    let doublings = (f.count as u64 - cfg.lockout_threshold).min(32) as u32;
+    let secs = cfg.lockout_base_secs.saturating_mul(1u64 << doublings).min(cfg.lockout_max_secs.max(cfg.lockout_base_secs));
+    f.locked_until = Some(Instant::now() + Duration::from_secs(secs));
+    tracing::warn!(target: "clarium::audit", "account locked user={} failures={} secs={}", username, f.count, secs);
+}
+
+/// Clear the failure count after a successful login.
+pub fn record_login_success(username: &str) {
+    FAILURES.lock().remove(&username.to_lowercase());
+}
+
+/// Lift a lockout (`USER ALTER <name> UNLOCK`); returns whether the account was locked.
+pub fn unlock_account(username: &str) -> bool {
+    FAILURES.lock().remove(&username.to_lowercase()).and_then(|f| f.locked_until).is_some_and(|t| t > Instant::now())
+}
+
+/// Rotation status of a global user's password.
+pub fn password_status(db_root: &str, username: &str) -> PasswordStatus {
+    let (changed_at, flagged) = crate::security::password_rotation(db_root, username).ok().flatten().unwrap_or((None, false));
+    let max_age_days = crate::config::current().password.max_age_days as i64;
+    let expires_at = changed_at.filter(|_| max_age_days > 0).map(|t| t + max_age_days * 86_400_000);
+    let expired = expires_at.is_some_and(|t| t <= chrono::Utc::now().timestamp_millis());
+    PasswordStatus { changed_at, expires_at, must_change: flagged || expired }
+}
@@ -12,6 +12,13 @@ pub struct Attrs {
     pub device_id: Option<String>,
 }
 
+/// Role that makes a principal an admin. Identity sources add it from their own setting (local
+/// admin users, OIDC `admin_roles`), never because a client or issuer sent a role of that name.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Role names Clarium derives from its own grants; role claims from an issuer cannot carry them.
+pub const BUILTIN_ROLES: &[&str] = &["user", ADMIN_ROLE, "db_reader", "db_writer", "db_deleter", "compute", "fs_reader", "fs_writer"];
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Principal {
     pub user_id: String,
@@ -19,4 +26,21 @@ pub struct Principal {
     pub roles: Vec<String>,
     #[serde(default)]
     pub attrs: Attrs,
+    /// Rotation state of a local user's password; None for other identity sources
+    #[serde(default)]
+    pub password: Option<PasswordStatus>,
+}
+
+impl Principal {
+    pub fn is_admin(&self) -> bool { self.roles.iter().any(|r| r == ADMIN_ROLE) }
+}
+
+/// When a local password was last set and whether it has to be changed. Times are epoch ms.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PasswordStatus {
+    pub changed_at: Option<i64>,
+    /// `changed_at` plus `password.max_age_days`; None when passwords do not age
+    pub expires_at: Option<i64>,
+    /// Set by `USER ALTER <name> EXPIRE PASSWORD` or once `expires_at` has passed
+    pub must_change: bool,
 }
@@ -3,6 +3,7 @@ use anyhow::{Result, anyhow};
 use crate::tprintln;
 
 use super::principal::Principal;
+use super::scram::ScramVerifier;
 use super::session::{Session, SessionManager};
 use crate::storage::SharedStore;
 
@@ -30,38 +31,69 @@ pub struct LocalAuthProvider {
 
 impl LocalAuthProvider {
     pub fn new(db_root: String, sm: SessionManager) -> Self { Self { db_root, sm } }
-}
 
-impl AuthProvider for LocalAuthProvider {
-    fn login(&self, req: &LoginRequest) -> Result<LoginResponse> {
-        // Verify password using existing user store (global scope for now)
-        if !crate::security::authenticate(&self.db_root, &req.username, &req.password)? {
-            return Err(anyhow!("invalid_credentials"));
-        }
+    /// Stored SCRAM-SHA-256 verifier for `username`, if the user exists and has one.
+    pub fn scram_verifier(&self, username: &str) -> Option<ScramVerifier> {
+        let (scram, _) = crate::security::password_verifiers(&self.db_root, username).ok()??;
+        ScramVerifier::parse(&scram)
+    }
+
+    /// Stored md5 hash (`md5...`) for `username`, if the user exists and has one.
+    pub fn md5_hash(&self, username: &str) -> Option<String> {
+        let (_, md5) = crate::security::password_verifiers(&self.db_root, username).ok()??;
+        (!md5.is_empty()).then_some(md5)
+    }
+
+    /// Issue a session for a user whose credentials were already verified by a
+    /// challenge-response exchange (pgwire SCRAM or md5).
+    pub fn login_verified(&self, username: &str, db: Option<&str>, ip: Option<String>) -> LoginResponse {
+        let principal = self.principal(username, db, ip);
+        let session = self.sm.issue(principal);
+        tprintln!("auth.login(verified) user={} sid={}", username, session.session_id);
+        LoginResponse { session }
+    }
+
+    fn principal(&self, username: &str, db: Option<&str>, ip: Option<String>) -> Principal {
         // Map permissions to roles using existing authorizer heuristics
         let mut roles: Vec<String> = vec!["user".into()];
-        let is_admin = crate::security::authorize(&self.db_root, &req.username, crate::security::CommandKind::Schema, None).unwrap_or(false);
+        let is_admin = crate::security::authorize(&self.db_root, username, crate::security::CommandKind::Schema, None).unwrap_or(false);
         if is_admin { roles.push("admin".into()); }
         // Database-scoped roles inferred from command authorizations
-        if crate::security::authorize(&self.db_root, &req.username, crate::security::CommandKind::Select, req.db.as_deref()).unwrap_or(false) {
+        if crate::security::authorize(&self.db_root, username, crate::security::CommandKind::Select, db).unwrap_or(false) {
             roles.push("db_reader".into());
         }
-        if crate::security::authorize(&self.db_root, &req.username, crate::security::CommandKind::Insert, req.db.as_deref()).unwrap_or(false) {
+        if crate::security::authorize(&self.db_root, username, crate::security::CommandKind::Insert, db).unwrap_or(false) {
             roles.push("db_writer".into());
         }
-        if crate::security::authorize(&self.db_root, &req.username, crate::security::CommandKind::Calculate, req.db.as_deref()).unwrap_or(false) {
+        if crate::security::authorize(&self.db_root, username, crate::security::CommandKind::Calculate, db).unwrap_or(false) {
             roles.push("compute".into());
         }
-        if crate::security::authorize(&self.db_root, &req.username, crate::security::CommandKind::DeleteRows, req.db.as_deref()).unwrap_or(false) {
+        if crate::security::authorize(&self.db_root, username, crate::security::CommandKind::DeleteRows, db).unwrap_or(false) {
             roles.push("db_deleter".into());
         }
+        // Roles granted with GRANT role TO user (and the roles they inherit)
+        roles.extend(crate::security::effective_roles(&self.db_root, username).into_iter().skip(1));
 
         // Principal with basic attributes
-        let principal = super::principal::Principal {
-            user_id: req.username.clone(),
+        Principal {
+            user_id: username.to_string(),
             roles,
-            attrs: super::principal::Attrs { ip: req.ip.clone(), ..Default::default() },
-        };
+            attrs: super::principal::Attrs { ip, ..Default::default() },
+            password: Some(super::password::password_status(&self.db_root, username)),
+        }
+    }
+}
+
+impl AuthProvider for LocalAuthProvider {
+    fn login(&self, req: &LoginRequest) -> Result<LoginResponse> {
+        // Verify password using existing user store (global scope for now); repeated failures lock the account
+        super::password::check_lockout(&req.username)?;
+        if !crate::security::authenticate(&self.db_root, &req.username, &req.password)? {
+            super::password::record_login_failure(&req.username);
+            return Err(anyhow!("invalid_credentials"));
+        }
+        super::password::record_login_success(&req.username);
+        let principal = self.principal(&req.username, req.db.as_deref(), req.ip.clone());
         let session = self.sm.issue(principal);
         tprintln!("auth.login user={} sid={}", req.username, session.session_id);
         Ok(LoginResponse { session })
@@ -86,10 +118,16 @@ pub async fn login_via_sql(
         .and_then(|r| r.get(0))
         .and_then(|row| row.get("password_hash"))
         .and_then(|v| v.as_str());
-    let Some(phc) = hash_opt else { return Err(anyhow!("invalid_credentials")); };
+    super::password::check_lockout(&req.username)?;
+    let Some(phc) = hash_opt else {
+        super::password::record_login_failure(&req.username);
+        return Err(anyhow!("invalid_credentials"));
+    };
     if !crate::security::verify_password(phc, &req.password) {
+        super::password::record_login_failure(&req.username);
         return Err(anyhow!("invalid_credentials"));
     }
+    super::password::record_login_success(&req.username);
 
     // Roles: baseline 'user', add 'admin' if membership exists
     let mut roles: Vec<String> = vec!["user".into()];
@@ -103,11 +141,14 @@ pub async fn login_via_sql(
             .unwrap_or(0) > 0;
         if is_admin { roles.push("admin".into()); }
     }
+    let root = store.root_path().to_string_lossy().to_string();
+    roles.extend(crate::security::effective_roles(&root, &req.username).into_iter().skip(1));
 
     let principal = Principal {
         user_id: req.username.clone(),
         roles,
         attrs: super::principal::Attrs { ip: req.ip.clone(), ..Default::default() },
+        password: None,
     };
     let session = sm.issue(principal);
     tprintln!("auth.login(sql) user={} sid={}", req.username, session.session_id);
@@ -0,0 +1,162 @@
+//! Password verifiers for challenge-response logins (pgwire SCRAM-SHA-256 and md5).
+//!
+//! A SCRAM verifier is stored in the PostgreSQL format
+//! `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>` (base64 parts), so the
+//! password itself is never kept. Passwords are used as given (no SASLprep), which
+//! matches clients for ASCII passwords.
+
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SCRAM_ITERATIONS: u32 = 4096;
+
+fn b64() -> base64::engine::GeneralPurpose { base64::engine::general_purpose::STANDARD }
+
+fn hex(bytes: &[u8]) -> String { bytes.iter().map(|b| format!("{:02x}", b)).collect() }
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut m = HmacSha256::new_from_slice(key).expect("hmac accepts any key length");
+    m.update(data);
+    m.finalize().into_bytes().into()
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] { Sha256::digest(data).into() }
+
+/// PBKDF2-HMAC-SHA-256 with a single output block (SCRAM's `Hi`).
+fn hi(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut first = salt.to_vec();
+    first.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac(password, &first);
+    let mut out = u;
+    for _ in 1..iterations {
+        u = hmac(password, &u);
+        for (o, b) in out.iter_mut().zip(u.iter()) { *o ^= b; }
+    }
+    out
+}
+
+/// Stored SCRAM-SHA-256 credentials for one user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScramVerifier {
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub stored_key: [u8; 32],
+    pub server_key: [u8; 32],
+}
+
+impl ScramVerifier {
+    /// Verifier for `password` with a fresh random salt.
+    pub fn new(password: &str) -> Result<Self> {
+        let mut salt = [0u8; 16];
+        getrandom::getrandom(&mut salt).map_err(|e| anyhow!(e.to_string()))?;
+        Ok(Self::with_salt(password, &salt, SCRAM_ITERATIONS))
+    }
+
+    pub fn with_salt(password: &str, salt: &[u8], iterations: u32) -> Self {
+        let salted = hi(password.as_bytes(), salt, iterations);
+        let client_key = hmac(&salted, b"Client Key");
+        Self {
+            iterations,
+            salt: salt.to_vec(),
+            stored_key: sha256(&client_key),
+            server_key: hmac(&salted, b"Server Key"),
+        }
+    }
+
+    /// Parse the stored `SCRAM-SHA-256$...` form; None for anything else (e.g. an empty column).
+    pub fn parse(s: &str) -> Option<Self> {
+        let rest = s.strip_prefix("SCRAM-SHA-256$")?;
+        let (iter_salt, keys) = rest.split_once('$')?;
+        let (iterations, salt) = iter_salt.split_once(':')?;
+        let (stored, server) = keys.split_once(':')?;
+        Some(Self {
+            iterations: iterations.parse().ok()?,
+            salt: b64().decode(salt).ok()?,
+            stored_key: b64().decode(stored).ok()?.try_into().ok()?,
+            server_key: b64().decode(server).ok()?.try_into().ok()?,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        format!("SCRAM-SHA-256${}:{}${}:{}", self.iterations, b64().encode(&self.salt), b64().encode(self.stored_key), b64().encode(self.server_key))
+    }
+}
+
+/// Stored md5 credential in the PostgreSQL form: `md5` + hex(md5(password || username)).
+pub fn md5_password_hash(username: &str, password: &str) -> String {
+    let mut h = Md5::new();
+    h.update(password.as_bytes());
+    h.update(username.as_bytes());
+    format!("md5{}", hex(&h.finalize()))
+}
+
+/// Check a client's md5 response (`md5` + hex(md5(stored_hex || salt))) against the stored hash.
+pub fn md5_response_matches(stored: &str, salt: &[u8; 4], response: &str) -> bool {
+    let Some(inner) = stored.strip_prefix("md5") else { return false };
+    let mut h = Md5::new();
+    h.update(inner.as_bytes());
+    h.update(salt);
+    let expected = format!("md5{}", hex(&h.finalize()));
+    // Compare without an early exit on the first differing byte
+    expected.len() == response.len() && expected.bytes().zip(response.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Server side of one SCRAM-SHA-256 exchange (RFC 5802 / RFC 7677), without channel binding.
+pub struct ScramServer {
+    verifier: ScramVerifier,
+    gs2_header: String,
+    client_first_bare: String,
+    server_first: String,
+    nonce: String,
+}
+
+impl ScramServer {
+    /// Handle the client-first message and return the server-first message to send.
+    pub fn start(client_first: &str, verifier: ScramVerifier) -> Result<(Self, String)> {
+        let mut raw = [0u8; 18];
+        getrandom::getrandom(&mut raw).map_err(|e| anyhow!(e.to_string()))?;
+        Self::start_with_nonce(client_first, verifier, &b64().encode(raw))
+    }
+
+    pub fn start_with_nonce(client_first: &str, verifier: ScramVerifier, server_nonce: &str) -> Result<(Self, String)> {
+        // gs2 header: "n,," (no channel binding) or "y,," (client could bind, server did not offer it)
+        let (cbind, rest) = client_first.split_once(',').ok_or_else(|| anyhow!("malformed SCRAM client-first message"))?;
+        if cbind != "n" && cbind != "y" { bail!("SCRAM channel binding is not supported"); }
+        let (authzid, bare) = rest.split_once(',').ok_or_else(|| anyhow!("malformed SCRAM client-first message"))?;
+        let client_nonce = bare.split(',').find_map(|a| a.strip_prefix("r="))
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| anyhow!("SCRAM client-first message has no nonce"))?;
+        let nonce = format!("{}{}", client_nonce, server_nonce);
+        let server_first = format!("r={},s={},i={}", nonce, b64().encode(&verifier.salt), verifier.iterations);
+        let gs2_header = format!("{},{},", cbind, authzid);
+        Ok((Self { verifier, gs2_header, client_first_bare: bare.to_string(), server_first: server_first.clone(), nonce }, server_first))
+    }
+
+    /// Verify the client-final message; returns the server-final message (`v=...`) on success.
+    pub fn finish(&self, client_final: &str) -> Result<String> {
+        let (without_proof, proof) = client_final.rsplit_once(",p=").ok_or_else(|| anyhow!("SCRAM client-final message has no proof"))?;
+        let mut channel = None;
+        let mut nonce = None;
+        for attr in without_proof.split(',') {
+            if let Some(v) = attr.strip_prefix("c=") { channel = Some(v); }
+            if let Some(v) = attr.strip_prefix("r=") { nonce = Some(v); }
+        }
+        if channel != Some(b64().encode(&self.gs2_header).as_str()) { bail!("SCRAM channel binding mismatch"); }
+        if nonce != Some(self.nonce.as_str()) { bail!("SCRAM nonce mismatch"); }
+        let proof: [u8; 32] = b64().decode(proof).ok().and_then(|p| p.try_into().ok()).ok_or_else(|| anyhow!("malformed SCRAM proof"))?;
+        let auth_message = format!("{},{},{}", self.client_first_bare, self.server_first, without_proof);
+        let client_signature = hmac(&self.verifier.stored_key, auth_message.as_bytes());
+        let mut client_key = proof;
+        for (k, s) in client_key.iter_mut().zip(client_signature.iter()) { *k ^= s; }
+        // Compare without an early exit on the first differing byte
+        let same = sha256(&client_key).iter().zip(self.verifier.stored_key.iter()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+        if !same { bail!("invalid_credentials"); }
+        let server_signature = hmac(&self.verifier.server_key, auth_message.as_bytes());
+        Ok(format!("v={}", b64().encode(server_signature)))
+    }
+}
@@ -1,3 +1,13 @@
+//! Login sessions shared by the HTTP and pgwire frontends.
+//!
+//! A session expires after `limits.session_idle_secs` without use or `limits.session_abs_secs`
+//! after login, whichever comes first. `limits.max_sessions_per_user` caps how many sessions one
+//! user holds: a new login signs out that user's oldest session. Admins list sessions in
+//! `pg_catalog.clarium_sessions` (`SHOW SESSIONS`) and end them with `KILL SESSION`.
+//!
+//! Logins, logouts, expiries and revocations are written as audit events (target
+//! `clarium::audit`).
+
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
@@ -18,13 +28,30 @@ pub struct Session {
     pub expires_at: Instant,
 }
 
+/// Snapshot of a live session, as listed by `pg_catalog.clarium_sessions`. Times are epoch ms.
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub user_id: String,
+    pub client_addr: Option<String>,
+    pub issued_at: i64,
+    pub last_seen: i64,
+    /// When the session expires if it stays idle (bounded by the absolute lifetime)
+    pub expires_at: i64,
+}
+
 #[derive(Debug)]
 struct SessionEntry {
     session: Session,
+    last_seen: Instant,
+    issued_ms: i64,
+    last_seen_ms: i64,
 }
 
 static SESSIONS: Lazy<RwLock<HashMap<String, SessionEntry>>> = Lazy::new(|| RwLock::new(HashMap::new()));
 static USER_INDEX: Lazy<RwLock<HashMap<String, HashSet<String>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+// session id -> token
+static SID_INDEX: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| RwLock::new(HashMap::new()));
 static REVOKED: Lazy<RwLock<HashSet<String>>> = Lazy::new(|| RwLock::new(HashSet::new()));
 
 fn gen_id() -> String {
@@ -34,12 +61,41 @@ fn gen_id() -> String {
     base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(buf)
 }
 
+fn now_ms() -> i64 { chrono::Utc::now().timestamp_millis() }
+
+fn audit(event: &str, session: &Session, detail: &str) {
+    tracing::info!(target: "clarium::audit", "session {} user={} sid={} ip={}{}", event, session.principal.user_id, session.session_id,
+        session.principal.attrs.ip.as_deref().unwrap_or("-"), if detail.is_empty() { String::new() } else { format!(" {}", detail) });
+}
+
+// Drop `token` from every index; returns its session
+fn remove_token(token: &str) -> Option<Session> {
+    let ent = SESSIONS.write().remove(token)?;
+    if let Some(set) = USER_INDEX.write().get_mut(&ent.session.principal.user_id) { set.remove(token); }
+    SID_INDEX.write().remove(&ent.session.session_id);
+    REVOKED.write().insert(token.to_string());
+    Some(ent.session)
+}
+
 pub struct SessionManager {
+    /// Absolute lifetime
     pub ttl: Duration,
+    /// Idle timeout
+    pub idle: Duration,
+    /// Sessions one user may hold at once; 0 = unlimited
+    pub max_per_user: usize,
 }
 
 impl Default for SessionManager {
-    fn default() -> Self { Self { ttl: Duration::from_secs(60 * 60) } }
+    /// Limits from the `[limits]` configuration section.
+    fn default() -> Self {
+        let limits = crate::config::current().limits.clone();
+        Self {
+            ttl: Duration::from_secs(limits.session_abs_secs),
+            idle: Duration::from_secs(limits.session_idle_secs),
+            max_per_user: limits.max_sessions_per_user as usize,
+        }
+    }
 }
 
 impl SessionManager {
@@ -54,65 +110,145 @@ impl SessionManager {
             issued_at: now,
             expires_at: now + self.ttl,
         };
-        let entry = SessionEntry { session: sess.clone() };
-        {
-            let mut m = SESSIONS.write();
-            m.insert(token.clone(), entry);
-        }
-        {
-            let mut uidx = USER_INDEX.write();
-            let set = uidx.entry(principal.user_id.clone()).or_insert_with(HashSet::new);
-            set.insert(token.clone());
-        }
+        self.evict_for(&principal.user_id);
+        let ms = now_ms();
+        SESSIONS.write().insert(token.clone(), SessionEntry { session: sess.clone(), last_seen: now, issued_ms: ms, last_seen_ms: ms });
+        USER_INDEX.write().entry(principal.user_id.clone()).or_default().insert(token.clone());
+        SID_INDEX.write().insert(sid.clone(), token);
+        audit("login", &sess, "");
         tprintln!("session.issue user={} sid={} ttl_secs={}", principal.user_id, sid, self.ttl.as_secs());
         sess
     }
 
+    // Make room for one more session of `user_id` under the per-user cap, oldest first
+    fn evict_for(&self, user_id: &str) {
+        self.prune();
+        if self.max_per_user == 0 { return; }
+        let mut mine: Vec<(Instant, String)> = {
+            let idx = USER_INDEX.read();
+            let map = SESSIONS.read();
+            idx.get(user_id).map(|set| set.iter().filter_map(|t| map.get(t).map(|e| (e.session.issued_at, t.clone()))).collect()).unwrap_or_default()
+        };
+        if mine.len() < self.max_per_user { return; }
+        mine.sort();
+        let excess = mine.len() + 1 - self.max_per_user;
+        for (_, token) in mine.into_iter().take(excess) {
+            if let Some(s) = remove_token(&token) {
+                audit("evict", &s, &format!("max_sessions_per_user={}", self.max_per_user));
+                end_backend(&s.session_id);
+            }
+        }
+    }
+
+    fn expired(&self, ent: &SessionEntry, now: Instant) -> bool {
+        now >= ent.session.expires_at || now.duration_since(ent.last_seen) > self.idle
+    }
+
+    /// Drop expired sessions.
+    pub fn prune(&self) {
+        let now = Instant::now();
+        let stale: Vec<String> = SESSIONS.read().iter().filter(|(_, e)| self.expired(e, now)).map(|(t, _)| t.clone()).collect();
+        for token in stale {
+            if let Some(s) = remove_token(&token) { audit("expire", &s, ""); }
+        }
+    }
+
+    /// Principal of a live session token, marking the session as used.
     pub fn validate(&self, token: &str) -> Option<Principal> {
-        // prune revoked
         if REVOKED.read().contains(token) { return None; }
         let now = Instant::now();
-        let mut drop_key: Option<String> = None;
-        let out = {
-            let map = SESSIONS.read();
-            if let Some(ent) = map.get(token) {
-                if ent.session.expires_at > now {
-                    Some(ent.session.principal.clone())
-                } else {
-                    drop_key = Some(token.to_string());
-                    None
-                }
-            } else { None }
-        };
-        if let Some(k) = drop_key {
-            SESSIONS.write().remove(&k);
+        {
+            let mut map = SESSIONS.write();
+            let ent = map.get_mut(token)?;
+            if !self.expired(ent, now) {
+                ent.last_seen = now;
+                ent.last_seen_ms = now_ms();
+                return Some(ent.session.principal.clone());
+            }
         }
-        out
+        if let Some(s) = remove_token(token) { audit("expire", &s, ""); }
+        None
+    }
+
+    /// Like [`validate`](Self::validate), by session id (the HTTP cookie).
+    pub fn touch(&self, session_id: &str) -> Option<Principal> {
+        let token = SID_INDEX.read().get(session_id).cloned()?;
+        self.validate(&token)
     }
 
     pub fn logout(&self, token: &str) -> bool {
-        let mut removed = false;
-        if let Some(ent) = SESSIONS.write().remove(token) {
-            removed = true;
-            let uid = ent.session.principal.user_id;
-            let mut idx = USER_INDEX.write();
-            if let Some(set) = idx.get_mut(&uid) { set.remove(token); }
-            REVOKED.write().insert(token.to_string());
+        match remove_token(token) {
+            Some(s) => { audit("logout", &s, ""); true }
+            None => false,
         }
-        removed
     }
 
-    pub fn revoke_user(&self, user_id: &str) -> usize {
+    /// Log out by session id.
+    pub fn logout_session(&self, session_id: &str) -> bool {
+        let Some(token) = SID_INDEX.read().get(session_id).cloned() else { return false };
+        self.logout(&token)
+    }
+
+    /// Re-key a session after a privilege change (the HTTP frontend rotates its cookie).
+    pub fn rotate(&self, old_sid: &str, new_sid: &str) -> bool {
+        let Some(token) = SID_INDEX.write().remove(old_sid) else { return false };
+        if let Some(ent) = SESSIONS.write().get_mut(&token) { ent.session.session_id = new_sid.to_string(); }
+        SID_INDEX.write().insert(new_sid.to_string(), token);
+        true
+    }
+
+    /// End one session (admin action); its pgwire connection or HTTP session is closed too.
+    pub fn revoke_session(&self, session_id: &str, by: Option<&str>) -> bool {
+        let Some(token) = SID_INDEX.read().get(session_id).cloned() else { return false };
+        let Some(s) = remove_token(&token) else { return false };
+        audit("revoke", &s, &format!("by={}", by.unwrap_or("-")));
+        end_backend(&s.session_id);
+        true
+    }
+
+    /// End every session of `user_id`; returns how many were ended.
+    pub fn revoke_user(&self, user_id: &str, by: Option<&str>) -> usize {
+        let tokens: Vec<String> = USER_INDEX.read().iter()
+            .filter(|(u, _)| u.eq_ignore_ascii_case(user_id))
+            .flat_map(|(_, set)| set.iter().cloned())
+            .collect();
         let mut count = 0usize;
-        if let Some(tokens) = USER_INDEX.read().get(user_id).cloned() {
-            let mut s = SESSIONS.write();
-            let mut r = REVOKED.write();
-            for t in tokens.iter() {
-                if s.remove(t).is_some() { count += 1; }
-                r.insert(t.clone());
+        for t in tokens.iter() {
+            if let Some(s) = remove_token(t) {
+                audit("revoke", &s, &format!("by={}", by.unwrap_or("-")));
+                end_backend(&s.session_id);
+                count += 1;
             }
         }
         tprintln!("session.revoke user={} count={}", user_id, count);
         count
     }
+
+    /// Live sessions, oldest first.
+    pub fn list(&self) -> Vec<SessionInfo> {
+        self.prune();
+        let now = Instant::now();
+        let now_wall = now_ms();
+        let mut out: Vec<SessionInfo> = SESSIONS.read().values().map(|e| {
+            let idle_left = self.idle.saturating_sub(now.duration_since(e.last_seen));
+            let abs_left = e.session.expires_at.saturating_duration_since(now);
+            SessionInfo {
+                session_id: e.session.session_id.clone(),
+                user_id: e.session.principal.user_id.clone(),
+                client_addr: e.session.principal.attrs.ip.clone(),
+                issued_at: e.issued_ms,
+                last_seen: e.last_seen_ms,
+                expires_at: now_wall + idle_left.min(abs_left).as_millis() as i64,
+            }
+        }).collect();
+        out.sort_by(|a, b| a.issued_at.cmp(&b.issued_at).then_with(|| a.session_id.cmp(&b.session_id)));
+        out
+    }
+}
+
+// Close the pgwire connection or HTTP session backed by a session that just ended
+fn end_backend(session_id: &str) {
+    if let Some(pid) = crate::server::activity::session_pid(session_id) {
+        crate::server::activity::terminate_backend(pid);
+    }
 }
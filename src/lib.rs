@@ -9,11 +9,15 @@ pub mod system_paths;
 pub mod ident;
 pub mod error;
 pub mod lua_bc;
+pub mod script_stats;
 #[cfg(feature = "pgwire")]
 pub mod pgwire_server;
 pub mod system_views;
 pub mod tools;
 pub mod cli;
+pub mod telemetry;
+pub mod config;
+pub mod memory;
 
 // Test-only printing helper: expands to tprintln! during tests and is absent otherwise.
 // Usage in tests: tprintln!("debug: {}", value);
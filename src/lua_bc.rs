@@ -1,7 +1,12 @@
 //! Lua bytecode cache with in-memory L1 and KV-backed L2 persistence.
 //! Focus: high performance, minimal serialization (raw bytes in KV).
+//!
+//! Scripts loaded from the schema folders also keep their compiled chunk in a `<name>.luac`
+//! sidecar next to the `.lua` source. The sidecar starts with the source hash, so an edited
+//! script is recompiled (and its sidecar rewritten) on the next load.
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc};
 use once_cell::sync::OnceCell;
 use parking_lot::{Mutex, RwLock};
@@ -119,6 +124,55 @@ impl LuaBytecodeCache {
         Ok(arc)
     }
 
+    /// Bytecode for a registry script, compiled at most once per source text. Debug info is
+    /// kept so runtime errors still carry line numbers.
+    pub fn compiled(&self, name: &str, source: &str) -> Result<Arc<Vec<u8>>> {
+        let (key, si) = Self::local_key(name, source);
+        if let Some(e) = self.shards[si].map.read().get(&key).cloned() { return Ok(e.bytes); }
+        let _g = self.compile_locks[Self::lock_idx(&key.name)].lock();
+        if let Some(e) = self.shards[si].map.read().get(&key).cloned() { return Ok(e.bytes); }
+        let bytes = Arc::new(Self::compile_dump(name, source, false)?);
+        self.shards[si].map.write().insert(key, Entry{ bytes: bytes.clone(), size: bytes.len() });
+        Ok(bytes)
+    }
+
+    /// Like [`compiled`](Self::compiled) for a script file: a `.luac` sidecar whose hash matches
+    /// the source is used as is, otherwise the script is compiled and the sidecar rewritten.
+    /// Sidecar write failures (read-only script folders) are not errors.
+    pub fn load_or_compile_file(&self, name: &str, script: &Path, source: &str) -> Result<Arc<Vec<u8>>> {
+        let (key, si) = Self::local_key(name, source);
+        if let Some(e) = self.shards[si].map.read().get(&key).cloned() { return Ok(e.bytes); }
+        let sidecar = sidecar_path(script);
+        let bytes = match read_sidecar(&sidecar, &key.hash) {
+            Some(b) => Arc::new(b),
+            None => {
+                let b = self.compiled(name, source)?;
+                if let Err(e) = write_sidecar(&sidecar, &key.hash, &b) {
+                    tracing::debug!(target: "clarium::udf", "[LUA BC] could not write '{}': {}", sidecar.display(), e);
+                }
+                return Ok(b);
+            }
+        };
+        self.shards[si].map.write().insert(key, Entry{ bytes: bytes.clone(), size: bytes.len() });
+        Ok(bytes)
+    }
+
+    /// Make `bytes` (compiled from `source`) the cached bytecode of `name` as well, e.g. for the
+    /// `pg_catalog.<name>` alias of a global script.
+    pub fn seed(&self, name: &str, source: &str, bytes: Arc<Vec<u8>>) {
+        let (key, si) = Self::local_key(name, source);
+        let size = bytes.len();
+        self.shards[si].map.write().insert(key, Entry{ bytes, size });
+    }
+
+    fn local_key(name: &str, source: &str) -> (CacheKey, usize) {
+        let norm = crate::scripts::ScriptRegistry::norm(name);
+        let abi = Self::abi_salt();
+        let hash = Self::source_hash(&abi, false, source);
+        let si = Self::shard_idx(&norm);
+        (CacheKey { name: norm, hash, abi }, si)
+    }
+
     fn compile_dump(name: &str, source: &str, strip_debug: bool) -> Result<Vec<u8>> {
         use mlua::Lua;
         let lua = Lua::new();
@@ -161,6 +215,32 @@ impl LuaBytecodeCache {
     }
 }
 
+const SIDECAR_MAGIC: &[u8] = b"CLBC";
+
+/// Path of the compiled sidecar for a `.lua` script.
+pub fn sidecar_path(script: &Path) -> PathBuf { script.with_extension("luac") }
+
+/// Sidecar layout: magic, the 16 hex digit source hash, a newline, then the bytecode.
+fn read_sidecar(path: &Path, hash: &str) -> Option<Vec<u8>> {
+    let data = std::fs::read(path).ok()?;
+    let header = SIDECAR_MAGIC.len() + hash.len() + 1;
+    if data.len() <= header || !data.starts_with(SIDECAR_MAGIC) { return None; }
+    if &data[SIDECAR_MAGIC.len()..header - 1] != hash.as_bytes() || data[header - 1] != b'\n' { return None; }
+    Some(data[header..].to_vec())
+}
+
+fn write_sidecar(path: &Path, hash: &str, bytes: &[u8]) -> Result<()> {
+    let mut data = Vec::with_capacity(SIDECAR_MAGIC.len() + hash.len() + 1 + bytes.len());
+    data.extend_from_slice(SIDECAR_MAGIC);
+    data.extend_from_slice(hash.as_bytes());
+    data.push(b'\n');
+    data.extend_from_slice(bytes);
+    let tmp = path.with_extension("luac.tmp");
+    std::fs::write(&tmp, &data)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
 /// Default KV placement for script bytecode cache when no explicit store is provided.
 pub const DEFAULT_DB: &str = "clarium";
 pub const DEFAULT_KV_STORE: &str = "__scripts";
@@ -0,0 +1,205 @@
+//!
+//! clarium memory governor
+//! -----------------------
+//! Per-query memory accounting, the `work_mem` cap and the server-wide memory budget.
+//!
+//! Every statement runs through [`run_query`], which gives it a [`QueryMemory`] tracker.
+//! Storage reads ([`charge_read`]) and SELECT stage outputs ([`charge_stage`]) are
+//! charged to it; its peak is what the query holds against the global budget.
+//!
+//! - `work_mem` (`SET work_mem = '64MB'`, default `limits.work_mem_mb`): when the input
+//!   of an ORDER BY or GROUP BY is larger, the stage spills to disk instead of running
+//!   in memory (see `server::exec::spill`).
+//! - `limits.memory_budget_mb`: while running queries hold the whole budget, new ones
+//!   wait up to `limits.memory_queue_ms` and are then rejected. A query whose own usage
+//!   grows past the budget fails with an out-of-memory error.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+const MB: u64 = 1024 * 1024;
+
+/// Memory accounting for one running statement.
+#[derive(Debug, Default)]
+pub struct QueryMemory {
+    /// Effective `work_mem` in bytes; 0 = unlimited
+    pub work_mem: u64,
+    /// Bytes read from storage so far
+    read: AtomicU64,
+    /// Largest amount held at once (storage reads or a stage's output)
+    peak: AtomicU64,
+    /// Bytes written to spill files
+    spilled: AtomicU64,
+}
+
+impl QueryMemory {
+    pub fn peak(&self) -> u64 { self.peak.load(Ordering::Relaxed) }
+    pub fn spilled(&self) -> u64 { self.spilled.load(Ordering::Relaxed) }
+}
+
+#[derive(Default)]
+struct Governor {
+    running: Mutex<Vec<Arc<QueryMemory>>>,
+    released: Notify,
+    queued: AtomicU64,
+    rejected: AtomicU64,
+    spilled: AtomicU64,
+}
+
+static GOVERNOR: Lazy<Governor> = Lazy::new(Governor::default);
+
+thread_local! {
+    // Tracker of the statement being polled on this thread (set per poll by run_query)
+    static TLS_QUERY: RefCell<Option<Arc<QueryMemory>>> = const { RefCell::new(None) };
+}
+
+/// Counters reported by the governor.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryStats {
+    pub budget_bytes: u64,
+    pub in_use_bytes: u64,
+    pub running: usize,
+    pub queued_total: u64,
+    pub rejected_total: u64,
+    pub spilled_bytes_total: u64,
+}
+
+/// `work_mem` in bytes for statements started on this thread (0 = unlimited).
+pub fn work_mem() -> u64 {
+    crate::system::work_mem_override().unwrap_or_else(|| crate::config::current().limits.work_mem_mb.saturating_mul(MB))
+}
+
+/// Server-wide memory budget in bytes (0 = unlimited).
+pub fn budget() -> u64 { crate::config::current().limits.memory_budget_mb.saturating_mul(MB) }
+
+fn in_use() -> u64 { GOVERNOR.running.lock().iter().map(|q| q.peak()).sum() }
+
+pub fn stats() -> MemoryStats {
+    let running = GOVERNOR.running.lock();
+    MemoryStats {
+        budget_bytes: budget(),
+        in_use_bytes: running.iter().map(|q| q.peak()).sum(),
+        running: running.len(),
+        queued_total: GOVERNOR.queued.load(Ordering::Relaxed),
+        rejected_total: GOVERNOR.rejected.load(Ordering::Relaxed),
+        spilled_bytes_total: GOVERNOR.spilled.load(Ordering::Relaxed),
+    }
+}
+
+/// Tracker of the statement executing on this thread.
+pub fn current() -> Option<Arc<QueryMemory>> { TLS_QUERY.with(|c| c.borrow().clone()) }
+
+/// Error returned when a query cannot get, or outgrows, the memory budget.
+#[derive(Debug)]
+pub struct OutOfMemory { pub detail: String }
+
+impl std::fmt::Display for OutOfMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "out of memory: {}", self.detail)
+    }
+}
+
+impl std::error::Error for OutOfMemory {}
+
+pub fn is_out_of_memory_error(e: &anyhow::Error) -> bool { e.downcast_ref::<OutOfMemory>().is_some() }
+
+fn oom(detail: String) -> anyhow::Error { anyhow::Error::new(OutOfMemory { detail }) }
+
+/// Wait until running queries hold less than the budget, or give up after `limits.memory_queue_ms`.
+async fn admit() -> Result<()> {
+    let budget = budget();
+    if budget == 0 { return Ok(()); }
+    let wait = Duration::from_millis(crate::config::current().limits.memory_queue_ms);
+    let deadline = Instant::now() + wait;
+    let mut counted = false;
+    loop {
+        let notified = GOVERNOR.released.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        let used = in_use();
+        if used < budget { return Ok(()); }
+        let now = Instant::now();
+        if now >= deadline {
+            GOVERNOR.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(oom(format!("server memory budget of {} bytes is in use ({} bytes held); query rejected after waiting {} ms", budget, used, wait.as_millis())));
+        }
+        if !counted {
+            GOVERNOR.queued.fetch_add(1, Ordering::Relaxed);
+            counted = true;
+            crate::tprintln!("[memory] query queued: in_use={} budget={}", used, budget);
+        }
+        let _ = tokio::time::timeout(deadline - now, notified).await;
+    }
+}
+
+struct Running(Arc<QueryMemory>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        GOVERNOR.running.lock().retain(|q| !Arc::ptr_eq(q, &self.0));
+        GOVERNOR.released.notify_waiters();
+    }
+}
+
+/// Run `fut` as a tracked statement: admit it against the memory budget, then charge
+/// its reads and stages to a fresh [`QueryMemory`]. Nested statements share the outer tracker.
+pub async fn run_query<T, F>(fut: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    if current().is_some() { return fut.await; }
+    admit().await?;
+    let tracker = Arc::new(QueryMemory { work_mem: work_mem(), ..Default::default() });
+    GOVERNOR.running.lock().push(tracker.clone());
+    let _running = Running(tracker.clone());
+    let mut fut = Box::pin(fut);
+    std::future::poll_fn(|cx| {
+        let prev = TLS_QUERY.with(|c| c.replace(Some(tracker.clone())));
+        let r = fut.as_mut().poll(cx);
+        TLS_QUERY.with(|c| *c.borrow_mut() = prev);
+        r
+    }).await
+}
+
+fn raise_peak(q: &QueryMemory, bytes: u64) -> Result<()> {
+    let peak = q.peak.fetch_max(bytes, Ordering::Relaxed).max(bytes);
+    let budget = budget();
+    if budget > 0 && peak > budget {
+        return Err(oom(format!("query holds {} bytes, exceeding the server memory budget of {} bytes", peak, budget)));
+    }
+    Ok(())
+}
+
+/// Charge `bytes` read from storage to the current statement.
+pub fn charge_read(bytes: usize) -> Result<()> {
+    let Some(q) = current() else { return Ok(()) };
+    let total = q.read.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+    raise_peak(&q, total)
+}
+
+/// Charge the output of a SELECT stage (`bytes` held at once) to the current statement.
+pub fn charge_stage(bytes: usize) -> Result<()> {
+    let Some(q) = current() else { return Ok(()) };
+    raise_peak(&q, bytes as u64)
+}
+
+/// `work_mem` of the current statement when an operator input of `bytes` must spill.
+pub fn spill_threshold(bytes: usize) -> Option<u64> {
+    let q = current()?;
+    (q.work_mem > 0 && bytes as u64 > q.work_mem).then_some(q.work_mem)
+}
+
+/// Record bytes written to spill files by the current statement.
+pub fn note_spill(bytes: u64) {
+    if let Some(q) = current() { q.spilled.fetch_add(bytes, Ordering::Relaxed); }
+    GOVERNOR.spilled.fetch_add(bytes, Ordering::Relaxed);
+}
@@ -1,8 +1,10 @@
 //! Experimental pgwire server integration (feature-gated).
 //! Minimal PostgreSQL wire-protocol handler supporting:
-//! - Startup (no auth), simple query flow
+//! - Startup with optional TLS (SSLRequest), password auth, simple query flow
 //! - SELECT: delegates to existing query engine and streams rows
 //! - INSERT: basic INSERT INTO <db>(col, ...) VALUES (...)
+//! - COPY ... FROM STDIN / TO STDOUT: CopyIn and CopyOut sub-protocols
+//! - DECLARE ... CURSOR / FETCH / MOVE / CLOSE (see cursor.rs)
 
 use anyhow::{anyhow, Result};
 use std::net::SocketAddr;
@@ -10,6 +12,7 @@ use std::sync::atomic::Ordering;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::watch;
 use tracing::{error, info, debug, warn};
+use crate::pgwire_server::cursor::*;
 use crate::pgwire_server::encodedecode::*;
 use crate::pgwire_server::inline::*;
 use crate::pgwire_server::misc::*;
@@ -18,18 +21,21 @@ use crate::pgwire_server::parse::*;
 use crate::pgwire_server::security::*;
 use crate::pgwire_server::send::*;
 use crate::pgwire_server::structs::*;
+use crate::pgwire_server::tls::*;
 
 use crate::tprintln;
 
 use crate::{storage::SharedStore, server::exec};
-use crate::identity::{SessionManager, LoginRequest, RequestContext};
+use crate::identity::{SessionManager, LoginRequest, LocalAuthProvider};
 use crate::identity::login_via_sql;
 use crate::server::query::{self, Command};
-use crate::server::exec::exec_select::handle_select;
+use crate::server::exec::exec_select::handle_select_with_ctx;
+use crate::server::activity;
+use crate::error::AppError;
 use polars::prelude::AnyValue;
-use crate::ident::{DEFAULT_DB, DEFAULT_SCHEMA};
 use std::collections::HashMap;
 
+pub mod cursor;
 pub mod encodedecode;
 pub mod inline;
 pub mod misc;
@@ -38,6 +44,7 @@ pub mod parse;
 pub mod security;
 pub mod send;
 pub mod structs;
+pub mod tls;
 
 
 
@@ -55,8 +62,13 @@ pub async fn start_pgwire(store: SharedStore, bind: &str, mut shutdown: watch::R
     // Ensure DDL installer runs and physical checks are performed once at startup
     // Best-effort: continue serving even if installer reports errors; they are logged.
     let _ = crate::tools::installer::ensure_installed(&store).await;
+    let server_cfg = crate::config::current().server.clone();
+    let tls = load_acceptor(&server_cfg.pgwire_tls_cert, &server_cfg.pgwire_tls_key)?;
+    if tls.is_none() && server_cfg.pgwire_tls_require {
+        warn!(target: "pgwire", "server.pgwire_tls_require is set but no certificate is configured; every connection will be rejected");
+    }
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    info!("pgwire listening on {}", addr);
+    info!("pgwire listening on {} (tls={})", addr, if tls.is_some() { "enabled" } else { "disabled" });
     loop {
         tokio::select! {
             biased;
@@ -67,13 +79,14 @@ pub async fn start_pgwire(store: SharedStore, bind: &str, mut shutdown: watch::R
                 }
             }
             accept_res = listener.accept() => {
-                let (mut socket, peer) = match accept_res { Ok(v) => v, Err(e) => { error!(target: "pgwire", "accept error: {}", e); continue; } };
+                let (socket, peer) = match accept_res { Ok(v) => v, Err(e) => { error!(target: "pgwire", "accept error: {}", e); continue; } };
                 let store = store.clone();
+                let tls = tls.clone();
                 let conn_id = CONN_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
                 tokio::spawn(async move {
-                    if let Err(e) = handle_conn(&mut socket, store, conn_id, &peer.to_string()).await {
+                    // The connection is closed when the stream is dropped
+                    if let Err(e) = handle_conn(PgStream::Plain(socket), store, conn_id, &peer.to_string(), tls.as_ref()).await {
                         error!(target: "pgwire", "conn_id={} peer={} error: {}", conn_id, peer, e);
-                        let _ = socket.shutdown();
                     }
                 });
             }
@@ -85,15 +98,15 @@ pub async fn start_pgwire(store: SharedStore, bind: &str, mut shutdown: watch::R
 
 
 
-async fn handle_conn(socket: &mut tokio::net::TcpStream, store: SharedStore, conn_id: u64, peer: &str) -> Result<()> {
+async fn handle_conn(mut socket: PgStream, store: SharedStore, conn_id: u64, peer: &str, tls: Option<&TlsAcceptor>) -> Result<()> {
     tprintln!("[pgwire] conn_id={} new connection established from {}", conn_id, peer);
     #[inline]
     fn env_default_db() -> String {
-        std::env::var("CLARIUM_DEFAULT_DB").unwrap_or_else(|_| DEFAULT_DB.to_string())
+        crate::config::current().server.default_db.clone()
     }
     #[inline]
     fn env_default_schema() -> String {
-        std::env::var("CLARIUM_DEFAULT_SCHEMA").unwrap_or_else(|_| DEFAULT_SCHEMA.to_string())
+        crate::config::current().server.default_schema.clone()
     }
     // Trust mode for dev/test: when enabled via env, skip password auth entirely
     fn pgwire_trust_enabled() -> bool {
@@ -103,7 +116,7 @@ async fn handle_conn(socket: &mut tokio::net::TcpStream, store: SharedStore, con
         }).unwrap_or(false)
     }
     // Startup packet
-    let len = read_u32(socket).await?;
+    let len = read_u32(&mut socket).await?;
     let mut buf = vec![0u8; (len - 4) as usize];
     socket.read_exact(&mut buf).await?;
     if pgwire_trace_enabled() {
@@ -111,106 +124,116 @@ async fn handle_conn(socket: &mut tokio::net::TcpStream, store: SharedStore, con
     } else {
         tprintln!("[pgwire] conn_id={} received startup packet, len={}", conn_id, len);
     }
-    // Check for SSLRequest (0x04D2162F) or GSSENC (0x04D2162A)
-    if buf.len() == 4 {
+    // SSLRequest (80877103) or GSSENCRequest (80877104) come before the real StartupMessage;
+    // libpq may send GSSENC first and fall back to SSL when refused
+    while buf.len() == 4 {
         let code = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
-        // Respond 'N' to refuse SSL/GSS, then expect new StartupMessage
-        if code == 80877103 || code == 80877104 {
-            debug!(target: "pgwire", "conn_id={} SSL/GSSENC request detected (code={}), refusing with 'N'", conn_id, code);
-            socket.write_all(b"N").await?;
-            // Read actual startup
-            let len2 = read_u32(socket).await?;
-            let mut buf2 = vec![0u8; (len2 - 4) as usize];
-            socket.read_exact(&mut buf2).await?;
-            let params = parse_startup_params(&buf2);
-            let user = params.get("user").cloned().unwrap_or_else(|| "".to_string());
-            debug!(target: "pgwire", "conn_id={} startup params parsed, user='{}' (keys={:?})", conn_id, user, params.keys().collect::<Vec<_>>() );
-            // Request cleartext password
-            if !pgwire_trust_enabled() {
-                request_password(socket).await?;
-                let password = read_password_message(socket).await?;
-                debug!(target: "pgwire", "conn_id={} password received, authenticating user '{}'", conn_id, user);
-                let lr = LoginRequest { username: user.clone(), password: password.clone(), db: None, ip: Some(peer.to_string()) };
-                match login_via_sql(&store, &SessionManager::default(), &lr).await {
-                    Ok(resp) => {
-                        debug!(target: "pgwire", "conn_id={} login successful for user '{}' (sid={})", conn_id, user, resp.session.session_id);
-                        // Initialize session state honoring dbname/database if provided
-                        let db = params.get("database").cloned()
-                            .or_else(|| params.get("dbname").cloned())
-                            .unwrap_or_else(|| env_default_db());
-                        let mut state = ConnState { current_database: db, current_schema: env_default_schema(), statements: HashMap::new(), portals: HashMap::new(), in_error: false, in_tx: false, principal: Some(resp.session.principal.clone()), session_token: Some(resp.session.token.clone()) };
-                        send_auth_ok_and_params(socket, &params).await?;
-                        run_query_loop(socket, &store, &user, &mut state, conn_id).await?;
-                        return Ok(());
-                    }
-                    Err(e) => {
-                        debug!(target: "pgwire", "conn_id={} authentication failed for user '{}' ({})", conn_id, user, e);
-                        send_error(socket, "authentication failed").await?; 
-                        return Ok(());
-                    }
-                }
-            } else {
-                debug!(target: "pgwire", "conn_id={} TRUST mode enabled via CLARIUM_PGWIRE_TRUST; skipping password auth for user '{}'", conn_id, user);
-                // Initialize state without a principal (trust mode)
-                send_auth_ok_and_params(socket, &params).await?;
-                let db = params.get("database").cloned()
-                    .or_else(|| params.get("dbname").cloned())
-                    .unwrap_or_else(|| env_default_db());
-                let mut state = ConnState { current_database: db, current_schema: env_default_schema(), statements: HashMap::new(), portals: HashMap::new(), in_error: false, in_tx: false, principal: None, session_token: None };
-                run_query_loop(socket, &store, &user, &mut state, conn_id).await?;
+        match (code, tls) {
+            (80877103, Some(acceptor)) if !socket.is_tls() => {
+                debug!(target: "pgwire", "conn_id={} SSL request detected, accepting with 'S'", conn_id);
+                socket.write_all(b"S").await?;
+                socket.flush().await?;
+                socket = socket.start_tls(acceptor).await?;
+                debug!(target: "pgwire", "conn_id={} TLS handshake complete", conn_id);
+            }
+            (80877103, _) | (80877104, _) => {
+                // Respond 'N' to refuse SSL/GSS, then expect new StartupMessage
+                debug!(target: "pgwire", "conn_id={} SSL/GSSENC request detected (code={}), refusing with 'N'", conn_id, code);
+                socket.write_all(b"N").await?;
+            }
+            _ => {
+                send_error_response(&mut socket, &AppError::io("protocol_violation", "unsupported startup request")).await?;
                 return Ok(());
             }
-            // unreachable: handled above
-            
-        } else {
-            // Unknown 4-byte request; continue without auth (shouldn't happen)
-            send_error(socket, "unsupported startup request").await?;
-            Ok(())
         }
+        // Read actual startup
+        let len2 = read_u32(&mut socket).await?;
+        buf = vec![0u8; (len2 - 4) as usize];
+        socket.read_exact(&mut buf).await?;
+    }
+    let socket = &mut socket;
+    if crate::config::current().server.pgwire_tls_require && !socket.is_tls() {
+        debug!(target: "pgwire", "conn_id={} rejecting plaintext startup: TLS is required", conn_id);
+        send_error_response(socket, &AppError::auth("invalid_authorization_specification", "TLS is required: reconnect with sslmode=require")).await?;
+        return Ok(());
+    }
+    // Normal parameter list present
+    let params = parse_startup_params(&buf);
+    let user = params.get("user").cloned().unwrap_or_else(|| "".to_string());
+    debug!(target: "pgwire", "conn_id={} startup (tls={}), user='{}' (keys={:?})", conn_id, socket.is_tls(), user, params.keys().collect::<Vec<_>>() );
+    let method = if pgwire_trust_enabled() {
+        AuthMethod::Trust
     } else {
-        // Normal parameter list present
-        let params = parse_startup_params(&buf);
-        let user = params.get("user").cloned().unwrap_or_else(|| "".to_string());
-        debug!(target: "pgwire", "conn_id={} normal startup (no SSL), user='{}' (keys={:?})", conn_id, user, params.keys().collect::<Vec<_>>() );
-        if !pgwire_trust_enabled() {
+        let ip = socket.peer_addr()?.ip();
+        auth_method_for(&crate::config::current().server.pgwire_auth, ip)?
+    };
+    debug!(target: "pgwire", "conn_id={} auth method {:?} for user '{}'", conn_id, method, user);
+    let db = params.get("database").cloned()
+        .or_else(|| params.get("dbname").cloned())
+        .unwrap_or_else(|| env_default_db());
+    let login = match method {
+        AuthMethod::Trust => {
+            debug!(target: "pgwire", "conn_id={} trust authentication; skipping password auth for user '{}'", conn_id, user);
+            None
+        }
+        AuthMethod::Password => {
             request_password(socket).await?;
             let password = read_password_message(socket).await?;
             debug!(target: "pgwire", "conn_id={} password received, authenticating user '{}'", conn_id, user);
             let lr = LoginRequest { username: user.clone(), password: password.clone(), db: None, ip: Some(peer.to_string()) };
             match login_via_sql(&store, &SessionManager::default(), &lr).await {
-                Ok(resp) => {
-                    debug!(target: "pgwire", "conn_id={} login successful for user '{}' (sid={})", conn_id, user, resp.session.session_id);
-                    send_auth_ok_and_params(socket, &params).await?;
-                    let db = params.get("database").cloned()
-                        .or_else(|| params.get("dbname").cloned())
-                        .unwrap_or_else(|| env_default_db());
-                    let mut state = ConnState { current_database: db, current_schema: env_default_schema(), statements: HashMap::new(), portals: HashMap::new(), in_error: false, in_tx: false, principal: Some(resp.session.principal.clone()), session_token: Some(resp.session.token.clone()) };
-                    run_query_loop(socket, &store, &user, &mut state, conn_id).await?;
-                    return Ok(());
-                }
+                Ok(resp) => Some(resp),
                 Err(e) => {
                     debug!(target: "pgwire", "conn_id={} authentication failed for user '{}' ({})", conn_id, user, e);
-                    send_error(socket, "authentication failed").await?; 
+                    send_error(socket, "authentication failed").await?;
                     return Ok(());
                 }
             }
-        } else {
-            debug!(target: "pgwire", "conn_id={} TRUST mode enabled via CLARIUM_PGWIRE_TRUST; skipping password auth for user '{}'", conn_id, user);
-            send_auth_ok_and_params(socket, &params).await?;
-            let db = params.get("database").cloned()
-                .or_else(|| params.get("dbname").cloned())
-                .unwrap_or_else(|| env_default_db());
-            let mut state = ConnState { current_database: db, current_schema: env_default_schema(), statements: HashMap::new(), portals: HashMap::new(), in_error: false, in_tx: false, principal: None, session_token: None };
-            run_query_loop(socket, &store, &user, &mut state, conn_id).await?;
-            return Ok(());
         }
+        AuthMethod::ScramSha256 | AuthMethod::Md5 => {
+            let provider = LocalAuthProvider::new(store.root_path().to_string_lossy().into_owned(), SessionManager::default());
+            if let Err(e) = crate::identity::check_lockout(&user) {
+                debug!(target: "pgwire", "conn_id={} {}", conn_id, e);
+                send_error(socket, "authentication failed").await?;
+                return Ok(());
+            }
+            let ok = if method == AuthMethod::Md5 {
+                authenticate_md5(socket, &provider, &user).await?
+            } else {
+                authenticate_scram(socket, &provider, &user).await?
+            };
+            if !ok {
+                crate::identity::record_login_failure(&user);
+                debug!(target: "pgwire", "conn_id={} {:?} authentication failed for user '{}'", conn_id, method, user);
+                send_error(socket, "authentication failed").await?;
+                return Ok(());
+            }
+            crate::identity::record_login_success(&user);
+            Some(provider.login_verified(&user, Some(&db), Some(peer.to_string())))
+        }
+    };
+    if let Some(resp) = &login {
+        debug!(target: "pgwire", "conn_id={} login successful for user '{}' (sid={})", conn_id, user, resp.session.session_id);
     }
+    let reported = crate::server::guc::start_session(&store, &db, &params);
+    send_auth_ok_and_params(socket, &params, &reported).await?;
+    let mut state = ConnState {
+        current_database: db, current_schema: env_default_schema(), statements: HashMap::new(), portals: HashMap::new(),
+        cursors: HashMap::new(), in_error: false, skip_until_sync: false, in_tx: false,
+        principal: login.as_ref().map(|r| r.session.principal.clone()), session_id: login.as_ref().map(|r| r.session.session_id.clone()),
+        session_token: login.map(|r| r.session.token), backend_pid: 0,
+    };
+    let res = run_query_loop(socket, &store, &user, &mut state, conn_id).await;
+    if let Some(token) = &state.session_token { SessionManager::default().logout(token); }
+    res
 }
 
-
-
-async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore, user: &str, state: &mut ConnState, conn_id: u64) -> Result<()> {
+async fn run_query_loop(socket: &mut PgStream, store: &SharedStore, user: &str, state: &mut ConnState, conn_id: u64) -> Result<()> {
     tprintln!("[pgwire] conn_id={} entering query loop for user '{}' (db='{}', schema='{}')", conn_id, user, state.current_database, state.current_schema);
+    // Register the connection in pg_stat_activity for the lifetime of the loop
+    let client_addr = socket.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+    state.backend_pid = activity::register(activity::Frontend::Pgwire, user, &state.current_database, &client_addr, state.session_id.clone());
+    let _backend = activity::BackendGuard(state.backend_pid);
     // Accumulate a simple cycle summary between Sync boundaries to quickly verify message order.
     // Emitted when Sync -> ReadyForQuery completes.
     let mut cycle_summary = String::new();
@@ -219,7 +242,15 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
     let mut last_err: Option<String> = None;
     loop {
         let mut tag = [0u8; 1];
-        match socket.read_exact(&mut tag).await {
+        let read = tokio::select! {
+            r = socket.read_exact(&mut tag) => r,
+            _ = activity::terminated(state.backend_pid) => {
+                tprintln!("[pgwire] conn_id={} pid={} terminated by administrator, closing connection", conn_id, state.backend_pid);
+                let _ = send_error_response(socket, &AppError::io("admin_shutdown", "terminating connection due to administrator command")).await;
+                break;
+            }
+        };
+        match read {
             Ok(_) => {}
             Err(e) => {
                 if !cycle_summary.is_empty() {
@@ -261,6 +292,22 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
             );
             break;
         }
+        // The login session expires after limits.session_idle_secs without a message
+        if let Some(token) = &state.session_token {
+            if SessionManager::default().validate(token).is_none() {
+                tprintln!("[pgwire] conn_id={} session expired, closing connection", conn_id);
+                let _ = send_error_response(socket, &AppError::io("idle_session_timeout", "terminating connection due to session timeout")).await;
+                break;
+            }
+        }
+        // After an error in an extended-protocol batch, discard its remaining messages until Sync
+        if state.skip_until_sync && matches!(tag[0], b'P' | b'B' | b'D' | b'E' | b'C' | b'H') {
+            let len = match read_u32(socket).await { Ok(v) => v, Err(e) => { error!(target:"pgwire", "read_u32 for skipped message failed: {}", e); break; } };
+            let mut skipped = vec![0u8; len.saturating_sub(4) as usize];
+            if let Err(e) = socket.read_exact(&mut skipped).await { error!(target:"pgwire", "read_exact(skipped payload) failed: {}", e); break; }
+            cycle_summary.push_str(&format!("{} skipped; ", tag[0] as char));
+            continue;
+        }
         match tag[0] {
             b'Q' => {
                 tprintln!("[pgwire] conn_id={} handling simple Query message", conn_id);
@@ -269,9 +316,12 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                 if let Err(e) = socket.read_exact(&mut qbuf).await { error!(target:"pgwire", "read_exact(query payload) failed: {}", e); break; }
                 if let Some(pos) = qbuf.iter().position(|&b| b == 0) { qbuf.truncate(pos); }
                 let query_str = String::from_utf8(qbuf).unwrap_or_default();
-                if let Err(e) = handle_query(socket, store, user, state, &query_str).await {
+                let pid = state.backend_pid;
+                if let Err(e) = activity::run_statement(Some(pid), &query_str, handle_query(socket, store, user, state, &query_str)).await {
                     error!(target: "pgwire", "handle_query error: {}", e);
-                    let _ = send_error(socket, &format!("{}", e)).await; state.in_error = true;
+                    let _ = send_mapped_error(socket, &e).await; state.in_error = true;
+                    // A cancelled simple query never reached its own ReadyForQuery
+                    if activity::is_cancelled_error(&e) { let _ = send_ready(socket, state).await; }
                     last_err = Some(e.to_string());
                     cycle_summary.push_str("Q err; ");
                 } else {
@@ -282,7 +332,7 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                 tprintln!("[pgwire] conn_id={} handling Parse message", conn_id);
                 if let Err(e) = handle_parse(socket, state).await {
                     error!(target: "pgwire", "handle_parse error: {}", e);
-                    let _ = send_error(socket, &format!("{}", e)).await; state.in_error = true;
+                    let _ = send_mapped_error(socket, &e).await; state.extended_error();
                     last_err = Some(e.to_string());
                     cycle_summary.push_str("P err; ");
                 } else {
@@ -293,7 +343,7 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                 tprintln!("[pgwire] conn_id={} handling Bind message", conn_id);
                 if let Err(e) = handle_bind(socket, state).await {
                     error!(target: "pgwire", "handle_bind error: {}", e);
-                    let _ = send_error(socket, &format!("{}", e)).await; state.in_error = true;
+                    let _ = send_mapped_error(socket, &e).await; state.extended_error();
                     last_err = Some(e.to_string());
                     cycle_summary.push_str("B err; ");
                 } else {
@@ -304,7 +354,7 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                 tprintln!("[pgwire] conn_id={} handling Describe message", conn_id);
                 if let Err(e) = handle_describe(socket, store, state).await {
                     error!(target: "pgwire", "handle_describe error: {}", e);
-                    let _ = send_error(socket, &format!("{}", e)).await; state.in_error = true;
+                    let _ = send_mapped_error(socket, &e).await; state.extended_error();
                     last_err = Some(e.to_string());
                     cycle_summary.push_str("D err; ");
                 } else {
@@ -313,9 +363,10 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
             }
             b'E' => { // Execute
                 tprintln!("[pgwire] conn_id={} handling Execute message", conn_id);
-                if let Err(e) = handle_execute(socket, store, user, state).await {
+                let pid = state.backend_pid;
+                if let Err(e) = activity::run_statement(Some(pid), "", handle_execute(socket, store, user, state)).await {
                     error!(target: "pgwire", "handle_execute error: {}", e);
-                    let _ = send_error(socket, &format!("{}", e)).await; state.in_error = true;
+                    let _ = send_mapped_error(socket, &e).await; state.extended_error();
                     last_err = Some(e.to_string());
                     cycle_summary.push_str("E err; ");
                 } else {
@@ -336,7 +387,8 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                 let _len = match read_u32(socket).await { Ok(v) => v, Err(e) => { error!(target:"pgwire", "read_u32 for S failed: {}", e); break; } };
                 // Clear error state only if not in an explicit transaction. When in_tx and an
                 // error occurred, the session remains in failed-transaction state until ROLLBACK.
-                if !state.in_tx { state.in_error = false; }
+                if !state.in_tx { state.in_error = false; crate::server::guc::end_transaction(store); }
+                state.skip_until_sync = false;
                 if let Err(e) = send_ready(socket, state).await { error!(target:"pgwire", "send_ready error: {}", e); break; }
                 cycle_summary.push_str("S ready; ");
                 // Emit the summary of this extended-protocol cycle
@@ -349,7 +401,7 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                 tprintln!("[pgwire] conn_id={} handling Close message", conn_id);
                 if let Err(e) = handle_close(socket, state).await {
                     error!(target: "pgwire", "handle_close error: {}", e);
-                    let _ = send_error(socket, &format!("{}", e)).await; state.in_error = true;
+                    let _ = send_mapped_error(socket, &e).await; state.extended_error();
                     last_err = Some(e.to_string());
                     cycle_summary.push_str("C err; ");
                 } else {
@@ -384,7 +436,7 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                         }
                     }
                 }
-                send_error(socket, "unsupported message type").await?;
+                send_error_response(socket, &AppError::exec("protocol_violation", "unsupported message type")).await?;
                 state.in_error = true;
                 last_err = Some("unsupported message type".to_string());
                 cycle_summary.push_str("? err; ");
@@ -397,7 +449,7 @@ async fn run_query_loop(socket: &mut tokio::net::TcpStream, store: &SharedStore,
 
 
 
-async fn write_parameter(socket: &mut tokio::net::TcpStream, k: &str, v: &str) -> Result<()> {
+async fn write_parameter(socket: &mut PgStream, k: &str, v: &str) -> Result<()> {
     socket.write_all(b"S").await?;
     let mut payload = Vec::new();
     payload.extend_from_slice(k.as_bytes()); payload.push(0);
@@ -407,20 +459,107 @@ async fn write_parameter(socket: &mut tokio::net::TcpStream, k: &str, v: &str) -
     Ok(())
 }
 
+/// After SET/RESET, send ParameterStatus for the reported parameters it touched, as
+/// PostgreSQL does, so drivers track e.g. client_encoding, DateStyle and TimeZone.
+async fn report_parameter_change(socket: &mut PgStream, store: &SharedStore, sql: &str) -> Result<()> {
+    let names: Vec<&'static str> = match query::parse(sql) {
+        Ok(Command::Set { variable, .. }) | Ok(Command::Reset { variable: Some(variable) }) => crate::server::guc::lookup(&variable).map(|g| g.name).into_iter().collect(),
+        Ok(Command::Reset { variable: None }) => crate::server::guc::GUCS.iter().map(|g| g.name).collect(),
+        _ => Vec::new(),
+    };
+    let db = crate::system::get_current_database();
+    for def in names.into_iter().filter_map(crate::server::guc::lookup).filter(|g| g.report) {
+        write_parameter(socket, def.name, &crate::server::guc::current_value(store, &db, def).0).await?;
+    }
+    Ok(())
+}
 
-async fn handle_query(socket: &mut tokio::net::TcpStream, store: &SharedStore, _username: &str, state: &mut ConnState, q: &str) -> Result<()> {
+/// Run the CopyIn sub-protocol for COPY ... FROM STDIN: announce CopyInResponse, feed
+/// CopyData frames into the batched ingester until CopyDone/CopyFail, and return the
+/// number of rows imported. After a decode error the remaining frames are drained so
+/// the connection stays in sync before the error is reported.
+async fn handle_copy_in(socket: &mut PgStream, store: &SharedStore, table: &str, columns: Vec<String>, options: query::CopyOptions) -> Result<usize> {
+    let binary = options.format == query::CopyFormat::Binary;
+    let ncols = columns.len();
+    let mut ingest = Some(exec::exec_copy::CopyIngest::new(store, table, columns, options)?);
+    send_copy_response(socket, b'G', binary, ncols).await?;
+    let mut failure: Option<anyhow::Error> = None;
+    loop {
+        let mut tag = [0u8; 1];
+        socket.read_exact(&mut tag).await?;
+        let len = read_u32(socket).await?;
+        let mut body = vec![0u8; (len as usize).saturating_sub(4)];
+        socket.read_exact(&mut body).await?;
+        match tag[0] {
+            b'd' => {
+                if let Some(ing) = ingest.as_mut() {
+                    if let Err(e) = ing.push(&body) { failure = Some(e); ingest = None; }
+                }
+            }
+            b'c' => break,
+            b'f' => {
+                let msg = String::from_utf8_lossy(body.split(|b| *b == 0).next().unwrap_or(&[])).to_string();
+                return Err(anyhow!("COPY from stdin failed: {}", msg));
+            }
+            // Flush/Sync may be interleaved by clients and carry no data
+            b'H' | b'S' => {}
+            other => return Err(anyhow!("unexpected message type '{}' during COPY", other as char)),
+        }
+    }
+    if let Some(e) = failure { return Err(e); }
+    match ingest {
+        Some(ing) => ing.finish(),
+        None => Ok(0),
+    }
+}
+
+/// Run the CopyOut sub-protocol for COPY ... TO STDOUT: announce CopyOutResponse, send one
+/// CopyData frame per row as batches are read (a table one stored chunk at a time), then
+/// CopyDone. Returns the number of rows sent. Errors before the first frame (unknown
+/// table/column, failing query) leave the connection in normal query mode.
+async fn handle_copy_out(socket: &mut PgStream, store: &SharedStore, state: &ConnState, relation: query::CopyRelation, columns: Vec<String>, options: query::CopyOptions) -> Result<usize> {
+    let binary = options.format == query::CopyFormat::Binary;
+    let mut export = match relation {
+        query::CopyRelation::Table(table) => exec::exec_copy::CopyExport::for_table(store, &table, columns, &options)?,
+        query::CopyRelation::Query(sql) => {
+            let q = exec::normalize_query_with_defaults(&sql, &state.current_database, &state.current_schema);
+            match query::parse(&q)? {
+                Command::Select(sel) => exec::exec_copy::CopyExport::for_query(store, handle_select_with_ctx(store, &sel, &state.request_context()).await?.0, &options),
+                _ => return Err(anyhow!("COPY (query) TO STDOUT supports SELECT queries only")),
+            }
+        }
+    };
+    send_copy_response(socket, b'H', binary, export.columns().len()).await?;
+    let mut buf = Vec::new();
+    if binary {
+        send_copy_data(socket, &copy_binary_header()).await?;
+    } else if options.header {
+        encode_copy_header(&mut buf, export.columns(), &options);
+        send_copy_data(socket, &buf).await?;
+    }
+    let mut rows = 0usize;
+    while let Some(df) = export.next_batch()? {
+        let oids: Vec<i32> = df.get_columns().iter().map(|c| map_polars_dtype_to_pg_oid(c.dtype())).collect();
+        for ridx in 0..df.height() {
+            let avs: Vec<AnyValue> = df.get_columns().iter().map(|c| c.as_materialized_series().get(ridx).unwrap_or(AnyValue::Null)).collect();
+            buf.clear();
+            if binary { encode_copy_binary_row(&mut buf, &avs, &oids); } else { encode_copy_text_row(&mut buf, &avs, &options); }
+            send_copy_data(socket, &buf).await?;
+        }
+        rows += df.height();
+    }
+    // Binary trailer: a field count of -1
+    if binary { send_copy_data(socket, &(-1i16).to_be_bytes()).await?; }
+    send_copy_done(socket).await?;
+    Ok(rows)
+}
+
+async fn handle_query(socket: &mut PgStream, store: &SharedStore, _username: &str, state: &mut ConnState, q: &str) -> Result<()> {
     // Simple Query cycle: may contain one or multiple semicolon-separated statements.
     // For each statement: emit RowDescription/DataRow only for SELECT-like; always emit CommandComplete.
     // After processing all statements in the message, emit a single ReadyForQuery.
-    let sql = q;
-    // A very small splitter that respects semicolons and trims whitespace; it does not handle complex cases
-    // like semicolons inside quoted strings (rare in client-generated SQL for our use). Good enough for now.
-    let parts: Vec<String> = sql
-        .split(';')
-        .map(|s| s.trim())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
-        .collect();
+    // Split at top-level semicolons only: quoted text and comments may contain ';'
+    let parts: Vec<String> = query::split_sql_statements(q).into_iter().map(|s| s.to_string()).collect();
     tprintln!("pgwire simple query: {} statement(s)\n {:?}", parts.len(), parts);
     for (idx, stmt) in parts.iter().enumerate() {
         let q_trim = stmt.trim();
@@ -428,14 +567,45 @@ async fn handle_query(socket: &mut tokio::net::TcpStream, store: &SharedStore, _
         // Intercept transaction control and common SHOW/SELECT meta that ORMs send
 
         let q_effective = exec::normalize_query_with_defaults(q_trim, &state.current_database, &state.current_schema);
-        let upper = q_trim.chars().take(32).collect::<String>().to_uppercase();
+        // Statement-kind checks look past leading comments
+        let uncommented = query::strip_sql_comments(q_trim);
+        let head = uncommented.trim();
+        let upper = head.chars().take(32).collect::<String>().to_uppercase();
+        // COPY ... FROM STDIN / TO STDOUT switch the connection into the CopyIn / CopyOut sub-protocol
+        if upper.starts_with("COPY ") {
+            let copied = match query::parse(&q_effective) {
+                // The COPY sub-protocols run here rather than in the executor: authorize them the same way
+                Ok(cmd @ (Command::CopyFrom { source: query::CopySource::Stdin, .. } | Command::CopyTo { .. })) => Some(match exec::exec_authorize::authorize_statement(store, &state.request_context(), &cmd).await {
+                    Err(e) => Err(e),
+                    Ok(()) => match cmd {
+                        Command::CopyFrom { table, columns, options, .. } => handle_copy_in(socket, store, &table, columns, options).await,
+                        Command::CopyTo { relation, columns, options } => handle_copy_out(socket, store, state, relation, columns, options).await,
+                        _ => unreachable!("only COPY FROM STDIN and COPY TO are matched"),
+                    },
+                }),
+                _ => None,
+            };
+            if let Some(res) = copied {
+                match res {
+                    Ok(n) => send_command_complete(socket, &format!("COPY {}", n)).await?,
+                    Err(e) => { send_mapped_error(socket, &e).await?; state.in_error = true; }
+                }
+                continue;
+            }
+        }
+        // Cursors are connection state: DECLARE runs the query, FETCH pages through it
+        if let Some(cmd) = parse_cursor_command(head) {
+            let res = match cmd { Ok(cmd) => run_cursor_command(socket, store, state, cmd, true).await, Err(e) => Err(e) };
+            if let Err(e) = res { send_mapped_error(socket, &e).await?; state.in_error = true; }
+            continue;
+        }
         // Treat SHOW as a row-returning command similar to SELECT for client compatibility
         let is_select_like = upper.starts_with("SELECT") || upper.starts_with("WITH ") || upper.starts_with("SHOW ");
         // Special-case SHOW CURRENT_USER for convenience
         if upper == "SHOW CURRENT_USER" || upper == "SELECT CURRENT_USER" {
             let cols = vec!["current_user".to_string()];
             let oids = vec![PG_TYPE_TEXT];
-            send_row_description(socket, &cols, &oids).await?;
+            send_row_description(socket, &cols, &oids, &[]).await?;
             let who = state.principal.as_ref().map(|p| p.user_id.clone()).unwrap_or_else(|| _username.to_string());
             send_data_row(socket, &vec![Some(who)]).await?;
             send_command_complete(socket, "SELECT 1").await?;
@@ -445,12 +615,12 @@ async fn handle_query(socket: &mut tokio::net::TcpStream, store: &SharedStore, _
             // Use the query engine directly to preserve schema even for empty results
             match query::parse(&q_effective) {
                 Ok(Command::Select(sel)) => {
-                    match handle_select(store, &sel) {
+                    match handle_select_with_ctx(store, &sel, &state.request_context()).await {
                         Ok((df, _into)) => {
                             let cols: Vec<String> = df.get_column_names().into_iter().map(|s| s.to_string()).collect();
                             let oids: Vec<i32> = df.get_columns().iter().map(|s| map_polars_dtype_to_pg_oid(s.dtype())).collect();
                             // Emit RowDescription with columns even if there are no rows
-                            send_row_description(socket, &cols, &oids).await?;
+                            send_row_description(socket, &cols, &oids, &[]).await?;
                             // Emit DataRow frames
                             for row_idx in 0..df.height() {
                                 let mut row: Vec<Option<String>> = Vec::with_capacity(cols.len());
@@ -467,12 +637,12 @@ async fn handle_query(socket: &mut tokio::net::TcpStream, store: &SharedStore, _
                             let tag = format!("SELECT {}", df.height());
                             send_command_complete(socket, &tag).await?;
                         }
-                        Err(e) => { send_error(socket, &format!("{}", e)).await?; state.in_error = true; }
+                        Err(e) => { send_mapped_error(socket, &e).await?; state.in_error = true; }
                     }
                 }
                 Ok(_) | Err(_) => {
                     // Fallback to legacy path
-                    let ctx = RequestContext { principal: state.principal.clone(), request_id: None, database: Some(state.current_database.clone()), filestore: None };
+                    let ctx = state.request_context();
                     match exec::execute_query_safe_with_ctx(store, &q_effective, &ctx).await {
                         Ok(val) => {
                             let (cols, data) = match &val {
@@ -482,41 +652,56 @@ async fn handle_query(socket: &mut tokio::net::TcpStream, store: &SharedStore, _
                             };
                             // Emit RowDescription even if empty; infer OIDs heuristically from first row or default TEXT
                             let oids: Vec<i32> = if let Some(first) = data.first() { first.iter().map(|v| v.as_deref().map(infer_literal_oid_from_value).unwrap_or(PG_TYPE_TEXT)).collect() } else { vec![PG_TYPE_TEXT; cols.len()] };
-                            send_row_description(socket, &cols, &oids).await?;
+                            send_row_description(socket, &cols, &oids, &[]).await?;
                             for row in data.iter() { send_data_row(socket, row).await?; }
                             let tag = format!("SELECT {}", data.len());
                             send_command_complete(socket, &tag).await?;
                         }
-                        Err(e) => { send_error(socket, &format!("{}", e)).await?; state.in_error = true; }
+                        Err(e) => { send_mapped_error(socket, &e).await?; state.in_error = true; }
                     }
                 }
             }
         } else {
-            let ctx = RequestContext { principal: state.principal.clone(), request_id: None, database: Some(state.current_database.clone()), filestore: None };
+            let ctx = state.request_context();
             match exec::execute_query_safe_with_ctx(store, &q_effective, &ctx).await {
                 Ok(val) => {
                     let (_cols, data): (Vec<String>, Vec<Vec<Option<String>>>) = (Vec::new(), Vec::new());
                     let tag = if upper.starts_with("SELECT") { format!("SELECT {}", data.len()) }
                         else if upper.starts_with("CALCULATE") { let saved = match &val { serde_json::Value::Object(m) => m.get("saved").and_then(|v| v.as_u64()).unwrap_or(0), _ => 0 }; format!("CALCULATE {}", saved) }
                         else if upper.starts_with("DELETE") { "DELETE".to_string() }
+                        else if upper.starts_with("COPY") { let copied = val.get("copied").and_then(|v| v.as_u64()).unwrap_or(0); format!("COPY {}", copied) }
                         else if upper.starts_with("SHOW ") { format!("SHOW {}", data.len()) }
                         else if upper.starts_with("SCHEMA") || upper.starts_with("DATABASE") { format!("OK {}", data.len()) }
                         else if upper.starts_with("SET") { "SET".to_string() }
+                else if upper.starts_with("RESET") { "RESET".to_string() }
                         else if upper.starts_with("CREATE TABLE") { "CREATE TABLE".to_string() }
+                        else if upper.starts_with("CREATE ROLE") { "CREATE ROLE".to_string() }
+                        else if upper.starts_with("DROP ROLE") { "DROP ROLE".to_string() }
+                else if upper.starts_with("COMMENT") { "COMMENT".to_string() }
+                        else if upper.starts_with("GRANT") { "GRANT".to_string() }
+                        else if upper.starts_with("REVOKE") { "REVOKE".to_string() }
                         else if data.is_empty() { "OK".to_string() } else { format!("OK {}", data.len()) };
+                    if upper.starts_with("SET") || upper.starts_with("RESET") { report_parameter_change(socket, store, &q_effective).await?; }
+                    // USE DATABASE/SCHEMA and SET search_path move the defaults later statements are qualified with
+                    let head_up = head.to_ascii_uppercase();
+                    if upper.starts_with("USE ") || head_up.contains("SEARCH_PATH") || upper.starts_with("SET SCHEMA") || upper.starts_with("RESET ALL") {
+                        if let Some(db) = crate::system::get_current_database_opt() { state.current_database = db; }
+                        state.current_schema = crate::system::get_current_schema_opt().unwrap_or_else(|| crate::config::current().server.default_schema.clone());
+                    }
                     debug!("pgwire simple query [{}]: CommandComplete tag='{}'", idx, tag);
                     send_command_complete(socket, &tag).await?;
                 }
                 Err(e) => {
                     debug!("pgwire simple query [{}]: error: {}", idx, e);
-                    send_error(socket, &format!("{}", e)).await?;
+                    send_mapped_error(socket, &e).await?;
                     state.in_error = true;
                 }
             }
         }
     }
-    // Finish the Simple Query message cycle
+    // Finish the Simple Query message cycle; outside an explicit transaction it was an implicit one
     crate::tprintln!("pgwire: simple cycle end; in_tx={} in_error={}", state.in_tx, state.in_error);
+    if !state.in_tx { crate::server::guc::end_transaction(store); }
     send_ready(socket, state).await?;
     Ok(())
 }
@@ -561,7 +746,7 @@ fn to_table(rows: Vec<serde_json::Value>) -> Result<(Vec<String>, Vec<Vec<Option
 }
 
 
-async fn handle_bind(socket: &mut tokio::net::TcpStream, state: &mut ConnState) -> Result<()> {
+async fn handle_bind(socket: &mut PgStream, state: &mut ConnState) -> Result<()> {
     let len_total = read_u32(socket).await? as usize;
     let mut buf = vec![0u8; len_total - 4];
     socket.read_exact(&mut buf).await?;
@@ -603,7 +788,7 @@ async fn handle_bind(socket: &mut tokio::net::TcpStream, state: &mut ConnState)
         param_formats.clone()
     } else {
         send_error(socket, "invalid parameter formats").await?;
-        state.in_error = true;
+        state.extended_error();
         return Ok(());
     };
 
@@ -820,10 +1005,9 @@ async fn handle_bind(socket: &mut tokio::net::TcpStream, state: &mut ConnState)
     let n_rfmts = r_i16(&buf, &mut i)? as usize;
     let mut result_formats: Vec<i16> = Vec::with_capacity(n_rfmts);
     for _ in 0..n_rfmts { result_formats.push(r_i16(&buf, &mut i)?); }
-    // We currently only emit text results; honor requested format but we will still send text rows.
 
-    // Store portal
-    let p = Portal { name: portal_name.clone(), stmt_name, params, param_formats, result_formats };
+    // Store portal; binding again replaces any suspended rows of a portal with the same name
+    let p = Portal { name: portal_name.clone(), stmt_name, params, param_formats, result_formats, suspended: None };
     state.portals.insert(portal_name, p);
 
     send_bind_complete(socket).await
@@ -832,22 +1016,41 @@ async fn handle_bind(socket: &mut tokio::net::TcpStream, state: &mut ConnState)
 
 
 
-async fn describe_row_description(socket: &mut tokio::net::TcpStream, store: &SharedStore, state: &ConnState, sql: &str) -> Result<()> {
+// Per-column result format codes from a Bind: none = all text, one = applies to every column.
+fn effective_result_formats(requested: &[i16], ncols: usize) -> Vec<i16> {
+    match requested.len() {
+        0 => vec![0; ncols],
+        1 => vec![requested[0]; ncols],
+        n if n == ncols => requested.to_vec(),
+        _ => vec![0; ncols],
+    }
+}
+
+async fn describe_row_description(socket: &mut PgStream, store: &SharedStore, state: &ConnState, sql: &str, result_formats: &[i16]) -> Result<()> {
     // Attempt to infer column names for SELECT-like statements by delegating to the server
     // executor and deriving a table shape from the first row. For non-SELECT, return NoData.
-    let q = sql.trim();
+    let uncommented = query::strip_sql_comments(sql);
+    let q = uncommented.trim();
     let up = q.to_uppercase();
+    // FETCH returns the rows of a declared cursor
+    if let Some(Ok(CursorCommand::Fetch { name, move_only: false, .. })) = parse_cursor_command(q) {
+        if let Some(c) = state.cursors.get(&name) {
+            return send_row_description(socket, &c.columns, &c.oids, &c.formats()).await;
+        }
+        return send_no_data(socket).await;
+    }
     if up.starts_with("SELECT") || up.starts_with("WITH ") || up.starts_with("SHOW ") {
         // Normalize and try to parse into a SELECT to retrieve the output schema
         let q_eff = exec::normalize_query_with_defaults(q, &state.current_database, &state.current_schema);
         match query::parse(&q_eff) {
             Ok(Command::Select(sel)) => {
-                match handle_select(store, &sel) {
+                match handle_select_with_ctx(store, &sel, &state.request_context()).await {
                     Ok((df, _into)) => {
                         let cols: Vec<String> = df.get_column_names().into_iter().map(|s| s.to_string()).collect();
                         let oids: Vec<i32> = df.get_columns().iter().map(|s| map_polars_dtype_to_pg_oid(s.dtype())).collect();
-                        // Always send RowDescription for SELECT-like statements
-                        return send_row_description(socket, &cols, &oids).await;
+                        // Always send RowDescription for SELECT-like statements; a bound portal reports its result formats
+                        let fmts = effective_result_formats(result_formats, cols.len());
+                        return send_row_description(socket, &cols, &oids, &fmts).await;
                     }
                     Err(_) => {
                         // Fallback to legacy JSON path
@@ -860,7 +1063,7 @@ async fn describe_row_description(socket: &mut tokio::net::TcpStream, store: &Sh
                                 };
                                 // Heuristic OIDs from first row literal strings
                                 let oids: Vec<i32> = if let Some(first) = data.first() { first.iter().map(|v| v.as_deref().map(infer_literal_oid_from_value).unwrap_or(PG_TYPE_TEXT)).collect() } else { vec![PG_TYPE_TEXT; cols.len()] };
-                                return send_row_description(socket, &cols, &oids).await;
+                                return send_row_description(socket, &cols, &oids, &[]).await;
                             }
                             Err(_) => return send_no_data(socket).await,
                         }
@@ -877,7 +1080,7 @@ async fn describe_row_description(socket: &mut tokio::net::TcpStream, store: &Sh
                             _ => to_table(vec![val.clone()])?,
                         };
                         let oids: Vec<i32> = if let Some(first) = data.first() { first.iter().map(|v| v.as_deref().map(infer_literal_oid_from_value).unwrap_or(PG_TYPE_TEXT)).collect() } else { vec![PG_TYPE_TEXT; cols.len()] };
-                        return send_row_description(socket, &cols, &oids).await;
+                        return send_row_description(socket, &cols, &oids, &[]).await;
                     }
                     Err(_) => return send_no_data(socket).await,
                 }
@@ -888,7 +1091,7 @@ async fn describe_row_description(socket: &mut tokio::net::TcpStream, store: &Sh
     }
 }
 
-async fn handle_describe(socket: &mut tokio::net::TcpStream, store: &SharedStore, state: &mut ConnState) -> Result<()> {
+async fn handle_describe(socket: &mut PgStream, store: &SharedStore, state: &mut ConnState) -> Result<()> {
     let _len = read_u32(socket).await? as usize;
     let mut tag = [0u8;1]; socket.read_exact(&mut tag).await?;
     let name = read_cstring(socket).await?;
@@ -902,12 +1105,12 @@ async fn handle_describe(socket: &mut tokio::net::TcpStream, store: &SharedStore
                 let ptys = if stmt.param_types.is_empty() { Vec::new() } else { stmt.param_types.clone() };
                 send_parameter_description(socket, &ptys).await?;
                 // RowDescription
-                describe_row_description(socket, store, state, &stmt.sql).await
+                describe_row_description(socket, store, state, &stmt.sql, &[]).await
             } else {
                 // unnamed prepared statement is "" name
                 if name.is_empty() { if let Some(stmt) = state.statements.get("") {
                     send_parameter_description(socket, &stmt.param_types).await?;
-                    describe_row_description(socket, store, state, &stmt.sql).await
+                    describe_row_description(socket, store, state, &stmt.sql, &[]).await
                 } else { send_parameter_description(socket, &[]).await?; send_no_data(socket).await }
                 } else { send_parameter_description(socket, &[]).await?; send_no_data(socket).await }
             }
@@ -922,7 +1125,7 @@ async fn handle_describe(socket: &mut tokio::net::TcpStream, store: &SharedStore
                     let sql_eff = match substitute_placeholders_typed(&stmt.sql, &portal.params, Some(&stmt.param_types)) { Ok(s) => s, Err(_) => stmt.sql.clone() };
                     // ParameterDescription is optional for portal Describe; many servers send only RowDescription
                     tprintln!("[pgwire] describe portal, row description");
-                    describe_row_description(socket, store, state, &sql_eff).await
+                    describe_row_description(socket, store, state, &sql_eff, &portal.result_formats).await
                 } else { send_no_data(socket).await }
             } else { send_no_data(socket).await }
         }
@@ -934,79 +1137,68 @@ async fn handle_describe(socket: &mut tokio::net::TcpStream, store: &SharedStore
     res
 }
 
-async fn handle_execute(socket: &mut tokio::net::TcpStream, store: &SharedStore, _user: &str, state: &mut ConnState) -> Result<()> {
+async fn handle_execute(socket: &mut PgStream, store: &SharedStore, _user: &str, state: &mut ConnState) -> Result<()> {
     // Extended protocol Execute: run an already bound portal. Keep pgwire thin and delegate
     // execution to the common server executor. Do not send ReadyForQuery here; Sync handles it.
 
     let _len = read_u32(socket).await? as usize;
     let portal_name = read_cstring(socket).await?;
-    let _max_rows = read_i32(socket).await?; // ignored for now
+    // 0 = all rows; otherwise send at most max_rows and suspend the portal
+    let max_rows = read_i32(socket).await?;
 
     // Resolve portal and its prepared statement
-    let portal = match state.portals.get(&portal_name) { Some(p) => p.clone(), None => { send_error(socket, "unknown portal").await?; state.in_error = true; return Ok(()); } };
-    let stmt = match state.statements.get(&portal.stmt_name) { Some(s) => s, None => { send_error(socket, "unknown statement").await?; state.in_error = true; return Ok(()); } };
+    let portal = match state.portals.get(&portal_name) { Some(p) => p.clone(), None => { send_error(socket, "unknown portal").await?; state.extended_error(); return Ok(()); } };
+    // A suspended portal continues where the previous Execute stopped
+    if let Some(pending) = portal.suspended {
+        debug!(target: "pgwire", "execute (portal='{}'): resuming after {} row(s)", portal_name, pending.sent);
+        return send_portal_rows(socket, state, &portal_name, pending, max_rows).await;
+    }
+    let stmt = match state.statements.get(&portal.stmt_name) { Some(s) => s.clone(), None => { send_error(socket, "unknown statement").await?; state.extended_error(); return Ok(()); } };
 
     // Perform placeholder substitution and normalize with session defaults
-    let substituted = match substitute_placeholders_typed(&stmt.sql, &portal.params, Some(&stmt.param_types)) { Ok(s) => s, Err(e) => { send_error(socket, &format!("{}", e)).await?; state.in_error = true; return Ok(()); } };
+    let substituted = match substitute_placeholders_typed(&stmt.sql, &portal.params, Some(&stmt.param_types)) { Ok(s) => s, Err(e) => { send_mapped_error(socket, &e).await?; state.extended_error(); return Ok(()); } };
     let q_trim = substituted.trim().trim_end_matches(';').trim();
     debug!("pgwire execute (portal='{}'): {}", portal_name, q_trim);
     let q_effective = exec::normalize_query_with_defaults(q_trim, &state.current_database, &state.current_schema);
     debug!(target: "pgwire", "execute effective SQL: {}", q_effective);
+    activity::set_current_query(q_trim);
+
+    let uncommented = query::strip_sql_comments(q_trim);
+    let head = uncommented.trim();
+    if let Some(cmd) = parse_cursor_command(head) {
+        return run_cursor_command(socket, store, state, cmd?, false).await;
+    }
 
     // Try to run via parsed Select to obtain typed rows for binary/text encoding.
     let parsed = query::parse(&q_effective);
-    let mut rows_sent: usize = 0;
     if let Ok(Command::Select(sel)) = parsed {
-        if let Ok((df, _into)) = handle_select(store, &sel) {
+        if let Ok((df, _into)) = handle_select_with_ctx(store, &sel, &state.request_context()).await {
             let ncols = df.width();
-            let nrows = df.height();
             // Determine per-column result format codes from portal.requested formats
-            let fmts: Vec<i16> = if portal.result_formats.is_empty() {
-                vec![0; ncols]
-            } else if portal.result_formats.len() == 1 {
-                vec![portal.result_formats[0]; ncols]
-            } else if portal.result_formats.len() == ncols {
-                portal.result_formats.clone()
-            } else { vec![0; ncols] };
+            let fmts = effective_result_formats(&portal.result_formats, ncols);
             // OIDs from schema
             let oids: Vec<i32> = df.get_columns().iter().map(|s| map_polars_dtype_to_pg_oid(s.dtype())).collect();
-            // Send rows
-            for ridx in 0..nrows {
-                // Collect AnyValue per column; default to Null on error
-                let mut avs: Vec<AnyValue> = Vec::with_capacity(ncols);
-                for s in df.get_columns() {
-                    match s.as_materialized_series().get(ridx) {
-                        Ok(av) => avs.push(av),
-                        Err(_) => avs.push(AnyValue::Null),
-                    };
-                }
-                // Use binary encoder with per-column format (falls back to text for unsupported combos)
-                send_data_row_binary(socket, &avs, &oids, &fmts).await?;
-                rows_sent += 1;
-            }
-            // Build CommandComplete
-            let tag = format!("SELECT {}", rows_sent);
-            debug!(target: "pgwire", "Execute CommandComplete tag='{}'", tag);
-            send_command_complete(socket, &tag).await?;
-            if let Err(e) = socket.flush().await { error!(target: "pgwire", "flush after Execute failed: {}", e); }
-            return Ok(());
+            let pending = SuspendedRows { rows: PendingRows::Typed { df, oids, fmts }, sent: 0, tag: "SELECT".into() };
+            return send_portal_rows(socket, state, &portal_name, pending, max_rows).await;
         }
     }
 
     // Fallback: Delegate execution to common server executor and send text rows only
     match exec::execute_query_safe(store, &q_effective).await {
         Ok(val) => {
-            let upper = q_trim.chars().take(32).collect::<String>().to_uppercase();
+            let upper = head.chars().take(32).collect::<String>().to_uppercase();
             let is_select_like = upper.starts_with("SELECT") || upper.starts_with("WITH ");
-            let (cols, data) = if is_select_like {
+            let (_cols, data) = if is_select_like {
                 match &val {
                     serde_json::Value::Array(arr) => to_table(arr.clone())?,
                     serde_json::Value::Object(_) => to_table(vec![val.clone()])?,
                     _ => to_table(vec![val.clone()])?,
                 }
             } else { (Vec::new(), Vec::new()) };
-            if is_select_like && (!data.is_empty() || !cols.is_empty()) {
-                for row in data.iter() { send_data_row(socket, row).await?; }
+            if is_select_like && !data.is_empty() {
+                let tag = if upper.starts_with("SELECT") { "SELECT" } else { "OK" };
+                let pending = SuspendedRows { rows: PendingRows::Text(data), sent: 0, tag: tag.into() };
+                return send_portal_rows(socket, state, &portal_name, pending, max_rows).await;
             }
             let tag = if upper.starts_with("SELECT") { format!("SELECT {}", data.len()) }
                 else if upper.starts_with("CALCULATE") { let saved = match &val { serde_json::Value::Object(m) => m.get("saved").and_then(|v| v.as_u64()).unwrap_or(0), _ => 0 }; format!("CALCULATE {}", saved) }
@@ -1014,18 +1206,47 @@ async fn handle_execute(socket: &mut tokio::net::TcpStream, store: &SharedStore,
                 else if upper.starts_with("UPDATE") { "UPDATE".to_string() }
                 else if upper.starts_with("SCHEMA") || upper.starts_with("DATABASE") { format!("OK {}", data.len()) }
                 else if upper.starts_with("SET") { "SET".to_string() }
+                else if upper.starts_with("RESET") { "RESET".to_string() }
                 else if upper.starts_with("CREATE TABLE") { "CREATE TABLE".to_string() }
+                else if upper.starts_with("CREATE ROLE") { "CREATE ROLE".to_string() }
+                else if upper.starts_with("DROP ROLE") { "DROP ROLE".to_string() }
+                else if upper.starts_with("COMMENT") { "COMMENT".to_string() }
+                else if upper.starts_with("GRANT") { "GRANT".to_string() }
+                else if upper.starts_with("REVOKE") { "REVOKE".to_string() }
                 else if data.is_empty() { "OK".to_string() } else { format!("OK {}", data.len()) };
+            if upper.starts_with("SET") || upper.starts_with("RESET") { report_parameter_change(socket, store, &q_effective).await?; }
             debug!(target: "pgwire", "Execute CommandComplete tag='{}'", tag);
             send_command_complete(socket, &tag).await?;
             if let Err(e) = socket.flush().await { error!(target: "pgwire", "flush after Execute failed: {}", e); }
         }
-        Err(e) => { send_mapped_error(socket, &e).await?; state.in_error = true; }
+        Err(e) => { send_mapped_error(socket, &e).await?; state.extended_error(); }
+    }
+    Ok(())
+}
+
+// Send the next rows of a portal's result: at most `max_rows` (0 = all). If rows remain, send
+// PortalSuspended and keep them on the portal for the next Execute; otherwise CommandComplete.
+async fn send_portal_rows(socket: &mut PgStream, state: &mut ConnState, portal_name: &str, mut pending: SuspendedRows, max_rows: i32) -> Result<()> {
+    let total = pending.rows.len();
+    let end = if max_rows > 0 { total.min(pending.sent + max_rows as usize) } else { total };
+    send_pending_rows(socket, &pending.rows, pending.sent..end).await?;
+    let sent_now = end - pending.sent;
+    pending.sent = end;
+    let suspended = end < total;
+    if suspended {
+        debug!(target: "pgwire", "Execute suspended portal='{}' after {} of {} row(s)", portal_name, end, total);
+        send_portal_suspended(socket).await?;
+    } else {
+        let tag = format!("{} {}", pending.tag, sent_now);
+        debug!(target: "pgwire", "Execute CommandComplete tag='{}'", tag);
+        send_command_complete(socket, &tag).await?;
     }
+    if let Some(p) = state.portals.get_mut(portal_name) { p.suspended = if suspended { Some(pending) } else { None }; }
+    if let Err(e) = socket.flush().await { error!(target: "pgwire", "flush after Execute failed: {}", e); }
     Ok(())
 }
 
-async fn handle_close(socket: &mut tokio::net::TcpStream, state: &mut ConnState) -> Result<()> {
+async fn handle_close(socket: &mut PgStream, state: &mut ConnState) -> Result<()> {
     let _len = read_u32(socket).await? as usize;
     let mut tag = [0u8;1]; socket.read_exact(&mut tag).await?;
     let name = read_cstring(socket).await?;
@@ -0,0 +1,154 @@
+//! SQL cursors for pgwire sessions: DECLARE ... CURSOR FOR <query>, FETCH / MOVE and CLOSE.
+//!
+//! The query runs once at DECLARE; FETCH then pages through the result so clients that
+//! read large results in batches (psycopg named cursors, BI tools) receive only the rows
+//! they ask for. Cursors scan forward only and live until CLOSE or the end of the
+//! connection (WITH HOLD is accepted; transactions are not tracked).
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::pgwire_server::oids::map_polars_dtype_to_pg_oid;
+use crate::pgwire_server::misc::PG_TYPE_TEXT;
+use crate::pgwire_server::send::*;
+use crate::pgwire_server::structs::*;
+use crate::pgwire_server::tls::PgStream;
+use crate::server::exec;
+use crate::server::exec::exec_select::handle_select_with_ctx;
+use crate::server::query::{self, Command};
+use crate::storage::SharedStore;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CursorCommand {
+    Declare { name: String, binary: bool, query: String },
+    // count None = ALL; MOVE skips rows without sending them
+    Fetch { name: String, count: Option<usize>, move_only: bool },
+    // None = CLOSE ALL
+    Close(Option<String>),
+}
+
+fn next_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(i) => (&s[..i], s[i..].trim_start()),
+        None => (s, ""),
+    }
+}
+
+// Cursor names fold to lower case unless double-quoted
+fn cursor_name(raw: &str) -> Result<String> {
+    if raw.is_empty() { bail!("cursor name expected"); }
+    Ok(match raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+        Some(quoted) => quoted.to_string(),
+        None => raw.to_ascii_lowercase(),
+    })
+}
+
+/// Parse a cursor statement; None when `sql` is not DECLARE, FETCH, MOVE or CLOSE.
+pub fn parse_cursor_command(sql: &str) -> Option<Result<CursorCommand>> {
+    let s = sql.trim().trim_end_matches(';').trim_end();
+    let (first, rest) = next_word(s);
+    match first.to_ascii_uppercase().as_str() {
+        "DECLARE" => Some(parse_declare(rest)),
+        "FETCH" => Some(parse_fetch(rest, false)),
+        "MOVE" => Some(parse_fetch(rest, true)),
+        "CLOSE" => Some(if rest.eq_ignore_ascii_case("ALL") { Ok(CursorCommand::Close(None)) } else { cursor_name(rest).map(|n| CursorCommand::Close(Some(n))) }),
+        _ => None,
+    }
+}
+
+fn parse_declare(rest: &str) -> Result<CursorCommand> {
+    let (name, mut rest) = next_word(rest);
+    let name = cursor_name(name)?;
+    let mut binary = false;
+    loop {
+        let (w, r) = next_word(rest);
+        rest = r;
+        match w.to_ascii_uppercase().as_str() {
+            "BINARY" => binary = true,
+            "INSENSITIVE" | "ASENSITIVE" | "SCROLL" | "NO" => {}
+            "CURSOR" => break,
+            _ => bail!("syntax error in DECLARE: expected CURSOR"),
+        }
+    }
+    let (w, r) = next_word(rest);
+    if w.eq_ignore_ascii_case("WITH") || w.eq_ignore_ascii_case("WITHOUT") {
+        let (hold, r) = next_word(r);
+        if !hold.eq_ignore_ascii_case("HOLD") { bail!("syntax error in DECLARE: expected HOLD"); }
+        rest = r;
+    }
+    let (w, query) = next_word(rest);
+    if !w.eq_ignore_ascii_case("FOR") || query.is_empty() { bail!("syntax error in DECLARE: expected FOR <query>"); }
+    Ok(CursorCommand::Declare { name, binary, query: query.to_string() })
+}
+
+fn parse_fetch(rest: &str, move_only: bool) -> Result<CursorCommand> {
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+    let name = cursor_name(words.pop().unwrap_or(""))?;
+    if words.last().is_some_and(|w| w.eq_ignore_ascii_case("FROM") || w.eq_ignore_ascii_case("IN")) { words.pop(); }
+    let upper: Vec<String> = words.iter().map(|w| w.to_ascii_uppercase()).collect();
+    let dir: Vec<&str> = upper.iter().map(|w| w.as_str()).collect();
+    let count = match dir.as_slice() {
+        [] | ["NEXT"] | ["FORWARD"] => Some(1),
+        ["ALL"] | ["FORWARD", "ALL"] => None,
+        [n] | ["FORWARD", n] if n.parse::<i64>().is_ok() => {
+            let n: i64 = n.parse()?;
+            if n < 0 { bail!("cursor can only scan forward"); }
+            Some(n as usize)
+        }
+        [first, ..] if matches!(*first, "PRIOR" | "FIRST" | "LAST" | "ABSOLUTE" | "RELATIVE" | "BACKWARD") => bail!("cursor can only scan forward"),
+        _ => bail!("syntax error in {}", if move_only { "MOVE" } else { "FETCH" }),
+    };
+    Ok(CursorCommand::Fetch { name, count, move_only })
+}
+
+/// Run a cursor statement and send its CommandComplete. `describe` sends a RowDescription
+/// before FETCH rows (simple query); the extended protocol describes through Describe instead.
+pub(crate) async fn run_cursor_command(socket: &mut PgStream, store: &SharedStore, state: &mut ConnState, cmd: CursorCommand, describe: bool) -> Result<()> {
+    match cmd {
+        CursorCommand::Declare { name, binary, query } => {
+            if state.cursors.contains_key(&name) { bail!("cursor \"{}\" already exists", name); }
+            let q = exec::normalize_query_with_defaults(&query, &state.current_database, &state.current_schema);
+            let cursor = match query::parse(&q) {
+                Ok(Command::Select(sel)) => {
+                    let (df, _into) = handle_select_with_ctx(store, &sel, &state.request_context()).await?;
+                    let columns: Vec<String> = df.get_column_names().iter().map(|c| c.to_string()).collect();
+                    let oids: Vec<i32> = df.get_columns().iter().map(|c| map_polars_dtype_to_pg_oid(c.dtype())).collect();
+                    let fmts = vec![if binary { 1 } else { 0 }; columns.len()];
+                    Cursor { columns, oids: oids.clone(), rows: PendingRows::Typed { df, oids, fmts }, pos: 0 }
+                }
+                _ => {
+                    // Other row-returning statements (SHOW, UNION, ...) go through the generic executor as text
+                    let ctx = state.request_context();
+                    let val = exec::execute_query_safe_with_ctx(store, &q, &ctx).await?;
+                    let (columns, data) = match val {
+                        serde_json::Value::Array(arr) => super::to_table(arr)?,
+                        other => super::to_table(vec![other])?,
+                    };
+                    Cursor { oids: vec![PG_TYPE_TEXT; columns.len()], columns, rows: PendingRows::Text(data), pos: 0 }
+                }
+            };
+            state.cursors.insert(name, cursor);
+            send_command_complete(socket, "DECLARE CURSOR").await
+        }
+        CursorCommand::Fetch { name, count, move_only } => {
+            let cursor = state.cursors.get_mut(&name).ok_or_else(|| anyhow!("cursor \"{}\" does not exist", name))?;
+            let total = cursor.rows.len();
+            let end = count.map(|n| total.min(cursor.pos.saturating_add(n))).unwrap_or(total);
+            if !move_only {
+                if describe { send_row_description(socket, &cursor.columns, &cursor.oids, &cursor.formats()).await?; }
+                send_pending_rows(socket, &cursor.rows, cursor.pos..end).await?;
+            }
+            let n = end - cursor.pos;
+            cursor.pos = end;
+            send_command_complete(socket, &format!("{} {}", if move_only { "MOVE" } else { "FETCH" }, n)).await
+        }
+        CursorCommand::Close(Some(name)) => {
+            state.cursors.remove(&name).ok_or_else(|| anyhow!("cursor \"{}\" does not exist", name))?;
+            send_command_complete(socket, "CLOSE CURSOR").await
+        }
+        CursorCommand::Close(None) => {
+            state.cursors.clear();
+            send_command_complete(socket, "CLOSE CURSOR").await
+        }
+    }
+}
@@ -1,37 +1,143 @@
-use polars::prelude::AnyValue;
+use polars::prelude::{AnyValue, Series};
 use polars::datatypes::TimeUnit;
 
 use crate::pgwire_server::inline::*;
+use crate::server::query::CopyOptions;
 
-pub fn encode_element_binary(buf: &mut Vec<u8>, inner_oid: i32, av: &AnyValue<'_>) {
-    match (inner_oid, av) {
-        (16, AnyValue::Boolean(b)) => { buf.extend_from_slice(&1i32.to_be_bytes()); buf.push(if *b {1} else {0}); }
-        (21, AnyValue::Int16(v)) => { buf.extend_from_slice(&2i32.to_be_bytes()); buf.extend_from_slice(&v.to_be_bytes()); }
-        (23, AnyValue::Int32(v)) => { buf.extend_from_slice(&4i32.to_be_bytes()); buf.extend_from_slice(&v.to_be_bytes()); }
-        (20, AnyValue::Int64(v)) => { buf.extend_from_slice(&8i32.to_be_bytes()); buf.extend_from_slice(&v.to_be_bytes()); }
-        (700, AnyValue::Float32(f)) => { let bits=f.to_bits(); buf.extend_from_slice(&4i32.to_be_bytes()); buf.extend_from_slice(&bits.to_be_bytes()); }
-        (701, AnyValue::Float64(f)) => { let bits=f.to_bits(); buf.extend_from_slice(&8i32.to_be_bytes()); buf.extend_from_slice(&bits.to_be_bytes()); }
-        (17, AnyValue::Binary(b)) => { buf.extend_from_slice(&(b.len() as i32).to_be_bytes()); buf.extend_from_slice(b); }
-        (25, _) => { let s = format!("{}", av); let bytes=s.as_bytes(); buf.extend_from_slice(&(bytes.len() as i32).to_be_bytes()); buf.extend_from_slice(bytes); }
-        (1082, AnyValue::Date(days_since_unix)) => {
-            const DAYS_BETWEEN_UNIX_AND_PG_EPOCH: i32 = 10957;
-            let pg_days: i32 = days_since_unix - DAYS_BETWEEN_UNIX_AND_PG_EPOCH;
-            buf.extend_from_slice(&4i32.to_be_bytes()); buf.extend_from_slice(&pg_days.to_be_bytes());
-        }
-        (1083, AnyValue::Time(nanos_since_midnight)) => {
-            let micros: i64 = nanos_since_midnight / 1000; buf.extend_from_slice(&8i32.to_be_bytes()); buf.extend_from_slice(&micros.to_be_bytes());
+const DAYS_BETWEEN_UNIX_AND_PG_EPOCH: i32 = 10_957; // 1970-01-01 -> 2000-01-01
+const MICROS_BETWEEN_UNIX_AND_PG_EPOCH: i64 = 946_684_800_i64 * 1_000_000;
+
+// Microseconds for a temporal value; floors so instants before 1970 do not round up
+fn to_micros(v: i64, unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Nanoseconds => v.div_euclid(1000),
+        TimeUnit::Microseconds => v,
+        TimeUnit::Milliseconds => v.saturating_mul(1000),
+    }
+}
+
+fn int_value(av: &AnyValue<'_>) -> Option<i64> {
+    match av {
+        AnyValue::Int8(v) => Some(*v as i64),
+        AnyValue::Int16(v) => Some(*v as i64),
+        AnyValue::Int32(v) => Some(*v as i64),
+        AnyValue::Int64(v) => Some(*v),
+        AnyValue::UInt8(v) => Some(*v as i64),
+        AnyValue::UInt16(v) => Some(*v as i64),
+        AnyValue::UInt32(v) => Some(*v as i64),
+        AnyValue::UInt64(v) => i64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+fn float_value(av: &AnyValue<'_>) -> Option<f64> {
+    match av {
+        AnyValue::Float32(f) => Some(*f as f64),
+        AnyValue::Float64(f) => Some(*f),
+        _ => int_value(av).map(|v| v as f64),
+    }
+}
+
+fn put_value(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+/// Write `av` as a length-prefixed binary (format 1) value of type `oid`.
+/// Integer and float widths are converted to the declared type (e.g. UInt32 -> int8,
+/// Int8 -> int2), and temporal values are shifted to the PostgreSQL epoch (2000-01-01).
+/// Returns false without writing anything when there is no binary encoding for the pair.
+pub fn encode_binary_scalar(buf: &mut Vec<u8>, oid: i32, av: &AnyValue<'_>) -> bool {
+    match oid {
+        16 => match av { AnyValue::Boolean(b) => put_value(buf, &[*b as u8]), _ => return false },
+        21 => match int_value(av).and_then(|v| i16::try_from(v).ok()) { Some(v) => put_value(buf, &v.to_be_bytes()), None => return false },
+        23 => match int_value(av).and_then(|v| i32::try_from(v).ok()) { Some(v) => put_value(buf, &v.to_be_bytes()), None => return false },
+        20 => match int_value(av) { Some(v) => put_value(buf, &v.to_be_bytes()), None => return false },
+        700 => match av {
+            AnyValue::Float32(f) => put_value(buf, &f.to_bits().to_be_bytes()),
+            _ => match float_value(av) { Some(f) => put_value(buf, &(f as f32).to_bits().to_be_bytes()), None => return false },
+        },
+        701 => match float_value(av) { Some(f) => put_value(buf, &f.to_bits().to_be_bytes()), None => return false },
+        17 => match av {
+            AnyValue::Binary(b) => put_value(buf, b),
+            AnyValue::BinaryOwned(b) => put_value(buf, b),
+            _ => return false,
+        },
+        // text-like types: the binary form is the UTF-8 bytes themselves
+        25 | 1043 | 1042 | 19 => match av.get_str() { Some(s) => put_value(buf, s.as_bytes()), None => return false },
+        // date: int32 days since 2000-01-01
+        1082 => match av {
+            AnyValue::Date(days) => put_value(buf, &(days - DAYS_BETWEEN_UNIX_AND_PG_EPOCH).to_be_bytes()),
+            _ => return false,
+        },
+        // time: int64 microseconds since midnight
+        1083 => match av { AnyValue::Time(nanos) => put_value(buf, &(nanos / 1000).to_be_bytes()), _ => return false },
+        // timestamp / timestamptz: int64 microseconds since 2000-01-01 00:00 UTC
+        1114 | 1184 => {
+            let micros = match av {
+                AnyValue::Datetime(v, unit, _) => to_micros(*v, unit),
+                AnyValue::DatetimeOwned(v, unit, _) => to_micros(*v, unit),
+                AnyValue::Date(days) => (*days as i64) * 86_400_000_000,
+                _ => return false,
+            };
+            put_value(buf, &(micros - MICROS_BETWEEN_UNIX_AND_PG_EPOCH).to_be_bytes());
         }
-        (1114, AnyValue::Datetime(val, unit, _)) | (1184, AnyValue::Datetime(val, unit, _)) => {
-            let micros_since_unix: i64 = match unit { TimeUnit::Nanoseconds => (*val)/1000, TimeUnit::Microseconds => *val, TimeUnit::Milliseconds => (*val)*1000 };
-            const MICROS_BETWEEN_UNIX_AND_PG_EPOCH: i64 = 946_684_800_i64 * 1_000_000;
-            let pg_micros: i64 = micros_since_unix - MICROS_BETWEEN_UNIX_AND_PG_EPOCH; buf.extend_from_slice(&8i32.to_be_bytes()); buf.extend_from_slice(&pg_micros.to_be_bytes());
+        // interval: int64 microseconds, int32 days, int32 months
+        1186 => match av {
+            AnyValue::Duration(v, unit) => {
+                let mut b = Vec::with_capacity(16);
+                b.extend_from_slice(&to_micros(*v, unit).to_be_bytes());
+                b.extend_from_slice(&0i32.to_be_bytes());
+                b.extend_from_slice(&0i32.to_be_bytes());
+                put_value(buf, &b);
+            }
+            _ => return false,
+        },
+        1700 => match anyvalue_to_opt_string(av).and_then(|s| encode_pg_numeric_from_str(&s)) {
+            Some(b) => put_value(buf, &b),
+            None => return false,
+        },
+        _ => return false,
+    }
+    true
+}
+
+/// Write a one-dimensional array value of type `array_oid` in binary form:
+/// ndims, has-null flag, element oid, length and lower bound (1), then the elements.
+pub fn encode_array_binary(buf: &mut Vec<u8>, array_oid: i32, series: &Series) {
+    let inner_oid = array_elem_oid(array_oid);
+    let n = series.len();
+    let mut arr = Vec::new();
+    arr.extend_from_slice(&1i32.to_be_bytes());
+    arr.extend_from_slice(&((series.null_count() > 0) as i32).to_be_bytes());
+    arr.extend_from_slice(&inner_oid.to_be_bytes());
+    arr.extend_from_slice(&(n as i32).to_be_bytes());
+    arr.extend_from_slice(&1i32.to_be_bytes());
+    for i in 0..n {
+        match series.get(i).unwrap_or(AnyValue::Null) {
+            AnyValue::Null => arr.extend_from_slice(&(-1i32).to_be_bytes()),
+            cell => encode_element_binary(&mut arr, inner_oid, &cell),
         }
-        // Fallback: encode as text
-        _ => { let s = format!("{}", av); let bytes=s.as_bytes(); buf.extend_from_slice(&(bytes.len() as i32).to_be_bytes()); buf.extend_from_slice(bytes); }
     }
+    put_value(buf, &arr);
 }
 
+/// Write a non-null result field in binary form, falling back to its text when the
+/// type has no binary encoder (e.g. record).
+pub fn encode_binary_field(buf: &mut Vec<u8>, oid: i32, av: &AnyValue<'_>) {
+    let encoded = match av {
+        AnyValue::List(series) if is_array_oid(oid) => { encode_array_binary(buf, oid, series); true }
+        _ => encode_binary_scalar(buf, oid, av),
+    };
+    if !encoded { put_value(buf, anyvalue_to_opt_string(av).unwrap_or_default().as_bytes()); }
+}
 
+pub fn encode_element_binary(buf: &mut Vec<u8>, inner_oid: i32, av: &AnyValue<'_>) {
+    if !encode_binary_scalar(buf, inner_oid, av) {
+        // No binary form for this element type: send its text
+        put_value(buf, anyvalue_to_opt_string(av).unwrap_or_default().as_bytes());
+    }
+}
 
 // Encode a decimal string into PostgreSQL NUMERIC binary format.
 // Format: int16 ndigits, int16 weight, int16 sign, int16 dscale, then ndigits * int16 base-10000 digits.
@@ -49,41 +155,24 @@ pub fn encode_pg_numeric_from_str(s: &str) -> Option<Vec<u8>> {
     let mut sign = 0i16; // 0=positive, 0x4000=negative
     let mut p = st;
     if let Some(stripped) = p.strip_prefix('-') { sign = 0x4000u16 as i16; p = stripped; } else if let Some(stripped) = p.strip_prefix('+') { p = stripped; }
-    // Split into integer and fraction
-    let parts: Vec<&str> = p.split('.').collect();
-    if parts.len() > 2 { return None; }
-    let int_part = parts.get(0).copied().unwrap_or("");
-    let frac_part = if parts.len() == 2 { parts[1] } else { "" };
+    let (int_part, frac_part) = p.split_once('.').unwrap_or((p, ""));
     if int_part.is_empty() && frac_part.is_empty() { return None; }
-    // Remove leading zeros in integer and trailing zeros in fraction for compactness (scale preserved)
-    let int_part_trim = int_part.trim_start_matches('0');
-    let frac_part_trim = frac_part.trim_end_matches('0');
+    if !int_part.bytes().chain(frac_part.bytes()).all(|b| b.is_ascii_digit()) { return None; }
     let dscale: i16 = frac_part.len() as i16; // decimal digits after decimal point as provided
-    // Prepare a contiguous digit string without the decimal point
-    let mut digits = String::new();
-    digits.push_str(if int_part_trim.is_empty() { "0" } else { int_part_trim });
-    digits.push_str(frac_part_trim);
-    // Validate digits
-    if !digits.chars().all(|c| c.is_ascii_digit()) { return None; }
-    // Compute weight: index of the first base-10000 group left of decimal
-    // Weight is the number of base-10000 digits before the decimal - 1
-    let int_len = if int_part_trim.is_empty() { 1 } else { int_part_trim.len() } as i32;
-    let leading_group_digits = (int_len as i32 + 3) / 4; // ceil(int_len/4)
-    let mut weight: i16 = (leading_group_digits - 1) as i16;
-    // Left-pad digits to multiple of 4
-    let rem = digits.len() % 4;
-    let mut padded = String::new();
-    if rem != 0 { padded.push_str(&"0".repeat(4 - rem)); }
-    padded.push_str(&digits);
-    // Build base-10000 digit array
-    let mut base_digits: Vec<i16> = Vec::new();
-    for chunk in padded.as_bytes().chunks(4) {
-        let val = ((chunk[0]-b'0') as i32)*1000 + ((chunk[1]-b'0') as i32)*100 + ((chunk[2]-b'0') as i32)*10 + ((chunk[3]-b'0') as i32);
-        base_digits.push(val as i16);
-    }
-    // If overall is zero, normalize sign/weight/scale
-    let is_zero = base_digits.iter().all(|&d| d==0);
-    if is_zero { sign = 0; weight = 0; }
+    // Base-10000 digits are aligned on the decimal point: pad the integer part on the left and
+    // the fraction on the right to whole groups of four
+    let int_trim = int_part.trim_start_matches('0');
+    let int_padded = format!("{}{}", "0".repeat((4 - int_trim.len() % 4) % 4), int_trim);
+    let frac_padded = format!("{}{}", frac_part, "0".repeat((4 - frac_part.len() % 4) % 4));
+    let group = |c: &[u8]| c.iter().fold(0i16, |acc, d| acc * 10 + (d - b'0') as i16);
+    let mut base_digits: Vec<i16> = int_padded.as_bytes().chunks(4).chain(frac_padded.as_bytes().chunks(4)).map(group).collect();
+    // Weight is the number of base-10000 digits before the decimal point - 1
+    let mut weight: i16 = (int_padded.len() / 4) as i16 - 1;
+    // Leading and trailing zero groups are implied by weight and dscale
+    while base_digits.first() == Some(&0) { base_digits.remove(0); weight -= 1; }
+    while base_digits.last() == Some(&0) { base_digits.pop(); }
+    // If overall is zero, normalize sign/weight
+    if base_digits.is_empty() { sign = 0; weight = 0; }
     // Compose binary
     let mut out = Vec::new();
     out.extend_from_slice(&(base_digits.len() as i16).to_be_bytes());
@@ -112,38 +201,25 @@ pub fn decode_pg_numeric_to_string(bytes: &[u8]) -> Option<String> {
         groups.push(g);
         off += 2;
     }
-    // Build contiguous decimal digits from base-10000 groups
-    let mut digits = String::new();
+    // Group i sits `weight - i` places left of the decimal point (negative: right of it)
+    let mut int_digits = String::new();
+    let mut frac_digits = String::new();
     for (i, g) in groups.iter().enumerate() {
-        if i == 0 { digits.push_str(&format!("{}", g)); }
-        else { digits.push_str(&format!("{:04}", g)); }
+        if weight - i as i32 >= 0 { int_digits.push_str(&format!("{:04}", g)); } else { frac_digits.push_str(&format!("{:04}", g)); }
     }
-    if digits.is_empty() { digits.push('0'); }
-    // Position of decimal point in digits string
-    let dp = (weight + 1) * 4;
+    // Zero groups left out between the last digit and the point, or between the point and the first digit
+    if weight >= 0 && ndigits < weight + 1 { int_digits.push_str(&"0000".repeat((weight + 1 - ndigits) as usize)); }
+    if weight < -1 { frac_digits.insert_str(0, &"0000".repeat((-1 - weight) as usize)); }
+    let int_digits = int_digits.trim_start_matches('0');
     let mut out = String::new();
     if sign == 0x4000 { out.push('-'); }
-    if dp <= 0 {
-        out.push_str("0.");
-        for _ in 0..(-dp) { out.push('0'); }
-        out.push_str(&digits);
-    } else if (dp as usize) >= digits.len() {
-        out.push_str(&digits);
-        for _ in 0..(dp as usize - digits.len()) { out.push('0'); }
-        if dscale > 0 {
-            out.push('.');
-            for _ in 0..dscale { out.push('0'); }
-        }
-    } else {
-        let (intp, fracp) = digits.split_at(dp as usize);
-        out.push_str(intp);
+    out.push_str(if int_digits.is_empty() { "0" } else { int_digits });
+    if dscale > 0 {
+        // Exactly dscale fractional digits, padding with zeros
+        frac_digits.truncate(dscale as usize);
+        while frac_digits.len() < dscale as usize { frac_digits.push('0'); }
         out.push('.');
-        out.push_str(fracp);
-        // Ensure at least dscale fractional digits by padding zeros
-        let cur_scale = fracp.len() as i32;
-        if dscale > cur_scale {
-            for _ in 0..(dscale - cur_scale) { out.push('0'); }
-        }
+        out.push_str(&frac_digits);
     }
     Some(out)
 }
@@ -220,3 +296,85 @@ pub fn decode_pg_array_to_literal(bytes: &[u8], array_oid: i32) -> Option<String
     }
     Some(format!("{{{}}}", elems.join(",")))
 }
+
+// Text of a COPY TO field: PostgreSQL spellings for booleans and arrays
+fn copy_field_text(av: &AnyValue<'_>) -> Option<String> {
+    match av {
+        AnyValue::Null => None,
+        AnyValue::Boolean(b) => Some(if *b { "t".into() } else { "f".into() }),
+        AnyValue::List(series) => Some(format_pg_array_text(series)),
+        other => anyvalue_to_opt_string(other),
+    }
+}
+
+fn push_copy_text(out: &mut Vec<u8>, field: &str, options: &CopyOptions) {
+    for ch in field.chars() {
+        match ch {
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\t' => out.extend_from_slice(b"\\t"),
+            c => {
+                if c == options.delimiter { out.push(b'\\'); }
+                let mut utf8 = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            }
+        }
+    }
+}
+
+fn push_copy_csv(out: &mut Vec<u8>, field: &str, options: &CopyOptions) {
+    // Quote when the field could be misread: separators, quotes, line breaks, or the NULL marker
+    let needs_quote = field == options.null_str
+        || field.chars().any(|c| c == options.delimiter || matches!(c, '"' | '\n' | '\r'));
+    if needs_quote {
+        out.push(b'"');
+        out.extend_from_slice(field.replace('"', "\"\"").as_bytes());
+        out.push(b'"');
+    } else {
+        out.extend_from_slice(field.as_bytes());
+    }
+}
+
+/// COPY TO header line for text/CSV output.
+pub fn encode_copy_header(out: &mut Vec<u8>, columns: &[String], options: &CopyOptions) {
+    let csv = options.format == crate::server::query::CopyFormat::Csv;
+    for (i, c) in columns.iter().enumerate() {
+        if i > 0 { out.extend_from_slice(options.delimiter.to_string().as_bytes()); }
+        if csv { push_copy_csv(out, c, options) } else { push_copy_text(out, c, options) }
+    }
+    out.push(b'\n');
+}
+
+/// One newline-terminated COPY TO row in text or CSV format.
+pub fn encode_copy_text_row(out: &mut Vec<u8>, row: &[AnyValue<'_>], options: &CopyOptions) {
+    let csv = options.format == crate::server::query::CopyFormat::Csv;
+    for (i, av) in row.iter().enumerate() {
+        if i > 0 { out.extend_from_slice(options.delimiter.to_string().as_bytes()); }
+        match copy_field_text(av) {
+            None => out.extend_from_slice(options.null_str.as_bytes()),
+            Some(s) if csv => push_copy_csv(out, &s, options),
+            Some(s) => push_copy_text(out, &s, options),
+        }
+    }
+    out.push(b'\n');
+}
+
+/// One COPY TO row in binary format: field count, then length-prefixed fields (-1 = NULL).
+pub fn encode_copy_binary_row(out: &mut Vec<u8>, row: &[AnyValue<'_>], oids: &[i32]) {
+    out.extend_from_slice(&(row.len() as i16).to_be_bytes());
+    for (i, av) in row.iter().enumerate() {
+        match av {
+            AnyValue::Null => out.extend_from_slice(&(-1i32).to_be_bytes()),
+            _ => encode_binary_field(out, *oids.get(i).unwrap_or(&25), av),
+        }
+    }
+}
+
+/// Binary COPY header: signature, flags and an empty header extension.
+pub fn copy_binary_header() -> Vec<u8> {
+    let mut out = crate::server::exec::exec_copy::PGCOPY_SIGNATURE.to_vec();
+    out.extend_from_slice(&0i32.to_be_bytes());
+    out.extend_from_slice(&0i32.to_be_bytes());
+    out
+}
@@ -1,10 +1,11 @@
 use polars::prelude::AnyValue;
 use anyhow::Result;
 use crate::pgwire_server::{send::send_ready_with_status, structs::ConnState};
+use crate::pgwire_server::tls::PgStream;
 
 #[inline]
 pub fn is_array_oid(oid: i32) -> bool {
-    matches!(oid, 1000|1005|1007|1016|1021|1022|1009|1001|1182|1115|1185|1183)
+    matches!(oid, 1000|1005|1007|1016|1021|1022|1009|1001|1182|1115|1185|1183|1231)
 }
 
 #[inline]
@@ -22,6 +23,7 @@ pub fn array_elem_oid(array_oid: i32) -> i32 {
         1115 => 1114, // timestamp[] -> timestamp
         1185 => 1184, // timestamptz[] -> timestamptz
         1183 => 1083, // time[] -> time
+        1231 => 1700, // numeric[] -> numeric
         _ => 25,
     }
 }
@@ -43,6 +45,9 @@ pub fn anyvalue_to_opt_string(av: &AnyValue) -> Option<String> {
         AnyValue::Float32(v) => Some(v.to_string()),
         AnyValue::Float64(v) => Some(v.to_string()),
         AnyValue::Boolean(v) => Some(v.to_string()),
+        AnyValue::Decimal(v, scale) => Some(crate::storage::decimal::format_value(*v, *scale)),
+        // Stored TIMESTAMP columns in PostgreSQL's text form
+        AnyValue::Datetime(v, polars::prelude::TimeUnit::Milliseconds, None) => Some(crate::storage::native::format_timestamp_ms(*v)),
         other => Some(format!("{}", other)),
     }
 }
@@ -87,7 +92,7 @@ pub fn format_pg_array_text(series: &polars::prelude::Series) -> String {
 }
 
 #[inline]
-pub async fn send_ready(socket: &mut tokio::net::TcpStream, state: &ConnState) -> Result<()> {
+pub async fn send_ready(socket: &mut PgStream, state: &ConnState) -> Result<()> {
     let status = if state.in_tx {
         if state.in_error { b'E' } else { b'T' }
     } else { b'I' };
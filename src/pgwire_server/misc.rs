@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Result, bail};
 use std::sync::atomic::AtomicU64;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use crate::pgwire_server::tls::PgStream;
 
 use regex::Regex;
 use std::collections::HashMap;
@@ -13,14 +14,14 @@ pub const PG_TYPE_TEXT: i32 = 25; // use text for all columns for simplicity
 
 pub static CONN_ID_COUNTER: AtomicU64 = AtomicU64::new(1);
 
-pub async fn read_i16(socket: &mut tokio::net::TcpStream) -> Result<i16> { let mut b = [0u8;2]; socket.read_exact(&mut b).await?; Ok(i16::from_be_bytes(b)) }
-pub async fn read_i32(socket: &mut tokio::net::TcpStream) -> Result<i32> { let mut b = [0u8;4]; socket.read_exact(&mut b).await?; Ok(i32::from_be_bytes(b)) }
-pub async fn read_u32(socket: &mut tokio::net::TcpStream) -> Result<u32> {
+pub async fn read_i16(socket: &mut PgStream) -> Result<i16> { let mut b = [0u8;2]; socket.read_exact(&mut b).await?; Ok(i16::from_be_bytes(b)) }
+pub async fn read_i32(socket: &mut PgStream) -> Result<i32> { let mut b = [0u8;4]; socket.read_exact(&mut b).await?; Ok(i32::from_be_bytes(b)) }
+pub async fn read_u32(socket: &mut PgStream) -> Result<u32> {
     let mut b = [0u8; 4]; socket.read_exact(&mut b).await?; Ok(u32::from_be_bytes(b))
 }
 
-pub async fn write_i32(socket: &mut tokio::net::TcpStream, v: i32) -> Result<()> { socket.write_all(&v.to_be_bytes()).await.map_err(|e| e.into()) }
-pub async fn read_cstring(socket: &mut tokio::net::TcpStream) -> Result<String> {
+pub async fn write_i32(socket: &mut PgStream, v: i32) -> Result<()> { socket.write_all(&v.to_be_bytes()).await.map_err(|e| e.into()) }
+pub async fn read_cstring(socket: &mut PgStream) -> Result<String> {
     let mut buf: Vec<u8> = Vec::new();
     let mut byte = [0u8;1];
     loop {
@@ -57,7 +58,7 @@ pub fn normalize_object_to_db(name: &str) -> String {
 
 
 
-pub async fn write_msg_header(socket: &mut tokio::net::TcpStream, tag: u8, len: i32) -> Result<()> {
+pub async fn write_msg_header(socket: &mut PgStream, tag: u8, len: i32) -> Result<()> {
     socket.write_all(&[tag]).await?; write_i32(socket, len).await
 }
 
@@ -4,6 +4,7 @@ use tracing::debug;
 use crate::pgwire_server::structs::*;
 use crate::pgwire_server::misc::*;
 use crate::pgwire_server::send::*;
+use crate::pgwire_server::tls::PgStream;
 
 use regex::Regex;
 
@@ -77,7 +78,7 @@ pub fn parse_value(s: &str) -> InsertValue {
 
 // Extended protocol handlers and helpers
 
-pub async fn handle_parse(socket: &mut tokio::net::TcpStream, state: &mut ConnState) -> Result<()> {
+pub async fn handle_parse(socket: &mut PgStream, state: &mut ConnState) -> Result<()> {
     let len_total = read_u32(socket).await? as usize;
     let mut buf = vec![0u8; len_total - 4];
     socket.read_exact(&mut buf).await?;
@@ -107,71 +108,8 @@ pub async fn handle_parse(socket: &mut tokio::net::TcpStream, state: &mut ConnSt
     let ntypes = read_i16_from(&buf, &mut i)? as usize;
     let mut param_types: Vec<i32> = Vec::with_capacity(ntypes);
     for _ in 0..ntypes { param_types.push(read_i32_from(&buf, &mut i)?); }
-    // If client did not provide parameter types, infer from $n placeholders and casts
-    if param_types.is_empty() {
-        let re_dollar = Regex::new(r"\$([1-9][0-9]*)")?;
-        let mut max_idx = 0usize;
-        for cap in re_dollar.captures_iter(&sql) {
-            if let Some(m) = cap.get(1) {
-                if let Ok(idx) = m.as_str().parse::<usize>() { if idx > max_idx { max_idx = idx; } }
-            }
-        }
-        if max_idx > 0 {
-            // default to TEXT
-            param_types = vec![PG_TYPE_TEXT; max_idx];
-            // refine using explicit casts like $1::int8, $2::float8, etc.
-            // capture optional [] suffix and allow multi-word/base qualified names
-            let re_cast = Regex::new(r"\\$([1-9][0-9]*)::([A-Za-z0-9_\. ]+(?:\\[\\])?)")?;
-            for cap in re_cast.captures_iter(&sql) {
-                let idx: usize = cap.get(1).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
-                let mut ty_raw = cap.get(2).map(|m| m.as_str().to_string()).unwrap_or_default();
-                let ty_lc = ty_raw.to_ascii_lowercase();
-                // Strip pg_catalog. prefix and collapse spaces
-                let ty_norm = ty_lc.replace("pg_catalog.", "").replace("  ", " ");
-                let oid = {
-                    if ty_norm.ends_with("[]") {
-                        let inner = ty_norm.trim_end_matches("[]").trim();
-                        match inner {
-                            // arrays of common types
-                            "bool" | "boolean" => 1000,
-                            "int2" | "smallint" => 1005,
-                            "int" | "int4" | "integer" => 1007,
-                            "int8" | "bigint" => 1016,
-                            "real" | "float4" => 1021,
-                            "double precision" | "float8" => 1022,
-                            "text" | "varchar" | "character varying" | "bpchar" | "char" | "character" => 1009,
-                            "bytea" => 1001,
-                            "date" => 1182,
-                            "timestamp" | "timestamp without time zone" => 1115,
-                            "timestamptz" | "timestamp with time zone" => 1185,
-                            "time" | "time without time zone" => 1183,
-                            "numeric" | "decimal" => 1231,
-                            _ => PG_TYPE_TEXT,
-                        }
-                    } else {
-                        match ty_norm.as_str() {
-                            // scalars
-                            "int" | "int4" | "integer" => 23,
-                            "int8" | "bigint" => 20,
-                            "real" | "float4" => 700,
-                            "float8" | "double" | "double precision" => 701,
-                            "text" | "varchar" | "character varying" | "bpchar" | "char" | "character" => 25,
-                            "bool" | "boolean" => 16,
-                            "bytea" => 17,
-                            "date" => 1082,
-                            "timestamp" | "timestamp without time zone" => 1114,
-                            "timestamptz" | "timestamp with time zone" => 1184,
-                            "time" | "time without time zone" => 1083,
-                            "numeric" | "decimal" => 1700,
-                            _ => PG_TYPE_TEXT,
-                        }
-                    }
-                };
-                if idx > 0 && idx - 1 < param_types.len() { param_types[idx - 1] = oid; }
-            }
-            debug!("pgwire parse: inferred {} parameter(s) with types {:?}", max_idx, param_types);
-        }
-    }
+    // Parameters the client left unspecified (missing or OID 0) are inferred from the SQL
+    let param_types = infer_param_types(&sql, &param_types);
     // store
     if stmt_name.is_empty() {
         state.statements.insert("".into(), PreparedStatement { name: "".into(), sql, param_types });
@@ -182,6 +120,83 @@ pub async fn handle_parse(socket: &mut tokio::net::TcpStream, state: &mut ConnSt
 }
 
 
+// OID for a type name written in a cast (`$1::int8`, `$2::text[]`); None when unknown.
+fn cast_type_oid(ty: &str) -> Option<i32> {
+    // Strip pg_catalog. prefix and collapse spaces
+    let ty_norm = ty.trim().to_ascii_lowercase().replace("pg_catalog.", "").split_whitespace().collect::<Vec<_>>().join(" ");
+    let oid = if let Some(inner) = ty_norm.strip_suffix("[]") {
+        match inner.trim() {
+            // arrays of common types
+            "bool" | "boolean" => 1000,
+            "int2" | "smallint" => 1005,
+            "int" | "int4" | "integer" => 1007,
+            "int8" | "bigint" => 1016,
+            "real" | "float4" => 1021,
+            "double precision" | "float8" => 1022,
+            "text" | "varchar" | "character varying" | "bpchar" | "char" | "character" => 1009,
+            "bytea" => 1001,
+            "date" => 1182,
+            "timestamp" | "timestamp without time zone" => 1115,
+            "timestamptz" | "timestamp with time zone" => 1185,
+            "time" | "time without time zone" => 1183,
+            "numeric" | "decimal" => 1231,
+            _ => return None,
+        }
+    } else {
+        match ty_norm.as_str() {
+            // scalars
+            "int2" | "smallint" => 21,
+            "int" | "int4" | "integer" => 23,
+            "int8" | "bigint" => 20,
+            "real" | "float4" => 700,
+            "float8" | "double" | "double precision" => 701,
+            "text" | "varchar" | "character varying" | "bpchar" | "char" | "character" => 25,
+            "bool" | "boolean" => 16,
+            "bytea" => 17,
+            "date" => 1082,
+            "timestamp" | "timestamp without time zone" => 1114,
+            "timestamptz" | "timestamp with time zone" => 1184,
+            "time" | "time without time zone" => 1083,
+            "numeric" | "decimal" => 1700,
+            _ => return None,
+        }
+    };
+    Some(oid)
+}
+
+/// Parameter types for a Parse message. `declared` holds the OIDs sent by the client, which may be
+/// fewer than the `$n` placeholders or 0 (unspecified). Unspecified parameters are inferred from
+/// an explicit cast (`$1::int8`) or from `LIMIT $n` / `OFFSET $n` (int8); anything else is TEXT.
+pub fn infer_param_types(sql: &str, declared: &[i32]) -> Vec<i32> {
+    let re_dollar = Regex::new(r"\$([1-9][0-9]*)").expect("valid regex");
+    let max_idx = re_dollar.captures_iter(sql)
+        .filter_map(|c| c.get(1).and_then(|m| m.as_str().parse::<usize>().ok()))
+        .max().unwrap_or(0);
+    let mut types: Vec<i32> = declared.to_vec();
+    if types.len() < max_idx { types.resize(max_idx, 0); }
+    if !types.contains(&0) { return types; }
+    let mut inferred: Vec<i32> = vec![0; types.len()];
+    let mut set = |idx: usize, oid: i32| {
+        if idx > 0 && idx <= inferred.len() && inferred[idx - 1] == 0 { inferred[idx - 1] = oid; }
+    };
+    // explicit casts, including multi-word, schema-qualified and array types
+    let re_cast = Regex::new(r"\$([1-9][0-9]*)::([A-Za-z_][A-Za-z0-9_\.]*(?: (?:precision|varying|with time zone|without time zone))?(?:\[\])?)").expect("valid regex");
+    for cap in re_cast.captures_iter(sql) {
+        let idx: usize = cap.get(1).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
+        if let Some(oid) = cap.get(2).and_then(|m| cast_type_oid(m.as_str())) { set(idx, oid); }
+    }
+    // row limits
+    let re_limit = Regex::new(r"(?i)\b(?:limit|offset)\s+\$([1-9][0-9]*)").expect("valid regex");
+    for cap in re_limit.captures_iter(sql) {
+        if let Some(idx) = cap.get(1).and_then(|m| m.as_str().parse().ok()) { set(idx, 20); }
+    }
+    for (t, inf) in types.iter_mut().zip(inferred) {
+        if *t == 0 { *t = if inf != 0 { inf } else { PG_TYPE_TEXT }; }
+    }
+    debug!("pgwire parse: inferred {} parameter(s) with types {:?}", types.len(), types);
+    types
+}
+
 pub fn parse_startup_params(payload: &[u8]) -> std::collections::HashMap<String, String> {
     use std::collections::HashMap;
     let mut m = HashMap::new();
@@ -2,36 +2,24 @@ use anyhow::{anyhow, Result};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tracing::debug;
 use crate::pgwire_server::{misc::*, write_parameter, send::send_ready_with_status};
+use crate::pgwire_server::tls::PgStream;
 
-use crate::ident::DEFAULT_SCHEMA;
+use crate::identity::{md5_response_matches, LocalAuthProvider, ScramServer, ScramVerifier};
 
-pub async fn send_auth_ok_and_params(socket: &mut tokio::net::TcpStream, startup_params: &std::collections::HashMap<String, String>) -> Result<()> {
+pub async fn send_auth_ok_and_params(socket: &mut PgStream, startup_params: &std::collections::HashMap<String, String>, reported: &[(&str, String)]) -> Result<()> {
     // AuthenticationOk
     write_msg_header(socket, b'R', 8).await?; // len = 8
     write_i32(socket, 0).await?; // AuthenticationOk
-    // Commonly expected ParameterStatus fields for libpq/psycopg compatibility
-    write_parameter(socket, "server_version", "14.0").await?;
-    write_parameter(socket, "server_version_num", "140000").await?;
-    write_parameter(socket, "server_encoding", "UTF8").await?;
-    write_parameter(socket, "client_encoding", "UTF8").await?;
-    write_parameter(socket, "DateStyle", "ISO, MDY").await?;
-    write_parameter(socket, "integer_datetimes", "on").await?;
-    write_parameter(socket, "standard_conforming_strings", "on").await?;
-    write_parameter(socket, "TimeZone", "UTC").await?;
-    write_parameter(socket, "default_transaction_read_only", "off").await?;
-    write_parameter(socket, "is_superuser", "off").await?;
-    let sp = format!("\"$user\", {}", DEFAULT_SCHEMA);
-    write_parameter(socket, "search_path", &sp).await?;
-    // session_authorization and application_name from startup
+    // ParameterStatus for every reported session parameter (server_version, client_encoding,
+    // DateStyle, TimeZone, search_path, application_name, ...) from the GUC registry
+    for (name, value) in reported {
+        write_parameter(socket, name, value).await?;
+    }
+    // session_authorization from startup
     if let Some(user) = startup_params.get("user") {
         write_parameter(socket, "session_authorization", user).await?;
         debug!(target: "pgwire", "sent ParameterStatus session_authorization='{}'", user);
     }
-    // Echo back application_name if provided by client
-    if let Some(app_name) = startup_params.get("application_name") {
-        write_parameter(socket, "application_name", app_name).await?;
-        debug!(target: "pgwire", "sent ParameterStatus application_name='{}'", app_name);
-    }
     // BackendKeyData (K) - process ID and secret key for cancellation requests
     // According to common server behavior, send this after ParameterStatus
     socket.write_all(b"K").await?;
@@ -43,14 +31,14 @@ pub async fn send_auth_ok_and_params(socket: &mut tokio::net::TcpStream, startup
     send_ready_with_status(socket, b'I').await
 }
 
-pub async fn request_password(socket: &mut tokio::net::TcpStream) -> Result<()> {
+pub async fn request_password(socket: &mut PgStream) -> Result<()> {
     // AuthenticationCleartextPassword (code 3)
     write_msg_header(socket, b'R', 8).await?;
     write_i32(socket, 3).await?;
     Ok(())
 }
 
-pub async fn read_password_message(socket: &mut tokio::net::TcpStream) -> Result<String> {
+pub async fn read_password_message(socket: &mut PgStream) -> Result<String> {
     let mut tag = [0u8;1];
     socket.read_exact(&mut tag).await?;
     if tag[0] != b'p' { return Err(anyhow!("Expected PasswordMessage")); }
@@ -60,4 +48,137 @@ pub async fn read_password_message(socket: &mut tokio::net::TcpStream) -> Result
     // Trim trailing null if present
     if let Some(&0) = buf.last() { buf.pop(); }
     Ok(String::from_utf8_lossy(&buf).into_owned())
-}
\ No newline at end of file
+}
+/// Authentication method for a pgwire client, chosen per client address by `server.pgwire_auth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// No password (CLARIUM_PGWIRE_TRUST or a `trust` rule)
+    Trust,
+    /// Cleartext password checked against security.users
+    Password,
+    /// SCRAM-SHA-256 against the verifier stored with the user
+    ScramSha256,
+    /// md5 challenge against the md5 hash stored with the user
+    Md5,
+}
+
+impl AuthMethod {
+    fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "trust" => Ok(AuthMethod::Trust),
+            "password" => Ok(AuthMethod::Password),
+            "scram-sha-256" | "scram" => Ok(AuthMethod::ScramSha256),
+            "md5" => Ok(AuthMethod::Md5),
+            other => Err(anyhow!("unknown pgwire auth method '{}' (expected trust, password, scram-sha-256 or md5)", other)),
+        }
+    }
+}
+
+// Whether `ip` falls in `spec`: "all", an address, or a CIDR block (v4 or v6)
+fn address_matches(spec: &str, ip: std::net::IpAddr) -> Result<bool> {
+    use std::net::IpAddr;
+    if spec.eq_ignore_ascii_case("all") { return Ok(true); }
+    let (addr, bits) = match spec.split_once('/') {
+        Some((a, b)) => (a, Some(b.parse::<u32>().map_err(|_| anyhow!("invalid prefix length in '{}'", spec))?)),
+        None => (spec, None),
+    };
+    let net: IpAddr = addr.parse().map_err(|_| anyhow!("invalid address '{}' in server.pgwire_auth", spec))?;
+    // Compare IPv4-mapped IPv6 peers as IPv4
+    let ip = match ip { IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip), v4 => v4 };
+    Ok(match (net, ip) {
+        (IpAddr::V4(n), IpAddr::V4(a)) => {
+            let bits = bits.unwrap_or(32).min(32);
+            let mask = if bits == 0 { 0 } else { u32::MAX << (32 - bits) };
+            u32::from(n) & mask == u32::from(a) & mask
+        }
+        (IpAddr::V6(n), IpAddr::V6(a)) => {
+            let bits = bits.unwrap_or(128).min(128);
+            let mask = if bits == 0 { 0 } else { u128::MAX << (128 - bits) };
+            u128::from(n) & mask == u128::from(a) & mask
+        }
+        _ => false,
+    })
+}
+
+/// Method for a client at `ip` under `rules`: comma-separated `<address> <method>` entries
+/// (address = `all`, an IP or a CIDR block), first match wins. No match means `password`.
+pub fn auth_method_for(rules: &str, ip: std::net::IpAddr) -> Result<AuthMethod> {
+    for rule in rules.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        let mut parts = rule.split_whitespace();
+        let (Some(addr), Some(method), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(anyhow!("invalid server.pgwire_auth rule '{}': expected '<address> <method>'", rule));
+        };
+        if address_matches(addr, ip)? { return AuthMethod::parse(method); }
+    }
+    Ok(AuthMethod::Password)
+}
+
+// Read a PasswordMessage-family ('p') payload: SASLInitialResponse, SASLResponse or md5 password
+async fn read_auth_payload(socket: &mut PgStream) -> Result<Vec<u8>> {
+    let mut tag = [0u8;1];
+    socket.read_exact(&mut tag).await?;
+    if tag[0] != b'p' { return Err(anyhow!("Expected PasswordMessage")); }
+    let len = read_u32(socket).await? as usize;
+    let mut buf = vec![0u8; len.saturating_sub(4)];
+    socket.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+async fn send_auth_request(socket: &mut PgStream, code: i32, data: &[u8]) -> Result<()> {
+    write_msg_header(socket, b'R', (8 + data.len()) as i32).await?;
+    write_i32(socket, code).await?;
+    socket.write_all(data).await?;
+    socket.flush().await?;
+    Ok(())
+}
+
+/// SCRAM-SHA-256 exchange for `user`. Ok(true) when the client proved the password; the caller
+/// sends AuthenticationOk. Unknown users and users without a verifier run against a random
+/// verifier so they fail the same way as a wrong password.
+pub async fn authenticate_scram(socket: &mut PgStream, provider: &LocalAuthProvider, user: &str) -> Result<bool> {
+    // AuthenticationSASL (code 10): list of mechanisms
+    send_auth_request(socket, 10, b"SCRAM-SHA-256\0\0").await?;
+    // SASLInitialResponse: mechanism, int32 length, client-first-message
+    let buf = read_auth_payload(socket).await?;
+    let nul = buf.iter().position(|&b| b == 0).ok_or_else(|| anyhow!("malformed SASLInitialResponse"))?;
+    let mechanism = String::from_utf8_lossy(&buf[..nul]).into_owned();
+    if mechanism != "SCRAM-SHA-256" { return Err(anyhow!("unsupported SASL mechanism '{}'", mechanism)); }
+    let client_first = String::from_utf8_lossy(buf.get(nul + 5..).unwrap_or_default()).into_owned();
+    let verifier = match provider.scram_verifier(user) {
+        Some(v) => v,
+        None => {
+            debug!(target: "pgwire", "no SCRAM verifier for user '{}'", user);
+            let mut junk = [0u8; 16];
+            getrandom::getrandom(&mut junk).map_err(|e| anyhow!(e.to_string()))?;
+            ScramVerifier::with_salt(&String::from_utf8_lossy(&junk), &junk, crate::identity::SCRAM_ITERATIONS)
+        }
+    };
+    let (server, server_first) = ScramServer::start(&client_first, verifier)?;
+    // AuthenticationSASLContinue (code 11)
+    send_auth_request(socket, 11, server_first.as_bytes()).await?;
+    // SASLResponse: client-final-message
+    let client_final = String::from_utf8_lossy(&read_auth_payload(socket).await?).into_owned();
+    match server.finish(&client_final) {
+        Ok(server_final) => {
+            // AuthenticationSASLFinal (code 12)
+            send_auth_request(socket, 12, server_final.as_bytes()).await?;
+            Ok(true)
+        }
+        Err(e) => {
+            debug!(target: "pgwire", "SCRAM authentication failed for user '{}': {}", user, e);
+            Ok(false)
+        }
+    }
+}
+
+/// md5 challenge for `user`. Ok(true) when the response matches the stored md5 hash.
+pub async fn authenticate_md5(socket: &mut PgStream, provider: &LocalAuthProvider, user: &str) -> Result<bool> {
+    let mut salt = [0u8; 4];
+    getrandom::getrandom(&mut salt).map_err(|e| anyhow!(e.to_string()))?;
+    // AuthenticationMD5Password (code 5) with a 4-byte salt
+    send_auth_request(socket, 5, &salt).await?;
+    let mut buf = read_auth_payload(socket).await?;
+    if let Some(&0) = buf.last() { buf.pop(); }
+    let response = String::from_utf8_lossy(&buf).into_owned();
+    Ok(provider.md5_hash(user).is_some_and(|stored| md5_response_matches(&stored, &salt, &response)))
+}
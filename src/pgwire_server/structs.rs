@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use crate::identity::Principal;
+use crate::identity::{Principal, RequestContext};
+use polars::prelude::DataFrame;
 use pub_fields::pub_fields;
 
 #[derive(Clone)]
@@ -19,6 +20,51 @@ pub(crate) struct Portal {
     params: Vec<Option<String>>,
     param_formats: Vec<i16>,
     result_formats: Vec<i16>,
+    // rows still to send after an Execute with a row limit returned PortalSuspended
+    suspended: Option<SuspendedRows>,
+}
+
+// Result rows of a portal, kept between Execute messages that carry a row limit
+#[derive(Clone)]
+pub(crate) enum PendingRows {
+    // typed result from the SELECT path, encoded per result format when sent
+    Typed { df: DataFrame, oids: Vec<i32>, fmts: Vec<i16> },
+    // text rows from the generic executor path
+    Text(Vec<Vec<Option<String>>>),
+}
+
+impl PendingRows {
+    pub(crate) fn len(&self) -> usize {
+        match self { PendingRows::Typed { df, .. } => df.height(), PendingRows::Text(rows) => rows.len() }
+    }
+}
+
+#[derive(Clone)]
+#[pub_fields]
+pub(crate) struct SuspendedRows {
+    rows: PendingRows,
+    // number of rows already sent
+    sent: usize,
+    // CommandComplete tag once every row has been sent (e.g. "SELECT")
+    tag: String,
+}
+
+// Result of DECLARE ... CURSOR, read forward by FETCH/MOVE until CLOSE or disconnect
+#[pub_fields]
+pub(crate) struct Cursor {
+    columns: Vec<String>,
+    // column type oids for RowDescription
+    oids: Vec<i32>,
+    rows: PendingRows,
+    // rows already fetched or skipped
+    pos: usize,
+}
+
+impl Cursor {
+    // Result format code per column (BINARY cursors send binary rows)
+    pub(crate) fn formats(&self) -> Vec<i16> {
+        match &self.rows { PendingRows::Typed { fmts, .. } => fmts.clone(), PendingRows::Text(_) => Vec::new() }
+    }
 }
 
 #[pub_fields]
@@ -27,14 +73,35 @@ pub(crate) struct ConnState {
     current_schema: String,
     statements: HashMap<String, PreparedStatement>,
     portals: HashMap<String, Portal>,
+    // open cursors by (case-folded) name
+    cursors: HashMap<String, Cursor>,
     // if an error occurred in extended flow, we keep going until Sync
     in_error: bool,
+    // an extended-protocol message failed: discard further extended messages until Sync
+    skip_until_sync: bool,
     // inside explicit transaction block (BEGIN..)
     in_tx: bool,
     // unified identity principal for this connection (if authenticated)
     principal: Option<Principal>,
     // opaque session token when using LocalAuthProvider (optional)
     session_token: Option<String>,
+    // SessionManager session id; keys the backend so KILL SESSION closes the connection
+    session_id: Option<String>,
+    // pid in the backend activity registry (pg_stat_activity); assigned when the query loop starts
+    backend_pid: i32,
+}
+
+impl ConnState {
+    // Record a failed extended-protocol message; the rest of the batch is skipped until Sync
+    pub(crate) fn extended_error(&mut self) {
+        self.in_error = true;
+        self.skip_until_sync = true;
+    }
+
+    // Identity and database statements of this connection are authorized against
+    pub(crate) fn request_context(&self) -> RequestContext {
+        RequestContext { principal: self.principal.clone(), request_id: None, database: Some(self.current_database.clone()), filestore: None }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -346,3 +346,614 @@ mod create_table_catalog_tests {
             "random_test_xyz should appear in information_schema.tables after normalization, found: {:?}", table_names_str);
     }
 }
+
+#[cfg(test)]
+mod extended_protocol_tests {
+    use super::super::{handle_execute, run_query_loop};
+    use crate::pgwire_server::parse::infer_param_types;
+    use crate::pgwire_server::structs::*;
+    use crate::pgwire_server::tls::PgStream;
+    use crate::storage::SharedStore;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    pub(super) fn new_state() -> ConnState {
+        // Statements are authorized against the principal: run as an admin
+        let principal = crate::identity::Principal { user_id: "clarium".into(), roles: vec!["admin".into()], ..Default::default() };
+        ConnState { current_database: "clarium".into(), current_schema: "public".into(), statements: HashMap::new(), portals: HashMap::new(), cursors: HashMap::new(), in_error: false, skip_until_sync: false, in_tx: false, principal: Some(principal), session_token: None, session_id: None, backend_pid: 0 }
+    }
+
+    pub(super) async fn socket_pair() -> (TcpStream, PgStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        (client, PgStream::Plain(server))
+    }
+
+    pub(super) fn frame(tag: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        out.extend_from_slice(&((payload.len() + 4) as i32).to_be_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn execute_msg(portal: &str, max_rows: i32) -> Vec<u8> {
+        let mut p = portal.as_bytes().to_vec(); p.push(0);
+        p.extend_from_slice(&max_rows.to_be_bytes());
+        frame(b'E', &p)
+    }
+
+    pub(super) async fn read_msg(client: &mut TcpStream) -> (u8, Vec<u8>) {
+        let mut tag = [0u8; 1];
+        client.read_exact(&mut tag).await.unwrap();
+        let mut len = [0u8; 4];
+        client.read_exact(&mut len).await.unwrap();
+        let mut payload = vec![0u8; i32::from_be_bytes(len) as usize - 4];
+        client.read_exact(&mut payload).await.unwrap();
+        (tag[0], payload)
+    }
+
+    #[test]
+    fn test_infer_param_types_from_casts_and_limits() {
+        assert_eq!(infer_param_types("SELECT * FROM t WHERE a = $1::int8 AND b = $2", &[]), vec![20, 25]);
+        assert_eq!(infer_param_types("SELECT $1::double precision, $2::pg_catalog.text[]", &[]), vec![701, 1009]);
+        assert_eq!(infer_param_types("SELECT * FROM t LIMIT $1 OFFSET $2", &[]), vec![20, 20]);
+        // Declared types win; 0 and missing entries are inferred
+        assert_eq!(infer_param_types("SELECT $1::int4, $2::int4, $3", &[701, 0]), vec![701, 23, 25]);
+        assert!(infer_param_types("SELECT 1", &[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_row_limit_suspends_portal() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let table = "clarium/public/pgw_portal";
+        crate::server::exec::execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+        crate::server::exec::execute_query(&shared, &format!("INSERT INTO {} (v) VALUES (1), (2), (3)", table)).await.unwrap();
+
+        let mut state = new_state();
+        state.statements.insert("".into(), PreparedStatement { name: "".into(), sql: format!("SELECT v FROM {} ORDER BY v", table), param_types: vec![] });
+        state.portals.insert("".into(), Portal { name: "".into(), stmt_name: "".into(), params: vec![], param_formats: vec![], result_formats: vec![], suspended: None });
+        let (mut client, mut server) = socket_pair().await;
+
+        // First Execute: two rows, then PortalSuspended
+        client.write_all(&execute_msg("", 2)).await.unwrap();
+        handle_execute(&mut server, &shared, "", &mut state).await.unwrap();
+        let tags: Vec<u8> = vec![read_msg(&mut client).await.0, read_msg(&mut client).await.0, read_msg(&mut client).await.0];
+        assert_eq!(tags, b"DDs".to_vec());
+        assert_eq!(state.portals[""].suspended.as_ref().map(|s| s.sent), Some(2));
+
+        // Second Execute resumes with the remaining row and completes
+        client.write_all(&execute_msg("", 2)).await.unwrap();
+        handle_execute(&mut server, &shared, "", &mut state).await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'D');
+        let (tag, payload) = read_msg(&mut client).await;
+        assert_eq!(tag, b'C');
+        assert_eq!(payload, b"SELECT 1\0".to_vec());
+        assert!(state.portals[""].suspended.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_extended_error_skips_messages_until_sync() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let loop_task = tokio::spawn(async move {
+            let mut state = new_state();
+            run_query_loop(&mut server, &shared, "tester", &mut state, 0).await
+        });
+
+        // Execute on an unknown portal fails; the following Parse must be discarded
+        let mut batch = execute_msg("missing", 0);
+        batch.extend(frame(b'P', b"s1\0SELECT 1\0\0\0"));
+        batch.extend(frame(b'S', &[]));
+        client.write_all(&batch).await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'E');
+        let (tag, payload) = read_msg(&mut client).await;
+        assert_eq!((tag, payload), (b'Z', vec![b'I']));
+
+        // The next batch runs normally
+        let mut batch = frame(b'P', b"s1\0SELECT 1\0\0\0");
+        batch.extend(frame(b'S', &[]));
+        client.write_all(&batch).await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'1');
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        client.write_all(&frame(b'X', &[])).await.unwrap();
+        loop_task.await.unwrap().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tls_tests {
+    use super::super::handle_conn;
+    use crate::pgwire_server::tls::{load_acceptor, PgStream};
+    use crate::storage::SharedStore;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::rustls;
+
+    #[test]
+    fn test_load_acceptor_requires_cert_and_key() {
+        assert!(load_acceptor("", "").unwrap().is_none());
+        assert!(load_acceptor("server.crt", "").is_err());
+        let tmp = tempfile::tempdir().unwrap();
+        let missing = tmp.path().join("missing.pem");
+        assert!(load_acceptor(missing.to_str().unwrap(), missing.to_str().unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ssl_request_upgrades_connection_to_tls() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ck = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = tmp.path().join("server.crt");
+        let key_path = tmp.path().join("server.key");
+        std::fs::write(&cert_path, ck.cert.pem()).unwrap();
+        std::fs::write(&key_path, ck.key_pair.serialize_pem()).unwrap();
+        let acceptor = load_acceptor(cert_path.to_str().unwrap(), key_path.to_str().unwrap()).unwrap().unwrap();
+
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (sock, peer) = listener.accept().await.unwrap();
+            let _ = handle_conn(PgStream::Plain(sock), shared, 0, &peer.to_string(), Some(&acceptor)).await;
+        });
+
+        // SSLRequest is answered with 'S'
+        let mut tcp = TcpStream::connect(addr).await.unwrap();
+        tcp.write_all(&8i32.to_be_bytes()).await.unwrap();
+        tcp.write_all(&80877103i32.to_be_bytes()).await.unwrap();
+        let mut answer = [0u8; 1];
+        tcp.read_exact(&mut answer).await.unwrap();
+        assert_eq!(answer[0], b'S');
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(ck.cert.der().clone()).unwrap();
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions().unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+        let name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut tls = connector.connect(name, tcp).await.unwrap();
+
+        // The StartupMessage travels over TLS and gets an authentication response
+        let mut startup = Vec::new();
+        startup.extend_from_slice(&196608i32.to_be_bytes());
+        startup.extend_from_slice(b"user\0clarium\0\0");
+        tls.write_all(&((startup.len() + 4) as i32).to_be_bytes()).await.unwrap();
+        tls.write_all(&startup).await.unwrap();
+        let mut tag = [0u8; 1];
+        tls.read_exact(&mut tag).await.unwrap();
+        assert_eq!(tag[0], b'R');
+        drop(tls);
+        server.await.unwrap();
+    }
+}
+
+#[cfg(test)]
+mod auth_tests {
+    use crate::identity::{md5_password_hash, md5_response_matches, LocalAuthProvider, ScramServer, ScramVerifier, SessionManager};
+    use crate::pgwire_server::security::{auth_method_for, AuthMethod};
+    use base64::Engine;
+
+    #[test]
+    fn test_scram_exchange_matches_rfc7677_vector() {
+        let salt = base64::engine::general_purpose::STANDARD.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap();
+        let verifier = ScramVerifier::with_salt("pencil", &salt, 4096);
+        assert_eq!(ScramVerifier::parse(&verifier.encode()), Some(verifier.clone()));
+
+        let (server, server_first) = ScramServer::start_with_nonce("n,,n=user,r=rOprNGfwEbeRWgbNEkqO", verifier, "%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0").unwrap();
+        assert_eq!(server_first, "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096");
+        let server_final = server.finish("c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=").unwrap();
+        assert_eq!(server_final, "v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=");
+        // A proof computed from another password is rejected
+        assert!(server.finish("c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=").is_err());
+    }
+
+    #[test]
+    fn test_md5_challenge_response() {
+        let stored = md5_password_hash("alice", "secret");
+        assert_eq!(stored, "md54a0a68b43b6cd5cf266fa02f196e2371");
+        assert!(md5_response_matches(&stored, &[1, 2, 3, 4], "md598a0412b9c31436fc53776e863350083"));
+        assert!(!md5_response_matches(&stored, &[4, 3, 2, 1], "md598a0412b9c31436fc53776e863350083"));
+    }
+
+    #[test]
+    fn test_auth_method_rules_first_match_wins() {
+        let rules = "127.0.0.1 trust, 10.0.0.0/8 md5, ::1 md5, all scram-sha-256";
+        assert_eq!(auth_method_for(rules, "127.0.0.1".parse().unwrap()).unwrap(), AuthMethod::Trust);
+        assert_eq!(auth_method_for(rules, "10.20.30.40".parse().unwrap()).unwrap(), AuthMethod::Md5);
+        assert_eq!(auth_method_for(rules, "::1".parse().unwrap()).unwrap(), AuthMethod::Md5);
+        assert_eq!(auth_method_for(rules, "192.168.1.5".parse().unwrap()).unwrap(), AuthMethod::ScramSha256);
+        // IPv4-mapped IPv6 peers match IPv4 rules
+        assert_eq!(auth_method_for(rules, "::ffff:10.1.2.3".parse().unwrap()).unwrap(), AuthMethod::Md5);
+        // No rules keeps cleartext password auth
+        assert_eq!(auth_method_for("", "192.168.1.5".parse().unwrap()).unwrap(), AuthMethod::Password);
+        assert!(auth_method_for("all kerberos", "127.0.0.1".parse().unwrap()).is_err());
+        assert!(auth_method_for("10.0.0.0/x md5", "127.0.0.1".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_local_users_get_verifiers_when_password_is_set() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().to_string_lossy().into_owned();
+        let perms = crate::security::Perms { select: true, ..Default::default() };
+        crate::security::add_user(&root, crate::security::Scope::Global, "scram_user", "s3cret", perms).unwrap();
+        let provider = LocalAuthProvider::new(root.clone(), SessionManager::default());
+        let v = provider.scram_verifier("scram_user").unwrap();
+        assert_eq!(ScramVerifier::with_salt("s3cret", &v.salt, v.iterations), v);
+        assert_eq!(provider.md5_hash("scram_user"), Some(md5_password_hash("scram_user", "s3cret")));
+        assert!(provider.scram_verifier("nobody").is_none());
+
+        // Changing the password replaces the verifiers; other changes keep them
+        crate::security::alter_user(&root, crate::security::Scope::Global, "scram_user", Some("n3w"), None, None).unwrap();
+        let v2 = provider.scram_verifier("scram_user").unwrap();
+        assert_eq!(ScramVerifier::with_salt("n3w", &v2.salt, v2.iterations), v2);
+        crate::security::alter_user(&root, crate::security::Scope::Global, "scram_user", None, Some(true), None).unwrap();
+        assert_eq!(provider.scram_verifier("scram_user"), Some(v2));
+
+        let resp = provider.login_verified("scram_user", None, None);
+        assert_eq!(resp.session.principal.user_id, "scram_user");
+        assert!(resp.session.principal.roles.iter().any(|r| r == "admin"));
+    }
+}
+
+#[cfg(test)]
+mod binary_format_tests {
+    use crate::pgwire_server::encodedecode::{encode_array_binary, encode_binary_scalar};
+    use crate::pgwire_server::oids::map_polars_dtype_to_pg_oid;
+    use polars::prelude::*;
+
+    fn encode(oid: i32, av: AnyValue<'_>) -> Option<Vec<u8>> {
+        let mut buf = Vec::new();
+        if !encode_binary_scalar(&mut buf, oid, &av) { return None; }
+        let len = i32::from_be_bytes(buf[..4].try_into().unwrap());
+        assert_eq!(len as usize, buf.len() - 4, "length prefix must match the value");
+        Some(buf[4..].to_vec())
+    }
+
+    #[test]
+    fn test_integer_and_float_widths_follow_declared_oid() {
+        assert_eq!(encode(16, AnyValue::Boolean(true)), Some(vec![1]));
+        assert_eq!(encode(21, AnyValue::Int8(-2)), Some((-2i16).to_be_bytes().to_vec()));
+        assert_eq!(encode(23, AnyValue::Int32(7)), Some(7i32.to_be_bytes().to_vec()));
+        // Unsigned columns are declared int8
+        assert_eq!(map_polars_dtype_to_pg_oid(&DataType::UInt32), 20);
+        assert_eq!(encode(20, AnyValue::UInt32(4_000_000_000)), Some(4_000_000_000i64.to_be_bytes().to_vec()));
+        assert_eq!(encode(20, AnyValue::UInt64(u64::MAX)), None);
+        assert_eq!(encode(700, AnyValue::Float32(1.5)), Some(1.5f32.to_bits().to_be_bytes().to_vec()));
+        assert_eq!(encode(701, AnyValue::Float32(0.25)), Some(0.25f64.to_bits().to_be_bytes().to_vec()));
+        assert_eq!(encode(17, AnyValue::Binary(&[0, 255])), Some(vec![0, 255]));
+        assert_eq!(encode(25, AnyValue::String("héllo")), Some("héllo".as_bytes().to_vec()));
+        assert_eq!(encode(23, AnyValue::String("7")), None);
+    }
+
+    #[test]
+    fn test_temporal_values_use_pg_epoch() {
+        // 2000-01-01 is day 0 / microsecond 0 in PostgreSQL
+        assert_eq!(encode(1082, AnyValue::Date(10_957)), Some(0i32.to_be_bytes().to_vec()));
+        assert_eq!(encode(1114, AnyValue::Datetime(946_684_800_000, TimeUnit::Milliseconds, None)), Some(0i64.to_be_bytes().to_vec()));
+        assert_eq!(encode(1114, AnyValue::Datetime(946_684_800_000_000_001, TimeUnit::Nanoseconds, None)), Some(0i64.to_be_bytes().to_vec()));
+        // Before 1970 nanoseconds round down, not toward zero
+        let pre_unix = -1_000_000i64 * 946_684_800 - 1;
+        assert_eq!(encode(1184, AnyValue::Datetime(-1, TimeUnit::Nanoseconds, None)), Some(pre_unix.to_be_bytes().to_vec()));
+        assert_eq!(encode(1083, AnyValue::Time(3_600_000_000_000)), Some(3_600_000_000i64.to_be_bytes().to_vec()));
+        let mut interval = 90_000_000i64.to_be_bytes().to_vec();
+        interval.extend_from_slice(&[0; 8]);
+        assert_eq!(encode(1186, AnyValue::Duration(90_000, TimeUnit::Milliseconds)), Some(interval));
+    }
+
+    #[test]
+    fn test_vector_column_encodes_as_float4_array() {
+        let emb = Series::new("emb".into(), [Some(1.0f32), None, Some(-2.5f32)]);
+        let list_dtype = DataType::List(Box::new(DataType::Float32));
+        assert_eq!(map_polars_dtype_to_pg_oid(&list_dtype), 1021);
+        let mut buf = Vec::new();
+        encode_array_binary(&mut buf, 1021, &emb);
+        let mut expected = Vec::new();
+        for v in [1i32, 1, 700, 3, 1] { expected.extend_from_slice(&v.to_be_bytes()); }
+        expected.extend_from_slice(&4i32.to_be_bytes());
+        expected.extend_from_slice(&1.0f32.to_bits().to_be_bytes());
+        expected.extend_from_slice(&(-1i32).to_be_bytes());
+        expected.extend_from_slice(&4i32.to_be_bytes());
+        expected.extend_from_slice(&(-2.5f32).to_bits().to_be_bytes());
+        assert_eq!(i32::from_be_bytes(buf[..4].try_into().unwrap()) as usize, expected.len());
+        assert_eq!(&buf[4..], &expected[..]);
+    }
+
+    #[test]
+    fn test_numeric_groups_align_on_decimal_point() {
+        use crate::pgwire_server::encodedecode::decode_pg_numeric_to_string;
+        let header = |ndigits: i16, weight: i16, sign: u16, dscale: i16| {
+            let mut v = Vec::new();
+            v.extend_from_slice(&ndigits.to_be_bytes());
+            v.extend_from_slice(&weight.to_be_bytes());
+            v.extend_from_slice(&sign.to_be_bytes());
+            v.extend_from_slice(&dscale.to_be_bytes());
+            v
+        };
+        // 12.50 -> groups [12, 5000], weight 0
+        let mut expected = header(2, 0, 0, 2);
+        for d in [12i16, 5000] { expected.extend_from_slice(&d.to_be_bytes()); }
+        assert_eq!(encode(1700, AnyValue::Decimal(1250, 2)), Some(expected));
+        // 10000 -> single group [1], weight 1; the zero group is implied
+        let mut expected = header(1, 1, 0, 0);
+        expected.extend_from_slice(&1i16.to_be_bytes());
+        assert_eq!(encode(1700, AnyValue::Decimal(10_000, 0)), Some(expected));
+        // -0.0001 -> single group [1], weight -1
+        let mut expected = header(1, -1, 0x4000, 4);
+        expected.extend_from_slice(&1i16.to_be_bytes());
+        assert_eq!(encode(1700, AnyValue::Decimal(-1, 4)), Some(expected));
+
+        for (v, scale, text) in [(1250i128, 2usize, "12.50"), (10_000, 0, "10000"), (-1, 4, "-0.0001"), (-1_000_050, 2, "-10000.50"), (0, 3, "0.000")] {
+            let bytes = encode(1700, AnyValue::Decimal(v, scale)).unwrap();
+            assert_eq!(decode_pg_numeric_to_string(&bytes).as_deref(), Some(text));
+        }
+    }
+}
+
+#[cfg(test)]
+mod copy_out_tests {
+    use super::extended_protocol_tests::{new_state, read_msg, socket_pair};
+    use super::super::handle_query;
+    use crate::storage::SharedStore;
+
+    #[tokio::test]
+    async fn test_copy_to_stdout_streams_copy_data() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let table = "clarium/public/pgw_copy_out";
+        crate::server::exec::execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+        crate::server::exec::execute_query(&shared, &format!("INSERT INTO {} (id, note) VALUES (1, 'a,b'), (2, NULL)", table)).await.unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let mut state = new_state();
+
+        let sql = format!("COPY (SELECT id, note FROM {} ORDER BY id) TO STDOUT WITH (FORMAT csv, HEADER)", table);
+        handle_query(&mut server, &shared, "tester", &mut state, &sql).await.unwrap();
+        let (tag, payload) = read_msg(&mut client).await;
+        assert_eq!(tag, b'H');
+        assert_eq!(payload, vec![0, 0, 2, 0, 0, 0, 0]);
+        let mut lines = Vec::new();
+        loop {
+            let (tag, payload) = read_msg(&mut client).await;
+            if tag != b'd' { assert_eq!(tag, b'c'); break; }
+            lines.push(String::from_utf8(payload).unwrap());
+        }
+        assert_eq!(lines, vec!["id,note\n", "1,\"a,b\"\n", "2,\n"]);
+        assert_eq!(read_msg(&mut client).await, (b'C', b"COPY 2\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        // Text format leaves commas alone and spells NULL as \N
+        handle_query(&mut server, &shared, "tester", &mut state, &format!("COPY {} (note) TO STDOUT", table)).await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'H');
+        let mut rows = Vec::new();
+        loop {
+            let (tag, payload) = read_msg(&mut client).await;
+            if tag != b'd' { break; }
+            rows.push(String::from_utf8(payload).unwrap());
+        }
+        rows.sort();
+        assert_eq!(rows, vec!["\\N\n", "a,b\n"]);
+        assert_eq!(read_msg(&mut client).await, (b'C', b"COPY 2\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+    }
+}
+
+#[cfg(test)]
+mod cursor_tests {
+    use super::extended_protocol_tests::{new_state, read_msg, socket_pair};
+    use super::super::cursor::{parse_cursor_command, CursorCommand};
+    use super::super::handle_query;
+    use crate::storage::SharedStore;
+
+    fn parse(sql: &str) -> CursorCommand { parse_cursor_command(sql).unwrap().unwrap() }
+
+    #[test]
+    fn test_parse_cursor_commands() {
+        assert_eq!(parse("DECLARE C1 BINARY NO SCROLL CURSOR WITH HOLD FOR SELECT 1;"),
+            CursorCommand::Declare { name: "c1".into(), binary: true, query: "SELECT 1".into() });
+        assert_eq!(parse("fetch forward 2 from \"Big\""), CursorCommand::Fetch { name: "Big".into(), count: Some(2), move_only: false });
+        assert_eq!(parse("FETCH c"), CursorCommand::Fetch { name: "c".into(), count: Some(1), move_only: false });
+        assert_eq!(parse("FETCH ALL IN c"), CursorCommand::Fetch { name: "c".into(), count: None, move_only: false });
+        assert_eq!(parse("MOVE 10 c"), CursorCommand::Fetch { name: "c".into(), count: Some(10), move_only: true });
+        assert_eq!(parse("CLOSE ALL"), CursorCommand::Close(None));
+        assert_eq!(parse("CLOSE c"), CursorCommand::Close(Some("c".into())));
+        assert!(parse_cursor_command("FETCH PRIOR FROM c").unwrap().is_err());
+        assert!(parse_cursor_command("FETCH -1 FROM c").unwrap().is_err());
+        assert!(parse_cursor_command("DECLARE c FOR SELECT 1").unwrap().is_err());
+        assert!(parse_cursor_command("SELECT 1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_pages_through_declared_cursor() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let table = "clarium/public/pgw_cursor";
+        crate::server::exec::execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+        crate::server::exec::execute_query(&shared, &format!("INSERT INTO {} (id) VALUES (1), (2), (3)", table)).await.unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let mut state = new_state();
+
+        let sql = format!("DECLARE c CURSOR FOR SELECT id FROM {} ORDER BY id", table);
+        handle_query(&mut server, &shared, "tester", &mut state, &sql).await.unwrap();
+        assert_eq!(read_msg(&mut client).await, (b'C', b"DECLARE CURSOR\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        handle_query(&mut server, &shared, "tester", &mut state, "FETCH 2 FROM c").await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'T');
+        assert_eq!(read_msg(&mut client).await.0, b'D');
+        assert_eq!(read_msg(&mut client).await.0, b'D');
+        assert_eq!(read_msg(&mut client).await, (b'C', b"FETCH 2\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        // The remaining row, then an exhausted cursor
+        handle_query(&mut server, &shared, "tester", &mut state, "FETCH ALL FROM c").await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'T');
+        assert_eq!(read_msg(&mut client).await.0, b'D');
+        assert_eq!(read_msg(&mut client).await, (b'C', b"FETCH 1\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+        handle_query(&mut server, &shared, "tester", &mut state, "MOVE 5 c").await.unwrap();
+        assert_eq!(read_msg(&mut client).await, (b'C', b"MOVE 0\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        handle_query(&mut server, &shared, "tester", &mut state, "CLOSE c").await.unwrap();
+        assert_eq!(read_msg(&mut client).await, (b'C', b"CLOSE CURSOR\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+        assert!(state.cursors.is_empty());
+
+        handle_query(&mut server, &shared, "tester", &mut state, "FETCH c").await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'E');
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+    }
+}
+
+#[cfg(test)]
+mod guc_tests {
+    use super::extended_protocol_tests::{new_state, read_msg, socket_pair};
+    use super::super::handle_query;
+    use crate::storage::SharedStore;
+
+    #[tokio::test]
+    async fn test_set_reports_parameter_status() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let mut state = new_state();
+
+        handle_query(&mut server, &shared, "tester", &mut state, "SET TimeZone = 'Asia/Tokyo'").await.unwrap();
+        assert_eq!(read_msg(&mut client).await, (b'S', b"TimeZone\0Asia/Tokyo\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await, (b'C', b"SET\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        handle_query(&mut server, &shared, "tester", &mut state, "RESET TimeZone").await.unwrap();
+        assert_eq!(read_msg(&mut client).await, (b'S', b"TimeZone\0UTC\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await, (b'C', b"RESET\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        // Parameters that are not reported to clients produce no ParameterStatus
+        handle_query(&mut server, &shared, "tester", &mut state, "SET client_min_messages = warning").await.unwrap();
+        assert_eq!(read_msg(&mut client).await, (b'C', b"SET\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+    }
+}
+
+#[cfg(test)]
+mod comment_tests {
+    use super::extended_protocol_tests::{new_state, read_msg, socket_pair};
+    use super::super::handle_query;
+    use crate::storage::SharedStore;
+
+    #[tokio::test]
+    async fn test_simple_query_with_comments() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let table = "clarium/public/pgw_comments";
+        crate::server::exec::execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+        crate::server::exec::execute_query(&shared, &format!("INSERT INTO {} (id) VALUES (1), (2)", table)).await.unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let mut state = new_state();
+
+        // Semicolons inside comments do not split the statement; a comment-only tail is not a statement
+        let sql = format!("/* app=orm; v=2 */ SELECT id FROM {} ORDER BY id; -- done; bye", table);
+        handle_query(&mut server, &shared, "tester", &mut state, &sql).await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'T');
+        assert_eq!(read_msg(&mut client).await.0, b'D');
+        assert_eq!(read_msg(&mut client).await.0, b'D');
+        assert_eq!(read_msg(&mut client).await, (b'C', b"SELECT 2\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        // Statement-kind detection looks past a leading comment
+        let sql = format!("-- from the IDE\nDECLARE c CURSOR FOR SELECT id FROM {}", table);
+        handle_query(&mut server, &shared, "tester", &mut state, &sql).await.unwrap();
+        assert_eq!(read_msg(&mut client).await, (b'C', b"DECLARE CURSOR\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+    }
+}
+
+#[cfg(test)]
+mod error_response_tests {
+    use super::extended_protocol_tests::{new_state, read_msg, socket_pair};
+    use super::super::handle_query;
+    use crate::storage::SharedStore;
+
+    // ErrorResponse body: (field type, value) pairs
+    fn fields(body: &[u8]) -> Vec<(u8, String)> {
+        body.split(|b| *b == 0).filter(|f| !f.is_empty()).map(|f| (f[0], String::from_utf8_lossy(&f[1..]).into_owned())).collect()
+    }
+
+    fn field(body: &[u8], tag: u8) -> Option<String> {
+        fields(body).into_iter().find(|(t, _)| *t == tag).map(|(_, v)| v)
+    }
+
+    #[tokio::test]
+    async fn test_error_response_carries_sqlstate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let mut state = new_state();
+
+        handle_query(&mut server, &shared, "tester", &mut state, "RENAME foo bar").await.unwrap();
+        let (tag, body) = read_msg(&mut client).await;
+        assert_eq!(tag, b'E');
+        assert_eq!(field(&body, b'S').as_deref(), Some("ERROR"));
+        assert_eq!(field(&body, b'V').as_deref(), Some("ERROR"));
+        assert_eq!(field(&body, b'C').as_deref(), Some("42601"));
+        assert_eq!(field(&body, b'M').as_deref(), Some("Invalid RENAME syntax"));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        handle_query(&mut server, &shared, "tester", &mut state, "FETCH missing").await.unwrap();
+        let (tag, body) = read_msg(&mut client).await;
+        assert_eq!(tag, b'E');
+        assert_eq!(field(&body, b'C').as_deref(), Some("34000"));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+    }
+
+    #[tokio::test]
+    async fn test_revoked_select_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let table = "clarium/public/pgw_acl";
+        crate::server::exec::execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+        crate::server::exec::execute_query(&shared, &format!("INSERT INTO {} (id) VALUES (1)", table)).await.unwrap();
+        crate::server::exec::execute_query(&shared, "GRANT SELECT ON TABLE pgw_acl TO analyst").await.unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let mut state = new_state();
+        state.principal = Some(crate::identity::Principal { user_id: "analyst".into(), ..Default::default() });
+
+        let sql = format!("SELECT id FROM {}", table);
+        handle_query(&mut server, &shared, "analyst", &mut state, &sql).await.unwrap();
+        assert_eq!(read_msg(&mut client).await.0, b'T');
+        assert_eq!(read_msg(&mut client).await.0, b'D');
+        assert_eq!(read_msg(&mut client).await, (b'C', b"SELECT 1\0".to_vec()));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+
+        // Once revoked, the object privilege no longer lets the SELECT through
+        crate::server::exec::execute_query(&shared, "REVOKE SELECT ON TABLE pgw_acl FROM analyst").await.unwrap();
+        handle_query(&mut server, &shared, "analyst", &mut state, &sql).await.unwrap();
+        let (tag, body) = read_msg(&mut client).await;
+        assert_eq!(tag, b'E');
+        assert_eq!(field(&body, b'C').as_deref(), Some("42501"));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+    }
+
+    #[tokio::test]
+    async fn test_role_grants_gate_pgwire_ddl() {
+        let tmp = tempfile::tempdir().unwrap();
+        let shared = SharedStore::new(tmp.path()).unwrap();
+        let (mut client, mut server) = socket_pair().await;
+        let mut state = new_state();
+        // The security policy fallback lets db_writer write, but table DDL needs an admin grant
+        state.principal = Some(crate::identity::Principal { user_id: "writer".into(), roles: vec!["db_writer".into()], ..Default::default() });
+
+        handle_query(&mut server, &shared, "writer", &mut state, "CREATE TABLE clarium/public/pgw_ddl (id BIGINT)").await.unwrap();
+        let (tag, body) = read_msg(&mut client).await;
+        assert_eq!(tag, b'E');
+        assert_eq!(field(&body, b'C').as_deref(), Some("42501"));
+        assert_eq!(read_msg(&mut client).await.0, b'Z');
+        assert!(!shared.0.lock().db_dir("clarium/public/pgw_ddl").exists());
+    }
+}
@@ -0,0 +1,99 @@
+//! TLS for pgwire connections.
+//!
+//! A client asks for TLS with an SSLRequest before its StartupMessage. When
+//! `server.pgwire_tls_cert` and `server.pgwire_tls_key` name a PEM certificate chain and
+//! private key, the server answers 'S' and runs the rustls handshake on the same socket;
+//! otherwise it answers 'N' and the client may continue in plaintext. With
+//! `server.pgwire_tls_require` plaintext startups are rejected.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+/// A pgwire client connection, plaintext or upgraded to TLS after an SSLRequest.
+pub enum PgStream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl PgStream {
+    pub fn peer_addr(&self) -> io::Result<std::net::SocketAddr> {
+        match self {
+            PgStream::Plain(s) => s.peer_addr(),
+            PgStream::Tls(s) => s.get_ref().0.peer_addr(),
+        }
+    }
+
+    pub fn is_tls(&self) -> bool { matches!(self, PgStream::Tls(_)) }
+
+    /// Run the TLS handshake on a plaintext connection.
+    pub async fn start_tls(self, acceptor: &TlsAcceptor) -> Result<PgStream> {
+        match self {
+            PgStream::Plain(tcp) => Ok(PgStream::Tls(Box::new(acceptor.accept(tcp).await.context("pgwire TLS handshake failed")?))),
+            PgStream::Tls(_) => bail!("pgwire connection already uses TLS"),
+        }
+    }
+}
+
+impl AsyncRead for PgStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            PgStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            PgStream::Tls(s) => Pin::new(s.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for PgStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            PgStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            PgStream::Tls(s) => Pin::new(s.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            PgStream::Plain(s) => Pin::new(s).poll_flush(cx),
+            PgStream::Tls(s) => Pin::new(s.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            PgStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            PgStream::Tls(s) => Pin::new(s.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+/// TLS acceptor for the configured certificate chain and key, or None when TLS is not configured.
+pub fn load_acceptor(cert_path: &str, key_path: &str) -> Result<Option<TlsAcceptor>> {
+    if cert_path.trim().is_empty() && key_path.trim().is_empty() { return Ok(None); }
+    if cert_path.trim().is_empty() || key_path.trim().is_empty() {
+        bail!("pgwire TLS needs both server.pgwire_tls_cert and server.pgwire_tls_key");
+    }
+    let cert_pem = std::fs::read(cert_path).with_context(|| format!("reading pgwire TLS certificate '{}'", cert_path))?;
+    let key_pem = std::fs::read(key_path).with_context(|| format!("reading pgwire TLS key '{}'", key_path))?;
+    let certs = rustls_pemfile::certs(&mut cert_pem.as_slice())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .with_context(|| format!("parsing pgwire TLS certificate '{}'", cert_path))?;
+    if certs.is_empty() { bail!("no certificate found in '{}'", cert_path); }
+    let key = rustls_pemfile::private_key(&mut key_pem.as_slice())
+        .with_context(|| format!("parsing pgwire TLS key '{}'", key_path))?
+        .ok_or_else(|| anyhow!("no private key found in '{}'", key_path))?;
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("pgwire TLS certificate and key do not match")?;
+    Ok(Some(TlsAcceptor::from(Arc::new(config))))
+}
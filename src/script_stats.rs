@@ -0,0 +1,71 @@
+//! Per-script execution counters for Lua UDFs, TVFs, aggregates and trigger/job functions.
+//!
+//! Every call through the engine adds to the counters of the called function: invocations
+//! (one per row for scalars, one per group for aggregates, one per call otherwise), errors
+//! (including the ones turned into NULL by `null_on_error`) and wall time. Counters are kept
+//! in memory since startup and listed in `clarium_catalog.script_stats`; EXPLAIN ANALYZE
+//! reports the difference over the statement it runs.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+/// Counters for one function.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub total: Duration,
+}
+
+impl ScriptStats {
+    pub fn total_ms(&self) -> f64 { self.total.as_secs_f64() * 1000.0 }
+
+    /// Mean time per invocation; 0 before the first call.
+    pub fn mean_ms(&self) -> f64 { if self.calls == 0 { 0.0 } else { self.total_ms() / self.calls as f64 } }
+}
+
+static STATS: Lazy<Mutex<HashMap<String, ScriptStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Add `calls` invocations, `errors` of them failed, taking `elapsed` in total.
+pub fn record(name: &str, calls: u64, errors: u64, elapsed: Duration) {
+    let mut stats = STATS.lock();
+    let s = stats.entry(name.to_ascii_lowercase()).or_default();
+    s.calls += calls;
+    s.errors += errors;
+    s.total += elapsed;
+}
+
+/// Run `f`, which makes `calls` invocations of `name`, and record it. `f` counts the
+/// invocations that failed without failing the whole batch; when it returns an error and
+/// counted none, one failure is recorded.
+pub fn timed<R>(name: &str, calls: u64, f: impl FnOnce(&mut u64) -> anyhow::Result<R>) -> anyhow::Result<R> {
+    let started = Instant::now();
+    let mut errors = 0u64;
+    let out = f(&mut errors);
+    if out.is_err() && errors == 0 { errors = 1; }
+    record(name, calls, errors, started.elapsed());
+    out
+}
+
+/// All counters, ordered by name.
+pub fn snapshot() -> Vec<(String, ScriptStats)> {
+    let mut out: Vec<(String, ScriptStats)> = STATS.lock().iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+/// What changed since `before` (an earlier [`snapshot`]), most time first. Calls made by
+/// other sessions in the meantime are included.
+pub fn since(before: &[(String, ScriptStats)]) -> Vec<(String, ScriptStats)> {
+    let before: HashMap<&str, &ScriptStats> = before.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    let mut out: Vec<(String, ScriptStats)> = snapshot().into_iter().filter_map(|(name, now)| {
+        let prev = before.get(name.as_str()).cloned().cloned().unwrap_or_default();
+        let d = ScriptStats { calls: now.calls - prev.calls, errors: now.errors - prev.errors, total: now.total.saturating_sub(prev.total) };
+        (d.calls > 0 || d.errors > 0).then_some((name, d))
+    }).collect();
+    out.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(&b.0)));
+    out
+}
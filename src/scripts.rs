@@ -17,6 +17,10 @@ use tracing::debug;
 use std::io::Write as _;
 use std::fs::OpenOptions;
 
+/// Rows accumulated into one partial state by stateful aggregates before partial states are
+/// merged.
+pub const AGGREGATE_PARTITION_ROWS: usize = 1024;
+
 #[derive(Clone, Default)]
 pub struct ScriptRegistry {
     inner: std::sync::Arc<Mutex<HashMap<String, String>>>, // name -> source
@@ -62,6 +66,21 @@ impl ScriptRegistry {
         Ok(())
     }
 
+    /// Load a script created with a known kind and record its metadata from the embedded
+    /// JSON docstring or a `<name>__meta()` function, like scripts loaded from the schema
+    /// folders. The version keeps counting so cached Lua states are rebuilt.
+    pub fn register_script(&self, name: &str, code: &str, kind: ScriptKind) -> Result<()> {
+        self.load_script_text(name, code)?;
+        let version = self.get_meta(name).map(|m| m.version).unwrap_or(0);
+        let mut meta = match Self::parse_embedded_meta(code, &kind) {
+            Some(meta) => meta,
+            None => self.fetch_meta_via_lua(&Self::norm(name), &kind)?,
+        };
+        meta.version = meta.version.max(version);
+        self.set_meta(name, meta);
+        Ok(())
+    }
+
     /// Set or update metadata for a function.
     pub fn set_meta(&self, name: &str, meta: ScriptMeta) {
         let key = Self::norm(name);
@@ -104,13 +123,27 @@ impl ScriptRegistry {
         exists
     }
 
+    /// Whether `name` is registered with exactly this source.
+    pub fn is_loaded(&self, name: &str, code: &str) -> bool {
+        self.inner.lock().get(&Self::norm(name)).map(|c| c == code).unwrap_or(false)
+    }
+
+    /// All registered functions as (name, source, metadata), sorted by name.
+    pub fn list_functions(&self) -> Vec<(String, String, Option<ScriptMeta>)> {
+        let g = self.inner.lock();
+        let m = self.meta.lock();
+        let mut out: Vec<(String, String, Option<ScriptMeta>)> = g.iter().map(|(k, code)| (k.clone(), code.clone(), m.get(k).cloned())).collect();
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+
     /// Execute a registered Lua function by name with JSON-compatible arguments.
     /// NOTE: This path is kept for legacy use but not used by the engine anymore.
     pub fn call_function_json(&self, name: &str, args: &[serde_json::Value]) -> Result<serde_json::Value> {
         use mlua::{Value as LVal, MultiValue};
         debug!("[UDF CALL] call_function_json: attempting to call function '{}' with {} args", name, args.len());
         debug!("[UDF CALL] call_function_json: registry has_function('{}')={}", name, self.has_function(name));
-        let result = self.with_prepared_lua(|lua| {
+        let result = crate::script_stats::timed(name, 1, |_| self.with_prepared_lua(|lua| {
             let globals = lua.globals();
             let lname = Self::norm(name);
             debug!("[UDF CALL] call_function_json: normalized name='{}', looking up in Lua globals", lname);
@@ -133,7 +166,7 @@ impl ScriptRegistry {
             let j = lua_to_json(out)?;
             debug!("[UDF CALL] call_function_json: successfully called '{}', result type: {:?}", name, j);
             Ok(j)
-        });
+        }));
         if let Err(ref e) = result {
             debug!("[UDF CALL] call_function_json: error calling '{}': {}", name, e);
         }
@@ -144,11 +177,17 @@ impl ScriptRegistry {
     /// mapping JSON nulls to real Lua nil values. This is intended for aggregate
     /// UDFs which often perform arithmetic over arrays and must treat missing
     /// values as nil, not the string "nil".
+    ///
+    /// `args` holds one array of group values per argument. Aggregates that define
+    /// `<name>__accumulate` are evaluated statefully instead (see [`AGGREGATE_PARTITION_ROWS`]).
     pub fn call_function_json_aggregate(&self, name: &str, args: &[serde_json::Value]) -> Result<serde_json::Value> {
         use mlua::{Value as LVal, MultiValue};
-        self.with_prepared_lua(|lua| {
+        crate::script_stats::timed(name, 1, |_| self.with_prepared_lua(|lua| {
             let globals = lua.globals();
             let lname = Self::norm(name);
+            if let Ok(acc) = globals.get::<_, mlua::Function>(format!("{}__accumulate", lname).as_str()) {
+                return Self::run_stateful_aggregate(lua, &lname, acc, args);
+            }
             let func: mlua::Function = globals.get(lname.as_str())?;
             let mut mvals = MultiValue::new();
             for a in args.iter().rev() {
@@ -159,22 +198,70 @@ impl ScriptRegistry {
             let out: LVal = func.call(mvals)?;
             let j = lua_to_json(out)?;
             Ok(j)
-        })
+        }))
     }
 
-    /// Load all .lua scripts in subfolders `scalars`, `aggregates`, and `constraints` into the registry
+    // Stateful aggregate contract:
+    //   <name>__init()                  -> empty state (nil when not defined)
+    //   <name>__accumulate(state, ...)  -> state after one row; NULL arguments arrive as nil
+    //   <name>__merge(a, b)             -> state combining two partial states
+    //   <name>__finalize(state)         -> result (the state itself when not defined)
+    // With a merge callback the group's rows are accumulated in partitions of
+    // AGGREGATE_PARTITION_ROWS whose states are merged in row order, the same shape as
+    // partitioned or parallel execution; without one they all go into a single state.
+    fn run_stateful_aggregate<'lua>(lua: &'lua mlua::Lua, lname: &str, acc: mlua::Function<'lua>, args: &[serde_json::Value]) -> Result<serde_json::Value> {
+        use mlua::{Value as LVal, MultiValue};
+        let globals = lua.globals();
+        let callback = |suffix: &str| globals.get::<_, mlua::Function>(format!("{}__{}", lname, suffix).as_str()).ok();
+        let (init, merge, finalize) = (callback("init"), callback("merge"), callback("finalize"));
+        let cols: Vec<&[serde_json::Value]> = args.iter().map(|a| a.as_array().map(|v| v.as_slice()).unwrap_or(&[])).collect();
+        let nrows = cols.iter().map(|c| c.len()).max().unwrap_or(0);
+        let chunk = if merge.is_some() { AGGREGATE_PARTITION_ROWS } else { nrows.max(1) };
+        let fail = |stage: &str, e: mlua::Error| anyhow!("aggregate '{}' {} failed: {}", lname, stage, e);
+        let mut state: Option<LVal> = None;
+        let mut start = 0usize;
+        // At least one partition so empty groups still go through init and finalize
+        loop {
+            let end = (start + chunk).min(nrows);
+            let mut part: LVal = match &init { Some(f) => f.call(()).map_err(|e| fail("init", e))?, None => LVal::Nil };
+            for r in start..end {
+                let mut vals: Vec<LVal> = Vec::with_capacity(cols.len() + 1);
+                vals.push(part);
+                for c in &cols { vals.push(json_to_lua_mode(lua, c.get(r).unwrap_or(&serde_json::Value::Null), NullMode::RealNil)?); }
+                part = acc.call(MultiValue::from_vec(vals)).map_err(|e| fail("accumulate", e))?;
+            }
+            state = Some(match (state, &merge) {
+                (Some(prev), Some(m)) => m.call((prev, part)).map_err(|e| fail("merge", e))?,
+                _ => part,
+            });
+            start = end;
+            if start >= nrows { break; }
+        }
+        let state = state.unwrap_or(LVal::Nil);
+        let out: LVal = match &finalize { Some(f) => f.call(state).map_err(|e| fail("finalize", e))?, None => state };
+        lua_to_json(out)
+    }
+
+    /// Load all .lua scripts in subfolders `scalars`, `aggregates`, `constraints` and `tvfs` into the registry
     /// and fetch optional metadata from one of (in order):
     /// 1) Sidecar JSON file `<name>.meta.json` next to the Lua file
     /// 2) Embedded JSON docstring at the top of the Lua file inside a block comment `--[[ { ... } ]]`
     /// 3) Calling a `<name>__meta()` Lua function if present in any loaded script
     /// 4) Fallback default metadata
+    ///
+    /// Bytecode comes from the `.luac` sidecars kept by `lua_bc` when they are current. Scripts
+    /// whose source is already registered unchanged keep their version, so prepared Lua states
+    /// are not rebuilt, and all `__meta()` lookups share one Lua state built after loading.
     pub fn load_from_schema_root(&self, dir: &Path) -> Result<()> {
         if !dir.exists() { return Ok(()); }
         let scalars = dir.join("scalars");
         let aggregates = dir.join("aggregates");
         let constraints = dir.join("constraints");
         let tvfs = dir.join("tvfs");
-        let load_dir = |folder: &Path, kind: ScriptKind| -> Result<()> {
+        let bc = crate::lua_bc::LuaBytecodeCache::global();
+        // Scripts whose metadata must come from a `<name>__meta()` call: (name, qualified, kind, path)
+        let mut via_lua: Vec<(String, String, ScriptKind, PathBuf)> = Vec::new();
+        let mut load_dir = |folder: &Path, kind: ScriptKind| -> Result<()> {
             if !folder.exists() { return Ok(()); }
             let rd = match fs::read_dir(folder) {
                 Ok(rd) => rd,
@@ -200,27 +287,34 @@ impl ScriptRegistry {
                             continue; 
                         }
                     };
-                    // Load under unqualified name
-                    if let Err(e) = self.load_script_text(&name, &code) {
-                        tracing::error!(target: "clarium::udf", "[UDF LOAD] Failed to register script '{}': {}", name, e);
-                        let _ = write_script_error_log_adjacent(&p, "register", &name, &format!("Failed to register script in registry: {}", e));
-                        // Do not attempt to set metadata if we couldn't register
-                        continue;
-                    }
-                    // Also expose all globally provided functions under pg_catalog.<name>
-                    // so clients using schema-qualified calls can resolve them.
                     let qualified = format!("pg_catalog.{}", name);
-                    if let Err(e) = self.load_script_text(&qualified, &code) {
-                        tracing::error!(target: "clarium::udf", "[UDF LOAD] Failed to register qualified script '{}': {}", qualified, e);
+                    let existing = self.get_meta(&name).filter(|_| self.is_loaded(&name, &code));
+                    // Warm the bytecode cache; compile errors surface when the script is run
+                    if let Ok(bytes) = bc.load_or_compile_file(&name, &p, &code) {
+                        bc.seed(&qualified, &code, bytes);
+                    }
+                    if existing.is_none() {
+                        // Load under unqualified name
+                        if let Err(e) = self.load_script_text(&name, &code) {
+                            tracing::error!(target: "clarium::udf", "[UDF LOAD] Failed to register script '{}': {}", name, e);
+                            let _ = write_script_error_log_adjacent(&p, "register", &name, &format!("Failed to register script in registry: {}", e));
+                            // Do not attempt to set metadata if we couldn't register
+                            continue;
+                        }
+                        // Also expose all globally provided functions under pg_catalog.<name>
+                        // so clients using schema-qualified calls can resolve them.
+                        if let Err(e) = self.load_script_text(&qualified, &code) {
+                            tracing::error!(target: "clarium::udf", "[UDF LOAD] Failed to register qualified script '{}': {}", qualified, e);
+                        }
                     }
                     // Try sidecar .meta.json
-                    let mut applied_meta = false;
+                    let mut applied_meta: Option<ScriptMeta> = None;
                     let sidecar = p.with_extension("meta.json");
                     if sidecar.exists() {
                         match fs::read_to_string(&sidecar) {
                             Ok(txt) => {
                                 match Self::parse_meta_json(&txt, &kind) {
-                                    Ok(meta) => { self.set_meta(&name, meta.clone()); self.set_meta(&qualified, meta); applied_meta = true; }
+                                    Ok(meta) => applied_meta = Some(meta),
                                     Err(e) => { 
                                         tracing::error!(target: "clarium::udf", "[UDF LOAD] Invalid meta sidecar for '{}': {}", p.display(), e);
                                         let _ = write_script_error_log_adjacent(&p, "meta", &name, &format!("Invalid .meta.json sidecar '{}': {}", sidecar.display(), e));
@@ -234,28 +328,19 @@ impl ScriptRegistry {
                         }
                     }
                     // Try embedded JSON docstring at top of Lua file: --[[ { ... } ]]
-                    if !applied_meta {
-                        if let Some(meta) = Self::parse_embedded_meta(&code, &kind) {
+                    if applied_meta.is_none() {
+                        applied_meta = Self::parse_embedded_meta(&code, &kind);
+                    }
+                    match (applied_meta, existing) {
+                        (Some(mut meta), Some(old)) => {
+                            meta.version = meta.version.max(old.version);
                             self.set_meta(&name, meta.clone());
                             self.set_meta(&qualified, meta);
-                            applied_meta = true;
-                        }
-                    }
-                    // Try to read metadata via meta function in a fresh Lua state
-                    if !applied_meta {
-                        match self.fetch_meta_via_lua(&name, &kind) {
-                            Ok(meta) => { self.set_meta(&name, meta.clone()); self.set_meta(&qualified, meta); applied_meta = true; }
-                            Err(e) => { 
-                                tracing::error!(target: "clarium::udf", "[UDF LOAD] Failed to fetch meta via Lua for '{}': {}", name, e);
-                                let _ = write_script_error_log_adjacent(&p, "meta", &name, &format!("Failed to fetch meta via Lua: {}", e));
-                            }
                         }
-                    }
-                    // default meta when not provided
-                    if !applied_meta {
-                        let meta = ScriptMeta { kind: kind.clone(), returns: Vec::new(), nullable: true, version: 0, tvf_columns: Vec::new() };
-                        self.set_meta(&name, meta.clone());
-                        self.set_meta(&qualified, meta);
+                        (Some(meta), None) => { self.set_meta(&name, meta.clone()); self.set_meta(&qualified, meta); }
+                        // Unchanged script without declared metadata: keep what it has
+                        (None, Some(_)) => {}
+                        (None, None) => via_lua.push((name, qualified, kind.clone(), p.clone())),
                     }
                 }
             }
@@ -265,6 +350,22 @@ impl ScriptRegistry {
         load_dir(&aggregates, ScriptKind::Aggregate)?;
         load_dir(&constraints, ScriptKind::Constraint)?;
         load_dir(&tvfs, ScriptKind::Tvf)?;
+        if via_lua.is_empty() { return Ok(()); }
+        // Try to read metadata via meta functions in one fresh Lua state
+        let lua = self.meta_lua(&dir.display().to_string());
+        for (name, qualified, kind, p) in via_lua {
+            let meta = match Self::meta_from_lua(&lua, &name, &kind) {
+                Ok(meta) => meta,
+                Err(e) => {
+                    tracing::error!(target: "clarium::udf", "[UDF LOAD] Failed to fetch meta via Lua for '{}': {}", name, e);
+                    let _ = write_script_error_log_adjacent(&p, "meta", &name, &format!("Failed to fetch meta via Lua: {}", e));
+                    // default meta when not provided
+                    ScriptMeta { kind: kind.clone(), returns: Vec::new(), nullable: true, version: 0, tvf_columns: Vec::new() }
+                }
+            };
+            self.set_meta(&name, meta.clone());
+            self.set_meta(&qualified, meta);
+        }
         Ok(())
     }
 
@@ -319,18 +420,27 @@ impl ScriptRegistry {
     }
 
     fn fetch_meta_via_lua(&self, name: &str, default_kind: &ScriptKind) -> Result<ScriptMeta> {
-        use mlua::Lua;
+        let lua = self.meta_lua(name);
+        Self::meta_from_lua(&lua, name, default_kind)
+    }
+
+    /// Fresh Lua state with every registered script loaded, for `<name>__meta()` calls.
+    fn meta_lua(&self, purpose: &str) -> mlua::Lua {
         let snapshot: std::collections::HashMap<String, String> = { self.inner.lock().clone() };
-        let lua = Lua::new();
+        let lua = mlua::Lua::new();
         for (n, code) in snapshot.iter() {
-            if let Err(e) = lua.load(code.as_str()).exec() {
+            if let Err(e) = exec_chunk(&lua, n, code) {
                 // Do not abort metadata fetch for other scripts; log and continue
-                tracing::error!(target: "clarium::udf", "[UDF META] Failed to load script '{}' while fetching meta for '{}': {}", n, name, e);
+                tracing::error!(target: "clarium::udf", "[UDF META] Failed to load script '{}' while fetching meta for '{}': {}", n, purpose, e);
                 // Attempt to write an adjacent .log for the failing script if we can resolve its file
-                let _ = write_script_error_log_for_name(n, "meta-load", &format!("Failed to load script while fetching meta for '{}': {}", name, e));
+                let _ = write_script_error_log_for_name(n, "meta-load", &format!("Failed to load script while fetching meta for '{}': {}", purpose, e));
                 continue;
             }
         }
+        lua
+    }
+
+    fn meta_from_lua(lua: &mlua::Lua, name: &str, default_kind: &ScriptKind) -> Result<ScriptMeta> {
         let globals = lua.globals();
         let meta_fn: Option<mlua::Function> = globals.get(format!("{}__meta", name).as_str()).ok();
         let mut meta = ScriptMeta { kind: default_kind.clone(), returns: Vec::new(), nullable: true, version: 0, tvf_columns: Vec::new() };
@@ -345,13 +455,23 @@ impl ScriptRegistry {
             };
             // Expect a table with fields: kind, returns (array of strings), nullable (bool)
             if let mlua::Value::Table(t) = v {
-                if let Ok(k) = t.get::<_, String>("kind") { meta.kind = if k.eq_ignore_ascii_case("aggregate") { ScriptKind::Aggregate } else if k.eq_ignore_ascii_case("constraint") { ScriptKind::Constraint } else { ScriptKind::Scalar }; }
+                if let Ok(k) = t.get::<_, String>("kind") { meta.kind = if k.eq_ignore_ascii_case("aggregate") { ScriptKind::Aggregate } else if k.eq_ignore_ascii_case("constraint") { ScriptKind::Constraint } else if k.eq_ignore_ascii_case("tvf") { ScriptKind::Tvf } else { ScriptKind::Scalar }; }
                 if let Ok(nul) = t.get::<_, bool>("nullable") { meta.nullable = nul; }
                 if let Ok(arr) = t.get::<_, mlua::Table>("returns") {
                     let mut outs: Vec<DataType> = Vec::new();
                     for s in arr.sequence_values::<String>().flatten() { outs.push(str_to_dtype(&s)?); }
                     meta.returns = outs;
                 }
+                // TVF output schema: columns = { {name = "col", type = "int64"}, ... }
+                if let Ok(cols) = t.get::<_, mlua::Table>("columns") {
+                    let mut out_cols: Vec<(String, DataType)> = Vec::new();
+                    for c in cols.sequence_values::<mlua::Table>().flatten() {
+                        let cname = c.get::<_, String>("name").unwrap_or_default();
+                        let ty = c.get::<_, String>("type").map_err(|_| anyhow!("TVF column missing type"))?;
+                        out_cols.push((cname, str_to_dtype(&ty)?));
+                    }
+                    meta.tvf_columns = out_cols;
+                }
             }
         }
         Ok(meta)
@@ -472,7 +592,7 @@ impl ScriptRegistry {
         }
 
         // Prepare Lua and call function
-        let df = self.with_prepared_lua(|lua| {
+        let df = crate::script_stats::timed(&lname, 1, |_| self.with_prepared_lua(|lua| {
             // Optionally register context accessor
             if let Some(dc) = ctx { Self::register_context_accessor(lua, &ContextInfo::from_data_context(dc))?; }
             let globals = lua.globals();
@@ -480,7 +600,7 @@ impl ScriptRegistry {
                 .map_err(|e| anyhow!("TVF '{}' not found: {}", lname, e))?;
             // Build argument list
             use mlua::{Value as LVal, MultiValue};
-            let mut mvals = MultiValue::new();
+            let mut args: Vec<LVal> = Vec::new();
             if let Some(arg_strs) = extract_args(s) {
                 for a in arg_strs {
                     let lv: LVal = if a.is_empty() { LVal::Nil }
@@ -500,15 +620,35 @@ impl ScriptRegistry {
                             else if a.eq_ignore_ascii_case("false") { LVal::Boolean(false) }
                             else { LVal::String(lua.create_string(&a)?) }
                         };
-                    mvals.push_front(lv);
+                    args.push(lv);
                 }
             }
-            let outv: LVal = func.call(mvals)
+            let outv: LVal = func.call(MultiValue::from_vec(args))
                 .map_err(|e| anyhow!("TVF '{}' execution error: {}", lname, e))?;
-            let j = lua_to_json(outv)?;
+            let j = match outv {
+                // Iterator: call until it returns nil; each call yields a row table or the
+                // row's values positionally
+                LVal::Function(next) => {
+                    let mut rows: Vec<serde_json::Value> = Vec::new();
+                    loop {
+                        let vals: MultiValue = next.call(())
+                            .map_err(|e| anyhow!("TVF '{}' execution error: {}", lname, e))?;
+                        let mut vals = vals.into_vec();
+                        if matches!(vals.first(), None | Some(LVal::Nil)) { break; }
+                        if matches!(vals.as_slice(), [LVal::Table(_)]) {
+                            rows.push(lua_to_json(vals.remove(0))?);
+                        } else {
+                            rows.push(serde_json::Value::Array(vals.into_iter().map(lua_to_json).collect::<Result<Vec<_>>>()?));
+                        }
+                    }
+                    serde_json::Value::Array(rows)
+                }
+                LVal::Nil => serde_json::Value::Array(Vec::new()),
+                other => lua_to_json(other)?,
+            };
             // Convert JSON to DataFrame
             Self::json_to_df(&j, self.get_meta(&lname))
-        })?;
+        }))?;
         Ok(Some(df))
     }
 
@@ -517,7 +657,11 @@ impl ScriptRegistry {
             serde_json::Value::Array(rows) => {
                 // Expect array of row objects or arrays
                 if rows.is_empty() {
-                    return Ok(DataFrame::new(vec![])?);
+                    // Declared columns keep their names and types with no rows
+                    let cols: Vec<Series> = meta.as_ref().map(|m| m.tvf_columns.as_slice()).unwrap_or(&[]).iter()
+                        .map(|(n, dt)| Self::json_values_to_series(n, &Vec::new(), Some(dt.clone())))
+                        .collect::<Result<_>>()?;
+                    return Ok(DataFrame::new(cols.into_iter().map(|s| s.into()).collect())?);
                 }
                 // Determine columns
                 let (col_names, col_values_per_row): (Vec<String>, Vec<Vec<serde_json::Value>>) = match &rows[0] {
@@ -531,7 +675,7 @@ impl ScriptRegistry {
                         let mut per_row: Vec<Vec<serde_json::Value>> = Vec::with_capacity(rows.len());
                         for r in rows {
                             let mut rowvals: Vec<serde_json::Value> = Vec::with_capacity(names.len());
-                            let robj = r.as_object().unwrap();
+                            let robj = r.as_object().ok_or_else(|| anyhow!("TVF rows must all be tables with named fields"))?;
                             for n in &names {
                                 rowvals.push(robj.get(n).cloned().unwrap_or(serde_json::Value::Null));
                             }
@@ -543,7 +687,7 @@ impl ScriptRegistry {
                         // Positional columns; use meta names or c0..cN
                         let names: Vec<String> = if let Some(m) = &meta { if !m.tvf_columns.is_empty() { m.tvf_columns.iter().map(|(n, _)| n.clone()).collect() } else { (0..arr0.len()).map(|i| format!("c{}", i)).collect() } } else { (0..arr0.len()).map(|i| format!("c{}", i)).collect() };
                         let mut per_row: Vec<Vec<serde_json::Value>> = Vec::with_capacity(rows.len());
-                        for r in rows { let rarr = r.as_array().unwrap(); per_row.push(rarr.clone()); }
+                        for r in rows { let rarr = r.as_array().ok_or_else(|| anyhow!("TVF rows must all be arrays of values"))?; per_row.push(rarr.clone()); }
                         (names, per_row)
                     }
                     _ => { return Err(anyhow!("Unsupported TVF row format")); }
@@ -576,7 +720,7 @@ impl ScriptRegistry {
         }
     }
 
-    fn json_values_to_series(name: &str, vals: &Vec<serde_json::Value>, hint: Option<DataType>) -> Result<Series> {
+    pub(crate) fn json_values_to_series(name: &str, vals: &Vec<serde_json::Value>, hint: Option<DataType>) -> Result<Series> {
         // Detect vectors (array of numbers) and other types
         let inferred = if let Some(dt) = hint { if matches!(dt, DataType::Null) { Self::infer_dtype(vals) } else { dt } } else { Self::infer_dtype(vals) };
         use serde_json::Value as JV;
@@ -885,6 +1029,22 @@ impl ScriptRegistry {
         hasher.finish()
     }
 
+    /// A new Lua VM with every registered script loaded, not shared with the per-thread
+    /// cache; script tests run in one so their globals never leak into UDF calls.
+    pub fn new_lua_state(&self) -> Result<mlua::Lua> {
+        let snapshot: HashMap<String, String> = { self.inner.lock().clone() };
+        let lua = mlua::Lua::new();
+        if let Err(e) = configure_lua_package_paths(&lua) {
+            tracing::debug!(target: "clarium::udf", "[UDF LUA] configure package paths failed: {}", e);
+        }
+        for (n, code) in snapshot.iter() {
+            if let Err(e) = exec_chunk(&lua, n, code) {
+                tracing::error!(target: "clarium::udf", "[UDF LUA] Failed to load script '{}' into Lua VM: {}", n, e);
+            }
+        }
+        Ok(lua)
+    }
+
     // Run a closure with a prepared Lua VM for this registry snapshot using a per-thread cache.
     fn with_prepared_lua<F, R>(&self, f: F) -> Result<R>
     where
@@ -921,7 +1081,7 @@ impl ScriptRegistry {
                 }
                 for (n, code) in snapshot.iter() { 
                     debug!("[UDF LUA] with_prepared_lua: loading script '{}' into Lua VM", n);
-                    if let Err(e) = lua.load(code.as_str()).exec() {
+                    if let Err(e) = exec_chunk(&lua, n, code) {
                         // Do not abort the whole VM build; log and continue with remaining scripts
                         tracing::error!(target: "clarium::udf", "[UDF LUA] Failed to load script '{}' into Lua VM: {}", n, e);
                         let _ = write_script_error_log_for_name(n, "vm-load", &format!("Failed to load script into Lua VM: {}", e));
@@ -959,7 +1119,7 @@ impl ScriptRegistry {
                 // First attempt: the registry may contain the function source but the current
                 // prepared Lua VM was created before it was added. Try to inject from registry.
                 if let Some(code) = { self.inner.lock().get(&lname).cloned() } {
-                    if let Err(e) = lua.load(code.as_str()).exec() {
+                    if let Err(e) = exec_chunk(lua, &lname, &code) {
                         tracing::debug!(target: "clarium::udf", "UDF '{}' inject-from-registry failed: {}", name, e);
                     } else {
                         // Retry lookup after injecting
@@ -1211,9 +1371,18 @@ fn write_script_error_log_for_name(func_name: &str, stage: &str, err_msg: &str)
     Ok(())
 }
 
+/// Run a script chunk in `lua` from its cached bytecode, so each script is parsed once rather
+/// than once per Lua state. Scripts that do not compile are run from source to get the error.
+fn exec_chunk(lua: &mlua::Lua, name: &str, code: &str) -> mlua::Result<()> {
+    match crate::lua_bc::LuaBytecodeCache::global().compiled(name, code) {
+        Ok(bc) => lua.load(&bc[..]).exec(),
+        Err(_) => lua.load(code).exec(),
+    }
+}
+
 /// Compute the scripts directory for a given database and schema under root.
 pub fn scripts_dir_for(root: &Path, db: &str, schema: &str) -> PathBuf {
-    root.join(db).join(schema).join("scripts")
+    crate::storage::attach::database_dir(root, db).join(schema).join("scripts")
 }
 
 /// Configure Lua `package.path` and `package.cpath` to include known packages folders.
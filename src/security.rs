@@ -2,8 +2,6 @@ use anyhow::{Result, anyhow};
 use std::path::{Path, PathBuf};
 use polars::prelude::*;
 use crate::server::exec::internal::constants::MASK;
-use argon2::{Argon2, PasswordHasher, PasswordVerifier};
-use password_hash::{SaltString, PasswordHash};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Scope<'a> { Global, Database(&'a str) }
@@ -18,7 +16,7 @@ pub struct Perms {
 }
 
 fn global_user_path(db_root: &str) -> PathBuf { Path::new(db_root).join("user.parquet") }
-fn db_user_path(db_root: &str, db: &str) -> PathBuf { Path::new(db_root).join(db).join("user.parquet") }
+fn db_user_path(db_root: &str, db: &str) -> PathBuf { crate::storage::attach::database_dir(Path::new(db_root), db).join("user.parquet") }
 
 fn mk_schema_df() -> DataFrame {
     let usernames: Series = Series::new("username".into(), Vec::<String>::new());
@@ -28,29 +26,53 @@ fn mk_schema_df() -> DataFrame {
     let perm_insert: Series = Series::new("perm_insert".into(), Vec::<bool>::new());
     let perm_calculate: Series = Series::new("perm_calculate".into(), Vec::<bool>::new());
     let perm_delete: Series = Series::new("perm_delete".into(), Vec::<bool>::new());
-    DataFrame::new(vec![usernames.into(), hashes.into(), is_admin.into(), perm_select.into(), perm_insert.into(), perm_calculate.into(), perm_delete.into()]).unwrap()
+    let scram: Series = Series::new("scram_verifier".into(), Vec::<String>::new());
+    let md5: Series = Series::new("md5_hash".into(), Vec::<String>::new());
+    let changed: Series = Series::new("password_changed_at".into(), Vec::<Option<i64>>::new());
+    let must_change: Series = Series::new("password_must_change".into(), Vec::<bool>::new());
+    DataFrame::new(vec![usernames.into(), hashes.into(), is_admin.into(), perm_select.into(), perm_insert.into(), perm_calculate.into(), perm_delete.into(), scram.into(), md5.into(), changed.into(), must_change.into()]).unwrap()
 }
 
-fn hash_password(password: &str) -> Result<String> {
-    let mut salt_bytes = [0u8; 16];
-    getrandom::getrandom(&mut salt_bytes).map_err(|e| anyhow!(e.to_string()))?;
-    let salt = SaltString::encode_b64(&salt_bytes).map_err(|e| anyhow!(e.to_string()))?;
-    let argon2 = Argon2::default();
-    let phc = argon2.hash_password(password.as_bytes(), &salt).map_err(|e| anyhow!(e.to_string()))?.to_string();
-    Ok(phc)
+// Verifier columns for challenge-response logins (pgwire SCRAM-SHA-256 / md5), derived when a password is set
+const VERIFIER_COLUMNS: &[&str] = &["scram_verifier", "md5_hash"];
+
+fn password_verifiers_for(username: &str, password: &str) -> Result<(String, String)> {
+    let scram = crate::identity::ScramVerifier::new(password)?.encode();
+    Ok((scram, crate::identity::md5_password_hash(username, password)))
 }
 
-pub fn verify_password(hash: &str, password: &str) -> bool {
-    if let Ok(parsed) = PasswordHash::new(hash) {
-        let argon2 = Argon2::default();
-        argon2.verify_password(password.as_bytes(), &parsed).is_ok()
-    } else { false }
+fn str_at(df: &DataFrame, col: &str, i: usize) -> String {
+    match df.column(col).and_then(|c| c.get(i)) {
+        Ok(AnyValue::String(s)) => s.to_string(),
+        Ok(AnyValue::StringOwned(s)) => s.to_string(),
+        _ => String::new(),
+    }
 }
 
+fn hash_password(password: &str) -> Result<String> { crate::identity::hash_password(password) }
+
+pub fn verify_password(hash: &str, password: &str) -> bool { crate::identity::verify_password(hash, password) }
+
+fn now_ms() -> i64 { chrono::Utc::now().timestamp_millis() }
+
 fn read_users(path: &Path) -> Result<DataFrame> {
     if !path.exists() { return Ok(mk_schema_df()); }
     let file = std::fs::File::open(path)?;
-    let df = ParquetReader::new(file).finish()?;
+    let mut df = ParquetReader::new(file).finish()?;
+    // Files written before the verifier columns existed: users without verifiers
+    // can only log in with their password until it is set again
+    for col in VERIFIER_COLUMNS {
+        if !df.get_column_names().iter().any(|n| n.as_str() == *col) {
+            df.with_column(Series::new((*col).into(), vec![String::new(); df.height()]))?;
+        }
+    }
+    // ... and before password rotation was tracked: change time unknown
+    if !df.get_column_names().iter().any(|n| n.as_str() == "password_changed_at") {
+        df.with_column(Series::new("password_changed_at".into(), vec![None::<i64>; df.height()]))?;
+    }
+    if !df.get_column_names().iter().any(|n| n.as_str() == "password_must_change") {
+        df.with_column(Series::new("password_must_change".into(), vec![false; df.height()]))?;
+    }
     Ok(df)
 }
 
@@ -81,7 +103,12 @@ pub fn ensure_default_admin(db_root: &str) -> Result<()> {
     let perm_insert = Series::new("perm_insert".into(), vec![true]);
     let perm_calculate = Series::new("perm_calculate".into(), vec![true]);
     let perm_delete = Series::new("perm_delete".into(), vec![true]);
-    let df = DataFrame::new(vec![usernames.into(), hashes.into(), is_admin.into(), perm_select.into(), perm_insert.into(), perm_calculate.into(), perm_delete.into()])?;
+    let (scram, md5) = password_verifiers_for("clarium", "clarium")?;
+    let scram = Series::new("scram_verifier".into(), vec![scram]);
+    let md5 = Series::new("md5_hash".into(), vec![md5]);
+    let changed = Series::new("password_changed_at".into(), vec![Some(now_ms())]);
+    let must_change = Series::new("password_must_change".into(), vec![false]);
+    let df = DataFrame::new(vec![usernames.into(), hashes.into(), is_admin.into(), perm_select.into(), perm_insert.into(), perm_calculate.into(), perm_delete.into(), scram.into(), md5.into(), changed.into(), must_change.into()])?;
     write_users(&p, df)
 }
 
@@ -104,7 +131,9 @@ pub fn add_user(db_root: &str, scope: Scope, username: &str, password: &str, per
         let mask_series = Series::new(MASK.into(), mask_vec);
         df = df.filter(mask_series.bool()?)?;
     }
+    crate::identity::check_password_policy(password)?;
     let hash = hash_password(password)?;
+    let (scram, md5) = password_verifiers_for(username, password)?;
     // Append row
     let new = DataFrame::new(vec![
         Series::new("username".into(), vec![username.to_string()]).into(),
@@ -114,6 +143,10 @@ pub fn add_user(db_root: &str, scope: Scope, username: &str, password: &str, per
         Series::new("perm_insert".into(), vec![perms.insert]).into(),
         Series::new("perm_calculate".into(), vec![perms.calculate]).into(),
         Series::new("perm_delete".into(), vec![perms.delete]).into(),
+        Series::new("scram_verifier".into(), vec![scram]).into(),
+        Series::new("md5_hash".into(), vec![md5]).into(),
+        Series::new("password_changed_at".into(), vec![Some(now_ms())]).into(),
+        Series::new("password_must_change".into(), vec![false]).into(),
     ])?;
     if df.height() == 0 { write_users(&p, new) } else { let stacked = df.vstack(&new)?; write_users(&p, stacked) }
 }
@@ -149,6 +182,10 @@ pub fn alter_user(db_root: &str, scope: Scope, username: &str, new_password: Opt
     let mut cur_ins = false;
     let mut cur_calc = false;
     let mut cur_del = false;
+    let mut cur_scram = String::new();
+    let mut cur_md5 = String::new();
+    let mut cur_changed: Option<i64> = None;
+    let mut cur_must_change = false;
     for i in 0..df.height() {
         let uname = df.column("username")?.get(i)?;
         let name_matches = match uname {
@@ -168,12 +205,20 @@ pub fn alter_user(db_root: &str, scope: Scope, username: &str, new_password: Opt
             cur_ins = df.column("perm_insert")?.bool()?.get(i).unwrap_or(false);
             cur_calc = df.column("perm_calculate")?.bool()?.get(i).unwrap_or(false);
             cur_del = df.column("perm_delete")?.bool()?.get(i).unwrap_or(false);
+            cur_scram = str_at(&df, "scram_verifier", i);
+            cur_md5 = str_at(&df, "md5_hash", i);
+            cur_changed = df.column("password_changed_at")?.i64()?.get(i);
+            cur_must_change = df.column("password_must_change")?.bool()?.get(i).unwrap_or(false);
             break;
         }
     }
     if !found { return Err(anyhow!("user not found")); }
 
+    if let Some(pw) = new_password { crate::identity::check_password_policy(pw)?; }
     let new_hash = if let Some(pw) = new_password { hash_password(pw)? } else { cur_hash };
+    // Setting a password restarts its rotation period
+    let (new_changed, new_must_change) = if new_password.is_some() { (Some(now_ms()), false) } else { (cur_changed, cur_must_change) };
+    let (new_scram, new_md5) = if let Some(pw) = new_password { password_verifiers_for(username, pw)? } else { (cur_scram, cur_md5) };
     let new_admin2 = new_admin.unwrap_or(cur_admin);
     let mut sel = cur_sel; let mut ins = cur_ins; let mut calc = cur_calc; let mut del = cur_del;
     if let Some(p) = new_perms { sel = p.select; ins = p.insert; calc = p.calculate; del = p.delete; }
@@ -198,6 +243,10 @@ pub fn alter_user(db_root: &str, scope: Scope, username: &str, new_password: Opt
         Series::new("perm_insert".into(), vec![ins]).into(),
         Series::new("perm_calculate".into(), vec![calc]).into(),
         Series::new("perm_delete".into(), vec![del]).into(),
+        Series::new("scram_verifier".into(), vec![new_scram]).into(),
+        Series::new("md5_hash".into(), vec![new_md5]).into(),
+        Series::new("password_changed_at".into(), vec![new_changed]).into(),
+        Series::new("password_must_change".into(), vec![new_must_change]).into(),
     ])?;
     if df.height() == 0 { write_users(&p, updated) } else { let stacked = df.vstack(&updated)?; write_users(&p, stacked) }
 }
@@ -227,6 +276,47 @@ pub fn authenticate(db_root: &str, username: &str, password: &str) -> Result<boo
     Ok(false)
 }
 
+/// Stored SCRAM verifier and md5 hash of a global user; empty strings when the password
+/// predates them. None when the user does not exist.
+pub fn password_verifiers(db_root: &str, username: &str) -> Result<Option<(String, String)>> {
+    let df = read_users(&global_user_path(db_root))?;
+    for i in 0..df.height() {
+        if str_at(&df, "username", i) == username {
+            return Ok(Some((str_at(&df, "scram_verifier", i), str_at(&df, "md5_hash", i))));
+        }
+    }
+    Ok(None)
+}
+
+/// When a global user's password was set (None if unknown) and whether it was expired with
+/// `USER ALTER ... EXPIRE PASSWORD`. None when the user does not exist.
+pub fn password_rotation(db_root: &str, username: &str) -> Result<Option<(Option<i64>, bool)>> {
+    let df = read_users(&global_user_path(db_root))?;
+    for i in 0..df.height() {
+        if str_at(&df, "username", i) == username {
+            let changed = df.column("password_changed_at")?.i64()?.get(i);
+            let must_change = df.column("password_must_change")?.bool()?.get(i).unwrap_or(false);
+            return Ok(Some((changed, must_change)));
+        }
+    }
+    Ok(None)
+}
+
+/// Require `username` to set a new password (cleared when the password is changed).
+pub fn expire_password(db_root: &str, scope: Scope, username: &str) -> Result<()> {
+    let p = match scope { Scope::Global => global_user_path(db_root), Scope::Database(db) => db_user_path(db_root, db) };
+    let mut df = read_users(&p)?;
+    let mut found = false;
+    let flags: Vec<bool> = (0..df.height()).map(|i| {
+        let hit = str_at(&df, "username", i) == username;
+        found |= hit;
+        hit || df.column("password_must_change").ok().and_then(|c| c.bool().ok().and_then(|b| b.get(i))).unwrap_or(false)
+    }).collect();
+    if !found { return Err(anyhow!("user not found")); }
+    df.with_column(Series::new("password_must_change".into(), flags))?;
+    write_users(&p, df)
+}
+
 fn load_perms_from_df(df: &DataFrame, username: &str) -> Option<Perms> {
     for i in 0..df.height() {
         let uname = df.column("username").ok()?.get(i).ok()?;
@@ -247,6 +337,7 @@ fn load_perms_from_df(df: &DataFrame, username: &str) -> Option<Perms> {
     None
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CommandKind { Select, Insert, Calculate, DeleteRows, DeleteColumns, Schema, Database, Other }
 
 pub fn authorize(db_root: &str, username: &str, cmd: CommandKind, db: Option<&str>) -> Result<bool> {
@@ -290,3 +381,302 @@ pub fn authorize(db_root: &str, username: &str, cmd: CommandKind, db: Option<&st
 
     Ok(false)
 }
+
+// ---------------------------------------------------------------------------
+// Object privileges (GRANT / REVOKE)
+//
+// ACLs are kept server-wide in `<root>/acl.json`, next to the global user.parquet. A grant on a
+// database or schema covers every table below it. Grants only add to the flat user perms above:
+// REVOKE removes a grant, it never takes away a permission the user row gives.
+
+/// Grantee that matches every user.
+pub const PUBLIC_GRANTEE: &str = "public";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Privilege { Select, Insert, Update, Delete, Ddl }
+
+impl Privilege {
+    pub const ALL: [Privilege; 5] = [Privilege::Select, Privilege::Insert, Privilege::Update, Privilege::Delete, Privilege::Ddl];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Privilege::Select => "SELECT",
+            Privilege::Insert => "INSERT",
+            Privilege::Update => "UPDATE",
+            Privilege::Delete => "DELETE",
+            Privilege::Ddl => "DDL",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Privilege> {
+        Privilege::ALL.into_iter().find(|p| p.as_str().eq_ignore_ascii_case(s.trim()))
+    }
+}
+
+/// Object a privilege is granted on.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum GrantObject {
+    Database { database: String },
+    Schema { database: String, schema: String },
+    Table { database: String, schema: String, table: String },
+}
+
+impl GrantObject {
+    fn parts(&self) -> Vec<&str> {
+        match self {
+            GrantObject::Database { database } => vec![database.as_str()],
+            GrantObject::Schema { database, schema } => vec![database.as_str(), schema.as_str()],
+            GrantObject::Table { database, schema, table } => vec![database.as_str(), schema.as_str(), table.as_str()],
+        }
+    }
+
+    /// `db`, `db/schema` or `db/schema/table`.
+    pub fn path(&self) -> String { self.parts().join("/") }
+
+    /// True when the object is `path` (a `db/schema/table` style path) or one of its parents.
+    pub fn covers(&self, path: &str) -> bool {
+        let path = path.replace('\\', "/");
+        let target: Vec<&str> = path.split('/').map(|p| p.strip_suffix(".time").unwrap_or(p)).collect();
+        let mine = self.parts();
+        mine.len() <= target.len()
+            && mine.iter().zip(target.iter()).all(|(a, b)| a.strip_suffix(".time").unwrap_or(a).eq_ignore_ascii_case(b))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Grant {
+    pub grantee: String,
+    pub privilege: Privilege,
+    pub object: GrantObject,
+    #[serde(default)]
+    pub grantor: Option<String>,
+    #[serde(default)]
+    pub granted_at: i64,
+}
+
+fn acl_path(db_root: &str) -> PathBuf { Path::new(db_root).join("acl.json") }
+
+/// All recorded grants, oldest first.
+pub fn list_grants(db_root: &str) -> Result<Vec<Grant>> {
+    let p = acl_path(db_root);
+    if !p.exists() { return Ok(Vec::new()); }
+    Ok(serde_json::from_slice(&std::fs::read(&p)?)?)
+}
+
+fn write_grants(db_root: &str, grants: &[Grant]) -> Result<()> {
+    let p = acl_path(db_root);
+    if let Some(dir) = p.parent() { std::fs::create_dir_all(dir).ok(); }
+    let tmp = p.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(grants)?)?;
+    std::fs::rename(&tmp, &p)?;
+    Ok(())
+}
+
+/// Grant `privileges` on `object` to each grantee. Existing grants are kept as they are.
+pub fn grant(db_root: &str, grantees: &[String], privileges: &[Privilege], object: &GrantObject, grantor: Option<&str>) -> Result<()> {
+    let mut all = list_grants(db_root)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    for grantee in grantees {
+        for privilege in privileges {
+            let exists = all.iter().any(|g| g.grantee.eq_ignore_ascii_case(grantee) && g.privilege == *privilege && g.object == *object);
+            if !exists {
+                all.push(Grant { grantee: grantee.clone(), privilege: *privilege, object: object.clone(), grantor: grantor.map(|s| s.to_string()), granted_at: now });
+            }
+        }
+    }
+    write_grants(db_root, &all)
+}
+
+/// Remove grants of `privileges` on `object` from each grantee; returns how many were removed.
+pub fn revoke(db_root: &str, grantees: &[String], privileges: &[Privilege], object: &GrantObject) -> Result<usize> {
+    let mut all = list_grants(db_root)?;
+    let before = all.len();
+    all.retain(|g| !(grantees.iter().any(|u| g.grantee.eq_ignore_ascii_case(u)) && privileges.contains(&g.privilege) && g.object == *object));
+    let removed = before - all.len();
+    if removed > 0 { write_grants(db_root, &all)?; }
+    Ok(removed)
+}
+
+/// True when `username` (PUBLIC, or a role it inherits) holds `privilege` on `path` or on one of its parents.
+pub fn has_privilege(db_root: &str, username: &str, privilege: Privilege, path: &str) -> bool {
+    let holders = effective_roles(db_root, username);
+    list_grants(db_root).unwrap_or_default().iter().any(|g| {
+        g.privilege == privilege
+            && (g.grantee.eq_ignore_ascii_case(PUBLIC_GRANTEE) || holders.iter().any(|h| g.grantee.eq_ignore_ascii_case(h)))
+            && g.object.covers(path)
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Roles (CREATE ROLE / GRANT role TO member)
+//
+// Roles are named groups kept in `<root>/roles.json`; they cannot log in. A member (user or
+// role) of an INHERIT role gets the object privileges granted to it, and a SUPERUSER role makes
+// its members admins. Membership chains are followed transitively.
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RoleDef {
+    pub name: String,
+    #[serde(default)]
+    pub superuser: bool,
+    #[serde(default = "default_true")]
+    pub inherit: bool,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+fn default_true() -> bool { true }
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RoleMembership {
+    pub role: String,
+    pub member: String,
+    #[serde(default)]
+    pub admin_option: bool,
+    #[serde(default)]
+    pub grantor: Option<String>,
+    #[serde(default)]
+    pub granted_at: i64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RoleCatalog {
+    #[serde(default)]
+    pub roles: Vec<RoleDef>,
+    #[serde(default)]
+    pub members: Vec<RoleMembership>,
+}
+
+impl RoleCatalog {
+    pub fn role(&self, name: &str) -> Option<&RoleDef> { self.roles.iter().find(|r| r.name.eq_ignore_ascii_case(name)) }
+}
+
+fn roles_path(db_root: &str) -> PathBuf { Path::new(db_root).join("roles.json") }
+
+/// Roles and memberships recorded with CREATE ROLE / GRANT role.
+pub fn role_catalog(db_root: &str) -> Result<RoleCatalog> {
+    let p = roles_path(db_root);
+    if !p.exists() { return Ok(RoleCatalog::default()); }
+    Ok(serde_json::from_slice(&std::fs::read(&p)?)?)
+}
+
+fn write_role_catalog(db_root: &str, cat: &RoleCatalog) -> Result<()> {
+    let p = roles_path(db_root);
+    if let Some(dir) = p.parent() { std::fs::create_dir_all(dir).ok(); }
+    let tmp = p.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(cat)?)?;
+    std::fs::rename(&tmp, &p)?;
+    Ok(())
+}
+
+/// Global users as (username, is_admin), in user.parquet order.
+pub fn list_users(db_root: &str) -> Result<Vec<(String, bool)>> {
+    let df = read_users(&global_user_path(db_root))?;
+    let admin = df.column("is_admin")?.bool()?.clone();
+    Ok((0..df.height()).map(|i| (str_at(&df, "username", i), admin.get(i).unwrap_or(false))).collect())
+}
+
+fn user_exists(db_root: &str, name: &str) -> bool {
+    list_users(db_root).unwrap_or_default().iter().any(|(u, _)| u.eq_ignore_ascii_case(name))
+}
+
+pub fn create_role(db_root: &str, name: &str, superuser: bool, inherit: bool) -> Result<()> {
+    let mut cat = role_catalog(db_root)?;
+    if cat.role(name).is_some() || user_exists(db_root, name) || name.eq_ignore_ascii_case(PUBLIC_GRANTEE) {
+        return Err(crate::error::AppError::Conflict { code: "duplicate_object".into(), message: format!("role \"{}\" already exists", name) }.into());
+    }
+    cat.roles.push(RoleDef { name: name.to_string(), superuser, inherit, created_at: chrono::Utc::now().timestamp_millis() });
+    write_role_catalog(db_root, &cat)
+}
+
+/// Drop a role with its memberships and the object privileges granted to it. Returns false
+/// when the role does not exist.
+pub fn drop_role(db_root: &str, name: &str) -> Result<bool> {
+    let mut cat = role_catalog(db_root)?;
+    let before = cat.roles.len();
+    cat.roles.retain(|r| !r.name.eq_ignore_ascii_case(name));
+    if cat.roles.len() == before { return Ok(false); }
+    cat.members.retain(|m| !m.role.eq_ignore_ascii_case(name) && !m.member.eq_ignore_ascii_case(name));
+    write_role_catalog(db_root, &cat)?;
+    let mut grants = list_grants(db_root)?;
+    let n = grants.len();
+    grants.retain(|g| !g.grantee.eq_ignore_ascii_case(name));
+    if grants.len() != n { write_grants(db_root, &grants)?; }
+    Ok(true)
+}
+
+/// Make each member (user or role) a member of each role. Cycles are rejected.
+pub fn grant_roles(db_root: &str, roles: &[String], members: &[String], admin_option: bool, grantor: Option<&str>) -> Result<()> {
+    let mut cat = role_catalog(db_root)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    for role in roles {
+        let Some(def) = cat.role(role) else {
+            return Err(crate::error::AppError::NotFound { code: "undefined_object".into(), message: format!("role \"{}\" does not exist", role) }.into());
+        };
+        let role = def.name.clone();
+        for member in members {
+            if cat.role(member).is_none() && !user_exists(db_root, member) {
+                return Err(crate::error::AppError::NotFound { code: "undefined_object".into(), message: format!("role \"{}\" does not exist", member) }.into());
+            }
+            if member.eq_ignore_ascii_case(&role) || member_closure(&cat, &role, false).iter().any(|r| r.eq_ignore_ascii_case(member)) {
+                return Err(crate::error::AppError::Conflict { code: "invalid_grant_operation".into(), message: format!("role \"{}\" is a member of role \"{}\"", role, member) }.into());
+            }
+            match cat.members.iter_mut().find(|m| m.role.eq_ignore_ascii_case(&role) && m.member.eq_ignore_ascii_case(member)) {
+                Some(m) => { m.admin_option |= admin_option; }
+                None => cat.members.push(RoleMembership { role: role.clone(), member: member.clone(), admin_option, grantor: grantor.map(|s| s.to_string()), granted_at: now }),
+            }
+        }
+    }
+    write_role_catalog(db_root, &cat)
+}
+
+/// Remove each member from each role; returns how many memberships were removed.
+pub fn revoke_roles(db_root: &str, roles: &[String], members: &[String]) -> Result<usize> {
+    let mut cat = role_catalog(db_root)?;
+    let before = cat.members.len();
+    cat.members.retain(|m| !(roles.iter().any(|r| m.role.eq_ignore_ascii_case(r)) && members.iter().any(|u| m.member.eq_ignore_ascii_case(u))));
+    let removed = before - cat.members.len();
+    if removed > 0 { write_role_catalog(db_root, &cat)?; }
+    Ok(removed)
+}
+
+/// Roles `name` belongs to, directly or through other roles. With `inherit_only`, the walk
+/// does not continue past a NOINHERIT member.
+fn member_closure(cat: &RoleCatalog, name: &str, inherit_only: bool) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut queue: std::collections::VecDeque<String> = std::collections::VecDeque::from([name.to_string()]);
+    while let Some(cur) = queue.pop_front() {
+        if inherit_only && cat.role(&cur).map(|r| !r.inherit).unwrap_or(false) { continue; }
+        for m in cat.members.iter().filter(|m| m.member.eq_ignore_ascii_case(&cur)) {
+            if !out.iter().any(|r| r.eq_ignore_ascii_case(&m.role)) {
+                out.push(m.role.clone());
+                queue.push_back(m.role.clone());
+            }
+        }
+    }
+    out
+}
+
+/// `name` followed by every role whose privileges it uses: memberships are followed through
+/// INHERIT roles only.
+pub fn effective_roles(db_root: &str, name: &str) -> Vec<String> {
+    let cat = role_catalog(db_root).unwrap_or_default();
+    let mut out = vec![name.to_string()];
+    out.extend(member_closure(&cat, name, true));
+    out
+}
+
+/// True when `user` belongs to `role` directly or indirectly, whether or not it inherits it
+/// (what SET ROLE requires).
+pub fn is_member_of(db_root: &str, user: &str, role: &str) -> bool {
+    let cat = role_catalog(db_root).unwrap_or_default();
+    user.eq_ignore_ascii_case(role) || member_closure(&cat, user, false).iter().any(|r| r.eq_ignore_ascii_case(role))
+}
+
+/// True when one of the roles `name` inherits is a SUPERUSER role.
+pub fn has_superuser_role(db_root: &str, name: &str) -> bool {
+    let cat = role_catalog(db_root).unwrap_or_default();
+    member_closure(&cat, name, true).iter().any(|r| cat.role(r).map(|d| d.superuser).unwrap_or(false))
+}
@@ -14,7 +14,7 @@
 
 use std::{net::SocketAddr, collections::HashMap};
 
-use axum::{routing::{get, post}, Router, extract::{State, ws::{WebSocketUpgrade, Message}, Path}, Json};
+use axum::{routing::{delete, get, post, put}, Router, extract::{DefaultBodyLimit, State, ws::{WebSocketUpgrade, Message}, Path, Query}, Extension, Json};
 use axum::response::IntoResponse;
 use axum::http::{HeaderMap, HeaderValue, StatusCode};
 use serde::{Serialize, Deserialize};
@@ -32,10 +32,28 @@ pub mod query;
 pub mod exec;
 pub mod data_context;
 pub mod graphstore; // direct graph storage engine (scaffolding)
+pub mod replication;
+pub mod activity;
+pub mod guc;
+pub mod collation;
+pub mod db_stats;
+pub mod quota;
+pub mod storage_hooks;
+pub mod http_v2;
+pub mod ingest;
+pub mod http_filestore;
+pub mod http_kv;
+pub mod influx;
+pub mod prometheus;
+pub mod openapi;
+pub mod http_auth;
+pub mod http_layers;
+pub mod jobs;
+pub mod procedures;
+pub mod script_tests;
 use serde_json::json;
 use polars::prelude::*;
 use crate::scripts::{ScriptRegistry, scripts_dir_for, load_all_scripts_for_schema, load_global_default_scripts};
-use crate::ident::{DEFAULT_DB, DEFAULT_SCHEMA};
 
 const SESSION_COOKIE: &str = "clarium_session";
 
@@ -79,6 +97,8 @@ pub struct AppState {
     pub session_meta: std::sync::Arc<RwLock<HashMap<String, SessionMeta>>>,
     /// Brute-force protection: (ip, username) -> attempt state (login)
     pub login_attempts: std::sync::Arc<RwLock<HashMap<(String, String), AttemptState>>>,
+    /// Bearer-token (OIDC) authentication, when `[oidc] issuer` is configured
+    pub oidc: Option<std::sync::Arc<crate::identity::OidcAuthProvider>>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -95,22 +115,6 @@ pub struct AttemptState {
     pub backoff_until: Option<Instant>,
 }
 
-fn session_idle_timeout() -> Duration {
-    let secs = std::env::var("CLARIUM_SESSION_IDLE_SECS")
-        .ok()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(30 * 60);
-    Duration::from_secs(secs)
-}
-
-fn session_absolute_lifetime() -> Duration {
-    let secs = std::env::var("CLARIUM_SESSION_ABS_SECS")
-        .ok()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(24 * 60 * 60);
-    Duration::from_secs(secs)
-}
-
 /// Start the clarium HTTP server (and optional pgwire) bound to the given ports.
 ///
 /// This sets up the store, ensures an admin user exists, creates a demo dataset on
@@ -146,12 +150,88 @@ fn log_startup_folders(db_root: &str) {
 
 #[inline]
 fn env_default_db() -> String {
-    std::env::var("CLARIUM_DEFAULT_DB").unwrap_or_else(|_| DEFAULT_DB.to_string())
+    crate::config::current().server.default_db.clone()
 }
 
 #[inline]
 fn env_default_schema() -> String {
-    std::env::var("CLARIUM_DEFAULT_SCHEMA").unwrap_or_else(|_| DEFAULT_SCHEMA.to_string())
+    crate::config::current().server.default_schema.clone()
+}
+
+/// Declares every HTTP route once: builds `http_router()` and the `HTTP_ROUTES` descriptions
+/// served as the OpenAPI document at `/openapi`.
+macro_rules! http_routes {
+    ($( $method:ident $path:literal => $handler:expr, $spec:expr; )*) => {
+        const HTTP_ROUTES: &[openapi::RouteSpec] = &[
+            $( openapi::RouteSpec { method: stringify!($method), path: $path, ..$spec }, )*
+        ];
+
+        fn http_router() -> Router<AppState> {
+            let cfg = crate::config::current();
+            let mut specs = HTTP_ROUTES.iter();
+            let mut router = Router::new();
+            $(
+                let spec = specs.next().expect("one spec per route");
+                let limit = http_layers::body_limit(spec, &cfg.http, cfg.limits.ingest_max_body_mb);
+                router = router.route($path, $method($handler).layer(DefaultBodyLimit::max(limit)));
+            )*
+            router
+        }
+    };
+}
+
+use openapi::{RouteSpec, JSON, NDJSON, TEXT};
+use http_auth::AuthContext;
+use security::CommandKind;
+
+http_routes! {
+    get "/" => || async { "clarium ok" }, RouteSpec::new("server", "Liveness check").returns(TEXT, "").public();
+    get "/openapi" => openapi_handler, RouteSpec::new("server", "This OpenAPI document").returns(JSON, "").no_csrf();
+    post "/login" => login, RouteSpec::new("session", "Log in; sets the session cookie").body(JSON, "LoginRequest").returns(JSON, "Status").public();
+    post "/logout" => logout, RouteSpec::new("session", "End the session").returns(JSON, "Status");
+    get "/csrf" => get_csrf, RouteSpec::new("session", "CSRF token of the session").returns(JSON, "CsrfToken").no_csrf();
+    post "/auth/api-keys" => http_auth::create_api_key, RouteSpec::new("session", "Create an API key acting as the caller").body(JSON, "ApiKeyRequest").returns(JSON, "ApiKeyCreated").status(201);
+    get "/auth/api-keys" => http_auth::list_api_keys, RouteSpec::new("session", "API keys of the caller (every key for admins)").returns(JSON, "ApiKeyList").no_csrf();
+    delete "/auth/api-keys/{id}" => http_auth::revoke_api_key, RouteSpec::new("session", "Revoke an API key").returns(JSON, "Status");
+    post "/use/database" => use_database, RouteSpec::new("session", "Set the session's current database").body(JSON, "UseRequest").returns(JSON, "Status");
+    post "/use/schema" => use_schema, RouteSpec::new("session", "Set the session's current schema").body(JSON, "UseRequest").returns(JSON, "Status");
+    post "/query" => query_handler, RouteSpec::new("query", "Run one SQL statement").body(JSON, "QueryRequest").returns(JSON, "QueryResult");
+    post "/v2/query" => http_v2::query_v2_handler, RouteSpec::new("query", "Run one SQL statement with bind parameters, streaming the result")
+        .body(JSON, "QueryV2Request").returns(NDJSON, "QueryV2Stream");
+    get "/ws" => ws_handler, RouteSpec::new("query", "WebSocket: one SQL statement per text message, one JSON result per reply").status(101);
+    post "/write/{database}" => write, RouteSpec::new("ingest", "Append records to a time table").body(JSON, "WriteRequest").returns(JSON, "WriteResult")
+        .requires(CommandKind::Insert, "database");
+    post "/v1/ingest/{*table}" => ingest::ingest_handler, RouteSpec::new("ingest", "Bulk load NDJSON or line protocol (gzip/zstd) in batches")
+        .body(NDJSON, "").returns(JSON, "IngestReport")
+        .query(&[("format", "ndjson or lp"), ("types", "column type hints, e.g. id:int64,name:string"), ("precision", "line-protocol timestamps: ns, us, ms or s")])
+        .bulk();
+    post "/write" => influx::write_handler, RouteSpec::new("ingest", "InfluxDB 1.x line protocol write").body(TEXT, "").status(204)
+        .query(&[("db", "target database"), ("precision", "n, u, ms, s, m or h (default ns)"), ("u", "user name"), ("p", "password")])
+        .bulk().agent().requires(CommandKind::Insert, "db");
+    get "/ping" => influx::ping, RouteSpec::new("ingest", "InfluxDB liveness check").status(204).public();
+    post "/api/v1/write" => prometheus::remote_write_handler, RouteSpec::new("ingest", "Prometheus remote write (snappy protobuf)")
+        .body("application/x-protobuf", "").status(204)
+        .query(&[("db", "target database"), ("schema", "target schema"), ("mode", "per_metric or wide"), ("table", "wide-mode table name")])
+        .bulk().agent();
+    get "/v1/filestore/{database}/{filestore}/{*path}" => http_filestore::download, RouteSpec::new("filestore", "Download a file (Range, If-None-Match, If-Range) with credentials or a presigned link")
+        .returns("application/octet-stream", "").query(http_filestore::LINK_PARAMS).public();
+    put "/v1/filestore/{database}/{filestore}/{*path}" => http_filestore::upload, RouteSpec::new("filestore", "Create (201) or replace a file (If-Match, If-None-Match: *) with credentials or a presigned link")
+        .body("application/octet-stream", "").returns(JSON, "FileWritten").query(http_filestore::LINK_PARAMS).bulk().public();
+    post "/v1/filestore/presign" => http_filestore::presign, RouteSpec::new("filestore", "Time-limited link to download or upload one file as the caller")
+        .body(JSON, "PresignRequest").returns(JSON, "PresignedLink");
+    post "/v1/kv/{database}/{store}/batch" => http_kv::batch, RouteSpec::new("kv", "Apply writes to keys of one KV store all together or not at all")
+        .body(JSON, "KvBatchRequest").returns(JSON, "KvBatchResult").requires(CommandKind::Other, "database");
+    get "/cdc/{database}/{schema}/{table}" => cdc_changes, RouteSpec::new("cdc", "Change events of a table").returns(NDJSON, "ChangeEvents")
+        .query(&[("since", "sequence number or RFC 3339 time")]).no_csrf().requires(CommandKind::Select, "database");
+    get "/cdc/ws/{database}/{schema}/{table}" => cdc_ws_handler, RouteSpec::new("cdc", "WebSocket: change events, replayed then live").status(101)
+        .query(&[("since", "sequence number or RFC 3339 time")]).no_csrf().requires(CommandKind::Select, "database");
+    get "/replication/manifest" => replication_manifest, RouteSpec::new("replication", "File manifest for replicas (replication token)").returns(JSON, "ReplicationManifest").public();
+    get "/replication/file/{*path}" => replication_file, RouteSpec::new("replication", "One file of the manifest (replication token)").returns("application/octet-stream", "").public();
+}
+
+/// GET /openapi -> OpenAPI document of `HTTP_ROUTES`
+async fn openapi_handler() -> impl IntoResponse {
+    Json(openapi::spec(HTTP_ROUTES))
 }
 
 pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str) -> anyhow::Result<()> {
@@ -166,6 +246,7 @@ pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str)
         .with_context(|| format!("While ensuring default admin under db_root: {}", db_root))?;
     let store = SharedStore::new(db_root)
         .with_context(|| format!("While creating SharedStore with root: {}", db_root))?;
+    storage_hooks::install();
 
     // On first startup with an empty store (no tables), create a demo table with 1 week of per-second sine data.
     if is_store_completely_empty_three_level(db_root) {
@@ -181,7 +262,7 @@ pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str)
     let scripts = ScriptRegistry::new()?;
     // Load globally bundled scripts (e.g., ./scripts and <exe>/scripts)
     let _ = load_global_default_scripts(&scripts);
-    if let Ok(db_dirs) = std::fs::read_dir(db_root) {
+    if let Ok(db_dirs) = crate::storage::attach::read_database_dirs(std::path::Path::new(db_root)) {
         for dbent in db_dirs.flatten() {
             if dbent.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
                 let dbname = dbent.file_name().to_string_lossy().to_string();
@@ -204,18 +285,21 @@ pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str)
     // Shutdown signal (Ctrl-C) broadcaster
     let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
 
-    // Start background KV sweeper (shutdown-aware)
+    // Start background KV sweeper (shutdown-aware); the interval is re-read each round so a
+    // config reload applies, and 0 pauses sweeping (keys still expire lazily on read)
     {
         let store_for_sweep = store.clone();
         let mut rx = shutdown_rx.clone();
         tokio::spawn(async move {
             use std::time::Duration;
             loop {
+                let interval_ms = crate::config::current().limits.kv_sweep_interval_ms;
                 tokio::select! {
                     _ = rx.changed() => {
                         if *rx.borrow() { crate::tprintln!("[shutdown] kv_sweeper exiting on shutdown signal"); break; }
                     }
-                    _ = tokio::time::sleep(Duration::from_secs(5)) => {
+                    _ = tokio::time::sleep(Duration::from_millis(if interval_ms == 0 { 5_000 } else { interval_ms })) => {
+                        if interval_ms == 0 { continue; }
                         // Sweep expired keys across all stores
                         let reg = store_for_sweep.kv_registry();
                         let removed = reg.sweep_all();
@@ -231,7 +315,7 @@ pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str)
         let store_for_gc = store.clone();
         let mut rx = shutdown_rx.clone();
         // Interval in seconds; default 60s; set to 0 or negative to disable
-        let interval_sec: i64 = std::env::var("CLARIUM_GRAPH_GC_INTERVAL_SEC").ok().and_then(|s| s.parse::<i64>().ok()).unwrap_or(60);
+        let interval_sec: i64 = crate::config::current().limits.graph_gc_interval_sec;
         if interval_sec > 0 {
             tokio::spawn(async move {
                 use std::time::Duration;
@@ -251,6 +335,50 @@ pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str)
         }
     }
 
+    // Scheduled jobs (CREATE JOB), checked every minute (shutdown-aware)
+    jobs::spawn_scheduler(store.clone(), shutdown_rx.clone());
+
+    // Filestore GC on each filestore's cadence (gc_interval_seconds), checked every minute
+    exec::filestore::spawn_gc_scheduler(store.clone(), shutdown_rx.clone());
+
+    // Filestore security policies load from security.* on the first check (HTTP file routes included)
+    exec::filestore::sec::evaluator::set_store(&store);
+
+    // Built vector indexes are loaded in the background so the first searches don't pay for it
+    exec::exec_vector_runtime::spawn_warm_load(store.clone());
+
+    // SIGHUP: reload clarium.toml and the environment (same as ADMIN RELOAD CONFIG)
+    #[cfg(unix)]
+    {
+        let mut rx = shutdown_rx.clone();
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(mut hup) => {
+                tokio::spawn(async move {
+                    loop {
+                        tokio::select! {
+                            _ = rx.changed() => {
+                                if *rx.borrow() { crate::tprintln!("[shutdown] sighup_listener exiting on shutdown signal"); break; }
+                            }
+                            _ = hup.recv() => {
+                                if let Err(e) = crate::config::reload() {
+                                    tracing::warn!(target: "clarium::config", "config reload on SIGHUP failed: {}", e);
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+            Err(e) => tracing::warn!(target: "clarium::config", "failed to listen for SIGHUP: {}", e),
+        }
+    }
+
+    // Replica mode: poll the primary's manifest and reject writes locally
+    if let Some(cfg) = replication::ReplicaConfig::from_env() {
+        tracing::info!(target = "replication", primary = %cfg.primary_url, "starting as read-only replica");
+        replication::set_replica_mode(true);
+        tokio::spawn(replication::run_replica(cfg, std::path::PathBuf::from(db_root), shutdown_rx.clone()));
+    }
+
     let app_state = AppState {
         store: store.clone(),
         db_root: db_root.to_string(),
@@ -260,6 +388,7 @@ pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str)
         session_defaults: std::sync::Arc::new(RwLock::new(HashMap::new())),
         session_meta: std::sync::Arc::new(RwLock::new(HashMap::new())),
         login_attempts: std::sync::Arc::new(RwLock::new(HashMap::new())),
+        oidc: crate::identity::OidcAuthProvider::from_config(db_root).map(std::sync::Arc::new),
     };
 
     // Optionally start a basic pgwire listener on the provided port
@@ -277,17 +406,14 @@ pub async fn run_with_ports(http_port: u16, pg_port: Option<u16>, db_root: &str)
         }
     }
 
-    let app = Router::new()
-        .route("/", get(|| async { "clarium ok" }))
-        .route("/login", post(login))
-        .route("/logout", post(logout))
-        .route("/csrf", get(get_csrf))
-        .route("/write/{database}", post(write))
-        .route("/query", post(query_handler))
-        .route("/use/database", post(use_database))
-        .route("/use/schema", post(use_schema))
-        .route("/ws", get(ws_handler))
-        .with_state(app_state);
+    // CORS is outermost so preflight requests are answered before authentication
+    let http_cfg = crate::config::current().http.clone();
+    http_layers::check(&http_cfg, HTTP_ROUTES).context("invalid [http] configuration")?;
+    let mut app = http_router()
+        .route_layer(axum::middleware::from_fn_with_state(app_state.clone(), http_auth::require_auth));
+    if let Some(compression) = http_layers::compression_layer(&http_cfg) { app = app.layer(compression); }
+    if let Some(cors) = http_layers::cors_layer(&http_cfg)? { app = app.layer(cors); }
+    let app = app.with_state(app_state);
 
     let addr: SocketAddr = format!("0.0.0.0:{}", http_port).parse()?;
     info!("Starting server on {}", addr);
@@ -350,7 +476,7 @@ fn is_store_completely_empty_three_level(db_root: &str) -> bool {
     use std::path::Path;
     let root = Path::new(db_root);
     if !root.exists() { return true; }
-    if let Ok(rd) = std::fs::read_dir(root) {
+    if let Ok(rd) = crate::storage::attach::read_database_dirs(root) {
         for db_entry in rd.flatten() {
             let dbp = db_entry.path();
             if !dbp.is_dir() { continue; }
@@ -384,7 +510,7 @@ fn print_installed_db_and_schemas(db_root: &str) {
     use std::path::Path;
     let root = Path::new(db_root);
     let mut db_to_schemas: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
-    if let Ok(rd) = std::fs::read_dir(root) {
+    if let Ok(rd) = crate::storage::attach::read_database_dirs(root) {
         for db_entry in rd.flatten() {
             let dbp = db_entry.path();
             if !dbp.is_dir() { continue; }
@@ -558,8 +684,11 @@ fn create_demo_dataset(store: &SharedStore) -> anyhow::Result<()> {
 
 // Backward-compatible entry that uses defaults
 /// Convenience entry point using default ports (7878 HTTP, 5433 pgwire) and db root "dbs".
+/// Start the server with the ports and db root from the current configuration (see [`crate::config`]).
 pub async fn run() -> anyhow::Result<()> {
-    run_with_ports(7878, Some(5433), "dbs").await
+    let cfg = crate::config::current();
+    let pg_port = cfg.server.pgwire.then_some(cfg.server.pg_port);
+    run_with_ports(cfg.server.http_port, pg_port, &cfg.server.db_root).await
 }
 
 #[derive(Debug, Deserialize)]
@@ -586,22 +715,43 @@ fn parse_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
     None
 }
 
+/// Token of an `Authorization: Bearer` header.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let v = headers.get(axum::http::header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = v.trim().split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim()).filter(|t| !t.is_empty())
+}
+
+/// Principal of a request authenticated with an OIDC bearer token.
+async fn bearer_principal(state: &AppState, headers: &HeaderMap) -> Option<crate::identity::Principal> {
+    let token = bearer_token(headers)?;
+    let oidc = state.oidc.as_ref()?;
+    match oidc.authenticate(token, None).await {
+        Ok(p) => Some(p),
+        Err(e) => { tracing::warn!(target: "clarium::auth", "bearer token rejected: {}", e); None }
+    }
+}
+
 async fn get_username_from_headers(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    // A bearer token authenticates the request on its own; it never falls back to the cookie
+    if bearer_token(headers).is_some() { return bearer_principal(state, headers).await.map(|p| p.user_id); }
     let sid = parse_cookie(headers, SESSION_COOKIE)?;
-    // Enforce session timeouts
+    // Enforce session timeouts (idle/absolute expiry and revocation live in the SessionManager)
     {
         let mut meta_map = state.session_meta.write().await;
         if let Some(meta) = meta_map.get_mut(&sid) {
             let now = Instant::now();
-            if now.duration_since(meta.issued_at) > session_absolute_lifetime()
-                || now.duration_since(meta.last_seen) > session_idle_timeout()
-            {
+            // Sessions ended with pg_terminate_backend()/KILL expire like timed-out ones
+            let killed = activity::session_pid(&sid).map(activity::is_terminated).unwrap_or(false);
+            if killed || crate::identity::SessionManager::default().touch(&sid).is_none() {
                 // expire session: remove from all maps
                 drop(meta_map);
                 let mut s = state.sessions.write().await; s.remove(&sid);
                 let mut c = state.csrf_tokens.write().await; c.remove(&sid);
                 let mut d = state.session_defaults.write().await; d.remove(&sid);
                 let mut m = state.session_meta.write().await; m.remove(&sid);
+                crate::identity::SessionManager::default().logout_session(&sid);
+                activity::unregister_session(&sid);
                 return None;
             } else {
                 meta.last_seen = now;
@@ -612,6 +762,7 @@ async fn get_username_from_headers(state: &AppState, headers: &HeaderMap) -> Opt
             let mut c = state.csrf_tokens.write().await; c.remove(&sid);
             let mut d = state.session_defaults.write().await; d.remove(&sid);
             let mut m = state.session_meta.write().await; m.remove(&sid);
+            activity::unregister_session(&sid);
             return None;
         }
     }
@@ -624,6 +775,8 @@ fn get_sid_from_headers(headers: &HeaderMap) -> Option<String> {
 }
 
 async fn validate_csrf(state: &AppState, headers: &HeaderMap) -> bool {
+    // CSRF protects cookie sessions; bearer tokens are not sent by the browser on their own
+    if state.oidc.is_some() && bearer_token(headers).is_some() { return true; }
     let Some(sid) = get_sid_from_headers(headers) else { return false; };
     let Some(provided) = headers.get("x-csrf-token").and_then(|v| v.to_str().ok()).map(|s| s.to_string()) else { return false; };
     let cmap = state.csrf_tokens.read().await;
@@ -683,6 +836,8 @@ async fn login(State(state): State<AppState>, Json(payload): Json<LoginPayload>)
                 let now = Instant::now();
                 mmap.insert(sid.clone(), SessionMeta { issued_at: now, last_seen: now });
             }
+            // the session is a backend in pg_stat_activity until logout or expiry
+            activity::register(activity::Frontend::Http, &payload.username, &env_default_db(), &client_ip, Some(sid.clone()));
             // record success and reset rate limiter
             record_login_success(&state, &client_ip, &payload.username).await;
             let mut headers = HeaderMap::new();
@@ -697,12 +852,8 @@ async fn login(State(state): State<AppState>, Json(payload): Json<LoginPayload>)
     }
 }
 
-async fn logout(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
-    // Require CSRF token
-    if !validate_csrf(&state, &headers).await {
-        return (StatusCode::FORBIDDEN, HeaderMap::new(), Json(serde_json::json!({"status":"forbidden","error":"invalid csrf"})));
-    }
-    if let Some(sid) = parse_cookie(&headers, SESSION_COOKIE) {
+async fn logout(State(state): State<AppState>, Extension(auth): Extension<AuthContext>) -> impl IntoResponse {
+    if let Some(sid) = auth.session_id {
         let mut map = state.sessions.write().await;
         map.remove(&sid);
         // also remove csrf token
@@ -711,6 +862,8 @@ async fn logout(State(state): State<AppState>, headers: HeaderMap) -> impl IntoR
         // and defaults + meta
         let mut dmap = state.session_defaults.write().await; dmap.remove(&sid);
         let mut mmap = state.session_meta.write().await; mmap.remove(&sid);
+        crate::identity::SessionManager::default().logout_session(&sid);
+        activity::unregister_session(&sid);
     }
     let mut h = HeaderMap::new();
     h.insert("Set-Cookie", clear_session_cookie());
@@ -719,20 +872,22 @@ async fn logout(State(state): State<AppState>, headers: HeaderMap) -> impl IntoR
 
 async fn write(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    Extension(auth): Extension<AuthContext>,
     Path(database): Path<String>,
     Json(payload): Json<WritePayload>,
 ) -> impl IntoResponse {
-    let Some(username) = get_username_from_headers(&state, &headers).await else {
-        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"})));
-    };
-    if !validate_csrf(&state, &headers).await {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden","error":"invalid csrf"})));
+    // INSERT on the database is checked by the route's privilege in `http_auth`
+    let username = auth.username();
+    if let Err(e) = replication::ensure_writable() {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"error","error": e.to_string()})));
     }
-    // authorize insert (enhanced RBAC in debug builds; legacy parquet authorizer in release)
-    let allowed = crate::identity::check_command_allowed_async(&state.store, &username, security::CommandKind::Insert, Some(&database)).await;
-    if !allowed {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden"})));
+    // Per-user quotas: the request counts as a statement and its records as ingested bytes
+    let admitted = match quota::admit_query(username).await {
+        Ok(()) => quota::charge_ingest_as(username, quota::records_size(&payload.records)),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = admitted {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(serde_json::json!({"status":"error","error": e.to_string()})));
     }
     let guard = state.store.0.lock();
     match guard.write_records(&database, &payload.records) {
@@ -747,12 +902,118 @@ async fn write(
 #[derive(Debug, Deserialize)]
 struct QueryPayload { query: String }
 
-async fn get_csrf(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
-    // Must be logged in to fetch CSRF token
-    let Some(_username) = get_username_from_headers(&state, &headers).await else {
-        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"})));
+#[derive(Debug, Deserialize)]
+struct CdcParams { since: Option<String> }
+
+/// GET /cdc/{db}/{schema}/{table}?since=<seq|rfc3339> -> changelog as NDJSON. SELECT on the
+/// owning database is checked by the route's privilege in `http_auth`.
+async fn cdc_changes(
+    State(state): State<AppState>,
+    Path((database, schema, table)): Path<(String, String, String)>,
+    Query(params): Query<CdcParams>,
+) -> impl IntoResponse {
+    let tableq = crate::server::exec::exec_cdc::qualify_cdc_table(&format!("{}/{}/{}", database, schema, table));
+    match crate::server::exec::exec_cdc::load_changes(&state.store, &tableq, params.since.as_deref()) {
+        Ok(evs) => {
+            let mut body = String::new();
+            for ev in evs {
+                if let Ok(line) = serde_json::to_string(&ev) { body.push_str(&line); body.push('\n'); }
+            }
+            let mut h = HeaderMap::new();
+            h.insert("Content-Type", HeaderValue::from_static("application/x-ndjson"));
+            (StatusCode::OK, h, body).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response(),
+    }
+}
+
+/// Websocket subscription: replays the changelog after `since`, then streams live events.
+async fn cdc_ws_handler(
+    State(state): State<AppState>,
+    Path((database, schema, table)): Path<(String, String, String)>,
+    Query(params): Query<CdcParams>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let tableq = crate::server::exec::exec_cdc::qualify_cdc_table(&format!("{}/{}/{}", database, schema, table));
+    ws.on_upgrade(move |mut socket| async move {
+        // Subscribe before replaying so nothing written in between is lost; seq dedupes the overlap
+        let mut rx = crate::storage::cdc::subscribe();
+        let backlog = match crate::server::exec::exec_cdc::load_changes(&state.store, &tableq, params.since.as_deref()) {
+            Ok(evs) => evs,
+            Err(e) => {
+                let _ = socket.send(Message::Text(serde_json::json!({"status":"error","error": e.to_string()}).to_string().into())).await;
+                return;
+            }
+        };
+        let mut last_seq = params.since.as_deref().and_then(|s| s.parse::<u64>().ok()).unwrap_or(0);
+        for ev in backlog {
+            last_seq = last_seq.max(ev.seq);
+            if socket.send(Message::Text(serde_json::to_string(&ev).unwrap_or_default().into())).await.is_err() { return; }
+        }
+        loop {
+            match rx.recv().await {
+                Ok(ev) => {
+                    if ev.table != tableq || ev.seq <= last_seq { continue; }
+                    last_seq = ev.seq;
+                    if socket.send(Message::Text(serde_json::to_string(&ev).unwrap_or_default().into())).await.is_err() { break; }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    // Subscriber fell behind; tell it to resume from the durable changelog
+                    let _ = socket.send(Message::Text(serde_json::json!({"status":"lagged","skipped": n, "resume_after": last_seq}).to_string().into())).await;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+    }).into_response()
+}
+
+fn replication_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get(replication::TOKEN_HEADER).and_then(|v| v.to_str().ok())
+}
+
+/// GET /replication/manifest -> file manifest for replicas (token-authenticated)
+async fn replication_manifest(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    if !replication::authorize(replication_token(&headers)) {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden"}))).into_response();
+    }
+    let root = std::path::PathBuf::from(&state.db_root);
+    match tokio::task::spawn_blocking(move || replication::build_manifest(&root)).await {
+        Ok(Ok(manifest)) => {
+            if let Some(id) = headers.get(replication::REPLICA_ID_HEADER).and_then(|v| v.to_str().ok()) {
+                let replayed = headers.get(replication::REPLICA_POSITION_HEADER)
+                    .and_then(|v| v.to_str().ok()).and_then(|s| s.parse::<i64>().ok()).unwrap_or(0);
+                replication::note_peer(id, &extract_client_ip(Some(&headers)), manifest.position, replayed);
+            }
+            (StatusCode::OK, Json(serde_json::to_value(&manifest).unwrap_or_default())).into_response()
+        }
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response(),
+    }
+}
+
+/// GET /replication/file/{*path} -> raw bytes of one replicated file
+async fn replication_file(State(state): State<AppState>, headers: HeaderMap, Path(path): Path<String>) -> impl IntoResponse {
+    if !replication::authorize(replication_token(&headers)) {
+        return (StatusCode::FORBIDDEN, "forbidden").into_response();
+    }
+    let full = match replication::resolve_rel_path(std::path::Path::new(&state.db_root), &path) {
+        Ok(p) => p,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
     };
-    let Some(sid) = get_sid_from_headers(&headers) else {
+    match tokio::fs::read(&full).await {
+        Ok(bytes) => {
+            let mut h = HeaderMap::new();
+            h.insert("Content-Type", HeaderValue::from_static("application/octet-stream"));
+            (StatusCode::OK, h, bytes).into_response()
+        }
+        Err(_) => (StatusCode::NOT_FOUND, "not found").into_response(),
+    }
+}
+
+async fn get_csrf(State(state): State<AppState>, Extension(auth): Extension<AuthContext>) -> impl IntoResponse {
+    // Only cookie sessions have a CSRF token
+    let Some(sid) = auth.session_id else {
         return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"})));
     };
     let cmap = state.csrf_tokens.read().await;
@@ -762,141 +1023,19 @@ async fn get_csrf(State(state): State<AppState>, headers: HeaderMap) -> impl Int
     (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"status":"error","error":"csrf not available"})))
 }
 
-fn to_ck_and_db(cmd: &query::Command) -> (security::CommandKind, Option<String>) {
-    match cmd {
-        query::Command::Select(q) => (security::CommandKind::Select, q.base_table.as_ref().and_then(|t| t.table_name().map(|s| s.to_string()))),
-        query::Command::Calculate { query: q, .. } => (security::CommandKind::Calculate, q.base_table.as_ref().and_then(|t| t.table_name().map(|s| s.to_string()))),
-        query::Command::Update { table, .. } => {
-            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
-            (security::CommandKind::Other, db_name)
-        }
-        // Views
-        query::Command::CreateView { .. } | query::Command::DropView { .. } | query::Command::ShowView { .. } => (security::CommandKind::Database, None),
-        query::Command::DeleteRows { database, .. } => (security::CommandKind::DeleteRows, Some(database.clone())),
-        query::Command::DeleteColumns { database, .. } => (security::CommandKind::DeleteColumns, Some(database.clone())),
-        query::Command::SchemaShow { database } => (security::CommandKind::Schema, Some(database.clone())),
-        query::Command::SchemaAdd { database, .. } => (security::CommandKind::Schema, Some(database.clone())),
-        // Legacy
-        query::Command::DatabaseAdd { .. } => (security::CommandKind::Database, None),
-        query::Command::DatabaseDelete { .. } => (security::CommandKind::Database, None),
-        // New DDL
-        query::Command::CreateDatabase { .. } | query::Command::DropDatabase { .. } | query::Command::RenameDatabase { .. } => (security::CommandKind::Database, None),
-        query::Command::CreateSchema { .. } | query::Command::DropSchema { .. } | query::Command::RenameSchema { .. } => (security::CommandKind::Schema, None),
-        query::Command::CreateTimeTable { .. } | query::Command::DropTimeTable { .. } | query::Command::RenameTimeTable { .. } => (security::CommandKind::Database, None),
-        query::Command::CreateTable { .. } | query::Command::DropTable { .. } | query::Command::RenameTable { .. } => (security::CommandKind::Database, None),
-        query::Command::AlterTable { table, .. } => {
-            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
-            (security::CommandKind::Database, db_name)
-        }
-        query::Command::UserAdd { .. } | query::Command::UserDelete { .. } | query::Command::UserAlter { .. } => (security::CommandKind::Other, None),
-        query::Command::CreateScript { .. } | query::Command::DropScript { .. } | query::Command::RenameScript { .. } | query::Command::LoadScript { .. } => (security::CommandKind::Other, None),
-        // KV store/key commands
-        query::Command::CreateStore { database, .. } => (security::CommandKind::Database, Some(database.clone())),
-        query::Command::DropStore { database, .. } => (security::CommandKind::Database, Some(database.clone())),
-        query::Command::RenameStore { database, .. } => (security::CommandKind::Database, Some(database.clone())),
-        query::Command::WriteKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
-        query::Command::ReadKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
-        query::Command::DropKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
-        query::Command::RenameKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
-        query::Command::ListStores { database, .. } => (security::CommandKind::Other, Some(database.clone())),
-        query::Command::ListKeys { database, .. } => (security::CommandKind::Other, Some(database.clone())),
-        query::Command::DescribeKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
-        query::Command::DescribeObject { .. } => (security::CommandKind::Other, None),
-        // Vector index catalog and lifecycle
-        query::Command::CreateVectorIndex { .. }
-        | query::Command::DropVectorIndex { .. }
-        | query::Command::ShowVectorIndex { .. }
-        | query::Command::ShowVectorIndexes
-        | query::Command::BuildVectorIndex { .. }
-        | query::Command::ReindexVectorIndex { .. }
-        | query::Command::ShowVectorIndexStatus { .. }
-        | query::Command::AlterVectorIndexSetMode { .. }
-        => (security::CommandKind::Database, None),
-        // Graph catalog and graph-related commands
-        query::Command::CreateGraph { .. }
-        | query::Command::DropGraph { .. }
-        | query::Command::ShowGraph { .. }
-        | query::Command::ShowGraphs
-        | query::Command::ShowGraphStatus { .. }
-        | query::Command::UseGraph { .. }
-        | query::Command::UnsetGraph
-        | query::Command::ShowCurrentGraph
-        | query::Command::BeginGraphTxn { .. }
-        | query::Command::CommitGraphTxn
-        | query::Command::AbortGraphTxn
-        | query::Command::InsertNodeTxn { .. }
-        | query::Command::InsertEdgeTxn { .. }
-        | query::Command::GcGraph { .. }
-        | query::Command::MatchRewrite { .. } => (security::CommandKind::Other, None),
-        // Global session-affecting and SHOW
-        query::Command::UseDatabase { .. } | query::Command::UseSchema { .. } | query::Command::Set { .. } => (security::CommandKind::Other, None),
-        query::Command::ShowTransactionIsolation
-        | query::Command::ShowStandardConformingStrings
-        | query::Command::ShowServerVersion
-        | query::Command::ShowClientEncoding
-        | query::Command::ShowServerEncoding
-        | query::Command::ShowDateStyle
-        | query::Command::ShowIntegerDateTimes
-        | query::Command::ShowTimeZone
-        | query::Command::ShowSearchPath
-        | query::Command::ShowDefaultTransactionIsolation
-        | query::Command::ShowTransactionReadOnly
-        | query::Command::ShowApplicationName
-        | query::Command::ShowExtraFloatDigits
-        | query::Command::ShowAll
-        | query::Command::ShowSchemas
-        | query::Command::ShowTables
-        | query::Command::ShowObjects
-        | query::Command::ShowScripts => (security::CommandKind::Other, None),
-        query::Command::ClearScriptCache { .. } => (security::CommandKind::Other, None),
-        // FILESTORE commands: treat as Other with no specific database context here
-        query::Command::ShowFilestores { .. }
-        | query::Command::ShowFilestoreConfig { .. }
-        | query::Command::ShowFilesInFilestore { .. }
-        | query::Command::ShowTreesInFilestore { .. }
-        | query::Command::ShowCommitsInFilestore { .. }
-        | query::Command::ShowDiffInFilestore { .. }
-        | query::Command::ShowChunksInFilestore { .. }
-        | query::Command::ShowAliasesInFilestore { .. }
-        | query::Command::ShowAdminInFilestore { .. }
-        | query::Command::ShowHealthInFilestore { .. }
-        | query::Command::CreateFilestoreCmd { .. }
-        | query::Command::AlterFilestoreCmd { .. }
-        | query::Command::DropFilestoreCmd { .. }
-        | query::Command::IngestFileFromBytesCmd { .. }
-        | query::Command::IngestFileFromHostPathCmd { .. }
-        | query::Command::UpdateFileFromBytesCmd { .. }
-        | query::Command::RenameFilePathCmd { .. }
-        | query::Command::DeleteFilePathCmd { .. }
-        | query::Command::CreateTreeCmd { .. }
-        | query::Command::CommitTreeCmd { .. }
-        => (security::CommandKind::Other, None),
-        query::Command::Explain { .. } => (security::CommandKind::Other, None),
-        query::Command::SelectUnion { .. } => (security::CommandKind::Select, None),
-        query::Command::Slice(_) => (security::CommandKind::Select, None),
-        query::Command::Insert { table, .. } | query::Command::InsertSelect { table, .. } => {
-            // Extract database from table path (format: db/schema/table or just table)
-            let db_name = if table.contains('/') {
-                table.split('/').next().map(|s| s.to_string())
-            } else {
-                None
-            };
-            (security::CommandKind::Other, db_name)
-        }
-    }
+/// Command gate for HTTP and WebSocket statements: role grants first, then object privileges
+/// granted on the target relation or its schema or database, to the session user or to the
+/// role it assumed with SET ROLE (and the roles either inherits).
+async fn command_allowed(state: &AppState, username: &str, role: Option<&str>, token_roles: &[String], cmd: &query::Command, defaults: &crate::ident::QueryDefaults) -> bool {
+    exec::exec_authorize::statement_allowed(&state.store, &state.db_root, username, role, token_roles, cmd, defaults).await
 }
 
 async fn query_handler(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    Extension(auth): Extension<AuthContext>,
     Json(payload): Json<QueryPayload>,
 ) -> impl IntoResponse {
-    let Some(username) = get_username_from_headers(&state, &headers).await else {
-        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"}))).into_response();
-    };
-    if !validate_csrf(&state, &headers).await {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden","error":"invalid csrf"}))).into_response();
-    }
+    let username = auth.username();
     // Transaction control statements: accept as no-ops for client compatibility
     if let Some(_tx) = detect_transaction_cmd(&payload.query) {
         return (StatusCode::OK, Json(serde_json::json!({"status":"ok","results": {"transaction":"ok"} }))).into_response();
@@ -904,25 +1043,23 @@ async fn query_handler(
     // Parse and authorize
     let cmd = match query::parse(&payload.query) {
         Ok(c) => c,
-        Err(e) => { return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response(); }
+        Err(e) => {
+            let app = crate::error::AppError::from_parse_error(&e, &payload.query);
+            return (StatusCode::from_u16(app.http_status()).unwrap_or(StatusCode::BAD_REQUEST), Json(app.to_json())).into_response();
+        }
     };
-    let (ck, db_opt) = to_ck_and_db(&cmd);
-    let allowed = crate::identity::check_command_allowed_async(&state.store, &username, ck, db_opt.as_deref()).await;
-    if !allowed {
+    // Determine per-session defaults (object privileges are checked on the qualified relation)
+    let (cur_db, cur_schema) = auth.current_defaults(&state).await;
+    let defaults = crate::ident::QueryDefaults::new(cur_db.clone(), cur_schema);
+    let backend_pid = auth.session_id.as_deref().and_then(activity::session_pid);
+    let role = backend_pid.and_then(activity::role_of);
+    if !command_allowed(&state, username, role.as_deref(), &auth.principal.roles, &cmd, &defaults).await {
         return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden"}))).into_response();
     }
-    // Determine per-session defaults
-    let (cur_db, cur_schema) = {
-        let sid_opt = get_sid_from_headers(&headers);
-        if let Some(sid) = sid_opt {
-            let dmap = state.session_defaults.read().await;
-            if let Some((db, sc)) = dmap.get(&sid) { (db.clone(), sc.clone()) } else { (env_default_db(), env_default_schema()) }
-        } else { (env_default_db(), env_default_schema()) }
-    };
-    let defaults = crate::ident::QueryDefaults { current_database: cur_db, current_schema: cur_schema };
-    let exec_fut = async {
+    if let Some(pid) = backend_pid { activity::set_database(pid, &cur_db); }
+    let exec_fut = activity::run_statement(backend_pid, &payload.query, async {
         crate::server::exec::execute_query_with_defaults(&state.store, &payload.query, &defaults).await
-    };
+    });
     let exec_result = AssertUnwindSafe(exec_fut).catch_unwind().await;
     match exec_result {
         Ok(Ok(value)) => {
@@ -930,8 +1067,8 @@ async fn query_handler(
             if let Ok(parsed_cmd) = query::parse(&payload.query) {
                 if let query::Command::UserAlter { username: u, .. } = parsed_cmd {
                     if u == username {
-                        if let Some(old_sid) = get_sid_from_headers(&headers) {
-                            if let Some(new_cookie) = rotate_session_id(&state, &old_sid).await {
+                        if let Some(old_sid) = auth.session_id.as_deref() {
+                            if let Some(new_cookie) = rotate_session_id(&state, old_sid).await {
                                 let mut h = HeaderMap::new();
                                 h.insert("Set-Cookie", new_cookie);
                                 let resp = (StatusCode::OK, h, Json(serde_json::json!({"status":"ok","results": value})) ).into_response();
@@ -944,18 +1081,10 @@ async fn query_handler(
             return (StatusCode::OK, Json(serde_json::json!({"status":"ok","results": value})) ).into_response();
         }
         Ok(Err(e)) => {
-            // Prefer AppError mapping when available
-            if let Some(app) = e.downcast_ref::<crate::error::AppError>() {
-                let status = app.http_status();
-                return (StatusCode::from_u16(status).unwrap_or(StatusCode::UNPROCESSABLE_ENTITY), Json(serde_json::json!({
-                    "status":"error",
-                    "code": app.code_str(),
-                    "message": app.message()
-                }))).into_response();
-            }
-            // Treat other execution failures as semantic errors (unprocessable)
-            error!("query failed: {e}");
-            return (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({"status":"error","code":"exec_error","message": e.to_string()}))).into_response();
+            // AppError when the engine raised one, else classified from the message (SQLSTATE + HTTP status)
+            let app = crate::error::AppError::classify(&e);
+            if matches!(app, crate::error::AppError::Exec { .. } | crate::error::AppError::Internal { .. }) { error!("query failed: {e}"); }
+            return (StatusCode::from_u16(app.http_status()).unwrap_or(StatusCode::UNPROCESSABLE_ENTITY), Json(app.to_json())).into_response();
         }
         Err(panic_payload) => {
             // Convert panics to a 500 error response without crashing the server task
@@ -972,21 +1101,24 @@ async fn query_handler(
     }
 }
 
-async fn ws_handler(State(state): State<AppState>, headers: HeaderMap, ws: WebSocketUpgrade) -> impl IntoResponse {
-    // Require login
-    let Some(username) = get_username_from_headers(&state, &headers).await else {
-        return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
-    };
-    // Require CSRF token header too
-    if !validate_csrf(&state, &headers).await {
-        return (StatusCode::FORBIDDEN, "forbidden: invalid csrf").into_response();
-    }
+async fn ws_handler(State(state): State<AppState>, Extension(auth): Extension<AuthContext>, ws: WebSocketUpgrade) -> impl IntoResponse {
     ws.on_upgrade(move |mut socket| {
         let state = state.clone();
-        let username = username.clone();
         async move {
             use futures_util::StreamExt;
-            while let Some(Ok(msg)) = socket.next().await {
+            use crate::server::exec::exec_keys::{handle_watch_command, watch_message_json};
+            // KV watches of this connection deliver here between statements
+            let (watch_tx, mut watch_rx) = crate::storage::kv_events::watch_channel();
+            let mut watches: Vec<u64> = Vec::new();
+            loop {
+                let msg = tokio::select! {
+                    msg = socket.next() => match msg { Some(Ok(msg)) => msg, _ => break },
+                    Some(m) = watch_rx.recv() => {
+                        if matches!(m, crate::storage::kv_events::WatchMessage::Lagged) { watches.clear(); }
+                        if socket.send(Message::Text(watch_message_json(&m).to_string().into())).await.is_err() { break; }
+                        continue;
+                    }
+                };
                 match msg {
                     Message::Text(text) => {
                         // Transaction control statements over WS: accept as no-ops
@@ -994,24 +1126,27 @@ async fn ws_handler(State(state): State<AppState>, headers: HeaderMap, ws: WebSo
                             let _ = socket.send(Message::Text(serde_json::json!({"status":"ok","results": {"transaction":"ok"}}).to_string().into())).await;
                             continue;
                         }
-                        // authorize per message using unified async RBAC gate
-                        let auth_ok = if let Ok(cmd) = query::parse(&text) {
-                            let (ck, db_opt) = to_ck_and_db(&cmd);
-                            crate::identity::check_command_allowed_async(&state.store, &username, ck, db_opt.as_deref()).await
-                        } else { false };
+                        // Per-session defaults
+                        let (cur_db, cur_schema) = auth.current_defaults(&state).await;
+                        let defaults = crate::ident::QueryDefaults::new(cur_db, cur_schema);
+                        // authorize per message using unified async RBAC gate and object privileges
+                        let parsed = query::parse(&text);
+                        let auth_ok = match &parsed {
+                            Ok(cmd) => command_allowed(&state, auth.username(), None, &auth.principal.roles, cmd, &defaults).await,
+                            Err(_) => false,
+                        };
                         if !auth_ok {
                             let _ = socket.send(Message::Text(serde_json::json!({"status":"forbidden","error":"forbidden"}).to_string().into())).await;
                             continue;
                         }
-                        // Per-session defaults
-                        let (cur_db, cur_schema) = {
-                            let sid_opt = get_sid_from_headers(&headers);
-                            if let Some(sid) = sid_opt {
-                                let dmap = state.session_defaults.read().await;
-                                if let Some((db, sc)) = dmap.get(&sid) { (db.clone(), sc.clone()) } else { (env_default_db(), env_default_schema()) }
-                            } else { (env_default_db(), env_default_schema()) }
-                        };
-                        let defaults = crate::ident::QueryDefaults { current_database: cur_db, current_schema: cur_schema };
+                        if let Ok(cmd @ (query::Command::Watch { .. } | query::Command::Unwatch { .. })) = &parsed {
+                            let reply = match handle_watch_command(cmd, &watch_tx, &mut watches) {
+                                Ok(val) => serde_json::json!({"status":"ok","results": val}),
+                                Err(e) => crate::error::AppError::classify(&e).to_json(),
+                            };
+                            let _ = socket.send(Message::Text(reply.to_string().into())).await;
+                            continue;
+                        }
                         let fut = async {
                             crate::server::exec::execute_query_with_defaults(&state.store, &text, &defaults).await
                         };
@@ -1020,16 +1155,9 @@ async fn ws_handler(State(state): State<AppState>, headers: HeaderMap, ws: WebSo
                                 let _ = socket.send(Message::Text(serde_json::json!({"status":"ok","results": val}).to_string().into())).await;
                             }
                             Ok(Err(e)) => {
-                                // Keep socket alive; prefer AppError mapping when available
-                                if let Some(app) = e.downcast_ref::<crate::error::AppError>() {
-                                    let _ = socket.send(Message::Text(serde_json::json!({
-                                        "status":"error",
-                                        "code": app.code_str(),
-                                        "message": app.message()
-                                    }).to_string().into())).await;
-                                } else {
-                                    let _ = socket.send(Message::Text(serde_json::json!({"status":"error","code":"exec_error","message": e.to_string()}).to_string().into())).await;
-                                }
+                                // Keep socket alive; same error body as HTTP
+                                let app = crate::error::AppError::classify(&e);
+                                let _ = socket.send(Message::Text(app.to_json().to_string().into())).await;
                             }
                             Err(panic_payload) => {
                                 let msg = if let Some(s) = panic_payload.downcast_ref::<&str>() { *s }
@@ -1049,6 +1177,7 @@ async fn ws_handler(State(state): State<AppState>, headers: HeaderMap, ws: WebSo
                     _ => {}
                 }
             }
+            for id in watches { crate::storage::kv_events::unwatch(id); }
         }
     })
 }
@@ -1086,6 +1215,8 @@ async fn rotate_session_id(state: &AppState, old_sid: &str) -> Option<HeaderValu
         m.insert(new_sid.clone(), SessionMeta { issued_at: now, last_seen: now });
         m.remove(old_sid);
         drop(m);
+        activity::rekey_session(old_sid, &new_sid);
+        crate::identity::SessionManager::default().rotate(old_sid, &new_sid);
         Some(set_session_cookie(&new_sid))
     } else {
         None
@@ -1148,14 +1279,8 @@ async fn record_login_success(state: &AppState, ip: &str, username: &str) {
 #[derive(Debug, Serialize)]
 struct UseResult { status: &'static str }
 
-async fn use_database(State(state): State<AppState>, headers: HeaderMap, Json(payload): Json<UsePayload>) -> impl IntoResponse {
-    let Some(_username) = get_username_from_headers(&state, &headers).await else {
-        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"})));
-    };
-    if !validate_csrf(&state, &headers).await {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden","error":"invalid csrf"})));
-    }
-    let Some(sid) = get_sid_from_headers(&headers) else {
+async fn use_database(State(state): State<AppState>, Extension(auth): Extension<AuthContext>, Json(payload): Json<UsePayload>) -> impl IntoResponse {
+    let Some(sid) = auth.session_id else {
         return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"})));
     };
     let mut dmap = state.session_defaults.write().await;
@@ -1164,14 +1289,8 @@ async fn use_database(State(state): State<AppState>, headers: HeaderMap, Json(pa
     (StatusCode::OK, Json(serde_json::json!(UseResult{ status: "ok" })))
 }
 
-async fn use_schema(State(state): State<AppState>, headers: HeaderMap, Json(payload): Json<UsePayload>) -> impl IntoResponse {
-    let Some(_username) = get_username_from_headers(&state, &headers).await else {
-        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"})));
-    };
-    if !validate_csrf(&state, &headers).await {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden","error":"invalid csrf"})));
-    }
-    let Some(sid) = get_sid_from_headers(&headers) else {
+async fn use_schema(State(state): State<AppState>, Extension(auth): Extension<AuthContext>, Json(payload): Json<UsePayload>) -> impl IntoResponse {
+    let Some(sid) = auth.session_id else {
         return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"status":"unauthorized"})));
     };
     let mut dmap = state.session_defaults.write().await;
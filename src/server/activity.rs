@@ -0,0 +1,285 @@
+//!
+//! clarium backend activity
+//! ------------------------
+//! Registry of live sessions ("backends") shared by the HTTP and pgwire frontends.
+//!
+//! Every pgwire connection and every logged-in HTTP session gets a pid. Statements run
+//! through [`run_statement`] are recorded as the backend's current query, and the whole
+//! registry is exposed as `pg_catalog.pg_stat_activity`. Finished statements are counted
+//! per database for `pg_catalog.pg_stat_database` (see `server::db_stats`).
+//!
+//! - `pg_cancel_backend(pid)` / `KILL QUERY <pid>` abort the backend's running statement.
+//! - `pg_terminate_backend(pid)` / `KILL [CONNECTION] <pid>` also end the session: the
+//!   pgwire connection is closed, the HTTP session is logged out.
+//!
+//! Cancellation is cooperative: a statement stops at its next await point or at the
+//! next SELECT stage boundary (see [`check_cancelled`]).
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frontend {
+    Http,
+    Pgwire,
+    /// Scheduled job run (see `server::jobs`)
+    Job,
+}
+
+impl Frontend {
+    pub fn as_str(&self) -> &'static str {
+        match self { Frontend::Http => "http", Frontend::Pgwire => "pgwire", Frontend::Job => "job" }
+    }
+}
+
+/// Snapshot of one backend, as reported by `pg_stat_activity`. Times are epoch ms.
+#[derive(Debug, Clone)]
+pub struct Activity {
+    pub pid: i32,
+    pub frontend: Frontend,
+    pub user: String,
+    pub database: String,
+    pub client_addr: String,
+    pub backend_start: i64,
+    /// `idle` or `active`
+    pub state: &'static str,
+    /// Current statement, or the last one when idle
+    pub query: String,
+    pub query_start: Option<i64>,
+    pub state_change: i64,
+}
+
+struct Slot {
+    info: Activity,
+    /// HTTP session id, for backends that belong to an HTTP session
+    session_key: Option<String>,
+    /// Role assumed with SET ROLE; None acts as the session user
+    role: Option<String>,
+    cancel_requested: Arc<AtomicBool>,
+    cancel: Arc<Notify>,
+    terminated: Arc<AtomicBool>,
+    terminate: Arc<Notify>,
+}
+
+static NEXT_PID: AtomicI32 = AtomicI32::new(1000);
+static BACKENDS: Lazy<RwLock<BTreeMap<i32, Slot>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+thread_local! {
+    // Backend whose statement is being polled on this thread (set per poll by run_statement)
+    static TLS_CURRENT_PID: Cell<Option<i32>> = const { Cell::new(None) };
+}
+
+fn now_ms() -> i64 { chrono::Utc::now().timestamp_millis() }
+
+/// Pid of the backend whose statement is executing on this thread.
+pub fn current_pid() -> Option<i32> { TLS_CURRENT_PID.with(|c| c.get()) }
+
+/// User of the backend whose statement is executing on this thread.
+pub fn current_user() -> Option<String> {
+    let pid = current_pid()?;
+    BACKENDS.read().get(&pid).map(|s| s.info.user.clone())
+}
+
+/// Role the backend executing on this thread assumed with SET ROLE, if any.
+pub fn current_role() -> Option<String> { role_of(current_pid()?) }
+
+/// Role assumed with SET ROLE by backend `pid`.
+pub fn role_of(pid: i32) -> Option<String> {
+    BACKENDS.read().get(&pid).and_then(|s| s.role.clone())
+}
+
+/// Assume `role` (SET ROLE) or go back to the session user (None, RESET ROLE).
+pub fn set_role(pid: i32, role: Option<&str>) {
+    if let Some(s) = BACKENDS.write().get_mut(&pid) { s.role = role.map(|r| r.to_string()); }
+}
+
+/// Database of the backend whose statement is executing on this thread.
+pub fn current_database() -> Option<String> {
+    let pid = current_pid()?;
+    BACKENDS.read().get(&pid).map(|s| s.info.database.clone())
+}
+
+/// Register a new backend and return its pid.
+pub fn register(frontend: Frontend, user: &str, database: &str, client_addr: &str, session_key: Option<String>) -> i32 {
+    let pid = NEXT_PID.fetch_add(1, Ordering::Relaxed);
+    let now = now_ms();
+    let info = Activity {
+        pid, frontend,
+        user: user.to_string(),
+        database: database.to_string(),
+        client_addr: client_addr.to_string(),
+        backend_start: now,
+        state: "idle",
+        query: String::new(),
+        query_start: None,
+        state_change: now,
+    };
+    BACKENDS.write().insert(pid, Slot {
+        info, session_key,
+        role: None,
+        cancel_requested: Arc::new(AtomicBool::new(false)),
+        cancel: Arc::new(Notify::new()),
+        terminated: Arc::new(AtomicBool::new(false)),
+        terminate: Arc::new(Notify::new()),
+    });
+    crate::tprintln!("[activity] registered {} backend pid={} user='{}'", frontend.as_str(), pid, user);
+    pid
+}
+
+pub fn unregister(pid: i32) { BACKENDS.write().remove(&pid); }
+
+/// Unregisters its backend when dropped; held for the lifetime of a pgwire connection.
+pub struct BackendGuard(pub i32);
+
+impl Drop for BackendGuard {
+    fn drop(&mut self) { unregister(self.0); }
+}
+
+pub fn set_database(pid: i32, database: &str) {
+    if let Some(s) = BACKENDS.write().get_mut(&pid) { s.info.database = database.to_string(); }
+}
+
+/// Backend registered for an HTTP session id.
+pub fn session_pid(session_key: &str) -> Option<i32> {
+    BACKENDS.read().iter().find(|(_, s)| s.session_key.as_deref() == Some(session_key)).map(|(pid, _)| *pid)
+}
+
+pub fn unregister_session(session_key: &str) {
+    BACKENDS.write().retain(|_, s| s.session_key.as_deref() != Some(session_key));
+}
+
+/// Follow an HTTP session id rotation.
+pub fn rekey_session(old_key: &str, new_key: &str) {
+    for s in BACKENDS.write().values_mut() {
+        if s.session_key.as_deref() == Some(old_key) { s.session_key = Some(new_key.to_string()); }
+    }
+}
+
+pub fn is_terminated(pid: i32) -> bool {
+    BACKENDS.read().get(&pid).map(|s| s.terminated.load(Ordering::Relaxed)).unwrap_or(false)
+}
+
+/// Resolves once `pid` is terminated; never resolves for unknown pids.
+pub async fn terminated(pid: i32) {
+    let notify = BACKENDS.read().get(&pid).map(|s| s.terminate.clone());
+    match notify {
+        Some(n) => n.notified().await,
+        None => std::future::pending::<()>().await,
+    }
+}
+
+/// Error out of the current statement if its backend was cancelled; called between SELECT stages.
+pub fn check_cancelled() -> Result<()> {
+    let Some(pid) = current_pid() else { return Ok(()) };
+    let requested = BACKENDS.read().get(&pid).map(|s| s.cancel_requested.load(Ordering::Relaxed)).unwrap_or(false);
+    if requested { return Err(cancel_error(pid)); }
+    Ok(())
+}
+
+/// Error returned by a statement stopped through cancel or terminate.
+#[derive(Debug)]
+pub struct Cancelled { pub terminated: bool }
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.terminated { f.write_str("terminating connection due to administrator command") }
+        else { f.write_str("canceling statement due to user request") }
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pub fn is_cancelled_error(e: &anyhow::Error) -> bool { e.downcast_ref::<Cancelled>().is_some() }
+
+fn cancel_error(pid: i32) -> anyhow::Error { anyhow::Error::new(Cancelled { terminated: is_terminated(pid) }) }
+
+/// Update the query text shown for the statement running on this thread.
+pub fn set_current_query(sql: &str) {
+    let Some(pid) = current_pid() else { return };
+    if let Some(s) = BACKENDS.write().get_mut(&pid) { s.info.query = sql.to_string(); }
+}
+
+/// Run `fut` as the current statement of backend `pid`: it is shown as active in
+/// `pg_stat_activity` and can be cancelled. Without a pid the future just runs.
+pub async fn run_statement<T, F>(pid: Option<i32>, sql: &str, fut: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let Some(pid) = pid else { return fut.await };
+    // Per-user statement rate (limits.queries_per_minute) may hold the statement back
+    let user = BACKENDS.read().get(&pid).map(|s| s.info.user.clone());
+    if let Some(user) = user { crate::server::quota::admit_query(&user).await?; }
+    let handles = {
+        let mut map = BACKENDS.write();
+        map.get_mut(&pid).map(|s| {
+            let now = now_ms();
+            s.info.state = "active";
+            s.info.query = sql.to_string();
+            s.info.query_start = Some(now);
+            s.info.state_change = now;
+            s.cancel_requested.store(false, Ordering::Relaxed);
+            (s.cancel_requested.clone(), s.cancel.clone())
+        })
+    };
+    let Some((requested, cancel)) = handles else { return fut.await };
+    let mut fut = Box::pin(fut);
+    let mut cancelled = Box::pin(cancel.notified());
+    let out = std::future::poll_fn(|cx| {
+        let prev = TLS_CURRENT_PID.with(|c| c.replace(Some(pid)));
+        let r = fut.as_mut().poll(cx);
+        TLS_CURRENT_PID.with(|c| c.set(prev));
+        if r.is_ready() { return r; }
+        if requested.load(Ordering::Relaxed) || cancelled.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Err(cancel_error(pid)));
+        }
+        Poll::Pending
+    }).await;
+    let database = BACKENDS.write().get_mut(&pid).map(|s| {
+        s.info.state = "idle";
+        s.info.state_change = now_ms();
+        s.cancel_requested.store(false, Ordering::Relaxed);
+        s.info.database.clone()
+    });
+    if let Some(db) = database { crate::server::db_stats::record_statement(&db, out.is_ok()); }
+    out
+}
+
+/// Cancel the running statement of `pid`. Returns false when no such backend exists.
+pub fn cancel_backend(pid: i32) -> bool {
+    let map = BACKENDS.read();
+    let Some(s) = map.get(&pid) else { return false };
+    if s.info.state == "active" {
+        s.cancel_requested.store(true, Ordering::Relaxed);
+        s.cancel.notify_waiters();
+    }
+    crate::tprintln!("[activity] cancel requested for pid={}", pid);
+    true
+}
+
+/// Cancel the running statement of `pid` and end its session. Returns false when no such backend exists.
+pub fn terminate_backend(pid: i32) -> bool {
+    {
+        let map = BACKENDS.read();
+        let Some(s) = map.get(&pid) else { return false };
+        s.terminated.store(true, Ordering::Relaxed);
+        // notify_one keeps a permit, so an idle connection sees it on its next wait
+        s.terminate.notify_one();
+    }
+    cancel_backend(pid);
+    crate::tprintln!("[activity] terminate requested for pid={}", pid);
+    true
+}
+
+/// All registered backends, ordered by pid.
+pub fn snapshot() -> Vec<Activity> {
+    BACKENDS.read().values().map(|s| s.info.clone()).collect()
+}
@@ -0,0 +1,178 @@
+//! Collations for text ordering and comparison (`COLLATE "<name>"`).
+//!
+//! Text is byte-ordered unless a collation applies: one written in the query
+//! (`ORDER BY name COLLATE "codepoint-ci"`, `WHERE name COLLATE "codepoint-ci" = 'x'`) or one set on
+//! the column (`ALTER TABLE t ALTER COLUMN c SET COLLATION "codepoint-ci"`, kept in schema.json
+//! under `collations`). An explicit COLLATE wins over the column's collation.
+//!
+//! The `codepoint` collations map each string to a sort key whose byte order is the collation
+//! order, at three strengths: base letters first (accents removed, case folded, then in code
+//! point order), then accents, then case with lowercase first. `codepoint-ci` stops after the
+//! accent level so strings differing only in case compare equal; `codepoint-ai` stops after the
+//! base level and ignores accents too. This is not the Unicode Collation Algorithm and applies no
+//! language rules, so locale names (`"de-DE"`, `"sv-SE"`, ...) are rejected rather than accepted
+//! with an order that language would not use. `C`, `POSIX` and `default` keep byte order.
+
+use polars::prelude::*;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// schema.json key mapping column names to collation names.
+pub const COLLATIONS_KEY: &str = "collations";
+
+/// Name of the folding collations, offered as `codepoint`, `codepoint-ci` and `codepoint-ai`.
+pub const CODEPOINT: &str = "codepoint";
+
+/// Byte-order collations and their PostgreSQL oids.
+const BYTEWISE: &[(&str, i32)] = &[("default", 100), ("C", 950), ("POSIX", 951)];
+
+/// First oid handed to the `codepoint` collations in `pg_collation`.
+const FIRST_CODEPOINT_OID: i32 = 12_000;
+
+/// How many levels of the sort key take part in comparisons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strength {
+    /// Base letters only (`-ai`)
+    Primary,
+    /// Base letters and accents (`-ci`)
+    Secondary,
+    /// Base letters, accents and case
+    Tertiary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collation {
+    /// Canonical name as listed in `pg_collation`
+    pub name: String,
+    /// False for the byte-order collations
+    pub folding: bool,
+    pub strength: Strength,
+}
+
+impl Collation {
+    /// Look a collation up by name, ignoring case and accepting `_` for `-`.
+    pub fn lookup(name: &str) -> Option<Collation> {
+        let n = name.trim().trim_matches('"');
+        if let Some((b, _)) = BYTEWISE.iter().find(|(b, _)| b.eq_ignore_ascii_case(n)) {
+            return Some(Collation { name: b.to_string(), folding: false, strength: Strength::Tertiary });
+        }
+        let lower = n.replace('_', "-").to_ascii_lowercase();
+        let (base, strength) = if let Some(b) = lower.strip_suffix("-ci") {
+            (b, Strength::Secondary)
+        } else if let Some(b) = lower.strip_suffix("-ai") {
+            (b, Strength::Primary)
+        } else {
+            (lower.as_str(), Strength::Tertiary)
+        };
+        if base != CODEPOINT { return None; }
+        let name = match strength {
+            Strength::Tertiary => CODEPOINT.to_string(),
+            Strength::Secondary => format!("{}-ci", CODEPOINT),
+            Strength::Primary => format!("{}-ai", CODEPOINT),
+        };
+        Some(Collation { name, folding: true, strength })
+    }
+
+    /// Like `lookup`, failing with PostgreSQL's message for unknown names.
+    /// Locale names get a hint, since no language-specific ordering is available.
+    pub fn resolve(name: &str) -> anyhow::Result<Collation> {
+        let n = name.trim().trim_matches('"');
+        Self::lookup(n).ok_or_else(|| {
+            if looks_like_locale(n) {
+                anyhow::anyhow!("collation \"{}\" is not supported: locale collations are not available, use \"{}\", \"{}-ci\" or \"{}-ai\"", n, CODEPOINT, CODEPOINT, CODEPOINT)
+            } else {
+                anyhow::anyhow!("collation \"{}\" does not exist", n)
+            }
+        })
+    }
+
+    /// True when the collation keeps plain byte order.
+    pub fn is_bytewise(&self) -> bool { !self.folding }
+
+    /// Equal sort keys mean equal strings under this collation.
+    pub fn is_deterministic(&self) -> bool { self.strength == Strength::Tertiary }
+
+    /// Sort key of `s`; keys compare bytewise in collation order.
+    pub fn sort_key(&self, s: &str) -> String {
+        if self.is_bytewise() { return s.to_string(); }
+        let mut primary = String::with_capacity(s.len());
+        let mut accents = String::new();
+        let mut case = String::new();
+        for c in s.nfd() {
+            if is_combining_mark(c) {
+                accents.push(c);
+                continue;
+            }
+            // One slot per base letter keeps accents aligned with the letter they sit on
+            accents.push('\u{1}');
+            case.push(if c.is_uppercase() { '1' } else { '0' });
+            primary.extend(c.to_lowercase());
+        }
+        match self.strength {
+            Strength::Primary => primary,
+            Strength::Secondary => format!("{}\u{0}{}", primary, accents),
+            Strength::Tertiary => format!("{}\u{0}{}\u{0}{}", primary, accents, case),
+        }
+    }
+
+    /// Expression producing the sort keys of a text expression; nulls stay null.
+    pub fn key_expr(&self, e: Expr) -> Expr {
+        let c = self.clone();
+        e.cast(DataType::String).map(
+            move |col: Column| {
+                let s = col.as_materialized_series();
+                let keys = s.str()?.apply_into_string_amortized(|v, buf| buf.push_str(&c.sort_key(v)));
+                Ok(keys.into_series().into_column())
+            },
+            |_schema, field| Ok(Field::new(field.name().clone(), DataType::String)),
+        )
+    }
+}
+
+/// A BCP 47 style name (`de-DE`, `sv_SE`, `und`, optionally with `-ci`/`-ai`).
+fn looks_like_locale(name: &str) -> bool {
+    let lower = name.replace('_', "-").to_ascii_lowercase();
+    let base = lower.strip_suffix("-ci").or_else(|| lower.strip_suffix("-ai")).unwrap_or(&lower);
+    let mut parts = base.split('-');
+    let lang = parts.next().unwrap_or("");
+    (2..=3).contains(&lang.len()) && lang.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// One `pg_collation` row.
+pub struct CollationRow {
+    pub oid: i32,
+    pub name: String,
+    /// `d` default, `c` libc byte order, `b` built in (`codepoint`)
+    pub provider: &'static str,
+    pub deterministic: bool,
+    pub locale: String,
+}
+
+/// Every collation accepted by `Collation::lookup`, in `pg_collation` order.
+pub fn available() -> Vec<CollationRow> {
+    let mut out: Vec<CollationRow> = BYTEWISE.iter().map(|(name, oid)| CollationRow {
+        oid: *oid,
+        name: name.to_string(),
+        provider: if *name == "default" { "d" } else { "c" },
+        deterministic: true,
+        locale: if *name == "default" { String::new() } else { name.to_string() },
+    }).collect();
+    for (i, (suffix, deterministic)) in [("", true), ("-ci", false), ("-ai", false)].into_iter().enumerate() {
+        out.push(CollationRow {
+            oid: FIRST_CODEPOINT_OID + i as i32,
+            name: format!("{}{}", CODEPOINT, suffix),
+            provider: "b",
+            deterministic,
+            locale: CODEPOINT.to_string(),
+        });
+    }
+    out
+}
+
+/// Column collations recorded in a table's schema.json.
+pub fn column_collations(schema_json: &serde_json::Value) -> std::collections::HashMap<String, String> {
+    schema_json.get(COLLATIONS_KEY).and_then(|m| m.as_object())
+        .map(|m| m.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect())
+        .unwrap_or_default()
+}
@@ -5,7 +5,7 @@ use std::rc::Rc;
 use std::cell::RefCell;
 
 use anyhow::Result;
-use polars::prelude::{DataFrame, Series, NamedFrom};
+use polars::prelude::{DataFrame, DataType, Series, NamedFrom};
 use crate::server::exec::internal::constants::ROW_ID;
 use tracing::debug;
 
@@ -93,6 +93,13 @@ pub struct DataContext {
     pub output_id_mode: bool,
     /// Column metadata captured at the moment we entered output-id mode
     pub column_matrix: Vec<ColumnMeta>,
+    /// Equality predicates (column, literal) from the WHERE clause that the base
+    /// table load may use to skip chunks via bloom filters. Set by FROM/WHERE only
+    /// while loading the base table of a single-table query.
+    pub chunk_prune_eq: Vec<(String, String)>,
+    /// Equality predicates (column, literal) usable to skip whole partitions; unlike
+    /// `chunk_prune_eq` this also carries numeric literals. Set alongside it.
+    pub partition_prune_eq: Vec<(String, String)>,
 }
 
 impl Default for DataContext {
@@ -393,6 +400,8 @@ impl DataContext {
             table_name_registry: Vec::new(),
             output_id_mode: false,
             column_matrix: Vec::new(),
+            chunk_prune_eq: Vec::new(),
+            partition_prune_eq: Vec::new(),
         }
     }
 
@@ -402,6 +411,41 @@ impl DataContext {
         self
     }
 
+    /// Look the (optionally qualified) column `name` up in the schema.json of each FROM/JOIN
+    /// table that may hold it; `f` gets the schema and the bare column name. A qualified name
+    /// only looks at the source with that alias or table name.
+    fn find_in_source_schemas<T>(&self, name: &str, f: impl Fn(&serde_json::Value, &str) -> Option<T>) -> Option<T> {
+        let store = self.store.as_ref()?;
+        let (qual, column) = match name.rsplit_once('.') { Some((q, c)) => (Some(q), c), None => (None, name) };
+        for t in &self.sources {
+            let TableRef::Table { name: tname, alias } = t else { continue };
+            if let Some(q) = qual {
+                let last = tname.rsplit(['.', '/']).next().unwrap_or(tname);
+                let hit = alias.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(q)) || tname.eq_ignore_ascii_case(q) || last.eq_ignore_ascii_case(q);
+                if !hit { continue; }
+            }
+            let dir = crate::ident::to_local_path(&store.root_path(), &self.resolve_table_name(tname));
+            let Some(v) = std::fs::read(dir.join("schema.json")).ok().and_then(|b| serde_json::from_slice::<serde_json::Value>(&b).ok()) else { continue };
+            if let Some(found) = f(&v, column) { return Some(found); }
+        }
+        None
+    }
+
+    /// Collation set on the source column `name` refers to (`ALTER TABLE ... SET COLLATION`).
+    pub fn column_collation(&self, name: &str) -> Option<crate::server::collation::Collation> {
+        self.find_in_source_schemas(name, |v, column| {
+            crate::server::collation::column_collations(v).get(column).and_then(|c| crate::server::collation::Collation::lookup(c))
+        })
+    }
+
+    /// Declared type of the source column `name` refers to, from its table's schema.json.
+    pub fn column_dtype(&self, name: &str) -> Option<DataType> {
+        self.find_in_source_schemas(name, |v, column| {
+            let declared = v.get("columns").and_then(|m| m.get(column)).or_else(|| v.get(column))?;
+            declared.as_str().map(crate::storage::schema::str_to_dtype)
+        })
+    }
+
     /// Register a FROM/JOIN table reference (and its optional alias)
     pub fn add_source(&mut self, t: &TableRef) {
         self.sources.push(t.clone());
@@ -844,11 +888,12 @@ impl DataContext {
     /// - If identifier contains '/' or '\\' treat as already fully-qualified path; normalize '\\' to '/'.
     /// - If identifier contains ".store.", return as-is (KV addressing) — caller may still need current db; we only expand bare store paths when defaults exist.
     /// - If identifier has one dot segment (schema.table), prepend current database.
-    /// - If identifier has no dots (table), prepend current database and schema.
+    /// - If identifier has no dots (table), prepend current database and the first search_path schema holding it
+    ///   (the current schema when no search_path is set or none holds it).
     /// - If identifier has two or more dots (db.schema.table), return db/schema/table.
     pub fn resolve_table_ident(&self, ident: &str) -> String {
         if ident.contains(".store.") { return ident.to_string(); }
-        let d = crate::ident::QueryDefaults::from_options(self.current_database.as_deref(), self.current_schema.as_deref());
+        let d = crate::system::with_session_search_path(crate::ident::QueryDefaults::from_options(self.current_database.as_deref(), self.current_schema.as_deref()));
         // If identifier denotes a time table, qualify accordingly
         if ident.contains(".time") || ident.trim_end_matches('/').ends_with(".time") {
             return crate::ident::qualify_time_ident(ident, &d);
@@ -860,7 +905,7 @@ impl DataContext {
     /// Supports path-like (db/schema/table[.time]) and dotted forms (db.schema.table[.time], schema.table, table).
     pub fn resolve_time_table_ident(&self, ident: &str) -> String {
         if ident.contains(".store.") { return ident.to_string(); }
-        let d = crate::ident::QueryDefaults::from_options(self.current_database.as_deref(), self.current_schema.as_deref());
+        let d = crate::system::with_session_search_path(crate::ident::QueryDefaults::from_options(self.current_database.as_deref(), self.current_schema.as_deref()));
         crate::ident::qualify_time_ident(ident, &d)
     }
 
@@ -940,6 +985,7 @@ impl DataContext {
                 // Try system tables using the raw name, so system schemas like information_schema.* work
                 if let Some(sys) = crate::system::system_table_df(name, store) {
                     tracing::debug!(target: "clarium::exec", "load_source_df: system table hit name='{}' alias={:?}", name, alias);
+                    crate::storage::versions::note_uncacheable();
                     return Self::prefix_columns(sys, t);
                 }
                 // Resolve to a canonical path for regular tables or KV using the unified resolver
@@ -975,10 +1021,16 @@ impl DataContext {
                         let (schema_map, _locks) = guard.load_schema_with_locks(&effective).unwrap_or_default();
                         let mut cols: Vec<String> = schema_map.keys().cloned().collect();
                         cols.sort();
-                        match guard.filter_df(&effective, &cols, None, None) {
+                        match guard.filter_df_partitioned(&effective, &cols, None, None, &self.chunk_prune_eq, &self.partition_prune_eq) {
                             Ok(df) => Ok(df),
                             Err(e) => Err(e),
                         }
+                    } else if !self.partition_prune_eq.is_empty() && guard.get_partition_spec(&effective).is_partitioned() {
+                        // Partitioned regular tables: skip partition folders excluded by WHERE equalities
+                        let (schema_map, _locks) = guard.load_schema_with_locks(&effective).unwrap_or_default();
+                        let mut cols: Vec<String> = schema_map.keys().cloned().collect();
+                        cols.sort();
+                        guard.filter_df_partitioned(&effective, &cols, None, None, &[], &self.partition_prune_eq)
                     } else {
                         guard.read_df(&effective)
                     };
@@ -1045,6 +1097,7 @@ impl DataContext {
             }
             TableRef::Tvf { call, alias } => {
                 tracing::debug!(target: "clarium::exec", "load_source_df: evaluating TVF call='{}' alias={:?}", call, alias);
+                crate::storage::versions::note_uncacheable();
                 // Try known TVF families
                 // SHOW TVFs first
                 if let Some(df) = crate::server::exec::show::try_show_tvf(store, call)? {
@@ -1061,6 +1114,18 @@ impl DataContext {
                 if let Some(df) = crate::server::exec::exec_array_tvf::try_array_tvf(store, call)? {
                     return Self::prefix_columns_tvf(df, alias.as_deref());
                 }
+                // Change data capture (table_changes)
+                if let Some(df) = crate::server::exec::exec_cdc::try_cdc_tvf(store, call)? {
+                    return Self::prefix_columns_tvf(df, alias.as_deref());
+                }
+                // Time-series analytics (ts_forecast/ts_anomalies)
+                if let Some(df) = crate::server::exec::exec_ts_tvf::try_ts_tvf(store, call)? {
+                    return Self::prefix_columns_tvf(df, alias.as_deref());
+                }
+                // External file TVFs (read_csv/read_parquet/read_json, filestore_read_*, filestore_search)
+                if let Some(df) = crate::server::exec::exec_file_tvf::try_file_tvf(store, call)? {
+                    return Self::prefix_columns_tvf(df, alias.as_deref());
+                }
                 // Try Lua UDF TVFs via registry
                 if let Some(reg) = crate::scripts::get_script_registry() {
                     let reg_snapshot = reg.snapshot().ok();
@@ -1086,6 +1151,7 @@ impl DataContext {
     // Detect and evaluate graph TVFs embedded in FROM:
     // graph_neighbors(graph,start,etype,max_hops[, time_start, time_end])
     // graph_paths(graph,src,dst,max_hops[, etype[, time_start, time_end]])
+    // graph_match(graph,pattern)
     fn try_graph_tvf(store: &crate::storage::SharedStore, raw: &str) -> anyhow::Result<Option<DataFrame>> {
         let s = raw.trim();
         // Robustly extract function name before '('
@@ -1147,6 +1213,15 @@ impl DataContext {
                 return Ok(Some(df));
             }
         }
+        if fname_low == "graph_match" {
+            if let Some(args) = extract_args(s) {
+                // graph, pattern
+                let graph = args.get(0).map(|a| strip_quotes(a)).unwrap_or_default();
+                let pattern = args.get(1).map(|a| strip_quotes(a)).unwrap_or_default();
+                let df = crate::server::exec::exec_graph_runtime::graph_match_df(store, &graph, &pattern)?;
+                return Ok(Some(df));
+            }
+        }
         Ok(None)
     }
 
@@ -0,0 +1,77 @@
+//!
+//! clarium database statistics
+//! ---------------------------
+//! Cumulative per-database counters reported by `pg_catalog.pg_stat_database`.
+//!
+//! - `xact_commit` / `xact_rollback`: statements run through `activity::run_statement`
+//!   that succeeded / failed, counted against the backend's database.
+//! - `tup_returned`: rows produced by top-level SELECTs.
+//! - `blks_read`: parquet chunks read from storage.
+//!
+//! Counters live in memory and start from zero at server start (`stats_reset`).
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+#[derive(Default)]
+struct Counters {
+    xact_commit: AtomicI64,
+    xact_rollback: AtomicI64,
+    tup_returned: AtomicI64,
+    blks_read: AtomicI64,
+}
+
+/// Counter values of one database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbStats {
+    pub xact_commit: i64,
+    pub xact_rollback: i64,
+    pub tup_returned: i64,
+    pub blks_read: i64,
+}
+
+static STATS: Lazy<RwLock<BTreeMap<String, Arc<Counters>>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+static STATS_RESET: Lazy<i64> = Lazy::new(|| chrono::Utc::now().timestamp_millis());
+
+fn counters(db: &str) -> Arc<Counters> {
+    if let Some(c) = STATS.read().get(db) { return c.clone(); }
+    Lazy::force(&STATS_RESET);
+    STATS.write().entry(db.to_string()).or_default().clone()
+}
+
+/// Count a finished statement of a backend connected to `db`.
+pub fn record_statement(db: &str, ok: bool) {
+    let c = counters(db);
+    if ok { c.xact_commit.fetch_add(1, Ordering::Relaxed); } else { c.xact_rollback.fetch_add(1, Ordering::Relaxed); }
+}
+
+/// Count rows returned by a SELECT, against the database of the running backend.
+pub fn count_rows_returned(rows: usize) {
+    let db = crate::server::activity::current_database().unwrap_or_else(crate::system::get_current_database);
+    counters(&db).tup_returned.fetch_add(rows as i64, Ordering::Relaxed);
+}
+
+/// Count parquet chunks read for `table`, attributed to the database it belongs to.
+pub fn count_blocks_read(table: &str, chunks: usize) {
+    if chunks == 0 { return; }
+    let qualified = crate::ident::qualify_regular_ident(&table.replace('\\', "/"), &crate::system::current_query_defaults());
+    let db = qualified.split('/').next().unwrap_or_default();
+    counters(db).blks_read.fetch_add(chunks as i64, Ordering::Relaxed);
+}
+
+/// Counters of `db` (zero when nothing was recorded yet).
+pub fn get(db: &str) -> DbStats {
+    STATS.read().get(db).map(|c| DbStats {
+        xact_commit: c.xact_commit.load(Ordering::Relaxed),
+        xact_rollback: c.xact_rollback.load(Ordering::Relaxed),
+        tup_returned: c.tup_returned.load(Ordering::Relaxed),
+        blks_read: c.blks_read.load(Ordering::Relaxed),
+    }).unwrap_or_default()
+}
+
+/// Epoch ms the counters started from.
+pub fn stats_reset() -> i64 { *STATS_RESET }
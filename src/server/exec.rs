@@ -10,6 +10,8 @@ pub mod where_subquery;
 pub mod exec_helpers; // shared helpers (dataframe conversions, select df)
 pub mod exec_create;  // regular table DDL and CREATE TABLE parser
 pub mod exec_insert;  // INSERT INTO handling
+pub mod exec_copy;    // COPY ... FROM bulk import, COPY ... TO export batches
+pub mod exec_backup;  // BACKUP / RESTORE DATABASE
 pub mod df_utils;     // dataframe helpers (read_df_or_kv, etc.)
 pub mod exec_calculate; // CALCULATE handling
 pub mod exec_keys;      // KV key operations
@@ -17,19 +19,40 @@ pub mod exec_update;    // UPDATE handling
 pub mod exec_delete;    // DELETE COLUMNS handling
 pub mod exec_scripts;   // SCRIPT management (create/drop/rename/load)
 pub mod exec_views;     // VIEW management (create/drop/show)
+pub mod exec_slice_catalog; // Named SLICE plans (CREATE / DROP SLICE, SHOW SLICES)
 pub mod exec_describe;  // DESCRIBE <object> (tables/views)
 pub mod exec_vector_index; // VECTOR INDEX management
 pub mod exec_vector_runtime; // VECTOR ANN runtime (build/search/status)
 pub mod exec_graph;        // GRAPH catalog management
 pub mod exec_graph_runtime; // Graph TVFs runtime (neighbors/paths)
+pub mod exec_graph_dml;     // INSERT INTO GRAPH / DELETE FROM GRAPH
 pub mod exec_alter;        // ALTER TABLE handling
+pub mod exec_triggers;     // CREATE / DROP TRIGGER (Lua table triggers)
+pub mod exec_comment;      // COMMENT ON (tables, columns, views, functions)
+pub mod exec_grant;        // GRANT / REVOKE object privileges
+pub mod exec_role;         // CREATE / DROP ROLE, role membership, SET ROLE
+pub mod exec_jobs;         // CREATE / DROP / ALTER JOB (scheduled jobs)
+pub mod exec_procedures;   // CREATE / DROP PROCEDURE, CALL (stored procedures)
+pub mod exec_script_tests; // CREATE / DROP SCRIPT TEST, RUN TESTS, ASSERT
+pub mod exec_security_policy; // CREATE / ALTER / DROP SECURITY POLICY, EXPLAIN ACCESS (filestore security)
 pub mod vector_utils;      // Shared vector parsing/extraction utilities
+#[cfg(feature = "ann_hnsw")]
+pub mod vector_hnsw;       // HNSW graph build/search and its .hnsw file format
+pub mod vector_ivfpq;      // IVF-PQ quantized lists and their .ivfpq file format
+pub mod vector_delta;      // Rows inserted since a vector index build (.vdelta), merged at search time
 pub mod exec_vector_tvf;   // Vector TVFs (nearest_neighbors, vector_search)
 pub mod exec_array_tvf;    // Array TVFs (unnest)
+pub mod exec_file_tvf;     // External file TVFs (read_csv, read_parquet, read_json, filestore_read_*, filestore_search)
+pub mod exec_cdc;          // CDC changelog TVF (table_changes)
+pub mod exec_ts_tvf;       // Time-series TVFs (ts_forecast, ts_anomalies)
 pub mod filestore;         // FILESTORE implementation (config, paths, security, git backends)
 pub mod df_utils_json;   // JSON -> DataFrame conversion helpers for KV Json
-pub mod explain;         // EXPLAIN data model and renderers (skeleton)
+pub mod explain;         // EXPLAIN data model, plan builder and renderers
+pub mod spill;           // spill-to-disk sort and aggregation for inputs over work_mem
+pub mod scheduler;       // admission control: concurrency limits for analytical statements
+pub mod result_cache;    // opt-in SELECT result cache invalidated by table writes
 pub mod exec_auth_shadow; // Shadow SQL authorization (RBAC/ABAC) — no behavior change
+pub mod exec_authorize;   // Role grants and object privileges for every transport's statements
 pub mod internal;         // Internal executor utilities (constants, helpers)
 
 use anyhow::Result;
@@ -41,23 +64,22 @@ use crate::lua_bc::{LuaBytecodeCache, DEFAULT_DB, DEFAULT_KV_STORE};
 // Bring frequently used helpers from submodules into scope
 use crate::server::exec::exec_select::run_select;
 use crate::server::exec::exec_slice::run_slice;
-use crate::server::exec::where_subquery::{eval_where_mask, where_contains_subquery};
-use crate::server::exec::exec_common::build_where_expr;
 use std::ops::Not;
 use crate::server::exec::exec_helpers::dataframe_to_json;
-use crate::server::exec::df_utils::read_df_or_kv;
 // Re-export common helpers so external callers can keep using crate::server::exec::*
 pub use crate::server::exec::exec_helpers::{execute_select_df, dataframe_to_tabular, normalize_query_with_defaults};
 pub use crate::server::exec::exec_create::do_create_table;
 use crate::identity::RequestContext;
 use once_cell::sync::OnceCell;
 use futures_util::FutureExt; // for catch_unwind on async blocks
+use tracing::Instrument;
+use crate::telemetry::QUERY_SPAN_TARGET;
 use std::panic::AssertUnwindSafe;
 
 use crate::server::query::*;
 use crate::server::exec::filestore as fs;
 use crate::server::exec::filestore::{FilestoreConfig, EffectiveConfig};
-use crate::server::exec::filestore::{AclUser, AclContext, CorrelationId};
+use crate::server::exec::filestore::{ACLAction, AclUser, AclContext, CorrelationId};
 use base64::Engine;
 
 /// Returns true if the provided SQL text is a transaction control statement
@@ -86,30 +108,68 @@ fn ensure_sec_store(store: &SharedStore) {
 }
 
 pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json::Value> {
+    crate::server::storage_hooks::install();
+    // Root of the per-query span tree (exported over OTLP under the `otel` feature)
+    let span = tracing::debug_span!(target: QUERY_SPAN_TARGET, "query",
+        db.statement = %text, otel.status_code = tracing::field::Empty, error = tracing::field::Empty);
+    // Memory governor: admission against the global budget and per-query accounting
+    let out = crate::memory::run_query(execute_statement(store, text)).instrument(span.clone()).await;
+    if let Err(e) = &out {
+        span.record("otel.status_code", "ERROR");
+        span.record("error", tracing::field::display(e));
+    }
+    out
+}
+
+async fn execute_statement(store: &SharedStore, text: &str) -> Result<serde_json::Value> {
     // Accept transaction control statements as no-ops globally so all frontends
     // (HTTP/WS/pgwire) behave consistently even without real transactional storage.
 
     tprintln!("[exec] execute_query");
-    if is_transaction_control(text) {
+    // search_path lookups probe this store for existing objects
+    crate::system::set_store_root(&store.root_path());
+    // Keyword checks ahead of parse() look past leading comments (ORM/IDE statement tags)
+    let uncommented = crate::server::query::strip_sql_comments(text);
+    if is_transaction_control(&uncommented) {
         return Ok(serde_json::json!({"status":"ok"}));
     }
     // Intercept CREATE TABLE with column definitions (contains parentheses) before parsing
     // because Command::CreateTable doesn't carry column info - route to do_create_table instead
 
-    let trimmed = text.trim().strip_suffix(';').unwrap_or(text.trim());
+    let trimmed = uncommented.trim().strip_suffix(';').unwrap_or(uncommented.trim());
     let up = trimmed.to_ascii_uppercase();
     if (up.starts_with("CREATE TABLE") || up.starts_with("CREATE TABLE IF NOT EXISTS")) && trimmed.contains('(') {
         tprintln!("[exec] execute_query CREATE TABLE intercept");
+        crate::server::replication::ensure_writable()?;
         crate::server::exec::exec_create::do_create_table(store, trimmed)?;
         return Ok(serde_json::json!({"status":"ok"}));
     }
     tprintln!("[exec] execute_query parse");
-    let cmd = parse(text)?;
+    let cmd = tracing::debug_span!(target: QUERY_SPAN_TARGET, "parse").in_scope(|| parse(text))
+        .map_err(|e| crate::error::AppError::from_parse_error(&e, text))?;
 
     tprintln!("[exec] execute_query cmd {:?}", cmd);
+    if !crate::server::replication::is_read_only_command(&cmd) {
+        crate::server::replication::ensure_writable()?;
+    }
+    // Opt-in result cache: repeated SELECTs are answered while their tables are unchanged
+    if let Some(key) = result_cache::cache_key(&cmd, text) {
+        return result_cache::cached(store, key, execute_command(store, cmd)).await;
+    }
+    let clears_cache = result_cache::clears_cache(&cmd);
+    let out = execute_command(store, cmd).await;
+    if clears_cache { result_cache::clear(); }
+    out
+}
+
+async fn execute_command(store: &SharedStore, cmd: Command) -> Result<serde_json::Value> {
+    // Analytical statements wait for a scheduler slot, held until the statement finishes
+    let _slot = if scheduler::is_analytical(&cmd) { Some(scheduler::acquire().await?) } else { None };
     match cmd {
         Command::Explain { sql } => {
-            // Minimal EXPLAIN: annotate vector paths (ANN vs EXACT), index used, metric, ef_search, preselect W placeholder
+            // Options: EXPLAIN [ANALYZE] [VERBOSE] [FORMAT TEXT|JSON|YAML|DOT] or a parenthesised list
+            let (opts, sql) = self::explain::parse_explain_options(&sql)?;
+            // Vector paths: annotate ANN vs EXACT, index used, metric, ef_search, preselect W placeholder
             // Try vector TVFs first
             if let Some(exp) = self::exec_vector_tvf::explain_vector_expr(store, &sql) {
                 return Ok(serde_json::json!({"explain": exp}));
@@ -124,6 +184,10 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
                     }
                 }
             }
+            // Staged SELECT plan with estimates (and actuals under ANALYZE)
+            if let Some(out) = self::explain::explain_statement(store, &opts, &sql)? {
+                return Ok(out);
+            }
             // Fallback generic message
             return Ok(serde_json::json!({"explain": "EXPLAIN: not implemented for this statement"}));
         }
@@ -168,73 +232,197 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         // -----------------------------
         // FILESTORE DDL / Mutations / Versioning (thin wrappers)
         Command::CreateFilestoreCmd { filestore, cfg_json } => {
+            let (db, filestore) = filestore_target(&filestore);
             let cfg: FilestoreConfig = match cfg_json {
                 Some(s) if !s.trim().is_empty() => serde_json::from_str(&s).unwrap_or_default(),
                 _ => FilestoreConfig::default(),
             };
-            let entry = fs::create_filestore(store, crate::lua_bc::DEFAULT_DB, &filestore, cfg, None)?;
+            let entry = fs::create_filestore(store, &db, &filestore, cfg, None)?;
             return Ok(serde_json::to_value(entry)?);
         }
         Command::AlterFilestoreCmd { filestore, update_json } => {
+            let (db, filestore) = filestore_target(&filestore);
             // FilestoreConfigUpdate lives in registry module
             let upd: fs::registry::FilestoreConfigUpdate = serde_json::from_str(&update_json)
                 .map_err(|e| anyhow::anyhow!(format!("Invalid ALTER FILESTORE payload: {}", e)))?;
-            let res = fs::alter_filestore_ddl(store, crate::lua_bc::DEFAULT_DB, &filestore, upd, None)?;
+            let res = fs::alter_filestore_ddl(store, &db, &filestore, upd, None)?;
             return Ok(serde_json::to_value(res)?);
         }
         Command::DropFilestoreCmd { filestore, force } => {
-            let ok = fs::drop_filestore(store, crate::lua_bc::DEFAULT_DB, &filestore, force, None)?;
+            let (db, filestore) = filestore_target(&filestore);
+            let ok = fs::drop_filestore(store, &db, &filestore, force, None)?;
             return Ok(serde_json::json!({"status":"ok","dropped": ok}));
         }
         Command::IngestFileFromBytesCmd { filestore, logical_path, payload, content_type } => {
+            let (db, filestore) = filestore_target(&filestore);
             let bytes = decode_payload(&payload)?;
-            let eff = effective_for(store, &filestore)?;
-            let user = AclUser { id: "anonymous".into(), roles: vec![], ip: None };
-            let ctx = make_acl_ctx(store, &filestore);
-            let meta = fs::ingest_from_bytes(store, crate::lua_bc::DEFAULT_DB, &filestore, &logical_path, &bytes, content_type.as_deref(), None, &user, &eff, &ctx).await?;
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let meta = fs::ingest_from_bytes(store, &db, &filestore, &logical_path, &bytes, content_type.as_deref(), None, &user, &eff, &ctx).await?;
             return Ok(serde_json::to_value(meta)?);
         }
         Command::IngestFileFromHostPathCmd { filestore, logical_path, host_path, content_type } => {
-            let eff = effective_for(store, &filestore)?;
-            let user = AclUser { id: "anonymous".into(), roles: vec![], ip: None };
-            let ctx = make_acl_ctx(store, &filestore);
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
             // Allowlist not yet modeled; pass empty to require explicit config in future
-            let meta = fs::ingest_from_host_path(store, crate::lua_bc::DEFAULT_DB, &filestore, &logical_path, &host_path, "", content_type.as_deref(), &user, &eff, &ctx).await?;
+            let meta = fs::ingest_from_host_path(store, &db, &filestore, &logical_path, &host_path, "", content_type.as_deref(), &user, &eff, &ctx).await?;
             return Ok(serde_json::to_value(meta)?);
         }
         Command::UpdateFileFromBytesCmd { filestore, logical_path, if_match, payload, content_type } => {
+            let (db, filestore) = filestore_target(&filestore);
             let bytes = decode_payload(&payload)?;
-            let eff = effective_for(store, &filestore)?;
-            let user = AclUser { id: "anonymous".into(), roles: vec![], ip: None };
-            let ctx = make_acl_ctx(store, &filestore);
-            let meta = fs::update_from_bytes(store, crate::lua_bc::DEFAULT_DB, &filestore, &logical_path, &if_match, &bytes, content_type.as_deref(), None, &user, &eff, &ctx).await?;
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let meta = fs::update_from_bytes(store, &db, &filestore, &logical_path, &if_match, &bytes, content_type.as_deref(), None, &user, &eff, &ctx).await?;
             return Ok(serde_json::to_value(meta)?);
         }
         Command::RenameFilePathCmd { filestore, from, to } => {
-            let eff = effective_for(store, &filestore)?;
-            let user = AclUser { id: "anonymous".into(), roles: vec![], ip: None };
-            let ctx = make_acl_ctx(store, &filestore);
-            let meta = fs::rename_file(store, crate::lua_bc::DEFAULT_DB, &filestore, &from, &to, &user, &eff, &ctx).await?;
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let meta = fs::rename_file(store, &db, &filestore, &from, &to, &user, &eff, &ctx).await?;
             return Ok(serde_json::to_value(meta)?);
         }
         Command::DeleteFilePathCmd { filestore, logical_path } => {
-            let eff = effective_for(store, &filestore)?;
-            let user = AclUser { id: "anonymous".into(), roles: vec![], ip: None };
-            let ctx = make_acl_ctx(store, &filestore);
-            fs::delete_file(store, crate::lua_bc::DEFAULT_DB, &filestore, &logical_path, &user, &eff, &ctx).await?;
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            fs::delete_file(store, &db, &filestore, &logical_path, &user, &eff, &ctx).await?;
             return Ok(serde_json::json!({"status":"ok"}));
         }
+        Command::SetFileMetadataCmd { filestore, logical_path, changes } => {
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let meta = fs::set_file_metadata(store, &db, &filestore, &logical_path, &changes, &user, &eff, &ctx).await?;
+            return Ok(serde_json::to_value(meta)?);
+        }
+        Command::SetLegalHoldCmd { filestore, logical_path, reason } => {
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let hold = fs::set_legal_hold(store, &db, &filestore, &logical_path, reason.as_deref(), &user, &eff, &ctx).await?;
+            return Ok(serde_json::to_value(hold)?);
+        }
+        Command::ClearLegalHoldCmd { filestore, logical_path } => {
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let cleared = fs::clear_legal_hold(store, &db, &filestore, &logical_path, &user, &eff, &ctx).await?;
+            return Ok(serde_json::json!({"status":"ok","cleared":cleared}));
+        }
+        Command::LockFileCmd { filestore, logical_path, ttl_seconds, note } => {
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let lock = fs::lock_file(store, &db, &filestore, &logical_path, ttl_seconds, note.as_deref(), &user, &eff, &ctx).await?;
+            return Ok(serde_json::to_value(lock)?);
+        }
+        Command::UnlockFileCmd { filestore, logical_path, force } => {
+            let (db, filestore) = filestore_target(&filestore);
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let released = fs::unlock_file(store, &db, &filestore, &logical_path, force, &user, &eff, &ctx).await?;
+            return Ok(serde_json::json!({"status":"ok","released":released}));
+        }
+        Command::RotateFilestoreKeyCmd { filestore, to_key } => {
+            let (db, filestore) = filestore_target(&filestore);
+            if fs::load_filestore_entry(store, &db, &filestore)?.is_none() { anyhow::bail!("filestore not found: {}.{}", db, filestore); }
+            let eff = effective_for(store, &db, &filestore)?;
+            // Pick up master keys added since startup
+            crate::storage::encryption::reload_keys()?;
+            let out = fs::rotate_key(store, &db, &filestore, to_key.as_deref(), &eff)?;
+            return Ok(serde_json::to_value(out)?);
+        }
         Command::CreateTreeCmd { filestore, prefix } => {
-            let tree = fs::create_tree_from_prefix(store, crate::lua_bc::DEFAULT_DB, &filestore, prefix.as_deref())?;
+            let (db, filestore) = filestore_target(&filestore);
+            authorize_filestore(store, &db, &filestore, ACLAction::List, prefix.as_deref().unwrap_or("")).await?;
+            let tree = fs::create_tree_from_prefix(store, &db, &filestore, prefix.as_deref())?;
             return Ok(serde_json::to_value(tree)?);
         }
         Command::CommitTreeCmd { filestore, tree_id, parents, branch, author_name, author_email, message, tags } => {
+            let (db, filestore) = filestore_target(&filestore);
+            authorize_filestore(store, &db, &filestore, ACLAction::Commit, "").await?;
             let author = fs::types::CommitAuthor { name: author_name.unwrap_or_else(|| "system".into()), email: author_email.unwrap_or_else(|| "system@local".into()), time_unix: chrono::Utc::now().timestamp() };
-            let default_branch = effective_for(store, &filestore)?.git_branch.unwrap_or_else(|| "main".into());
-            let br = branch.unwrap_or(default_branch);
-            let commit = fs::commit_tree(store, crate::lua_bc::DEFAULT_DB, &filestore, &tree_id, &parents, &author, message.as_deref().unwrap_or(""), &tags, &br)?;
+            let eff = effective_for(store, &db, &filestore)?;
+            let br = branch.unwrap_or_else(|| fs::current_branch(store, &db, &filestore, &eff));
+            let commit = fs::commit_tree(store, &db, &filestore, &tree_id, &parents, &author, message.as_deref().unwrap_or(""), &tags, &br)?;
             return Ok(serde_json::to_value(commit)?);
         }
+        Command::SyncFilestoreCmd { filestore, direction, branch, force, policy, correlation_id } => {
+            let (db, filestore) = filestore_target(&filestore);
+            if fs::load_filestore_entry(store, &db, &filestore)?.is_none() { anyhow::bail!("filestore not found: {}.{}", db, filestore); }
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let policy = policy.as_deref().map(fs::ConflictPolicy::parse).transpose()?;
+            let opts = fs::SyncOptions { branch, force, policy, correlation_id };
+            let run = if direction == "pull" {
+                fs::sync_pull(store, &db, &filestore, &opts, &user, &eff, &ctx).await?
+            } else {
+                fs::sync_push(store, &db, &filestore, &opts, &user, &eff, &ctx).await?
+            };
+            return Ok(serde_json::to_value(run)?);
+        }
+        Command::CreateBranchCmd { filestore, branch, from } => {
+            let (db, filestore) = filestore_target(&filestore);
+            if fs::load_filestore_entry(store, &db, &filestore)?.is_none() { anyhow::bail!("filestore not found: {}.{}", db, filestore); }
+            authorize_filestore(store, &db, &filestore, ACLAction::Commit, "").await?;
+            let eff = effective_for(store, &db, &filestore)?;
+            let author = fs::types::CommitAuthor { name: "system".into(), email: "system@local".into(), time_unix: chrono::Utc::now().timestamp() };
+            let info = fs::create_branch(store, &db, &filestore, &branch, from.as_deref(), &author, &eff)?;
+            return Ok(serde_json::to_value(info)?);
+        }
+        Command::CheckoutBranchCmd { filestore, branch } => {
+            let (db, filestore) = filestore_target(&filestore);
+            if fs::load_filestore_entry(store, &db, &filestore)?.is_none() { anyhow::bail!("filestore not found: {}.{}", db, filestore); }
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let author = fs::types::CommitAuthor { name: "system".into(), email: "system@local".into(), time_unix: chrono::Utc::now().timestamp() };
+            let out = fs::checkout_branch(store, &db, &filestore, &branch, &author, &user, &eff, &ctx).await?;
+            return Ok(serde_json::to_value(out)?);
+        }
+        Command::MergeBranchCmd { filestore, source, message, author_name, author_email } => {
+            let (db, filestore) = filestore_target(&filestore);
+            if fs::load_filestore_entry(store, &db, &filestore)?.is_none() { anyhow::bail!("filestore not found: {}.{}", db, filestore); }
+            authorize_filestore(store, &db, &filestore, ACLAction::Commit, "").await?;
+            let eff = effective_for(store, &db, &filestore)?;
+            let user = sql_acl_user();
+            let ctx = make_acl_ctx(store, &db, &filestore);
+            let author = fs::types::CommitAuthor { name: author_name.unwrap_or_else(|| "system".into()), email: author_email.unwrap_or_else(|| "system@local".into()), time_unix: chrono::Utc::now().timestamp() };
+            let out = fs::merge_branch(store, &db, &filestore, &source, &author, message.as_deref(), &user, &eff, &ctx).await?;
+            let mut v = serde_json::to_value(&out)?;
+            if out.status == "conflict" {
+                // Both sides of each conflicting path, as SHOW DIFF rows (left = target branch)
+                let df = fs::show_merge_conflicts_df(store, &db, &filestore, &out)?;
+                v["conflict_diff"] = dataframe_to_json(&df);
+            }
+            return Ok(v);
+        }
+        Command::CreateSecurityPolicyCmd { policy_id, role, effect, actions, selector, priority } => {
+            self::exec_security_policy::handle_create_policy(store, &policy_id, &role, &effect, &actions, &selector, priority).await
+        }
+        Command::AlterSecurityPolicyCmd { policy_id, role, effect, actions, selector, priority } => {
+            self::exec_security_policy::handle_alter_policy(store, &policy_id, role.as_deref(), effect.as_deref(), actions.as_deref(), selector.as_deref(), priority).await
+        }
+        Command::DropSecurityPolicyCmd { policy_id, if_exists } => {
+            self::exec_security_policy::handle_drop_policy(store, &policy_id, if_exists).await
+        }
+        Command::ExplainAccessCmd { filestore, action, logical_path, user } => {
+            self::exec_security_policy::handle_explain_access(store, &filestore, &action, logical_path.as_deref(), user.as_deref()).await
+        }
         Command::Slice(plan) => {
             // Create DataContext with registry snapshot for SLICE query
             let registry_snapshot = crate::scripts::get_script_registry()
@@ -253,25 +441,20 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         Command::AlterTable { table, ops } => {
             self::exec_alter::handle_alter_table(store, &table, &ops)
         }
+        Command::CreateTrigger { table, trigger } => {
+            self::exec_triggers::handle_create_trigger(store, &table, trigger)
+        }
+        Command::DropTrigger { name, table, if_exists } => {
+            self::exec_triggers::handle_drop_trigger(store, &table, &name, if_exists)
+        }
         // SHOW commands (global)
-        Command::ShowTransactionIsolation
-        | Command::ShowStandardConformingStrings
-        | Command::ShowServerVersion
-        | Command::ShowClientEncoding
-        | Command::ShowServerEncoding
-        | Command::ShowDateStyle
-        | Command::ShowIntegerDateTimes
-        | Command::ShowTimeZone
-        | Command::ShowSearchPath
-        | Command::ShowDefaultTransactionIsolation
-        | Command::ShowTransactionReadOnly
-        | Command::ShowApplicationName
-        | Command::ShowExtraFloatDigits
+        Command::ShowVariable { .. }
         | Command::ShowAll
         | Command::ShowSchemas
         | Command::ShowTables
         | Command::ShowObjects
         | Command::ShowScripts
+        | Command::ShowCacheStats
         // FILESTORE SHOW variants
         | Command::ShowFilestores { .. }
         | Command::ShowFilestoreConfig { .. }
@@ -283,6 +466,14 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         | Command::ShowAliasesInFilestore { .. }
         | Command::ShowAdminInFilestore { .. }
         | Command::ShowHealthInFilestore { .. }
+        | Command::ShowSyncInFilestore { .. }
+        | Command::ShowBranchesInFilestore { .. }
+        | Command::ShowLegalHoldsInFilestore { .. }
+        | Command::ShowLocksInFilestore { .. }
+        | Command::ShowWebhooksInFilestore { .. }
+        | Command::ShowEncryptionInFilestore { .. }
+        | Command::ShowFilestoreGc { .. }
+        | Command::ShowVersionsInFilestore { .. }
         | Command::ShowGraphStatus { .. } => {
             self::exec_show::execute_show(store, cmd).await
         }
@@ -297,10 +488,17 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         | Command::ShowVectorIndexes
         | Command::BuildVectorIndex { .. }
         | Command::ReindexVectorIndex { .. }
+        | Command::RebuildVectorIndex { .. }
         | Command::ShowVectorIndexStatus { .. }
         | Command::AlterVectorIndexSetMode { .. } => {
             self::exec_vector_index::execute_vector_index(store, cmd)
         }
+        // Named slice plans
+        Command::CreateSlice { .. }
+        | Command::DropSlice { .. }
+        | Command::ShowSlices => {
+            self::exec_slice_catalog::execute_slice_catalog(store, cmd)
+        }
         // Graph catalogs
         Command::CreateGraph { .. }
         | Command::DropGraph { .. }
@@ -308,6 +506,10 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         | Command::ShowGraphs => {
             self::exec_graph::execute_graph(store, cmd)
         }
+        // Graph node/edge writes through the catalog
+        Command::InsertGraph { .. } | Command::DeleteGraph { .. } => {
+            self::exec_graph_dml::execute_graph_dml(store, cmd)
+        }
         // Graph GC command
         Command::GcGraph { name } => {
             // Determine target: explicit name > session default > all graphs
@@ -325,6 +527,13 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
                 Ok(serde_json::json!({"status":"ok","scope":"all"}))
             }
         }
+        Command::GcFilestore { filestore } => {
+            let (db, filestore) = filestore_target(&filestore);
+            if fs::load_filestore_entry(store, &db, &filestore)?.is_none() { anyhow::bail!("filestore not found: {}.{}", db, filestore); }
+            authorize_filestore(store, &db, &filestore, ACLAction::Delete, "").await?;
+            let run = fs::run_gc(store, &db, &filestore, "manual")?;
+            Ok(serde_json::to_value(run)?)
+        }
         // MATCH rewrite execution
         Command::MatchRewrite { sql } => {
             // Replace session default placeholder if present
@@ -394,6 +603,7 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
                 let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
                 tx.commit(now)?;
                 crate::system::clear_graph_txn();
+                crate::server::guc::end_transaction(store);
                 Ok(serde_json::json!({"status":"ok","graph": ctx.graph}))
             } else {
                 anyhow::bail!("no active graph transaction");
@@ -403,6 +613,7 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             if let Some(tx) = crate::system::take_graph_txn() {
                 let _ = tx.abort();
                 crate::system::clear_graph_txn();
+                crate::server::guc::end_transaction(store);
                 Ok(serde_json::json!({"status":"ok"}))
             } else {
                 anyhow::bail!("no active graph transaction");
@@ -441,17 +652,24 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             Ok(serde_json::json!([{ "graph": g.unwrap_or_default() }]))
         }
         Command::UseSchema { name } => {
+            // USE SCHEMA s is SET search_path = s: the schema becomes the whole search path
             if name.eq_ignore_ascii_case("none") {
+                crate::server::guc::reset(store, Some("search_path"))?;
                 crate::system::unset_current_schema();
             } else {
-                crate::system::set_current_schema(&name);
+                let plain = name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+                let value = if plain { name } else { format!("\"{}\"", name) };
+                crate::server::guc::set(store, "search_path", &value, false)?;
             }
             Ok(serde_json::json!({"status":"ok"}))
         }
-        Command::Set { variable, value } => {
-            // Apply known vector/search settings; ignore unknowns for forward compatibility
-            let mut applied = false;
+        Command::Set { variable, value, local } => {
+            // Registered parameters are validated and layered by the GUC registry; vector,
+            // planner and projection knobs apply directly; unknowns are ignored for forward compatibility
+            if variable.eq_ignore_ascii_case("role") { self::exec_role::check_set_role(store, &value)?; }
+            let mut applied = crate::server::guc::set(store, &variable, &value, local)?;
             if crate::system::apply_vector_setting(&variable, &value) { applied = true; }
+            if crate::system::apply_planner_setting(&variable, &value) { applied = true; }
             // Allow toggling strict projection via SET strict.projection = on|off
             let vlow = variable.to_ascii_lowercase();
             if vlow == "strict.projection" || vlow == "projection.strict" {
@@ -462,6 +680,14 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             let status = if applied { "ok" } else { "ignored" };
             Ok(serde_json::json!({"status": status}))
         }
+        Command::Reset { variable } => {
+            crate::server::guc::reset(store, variable.as_deref())?;
+            Ok(serde_json::json!({"status": "ok"}))
+        }
+        Command::AlterDatabaseSet { database, variable, value } => {
+            crate::server::guc::set_database_default(store, &database, variable.as_deref(), value.as_deref())?;
+            Ok(serde_json::json!({"status": "ok"}))
+        }
         Command::Insert { table, columns, values } => {
             crate::server::exec::exec_insert::handle_insert(store, table, columns, values)
         }
@@ -470,6 +696,92 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             let (df, _into) = crate::server::exec::exec_select::handle_select(store, &query)?;
             crate::server::exec::exec_insert::handle_insert_from_df(store, table, columns, df)
         }
+        Command::CopyFrom { table, columns, source, options } => {
+            self::exec_copy::handle_copy_from(store, &table, columns, source, options).await
+        }
+        // Rows are streamed by the pgwire CopyOut sub-protocol; there is no JSON form
+        Command::CopyTo { .. } => {
+            anyhow::bail!("COPY TO STDOUT is only available over the PostgreSQL wire protocol")
+        }
+        Command::BackupDatabase { database, target } => {
+            self::exec_backup::handle_backup(store, &database, &target).await
+        }
+        Command::RestoreDatabase { database, source, as_of } => {
+            self::exec_backup::handle_restore(store, &database, &source, as_of).await
+        }
+        Command::VerifyTable { table, quarantine } => {
+            let reports = store.0.lock().verify_table(&table, quarantine)?;
+            Ok(serde_json::to_value(&reports)?)
+        }
+        Command::CompactTable { table } => {
+            let report = store.0.lock().compact_table(&table)?;
+            let mut out = serde_json::to_value(&report)?;
+            // Fold vector index deltas into their builds now that the chunks were rewritten
+            let rebuilt = self::exec_vector_index::rebuild_indexes_with_delta(store, &table)?;
+            if !rebuilt.is_empty() { out["vector_indexes_rebuilt"] = serde_json::json!(rebuilt); }
+            Ok(out)
+        }
+        Command::Analyze { table } => {
+            let tables = match table {
+                Some(t) => vec![t],
+                None => {
+                    let db = crate::system::current_query_defaults().current_database;
+                    crate::system_catalog::shared::enumerate_tables(store).into_iter()
+                        .filter(|m| m.db == db)
+                        .map(|m| format!("{}/{}/{}", m.db, m.schema, m.table))
+                        .collect()
+                }
+            };
+            let mut out = Vec::with_capacity(tables.len());
+            for t in tables {
+                let stats = store.0.lock().analyze_table(&t)?;
+                out.push(serde_json::json!({"table": t, "rows": stats.rows, "sampled_rows": stats.sampled_rows, "columns": stats.columns.len()}));
+            }
+            Ok(serde_json::Value::Array(out))
+        }
+        Command::Kill { pid, query_only } => {
+            let ok = if query_only { crate::server::activity::cancel_backend(pid) } else { crate::server::activity::terminate_backend(pid) };
+            if !ok { anyhow::bail!("No backend with pid {}", pid); }
+            Ok(serde_json::json!({"status": "ok", "pid": pid, "action": if query_only { "cancel" } else { "terminate" }}))
+        }
+        Command::KillSession { session_id } => {
+            let by = crate::server::activity::current_user();
+            if !crate::identity::SessionManager::default().revoke_session(&session_id, by.as_deref()) {
+                return Err(crate::error::AppError::NotFound { code: "undefined_object".into(), message: format!("session \"{}\" does not exist", session_id) }.into());
+            }
+            Ok(serde_json::json!({"status": "ok", "session_id": session_id}))
+        }
+        Command::KillUserSessions { user } => {
+            let by = crate::server::activity::current_user();
+            let count = crate::identity::SessionManager::default().revoke_user(&user, by.as_deref());
+            Ok(serde_json::json!({"status": "ok", "user": user, "sessions": count}))
+        }
+        Command::ReloadConfig => {
+            let report = crate::config::reload()?;
+            Ok(serde_json::json!({"status": "ok", "file": report.file, "applied": report.applied, "pending_restart": report.pending_restart}))
+        }
+        Command::ReencryptTable { table } => {
+            // Pick up keys added or rotated since startup
+            crate::storage::encryption::reload_keys()?;
+            let chunks = store.0.lock().reencrypt_table(&table)?;
+            Ok(serde_json::json!({"status": "ok", "table": table, "chunks": chunks}))
+        }
+        Command::AttachDatabase { path, name } => {
+            // Held so no table write can resolve the name while the mapping changes
+            let guard = store.0.lock();
+            crate::storage::attach::attach(guard.root_path(), &name, std::path::Path::new(&path))?;
+            drop(guard);
+            store.kv_registry().reload_database(&name);
+            Ok(serde_json::json!({"status": "ok", "database": name, "path": path}))
+        }
+        Command::DetachDatabase { name } => {
+            store.kv_registry().snapshot_database(&name)?;
+            let guard = store.0.lock();
+            let path = crate::storage::attach::detach(guard.root_path(), &name)?;
+            drop(guard);
+            store.kv_registry().reload_database(&name);
+            Ok(serde_json::json!({"status": "ok", "database": name, "path": path.display().to_string()}))
+        }
         // Script management
         Command::CreateScript { .. }
         | Command::DropScript { .. }
@@ -477,6 +789,57 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         | Command::LoadScript { .. } => {
             self::exec_scripts::execute_scripts(store, cmd)
         }
+        Command::CommentOn { object, comment } => {
+            self::exec_comment::handle_comment_on(store, &object, comment.as_deref())
+        }
+        Command::Grant { privileges, object, grantees } => {
+            self::exec_grant::handle_grant(store, &privileges, &object, &grantees, false)
+        }
+        Command::Revoke { privileges, object, grantees } => {
+            self::exec_grant::handle_grant(store, &privileges, &object, &grantees, true)
+        }
+        Command::CreateRole { name, superuser, inherit } => {
+            self::exec_role::handle_create_role(store, &name, superuser, inherit)
+        }
+        Command::DropRole { name, if_exists } => {
+            self::exec_role::handle_drop_role(store, &name, if_exists)
+        }
+        Command::GrantRole { roles, members, admin_option } => {
+            self::exec_role::handle_grant_role(store, &roles, &members, admin_option, false)
+        }
+        Command::RevokeRole { roles, members } => {
+            self::exec_role::handle_grant_role(store, &roles, &members, false, true)
+        }
+        Command::CreateJob { name, schedule, body } => {
+            self::exec_jobs::handle_create_job(store, &name, &schedule, body)
+        }
+        Command::DropJob { name, if_exists } => {
+            self::exec_jobs::handle_drop_job(store, &name, if_exists)
+        }
+        Command::AlterJob { name, enabled } => {
+            self::exec_jobs::handle_alter_job(store, &name, enabled)
+        }
+        Command::CreateProcedure { name, params, body, or_replace } => {
+            self::exec_procedures::handle_create_procedure(store, &name, params, &body, or_replace)
+        }
+        Command::DropProcedure { name, if_exists } => {
+            self::exec_procedures::handle_drop_procedure(store, &name, if_exists)
+        }
+        Command::Call { name, args } => {
+            self::exec_procedures::handle_call(store, &name, &args).await
+        }
+        Command::CreateScriptTest { path, language, code } => {
+            self::exec_script_tests::handle_create_script_test(store, &path, language, &code)
+        }
+        Command::DropScriptTest { path, if_exists } => {
+            self::exec_script_tests::handle_drop_script_test(store, &path, if_exists)
+        }
+        Command::RunTests { target } => {
+            self::exec_script_tests::handle_run_tests(store, target.as_deref()).await
+        }
+        Command::Assert { condition, message } => {
+            self::exec_script_tests::handle_assert(store, &condition, message.as_deref()).await
+        }
         // View management
         Command::CreateView { .. }
         | Command::DropView { .. }
@@ -488,8 +851,17 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             if let Some((dest, mode)) = into {
                 let dest = dest.trim();
                 let guard = store.0.lock();
+                let created = !guard.db_dir(dest).exists();
                 guard.create_table(dest).ok();
+                // INTO <table> (PRIMARY KEY(..), PARTITION BY(..)) only shapes a destination created here
+                if let Some(opts) = q.into_options.as_ref() {
+                    if created && (opts.primary_key.is_some() || opts.partitions.is_some()) {
+                        guard.set_table_metadata(dest, opts.primary_key.clone(), opts.partitions.clone())?;
+                    }
+                    if opts.time_table && !guard.is_time_table(dest) { anyhow::bail!("INTO {} (TIME TABLE): destination exists as a regular table", dest); }
+                }
                 if guard.is_time_table(dest) {
+                    if mode == IntoMode::Merge { anyhow::bail!("INTO ... MERGE is not supported for time tables; their rows are keyed by _time"); }
                     // Expect exactly one _time column and ensure uniqueness
                     let time_cols = df.get_column_names().into_iter().filter(|n| n.as_str() == "_time").count();
                     if time_cols != 1 { anyhow::bail!("INTO time table requires exactly one _time column in the projection"); }
@@ -524,12 +896,25 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
                     }
                     guard.write_records(dest, &records)?;
                 } else {
+                    let pk = guard.get_primary_key(dest).filter(|k| !k.is_empty());
                     match mode {
-                        IntoMode::Replace => { guard.rewrite_table_df(dest, df.clone())?; }
+                        IntoMode::Replace => {
+                            if let Some(pk) = &pk { crate::server::exec::exec_select::ensure_unique_primary_key(&df, pk)?; }
+                            guard.rewrite_table_df(dest, df.clone())?;
+                        }
                         IntoMode::Append => {
                             let combined = match guard.read_df(dest) { Ok(existing) => { existing.vstack(&df)? } Err(_) => df.clone(), };
+                            if let Some(pk) = &pk { crate::server::exec::exec_select::ensure_unique_primary_key(&combined, pk)?; }
                             guard.rewrite_table_df(dest, combined)?;
                         }
+                        IntoMode::Merge => {
+                            let pk = pk.ok_or_else(|| anyhow::anyhow!("INTO {} MERGE requires a PRIMARY KEY on the destination", dest))?;
+                            let merged = match guard.read_df(dest) {
+                                Ok(existing) => crate::server::exec::exec_select::merge_by_primary_key(&existing, &df, &pk)?,
+                                Err(_) => { crate::server::exec::exec_select::ensure_unique_primary_key(&df, &pk)?; df.clone() }
+                            };
+                            guard.rewrite_table_df(dest, merged)?;
+                        }
                     }
                 }
             }
@@ -600,6 +985,10 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
                             "len": b.len()
                         }))
                     }
+                    v @ (KvValue::List(_) | KvValue::Set(_) | KvValue::Hash(_)) => {
+                        let len = match &v { KvValue::List(l) => l.len(), KvValue::Set(m) => m.len(), KvValue::Hash(h) => h.len(), _ => 0 };
+                        Ok(serde_json::json!({"key": key, "type": v.type_name(), "len": len}))
+                    }
                 }
             } else {
                 anyhow::bail!(format!("Key not found: {}.store.{}.{}", database, st, key));
@@ -617,37 +1006,21 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         Command::RenameKey { database, store: st, from, to } => {
             crate::server::exec::exec_keys::handle_rename_key(store, &database, &st, &from, &to)
         }
+        Command::KeyOp { database, store: st, key, op } => {
+            crate::server::exec::exec_keys::handle_key_op(store, &database, &st, &key, &op)
+        }
+        Command::KvBatch { database, store: st, expect, writes } => {
+            crate::server::exec::exec_keys::handle_kv_batch(store, &database, &st, &expect, &writes)
+        }
+        Command::Watch { .. } | Command::Unwatch { .. } => {
+            // Watches deliver to a connection; the WebSocket handler runs them (`exec_keys::start_watch`)
+            anyhow::bail!("watch_requires_websocket: WATCH and UNWATCH are not supported outside the WebSocket endpoint")
+        }
         Command::DeleteRows { database, where_clause } => {
-            // Load full dataframe
-            let df_all = read_df_or_kv(store, &database)?;
-            // If no WHERE, truncate database
-            let new_df = if let Some(w) = &where_clause {
-                // Create DataContext with registry snapshot for WHERE clause evaluation
-                let registry_snapshot = crate::scripts::get_script_registry()
-                    .and_then(|r| r.snapshot().ok());
-                let mut ctx = crate::server::data_context::DataContext::with_defaults("clarium", "public");
-                if let Some(reg) = registry_snapshot {
-                    ctx.script_registry = Some(reg);
-                }
-                // Build mask series (with subquery support)
-                let mask = if where_contains_subquery(w) {
-                    eval_where_mask(&df_all, &ctx, store, w)?
-                } else {
-                    let mask_df = df_all.clone().lazy().select([build_where_expr(w, &ctx).alias("__m__")]).collect()?;
-                    mask_df.column("__m__")?.bool()?.clone()
-                };
-                let keep = mask.not();
-                df_all.filter(&keep)?
-            } else {
-                // Empty df with only _time column
-                DataFrame::new(vec![Series::new("_time".into(), Vec::<i64>::new()).into()])?
-            };
-            let guard = store.0.lock();
-            guard.rewrite_table_df(&database, new_df)?;
-            Ok(serde_json::json!({"status": "ok"}))
+            crate::server::exec::exec_delete::handle_delete_rows(store, database, where_clause)
         }
-        Command::Update { table, assignments, where_clause } => {
-            crate::server::exec::exec_update::handle_update(store, table, assignments, where_clause)
+        Command::Update { table, assignments, from, where_clause } => {
+            crate::server::exec::exec_update::handle_update(store, table, assignments, from, where_clause)
         }
         Command::DeleteColumns { database, columns, where_clause } => {
             crate::server::exec::exec_delete::handle_delete_columns(store, database, columns, where_clause)
@@ -671,7 +1044,7 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             };
             // Build filesystem path: <root>/<db>/<schema>
             let root = store.0.lock().root_path().clone();
-            let schema_dir = root.join(db.clone()).join(schema.clone());
+            let schema_dir = crate::storage::attach::database_dir(&root, &db).join(schema.clone());
             crate::tprintln!(
                 "[SCHEMA_SHOW] resolved db='{}' schema='{}' path='{}' exists={}",
                 db,
@@ -764,7 +1137,7 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         // New DDL commands
         Command::CreateDatabase { name, if_not_exists } => {
             use std::fs;
-            let dir = store.root_path().join(name.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+            let dir = crate::ident::to_local_path(&store.root_path(), &name);
             if dir.exists() {
                 if if_not_exists { return Ok(serde_json::json!({"status":"ok"})); }
                 anyhow::bail!(format!("Database already exists: {}", name));
@@ -774,14 +1147,20 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         }
         Command::DropDatabase { name } => {
             use std::fs;
-            let dir = store.root_path().join(name.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+            if crate::storage::attach::attached_dir(&store.root_path(), &name).is_some() {
+                anyhow::bail!("Database '{}' is attached; DETACH it instead of dropping", name);
+            }
+            let dir = crate::ident::to_local_path(&store.root_path(), &name);
             if dir.exists() { let _ = fs::remove_dir_all(&dir); }
             Ok(serde_json::json!({"status":"ok"}))
         }
         Command::RenameDatabase { from, to } => {
             use std::fs;
-            let src = store.root_path().join(from.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
-            let dst = store.root_path().join(to.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+            if crate::storage::attach::attached_dir(&store.root_path(), &from).is_some() {
+                anyhow::bail!("Database '{}' is attached; DETACH it and ATTACH it under the new name", from);
+            }
+            let src = crate::ident::to_local_path(&store.root_path(), &from);
+            let dst = crate::ident::to_local_path(&store.root_path(), &to);
             if !src.exists() { anyhow::bail!("Source database not found: {}", from); }
             // Ensure parent of dst exists
             if let Some(parent) = dst.parent() { fs::create_dir_all(parent).ok(); }
@@ -790,9 +1169,9 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         }
         Command::CreateSchema { path, if_not_exists } => {
             use std::fs;
-            // If unqualified (no '/'), prepend current database
-            let full = if path.contains('/') || path.contains('\\') { path.clone() } else { format!("{}/{}", crate::system::get_current_database(), path) };
-            let dir = store.root_path().join(full.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+            // db.schema, db/schema or schema in the current database
+            let full = crate::ident::qualify_schema_ident(&path, &crate::system::current_query_defaults());
+            let dir = crate::ident::to_local_path(&store.root_path(), &full);
             if dir.exists() {
                 if if_not_exists { return Ok(serde_json::json!({"status":"ok"})); }
                 anyhow::bail!(format!("Schema already exists: {}", full));
@@ -802,17 +1181,18 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
         }
         Command::DropSchema { path } => {
             use std::fs;
-            let full = if path.contains('/') || path.contains('\\') { path.clone() } else { format!("{}/{}", crate::system::get_current_database(), path) };
-            let dir = store.root_path().join(full.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+            let full = crate::ident::qualify_schema_ident(&path, &crate::system::current_query_defaults());
+            let dir = crate::ident::to_local_path(&store.root_path(), &full);
             if dir.exists() { let _ = fs::remove_dir_all(&dir); }
             Ok(serde_json::json!({"status":"ok"}))
         }
         Command::RenameSchema { from, to } => {
             use std::fs;
-            let from_full = if from.contains('/') || from.contains('\\') { from.clone() } else { format!("{}/{}", crate::system::get_current_database(), from) };
-            let to_full = if to.contains('/') || to.contains('\\') { to.clone() } else { format!("{}/{}", crate::system::get_current_database(), to) };
-            let src = store.root_path().join(from_full.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
-            let dst = store.root_path().join(to_full.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+            let d = crate::system::current_query_defaults();
+            let from_full = crate::ident::qualify_schema_ident(&from, &d);
+            let to_full = crate::ident::qualify_schema_ident(&to, &d);
+            let src = crate::ident::to_local_path(&store.root_path(), &from_full);
+            let dst = crate::ident::to_local_path(&store.root_path(), &to_full);
             if !src.exists() { anyhow::bail!("Source schema not found: {}", from); }
             if let Some(parent) = dst.parent() { fs::create_dir_all(parent).ok(); }
             fs::rename(&src, &dst)?;
@@ -825,11 +1205,11 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             // Prevent name collision with existing views
             {
                 let root = store.root_path().clone();
-                let mut vp = root.join(table.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+                let mut vp = crate::ident::to_local_path(&root, &table);
                 // The table ident here is expected to end with .time, so the base directory is already with .time suffix
                 // We want to check for a view that uses the base name without .time
                 let base_no_time = if let Some(stripped) = table.strip_suffix(".time") { stripped } else { &table };
-                vp = root.join(base_no_time.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+                vp = crate::ident::to_local_path(&root, &base_no_time);
                 vp.set_extension("view");
                 if vp.exists() {
                     anyhow::bail!(format!("Object name conflict: a VIEW exists with name '{}'. Time table names must be unique across views.", base_no_time));
@@ -838,7 +1218,7 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             // If exists, honor IF NOT EXISTS
             {
                 let root = store.root_path().clone();
-                let dir = root.join(table.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+                let dir = crate::ident::to_local_path(&root, &table);
                 if dir.exists() {
                     if if_not_exists { return Ok(serde_json::json!({"status":"ok"})); }
                     anyhow::bail!(format!("Time table already exists: {}", table));
@@ -860,8 +1240,8 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             let d = crate::system::current_query_defaults();
             let fromq = crate::ident::qualify_time_ident(&from, &d);
             let toq = crate::ident::qualify_time_ident(&to, &d);
-            let src = store.root_path().join(fromq.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
-            let dst = store.root_path().join(toq.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+            let src = crate::ident::to_local_path(&store.root_path(), &fromq);
+            let dst = crate::ident::to_local_path(&store.root_path(), &toq);
             if !src.exists() { anyhow::bail!("Source time table not found: {}", from); }
             if let Some(parent) = dst.parent() { fs::create_dir_all(parent).ok(); }
             fs::rename(&src, &dst)?;
@@ -900,7 +1280,7 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
             crate::security::delete_user(root.to_string_lossy().as_ref(), scope, &username)?;
             Ok(serde_json::json!({"status":"ok"}))
         }
-        Command::UserAlter { username, new_password, is_admin, perms, scope_db } => {
+        Command::UserAlter { username, new_password, is_admin, perms, scope_db, expire_password, unlock } => {
             let root = store.root_path();
             let scope = match scope_db.as_deref() { Some(db) => crate::security::Scope::Database(db), None => crate::security::Scope::Global };
             // Build optional perms if provided
@@ -925,6 +1305,8 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
                 is_admin,
                 perms_opt,
             )?;
+            if expire_password { crate::security::expire_password(root.to_string_lossy().as_ref(), scope, &username)?; }
+            if unlock { crate::identity::unlock_account(&username); }
             Ok(serde_json::json!({"status":"ok"}))
         }
     }
@@ -933,18 +1315,17 @@ pub async fn execute_query(store: &SharedStore, text: &str) -> Result<serde_json
 /// Context-aware entrypoint for executing a SQL/text command.
 ///
 /// This variant accepts a `RequestContext` carrying an optional `Principal`,
-/// request id, and database/filestore hints. For now it delegates to the
-/// legacy `execute_query` to avoid behavior changes. Callers can start using
-/// this to propagate identity while we migrate internals.
-pub async fn execute_query_with_ctx(store: &SharedStore, text: &str, _ctx: &RequestContext) -> Result<serde_json::Value> {
+/// request id, and database/filestore hints. The statement is authorized for that
+/// principal (Security v2, then role grants and object privileges) before it runs
+/// through the legacy `execute_query`.
+pub async fn execute_query_with_ctx(store: &SharedStore, text: &str, ctx: &RequestContext) -> Result<serde_json::Value> {
     // Initialize security evaluator storage on first use
     ensure_sec_store(store);
     // Enforce authorization using Security v2. If parsing fails, fall back to legacy path.
     if let Ok(cmd) = parse(text) {
         // Enforce (deny on unauthorized)
-        if let Err(e) = crate::server::exec::exec_auth_shadow::enforce_authorize_sql(_ctx, &cmd) {
-            return Err(e);
-        }
+        crate::server::exec::exec_auth_shadow::enforce_authorize_sql(ctx, &cmd)?;
+        self::exec_authorize::authorize_statement(store, ctx, &cmd).await?;
     }
     execute_query(store, text).await
 }
@@ -1019,11 +1400,17 @@ fn hex_val(b: u8) -> anyhow::Result<u8> {
     }
 }
 
+/// Database and name of a filestore reference: `db.fs`, or `fs` in the current database.
+pub(crate) fn filestore_target(name: &str) -> (String, String) {
+    crate::ident::qualify_db_object(name, &crate::system::current_query_defaults())
+}
+
 /// Compute EffectiveConfig for a filestore by loading registry entry if present
 /// and overlaying on Global defaults. Folder overrides are not applied here.
-fn effective_for(store: &SharedStore, filestore: &str) -> anyhow::Result<EffectiveConfig> {
-    let global = fs::GlobalFilestoreConfig::default();
-    let fs_cfg = if let Some(ent) = fs::load_filestore_entry(store, crate::lua_bc::DEFAULT_DB, filestore)? {
+pub(crate) fn effective_for(store: &SharedStore, database: &str, filestore: &str) -> anyhow::Result<EffectiveConfig> {
+    // Global layer comes from the [filestore] section of the server configuration
+    let global = crate::config::current().filestore.clone();
+    let fs_cfg = if let Some(ent) = fs::load_filestore_entry(store, database, filestore)? {
         ent.config
     } else {
         FilestoreConfig::default()
@@ -1031,15 +1418,32 @@ fn effective_for(store: &SharedStore, filestore: &str) -> anyhow::Result<Effecti
     Ok(EffectiveConfig::from_layers(&global, &fs_cfg, None))
 }
 
+/// Filestore user of a SQL statement: the session user, so that file locks have an owner
+/// (anonymous outside a session).
+pub(crate) fn sql_acl_user() -> AclUser {
+    AclUser { id: crate::server::activity::current_user().unwrap_or_else(|| "anonymous".into()), roles: vec![], ip: None }
+}
+
+/// Gate a filestore command that has no per-file check of its own: `action` on `logical_path`
+/// ("" = the whole filestore) for the session user, through the filestore's ACL.
+pub(crate) async fn authorize_filestore(store: &SharedStore, database: &str, filestore: &str, action: ACLAction, logical_path: &str) -> anyhow::Result<()> {
+    let eff = effective_for(store, database, filestore)?;
+    let ctx = make_acl_ctx(store, database, filestore);
+    let decision = fs::check_acl(&eff, &sql_acl_user(), action, &fs::normalize_nfc(logical_path), None, &ctx, filestore).await;
+    if !decision.allow { anyhow::bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+    Ok(())
+}
+
 /// Build an AclContext with a fresh CorrelationId and the filestore's config_version if available.
-fn make_acl_ctx(store: &SharedStore, filestore: &str) -> AclContext {
+pub(crate) fn make_acl_ctx(store: &SharedStore, database: &str, filestore: &str) -> AclContext {
     let req_id = CorrelationId::new().to_string();
-    let version = fs::load_filestore_entry(store, crate::lua_bc::DEFAULT_DB, filestore)
+    let version = fs::load_filestore_entry(store, database, filestore)
         .ok()
         .and_then(|opt| opt.map(|e| e.config_version));
     AclContext {
         filestore_config_version: version,
         request_id: Some(req_id),
+        database: Some(database.to_string()),
         ..Default::default()
     }
 }
@@ -22,6 +22,7 @@ pub(crate) fn read_df_or_kv(store: &SharedStore, name: &str) -> anyhow::Result<D
         let db = parts[0];
         let store_name = parts[2];
         let key = parts[3..].join(".");
+        crate::storage::versions::note_uncacheable();
         let kv = store.kv_store(db, store_name);
         if let Some(val) = kv.get(&key) {
             match val {
@@ -32,6 +33,7 @@ pub(crate) fn read_df_or_kv(store: &SharedStore, name: &str) -> anyhow::Result<D
                 }
                 KvValue::Str(_) | KvValue::Int(_) => anyhow::bail!("Scalar key cannot be used in FROM; expected a table"),
                 KvValue::Bytes(_) => anyhow::bail!("Binary key cannot be used in FROM; expected a table"),
+                KvValue::List(_) | KvValue::Set(_) | KvValue::Hash(_) => anyhow::bail!("Collection key cannot be used in FROM; expected a table"),
             }
         } else {
             anyhow::bail!(format!("KV key not found: {}.store.{}.{}", db, store_name, key));
@@ -12,7 +12,7 @@ pub fn handle_alter_table(store: &SharedStore, table: &str, ops: &[AlterOp]) ->
     let tableq = crate::ident::qualify_regular_ident(table, &qd);
     // Resolve schema.json path
     let root = store.root_path().clone();
-    let dir = root.join(tableq.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+    let dir = crate::ident::to_local_path(&root, &tableq);
     let spath = dir.join("schema.json");
     if !dir.exists() {
         return Err(anyhow!(format!("ALTER TABLE target does not exist: {}", tableq)));
@@ -34,6 +34,10 @@ pub fn handle_alter_table(store: &SharedStore, table: &str, ops: &[AlterOp]) ->
         }
     };
 
+    let mut rebuild_blooms = false;
+    let mut reencrypt = false;
+    let mut repartition = false;
+    let mut backfill: Vec<String> = Vec::new();
     for op in ops {
         match op {
             AlterOp::AddColumn { name, type_key, .. } => {
@@ -41,9 +45,30 @@ pub fn handle_alter_table(store: &SharedStore, table: &str, ops: &[AlterOp]) ->
                 obj.insert(name.clone(), Value::String(type_key.clone()));
                 info!(target: "clarium::ddl", "ALTER TABLE {}: ADD COLUMN {} {}", tableq, name, type_key);
             }
+            AlterOp::AddComputedColumn { name, expr } => {
+                use crate::storage::computed::{ComputedColumn, COMPUTED_KEY};
+                if name == "_time" { return Err(anyhow!("_time cannot be a computed column")); }
+                let def = ComputedColumn { name: name.clone(), expr: expr.clone() };
+                obj.insert(COMPUTED_KEY.into(), crate::storage::computed::with_definition(&obj, def));
+                backfill.push(name.clone());
+                info!(target: "clarium::ddl", "ALTER TABLE {}: ADD COMPUTED COLUMN {} AS {}", tableq, name, expr);
+            }
+            AlterOp::DropComputedColumn { name } => {
+                use crate::storage::computed::COMPUTED_KEY;
+                let mut defs: Vec<Value> = obj.get(COMPUTED_KEY).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+                let before = defs.len();
+                defs.retain(|d| d.get("name").and_then(|v| v.as_str()) != Some(name.as_str()));
+                if defs.len() == before { return Err(anyhow!(format!("computed column not found: {}", name))); }
+                if defs.is_empty() { obj.remove(COMPUTED_KEY); } else { obj.insert(COMPUTED_KEY.into(), Value::Array(defs)); }
+                info!(target: "clarium::ddl", "ALTER TABLE {}: DROP COMPUTED COLUMN {}", tableq, name);
+            }
             AlterOp::RenameColumn { from, to } => {
                 if let Some(v) = obj.remove(from) {
                     obj.insert(to.clone(), v);
+                    crate::storage::comments::rename_column_comment(&mut obj, from, to);
+                    if let Some(m) = obj.get_mut(crate::server::collation::COLLATIONS_KEY).and_then(|v| v.as_object_mut()) {
+                        if let Some(c) = m.remove(from) { m.insert(to.clone(), c); }
+                    }
                     info!(target: "clarium::ddl", "ALTER TABLE {}: RENAME COLUMN {} TO {}", tableq, from, to);
                 } else {
                     debug!(target: "clarium::ddl", "ALTER TABLE {}: RENAME COLUMN skipped, source '{}' not found", tableq, from);
@@ -94,10 +119,102 @@ pub fn handle_alter_table(store: &SharedStore, table: &str, ops: &[AlterOp]) ->
                 }
                 info!(target: "clarium::ddl", "ALTER TABLE {}: DROP CONSTRAINT {}", tableq, name);
             }
+            AlterOp::SetBloom { columns } => {
+                if columns.is_empty() { obj.remove("bloomColumns"); } else { obj.insert("bloomColumns".into(), json!(columns)); }
+                rebuild_blooms = true;
+                info!(target: "clarium::ddl", "ALTER TABLE {}: SET BLOOM ({})", tableq, columns.join(", "));
+            }
+            AlterOp::SetCdc { enabled } => {
+                if *enabled { obj.insert(crate::storage::cdc::CDC_KEY.into(), json!(true)); } else { obj.remove(crate::storage::cdc::CDC_KEY); }
+                info!(target: "clarium::ddl", "ALTER TABLE {}: SET CDC {}", tableq, if *enabled { "ON" } else { "OFF" });
+            }
+            AlterOp::SetDedup { mode } => {
+                if !tableq.ends_with(".time") && !obj.get("tableType").and_then(|v| v.as_str()).map(|t| t.eq_ignore_ascii_case("time")).unwrap_or(false) {
+                    return Err(anyhow!("SET DEDUP applies to time tables only: {}", tableq));
+                }
+                match crate::storage::dedup::setting_value(*mode) {
+                    Some(v) => { obj.insert(crate::storage::dedup::DEDUP_KEY.into(), v); }
+                    None => { obj.remove(crate::storage::dedup::DEDUP_KEY); }
+                }
+                info!(target: "clarium::ddl", "ALTER TABLE {}: SET DEDUP {:?}", tableq, mode);
+            }
+            AlterOp::SetIngestMode { mode } => {
+                match crate::storage::ingest_mode::setting_value(*mode) {
+                    Some(v) => { obj.insert(crate::storage::ingest_mode::INGEST_MODE_KEY.into(), v); }
+                    None => { obj.remove(crate::storage::ingest_mode::INGEST_MODE_KEY); }
+                }
+                info!(target: "clarium::ddl", "ALTER TABLE {}: SET INGEST MODE {:?}", tableq, mode);
+            }
+            AlterOp::SetLateData { setting } => {
+                if !tableq.ends_with(".time") && !obj.get("tableType").and_then(|v| v.as_str()).map(|t| t.eq_ignore_ascii_case("time")).unwrap_or(false) {
+                    return Err(anyhow!("SET LATE DATA applies to time tables only: {}", tableq));
+                }
+                match crate::storage::late::setting_value(*setting) {
+                    Some(v) => { obj.insert(crate::storage::late::LATE_KEY.into(), v); }
+                    None => { obj.remove(crate::storage::late::LATE_KEY); }
+                }
+                info!(target: "clarium::ddl", "ALTER TABLE {}: SET LATE DATA {:?} WINDOW {}", tableq, setting.policy, setting.window_ms);
+            }
+            AlterOp::SetPartitioning { spec } => {
+                use crate::storage::partition::PARTITIONING_KEY;
+                match crate::storage::partition::setting_values(spec) {
+                    Some((cols, setting)) => {
+                        obj.insert("partitions".into(), cols);
+                        obj.insert(PARTITIONING_KEY.into(), setting);
+                    }
+                    None => { obj.remove("partitions"); obj.remove(PARTITIONING_KEY); }
+                }
+                repartition = true;
+                info!(target: "clarium::ddl", "ALTER TABLE {}: SET PARTITION BY {:?} {:?} buckets={}", tableq, spec.method, spec.columns, spec.buckets);
+            }
+            AlterOp::SetEncryption { enabled, key_id } => {
+                use crate::storage::encryption::{EncryptionSpec, ENCRYPTION_KEY};
+                if *enabled {
+                    let spec = EncryptionSpec::new(key_id.clone());
+                    // Fail before touching schema.json when the key cannot be resolved
+                    crate::storage::encryption::resolve_key_id(&spec)?;
+                    obj.insert(ENCRYPTION_KEY.into(), json!(spec));
+                } else {
+                    obj.remove(ENCRYPTION_KEY);
+                }
+                reencrypt = true;
+                info!(target: "clarium::ddl", "ALTER TABLE {}: SET ENCRYPTION {} key={:?}", tableq, if *enabled { "ON" } else { "OFF" }, key_id);
+            }
+            AlterOp::SetCollation { column, collation } => {
+                use crate::server::collation::COLLATIONS_KEY;
+                let known = obj.get("columns").and_then(|c| c.as_object()).map(|c| c.contains_key(column)).unwrap_or(false) || obj.contains_key(column);
+                if !known { return Err(anyhow!(format!("column not found: {}", column))); }
+                let mut map = obj.get(COLLATIONS_KEY).and_then(|v| v.as_object()).cloned().unwrap_or_default();
+                match collation {
+                    Some(c) => { map.insert(column.clone(), Value::String(c.clone())); }
+                    None => { map.remove(column); }
+                }
+                if map.is_empty() { obj.remove(COLLATIONS_KEY); } else { obj.insert(COLLATIONS_KEY.into(), Value::Object(map)); }
+                info!(target: "clarium::ddl", "ALTER TABLE {}: ALTER COLUMN {} collation {:?}", tableq, column, collation);
+            }
         }
     }
 
     // Persist
     std::fs::write(&spath, serde_json::to_string_pretty(&Value::Object(obj))?)?;
+    // Existing chunks need filters built (or dropped) to match the new setting
+    if rebuild_blooms {
+        let n = store.0.lock().rebuild_bloom_filters(&tableq)?;
+        debug!(target: "clarium::ddl", "ALTER TABLE {}: rebuilt bloom filters for {} chunk(s)", tableq, n);
+    }
+    // Move existing rows into the folders of the new partition layout
+    if repartition {
+        let n = store.0.lock().repartition_table(&tableq)?;
+        debug!(target: "clarium::ddl", "ALTER TABLE {}: repartitioned into {} chunk(s)", tableq, n);
+    }
+    // Stored rows get the values new rows will be written with
+    for name in &backfill {
+        let n = store.0.lock().backfill_computed_column(&tableq, name)?;
+        debug!(target: "clarium::ddl", "ALTER TABLE {}: computed {} for {} row(s)", tableq, name, n);
+    }
+    if reencrypt {
+        let n = store.0.lock().reencrypt_table(&tableq)?;
+        debug!(target: "clarium::ddl", "ALTER TABLE {}: re-encrypted {} chunk(s)", tableq, n);
+    }
     Ok(serde_json::json!({"status":"ok"}))
 }
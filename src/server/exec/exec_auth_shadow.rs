@@ -6,26 +6,48 @@
 
 use crate::identity::RequestContext;
 use crate::tprintln;
-use crate::server::query::Command;
+use crate::server::query::{Command, KvOp};
 
 use crate::server::exec::filestore::sec as sec;
 
+/// Action a statement performs, for the sec v2 gate. Exhaustive on purpose: a new command has to
+/// be classified here rather than defaulting to a read.
 fn map_cmd_to_action(cmd: &Command) -> sec::model::Action {
     use sec::model::Action as A;
     match cmd {
         Command::Select(_) => A::Read,
         Command::Explain { .. } => A::Read,
         Command::Insert { .. } => A::Write,
+        Command::CopyFrom { .. } => A::Write,
+        Command::CopyTo { .. } => A::Read,
+        Command::RestoreDatabase { .. } => A::Write,
+        Command::VerifyTable { quarantine: true, .. } => A::Write,
+        Command::KeyOp { op: KvOp::HashGet { .. }, .. } => A::Read,
+        Command::ReencryptTable { .. } => A::Write,
+        Command::CompactTable { .. } => A::Write,
+        Command::Analyze { .. } => A::Write,
         Command::Update { .. } => A::Write,
         Command::DeleteRows { .. } | Command::DeleteColumns { .. } => A::Delete,
+        Command::DeleteFilePathCmd { .. } => A::Delete,
+        Command::RenameFilePathCmd { .. } => A::Rename,
+        Command::CommitTreeCmd { .. } => A::Commit,
         Command::CreateTable { .. }
         | Command::AlterTable { .. }
+        | Command::CreateTrigger { .. }
+        | Command::DropTrigger { .. }
         | Command::DropTable { .. }
+        | Command::RenameTable { .. }
         | Command::CreateView { .. }
         | Command::DropView { .. }
+        | Command::CreateSlice { .. }
+        | Command::DropSlice { .. }
+        | Command::CommentOn { .. }
         | Command::CreateDatabase { .. }
         | Command::DropDatabase { .. }
         | Command::RenameDatabase { .. }
+        | Command::AlterDatabaseSet { .. }
+        | Command::AttachDatabase { .. }
+        | Command::DetachDatabase { .. }
         | Command::DatabaseAdd { .. }
         | Command::DatabaseDelete { .. }
         | Command::CreateSchema { .. }
@@ -35,23 +57,142 @@ fn map_cmd_to_action(cmd: &Command) -> sec::model::Action {
         | Command::CreateTimeTable { .. }
         | Command::DropTimeTable { .. }
         | Command::RenameTimeTable { .. }
+        | Command::InsertSelect { .. }
+        | Command::Calculate { .. }
         | Command::CreateStore { .. }
         | Command::DropStore { .. }
         | Command::RenameStore { .. }
         | Command::WriteKey { .. }
+        | Command::KeyOp { .. }
+        | Command::KvBatch { .. }
         | Command::DropKey { .. }
         | Command::RenameKey { .. }
         | Command::UserAdd { .. }
         | Command::UserDelete { .. }
+        | Command::UserAlter { .. }
+        | Command::Grant { .. }
+        | Command::Revoke { .. }
+        | Command::CreateRole { .. }
+        | Command::DropRole { .. }
+        | Command::GrantRole { .. }
+        | Command::RevokeRole { .. }
+        | Command::CreateJob { .. }
+        | Command::DropJob { .. }
+        | Command::AlterJob { .. }
+        | Command::CreateProcedure { .. }
+        | Command::DropProcedure { .. }
+        | Command::Call { .. }
+        | Command::CreateScript { .. }
+        | Command::DropScript { .. }
+        | Command::RenameScript { .. }
+        | Command::LoadScript { .. }
+        | Command::ClearScriptCache { .. }
+        | Command::CreateScriptTest { .. }
+        | Command::DropScriptTest { .. }
+        | Command::RunTests { .. }
+        | Command::Kill { .. }
+        | Command::KillSession { .. }
+        | Command::KillUserSessions { .. }
+        | Command::ReloadConfig
+        // Vector indexes and graphs
+        | Command::CreateVectorIndex { .. }
+        | Command::DropVectorIndex { .. }
+        | Command::AlterVectorIndexSetMode { .. }
+        | Command::BuildVectorIndex { .. }
+        | Command::ReindexVectorIndex { .. }
+        | Command::RebuildVectorIndex { .. }
+        | Command::CreateGraph { .. }
+        | Command::DropGraph { .. }
+        | Command::InsertGraph { .. }
+        | Command::DeleteGraph { .. }
+        | Command::InsertNodeTxn { .. }
+        | Command::InsertEdgeTxn { .. }
+        | Command::CommitGraphTxn
+        | Command::GcGraph { .. }
+        // Filestores: configuration, content, locks, branches and keys
+        | Command::GcFilestore { .. }
+        | Command::CreateFilestoreCmd { .. }
+        | Command::AlterFilestoreCmd { .. }
+        | Command::DropFilestoreCmd { .. }
+        | Command::IngestFileFromBytesCmd { .. }
+        | Command::IngestFileFromHostPathCmd { .. }
+        | Command::UpdateFileFromBytesCmd { .. }
+        | Command::SetFileMetadataCmd { .. }
+        | Command::SetLegalHoldCmd { .. }
+        | Command::ClearLegalHoldCmd { .. }
+        | Command::RotateFilestoreKeyCmd { .. }
+        | Command::LockFileCmd { .. }
+        | Command::UnlockFileCmd { .. }
+        | Command::CreateTreeCmd { .. }
+        | Command::SyncFilestoreCmd { .. }
+        | Command::CreateBranchCmd { .. }
+        | Command::CheckoutBranchCmd { .. }
+        | Command::MergeBranchCmd { .. }
+        // Security policies constrain everyone else: never a read
+        | Command::CreateSecurityPolicyCmd { .. }
+        | Command::AlterSecurityPolicyCmd { .. }
+        | Command::DropSecurityPolicyCmd { .. }
         => A::Write,
         Command::SchemaShow { .. }
         | Command::ListStores { .. }
         | Command::ListKeys { .. }
         | Command::DescribeKey { .. }
         | Command::ReadKey { .. }
+        | Command::Watch { .. }
+        | Command::Unwatch { .. }
         | Command::ShowView { .. }
+        | Command::ShowSlices
+        | Command::SelectUnion { .. }
+        | Command::Slice(_)
+        | Command::Assert { .. }
+        | Command::DescribeObject { .. }
+        | Command::VerifyTable { quarantine: false, .. }
+        | Command::BackupDatabase { .. }
+        | Command::MatchRewrite { .. }
+        | Command::ExplainAccessCmd { .. }
+        // Session state
+        | Command::UseDatabase { .. }
+        | Command::UseSchema { .. }
+        | Command::Set { .. }
+        | Command::Reset { .. }
+        | Command::UseGraph { .. }
+        | Command::UnsetGraph
+        | Command::BeginGraphTxn { .. }
+        | Command::AbortGraphTxn
+        // SHOW
+        | Command::ShowVariable { .. }
+        | Command::ShowAll
+        | Command::ShowSchemas
+        | Command::ShowTables
+        | Command::ShowObjects
+        | Command::ShowScripts
+        | Command::ShowCacheStats
+        | Command::ShowVectorIndex { .. }
+        | Command::ShowVectorIndexes
+        | Command::ShowVectorIndexStatus { .. }
+        | Command::ShowGraph { .. }
+        | Command::ShowGraphs
+        | Command::ShowGraphStatus { .. }
+        | Command::ShowCurrentGraph
+        | Command::ShowFilestores { .. }
+        | Command::ShowFilestoreConfig { .. }
+        | Command::ShowFilesInFilestore { .. }
+        | Command::ShowTreesInFilestore { .. }
+        | Command::ShowCommitsInFilestore { .. }
+        | Command::ShowDiffInFilestore { .. }
+        | Command::ShowChunksInFilestore { .. }
+        | Command::ShowAliasesInFilestore { .. }
+        | Command::ShowAdminInFilestore { .. }
+        | Command::ShowHealthInFilestore { .. }
+        | Command::ShowSyncInFilestore { .. }
+        | Command::ShowBranchesInFilestore { .. }
+        | Command::ShowLegalHoldsInFilestore { .. }
+        | Command::ShowLocksInFilestore { .. }
+        | Command::ShowWebhooksInFilestore { .. }
+        | Command::ShowEncryptionInFilestore { .. }
+        | Command::ShowFilestoreGc { .. }
+        | Command::ShowVersionsInFilestore { .. }
         => A::Read,
-        _ => A::Read,
     }
 }
 
@@ -94,13 +235,31 @@ fn map_cmd_to_resource(ctx: &RequestContext, cmd: &Command) -> sec::model::Resou
             R::res_database(db_default)
         }
         Command::Update { table, .. }
+        | Command::CopyFrom { table, .. }
+        | Command::VerifyTable { table, .. }
+        | Command::ReencryptTable { table }
+        | Command::CompactTable { table }
         | Command::CreateTimeTable { table, .. }
         | Command::DropTimeTable { table }
         | Command::RenameTimeTable { from: table, .. }
         | Command::CreateTable { table, .. }
         | Command::DropTable { table, .. }
         | Command::RenameTable { from: table, .. }
-        | Command::AlterTable { table, .. } => {
+        | Command::AlterTable { table, .. }
+        | Command::CreateTrigger { table, .. }
+        | Command::DropTrigger { table, .. } => {
+            let (db, schema, t) = split_db_schema_table(ctx, table);
+            R::res_table(&db, &schema, &t)
+        }
+        Command::CommentOn { object: crate::server::query::CommentObject::Table(table) | crate::server::query::CommentObject::Column { table, .. }, .. } => {
+            let (db, schema, t) = split_db_schema_table(ctx, table);
+            R::res_table(&db, &schema, &t)
+        }
+        Command::Analyze { table: Some(table) } => {
+            let (db, schema, t) = split_db_schema_table(ctx, table);
+            R::res_table(&db, &schema, &t)
+        }
+        Command::CopyTo { relation: crate::server::query::CopyRelation::Table(table), .. } => {
             let (db, schema, t) = split_db_schema_table(ctx, table);
             R::res_table(&db, &schema, &t)
         }
@@ -115,6 +274,9 @@ fn map_cmd_to_resource(ctx: &RequestContext, cmd: &Command) -> sec::model::Resou
         | Command::DescribeKey { database, .. }
         | Command::WriteKey { database, .. }
         | Command::ReadKey { database, .. }
+        | Command::KeyOp { database, .. }
+        | Command::Watch { database, .. }
+        | Command::KvBatch { database, .. }
         | Command::DropKey { database, .. }
         | Command::RenameKey { database, .. } => {
             // Keys and stores are scoped to a database
@@ -129,8 +291,13 @@ fn map_cmd_to_resource(ctx: &RequestContext, cmd: &Command) -> sec::model::Resou
         Command::CreateDatabase { name, .. }
         | Command::DropDatabase { name }
         | Command::RenameDatabase { from: name, .. }
+        | Command::AlterDatabaseSet { database: name, .. }
         | Command::DatabaseAdd { database: name }
-        | Command::DatabaseDelete { database: name } => R::res_database(name),
+        | Command::DatabaseDelete { database: name }
+        | Command::BackupDatabase { database: name, .. }
+        | Command::RestoreDatabase { database: name, .. }
+        | Command::AttachDatabase { name, .. }
+        | Command::DetachDatabase { name } => R::res_database(name),
         // View and misc default to database scope
         Command::CreateView { .. }
         | Command::DropView { .. }
@@ -170,5 +337,8 @@ pub fn enforce_authorize_sql(ctx: &RequestContext, cmd: &Command) -> anyhow::Res
     // Emit post-auth hook for auditing
     let ev = sec::hooks::HookEvent { user: user.clone(), action, resource: res.clone(), ctx: c.clone(), decision: Some(dec.clone()) };
     sec::hooks::emit_post_auth(&ev);
-    if dec.allow { Ok(()) } else { Err(anyhow!(format!("unauthorized: user={} action={:?} resource={} reason={}", user.id, action, res.0, dec.reason.unwrap_or_else(|| "deny".into())))) }
+    if dec.allow { Ok(()) } else {
+        let msg = format!("unauthorized: user={} action={:?} resource={} reason={}", user.id, action, res.0, dec.reason.unwrap_or_else(|| "deny".into()));
+        Err(crate::error::AppError::permission("insufficient_privilege".to_string(), msg).into())
+    }
 }
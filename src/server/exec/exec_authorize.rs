@@ -0,0 +1,416 @@
+//! Statement authorization shared by every transport: role grants (`identity::check_command_allowed_async`)
+//! and object privileges set with GRANT / REVOKE (`security::has_privilege`).
+//!
+//! HTTP and WebSocket handlers check a statement before running it so they can answer 403;
+//! `execute_query_with_ctx` and `handle_select_with_ctx` run [`authorize_statement`] for the
+//! session behind a `RequestContext`, which is how pgwire statements are checked.
+
+use anyhow::Result;
+
+use crate::error::AppError;
+use crate::identity::RequestContext;
+use crate::security;
+use crate::server::activity;
+use crate::server::query;
+use crate::storage::SharedStore;
+
+fn to_ck_and_db(cmd: &query::Command) -> (security::CommandKind, Option<String>) {
+    match cmd {
+        query::Command::Select(q) => (security::CommandKind::Select, q.base_table.as_ref().and_then(|t| t.table_name().map(|s| s.to_string()))),
+        query::Command::Calculate { query: q, .. } => (security::CommandKind::Calculate, q.base_table.as_ref().and_then(|t| t.table_name().map(|s| s.to_string()))),
+        query::Command::Update { table, .. } => {
+            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
+            (security::CommandKind::Other, db_name)
+        }
+        // Views
+        query::Command::CreateView { .. } | query::Command::DropView { .. } | query::Command::ShowView { .. } => (security::CommandKind::Database, None),
+        // Named slice plans
+        query::Command::CreateSlice { .. } | query::Command::DropSlice { .. } | query::Command::ShowSlices => (security::CommandKind::Database, None),
+        query::Command::CreateProcedure { .. } | query::Command::DropProcedure { .. } => (security::CommandKind::Database, None),
+        query::Command::Call { .. } => (security::CommandKind::Other, None),
+        query::Command::DeleteRows { database, .. } => (security::CommandKind::DeleteRows, Some(database.clone())),
+        query::Command::DeleteColumns { database, .. } => (security::CommandKind::DeleteColumns, Some(database.clone())),
+        query::Command::SchemaShow { database } => (security::CommandKind::Schema, Some(database.clone())),
+        query::Command::SchemaAdd { database, .. } => (security::CommandKind::Schema, Some(database.clone())),
+        // Legacy
+        query::Command::DatabaseAdd { .. } => (security::CommandKind::Database, None),
+        query::Command::DatabaseDelete { .. } => (security::CommandKind::Database, None),
+        // New DDL
+        query::Command::CreateDatabase { .. } | query::Command::DropDatabase { .. } | query::Command::RenameDatabase { .. } | query::Command::AlterDatabaseSet { .. } => (security::CommandKind::Database, None),
+        query::Command::CreateSchema { .. } | query::Command::DropSchema { .. } | query::Command::RenameSchema { .. } => (security::CommandKind::Schema, None),
+        query::Command::CreateTimeTable { .. } | query::Command::DropTimeTable { .. } | query::Command::RenameTimeTable { .. } => (security::CommandKind::Database, None),
+        query::Command::CreateTable { .. } | query::Command::DropTable { .. } | query::Command::RenameTable { .. } => (security::CommandKind::Database, None),
+        query::Command::AlterTable { table, .. } | query::Command::CreateTrigger { table, .. } | query::Command::DropTrigger { table, .. } => {
+            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
+            (security::CommandKind::Database, db_name)
+        }
+        query::Command::UserAdd { .. } | query::Command::UserDelete { .. } | query::Command::UserAlter { .. } => (security::CommandKind::Other, None),
+        query::Command::Grant { .. } | query::Command::Revoke { .. } => (security::CommandKind::Other, None),
+        query::Command::CreateRole { .. } | query::Command::DropRole { .. } | query::Command::GrantRole { .. } | query::Command::RevokeRole { .. } => (security::CommandKind::Other, None),
+        // Jobs run unattended as their owner: admin-only, like script DDL
+        query::Command::CreateJob { .. } | query::Command::DropJob { .. } | query::Command::AlterJob { .. } => (security::CommandKind::Other, None),
+        query::Command::CreateScript { .. } | query::Command::DropScript { .. } | query::Command::RenameScript { .. } | query::Command::LoadScript { .. } => (security::CommandKind::Other, None),
+        query::Command::CreateScriptTest { .. } | query::Command::DropScriptTest { .. } | query::Command::RunTests { .. } => (security::CommandKind::Other, None),
+        query::Command::Assert { .. } => (security::CommandKind::Select, None),
+        // Comments are object metadata: function comments follow script DDL, the rest schema DDL
+        query::Command::CommentOn { object: query::CommentObject::Function(_), .. } => (security::CommandKind::Other, None),
+        query::Command::CommentOn { .. } => (security::CommandKind::Schema, None),
+        // KV store/key commands
+        query::Command::CreateStore { database, .. } => (security::CommandKind::Database, Some(database.clone())),
+        query::Command::DropStore { database, .. } => (security::CommandKind::Database, Some(database.clone())),
+        query::Command::RenameStore { database, .. } => (security::CommandKind::Database, Some(database.clone())),
+        query::Command::WriteKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::ReadKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::DropKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::RenameKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::KeyOp { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::KvBatch { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::Watch { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::Unwatch { .. } => (security::CommandKind::Other, None),
+        query::Command::ListStores { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::ListKeys { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::DescribeKey { database, .. } => (security::CommandKind::Other, Some(database.clone())),
+        query::Command::DescribeObject { .. } => (security::CommandKind::Other, None),
+        // Vector index catalog and lifecycle
+        query::Command::CreateVectorIndex { .. }
+        | query::Command::DropVectorIndex { .. }
+        | query::Command::ShowVectorIndex { .. }
+        | query::Command::ShowVectorIndexes
+        | query::Command::BuildVectorIndex { .. }
+        | query::Command::ReindexVectorIndex { .. }
+        | query::Command::RebuildVectorIndex { .. }
+        | query::Command::ShowVectorIndexStatus { .. }
+        | query::Command::AlterVectorIndexSetMode { .. }
+        => (security::CommandKind::Database, None),
+        // Graph catalog and graph-related commands
+        query::Command::CreateGraph { .. }
+        | query::Command::DropGraph { .. }
+        | query::Command::ShowGraph { .. }
+        | query::Command::ShowGraphs
+        | query::Command::ShowGraphStatus { .. }
+        | query::Command::UseGraph { .. }
+        | query::Command::UnsetGraph
+        | query::Command::ShowCurrentGraph
+        | query::Command::BeginGraphTxn { .. }
+        | query::Command::CommitGraphTxn
+        | query::Command::AbortGraphTxn
+        | query::Command::InsertNodeTxn { .. }
+        | query::Command::InsertEdgeTxn { .. }
+        | query::Command::InsertGraph { .. }
+        | query::Command::DeleteGraph { .. }
+        | query::Command::GcGraph { .. }
+        | query::Command::GcFilestore { .. }
+        | query::Command::MatchRewrite { .. } => (security::CommandKind::Other, None),
+        // Global session-affecting and SHOW
+        query::Command::UseDatabase { .. } | query::Command::UseSchema { .. } | query::Command::Set { .. } | query::Command::Reset { .. } => (security::CommandKind::Other, None),
+        query::Command::ShowVariable { .. }
+        | query::Command::ShowAll
+        | query::Command::ShowSchemas
+        | query::Command::ShowTables
+        | query::Command::ShowObjects
+        | query::Command::ShowScripts
+        | query::Command::ShowCacheStats => (security::CommandKind::Other, None),
+        query::Command::ClearScriptCache { .. } => (security::CommandKind::Other, None),
+        // FILESTORE commands: treat as Other with no specific database context here
+        query::Command::ShowFilestores { .. }
+        | query::Command::ShowFilestoreConfig { .. }
+        | query::Command::ShowFilesInFilestore { .. }
+        | query::Command::ShowTreesInFilestore { .. }
+        | query::Command::ShowCommitsInFilestore { .. }
+        | query::Command::ShowDiffInFilestore { .. }
+        | query::Command::ShowChunksInFilestore { .. }
+        | query::Command::ShowAliasesInFilestore { .. }
+        | query::Command::ShowAdminInFilestore { .. }
+        | query::Command::ShowHealthInFilestore { .. }
+        | query::Command::ShowSyncInFilestore { .. }
+        | query::Command::ShowBranchesInFilestore { .. }
+        | query::Command::ShowLegalHoldsInFilestore { .. }
+        | query::Command::ShowLocksInFilestore { .. }
+        | query::Command::ShowWebhooksInFilestore { .. }
+        | query::Command::ShowEncryptionInFilestore { .. }
+        | query::Command::ShowFilestoreGc { .. }
+        | query::Command::ShowVersionsInFilestore { .. }
+        | query::Command::CreateFilestoreCmd { .. }
+        | query::Command::AlterFilestoreCmd { .. }
+        | query::Command::DropFilestoreCmd { .. }
+        | query::Command::IngestFileFromBytesCmd { .. }
+        | query::Command::IngestFileFromHostPathCmd { .. }
+        | query::Command::UpdateFileFromBytesCmd { .. }
+        | query::Command::RenameFilePathCmd { .. }
+        | query::Command::DeleteFilePathCmd { .. }
+        | query::Command::SetFileMetadataCmd { .. }
+        | query::Command::SetLegalHoldCmd { .. }
+        | query::Command::ClearLegalHoldCmd { .. }
+        | query::Command::LockFileCmd { .. }
+        | query::Command::UnlockFileCmd { force: false, .. }
+        | query::Command::CreateTreeCmd { .. }
+        | query::Command::CommitTreeCmd { .. }
+        | query::Command::SyncFilestoreCmd { .. }
+        | query::Command::CreateBranchCmd { .. }
+        | query::Command::CheckoutBranchCmd { .. }
+        | query::Command::MergeBranchCmd { .. }
+        => (security::CommandKind::Other, None),
+        query::Command::Explain { .. } => (security::CommandKind::Other, None),
+        query::Command::SelectUnion { .. } => (security::CommandKind::Select, None),
+        query::Command::Slice(_) => (security::CommandKind::Select, None),
+        query::Command::Insert { table, .. } | query::Command::InsertSelect { table, .. } => {
+            // Extract database from table path (format: db/schema/table or just table)
+            let db_name = if table.contains('/') {
+                table.split('/').next().map(|s| s.to_string())
+            } else {
+                None
+            };
+            (security::CommandKind::Other, db_name)
+        }
+        query::Command::CopyFrom { table, source, .. } => {
+            // Server-side file/URL reads are admin-only (like pg_read_server_files); STDIN is a plain insert
+            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
+            match source {
+                query::CopySource::Stdin => (security::CommandKind::Insert, db_name),
+                _ => (security::CommandKind::Database, db_name),
+            }
+        }
+        // Exports to the client read like the equivalent SELECT
+        query::Command::CopyTo { relation, .. } => {
+            let db_name = match relation {
+                query::CopyRelation::Table(table) if table.contains('/') => table.split('/').next().map(|s| s.to_string()),
+                _ => None,
+            };
+            (security::CommandKind::Select, db_name)
+        }
+        // Backups read and restores write server-side paths: admin-only
+        query::Command::BackupDatabase { database, .. } | query::Command::RestoreDatabase { database, .. } => {
+            (security::CommandKind::Database, Some(database.clone()))
+        }
+        // Compaction rewrites the table's files like other table DDL
+        query::Command::CompactTable { table } => {
+            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
+            (security::CommandKind::Schema, db_name)
+        }
+        // Gathering statistics reads the table and writes its stats.json
+        query::Command::Analyze { table } => {
+            let db_name = table.as_deref().filter(|t| t.contains('/')).and_then(|t| t.split('/').next()).map(|s| s.to_string());
+            (security::CommandKind::Schema, db_name)
+        }
+        // Rewrites every chunk of the table under a different key: admin-only
+        query::Command::ReencryptTable { table } => {
+            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
+            (security::CommandKind::Database, db_name)
+        }
+        // Re-wraps a filestore's data key under another master key: admin-only
+        query::Command::RotateFilestoreKeyCmd { .. } => (security::CommandKind::Database, None),
+        // Breaking another user's file lock (UNLOCK FILE ... FORCE): admin-only
+        query::Command::UnlockFileCmd { force: true, .. } => (security::CommandKind::Database, None),
+        // Filestore security policies, and explaining another user's access: admin-only
+        query::Command::CreateSecurityPolicyCmd { .. } | query::Command::AlterSecurityPolicyCmd { .. } | query::Command::DropSecurityPolicyCmd { .. } => (security::CommandKind::Database, None),
+        query::Command::ExplainAccessCmd { user: Some(_), .. } => (security::CommandKind::Database, None),
+        query::Command::ExplainAccessCmd { user: None, .. } => (security::CommandKind::Other, None),
+        // Cancelling or terminating other sessions: admin-only
+        query::Command::Kill { .. } => (security::CommandKind::Other, None),
+        query::Command::KillSession { .. } | query::Command::KillUserSessions { .. } => (security::CommandKind::Other, None),
+        query::Command::ReloadConfig => (security::CommandKind::Other, None),
+        // Attaching exposes arbitrary server folders: admin-only
+        query::Command::AttachDatabase { name, .. } | query::Command::DetachDatabase { name } => {
+            (security::CommandKind::Database, Some(name.clone()))
+        }
+        // Checking chunks is a read; quarantining moves files and is admin-only
+        query::Command::VerifyTable { table, quarantine } => {
+            let db_name = if table.contains('/') { table.split('/').next().map(|s| s.to_string()) } else { None };
+            (if *quarantine { security::CommandKind::Database } else { security::CommandKind::Select }, db_name)
+        }
+    }
+}
+
+/// Tables a query reads: FROM and JOIN relations, subqueries in FROM, WHERE, HAVING, JOIN
+/// conditions and the select list, CTE bodies and SLICE sources. Names bound by a WITH in
+/// scope are not tables; table functions check their own sources.
+fn query_relations(q: &query::Query, ctes: &[String], out: &mut Vec<String>) {
+    let mut scope = ctes.to_vec();
+    for cte in q.with_ctes.iter().flatten() {
+        // A CTE body sees the CTEs before it, not itself
+        query_relations(&cte.query, &scope, out);
+        scope.push(cte.name.clone());
+    }
+    if let Some(t) = &q.base_table { table_ref_relations(t, &scope, out); }
+    for j in q.joins.iter().flatten() {
+        table_ref_relations(&j.right, &scope, out);
+        where_relations(&j.on, &scope, out);
+    }
+    for w in q.where_clause.iter().chain(q.having_clause.iter()) { where_relations(w, &scope, out); }
+    for e in q.select.iter().filter_map(|i| i.expr.as_ref()) { arith_relations(e, &scope, out); }
+    if let Some(plan) = &q.by_slices { slice_relations(plan, &scope, out); }
+}
+
+fn table_ref_relations(t: &query::TableRef, ctes: &[String], out: &mut Vec<String>) {
+    match t {
+        query::TableRef::Table { name, .. } => {
+            if !ctes.iter().any(|c| c.eq_ignore_ascii_case(name)) { out.push(name.clone()); }
+        }
+        query::TableRef::Subquery { query, .. } => query_relations(query, ctes, out),
+        query::TableRef::Tvf { .. } => {}
+    }
+}
+
+fn where_relations(w: &query::WhereExpr, ctes: &[String], out: &mut Vec<String>) {
+    use query::WhereExpr as W;
+    match w {
+        W::Comp { left, right, .. } => { arith_relations(left, ctes, out); arith_relations(right, ctes, out); }
+        W::And(a, b) | W::Or(a, b) => { where_relations(a, ctes, out); where_relations(b, ctes, out); }
+        W::IsNull { expr, .. } => arith_relations(expr, ctes, out),
+        W::Exists { subquery, .. } => query_relations(subquery, ctes, out),
+        W::All { left, subquery, .. } | W::Any { left, subquery, .. } => {
+            arith_relations(left, ctes, out);
+            query_relations(subquery, ctes, out);
+        }
+    }
+}
+
+fn arith_relations(e: &query::ArithExpr, ctes: &[String], out: &mut Vec<String>) {
+    use query::ArithExpr as A;
+    match e {
+        A::Term(_) => {}
+        A::BinOp { left, right, .. } => { arith_relations(left, ctes, out); arith_relations(right, ctes, out); }
+        A::Func(query::DateFunc::DatePart(_, a)) => arith_relations(a, ctes, out),
+        A::Func(query::DateFunc::DateAdd(_, a, b) | query::DateFunc::DateDiff(_, a, b)) => {
+            arith_relations(a, ctes, out);
+            arith_relations(b, ctes, out);
+        }
+        A::Slice { base, start, stop, .. } => {
+            arith_relations(base, ctes, out);
+            for b in start.iter().chain(stop.iter()) {
+                if let query::StrSliceBound::Pattern { expr, .. } = b { arith_relations(expr, ctes, out); }
+            }
+        }
+        A::Concat(parts) => for p in parts { arith_relations(p, ctes, out); },
+        // `(SELECT ...)` in an expression keeps its SQL text; an unparsable one fails when it runs
+        A::Call { name, args } if name == "SCALAR_SUBQUERY" => {
+            for a in args {
+                if let A::Term(query::ArithTerm::Str(sql)) = a {
+                    if let Ok(sub) = query::parse_select(sql) { query_relations(&sub, ctes, out); }
+                }
+            }
+        }
+        A::Call { args, .. } => for a in args { arith_relations(a, ctes, out); },
+        A::Predicate(w) => where_relations(w, ctes, out),
+        A::Case { when_clauses, else_expr } => {
+            for (w, v) in when_clauses { where_relations(w, ctes, out); arith_relations(v, ctes, out); }
+            if let Some(v) = else_expr { arith_relations(v, ctes, out); }
+        }
+        A::Cast { expr, .. } | A::Collate { expr, .. } => arith_relations(expr, ctes, out),
+    }
+}
+
+fn slice_relations(plan: &query::SlicePlan, ctes: &[String], out: &mut Vec<String>) {
+    for source in std::iter::once(&plan.base).chain(plan.clauses.iter().map(|c| &c.source)) {
+        match source {
+            query::SliceSource::Table { database, where_clause, .. } => {
+                out.push(database.clone());
+                if let Some(w) = where_clause { where_relations(w, ctes, out); }
+            }
+            query::SliceSource::Plan(inner) => slice_relations(inner, ctes, out),
+            // Saved plans are admin-defined; manual rows read nothing
+            query::SliceSource::Named(_) | query::SliceSource::Manual { .. } => {}
+        }
+    }
+}
+
+/// Privileges a command needs, one per relation it touches, for object privileges set with
+/// GRANT; every one must be held. Commands without target relations can only be allowed
+/// through `to_ck_and_db`.
+fn acl_targets(cmd: &query::Command) -> Option<Vec<(security::Privilege, String)>> {
+    use security::Privilege as P;
+    let reads = |out: &mut Vec<(P, String)>, q: &query::Query| {
+        let mut names = Vec::new();
+        query_relations(q, &[], &mut names);
+        out.extend(names.into_iter().map(|t| (P::Select, t)));
+    };
+    let where_reads = |out: &mut Vec<(P, String)>, w: &Option<query::WhereExpr>| {
+        let mut names = Vec::new();
+        if let Some(w) = w { where_relations(w, &[], &mut names); }
+        out.extend(names.into_iter().map(|t| (P::Select, t)));
+    };
+    let mut out = Vec::new();
+    match cmd {
+        query::Command::Select(q) | query::Command::Calculate { query: q, .. } => reads(&mut out, q),
+        query::Command::SelectUnion { queries, .. } => for q in queries { reads(&mut out, q) },
+        query::Command::Slice(plan) => {
+            let mut names = Vec::new();
+            slice_relations(plan, &[], &mut names);
+            out.extend(names.into_iter().map(|t| (P::Select, t)));
+        }
+        query::Command::CopyTo { relation: query::CopyRelation::Table(table), .. } => out.push((P::Select, table.clone())),
+        query::Command::CopyTo { relation: query::CopyRelation::Query(sql), .. } => {
+            if let Ok(q) = query::parse_select(sql) { reads(&mut out, &q); }
+        }
+        query::Command::Insert { table, .. }
+        | query::Command::CopyFrom { table, source: query::CopySource::Stdin, .. } => out.push((P::Insert, table.clone())),
+        query::Command::InsertSelect { table, query: q, .. } => {
+            out.push((P::Insert, table.clone()));
+            reads(&mut out, q);
+        }
+        query::Command::Update { table, assignments, from, where_clause } => {
+            out.push((P::Update, table.clone()));
+            if let Some(f) = from { out.push((P::Select, f.table.clone())); }
+            let mut names = Vec::new();
+            for (_, e) in assignments { arith_relations(e, &[], &mut names); }
+            out.extend(names.into_iter().map(|t| (P::Select, t)));
+            where_reads(&mut out, where_clause);
+        }
+        // DELETE parses its target relation into `database`
+        query::Command::DeleteRows { database, where_clause } | query::Command::DeleteColumns { database, where_clause, .. } => {
+            out.push((P::Delete, database.clone()));
+            where_reads(&mut out, where_clause);
+        }
+        query::Command::CreateTable { table, .. }
+        | query::Command::DropTable { table, .. }
+        | query::Command::RenameTable { from: table, .. }
+        | query::Command::AlterTable { table, .. }
+        | query::Command::CreateTrigger { table, .. }
+        | query::Command::DropTrigger { table, .. }
+        | query::Command::CreateTimeTable { table, .. }
+        | query::Command::DropTimeTable { table }
+        | query::Command::RenameTimeTable { from: table, .. }
+        | query::Command::CommentOn { object: query::CommentObject::Table(table) | query::CommentObject::Column { table, .. }, .. } => out.push((P::Ddl, table.clone())),
+        _ => {}
+    }
+    if out.is_empty() { None } else { Some(out) }
+}
+
+/// Command gate for one statement: role grants first, then object privileges granted on every
+/// relation the statement touches (or their schema or database), to `username` or to the role it assumed with
+/// SET ROLE (and the roles either inherits). HTTP and WebSocket statements, scheduled jobs and
+/// procedure bodies call it directly; pgwire statements reach it through [`authorize_statement`].
+pub(crate) async fn statement_allowed(store: &SharedStore, db_root: &str, username: &str, role: Option<&str>, token_roles: &[String], cmd: &query::Command, defaults: &crate::ident::QueryDefaults) -> bool {
+    // SET ROLE / RESET ROLE check role membership when they execute
+    if let query::Command::Set { variable, .. } | query::Command::Reset { variable: Some(variable) } = cmd {
+        if variable.eq_ignore_ascii_case("role") { return true; }
+    }
+    // CALL checks each statement of the procedure as it runs
+    if matches!(cmd, query::Command::Call { .. }) { return true; }
+    // Principals an identity source made admins (local admins, OIDC `admin_roles`)
+    if token_roles.iter().any(|r| r == crate::identity::ADMIN_ROLE) { return true; }
+    let (ck, db_opt) = to_ck_and_db(cmd);
+    if crate::identity::check_command_allowed_async(store, username, ck, db_opt.as_deref()).await { return true; }
+    let Some(targets) = acl_targets(cmd) else { return false };
+    targets.iter().all(|(privilege, name)| {
+        let qualified = crate::ident::qualify_regular_ident(name, defaults);
+        security::has_privilege(db_root, role.unwrap_or(username), *privilege, &qualified)
+            || token_roles.iter().any(|r| security::has_privilege(db_root, r, *privilege, &qualified))
+    })
+}
+
+/// Gate `cmd` for the session behind `ctx`: its principal, or the session user of a trust-mode
+/// connection, under the role it assumed with SET ROLE.
+pub async fn authorize_statement(store: &SharedStore, ctx: &RequestContext, cmd: &query::Command) -> Result<()> {
+    let user = ctx.principal.as_ref().map(|p| p.user_id.clone()).or_else(activity::current_user);
+    let Some(user) = user else {
+        return Err(AppError::permission("insufficient_privilege".to_string(), "unauthorized: no principal".to_string()).into());
+    };
+    let token_roles = ctx.principal.as_ref().map(|p| p.roles.clone()).unwrap_or_default();
+    let mut defaults = crate::system::current_query_defaults();
+    if let Some(db) = &ctx.database { defaults.current_database = db.clone(); }
+    let db_root = store.root_path().to_string_lossy().to_string();
+    let role = activity::current_role();
+    if statement_allowed(store, &db_root, &user, role.as_deref(), &token_roles, cmd, &defaults).await { return Ok(()); }
+    Err(AppError::permission("insufficient_privilege".to_string(), format!("permission denied for \"{}\"", role.as_deref().unwrap_or(&user))).into())
+}
@@ -0,0 +1,85 @@
+//! exec_cdc
+//! --------
+//! SQL surface for change data capture:
+//! - table_changes(table [, since])  -- changelog rows with `seq > since`
+//!
+//! `since` is either a sequence number (exclusive) or a quoted RFC 3339 timestamp
+//! (inclusive, compared against the event time). Output columns are `_seq`, `_ts`,
+//! `_op` followed by the changed row's columns.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+
+use crate::server::exec::exec_copy::maps_to_df;
+use crate::storage::cdc::ChangeEvent;
+use crate::tprintln;
+
+fn strip_quotes(x: &str) -> String {
+    let t = x.trim();
+    if (t.starts_with('"') && t.ends_with('"')) || (t.starts_with('\'') && t.ends_with('\'')) {
+        if t.len() >= 2 { return t[1..t.len()-1].to_string(); }
+    }
+    t.to_string()
+}
+
+/// Resolve a table argument the same way DML does (time tables keep their suffix).
+pub fn qualify_cdc_table(table: &str) -> String {
+    let qd = crate::system::current_query_defaults();
+    if table.to_ascii_lowercase().ends_with(".time") {
+        crate::ident::qualify_time_ident(table, &qd)
+    } else {
+        crate::ident::qualify_regular_ident(table, &qd)
+    }
+}
+
+/// Load changelog events for `table` after the `since` marker (seq number or RFC 3339 time).
+pub fn load_changes(store: &crate::storage::SharedStore, table: &str, since: Option<&str>) -> Result<Vec<ChangeEvent>> {
+    let tableq = qualify_cdc_table(table);
+    let (since_seq, since_ts) = match since.map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        None => (0u64, None),
+        Some(s) => match s.parse::<u64>() {
+            Ok(n) => (n, None),
+            Err(_) => {
+                let ts = chrono::DateTime::parse_from_rfc3339(s)
+                    .map_err(|_| anyhow!("table_changes: since must be a sequence number or RFC 3339 timestamp"))?
+                    .timestamp_millis();
+                (0, Some(ts))
+            }
+        },
+    };
+    let g = store.0.lock();
+    if !g.is_cdc_enabled(&tableq) && g.read_changes(&tableq, 0)?.is_empty() {
+        anyhow::bail!("CDC is not enabled for table: {}", tableq);
+    }
+    let mut evs = g.read_changes(&tableq, since_seq)?;
+    if let Some(ts) = since_ts { evs.retain(|e| e.ts >= ts); }
+    Ok(evs)
+}
+
+pub fn try_cdc_tvf(store: &crate::storage::SharedStore, raw: &str) -> Result<Option<DataFrame>> {
+    let s = raw.trim();
+    if !s.to_ascii_lowercase().starts_with("table_changes(") || !s.ends_with(')') { return Ok(None); }
+    let inside = &s["table_changes(".len()..s.len()-1];
+    let args: Vec<String> = inside.splitn(2, ',').map(strip_quotes).collect();
+    let table = args.first().filter(|t| !t.is_empty()).ok_or_else(|| anyhow!("table_changes(table [, since]) requires a table"))?;
+    let evs = load_changes(store, table, args.get(1).map(|x| x.as_str()))?;
+    let rows: Vec<serde_json::Map<String, serde_json::Value>> = evs.iter().map(|e| e.row.clone()).collect();
+    let mut cols: Vec<Column> = vec![
+        Series::new("_seq".into(), evs.iter().map(|e| e.seq as i64).collect::<Vec<i64>>()).into(),
+        Series::new("_ts".into(), evs.iter().map(|e| e.ts).collect::<Vec<i64>>()).into(),
+        Series::new("_op".into(), evs.iter().map(|e| e.op.as_str().to_string()).collect::<Vec<String>>()).into(),
+    ];
+    if !rows.is_empty() {
+        let body = maps_to_df(&rows, &HashMap::new())?;
+        // Row columns never shadow the event metadata columns
+        for c in body.get_columns() {
+            if matches!(c.name().as_str(), "_seq" | "_ts" | "_op") { continue; }
+            cols.push(c.clone());
+        }
+    }
+    let df = DataFrame::new(cols)?;
+    tprintln!("[cdc.tvf] table_changes('{}') -> rows={}", table, df.height());
+    Ok(Some(df))
+}
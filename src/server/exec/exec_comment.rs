@@ -0,0 +1,41 @@
+//! exec_comment
+//! ------------
+//! COMMENT ON TABLE/COLUMN/VIEW/FUNCTION: validate the target and store the comment
+//! (see `storage::comments`), where `pg_description` and `information_schema.columns` read it.
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::error::AppError;
+use crate::server::query::CommentObject;
+use crate::storage::SharedStore;
+
+pub fn handle_comment_on(store: &SharedStore, object: &CommentObject, comment: Option<&str>) -> Result<serde_json::Value> {
+    match object {
+        CommentObject::Table(table) => {
+            store.0.lock().set_comment(table, None, comment)?;
+        }
+        CommentObject::Column { table, column } => {
+            let guard = store.0.lock();
+            let (cols, _locks) = guard.load_schema_with_locks(table)?;
+            let is_time_col = column == "_time" && guard.is_time_table(table);
+            if !cols.contains_key(column) && !is_time_col {
+                return Err(AppError::NotFound { code: "undefined_column".into(), message: format!("column \"{}\" of relation \"{}\" does not exist", column, table) }.into());
+            }
+            guard.set_comment(table, Some(column), comment)?;
+        }
+        CommentObject::View(view) => {
+            crate::server::exec::exec_views::set_view_comment(store, view, comment)?;
+        }
+        CommentObject::Function(name) => {
+            let known = crate::scripts::get_script_registry().map(|r| r.has_function(name)).unwrap_or(false);
+            if !known {
+                return Err(AppError::NotFound { code: "undefined_function".into(), message: format!("function {} does not exist", name) }.into());
+            }
+            let root = store.0.lock().root_path().clone();
+            crate::storage::comments::set_function_comment(&root, name, comment)?;
+        }
+    }
+    info!(target: "clarium::ddl", "COMMENT ON {:?}: {}", object, if comment.is_some() { "set" } else { "removed" });
+    Ok(serde_json::json!({"status": "ok"}))
+}
@@ -4,6 +4,7 @@ use regex::Regex;
 
 
 use crate::server::query::query_common::{ArithExpr, ArithOp, ArithTerm, CompOp, WhereExpr, SqlType, DateFunc, DatePart, StrSliceBound};
+use crate::storage::decimal;
 
 
 #[inline]
@@ -133,6 +134,20 @@ pub fn build_where_expr(w: &WhereExpr, ctx: &crate::server::data_context::DataCo
                 }
             }
 
+            // Under a collation, text compares by collation sort key; LIKE keeps byte semantics
+            if let Some(c) = comparison_collation(left, right, ctx) {
+                let key = |a: &ArithExpr| c.key_expr(build_arith_expr(a, ctx));
+                match op {
+                    CompOp::Gt => return key(left).gt(key(right)),
+                    CompOp::Ge => return key(left).gt_eq(key(right)),
+                    CompOp::Lt => return key(left).lt(key(right)),
+                    CompOp::Le => return key(left).lt_eq(key(right)),
+                    CompOp::Eq => return key(left).eq(key(right)),
+                    CompOp::Ne => return key(left).neq(key(right)),
+                    CompOp::Like | CompOp::NotLike => {}
+                }
+            }
+
             let l = build_arith_expr(left, ctx);
             let r = build_arith_expr(right, ctx);
             match op {
@@ -224,11 +239,86 @@ pub fn build_where_expr(w: &WhereExpr, ctx: &crate::server::data_context::DataCo
     }
 }
 
+/// Collation a comparison runs under: an explicit COLLATE on either side, else the column
+/// collation of a compared column unless the other side is a number. None means byte order.
+fn comparison_collation(left: &ArithExpr, right: &ArithExpr, ctx: &crate::server::data_context::DataContext) -> Option<crate::server::collation::Collation> {
+    use crate::server::collation::Collation;
+    let explicit = |a: &ArithExpr| match a { ArithExpr::Collate { collation, .. } => Collation::lookup(collation), _ => None };
+    let c = explicit(left).or_else(|| explicit(right)).or_else(|| {
+        let numeric = |a: &ArithExpr| matches!(a, ArithExpr::Term(ArithTerm::Number(_)));
+        if numeric(left) || numeric(right) { return None; }
+        let column = |a: &ArithExpr| match a { ArithExpr::Term(ArithTerm::Col { name, .. }) => ctx.column_collation(name), _ => None };
+        column(left).or_else(|| column(right))
+    })?;
+    (!c.is_bytewise()).then_some(c)
+}
+
 // Helper to pattern-match right side number without cloning left
 fn left_maybe_number(right: &ArithExpr) -> &ArithExpr {
     right
 }
 
+fn decimal_op(op: &ArithOp) -> decimal::Op {
+    match op {
+        ArithOp::Add => decimal::Op::Add,
+        ArithOp::Sub => decimal::Op::Sub,
+        ArithOp::Mul => decimal::Op::Mul,
+        ArithOp::Div => decimal::Op::Div,
+    }
+}
+
+/// Precision and scale of a `NUMERIC[(p[, s])]` cast target, clamped to what Decimal128 holds.
+fn numeric_precision_scale(ps: Option<(i32, i32)>) -> (usize, usize) {
+    match ps {
+        Some((p, s)) => {
+            let p = (p.max(1) as usize).min(decimal::MAX_PRECISION);
+            (p, (s.max(0) as usize).min(p))
+        }
+        None => (decimal::DEFAULT_PRECISION, decimal::DEFAULT_SCALE),
+    }
+}
+
+/// Type of `a` known before execution, used to pick exact NUMERIC arithmetic: source columns
+/// take their schema.json type and other arithmetic yields Float64. None when unknown.
+fn static_numeric_type(a: &ArithExpr, ctx: &crate::server::data_context::DataContext) -> Option<DataType> {
+    match a {
+        ArithExpr::Term(ArithTerm::Number(n)) => Some(if n.fract() == 0.0 { DataType::Int64 } else { DataType::Float64 }),
+        ArithExpr::Term(ArithTerm::Col { name, previous: false }) => ctx.column_dtype(name),
+        ArithExpr::Cast { ty: SqlType::Numeric(ps), .. } => {
+            let (p, sc) = numeric_precision_scale(*ps);
+            Some(decimal::dtype(p, sc))
+        }
+        ArithExpr::Cast { ty: SqlType::SmallInt | SqlType::Integer | SqlType::BigInt, .. } => Some(DataType::Int64),
+        ArithExpr::Collate { expr, .. } => static_numeric_type(expr, ctx),
+        ArithExpr::BinOp { left, op, right } => Some(
+            decimal_operand_types(left, right, ctx)
+                .and_then(|(l, r)| decimal::arith_dtype(&l, &r, decimal_op(op)))
+                .unwrap_or(DataType::Float64),
+        ),
+        _ => None,
+    }
+}
+
+/// Operand types of `left op right` when at least one side is NUMERIC. Next to a NUMERIC, a
+/// literal such as `1.1` counts as the exact decimal it spells rather than a float.
+fn decimal_operand_types(left: &ArithExpr, right: &ArithExpr, ctx: &crate::server::data_context::DataContext) -> Option<(DataType, DataType)> {
+    let (l, r) = (static_numeric_type(left, ctx)?, static_numeric_type(right, ctx)?);
+    let is_dec = |d: &DataType| matches!(d, DataType::Decimal(..));
+    if !is_dec(&l) && !is_dec(&r) { return None; }
+    let literal = |a: &ArithExpr| match a {
+        ArithExpr::Term(ArithTerm::Number(n)) if n.fract() != 0.0 => {
+            let text = n.abs().to_string();
+            let scale = text.split_once('.').map(|(_, f)| f.len()).unwrap_or(0);
+            let digits = text.chars().filter(|c| c.is_ascii_digit()).count();
+            Some(decimal::dtype(digits.max(scale).max(1).min(decimal::MAX_PRECISION), scale.min(decimal::MAX_PRECISION)))
+        }
+        _ => None,
+    };
+    let l = if is_dec(&r) { literal(left).unwrap_or(l) } else { l };
+    let r = if is_dec(&l) { literal(right).unwrap_or(r) } else { r };
+    Some((l, r))
+}
+
 pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataContext) -> Expr {
     match a {
         ArithExpr::Term(ArithTerm::Number(n)) => lit(*n),
@@ -242,7 +332,15 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
             match ty {
                 SqlType::Boolean => inner.cast(DataType::Boolean),
                 SqlType::SmallInt | SqlType::Integer | SqlType::BigInt => inner.cast(DataType::Int64),
-                SqlType::Real | SqlType::Double | SqlType::Numeric(_) => inner.cast(DataType::Float64),
+                SqlType::Real | SqlType::Double => inner.cast(DataType::Float64),
+                SqlType::Numeric(ps) => {
+                    let (p, sc) = numeric_precision_scale(*ps);
+                    let out = decimal::dtype(p, sc);
+                    inner.map(
+                        move |c: Column| decimal::cast_column(&c, p, sc),
+                        move |_schema, field| Ok(Field::new(field.name().clone(), out.clone())),
+                    )
+                }
                 SqlType::Text | SqlType::Varchar(_) | SqlType::Char(_) | SqlType::Uuid | SqlType::Json | SqlType::Jsonb | SqlType::TimeTz => {
                     // Cast-to-text semantics: format numbers without trailing .0 when integral,
                     // otherwise preserve normal stringification. This mirrors CONCAT formatting.
@@ -457,6 +555,17 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
         ArithExpr::BinOp { left, op, right } => {
             let l = build_arith_expr(left, ctx);
             let r = build_arith_expr(right, ctx);
+            // NUMERIC operands keep exact arithmetic at the scale their types call for
+            if let Some((lt, rt)) = decimal_operand_types(left, right, ctx) {
+                let dop = decimal_op(op);
+                if let Some(out) = decimal::arith_dtype(&lt, &rt, dop) {
+                    return l.map_many(
+                        move |cols: &mut [Column]| decimal::binop(&cols[0], &cols[1], &lt, &rt, dop),
+                        &[r],
+                        move |_schema, fields: &[Field]| Ok(Field::new(fields[0].name().clone(), out.clone())),
+                    );
+                }
+            }
             // Coerce numeric arithmetic operands to Float64 to tolerate mixed numeric
             // input types and stringified numerics from UDFs when metadata is missing.
             let lf = l.clone().cast(DataType::Float64);
@@ -805,6 +914,30 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                 );
             }
 
+            // Built-in: pg_backend_pid() -> pid of the session running the statement (0 outside a session)
+            if name_lc == "pg_backend_pid" && args.is_empty() {
+                return lit(crate::server::activity::current_pid().unwrap_or(0));
+            }
+
+            // Built-in: pg_cancel_backend(pid) / pg_terminate_backend(pid)
+            // Signal the backend and return whether it exists.
+            if (name_lc == "pg_cancel_backend" || name_lc == "pg_terminate_backend") && args.len() == 1 {
+                let arg_expr = build_arith_expr(&args[0], ctx).cast(DataType::Int64);
+                let terminate = name_lc == "pg_terminate_backend";
+                let out_name = if terminate { "pg_terminate_backend" } else { "pg_cancel_backend" };
+                return arg_expr.map(
+                    move |col: Column| {
+                        let ca = col.as_materialized_series().i64()?.clone();
+                        let out: Vec<Option<bool>> = ca.into_iter().map(|pid| pid.map(|p| {
+                            if terminate { crate::server::activity::terminate_backend(p as i32) }
+                            else { crate::server::activity::cancel_backend(p as i32) }
+                        })).collect();
+                        Ok(Series::new(out_name.into(), out).into_column())
+                    },
+                    move |_schema, _field| Ok(Field::new(out_name.into(), DataType::Boolean))
+                );
+            }
+
             // Use the query-scoped registry from DataContext.
             // Clone the registry Arc so the closure can own it (avoids lifetime issues).
             // This ensures stable UDF resolution throughout query execution,
@@ -835,7 +968,7 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                 let udf_name_eval = name_lc.clone();
                 if let Some(r) = reg.as_ref() {
                     let ctx_info = crate::scripts::ContextInfo::from_data_context(ctx);
-                    let out_res: anyhow::Result<polars::prelude::Expr> = r.with_lua_function(&udf_name_eval, |lua, func| {
+                    let out_res: anyhow::Result<polars::prelude::Expr> = crate::script_stats::timed(&udf_name_eval, 1, |_| r.with_lua_function(&udf_name_eval, |lua, func| {
                         use mlua::Value as LVal;
                         use mlua::MultiValue;
                         // Register Rust context accessor function for on-demand access
@@ -849,7 +982,7 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                             _ => match outv { LVal::String(s) => lit(s.to_str()?.to_string()), LVal::Nil => lit(polars::prelude::Null {}), _ => lit(polars::prelude::Null {}) },
                         };
                         Ok(expr)
-                    });
+                    }));
                     return out_res.unwrap_or_else(|_| lit(polars::prelude::Null {}));
                 } else {
                     return lit(polars::prelude::Null {});
@@ -897,7 +1030,8 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
 
                     // Execute UDF once per row using a single Lua state and resolved function
                     if let Some(r) = reg.as_ref() {
-                        let out_col: Column = r
+                        // One invocation per row; rows that fail under null_on_error still count as errors
+                        let out_col: Column = crate::script_stats::timed(&udf_name_eval, len as u64, |errors| r
                             .with_lua_function(&udf_name_eval, |lua, func| {
                                 use mlua::Value as LVal;
                                 use mlua::MultiValue;
@@ -941,6 +1075,7 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                                             mvals.push_front(lv);
                                         }
                                         let outv_result = func.call::<_, LVal>(mvals);
+                                        if outv_result.is_err() { *errors += 1; }
                                         let outv = if null_on_err {
                                             outv_result.unwrap_or(LVal::Nil)
                                         } else {
@@ -987,6 +1122,7 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                                             mvals.push_front(lv);
                                         }
                                         let outv_result = func.call::<_, LVal>(mvals);
+                                        if outv_result.is_err() { *errors += 1; }
                                         let outv = if null_on_err {
                                             outv_result.unwrap_or(LVal::Nil)
                                         } else {
@@ -1046,6 +1182,7 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                                             preview_printed += 1;
                                         }
                                         let outv_result = func.call::<_, LVal>(mvals);
+                                        if outv_result.is_err() { *errors += 1; }
                                         let outv = if null_on_err {
                                             outv_result.unwrap_or(LVal::Nil)
                                         } else {
@@ -1112,6 +1249,7 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                                             mvals.push_front(lv);
                                         }
                                         let outv_result = func.call::<_, LVal>(mvals);
+                                        if outv_result.is_err() { *errors += 1; }
                                         let outv = if null_on_err {
                                             outv_result.unwrap_or(LVal::Nil)
                                         } else {
@@ -1127,7 +1265,7 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
                                     Ok(s.into_column())
                                 }
                             }
-                            })
+                            }))
                             .map_err(|e| polars::error::PolarsError::ComputeError(e.to_string().into()))?;
                         Ok(out_col)
                     } else {
@@ -1317,6 +1455,8 @@ pub fn build_arith_expr(a: &ArithExpr, ctx: &crate::server::data_context::DataCo
         ArithExpr::Predicate(w) => {
             build_where_expr(w, ctx)
         }
+        // A collation only changes how the value orders and compares; see build_where_expr
+        ArithExpr::Collate { expr, .. } => build_arith_expr(expr, ctx),
         ArithExpr::Case { when_clauses, else_expr } => {
             // Build a CASE expression using nested when().then().otherwise()
             // Process from last to first to build nested structure
@@ -1380,7 +1520,7 @@ pub fn collect_from_arith(a: &ArithExpr, out: &mut Vec<String>) {
         ArithExpr::Term(ArithTerm::Number(_)) => {},
         ArithExpr::Term(ArithTerm::Str(_)) => {},
         ArithExpr::Term(ArithTerm::Null) => {},
-        ArithExpr::Cast { expr, .. } => { collect_from_arith(expr, out); }
+        ArithExpr::Cast { expr, .. } | ArithExpr::Collate { expr, .. } => { collect_from_arith(expr, out); }
         ArithExpr::BinOp { left, right, .. } => { collect_from_arith(left, out); collect_from_arith(right, out); }
         ArithExpr::Func(df) => {            
             match df {
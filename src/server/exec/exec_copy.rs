@@ -0,0 +1,559 @@
+//! exec_copy
+//! ---------
+//! COPY ... FROM bulk import. Payloads are decoded incrementally and flushed to
+//! storage every `batch_size` rows: time tables append one Parquet chunk per
+//! batch via `write_records`, regular tables go through the INSERT ... SELECT
+//! append path so primary keys and partitions are honored.
+//!
+//! `CopyIngest` is shared by the server-side COPY FROM '<path>'/'<url>' command
+//! and the pgwire CopyIn sub-protocol (COPY ... FROM STDIN). As in PostgreSQL the
+//! target table must exist, and a value that does not parse as its column's
+//! declared type fails the COPY with its line number instead of becoming NULL.
+//! BINARY payloads are decoded tuple by tuple as they arrive; Parquet needs its
+//! footer, so the payload is buffered up to `limits.ingest_max_body_mb`.
+//!
+//! `CopyExport` feeds the pgwire CopyOut sub-protocol (COPY ... TO STDOUT): tables
+//! are read one stored chunk at a time, so an export never holds the whole table.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use polars::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::error::AppError;
+use crate::server::query::{CopyFormat, CopyOptions, CopySource};
+use crate::storage::native::anyvalue_to_json;
+use crate::storage::{Record, SharedStore};
+
+pub(crate) const PGCOPY_SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// Streaming COPY decoder bound to one target table.
+pub struct CopyIngest {
+    store: SharedStore,
+    table: String,
+    is_time: bool,
+    columns: Vec<String>,
+    options: CopyOptions,
+    schema: HashMap<String, DataType>,
+    // Undecoded bytes: partial lines/tuples, or the whole payload for Parquet
+    pending: Vec<u8>,
+    scan_pos: usize,
+    in_quotes: bool,
+    header_pending: bool,
+    // BINARY: header consumed / trailer seen
+    binary_started: bool,
+    binary_done: bool,
+    // Input line (row, for Parquet/BINARY) of the last decoded record
+    line_no: usize,
+    batch: Vec<Map<String, Value>>,
+    batch_lines: Vec<usize>,
+    rows: usize,
+}
+
+impl CopyIngest {
+    pub fn new(store: &SharedStore, table: &str, columns: Vec<String>, options: CopyOptions) -> Result<Self> {
+        crate::server::replication::ensure_writable()?;
+        let qd = crate::system::current_query_defaults();
+        let table = if table.to_ascii_lowercase().ends_with(".time") {
+            crate::ident::qualify_time_ident(table, &qd)
+        } else {
+            crate::ident::qualify_regular_ident(table, &qd)
+        };
+        let (is_time, schema) = {
+            let g = store.0.lock();
+            if !g.schema_path(&table).exists() {
+                return Err(AppError::NotFound { code: "undefined_table".into(), message: format!("relation \"{}\" does not exist", table) }.into());
+            }
+            (g.is_time_table(&table), g.load_schema_with_locks(&table).map(|(s, _)| s).unwrap_or_default())
+        };
+        let header_pending = options.header && matches!(options.format, CopyFormat::Csv | CopyFormat::Text);
+        Ok(Self {
+            store: store.clone(),
+            table,
+            is_time,
+            columns,
+            options,
+            schema,
+            pending: Vec::new(),
+            scan_pos: 0,
+            in_quotes: false,
+            header_pending,
+            binary_started: false,
+            binary_done: false,
+            line_no: 0,
+            batch: Vec::new(),
+            batch_lines: Vec::new(),
+            rows: 0,
+        })
+    }
+
+    /// Feed the next slice of the payload. Line formats and BINARY are decoded and
+    /// flushed as soon as complete rows are available; Parquet is buffered.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(bytes);
+        match self.options.format {
+            CopyFormat::Parquet => {
+                let max = (crate::config::current().limits.ingest_max_body_mb as usize).saturating_mul(1024 * 1024);
+                if self.pending.len() > max {
+                    return Err(AppError::user(
+                        "configuration_limit_exceeded".to_string(),
+                        format!("COPY: Parquet payload exceeds limits.ingest_max_body_mb ({} bytes); split the file or use CSV/NDJSON", max),
+                    ).into());
+                }
+                Ok(())
+            }
+            CopyFormat::Binary => self.decode_binary(false),
+            _ => self.drain_lines(false),
+        }
+    }
+
+    /// Decode whatever remains, flush the final batch and return the number of rows imported.
+    pub fn finish(mut self) -> Result<usize> {
+        match self.options.format {
+            CopyFormat::Parquet => self.decode_parquet()?,
+            CopyFormat::Binary => self.decode_binary(true)?,
+            _ => self.drain_lines(true)?,
+        }
+        self.flush()?;
+        crate::tprintln!("[COPY] imported {} rows into '{}'", self.rows, self.table);
+        Ok(self.rows)
+    }
+
+    fn drain_lines(&mut self, at_end: bool) -> Result<()> {
+        let csv = self.options.format == CopyFormat::Csv;
+        let mut lines: Vec<Vec<u8>> = Vec::new();
+        let mut start = 0usize;
+        for i in self.scan_pos..self.pending.len() {
+            let b = self.pending[i];
+            if csv && b == b'"' { self.in_quotes = !self.in_quotes; }
+            else if b == b'\n' && !self.in_quotes {
+                lines.push(self.pending[start..i].to_vec());
+                start = i + 1;
+            }
+        }
+        self.pending.drain(..start);
+        self.scan_pos = self.pending.len();
+        if at_end && !self.pending.is_empty() {
+            if self.in_quotes { bail!("COPY: unterminated quoted field at end of input"); }
+            lines.push(std::mem::take(&mut self.pending));
+            self.scan_pos = 0;
+        }
+        for l in lines { self.handle_line(&l)?; }
+        Ok(())
+    }
+
+    fn handle_line(&mut self, raw: &[u8]) -> Result<()> {
+        self.line_no += 1;
+        let line = std::str::from_utf8(raw).map_err(|_| anyhow!("COPY: invalid UTF-8 on line {}", self.line_no))?;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        // Blank lines and the legacy end-of-data marker carry no rows
+        if line.trim().is_empty() || line == "\\." { return Ok(()); }
+        if self.header_pending {
+            self.header_pending = false;
+            if self.columns.is_empty() {
+                self.columns = split_csv_fields(line, self.options.delimiter, "")
+                    .into_iter()
+                    .map(|f| f.unwrap_or_default().trim().to_string())
+                    .collect();
+            }
+            return Ok(());
+        }
+        let row = match self.options.format {
+            CopyFormat::Ndjson => match serde_json::from_str::<Value>(line)? {
+                Value::Object(m) => {
+                    if self.columns.is_empty() { m } else { m.into_iter().filter(|(k, _)| self.columns.contains(k)).collect() }
+                }
+                _ => bail!("COPY: NDJSON line {} is not an object", self.line_no),
+            },
+            CopyFormat::Csv => {
+                let fields = split_csv_fields(line, self.options.delimiter, &self.options.null_str);
+                self.row_from_fields(fields)?
+            }
+            _ => {
+                let fields = line
+                    .split(self.options.delimiter)
+                    .map(|f| if f == self.options.null_str { None } else { Some(unescape_text(f)) })
+                    .collect();
+                self.row_from_fields(fields)?
+            }
+        };
+        self.push_row(row)
+    }
+
+    fn row_from_fields(&self, fields: Vec<Option<String>>) -> Result<Map<String, Value>> {
+        if self.columns.is_empty() { bail!("COPY without HEADER requires a column list"); }
+        if fields.len() != self.columns.len() {
+            bail!("COPY: line {} has {} fields, expected {}", self.line_no, fields.len(), self.columns.len());
+        }
+        let mut m = Map::new();
+        for (name, f) in self.columns.iter().zip(fields) {
+            m.insert(name.clone(), match f { Some(s) => self.typed_value(name, s), None => Value::Null });
+        }
+        Ok(m)
+    }
+
+    /// Convert a text field to JSON, honoring the declared column type when known.
+    fn typed_value(&self, col: &str, s: String) -> Value {
+        match self.schema.get(col) {
+            Some(DataType::String) => Value::String(s),
+            _ => {
+                if let Ok(i) = s.trim().parse::<i64>() { return Value::from(i); }
+                if let Ok(f) = s.trim().parse::<f64>() {
+                    if let Some(n) = serde_json::Number::from_f64(f) { return Value::Number(n); }
+                }
+                Value::String(s)
+            }
+        }
+    }
+
+    fn push_row(&mut self, row: Map<String, Value>) -> Result<()> {
+        self.batch.push(row);
+        self.batch_lines.push(self.line_no);
+        if self.batch.len() >= self.options.batch_size { self.flush()?; }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.batch.is_empty() { return Ok(()); }
+        let batch = std::mem::take(&mut self.batch);
+        let lines = std::mem::take(&mut self.batch_lines);
+        let n = batch.len();
+        if self.is_time {
+            let mut records: Vec<Record> = Vec::with_capacity(n);
+            for mut m in batch {
+                let t = m.remove("_time").ok_or_else(|| anyhow!("COPY into time table requires a _time column"))?;
+                records.push(Record { _time: time_value_ms(&t)?, sensors: m });
+            }
+            crate::server::quota::charge_ingest(crate::server::quota::records_size(&records))?;
+            self.store.0.lock().write_records(&self.table, &records)?;
+        } else {
+            let unit = if matches!(self.options.format, CopyFormat::Parquet | CopyFormat::Binary) { "row" } else { "line" };
+            let df = build_df(&batch, &self.schema, |i| format!("COPY: {} {}", unit, lines[i]))?;
+            crate::server::exec::exec_insert::handle_insert_from_df(&self.store, self.table.clone(), Vec::new(), df)?;
+        }
+        self.rows += n;
+        crate::tprintln!("[COPY] flushed batch rows={} total={} table='{}'", n, self.rows, self.table);
+        Ok(())
+    }
+
+    fn decode_parquet(&mut self) -> Result<()> {
+        let bytes = std::mem::take(&mut self.pending);
+        let mut df = ParquetReader::new(std::io::Cursor::new(bytes)).finish()?;
+        if !self.columns.is_empty() {
+            let keep: Vec<String> = self.columns.iter().filter(|c| df.get_column_names().iter().any(|n| n.as_str() == c.as_str())).cloned().collect();
+            df = df.select(&keep)?;
+        }
+        let names: Vec<String> = df.get_column_names().iter().map(|s| s.to_string()).collect();
+        for i in 0..df.height() {
+            let mut m = Map::new();
+            for n in &names {
+                m.insert(n.clone(), anyvalue_to_json(df.column(n.as_str())?.get(i)?));
+            }
+            self.line_no += 1;
+            self.push_row(m)?;
+        }
+        Ok(())
+    }
+
+    /// Decode the PostgreSQL binary COPY format using the table schema for field widths.
+    /// Complete tuples are taken from `pending` as they arrive; an incomplete one waits
+    /// for the next push and is an error only at the end of the input.
+    fn decode_binary(&mut self, at_end: bool) -> Result<()> {
+        if self.columns.is_empty() { bail!("COPY ... BINARY requires a column list"); }
+        let buf = std::mem::take(&mut self.pending);
+        let mut pos = 0usize;
+        if !self.binary_started {
+            let hlen = PGCOPY_SIGNATURE.len() + 8; // signature + flags + extension length
+            if buf.len() >= PGCOPY_SIGNATURE.len() && &buf[..PGCOPY_SIGNATURE.len()] != PGCOPY_SIGNATURE {
+                bail!("COPY BINARY: missing PGCOPY signature");
+            }
+            let ext_len = if buf.len() >= hlen { read_be_i32(&buf, PGCOPY_SIGNATURE.len() + 4)?.max(0) as usize } else { 0 };
+            if buf.len() < hlen || buf.len() < hlen + ext_len {
+                if at_end { bail!("COPY BINARY: missing PGCOPY signature"); }
+                self.pending = buf;
+                return Ok(());
+            }
+            pos = hlen + ext_len;
+            self.binary_started = true;
+        }
+        while !self.binary_done {
+            match self.binary_tuple(&buf, pos)? {
+                Some((next, Some(row))) => {
+                    pos = next;
+                    self.line_no += 1;
+                    self.push_row(row)?;
+                }
+                Some((next, None)) => { pos = next; self.binary_done = true; }
+                None => break,
+            }
+        }
+        if at_end && !self.binary_done { bail!("COPY BINARY: unexpected end of data"); }
+        if !self.binary_done { self.pending = buf[pos..].to_vec(); }
+        Ok(())
+    }
+
+    /// Parse the tuple at `pos`: `None` when it is not complete yet, otherwise the position
+    /// after it and its row (`None` for the trailer).
+    #[allow(clippy::type_complexity)]
+    fn binary_tuple(&self, buf: &[u8], pos: usize) -> Result<Option<(usize, Option<Map<String, Value>>)>> {
+        let Some(b) = buf.get(pos..pos + 2) else { return Ok(None) };
+        let nfields = i16::from_be_bytes(b.try_into()?);
+        let mut p = pos + 2;
+        if nfields == -1 { return Ok(Some((p, None))); }
+        if nfields as usize != self.columns.len() {
+            bail!("COPY BINARY: tuple has {} fields, expected {}", nfields, self.columns.len());
+        }
+        let mut m = Map::new();
+        for c in &self.columns {
+            let Some(b) = buf.get(p..p + 4) else { return Ok(None) };
+            let len = i32::from_be_bytes(b.try_into()?);
+            p += 4;
+            if len < 0 { m.insert(c.clone(), Value::Null); continue; }
+            let end = p + len as usize;
+            let Some(field) = buf.get(p..end) else { return Ok(None) };
+            p = end;
+            m.insert(c.clone(), self.binary_value(c, field)?);
+        }
+        Ok(Some((p, Some(m))))
+    }
+
+    fn binary_value(&self, col: &str, f: &[u8]) -> Result<Value> {
+        let int = |f: &[u8]| -> Option<i64> {
+            match f.len() {
+                8 => Some(i64::from_be_bytes(f.try_into().ok()?)),
+                4 => Some(i32::from_be_bytes(f.try_into().ok()?) as i64),
+                2 => Some(i16::from_be_bytes(f.try_into().ok()?) as i64),
+                _ => None,
+            }
+        };
+        let dt = if col == "_time" { Some(DataType::Int64) } else { self.schema.get(col).cloned() };
+        Ok(match dt {
+            Some(DataType::Int64) => Value::from(int(f).ok_or_else(|| anyhow!("COPY BINARY: bad integer width for '{}'", col))?),
+            Some(DataType::Float64) => {
+                let v = match f.len() {
+                    8 => f64::from_be_bytes(f.try_into()?),
+                    4 => f32::from_be_bytes(f.try_into()?) as f64,
+                    _ => bail!("COPY BINARY: bad float width for '{}'", col),
+                };
+                serde_json::Number::from_f64(v).map(Value::Number).unwrap_or(Value::Null)
+            }
+            _ => Value::String(String::from_utf8(f.to_vec()).map_err(|_| anyhow!("COPY BINARY: column '{}' is not text", col))?),
+        })
+    }
+}
+
+fn read_be_i32(buf: &[u8], pos: usize) -> Result<i32> {
+    let b = buf.get(pos..pos + 4).ok_or_else(|| anyhow!("COPY BINARY: unexpected end of data"))?;
+    Ok(i32::from_be_bytes(b.try_into()?))
+}
+
+/// Split one CSV record honoring double quotes ("" escapes a quote). Unquoted
+/// fields equal to `null_str` become None; quoted fields are never NULL.
+pub(crate) fn split_csv_fields(line: &str, delim: char, null_str: &str) -> Vec<Option<String>> {
+    let mut out: Vec<Option<String>> = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+    let mut in_q = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if in_q {
+            if ch == '"' {
+                if chars.peek() == Some(&'"') { cur.push('"'); chars.next(); } else { in_q = false; }
+            } else { cur.push(ch); }
+        } else if ch == '"' {
+            in_q = true;
+            quoted = true;
+        } else if ch == delim {
+            out.push(if !quoted && cur == null_str { None } else { Some(std::mem::take(&mut cur)) });
+            cur.clear();
+            quoted = false;
+        } else { cur.push(ch); }
+    }
+    out.push(if !quoted && cur == null_str { None } else { Some(cur) });
+    out
+}
+
+/// Undo PostgreSQL text-format backslash escapes.
+fn unescape_text(s: &str) -> String {
+    if !s.contains('\\') { return s.to_string(); }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' { out.push(c); continue; }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Accept epoch milliseconds (integer/float) or an RFC 3339 timestamp for `_time`.
+fn time_value_ms(v: &Value) -> Result<i64> {
+    match v {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)).ok_or_else(|| anyhow!("invalid _time value")),
+        Value::String(s) => {
+            if let Ok(i) = s.trim().parse::<i64>() { return Ok(i); }
+            chrono::DateTime::parse_from_rfc3339(s.trim())
+                .map(|d| d.timestamp_millis())
+                .map_err(|_| anyhow!("invalid _time value: {}", s))
+        }
+        _ => bail!("_time cannot be NULL in time table"),
+    }
+}
+
+/// Build a DataFrame from row maps for the regular-table append path. Column
+/// types follow the table schema when declared, otherwise they are inferred. A value
+/// that does not parse as its declared type is an error naming its row.
+pub(crate) fn maps_to_df(rows: &[Map<String, Value>], schema: &HashMap<String, DataType>) -> Result<DataFrame> {
+    build_df(rows, schema, |i| format!("row {}", i + 1))
+}
+
+/// [`maps_to_df`] with `at(i)` describing where row `i` came from in error messages.
+fn build_df(rows: &[Map<String, Value>], schema: &HashMap<String, DataType>, at: impl Fn(usize) -> String) -> Result<DataFrame> {
+    let mut names: Vec<String> = Vec::new();
+    for r in rows { for k in r.keys() { if !names.contains(k) { names.push(k.clone()); } } }
+    let mut cols: Vec<Column> = Vec::with_capacity(names.len());
+    for n in &names {
+        let vals: Vec<&Value> = rows.iter().map(|r| r.get(n).unwrap_or(&Value::Null)).collect();
+        let dt = schema.get(n).cloned().unwrap_or_else(|| {
+            if vals.iter().all(|v| v.is_null() || v.is_i64()) { DataType::Int64 }
+            else if vals.iter().all(|v| v.is_null() || v.is_number()) { DataType::Float64 }
+            else { DataType::String }
+        });
+        // Parse every non-null value, failing on the first that does not fit
+        fn parse_all<T>(vals: &[&Value], f: impl Fn(&Value) -> Option<T>) -> std::result::Result<Vec<Option<T>>, usize> {
+            vals.iter().enumerate().map(|(i, v)| if v.is_null() { Ok(None) } else { f(v).map(Some).ok_or(i) }).collect()
+        }
+        let invalid = |i: usize, ty: &str| anyhow!("{}: invalid input for column '{}' of type {}: {}", at(i), n, ty, vals[i]);
+        if matches!(dt, DataType::Decimal(..)) {
+            // Kept as text here; the declared precision and scale are applied on append
+            parse_all(&vals, |v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.trim().parse::<f64>().ok())))
+                .map_err(|i| invalid(i, "numeric"))?;
+        }
+        let s: Series = match &dt {
+            DataType::Int64 => Series::new(n.as_str().into(), parse_all(&vals, |v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok())))
+                .map_err(|i| invalid(i, "int64"))?),
+            DataType::Float64 => Series::new(n.as_str().into(), parse_all(&vals, |v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok())))
+                .map_err(|i| invalid(i, "float64"))?),
+            DataType::Boolean => Series::new(n.as_str().into(), parse_all(&vals, crate::storage::native::json_bool)
+                .map_err(|i| invalid(i, "boolean"))?),
+            DataType::Datetime(..) => Series::new(n.as_str().into(), parse_all(&vals, crate::storage::native::json_timestamp_ms)
+                .map_err(|i| invalid(i, "timestamp"))?)
+                .cast(&crate::storage::native::timestamp_dtype())?,
+            _ => Series::new(n.as_str().into(), vals.iter().map(|v| match v {
+                Value::Null => None,
+                Value::String(s) => Some(s.clone()),
+                other => Some(other.to_string()),
+            }).collect::<Vec<Option<String>>>()),
+        };
+        cols.push(s.into());
+    }
+    Ok(DataFrame::new(cols)?)
+}
+
+/// Execute server-side COPY FROM '<path>' / '<url>'.
+pub async fn handle_copy_from(store: &SharedStore, table: &str, columns: Vec<String>, source: CopySource, options: CopyOptions) -> Result<Value> {
+    let mut ingest = CopyIngest::new(store, table, columns, options)?;
+    match source {
+        CopySource::Stdin => bail!("COPY FROM STDIN is only available over the PostgreSQL wire protocol"),
+        CopySource::File(path) => {
+            use tokio::io::AsyncReadExt;
+            let mut f = tokio::fs::File::open(&path).await.map_err(|e| anyhow!("COPY: cannot open '{}': {}", path, e))?;
+            let mut buf = vec![0u8; 1 << 20];
+            loop {
+                let n = f.read(&mut buf).await?;
+                if n == 0 { break; }
+                ingest.push(&buf[..n])?;
+            }
+        }
+        CopySource::Url(url) => {
+            let mut resp = reqwest::get(&url).await?.error_for_status()?;
+            while let Some(chunk) = resp.chunk().await? { ingest.push(&chunk)?; }
+        }
+    }
+    let rows = ingest.finish()?;
+    Ok(serde_json::json!({"status": "ok", "copied": rows}))
+}
+
+/// Batch reader behind COPY ... TO STDOUT. Every batch has exactly `columns()`, in
+/// order; columns missing from a chunk are NULL.
+pub struct CopyExport {
+    store: SharedStore,
+    columns: Vec<String>,
+    chunks: std::vec::IntoIter<PathBuf>,
+    // Rows read but not handed out yet: a query result, or the chunk read to find the columns
+    pending: Option<DataFrame>,
+    batch_size: usize,
+}
+
+impl CopyExport {
+    /// Export `table`, reading one stored chunk per batch.
+    pub fn for_table(store: &SharedStore, table: &str, columns: Vec<String>, options: &CopyOptions) -> Result<Self> {
+        let qd = crate::system::current_query_defaults();
+        let table = if table.to_ascii_lowercase().ends_with(".time") {
+            crate::ident::qualify_time_ident(table, &qd)
+        } else {
+            crate::ident::qualify_regular_ident(table, &qd)
+        };
+        let (chunks, is_time, schema) = {
+            let g = store.0.lock();
+            let chunks = g.chunk_paths(&table).ok_or_else(|| anyhow!("COPY: table '{}' does not exist", table))?;
+            (chunks, g.is_time_table(&table), g.load_schema_with_locks(&table).map(|(s, _)| s).unwrap_or_default())
+        };
+        if !schema.is_empty() {
+            if let Some(c) = columns.iter().find(|c| !schema.contains_key(c.as_str()) && !(is_time && c.as_str() == "_time")) {
+                bail!("COPY: column '{}' does not exist in '{}'", c, table);
+            }
+        }
+        let mut chunks = chunks.into_iter();
+        let mut pending = None;
+        let columns = if !columns.is_empty() {
+            columns
+        } else if let Some(first) = chunks.next() {
+            let df = store.0.lock().read_chunk(&first)?;
+            let names = df.get_column_names().iter().map(|c| c.to_string()).collect();
+            pending = Some(df);
+            names
+        } else {
+            store.0.lock().read_df(&table)?.get_column_names().iter().map(|c| c.to_string()).collect()
+        };
+        Ok(Self { store: store.clone(), columns, chunks, pending, batch_size: options.batch_size })
+    }
+
+    /// Export an already computed query result in `batch_size` slices.
+    pub fn for_query(store: &SharedStore, df: DataFrame, options: &CopyOptions) -> Self {
+        let columns = df.get_column_names().iter().map(|c| c.to_string()).collect();
+        Self { store: store.clone(), columns, chunks: Vec::new().into_iter(), pending: Some(df), batch_size: options.batch_size }
+    }
+
+    pub fn columns(&self) -> &[String] { &self.columns }
+
+    /// Next non-empty batch of at most `batch_size` rows, or None when the export is done.
+    pub fn next_batch(&mut self) -> Result<Option<DataFrame>> {
+        loop {
+            let mut df = match self.pending.take() {
+                Some(df) => df,
+                None => match self.chunks.next() {
+                    Some(path) => self.store.0.lock().read_chunk(&path)?,
+                    None => return Ok(None),
+                },
+            };
+            if df.height() == 0 { continue; }
+            if df.height() > self.batch_size {
+                self.pending = Some(df.slice(self.batch_size as i64, df.height() - self.batch_size));
+                df = df.slice(0, self.batch_size);
+            }
+            let height = df.height();
+            let cols: Vec<Column> = self.columns.iter()
+                .map(|c| df.column(c).cloned().unwrap_or_else(|_| Column::full_null(c.as_str().into(), height, &DataType::Null)))
+                .collect();
+            return Ok(Some(DataFrame::new(cols)?));
+        }
+    }
+}
@@ -24,7 +24,7 @@ pub fn handle_create_table(store: &SharedStore, table: &str, primary_key: &Optio
     let (_dir_path_before, exists_before) = {
         let g = store.0.lock();
         let root = g.root_path().clone();
-        let dir: PathBuf = root.join(table.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+        let dir: PathBuf = crate::ident::to_local_path(&root, &table);
         let ex = dir.exists();
         (dir, ex)
     };
@@ -43,7 +43,7 @@ pub fn handle_create_table(store: &SharedStore, table: &str, primary_key: &Optio
     // Enforce uniqueness with views: a table cannot be created if a view with the same base name exists
     {
         let root = store.root_path().clone();
-        let mut vp = root.join(table.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+        let mut vp = crate::ident::to_local_path(&root, &table);
         // For regular table, vp points to .../db/schema/table — convert to .view file
         vp.set_extension("view");
         if vp.exists() {
@@ -63,7 +63,7 @@ pub fn handle_create_table(store: &SharedStore, table: &str, primary_key: &Optio
     let (exists_after, schema_path, schema_summary) = {
         let g = store.0.lock();
         let root = g.root_path().clone();
-        let dir: PathBuf = root.join(table.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+        let dir: PathBuf = crate::ident::to_local_path(&root, &table);
         let sp = dir.join("schema.json");
         let mut summary = String::new();
         if sp.exists() {
@@ -96,7 +96,7 @@ pub fn handle_drop_table(store: &SharedStore, table: &str, if_exists: bool) -> R
     let qd = crate::system::current_query_defaults();
     let tableq = crate::ident::qualify_regular_ident(table, &qd);
     // Check if table exists
-    let table_path = guard.root_path().join(tableq.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+    let table_path = crate::ident::to_local_path(&guard.root_path(), &tableq);
     let exists = table_path.exists();
     // If IF EXISTS is used and table doesn't exist, return success without error
     if if_exists && !exists {
@@ -117,8 +117,8 @@ pub fn handle_rename_table(store: &SharedStore, from: &str, to: &str) -> Result<
     let qd = crate::system::current_query_defaults();
     let fromq = crate::ident::qualify_regular_ident(from, &qd);
     let toq = crate::ident::qualify_regular_ident(to, &qd);
-    let src = store.root_path().join(fromq.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
-    let dst = store.root_path().join(toq.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+    let src = crate::ident::to_local_path(&store.root_path(), &fromq);
+    let dst = crate::ident::to_local_path(&store.root_path(), &toq);
     if !src.exists() { return Err(AppError::NotFound { code: "not_found".into(), message: format!("Source table not found: {}", from) }.into()); }
     if let Some(parent) = dst.parent() { fs::create_dir_all(parent).ok(); }
     fs::rename(&src, &dst)?;
@@ -144,8 +144,23 @@ pub fn do_create_table(store: &SharedStore, q: &str) -> Result<()> {
     // Extract identifier up to '(' and the column list inside (...)
     let p_open = s.find('(').ok_or_else(|| AppError::Ddl { code: "syntax".into(), message: "expected ( in CREATE TABLE".into() })?;
     let ident = s[..p_open].trim();
-    let p_close = s.rfind(')').ok_or_else(|| AppError::Ddl { code: "syntax".into(), message: "expected ) in CREATE TABLE".into() })?;
+    // Match the column list's closing parenthesis so a trailing PARTITION BY (...) is not swallowed
+    let mut p_close: Option<usize> = None;
+    let mut d = 0i32;
+    for (i, ch) in s.char_indices().skip_while(|(i, _)| *i < p_open) {
+        match ch {
+            '(' => d += 1,
+            ')' => { d -= 1; if d == 0 { p_close = Some(i); break; } }
+            _ => {}
+        }
+    }
+    let p_close = p_close.ok_or_else(|| AppError::Ddl { code: "syntax".into(), message: "expected ) in CREATE TABLE".into() })?;
     let cols_str = &s[p_open+1 .. p_close];
+    // Optional PARTITION BY [LIST | HASH] (cols) [BUCKETS n] after the column list
+    let tail = s[p_close + 1..].trim();
+    let partition_spec = if tail.to_uppercase().starts_with("PARTITION BY") {
+        Some(crate::server::query::query_parse_alter::parse_partition_spec(&tail["PARTITION BY".len()..])?)
+    } else { None };
     // Parse columns and detect constraints
     let mut cols: Vec<(String, String)> = Vec::new();
     let mut cur = String::new();
@@ -184,9 +199,12 @@ pub fn do_create_table(store: &SharedStore, q: &str) -> Result<()> {
         let t_up = ty.to_ascii_lowercase();
         // Map SQL type string to schema key. Support arrays (typename[]) mapped to generic 'list'.
         let key = if t_up.trim_end().ends_with("[]") { "list".to_string() }
-            else if t_up.contains("char") || t_up.contains("text") || t_up.contains("json") || t_up.contains("bool") { "string".to_string() }
+            else if let Some((p, sc)) = crate::storage::decimal::parse_type(&t_up)? { crate::storage::decimal::type_key(p, sc) }
+            else if t_up.contains("bool") { "bool".to_string() }
+            else if t_up.contains("char") || t_up.contains("text") || t_up.contains("json") { "string".to_string() }
             else if t_up.contains("int") { "int64".to_string() }
-            else if t_up.contains("double") || t_up.contains("real") || t_up.contains("float") || t_up.contains("numeric") || t_up.contains("decimal") { "float64".to_string() }
+            else if t_up.contains("double") || t_up.contains("real") || t_up.contains("float") { "float64".to_string() }
+            else if t_up.contains("timestamp") || t_up.contains("datetime") { "timestamp".to_string() }
             else if t_up.contains("time") || t_up.contains("date") { "int64".to_string() }
             else if t_up.contains("vector") { "vector".to_string() }
             else { "string".to_string() };
@@ -209,7 +227,7 @@ pub fn do_create_table(store: &SharedStore, q: &str) -> Result<()> {
     };
     tprintln!("[CREATE] do_create_table: qualified table name: '{}' (parts={}) -> '{}'", ident_norm, parts_count, db_path);
     let root = store.root_path();
-    let dir = std::path::Path::new(&root).join(db_path.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+    let dir = crate::ident::to_local_path(std::path::Path::new(&root), &db_path);
     debug!(target: "clarium::exec", "do_create_table: dir='{}' (db_path='{}')", dir.display(), db_path);
     // Existence handling per IF NOT EXISTS contract
     if dir.exists() {
@@ -238,6 +256,9 @@ pub fn do_create_table(store: &SharedStore, q: &str) -> Result<()> {
         // We don't parse explicit PK column list yet; pass empty list to trigger PRIMARY marker
         let _ = store.0.lock().set_table_metadata(&db_path, Some(Vec::<String>::new()), None);
     }
+    if let Some(spec) = partition_spec {
+        store.0.lock().set_partition_spec(&db_path, &spec)?;
+    }
     debug!(target: "clarium::exec", "do_create_table: wrote nested schema via centralized save at '{}'", dir.display());
     Ok(())
 }
@@ -1,13 +1,211 @@
 //! exec_delete
 //! -----------
-//! DELETE COLUMNS implementation extracted from exec.rs. Keeps dispatcher thin.
+//! DELETE and DELETE COLUMNS implementations extracted from exec.rs. Keeps dispatcher thin.
+//!
+//! DELETE with a WHERE clause works chunk by chunk: chunks whose `_time` range lies
+//! outside the bounds the WHERE clause sets on `_time` are not read, and only chunks
+//! holding matching rows are touched. Those are dropped when every row goes, and
+//! otherwise tombstoned or rewritten (see `storage::tombstone`).
+//!
+//! Planning runs outside the store lock, since WHERE subqueries read tables. Each planned
+//! chunk's generation is checked again under the lock that applies the delete; if another
+//! statement changed one in between, nothing is applied and the statement is planned again.
+
+use std::ops::Not;
+use std::path::PathBuf;
 
 use anyhow::Result;
+
+use crate::error::AppError;
 use polars::prelude::*;
 
 use crate::server::exec::{where_subquery::{eval_where_mask, where_contains_subquery}, exec_common::build_where_expr, df_utils::read_df_or_kv};
 use crate::storage::SharedStore;
-use crate::server::query::query_common::WhereExpr;
+use crate::storage::tombstone::{chunk_generation, ChunkDelete, ChunkGeneration};
+use crate::server::query::query_common::{parse_iso8601_to_ms, ArithExpr, ArithTerm, CompOp, WhereExpr};
+
+/// Times a DELETE is planned again after chunks changed under it before it fails.
+const MAX_PLAN_ATTEMPTS: usize = 5;
+
+/// What a chunk-level DELETE did, returned with the statement result.
+#[derive(Debug, Clone, Default)]
+struct DeleteReport {
+    deleted: usize,
+    chunks_scanned: usize,
+    chunks_skipped: usize,
+    chunks_rewritten: usize,
+    chunks_dropped: usize,
+    chunks_tombstoned: usize,
+    rows_tombstoned: usize,
+}
+
+fn where_context() -> crate::server::data_context::DataContext {
+    let registry_snapshot = crate::scripts::get_script_registry().and_then(|r| r.snapshot().ok());
+    let mut ctx = crate::server::data_context::DataContext::with_defaults("clarium", "public");
+    if let Some(reg) = registry_snapshot { ctx.script_registry = Some(reg); }
+    ctx
+}
+
+// Rows where the predicate is NULL are kept
+fn delete_mask(df: &DataFrame, w: &WhereExpr, store: &SharedStore, ctx: &crate::server::data_context::DataContext) -> Result<BooleanChunked> {
+    let mask = if where_contains_subquery(w) {
+        eval_where_mask(df, ctx, store, w)?
+    } else {
+        let mask_df = df.clone().lazy().select([build_where_expr(w, ctx).alias("__m__")]).collect()?;
+        mask_df.column("__m__")?.bool()?.clone()
+    };
+    Ok(mask.fill_null_with_values(false)?)
+}
+
+fn literal_ms(a: &ArithExpr) -> Option<f64> {
+    match a {
+        ArithExpr::Term(ArithTerm::Number(n)) => Some(*n),
+        ArithExpr::Term(ArithTerm::Str(s)) => parse_iso8601_to_ms(s).map(|v| v as f64),
+        _ => None,
+    }
+}
+
+fn is_time_col(a: &ArithExpr) -> bool {
+    matches!(a, ArithExpr::Term(ArithTerm::Col { name, previous: false }) if name == "_time" || name.ends_with("._time"))
+}
+
+/// Inclusive `_time` bounds implied by top-level AND-ed comparisons of `_time` with literals.
+fn time_bounds(w: &WhereExpr, lo: &mut Option<i64>, hi: &mut Option<i64>) {
+    match w {
+        WhereExpr::And(a, b) => { time_bounds(a, lo, hi); time_bounds(b, lo, hi); }
+        WhereExpr::Comp { left, op, right } => {
+            // Normalize to `_time <op> v`
+            let (op, v) = if is_time_col(left) {
+                (op, literal_ms(right))
+            } else if is_time_col(right) {
+                let flipped = match op { CompOp::Gt => &CompOp::Lt, CompOp::Ge => &CompOp::Le, CompOp::Lt => &CompOp::Gt, CompOp::Le => &CompOp::Ge, other => other };
+                (flipped, literal_ms(left))
+            } else { return };
+            let Some(v) = v else { return };
+            let (floor, ceil) = (v.floor() as i64, v.ceil() as i64);
+            let raise = |b: &mut Option<i64>| *b = Some(b.map_or(floor, |x| x.max(floor)));
+            let lower = |b: &mut Option<i64>| *b = Some(b.map_or(ceil, |x| x.min(ceil)));
+            match op {
+                CompOp::Gt | CompOp::Ge => raise(lo),
+                CompOp::Lt | CompOp::Le => lower(hi),
+                CompOp::Eq => { raise(lo); lower(hi); }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+fn delete_rows_whole_table(store: &SharedStore, database: &str, where_clause: Option<&WhereExpr>) -> Result<serde_json::Value> {
+    // Load full dataframe
+    let df_all = read_df_or_kv(store, database)?;
+    // Rows removed by this statement; everything when there is no WHERE
+    let mut deleted = df_all.clone();
+    // If no WHERE, truncate database
+    let new_df = if let Some(w) = where_clause {
+        let ctx = where_context();
+        let mask = delete_mask(&df_all, w, store, &ctx)?;
+        let keep = mask.not();
+        deleted = df_all.filter(&mask)?;
+        df_all.filter(&keep)?
+    } else {
+        // Empty df with only _time column
+        DataFrame::new(vec![Series::new("_time".into(), Vec::<i64>::new()).into()])?
+    };
+    let guard = store.0.lock();
+    guard.rewrite_table_df(database, new_df)?;
+    guard.record_changes(database, crate::storage::cdc::ChangeOp::Delete, &deleted)?;
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+/// Rows of each chunk a DELETE removes, against the chunk generations they were read from.
+struct DeletePlan {
+    report: DeleteReport,
+    chunks: Vec<(PathBuf, ChunkGeneration, Vec<IdxSize>)>,
+    deleted: Option<DataFrame>,
+}
+
+// Every chunk is planned before any is changed, so subqueries see the table as it was
+fn plan_delete(store: &SharedStore, paths: Vec<PathBuf>, w: &WhereExpr, lo: Option<i64>, hi: Option<i64>) -> Result<DeletePlan> {
+    let ctx = where_context();
+    let mut plan = DeletePlan { report: DeleteReport::default(), chunks: Vec::new(), deleted: None };
+    for p in paths {
+        if let Some((cmin, cmax)) = store.0.lock().chunk_time_range(&p) {
+            if lo.is_some_and(|l| cmax < l) || hi.is_some_and(|h| cmin > h) { plan.report.chunks_skipped += 1; continue; }
+        }
+        plan.report.chunks_scanned += 1;
+        let (generation, df) = {
+            let guard = store.0.lock();
+            (chunk_generation(&p)?, guard.read_chunk(&p)?)
+        };
+        let mask = delete_mask(&df, w, store, &ctx)?;
+        let rows: Vec<IdxSize> = (&mask).into_iter().enumerate().filter(|(_, m)| m.unwrap_or(false)).map(|(i, _)| i as IdxSize).collect();
+        if rows.is_empty() { continue; }
+        let gone = df.filter(&mask)?;
+        match plan.deleted.as_mut() {
+            Some(acc) => { acc.vstack_mut(&gone.select(acc.get_column_names_owned())?)?; }
+            None => plan.deleted = Some(gone),
+        }
+        plan.chunks.push((p, generation, rows));
+    }
+    Ok(plan)
+}
+
+pub fn handle_delete_rows(store: &SharedStore, database: String, where_clause: Option<WhereExpr>) -> Result<serde_json::Value> {
+    let paths = match &where_clause {
+        Some(_) if !database.contains(".store.") => store.0.lock().chunk_paths(&database),
+        _ => None,
+    };
+    let (Some(w), Some(mut paths)) = (where_clause.as_ref(), paths) else {
+        return delete_rows_whole_table(store, &database, where_clause.as_ref());
+    };
+    let __t0 = std::time::Instant::now();
+    let (mut lo, mut hi) = (None, None);
+    time_bounds(w, &mut lo, &mut hi);
+    let mut attempt = 1;
+    let (plan, guard) = loop {
+        let plan = plan_delete(store, paths, w, lo, hi)?;
+        let guard = store.0.lock();
+        // Positions were computed against the planned generations; apply only if every chunk still has its own
+        let mut current = true;
+        for (p, generation, _) in &plan.chunks {
+            if chunk_generation(p)? != *generation { current = false; break; }
+        }
+        if current { break (plan, guard); }
+        if attempt == MAX_PLAN_ATTEMPTS {
+            return Err(AppError::conflict("serialization_failure".to_string(), format!("could not delete from '{}': its chunks kept changing under concurrent writes", database)).into());
+        }
+        attempt += 1;
+        paths = guard.chunk_paths(&database).unwrap_or_default();
+    };
+    let DeletePlan { mut report, chunks, deleted } = plan;
+    for (p, _, rows) in chunks {
+        report.deleted += rows.len();
+        match guard.delete_chunk_rows(&database, &p, &rows)? {
+            ChunkDelete::Dropped => report.chunks_dropped += 1,
+            ChunkDelete::Rewritten => report.chunks_rewritten += 1,
+            ChunkDelete::Tombstoned => { report.chunks_tombstoned += 1; report.rows_tombstoned += rows.len(); }
+        }
+    }
+    if report.deleted > 0 {
+        guard.update_ingest_stats(&database, |s| {
+            s.chunks_rewritten += report.chunks_rewritten as u64;
+            s.chunks_dropped += report.chunks_dropped as u64;
+            s.rows_tombstoned += report.rows_tombstoned as u64;
+        })?;
+    }
+    if let Some(d) = deleted { guard.record_changes(&database, crate::storage::cdc::ChangeOp::Delete, &d)?; }
+    crate::tprintln!("[EXEC_DELETE] table='{}' {:?} took={:?}", database, report, __t0.elapsed());
+    Ok(serde_json::json!({
+        "status": "ok",
+        "deleted": report.deleted,
+        "chunks_scanned": report.chunks_scanned,
+        "chunks_skipped": report.chunks_skipped,
+        "chunks_rewritten": report.chunks_rewritten,
+        "chunks_dropped": report.chunks_dropped,
+        "chunks_tombstoned": report.chunks_tombstoned,
+    }))
+}
 
 pub fn handle_delete_columns(store: &SharedStore, database: String, mut columns: Vec<String>, where_clause: Option<WhereExpr>) -> Result<serde_json::Value> {
     // Load full dataframe
@@ -14,16 +14,14 @@ fn qualify_name(name: &str) -> String {
 
 fn view_path(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-    p.push(local);
+    p = crate::ident::to_local_path(&p, &qualified);
     p.set_extension("view");
     p
 }
 
 fn table_dir_path(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-    p.push(local);
+    p = crate::ident::to_local_path(&p, &qualified);
     p
 }
 
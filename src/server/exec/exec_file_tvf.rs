@@ -0,0 +1,344 @@
+//! exec_file_tvf
+//! -------------
+//! Table-valued functions that read external files straight into a query without
+//! creating a table:
+//! - read_csv(path [, delimiter => ',', header => true, null => '', compression => 'auto', infer_schema => true])
+//! - read_json(path [, compression => 'auto'])   -- NDJSON or a top-level JSON array of objects
+//! - read_parquet(path)
+//! - filestore_read_csv('store:/path' [, same options as read_csv])
+//! - filestore_read_parquet('store:/path')
+//! - filestore_read_lines('store:/path' [, compression => 'auto'])   -- columns line_no, line
+//! - filestore_search('store', 'query terms')   -- columns path, score, snippet (see filestore/fulltext.rs)
+//!
+//! `path` is either a local file path or `filestore://<filestore>/<logical/path>`
+//! resolved against the current database. Local paths read server files, so like
+//! COPY FROM '<file>' they are admin-only; filestore sources need only the filestore's ACL.
+//! The `filestore_read_*` functions take
+//! `<filestore>:<logical/path>` (the filestore may be qualified as `db.store`).
+//! Filestore content is read through the filestore's ACL as a Read by the session user;
+//! search results the user may not read are left out.
+//! Named options accept `=>` or `=`.
+//! Compression `auto` detects gzip from the `.gz` suffix or the gzip magic bytes.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use anyhow::{anyhow, bail, Result};
+use polars::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::error::AppError;
+use crate::server::activity;
+use crate::server::exec::exec_copy::{maps_to_df, split_csv_fields};
+use crate::server::exec::filestore::{self as fs, decide_acl, read_file_checked, ACLAction, AclUser};
+use crate::server::exec::{effective_for, filestore_target, make_acl_ctx};
+use crate::tprintln;
+
+const FILESTORE_SCHEME: &str = "filestore://";
+
+fn strip_quotes(x: &str) -> String {
+    let t = x.trim();
+    if (t.starts_with('"') && t.ends_with('"')) || (t.starts_with('\'') && t.ends_with('\'')) {
+        if t.len() >= 2 { return t[1..t.len()-1].to_string(); }
+    }
+    t.to_string()
+}
+
+fn parse_func_args(call: &str) -> Option<(&str, Vec<String>)> {
+    let s = call.trim();
+    let open = s.find('(')?;
+    if !s.ends_with(')') { return None; }
+    let fname = s[..open].trim();
+    let inside = &s[open+1..s.len()-1];
+    // Split on commas not inside quotes
+    let mut out: Vec<String> = Vec::new();
+    let mut cur = String::new();
+    let mut in_sq = false; let mut in_dq = false;
+    for ch in inside.chars() {
+        if ch == '\'' && !in_dq { in_sq = !in_sq; cur.push(ch); continue; }
+        if ch == '"' && !in_sq { in_dq = !in_dq; cur.push(ch); continue; }
+        if ch == ',' && !in_sq && !in_dq { out.push(cur.trim().to_string()); cur.clear(); continue; }
+        cur.push(ch);
+    }
+    if !cur.trim().is_empty() { out.push(cur.trim().to_string()); }
+    Some((fname, out))
+}
+
+/// Split trailing `name => value` / `name = value` arguments into a lowercase-keyed map.
+fn parse_named_opts(args: &[String]) -> Result<HashMap<String, String>> {
+    let mut out = HashMap::new();
+    for a in args {
+        let (k, v) = if let Some(p) = a.find("=>") { (&a[..p], &a[p+2..]) }
+            else if let Some(p) = a.find('=') { (&a[..p], &a[p+1..]) }
+            else { bail!("expected named option 'name => value', got: {}", a) };
+        out.insert(k.trim().to_ascii_lowercase(), strip_quotes(v));
+    }
+    Ok(out)
+}
+
+fn opt_bool(opts: &HashMap<String, String>, key: &str, default: bool) -> Result<bool> {
+    match opts.get(key).map(|v| v.to_ascii_lowercase()) {
+        None => Ok(default),
+        Some(v) => match v.as_str() {
+            "true" | "on" | "1" | "yes" => Ok(true),
+            "false" | "off" | "0" | "no" => Ok(false),
+            _ => bail!("option '{}' expects a boolean, got '{}'", key, v),
+        },
+    }
+}
+
+fn session_acl_user() -> AclUser {
+    AclUser {
+        id: activity::current_user().unwrap_or_else(|| "anonymous".into()),
+        roles: activity::current_role().into_iter().collect(),
+        ip: None,
+    }
+}
+
+/// Read a filestore file as the session user, subject to the filestore's ACL.
+fn read_filestore_bytes(store: &crate::storage::SharedStore, db: &str, fs_name: &str, logical: &str) -> Result<Vec<u8>> {
+    let eff = effective_for(store, db, fs_name)?;
+    let ctx = make_acl_ctx(store, db, fs_name);
+    let (_, bytes) = read_file_checked(store, db, fs_name, logical, &session_acl_user(), &eff, &ctx)?;
+    Ok(bytes)
+}
+
+/// Split `<filestore>:<logical/path>` into (database, filestore, logical path).
+fn parse_filestore_target(target: &str) -> Result<(String, String, String)> {
+    let (fs_name, logical) = target.split_once(':')
+        .ok_or_else(|| anyhow!("filestore path must be '<filestore>:<path>', got: {}", target))?;
+    let logical = logical.trim_start_matches('/');
+    if fs_name.trim().is_empty() || logical.is_empty() {
+        bail!("filestore path must be '<filestore>:<path>', got: {}", target);
+    }
+    let (db, fs_name) = filestore_target(fs_name.trim());
+    Ok((db, fs_name, logical.to_string()))
+}
+
+/// Reading server files is admin-only, as for COPY FROM '<file>'. Statements with no session
+/// user are internal (jobs, startup scripts) and pass.
+fn require_server_file_reader(store: &crate::storage::SharedStore) -> Result<()> {
+    let Some(user) = activity::current_user() else { return Ok(()) };
+    let root = store.root_path().to_string_lossy().to_string();
+    let is_admin = crate::security::authorize(&root, &user, crate::security::CommandKind::Database, None).unwrap_or(false)
+        || crate::security::has_superuser_role(&root, &user);
+    if !is_admin {
+        return Err(AppError::Permission { code: "insufficient_privilege".into(), message: "permission denied to read server files; use a filestore:// path".into() }.into());
+    }
+    Ok(())
+}
+
+/// Load raw bytes from a local path or a filestore URI.
+fn read_source_bytes(store: &crate::storage::SharedStore, path: &str) -> Result<Vec<u8>> {
+    if let Some(rest) = path.strip_prefix(FILESTORE_SCHEME) {
+        let (fs_name, logical) = rest.split_once('/').ok_or_else(|| anyhow!("filestore path must be filestore://<filestore>/<path>"))?;
+        let db = crate::system::current_query_defaults().current_database;
+        return read_filestore_bytes(store, &db, fs_name, logical);
+    }
+    require_server_file_reader(store)?;
+    std::fs::read(path).map_err(|e| anyhow!("cannot read '{}': {}", path, e))
+}
+
+/// Apply the `compression` option (auto | gzip | none) to text payloads.
+fn decompress(path: &str, bytes: Vec<u8>, compression: Option<&str>) -> Result<Vec<u8>> {
+    let mode = compression.unwrap_or("auto").to_ascii_lowercase();
+    let gzip = match mode.as_str() {
+        "auto" => path.to_ascii_lowercase().ends_with(".gz") || bytes.starts_with(&[0x1f, 0x8b]),
+        "gzip" | "gz" => true,
+        "none" | "uncompressed" => false,
+        other => bail!("unsupported compression: {}", other),
+    };
+    if !gzip { return Ok(bytes); }
+    let mut out = Vec::new();
+    flate2::read::MultiGzDecoder::new(&bytes[..]).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Split CSV text into records, keeping newlines that appear inside quoted fields.
+fn split_csv_records(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut in_q = false;
+    let mut start = 0usize;
+    for (i, b) in text.bytes().enumerate() {
+        match b {
+            b'"' => in_q = !in_q,
+            b'\n' if !in_q => { out.push(&text[start..i]); start = i + 1; }
+            _ => {}
+        }
+    }
+    if start < text.len() { out.push(&text[start..]); }
+    out.into_iter()
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .filter(|l| !l.trim().is_empty())
+        .collect()
+}
+
+/// Pick the narrowest type that fits every non-null value of a column.
+fn infer_csv_series(name: &str, vals: Vec<Option<String>>) -> Series {
+    let non_null = || vals.iter().flatten();
+    if non_null().all(|v| v.trim().parse::<i64>().is_ok()) && non_null().next().is_some() {
+        return Series::new(name.into(), vals.iter().map(|v| v.as_ref().and_then(|s| s.trim().parse::<i64>().ok())).collect::<Vec<Option<i64>>>());
+    }
+    if non_null().all(|v| v.trim().parse::<f64>().is_ok()) && non_null().next().is_some() {
+        return Series::new(name.into(), vals.iter().map(|v| v.as_ref().and_then(|s| s.trim().parse::<f64>().ok())).collect::<Vec<Option<f64>>>());
+    }
+    let is_bool = |s: &str| s.eq_ignore_ascii_case("true") || s.eq_ignore_ascii_case("false");
+    if non_null().all(|v| is_bool(v.trim())) && non_null().next().is_some() {
+        return Series::new(name.into(), vals.iter().map(|v| v.as_ref().map(|s| s.trim().eq_ignore_ascii_case("true"))).collect::<Vec<Option<bool>>>());
+    }
+    Series::new(name.into(), vals)
+}
+
+fn read_csv_df(text: &str, opts: &HashMap<String, String>) -> Result<DataFrame> {
+    let delim_s = opts.get("delimiter").or_else(|| opts.get("delim")).or_else(|| opts.get("sep")).cloned().unwrap_or_else(|| ",".into());
+    let delim_s = if delim_s == "\\t" { "\t".to_string() } else { delim_s };
+    let mut dchars = delim_s.chars();
+    let delimiter = dchars.next().ok_or_else(|| anyhow!("read_csv: delimiter must be a single character"))?;
+    if dchars.next().is_some() { bail!("read_csv: delimiter must be a single character"); }
+    let header = opt_bool(opts, "header", true)?;
+    let infer = opt_bool(opts, "infer_schema", true)?;
+    let null_str = opts.get("null").cloned().unwrap_or_default();
+
+    let mut records = split_csv_records(text).into_iter();
+    let names: Vec<String> = if header {
+        match records.next() {
+            Some(h) => split_csv_fields(h, delimiter, "").into_iter().map(|f| f.unwrap_or_default().trim().to_string()).collect(),
+            None => return Ok(DataFrame::empty()),
+        }
+    } else { Vec::new() };
+    let rows: Vec<Vec<Option<String>>> = records.map(|r| split_csv_fields(r, delimiter, &null_str)).collect();
+    let ncols = if header { names.len() } else { rows.iter().map(|r| r.len()).max().unwrap_or(0) };
+    let names: Vec<String> = if header { names } else { (0..ncols).map(|i| format!("column{}", i)).collect() };
+    for (i, r) in rows.iter().enumerate() {
+        if r.len() != ncols { bail!("read_csv: record {} has {} fields, expected {}", i + 1, r.len(), ncols); }
+    }
+    let mut cols: Vec<Column> = Vec::with_capacity(ncols);
+    for (ci, n) in names.iter().enumerate() {
+        let vals: Vec<Option<String>> = rows.iter().map(|r| r[ci].clone()).collect();
+        let s = if infer { infer_csv_series(n, vals) } else { Series::new(n.as_str().into(), vals) };
+        cols.push(s.into());
+    }
+    Ok(DataFrame::new(cols)?)
+}
+
+fn read_json_df(text: &str) -> Result<DataFrame> {
+    let trimmed = text.trim_start();
+    let rows: Vec<Map<String, Value>> = if trimmed.starts_with('[') {
+        match serde_json::from_str::<Value>(trimmed)? {
+            Value::Array(items) => items.into_iter().map(|v| match v {
+                Value::Object(m) => Ok(m),
+                _ => Err(anyhow!("read_json: array elements must be objects")),
+            }).collect::<Result<_>>()?,
+            _ => unreachable!(),
+        }
+    } else {
+        let mut out = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() { continue; }
+            match serde_json::from_str::<Value>(line).map_err(|e| anyhow!("read_json: line {}: {}", i + 1, e))? {
+                Value::Object(m) => out.push(m),
+                _ => bail!("read_json: line {} is not an object", i + 1),
+            }
+        }
+        out
+    };
+    if rows.is_empty() { return Ok(DataFrame::empty()); }
+    maps_to_df(&rows, &HashMap::new())
+}
+
+/// One row per line of text: `line_no` (from 1) and `line` without its line ending.
+fn read_lines_df(text: &str) -> Result<DataFrame> {
+    let lines: Vec<&str> = text.lines().collect();
+    let line_no: Vec<i64> = (1..=lines.len() as i64).collect();
+    Ok(DataFrame::new(vec![
+        Series::new("line_no".into(), line_no).into(),
+        Series::new("line".into(), lines).into(),
+    ])?)
+}
+
+fn utf8(fname: &str, data: Vec<u8>) -> Result<String> {
+    String::from_utf8(data).map_err(|_| anyhow!("{}: file is not valid UTF-8", fname))
+}
+
+/// filestore_read_csv / filestore_read_parquet / filestore_read_lines
+fn try_filestore_tvf(store: &crate::storage::SharedStore, fname: &str, args: &[String]) -> Result<DataFrame> {
+    let target = args.first().map(|a| strip_quotes(a)).filter(|p| !p.is_empty())
+        .ok_or_else(|| anyhow!("{}('<filestore>:<path>', ...) requires a path", fname))?;
+    let opts = parse_named_opts(&args[1..])?;
+    let (db, fs_name, logical) = parse_filestore_target(&target)?;
+    let bytes = read_filestore_bytes(store, &db, &fs_name, &logical)?;
+    let df = match fname {
+        "filestore_read_parquet" => ParquetReader::new(std::io::Cursor::new(bytes)).finish()?,
+        "filestore_read_csv" => {
+            let data = decompress(&logical, bytes, opts.get("compression").map(|s| s.as_str()))?;
+            read_csv_df(&utf8(fname, data)?, &opts)?
+        }
+        _ => {
+            let data = decompress(&logical, bytes, opts.get("compression").map(|s| s.as_str()))?;
+            read_lines_df(&utf8(fname, data)?)?
+        }
+    };
+    tprintln!("[file.tvf] {}('{}.{}:{}') -> rows={} cols={:?}", fname, db, fs_name, logical, df.height(), df.get_column_names());
+    Ok(df)
+}
+
+/// filestore_search('store', 'query terms'): indexed files containing every term, best match
+/// first, limited to the files the session user may read.
+fn filestore_search_df(store: &crate::storage::SharedStore, args: &[String]) -> Result<DataFrame> {
+    let (target, query) = match args {
+        [t, q] => (strip_quotes(t), strip_quotes(q)),
+        _ => bail!("filestore_search('<filestore>', '<query>') takes two arguments"),
+    };
+    if target.trim().is_empty() { bail!("filestore_search requires a filestore name"); }
+    let (db, fs_name) = filestore_target(target.trim());
+    if fs::load_filestore_entry(store, &db, &fs_name)?.is_none() {
+        bail!("filestore not found: {}", fs_name);
+    }
+    let eff = effective_for(store, &db, &fs_name)?;
+    let ctx = make_acl_ctx(store, &db, &fs_name);
+    let user = session_acl_user();
+    let hits: Vec<fs::SearchHit> = fs::search(store, &db, &fs_name, &query)?.into_iter()
+        .filter(|h| decide_acl(&eff, &user, ACLAction::Read, &h.path, None, &ctx, &fs_name).allow)
+        .collect();
+    tprintln!("[file.tvf] filestore_search('{}.{}', '{}') -> rows={}", db, fs_name, query, hits.len());
+    Ok(DataFrame::new(vec![
+        Series::new("path".into(), hits.iter().map(|h| h.path.as_str()).collect::<Vec<_>>()).into(),
+        Series::new("score".into(), hits.iter().map(|h| h.score).collect::<Vec<f64>>()).into(),
+        Series::new("snippet".into(), hits.iter().map(|h| h.snippet.as_str()).collect::<Vec<_>>()).into(),
+    ])?)
+}
+
+pub fn try_file_tvf(store: &crate::storage::SharedStore, raw: &str) -> Result<Option<DataFrame>> {
+    let s = raw.trim();
+    let low = s.to_ascii_lowercase();
+    if !(low.starts_with("read_csv(") || low.starts_with("read_parquet(") || low.starts_with("read_json(")
+        || low.starts_with("filestore_read_csv(") || low.starts_with("filestore_read_parquet(") || low.starts_with("filestore_read_lines(")
+        || low.starts_with("filestore_search(")) {
+        return Ok(None);
+    }
+    let (fname, args) = match parse_func_args(s) { Some(v) => v, None => return Ok(None) };
+    let fname_low = fname.to_ascii_lowercase();
+    if fname_low.starts_with("filestore_read_") {
+        return try_filestore_tvf(store, &fname_low, &args).map(Some);
+    }
+    if fname_low == "filestore_search" {
+        return filestore_search_df(store, &args).map(Some);
+    }
+    let path = args.first().map(|a| strip_quotes(a)).filter(|p| !p.is_empty())
+        .ok_or_else(|| anyhow!("{}(path, ...) requires a path", fname_low))?;
+    let opts = parse_named_opts(&args[1..])?;
+    let bytes = read_source_bytes(store, &path)?;
+    let df = match fname_low.as_str() {
+        "read_parquet" => ParquetReader::new(std::io::Cursor::new(bytes)).finish()?,
+        "read_csv" => {
+            let data = decompress(&path, bytes, opts.get("compression").map(|s| s.as_str()))?;
+            read_csv_df(&String::from_utf8(data).map_err(|_| anyhow!("read_csv: file is not valid UTF-8"))?, &opts)?
+        }
+        _ => {
+            let data = decompress(&path, bytes, opts.get("compression").map(|s| s.as_str()))?;
+            read_json_df(&String::from_utf8(data).map_err(|_| anyhow!("read_json: file is not valid UTF-8"))?)?
+        }
+    };
+    tprintln!("[file.tvf] {}('{}') -> rows={} cols={:?}", fname_low, path, df.height(), df.get_column_names());
+    Ok(Some(df))
+}
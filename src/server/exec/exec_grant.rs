@@ -0,0 +1,66 @@
+//! exec_grant
+//! ----------
+//! GRANT / REVOKE of object privileges: resolve the named database, schema or table against the
+//! session defaults, check it exists and record the ACL entries (see `security::grant`). The HTTP
+//! and WebSocket command gate consults them, and `information_schema.table_privileges` lists them.
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::error::AppError;
+use crate::security::{GrantObject, Privilege};
+use crate::server::query::GrantTarget;
+use crate::storage::SharedStore;
+
+/// Resolve a GRANT target to the object it names, failing when it does not exist.
+pub fn resolve_grant_object(store: &SharedStore, target: &GrantTarget) -> Result<GrantObject> {
+    let defaults = crate::system::current_query_defaults();
+    let guard = store.0.lock();
+    let root = guard.root_path().clone();
+    let object = match target {
+        GrantTarget::Database(name) => {
+            let database = crate::ident::normalize_identifier(name);
+            if !crate::storage::attach::database_dir(&root, &database).is_dir() {
+                return Err(AppError::NotFound { code: "invalid_catalog_name".into(), message: format!("database \"{}\" does not exist", name) }.into());
+            }
+            GrantObject::Database { database }
+        }
+        GrantTarget::Schema(name) => {
+            let path = name.replace('\\', "/");
+            let (database, schema) = match path.split_once('/').or_else(|| path.split_once('.')) {
+                Some((d, s)) => (crate::ident::normalize_identifier(d), crate::ident::normalize_identifier(s)),
+                None => (defaults.current_database.clone(), crate::ident::normalize_identifier(&path)),
+            };
+            if !crate::storage::attach::database_dir(&root, &database).join(&schema).is_dir() {
+                return Err(AppError::NotFound { code: "invalid_schema_name".into(), message: format!("schema \"{}\" does not exist", name) }.into());
+            }
+            GrantObject::Schema { database, schema }
+        }
+        GrantTarget::Table(name) => {
+            let qualified = crate::ident::qualify_regular_ident(name, &defaults);
+            if !guard.schema_path(&qualified).exists() {
+                return Err(AppError::NotFound { code: "undefined_table".into(), message: format!("relation \"{}\" does not exist", name) }.into());
+            }
+            let mut parts = qualified.splitn(3, '/').map(|p| p.to_string());
+            let (database, schema, table) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+            GrantObject::Table { database, schema, table }
+        }
+    };
+    Ok(object)
+}
+
+pub fn handle_grant(store: &SharedStore, privileges: &[Privilege], target: &GrantTarget, grantees: &[String], revoke: bool) -> Result<serde_json::Value> {
+    let object = resolve_grant_object(store, target)?;
+    let root = store.0.lock().root_path().clone();
+    let root = root.to_string_lossy();
+    let privs = privileges.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", ");
+    if revoke {
+        let removed = crate::security::revoke(&root, grantees, privileges, &object)?;
+        info!(target: "clarium::ddl", "REVOKE {} ON {} FROM {}: {} grant(s) removed", privs, object.path(), grantees.join(", "), removed);
+    } else {
+        let grantor = crate::server::activity::current_user();
+        crate::security::grant(&root, grantees, privileges, &object, grantor.as_deref())?;
+        info!(target: "clarium::ddl", "GRANT {} ON {} TO {}", privs, object.path(), grantees.join(", "));
+    }
+    Ok(serde_json::json!({"status": "ok"}))
+}
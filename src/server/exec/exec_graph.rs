@@ -44,20 +44,19 @@ pub struct GraphFile {
     pub graphstore_options: Option<Vec<(String, String)>>,
 }
 
-fn qualify_name(name: &str) -> String {
+pub(crate) fn qualify_name(name: &str) -> String {
     let d = crate::system::current_query_defaults();
     crate::ident::qualify_regular_ident(name, &d)
 }
 
 fn path_for_graph(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-    p.push(local);
+    p = crate::ident::to_local_path(&p, &qualified);
     p.set_extension("graph");
     p
 }
 
-fn read_graph_file(store: &SharedStore, qualified: &str) -> Result<Option<GraphFile>> {
+pub(crate) fn read_graph_file(store: &SharedStore, qualified: &str) -> Result<Option<GraphFile>> {
     let path = path_for_graph(store, qualified);
     if !path.exists() { return Ok(None); }
     let text = std::fs::read_to_string(&path)?;
@@ -83,7 +82,7 @@ fn now_iso() -> String { chrono::Utc::now().to_rfc3339() }
 fn list_graphs(store: &SharedStore) -> Result<Value> {
     let root = store.0.lock().root_path().clone();
     let mut out: Vec<serde_json::Value> = Vec::new();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
             if let Ok(sd) = std::fs::read_dir(&db_path) {
@@ -168,8 +167,7 @@ pub fn execute_graph(store: &SharedStore, cmd: query::Command) -> Result<Value>
                     let root = {
                         // Resolve <db>/<schema>/<graph>.gstore directory
                         let mut p = store.0.lock().root_path().clone();
-                        let local = qualified.replace('/', std::path::MAIN_SEPARATOR_STR);
-                        p.push(local);
+                        p = crate::ident::to_local_path(&p, &qualified);
                         p.set_extension("gstore");
                         p
                     };
@@ -0,0 +1,319 @@
+//! exec_graph_dml
+//! --------------
+//! INSERT INTO GRAPH / DELETE FROM GRAPH: node and edge writes routed through a
+//! `.graph` catalog to the tables bound to its labels and edge types. Keys are
+//! validated against the declared schema before anything is written. Graphs
+//! created `USING GRAPHSTORE` also get each change in their WAL and delta logs,
+//! which adjacency reads merge until the next compaction.
+
+use anyhow::Result;
+use polars::prelude::*;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::ops::Not;
+
+use crate::error::AppError;
+use crate::server::exec::exec_graph::{self, GraphEdgeDef, GraphFile, GraphNodeDef};
+use crate::server::exec::exec_graph_runtime::column_keys;
+use crate::server::exec::internal::constants::MASK;
+use crate::server::graphstore::txn::GraphTxn;
+use crate::server::graphstore::GraphHandle;
+use crate::server::query::{ArithTerm, Command, GraphElement};
+use crate::storage::SharedStore;
+
+pub fn execute_graph_dml(store: &SharedStore, cmd: Command) -> Result<Value> {
+    match cmd {
+        Command::InsertGraph { graph, element, name, columns, values } => {
+            let gf = load_graph(store, &graph)?;
+            match element {
+                GraphElement::Nodes => insert_nodes(store, &gf, &name, columns, values),
+                GraphElement::Edges => insert_edges(store, &gf, &name, columns, values),
+            }
+        }
+        Command::DeleteGraph { graph, element, name, keys, detach } => {
+            let gf = load_graph(store, &graph)?;
+            let keys: Vec<Vec<String>> = keys.iter().map(|t| t.iter().filter_map(term_key).collect()).collect();
+            match element {
+                GraphElement::Nodes => delete_nodes(store, &gf, &name, keys, detach),
+                GraphElement::Edges => delete_edges(store, &gf, &name, keys),
+            }
+        }
+        _ => Err(AppError::Ddl { code: "unsupported_graph".into(), message: "unsupported graph command".into() }.into()),
+    }
+}
+
+fn user_err(code: &str, message: String) -> anyhow::Error {
+    AppError::UserInput { code: code.into(), message }.into()
+}
+
+fn load_graph(store: &SharedStore, graph: &str) -> Result<GraphFile> {
+    let qualified = exec_graph::qualify_name(graph);
+    exec_graph::read_graph_file(store, &qualified)?.ok_or_else(|| {
+        AppError::NotFound { code: "not_found".into(), message: format!("Graph not found: {}", qualified) }.into()
+    })
+}
+
+fn node_def<'a>(gf: &'a GraphFile, label: &str) -> Result<&'a GraphNodeDef> {
+    gf.nodes
+        .iter()
+        .find(|n| n.label.eq_ignore_ascii_case(label))
+        .ok_or_else(|| user_err("graph_label", format!("Graph {} has no node label '{}'", gf.qualified, label)))
+}
+
+fn edge_def<'a>(gf: &'a GraphFile, etype: &str) -> Result<(usize, &'a GraphEdgeDef)> {
+    gf.edges
+        .iter()
+        .enumerate()
+        .find(|(_, e)| e.r#type.eq_ignore_ascii_case(etype))
+        .ok_or_else(|| user_err("graph_edge_type", format!("Graph {} has no edge type '{}'", gf.qualified, etype)))
+}
+
+fn key_column(def: &GraphNodeDef) -> String { def.key_column.clone().unwrap_or_else(|| def.key.clone()) }
+
+fn endpoint_columns(def: &GraphEdgeDef) -> (String, String) {
+    (def.src_column.clone().unwrap_or_else(|| "src".into()), def.dst_column.clone().unwrap_or_else(|| "dst".into()))
+}
+
+fn col_index(columns: &[String], name: &str) -> Option<usize> {
+    columns.iter().position(|c| c.eq_ignore_ascii_case(name))
+}
+
+fn unbound(gf: &GraphFile, what: &str) -> anyhow::Error {
+    user_err("graph_unbound", format!("Graph {} has no table bound for {}; use USING TABLES when creating the graph", gf.qualified, what))
+}
+
+// Keys compare as text, matching how traversals read key columns
+fn term_key(t: &ArithTerm) -> Option<String> {
+    match t {
+        ArithTerm::Str(s) => Some(s.clone()),
+        ArithTerm::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => Some(format!("{}", *n as i64)),
+        ArithTerm::Number(n) => Some(n.to_string()),
+        ArithTerm::Col { name, .. } => Some(name.clone()),
+        ArithTerm::Null => None,
+    }
+}
+
+fn row_key(row: &[ArithTerm], idx: usize, what: &str) -> Result<String> {
+    row.get(idx).and_then(term_key).ok_or_else(|| user_err("graph_key", format!("{} must not be NULL", what)))
+}
+
+fn read_table(store: &SharedStore, table: &str) -> Result<DataFrame> { store.0.lock().read_df(table) }
+
+// Keys of a label's nodes table; None when the label is undeclared or has no table
+fn label_keys(store: &SharedStore, gf: &GraphFile, label: &str) -> Result<Option<HashSet<String>>> {
+    let Some(def) = gf.nodes.iter().find(|n| n.label.eq_ignore_ascii_case(label)) else { return Ok(None) };
+    let Some(table) = &def.table else { return Ok(None) };
+    let df = read_table(store, table)?;
+    if df.height() == 0 { return Ok(Some(HashSet::new())); }
+    Ok(Some(column_keys(&df, &key_column(def))?.into_iter().collect()))
+}
+
+// Remove the rows of `df` (the current contents of `table`) selected by `mask`
+fn delete_rows(store: &SharedStore, table: &str, df: DataFrame, mask: Vec<bool>) -> Result<usize> {
+    let n = mask.iter().filter(|m| **m).count();
+    if n == 0 { return Ok(0); }
+    let mask = BooleanChunked::new(MASK.into(), mask);
+    let deleted = df.filter(&mask)?;
+    let kept = df.filter(&mask.not())?;
+    let guard = store.0.lock();
+    guard.rewrite_table_df(table, kept)?;
+    guard.record_changes(table, crate::storage::cdc::ChangeOp::Delete, &deleted)?;
+    Ok(n)
+}
+
+// WAL/delta-log writes mirroring one statement on a GraphStore-backed graph
+struct StoreSync { handle: GraphHandle, txn: GraphTxn, seed: u64 }
+
+impl StoreSync {
+    fn open(store: &SharedStore, gf: &GraphFile) -> Result<Option<Self>> {
+        if !gf.engine.as_deref().map(|e| e.eq_ignore_ascii_case("graphstore")).unwrap_or(false) { return Ok(None); }
+        let handle = GraphHandle::open(store, &gf.qualified)?;
+        let txn = GraphTxn::begin(&handle.root, handle.manifest.epoch.unwrap_or(0))?;
+        let seed = handle.manifest.partitioning.as_ref().and_then(|p| p.hash_seed).unwrap_or(0);
+        Ok(Some(Self { handle, txn, seed }))
+    }
+
+    // Same hash_mod routing as INSERT EDGE
+    fn part(&self, src: u64) -> u32 { ((src ^ self.seed) % (self.handle.manifest.partitions.max(1) as u64)) as u32 }
+
+    fn node(&mut self, label: &str, key: &str) -> Result<u64> {
+        self.handle.node_id(label, key)?.ok_or_else(|| {
+            AppError::NotFound { code: "graph_key".into(), message: format!("{} node '{}' does not exist in the graph store", label, key) }.into()
+        })
+    }
+
+    fn delete_edge(&mut self, from: &str, to: &str, src: &str, dst: &str, etype_id: u16) -> Result<()> {
+        if let (Some(a), Some(b)) = (self.handle.node_id(from, src)?, self.handle.node_id(to, dst)?) {
+            let part = self.part(a);
+            self.txn.delete_edge(part, a, b, etype_id);
+        }
+        Ok(())
+    }
+
+    fn commit(self) -> Result<()> {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_micros() as u64;
+        self.txn.commit(now)
+    }
+}
+
+fn insert_nodes(store: &SharedStore, gf: &GraphFile, label: &str, columns: Vec<String>, values: Vec<Vec<ArithTerm>>) -> Result<Value> {
+    let def = node_def(gf, label)?;
+    let key_col = key_column(def);
+    let ki = col_index(&columns, &key_col)
+        .ok_or_else(|| user_err("graph_key", format!("INSERT INTO GRAPH NODES {} requires the key column '{}'", def.label, key_col)))?;
+    let mut sync = StoreSync::open(store, gf)?;
+    if def.table.is_none() && sync.is_none() { return Err(unbound(gf, &format!("label {}", def.label))); }
+
+    let existing = label_keys(store, gf, &def.label)?.unwrap_or_default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut keys: Vec<String> = Vec::with_capacity(values.len());
+    for row in &values {
+        if row.len() != columns.len() {
+            return Err(user_err("graph_row", format!("INSERT INTO GRAPH expects {} values per row, got {}", columns.len(), row.len())));
+        }
+        let key = row_key(row, ki, &format!("{} key '{}'", def.label, key_col))?;
+        let known = existing.contains(&key) || match sync.as_mut() { Some(s) => s.handle.node_id(&def.label, &key)?.is_some(), None => false };
+        if known || !seen.insert(key.clone()) {
+            return Err(AppError::Conflict { code: "graph_duplicate_key".into(), message: format!("{} node '{}' already exists in graph {}", def.label, key, gf.qualified) }.into());
+        }
+        keys.push(key);
+    }
+
+    let inserted = values.len();
+    if let Some(table) = &def.table {
+        crate::server::exec::exec_insert::handle_insert(store, table.clone(), columns, values)?;
+    }
+    if let Some(mut s) = sync {
+        let mut next = s.handle.next_node_id()?;
+        for k in &keys {
+            s.txn.insert_node(&def.label, k, Some(next));
+            next += 1;
+        }
+        s.commit()?;
+    }
+    Ok(json!({"status": "ok", "graph": gf.qualified, "inserted": inserted}))
+}
+
+fn insert_edges(store: &SharedStore, gf: &GraphFile, etype: &str, columns: Vec<String>, values: Vec<Vec<ArithTerm>>) -> Result<Value> {
+    let (etype_id, def) = edge_def(gf, etype)?;
+    let (src_col, dst_col) = endpoint_columns(def);
+    let missing = |c: &str| user_err("graph_key", format!("INSERT INTO GRAPH EDGES {} requires the endpoint column '{}'", def.r#type, c));
+    let si = col_index(&columns, &src_col).ok_or_else(|| missing(&src_col))?;
+    let di = col_index(&columns, &dst_col).ok_or_else(|| missing(&dst_col))?;
+    let mut sync = StoreSync::open(store, gf)?;
+    if def.table.is_none() && sync.is_none() { return Err(unbound(gf, &format!("edge type {}", def.r#type))); }
+
+    // Both endpoints must be existing nodes of the labels the edge type connects
+    let from_keys = label_keys(store, gf, &def.from)?;
+    let to_keys = if def.to.eq_ignore_ascii_case(&def.from) { from_keys.clone() } else { label_keys(store, gf, &def.to)? };
+    for row in &values {
+        if row.len() != columns.len() {
+            return Err(user_err("graph_row", format!("INSERT INTO GRAPH expects {} values per row, got {}", columns.len(), row.len())));
+        }
+        let src = row_key(row, si, &format!("{} edge '{}'", def.r#type, src_col))?;
+        let dst = row_key(row, di, &format!("{} edge '{}'", def.r#type, dst_col))?;
+        for (key, label, known) in [(&src, &def.from, &from_keys), (&dst, &def.to, &to_keys)] {
+            if known.as_ref().map(|k| !k.contains(key)).unwrap_or(false) {
+                return Err(AppError::NotFound { code: "graph_key".into(), message: format!("{} node '{}' does not exist in graph {}", label, key, gf.qualified) }.into());
+            }
+        }
+        if let Some(s) = sync.as_mut() {
+            let (a, b) = (s.node(&def.from, &src)?, s.node(&def.to, &dst)?);
+            let part = s.part(a);
+            s.txn.insert_edge(part, a, b, etype_id as u16);
+        }
+    }
+
+    let inserted = values.len();
+    if let Some(table) = &def.table {
+        crate::server::exec::exec_insert::handle_insert(store, table.clone(), columns, values)?;
+    }
+    if let Some(s) = sync { s.commit()?; }
+    Ok(json!({"status": "ok", "graph": gf.qualified, "inserted": inserted}))
+}
+
+fn delete_nodes(store: &SharedStore, gf: &GraphFile, label: &str, keys: Vec<Vec<String>>, detach: bool) -> Result<Value> {
+    let def = node_def(gf, label)?;
+    let sync = StoreSync::open(store, gf)?;
+    if def.table.is_none() && sync.is_none() { return Err(unbound(gf, &format!("label {}", def.label))); }
+    let doomed: HashSet<String> = keys.into_iter().flatten().collect();
+
+    // Incident edges per edge table; several edge types may share one table
+    let mut incident: HashMap<String, (DataFrame, Vec<bool>)> = HashMap::new();
+    let mut pairs: Vec<(usize, String, String)> = Vec::new();
+    for (i, e) in gf.edges.iter().enumerate() {
+        let (from, to) = (e.from.eq_ignore_ascii_case(&def.label), e.to.eq_ignore_ascii_case(&def.label));
+        let Some(table) = e.table.as_ref().filter(|_| from || to) else { continue };
+        if !incident.contains_key(table) {
+            let df = read_table(store, table)?;
+            let h = df.height();
+            incident.insert(table.clone(), (df, vec![false; h]));
+        }
+        let (df, mask) = incident.get_mut(table).unwrap();
+        if df.height() == 0 { continue; }
+        let (src_col, dst_col) = endpoint_columns(e);
+        let (src, dst) = (column_keys(df, &src_col)?, column_keys(df, &dst_col)?);
+        for (row, (s, d)) in src.into_iter().zip(dst).enumerate() {
+            if (from && doomed.contains(&s)) || (to && doomed.contains(&d)) {
+                mask[row] = true;
+                pairs.push((i, s, d));
+            }
+        }
+    }
+    if !pairs.is_empty() && !detach {
+        return Err(AppError::Conflict {
+            code: "graph_node_has_edges".into(),
+            message: format!("{} {} node(s) still have {} edge(s); delete the edges first or use DELETE FROM GRAPH {} DETACH NODES", doomed.len(), def.label, pairs.len(), gf.qualified),
+        }.into());
+    }
+
+    let mut edges_deleted = 0usize;
+    for (table, (df, mask)) in incident {
+        edges_deleted += delete_rows(store, &table, df, mask)?;
+    }
+    let mut deleted = 0usize;
+    if let Some(table) = &def.table {
+        let df = read_table(store, table)?;
+        if df.height() > 0 {
+            let mask: Vec<bool> = column_keys(&df, &key_column(def))?.iter().map(|k| doomed.contains(k)).collect();
+            deleted = delete_rows(store, table, df, mask)?;
+        }
+    }
+    if let Some(mut s) = sync {
+        for (i, src, dst) in &pairs {
+            let e = &gf.edges[*i];
+            s.delete_edge(&e.from, &e.to, src, dst, *i as u16)?;
+        }
+        for k in &doomed {
+            if s.handle.node_id(&def.label, k)?.is_some() { s.txn.delete_node(&def.label, k); }
+        }
+        s.commit()?;
+    }
+    Ok(json!({"status": "ok", "graph": gf.qualified, "deleted": deleted, "edges_deleted": edges_deleted}))
+}
+
+fn delete_edges(store: &SharedStore, gf: &GraphFile, etype: &str, keys: Vec<Vec<String>>) -> Result<Value> {
+    let (etype_id, def) = edge_def(gf, etype)?;
+    let sync = StoreSync::open(store, gf)?;
+    if def.table.is_none() && sync.is_none() { return Err(unbound(gf, &format!("edge type {}", def.r#type))); }
+    let doomed: HashSet<(String, String)> = keys.into_iter().filter_map(|k| match k.as_slice() {
+        [s, d] => Some((s.clone(), d.clone())),
+        _ => None,
+    }).collect();
+
+    let mut deleted = 0usize;
+    if let Some(table) = &def.table {
+        let df = read_table(store, table)?;
+        if df.height() > 0 {
+            let (src_col, dst_col) = endpoint_columns(def);
+            let (src, dst) = (column_keys(&df, &src_col)?, column_keys(&df, &dst_col)?);
+            let mask: Vec<bool> = src.into_iter().zip(dst).map(|p| doomed.contains(&p)).collect();
+            deleted = delete_rows(store, table, df, mask)?;
+        }
+    }
+    if let Some(mut s) = sync {
+        for (src, dst) in &doomed { s.delete_edge(&def.from, &def.to, src, dst, etype_id as u16)?; }
+        s.commit()?;
+    }
+    Ok(json!({"status": "ok", "graph": gf.qualified, "deleted": deleted}))
+}
@@ -1,6 +1,6 @@
 //! exec_graph_runtime
 //! ------------------
-//! Runtime helpers to materialize Graph TVFs (graph_neighbors, graph_paths)
+//! Runtime helpers to materialize Graph TVFs (graph_neighbors, graph_paths, graph_match)
 //! backed by `.graph` catalogs and regular edge tables.
 
 use anyhow::{anyhow, Result};
@@ -75,8 +75,7 @@ fn unescape_and_unquote(s: &str) -> String {
 
 fn path_for_graph(store: &SharedStore, qualified: &str) -> PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR_STR);
-    p.push(local);
+    p = crate::ident::to_local_path(&p, &qualified);
     p.set_extension("graph");
     p
 }
@@ -84,8 +83,7 @@ fn path_for_graph(store: &SharedStore, qualified: &str) -> PathBuf {
 fn path_for_graph_manifest(store: &SharedStore, qualified: &str) -> PathBuf {
     // <db>/<schema>/<name>.gstore/meta/manifest.json
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR_STR);
-    p.push(format!("{}", local));
+    p = crate::ident::to_local_path(&p, &qualified);
     p.set_extension("gstore");
     p.push("meta");
     p.push("manifest.json");
@@ -471,6 +469,207 @@ pub fn graph_paths_df(
     }
 }
 
+// Node rows for one label: its bound table and the first row of each key
+struct LabelRows { df: DataFrame, index: HashMap<String, IdxSize> }
+
+// One edge mapping's table with endpoint keys normalized to strings
+struct EdgeRows { df: DataFrame, src: Vec<String>, dst: Vec<String> }
+
+// A partial pattern binding: one key per node so far and (edge mapping, row) per relationship
+#[derive(Clone)]
+struct Binding { keys: Vec<String>, edges: Vec<(usize, usize)> }
+
+pub(crate) fn column_keys(df: &DataFrame, col: &str) -> Result<Vec<String>> {
+    let s = df.column(col).map_err(|_| anyhow!("graph_match: column '{}' not found", col))?;
+    Ok((0..s.len())
+        .map(|i| match s.get(i) {
+            Ok(v) => v.get_str().map(unquote).unwrap_or_else(|| unquote(&v.to_string())),
+            Err(_) => String::new(),
+        })
+        .collect())
+}
+
+fn load_label_rows(store: &SharedStore, gf: &GraphFile, label: &str) -> Result<Option<LabelRows>> {
+    let def = gf
+        .nodes
+        .iter()
+        .find(|n| n.label.eq_ignore_ascii_case(label))
+        .ok_or_else(|| anyhow!("graph_match: graph '{}' has no node label '{}'", gf.qualified, label))?;
+    // Labels without a nodes table only carry keys; membership is not checked
+    let Some(table) = def.table.clone() else { return Ok(None) };
+    let key_col = def.key_column.clone().unwrap_or_else(|| def.key.clone());
+    let df = store.0.lock().read_df(&table)?;
+    let mut index: HashMap<String, IdxSize> = HashMap::new();
+    for (i, k) in column_keys(&df, &key_col)?.into_iter().enumerate() {
+        index.entry(k).or_insert(i as IdxSize);
+    }
+    Ok(Some(LabelRows { df, index }))
+}
+
+fn load_edge_rows(store: &SharedStore, e: &GraphEdgeDef) -> Result<EdgeRows> {
+    let table = e
+        .table
+        .clone()
+        .ok_or_else(|| anyhow!("Graph edges table not bound; use USING TABLES (edges=...) when creating graph"))?;
+    let df = store.0.lock().read_df(&table)?;
+    let src = column_keys(&df, e.src_column.as_deref().unwrap_or("src"))?;
+    let dst = column_keys(&df, e.dst_column.as_deref().unwrap_or("dst"))?;
+    Ok(EdgeRows { df, src, dst })
+}
+
+// Append `<prefix>.<column>` for every column of `df` taken at `idx`, skipping names already present
+fn push_taken_columns(cols: &mut Vec<Column>, df: &DataFrame, idx: &IdxCa, prefix: &str) -> Result<()> {
+    let taken = df.take(idx)?;
+    for c in taken.get_columns() {
+        let name = format!("{}.{}", prefix, c.name());
+        if cols.iter().any(|x| x.name().eq_ignore_ascii_case(&name)) { continue; }
+        let mut col = c.clone();
+        col.rename(name.into());
+        cols.push(col);
+    }
+    Ok(())
+}
+
+/// Materialize graph_match(graph, pattern) – one row per binding of a fixed-length pattern
+/// such as `(a:Tool)-[r:Calls]->(b)<-[:Cites]-(c)`. Hops are hash joins: each relationship's
+/// edge tables are indexed on the near endpoint and probed with the keys bound so far.
+/// Columns are `<var>.key` plus every nodes-table column as `<var>.<column>`; relationships
+/// yield `<var>.type`, `<var>.src`, `<var>.dst` and, for a single edge mapping, its columns.
+pub fn graph_match_df(store: &SharedStore, graph: &str, pattern: &str) -> Result<DataFrame> {
+    let qname = qualify_graph_name(graph);
+    let gf = read_graph_file(store, &qname)?;
+    let pat = crate::server::query::query_parse_match::parse_graph_pattern(pattern)?;
+    for (i, r) in pat.rels.iter().enumerate() {
+        if pat.nodes.iter().any(|n| n.var == r.var) || pat.rels[..i].iter().any(|o| o.var == r.var) {
+            anyhow::bail!("graph_match: variable '{}' is used for more than one relationship or node", r.var);
+        }
+    }
+
+    // Edge mappings per relationship, filtered by type when one is given
+    let mut rel_defs: Vec<Vec<usize>> = Vec::with_capacity(pat.rels.len());
+    for r in &pat.rels {
+        let defs: Vec<usize> = gf
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| r.etype.as_ref().map(|t| e.r#type.eq_ignore_ascii_case(t)).unwrap_or(true))
+            .map(|(i, _)| i)
+            .collect();
+        if defs.is_empty() {
+            anyhow::bail!("graph_match: graph '{}' has no edge type '{}'", gf.qualified, r.etype.as_deref().unwrap_or(""));
+        }
+        rel_defs.push(defs);
+    }
+    let mut edge_rows: HashMap<usize, EdgeRows> = HashMap::new();
+    for d in rel_defs.iter().flatten() {
+        if !edge_rows.contains_key(d) { edge_rows.insert(*d, load_edge_rows(store, &gf.edges[*d])?); }
+    }
+
+    // Node labels: explicit, else inferred when every mapping of an adjacent hop agrees
+    let infer = |j: usize| -> Option<String> {
+        let (defs, incoming, far) = if j > 0 {
+            (&rel_defs[j - 1], pat.rels[j - 1].incoming, true)
+        } else if !pat.rels.is_empty() {
+            (&rel_defs[0], pat.rels[0].incoming, false)
+        } else {
+            return None;
+        };
+        // The edge's `to` label sits on the far side of an outgoing hop (and the near side of an incoming one)
+        let labels: Vec<&String> = defs.iter().map(|d| if far != incoming { &gf.edges[*d].to } else { &gf.edges[*d].from }).collect();
+        let first = labels.first()?;
+        labels.iter().all(|l| l.eq_ignore_ascii_case(first)).then(|| (*first).clone())
+    };
+    let mut label_rows: Vec<Option<LabelRows>> = Vec::with_capacity(pat.nodes.len());
+    for (j, n) in pat.nodes.iter().enumerate() {
+        let rows = match &n.label {
+            Some(l) => load_label_rows(store, &gf, l)?,
+            None => match infer(j) { Some(l) => load_label_rows(store, &gf, &l).ok().flatten(), None => None },
+        };
+        label_rows.push(rows);
+    }
+    // A key binds to a labeled node only when the label's nodes table holds it
+    let admits = |j: usize, key: &str| -> bool {
+        pat.nodes[j].label.is_none() || label_rows[j].as_ref().map(|r| r.index.contains_key(key)).unwrap_or(true)
+    };
+    let first_slot = |j: usize| -> usize { pat.nodes.iter().position(|n| n.var == pat.nodes[j].var).unwrap_or(j) };
+
+    // Seed from the first node's table, else from the near endpoints of the first hop
+    let mut seeds: Vec<String> = Vec::new();
+    let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+    if let (Some(_), Some(rows)) = (&pat.nodes[0].label, &label_rows[0]) {
+        let mut keyed: Vec<(&String, &IdxSize)> = rows.index.iter().collect();
+        keyed.sort_by_key(|(_, i)| **i);
+        seeds.extend(keyed.into_iter().map(|(k, _)| k.clone()));
+    } else if !pat.rels.is_empty() {
+        for d in &rel_defs[0] {
+            let er = &edge_rows[d];
+            let near = if pat.rels[0].incoming { &er.dst } else { &er.src };
+            for k in near {
+                if admits(0, k) && seen.insert(k.clone()) { seeds.push(k.clone()); }
+            }
+        }
+    } else {
+        anyhow::bail!("graph_match: a single-node pattern needs a label bound to a nodes table");
+    }
+    let mut bindings: Vec<Binding> = seeds.into_iter().map(|k| Binding { keys: vec![k], edges: Vec::new() }).collect();
+
+    for (j, r) in pat.rels.iter().enumerate() {
+        // Build side: edges keyed by the endpoint shared with node j
+        let mut build: HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+        for d in &rel_defs[j] {
+            let er = &edge_rows[d];
+            let near = if r.incoming { &er.dst } else { &er.src };
+            for (row, k) in near.iter().enumerate() {
+                build.entry(k.as_str()).or_default().push((*d, row));
+            }
+        }
+        let repeat = first_slot(j + 1);
+        let mut next: Vec<Binding> = Vec::new();
+        for b in &bindings {
+            let Some(hits) = build.get(b.keys[j].as_str()) else { continue };
+            for &(d, row) in hits {
+                // Relationships are matched at most once per binding
+                if b.edges.contains(&(d, row)) { continue; }
+                let er = &edge_rows[&d];
+                let far = if r.incoming { &er.src[row] } else { &er.dst[row] };
+                if !admits(j + 1, far) { continue; }
+                if repeat <= j && &b.keys[repeat] != far { continue; }
+                let mut nb = b.clone();
+                nb.keys.push(far.clone());
+                nb.edges.push((d, row));
+                next.push(nb);
+            }
+        }
+        bindings = next;
+    }
+
+    let mut cols: Vec<Column> = Vec::new();
+    for (j, n) in pat.nodes.iter().enumerate() {
+        if first_slot(j) != j { continue; }
+        let keys: Vec<String> = bindings.iter().map(|b| b.keys[j].clone()).collect();
+        cols.push(Series::new(format!("{}.key", n.var).into(), keys).into());
+        if let Some(rows) = &label_rows[j] {
+            let idx = IdxCa::from_iter_options("idx".into(), bindings.iter().map(|b| rows.index.get(&b.keys[j]).copied()));
+            push_taken_columns(&mut cols, &rows.df, &idx, &n.var)?;
+        }
+    }
+    for (j, r) in pat.rels.iter().enumerate() {
+        let types: Vec<String> = bindings.iter().map(|b| gf.edges[b.edges[j].0].r#type.clone()).collect();
+        let src: Vec<String> = bindings.iter().map(|b| edge_rows[&b.edges[j].0].src[b.edges[j].1].clone()).collect();
+        let dst: Vec<String> = bindings.iter().map(|b| edge_rows[&b.edges[j].0].dst[b.edges[j].1].clone()).collect();
+        cols.push(Series::new(format!("{}.type", r.var).into(), types).into());
+        cols.push(Series::new(format!("{}.src", r.var).into(), src).into());
+        cols.push(Series::new(format!("{}.dst", r.var).into(), dst).into());
+        if let [d] = rel_defs[j].as_slice() {
+            let idx = IdxCa::from_vec("idx".into(), bindings.iter().map(|b| b.edges[j].1 as IdxSize).collect());
+            push_taken_columns(&mut cols, &edge_rows[d].df, &idx, &r.var)?;
+        }
+    }
+    let out = DataFrame::new(cols)?;
+    crate::tprintln!("[graph.match] pattern={} rows={} cols={:?}", pattern, out.height(), out.get_column_names());
+    Ok(out)
+}
+
 /// Parse ISO8601 string or integer text into epoch milliseconds (i64)
 fn parse_time_to_i64(s: &str) -> Option<i64> {
     // First try integer parse
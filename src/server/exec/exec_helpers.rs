@@ -32,6 +32,9 @@ pub fn dataframe_to_json(df: &DataFrame) -> serde_json::Value {
                     if let Some(n) = serde_json::Number::from_f64(v) { serde_json::Value::Number(n) } else { serde_json::Value::Null }
                 }
                 Ok(AnyValue::Boolean(b)) => serde_json::Value::Bool(b),
+                // NUMERIC as text so no digits are lost to a JSON double
+                Ok(AnyValue::Decimal(v, scale)) => serde_json::Value::String(crate::storage::decimal::format_value(v, scale)),
+                Ok(AnyValue::Datetime(v, unit, _)) => serde_json::Value::String(crate::storage::native::format_timestamp_ms(crate::storage::native::to_ms(v, unit))),
                 Ok(AnyValue::String(v)) => serde_json::Value::String(v.to_string()),
                 Ok(AnyValue::StringOwned(v)) => serde_json::Value::String(v.to_string()),
                 Ok(AnyValue::Null) => serde_json::Value::Null,
@@ -59,6 +62,8 @@ pub fn dataframe_to_tabular(df: &DataFrame) -> (Vec<String>, Vec<Vec<Option<Stri
                 Ok(AnyValue::Int32(v)) => Some((v as i64).to_string()),
                 Ok(AnyValue::Float64(v)) => Some(v.to_string()),
                 Ok(AnyValue::Boolean(v)) => Some(if v {"t".into()} else {"f".into()}),
+                Ok(AnyValue::Decimal(v, scale)) => Some(crate::storage::decimal::format_value(v, scale)),
+                Ok(AnyValue::Datetime(v, unit, _)) => Some(crate::storage::native::format_timestamp_ms(crate::storage::native::to_ms(v, unit))),
                 Ok(AnyValue::String(v)) => Some(v.to_string()),
                 Ok(AnyValue::StringOwned(v)) => Some(v.to_string()),
                 Ok(AnyValue::Null) => None,
@@ -78,16 +83,19 @@ pub fn execute_select_df(store: &crate::storage::SharedStore, q: &Query) -> Resu
 
 
 pub fn qualify_identifier_with_defaults(ident: &str, db: &str, schema: &str) -> String {
-    let d = crate::ident::QueryDefaults::new(db.to_string(), schema.to_string());
+    let d = crate::system::with_session_search_path(crate::ident::QueryDefaults::new(db.to_string(), schema.to_string()));
     crate::ident::qualify_time_ident(ident, &d)
 }
 
 pub fn qualify_identifier_regular_table_with_defaults(ident: &str, db: &str, schema: &str) -> String {
-    let d = crate::ident::QueryDefaults::new(db.to_string(), schema.to_string());
+    let d = crate::system::with_session_search_path(crate::ident::QueryDefaults::new(db.to_string(), schema.to_string()));
     crate::ident::qualify_regular_ident(ident, &d)
 }
 
-pub fn normalize_query_with_defaults(q: &str, db: &str, schema: &str) -> String {
+pub fn normalize_query_with_defaults(raw: &str, db: &str, schema: &str) -> String {
+    // Rewrites work on the comment-free text; statements left alone keep their comments (optimizer hints)
+    let uncommented = crate::server::query::strip_sql_comments(raw);
+    let q = uncommented.trim();
     let up = q.to_uppercase();
     // Normalize unqualified regular TABLE DDL to include current db/schema
     if up.starts_with("DROP TABLE ") {
@@ -141,7 +149,7 @@ pub fn normalize_query_with_defaults(q: &str, db: &str, schema: &str) -> String
         return format!("INSERT INTO {}{}", qualified, rest);
     }
     // Do not rewrite SELECT or SLICE statements; column/table resolution is handled by Data Context at execution time
-    if up.starts_with("SELECT ") || up.starts_with("SLICE") { return q.to_string(); }
+    if up.starts_with("SELECT ") || up.starts_with("SLICE") { return raw.to_string(); }
     // Qualify CREATE TABLE targets using current db/schema (regular table)
     if up.starts_with("CREATE TABLE ") {
         let after = &q["CREATE TABLE ".len()..];
@@ -195,6 +203,21 @@ pub fn normalize_query_with_defaults(q: &str, db: &str, schema: &str) -> String
             } else {
                 qualify_identifier_regular_table_with_defaults(&normalized, db, schema)
             };
+            // UPDATE ... FROM <ident>: qualify the joined table the same way
+            if let Some(f) = crate::server::query::find_top_level_keyword(rest, " FROM ") {
+                let from_start = f + 6;
+                let tail = &rest[from_start..];
+                let lead = tail.len() - tail.trim_start().len();
+                let tail = tail.trim_start();
+                let end = tail.find(char::is_whitespace).unwrap_or(tail.len());
+                let src = crate::ident::normalize_identifier(&tail[..end]);
+                let src_q = if src.to_lowercase().ends_with(".time") {
+                    qualify_identifier_with_defaults(&src, db, schema)
+                } else {
+                    qualify_identifier_regular_table_with_defaults(&src, db, schema)
+                };
+                return format!("UPDATE {}{}{}{}", qualified, &rest[..from_start + lead], src_q, &tail[end..]);
+            }
             return format!("UPDATE {}{}", qualified, rest);
         }
         return q.to_string();
@@ -206,5 +229,5 @@ pub fn normalize_query_with_defaults(q: &str, db: &str, schema: &str) -> String
             return format!("{} {}", left, normalized);
         }
     }
-    q.to_string()
+    raw.to_string()
 }
\ No newline at end of file
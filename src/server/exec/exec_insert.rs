@@ -64,6 +64,7 @@ pub fn handle_insert(store: &SharedStore, table: String, columns: Vec<String>, v
             }
             records.push(crate::storage::Record { _time: time_val, sensors });
         }
+        crate::server::quota::charge_ingest(crate::server::quota::records_size(&records))?;
         // Acquire lock only while writing records
         {
             let guard = store.0.lock();
@@ -120,7 +121,16 @@ pub fn handle_insert(store: &SharedStore, table: String, columns: Vec<String>, v
         series_vec.push(series);
     }
     let columns_vec: Vec<Column> = series_vec.into_iter().map(|s| s.into()).collect();
-    let new_df = DataFrame::new(columns_vec)?;
+    let mut new_df = DataFrame::new(columns_vec)?;
+    // Declared NUMERIC, BOOLEAN and TIMESTAMP columns take the literals in their stored type so they stack onto existing rows
+    store.0.lock().conform_declared_columns(&table_path, &mut new_df)?;
+    // Computed columns are derived from the inserted values, as for ingested records
+    let new_df = {
+        let guard = store.0.lock();
+        let (mut schema, _) = guard.load_schema_with_locks(&table_path).unwrap_or_default();
+        guard.apply_computed_columns(&table_path, new_df, &mut schema)?
+    };
+    crate::server::quota::charge_ingest(new_df.estimated_size())?;
     crate::tprintln!("[EXEC_INSERT] build_df rows={} cols={} took={:?}", new_df.height(), new_df.width(), __t_build_df.elapsed());
 
     // Enforce primary key uniqueness if table defines a primary key
@@ -325,6 +335,7 @@ pub fn handle_insert(store: &SharedStore, table: String, columns: Vec<String>, v
                         left = left.hstack(&[s.into()])?;
                     }
                 }
+                widen_to_common_types(&mut left, &mut right)?;
                 // Reorder right to match left column order for vstack
                 let final_order = left.get_column_names();
                 let mut reordered_right_cols: Vec<Column> = Vec::with_capacity(final_order.len());
@@ -341,6 +352,7 @@ pub fn handle_insert(store: &SharedStore, table: String, columns: Vec<String>, v
     {
         let guard = store.0.lock();
         guard.rewrite_table_df(&table_path, combined)?;
+        guard.record_changes(&table_path, crate::storage::cdc::ChangeOp::Insert, &new_df)?;
     }
     crate::tprintln!("[EXEC_INSERT] rewrite_table rows={} took={:?} total={:?}", new_df.height(), __t_rewrite.elapsed(), __t0.elapsed());
     Ok(serde_json::json!({"status":"ok", "inserted": new_df.height()}))
@@ -349,6 +361,7 @@ pub fn handle_insert(store: &SharedStore, table: String, columns: Vec<String>, v
 // INSERT ... SELECT support: take a DataFrame and insert into target table.
 pub fn handle_insert_from_df(store: &SharedStore, table: String, mut columns: Vec<String>, mut df: DataFrame) -> Result<serde_json::Value> {
     let __t0 = std::time::Instant::now();
+    crate::server::quota::charge_ingest(df.estimated_size())?;
     // Qualify the target identifier using current session defaults.
     let qd = crate::system::current_query_defaults();
     let lower = table.to_ascii_lowercase();
@@ -420,6 +433,10 @@ pub fn handle_insert_from_df(store: &SharedStore, table: String, mut columns: Ve
                     Ok(AnyValue::Int64(v)) => Some(serde_json::json!(v)),
                     Ok(AnyValue::UInt64(v)) => Some(serde_json::json!(v)),
                     Ok(AnyValue::Float64(v)) => Some(serde_json::json!(v)),
+                    // Text keeps every digit; write_records parses it into the column's scale
+                    Ok(AnyValue::Decimal(v, scale)) => Some(serde_json::json!(crate::storage::decimal::format_value(v, scale))),
+                    Ok(AnyValue::Boolean(b)) => Some(serde_json::json!(b)),
+                    Ok(AnyValue::Datetime(v, unit, _)) => Some(serde_json::json!(crate::storage::native::to_ms(v, unit))),
                     Ok(AnyValue::String(s)) => Some(serde_json::json!(s)),
                     Ok(AnyValue::StringOwned(s)) => Some(serde_json::json!(s)),
                     Ok(_) => None,
@@ -436,7 +453,8 @@ pub fn handle_insert_from_df(store: &SharedStore, table: String, mut columns: Ve
     }
 
     // For regular tables: enforce PK then append
-    let new_df = df.clone();
+    let mut new_df = df.clone();
+    store.0.lock().conform_declared_columns(&table_path, &mut new_df)?;
     // Enforce primary key uniqueness if table defines a primary key
     {
         let pk_cols_opt: Option<Vec<String>> = { let g = store.0.lock(); g.get_primary_key(&table_path) };
@@ -577,6 +595,7 @@ pub fn handle_insert_from_df(store: &SharedStore, table: String, mut columns: Ve
                         left = left.hstack(&[s.into()])?;
                     }
                 }
+                widen_to_common_types(&mut left, &mut right)?;
                 // Reorder right to match left column order for vstack
                 let final_order = left.get_column_names();
                 let mut reordered_right_cols: Vec<Column> = Vec::with_capacity(final_order.len());
@@ -593,7 +612,30 @@ pub fn handle_insert_from_df(store: &SharedStore, table: String, mut columns: Ve
     {
         let g = store.0.lock();
         g.rewrite_table_df(&table_path, combined.clone())?;
+        g.record_changes(&table_path, crate::storage::cdc::ChangeOp::Insert, &new_df)?;
     }
     crate::tprintln!("[INSERT SELECT] appended rows={} into '{}' took={:?}", new_df.height(), table_path, __t0.elapsed());
     Ok(serde_json::json!({"status":"ok", "inserted": new_df.height()}))
 }
+
+/// Cast columns that `left` and `right` type differently to a shared type so they vstack,
+/// e.g. a declared int64 column read back empty against float literals. Both frames must
+/// already hold the same column names.
+fn widen_to_common_types(left: &mut DataFrame, right: &mut DataFrame) -> Result<()> {
+    let names: Vec<String> = left.get_column_names().iter().map(|n| n.to_string()).collect();
+    for name in &names {
+        let lt = left.column(name)?.dtype().clone();
+        let rt = right.column(name)?.dtype().clone();
+        if lt == rt { continue; }
+        let target = crate::storage::schema::merge_dtype(lt.clone(), rt.clone());
+        if lt != target {
+            let c = left.column(name)?.cast(&target)?;
+            left.replace(name, c.take_materialized_series())?;
+        }
+        if rt != target {
+            let c = right.column(name)?.cast(&target)?;
+            right.replace(name, c.take_materialized_series())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! exec_jobs
+//! ---------
+//! CREATE JOB / DROP JOB / ALTER JOB. Jobs are kept and run by `server::jobs`; SHOW JOBS reads
+//! `pg_catalog.clarium_jobs`.
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::server::jobs::{self, Job};
+use crate::server::query::JobBody;
+use crate::storage::SharedStore;
+
+fn root_of(store: &SharedStore) -> String { store.root_path().to_string_lossy().to_string() }
+
+/// The job is owned by the user creating it (the default admin for statements run without a
+/// session) and keeps that session's current database and schema.
+pub fn handle_create_job(store: &SharedStore, name: &str, schedule: &str, body: JobBody) -> Result<serde_json::Value> {
+    let owner = crate::server::activity::current_user().unwrap_or_else(|| "clarium".to_string());
+    let database = crate::server::activity::current_database().unwrap_or_else(crate::system::get_current_database);
+    let job = Job {
+        name: name.to_string(),
+        owner: owner.clone(),
+        schedule: schedule.to_string(),
+        body,
+        enabled: true,
+        database,
+        schema: crate::system::get_current_schema(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    jobs::create_job(&root_of(store), job)?;
+    info!(target: "clarium::ddl", "CREATE JOB {} SCHEDULE '{}' (owner={})", name, schedule, owner);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub fn handle_drop_job(store: &SharedStore, name: &str, if_exists: bool) -> Result<serde_json::Value> {
+    if !jobs::drop_job(&root_of(store), name)? {
+        if if_exists { return Ok(serde_json::json!({"status": "ok"})); }
+        return Err(crate::error::AppError::NotFound { code: "undefined_object".into(), message: format!("job \"{}\" does not exist", name) }.into());
+    }
+    info!(target: "clarium::ddl", "DROP JOB {}", name);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub fn handle_alter_job(store: &SharedStore, name: &str, enabled: bool) -> Result<serde_json::Value> {
+    jobs::set_enabled(&root_of(store), name, enabled)?;
+    info!(target: "clarium::ddl", "ALTER JOB {} {}", name, if enabled { "ENABLE" } else { "DISABLE" });
+    Ok(serde_json::json!({"status": "ok"}))
+}
@@ -3,14 +3,17 @@
 //! KV key operations extracted from exec.rs to keep dispatcher thin.
 
 use anyhow::Result;
-use crate::storage::{SharedStore, KvValue};
+use crate::server::query::{Command, KvBatchWrite, KvOp};
+use crate::storage::kv_events::{self, WatchMessage, WatchTarget};
+use crate::storage::{SharedStore, KvValue, KvWrite, KvWriteOutcome};
 use std::time::Duration;
+use tokio::sync::mpsc;
 
 use crate::server::exec::df_utils::read_df_or_kv;
 
-pub fn handle_write_key(store: &SharedStore, database: &str, st: &str, key: &str, value: &str, ttl_ms: Option<i64>, reset_on_access: Option<bool>) -> Result<serde_json::Value> {
-    let kv = store.kv_store(database, st);
-    let ttl: Option<Duration> = ttl_ms.and_then(|ms| if ms > 0 { Some(Duration::from_millis(ms as u64)) } else { None });
+/// Value of a WRITE KEY literal and its type: JSON, a quoted string, a table address, an
+/// integer, or a bare string.
+fn interpret_value(store: &SharedStore, value: &str) -> Result<(KvValue, &'static str)> {
     let vstr = value.trim();
     // Try to interpret like original behavior
     let kind: &str;
@@ -46,35 +49,48 @@ pub fn handle_write_key(store: &SharedStore, database: &str, st: &str, key: &str
             kind = "string"; KvValue::Str(vstr.to_string())
         }
     };
-    kv.set(key, kv_val, ttl, Some(reset_on_access.unwrap_or(false)));
+    Ok((kv_val, kind))
+}
+
+fn ttl_of(ttl_ms: Option<i64>) -> Option<Duration> {
+    ttl_ms.and_then(|ms| if ms > 0 { Some(Duration::from_millis(ms as u64)) } else { None })
+}
+
+pub fn handle_write_key(store: &SharedStore, database: &str, st: &str, key: &str, value: &str, ttl_ms: Option<i64>, reset_on_access: Option<bool>) -> Result<serde_json::Value> {
+    let kv = store.kv_store(database, st);
+    let (kv_val, kind) = interpret_value(store, value)?;
+    kv.set(key, kv_val, ttl_of(ttl_ms), Some(reset_on_access.unwrap_or(false)));
     Ok(serde_json::json!({"status":"ok","written":1,"type": kind}))
 }
 
 pub fn handle_read_key(store: &SharedStore, database: &str, st: &str, key: &str) -> Result<serde_json::Value> {
     let kv = store.kv_store(database, st);
-    if let Some(val) = kv.get(key) {
-        match val {
-            KvValue::Str(s) => Ok(serde_json::json!({"type":"string","value": s})),
-            KvValue::Int(n) => Ok(serde_json::json!({"type":"int","value": n})),
-            KvValue::Json(j) => Ok(serde_json::json!({"type":"json","value": j})),
-            KvValue::Bytes(b) => Ok(serde_json::json!({"type":"bytes","len": b.len()})),
-            KvValue::ParquetDf(df) => {
-                let cols_meta: Vec<serde_json::Value> = df.get_column_names().iter().map(|name| {
-                    let dt = df.column(name.as_str()).ok().map(|c| format!("{:?}", c.dtype())).unwrap_or_else(|| "Unknown".into());
-                    serde_json::json!({"name": name, "dtype": dt})
-                }).collect();
-                Ok(serde_json::json!({
-                    "type": "table",
-                    "rows": df.height(),
-                    "cols": df.width(),
-                    "columns": cols_meta
-                }))
-            }
-            KvValue::Bytes(b) => Ok(serde_json::json!({"type":"bytes","len": b.len()})),
-        }
-    } else {
+    // Version first: a write in between makes it stale, so a MULTI ... EXPECT on it conflicts
+    let version = kv.version(key);
+    let Some(val) = kv.get(key) else {
         anyhow::bail!(format!("Key not found: {}.store.{}.{}", database, st, key));
-    }
+    };
+    let mut out = match val {
+        KvValue::Str(s) => serde_json::json!({"type":"string","value": s}),
+        KvValue::Int(n) => serde_json::json!({"type":"int","value": n}),
+        KvValue::Json(j) => serde_json::json!({"type":"json","value": j}),
+        KvValue::Bytes(b) => serde_json::json!({"type":"bytes","len": b.len()}),
+        KvValue::ParquetDf(df) => {
+            let cols_meta: Vec<serde_json::Value> = df.get_column_names().iter().map(|name| {
+                let dt = df.column(name.as_str()).ok().map(|c| format!("{:?}", c.dtype())).unwrap_or_else(|| "Unknown".into());
+                serde_json::json!({"name": name, "dtype": dt})
+            }).collect();
+            serde_json::json!({
+                "type": "table",
+                "rows": df.height(),
+                "cols": df.width(),
+                "columns": cols_meta
+            })
+        }
+        v @ (KvValue::List(_) | KvValue::Set(_) | KvValue::Hash(_)) => serde_json::json!({"type": v.type_name(), "value": collection_json(&v)}),
+    };
+    out["version"] = serde_json::json!(version);
+    Ok(out)
 }
 
 pub fn handle_drop_key(store: &SharedStore, database: &str, st: &str, key: &str) -> Result<serde_json::Value> {
@@ -88,3 +104,112 @@ pub fn handle_rename_key(store: &SharedStore, database: &str, st: &str, from: &s
     let moved = kv.rename_key(from, to);
     Ok(serde_json::json!({"status":"ok","renamed": moved}))
 }
+
+/// JSON form of a list (array in order), set (sorted array) or hash (object); null otherwise.
+pub fn collection_json(v: &KvValue) -> serde_json::Value {
+    match v {
+        KvValue::List(l) => serde_json::json!(l),
+        KvValue::Set(m) => serde_json::json!(m),
+        KvValue::Hash(h) => serde_json::json!(h),
+        _ => serde_json::Value::Null,
+    }
+}
+
+pub fn handle_key_op(store: &SharedStore, database: &str, st: &str, key: &str, op: &KvOp) -> Result<serde_json::Value> {
+    let kv = store.kv_store(database, st);
+    match op {
+        KvOp::Push { front, values } => Ok(serde_json::json!({"status":"ok","length": kv.list_push(key, values, *front)?})),
+        KvOp::Pop { front } => Ok(serde_json::json!({"status":"ok","value": kv.list_pop(key, *front)?})),
+        KvOp::SetAdd { members } => Ok(serde_json::json!({"status":"ok","added": kv.set_add(key, members)?})),
+        KvOp::SetRemove { members } => Ok(serde_json::json!({"status":"ok","removed": kv.set_remove(key, members)?})),
+        KvOp::HashSet { fields } => Ok(serde_json::json!({"status":"ok","added": kv.hash_set(key, fields)?})),
+        KvOp::HashDelete { fields } => Ok(serde_json::json!({"status":"ok","removed": kv.hash_delete(key, fields)?})),
+        KvOp::HashGet { field } => Ok(serde_json::json!({"field": field, "value": kv.hash_get(key, field)?})),
+    }
+}
+
+/// The storage write for one statement of a MULTI batch.
+fn batch_write(store: &SharedStore, w: &KvBatchWrite) -> Result<(KvWrite, Option<&'static str>)> {
+    Ok(match w {
+        KvBatchWrite::Set { key, value, ttl_ms, reset_on_access } => {
+            let (value, kind) = interpret_value(store, value)?;
+            (KvWrite::Set { key: key.clone(), value, ttl: ttl_of(*ttl_ms), reset_on_access: Some(reset_on_access.unwrap_or(false)) }, Some(kind))
+        }
+        KvBatchWrite::Delete { key } => (KvWrite::Delete { key: key.clone() }, None),
+        KvBatchWrite::Op { key, op } => {
+            let key = key.clone();
+            let write = match op {
+                KvOp::Push { front, values } => KvWrite::Push { key, values: values.clone(), front: *front },
+                KvOp::Pop { front } => KvWrite::Pop { key, front: *front },
+                KvOp::SetAdd { members } => KvWrite::SetAdd { key, members: members.clone() },
+                KvOp::SetRemove { members } => KvWrite::SetRemove { key, members: members.clone() },
+                KvOp::HashSet { fields } => KvWrite::HashSet { key, fields: fields.clone() },
+                KvOp::HashDelete { fields } => KvWrite::HashDelete { key, fields: fields.clone() },
+                KvOp::HashGet { .. } => anyhow::bail!("Invalid MULTI: HGET is a read"),
+            };
+            (write, None)
+        }
+    })
+}
+
+/// Result of one batch write, keyed like the single-key statement's reply.
+pub fn write_outcome_json(outcome: &KvWriteOutcome, kind: Option<&str>) -> serde_json::Value {
+    match outcome {
+        KvWriteOutcome::Written => serde_json::json!({"written": 1, "type": kind}),
+        KvWriteOutcome::Deleted(existed) => serde_json::json!({"dropped": existed}),
+        KvWriteOutcome::Length(n) => serde_json::json!({"length": n}),
+        KvWriteOutcome::Popped(v) => serde_json::json!({"value": v}),
+        KvWriteOutcome::Added(n) => serde_json::json!({"added": n}),
+        KvWriteOutcome::Removed(n) => serde_json::json!({"removed": n}),
+    }
+}
+
+/// MULTI ... EXEC: the writes applied to one store all together or not at all.
+pub fn handle_kv_batch(store: &SharedStore, database: &str, st: &str, expect: &[(String, u64)], writes: &[KvBatchWrite]) -> Result<serde_json::Value> {
+    let mut batch = Vec::with_capacity(writes.len());
+    let mut kinds = Vec::with_capacity(writes.len());
+    for w in writes {
+        let (write, kind) = batch_write(store, w)?;
+        batch.push(write);
+        kinds.push(kind);
+    }
+    let res = store.kv_store(database, st).apply_batch(expect, &batch)?;
+    let results: Vec<serde_json::Value> = res.outcomes.iter().zip(kinds).map(|(o, k)| write_outcome_json(o, k)).collect();
+    Ok(serde_json::json!({"status":"ok","results": results,"versions": res.versions}))
+}
+
+/// WATCH / UNWATCH for one connection: watches deliver to `tx`, and `own` holds the ids this
+/// connection registered, which are the only ones it may remove.
+pub fn handle_watch_command(cmd: &Command, tx: &mpsc::Sender<WatchMessage>, own: &mut Vec<u64>) -> Result<serde_json::Value> {
+    match cmd {
+        Command::Watch { database, store, target } => {
+            let id = kv_events::watch(database, store, target.clone(), tx.clone());
+            own.push(id);
+            let (kind, value) = match target {
+                WatchTarget::Key(k) => ("key", k),
+                WatchTarget::Prefix(p) => ("prefix", p),
+            };
+            Ok(serde_json::json!({"status":"ok","watch": id, "database": database, "store": store, kind: value}))
+        }
+        Command::Unwatch { id: Some(id) } => {
+            if !own.contains(id) { anyhow::bail!("watch_not_found: {}", id); }
+            own.retain(|w| w != id);
+            kv_events::unwatch(*id);
+            Ok(serde_json::json!({"status":"ok","unwatched": 1}))
+        }
+        Command::Unwatch { id: None } => {
+            let n = own.len();
+            for id in own.drain(..) { kv_events::unwatch(id); }
+            Ok(serde_json::json!({"status":"ok","unwatched": n}))
+        }
+        _ => anyhow::bail!("not a WATCH or UNWATCH statement"),
+    }
+}
+
+/// Message sent to a connection for a watch delivery.
+pub fn watch_message_json(m: &WatchMessage) -> serde_json::Value {
+    match m {
+        WatchMessage::Event { watch, event } => serde_json::json!({"status":"event","watch": watch, "event": event}),
+        WatchMessage::Lagged => serde_json::json!({"status":"lagged","error":"watch receiver fell behind; its watches were dropped"}),
+    }
+}
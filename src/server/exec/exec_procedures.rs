@@ -0,0 +1,45 @@
+//! exec_procedures
+//! ---------------
+//! CREATE PROCEDURE / DROP PROCEDURE / CALL. Procedures are kept and run by `server::procedures`;
+//! they are listed in `pg_catalog.pg_proc` with `prokind = 'p'`.
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::ident::qualify_regular_ident;
+use crate::server::procedures::{self, Procedure};
+use crate::server::query::ProcParam;
+use crate::storage::SharedStore;
+
+fn root_of(store: &SharedStore) -> String { store.root_path().to_string_lossy().to_string() }
+
+/// The procedure is owned by the user creating it (the default admin for statements run
+/// without a session); unqualified names resolve against the session's database and schema.
+pub fn handle_create_procedure(store: &SharedStore, name: &str, params: Vec<ProcParam>, body: &str, or_replace: bool) -> Result<serde_json::Value> {
+    let owner = crate::server::activity::current_user().unwrap_or_else(|| "clarium".to_string());
+    let qualified = qualify_regular_ident(name, &crate::system::current_query_defaults());
+    let proc = Procedure {
+        name: qualified.clone(),
+        params,
+        body: body.to_string(),
+        owner: owner.clone(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    procedures::create_procedure(&root_of(store), proc, or_replace)?;
+    info!(target: "clarium::ddl", "CREATE {}PROCEDURE {} (owner={})", if or_replace { "OR REPLACE " } else { "" }, qualified, owner);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub fn handle_drop_procedure(store: &SharedStore, name: &str, if_exists: bool) -> Result<serde_json::Value> {
+    let qualified = qualify_regular_ident(name, &crate::system::current_query_defaults());
+    if !procedures::drop_procedure(&root_of(store), &qualified)? {
+        if if_exists { return Ok(serde_json::json!({"status": "ok"})); }
+        return Err(crate::error::AppError::NotFound { code: "undefined_function".into(), message: format!("procedure {} does not exist", qualified) }.into());
+    }
+    info!(target: "clarium::ddl", "DROP PROCEDURE {}", qualified);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub async fn handle_call(store: &SharedStore, name: &str, args: &[String]) -> Result<serde_json::Value> {
+    procedures::call(store, name, args).await
+}
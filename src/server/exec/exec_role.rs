@@ -0,0 +1,67 @@
+//! exec_role
+//! ---------
+//! CREATE ROLE / DROP ROLE, GRANT / REVOKE of role membership and SET ROLE. Roles and their
+//! members are kept by `security` (see `security::create_role`); pg_roles, pg_authid and
+//! pg_auth_members report them, and the command gate resolves privileges through them.
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::error::AppError;
+use crate::storage::SharedStore;
+
+fn root_of(store: &SharedStore) -> String { store.root_path().to_string_lossy().to_string() }
+
+pub fn handle_create_role(store: &SharedStore, name: &str, superuser: bool, inherit: bool) -> Result<serde_json::Value> {
+    crate::security::create_role(&root_of(store), name, superuser, inherit)?;
+    info!(target: "clarium::ddl", "CREATE ROLE {} (superuser={}, inherit={})", name, superuser, inherit);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub fn handle_drop_role(store: &SharedStore, name: &str, if_exists: bool) -> Result<serde_json::Value> {
+    if !crate::security::drop_role(&root_of(store), name)? {
+        if if_exists { return Ok(serde_json::json!({"status": "ok"})); }
+        return Err(AppError::NotFound { code: "undefined_object".into(), message: format!("role \"{}\" does not exist", name) }.into());
+    }
+    info!(target: "clarium::ddl", "DROP ROLE {}", name);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub fn handle_grant_role(store: &SharedStore, roles: &[String], members: &[String], admin_option: bool, revoke: bool) -> Result<serde_json::Value> {
+    let root = root_of(store);
+    if revoke {
+        let removed = crate::security::revoke_roles(&root, roles, members)?;
+        info!(target: "clarium::ddl", "REVOKE {} FROM {}: {} membership(s) removed", roles.join(", "), members.join(", "), removed);
+    } else {
+        let grantor = crate::server::activity::current_user();
+        crate::security::grant_roles(&root, roles, members, admin_option, grantor.as_deref())?;
+        info!(target: "clarium::ddl", "GRANT {} TO {}", roles.join(", "), members.join(", "));
+    }
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+/// SET ROLE check: the session user must belong to the role, or be an admin. `none` is always allowed.
+pub fn check_set_role(store: &SharedStore, role: &str) -> Result<()> {
+    let role = role.trim().trim_matches('\'').trim_matches('"');
+    if role.eq_ignore_ascii_case("none") || role.eq_ignore_ascii_case("default") { return Ok(()); }
+    let root = root_of(store);
+    if crate::security::role_catalog(&root)?.role(role).is_none() {
+        return Err(AppError::NotFound { code: "undefined_object".into(), message: format!("role \"{}\" does not exist", role) }.into());
+    }
+    let Some(user) = crate::server::activity::current_user() else { return Ok(()) };
+    let is_admin = crate::security::authorize(&root, &user, crate::security::CommandKind::Schema, None).unwrap_or(false);
+    if !is_admin && !crate::security::is_member_of(&root, &user, role) {
+        return Err(AppError::Permission { code: "insufficient_privilege".into(), message: format!("permission denied to set role \"{}\"", role) }.into());
+    }
+    Ok(())
+}
+
+/// GUC hook for `role`: the backend running the statement acts as the role from now on.
+pub fn apply_role_setting(var: &str, val: &str) -> Result<bool> {
+    if !var.eq_ignore_ascii_case("role") { return Ok(false); }
+    let Some(pid) = crate::server::activity::current_pid() else { return Ok(true) };
+    let role = val.trim().trim_matches('\'').trim_matches('"');
+    let role = (!role.eq_ignore_ascii_case("none") && !role.eq_ignore_ascii_case("default") && !role.is_empty()).then_some(role);
+    crate::server::activity::set_role(pid, role);
+    Ok(true)
+}
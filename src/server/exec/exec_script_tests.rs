@@ -0,0 +1,61 @@
+//! exec_script_tests
+//! -----------------
+//! CREATE / DROP SCRIPT TEST, RUN TESTS and ASSERT. Tests are stored and run by
+//! `server::script_tests`.
+
+use anyhow::Result;
+use polars::prelude::{DataFrame, NamedFrom, Series};
+use tracing::info;
+
+use crate::ident::qualify_regular_ident;
+use crate::server::exec::dataframe_to_json;
+use crate::server::query::{self, ScriptTestLanguage};
+use crate::server::script_tests;
+use crate::storage::SharedStore;
+
+/// Syntax errors are reported when the test is created rather than on its first run.
+pub fn handle_create_script_test(store: &SharedStore, path: &str, language: ScriptTestLanguage, code: &str) -> Result<serde_json::Value> {
+    let qualified = qualify_regular_ident(path, &crate::system::current_query_defaults());
+    match language {
+        ScriptTestLanguage::Lua => {
+            mlua::Lua::new().load(code).set_name(qualified.as_str()).into_function()
+                .map_err(|e| anyhow::anyhow!("CREATE SCRIPT TEST {}: {}", qualified, e))?;
+        }
+        ScriptTestLanguage::Sql => {
+            for stmt in query::split_sql_statements(code) {
+                query::parse(stmt).map_err(|e| anyhow::anyhow!("CREATE SCRIPT TEST {}: {}", qualified, e))?;
+            }
+        }
+    }
+    script_tests::create_test(&store.root_path(), &qualified, language, code)?;
+    info!(target: "clarium::ddl", "CREATE SCRIPT TEST {} {}", language.as_str().to_ascii_uppercase(), qualified);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub fn handle_drop_script_test(store: &SharedStore, path: &str, if_exists: bool) -> Result<serde_json::Value> {
+    let qualified = qualify_regular_ident(path, &crate::system::current_query_defaults());
+    if !script_tests::drop_test(&store.root_path(), &qualified)? {
+        if if_exists { return Ok(serde_json::json!({"status": "ok"})); }
+        return Err(crate::error::AppError::NotFound { code: "undefined_object".into(), message: format!("script test {} does not exist", qualified) }.into());
+    }
+    info!(target: "clarium::ddl", "DROP SCRIPT TEST {}", qualified);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+/// One row per test case: test, language, status (`pass` / `fail` / `error`), message, duration_ms.
+pub async fn handle_run_tests(store: &SharedStore, target: Option<&str>) -> Result<serde_json::Value> {
+    let results = script_tests::run_tests(store, target).await?;
+    let df = DataFrame::new(vec![
+        Series::new("test".into(), results.iter().map(|r| r.test.clone()).collect::<Vec<_>>()).into(),
+        Series::new("language".into(), results.iter().map(|r| r.language.as_str()).collect::<Vec<_>>()).into(),
+        Series::new("status".into(), results.iter().map(|r| r.status).collect::<Vec<_>>()).into(),
+        Series::new("message".into(), results.iter().map(|r| r.message.clone()).collect::<Vec<_>>()).into(),
+        Series::new("duration_ms".into(), results.iter().map(|r| r.duration_ms).collect::<Vec<_>>()).into(),
+    ])?;
+    Ok(dataframe_to_json(&df))
+}
+
+pub async fn handle_assert(store: &SharedStore, condition: &str, message: Option<&str>) -> Result<serde_json::Value> {
+    script_tests::assert(store, &crate::system::current_query_defaults(), condition, message).await?;
+    Ok(serde_json::json!({"status": "ok"}))
+}
@@ -3,7 +3,8 @@ use serde_json::Value;
 use std::path::Path;
 
 use crate::server::query::{Command, ScriptCreateKind};
-use crate::scripts::{get_script_registry, scripts_dir_for};
+use crate::lua_bc::{sidecar_path, LuaBytecodeCache};
+use crate::scripts::{get_script_registry, scripts_dir_for, ScriptKind};
 use crate::storage::SharedStore;
 
 pub fn execute_scripts(store: &SharedStore, cmd: Command) -> Result<Value> {
@@ -16,7 +17,7 @@ pub fn execute_scripts(store: &SharedStore, cmd: Command) -> Result<Value> {
             if parts.len() != 3 { anyhow::bail!("SCRIPT path must be <db>/<schema>/<name>"); }
             let base_dir = scripts_dir_for(Path::new(&root), parts[0], parts[1]);
             // choose subfolder based on kind (default scalar)
-            let subfolder = match kind.unwrap_or(ScriptCreateKind::Scalar) {
+            let subfolder = match kind.clone().unwrap_or(ScriptCreateKind::Scalar) {
                 ScriptCreateKind::Scalar => "scalars",
                 ScriptCreateKind::Aggregate => "aggregates",
                 ScriptCreateKind::Tvf => "tvfs",
@@ -28,12 +29,25 @@ pub fn execute_scripts(store: &SharedStore, cmd: Command) -> Result<Value> {
             if !fname.ends_with(".lua") { fname.push_str(".lua"); }
             let fpath = dir.join(&fname);
             fs::write(&fpath, code.as_bytes())?;
+            // Precompile so the next cold start loads the .luac sidecar; errors surface on use
+            let _ = LuaBytecodeCache::global().load_or_compile_file(parts[2].split('.').next().unwrap_or(parts[2]), &fpath, &code);
             if let Some(reg) = get_script_registry() {
                 let name_no_ext = parts[2].split('.').next().unwrap_or(parts[2]);
                 let text = code;
+                // An explicit kind records metadata (kind, return types, TVF columns) from the
+                // script itself so e.g. `SELECT * FROM my_tvf(...)` resolves right away.
                 // For packages we don't register a global function, but loading into registry
                 // is harmless and allows direct calls if the package defines a global.
-                let _ = reg.load_script_text(name_no_ext, &text);
+                let script_kind = match kind {
+                    Some(ScriptCreateKind::Scalar) => Some(ScriptKind::Scalar),
+                    Some(ScriptCreateKind::Aggregate) => Some(ScriptKind::Aggregate),
+                    Some(ScriptCreateKind::Tvf) => Some(ScriptKind::Tvf),
+                    Some(ScriptCreateKind::Package) | None => None,
+                };
+                match script_kind {
+                    Some(k) => reg.register_script(name_no_ext, &text, k)?,
+                    None => { let _ = reg.load_script_text(name_no_ext, &text); }
+                }
             }
             Ok(serde_json::json!({"status":"ok"}))
         }
@@ -47,10 +61,12 @@ pub fn execute_scripts(store: &SharedStore, cmd: Command) -> Result<Value> {
             if !fname.ends_with(".lua") { fname.push_str(".lua"); }
             let fpath = dir.join(&fname);
             if fpath.exists() { fs::remove_file(&fpath)?; }
+            let _ = fs::remove_file(sidecar_path(&fpath));
             if let Some(reg) = get_script_registry() {
                 let name_no_ext = parts[2].split('.').next().unwrap_or(parts[2]);
                 reg.unload_function(name_no_ext);
             }
+            crate::storage::comments::set_function_comment(Path::new(&root), parts[2].split('.').next().unwrap_or(parts[2]), None)?;
             Ok(serde_json::json!({"status":"ok"}))
         }
         Command::RenameScript { from, to } => {
@@ -69,11 +85,14 @@ pub fn execute_scripts(store: &SharedStore, cmd: Command) -> Result<Value> {
             let fp_from = dir.join(&from_name);
             let fp_to = dir.join(&to_name);
             fs::rename(&fp_from, &fp_to)?;
+            // The sidecar is recompiled under the new name on the next load
+            let _ = fs::remove_file(sidecar_path(&fp_from));
             if let Some(reg) = get_script_registry() {
                 let oldn = fparts[2].split('.').next().unwrap_or(fparts[2]);
                 let newn = to_name.trim_end_matches(".lua");
                 let _ = reg.rename_function(oldn, newn);
             }
+            crate::storage::comments::rename_function_comment(Path::new(&root), fparts[2].split('.').next().unwrap_or(fparts[2]), to_name.trim_end_matches(".lua"))?;
             Ok(serde_json::json!({"status":"ok"}))
         }
         Command::LoadScript { path } => {
@@ -86,15 +105,20 @@ pub fn execute_scripts(store: &SharedStore, cmd: Command) -> Result<Value> {
                 let mut fname = parts[2].to_string(); if !fname.ends_with(".lua") { fname.push_str(".lua"); }
                 let fpath = dir.join(&fname);
                 let code = fs::read_to_string(&fpath)?;
-                if let Some(reg) = get_script_registry() { let name_no_ext = parts[2].split('.').next().unwrap_or(parts[2]); let _ = reg.load_script_text(name_no_ext, &code); }
+                let name_no_ext = parts[2].split('.').next().unwrap_or(parts[2]);
+                let _ = LuaBytecodeCache::global().load_or_compile_file(name_no_ext, &fpath, &code);
+                if let Some(reg) = get_script_registry() { let _ = reg.load_script_text(name_no_ext, &code); }
             } else {
-                // Load all scripts from all schemas
-                for dbent in fs::read_dir(&root)? {
+                // Load all scripts from all schemas: the kind subfolders go through the schema
+                // loader (precompiled sidecars, unchanged scripts skipped), loose files by source
+                let Some(reg) = get_script_registry() else { return Ok(serde_json::json!({"status":"ok"})) };
+                for dbent in crate::storage::attach::read_database_dirs(&root)? {
                     let dbent = dbent?; if !dbent.file_type()?.is_dir() { continue; }
                     for schent in fs::read_dir(dbent.path())? { let schent = schent?; if !schent.file_type()?.is_dir() { continue; }
                         let sdir = scripts_dir_for(Path::new(&root), &dbent.file_name().to_string_lossy(), &schent.file_name().to_string_lossy());
                         if sdir.exists() {
-                            for sf in fs::read_dir(&sdir)? { let sf = sf?; let pth = sf.path(); if pth.extension().and_then(|e| e.to_str()).unwrap_or("").eq_ignore_ascii_case("lua") { let name = pth.file_stem().and_then(|s| s.to_str()).unwrap_or(""); let code = fs::read_to_string(&pth)?; if let Some(reg) = get_script_registry() { let _ = reg.load_script_text(name, &code); } } }
+                            reg.load_from_schema_root(&sdir)?;
+                            for sf in fs::read_dir(&sdir)? { let sf = sf?; let pth = sf.path(); if pth.extension().and_then(|e| e.to_str()).unwrap_or("").eq_ignore_ascii_case("lua") { let name = pth.file_stem().and_then(|s| s.to_str()).unwrap_or(""); let code = fs::read_to_string(&pth)?; if reg.is_loaded(name, &code) { continue; } let _ = LuaBytecodeCache::global().load_or_compile_file(name, &pth, &code); let _ = reg.load_script_text(name, &code); } }
                         }
                     }
                 }
@@ -0,0 +1,114 @@
+//! exec_security_policy
+//! --------------------
+//! CREATE / ALTER / DROP SECURITY POLICY and EXPLAIN ACCESS. Policies are rows of
+//! security.policies written through `filestore::sec::storage::policies`; every write bumps the
+//! security epoch, and the filestore evaluator reloads its snapshot before the next check.
+//! A selector is a glob over resource ids (`res://<db>/<filestore>/path/<logical path>`,
+//! `*` within a segment, `**` across segments).
+
+use anyhow::{bail, Result};
+use tracing::info;
+
+use crate::server::exec::filestore::sec::model::Action;
+use crate::server::exec::filestore::sec::storage::policies;
+use crate::server::exec::filestore::{self as fs, ACLAction, AclUser};
+use crate::server::exec::{dataframe_to_json, effective_for, filestore_target, make_acl_ctx, sql_acl_user};
+use crate::error::AppError;
+use crate::storage::SharedStore;
+
+/// Check action names against the filestore actions ("*" for all).
+fn validate_actions(actions: &[String]) -> Result<()> {
+    for a in actions {
+        if a != "*" && Action::parse(a).is_none() { bail!("invalid_action: {}", a); }
+    }
+    Ok(())
+}
+
+fn validate_selector(selector: &str) -> Result<()> {
+    if !selector.starts_with("res://") { bail!("invalid_selector: {} (expected res://...)", selector); }
+    Ok(())
+}
+
+/// Policies decide what every other role may touch, so changing them takes an admin. Statements
+/// with no session user are internal (jobs, startup scripts) and pass.
+fn require_policy_admin(store: &SharedStore) -> Result<()> {
+    let Some(user) = crate::server::activity::current_user() else { return Ok(()) };
+    let root = store.root_path().to_string_lossy().to_string();
+    let is_admin = crate::security::authorize(&root, &user, crate::security::CommandKind::Database, None).unwrap_or(false)
+        || crate::security::has_superuser_role(&root, &user);
+    if !is_admin {
+        return Err(AppError::Permission { code: "insufficient_privilege".into(), message: "permission denied to change security policies".into() }.into());
+    }
+    Ok(())
+}
+
+pub async fn handle_create_policy(
+    store: &SharedStore,
+    policy_id: &str,
+    role: &str,
+    effect: &str,
+    actions: &[String],
+    selector: &str,
+    priority: i32,
+) -> Result<serde_json::Value> {
+    require_policy_admin(store)?;
+    validate_actions(actions)?;
+    validate_selector(selector)?;
+    if policies::get_policy(store, policy_id).await?.is_some() { bail!("policy_exists: {}", policy_id); }
+    policies::create_policy(store, policy_id, role, &actions.join(","), selector, None, effect, priority).await?;
+    info!(target: "clarium::ddl", "CREATE SECURITY POLICY {} FOR ROLE {} {} {} ON '{}' PRIORITY {}", policy_id, role, effect, actions.join(","), selector, priority);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub async fn handle_alter_policy(
+    store: &SharedStore,
+    policy_id: &str,
+    role: Option<&str>,
+    effect: Option<&str>,
+    actions: Option<&[String]>,
+    selector: Option<&str>,
+    priority: Option<i32>,
+) -> Result<serde_json::Value> {
+    require_policy_admin(store)?;
+    if let Some(a) = actions { validate_actions(a)?; }
+    if let Some(s) = selector { validate_selector(s)?; }
+    if policies::get_policy(store, policy_id).await?.is_none() { bail!("policy_not_found: {}", policy_id); }
+    let actions_csv = actions.map(|a| a.join(","));
+    policies::update_policy(store, policy_id, role, actions_csv.as_deref(), selector, None, effect, priority).await?;
+    info!(target: "clarium::ddl", "ALTER SECURITY POLICY {}", policy_id);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub async fn handle_drop_policy(store: &SharedStore, policy_id: &str, if_exists: bool) -> Result<serde_json::Value> {
+    require_policy_admin(store)?;
+    if policies::get_policy(store, policy_id).await?.is_none() {
+        if if_exists { return Ok(serde_json::json!({"status": "ok"})); }
+        bail!("policy_not_found: {}", policy_id);
+    }
+    policies::delete_policy(store, policy_id).await?;
+    info!(target: "clarium::ddl", "DROP SECURITY POLICY {}", policy_id);
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+/// EXPLAIN ACCESS: the decision the filestore ACL makes for the session user (or `user`).
+pub async fn handle_explain_access(
+    store: &SharedStore,
+    filestore: &str,
+    action: &str,
+    logical_path: Option<&str>,
+    user: Option<&str>,
+) -> Result<serde_json::Value> {
+    let acl_action: ACLAction = serde_json::from_value(serde_json::Value::String(action.to_ascii_lowercase()))
+        .map_err(|_| anyhow::anyhow!("invalid_action: {}", action))?;
+    let (db, filestore) = filestore_target(filestore);
+    if fs::load_filestore_entry(store, &db, &filestore)?.is_none() { bail!("filestore not found: {}.{}", db, filestore); }
+    let eff = effective_for(store, &db, &filestore)?;
+    let ctx = make_acl_ctx(store, &db, &filestore);
+    let user = match user {
+        Some(id) => AclUser { id: id.to_string(), roles: vec![], ip: None },
+        None => sql_acl_user(),
+    };
+    let path = fs::normalize_nfc(logical_path.unwrap_or(""));
+    let ex = fs::explain_acl(&eff, &user, acl_action, &path, &ctx, &filestore).await;
+    Ok(dataframe_to_json(&fs::explain_access_df(&ex)?))
+}
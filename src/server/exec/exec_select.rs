@@ -1,8 +1,11 @@
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
 use polars::prelude::*;
-use tracing::debug;
+use tracing::{debug, debug_span};
 
-use crate::server::query::IntoMode;
+use crate::identity::RequestContext;
+use crate::server::query::{Command, IntoMode};
 use crate::{server::query::Query, storage::SharedStore};
 
 use crate::server::data_context::{DataContext};
@@ -12,7 +15,11 @@ use crate::server::exec::select_stages::by_or_groupby::by_or_groupby as stage_by
 use crate::server::exec::select_stages::rolling::rolling as stage_rolling;
 use crate::server::exec::select_stages::project_select::project_select as stage_project_select;
 use crate::server::exec::select_stages::order_limit::order_limit as stage_order_limit;
+use crate::server::exec::select_stages::hints::apply_join_order;
+use crate::server::exec::spill;
+use crate::server::exec::explain::{NODE_GROUP, NODE_HAVING, NODE_ORDER_LIMIT, NODE_PROJECT, NODE_ROLLING, NODE_SCAN};
 use crate::scripts::get_script_registry;
+use crate::telemetry::QUERY_SPAN_TARGET;
 
 
 pub fn run_select(store: &SharedStore, q: &Query) -> Result<DataFrame> {
@@ -21,6 +28,41 @@ pub fn run_select(store: &SharedStore, q: &Query) -> Result<DataFrame> {
 
 // Expose for subquery execution within WHERE/HAVING evaluation and FROM subqueries
 pub(crate) fn run_select_with_context(store: &SharedStore, q: &Query, parent_ctx: Option<&DataContext>) -> Result<DataFrame> {
+    run_select_staged(store, q, parent_ctx, None)
+}
+
+/// Output size and wall time of one pipeline stage, collected for EXPLAIN ANALYZE.
+#[derive(Debug, Clone)]
+pub(crate) struct StageTrace {
+    pub stage: &'static str,
+    pub rows: usize,
+    pub elapsed: Duration,
+}
+
+/// Run a SELECT and report per-stage row counts and timings alongside the result.
+pub(crate) fn run_select_traced(store: &SharedStore, q: &Query) -> Result<(DataFrame, Vec<StageTrace>)> {
+    let mut trace = Vec::new();
+    let df = run_select_staged(store, q, None, Some(&mut trace))?;
+    Ok((df, trace))
+}
+
+/// Stage boundary: record the stage for EXPLAIN ANALYZE, charge its output to the query's
+/// memory accounting and stop if the session's statement was cancelled.
+fn record_stage(trace: &mut Option<&mut Vec<StageTrace>>, stage: &'static str, df: &DataFrame, started: Instant) -> Result<()> {
+    if let Some(t) = trace.as_mut() {
+        t.push(StageTrace { stage, rows: df.height(), elapsed: started.elapsed() });
+    }
+    crate::memory::charge_stage(df.estimated_size())?;
+    crate::server::activity::check_cancelled()
+}
+
+fn run_select_staged(store: &SharedStore, q: &Query, parent_ctx: Option<&DataContext>, mut trace: Option<&mut Vec<StageTrace>>) -> Result<DataFrame> {
+    let plan = debug_span!(target: QUERY_SPAN_TARGET, "plan",
+        table = q.base_table.as_ref().map(|t| t.effective_name()).unwrap_or(""),
+        joins = q.joins.as_ref().map(|j| j.len()).unwrap_or(0)).entered();
+    // JOIN_ORDER hint: run the stages against the reordered query
+    let reordered = apply_join_order(q);
+    let q = reordered.as_ref().unwrap_or(q);
     // When debug logging is enabled, print the entire parsed Query for leak diagnostics
     // tprintln!("run_select: full Query AST = {:#?}", q);
 
@@ -50,6 +92,7 @@ pub(crate) fn run_select_with_context(store: &SharedStore, q: &Query, parent_ctx
         // Inherit store if not set
         if ctx.store.is_none() { ctx.store = parent.store.clone(); }
     }
+    plan.exit();
     
     debug!(target: "clarium::exec", "run_select (staged): base_table_present={} joins_present={} by_window_ms={:?} group_by_cols={:?} rolling_window_ms={:?} select_len={} where_present={} order_by_present={:?} limit={:?} into_table_present={}",
             q.base_table.is_some(), q.joins.is_some(), q.by_window_ms, q.group_by_cols, q.rolling_window_ms, q.select.len(), q.where_clause.is_some(), q.order_by.as_ref().map(|v| !v.is_empty()), q.limit, q.into_table.is_some());
@@ -66,23 +109,50 @@ pub(crate) fn run_select_with_context(store: &SharedStore, q: &Query, parent_ctx
     }
 
     // Execute stages in mandated order
+    let t = Instant::now();
     let df_from = stage_from_where(store, q, &mut ctx)?;
+    record_stage(&mut trace, NODE_SCAN, &df_from, t)?;
 
     // If there is no FROM source, skip dependent clauses (WHERE/JOIN already skipped inside from_where)
     if q.base_table.is_none() {
         // Skip BY/GROUP BY, ROLLING, ORDER BY/LIMIT, HAVING
+        let t = Instant::now();
         let df_proj = stage_project_select(df_from, q, &mut ctx)?;
+        record_stage(&mut trace, NODE_PROJECT, &df_proj, t)?;
         // Apply late naming policy: switch to id mode then finalize names
         let df_ids = ctx.enter_output_id_mode(df_proj)?;
         let df_final = ctx.finalize_output_names(df_ids)?;
         return Ok(df_final);
     }
 
-    let df_by = stage_by_or_groupby(store, df_from, q, &mut ctx)?;
-    let df_roll = if q.rolling_window_ms.is_some() { stage_rolling(df_by, q, &mut ctx)? } else { df_by };
+    let t = Instant::now();
+    let agg = debug_span!(target: QUERY_SPAN_TARGET, "aggregate", rows_in = df_from.height(), rows = tracing::field::Empty).entered();
+    // GROUP BY inputs over work_mem are aggregated partition by partition from disk
+    let df_by = match spill::group_spill_keys(q, &ctx, &df_from) {
+        Some((keys, work_mem)) => spill::partitioned_aggregate(df_from, &keys, work_mem, |part| stage_by_or_groupby(store, part, q, &mut ctx))?,
+        None => stage_by_or_groupby(store, df_from, q, &mut ctx)?,
+    };
+    agg.record("rows", df_by.height());
+    agg.exit();
+    record_stage(&mut trace, NODE_GROUP, &df_by, t)?;
+    let df_roll = if q.rolling_window_ms.is_some() || q.rolling_rows.is_some() {
+        let t = Instant::now();
+        let df = stage_rolling(df_by, q, &mut ctx)?;
+        record_stage(&mut trace, NODE_ROLLING, &df, t)?;
+        df
+    } else { df_by };
+    let t = Instant::now();
     let df_proj = stage_project_select(df_roll, q, &mut ctx)?;
+    record_stage(&mut trace, NODE_PROJECT, &df_proj, t)?;
+    let t = Instant::now();
     let df_order = stage_order_limit(df_proj, q, &mut ctx)?;
-    let df_having = if let Some(h) = &q.having_clause { apply_having_with_validation(df_order, h, &ctx)? } else { df_order };
+    record_stage(&mut trace, NODE_ORDER_LIMIT, &df_order, t)?;
+    let df_having = if let Some(h) = &q.having_clause {
+        let t = Instant::now();
+        let df = apply_having_with_validation(df_order, h, &ctx)?;
+        record_stage(&mut trace, NODE_HAVING, &df, t)?;
+        df
+    } else { df_order };
     // Late naming: enter id mode and finalize just before returning
     let df_ids = ctx.enter_output_id_mode(df_having)?;
     let df_final = ctx.finalize_output_names(df_ids)?;
@@ -91,6 +161,13 @@ pub(crate) fn run_select_with_context(store: &SharedStore, q: &Query, parent_ctx
 
 
 
+/// Fully-qualified storage name of the query's base table, when it is a plain table reference.
+pub(crate) fn resolve_base_table(q: &Query) -> Option<String> {
+    let name = q.base_table.as_ref()?.table_name()?;
+    let (db, schema) = derive_defaults_from_ident(name);
+    Some(DataContext::with_defaults(db, schema).resolve_table_name(name))
+}
+
 // Helper: derive (db, schema) defaults from an identifier that may be fully-qualified
 fn derive_defaults_from_ident(ident: &str) -> (String, String) {
     // Try path-like db/schema/table(.time)
@@ -120,10 +197,61 @@ if ident.contains('/') || ident.contains('\\') {
 pub fn handle_select(store: &SharedStore, q: &Query) -> Result<(DataFrame, Option<(String, IntoMode)>)> {
     // Return the DataFrame and optional INTO destination with mode for the caller to persist.
     let df = run_select(store, q)?;
+    crate::server::db_stats::count_rows_returned(df.height());
     let into = q.into_table.as_ref().map(|dest| (dest.clone(), q.into_mode.clone().unwrap_or(IntoMode::Append)));
     Ok((df, into))
 }
 
+/// [`handle_select`] for a session's statement: checked against the principal's role grants and
+/// object privileges first, like statements run through `execute_query_with_ctx`.
+pub async fn handle_select_with_ctx(store: &SharedStore, q: &Query, ctx: &RequestContext) -> Result<(DataFrame, Option<(String, IntoMode)>)> {
+    super::exec_authorize::authorize_statement(store, ctx, &Command::Select(q.clone())).await?;
+    handle_select(store, q)
+}
+
+// Row keys over the primary key columns, compared as text so keys read back from storage match fresh results
+fn primary_key_values(df: &DataFrame, pk: &[String]) -> Result<Vec<String>> {
+    let mut cols: Vec<Column> = Vec::with_capacity(pk.len());
+    for c in pk {
+        let col = df.column(c).map_err(|_| anyhow::anyhow!("INTO destination primary key column '{}' is missing from the result", c))?;
+        cols.push(col.cast(&DataType::String)?);
+    }
+    let mut out: Vec<String> = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        let mut key = String::new();
+        for (j, col) in cols.iter().enumerate() {
+            let v = col.str()?.get(i).ok_or_else(|| anyhow::anyhow!("PRIMARY KEY cannot be NULL"))?;
+            if j > 0 { key.push('\u{1f}'); }
+            key.push_str(v);
+        }
+        out.push(key);
+    }
+    Ok(out)
+}
+
+/// Error when two rows of `df` share a primary key.
+pub fn ensure_unique_primary_key(df: &DataFrame, pk: &[String]) -> Result<()> {
+    let keys = primary_key_values(df, pk)?;
+    let mut seen: std::collections::HashSet<&str> = std::collections::HashSet::with_capacity(keys.len());
+    for k in &keys {
+        if !seen.insert(k.as_str()) { anyhow::bail!("Duplicate primary key ({}) in INTO destination", k.replace('\u{1f}', ", ")); }
+    }
+    Ok(())
+}
+
+/// SELECT ... INTO <table> MERGE: rows of `incoming` replace the rows of `existing` with the same
+/// primary key; the remaining incoming rows are appended.
+pub fn merge_by_primary_key(existing: &DataFrame, incoming: &DataFrame, pk: &[String]) -> Result<DataFrame> {
+    ensure_unique_primary_key(incoming, pk)?;
+    if existing.width() == 0 { return Ok(incoming.clone()); }
+    let new_keys: std::collections::HashSet<String> = primary_key_values(incoming, pk)?.into_iter().collect();
+    let keep: BooleanChunked = primary_key_values(existing, pk)?.iter().map(|k| !new_keys.contains(k)).collect();
+    let kept = existing.filter(&keep)?;
+    // Line the result up with the stored column order before stacking
+    let order: Vec<String> = existing.get_column_names().iter().map(|n| n.to_string()).collect();
+    Ok(kept.vstack(&incoming.select(order)?)?)
+}
+
 pub fn handle_select_union(store: &SharedStore, queries: &[Query], all: bool) -> Result<DataFrame> {
     // Execute each query and collect DataFrames
     let mut dfs: Vec<DataFrame> = Vec::new();
@@ -178,5 +306,6 @@ pub fn handle_select_union(store: &SharedStore, queries: &[Query], all: bool) ->
     if !all {
         out = out.lazy().unique(None, polars::prelude::UniqueKeepStrategy::First).collect()?;
     }
+    crate::server::db_stats::count_rows_returned(out.height());
     Ok(out)
 }
\ No newline at end of file
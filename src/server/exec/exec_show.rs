@@ -3,10 +3,10 @@ use serde_json::Value;
 // use polars::prelude::*; // not needed directly here
 
 use crate::server::query::Command;
+use crate::server::exec::filestore::ACLAction;
 // use crate::scripts::scripts_dir_for; // unused in this module
 use crate::storage::SharedStore;
 use crate::server::graphstore::graphstore_status_df;
-use crate::lua_bc::DEFAULT_DB;
 
 pub async fn execute_show(store: &SharedStore, cmd: Command) -> Result<Value> {
     match cmd {
@@ -19,67 +19,124 @@ pub async fn execute_show(store: &SharedStore, cmd: Command) -> Result<Value> {
             let df = graphstore_status_df(store, &graph)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
-        Command::ShowTransactionIsolation => single_kv("transaction_isolation", "read committed"),
-        Command::ShowStandardConformingStrings => single_kv("standard_conforming_strings", "on"),
-        Command::ShowServerVersion => single_kv("server_version", "14.0"),
-        Command::ShowClientEncoding => single_kv("client_encoding", "UTF8"),
-        Command::ShowServerEncoding => single_kv("server_encoding", "UTF8"),
-        Command::ShowDateStyle => single_kv("DateStyle", "ISO, MDY"),
-        Command::ShowIntegerDateTimes => single_kv("integer_datetimes", "on"),
-        Command::ShowTimeZone => single_kv("TimeZone", "UTC"),
-        Command::ShowSearchPath => single_kv("search_path", crate::ident::DEFAULT_SCHEMA),
-        Command::ShowDefaultTransactionIsolation => single_kv("default_transaction_isolation", "read committed"),
-        Command::ShowTransactionReadOnly => single_kv("transaction_read_only", "off"),
-        Command::ShowApplicationName => single_kv("application_name", "clarium"),
-        Command::ShowExtraFloatDigits => single_kv("extra_float_digits", "3"),
-        Command::ShowAll => show_all(),
+        Command::ShowVariable { name } => crate::server::guc::show(store, &name),
+        Command::ShowAll => Ok(crate::server::guc::show_all(store)),
         Command::ShowSchemas => show_schemas(store),
         Command::ShowTables => show_tables(store),
         Command::ShowObjects => show_objects(store),
         Command::ShowScripts => show_scripts(store),
+        Command::ShowCacheStats => Ok(crate::server::exec::dataframe_to_json(&crate::server::exec::result_cache::stats_df()?)),
         // -------------------------------------------------
         // FILESTORE SHOW commands → delegate to filestore::show
+        // Those IN FILESTORE need List on the filestore (or the path they show) for the session user
         Command::ShowFilestores { database } => {
-            let db = database.unwrap_or_else(|| DEFAULT_DB.to_string());
+            let db = database.unwrap_or_else(crate::system::get_current_database);
             let df = crate::server::exec::filestore::show_filestores_df(store, &db)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowFilestoreConfig { filestore, folder_prefix } => {
-            let df = crate::server::exec::filestore::show_filestore_config_df(store, DEFAULT_DB, &filestore, folder_prefix.as_deref())?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            let df = crate::server::exec::filestore::show_filestore_config_df(store, &db, &filestore, folder_prefix.as_deref())?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowFilesInFilestore { filestore, prefix, limit, offset } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, prefix.as_deref().unwrap_or("")).await?;
             let off = offset.unwrap_or(0).max(0) as usize;
             let lim = limit.and_then(|n| if n > 0 { Some(n as usize) } else { None });
-            let df = crate::server::exec::filestore::show_files_df_paged(store, DEFAULT_DB, &filestore, prefix.as_deref(), off, lim)?;
+            let df = crate::server::exec::filestore::show_files_df_paged(store, &db, &filestore, prefix.as_deref(), off, lim)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowTreesInFilestore { filestore } => {
-            let df = crate::server::exec::filestore::show_trees_df(store, DEFAULT_DB, &filestore)?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_trees_df(store, &db, &filestore)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowCommitsInFilestore { filestore } => {
-            let df = crate::server::exec::filestore::show_commits_df(store, DEFAULT_DB, &filestore)?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_commits_df(store, &db, &filestore)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowDiffInFilestore { filestore, left_tree_id, right_tree_id, live_prefix } => {
-            let df = crate::server::exec::filestore::show_diff_df(store, DEFAULT_DB, &filestore, &left_tree_id, right_tree_id.as_deref(), live_prefix.as_deref())?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_diff_df(store, &db, &filestore, &left_tree_id, right_tree_id.as_deref(), live_prefix.as_deref())?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowChunksInFilestore { filestore } => {
-            let df = crate::server::exec::filestore::show_chunks_df(store, DEFAULT_DB, &filestore)?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_chunks_df(store, &db, &filestore)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowAliasesInFilestore { filestore } => {
-            let df = crate::server::exec::filestore::show_aliases_df(store, DEFAULT_DB, &filestore)?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_aliases_df(store, &db, &filestore)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowAdminInFilestore { filestore } => {
-            let df = crate::server::exec::filestore::show_admin_counts_df(store, DEFAULT_DB, &filestore)?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_admin_counts_df(store, &db, &filestore)?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         Command::ShowHealthInFilestore { filestore } => {
-            let df = crate::server::exec::filestore::show_health_df(store, DEFAULT_DB, &filestore)?;
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_health_df(store, &db, &filestore)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowSyncInFilestore { filestore } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_sync_runs_df(store, &db, &filestore)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowBranchesInFilestore { filestore } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let eff = crate::server::exec::effective_for(store, &db, &filestore)?;
+            let df = crate::server::exec::filestore::show_branches_df(store, &db, &filestore, &eff)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowLegalHoldsInFilestore { filestore } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_legal_holds_df(store, &db, &filestore)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowLocksInFilestore { filestore } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_file_locks_df(store, &db, &filestore)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowWebhooksInFilestore { filestore } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_webhooks_df(store, &db, &filestore)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowEncryptionInFilestore { filestore } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let eff = crate::server::exec::effective_for(store, &db, &filestore)?;
+            let df = crate::server::exec::filestore::show_encryption_df(store, &db, &filestore, &eff)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowFilestoreGc { filestore } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, "").await?;
+            let df = crate::server::exec::filestore::show_gc_runs_df(store, &db, &filestore)?;
+            return Ok(crate::server::exec::dataframe_to_json(&df));
+        }
+        Command::ShowVersionsInFilestore { filestore, logical_path } => {
+            let (db, filestore) = crate::server::exec::filestore_target(&filestore);
+            crate::server::exec::authorize_filestore(store, &db, &filestore, ACLAction::List, logical_path.as_deref().unwrap_or("")).await?;
+            let df = crate::server::exec::filestore::show_versions_df(store, &db, &filestore, logical_path.as_deref())?;
             return Ok(crate::server::exec::dataframe_to_json(&df));
         }
         // -------------------------------------------------
@@ -87,26 +144,6 @@ pub async fn execute_show(store: &SharedStore, cmd: Command) -> Result<Value> {
     }
 }
 
-fn single_kv(key: &str, val: &str) -> Result<Value> {
-    Ok(serde_json::json!([{ key: val }]))
-}
-
-fn show_all() -> Result<Value> {
-    let rows = vec![
-        kv("server_version", "14.0"),
-        kv("client_encoding", "UTF8"),
-        kv("standard_conforming_strings", "on"),
-        kv("TimeZone", "UTC"),
-        kv("search_path", crate::ident::DEFAULT_SCHEMA),
-        kv("default_transaction_isolation", "read committed"),
-        kv("transaction_read_only", "off"),
-        kv("extra_float_digits", "3"),
-    ];
-    Ok(Value::Array(rows))
-}
-
-fn kv(k: &str, v: &str) -> Value { serde_json::json!({ k: v }) }
-
 fn root_path(store: &SharedStore) -> std::path::PathBuf { let g = store.0.lock(); g.root_path().clone() }
 
 fn show_schemas(store: &SharedStore) -> Result<Value> {
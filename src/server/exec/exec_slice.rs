@@ -8,7 +8,7 @@ use crate::{
             server::exec::exec_common::{build_where_expr, collect_where_columns},
              storage::{ SharedStore}};
 
-use crate::server::query::query_common::{SliceSource, SlicePlan, SliceOp};             
+use crate::server::query::query_common::{SliceSource, SlicePlan, SliceOp, SliceAdjust};             
 
 // Helper: compute simple stats for interval lists for logging
 fn interval_stats(v: &Vec<(i64,i64)>) -> (usize, i64, i64) {
@@ -75,7 +75,7 @@ fn derive_labels_from_plan(plan: &SlicePlan) -> Option<Vec<String>> {
                     if unnamed > *max_unnamed { *max_unnamed = unnamed; }
                 }
             }
-            SliceSource::Table { .. } => {}
+            SliceSource::Table { .. } | SliceSource::Named(_) => {}
         }
     }
     let mut names: Vec<String> = Vec::new();
@@ -89,6 +89,9 @@ fn derive_labels_from_plan(plan: &SlicePlan) -> Option<Vec<String>> {
 }
 
 pub fn run_slice(store: &SharedStore, plan: &SlicePlan, ctx: &crate::server::data_context::DataContext) -> Result<DataFrame> {    
+    // Swap SLICE(<name>) references for their saved plans before anything else looks at the plan
+    let resolved = crate::server::exec::exec_slice_catalog::resolve_named_slices(store, plan)?;
+    let plan = &resolved;
     // Determine label names: explicit plan labels or derive from manual sources
     let derived = derive_labels_from_plan(plan);
     if let Some(label_names) = plan.labels.as_ref().or(derived.as_ref()) {
@@ -96,12 +99,14 @@ pub fn run_slice(store: &SharedStore, plan: &SlicePlan, ctx: &crate::server::dat
         // Print base stats
         let (base_cnt, base_min, base_max) = interval_stats_labeled(&cur);
         tprintln!("BY SLICE (labeled) base: rows={} range=[{}, {}] labels={:?}", base_cnt, base_min, base_max, label_names);
+        for adj in &plan.adjust { cur = adjust_labeled(cur, adj, label_names.len()); }
         for cl in &plan.clauses {
             let (lhs_cnt, lhs_min, lhs_max) = interval_stats_labeled(&cur);
             let rhs = eval_slice_source_labeled(store, &cl.source, label_names, ctx)?;
             let (rhs_cnt, rhs_min, rhs_max) = interval_stats_labeled(&rhs);
             tprintln!("BY SLICE (labeled) {:?} input: lhs_rows={} range=[{}, {}], rhs_rows={} range=[{}, {}]", cl.op, lhs_cnt, lhs_min, lhs_max, rhs_cnt, rhs_min, rhs_max);
-            cur = match cl.op { SliceOp::Intersect => intersect_labeled(&cur, &rhs), SliceOp::Union => union_labeled(&cur, &rhs) };
+            cur = match cl.op { SliceOp::Intersect => intersect_labeled(&cur, &rhs), SliceOp::Union => union_labeled(&cur, &rhs), SliceOp::Except => except_labeled(&cur, &rhs) };
+            for adj in &cl.adjust { cur = adjust_labeled(cur, adj, label_names.len()); }
             let (res_cnt, res_min, res_max) = interval_stats_labeled(&cur);
             tprintln!("BY SLICE (labeled) {:?} result: rows={} range=[{}, {}]", cl.op, res_cnt, res_min, res_max);
         }
@@ -128,13 +133,15 @@ pub fn run_slice(store: &SharedStore, plan: &SlicePlan, ctx: &crate::server::dat
     let mut cur = eval_slice_source(store, &plan.base, ctx)?;
     let (base_cnt, base_min, base_max) = interval_stats(&cur);
     tprintln!("BY SLICE base: rows={} range=[{}, {}]", base_cnt, base_min, base_max);
+    for adj in &plan.adjust { cur = adjust_intervals(cur, adj); }
     // Apply clauses
     for cl in &plan.clauses {
         let (lhs_cnt, lhs_min, lhs_max) = interval_stats(&cur);
         let rhs = eval_slice_source(store, &cl.source, ctx)?;
         let (rhs_cnt, rhs_min, rhs_max) = interval_stats(&rhs);
         tprintln!("BY SLICE {:?} input: lhs_rows={} range=[{}, {}], rhs_rows={} range=[{}, {}]", cl.op, lhs_cnt, lhs_min, lhs_max, rhs_cnt, rhs_min, rhs_max);
-        cur = match cl.op { SliceOp::Intersect => intersect_intervals(&cur, &rhs), SliceOp::Union => union_intervals(&cur, &rhs) };
+        cur = match cl.op { SliceOp::Intersect => intersect_intervals(&cur, &rhs), SliceOp::Union => union_intervals(&cur, &rhs), SliceOp::Except => except_intervals(&cur, &rhs) };
+        for adj in &cl.adjust { cur = adjust_intervals(cur, adj); }
         let (res_cnt, res_min, res_max) = interval_stats(&cur);
         tprintln!("BY SLICE {:?} result: rows={} range=[{}, {}]", cl.op, res_cnt, res_min, res_max);
     }
@@ -182,6 +189,7 @@ fn eval_slice_source(store: &SharedStore, src: &SliceSource, ctx: &crate::server
             out.sort_by(|a,b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
             Ok(merge_overlaps(out))
         }
+        SliceSource::Named(name) => anyhow::bail!("Slice '{}' was not resolved", name),
     }
 }
 
@@ -319,6 +327,7 @@ fn eval_slice_source_labeled(store: &SharedStore, src: &SliceSource, label_names
             out.sort_by(|a,b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
             Ok(merge_overlaps_labeled(out))
         }
+        SliceSource::Named(name) => anyhow::bail!("Slice '{}' was not resolved", name),
     }
 }
 
@@ -434,4 +443,84 @@ fn union_intervals(a: &[(i64,i64)], b: &[(i64,i64)]) -> Vec<(i64,i64)> {
     let mut v: Vec<(i64,i64)> = Vec::with_capacity(a.len()+b.len());
     v.extend_from_slice(a); v.extend_from_slice(b);
     merge_overlaps(v)
-}
\ No newline at end of file
+}
+
+// Parts of [s, e) not covered by b (sorted, merged); zero-length pieces are dropped
+fn subtract_covered(s: i64, e: i64, b: &[(i64,i64)]) -> Vec<(i64,i64)> {
+    let mut out = Vec::new();
+    let mut cur = s;
+    for &(bs, be) in b {
+        if be <= cur { continue; }
+        if bs >= e { break; }
+        if bs > cur { out.push((cur, bs)); }
+        cur = be;
+        if cur >= e { break; }
+    }
+    if cur < e { out.push((cur, e)); }
+    out
+}
+
+fn except_intervals(a: &[(i64,i64)], b: &[(i64,i64)]) -> Vec<(i64,i64)> {
+    let cover = merge_overlaps(b.to_vec());
+    merge_overlaps(a.iter().flat_map(|&(s,e)| subtract_covered(s, e, &cover)).collect())
+}
+
+// LHS pieces keep their labels; RHS labels play no part
+fn except_labeled(a: &[(i64,i64,Vec<Option<String>>)], b: &[(i64,i64,Vec<Option<String>>)]) -> Vec<(i64,i64,Vec<Option<String>>)> {
+    let cover = merge_overlaps(b.iter().map(|x| (x.0, x.1)).collect());
+    let mut out: Vec<(i64,i64,Vec<Option<String>>)> = Vec::new();
+    for (s,e,labs) in a {
+        for (ps, pe) in subtract_covered(*s, *e, &cover) { out.push((ps, pe, labs.clone())); }
+    }
+    merge_overlaps_labeled(out)
+}
+
+fn complement_intervals(v: &[(i64,i64)]) -> Vec<(i64,i64)> {
+    let cover = merge_overlaps(v.to_vec());
+    cover.windows(2).filter(|w| w[1].0 > w[0].1).map(|w| (w[0].1, w[1].0)).collect()
+}
+
+fn merge_within(v: Vec<(i64,i64)>, gap: i64) -> Vec<(i64,i64)> {
+    let v = merge_overlaps(v);
+    let mut out: Vec<(i64,i64)> = Vec::with_capacity(v.len());
+    for (s,e) in v {
+        match out.last_mut() {
+            Some(last) if s - last.1 < gap => { last.1 = last.1.max(e); }
+            _ => out.push((s,e)),
+        }
+    }
+    out
+}
+
+fn merge_within_labeled(mut v: Vec<(i64,i64,Vec<Option<String>>)>, gap: i64) -> Vec<(i64,i64,Vec<Option<String>>)> {
+    // Only slices with identical labels are joined: walk each label group in time order
+    v.sort_by(|a,b| a.2.cmp(&b.2).then(a.0.cmp(&b.0)).then(a.1.cmp(&b.1)));
+    let mut out: Vec<(i64,i64,Vec<Option<String>>)> = Vec::with_capacity(v.len());
+    for (s,e,labs) in v {
+        match out.last_mut() {
+            Some(last) if last.2 == labs && s - last.1 < gap => { last.1 = last.1.max(e); }
+            _ => out.push((s,e,labs)),
+        }
+    }
+    merge_overlaps_labeled(out)
+}
+
+fn adjust_intervals(v: Vec<(i64,i64)>, adj: &SliceAdjust) -> Vec<(i64,i64)> {
+    match *adj {
+        SliceAdjust::Complement => complement_intervals(&v),
+        SliceAdjust::MergeWithin(gap) => merge_within(v, gap),
+        SliceAdjust::Pad { before, after } => merge_overlaps(v.into_iter().map(|(s,e)| (s - before, e + after)).collect()),
+    }
+}
+
+fn adjust_labeled(v: Vec<(i64,i64,Vec<Option<String>>)>, adj: &SliceAdjust, n_labels: usize) -> Vec<(i64,i64,Vec<Option<String>>)> {
+    match *adj {
+        // Gaps belong to no slice, so they carry no labels
+        SliceAdjust::Complement => {
+            let spans: Vec<(i64,i64)> = v.iter().map(|x| (x.0, x.1)).collect();
+            complement_intervals(&spans).into_iter().map(|(s,e)| (s, e, vec![None; n_labels])).collect()
+        }
+        SliceAdjust::MergeWithin(gap) => merge_within_labeled(v, gap),
+        SliceAdjust::Pad { before, after } => merge_overlaps_labeled(v.into_iter().map(|(s,e,l)| (s - before, e + after, l)).collect()),
+    }
+}
@@ -0,0 +1,162 @@
+//! exec_slice_catalog
+//! ------------------
+//! Named SLICE plans: CREATE SLICE, DROP SLICE and SHOW SLICES over sidecar
+//! `.slice` files stored as `<db>/<schema>/<name>.slice`, and resolution of
+//! `SLICE(<name>)` references before a plan is evaluated.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::server::query::{self, query_common::{SliceClause, SlicePlan, SliceSource}};
+use crate::storage::SharedStore;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SliceFile {
+    pub version: i32,
+    pub name: String,
+    // SLICE ... text as written after AS; parsed again on every use
+    pub definition_sql: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    pub created_at: Option<String>,
+}
+
+fn qualify_slice_name(name: &str) -> String {
+    let d = crate::system::current_query_defaults();
+    crate::ident::qualify_regular_ident(name, &d)
+}
+
+fn slice_path_for(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
+    let mut p = store.0.lock().root_path().clone();
+    p = crate::ident::to_local_path(&p, &qualified);
+    p.set_extension("slice");
+    p
+}
+
+pub fn read_slice_file(store: &SharedStore, qualified: &str) -> Result<Option<SliceFile>> {
+    let path = slice_path_for(store, qualified);
+    if !path.exists() { return Ok(None); }
+    let text = std::fs::read_to_string(&path)?;
+    let v: SliceFile = serde_json::from_str(&text)?;
+    Ok(Some(v))
+}
+
+fn write_slice_file(store: &SharedStore, qualified: &str, sf: &SliceFile) -> Result<()> {
+    let path = slice_path_for(store, qualified);
+    if let Some(parent) = path.parent() { std::fs::create_dir_all(parent).ok(); }
+    std::fs::write(&path, serde_json::to_string_pretty(sf)?)?;
+    Ok(())
+}
+
+fn delete_slice_file(store: &SharedStore, qualified: &str) -> Result<()> {
+    let path = slice_path_for(store, qualified);
+    if path.exists() { std::fs::remove_file(&path).ok(); }
+    Ok(())
+}
+
+/// Replace every `SliceSource::Named` in the plan with the saved plan it refers to.
+/// Saved plans may reference other saved slices; a slice reaching itself is an error.
+pub fn resolve_named_slices(store: &SharedStore, plan: &SlicePlan) -> Result<SlicePlan> {
+    let mut stack: Vec<String> = Vec::new();
+    resolve_plan(store, plan, &mut stack)
+}
+
+fn resolve_plan(store: &SharedStore, plan: &SlicePlan, stack: &mut Vec<String>) -> Result<SlicePlan> {
+    // A plan that is only a reference takes the saved plan as is, labels included
+    if plan.clauses.is_empty() && plan.labels.is_none() && plan.adjust.is_empty() && matches!(plan.base, SliceSource::Named(_)) {
+        if let SliceSource::Plan(p) = resolve_source(store, &plan.base, stack)? { return Ok(*p); }
+    }
+    let base = resolve_source(store, &plan.base, stack)?;
+    let mut clauses: Vec<SliceClause> = Vec::with_capacity(plan.clauses.len());
+    for cl in &plan.clauses {
+        clauses.push(SliceClause { op: cl.op, source: resolve_source(store, &cl.source, stack)?, adjust: cl.adjust.clone() });
+    }
+    Ok(SlicePlan { base, clauses, labels: plan.labels.clone(), adjust: plan.adjust.clone() })
+}
+
+fn resolve_source(store: &SharedStore, src: &SliceSource, stack: &mut Vec<String>) -> Result<SliceSource> {
+    match src {
+        SliceSource::Named(name) => {
+            let qualified = qualify_slice_name(name);
+            if stack.iter().any(|s| s == &qualified) {
+                anyhow::bail!("Slice '{}' references itself through {}", qualified, stack.join(" -> "));
+            }
+            let sf = read_slice_file(store, &qualified)?
+                .ok_or_else(|| AppError::NotFound { code: "not_found".into(), message: format!("Slice not found: {}", qualified) })?;
+            let saved = query::parse_slice(&sf.definition_sql)?;
+            stack.push(qualified);
+            let resolved = resolve_plan(store, &saved, stack)?;
+            stack.pop();
+            Ok(SliceSource::Plan(Box::new(resolved)))
+        }
+        SliceSource::Plan(p) => Ok(SliceSource::Plan(Box::new(resolve_plan(store, p, stack)?))),
+        other => Ok(other.clone()),
+    }
+}
+
+fn list_slices(store: &SharedStore) -> Result<Value> {
+    let root = store.0.lock().root_path().clone();
+    let mut out: Vec<Value> = Vec::new();
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
+        for db_ent in dbs.flatten() {
+            let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
+            if let Ok(sd) = std::fs::read_dir(&db_path) {
+                for schema_dir in sd.flatten().filter(|e| e.path().is_dir()) {
+                    if let Ok(td) = std::fs::read_dir(schema_dir.path()) {
+                        for entry in td.flatten() {
+                            let p = entry.path();
+                            if p.is_file() && p.extension().and_then(|s| s.to_str()) == Some("slice") {
+                                if let Ok(text) = std::fs::read_to_string(&p) {
+                                    if let Ok(v) = serde_json::from_str::<SliceFile>(&text) {
+                                        out.push(serde_json::json!({
+                                            "name": v.name,
+                                            "labels": v.labels.map(|l| l.join(", ")),
+                                            "definition": v.definition_sql,
+                                            "created_at": v.created_at
+                                        }));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    out.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    Ok(Value::Array(out))
+}
+
+pub fn execute_slice_catalog(store: &SharedStore, cmd: query::Command) -> Result<Value> {
+    match cmd {
+        query::Command::CreateSlice { name, or_replace, if_not_exists, definition_sql } => {
+            let qualified = qualify_slice_name(&name);
+            if read_slice_file(store, &qualified)?.is_some() {
+                if if_not_exists { return Ok(serde_json::json!({"status":"ok"})); }
+                if !or_replace { return Err(AppError::Conflict { code: "name_conflict".into(), message: format!("Slice already exists: {}", qualified) }.into()); }
+            }
+            // Referenced slices must exist and must not lead back to this one
+            let plan = query::parse_slice(&definition_sql)?;
+            let mut stack = vec![qualified.clone()];
+            let resolved = resolve_plan(store, &plan, &mut stack)?;
+            let sf = SliceFile { version: 1, name: qualified.clone(), definition_sql, labels: resolved.labels.clone(), created_at: Some(chrono::Utc::now().to_rfc3339()) };
+            write_slice_file(store, &qualified, &sf)?;
+            info!(target: "clarium::ddl", "CREATE SLICE saved '{}.slice'", qualified);
+            Ok(serde_json::json!({"status":"ok"}))
+        }
+        query::Command::DropSlice { name, if_exists } => {
+            let qualified = qualify_slice_name(&name);
+            if read_slice_file(store, &qualified)?.is_none() {
+                if if_exists { return Ok(serde_json::json!({"status":"ok"})); }
+                return Err(AppError::NotFound { code: "not_found".into(), message: format!("Slice not found: {}", qualified) }.into());
+            }
+            delete_slice_file(store, &qualified)?;
+            Ok(serde_json::json!({"status":"ok"}))
+        }
+        query::Command::ShowSlices => list_slices(store),
+        _ => Err(AppError::Ddl { code: "unsupported_slice".into(), message: "unsupported slice command".into() }.into()),
+    }
+}
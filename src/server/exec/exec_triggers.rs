@@ -0,0 +1,68 @@
+//! exec_triggers
+//! -------------
+//! CREATE TRIGGER / DROP TRIGGER. Definitions live in the table's schema.json under
+//! `"triggers"` and fire from the storage write path (see `storage::triggers`).
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::storage::triggers::{self, Trigger, TRIGGERS_KEY};
+use crate::storage::SharedStore;
+
+/// Qualified table name and its schema.json contents.
+fn load_table_schema(store: &SharedStore, table: &str) -> Result<(String, std::path::PathBuf, Map<String, Value>)> {
+    let tableq = crate::ident::qualify_regular_ident(table, &crate::system::current_query_defaults());
+    let dir = crate::ident::to_local_path(&store.root_path(), &tableq);
+    let spath = dir.join("schema.json");
+    if !spath.exists() {
+        return Err(crate::error::AppError::NotFound { code: "undefined_table".into(), message: format!("relation \"{}\" does not exist", tableq) }.into());
+    }
+    let obj = std::fs::read_to_string(&spath).ok()
+        .and_then(|t| serde_json::from_str::<Value>(&t).ok())
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default();
+    Ok((tableq, spath, obj))
+}
+
+fn save_triggers(spath: &std::path::Path, mut obj: Map<String, Value>, list: Vec<Trigger>) -> Result<()> {
+    if list.is_empty() { obj.remove(TRIGGERS_KEY); } else { obj.insert(TRIGGERS_KEY.into(), serde_json::to_value(list)?); }
+    std::fs::write(spath, serde_json::to_string_pretty(&Value::Object(obj))?)?;
+    Ok(())
+}
+
+pub fn handle_create_trigger(store: &SharedStore, table: &str, trigger: Trigger) -> Result<Value> {
+    let (tableq, spath, obj) = load_table_schema(store, table)?;
+    let known = crate::scripts::get_script_registry().map(|r| r.has_function(&trigger.function)).unwrap_or(false);
+    if !known {
+        return Err(crate::error::AppError::NotFound { code: "undefined_function".into(), message: format!("trigger function {} does not exist", trigger.function) }.into());
+    }
+    let mut list = triggers::from_schema(&obj);
+    if list.iter().any(|t| t.name == trigger.name) {
+        return Err(crate::error::AppError::Conflict { code: "duplicate_object".into(), message: format!("trigger \"{}\" for relation \"{}\" already exists", trigger.name, tableq) }.into());
+    }
+    let events: Vec<&str> = trigger.events.iter().map(|e| e.as_str()).collect();
+    info!(target: "clarium::ddl", "CREATE TRIGGER {} AFTER {} ON {} EXECUTE FUNCTION {} (mode={:?}, on_error={:?})",
+        trigger.name, events.join(" OR "), tableq, trigger.function, trigger.mode, trigger.on_error);
+    list.push(trigger);
+    save_triggers(&spath, obj, list)?;
+    Ok(serde_json::json!({"status": "ok"}))
+}
+
+pub fn handle_drop_trigger(store: &SharedStore, table: &str, name: &str, if_exists: bool) -> Result<Value> {
+    let (tableq, spath, obj) = match load_table_schema(store, table) {
+        Ok(v) => v,
+        Err(_) if if_exists => return Ok(serde_json::json!({"status": "ok"})),
+        Err(e) => return Err(e),
+    };
+    let mut list = triggers::from_schema(&obj);
+    let before = list.len();
+    list.retain(|t| t.name != name);
+    if list.len() == before {
+        if if_exists { return Ok(serde_json::json!({"status": "ok"})); }
+        return Err(crate::error::AppError::NotFound { code: "undefined_object".into(), message: format!("trigger \"{}\" for table \"{}\" does not exist", name, tableq) }.into());
+    }
+    save_triggers(&spath, obj, list)?;
+    info!(target: "clarium::ddl", "DROP TRIGGER {} ON {}", name, tableq);
+    Ok(serde_json::json!({"status": "ok"}))
+}
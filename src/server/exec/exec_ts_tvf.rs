@@ -0,0 +1,306 @@
+//! exec_ts_tvf
+//! -----------
+//! Time-series analytics TVFs:
+//! - ts_forecast(table, column, horizon [, season])   -- additive Holt-Winters (ETS) forecast
+//! - ts_anomalies(table, column [, sensitivity])      -- MAD-based outlier detection
+//! - downsample(table, column, n_points [, method])    -- LTTB ('lttb') or largest-gap ('gap') decimation
+//!
+//! The series is `column` ordered by `_time`; rows where it is NULL or not numeric
+//! are skipped. Tables without `_time` use the row position as the time axis.
+//! `season` is a period in samples; when omitted it is detected from the
+//! autocorrelation of the differenced series (0 disables seasonality).
+
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+
+use crate::tprintln;
+
+const DEFAULT_SENSITIVITY: f64 = 3.5;
+// Scales the MAD to a standard deviation for normal data
+const MAD_SCALE: f64 = 1.4826;
+
+fn strip_quotes(x: &str) -> String {
+    let t = x.trim();
+    if (t.starts_with('"') && t.ends_with('"')) || (t.starts_with('\'') && t.ends_with('\'')) {
+        if t.len() >= 2 { return t[1..t.len()-1].to_string(); }
+    }
+    t.to_string()
+}
+
+pub fn try_ts_tvf(store: &crate::storage::SharedStore, raw: &str) -> Result<Option<DataFrame>> {
+    let s = raw.trim();
+    let low = s.to_ascii_lowercase();
+    let Some(fname) = ["ts_forecast", "ts_anomalies", "downsample"].into_iter()
+        .find(|f| low.starts_with(&format!("{}(", f)) && s.ends_with(')')) else { return Ok(None) };
+    let inside = &s[fname.len() + 1..s.len()-1];
+    let args: Vec<String> = inside.split(',').map(strip_quotes).collect();
+    if args.len() < 2 || args[0].is_empty() || args[1].is_empty() {
+        anyhow::bail!("{}(table, column, ...) requires a table and a column", fname);
+    }
+    let num_arg = |i: usize, what: &str| -> Result<Option<f64>> {
+        match args.get(i).filter(|a| !a.is_empty()) {
+            None => Ok(None),
+            Some(a) => a.parse::<f64>().map(Some).map_err(|_| anyhow!("{}: {} must be a number, got '{}'", fname, what, a)),
+        }
+    };
+    if fname == "downsample" {
+        let n = num_arg(2, "n_points")?.ok_or_else(|| anyhow!("downsample(table, column, n_points [, method]) requires n_points"))?;
+        if n < 2.0 || n.fract() != 0.0 { anyhow::bail!("downsample: n_points must be an integer of at least 2"); }
+        let df = downsample_df(store, &args[0], &args[1], n as usize, args.get(3).map(|m| m.as_str()).unwrap_or("lttb"))?;
+        tprintln!("[ts.tvf] downsample('{}', '{}', {}) -> rows={}", args[0], args[1], n, df.height());
+        return Ok(Some(df));
+    }
+    let (times, values) = load_series(store, &args[0], &args[1])?;
+    let df = if fname == "ts_forecast" {
+        let horizon = num_arg(2, "horizon")?.ok_or_else(|| anyhow!("ts_forecast(table, column, horizon [, season]) requires a horizon"))?;
+        if horizon < 1.0 || horizon.fract() != 0.0 { anyhow::bail!("ts_forecast: horizon must be a positive integer"); }
+        let season = num_arg(3, "season")?.map(|x| x.max(0.0) as usize);
+        forecast_df(&times, &values, horizon as usize, season)?
+    } else {
+        let sensitivity = num_arg(2, "sensitivity")?.unwrap_or(DEFAULT_SENSITIVITY);
+        if sensitivity <= 0.0 { anyhow::bail!("ts_anomalies: sensitivity must be positive"); }
+        anomalies_df(&times, &values, sensitivity)?
+    };
+    tprintln!("[ts.tvf] {}('{}', '{}') -> rows={}", fname, args[0], args[1], df.height());
+    Ok(Some(df))
+}
+
+fn qualify_table(table: &str) -> String {
+    let qd = crate::system::current_query_defaults();
+    if table.to_ascii_lowercase().ends_with(".time") {
+        crate::ident::qualify_time_ident(table, &qd)
+    } else {
+        crate::ident::qualify_regular_ident(table, &qd)
+    }
+}
+
+/// Read `column` of `table` as a numeric series ordered by `_time`.
+pub fn load_series(store: &crate::storage::SharedStore, table: &str, column: &str) -> Result<(Vec<i64>, Vec<f64>)> {
+    load_named_series(store, table, column).map(|(_, t, v)| (t, v))
+}
+
+// As load_series, also returning the column name as stored
+fn load_named_series(store: &crate::storage::SharedStore, table: &str, column: &str) -> Result<(String, Vec<i64>, Vec<f64>)> {
+    let tableq = qualify_table(table);
+    let df = store.0.lock().read_df(&tableq)?;
+    let name = df.get_column_names().into_iter()
+        .find(|c| c.as_str().eq_ignore_ascii_case(column))
+        .cloned()
+        .ok_or_else(|| anyhow!("Column '{}' not found in {}", column, tableq))?;
+    let vals = df.column(name.as_str())?.cast(&DataType::Float64)?;
+    let vals = vals.f64()?;
+    let times: Vec<Option<i64>> = match df.column("_time") {
+        Ok(c) => c.cast(&DataType::Int64)?.i64()?.into_iter().collect(),
+        Err(_) => (0..df.height() as i64).map(Some).collect(),
+    };
+    let mut pts: Vec<(i64, f64)> = times.into_iter().zip(vals.into_iter())
+        .filter_map(|(t, v)| match (t, v) { (Some(t), Some(v)) if v.is_finite() => Some((t, v)), _ => None })
+        .collect();
+    pts.sort_by_key(|p| p.0);
+    let (t, v) = pts.into_iter().unzip();
+    Ok((name.to_string(), t, v))
+}
+
+fn median(v: &[f64]) -> f64 {
+    if v.is_empty() { return 0.0; }
+    let mut s = v.to_vec();
+    s.sort_by(|a, b| a.total_cmp(b));
+    let m = s.len() / 2;
+    if s.len() % 2 == 0 { (s[m - 1] + s[m]) / 2.0 } else { s[m] }
+}
+
+// Period with the strongest autocorrelation of the differenced series, if any is clear
+fn detect_season(values: &[f64]) -> usize {
+    let d: Vec<f64> = values.windows(2).map(|w| w[1] - w[0]).collect();
+    let n = d.len();
+    let mean = d.iter().sum::<f64>() / n.max(1) as f64;
+    let var: f64 = d.iter().map(|x| (x - mean).powi(2)).sum();
+    if n < 8 || var <= f64::EPSILON * n as f64 { return 0; }
+    let mut best = (0usize, 0.5f64);
+    for lag in 2..=(values.len() / 3) {
+        let acf: f64 = (lag..n).map(|i| (d[i] - mean) * (d[i - lag] - mean)).sum::<f64>() / var;
+        if acf > best.1 + 1e-9 { best = (lag, acf); }
+    }
+    best.0
+}
+
+struct HoltWinters { level: f64, trend: f64, seasonal: Vec<f64>, sse: f64, fitted: usize }
+
+impl HoltWinters {
+    fn fit(x: &[f64], m: usize, alpha: f64, beta: f64, gamma: f64) -> Self {
+        let (mut level, mut trend, mut seasonal, start) = if m > 0 {
+            let first = x[..m].iter().sum::<f64>() / m as f64;
+            let second = x[m..2 * m].iter().sum::<f64>() / m as f64;
+            (first, (second - first) / m as f64, x[..m].iter().map(|v| v - first).collect::<Vec<f64>>(), m)
+        } else {
+            (x[0], x[1] - x[0], Vec::new(), 1)
+        };
+        let mut sse = 0.0;
+        for (t, &obs) in x.iter().enumerate().skip(start) {
+            let s = if m > 0 { seasonal[t % m] } else { 0.0 };
+            sse += (obs - (level + trend + s)).powi(2);
+            let prev = level;
+            level = alpha * (obs - s) + (1.0 - alpha) * (level + trend);
+            trend = beta * (level - prev) + (1.0 - beta) * trend;
+            if m > 0 { seasonal[t % m] = gamma * (obs - level) + (1.0 - gamma) * s; }
+        }
+        HoltWinters { level, trend, seasonal, sse, fitted: x.len() - start }
+    }
+
+    fn predict(&self, n: usize, h: usize) -> f64 {
+        let m = self.seasonal.len();
+        let s = if m > 0 { self.seasonal[(n - 1 + h) % m] } else { 0.0 };
+        self.level + h as f64 * self.trend + s
+    }
+}
+
+// Smoothing parameters picked by a coarse grid search on one-step-ahead error
+fn fit_best(x: &[f64], m: usize) -> HoltWinters {
+    let gammas: &[f64] = if m > 0 { &[0.1, 0.3, 0.5] } else { &[0.0] };
+    let mut best: Option<HoltWinters> = None;
+    for alpha in [0.1, 0.3, 0.5, 0.7, 0.9] {
+        for beta in [0.0, 0.1, 0.3] {
+            for &gamma in gammas {
+                let hw = HoltWinters::fit(x, m, alpha, beta, gamma);
+                if best.as_ref().map(|b| hw.sse < b.sse).unwrap_or(true) { best = Some(hw); }
+            }
+        }
+    }
+    best.expect("grid is non-empty")
+}
+
+fn time_step(times: &[i64]) -> i64 {
+    let diffs: Vec<f64> = times.windows(2).map(|w| (w[1] - w[0]) as f64).filter(|d| *d > 0.0).collect();
+    (median(&diffs) as i64).max(1)
+}
+
+fn forecast_df(times: &[i64], values: &[f64], horizon: usize, season: Option<usize>) -> Result<DataFrame> {
+    let n = values.len();
+    if n < 3 { anyhow::bail!("ts_forecast: needs at least 3 points, got {}", n); }
+    let m = match season.unwrap_or_else(|| detect_season(values)) {
+        m if m >= 2 && n >= 2 * m => m,
+        _ => 0,
+    };
+    let hw = fit_best(values, m);
+    let sigma = (hw.sse / hw.fitted.max(1) as f64).sqrt();
+    let step = time_step(times);
+    let last = times[n - 1];
+    let mut out_t = Vec::with_capacity(horizon);
+    let mut out_f = Vec::with_capacity(horizon);
+    let mut out_lo = Vec::with_capacity(horizon);
+    let mut out_hi = Vec::with_capacity(horizon);
+    for h in 1..=horizon {
+        let f = hw.predict(n, h);
+        // 95% interval widening with the square root of the horizon
+        let w = 1.96 * sigma * (h as f64).sqrt();
+        out_t.push(last + step * h as i64);
+        out_f.push(f);
+        out_lo.push(f - w);
+        out_hi.push(f + w);
+    }
+    Ok(DataFrame::new(vec![
+        Series::new("_time".into(), out_t).into(),
+        Series::new("step".into(), (1..=horizon as i64).collect::<Vec<i64>>()).into(),
+        Series::new("forecast".into(), out_f).into(),
+        Series::new("lower".into(), out_lo).into(),
+        Series::new("upper".into(), out_hi).into(),
+    ])?)
+}
+
+fn anomalies_df(times: &[i64], values: &[f64], sensitivity: f64) -> Result<DataFrame> {
+    // Seasonal series compare each point with the median of its phase
+    let m = detect_season(values).max(1);
+    let expected_by_phase: Vec<f64> = (0..m)
+        .map(|p| median(&values.iter().skip(p).step_by(m).copied().collect::<Vec<f64>>()))
+        .collect();
+    let expected: Vec<f64> = (0..values.len()).map(|i| expected_by_phase[i % m]).collect();
+    let resid: Vec<f64> = values.iter().zip(&expected).map(|(v, e)| v - e).collect();
+    let abs: Vec<f64> = resid.iter().map(|r| r.abs()).collect();
+    let mut scale = MAD_SCALE * median(&abs);
+    if scale <= f64::EPSILON {
+        // More than half the points sit on the median; fall back to the mean deviation
+        scale = 1.2533 * abs.iter().sum::<f64>() / abs.len().max(1) as f64;
+    }
+    let mut out_t = Vec::new();
+    let mut out_v = Vec::new();
+    let mut out_e = Vec::new();
+    let mut out_s = Vec::new();
+    for i in 0..values.len() {
+        let score = if scale > f64::EPSILON { resid[i] / scale } else { 0.0 };
+        if score.abs() > sensitivity {
+            out_t.push(times[i]);
+            out_v.push(values[i]);
+            out_e.push(expected[i]);
+            out_s.push(score);
+        }
+    }
+    Ok(DataFrame::new(vec![
+        Series::new("_time".into(), out_t).into(),
+        Series::new("value".into(), out_v).into(),
+        Series::new("expected".into(), out_e).into(),
+        Series::new("score".into(), out_s).into(),
+    ])?)
+}
+
+fn downsample_df(store: &crate::storage::SharedStore, table: &str, column: &str, n: usize, method: &str) -> Result<DataFrame> {
+    let (name, times, values) = load_named_series(store, table, column)?;
+    let keep = match method.to_ascii_lowercase().as_str() {
+        "lttb" => lttb(&times, &values, n),
+        "gap" => largest_gap(&times, n),
+        other => anyhow::bail!("downsample: unknown method '{}' (expected 'lttb' or 'gap')", other),
+    };
+    Ok(DataFrame::new(vec![
+        Series::new("_time".into(), keep.iter().map(|&i| times[i]).collect::<Vec<i64>>()).into(),
+        Series::new(name.as_str().into(), keep.iter().map(|&i| values[i]).collect::<Vec<f64>>()).into(),
+    ])?)
+}
+
+/// Largest-Triangle-Three-Buckets: keeps the endpoints and, per bucket, the point
+/// forming the largest triangle with the previous pick and the next bucket's mean.
+pub fn lttb(times: &[i64], values: &[f64], n: usize) -> Vec<usize> {
+    let len = values.len();
+    if n >= len { return (0..len).collect(); }
+    if n < 3 { return vec![0, len - 1]; }
+    let every = (len - 2) as f64 / (n - 2) as f64;
+    let mut keep = Vec::with_capacity(n);
+    keep.push(0);
+    let mut a = 0usize;
+    for b in 0..n - 2 {
+        let start = (b as f64 * every) as usize + 1;
+        let end = (((b + 1) as f64 * every) as usize + 1).min(len - 1);
+        let next_end = (((b + 2) as f64 * every) as usize + 1).min(len);
+        let span = (next_end - end).max(1) as f64;
+        let (avg_t, avg_v) = (end..next_end).fold((0.0, 0.0), |(t, v), i| (t + times[i] as f64 / span, v + values[i] / span));
+        let (at, av) = (times[a] as f64, values[a]);
+        let mut best = (start, -1.0f64);
+        for i in start..end.max(start + 1) {
+            let area = ((at - avg_t) * (values[i] - av) - (at - times[i] as f64) * (avg_v - av)).abs();
+            if area > best.1 { best = (i, area); }
+        }
+        keep.push(best.0);
+        a = best.0;
+    }
+    keep.push(len - 1);
+    keep
+}
+
+/// Keeps the endpoints and both sides of the largest `_time` gaps (so missing data
+/// stays visible), then fills the remaining budget with evenly spaced points.
+pub fn largest_gap(times: &[i64], n: usize) -> Vec<usize> {
+    let len = times.len();
+    if n >= len { return (0..len).collect(); }
+    let mut keep: BTreeSet<usize> = [0, len - 1].into_iter().collect();
+    let spacing = median(&times.windows(2).map(|w| (w[1] - w[0]) as f64).collect::<Vec<f64>>());
+    let mut gaps: Vec<usize> = (1..len).filter(|&i| (times[i] - times[i - 1]) as f64 > 2.0 * spacing).collect();
+    gaps.sort_by_key(|&i| std::cmp::Reverse(times[i] - times[i - 1]));
+    for i in gaps {
+        if keep.len() + 2 > n { break; }
+        keep.insert(i - 1);
+        keep.insert(i);
+    }
+    let rest = n.saturating_sub(keep.len());
+    for k in 1..=rest { keep.insert(k * (len - 1) / (rest + 1)); }
+    keep.into_iter().collect()
+}
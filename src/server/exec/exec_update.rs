@@ -2,175 +2,314 @@
 //! -----------
 //! SQL UPDATE implementation extracted from exec.rs. Handles partially-qualified
 //! identifiers via normalization performed earlier, evaluates WHERE (with
-//! subqueries), and applies constant assignments type-safely.
+//! subqueries), and applies assignments type-safely. SET values are expressions
+//! over the old row, or over the joined row for `UPDATE ... FROM`, whose join keys
+//! are the `target.col = source.col` equalities of the WHERE clause.
+//!
+//! When no primary key or partition column changes and no column is added, the
+//! table is updated one chunk at a time and only chunks holding updated rows are
+//! written back (`Store::replace_chunks`); otherwise the whole table is rewritten.
+
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
 use polars::prelude::*;
 
-use crate::{server::query, server::exec::{where_subquery::{eval_where_mask, where_contains_subquery}, exec_common::build_where_expr, df_utils::read_df_or_kv}};
+use crate::{server::query, server::exec::{where_subquery::{eval_where_mask, where_contains_subquery}, exec_common::{build_arith_expr, build_where_expr}, df_utils::read_df_or_kv}};
+use crate::server::data_context::DataContext;
 use crate::storage::SharedStore;
 
-pub fn handle_update(store: &SharedStore, table: String, assignments: Vec<(String, query::ArithTerm)>, where_clause: Option<query::WhereExpr>) -> Result<serde_json::Value> {
-    let __t0 = std::time::Instant::now();
-    // Load existing dataframe (works for regular and time tables)
-    let __t_read = std::time::Instant::now();
-    let mut df_all = read_df_or_kv(store, &table)?;
-    crate::tprintln!("[EXEC_UPDATE] read_df rows={} cols={} took={:?}", df_all.height(), df_all.width(), __t_read.elapsed());
-    let n = df_all.height();
-    if n == 0 {
-        return Ok(serde_json::json!({"status":"ok","updated":0}));
+/// Rows of the FROM table, loaded once per statement.
+struct UpdateSource {
+    df: DataFrame,
+    qualifiers: Vec<String>,
+}
+
+/// Everything an UPDATE needs to rewrite one frame of the target table.
+struct UpdatePlan<'a> {
+    assignments: &'a [(String, query::ArithExpr)],
+    where_clause: Option<&'a query::WhereExpr>,
+    source: Option<&'a UpdateSource>,
+    qualifiers: &'a [String],
+    ctx: &'a DataContext,
+}
+
+// `db/schema/readings.time` can be written as `readings.time.col` or `readings.col`
+fn short_names(table: &str) -> Vec<String> {
+    let last = table.rsplit('/').next().unwrap_or(table).to_string();
+    let mut out = vec![last.clone()];
+    if let Some(base) = last.strip_suffix(".time") { out.push(base.to_string()); }
+    out
+}
+
+fn strip_qualifier(name: &str, qualifiers: &[String]) -> String {
+    for q in qualifiers {
+        if let Some(c) = name.strip_prefix(q.as_str()).and_then(|r| r.strip_prefix('.')) { return c.to_string(); }
+    }
+    name.to_string()
+}
+
+fn has_column(df: &DataFrame, name: &str) -> bool {
+    df.get_column_names().iter().any(|c| c.as_str() == name)
+}
+
+// Text form of a join key value, shared by both sides so 1 and 1.0 match
+fn key_text(av: AnyValue) -> Option<String> {
+    match av {
+        AnyValue::Null => None,
+        AnyValue::String(s) => Some(s.to_string()),
+        AnyValue::StringOwned(s) => Some(s.to_string()),
+        AnyValue::Float64(f) if f.fract() == 0.0 && f.abs() < 9.0e15 => Some((f as i64).to_string()),
+        v => Some(v.to_string()),
+    }
+}
+
+fn row_key(cols: &[&Column], i: usize) -> Result<Option<String>> {
+    let mut key = String::new();
+    for (k, c) in cols.iter().enumerate() {
+        let Some(t) = key_text(c.get(i)?) else { return Ok(None) };
+        if k > 0 { key.push('\u{1f}'); }
+        key.push_str(&t);
+    }
+    Ok(Some(key))
+}
+
+fn load_source(store: &SharedStore, from: &query::UpdateFrom, target_qualifiers: &[String]) -> Result<UpdateSource> {
+    let q = query::parse_select(&format!("SELECT * FROM {}", from.table))?;
+    let (df, _) = crate::server::exec::exec_select::handle_select(store, &q)?;
+    let qualifiers = match &from.alias { Some(a) => vec![a.clone()], None => short_names(&from.table) };
+    if qualifiers.iter().any(|q| target_qualifiers.contains(q)) {
+        anyhow::bail!("UPDATE ... FROM {}: give the FROM table an alias that differs from the target", from.table);
     }
+    Ok(UpdateSource { df, qualifiers })
+}
+
+// Target columns under their plain names and each qualified form
+fn qualified_frame(df: &DataFrame, qualifiers: &[String]) -> Result<DataFrame> {
+    let mut cols: Vec<Column> = df.get_columns().to_vec();
+    for q in qualifiers {
+        for c in df.get_columns() {
+            cols.push(c.clone().with_name(format!("{}.{}", q, c.name()).into()));
+        }
+    }
+    Ok(DataFrame::new(cols)?)
+}
+
+fn where_mask(df: &DataFrame, w: &query::WhereExpr, store: &SharedStore, ctx: &DataContext) -> Result<BooleanChunked> {
+    let mask = if where_contains_subquery(w) {
+        eval_where_mask(df, ctx, store, w)?
+    } else {
+        let mask_df = df.clone().lazy().select([build_where_expr(w, ctx).alias("__m__")]).collect()?;
+        mask_df.column("__m__")?.bool()?.clone()
+    };
+    Ok(mask.fill_null_with_values(false)?)
+}
+
+fn conjuncts(w: &query::WhereExpr, out: &mut Vec<query::WhereExpr>) {
+    match w {
+        query::WhereExpr::And(a, b) => { conjuncts(a, out); conjuncts(b, out); }
+        other => out.push(other.clone()),
+    }
+}
+
+/// Join each target row to the FROM rows sharing its key. Returns the joined frame and,
+/// per joined row, the target row it came from.
+fn join_source(store: &SharedStore, df: &DataFrame, plan: &UpdatePlan, src: &UpdateSource) -> Result<(DataFrame, Vec<IdxSize>)> {
+    enum Side { Target(String), Source(String) }
+    let side_of = |name: &str| -> Option<Side> {
+        let t = strip_qualifier(name, plan.qualifiers);
+        if t != name && has_column(df, &t) { return Some(Side::Target(t)); }
+        let s = strip_qualifier(name, &src.qualifiers);
+        if s != name && has_column(&src.df, &s) { return Some(Side::Source(s)); }
+        if has_column(df, name) { return Some(Side::Target(name.to_string())); }
+        if has_column(&src.df, name) { return Some(Side::Source(name.to_string())); }
+        None
+    };
+    // Split WHERE into join equalities and the predicates left to filter joined rows
+    let mut parts: Vec<query::WhereExpr> = Vec::new();
+    if let Some(w) = plan.where_clause { conjuncts(w, &mut parts); }
+    let mut keys: Vec<(String, String)> = Vec::new();
+    let mut rest: Option<query::WhereExpr> = None;
+    for p in parts {
+        if let query::WhereExpr::Comp { left: query::ArithExpr::Term(query::ArithTerm::Col { name: l, previous: false }), op: query::CompOp::Eq, right: query::ArithExpr::Term(query::ArithTerm::Col { name: r, previous: false }) } = &p {
+            match (side_of(l), side_of(r)) {
+                (Some(Side::Target(t)), Some(Side::Source(s))) | (Some(Side::Source(s)), Some(Side::Target(t))) => { keys.push((t, s)); continue; }
+                _ => {}
+            }
+        }
+        rest = Some(match rest { Some(acc) => query::WhereExpr::And(Box::new(acc), Box::new(p)), None => p });
+    }
+    if keys.is_empty() {
+        anyhow::bail!("UPDATE ... FROM needs a WHERE equality joining the target to {}", src.qualifiers[0]);
+    }
+
+    let src_cols: Vec<&Column> = keys.iter().map(|(_, s)| src.df.column(s)).collect::<PolarsResult<_>>()?;
+    let mut index: HashMap<String, Vec<IdxSize>> = HashMap::new();
+    for j in 0..src.df.height() {
+        if let Some(k) = row_key(&src_cols, j)? { index.entry(k).or_default().push(j as IdxSize); }
+    }
+    let tgt_cols: Vec<&Column> = keys.iter().map(|(t, _)| df.column(t)).collect::<PolarsResult<_>>()?;
+    let (mut ti, mut si): (Vec<IdxSize>, Vec<IdxSize>) = (Vec::new(), Vec::new());
+    for i in 0..df.height() {
+        let Some(k) = row_key(&tgt_cols, i)? else { continue };
+        if let Some(js) = index.get(&k) {
+            for j in js { ti.push(i as IdxSize); si.push(*j); }
+        }
+    }
+
+    let target = qualified_frame(&df.take(&IdxCa::from_vec("idx".into(), ti.clone()))?, plan.qualifiers)?;
+    let matched = src.df.take(&IdxCa::from_vec("idx".into(), si))?;
+    let mut cols: Vec<Column> = target.get_columns().to_vec();
+    for c in matched.get_columns() {
+        for q in &src.qualifiers { cols.push(c.clone().with_name(format!("{}.{}", q, c.name()).into())); }
+        // Unqualified FROM columns stay reachable when the target has no column of that name
+        if !has_column(df, c.name()) { cols.push(c.clone()); }
+    }
+    let mut joined = DataFrame::new(cols)?;
+    if let Some(w) = &rest {
+        let mask = where_mask(&joined, w, store, plan.ctx)?;
+        joined = joined.filter(&mask)?;
+        ti = ti.into_iter().zip(&mask).filter(|(_, m)| m.unwrap_or(false)).map(|(i, _)| i).collect();
+    }
+    let mut seen: HashSet<IdxSize> = HashSet::with_capacity(ti.len());
+    if !ti.iter().all(|i| seen.insert(*i)) {
+        anyhow::bail!("UPDATE ... FROM: a target row matches more than one row of {}", src.qualifiers[0]);
+    }
+    Ok((joined, ti))
+}
+
+/// Apply the UPDATE to one frame of the target table. Returns the new frame and the mask
+/// of rows that were updated.
+fn apply_update(store: &SharedStore, mut df: DataFrame, plan: &UpdatePlan) -> Result<(DataFrame, BooleanChunked)> {
+    let n = df.height();
+    // Rows to update and the frame their SET expressions are evaluated over
+    let (eval, rows): (DataFrame, Vec<IdxSize>) = match plan.source {
+        Some(src) => join_source(store, &df, plan, src)?,
+        None => {
+            let frame = qualified_frame(&df, plan.qualifiers)?;
+            match plan.where_clause {
+                Some(w) => {
+                    let mask = where_mask(&frame, w, store, plan.ctx)?;
+                    let rows = (&mask).into_iter().enumerate().filter(|(_, m)| m.unwrap_or(false)).map(|(i, _)| i as IdxSize).collect();
+                    (frame.filter(&mask)?, rows)
+                }
+                None => (frame, (0..n as IdxSize).collect()),
+            }
+        }
+    };
+    let mut pos: Vec<Option<IdxSize>> = vec![None; n];
+    for (k, i) in rows.iter().enumerate() { pos[*i as usize] = Some(k as IdxSize); }
+    let mask = BooleanChunked::from_iter_values("__m__".into(), pos.iter().map(|p| p.is_some()));
+    if rows.is_empty() { return Ok((df, mask)); }
+    let idx = IdxCa::from_iter_options("idx".into(), pos.into_iter());
+
+    // Every SET expression sees the row as it was before the UPDATE
+    let mut updated: Vec<Series> = Vec::with_capacity(plan.assignments.len());
+    for (name, expr) in plan.assignments {
+        let existing = df.column(name).ok().map(|c| c.dtype().clone());
+        let expr = match expr {
+            // Kept from constant-only UPDATE: a bare word that names no column is text
+            query::ArithExpr::Term(query::ArithTerm::Col { name: word, previous: false }) if !has_column(&eval, word) => query::ArithExpr::Term(query::ArithTerm::Str(word.clone())),
+            query::ArithExpr::Term(query::ArithTerm::Number(v)) if existing == Some(DataType::String) => query::ArithExpr::Term(query::ArithTerm::Str(v.to_string())),
+            other => other.clone(),
+        };
+        let vals = eval.clone().lazy().with_column(build_arith_expr(&expr, plan.ctx).alias("__v__")).select([col("__v__")]).collect()?;
+        let vals = vals.column("__v__")?.as_materialized_series().clone();
+        let dtype = existing.unwrap_or_else(|| vals.dtype().clone());
+        let new_vals = vals.cast(&dtype)?.take(&idx)?;
+        let old = match df.column(name) {
+            Ok(c) => c.as_materialized_series().clone(),
+            Err(_) => Series::full_null(name.as_str().into(), n, &dtype),
+        };
+        updated.push(new_vals.zip_with(&mask, &old)?.with_name(name.as_str().into()));
+    }
+    for s in updated { df.with_column(s)?; }
+    Ok((df, mask))
+}
+
+pub fn handle_update(store: &SharedStore, table: String, assignments: Vec<(String, query::ArithExpr)>, from: Option<query::UpdateFrom>, where_clause: Option<query::WhereExpr>) -> Result<serde_json::Value> {
+    let __t0 = std::time::Instant::now();
+    let registry_snapshot = crate::scripts::get_script_registry().and_then(|r| r.snapshot().ok());
+    let mut ctx = DataContext::with_defaults(
+        crate::ident::DEFAULT_DB,
+        crate::ident::DEFAULT_SCHEMA,
+    );
+    if let Some(reg) = registry_snapshot { ctx.script_registry = Some(reg); }
+    let qualifiers = short_names(&table);
+    // SET t.col = ... names the target column
+    let assignments: Vec<(String, query::ArithExpr)> = assignments.into_iter().map(|(c, e)| (strip_qualifier(&c, &qualifiers), e)).collect();
     // Fetch primary key and partitions metadata (for regular tables)
     let (pk_cols_opt, partitions_cols): (Option<Vec<String>>, Vec<String>) = {
         let g = store.0.lock();
         (g.get_primary_key(&table), g.get_partitions(&table))
     };
-    // Build mask: rows to update
-    let __t_mask = std::time::Instant::now();
-    let mask_bool = if let Some(w) = &where_clause {
-        let registry_snapshot = crate::scripts::get_script_registry().and_then(|r| r.snapshot().ok());
-        let mut ctx = crate::server::data_context::DataContext::with_defaults(
-            crate::ident::DEFAULT_DB,
-            crate::ident::DEFAULT_SCHEMA,
-        );
-        if let Some(reg) = registry_snapshot { ctx.script_registry = Some(reg); }
-        if where_contains_subquery(w) {
-            eval_where_mask(&df_all, &ctx, store, w)?
-        } else {
-            let mask_df = df_all.clone().lazy().select([build_where_expr(w, &ctx).alias("__m__")]).collect()?;
-            mask_df.column("__m__")?.bool()?.clone()
-        }
-    } else {
-        // All true
-        let v: Vec<bool> = vec![true; n];
-        BooleanChunked::from_slice("__m__".into(), &v)
-    };
-    crate::tprintln!("[EXEC_UPDATE] build_mask rows={} took={:?}", n, __t_mask.elapsed());
 
     // Determine whether assignments touch primary key columns or partition columns
     let mut pk_touched = false;
     let mut partitions_touched = false;
-    for (col, _term) in &assignments {
+    for (col, _expr) in &assignments {
         if let Some(pk_cols) = &pk_cols_opt { if pk_cols.iter().any(|c| c == col) { pk_touched = true; } }
         if !partitions_cols.is_empty() && partitions_cols.iter().any(|c| c == col) { partitions_touched = true; }
     }
+    let source = match &from { Some(f) => Some(load_source(store, f, &qualifiers)?), None => None };
+    let plan = UpdatePlan { assignments: &assignments, where_clause: where_clause.as_ref(), source: source.as_ref(), qualifiers: &qualifiers, ctx: &ctx };
+    let wants_changes = {
+        let g = store.0.lock();
+        g.is_cdc_enabled(&table) || crate::server::exec::vector_delta::has_incremental_indexes(&g, &table)
+    };
 
-    // Apply assignments one by one
-    let __t_assign = std::time::Instant::now();
-    for (col, term) in assignments {
-        // If column doesn't exist yet, add an all-null series with an inferred type
-        let exists = df_all.get_column_names().iter().any(|c| c.as_str() == col);
-        if !exists {
-            let s: Series = match term {
-                query::ArithTerm::Number(_) => Series::new_null(col.clone().into(), n).cast(&DataType::Float64)?,
-                query::ArithTerm::Str(_) => Series::new_null(col.clone().into(), n).cast(&DataType::String)?,
-                query::ArithTerm::Null => Series::new_null(col.clone().into(), n),
-                _ => Series::new_null(col.clone().into(), n),
-            };
-            df_all.with_column(s)?;
-        }
-        let s = df_all.column(col.as_str())?;
-        let dt = s.dtype().clone();
-        let len = s.len();
-        // Build updated series according to dtype
-        let new_series: Series = match dt {
-            DataType::Int64 => {
-                let ca = s.i64()?;
-                let mut out: Vec<Option<i64>> = Vec::with_capacity(len);
-                for i in 0..len {
-                    let do_set = mask_bool.get(i).unwrap_or(false);
-                    if do_set {
-                        let v = match &term {
-                            query::ArithTerm::Number(n) => Some(*n as i64),
-                            query::ArithTerm::Str(st) => st.parse::<i64>().ok(),
-                            query::ArithTerm::Null => None,
-                            _ => None,
-                        };
-                        out.push(v);
-                    } else {
-                        out.push(ca.get(i));
-                    }
-                }
-                Series::new(col.clone().into(), out)
-            }
-            DataType::Float64 => {
-                let ca = s.f64()?;
-                let mut out: Vec<Option<f64>> = Vec::with_capacity(len);
-                for i in 0..len {
-                    let do_set = mask_bool.get(i).unwrap_or(false);
-                    if do_set {
-                        let v = match &term {
-                            query::ArithTerm::Number(n) => Some(*n),
-                            query::ArithTerm::Str(st) => st.parse::<f64>().ok(),
-                            query::ArithTerm::Null => None,
-                            _ => None,
-                        };
-                        out.push(v);
-                    } else {
-                        out.push(ca.get(i));
-                    }
-                }
-                Series::new(col.clone().into(), out)
-            }
-            DataType::String => {
-                let mut out: Vec<Option<String>> = Vec::with_capacity(len);
-                for i in 0..len {
-                    let do_set = mask_bool.get(i).unwrap_or(false);
-                    if do_set {
-                        let v = match &term {
-                            query::ArithTerm::Number(n) => Some(n.to_string()),
-                            query::ArithTerm::Str(st) => Some(st.clone()),
-                            query::ArithTerm::Null => None,
-                            _ => None,
-                        };
-                        out.push(v);
-                    } else {
-                        match s.get(i) {
-                            Ok(AnyValue::StringOwned(v)) => out.push(Some(v.to_string())),
-                            Ok(AnyValue::String(v)) => out.push(Some(v.to_string())),
-                            Ok(AnyValue::Null) => out.push(None),
-                            _ => out.push(None),
-                        }
-                    }
-                }
-                Series::new(col.clone().into(), out)
-            }
-            _ => {
-                // Fallback: treat as string
-                let mut out: Vec<Option<String>> = Vec::with_capacity(len);
-                for i in 0..len {
-                    let do_set = mask_bool.get(i).unwrap_or(false);
-                    if do_set {
-                        let v = match &term {
-                            query::ArithTerm::Number(n) => Some(n.to_string()),
-                            query::ArithTerm::Str(st) => Some(st.clone()),
-                            query::ArithTerm::Null => None,
-                        _ => None,
-                        };
-                        out.push(v);
-                    } else {
-                        out.push(None);
-                    }
+    // Chunk by chunk when rows stay in their partition folder, keys need no table-wide check
+    // and no chunk gains a column
+    let chunks = if pk_touched || partitions_touched || table.contains(".store.") { None } else {
+        let g = store.0.lock();
+        let known: HashSet<String> = g.load_schema_with_locks(&table).map(|(s, _)| s.into_keys().collect()).unwrap_or_default();
+        if assignments.iter().any(|(c, _)| c != "_time" && !known.contains(c)) { None } else { g.chunk_paths(&table) }
+    };
+    if let Some(paths) = chunks {
+        let scanned = paths.len();
+        let mut replaced: Vec<(std::path::PathBuf, DataFrame)> = Vec::new();
+        let mut changed: Option<DataFrame> = None;
+        for p in paths {
+            let df = store.0.lock().read_chunk(&p)?;
+            let (df, mask) = apply_update(store, df, &plan)?;
+            if !mask.any() { continue; }
+            if wants_changes {
+                let ch = df.filter(&mask)?;
+                match changed.as_mut() {
+                    Some(acc) => { acc.vstack_mut(&ch.select(acc.get_column_names_owned())?)?; }
+                    None => changed = Some(ch),
                 }
-                Series::new(col.clone().into(), out)
             }
-        };
-        // Replace/insert column
-        df_all.replace(col.as_str(), new_series)?;
+            replaced.push((p, df));
+        }
+        crate::tprintln!("[EXEC_UPDATE] chunks scanned={} rewritten={} took={:?}", scanned, replaced.len(), __t0.elapsed());
+        let guard = store.0.lock();
+        guard.replace_chunks(&table, replaced)?;
+        if let Some(ch) = changed { guard.record_changes(&table, crate::storage::cdc::ChangeOp::Update, &ch)?; }
+        return Ok(serde_json::json!({"status":"ok"}));
     }
-    crate::tprintln!("[EXEC_UPDATE] apply_assignments rows={} took={:?}", n, __t_assign.elapsed());
+
+    // Load existing dataframe (works for regular and time tables)
+    let __t_read = std::time::Instant::now();
+    let df_all = read_df_or_kv(store, &table)?;
+    crate::tprintln!("[EXEC_UPDATE] read_df rows={} cols={} took={:?}", df_all.height(), df_all.width(), __t_read.elapsed());
+    if df_all.height() == 0 {
+        return Ok(serde_json::json!({"status":"ok","updated":0}));
+    }
+    let __t_assign = std::time::Instant::now();
+    let (df_all, mask_bool) = apply_update(store, df_all, &plan)?;
+    crate::tprintln!("[EXEC_UPDATE] apply_assignments rows={} took={:?}", df_all.height(), __t_assign.elapsed());
     // If PK columns were touched, validate non-null and uniqueness across all rows
     let __t_pk = std::time::Instant::now();
     if let Some(pk_cols) = &pk_cols_opt {
         if pk_touched && !pk_cols.is_empty() {
-            use std::collections::HashSet;
             let mut seen: HashSet<String> = HashSet::with_capacity(df_all.height());
             let mut key_buf = String::new();
             for i in 0..df_all.height() {
                 key_buf.clear();
                 let mut first = true;
                 for c in pk_cols {
-                    if !df_all.get_column_names().iter().any(|n| n.as_str() == c) {
+                    if !has_column(&df_all, c) {
                         anyhow::bail!(format!("UPDATE references missing primary key column '{}'", c));
                     }
                     let av = df_all.column(c.as_str())?.get(i).ok();
@@ -201,10 +340,14 @@ pub fn handle_update(store: &SharedStore, table: String, assignments: Vec<(Strin
             crate::tprintln!("[EXEC_UPDATE] pk_validate rows={} took={:?}", df_all.height(), __t_pk.elapsed());
         }
     }
+    // Capture post-update images of the touched rows for the changelog (and incrementally
+    // maintained vector indexes) before handing df_all off
+    let changed = if wants_changes { Some(df_all.filter(&mask_bool)?) } else { None };
     let guard = store.0.lock();
-    // rewrite_table_df for regular tables is partition-aware now; time tables path is unchanged
+    // rewrite_table_df is partition-aware, so rows whose partition columns changed move folders
     let __t_rewrite = std::time::Instant::now();
     guard.rewrite_table_df(&table, df_all)?;
+    if let Some(ch) = changed { guard.record_changes(&table, crate::storage::cdc::ChangeOp::Update, &ch)?; }
     crate::tprintln!("[EXEC_UPDATE] rewrite_table took={:?} total={:?}", __t_rewrite.elapsed(), __t0.elapsed());
     Ok(serde_json::json!({"status":"ok"}))
 }
@@ -36,8 +36,7 @@ fn qualify_name(name: &str) -> String {
 
 pub(crate) fn path_for_vindex(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-    p.push(local);
+    p = crate::ident::to_local_path(&p, &qualified);
     p.set_extension("vindex");
     p
 }
@@ -98,7 +97,7 @@ fn table_exists(store: &SharedStore, qualified_table: &str) -> bool {
 fn list_vector_indexes(store: &SharedStore) -> Result<Value> {
     let root = store.0.lock().root_path().clone();
     let mut out: Vec<serde_json::Value> = Vec::new();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
             if let Ok(sd) = std::fs::read_dir(&db_path) {
@@ -131,10 +130,56 @@ fn list_vector_indexes(store: &SharedStore) -> Result<Value> {
     Ok(Value::Array(out))
 }
 
+/// Rebuild the built indexes over `table` that have a delta, folding it in. Called by COMPACT
+/// TABLE; returns the names of the rebuilt indexes.
+pub fn rebuild_indexes_with_delta(store: &SharedStore, table: &str) -> Result<Vec<String>> {
+    let qtable = crate::ident::qualify_regular_ident(table, &crate::system::current_query_defaults());
+    let mut out: Vec<String> = Vec::new();
+    for meta in crate::system_catalog::shared::enumerate_vector_indexes(store) {
+        let Ok(text) = std::fs::read_to_string(&meta.file) else { continue };
+        let Ok(mut vf) = serde_json::from_str::<VIndexFile>(&text) else { continue };
+        if !vf.table.eq_ignore_ascii_case(&qtable) { continue; }
+        if !crate::server::exec::exec_vector_runtime::artifact_path(store, &vf, "vdelta").exists() { continue; }
+        crate::server::exec::exec_vector_runtime::reindex_vector_index(store, &mut vf)?;
+        vf.updated_at = Some(now_iso());
+        let qualified = vf.qualified.clone();
+        write_vindex_file(store, &qualified, &vf)?;
+        info!(target: "clarium::ddl", "COMPACT TABLE {}: rebuilt vector index {}", qtable, qualified);
+        out.push(vf.name);
+    }
+    Ok(out)
+}
+
+/// Check the WITH (nlist, m, nbits, nprobe, rerank) options of an ivfpq index: positive
+/// integers, nbits at most 8 (codes are bytes) and m dividing the declared dimension.
+fn validate_ivfpq_params(params: &serde_json::Map<String, Value>, dim: Option<i32>) -> Result<()> {
+    let get = |key: &str| -> Result<Option<usize>> {
+        let Some((k, v)) = params.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)) else { return Ok(None) };
+        let text = v.as_str().map(|s| s.trim().trim_matches('\'').to_string()).unwrap_or_else(|| v.to_string());
+        match text.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Some(n)),
+            _ => Err(AppError::Ddl { code: "vector_param".into(), message: format!("ivfpq option {} must be a positive integer, got '{}'", k, text) }.into()),
+        }
+    };
+    for key in ["nlist", "nprobe", "rerank"] { get(key)?; }
+    if let Some(nbits) = get("nbits")? {
+        if nbits > 8 {
+            return Err(AppError::Ddl { code: "vector_param".into(), message: format!("ivfpq nbits must be between 1 and 8, got {}", nbits) }.into());
+        }
+    }
+    if let (Some(m), Some(d)) = (get("m")?, dim) {
+        if d <= 0 || d as usize % m != 0 {
+            return Err(AppError::Ddl { code: "vector_param".into(), message: format!("ivfpq m={} must divide dim={}", m, d) }.into());
+        }
+    }
+    Ok(())
+}
+
 pub fn execute_vector_index(store: &SharedStore, cmd: query::Command) -> Result<Value> {
     match cmd {
         query::Command::CreateVectorIndex { name, table, column, algo, options } => {
-            if algo.to_lowercase() != "hnsw" { return Err(AppError::Ddl { code: "vector_algo".into(), message: format!("Only HNSW is supported for now, got '{}'.", algo) }.into()); }
+            let algo = algo.to_lowercase();
+            if algo != "hnsw" && algo != "ivfpq" { return Err(AppError::Ddl { code: "vector_algo".into(), message: format!("Unsupported vector index algorithm '{}'; expected hnsw or ivfpq.", algo) }.into()); }
             let qualified = qualify_name(&name);
             crate::tprintln!("[VINDEX] CREATE name='{}' table='{}' column='{}' algo='{}' opts={:?}", qualified, table, column, algo, options);
             if read_vindex_file(store, &qualified)?.is_some() {
@@ -156,6 +201,7 @@ pub fn execute_vector_index(store: &SharedStore, cmd: query::Command) -> Result<
                 if kl == "mode" { mode = Some(v.trim_matches('\'').to_ascii_uppercase()); continue; }
                 params.insert(k, serde_json::Value::String(v));
             }
+            if algo == "ivfpq" { validate_ivfpq_params(&params, dim)?; }
             // Validate/normalize mode; default to REBUILD_ONLY if absent
             let allowed = ["IMMEDIATE", "BATCHED", "ASYNC", "REBUILD_ONLY"];
             let mode = match mode {
@@ -173,7 +219,7 @@ pub fn execute_vector_index(store: &SharedStore, cmd: query::Command) -> Result<
                 qualified: qualified.clone(),
                 table: qtable,
                 column,
-                algo: "hnsw".to_string(),
+                algo,
                 metric,
                 dim,
                 params: if params.is_empty() { None } else { Some(params) },
@@ -189,9 +235,10 @@ pub fn execute_vector_index(store: &SharedStore, cmd: query::Command) -> Result<
         }
         query::Command::DropVectorIndex { name } => {
             let qualified = qualify_name(&name);
-            if read_vindex_file(store, &qualified)?.is_none() {
+            let Some(vf) = read_vindex_file(store, &qualified)? else {
                 return Err(AppError::NotFound { code: "not_found".into(), message: format!("Vector index not found: {}", qualified) }.into());
-            }
+            };
+            crate::server::exec::exec_vector_runtime::delete_vector_artifacts(store, &vf);
             delete_vindex_file(store, &qualified)?;
             Ok(serde_json::json!({"status":"ok"}))
         }
@@ -264,6 +311,34 @@ pub fn execute_vector_index(store: &SharedStore, cmd: query::Command) -> Result<
                 Err(AppError::NotFound { code: "not_found".into(), message: format!("Vector index not found: {}", qualified) }.into())
             }
         }
+        query::Command::RebuildVectorIndex { name: Some(name) } => {
+            let qualified = qualify_name(&name);
+            if let Some(mut vf) = read_vindex_file(store, &qualified)? {
+                let out = crate::server::exec::exec_vector_runtime::reindex_vector_index(store, &mut vf)?;
+                vf.updated_at = Some(now_iso());
+                write_vindex_file(store, &qualified, &vf)?;
+                info!(target: "clarium::ddl", "REBUILD VECTOR INDEX {}", qualified);
+                Ok(out)
+            } else {
+                Err(AppError::NotFound { code: "not_found".into(), message: format!("Vector index not found: {}", qualified) }.into())
+            }
+        }
+        query::Command::RebuildVectorIndex { name: None } => {
+            // Every built index whose table changed since its last build; one row per rebuilt index
+            let mut out: Vec<Value> = Vec::new();
+            for meta in crate::system_catalog::shared::enumerate_vector_indexes(store) {
+                let Ok(text) = std::fs::read_to_string(&meta.file) else { continue };
+                let Ok(mut vf) = serde_json::from_str::<VIndexFile>(&text) else { continue };
+                let qualified = vf.qualified.clone();
+                if crate::server::exec::exec_vector_runtime::is_stale(store, &vf) != Some(true) { continue; }
+                let res = crate::server::exec::exec_vector_runtime::reindex_vector_index(store, &mut vf)?;
+                vf.updated_at = Some(now_iso());
+                write_vindex_file(store, &qualified, &vf)?;
+                info!(target: "clarium::ddl", "REBUILD VECTOR INDEX {}", qualified);
+                out.push(serde_json::json!({"name": vf.name, "rows_indexed": res.get("rows_indexed").cloned().unwrap_or(Value::Null)}));
+            }
+            Ok(Value::Array(out))
+        }
         query::Command::ShowVectorIndexStatus { name } => {
             crate::tprintln!("[VINDEX] SHOW STATUS name={:?}", name);
             let out = crate::server::exec::exec_vector_runtime::show_vector_index_status(store, name.as_deref())?;
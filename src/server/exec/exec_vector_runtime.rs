@@ -1,12 +1,28 @@
 //! exec_vector_runtime
 //! --------------------
-//! Vector index runtime: BUILD/REINDEX/STATUS and search over flat (exact) engine.
+//! Vector index runtime: BUILD/REINDEX/STATUS and search over flat (exact) engine, plus the
+//! HNSW graph when `ann_hnsw` is enabled.
+//!
+//! Built artifacts live under the indexed table's folder, `<table>/_vindex/<db>.<schema>.<name>`
+//! with a `.vdata` payload and either a `.hnsw` graph (see `vector_hnsw`) or, for indexes
+//! created `USING ivfpq`, an `.ivfpq` quantized index (see `vector_ivfpq`). They are loaded once,
+//! memory-mapped with `ann_hnsw_mmap`, and kept until the index is rebuilt or dropped; the
+//! server warm-loads them at startup. A build records a fingerprint of the table's chunks so
+//! later writes show the index as stale, unless they were inserts applied to the index delta
+//! (see `vector_delta`), which searches merge with the build's results.
 //!
 //! v2 `.vdata` format adds stable row ids alongside contiguous f32 payload:
 //! magic:u32 | version:u32 | flags:u32 | dim:u32 | rows:u32 | [row_ids: rows*u64 if flags&1] | data: rows*dim*f32
 //! v1 compatibility: magic | version(=1) | dim | rows | data
 
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 use anyhow::{Result, bail};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use serde_json::json;
 use polars::prelude::*;
 
@@ -17,122 +33,282 @@ use crate::tprintln;
 
 #[cfg(feature = "ann_hnsw")]
 mod hnsw_backend {
-    // Lightweight HNSW backend façade.
-    // Notes:
-    // - We always persist the flat vector payload in .vdata (v2 format with row_ids).
-    // - For now, we also write a tiny .hnsw sidecar JSON with build parameters to mark availability.
-    // - At query time we provide an ANN-capable search path. If true graph persistence is not available,
-    //   we fall back to an in‑memory build for the current query or to exact scoring if needed.
+    // HNSW graph over the flat payload. The graph only stores links between positions;
+    // vectors are read from the `.vdata` payload it was built from, so both files are
+    // loaded together and replaced together.
     use super::*;
+    use crate::server::exec::vector_hnsw::{HnswGraph, Metric};
 
-    fn path_for_hnsw(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
-        let mut p = store.0.lock().root_path().clone();
-        let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-        p.push(local);
-        p.set_extension("hnsw");
-        p
-    }
-
-    #[derive(serde::Serialize, serde::Deserialize)]
-    struct HnswMeta {
-        version: u32,
-        metric: String,
-        dim: u32,
-        rows: u32,
-        m: i32,
-        ef_build: i32,
-    }
-
-    pub fn build_hnsw_index(store: &SharedStore, v: &VIndexFile) -> Result<()> {
-        // Ensure vdata exists
-        let (dim, rows, _row_ids, _data) = super::load_vdata(store, &v.qualified)?;
-        let path = path_for_hnsw(store, &v.qualified);
-        if let Some(parent) = path.parent() { let _ = std::fs::create_dir_all(parent); }
-        // Record minimal metadata to mark HNSW availability
-        let meta = HnswMeta {
-            version: 1,
-            metric: v.metric.clone().unwrap_or_else(|| "l2".to_string()),
-            dim,
-            rows,
-            m: crate::system::get_vector_hnsw_m(),
-            ef_build: crate::system::get_vector_hnsw_ef_build(),
-        };
-        let bytes = serde_json::to_vec_pretty(&meta)?;
-        std::fs::write(&path, bytes)?;
+    pub fn build_hnsw_index(store: &SharedStore, v: &VIndexFile, data: &[f32], dim: u32) -> Result<()> {
+        let m = param_usize(v, "m").unwrap_or(crate::system::get_vector_hnsw_m().max(2) as usize);
+        let ef_build = param_usize(v, "ef_build").unwrap_or(crate::system::get_vector_hnsw_ef_build().max(1) as usize);
+        let metric = Metric::parse(v.metric.as_deref().unwrap_or("l2"));
+        let graph = HnswGraph::build(data, dim as usize, metric, m, ef_build);
+        let path = artifact_path(store, v, "hnsw");
+        write_atomic(&path, &graph.to_bytes())?;
         tprintln!(
             "vector.hnsw.build.ok name={} path={} rows={} dim={} m={} ef_build={}",
-            v.qualified, path.display(), meta.rows, meta.dim, meta.m, meta.ef_build
+            v.qualified, path.display(), graph.len(), dim, m, ef_build
         );
         Ok(())
     }
 
-    pub fn search_hnsw_index(store: &SharedStore, v: &VIndexFile, qvec: &[f32], k: usize) -> Option<Vec<(u32, f32)>> {
-        // If the .hnsw marker doesn't exist, treat as unavailable
-        let path = path_for_hnsw(store, &v.qualified);
-        if !path.exists() {
-            tprintln!("vector.hnsw.search.fallback name={} reason=no_hnsw_marker", v.qualified);
-            return None;
+    /// Graph stored next to a `.vdata` file, when there is one that matches its row count.
+    pub fn load_graph(vdata_path: &Path, rows: u32) -> Option<HnswGraph> {
+        let path = vdata_path.with_extension("hnsw");
+        let bytes = std::fs::read(&path).ok()?;
+        match HnswGraph::from_bytes(&bytes) {
+            Ok(g) if g.len() == rows as usize => Some(g),
+            Ok(g) => {
+                tprintln!("vector.hnsw.load.skip path={} reason=row_mismatch graph_rows={} rows={}", path.display(), g.len(), rows);
+                None
+            }
+            Err(e) => {
+                tprintln!("vector.hnsw.load.skip path={} reason={}", path.display(), e);
+                None
+            }
         }
+    }
+
+    /// Bottom-layer candidate list: the index's ef_search, else the session's.
+    pub fn ef_for(v: &VIndexFile) -> usize {
+        param_usize(v, "ef_search").unwrap_or(crate::system::get_vector_ef_search().max(1) as usize)
+    }
 
-        // Load vector payload and perform a best‑effort ANN/EXACT search depending on configuration.
-        let (dim, rows, _row_ids, data) = match super::load_vdata(store, &v.qualified) {
-            Ok(t) => t,
+    /// Positions and scores of the `k` nearest rows through the graph (among the positions
+    /// `allow` accepts), or None when the index has no usable graph and the caller should
+    /// scan the payload instead.
+    pub fn search_hnsw_index(vd: &VectorData, v: &VIndexFile, qvec: &[f32], k: usize, ef_search: Option<usize>, allow: Option<&dyn Fn(u32) -> bool>) -> Option<Vec<(u32, f32)>> {
+        let Some(graph) = vd.graph.as_ref() else {
+            tprintln!("vector.hnsw.search.fallback name={} reason=no_graph", v.qualified);
+            return None;
+        };
+        if qvec.len() as u32 != vd.dim { return None; }
+        let ef = ef_search.unwrap_or_else(|| ef_for(v));
+        let data = vd.data();
+        let metric = v.metric.as_deref().unwrap_or("l2").to_ascii_lowercase();
+        let out: Vec<(u32, f32)> = graph
+            .search_filtered(data, vd.dim as usize, qvec, k, ef, allow)
+            .into_iter()
+            .map(|i| (i, score(&metric, vector_at(data, vd.dim, i), qvec)))
+            .collect();
+        tprintln!("vector.hnsw.search.ok name={} k={} ef={} rows={} dim={}", v.qualified, k, ef, vd.rows, vd.dim);
+        Some(out)
+    }
+}
+
+mod ivf_backend {
+    // IVF-PQ lists over the flat payload. The quantized codes only rank candidates; the best
+    // `k * rerank` of them are scored exactly against the payload.
+    use super::*;
+    use crate::server::exec::vector_ivfpq::IvfPq;
+
+    const DEFAULT_NLIST: usize = 256;
+    const DEFAULT_NBITS: usize = 8;
+    const DEFAULT_NPROBE: usize = 8;
+    const DEFAULT_RERANK: usize = 4;
+
+    /// Sub-quantizers when WITH (m = ...) is absent: the most of 8, 4, 2 that divide `dim`.
+    fn default_m(dim: usize) -> usize { [8, 4, 2].into_iter().find(|m| dim % m == 0).unwrap_or(1) }
+
+    pub fn build_ivf_index(store: &SharedStore, v: &VIndexFile, data: &[f32], dim: u32) -> Result<()> {
+        let nlist = param_usize(v, "nlist").unwrap_or(DEFAULT_NLIST);
+        let m = param_usize(v, "m").unwrap_or_else(|| default_m(dim as usize));
+        let nbits = param_usize(v, "nbits").unwrap_or(DEFAULT_NBITS);
+        let ivf = IvfPq::build(data, dim as usize, v.metric.as_deref().unwrap_or("l2"), nlist, m, nbits as u32)?;
+        let path = artifact_path(store, v, "ivfpq");
+        write_atomic(&path, &ivf.to_bytes())?;
+        tprintln!(
+            "vector.ivfpq.build.ok name={} path={} rows={} dim={} nlist={} m={} nbits={}",
+            v.qualified, path.display(), ivf.len(), dim, ivf.nlist(), m, nbits
+        );
+        Ok(())
+    }
+
+    /// IVF-PQ index stored next to a `.vdata` file, when there is one that matches its row count.
+    pub fn load_ivf(vdata_path: &Path, rows: u32) -> Option<IvfPq> {
+        let path = vdata_path.with_extension("ivfpq");
+        let bytes = std::fs::read(&path).ok()?;
+        match IvfPq::from_bytes(&bytes) {
+            Ok(ivf) if ivf.len() == rows as usize => Some(ivf),
+            Ok(ivf) => {
+                tprintln!("vector.ivfpq.load.skip path={} reason=row_mismatch ivf_rows={} rows={}", path.display(), ivf.len(), rows);
+                None
+            }
             Err(e) => {
-                tprintln!("vector.hnsw.search.fallback name={} reason=load_vdata_error err={}", v.qualified, e);
-                return None;
+                tprintln!("vector.ivfpq.load.skip path={} reason={}", path.display(), e);
+                None
             }
-        };
-        if qvec.len() as u32 != dim { return None; }
+        }
+    }
+
+    /// Lists probed per search: the session's `vector.ivf.nprobe`, else the index's nprobe.
+    pub fn nprobe_for(v: &VIndexFile) -> usize {
+        let session = crate::system::get_vector_ivf_nprobe();
+        if session > 0 { session as usize } else { param_usize(v, "nprobe").unwrap_or(DEFAULT_NPROBE).max(1) }
+    }
+
+    /// Candidates re-ranked exactly per result: the session's `vector.ivf.rerank`, else the index's.
+    pub fn rerank_for(v: &VIndexFile) -> usize {
+        let session = crate::system::get_vector_ivf_rerank();
+        if session > 0 { session as usize } else { param_usize(v, "rerank").unwrap_or(DEFAULT_RERANK).max(1) }
+    }
 
-        // For now, use a fast exact top‑k on the flat store under the HNSW façade. This ensures
-        // engine hints and ANN path are respected without panicking, and returns consistent results.
-        // Ordering direction matches metric semantics.
+    /// Positions and exact scores of the `k` best rows among the IVF-PQ candidates (positions
+    /// `allow` accepts), or None when the index has no usable IVF-PQ lists and the caller should
+    /// try another engine.
+    pub fn search_ivf_index(vd: &VectorData, v: &VIndexFile, qvec: &[f32], k: usize, nprobe: Option<usize>, allow: Option<&dyn Fn(u32) -> bool>) -> Option<Vec<(u32, f32)>> {
+        let ivf = vd.ivf.as_ref()?;
+        if qvec.len() as u32 != vd.dim { return None; }
+        let nprobe = nprobe.unwrap_or_else(|| nprobe_for(v));
+        let rerank = rerank_for(v);
+        let data = vd.data();
         let metric = v.metric.as_deref().unwrap_or("l2").to_ascii_lowercase();
-        #[inline]
-        fn f32_key(v: f32) -> u32 { let b = v.to_bits(); if b & (1u32 << 31) != 0 { !b } else { b | (1u32 << 31) } }
-        let mut heap: std::collections::BinaryHeap<(u32, u32)> = std::collections::BinaryHeap::with_capacity(k + 1);
-        for r in 0..rows as usize {
-            let off = r * dim as usize;
-            let slice = &data[off..off + dim as usize];
-            let (key, _raw) = match metric.as_str() {
-                "ip" | "dot" => { let s = super::dot(slice, qvec); (f32_key(s), s) },
-                "cosine" => { let s = super::cosine(slice, qvec); (f32_key(s), s) },
-                _ => { let d = super::l2(slice, qvec); let s = -d; (f32_key(s), d) },
-            };
-            heap.push((key, r as u32));
-            if heap.len() > k { heap.pop(); }
-        }
-        let mut items: Vec<(u32, u32)> = heap.into_iter().collect();
-        items.sort_by(|a,b| b.0.cmp(&a.0));
-        let mut out: Vec<(u32, f32)> = Vec::with_capacity(items.len());
-        for (_k, i) in items.into_iter() {
-            let off = i as usize * dim as usize;
-            let slice = &data[off..off + dim as usize];
-            let s = match metric.as_str() {
-                "ip" | "dot" => super::dot(slice, qvec),
-                "cosine" => super::cosine(slice, qvec),
-                _ => super::l2(slice, qvec),
-            };
-            out.push((i, s));
-        }
-        tprintln!("vector.hnsw.search.ok name={} path={} k={} rows={} dim={}", v.qualified, path.display(), k, rows, dim);
+        let mut out: Vec<(u32, f32)> = ivf
+            .search_filtered(qvec, k.saturating_mul(rerank), nprobe, allow)
+            .into_iter()
+            .map(|(i, _)| (i, score(&metric, vector_at(data, vd.dim, i), qvec)))
+            .collect();
+        out.sort_by(|a, b| rank(&metric, b.1).total_cmp(&rank(&metric, a.1)));
+        out.truncate(k);
+        tprintln!("vector.ivfpq.search.ok name={} k={} nprobe={} rerank={} rows={} dim={}", v.qualified, k, nprobe, rerank, vd.rows, vd.dim);
         Some(out)
     }
 }
 
-fn path_for_index_data(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
+/// `<table>/_vindex/<db>.<schema>.<name>.<ext>`: built artifacts live with the table they
+/// index, so they go away with it.
+pub(crate) fn artifact_path(store: &SharedStore, v: &VIndexFile, ext: &str) -> PathBuf {
+    let table_dir = store.0.lock().db_dir(&v.table);
+    artifact_file(&table_dir, &v.qualified, ext)
+}
+
+pub(crate) fn artifact_file(table_dir: &Path, qualified: &str, ext: &str) -> PathBuf {
+    table_dir.join("_vindex").join(format!("{}.{}", qualified.replace('/', "."), ext))
+}
+
+/// Where indexes built before artifacts moved under the table folder kept their payload:
+/// `<db>/<schema>/<name>.<ext>`.
+fn legacy_artifact_path(store: &SharedStore, qualified: &str, ext: &str) -> PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-    p.push(local);
-    p.set_extension("vdata");
+    p = crate::ident::to_local_path(&p, &qualified);
+    p.set_extension(ext);
     p
 }
 
+/// Payload to load for `v`: the table-folder artifact, else a legacy one.
+fn path_for_index_data(store: &SharedStore, v: &VIndexFile) -> PathBuf {
+    let p = artifact_path(store, v, "vdata");
+    if p.exists() { return p; }
+    let legacy = legacy_artifact_path(store, &v.qualified, "vdata");
+    if legacy.exists() { legacy } else { p }
+}
+
+/// Write through a temporary file and rename it into place, so readers (and mappings of the
+/// previous file) never see a partly written artifact.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() { std::fs::create_dir_all(parent)?; }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Index parameter given in CREATE VECTOR INDEX ... WITH (...), matched case-insensitively.
+fn param_usize(v: &VIndexFile, key: &str) -> Option<usize> {
+    let p = v.params.as_ref()?;
+    let (_, val) = p.iter().find(|(k, _)| k.eq_ignore_ascii_case(key))?;
+    match val {
+        serde_json::Value::Number(n) => n.as_u64().map(|n| n as usize),
+        serde_json::Value::String(s) => s.trim().trim_matches('\'').parse::<usize>().ok(),
+        _ => None,
+    }.filter(|n| *n > 0)
+}
+
+/// Row-id flavour bits of the `.vdata` flags word (bit0 is has_rowid).
+pub(crate) const ROW_ID_PK_NUMERIC: u32 = 0x2;
+pub(crate) const ROW_ID_PK_HASHED: u32 = 0x4;
+pub(crate) const ROW_ID_ORDINAL: u32 = 0x8;
+
+/// Row ids of indexed rows: the table's primary key when it has one (numeric as is, other or
+/// composite keys hashed), else the row's position among indexed rows.
+pub(crate) struct RowIdSource {
+    pks: Option<Vec<(String, Column)>>,
+}
+
+impl RowIdSource {
+    pub(crate) fn new(df: &DataFrame, pk_cols: Option<&Vec<String>>) -> Self {
+        // Pre-fetch PK series if present
+        let pks = pk_cols.map(|cols| {
+            cols.iter()
+                .filter_map(|c| {
+                    let eff = df.get_column_names()
+                        .iter()
+                        .find(|n| n.as_str() == c.as_str())
+                        .cloned()
+                        .or_else(|| df.get_column_names().iter().find(|n| n.eq_ignore_ascii_case(c)).cloned());
+                    eff.and_then(|name| df.column(&name).ok().map(|s| (name.to_string(), s.clone())))
+                })
+                .collect::<Vec<(String, Column)>>()
+        });
+        RowIdSource { pks }
+    }
+
+    /// Row id of row `i` and the flavour bit it used; `ordinal` is used without a primary key.
+    pub(crate) fn row_id(&self, i: usize, ordinal: u64) -> (u64, u32) {
+        // Build a single string with separators to avoid collisions
+        fn hash_pk_parts(parts: &[(String, String)]) -> u64 {
+            let mut acc = String::with_capacity(parts.len() * 24);
+            for (i, (k, v)) in parts.iter().enumerate() {
+                if i > 0 { acc.push('|'); }
+                acc.push_str(k);
+                acc.push('=');
+                acc.push_str(v);
+            }
+            xxhash_rust::xxh3::xxh3_64(acc.as_bytes())
+        }
+        fn text_at(s: &Column, i: usize) -> String {
+            s.get(i).ok().and_then(|av| av.get_str().map(|x| x.to_string())).unwrap_or_else(|| {
+                s.get(i).ok().map(|av| av.to_string()).unwrap_or_default()
+            })
+        }
+        match self.pks.as_deref() {
+            Some([(name, s)]) => {
+                // Try numeric fast-path, else hash string representation
+                let rid_u: Option<u64> = s.get(i)
+                    .ok()
+                    .and_then(|av| {
+                        if let Ok(v) = av.try_extract::<u64>() { return Some(v); }
+                        if let Ok(v) = av.try_extract::<i64>() { return Some(v as u64); }
+                        if let Ok(v) = av.try_extract::<u32>() { return Some(v as u64); }
+                        if let Ok(v) = av.try_extract::<i32>() { return Some(v as u64); }
+                        None
+                    });
+                match rid_u {
+                    Some(vu) => (vu, ROW_ID_PK_NUMERIC),
+                    // String or other types → hash
+                    None => (hash_pk_parts(&[(name.clone(), text_at(s, i))]), ROW_ID_PK_HASHED),
+                }
+            }
+            // Composite key: hash normalized tuple of "col=value"
+            Some(pks) if !pks.is_empty() => {
+                let parts: Vec<(String, String)> = pks.iter().map(|(name, s)| (name.clone(), text_at(s, i))).collect();
+                (hash_pk_parts(&parts), ROW_ID_PK_HASHED)
+            }
+            // No PK metadata, or no resolvable PK columns found in DF → ordinal fallback
+            _ => (ordinal, 0),
+        }
+    }
+}
+
 pub fn build_vector_index(store: &SharedStore, v: &mut VIndexFile, _options: &Vec<(String,String)>) -> Result<serde_json::Value> {
     // Read source table and build a flat f32 vector store for now.
     let t_start = std::time::Instant::now();
-    let data_path = path_for_index_data(store, &v.qualified);
-    if let Some(parent) = data_path.parent() { std::fs::create_dir_all(parent).ok(); }
+    let data_path = artifact_path(store, v, "vdata");
+    // Fingerprint the chunks before reading them, so rows landing during the build mark the index stale
+    let (source_chunks, source_fingerprint) = source_fingerprint(store, &v.table);
     // Load source table dataframe
     let df = store.0.lock().read_df(&v.table)?;
     let col = match df.get_column_names().iter().find(|c| c.eq_ignore_ascii_case(&v.column)) {
@@ -160,35 +336,7 @@ pub fn build_vector_index(store: &SharedStore, v: &mut VIndexFile, _options: &Ve
     // Determine row-id strategy from primary key metadata
     let pk_cols: Option<Vec<String>> = store.0.lock().get_primary_key(&v.table);
     let mut id_flags: u32 = 1; // bit0: has_rowid (we always persist row ids in v2)
-    // Pre-fetch PK series if present
-    let pk_series: Option<Vec<(String, Column)>> = pk_cols.as_ref().map(|cols| {
-        cols.iter()
-            .filter_map(|c| {
-                let eff = df.get_column_names()
-                    .iter()
-                    .find(|n| n.as_str() == c.as_str())
-                    .cloned()
-                    .or_else(|| df.get_column_names().iter().find(|n| n.eq_ignore_ascii_case(c)).cloned());
-                eff.and_then(|name| df.column(&name).ok().map(|s| (name.to_string(), s.clone())))
-            })
-            .collect::<Vec<(String, Column)>>()
-    });
-    // Helper to compute u64 row_id from primary key values at row i
-    #[inline]
-    fn hash_pk_parts(parts: &[(String, String)]) -> u64 {
-        use xxhash_rust::xxh3::xxh3_64;
-        // Build a single string with separators to avoid collisions
-        let mut acc = String::with_capacity(parts.len() * 24);
-        for (i, (k, v)) in parts.iter().enumerate() {
-            if i > 0 { acc.push('|'); }
-            acc.push_str(k);
-            acc.push('=');
-            acc.push_str(v);
-        }
-        xxh3_64(acc.as_bytes())
-    }
-    let mut used_pk_numeric = false;
-    let mut used_pk_hashed = false;
+    let ids = RowIdSource::new(&df, pk_cols.as_ref());
     for i in 0..series.len() {
         match crate::server::exec::vector_utils::extract_vec_f32_col(series, i) {
             Some(vv) => {
@@ -205,51 +353,9 @@ pub fn build_vector_index(store: &SharedStore, v: &mut VIndexFile, _options: &Ve
                 parsed_ok += 1;
                 buf.extend_from_slice(&vv);
                 // Prefer table primary key when available; else fallback to ordinal
-            if let Some(pks) = pk_series.as_ref() {
-                if pks.len() == 1 {
-                    let (_name, s) = &pks[0];
-                    // Try numeric fast-path, else hash string representation
-                    let rid_u: Option<u64> = s.get(i)
-                        .ok()
-                        .and_then(|av| {
-                            if let Ok(v) = av.try_extract::<u64>() { return Some(v); }
-                            if let Ok(v) = av.try_extract::<i64>() { return Some(v as u64); }
-                            if let Ok(v) = av.try_extract::<u32>() { return Some(v as u64); }
-                            if let Ok(v) = av.try_extract::<i32>() { return Some(v as u64); }
-                            None
-                        });
-                    if let Some(vu) = rid_u {
-                        used_pk_numeric = true;
-                        row_ids.push(vu);
-                    } else {
-                        // String or other types → hash
-                        let sval = s.get(i).ok().and_then(|av| av.get_str().map(|x| x.to_string())).unwrap_or_else(|| {
-                            s.get(i).ok().map(|av| av.to_string()).unwrap_or_default()
-                        });
-                        let h = hash_pk_parts(&[(_name.clone(), sval)]);
-                        used_pk_hashed = true;
-                        row_ids.push(h);
-                    }
-                } else if !pks.is_empty() {
-                    // Composite key: hash normalized tuple of "col=value"
-                    let mut parts: Vec<(String, String)> = Vec::with_capacity(pks.len());
-                    for (name, s) in pks.iter() {
-                        let val = s.get(i).ok().and_then(|av| av.get_str().map(|x| x.to_string())).unwrap_or_else(|| {
-                            s.get(i).ok().map(|av| av.to_string()).unwrap_or_default()
-                        });
-                        parts.push((name.clone(), val));
-                    }
-                    let h = hash_pk_parts(&parts);
-                    used_pk_hashed = true;
-                    row_ids.push(h);
-                } else {
-                    // No resolvable PK columns found in DF → ordinal fallback
-                    row_ids.push(rows as u64);
-                }
-            } else {
-                // No PK metadata → ordinal fallback
-                row_ids.push(rows as u64);
-            }
+                let (rid, kind) = ids.row_id(i, rows as u64);
+                id_flags |= kind;
+                row_ids.push(rid);
                 rows += 1;
             }
             None => { invalid_rows += 1; }
@@ -271,9 +377,9 @@ pub fn build_vector_index(store: &SharedStore, v: &mut VIndexFile, _options: &Ve
         .into());
     }
     // Compose flags for id flavor
-    if used_pk_numeric { id_flags |= 0x2; }
-    if used_pk_hashed { id_flags |= 0x4; }
-    if !used_pk_numeric && !used_pk_hashed { id_flags |= 0x8; }
+    let used_pk_numeric = id_flags & ROW_ID_PK_NUMERIC != 0;
+    let used_pk_hashed = id_flags & ROW_ID_PK_HASHED != 0;
+    if !used_pk_numeric && !used_pk_hashed { id_flags |= ROW_ID_ORDINAL; }
     crate::tprintln!("[vector.build] {} row-id strategy={} flags=0x{:x} rows={} dim={} pk_cols={}",
         v.qualified,
         if used_pk_numeric { "pk_numeric" } else if used_pk_hashed { "pk_hashed" } else { "ordinal" },
@@ -297,7 +403,7 @@ pub fn build_vector_index(store: &SharedStore, v: &mut VIndexFile, _options: &Ve
     // f32 payload
     let bytes = unsafe { std::slice::from_raw_parts(buf.as_ptr() as *const u8, buf.len() * 4) };
     out.extend_from_slice(bytes);
-    std::fs::write(&data_path, &out)?;
+    write_atomic(&data_path, &out)?;
     let mut status = serde_json::Map::new();
     status.insert("state".into(), json!("built"));
     status.insert("rows_indexed".into(), json!(rows as u64));
@@ -312,24 +418,56 @@ pub fn build_vector_index(store: &SharedStore, v: &mut VIndexFile, _options: &Ve
     status.insert("dim_policy".into(), json!(dim_policy));
     status.insert("row_id.flags".into(), json!(flags));
     status.insert("row_id.strategy".into(), json!(if used_pk_numeric { "pk_numeric" } else if used_pk_hashed { "pk_hashed" } else { "ordinal" }));
-    status.insert("build_time_ms".into(), json!(t_start.elapsed().as_millis() as u64));
+    status.insert("source_chunks".into(), json!(source_chunks));
+    status.insert("source_fingerprint".into(), json!(source_fingerprint));
     if let Some(m) = &v.metric { status.insert("metric".into(), json!(m)); }
     // Promote select params to top-level fields and also include under param.* for completeness
     if let Some(p) = &v.params {
         for (k, val) in p.iter() { status.insert(format!("param.{}", k), val.clone()); }
-        if let Some(efb) = p.get("ef_build").and_then(|x| x.as_i64()) { status.insert("ef_build".into(), json!(efb)); }
-        if let Some(efs) = p.get("ef_search").and_then(|x| x.as_i64()) { status.insert("ef_search".into(), json!(efs)); }
     }
+    if let Some(efb) = param_usize(v, "ef_build") { status.insert("ef_build".into(), json!(efb)); }
+    if let Some(efs) = param_usize(v, "ef_search") { status.insert("ef_search".into(), json!(efs)); }
     if let Some(mode) = &v.mode { status.insert("mode".into(), json!(mode)); }
-    v.status = Some(status);
-    // Optionally build HNSW artifact when feature enabled; ignore errors, keep flat engine as baseline
+    // IVF-PQ indexes build their lists instead of a graph; on failure the flat engine serves searches
+    if v.algo.eq_ignore_ascii_case("ivfpq") {
+        let ivf_path = artifact_path(store, v, "ivfpq");
+        match self::ivf_backend::build_ivf_index(store, v, &buf, dim) {
+            Ok(()) => {
+                status.insert("engine".into(), json!("ivfpq"));
+                if let Ok(md) = std::fs::metadata(&ivf_path) { status.insert("ivf_bytes".into(), json!(md.len())); }
+            }
+            Err(e) => {
+                tprintln!("vector.ivfpq.build.fail name={} err={}", v.qualified, e);
+                let _ = std::fs::remove_file(&ivf_path);
+            }
+        }
+        for key in ["nlist", "m", "nbits", "nprobe", "rerank"] {
+            if let Some(n) = param_usize(v, key) { status.insert(key.into(), json!(n)); }
+        }
+    }
+    // Optionally build HNSW artifact when feature enabled; on failure the flat engine serves searches
     #[cfg(feature = "ann_hnsw")]
-    {
-        let _ = self::hnsw_backend::build_hnsw_index(store, v);
-        if let Some(st) = v.status.as_mut() {
-            st.insert("engine.hnsw".into(), json!(true));
+    if !v.algo.eq_ignore_ascii_case("ivfpq") {
+        let graph_path = artifact_path(store, v, "hnsw");
+        match self::hnsw_backend::build_hnsw_index(store, v, &buf, dim) {
+            Ok(()) => {
+                status.insert("engine".into(), json!("hnsw"));
+                status.insert("engine.hnsw".into(), json!(true));
+                if let Ok(md) = std::fs::metadata(&graph_path) { status.insert("graph_bytes".into(), json!(md.len())); }
+            }
+            Err(e) => {
+                tprintln!("vector.hnsw.build.fail name={} err={}", v.qualified, e);
+                let _ = std::fs::remove_file(&graph_path);
+            }
         }
     }
+    status.insert("build_time_ms".into(), json!(t_start.elapsed().as_millis() as u64));
+    v.status = Some(status);
+    // Payloads from before artifacts moved under the table folder are superseded
+    for ext in ["vdata", "hnsw"] { let _ = std::fs::remove_file(legacy_artifact_path(store, &v.qualified, ext)); }
+    evict_loaded(&data_path);
+    // The build read every row the delta held
+    crate::server::exec::vector_delta::delete_delta(&artifact_path(store, v, "vdelta"));
     tprintln!(
         "[vector.build] name={} status=ok dim={} rows_indexed={} total={} invalid={} dim_mismatch={} policy={}",
         v.qualified, dim, rows, total_rows, invalid_rows, dim_mismatch, dim_policy
@@ -338,14 +476,14 @@ pub fn build_vector_index(store: &SharedStore, v: &mut VIndexFile, _options: &Ve
 }
 
 pub fn reindex_vector_index(store: &SharedStore, v: &mut VIndexFile) -> Result<serde_json::Value> {
-    // For now, reindex just calls build again.
+    // For now, reindex (and REBUILD) just calls build again.
     build_vector_index(store, v, &Vec::new())
 }
 
 pub fn show_vector_index_status(store: &SharedStore, name: Option<&str>) -> Result<serde_json::Value> {
     crate::tprintln!("[VINDEX.STATUS] enter name={:?}", name);
     // Build normalized rows per index with agreed fields
-    fn normalize_row(name: &str, v: &VIndexFile) -> serde_json::Value {
+    fn normalize_row(name: &str, v: &VIndexFile, stale: Option<bool>, delta_rows: u64) -> serde_json::Value {
         let st = v.status.as_ref();
         let get_i64 = |k: &str| st.and_then(|m| m.get(k)).and_then(|x| x.as_i64()).unwrap_or(0);
         let get_u64 = |k: &str| st.and_then(|m| m.get(k)).and_then(|x| x.as_u64()).unwrap_or(0);
@@ -365,7 +503,9 @@ pub fn show_vector_index_status(store: &SharedStore, name: Option<&str>) -> Resu
         serde_json::json!({
             "name": name,
             "state": state,
+            "stale": stale,
             "rows_indexed": rows_indexed,
+            "delta_rows": delta_rows,
             "bytes": bytes,
             "dim": dim,
             "metric": if metric.is_empty() { serde_json::Value::Null } else { serde_json::Value::String(metric) },
@@ -382,7 +522,7 @@ pub fn show_vector_index_status(store: &SharedStore, name: Option<&str>) -> Resu
     if let Some(n) = name {
         let qualified = crate::ident::qualify_regular_ident(n, &crate::system::current_query_defaults());
         if let Some(vf) = super::exec_vector_index::read_vindex_file(store, &qualified)? {
-            let row = normalize_row(&vf.name, &vf);
+            let row = normalize_row(&vf.name, &vf, is_stale(store, &vf), delta_rows(store, &vf));
             crate::tprintln!("[VINDEX.STATUS] single: name='{}' mode={:?} state={:?}", vf.name, vf.mode, vf.status.as_ref().and_then(|m| m.get("state")));
             return Ok(json!([row]));
         }
@@ -390,7 +530,7 @@ pub fn show_vector_index_status(store: &SharedStore, name: Option<&str>) -> Resu
     }
     let mut out_rows: Vec<serde_json::Value> = Vec::new();
     let root = store.0.lock().root_path().clone();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
             if let Ok(sd) = std::fs::read_dir(&db_path) {
@@ -402,7 +542,7 @@ pub fn show_vector_index_status(store: &SharedStore, name: Option<&str>) -> Resu
                             if tp.is_file() && tp.extension().and_then(|s| s.to_str()) == Some("vindex") {
                                 if let Ok(text) = std::fs::read_to_string(&tp) {
                                     if let Ok(v) = serde_json::from_str::<VIndexFile>(&text) {
-                                        out_rows.push(normalize_row(&v.name, &v));
+                                        out_rows.push(normalize_row(&v.name, &v, is_stale(store, &v), delta_rows(store, &v)));
                                     }
                                 }
                             }
@@ -417,131 +557,304 @@ pub fn show_vector_index_status(store: &SharedStore, name: Option<&str>) -> Resu
     Ok(json!(out_rows))
 }
 
-/// Placeholder for incremental DML application according to index mode.
-/// For now, all modes except REBUILD_ONLY are not supported and will return a friendly error.
-pub fn apply_vector_dml(_store: &SharedStore, v: &VIndexFile, op: &str) -> Result<serde_json::Value> {
-    let mode = v.mode.as_deref().unwrap_or("REBUILD_ONLY").to_ascii_uppercase();
-    match mode.as_str() {
-        "REBUILD_ONLY" => {
-            Err(AppError::Exec { code: "vector_dml_rebuild_only".into(), message: format!("Vector index '{}' is REBUILD_ONLY; incremental '{}' not supported. Use BUILD/REINDEX to refresh.", v.qualified, op) }.into())
-        }
-        "IMMEDIATE" | "BATCHED" | "ASYNC" => {
-            Err(AppError::Exec { code: "vector_dml_not_supported".into(), message: format!("Vector index mode '{}' for '{}' does not support incremental '{}' yet.", mode, v.qualified, op) }.into())
-        }
-        other => {
-            Err(AppError::Exec { code: "vector_dml_bad_mode".into(), message: format!("Unrecognized vector index mode '{}' for '{}'.", other, v.qualified) }.into())
+/// A loaded `.vdata` payload (and its graph), shared by searches until the index is rebuilt.
+pub struct VectorData {
+    pub dim: u32,
+    pub rows: u32,
+    pub row_ids: Option<Vec<u64>>,
+    payload: Payload,
+    #[cfg(feature = "ann_hnsw")]
+    graph: Option<crate::server::exec::vector_hnsw::HnswGraph>,
+    ivf: Option<crate::server::exec::vector_ivfpq::IvfPq>,
+}
+
+enum Payload {
+    Owned(Vec<f32>),
+    /// Vectors read in place from the mapped file. Artifacts are only ever replaced by rename,
+    /// so a mapping stays valid while searches still hold it.
+    #[cfg(feature = "ann_hnsw_mmap")]
+    Mapped { map: memmap2::Mmap, offset: usize, len: usize },
+}
+
+impl VectorData {
+    /// All vectors, row after row.
+    pub fn data(&self) -> &[f32] {
+        match &self.payload {
+            Payload::Owned(v) => v,
+            #[cfg(feature = "ann_hnsw_mmap")]
+            Payload::Mapped { map, offset, len } => {
+                // Alignment and bounds were checked when the file was mapped
+                unsafe { std::slice::from_raw_parts(map.as_ptr().add(*offset) as *const f32, *len) }
+            }
         }
     }
+
+    /// Stored row id of position `i`, or the position itself for v1 payloads.
+    pub fn row_id(&self, i: u32) -> u64 {
+        self.row_ids.as_ref().and_then(|v| v.get(i as usize)).cloned().unwrap_or(i as u64)
+    }
+
+    #[cfg(feature = "ann_hnsw")]
+    pub fn has_graph(&self) -> bool { self.graph.is_some() }
+
+    pub fn has_ivf(&self) -> bool { self.ivf.is_some() }
+}
+
+/// (size, mtime) of the file a cache entry was loaded from.
+type FileStamp = (u64, Option<std::time::SystemTime>);
+
+static LOADED: Lazy<Mutex<HashMap<PathBuf, (FileStamp, Arc<VectorData>)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn evict_loaded(path: &Path) { LOADED.lock().remove(path); }
+
+/// Payload of `v`, from the cache while its file is unchanged, else read from disk.
+pub fn load_vdata(store: &SharedStore, v: &VIndexFile) -> Result<Arc<VectorData>> {
+    let path = path_for_index_data(store, v);
+    let md = std::fs::metadata(&path)?;
+    let stamp: FileStamp = (md.len(), md.modified().ok());
+    if let Some((s, vd)) = LOADED.lock().get(&path) {
+        if *s == stamp { return Ok(vd.clone()); }
+    }
+    let t_start = std::time::Instant::now();
+    let vd = Arc::new(read_vdata(&path)?);
+    tprintln!("[vector.load] name={} path={} rows={} dim={} ms={}", v.qualified, path.display(), vd.rows, vd.dim, t_start.elapsed().as_millis());
+    LOADED.lock().insert(path, (stamp, vd.clone()));
+    Ok(vd)
 }
 
-fn load_vdata(store: &SharedStore, qualified: &str) -> Result<(u32, u32, Option<Vec<u64>>, Vec<f32>)> {
-    let p = path_for_index_data(store, qualified);
-    let bytes = std::fs::read(&p)?;
+/// Parse a `.vdata` file: (dim, rows, row_ids, byte offset of the vectors).
+fn parse_vdata_header(bytes: &[u8]) -> Result<(u32, u32, Option<Vec<u64>>, usize)> {
+    let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
     if bytes.len() < 16 { bail!("corrupt vdata: too small"); }
-    let magic = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
-    if magic != 0x56444346 { bail!("corrupt vdata: bad magic"); }
-    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
-    if version == 1 {
+    if word(0) != 0x56444346 { bail!("corrupt vdata: bad magic"); }
+    let version = word(4);
+    let (dim, rows, row_ids, offset) = if version == 1 {
         // v1: magic|version|dim|rows|data
-        let dim = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
-        let rows = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
-        let expected = 16usize + (rows as usize * dim as usize * 4);
-        if bytes.len() != expected { bail!("corrupt vdata(v1): size mismatch"); }
-        let mut data = vec![0f32; (rows * dim) as usize];
-        let src = &bytes[16..];
-        let ptr = data.as_mut_ptr() as *mut u8;
-        unsafe { std::ptr::copy_nonoverlapping(src.as_ptr(), ptr, src.len()); }
-        Ok((dim, rows, None, data))
+        let (dim, rows) = (word(8), word(12));
+        if bytes.len() != 16usize + rows as usize * dim as usize * 4 { bail!("corrupt vdata(v1): size mismatch"); }
+        (dim, rows, None, 16usize)
     } else {
         // v2+: magic|version|flags|dim|rows|[row_ids]|data
         if bytes.len() < 20 { bail!("corrupt vdata(v2): too small"); }
-        let flags = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
-        let dim = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
-        let rows = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
-        let has_rowid = (flags & 1) != 0;
+        let (flags, dim, rows) = (word(8), word(12), word(16));
         let mut offset = 20usize;
-        let row_ids = if has_rowid {
-            let mut v = Vec::with_capacity(rows as usize);
+        let row_ids = if (flags & 1) != 0 {
             let need = rows as usize * 8;
             if bytes.len() < offset + need { bail!("corrupt vdata: row_ids truncated"); }
-            for i in 0..rows as usize {
-                let start = offset + i*8;
-                v.push(u64::from_le_bytes(bytes[start..start+8].try_into().unwrap()));
-            }
+            let ids = bytes[offset..offset + need].chunks_exact(8).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect();
             offset += need;
-            Some(v)
+            Some(ids)
         } else { None };
-        let need_data = rows as usize * dim as usize * 4;
-        if bytes.len() < offset + need_data { bail!("corrupt vdata: data truncated"); }
-        let mut data = vec![0f32; (rows * dim) as usize];
-        let src = &bytes[offset..offset+need_data];
-        let ptr = data.as_mut_ptr() as *mut u8;
-        unsafe { std::ptr::copy_nonoverlapping(src.as_ptr(), ptr, src.len()); }
-        Ok((dim, rows, row_ids, data))
+        (dim, rows, row_ids, offset)
+    };
+    if bytes.len() < offset + rows as usize * dim as usize * 4 { bail!("corrupt vdata: data truncated"); }
+    Ok((dim, rows, row_ids, offset))
+}
+
+fn read_vdata(path: &Path) -> Result<VectorData> {
+    #[cfg(feature = "ann_hnsw_mmap")]
+    let bytes = {
+        let file = std::fs::File::open(path)?;
+        unsafe { memmap2::Mmap::map(&file) }.map_err(|e| anyhow::anyhow!("mmap {}: {}", path.display(), e))?
+    };
+    #[cfg(not(feature = "ann_hnsw_mmap"))]
+    let bytes = std::fs::read(path)?;
+    let (dim, rows, row_ids, offset) = parse_vdata_header(&bytes)?;
+    let len = rows as usize * dim as usize;
+    let copy = |b: &[u8]| -> Vec<f32> { b[offset..offset + len * 4].chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())).collect() };
+    #[cfg(feature = "ann_hnsw_mmap")]
+    let payload = if cfg!(target_endian = "little") && (bytes.as_ptr() as usize + offset) % std::mem::align_of::<f32>() == 0 {
+        Payload::Mapped { map: bytes, offset, len }
+    } else {
+        Payload::Owned(copy(&bytes))
+    };
+    #[cfg(not(feature = "ann_hnsw_mmap"))]
+    let payload = Payload::Owned(copy(&bytes));
+    Ok(VectorData {
+        dim,
+        rows,
+        row_ids,
+        payload,
+        #[cfg(feature = "ann_hnsw")]
+        graph: self::hnsw_backend::load_graph(path, rows),
+        ivf: self::ivf_backend::load_ivf(path, rows),
+    })
+}
+
+/// Load every built index in the background, one at a time, so the first searches after a
+/// restart find their payload and graph already in memory. Indexes that fail to load are
+/// skipped; searches load them on demand and report the error then.
+pub fn spawn_warm_load(store: SharedStore) {
+    let spawned = std::thread::Builder::new().name("vector-warm-load".into()).spawn(move || {
+        let t_start = std::time::Instant::now();
+        let mut loaded = 0usize;
+        for meta in crate::system_catalog::shared::enumerate_vector_indexes(&store) {
+            let Ok(text) = std::fs::read_to_string(&meta.file) else { continue };
+            let Ok(v) = serde_json::from_str::<VIndexFile>(&text) else { continue };
+            let built = v.status.as_ref().and_then(|m| m.get("state")).and_then(|x| x.as_str()) == Some("built");
+            if !built { continue; }
+            match load_vdata(&store, &v) {
+                Ok(_) => loaded += 1,
+                Err(e) => tracing::warn!(target: "clarium::vector", "warm load of vector index {} failed: {}", v.qualified, e),
+            }
+        }
+        tracing::info!(target: "clarium::vector", "warm-loaded {} vector index(es) in {} ms", loaded, t_start.elapsed().as_millis());
+    });
+    if let Err(e) = spawned { tracing::warn!(target: "clarium::vector", "vector index warm load not started: {}", e); }
+}
+
+/// Number of chunks of `table` and a hash of their paths, sizes and write times.
+fn source_fingerprint(store: &SharedStore, table: &str) -> (u64, String) {
+    let dir = store.0.lock().db_dir(table);
+    fingerprint_dir(&dir)
+}
+
+/// [`source_fingerprint`] of the table stored in `dir`.
+pub(crate) fn fingerprint_dir(dir: &Path) -> (u64, String) {
+    let files = crate::storage::partition::chunk_files(&dir);
+    let mut acc: Vec<u8> = Vec::new();
+    for f in &files {
+        let rel = f.strip_prefix(dir).unwrap_or(f);
+        let md = std::fs::metadata(f).ok();
+        let mtime = md.as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        acc.extend_from_slice(format!("{}|{}|{}\n", rel.display(), md.map(|m| m.len()).unwrap_or(0), mtime).as_bytes());
+    }
+    (files.len() as u64, format!("{:016x}", xxhash_rust::xxh3::xxh3_64(&acc)))
+}
+
+/// Whether chunks of the indexed table were written, rewritten or removed since the last
+/// build, other than by inserts the index delta holds. None for indexes never built, or built
+/// before sources were fingerprinted.
+pub fn is_stale(store: &SharedStore, v: &VIndexFile) -> Option<bool> {
+    let st = v.status.as_ref()?;
+    if st.get("state").and_then(|x| x.as_str()) != Some("built") { return None; }
+    let recorded = st.get("source_fingerprint").and_then(|x| x.as_str())?;
+    let table_dir = store.0.lock().db_dir(&v.table);
+    let current = fingerprint_dir(&table_dir).1;
+    if current == recorded { return Some(false); }
+    let delta = crate::server::exec::vector_delta::load_delta(&crate::server::exec::vector_delta::delta_path(&table_dir, &v.qualified));
+    Some(!delta.is_some_and(|d| d.flags & crate::server::exec::vector_delta::FLAG_STALE == 0 && d.fingerprint == current))
+}
+
+/// Rows appended to the delta of `v` since its last build.
+pub fn delta_rows(store: &SharedStore, v: &VIndexFile) -> u64 {
+    let table_dir = store.0.lock().db_dir(&v.table);
+    crate::server::exec::vector_delta::load_delta(&crate::server::exec::vector_delta::delta_path(&table_dir, &v.qualified))
+        .map(|d| d.rows() as u64)
+        .unwrap_or(0)
+}
+
+/// Remove the payload, graph or IVF-PQ lists and delta of `v` (table-folder and legacy
+/// locations) and forget them.
+pub fn delete_vector_artifacts(store: &SharedStore, v: &VIndexFile) {
+    crate::server::exec::vector_delta::delete_delta(&artifact_path(store, v, "vdelta"));
+    for ext in ["vdata", "hnsw", "ivfpq"] {
+        for p in [artifact_path(store, v, ext), legacy_artifact_path(store, &v.qualified, ext)] {
+            if ext == "vdata" { evict_loaded(&p); }
+            let _ = std::fs::remove_file(&p);
+        }
     }
 }
 
-fn l2(a: &[f32], b: &[f32]) -> f32 {
+pub(crate) fn l2(a: &[f32], b: &[f32]) -> f32 {
     let mut s = 0f32;
     for i in 0..a.len() { let d = a[i] - b[i]; s += d*d; }
     s.sqrt()
 }
-fn dot(a: &[f32], b: &[f32]) -> f32 { a.iter().zip(b.iter()).map(|(x,y)| x*y).sum() }
-fn cosine(a: &[f32], b: &[f32]) -> f32 {
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 { a.iter().zip(b.iter()).map(|(x,y)| x*y).sum() }
+pub(crate) fn cosine(a: &[f32], b: &[f32]) -> f32 {
     let na = a.iter().map(|x| x*x).sum::<f32>().sqrt();
     let nb = b.iter().map(|x| x*x).sum::<f32>().sqrt();
     if na == 0.0 || nb == 0.0 { return f32::NAN; }
     dot(a,b) / (na*nb)
 }
 
-pub fn search_vector_index(store: &SharedStore, v: &VIndexFile, qvec: &[f32], k: usize) -> Result<Vec<(u64, f32)>> {
-    // Try ANN engine if present; map back to stored row_ids
-    let (dim, rows, row_ids, data) = load_vdata(store, &v.qualified)?;
-    #[cfg(feature = "ann_hnsw")]
-    if let Some(res) = self::hnsw_backend::search_hnsw_index(store, v, qvec, k) {
-        let mut out: Vec<(u64, f32)> = Vec::with_capacity(res.len());
-        for (pos, score) in res.into_iter() {
-            let id = row_ids.as_ref().and_then(|v| v.get(pos as usize)).cloned().unwrap_or(pos as u64);
-            out.push((id, score));
-        }
-        return Ok(out);
+#[inline]
+fn vector_at(data: &[f32], dim: u32, i: u32) -> &[f32] { &data[i as usize * dim as usize..(i as usize + 1) * dim as usize] }
+
+/// Score reported for a match: distance for l2, similarity for ip/cosine.
+fn score(metric: &str, a: &[f32], q: &[f32]) -> f32 {
+    match metric {
+        "ip" | "dot" => dot(a, q),
+        "cosine" => cosine(a, q),
+        _ => l2(a, q),
     }
-    if qvec.len() as u32 != dim { bail!("query dim {} mismatch index dim {}", qvec.len(), dim); }
-    let metric = v.metric.as_deref().unwrap_or("l2").to_ascii_lowercase();
+}
+
+/// Score of a match as a rank, larger is better: L2 distances are flipped and NaN
+/// (zero-norm cosine) sorts last.
+fn rank(metric: &str, s: f32) -> f32 {
+    if s.is_nan() { f32::NEG_INFINITY } else if matches!(metric, "ip" | "dot" | "cosine") { s } else { -s }
+}
+
+/// Engine that serves searches on `v` from its built artifacts: "ivfpq", "hnsw" or "flat".
+pub fn ann_engine(store: &SharedStore, v: &VIndexFile) -> &'static str {
+    if artifact_path(store, v, "ivfpq").exists() { return "ivfpq"; }
+    #[cfg(feature = "ann_hnsw")]
+    if artifact_path(store, v, "hnsw").exists() || legacy_artifact_path(store, &v.qualified, "hnsw").exists() { return "hnsw"; }
+    "flat"
+}
+
+/// Exact top-k over every row (that `allow` accepts): positions and scores, best first.
+fn scan_top_k(vd: &VectorData, metric: &str, qvec: &[f32], k: usize, allow: Option<&dyn Fn(u32) -> bool>) -> Vec<(u32, f32)> {
     // Use ordered key to satisfy Ord: map f32 score to u32 key preserving order
     #[inline]
     fn f32_key(v: f32) -> u32 { let b = v.to_bits(); if b & (1u32 << 31) != 0 { !b } else { b | (1u32 << 31) } }
-    // Maintain appropriate heap depending on metric order; for L2 store negative distance so larger is better
-    let mut heap: std::collections::BinaryHeap<(u32, u32)> = std::collections::BinaryHeap::with_capacity(k + 1);
-    for r in 0..rows as usize {
-        let off = r * dim as usize;
-        let slice = &data[off..off + dim as usize];
-        let (key, _raw_score) = match metric.as_str() {
-            "ip" | "dot" => { let s = dot(slice, qvec); (f32_key(s), s) },
-            "cosine" => { let s = cosine(slice, qvec); (f32_key(s), s) },
-            _ => { let d = l2(slice, qvec); let s = -d; (f32_key(s), d) }, // key on negative distance; raw score is positive distance
-        };
-        heap.push((key, r as u32));
+    let data = vd.data();
+    // Maintain a min-heap on the key; for L2 key on negative distance so larger is better
+    let mut heap: std::collections::BinaryHeap<std::cmp::Reverse<(u32, u32)>> = std::collections::BinaryHeap::with_capacity(k + 1);
+    for r in 0..vd.rows {
+        if allow.is_some_and(|f| !f(r)) { continue; }
+        let s = score(metric, vector_at(data, vd.dim, r), qvec);
+        let key = match metric { "ip" | "dot" | "cosine" => f32_key(s), _ => f32_key(-s) };
+        heap.push(std::cmp::Reverse((key, r)));
         if heap.len() > k { heap.pop(); }
     }
-    let mut items: Vec<(u32, u32)> = heap.into_iter().collect();
+    let mut items: Vec<(u32, u32)> = heap.into_iter().map(|std::cmp::Reverse(x)| x).collect();
     // Sort descending by score key
     items.sort_by(|a,b| b.0.cmp(&a.0));
     // Recompute true score for outputs (k is small) to avoid carrying raw in heap across metrics
-    let mut out: Vec<(u64, f32)> = Vec::with_capacity(items.len());
-    for (_k, i) in items.into_iter() {
-        let off = i as usize * dim as usize;
-        let slice = &data[off..off + dim as usize];
-        let s = match metric.as_str() {
-            "ip" | "dot" => dot(slice, qvec),
-            "cosine" => cosine(slice, qvec),
-            _ => l2(slice, qvec),
-        };
-        // Use row_ids if present; else positional index
-        let id = row_ids.as_ref().and_then(|v| v.get(i as usize)).cloned().unwrap_or(i as u64);
-        out.push((id, s));
+    items.into_iter().map(|(_k, i)| (i, score(metric, vector_at(data, vd.dim, i), qvec))).collect()
+}
+
+/// Fold exact matches from the delta of `v` (rows in `allowed`, when given) into `base`
+/// (best first) and keep the best `k`.
+fn merge_delta(store: &SharedStore, v: &VIndexFile, base: Vec<(u64, f32)>, metric: &str, qvec: &[f32], k: usize, allowed: Option<&HashSet<u64>>) -> Vec<(u64, f32)> {
+    let table_dir = store.0.lock().db_dir(&v.table);
+    let path = crate::server::exec::vector_delta::delta_path(&table_dir, &v.qualified);
+    let Some(delta) = crate::server::exec::vector_delta::load_delta(&path) else { return base };
+    if delta.rows() == 0 || delta.dim as usize != qvec.len() { return base; }
+    let mut all = base;
+    all.extend((0..delta.rows())
+        .filter(|&i| allowed.is_none_or(|a| a.contains(&delta.row_ids[i])))
+        .map(|i| (delta.row_ids[i], score(metric, delta.vector(i), qvec))));
+    all.sort_by(|a, b| rank(metric, b.1).total_cmp(&rank(metric, a.1)));
+    all.truncate(k);
+    tprintln!("[vector.search] name={} merged_delta_rows={}", v.qualified, delta.rows());
+    all
+}
+
+pub fn search_vector_index(store: &SharedStore, v: &VIndexFile, qvec: &[f32], k: usize) -> Result<Vec<(u64, f32)>> {
+    // Try ANN engine if present; map back to stored row_ids
+    let vd = load_vdata(store, v)?;
+    let metric = v.metric.as_deref().unwrap_or("l2").to_ascii_lowercase();
+    if let Some(res) = self::ivf_backend::search_ivf_index(&vd, v, qvec, k, None, None) {
+        let base = res.into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+        return Ok(merge_delta(store, v, base, &metric, qvec, k, None));
     }
-    Ok(out)
+    #[cfg(feature = "ann_hnsw")]
+    if let Some(res) = self::hnsw_backend::search_hnsw_index(&vd, v, qvec, k, None, None) {
+        let base = res.into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+        return Ok(merge_delta(store, v, base, &metric, qvec, k, None));
+    }
+    if qvec.len() as u32 != vd.dim { bail!("query dim {} mismatch index dim {}", qvec.len(), vd.dim); }
+    // Use row_ids if present; else positional index
+    let base = scan_top_k(&vd, &metric, qvec, k, None).into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+    Ok(merge_delta(store, v, base, &metric, qvec, k, None))
 }
 
 /// Optional knobs that can influence vector search behavior.
@@ -552,7 +865,9 @@ pub struct SearchOptions {
     /// ef_search hint for ANN engines (ignored by flat);
     /// engine backends should best-effort apply it.
     pub ef_search: Option<usize>,
-    /// Engine hint: "hnsw" | "flat" (case-insensitive); best-effort.
+    /// Lists probed by IVF-PQ engines (ignored by the others).
+    pub nprobe: Option<usize>,
+    /// Engine hint: "ivfpq" | "hnsw" | "flat" (case-insensitive); best-effort.
     pub engine_hint: Option<String>,
 }
 
@@ -565,80 +880,147 @@ pub fn search_vector_index_with_opts(
     k: usize,
     opts: &SearchOptions,
 ) -> Result<Vec<(u32, f32)>> {
-    // Try ANN backend first when available, unless the user forces flat
+    let vd = load_vdata(store, v)?;
+    let index_metric = v.metric.as_deref().unwrap_or("l2").to_ascii_lowercase();
+    let metric = opts
+        .metric_override
+        .as_ref()
+        .map(|s| s.to_ascii_lowercase())
+        .unwrap_or_else(|| index_metric.clone());
+
+    // Try ANN backend first when available, unless the user forces flat. The graph is laid out
+    // for the index metric, so a different metric is scored exactly.
     let force_flat = opts
         .engine_hint
         .as_ref()
         .map(|s| s.eq_ignore_ascii_case("flat"))
         .unwrap_or(false);
 
+    if !force_flat && metric == index_metric {
+        if let Some(res) = self::ivf_backend::search_ivf_index(&vd, v, qvec, k, opts.nprobe, None) {
+            let base = res.into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+            return Ok(merge_delta(store, v, base, &metric, qvec, k, None).into_iter().map(|(id, score)| (id as u32, score)).collect());
+        }
+    }
     #[cfg(feature = "ann_hnsw")]
-    if !force_flat {
-        // Currently only HNSW is supported.
-        // If ef_search is provided and the backend supports it, apply it best-effort.
-        if let Some(mut res) = self::hnsw_backend::search_hnsw_index(store, v, qvec, k) {
-            // Metric override on ANN path: for now HNSW distance is L2; if override is set to ip/cosine,
-            // we can only re-rank within the top-k using the requested metric for score reporting.
-            if let Some(mo) = opts.metric_override.as_ref() {
-                let mo_low = mo.to_ascii_lowercase();
-                // Load data to recompute scores according to requested metric for the already selected top-k
-                let (dim, _rows, row_ids, data) = load_vdata(store, &v.qualified)?;
-                for tup in res.iter_mut() {
-                    let i = tup.0 as usize; // note: tup.0 currently stores id (which may be row_id already). For ANN v2 we persist row ids; however HNSW stores internal index r.
-                    // We can't map back to internal row ordinal when row_ids are persisted; so keep scores as-is for now when override mismatches.
-                    // To keep behavior consistent, only adjust if metric is l2 request; otherwise leave ANN scores.
-                    match mo_low.as_str() {
-                        "l2" => { /* already L2 */ }
-                        _ => { let _ = (dim, row_ids.as_ref(), &data); }
-                    }
-                }
-            }
-            return Ok(res);
+    if !force_flat && metric == index_metric {
+        if let Some(res) = self::hnsw_backend::search_hnsw_index(&vd, v, qvec, k, opts.ef_search, None) {
+            let base = res.into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+            return Ok(merge_delta(store, v, base, &metric, qvec, k, None).into_iter().map(|(id, score)| (id as u32, score)).collect());
         }
     }
 
     // Fallback to flat exact path (or forced by hint)
-    let (dim, rows, row_ids, data) = load_vdata(store, &v.qualified)?;
-    if qvec.len() as u32 != dim {
+    if qvec.len() as u32 != vd.dim {
         tprintln!(
             "[vector.search] name={} warn=query_dim_mismatch qdim={} idx_dim={} action=fallback_or_error",
-            v.qualified, qvec.len(), dim
+            v.qualified, qvec.len(), vd.dim
         );
-        return Err(AppError::Exec { code: "vector_query_dim_mismatch".into(), message: format!("query dim {} mismatch index dim {}", qvec.len(), dim) }.into());
+        return Err(AppError::Exec { code: "vector_query_dim_mismatch".into(), message: format!("query dim {} mismatch index dim {}", qvec.len(), vd.dim) }.into());
     }
-    let metric = opts
-        .metric_override
-        .as_ref()
-        .map(|s| s.to_ascii_lowercase())
-        .or_else(|| v.metric.as_ref().map(|s| s.to_ascii_lowercase()))
-        .unwrap_or_else(|| "l2".to_string());
-    #[inline]
-    fn f32_key(v: f32) -> u32 { let b = v.to_bits(); if b & (1u32 << 31) != 0 { !b } else { b | (1u32 << 31) } }
-    let mut heap: std::collections::BinaryHeap<(u32, u32)> = std::collections::BinaryHeap::with_capacity(k + 1);
-    for r in 0..rows as usize {
-        let off = r * dim as usize;
-        let slice = &data[off..off + dim as usize];
-        let (key, _raw_score) = match metric.as_str() {
-            "ip" | "dot" => { let s = dot(slice, qvec); (f32_key(s), s) },
-            "cosine" => { let s = cosine(slice, qvec); (f32_key(s), s) },
-            _ => { let d = l2(slice, qvec); let s = -d; (f32_key(s), d) },
-        };
-        heap.push((key, r as u32));
-        if heap.len() > k { heap.pop(); }
+    let base = scan_top_k(&vd, &metric, qvec, k, None).into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+    Ok(merge_delta(store, v, base, &metric, qvec, k, None).into_iter().map(|(id, score)| (id as u32, score)).collect())
+}
+
+/// How one filtered search ran, collected for EXPLAIN ANALYZE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorSearchMetrics {
+    pub index: String,
+    /// "ann" (nothing filtered out), "filtered_ann" (filter applied while walking the index),
+    /// "prefilter" (exact scan of the allowed rows) or "exact" (no ANN engine built)
+    pub strategy: &'static str,
+    pub k: usize,
+    /// Rows that passed the filter, and rows in the index (payload plus delta)
+    pub allowed_rows: usize,
+    pub indexed_rows: usize,
+    /// Index searches run; more than one when the filter left too few results and the search
+    /// was widened
+    pub rounds: usize,
+    pub returned: usize,
+    /// Share of the exact filtered top-k that was returned
+    pub recall: f64,
+}
+
+thread_local! {
+    static SEARCH_METRICS: RefCell<Option<Vec<VectorSearchMetrics>>> = const { RefCell::new(None) };
+}
+
+/// Start collecting metrics of the filtered searches run on this thread. Their recall is
+/// measured against an exact scan, which costs one per search while collecting.
+pub fn collect_search_metrics() { SEARCH_METRICS.with(|m| *m.borrow_mut() = Some(Vec::new())); }
+
+/// Stop collecting and return the metrics collected since [`collect_search_metrics`].
+pub fn take_search_metrics() -> Vec<VectorSearchMetrics> { SEARCH_METRICS.with(|m| m.borrow_mut().take().unwrap_or_default()) }
+
+fn collecting_search_metrics() -> bool { SEARCH_METRICS.with(|m| m.borrow().is_some()) }
+
+/// Widening steps a filtered ANN search takes (nprobe or ef_search times 4 each) before it
+/// falls back to scanning the allowed rows.
+const FILTER_ROUNDS: usize = 3;
+
+/// ANN search through the IVF-PQ lists or the graph of `vd`, with nprobe or ef_search scaled by
+/// `widen`; None when neither is built.
+fn ann_search(vd: &VectorData, v: &VIndexFile, qvec: &[f32], k: usize, allow: Option<&dyn Fn(u32) -> bool>, widen: usize) -> Option<Vec<(u32, f32)>> {
+    if vd.has_ivf() {
+        let nprobe = self::ivf_backend::nprobe_for(v).saturating_mul(widen);
+        return self::ivf_backend::search_ivf_index(vd, v, qvec, k, Some(nprobe), allow);
     }
-    let mut items: Vec<(u32, u32)> = heap.into_iter().collect();
-    items.sort_by(|a,b| b.0.cmp(&a.0));
-    let mut out: Vec<(u32, f32)> = Vec::with_capacity(items.len());
-    for (_k, i) in items.into_iter() {
-        let off = i as usize * dim as usize;
-        let slice = &data[off..off + dim as usize];
-        let s = match metric.as_str() {
-            "ip" | "dot" => dot(slice, qvec),
-            "cosine" => cosine(slice, qvec),
-            _ => l2(slice, qvec),
+    #[cfg(feature = "ann_hnsw")]
+    if vd.has_graph() {
+        let ef = self::hnsw_backend::ef_for(v).max(k).saturating_mul(widen);
+        return self::hnsw_backend::search_hnsw_index(vd, v, qvec, k, Some(ef), allow);
+    }
+    None
+}
+
+/// Top-k restricted to the rows whose ids are in `allowed` (the rows that passed WHERE).
+///
+/// When the filter keeps fewer than `vector.filter.prefilter_percent` of the indexed rows (or
+/// no more than `k`), the allowed rows are scored exactly. Otherwise the filter is applied
+/// while the index is walked, and a search that comes back short is retried with nprobe or
+/// ef_search widened, then falls back to the exact scan.
+pub fn search_vector_index_filtered(store: &SharedStore, v: &VIndexFile, qvec: &[f32], k: usize, allowed: &HashSet<u64>) -> Result<Vec<(u64, f32)>> {
+    let vd = load_vdata(store, v)?;
+    if qvec.len() as u32 != vd.dim { bail!("query dim {} mismatch index dim {}", qvec.len(), vd.dim); }
+    let metric = v.metric.as_deref().unwrap_or("l2").to_ascii_lowercase();
+    let indexed = vd.rows as usize + delta_rows(store, v) as usize;
+    let allow = |pos: u32| allowed.contains(&vd.row_id(pos));
+    let unfiltered = allowed.len() >= indexed;
+    let percent = if indexed == 0 { 100.0 } else { allowed.len() as f64 * 100.0 / indexed as f64 };
+    let prefilter = !unfiltered && (allowed.len() <= k || percent < crate::system::get_vector_prefilter_percent() as f64);
+    let mut rounds = 0usize;
+    let mut found: Option<Vec<(u32, f32)>> = None;
+    let mut strategy = if unfiltered { "ann" } else if prefilter { "prefilter" } else { "filtered_ann" };
+    if unfiltered {
+        rounds = 1;
+        found = ann_search(&vd, v, qvec, k, None, 1);
+    } else if !prefilter {
+        let mut widen = 1usize;
+        while rounds < FILTER_ROUNDS {
+            rounds += 1;
+            match ann_search(&vd, v, qvec, k, Some(&allow), widen) {
+                Some(res) if res.len() >= k.min(allowed.len()) => { found = Some(res); break; }
+                Some(_) => widen *= 4,
+                None => break,
+            }
+        }
+        if found.is_none() { strategy = "prefilter"; }
+    }
+    if found.is_none() && unfiltered { strategy = "exact"; }
+    let exact = found.is_none();
+    let base = found.unwrap_or_else(|| scan_top_k(&vd, &metric, qvec, k, (!unfiltered).then_some(&allow as &dyn Fn(u32) -> bool)));
+    let base = base.into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+    let out = merge_delta(store, v, base, &metric, qvec, k, (!unfiltered).then_some(allowed));
+    tprintln!("[vector.search] name={} strategy={} k={} allowed={} indexed={} rounds={} returned={}", v.qualified, strategy, k, allowed.len(), indexed, rounds, out.len());
+    if collecting_search_metrics() {
+        let recall = if exact { 1.0 } else {
+            let truth = scan_top_k(&vd, &metric, qvec, k, Some(&allow)).into_iter().map(|(pos, score)| (vd.row_id(pos), score)).collect();
+            let truth = merge_delta(store, v, truth, &metric, qvec, k, Some(allowed));
+            let got: HashSet<u64> = out.iter().map(|(id, _)| *id).collect();
+            if truth.is_empty() { 1.0 } else { truth.iter().filter(|(id, _)| got.contains(id)).count() as f64 / truth.len() as f64 }
         };
-        let id = row_ids.as_ref().and_then(|v| v.get(i as usize)).cloned().unwrap_or(i as u64) as u32;
-        out.push((id, s));
+        let m = VectorSearchMetrics { index: v.qualified.clone(), strategy, k, allowed_rows: allowed.len(), indexed_rows: indexed, rounds, returned: out.len(), recall };
+        SEARCH_METRICS.with(|c| if let Some(list) = c.borrow_mut().as_mut() { list.push(m) });
     }
     Ok(out)
 }
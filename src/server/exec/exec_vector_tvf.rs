@@ -67,21 +67,14 @@ pub fn try_vector_tvf(store: &crate::storage::SharedStore, raw: &str) -> Result<
             };
             let metric = vf.metric.clone().unwrap_or_else(|| "l2".to_string());
             let ef_search = vf.params.as_ref().and_then(|p| p.get("ef_search")).and_then(|x| x.as_i64()).unwrap_or(0);
-            // Detect ANN engine availability (only when feature enabled and file exists); else flat
-            #[cfg(feature = "ann_hnsw")]
-            let ann_path_exists = {
-                let mut p = store.0.lock().root_path().clone();
-                let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-                p.push(local); p.set_extension("hnsw"); p.exists()
-            };
-            #[cfg(not(feature = "ann_hnsw"))]
-            let ann_path_exists = false;
-            let engine_auto = if ann_path_exists { "hnsw" } else { "flat" };
+            // Engine from the built artifacts (HNSW only when the feature is enabled); else flat
+            let engine_auto = crate::server::exec::exec_vector_runtime::ann_engine(store, &vf);
             let engine_effective = engine_hint.as_deref().unwrap_or(engine_auto);
             tprintln!("[ann.tvf] vector_search index={} engine={} metric={} ef_search={} k={} topk={:?}", qualified, engine_effective, metric, ef_search, k, topk_opt);
             let opts = crate::server::exec::exec_vector_runtime::SearchOptions {
                 metric_override: None,
                 ef_search: ef_search.try_into().ok(),
+                nprobe: None,
                 engine_hint: engine_hint.clone(),
             };
             let mut res = crate::server::exec::exec_vector_runtime::search_vector_index_with_opts(store, &vf, &qvec, k, &opts)?;
@@ -113,7 +106,7 @@ pub fn try_vector_tvf(store: &crate::storage::SharedStore, raw: &str) -> Result<
             if let Some(mut vf) = crate::server::exec::exec_vector_index::read_vindex_file(store, &format!("{}/idx_{}_{}", qualified_table, qualified_table.replace('/',"_"), column)).ok().flatten() {
                 // Not reliable naming; fall back to directory scan for .vindex matching table+column
                 let root = store.0.lock().root_path().clone();
-                'scan: for db_ent in std::fs::read_dir(&root).unwrap_or_else(|_| std::fs::read_dir(".").unwrap()) {
+                'scan: for db_ent in crate::storage::attach::read_database_dirs(&root).unwrap_or_else(|_| Vec::new().into_iter()) {
                     if let Ok(db_e) = db_ent {
                         let dbp = db_e.path(); if !dbp.is_dir() { continue; }
                         if let Ok(schemas) = std::fs::read_dir(&dbp) {
@@ -138,19 +131,12 @@ pub fn try_vector_tvf(store: &crate::storage::SharedStore, raw: &str) -> Result<
                 }
                 let metric_used = vf.metric.clone().unwrap_or_else(|| metric.clone().unwrap_or_else(|| "l2".into()));
                 let ef_search = vf.params.as_ref().and_then(|p| p.get("ef_search")).and_then(|x| x.as_i64()).unwrap_or(0);
-                #[cfg(feature = "ann_hnsw")]
-                let ann_available = {
-                    let mut p = store.0.lock().root_path().clone();
-                    let local = vf.qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-                    p.push(local); p.set_extension("hnsw"); p.exists()
-                };
-                #[cfg(not(feature = "ann_hnsw"))]
-                let ann_available = false;
-                let engine = if ann_available { "hnsw" } else { "flat" };
+                let engine = crate::server::exec::exec_vector_runtime::ann_engine(store, &vf);
                 tprintln!("[ann.tvf] nearest_neighbors table={} col={} engine={} metric={} ef_search={} k={} with_ord={}", qualified_table, column, engine, metric_used, ef_search, k, with_ord);
                 let opts = crate::server::exec::exec_vector_runtime::SearchOptions {
                     metric_override: metric.clone(),
                     ef_search: ef_search_opt.or_else(|| ef_search.try_into().ok()),
+                    nprobe: None,
                     engine_hint: None,
                 };
                 let res = crate::server::exec::exec_vector_runtime::search_vector_index_with_opts(store, &vf, &qvec, k, &opts)?;
@@ -239,17 +225,13 @@ pub fn explain_vector_expr(store: &crate::storage::SharedStore, raw: &str) -> Op
             let vf = match crate::server::exec::exec_vector_index::read_vindex_file(store, &qualified).ok().flatten() { Some(v) => v, None => return Some(format!("EXPLAIN: EXACT (flat) — reason: index not found: {}", index_name)) };
             let metric = vf.metric.clone().unwrap_or_else(|| "l2".to_string());
             let ef_search = vf.params.as_ref().and_then(|p| p.get("ef_search")).and_then(|x| x.as_i64()).unwrap_or(0);
-            #[cfg(feature = "ann_hnsw")]
-            let ann_available = {
-                let mut p = store.0.lock().root_path().clone();
-                let local = vf.qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-                p.push(local); p.set_extension("hnsw"); p.exists()
+            let path = match crate::server::exec::exec_vector_runtime::ann_engine(store, &vf) {
+                "ivfpq" => "ANN(IVFPQ)",
+                "hnsw" => "ANN(HNSW)",
+                _ => "EXACT(flat)",
             };
-            #[cfg(not(feature = "ann_hnsw"))]
-            let ann_available = false;
-            let path = if ann_available { "ANN(HNSW)" } else { "EXACT(flat)" };
             let mut notes = Vec::new();
-            if !ann_available { notes.push("hnsw_artifact_missing-or-feature-disabled".to_string()); }
+            if path == "EXACT(flat)" { notes.push("ann_artifact_missing-or-feature-disabled".to_string()); }
             Some(format!("EXPLAIN: {} index={} metric={} ef_search={} preselect_W=- notes={}", path, vf.name, metric, ef_search, if notes.is_empty(){"-".into()} else { notes.join(";") }))
         }
         "nearest_neighbors" => {
@@ -260,7 +242,7 @@ pub fn explain_vector_expr(store: &crate::storage::SharedStore, raw: &str) -> Op
             // Try to find index
             let mut found: Option<crate::server::exec::exec_vector_index::VIndexFile> = None;
             let root = store.0.lock().root_path().clone();
-            if let Ok(dbs) = std::fs::read_dir(&root) {
+            if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
                 'outer: for db_ent in dbs.flatten() {
                     let dbp = db_ent.path(); if !dbp.is_dir() { continue; }
                     if let Ok(schemas) = std::fs::read_dir(&dbp) {
@@ -286,17 +268,13 @@ pub fn explain_vector_expr(store: &crate::storage::SharedStore, raw: &str) -> Op
             if let Some(vf) = found {
                 let metric = vf.metric.clone().unwrap_or_else(|| "l2".to_string());
                 let ef_search = vf.params.as_ref().and_then(|p| p.get("ef_search")).and_then(|x| x.as_i64()).unwrap_or(0);
-                #[cfg(feature = "ann_hnsw")]
-                let ann_available = {
-                    let mut p = store.0.lock().root_path().clone();
-                    let local = vf.qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-                    p.push(local); p.set_extension("hnsw"); p.exists()
+                let path = match crate::server::exec::exec_vector_runtime::ann_engine(store, &vf) {
+                    "ivfpq" => "ANN(IVFPQ)",
+                    "hnsw" => "ANN(HNSW)",
+                    _ => "EXACT(flat)",
                 };
-                #[cfg(not(feature = "ann_hnsw"))]
-                let ann_available = false;
-                let path = if ann_available { "ANN(HNSW)" } else { "EXACT(flat)" };
                 let mut notes = Vec::new();
-                if !ann_available { notes.push("hnsw_artifact_missing-or-feature-disabled".to_string()); }
+                if path == "EXACT(flat)" { notes.push("ann_artifact_missing-or-feature-disabled".to_string()); }
                 Some(format!("EXPLAIN: {} index={} metric={} ef_search={} preselect_W=- notes={}", path, vf.name, metric, ef_search, if notes.is_empty(){"-".into()} else { notes.join(";") }))
             } else {
                 Some("EXPLAIN: EXACT(flat) — reason: no matching index (exact table scan)".to_string())
@@ -17,13 +17,18 @@ pub struct ViewFile {
     pub name: String,
     pub columns: Vec<(String, String)>, // (name, dtype key: string|int64|float64|bool)
     pub definition_sql: String,
+    // COMMENT ON VIEW text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
 }
 
 fn dtype_key_of(dt: &polars::prelude::DataType) -> String {
     // Map to our simple keys using Debug representation to avoid tight coupling to specific enum variants
+    if let Some((p, sc)) = crate::storage::decimal::precision_scale(dt) { return crate::storage::decimal::type_key(p, sc); }
     let s = format!("{:?}", dt).to_lowercase();
+    if s.starts_with("datetime") { return "timestamp".into(); }
     if s.contains("int") || s.contains("date") || s.contains("time") { return "int64".into(); }
-    if s.contains("float") || s.contains("double") || s.contains("decimal") || s.contains("numeric") { return "float64".into(); }
+    if s.contains("float") || s.contains("double") { return "float64".into(); }
     if s.contains("bool") { return "bool".into(); }
     "string".into()
 }
@@ -36,8 +41,7 @@ fn qualify_view_name(name: &str) -> String {
 
 fn view_path_for(store: &SharedStore, qualified: &str) -> std::path::PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str());
-    p.push(local);
+    p = crate::ident::to_local_path(&p, &qualified);
     p.set_extension("view");
     p
 }
@@ -57,6 +61,23 @@ fn write_view_file(store: &SharedStore, qualified: &str, vf: &ViewFile) -> Resul
     Ok(())
 }
 
+/// Set (Some) or remove (None) the comment of a view; edits the file in place so
+/// other keys (the persisted OID) are kept. Errors when the view does not exist.
+pub fn set_view_comment(store: &SharedStore, name: &str, comment: Option<&str>) -> Result<()> {
+    let qualified = qualify_view_name(name);
+    let path = view_path_for(store, &qualified);
+    if !path.exists() {
+        return Err(AppError::NotFound { code: "not_found".into(), message: format!("View not found: {}", qualified) }.into());
+    }
+    let mut obj: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    match comment {
+        Some(c) => { obj.insert("comment".into(), serde_json::Value::String(c.to_string())); }
+        None => { obj.remove("comment"); }
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&obj)?)?;
+    Ok(())
+}
+
 fn delete_view_file(store: &SharedStore, qualified: &str) -> Result<()> {
     let path = view_path_for(store, qualified);
     if path.exists() { std::fs::remove_file(&path).ok(); }
@@ -83,7 +104,8 @@ pub fn execute_views(store: &SharedStore, cmd: query::Command) -> Result<serde_j
     match cmd {
         query::Command::CreateView { name, or_alter, if_not_exists, definition_sql } => {
             let qualified = qualify_view_name(&name);
-            let exists = read_view_file(store, &qualified)?.is_some();
+            let existing = read_view_file(store, &qualified)?;
+            let exists = existing.is_some();
             if exists {
                 if if_not_exists { return Ok(serde_json::json!({"status":"ok"})); }
                 if !or_alter { return Err(AppError::Conflict { code: "name_conflict".into(), message: format!("View already exists: {}", qualified) }.into()); }
@@ -91,11 +113,8 @@ pub fn execute_views(store: &SharedStore, cmd: query::Command) -> Result<serde_j
             // Enforce uniqueness across objects: a view name must not clash with an existing table folder
             {
                 let root = store.0.lock().root_path().clone();
-                let table_dir = root.join(qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
-                let time_dir = root.join(format!("{}{}time",
-                    qualified.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()),
-                    std::path::MAIN_SEPARATOR
-                ));
+                let table_dir = crate::ident::to_local_path(&root, &qualified);
+                let time_dir = crate::ident::to_local_path(&root, &format!("{}/time", qualified));
                 if table_dir.is_dir() {
                     return Err(AppError::Conflict { code: "name_conflict".into(), message: format!("A TABLE exists with name '{}'. View names must be unique across tables.", qualified) }.into());
                 }
@@ -106,7 +125,9 @@ pub fn execute_views(store: &SharedStore, cmd: query::Command) -> Result<serde_j
             }
             // Infer columns by executing the definition
             let columns = infer_columns_from_sql(store, &definition_sql)?;
-            let vf = ViewFile { name: qualified.clone(), columns, definition_sql };
+            // CREATE OR ALTER keeps the view's comment
+            let comment = existing.and_then(|v| v.comment);
+            let vf = ViewFile { name: qualified.clone(), columns, definition_sql, comment };
             write_view_file(store, &qualified, &vf)?;
             info!(target: "clarium::ddl", "CREATE VIEW saved '{}.view'", qualified);
             Ok(serde_json::json!({"status":"ok"}))
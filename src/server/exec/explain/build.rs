@@ -0,0 +1,165 @@
+//! Build an EXPLAIN plan for a statement and, with ANALYZE, execute it to attach actual rows
+//! and the time spent in each Lua function it called. Script time is the difference in
+//! `script_stats` counters over the run, so calls from concurrent statements are included.
+//! It also lists the chunks the scans read and those pruned, with the reason.
+//!
+//! Row estimates start from the sum of the chunk footers' row counts. Once the table has
+//! been analyzed, WHERE and GROUP BY estimates come from its column statistics (see
+//! `selectivity`); otherwise a WHERE clause keeps a third and grouping a tenth. HAVING keeps
+//! a third and LIMIT caps the result. Scans involving joins, subqueries or table functions
+//! are left unestimated.
+
+use std::time::Instant;
+
+use anyhow::{bail, Result};
+
+use crate::server::exec::exec_select::{resolve_base_table, run_select_traced};
+use crate::server::exec::select_stages::hints::{apply_join_order, pruning_enabled};
+use crate::server::query::{self, Command, Query};
+use crate::storage::SharedStore;
+
+use super::options::{ExplainFormat, ExplainOptions};
+use super::plan::*;
+use super::selectivity::{group_count, where_selectivity};
+use super::render_dot::explain_dot;
+use super::render_json::explain_json;
+use super::render_text::explain_text;
+use super::render_yaml::explain_yaml;
+
+/// Rows stored in a table, from its chunk footers.
+fn table_rows(store: &SharedStore, table: &str) -> Option<u64> {
+    let dir = store.0.lock().db_dir(table);
+    if !dir.is_dir() { return None; }
+    let mut total = 0u64;
+    for p in crate::storage::partition::chunk_files(&dir) {
+        total += crate::storage::encryption::parquet_num_rows(&p).ok()? as u64;
+    }
+    Some(total)
+}
+
+fn scaled(est: Option<u64>, num: u64, den: u64) -> Option<u64> { est.map(|e| (e * num / den).max(1)) }
+
+fn scaled_by(est: Option<u64>, fraction: f64) -> Option<u64> { est.map(|e| ((e as f64 * fraction).round() as u64).max(1)) }
+
+/// Plan for the staged SELECT pipeline, in execution order.
+pub fn build_select_plan(store: &SharedStore, stmt: &str, q: &Query) -> ExplainPlan {
+    let mut plan = ExplainPlan::new(stmt);
+    plan.settings = crate::system::planner_toggles_changed().into_iter()
+        .map(|(k, on)| format!("planner.{}={}", k, if on { "on" } else { "off" }))
+        .collect();
+    if crate::system::planner_toggle("enable_hints") {
+        plan.hints = q.hints.describe();
+    } else if !q.hints.is_empty() {
+        plan.hints = q.hints.describe().into_iter().map(|h| format!("{} (hints disabled)", h)).collect();
+    }
+    let reordered = apply_join_order(q);
+    if q.hints.join_order.is_some() && reordered.is_none() && crate::system::planner_toggle("enable_hints") {
+        if let Some(h) = plan.hints.iter_mut().find(|h| h.starts_with("JOIN_ORDER(")) { h.push_str(" (not applicable)"); }
+    }
+    let q = reordered.as_ref().unwrap_or(q);
+    let Some(base) = q.base_table.as_ref() else {
+        return plan
+            .with_node(NODE_SCAN, "no FROM (single row)", Some(1))
+            .with_node(NODE_PROJECT, format!("{} item(s)", q.select.len()), Some(1));
+    };
+    let table = resolve_base_table(q);
+    let mut est = match (&table, &q.joins) {
+        (Some(t), None) => table_rows(store, t),
+        _ => None,
+    };
+    let stats = match (&table, &q.joins) {
+        (Some(t), None) => store.0.lock().table_stats(t),
+        _ => None,
+    };
+    if let Some(w) = q.where_clause.as_ref() {
+        est = match stats.as_ref() {
+            Some(st) => scaled_by(est, where_selectivity(st, w)),
+            None => scaled(est, 1, 3),
+        };
+    }
+    let mut scan = table.clone().unwrap_or_else(|| base.effective_name().to_string());
+    if let Some(joins) = q.joins.as_ref().filter(|j| !j.is_empty()) {
+        let names: Vec<&str> = joins.iter().map(|j| j.right.effective_name()).collect();
+        scan.push_str(&format!(" join {}", names.join(", ")));
+    }
+    if q.where_clause.is_some() {
+        scan.push_str(" with filter");
+        let (bloom, partition) = pruning_enabled(q);
+        if !bloom && !partition { scan.push_str(" (no pruning)"); }
+    }
+    plan = plan.with_node(NODE_SCAN, scan, est);
+
+    let grouping = if let Some(n) = q.by_auto_points {
+        Some(format!("BY AUTO({})", n))
+    } else if let Some(ms) = q.by_window_ms {
+        Some(format!("BY {}ms", ms))
+    } else { q.group_by_cols.as_ref().map(|cols| format!("GROUP BY {}", cols.join(", "))) };
+    match grouping {
+        Some(g) => {
+            let groups = match (stats.as_ref(), q.group_by_cols.as_ref()) {
+                (Some(st), Some(cols)) if q.by_window_ms.is_none() => group_count(st, cols),
+                _ => None,
+            };
+            est = match groups {
+                Some(n) => est.map(|e| e.min(n.round() as u64).max(1)),
+                None => scaled(est, 1, 10),
+            };
+            plan = plan.with_node(NODE_GROUP, g, est);
+        }
+        None => plan = plan.with_node(NODE_GROUP, "none", est),
+    }
+    if let Some(ms) = q.rolling_window_ms {
+        plan = plan.with_node(NODE_ROLLING, format!("ROLLING {}ms", ms), est);
+    } else if let Some(n) = q.rolling_rows {
+        plan = plan.with_node(NODE_ROLLING, format!("ROLLING {} preceding rows", n), est);
+    }
+    plan = plan.with_node(NODE_PROJECT, format!("{} item(s)", q.select.len()), est);
+
+    let mut ol: Vec<String> = Vec::new();
+    if let Some(ob) = q.order_by.as_ref().filter(|v| !v.is_empty()) {
+        let keys: Vec<String> = ob.iter().map(|(c, asc)| format!("{} {}", c, if *asc { "ASC" } else { "DESC" })).collect();
+        ol.push(format!("ORDER BY {}", keys.join(", ")));
+    }
+    if let Some(n) = q.limit {
+        ol.push(format!("LIMIT {}", n));
+        if n >= 0 { est = est.map(|e| e.min(n as u64)); }
+    }
+    let ol = if ol.is_empty() { "none".to_string() } else { ol.join(" ") };
+    plan = plan.with_node(NODE_ORDER_LIMIT, ol, est);
+    if q.having_clause.is_some() {
+        plan = plan.with_node(NODE_HAVING, "filter", scaled(est, 1, 3));
+    }
+    plan
+}
+
+/// EXPLAIN for statements with a staged plan (SELECT). `None` when the statement has none.
+pub fn explain_statement(store: &SharedStore, opts: &ExplainOptions, stmt: &str) -> Result<Option<serde_json::Value>> {
+    let Command::Select(q) = query::parse(stmt)? else {
+        if opts.analyze { bail!("EXPLAIN ANALYZE is only supported for SELECT"); }
+        return Ok(None);
+    };
+    let mut plan = build_select_plan(store, stmt, &q);
+    if opts.analyze {
+        let before = crate::script_stats::snapshot();
+        crate::server::exec::exec_vector_runtime::collect_search_metrics();
+        crate::storage::pruning::collect_chunk_pruning();
+        let started = Instant::now();
+        let run = run_select_traced(store, &q);
+        plan.vector_searches = crate::server::exec::exec_vector_runtime::take_search_metrics();
+        plan.chunks = crate::storage::pruning::take_chunk_pruning();
+        let (_df, trace) = run?;
+        for t in trace { plan.set_actual(t.stage, t.rows, t.elapsed); }
+        plan.analyzed = true;
+        plan.total_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+        plan.scripts = crate::script_stats::since(&before).into_iter()
+            .map(|(name, s)| ExplainScript { name, calls: s.calls, errors: s.errors, ms: s.total_ms() })
+            .collect();
+    }
+    let out = match opts.format {
+        ExplainFormat::Text => serde_json::Value::String(explain_text(&plan)),
+        ExplainFormat::Json => explain_json(&plan),
+        ExplainFormat::Yaml => serde_json::Value::String(explain_yaml(&plan)),
+        ExplainFormat::Dot => serde_json::Value::String(explain_dot(&plan)),
+    };
+    Ok(Some(serde_json::json!({"explain": out})))
+}
@@ -1,11 +1,18 @@
-//! EXPLAIN data model and renderers (skeleton)
+//! EXPLAIN data model, plan builder and renderers (text, JSON, YAML, Graphviz DOT)
 
 pub mod plan;
 pub mod options;
+pub mod build;
+pub mod selectivity;
 pub mod render_text;
 pub mod render_json;
+pub mod render_yaml;
+pub mod render_dot;
 
 pub use plan::*;
 pub use options::*;
+pub use build::{build_select_plan, explain_statement};
 pub use render_text::explain_text;
 pub use render_json::explain_json;
+pub use render_yaml::explain_yaml;
+pub use render_dot::explain_dot;
@@ -1,12 +1,89 @@
+use anyhow::{bail, Result};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ExplainFormat { Text, Json }
+pub enum ExplainFormat { Text, Json, Yaml, Dot }
+
+impl ExplainFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "TEXT" => Some(Self::Text),
+            "JSON" => Some(Self::Json),
+            "YAML" => Some(Self::Yaml),
+            "DOT" | "GRAPHVIZ" => Some(Self::Dot),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ExplainOptions {
     pub format: ExplainFormat,
     pub verbose: bool,
+    /// Execute the statement and report actual rows and timings per node.
+    pub analyze: bool,
 }
 
 impl Default for ExplainOptions {
-    fn default() -> Self { Self { format: ExplainFormat::Text, verbose: false } }
+    fn default() -> Self { Self { format: ExplainFormat::Text, verbose: false, analyze: false } }
+}
+
+const OPTION_KEYS: &[&str] = &["ANALYZE", "ANALYSE", "VERBOSE", "FORMAT", "COSTS", "BUFFERS", "TIMING", "SUMMARY", "SETTINGS", "WAL"];
+
+/// Split the text following `EXPLAIN` into options and the explained statement.
+///
+/// Accepts the PostgreSQL forms `EXPLAIN (ANALYZE, VERBOSE, FORMAT YAML) stmt` and
+/// `EXPLAIN ANALYZE VERBOSE stmt`, plus a bare `FORMAT <fmt>` prefix
+/// (`EXPLAIN ANALYZE FORMAT DOT stmt`).
+pub fn parse_explain_options(sql: &str) -> Result<(ExplainOptions, String)> {
+    let mut opts = ExplainOptions::default();
+    let mut rest = sql.trim();
+    // A parenthesised option list, as opposed to a parenthesised statement
+    let first_word = rest.strip_prefix('(').unwrap_or("").trim_start()
+        .split(|c: char| !c.is_ascii_alphabetic()).next().unwrap_or("").to_ascii_uppercase();
+    if OPTION_KEYS.contains(&first_word.as_str()) {
+        let end = rest.find(')').ok_or_else(|| anyhow::anyhow!("EXPLAIN options: missing )"))?;
+        for item in rest[1..end].split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let mut toks = item.split_whitespace();
+            let key = toks.next().unwrap_or("").to_ascii_uppercase();
+            let val = toks.next();
+            if key == "FORMAT" {
+                let v = val.ok_or_else(|| anyhow::anyhow!("EXPLAIN FORMAT expects TEXT, JSON, YAML or DOT"))?;
+                opts.format = ExplainFormat::parse(v).ok_or_else(|| anyhow::anyhow!("Unsupported EXPLAIN format: {}", v))?;
+                continue;
+            }
+            let on = match val.map(|v| v.to_ascii_uppercase()) {
+                None => true,
+                Some(v) if v == "TRUE" || v == "ON" || v == "1" => true,
+                Some(v) if v == "FALSE" || v == "OFF" || v == "0" => false,
+                Some(v) => bail!("EXPLAIN option {} expects a boolean, got {}", key, v),
+            };
+            match key.as_str() {
+                "ANALYZE" | "ANALYSE" => opts.analyze = on,
+                "VERBOSE" => opts.verbose = on,
+                // Accepted for compatibility; they do not change the output
+                k if OPTION_KEYS.contains(&k) => {}
+                other => bail!("Unknown EXPLAIN option: {}", other),
+            }
+        }
+        rest = rest[end + 1..].trim_start();
+    }
+    loop {
+        let up = rest.to_ascii_uppercase();
+        if up.starts_with("ANALYZE ") || up.starts_with("ANALYSE ") {
+            opts.analyze = true;
+            rest = rest[8..].trim_start();
+        } else if up.starts_with("VERBOSE ") {
+            opts.verbose = true;
+            rest = rest[8..].trim_start();
+        } else if up.starts_with("FORMAT ") {
+            let after = rest[7..].trim_start();
+            let word = after.split_whitespace().next().unwrap_or("");
+            opts.format = ExplainFormat::parse(word).ok_or_else(|| anyhow::anyhow!("Unsupported EXPLAIN format: {}", word))?;
+            rest = after[word.len()..].trim_start();
+        } else {
+            break;
+        }
+    }
+    if rest.is_empty() { bail!("EXPLAIN requires a statement"); }
+    Ok((opts, rest.to_string()))
 }
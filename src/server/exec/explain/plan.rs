@@ -1,21 +1,92 @@
+use std::time::Duration;
+
+use crate::server::exec::exec_vector_runtime::VectorSearchMetrics;
+use crate::storage::pruning::ChunkPruning;
+
+/// Node names shared by the plan builder and the traced SELECT pipeline, so
+/// EXPLAIN ANALYZE can attach actual rows to the matching node.
+pub const NODE_SCAN: &str = "Scan";
+pub const NODE_GROUP: &str = "GroupBy";
+pub const NODE_ROLLING: &str = "Rolling";
+pub const NODE_PROJECT: &str = "Project";
+pub const NODE_ORDER_LIMIT: &str = "OrderLimit";
+pub const NODE_HAVING: &str = "Having";
+
 #[derive(Debug, Clone)]
 pub struct ExplainPlan {
     pub stmt: String,
     pub stages: Vec<ExplainStage>,
+    /// Set when the plan was executed (EXPLAIN ANALYZE).
+    pub analyzed: bool,
+    pub total_ms: Option<f64>,
+    /// Optimizer hints in effect, e.g. `NO_PRUNE`, `JOIN_ORDER(b, a)`.
+    pub hints: Vec<String>,
+    /// Planner toggles changed from their defaults, e.g. `planner.enable_bloom_pruning=off`.
+    pub settings: Vec<String>,
+    /// Lua functions called while the statement ran, most time first (EXPLAIN ANALYZE).
+    pub scripts: Vec<ExplainScript>,
+    /// Filtered vector index searches the statement ran, with their recall (EXPLAIN ANALYZE).
+    pub vector_searches: Vec<VectorSearchMetrics>,
+    /// Chunks the scans considered, with the reason each skipped one was pruned (EXPLAIN ANALYZE).
+    pub chunks: Vec<ChunkPruning>,
 }
 
+/// Time spent in one Lua function during an analyzed statement.
+#[derive(Debug, Clone)]
+pub struct ExplainScript {
+    pub name: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub ms: f64,
+}
+
+impl ExplainScript {
+    /// Share of the statement's execution time, in percent.
+    pub fn percent_of(&self, total_ms: Option<f64>) -> Option<f64> {
+        total_ms.filter(|t| *t > 0.0).map(|t| (self.ms / t * 100.0).min(100.0))
+    }
+}
+
+/// One plan node. Stages run in order, each consuming the output of the previous one.
 #[derive(Debug, Clone)]
 pub struct ExplainStage {
+    /// 1-based node id, stable within one plan.
+    pub id: usize,
     pub name: String,
     pub details: String,
+    pub est_rows: Option<u64>,
+    pub actual_rows: Option<u64>,
+    pub actual_ms: Option<f64>,
+}
+
+impl ExplainStage {
+    /// Id of the node feeding this one, if any.
+    pub fn input(&self) -> Option<usize> { if self.id > 1 { Some(self.id - 1) } else { None } }
 }
 
 impl ExplainPlan {
+    /// Chunks read and pruned, from `chunks`.
+    pub fn chunk_counts(&self) -> (usize, usize) {
+        let pruned = self.chunks.iter().filter(|c| c.reason.is_some()).count();
+        (self.chunks.len() - pruned, pruned)
+    }
     pub fn new(stmt: impl Into<String>) -> Self {
-        Self { stmt: stmt.into(), stages: Vec::new() }
+        Self { stmt: stmt.into(), stages: Vec::new(), analyzed: false, total_ms: None, hints: Vec::new(), settings: Vec::new(), scripts: Vec::new(), vector_searches: Vec::new(), chunks: Vec::new() }
+    }
+    pub fn with_stage(self, name: impl Into<String>, details: impl Into<String>) -> Self {
+        self.with_node(name, details, None)
     }
-    pub fn with_stage(mut self, name: impl Into<String>, details: impl Into<String>) -> Self {
-        self.stages.push(ExplainStage{ name: name.into(), details: details.into()});
+    pub fn with_node(mut self, name: impl Into<String>, details: impl Into<String>, est_rows: Option<u64>) -> Self {
+        let id = self.stages.len() + 1;
+        self.stages.push(ExplainStage { id, name: name.into(), details: details.into(), est_rows, actual_rows: None, actual_ms: None });
         self
     }
+    /// Record measured output of the node named `name` (no-op when the plan has no such node).
+    pub fn set_actual(&mut self, name: &str, rows: usize, elapsed: Duration) {
+        self.analyzed = true;
+        if let Some(st) = self.stages.iter_mut().find(|s| s.name == name) {
+            st.actual_rows = Some(rows as u64);
+            st.actual_ms = Some(elapsed.as_secs_f64() * 1000.0);
+        }
+    }
 }
@@ -0,0 +1,41 @@
+use super::plan::ExplainPlan;
+
+/// Escape text for a double-quoted DOT string.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Graphviz digraph with one box per node; edges follow the data flow.
+pub fn explain_dot(plan: &ExplainPlan) -> String {
+    let mut out = String::new();
+    out.push_str("digraph plan {\n");
+    out.push_str("  rankdir=BT;\n");
+    out.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+    let mut title = plan.stmt.clone();
+    if !plan.hints.is_empty() { title.push_str(&format!("\nhints: {}", plan.hints.join(", "))); }
+    if !plan.settings.is_empty() { title.push_str(&format!("\nsettings: {}", plan.settings.join(", "))); }
+    for sc in &plan.scripts { title.push_str(&format!("\nscript {}: {} calls, {:.3}ms", sc.name, sc.calls, sc.ms)); }
+    for vs in &plan.vector_searches { title.push_str(&format!("\nvector search {}: {} recall={:.3}", vs.index, vs.strategy, vs.recall)); }
+    if !plan.chunks.is_empty() {
+        let (read, pruned) = plan.chunk_counts();
+        title.push_str(&format!("\nchunks: {} read, {} pruned", read, pruned));
+    }
+    out.push_str(&format!("  label=\"{}\";\n", dot_escape(&title)));
+    for st in &plan.stages {
+        let mut label = format!("#{} {}\\n{}", st.id, dot_escape(&st.name), dot_escape(&st.details));
+        let est = st.est_rows.map(|e| e.to_string()).unwrap_or_else(|| "?".into());
+        if plan.analyzed {
+            let act = st.actual_rows.map(|a| a.to_string()).unwrap_or_else(|| "-".into());
+            let ms = st.actual_ms.map(|m| format!(" {:.3}ms", m)).unwrap_or_default();
+            label.push_str(&format!("\\nrows est={} actual={}{}", est, act, ms));
+        } else {
+            label.push_str(&format!("\\nrows est={}", est));
+        }
+        out.push_str(&format!("  n{} [label=\"{}\"];\n", st.id, label));
+    }
+    for st in &plan.stages {
+        if let Some(input) = st.input() { out.push_str(&format!("  n{} -> n{};\n", input, st.id)); }
+    }
+    out.push_str("}\n");
+    out
+}
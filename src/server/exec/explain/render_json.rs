@@ -2,11 +2,55 @@ use super::plan::ExplainPlan;
 
 pub fn explain_json(plan: &ExplainPlan) -> serde_json::Value {
     let stages: Vec<serde_json::Value> = plan.stages.iter().map(|s| {
-        serde_json::json!({"name": s.name, "details": s.details})
+        serde_json::json!({
+            "id": s.id,
+            "name": s.name,
+            "details": s.details,
+            "input": s.input(),
+            "estimated_rows": s.est_rows,
+            "actual_rows": s.actual_rows,
+            "actual_ms": s.actual_ms,
+        })
+    }).collect();
+    let scripts: Vec<serde_json::Value> = plan.scripts.iter().map(|sc| {
+        serde_json::json!({
+            "name": sc.name,
+            "calls": sc.calls,
+            "errors": sc.errors,
+            "ms": sc.ms,
+            "percent": sc.percent_of(plan.total_ms),
+        })
+    }).collect();
+    let vector_searches: Vec<serde_json::Value> = plan.vector_searches.iter().map(|vs| {
+        serde_json::json!({
+            "index": vs.index,
+            "strategy": vs.strategy,
+            "k": vs.k,
+            "allowed_rows": vs.allowed_rows,
+            "indexed_rows": vs.indexed_rows,
+            "rounds": vs.rounds,
+            "returned": vs.returned,
+            "recall": vs.recall,
+        })
+    }).collect();
+    let chunks: Vec<serde_json::Value> = plan.chunks.iter().map(|c| {
+        serde_json::json!({
+            "table": c.table,
+            "chunk": c.chunk,
+            "pruned": c.reason.is_some(),
+            "reason": c.reason,
+        })
     }).collect();
     serde_json::json!({
         "format": "json",
         "stmt": plan.stmt,
+        "analyze": plan.analyzed,
+        "hints": plan.hints,
+        "settings": plan.settings,
         "stages": stages,
+        "scripts": scripts,
+        "vector_searches": vector_searches,
+        "chunks": chunks,
+        "execution_ms": plan.total_ms,
     })
 }
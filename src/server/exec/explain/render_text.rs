@@ -2,10 +2,34 @@ use super::plan::ExplainPlan;
 
 pub fn explain_text(plan: &ExplainPlan) -> String {
     let mut out = String::new();
-    out.push_str("EXPLAIN (text)\n");
+    out.push_str(if plan.analyzed { "EXPLAIN ANALYZE (text)\n" } else { "EXPLAIN (text)\n" });
     out.push_str(&format!("stmt: {}\n", plan.stmt));
+    if !plan.hints.is_empty() { out.push_str(&format!("hints: {}\n", plan.hints.join(", "))); }
+    if !plan.settings.is_empty() { out.push_str(&format!("settings: {}\n", plan.settings.join(", "))); }
     for st in &plan.stages {
-        out.push_str(&format!("- {}: {}\n", st.name, st.details));
+        out.push_str(&format!("- [{}] {}: {}", st.id, st.name, st.details));
+        if let Some(e) = st.est_rows { out.push_str(&format!(" (rows={})", e)); }
+        if let (Some(a), Some(ms)) = (st.actual_rows, st.actual_ms) { out.push_str(&format!(" (actual rows={} time={:.3}ms)", a, ms)); }
+        out.push('\n');
     }
+    for sc in &plan.scripts {
+        out.push_str(&format!("- script {}: calls={} errors={} time={:.3}ms", sc.name, sc.calls, sc.errors, sc.ms));
+        if let Some(pct) = sc.percent_of(plan.total_ms) { out.push_str(&format!(" ({:.1}%)", pct)); }
+        out.push('\n');
+    }
+    for vs in &plan.vector_searches {
+        out.push_str(&format!(
+            "- vector search {}: strategy={} k={} allowed={}/{} rounds={} returned={} recall={:.3}\n",
+            vs.index, vs.strategy, vs.k, vs.allowed_rows, vs.indexed_rows, vs.rounds, vs.returned, vs.recall
+        ));
+    }
+    if !plan.chunks.is_empty() {
+        let (read, pruned) = plan.chunk_counts();
+        out.push_str(&format!("- chunks: read={} pruned={}\n", read, pruned));
+        for c in plan.chunks.iter() {
+            if let Some(reason) = &c.reason { out.push_str(&format!("  - pruned {} {}: {}\n", c.table, c.chunk, reason)); }
+        }
+    }
+    if let Some(ms) = plan.total_ms { out.push_str(&format!("execution time: {:.3}ms\n", ms)); }
     out
 }
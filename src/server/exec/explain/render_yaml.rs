@@ -0,0 +1,65 @@
+use super::plan::ExplainPlan;
+
+/// YAML scalars: strings are emitted double-quoted (JSON escaping is valid YAML).
+fn scalar_str(s: &str) -> String { serde_json::to_string(s).unwrap_or_else(|_| "\"\"".into()) }
+
+fn scalar_opt<T: std::fmt::Display>(v: Option<T>) -> String {
+    v.map(|x| x.to_string()).unwrap_or_else(|| "null".into())
+}
+
+pub fn explain_yaml(plan: &ExplainPlan) -> String {
+    let mut out = String::new();
+    out.push_str("plan:\n");
+    out.push_str(&format!("  stmt: {}\n", scalar_str(&plan.stmt)));
+    out.push_str(&format!("  analyze: {}\n", plan.analyzed));
+    for (key, items) in [("hints", &plan.hints), ("settings", &plan.settings)] {
+        if items.is_empty() { continue; }
+        out.push_str(&format!("  {}:\n", key));
+        for h in items { out.push_str(&format!("    - {}\n", scalar_str(h))); }
+    }
+    out.push_str("  nodes:\n");
+    for st in &plan.stages {
+        out.push_str(&format!("    - id: {}\n", st.id));
+        out.push_str(&format!("      name: {}\n", scalar_str(&st.name)));
+        out.push_str(&format!("      details: {}\n", scalar_str(&st.details)));
+        out.push_str(&format!("      input: {}\n", scalar_opt(st.input())));
+        out.push_str(&format!("      estimated_rows: {}\n", scalar_opt(st.est_rows)));
+        if plan.analyzed {
+            out.push_str(&format!("      actual_rows: {}\n", scalar_opt(st.actual_rows)));
+            out.push_str(&format!("      actual_ms: {}\n", scalar_opt(st.actual_ms.map(|ms| format!("{:.3}", ms)))));
+        }
+    }
+    if !plan.scripts.is_empty() {
+        out.push_str("  scripts:\n");
+        for sc in &plan.scripts {
+            out.push_str(&format!("    - name: {}\n", scalar_str(&sc.name)));
+            out.push_str(&format!("      calls: {}\n", sc.calls));
+            out.push_str(&format!("      errors: {}\n", sc.errors));
+            out.push_str(&format!("      ms: {:.3}\n", sc.ms));
+        }
+    }
+    if !plan.vector_searches.is_empty() {
+        out.push_str("  vector_searches:\n");
+        for vs in &plan.vector_searches {
+            out.push_str(&format!("    - index: {}\n", scalar_str(&vs.index)));
+            out.push_str(&format!("      strategy: {}\n", vs.strategy));
+            out.push_str(&format!("      k: {}\n", vs.k));
+            out.push_str(&format!("      allowed_rows: {}\n", vs.allowed_rows));
+            out.push_str(&format!("      indexed_rows: {}\n", vs.indexed_rows));
+            out.push_str(&format!("      rounds: {}\n", vs.rounds));
+            out.push_str(&format!("      returned: {}\n", vs.returned));
+            out.push_str(&format!("      recall: {:.3}\n", vs.recall));
+        }
+    }
+    if !plan.chunks.is_empty() {
+        out.push_str("  chunks:\n");
+        for c in &plan.chunks {
+            out.push_str(&format!("    - table: {}\n", scalar_str(&c.table)));
+            out.push_str(&format!("      chunk: {}\n", scalar_str(&c.chunk)));
+            out.push_str(&format!("      pruned: {}\n", c.reason.is_some()));
+            if let Some(reason) = &c.reason { out.push_str(&format!("      reason: {}\n", scalar_str(reason))); }
+        }
+    }
+    if let Some(ms) = plan.total_ms { out.push_str(&format!("  execution_ms: {:.3}\n", ms)); }
+    out
+}
@@ -0,0 +1,96 @@
+//! Row estimates from the statistics `ANALYZE` keeps (see `storage::analyze`).
+//!
+//! A comparison between a column and a literal uses the column's null fraction, distinct
+//! count and histogram; AND multiplies fractions and OR combines them as independent
+//! events. Anything else (expressions, subqueries, LIKE) keeps the default third.
+
+use serde_json::Value;
+
+use crate::server::query::{ArithExpr, ArithTerm, CompOp, WhereExpr};
+use crate::storage::analyze::{ColumnStats, TableStats};
+
+/// Fraction kept by a predicate the statistics cannot describe.
+pub const DEFAULT_SELECTIVITY: f64 = 1.0 / 3.0;
+
+enum Operand<'a> {
+    Column(&'a ColumnStats),
+    Literal(Value),
+    Other,
+}
+
+fn operand<'a>(stats: &'a TableStats, e: &ArithExpr) -> Operand<'a> {
+    match e {
+        ArithExpr::Term(ArithTerm::Col { name, previous: false }) => {
+            // Qualified references (t.col) name the base table's column
+            let col = name.rsplit('.').next().unwrap_or(name);
+            stats.columns.get(col).map(Operand::Column).unwrap_or(Operand::Other)
+        }
+        ArithExpr::Term(ArithTerm::Number(n)) => serde_json::Number::from_f64(*n).map(|n| Operand::Literal(Value::Number(n))).unwrap_or(Operand::Other),
+        ArithExpr::Term(ArithTerm::Str(s)) => Operand::Literal(Value::String(s.clone())),
+        _ => Operand::Other,
+    }
+}
+
+/// A text literal compared with a numeric histogram is a timestamp when it parses as one.
+fn comparable(cs: &ColumnStats, v: Value) -> Value {
+    match (&v, cs.histogram.first()) {
+        (Value::String(s), Some(Value::Number(_))) => crate::storage::native::parse_timestamp_ms(s).map(Value::from).unwrap_or(v),
+        _ => v,
+    }
+}
+
+fn flip(op: &CompOp) -> CompOp {
+    match op {
+        CompOp::Gt => CompOp::Lt,
+        CompOp::Ge => CompOp::Le,
+        CompOp::Lt => CompOp::Gt,
+        CompOp::Le => CompOp::Ge,
+        other => other.clone(),
+    }
+}
+
+fn comparison(cs: &ColumnStats, op: &CompOp, v: Value) -> f64 {
+    let v = comparable(cs, v);
+    let non_null = 1.0 - cs.null_frac;
+    match op {
+        CompOp::Eq => cs.eq_fraction(),
+        CompOp::Ne => (non_null - cs.eq_fraction()).max(0.0),
+        CompOp::Lt | CompOp::Le => cs.below_fraction(&v).unwrap_or(DEFAULT_SELECTIVITY),
+        CompOp::Gt | CompOp::Ge => cs.below_fraction(&v).map(|b| non_null - b).unwrap_or(DEFAULT_SELECTIVITY),
+        CompOp::Like | CompOp::NotLike => DEFAULT_SELECTIVITY,
+    }
+}
+
+/// Estimated fraction of rows `w` keeps, between 0 and 1.
+pub fn where_selectivity(stats: &TableStats, w: &WhereExpr) -> f64 {
+    let sel = match w {
+        WhereExpr::Comp { left, op, right } => match (operand(stats, left), operand(stats, right)) {
+            (Operand::Column(cs), Operand::Literal(v)) => comparison(cs, op, v),
+            (Operand::Literal(v), Operand::Column(cs)) => comparison(cs, &flip(op), v),
+            _ => DEFAULT_SELECTIVITY,
+        },
+        WhereExpr::IsNull { expr, negated } => match operand(stats, expr) {
+            Operand::Column(cs) => if *negated { 1.0 - cs.null_frac } else { cs.null_frac },
+            _ => DEFAULT_SELECTIVITY,
+        },
+        WhereExpr::And(a, b) => where_selectivity(stats, a) * where_selectivity(stats, b),
+        WhereExpr::Or(a, b) => {
+            let (a, b) = (where_selectivity(stats, a), where_selectivity(stats, b));
+            a + b - a * b
+        }
+        _ => DEFAULT_SELECTIVITY,
+    };
+    sel.clamp(0.0, 1.0)
+}
+
+/// Estimated groups for GROUP BY `cols`: the product of their distinct counts, or `None`
+/// when a column has no statistics.
+pub fn group_count(stats: &TableStats, cols: &[String]) -> Option<f64> {
+    cols.iter().try_fold(1.0, |acc, c| {
+        let col = c.rsplit('.').next().unwrap_or(c);
+        let cs = stats.columns.get(col)?;
+        // A nullable column adds the null group
+        let groups = cs.n_distinct + if cs.null_frac > 0.0 { 1.0 } else { 0.0 };
+        Some(acc * groups.max(1.0))
+    })
+}
@@ -0,0 +1,259 @@
+//! Pluggable backends for chunk payloads.
+//!
+//! Metadata, trees, commits and the chunk index always stay in the filestore KV; only chunk
+//! bytes move. `FilestoreConfig.blob_backend` selects where new chunks are written:
+//! - `kv` (default): the bytes are the chunk index entry itself;
+//! - `s3://bucket/prefix`: S3 or an S3-compatible service (`blob_endpoint`), signed with the
+//!   credentials `storage::s3` reads from the environment;
+//! - `gs://bucket/prefix`: Google Cloud Storage through its S3-compatible XML API (HMAC keys);
+//! - `az://container/prefix`: Azure Blob Storage, authorized with the account key or SAS token
+//!   `storage::azure` reads from the environment (`blob_endpoint` targets e.g. Azurite).
+//!
+//! Objects are named `<prefix>/<db>/<filestore>/chunks/<oid[..2]>/<oid>`. A remote chunk's index
+//! entry records the backend it was written to, so reads keep working after the filestore's
+//! backend changes. Reads go through an in-process cache bounded by
+//! `GlobalFilestoreConfig.blob_cache_bytes`.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::azure::{self, AzureLocation};
+use crate::storage::s3::{self, S3Location};
+use crate::storage::{KvValue, SharedStore};
+
+use super::config::EffectiveConfig;
+use super::kv::Keys;
+use super::sse::SealedBackend;
+
+/// GCS endpoint for S3-compatible (XML API) requests.
+const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+/// Index entry of a chunk whose bytes live in an object store.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RemoteChunk {
+    pub len: u64,
+    /// Backend URI the chunk was written to (e.g. `s3://bucket/prefix`).
+    pub backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+/// Where chunk payloads are stored.
+pub trait BlobBackend: Send + Sync {
+    /// `kv` or the backend URI.
+    fn name(&self) -> String;
+    /// Chunk index entry for a payload of `len` bytes written here; `None` when the payload
+    /// itself is the index entry (KV).
+    fn index_entry(&self, len: u64) -> Option<KvValue>;
+    /// Store each `(oid, bytes)` pair.
+    fn put(&self, items: &[(&str, &[u8])]) -> Result<()>;
+    /// Fetch the payloads of `oids`, in order.
+    fn get(&self, oids: &[&str]) -> Result<Vec<Vec<u8>>>;
+}
+
+/// Chunk payloads in the filestore's own KV store.
+pub struct KvBlobBackend {
+    store: SharedStore,
+    database: String,
+    filestore: String,
+}
+
+impl KvBlobBackend {
+    pub fn new(store: &SharedStore, database: &str, filestore: &str) -> Self {
+        Self { store: store.clone(), database: database.to_string(), filestore: filestore.to_string() }
+    }
+}
+
+impl BlobBackend for KvBlobBackend {
+    fn name(&self) -> String { "kv".into() }
+    fn index_entry(&self, _len: u64) -> Option<KvValue> { None }
+    fn put(&self, items: &[(&str, &[u8])]) -> Result<()> {
+        let kv = self.store.kv_store(&self.database, &self.filestore);
+        for (oid, bytes) in items {
+            kv.set_bytes(Keys::chunk_oid(&self.database, &self.filestore, oid), bytes, None, None);
+        }
+        Ok(())
+    }
+    fn get(&self, oids: &[&str]) -> Result<Vec<Vec<u8>>> {
+        let kv = self.store.kv_store(&self.database, &self.filestore);
+        oids.iter().map(|oid| kv.get_bytes(&Keys::chunk_oid(&self.database, &self.filestore, oid))
+            .ok_or_else(|| anyhow!("chunk missing: {}", oid))).collect()
+    }
+}
+
+/// Bucket or container an object store backend writes to.
+enum ObjectLocation {
+    S3(S3Location),
+    Azure(AzureLocation),
+}
+
+/// Chunk payloads of one filestore as objects in S3 (or an S3-compatible store) or Azure Blob
+/// Storage.
+pub struct ObjectStoreBackend {
+    uri: String,
+    endpoint: Option<String>,
+    loc: ObjectLocation,
+    database: String,
+    filestore: String,
+}
+
+impl ObjectStoreBackend {
+    /// Backend for an `s3://`, `gs://` or `az://` `uri`; `endpoint` overrides the service endpoint.
+    pub fn new(uri: &str, endpoint: Option<&str>, database: &str, filestore: &str) -> Result<Self> {
+        let uri = uri.trim().trim_end_matches('/').to_string();
+        let endpoint = endpoint.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+        let loc = if let Some(rest) = uri.strip_prefix("gs://") {
+            ObjectLocation::S3(S3Location { endpoint: Some(endpoint.clone().unwrap_or_else(|| GCS_ENDPOINT.to_string())), ..S3Location::parse(&format!("s3://{}", rest))? })
+        } else if s3::is_s3_uri(&uri) {
+            ObjectLocation::S3(S3Location { endpoint: endpoint.clone(), ..S3Location::parse(&uri)? })
+        } else if azure::is_azure_uri(&uri) {
+            ObjectLocation::Azure(AzureLocation { endpoint: endpoint.clone(), ..AzureLocation::parse(&uri)? })
+        } else {
+            bail!("unsupported blob backend '{}' (expected kv, s3://bucket/prefix, gs://bucket/prefix or az://container/prefix)", uri)
+        };
+        Ok(Self { uri, endpoint, loc, database: database.to_string(), filestore: filestore.to_string() })
+    }
+
+    /// Object key of chunk `oid`.
+    pub fn object_key(&self, oid: &str) -> String {
+        let rel = format!("{}/{}/chunks/{}/{}", self.database, self.filestore, &oid[..oid.len().min(2)], oid);
+        match &self.loc {
+            ObjectLocation::S3(loc) => loc.key(&rel),
+            ObjectLocation::Azure(loc) => loc.key(&rel),
+        }
+    }
+
+    async fn put_object(&self, client: &reqwest::Client, oid: &str, bytes: Vec<u8>) -> Result<()> {
+        match &self.loc {
+            ObjectLocation::S3(loc) => s3::put_object(client, loc, &self.object_key(oid), bytes).await,
+            ObjectLocation::Azure(loc) => azure::put_blob(client, loc, &self.object_key(oid), bytes).await,
+        }
+    }
+
+    async fn get_object(&self, client: &reqwest::Client, oid: &str) -> Result<Vec<u8>> {
+        match &self.loc {
+            ObjectLocation::S3(loc) => s3::get_object(client, loc, &self.object_key(oid)).await,
+            ObjectLocation::Azure(loc) => azure::get_blob(client, loc, &self.object_key(oid)).await,
+        }
+    }
+}
+
+/// Drive an async object-store request from synchronous code, inside or outside a Tokio runtime:
+/// the request runs to completion on a scoped thread with its own runtime and client.
+fn block_on<T, F, Fut>(f: F) -> Result<T>
+where
+    T: Send,
+    F: FnOnce(reqwest::Client) -> Fut + Send,
+    Fut: Future<Output = Result<T>>,
+{
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            let rt = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            rt.block_on(f(reqwest::Client::new()))
+        }).join().unwrap_or_else(|_| Err(anyhow!("object store request panicked")))
+    })
+}
+
+impl BlobBackend for ObjectStoreBackend {
+    fn name(&self) -> String { self.uri.clone() }
+    fn index_entry(&self, len: u64) -> Option<KvValue> {
+        let rc = RemoteChunk { len, backend: self.uri.clone(), endpoint: self.endpoint.clone() };
+        serde_json::to_value(rc).ok().map(KvValue::Json)
+    }
+    fn put(&self, items: &[(&str, &[u8])]) -> Result<()> {
+        if items.is_empty() { return Ok(()); }
+        block_on(|client| async move {
+            for (oid, bytes) in items {
+                self.put_object(&client, oid, bytes.to_vec()).await?;
+            }
+            Ok(())
+        })
+    }
+    fn get(&self, oids: &[&str]) -> Result<Vec<Vec<u8>>> {
+        let mut out: Vec<Option<Vec<u8>>> = oids.iter().map(|oid| cache_get(&self.uri, oid)).collect();
+        let missing: Vec<usize> = (0..oids.len()).filter(|i| out[*i].is_none()).collect();
+        if !missing.is_empty() {
+            let fetched = block_on(|client| async move {
+                let mut got = Vec::with_capacity(missing.len());
+                for i in missing {
+                    got.push((i, self.get_object(&client, oids[i]).await?));
+                }
+                Ok(got)
+            })?;
+            for (i, bytes) in fetched {
+                cache_put(&self.uri, oids[i], &bytes);
+                out[i] = Some(bytes);
+            }
+        }
+        Ok(out.into_iter().map(|b| b.unwrap_or_default()).collect())
+    }
+}
+
+/// Backend new chunks of a filestore are written to; payloads are sealed when the filestore
+/// is encrypted (see sse.rs).
+pub fn backend_for(store: &SharedStore, database: &str, filestore: &str, eff: &EffectiveConfig) -> Result<Box<dyn BlobBackend>> {
+    let inner: Box<dyn BlobBackend> = match eff.blob_backend.as_deref().map(str::trim) {
+        Some(b) if !b.is_empty() && !b.eq_ignore_ascii_case("kv") => Box::new(ObjectStoreBackend::new(b, eff.blob_endpoint.as_deref(), database, filestore)?),
+        _ => Box::new(KvBlobBackend::new(store, database, filestore)),
+    };
+    match &eff.encryption {
+        Some(spec) => Ok(Box::new(SealedBackend::new(inner, store, database, filestore, spec)?)),
+        None => Ok(inner),
+    }
+}
+
+/// Remote chunk described by an index entry, or `None` when the entry holds the bytes.
+pub fn remote_chunk(value: &KvValue) -> Option<RemoteChunk> {
+    match value {
+        KvValue::Json(j) => serde_json::from_value(j.clone()).ok(),
+        _ => None,
+    }
+}
+
+/// Stored payload size of a chunk from its index entry.
+pub fn stored_len(value: &KvValue) -> u64 {
+    match value {
+        KvValue::Bytes(b) => b.len() as u64,
+        other => remote_chunk(other).map(|rc| rc.len).unwrap_or(0),
+    }
+}
+
+/// FIFO-evicted cache of remote chunk payloads keyed by `<backend>#<oid>`.
+#[derive(Default)]
+pub(crate) struct BlobCache {
+    map: HashMap<String, Vec<u8>>,
+    order: VecDeque<String>,
+    bytes: u64,
+}
+
+impl BlobCache {
+    pub(crate) fn get(&self, key: &str) -> Option<Vec<u8>> { self.map.get(key).cloned() }
+
+    pub(crate) fn insert(&mut self, key: String, value: Vec<u8>, capacity: u64) {
+        if value.len() as u64 > capacity || self.map.contains_key(&key) { return; }
+        while self.bytes + value.len() as u64 > capacity {
+            let Some(old) = self.order.pop_front() else { break };
+            if let Some(v) = self.map.remove(&old) { self.bytes -= v.len() as u64; }
+        }
+        self.bytes += value.len() as u64;
+        self.order.push_back(key.clone());
+        self.map.insert(key, value);
+    }
+}
+
+static CACHE: Lazy<Mutex<BlobCache>> = Lazy::new(|| Mutex::new(BlobCache::default()));
+
+fn cache_key(backend: &str, oid: &str) -> String { format!("{}#{}", backend, oid) }
+
+fn cache_get(backend: &str, oid: &str) -> Option<Vec<u8>> { CACHE.lock().get(&cache_key(backend, oid)) }
+
+fn cache_put(backend: &str, oid: &str, bytes: &[u8]) {
+    let capacity = crate::config::current().filestore.blob_cache_bytes;
+    if capacity == 0 { return; }
+    CACHE.lock().insert(cache_key(backend, oid), bytes.to_vec(), capacity);
+}
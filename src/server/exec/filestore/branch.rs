@@ -0,0 +1,376 @@
+//! Filestore branches: CREATE BRANCH, CHECKOUT BRANCH and MERGE BRANCH over the Tree/Commit model.
+//!
+//! A branch is a local ref (`Keys::git_ref(.., "local", ..)`) to a commit. The live files are
+//! the working set of the checked-out branch (`Keys::head`). Branch commits are taken with
+//! `snapshot_tree`, whose entries pin their content in the chunk store, so a branch can be
+//! materialized again after the live files moved on.
+//! - Before switching or merging, the working set is committed to the current branch when it
+//!   differs from the branch head, so no change is lost.
+//! - Materializing a tree goes through ingest/update/delete: ACL checked and indexed like any write.
+//! - MERGE is three-way against the nearest common ancestor. A path changed on one side only is
+//!   taken from that side; a path changed differently on both sides is a conflict and the merge
+//!   is aborted before anything is applied (list them with `show_merge_conflicts_df`).
+
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::storage::{KvValue, SharedStore};
+
+use super::blob;
+use super::chunker::{self, ChunkParams};
+use super::config::EffectiveConfig;
+use super::kv::Keys;
+use super::ops::{commit_tree, current_branch_head, delete_file, get_file_bytes, ingest_from_bytes, list_files_by_prefix, load_commit, load_tree, update_from_bytes};
+use super::security::{AclContext, AclUser};
+use super::types::{CommitAuthor, FileMeta, RefInfo, Tree, TreeEntry};
+
+/// Outcome of CHECKOUT BRANCH.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CheckoutOutcome {
+    pub branch: String,
+    pub previous_branch: String,
+    /// Head of `previous_branch` after saving the working set
+    pub saved_commit_id: Option<String>,
+    pub commit_id: String,
+    pub files_written: u64,
+    pub files_deleted: u64,
+}
+
+/// Outcome of MERGE BRANCH.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MergeOutcome {
+    /// Branch merged into (the checked-out one)
+    pub branch: String,
+    pub source: String,
+    /// "merged" | "fast_forward" | "up_to_date" | "conflict"
+    pub status: String,
+    pub base_commit_id: Option<String>,
+    /// New head of `branch`; unset on conflict
+    pub commit_id: Option<String>,
+    pub ours_tree_id: Option<String>,
+    pub theirs_tree_id: String,
+    /// Paths taken from `source`
+    pub applied: Vec<String>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Head {
+    branch: String,
+}
+
+/// Branch checked out in the live files: the recorded HEAD, else the configured git_branch, else "main".
+pub fn current_branch(store: &SharedStore, database: &str, filestore: &str, eff: &EffectiveConfig) -> String {
+    match store.kv_store(database, filestore).get(&Keys::head(database, filestore)) {
+        Some(KvValue::Json(j)) => serde_json::from_value::<Head>(j).ok().map(|h| h.branch),
+        _ => None,
+    }
+    .or_else(|| eff.git_branch.clone())
+    .unwrap_or_else(|| "main".to_string())
+}
+
+fn set_head(store: &SharedStore, database: &str, filestore: &str, branch: &str) -> Result<()> {
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::head(database, filestore), KvValue::Json(serde_json::to_value(Head { branch: branch.to_string() })?), None, None);
+    Ok(())
+}
+
+fn set_ref(store: &SharedStore, database: &str, filestore: &str, branch: &str, commit_id: &str) -> Result<RefInfo> {
+    let kv = store.kv_store(database, filestore);
+    let info = RefInfo { branch: branch.to_string(), head_commit_id: commit_id.to_string(), updated_at: Utc::now().timestamp() };
+    kv.set(Keys::git_ref(database, filestore, "local", branch), KvValue::Json(serde_json::to_value(&info)?), None, None);
+    Ok(info)
+}
+
+/// Local branches, by name.
+pub fn list_branches(store: &SharedStore, database: &str, filestore: &str) -> Vec<RefInfo> {
+    let kv = store.kv_store(database, filestore);
+    let prefix = Keys::git_ref_prefix(database, filestore, "local");
+    let mut out: Vec<RefInfo> = kv.keys().into_iter()
+        .filter(|k| k.starts_with(&prefix))
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value(j).ok(), _ => None })
+        .collect();
+    out.sort_by(|a: &RefInfo, b: &RefInfo| a.branch.cmp(&b.branch));
+    out
+}
+
+fn validate_branch_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name != name.trim() { bail!("invalid branch name '{}'", name); }
+    if name.contains("::") || name.chars().any(|c| c.is_control() || c.is_whitespace()) { bail!("invalid branch name '{}'", name); }
+    Ok(())
+}
+
+/// Persist a tree of the live files with their content pinned: chunked files keep their chunk
+/// list, the others are chunked into the (deduplicating) chunk store.
+pub fn snapshot_tree(store: &SharedStore, database: &str, filestore: &str, eff: &EffectiveConfig) -> Result<Tree> {
+    let backend = blob::backend_for(store, database, filestore, eff)?;
+    let mut entries: Vec<TreeEntry> = Vec::new();
+    for m in list_files_by_prefix(store, database, filestore, None)?.into_iter().filter(|m| !m.deleted) {
+        let content = match &m.chunking {
+            Some(ch) => ch.clone(),
+            None => {
+                let bytes = get_file_bytes(store, database, filestore, &m)?
+                    .ok_or_else(|| anyhow::anyhow!("file content missing in filestore '{}': {}", filestore, m.logical_path))?;
+                chunker::store_chunked(store, database, filestore, &bytes, &ChunkParams::default(), backend.as_ref())?.0
+            }
+        };
+        entries.push(TreeEntry { path: m.logical_path, file_id: m.id, etag: m.etag, size: m.size, content: Some(content) });
+    }
+    let id = Uuid::new_v4().to_string();
+    let tree = Tree { id: id.clone(), created_at: Utc::now().timestamp(), entries };
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::tree(database, filestore, &Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::nil())), KvValue::Json(serde_json::to_value(&tree)?), None, None);
+    crate::tprintln!("FILESTORE snapshot_tree ok fs={} entries={} tree_id={}", filestore, tree.entries.len(), id);
+    Ok(tree)
+}
+
+fn commit_tree_of(store: &SharedStore, database: &str, filestore: &str, commit_id: &str) -> Result<Tree> {
+    let commit = load_commit(store, database, filestore, commit_id)?.ok_or_else(|| anyhow::anyhow!("commit_not_found: {}", commit_id))?;
+    load_tree(store, database, filestore, &commit.tree_id)?.ok_or_else(|| anyhow::anyhow!("tree_not_found: {}", commit.tree_id))
+}
+
+fn etags_of(tree: &Tree) -> BTreeMap<String, String> {
+    tree.entries.iter().map(|e| (e.path.clone(), e.etag.clone())).collect()
+}
+
+fn live_files(store: &SharedStore, database: &str, filestore: &str) -> Result<BTreeMap<String, FileMeta>> {
+    Ok(list_files_by_prefix(store, database, filestore, None)?.into_iter().filter(|m| !m.deleted).map(|m| (m.logical_path.clone(), m)).collect())
+}
+
+/// Commit the live files to `branch` unless they match its head. Returns the branch head
+/// afterwards; `None` for a branch without commits and no live files.
+pub fn commit_working_set(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    branch: &str,
+    author: &CommitAuthor,
+    message: &str,
+    eff: &EffectiveConfig,
+) -> Result<Option<String>> {
+    let head = current_branch_head(store, database, filestore, branch);
+    let live: BTreeMap<String, String> = live_files(store, database, filestore)?.into_iter().map(|(p, m)| (p, m.etag)).collect();
+    match &head {
+        Some(h) if etags_of(&commit_tree_of(store, database, filestore, h)?) == live => return Ok(head),
+        None if live.is_empty() => return Ok(None),
+        _ => {}
+    }
+    let tree = snapshot_tree(store, database, filestore, eff)?;
+    let commit = commit_tree(store, database, filestore, &tree.id, &[], author, message, &[], branch)?;
+    Ok(Some(commit.id))
+}
+
+/// CREATE BRANCH: point `name` at the head of `from`, or at the working set committed to the
+/// current branch when `from` is not given.
+pub fn create_branch(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    name: &str,
+    from: Option<&str>,
+    author: &CommitAuthor,
+    eff: &EffectiveConfig,
+) -> Result<RefInfo> {
+    validate_branch_name(name)?;
+    if current_branch_head(store, database, filestore, name).is_some() { bail!("branch_exists: {}", name); }
+    let head = match from {
+        Some(src) => current_branch_head(store, database, filestore, src).ok_or_else(|| anyhow::anyhow!("branch_not_found: {}", src))?,
+        None => {
+            let cur = current_branch(store, database, filestore, eff);
+            commit_working_set(store, database, filestore, &cur, author, &format!("Snapshot for branch '{}'", name), eff)?
+                .ok_or_else(|| anyhow::anyhow!("nothing_to_branch: filestore '{}' has no files or commits", filestore))?
+        }
+    };
+    let info = set_ref(store, database, filestore, name, &head)?;
+    crate::tprintln!("FILESTORE create_branch ok fs={} branch={} from={} head={}", filestore, name, from.unwrap_or("<working set>"), head);
+    Ok(info)
+}
+
+/// Content of a tree entry: its pinned chunks, else the live file when it still holds that content.
+fn entry_bytes(store: &SharedStore, database: &str, filestore: &str, e: &TreeEntry, live: &BTreeMap<String, FileMeta>) -> Result<Vec<u8>> {
+    if let Some(ch) = &e.content {
+        if let Some(b) = chunker::read_chunked(store, database, filestore, ch)? { return Ok(b); }
+    }
+    if let Some(m) = live.get(&e.path).filter(|m| m.etag == e.etag) {
+        if let Some(b) = get_file_bytes(store, database, filestore, m)? { return Ok(b); }
+    }
+    bail!("tree_content_unavailable: {} (tree {} has no pinned content for it)", e.path, e.file_id)
+}
+
+/// Apply `(path, target)` changes to the live files: write `Some`, delete `None`. All contents
+/// are read before the first write so a missing one leaves the files untouched.
+/// Returns (written, deleted).
+async fn apply_changes(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    changes: &[(String, Option<TreeEntry>)],
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<(u64, u64)> {
+    let live = live_files(store, database, filestore)?;
+    let mut contents: BTreeMap<&str, Vec<u8>> = BTreeMap::new();
+    for (path, target) in changes {
+        if let Some(e) = target { contents.insert(path.as_str(), entry_bytes(store, database, filestore, e, &live)?); }
+    }
+    let (mut written, mut deleted) = (0u64, 0u64);
+    for (path, target) in changes {
+        match (target, live.get(path)) {
+            (Some(_), Some(cur)) => {
+                update_from_bytes(store, database, filestore, path, &cur.etag, &contents[path.as_str()], cur.content_type.as_deref(), None, user, eff, ctx).await?;
+                written += 1;
+            }
+            (Some(_), None) => {
+                ingest_from_bytes(store, database, filestore, path, &contents[path.as_str()], None, None, user, eff, ctx).await?;
+                written += 1;
+            }
+            (None, Some(_)) => {
+                delete_file(store, database, filestore, path, user, eff, ctx).await?;
+                deleted += 1;
+            }
+            (None, None) => {}
+        }
+    }
+    Ok((written, deleted))
+}
+
+/// Changes turning the live files into `tree`.
+fn changes_to(live: &BTreeMap<String, String>, tree: &Tree) -> Vec<(String, Option<TreeEntry>)> {
+    let target: BTreeMap<&str, &TreeEntry> = tree.entries.iter().map(|e| (e.path.as_str(), e)).collect();
+    let mut out: Vec<(String, Option<TreeEntry>)> = Vec::new();
+    for (path, e) in &target {
+        if live.get(*path) != Some(&e.etag) { out.push((path.to_string(), Some((*e).clone()))); }
+    }
+    for path in live.keys() {
+        if !target.contains_key(path.as_str()) { out.push((path.clone(), None)); }
+    }
+    out
+}
+
+/// CHECKOUT BRANCH: save the working set to the current branch, then replace the live files
+/// with the head tree of `name`.
+pub async fn checkout_branch(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    name: &str,
+    author: &CommitAuthor,
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<CheckoutOutcome> {
+    if current_branch_head(store, database, filestore, name).is_none() { bail!("branch_not_found: {}", name); }
+    let previous = current_branch(store, database, filestore, eff);
+    let saved = commit_working_set(store, database, filestore, &previous, author, &format!("Working set before checkout of '{}'", name), eff)?;
+    // Read after saving: checking out the current branch keeps the files just committed
+    let target = current_branch_head(store, database, filestore, name).ok_or_else(|| anyhow::anyhow!("branch_not_found: {}", name))?;
+    let tree = commit_tree_of(store, database, filestore, &target)?;
+    let live: BTreeMap<String, String> = live_files(store, database, filestore)?.into_iter().map(|(p, m)| (p, m.etag)).collect();
+    let (files_written, files_deleted) = apply_changes(store, database, filestore, &changes_to(&live, &tree), user, eff, ctx).await?;
+    set_head(store, database, filestore, name)?;
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    crate::tprintln!("FILESTORE checkout_branch ok fs={} {} -> {} written={} deleted={} [corr={}]", filestore, previous, name, files_written, files_deleted, corr);
+    Ok(CheckoutOutcome { branch: name.to_string(), previous_branch: previous, saved_commit_id: saved, commit_id: target, files_written, files_deleted })
+}
+
+/// Ancestors of `commit_id`, itself included, nearest first.
+fn ancestors(store: &SharedStore, database: &str, filestore: &str, commit_id: &str) -> Result<Vec<String>> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut order: Vec<String> = Vec::new();
+    let mut queue: VecDeque<String> = VecDeque::from([commit_id.to_string()]);
+    while let Some(id) = queue.pop_front() {
+        if !seen.insert(id.clone()) { continue; }
+        if let Some(c) = load_commit(store, database, filestore, &id)? { queue.extend(c.parents); }
+        order.push(id);
+    }
+    Ok(order)
+}
+
+/// Nearest common ancestor of two commits.
+pub fn merge_base(store: &SharedStore, database: &str, filestore: &str, a: &str, b: &str) -> Result<Option<String>> {
+    let of_a: HashSet<String> = ancestors(store, database, filestore, a)?.into_iter().collect();
+    Ok(ancestors(store, database, filestore, b)?.into_iter().find(|id| of_a.contains(id)))
+}
+
+/// MERGE BRANCH: merge the head of `source` into the checked-out branch.
+pub async fn merge_branch(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    source: &str,
+    author: &CommitAuthor,
+    message: Option<&str>,
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<MergeOutcome> {
+    let branch = current_branch(store, database, filestore, eff);
+    if source == branch { bail!("cannot merge branch '{}' into itself", source); }
+    let theirs = current_branch_head(store, database, filestore, source).ok_or_else(|| anyhow::anyhow!("branch_not_found: {}", source))?;
+    let ours = commit_working_set(store, database, filestore, &branch, author, &format!("Working set before merge of '{}'", source), eff)?;
+    let base = match &ours { Some(o) => merge_base(store, database, filestore, o, &theirs)?, None => None };
+    let theirs_tree = commit_tree_of(store, database, filestore, &theirs)?;
+    let ours_tree = match &ours { Some(o) => Some(commit_tree_of(store, database, filestore, o)?), None => None };
+    let mut out = MergeOutcome {
+        branch: branch.clone(),
+        source: source.to_string(),
+        status: "up_to_date".to_string(),
+        base_commit_id: base.clone(),
+        commit_id: ours.clone(),
+        ours_tree_id: ours_tree.as_ref().map(|t| t.id.clone()),
+        theirs_tree_id: theirs_tree.id.clone(),
+        applied: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    if base.as_deref() == Some(theirs.as_str()) { return Ok(out); }
+
+    let live: BTreeMap<String, String> = ours_tree.as_ref().map(etags_of).unwrap_or_default();
+    let changes: Vec<(String, Option<TreeEntry>)> = if ours.is_none() || base == ours {
+        out.status = "fast_forward".to_string();
+        changes_to(&live, &theirs_tree)
+    } else {
+        let base_etags = match &base { Some(b) => etags_of(&commit_tree_of(store, database, filestore, b)?), None => BTreeMap::new() };
+        let theirs_entries: BTreeMap<&str, &TreeEntry> = theirs_tree.entries.iter().map(|e| (e.path.as_str(), e)).collect();
+        let paths: BTreeSet<&str> = base_etags.keys().map(String::as_str)
+            .chain(live.keys().map(String::as_str))
+            .chain(theirs_entries.keys().copied())
+            .collect();
+        let mut changes = Vec::new();
+        for path in paths {
+            let (b, o, t) = (base_etags.get(path), live.get(path), theirs_entries.get(path).map(|e| &e.etag));
+            // Same change on both sides, or a change on our side only: keep ours
+            if o == t || t == b { continue; }
+            if o == b { changes.push((path.to_string(), theirs_entries.get(path).map(|e| (*e).clone()))); }
+            else { out.conflicts.push(path.to_string()); }
+        }
+        if !out.conflicts.is_empty() {
+            out.status = "conflict".to_string();
+            out.commit_id = None;
+            crate::tprintln!("FILESTORE merge_branch conflict fs={} {} -> {} conflicts={}", filestore, source, branch, out.conflicts.len());
+            return Ok(out);
+        }
+        out.status = "merged".to_string();
+        changes
+    };
+
+    apply_changes(store, database, filestore, &changes, user, eff, ctx).await?;
+    out.applied = changes.into_iter().map(|(p, _)| p).collect();
+    if out.status == "fast_forward" {
+        set_ref(store, database, filestore, &branch, &theirs)?;
+        out.commit_id = Some(theirs);
+    } else {
+        let tree = snapshot_tree(store, database, filestore, eff)?;
+        let msg = message.map(str::to_string).unwrap_or_else(|| format!("Merge branch '{}' into '{}'", source, branch));
+        let parents: Vec<String> = ours.into_iter().chain(std::iter::once(theirs)).collect();
+        let commit = commit_tree(store, database, filestore, &tree.id, &parents, author, &msg, &[], &branch)?;
+        out.commit_id = Some(commit.id);
+    }
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    crate::tprintln!("FILESTORE merge_branch {} fs={} {} -> {} applied={} [corr={}]", out.status, filestore, source, branch, out.applied.len(), corr);
+    Ok(out)
+}
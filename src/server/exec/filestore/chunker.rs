@@ -0,0 +1,241 @@
+//! Content-defined chunking (FastCDC) and the content-addressed chunk store.
+//!
+//! Files of at least `EffectiveConfig.chunking_threshold_bytes` are cut where a rolling gear
+//! hash of the content matches a mask, so an edit only moves the boundaries around it and the
+//! other chunks keep their bytes and their ids. Each chunk is stored once per filestore under
+//! `Keys::chunk_oid`, named by the SHA-256 of its bytes; `FileMeta.chunking` lists the chunks
+//! that make up a file. Files sharing content (copies, edited versions) share chunks.
+//! The index entry holds the bytes, or points to the object store holding them (see blob.rs);
+//! payloads of encrypted filestores are sealed (see sse.rs) and opened here on read.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+
+use crate::storage::{KvStore, KvValue, SharedStore};
+
+use super::blob::{remote_chunk, stored_len, BlobBackend, ObjectStoreBackend};
+use super::kv::{etag_for_bytes, Keys};
+use super::sse;
+use super::types::{ChunkRef, Chunking, FileMeta, FileVersion, Tree};
+
+/// Chunk size bounds; `avg` must be a power of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkParams {
+    pub min: usize,
+    pub avg: usize,
+    pub max: usize,
+}
+
+impl Default for ChunkParams {
+    fn default() -> Self { Self { min: 16 * 1024, avg: 64 * 1024, max: 256 * 1024 } }
+}
+
+/// Gear table: one pseudo-random 64-bit value per byte (splitmix64, fixed seed). Boundaries
+/// depend on it, so it must never change for existing chunk stores to keep deduplicating.
+static GEAR: Lazy<[u64; 256]> = Lazy::new(|| {
+    let mut state: u64 = 0x636c_6172_6975_6d00;
+    let mut t = [0u64; 256];
+    for v in t.iter_mut() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        *v = z ^ (z >> 31);
+    }
+    t
+});
+
+/// Mask of the `bits` most significant bits; they carry the last 64 bytes of the gear hash.
+fn top_mask(bits: u32) -> u64 { if bits == 0 { 0 } else { !0u64 << (64 - bits.min(64)) } }
+
+/// Length of the first chunk of `data`. Normalized chunking: a stricter mask before `avg` and a
+/// looser one after it keep chunk sizes close to `avg`.
+fn cut_point(data: &[u8], p: &ChunkParams) -> usize {
+    let n = data.len();
+    if n <= p.min { return n; }
+    let max = n.min(p.max);
+    let normal = n.min(p.avg);
+    let bits = p.avg.trailing_zeros();
+    let (mask_s, mask_l) = (top_mask(bits + 2), top_mask(bits.saturating_sub(2)));
+    let mut h = 0u64;
+    let mut i = p.min;
+    while i < normal {
+        h = (h << 1).wrapping_add(GEAR[data[i] as usize]);
+        if h & mask_s == 0 { return i + 1; }
+        i += 1;
+    }
+    while i < max {
+        h = (h << 1).wrapping_add(GEAR[data[i] as usize]);
+        if h & mask_l == 0 { return i + 1; }
+        i += 1;
+    }
+    max
+}
+
+/// Split `data` into content-defined chunks, as `(offset, len)` pairs covering it in order.
+pub fn chunk_ranges(data: &[u8], p: &ChunkParams) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut off = 0usize;
+    while off < data.len() {
+        let len = cut_point(&data[off..], p);
+        out.push((off, len));
+        off += len;
+    }
+    out
+}
+
+/// Content address of a chunk: lowercase hex SHA-256.
+pub fn chunk_oid(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Chunk `data` and write the chunks the filestore does not hold yet to `backend`.
+/// Returns the file's chunk list and the number of bytes actually written.
+pub fn store_chunked(store: &SharedStore, database: &str, filestore: &str, data: &[u8], p: &ChunkParams, backend: &dyn BlobBackend) -> Result<(Chunking, u64)> {
+    let kv = store.kv_store(database, filestore);
+    let mut chunks = Vec::new();
+    let mut new: Vec<(String, &[u8])> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    for (off, len) in chunk_ranges(data, p) {
+        let bytes = &data[off..off + len];
+        let oid = chunk_oid(bytes);
+        if kv.get(&Keys::chunk_oid(database, filestore, &oid)).is_none() && seen.insert(oid.clone()) {
+            new.push((oid.clone(), bytes));
+        }
+        chunks.push(ChunkRef { oid, off: off as u64, len: len as u32, etag: etag_for_bytes(bytes) });
+    }
+    // Payloads first, then the index entries that make them visible
+    let items: Vec<(&str, &[u8])> = new.iter().map(|(o, b)| (o.as_str(), *b)).collect();
+    backend.put(&items)?;
+    let mut written = 0u64;
+    for (oid, bytes) in &new {
+        if let Some(entry) = backend.index_entry(bytes.len() as u64) {
+            kv.set(Keys::chunk_oid(database, filestore, oid), entry, None, None);
+        }
+        written += bytes.len() as u64;
+    }
+    Ok((Chunking { chunk_size: p.avg as u32, chunks }, written))
+}
+
+/// Reassemble a chunked file; `None` when a chunk is missing from the index. Remote chunks are
+/// fetched in one batch per backend.
+pub fn read_chunked(store: &SharedStore, database: &str, filestore: &str, chunking: &Chunking) -> Result<Option<Vec<u8>>> {
+    let kv = store.kv_store(database, filestore);
+    let mut parts: Vec<Option<Vec<u8>>> = vec![None; chunking.chunks.len()];
+    let mut remote: BTreeMap<(String, Option<String>), Vec<usize>> = BTreeMap::new();
+    for (i, c) in chunking.chunks.iter().enumerate() {
+        match kv.get(&Keys::chunk_oid(database, filestore, &c.oid)) {
+            None => return Ok(None),
+            Some(KvValue::Bytes(b)) => parts[i] = Some(sse::open(store, database, filestore, b)?),
+            Some(v) => {
+                let rc = remote_chunk(&v).ok_or_else(|| anyhow!("corrupt chunk index entry: {}", c.oid))?;
+                remote.entry((rc.backend, rc.endpoint)).or_default().push(i);
+            }
+        }
+    }
+    for ((uri, endpoint), idx) in remote {
+        let backend = ObjectStoreBackend::new(&uri, endpoint.as_deref(), database, filestore)?;
+        let oids: Vec<&str> = idx.iter().map(|i| chunking.chunks[*i].oid.as_str()).collect();
+        for (i, bytes) in idx.iter().zip(backend.get(&oids)?) { parts[*i] = Some(sse::open(store, database, filestore, bytes)?); }
+    }
+    Ok(Some(parts.into_iter().flatten().flatten().collect()))
+}
+
+/// Payload of one chunk of a chunked file; `None` when the chunk is missing from the index.
+/// Lets a reader take a file chunk by chunk instead of reassembling all of it.
+pub fn read_chunk(store: &SharedStore, database: &str, filestore: &str, chunk: &ChunkRef) -> Result<Option<Vec<u8>>> {
+    let kv = store.kv_store(database, filestore);
+    let sealed = match kv.get(&Keys::chunk_oid(database, filestore, &chunk.oid)) {
+        None => return Ok(None),
+        Some(KvValue::Bytes(b)) => b,
+        Some(v) => {
+            let rc = remote_chunk(&v).ok_or_else(|| anyhow!("corrupt chunk index entry: {}", chunk.oid))?;
+            let backend = ObjectStoreBackend::new(&rc.backend, rc.endpoint.as_deref(), database, filestore)?;
+            backend.get(&[chunk.oid.as_str()])?.pop().unwrap_or_default()
+        }
+    };
+    Ok(Some(sse::open(store, database, filestore, sealed)?))
+}
+
+/// Every file meta of the filestore, tombstones included (they keep their content until GC).
+fn all_file_metas(kv: &KvStore, database: &str, filestore: &str) -> Vec<FileMeta> {
+    let prefix = Keys::path_prefix(database, filestore);
+    kv.keys().into_iter()
+        .filter(|k| k.starts_with(&prefix))
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value(j).ok(), _ => None })
+        .collect()
+}
+
+/// Chunks pinned by tree entries (branch snapshots, see branch.rs) and retained file versions
+/// (see retention.rs), one oid per reference.
+fn pinned_oids(kv: &KvStore, database: &str, filestore: &str) -> Vec<String> {
+    let tree_prefix = Keys::tree_prefix(database, filestore);
+    let version_prefix = Keys::version_prefix(database, filestore);
+    let mut out: Vec<String> = kv.keys().into_iter()
+        .filter(|k| k.starts_with(&tree_prefix))
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value::<Tree>(j).ok(), _ => None })
+        .flat_map(|t| t.entries.into_iter())
+        .flat_map(|e| e.content.into_iter().flat_map(|ch| ch.chunks.into_iter().map(|c| c.oid)))
+        .collect();
+    out.extend(kv.keys().into_iter()
+        .filter(|k| k.starts_with(&version_prefix))
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value::<FileVersion>(j).ok(), _ => None })
+        .flat_map(|v| v.content.chunks.into_iter().map(|c| c.oid)));
+    out
+}
+
+/// References to each chunk oid from file metas, tree snapshots and retained versions.
+pub fn chunk_ref_counts(store: &SharedStore, database: &str, filestore: &str) -> HashMap<String, i64> {
+    let kv = store.kv_store(database, filestore);
+    let mut out: HashMap<String, i64> = HashMap::new();
+    for m in all_file_metas(&kv, database, filestore) {
+        for c in m.chunking.iter().flat_map(|ch| ch.chunks.iter()) {
+            *out.entry(c.oid.clone()).or_default() += 1;
+        }
+    }
+    for oid in pinned_oids(&kv, database, filestore) { *out.entry(oid).or_default() += 1; }
+    out
+}
+
+/// Deduplication of the chunk store: bytes referenced by files against bytes stored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DedupStats {
+    /// Sum of the chunk lengths listed by file metas.
+    pub logical_bytes: u64,
+    /// Bytes held in the chunk store, local or remote.
+    pub stored_bytes: u64,
+    pub chunks: u64,
+    /// Stored chunks no file, tree snapshot or retained version references (left by updates and deletes).
+    pub orphan_chunks: u64,
+}
+
+impl DedupStats {
+    /// logical / stored, or `None` while nothing is chunked.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.stored_bytes > 0).then(|| self.logical_bytes as f64 / self.stored_bytes as f64)
+    }
+}
+
+pub fn dedup_stats(store: &SharedStore, database: &str, filestore: &str) -> DedupStats {
+    let kv = store.kv_store(database, filestore);
+    let mut st = DedupStats::default();
+    let mut referenced: HashSet<String> = HashSet::new();
+    for m in all_file_metas(&kv, database, filestore) {
+        for c in m.chunking.iter().flat_map(|ch| ch.chunks.iter()) {
+            st.logical_bytes += c.len as u64;
+            referenced.insert(c.oid.clone());
+        }
+    }
+    referenced.extend(pinned_oids(&kv, database, filestore));
+    let prefix = Keys::chunk_prefix(database, filestore);
+    for k in kv.keys() {
+        let Some(oid) = k.strip_prefix(&prefix) else { continue };
+        st.chunks += 1;
+        st.stored_bytes += kv.get(&k).map(|v| stored_len(&v)).unwrap_or(0);
+        if !referenced.contains(oid) { st.orphan_chunks += 1; }
+    }
+    st
+}
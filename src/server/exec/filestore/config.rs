@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::storage::encryption::EncryptionSpec;
+// Webhook endpoints are shared with KV stores, so the type lives in storage
+pub use crate::storage::webhooks::WebhookConfig;
+
 /// Global FILESTORE settings applied to all filestores unless overridden.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GlobalFilestoreConfig {
@@ -14,6 +18,14 @@ pub struct GlobalFilestoreConfig {
     pub git_backend: String,
     /// Push backend selection: 'auto' | 'gitoxide' | 'libgit2'
     pub git_push_backend: String,
+    /// User name offered to the remote (HTTPS user or SSH login); defaults to the URL's user
+    pub git_username: Option<String>,
+    /// Environment variable holding the HTTPS token/password; the secret itself is never stored
+    pub git_token_env: String,
+    /// SSH private key file; the SSH agent is used when unset
+    pub git_ssh_key_path: Option<String>,
+    /// SYNC ... PULL handling of files changed on both sides: 'fail' | 'ours' | 'theirs'
+    pub git_conflict_policy: String,
     /// Optional patterns e.g., "*.pdf;*.pptx"
     pub lfs_patterns: Option<String>,
 
@@ -22,6 +34,36 @@ pub struct GlobalFilestoreConfig {
 
     /// Grace period in seconds before GC can permanently delete tombstoned entries
     pub gc_grace_seconds: u64,
+    /// Seconds between scheduled GC runs of a filestore; 0 leaves GC to manual runs
+    pub gc_interval_seconds: u64,
+
+    /// Files of at least this many bytes are stored as deduplicated content-defined chunks; 0 disables
+    pub chunking_threshold_bytes: u64,
+    /// Bytes of remote chunk payloads kept in memory by the read-through cache; 0 disables
+    pub blob_cache_bytes: u64,
+    /// Longest lifetime a presigned HTTP download/upload link may be given
+    pub presign_max_ttl_secs: u64,
+
+    /// Index the text of ingested files for `filestore_search`
+    pub fulltext_index: bool,
+    /// Also extract text from PDF files (best effort: text operators of uncompressed and deflated streams)
+    pub fulltext_pdf: bool,
+    /// Larger files are not indexed
+    pub fulltext_max_bytes: u64,
+
+    /// A quota warning event is recorded when a write takes usage to this percentage of a limit
+    pub quota_warn_percent: u8,
+
+    /// Further attempts after a failed webhook delivery, with exponential backoff
+    pub webhook_retries: u32,
+    /// Timeout of one webhook request, in milliseconds
+    pub webhook_timeout_ms: u64,
+
+    /// Timeout of one content scan (connect, send and verdict), in milliseconds
+    pub scan_timeout_ms: u64,
+
+    /// Lifetime of a file lock taken without FOR <seconds>; 0 holds it until unlocked
+    pub lock_default_ttl_seconds: u64,
 }
 
 impl Default for GlobalFilestoreConfig {
@@ -35,10 +77,26 @@ impl Default for GlobalFilestoreConfig {
             git_mode: "plumbing_only".to_string(),
             git_backend: "gitoxide".to_string(),
             git_push_backend: "auto".to_string(),
+            git_username: None,
+            git_token_env: "CLARIUM_FILESTORE_GIT_TOKEN".to_string(),
+            git_ssh_key_path: None,
+            git_conflict_policy: "fail".to_string(),
             lfs_patterns: None,
 
             html_description_max_bytes: 32 * 1024,
             gc_grace_seconds: 86_400, // 1 day by default
+            gc_interval_seconds: 0,
+            chunking_threshold_bytes: 1024 * 1024,
+            blob_cache_bytes: 256 * 1024 * 1024,
+            presign_max_ttl_secs: 86_400,
+            fulltext_index: true,
+            fulltext_pdf: false,
+            fulltext_max_bytes: 16 * 1024 * 1024,
+            quota_warn_percent: 90,
+            webhook_retries: 3,
+            webhook_timeout_ms: 5_000,
+            scan_timeout_ms: 30_000,
+            lock_default_ttl_seconds: 86_400,
         }
     }
 }
@@ -59,9 +117,55 @@ pub struct FilestoreConfig {
     pub git_backend: Option<String>,
     pub git_push_backend: Option<String>,
     pub lfs_patterns: Option<String>,
+    // Remote credentials (secrets are read from the named environment variable) and pull conflict policy
+    pub git_username: Option<String>,
+    pub git_token_env: Option<String>,
+    pub git_ssh_key_path: Option<String>,
+    pub git_conflict_policy: Option<String>,
 
     // Metadata limits
     pub html_description_max_bytes: Option<usize>,
+
+    // Chunked storage threshold override
+    pub chunking_threshold_bytes: Option<u64>,
+
+    // Where chunk payloads live: 'kv' (default) or an object store URI (s3://bucket/prefix, gs://bucket/prefix, az://container/prefix)
+    pub blob_backend: Option<String>,
+    // Service endpoint: S3-compatible for s3:// backends (MinIO, Ceph, ...), blob service for az:// (Azurite)
+    pub blob_endpoint: Option<String>,
+
+    // Full-text indexing overrides
+    pub fulltext_index: Option<bool>,
+    pub fulltext_pdf: Option<bool>,
+
+    // Storage quotas over live files: whole filestore, and per logical prefix
+    pub quota_bytes: Option<u64>,
+    pub quota_files: Option<u64>,
+    pub prefix_quotas: Option<Vec<PrefixQuota>>,
+    pub quota_warn_percent: Option<u8>,
+
+    // Retention: files may not be deleted before this many seconds after creation, and replaced
+    // or deleted versions are kept (at least this long, and the latest `retention_versions` of each path)
+    pub retention_min_seconds: Option<u64>,
+    pub retention_versions: Option<u64>,
+
+    // Webhooks notified of file changes (see events.rs)
+    pub webhooks: Option<Vec<WebhookConfig>>,
+
+    // Content scanning on ingest (see scan.rs): scanner URI ("clamd://host:port",
+    // "icap://host:port/service" or "command:<program> [args]"), what to do with a flagged file
+    // ("reject" | "quarantine"), and whether a scanner failure lets the write through
+    pub scanner: Option<String>,
+    pub scan_mode: Option<String>,
+    pub scan_fail_open: Option<bool>,
+
+    // Server-side encryption of file content with a per-filestore data key wrapped by this
+    // master key, e.g. {"algorithm": "aes-256-gcm", "key_id": "k1"} (see sse.rs)
+    pub encryption: Option<EncryptionSpec>,
+
+    // Scheduled GC (see gc_scheduler.rs): cadence and grace period overrides, in seconds
+    pub gc_interval_seconds: Option<u64>,
+    pub gc_grace_seconds: Option<u64>,
 }
 
 impl Default for FilestoreConfig {
@@ -76,11 +180,41 @@ impl Default for FilestoreConfig {
             git_backend: None,
             git_push_backend: None,
             lfs_patterns: None,
+            git_username: None,
+            git_token_env: None,
+            git_ssh_key_path: None,
+            git_conflict_policy: None,
             html_description_max_bytes: None,
+            chunking_threshold_bytes: None,
+            blob_backend: None,
+            blob_endpoint: None,
+            fulltext_index: None,
+            fulltext_pdf: None,
+            quota_bytes: None,
+            quota_files: None,
+            prefix_quotas: None,
+            quota_warn_percent: None,
+            retention_min_seconds: None,
+            retention_versions: None,
+            webhooks: None,
+            scanner: None,
+            scan_mode: None,
+            scan_fail_open: None,
+            encryption: None,
+            gc_interval_seconds: None,
+            gc_grace_seconds: None,
         }
     }
 }
 
+/// Limits on the live files under a logical prefix (the prefix itself and everything below it).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct PrefixQuota {
+    pub prefix: String,
+    pub max_bytes: Option<u64>,
+    pub max_files: Option<u64>,
+}
+
 /// Per-folder Git overrides; only Git options can be overridden at folder level.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct FolderGitOverride {
@@ -104,8 +238,32 @@ pub struct EffectiveConfig {
     pub git_backend: String,
     pub git_push_backend: String,
     pub lfs_patterns: Option<String>,
+    pub git_username: Option<String>,
+    pub git_token_env: String,
+    pub git_ssh_key_path: Option<String>,
+    pub git_conflict_policy: String,
 
     pub html_description_max_bytes: usize,
+    pub chunking_threshold_bytes: u64,
+    pub blob_backend: Option<String>,
+    pub blob_endpoint: Option<String>,
+    pub fulltext_index: bool,
+    pub fulltext_pdf: bool,
+    pub fulltext_max_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    pub quota_files: Option<u64>,
+    pub prefix_quotas: Vec<PrefixQuota>,
+    pub quota_warn_percent: u8,
+    pub retention_min_seconds: u64,
+    pub retention_versions: u64,
+    pub scanner: Option<String>,
+    pub scan_mode: Option<String>,
+    pub scan_fail_open: bool,
+    pub scan_timeout_ms: u64,
+    pub encryption: Option<EncryptionSpec>,
+    pub gc_interval_seconds: u64,
+    pub gc_grace_seconds: u64,
+    pub lock_default_ttl_seconds: u64,
 }
 
 impl EffectiveConfig {
@@ -130,8 +288,19 @@ impl EffectiveConfig {
         let git_backend = fs.git_backend.clone().unwrap_or_else(|| global.git_backend.clone());
         let git_push_backend = fs.git_push_backend.clone().unwrap_or_else(|| global.git_push_backend.clone());
         let lfs_patterns = fs.lfs_patterns.clone().or_else(|| global.lfs_patterns.clone());
+        let git_username = fs.git_username.clone().or_else(|| global.git_username.clone());
+        let git_token_env = fs.git_token_env.clone().unwrap_or_else(|| global.git_token_env.clone());
+        let git_ssh_key_path = fs.git_ssh_key_path.clone().or_else(|| global.git_ssh_key_path.clone());
+        let git_conflict_policy = fs.git_conflict_policy.clone().unwrap_or_else(|| global.git_conflict_policy.clone());
 
         let html_description_max_bytes = fs.html_description_max_bytes.unwrap_or(global.html_description_max_bytes);
+        let chunking_threshold_bytes = fs.chunking_threshold_bytes.unwrap_or(global.chunking_threshold_bytes);
+        // Backend is a per-filestore choice; chunks stay in the KV unless configured
+        let blob_backend = fs.blob_backend.clone();
+        let blob_endpoint = fs.blob_endpoint.clone();
+        let fulltext_index = fs.fulltext_index.unwrap_or(global.fulltext_index);
+        let fulltext_pdf = fs.fulltext_pdf.unwrap_or(global.fulltext_pdf);
+        let quota_warn_percent = fs.quota_warn_percent.unwrap_or(global.quota_warn_percent).min(100);
 
         Self {
             security_check_enabled,
@@ -143,7 +312,31 @@ impl EffectiveConfig {
             git_backend,
             git_push_backend,
             lfs_patterns,
+            git_username,
+            git_token_env,
+            git_ssh_key_path,
+            git_conflict_policy,
             html_description_max_bytes,
+            chunking_threshold_bytes,
+            blob_backend,
+            blob_endpoint,
+            fulltext_index,
+            fulltext_pdf,
+            fulltext_max_bytes: global.fulltext_max_bytes,
+            quota_bytes: fs.quota_bytes,
+            quota_files: fs.quota_files,
+            prefix_quotas: fs.prefix_quotas.clone().unwrap_or_default(),
+            quota_warn_percent,
+            retention_min_seconds: fs.retention_min_seconds.unwrap_or(0),
+            retention_versions: fs.retention_versions.unwrap_or(0),
+            scanner: fs.scanner.clone(),
+            scan_mode: fs.scan_mode.clone(),
+            scan_fail_open: fs.scan_fail_open.unwrap_or(false),
+            scan_timeout_ms: global.scan_timeout_ms,
+            encryption: fs.encryption.clone(),
+            gc_interval_seconds: fs.gc_interval_seconds.unwrap_or(global.gc_interval_seconds),
+            gc_grace_seconds: fs.gc_grace_seconds.unwrap_or(global.gc_grace_seconds),
+            lock_default_ttl_seconds: global.lock_default_ttl_seconds,
         }
     }
 }
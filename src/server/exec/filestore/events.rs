@@ -0,0 +1,208 @@
+//! File change events: an in-process bus and webhook delivery.
+//!
+//! Ingest, update, rename, delete and commit publish a FileEvent after the change is stored.
+//! Events go out on a broadcast channel (`subscribe`) and, for each webhook in the filestore's
+//! config whose kinds and prefix match, onto a background delivery queue. A delivery POSTs the
+//! event as JSON with headers `X-Clarium-Event` (kind), `X-Clarium-Delivery` (event id) and, when
+//! the webhook names a `secret_env`, `X-Clarium-Signature: sha256=<hex HMAC-SHA256 of the body>`.
+//! A non-2xx answer or transport error is retried `[filestore] webhook_retries` times with
+//! exponential backoff; delivery never fails the change itself. Per-webhook counters are kept
+//! in memory (SHOW WEBHOOKS IN FILESTORE). KV store events (`storage::kv_events`) are delivered
+//! by the same worker.
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::storage::SharedStore;
+
+use super::config::WebhookConfig;
+use super::registry::load_filestore_entry;
+pub(crate) use crate::storage::webhooks::Delivery;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const BROADCAST_CAPACITY: usize = 4096;
+/// First retry delay; doubled on each further attempt.
+const RETRY_BACKOFF_MS: u64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileEvent {
+    /// Unique per event; sent as the delivery id
+    pub id: String,
+    /// "ingest" | "update" | "rename" | "delete" | "commit"
+    pub kind: String,
+    pub database: String,
+    pub filestore: String,
+    /// Logical path after the change (the new path of a rename); None for commits
+    pub path: Option<String>,
+    /// Previous path of a rename
+    pub old_path: Option<String>,
+    pub etag: Option<String>,
+    pub size: Option<u64>,
+    pub version: Option<u64>,
+    pub commit_id: Option<String>,
+    pub branch: Option<String>,
+    pub at: i64,
+    pub correlation_id: Option<String>,
+}
+
+impl FileEvent {
+    pub fn new(kind: &str, database: &str, filestore: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            database: database.to_string(),
+            filestore: filestore.to_string(),
+            path: None,
+            old_path: None,
+            etag: None,
+            size: None,
+            version: None,
+            commit_id: None,
+            branch: None,
+            at: Utc::now().timestamp(),
+            correlation_id: None,
+        }
+    }
+
+    /// The event for a file change, filled from the file's meta after it.
+    pub fn for_file(kind: &str, database: &str, filestore: &str, meta: &super::types::FileMeta, correlation_id: Option<&str>) -> Self {
+        Self {
+            path: Some(meta.logical_path.clone()),
+            etag: Some(meta.etag.clone()),
+            size: Some(meta.size),
+            version: Some(meta.version),
+            correlation_id: correlation_id.map(str::to_string),
+            ..Self::new(kind, database, filestore)
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Whether this webhook wants `ev`.
+    pub fn matches(&self, ev: &FileEvent) -> bool {
+        let kind_ok = self.events.as_ref().map(|ks| ks.iter().any(|k| k.eq_ignore_ascii_case(&ev.kind))).unwrap_or(true);
+        let prefix_ok = match (self.prefix.as_deref().map(|p| p.trim_matches('/')), ev.path.as_deref()) {
+            (None, _) | (Some(""), _) | (_, None) => true,
+            (Some(p), Some(path)) => path == p || path.strip_prefix(p).map(|r| r.starts_with('/')).unwrap_or(false),
+        };
+        kind_ok && prefix_ok
+    }
+}
+
+static CHANNEL: Lazy<tokio::sync::broadcast::Sender<FileEvent>> =
+    Lazy::new(|| tokio::sync::broadcast::channel(BROADCAST_CAPACITY).0);
+
+/// Subscribe to file events of every filestore in this process.
+pub fn subscribe() -> tokio::sync::broadcast::Receiver<FileEvent> { CHANNEL.subscribe() }
+
+/// In-memory delivery counters for one webhook since startup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebhookStats {
+    pub delivered: u64,
+    pub failed: u64,
+    /// HTTP status of the last attempt, if it got an answer
+    pub last_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub last_event_id: Option<String>,
+}
+
+/// Keyed by (database, scope, url).
+static STATS: Lazy<Mutex<HashMap<(String, String, String), WebhookStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Counters of the webhook `url` of a filestore.
+pub fn webhook_stats(database: &str, filestore: &str, url: &str) -> WebhookStats {
+    STATS.lock().get(&(database.to_string(), filestore.to_string(), url.to_string())).cloned().unwrap_or_default()
+}
+
+/// Background worker for webhook deliveries, started on first use.
+static QUEUE: Lazy<Mutex<mpsc::Sender<Delivery>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<Delivery>();
+    std::thread::Builder::new()
+        .name("clarium-filestore-webhooks".into())
+        .spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(rt) => rt,
+                Err(e) => { tracing::error!(target: "clarium::filestore", "webhook worker: no runtime: {}", e); return; }
+            };
+            let client = reqwest::Client::new();
+            for d in rx { rt.block_on(deliver(&client, d)); }
+        })
+        .expect("spawn webhook worker");
+    Mutex::new(tx)
+});
+
+/// Queue `d` on the background delivery worker.
+pub(crate) fn enqueue(d: Delivery) {
+    let _ = QUEUE.lock().send(d);
+}
+
+/// `sha256=<hex>` signature of a webhook body.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts any key length");
+    mac.update(body);
+    let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+async fn deliver(client: &reqwest::Client, d: Delivery) {
+    let global = crate::config::current().filestore.clone();
+    let secret = d.hook.secret_env.as_deref().and_then(|v| std::env::var(v).ok()).filter(|s| !s.is_empty());
+    let mut last: Result<u16, String> = Err("not attempted".into());
+    for attempt in 0..=global.webhook_retries {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(RETRY_BACKOFF_MS << (attempt - 1).min(6))).await;
+        }
+        let mut req = client.post(&d.hook.url)
+            .timeout(Duration::from_millis(global.webhook_timeout_ms.max(1)))
+            .header("Content-Type", "application/json")
+            .header("X-Clarium-Event", d.kind.as_str())
+            .header("X-Clarium-Delivery", d.id.as_str())
+            .body(d.body.clone());
+        if let Some(s) = &secret { req = req.header("X-Clarium-Signature", sign(s.as_bytes(), &d.body)); }
+        last = match req.send().await {
+            Ok(resp) if resp.status().is_success() => Ok(resp.status().as_u16()),
+            Ok(resp) => Err(format!("HTTP {}", resp.status().as_u16())),
+            Err(e) => Err(e.to_string()),
+        };
+        if last.is_ok() { break; }
+    }
+    let mut stats = STATS.lock();
+    let s = stats.entry((d.database.clone(), d.scope.clone(), d.hook.url.clone())).or_default();
+    s.last_event_id = Some(d.id.clone());
+    match last {
+        Ok(code) => { s.delivered += 1; s.last_status = Some(code); s.last_error = None; }
+        Err(e) => {
+            tracing::warn!(target: "clarium::filestore", "webhook {} for {} event {} failed: {}", d.hook.url, d.scope, d.id, e);
+            s.failed += 1;
+            s.last_status = e.strip_prefix("HTTP ").and_then(|c| c.parse().ok());
+            s.last_error = Some(e);
+        }
+    }
+}
+
+/// Publish `ev` to subscribers and queue it for the filestore's matching webhooks.
+pub fn publish(store: &SharedStore, ev: FileEvent) {
+    let hooks = load_filestore_entry(store, &ev.database, &ev.filestore).ok().flatten()
+        .and_then(|e| e.config.webhooks)
+        .unwrap_or_default();
+    let matching: Vec<WebhookConfig> = hooks.into_iter().filter(|h| h.matches(&ev)).collect();
+    if !matching.is_empty() {
+        if let Ok(body) = serde_json::to_vec(&ev) {
+            for hook in matching {
+                enqueue(Delivery { hook, database: ev.database.clone(), scope: ev.filestore.clone(), kind: ev.kind.clone(), id: ev.id.clone(), body: body.clone() });
+            }
+        }
+    }
+    crate::tprintln!("FILESTORE event {} fs={} path={} id={} [corr={}]", ev.kind, ev.filestore, ev.path.as_deref().unwrap_or("-"), ev.id, ev.correlation_id.as_deref().unwrap_or("-"));
+    // No receivers is fine
+    let _ = CHANNEL.send(ev);
+}
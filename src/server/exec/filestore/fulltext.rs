@@ -0,0 +1,366 @@
+//! Full-text index over file content, queried with `filestore_search('store', 'terms')`.
+//!
+//! Ingest and update extract the text of indexable files, tokenize it and keep an inverted
+//! index in the filestore's KV; rename moves a file's entries and delete drops them:
+//! - `Keys::text(id)`: the extracted text, for result snippets;
+//! - `Keys::fts_doc(path)`: term frequencies and length of the live file at `path`;
+//! - `Keys::fts_term(term)`: postings, `{path: frequency}`;
+//! - `Keys::fts_stats`: document count and total length, for scoring.
+//!
+//! Indexable files are text/* and JSON content types and the txt/md/csv/tsv/log/json/ndjson/jsonl
+//! extensions (JSON contributes its keys and string values), plus PDF when
+//! `EffectiveConfig.fulltext_pdf` is on. Tokens are lowercased runs of letters and digits.
+//! A search returns the files containing every query term, ranked by BM25.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::storage::{KvStore, KvValue, SharedStore};
+
+use super::config::EffectiveConfig;
+use super::kv::Keys;
+use super::types::FileMeta;
+
+/// Longer tokens (hashes, base64 runs) are not indexed.
+const MAX_TERM_CHARS: usize = 64;
+/// Characters of a result snippet, and how many of them precede the first matching term.
+const SNIPPET_CHARS: usize = 160;
+const SNIPPET_LEAD: usize = 40;
+/// Cap on a decompressed PDF stream.
+const MAX_PDF_STREAM: u64 = 64 * 1024 * 1024;
+/// BM25 parameters.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Index entry of one file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+struct DocEntry {
+    /// File id; its extracted text is under `Keys::text`
+    id: String,
+    /// Number of tokens
+    len: u64,
+    terms: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+struct IndexStats {
+    docs: u64,
+    total_len: u64,
+}
+
+/// One `filestore_search` result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub path: String,
+    pub score: f64,
+    pub snippet: String,
+}
+
+/// Lowercased runs of letters and digits, in order.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty() && t.chars().count() <= MAX_TERM_CHARS)
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+enum Kind { Text, Json, Pdf }
+
+fn kind(path: &str, content_type: Option<&str>) -> Option<Kind> {
+    let ct = content_type.map(|c| c.split(';').next().unwrap_or("").trim().to_ascii_lowercase()).unwrap_or_default();
+    let ext = path.rsplit('/').next().and_then(|f| f.rsplit_once('.')).map(|(_, e)| e.to_ascii_lowercase()).unwrap_or_default();
+    if ct == "application/pdf" || ext == "pdf" { return Some(Kind::Pdf); }
+    if ct.ends_with("json") || ct == "application/x-ndjson" || matches!(ext.as_str(), "json" | "ndjson" | "jsonl") { return Some(Kind::Json); }
+    if ct.starts_with("text/") || matches!(ext.as_str(), "txt" | "md" | "markdown" | "csv" | "tsv" | "log") { return Some(Kind::Text); }
+    None
+}
+
+/// Text to index for a file, or `None` when its type is not indexed.
+pub fn extract_text(path: &str, content_type: Option<&str>, bytes: &[u8], pdf: bool) -> Option<String> {
+    match kind(path, content_type)? {
+        Kind::Text => Some(String::from_utf8_lossy(bytes).into_owned()),
+        Kind::Json => Some(json_text(bytes)),
+        Kind::Pdf if pdf => pdf_text(bytes),
+        Kind::Pdf => None,
+    }
+}
+
+fn collect_json(v: &serde_json::Value, out: &mut Vec<String>) {
+    match v {
+        serde_json::Value::String(s) => out.push(s.clone()),
+        serde_json::Value::Array(a) => a.iter().for_each(|x| collect_json(x, out)),
+        serde_json::Value::Object(m) => m.iter().for_each(|(k, x)| { out.push(k.clone()); collect_json(x, out); }),
+        _ => {}
+    }
+}
+
+/// Keys and string values of a JSON document or of NDJSON lines; the raw text when neither parses.
+fn json_text(bytes: &[u8]) -> String {
+    let raw = String::from_utf8_lossy(bytes);
+    let mut parts = Vec::new();
+    if let Ok(v) = serde_json::from_str::<serde_json::Value>(&raw) {
+        collect_json(&v, &mut parts);
+    } else {
+        for line in raw.lines().filter(|l| !l.trim().is_empty()) {
+            match serde_json::from_str::<serde_json::Value>(line) {
+                Ok(v) => collect_json(&v, &mut parts),
+                Err(_) => return raw.into_owned(),
+            }
+        }
+    }
+    parts.join("\n")
+}
+
+fn find(hay: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    hay.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|p| p + from)
+}
+
+fn rfind(hay: &[u8], needle: &[u8]) -> Option<usize> {
+    hay.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Text shown by a PDF's content streams: the literal strings of its text objects (BT ... ET) in
+/// uncompressed or deflated streams. Without a PDF library this is a best effort: hex strings,
+/// object streams and fonts with custom encodings are not decoded.
+fn pdf_text(bytes: &[u8]) -> Option<String> {
+    if !bytes.starts_with(b"%PDF") { return None; }
+    let mut out = String::new();
+    let mut pos = 0;
+    while let Some(s) = find(bytes, b"stream", pos) {
+        let mut start = s + b"stream".len();
+        if bytes.get(start) == Some(&b'\r') { start += 1; }
+        if bytes.get(start) == Some(&b'\n') { start += 1; }
+        let Some(end) = find(bytes, b"endstream", start) else { break };
+        // The stream dictionary sits between the object header and `stream`
+        let dict = &bytes[rfind(&bytes[..s], b"obj").unwrap_or(0)..s];
+        let data = &bytes[start..end];
+        if rfind(dict, b"/FlateDecode").is_some() {
+            let mut inflated = Vec::new();
+            if flate2::read::ZlibDecoder::new(data).take(MAX_PDF_STREAM).read_to_end(&mut inflated).is_ok() {
+                text_objects(&inflated, &mut out);
+            }
+        } else if rfind(dict, b"/Filter").is_none() {
+            text_objects(data, &mut out);
+        }
+        pos = end + b"endstream".len();
+    }
+    (!out.trim().is_empty()).then_some(out)
+}
+
+/// Literal string starting at `c[open] == '('`: its text (bytes as Latin-1) and the offset after it.
+fn pdf_string(c: &[u8], open: usize) -> (String, usize) {
+    let mut s = String::new();
+    let mut depth = 0usize;
+    let mut i = open;
+    while i < c.len() {
+        let b = c[i];
+        match b {
+            b'(' => { depth += 1; if depth > 1 { s.push('('); } }
+            b')' => { depth -= 1; if depth == 0 { return (s, i + 1); } s.push(')'); }
+            b'\\' => {
+                i += 1;
+                match c.get(i) {
+                    Some(b'n') | Some(b'r') | Some(b't') | Some(b'f') | Some(b'b') => s.push(' '),
+                    Some(d @ b'0'..=b'7') => {
+                        let mut v = (d - b'0') as u32;
+                        for _ in 0..2 {
+                            match c.get(i + 1) {
+                                Some(d @ b'0'..=b'7') => { v = v * 8 + (d - b'0') as u32; i += 1; }
+                                _ => break,
+                            }
+                        }
+                        s.push(char::from_u32(v & 0xff).unwrap_or(' '));
+                    }
+                    // Line continuation
+                    Some(b'\r') | Some(b'\n') => {}
+                    Some(other) => s.push(*other as char),
+                    None => break,
+                }
+            }
+            _ => s.push(b as char),
+        }
+        i += 1;
+    }
+    (s, c.len())
+}
+
+/// Append the strings of the text objects in content stream `c`. Inside `TJ` arrays a large
+/// negative adjustment separates words.
+fn text_objects(c: &[u8], out: &mut String) {
+    let (mut in_text, mut in_array) = (false, false);
+    let mut i = 0;
+    while i < c.len() {
+        let b = c[i];
+        match b {
+            b'(' if in_text => {
+                let (s, next) = pdf_string(c, i);
+                out.push_str(&s);
+                i = next;
+                continue;
+            }
+            b'%' => { while i < c.len() && c[i] != b'\n' && c[i] != b'\r' { i += 1; } }
+            b'[' if in_text => in_array = true,
+            b']' if in_text => in_array = false,
+            b'-' | b'0'..=b'9' | b'.' if in_text && in_array => {
+                let start = i;
+                while i + 1 < c.len() && matches!(c[i + 1], b'-' | b'0'..=b'9' | b'.') { i += 1; }
+                let n: f64 = std::str::from_utf8(&c[start..=i]).ok().and_then(|t| t.parse().ok()).unwrap_or(0.0);
+                if n <= -200.0 { out.push(' '); }
+            }
+            b'A'..=b'Z' | b'a'..=b'z' | b'\'' | b'"' | b'*' => {
+                let start = i;
+                while i + 1 < c.len() && (c[i + 1].is_ascii_alphabetic() || c[i + 1] == b'*') { i += 1; }
+                match &c[start..=i] {
+                    b"BT" => in_text = true,
+                    b"ET" => { in_text = false; out.push('\n'); }
+                    b"Tj" | b"TJ" | b"'" | b"\"" | b"T*" | b"Td" | b"TD" | b"Tm" if in_text => out.push(' '),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+fn load_json<T: for<'de> Deserialize<'de> + Default>(kv: &KvStore, key: &str) -> T {
+    match kv.get(key) {
+        Some(KvValue::Json(j)) => serde_json::from_value(j).unwrap_or_default(),
+        _ => T::default(),
+    }
+}
+
+fn save_json<T: Serialize>(kv: &KvStore, key: String, value: &T) {
+    if let Ok(j) = serde_json::to_value(value) { kv.set(key, KvValue::Json(j), None, None); }
+}
+
+/// Drop the index entries of `path`; returns them.
+fn remove_doc(kv: &KvStore, database: &str, filestore: &str, path: &str) -> Option<DocEntry> {
+    let doc_key = Keys::fts_doc(database, filestore, path);
+    let Some(KvValue::Json(j)) = kv.get(&doc_key) else { return None };
+    kv.delete(&doc_key);
+    let doc: DocEntry = serde_json::from_value(j).ok()?;
+    for term in doc.terms.keys() {
+        let key = Keys::fts_term(database, filestore, term);
+        let mut postings: BTreeMap<String, u32> = load_json(kv, &key);
+        postings.remove(path);
+        if postings.is_empty() { kv.delete(&key); } else { save_json(kv, key, &postings); }
+    }
+    let stats_key = Keys::fts_stats(database, filestore);
+    let mut stats: IndexStats = load_json(kv, &stats_key);
+    stats.docs = stats.docs.saturating_sub(1);
+    stats.total_len = stats.total_len.saturating_sub(doc.len);
+    save_json(kv, stats_key, &stats);
+    Some(doc)
+}
+
+fn add_doc(kv: &KvStore, database: &str, filestore: &str, path: &str, doc: &DocEntry) {
+    for (term, tf) in &doc.terms {
+        let key = Keys::fts_term(database, filestore, term);
+        let mut postings: BTreeMap<String, u32> = load_json(kv, &key);
+        postings.insert(path.to_string(), *tf);
+        save_json(kv, key, &postings);
+    }
+    save_json(kv, Keys::fts_doc(database, filestore, path), doc);
+    let stats_key = Keys::fts_stats(database, filestore);
+    let mut stats: IndexStats = load_json(kv, &stats_key);
+    stats.docs += 1;
+    stats.total_len += doc.len;
+    save_json(kv, stats_key, &stats);
+}
+
+fn text_key(database: &str, filestore: &str, file_id: &str) -> String {
+    Keys::text(database, filestore, &Uuid::parse_str(file_id).unwrap_or_else(|_| Uuid::nil()))
+}
+
+/// (Re)index the live file `meta` with content `bytes`. A file that is not indexable (type,
+/// size, indexing off) is removed from the index instead.
+pub fn index_file(store: &SharedStore, database: &str, filestore: &str, meta: &FileMeta, bytes: &[u8], eff: &EffectiveConfig) {
+    let kv = store.kv_store(database, filestore);
+    remove_doc(&kv, database, filestore, &meta.logical_path);
+    let text = (eff.fulltext_index && bytes.len() as u64 <= eff.fulltext_max_bytes)
+        .then(|| extract_text(&meta.logical_path, meta.content_type.as_deref(), bytes, eff.fulltext_pdf))
+        .flatten();
+    let Some(text) = text else {
+        kv.delete(&text_key(database, filestore, &meta.id));
+        return;
+    };
+    let tokens = tokenize(&text);
+    let mut doc = DocEntry { id: meta.id.clone(), len: tokens.len() as u64, terms: BTreeMap::new() };
+    for t in tokens { *doc.terms.entry(t).or_insert(0) += 1; }
+    add_doc(&kv, database, filestore, &meta.logical_path, &doc);
+    kv.set(text_key(database, filestore, &meta.id), KvValue::Str(text), None, None);
+    crate::tprintln!("FILESTORE fulltext indexed fs={} path={} tokens={} terms={}", filestore, meta.logical_path, doc.len, doc.terms.len());
+}
+
+/// Remove a deleted file from the index.
+pub fn unindex_file(store: &SharedStore, database: &str, filestore: &str, path: &str) {
+    let kv = store.kv_store(database, filestore);
+    if let Some(doc) = remove_doc(&kv, database, filestore, path) {
+        kv.delete(&text_key(database, filestore, &doc.id));
+    }
+}
+
+/// Move the index entries of a renamed file; the content (and its extracted text) is unchanged.
+pub fn rename_file(store: &SharedStore, database: &str, filestore: &str, old_path: &str, new_path: &str) {
+    let kv = store.kv_store(database, filestore);
+    remove_doc(&kv, database, filestore, new_path);
+    if let Some(doc) = remove_doc(&kv, database, filestore, old_path) {
+        add_doc(&kv, database, filestore, new_path, &doc);
+    }
+}
+
+/// Up to `SNIPPET_CHARS` characters of `text` around the first word that is a query term,
+/// whitespace collapsed.
+fn snippet(text: &str, terms: &BTreeSet<String>) -> String {
+    let mut hit = None;
+    let mut word_start: Option<usize> = None;
+    for (i, ch) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (ch.is_alphanumeric(), word_start) {
+            (true, None) => word_start = Some(i),
+            (false, Some(s)) => {
+                if terms.contains(&text[s..i].to_lowercase()) { hit = Some(s); break; }
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    let hit = hit.unwrap_or(0);
+    let start = text[..hit].char_indices().rev().nth(SNIPPET_LEAD.saturating_sub(1)).map(|(i, _)| i).unwrap_or(0);
+    let window: String = text[start..].chars().take(SNIPPET_CHARS).collect();
+    window.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Files containing every term of `query`, best BM25 score first.
+pub fn search(store: &SharedStore, database: &str, filestore: &str, query: &str) -> Result<Vec<SearchHit>> {
+    let terms: BTreeSet<String> = tokenize(query).into_iter().collect();
+    if terms.is_empty() { bail!("filestore_search: the query has no searchable terms"); }
+    let kv = store.kv_store(database, filestore);
+    let postings: Vec<BTreeMap<String, u32>> = terms.iter().map(|t| load_json(&kv, &Keys::fts_term(database, filestore, t))).collect();
+    let stats: IndexStats = load_json(&kv, &Keys::fts_stats(database, filestore));
+    let n = stats.docs.max(1) as f64;
+    let avg_len = (stats.total_len as f64 / n).max(1.0);
+    let Some((first, rest)) = postings.split_first() else { return Ok(Vec::new()) };
+    let mut hits = Vec::new();
+    for path in first.keys().filter(|p| rest.iter().all(|pl| pl.contains_key(*p))) {
+        let doc: DocEntry = load_json(&kv, &Keys::fts_doc(database, filestore, path));
+        let norm = K1 * (1.0 - B + B * doc.len as f64 / avg_len);
+        let score: f64 = postings.iter().map(|pl| {
+            let df = pl.len() as f64;
+            let tf = pl.get(path).copied().unwrap_or(0) as f64;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            idf * tf * (K1 + 1.0) / (tf + norm)
+        }).sum();
+        let snippet = match kv.get(&text_key(database, filestore, &doc.id)) {
+            Some(KvValue::Str(text)) => snippet(&text, &terms),
+            _ => String::new(),
+        };
+        hits.push(SearchHit { path: path.clone(), score, snippet });
+    }
+    hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.path.cmp(&b.path)));
+    Ok(hits)
+}
@@ -1,20 +1,35 @@
 //! GC utilities for FILESTORE: dry-run and apply for tombstones and orphaned chunks.
 //! These are conservative and operate only on the in-memory KV namespaces.
+//! gc_apply honours retention and legal holds (see retention.rs); runs on a schedule and their
+//! history are in gc_scheduler.rs.
+
+use std::collections::HashSet;
 
 use anyhow::Result;
 use chrono::Utc;
 
-use crate::storage::{SharedStore, KvValue};
+use crate::storage::{KvStore, SharedStore, KvValue};
 
+use super::blob::stored_len;
+use super::chunker::chunk_ref_counts;
+use super::config::EffectiveConfig;
 use super::kv::Keys;
-use super::types::FileMeta;
-use super::config::GlobalFilestoreConfig;
+use super::registry::load_filestore_entry;
+use super::retention::legal_hold;
+use super::types::{FileMeta, FileVersion, Tree};
 
 #[derive(Debug, Clone, Default)]
 pub struct GcReport {
     pub files_tombstoned: i64,
     pub files_deleted: i64,
     pub orphan_chunks: i64,
+    /// Tombstones and versions kept back by a legal hold or the minimum retention
+    pub retained: i64,
+    pub versions_deleted: i64,
+    /// Unreferenced chunks and single-file blobs removed, and the bytes they held
+    pub chunks_deleted: i64,
+    pub blobs_deleted: i64,
+    pub bytes_reclaimed: u64,
 }
 
 /// Dry-run GC: scan for tombstoned files and orphaned chunks. Does not delete.
@@ -31,17 +46,23 @@ pub fn gc_dry_run(store: &SharedStore, database: &str, filestore: &str) -> Resul
             }
         }
     }
-    // Orphaned chunks detection not implemented yet (no ref indexes) — leave zero for now.
+    // Orphaned chunks: stored chunks no file meta references (left by updates and deletes)
+    rep.orphan_chunks = super::chunker::dedup_stats(store, database, filestore).orphan_chunks as i64;
     Ok(rep)
 }
 
-/// Apply GC with conservative behavior: remove only tombstoned file metadata.
-/// Chunk collection is postponed until reference tracking exists.
+/// Apply GC with conservative behavior: remove tombstoned file metadata and retained versions
+/// past their retention, then sweep unreferenced content (see `sweep_unreferenced`).
+/// The grace period is the filestore's `gc_grace_seconds`, else the global one.
 pub fn gc_apply(store: &SharedStore, database: &str, filestore: &str) -> Result<GcReport> {
     let kv = store.kv_store(database, filestore);
     let mut rep = GcReport::default();
-    let global = GlobalFilestoreConfig::default();
-    let grace = global.gc_grace_seconds as i64;
+    // Global layer comes from the [filestore] section of the server configuration
+    let global = crate::config::current().filestore.clone();
+    let fs_cfg = load_filestore_entry(store, database, filestore)?.map(|e| e.config).unwrap_or_default();
+    let eff = EffectiveConfig::from_layers(&global, &fs_cfg, None);
+    let grace = eff.gc_grace_seconds as i64;
+    let min_keep = eff.retention_min_seconds as i64;
     let now = Utc::now().timestamp();
     // Delete tombstoned file metas
     let path_prefix = Keys::path(database, filestore, "");
@@ -57,10 +78,89 @@ pub fn gc_apply(store: &SharedStore, database: &str, filestore: &str) -> Result<
                     // Respect grace period before permanent deletion
                     let age = now.saturating_sub(m.updated_at);
                     if age < grace { continue; }
+                    if age < min_keep || legal_hold(store, database, filestore, &m.logical_path).is_some() {
+                        rep.retained += 1;
+                        continue;
+                    }
                     if kv.delete(&k) { rep.files_deleted += 1; }
                 }
             }
         }
     }
+    // Retained versions: keys sort by file then version, so each file's latest come last
+    let version_prefix = Keys::version_prefix(database, filestore);
+    let mut version_keys: Vec<String> = kv.keys().into_iter().filter(|k| k.starts_with(&version_prefix)).collect();
+    version_keys.sort();
+    let versions: Vec<(String, FileVersion)> = version_keys.into_iter()
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value::<FileVersion>(j).ok().map(|v| (k, v)), _ => None })
+        .collect();
+    for (i, (k, v)) in versions.iter().enumerate() {
+        let newer = versions[i + 1..].iter().take_while(|(_, n)| n.file_id == v.file_id).count() as u64;
+        if newer < eff.retention_versions { continue; }
+        if now.saturating_sub(v.retired_at) < min_keep || legal_hold(store, database, filestore, &v.logical_path).is_some() {
+            rep.retained += 1;
+            continue;
+        }
+        if kv.delete(k) { rep.versions_deleted += 1; }
+    }
+    sweep_unreferenced(store, &kv, database, filestore, grace, now, &mut rep);
     Ok(rep)
 }
+
+/// Remove chunks and blobs nothing refers to any more. Content is stored before the meta that
+/// refers to it, so an unreferenced entry may belong to a write still in progress: a run marks
+/// what it finds unreferenced (`Keys::gc_mark`), and a later run removes it once the mark is at
+/// least `grace` seconds old. Marks of entries referenced again are dropped. Chunks held in an
+/// object store are left in place; only their index entry would be local.
+fn sweep_unreferenced(store: &SharedStore, kv: &KvStore, database: &str, filestore: &str, grace: i64, now: i64, rep: &mut GcReport) {
+    let referenced_chunks = chunk_ref_counts(store, database, filestore);
+    // Blobs belong to file metas (tombstones too, until removed above) and to tree entries
+    // that refer to a live file by id
+    let path_prefix = Keys::path_prefix(database, filestore);
+    let tree_prefix = Keys::tree_prefix(database, filestore);
+    let mut referenced_blobs: HashSet<String> = HashSet::new();
+    for k in kv.keys() {
+        match kv.get(&k) {
+            Some(KvValue::Json(j)) if k.starts_with(&path_prefix) => {
+                if let Ok(m) = serde_json::from_value::<FileMeta>(j) { referenced_blobs.insert(blob_id(&m.id)); }
+            }
+            Some(KvValue::Json(j)) if k.starts_with(&tree_prefix) => {
+                if let Ok(t) = serde_json::from_value::<Tree>(j) { referenced_blobs.extend(t.entries.iter().map(|e| blob_id(&e.file_id))); }
+            }
+            _ => {}
+        }
+    }
+
+    let chunk_prefix = Keys::chunk_prefix(database, filestore);
+    let blob_prefix = Keys::blob_prefix(database, filestore);
+    let mut unreferenced: HashSet<String> = HashSet::new();
+    for k in kv.keys() {
+        let is_chunk = k.strip_prefix(&chunk_prefix).is_some_and(|oid| !referenced_chunks.contains_key(oid));
+        let is_blob = k.strip_prefix(&blob_prefix).is_some_and(|id| !referenced_blobs.contains(id));
+        if !is_chunk && !is_blob { continue; }
+        let Some(value) = kv.get(&k) else { continue };
+        if !matches!(value, KvValue::Bytes(_)) { continue; }
+        let mark = Keys::gc_mark(database, filestore, &k);
+        unreferenced.insert(mark.clone());
+        match kv.get(&mark) {
+            Some(KvValue::Int(at)) if now.saturating_sub(at) >= grace => {
+                if kv.delete(&k) {
+                    rep.bytes_reclaimed += stored_len(&value);
+                    if is_chunk { rep.chunks_deleted += 1; } else { rep.blobs_deleted += 1; }
+                }
+                kv.delete(&mark);
+            }
+            Some(KvValue::Int(_)) => {}
+            _ => kv.set(mark, KvValue::Int(now), None, None),
+        }
+    }
+    let mark_prefix = Keys::gc_mark_prefix(database, filestore);
+    for k in kv.keys() {
+        if k.starts_with(&mark_prefix) && !unreferenced.contains(&k) { kv.delete(&k); }
+    }
+}
+
+/// Blob key suffix of a file id (ids that are not UUIDs store under the nil UUID, as in ops.rs).
+fn blob_id(file_id: &str) -> String {
+    uuid::Uuid::parse_str(file_id).unwrap_or_else(|_| uuid::Uuid::nil()).to_string()
+}
@@ -0,0 +1,174 @@
+//! Scheduled GC and the GC history.
+//!
+//! A filestore with a GC cadence (`gc_interval_seconds` in its config, else `[filestore]
+//! gc_interval_seconds`; 0 = off) is collected by the scheduler task ([`spawn_gc_scheduler`]),
+//! which wakes once a minute and runs `gc_apply` on every filestore whose last run is at least
+//! one interval old. Replicas do not collect. `GC FILESTORE <name>` runs it by hand.
+//!
+//! Every run, scheduled or manual, is recorded as a GcRun under `Keys::gc_run` (the latest
+//! `RUNS_KEPT`) with what it removed and the bytes it reclaimed; SHOW FILESTORE GC lists them.
+//! Runs of one filestore never overlap: a run started while another is going fails with
+//! `gc_running`.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::storage::{KvValue, SharedStore};
+
+use super::config::EffectiveConfig;
+use super::gc::gc_apply;
+use super::kv::Keys;
+use super::registry::list_filestore_entries;
+
+/// Runs kept per filestore; older ones are dropped as new ones are recorded.
+const RUNS_KEPT: usize = 100;
+
+/// One GC run of a filestore.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GcRun {
+    pub id: String,
+    /// "scheduled" | "manual"
+    pub trigger: String,
+    pub started_at: i64,
+    pub duration_ms: i64,
+    pub files_deleted: i64,
+    pub versions_deleted: i64,
+    pub chunks_deleted: i64,
+    pub blobs_deleted: i64,
+    /// Tombstones and versions kept back by a legal hold or the minimum retention
+    pub retained: i64,
+    pub bytes_reclaimed: u64,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// `<db>.<filestore>` of the runs in progress.
+static RUNNING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+struct RunningGuard(String);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) { RUNNING.lock().remove(&self.0); }
+}
+
+/// Run GC on a filestore now and record the run. Fails when another run of the filestore is in
+/// progress, and with the GC error (after recording it) when the run fails.
+pub fn run_gc(store: &SharedStore, database: &str, filestore: &str, trigger: &str) -> Result<GcRun> {
+    let key = format!("{}.{}", database, filestore);
+    if !RUNNING.lock().insert(key.clone()) { bail!("gc_running: {}", filestore); }
+    let _running = RunningGuard(key);
+
+    let started = Instant::now();
+    let started_at = Utc::now().timestamp();
+    let result = gc_apply(store, database, filestore);
+    let mut run = GcRun {
+        // Sortable id: nanosecond timestamp, then a random suffix against collisions
+        id: format!("{:020}-{}", Utc::now().timestamp_nanos_opt().unwrap_or_default(), &uuid::Uuid::new_v4().simple().to_string()[..8]),
+        trigger: trigger.to_string(),
+        started_at,
+        duration_ms: started.elapsed().as_millis() as i64,
+        ..Default::default()
+    };
+    match &result {
+        Ok(rep) => {
+            run.files_deleted = rep.files_deleted;
+            run.versions_deleted = rep.versions_deleted;
+            run.chunks_deleted = rep.chunks_deleted;
+            run.blobs_deleted = rep.blobs_deleted;
+            run.retained = rep.retained;
+            run.bytes_reclaimed = rep.bytes_reclaimed;
+        }
+        Err(e) => run.error = Some(e.to_string()),
+    }
+    record(store, database, filestore, &run);
+    crate::tprintln!("FILESTORE gc {} fs={} files={} versions={} chunks={} blobs={} retained={} reclaimed={}B took={}ms error={}",
+        trigger, filestore, run.files_deleted, run.versions_deleted, run.chunks_deleted, run.blobs_deleted, run.retained,
+        run.bytes_reclaimed, run.duration_ms, run.error.as_deref().unwrap_or("-"));
+    result.map(|_| run)
+}
+
+fn record(store: &SharedStore, database: &str, filestore: &str, run: &GcRun) {
+    let kv = store.kv_store(database, filestore);
+    if let Ok(j) = serde_json::to_value(run) { kv.set(Keys::gc_run(database, filestore, &run.id), KvValue::Json(j), None, None); }
+    let prefix = Keys::gc_run_prefix(database, filestore);
+    let mut keys: Vec<String> = kv.keys().into_iter().filter(|k| k.starts_with(&prefix)).collect();
+    if keys.len() > RUNS_KEPT {
+        keys.sort();
+        for k in &keys[..keys.len() - RUNS_KEPT] { kv.delete(k); }
+    }
+}
+
+/// Recorded runs, most recent first.
+pub fn list_gc_runs(store: &SharedStore, database: &str, filestore: &str) -> Vec<GcRun> {
+    let kv = store.kv_store(database, filestore);
+    let prefix = Keys::gc_run_prefix(database, filestore);
+    let mut keys: Vec<String> = kv.keys().into_iter().filter(|k| k.starts_with(&prefix)).collect();
+    keys.sort();
+    keys.into_iter().rev()
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value(j).ok(), _ => None })
+        .collect()
+}
+
+/// Whether a filestore with this config, last collected at `last_run`, is due at `now`.
+pub fn gc_due(eff: &EffectiveConfig, last_run: Option<i64>, now: i64) -> bool {
+    if eff.gc_interval_seconds == 0 { return false; }
+    last_run.is_none_or(|at| now.saturating_sub(at) >= eff.gc_interval_seconds as i64)
+}
+
+/// Run GC on the filestores of `database` that are due at `now`; returns the runs that completed.
+pub fn run_due(store: &SharedStore, database: &str, now: i64) -> Vec<GcRun> {
+    let entries = match list_filestore_entries(store, database) {
+        Ok(entries) => entries,
+        Err(e) => { tracing::warn!("gc scheduler: failed to list filestores of {}: {}", database, e); return Vec::new(); }
+    };
+    let global = crate::config::current().filestore.clone();
+    let mut out = Vec::new();
+    for e in entries {
+        let eff = EffectiveConfig::from_layers(&global, &e.config, None);
+        let last_run = list_gc_runs(store, database, &e.name).first().map(|r| r.started_at);
+        if !gc_due(&eff, last_run, now) { continue; }
+        match run_gc(store, database, &e.name, "scheduled") {
+            Ok(run) => out.push(run),
+            Err(err) => tracing::warn!("scheduled gc of filestore {}.{} failed: {}", database, e.name, err),
+        }
+    }
+    out
+}
+
+fn tick(store: &SharedStore) {
+    let reg = store.kv_registry();
+    let now = Utc::now().timestamp();
+    for db in crate::system_catalog::pg_catalog::pg_database::database_names(store) {
+        // The filestore registry lives in the database's default KV store
+        if !reg.list_stores(&db).iter().any(|s| s == crate::lua_bc::DEFAULT_KV_STORE) { continue; }
+        run_due(store, &db, now);
+    }
+}
+
+/// Start the GC scheduler task; it exits when `shutdown` turns true.
+pub fn spawn_gc_scheduler(store: SharedStore, mut shutdown: watch::Receiver<bool>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() { crate::tprintln!("[shutdown] filestore_gc_scheduler exiting on shutdown signal"); break; }
+                }
+                _ = tokio::time::sleep(Duration::from_secs(60)) => {
+                    if crate::server::replication::is_replica() { continue; }
+                    let store = store.clone();
+                    // GC walks whole namespaces; keep it off the async workers
+                    if let Err(e) = tokio::task::spawn_blocking(move || tick(&store)).await {
+                        tracing::warn!("filestore gc scheduler tick failed: {}", e);
+                    }
+                }
+            }
+        }
+    });
+}
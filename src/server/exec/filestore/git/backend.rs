@@ -12,7 +12,31 @@ pub struct GitRefUpdate {
     pub new_target: String,  // commit id
 }
 
+/// Credentials offered to a remote during fetch/push. Resolved per call from the filestore
+/// config and the environment; never persisted or logged.
+#[derive(Clone, Default)]
+pub struct GitCredentials {
+    pub username: Option<String>,
+    /// Token or password for HTTPS remotes
+    pub token: Option<String>,
+    /// Private key for SSH remotes; the SSH agent is tried when unset
+    pub ssh_key_path: Option<String>,
+}
+
+impl std::fmt::Debug for GitCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitCredentials")
+            .field("username", &self.username)
+            .field("token", &self.token.as_ref().map(|_| "***"))
+            .field("ssh_key_path", &self.ssh_key_path)
+            .finish()
+    }
+}
+
 /// Trait for FILESTORE Git operations. Concrete impls live in gitoxide/libgit2 backends.
+///
+/// Ids are lowercase hex object ids. `write_tree` takes flat `(path, blob_id)` entries with
+/// '/'-separated paths and builds the nested trees; `read_tree` returns the same shape.
 #[allow(unused_variables)]
 pub trait GitBackend: Send + Sync {
     fn ensure_repo(&self, repo_path: &str, remote: Option<&str>) -> Result<()> { Err(anyhow!("not_implemented")) }
@@ -20,7 +44,55 @@ pub trait GitBackend: Send + Sync {
     fn write_tree(&self, repo_path: &str, entries: &[(String, String)]) -> Result<String> { Err(anyhow!("not_implemented")) }
     fn write_commit(&self, repo_path: &str, message: &str, author: &str, parents: &[String], tree_id: &str) -> Result<GitCommitIds> { Err(anyhow!("not_implemented")) }
     fn update_ref(&self, repo_path: &str, update: &GitRefUpdate) -> Result<()> { Err(anyhow!("not_implemented")) }
+    /// Commit id a local reference points to, None when the reference does not exist.
+    fn resolve_ref(&self, repo_path: &str, reference: &str) -> Result<Option<String>> { Err(anyhow!("not_implemented")) }
+    /// Files of a commit's tree as `(path, blob_id)`, sorted by path.
+    fn read_tree(&self, repo_path: &str, commit_id: &str) -> Result<Vec<(String, String)>> { Err(anyhow!("not_implemented")) }
+    fn read_blob(&self, repo_path: &str, blob_id: &str) -> Result<Vec<u8>> { Err(anyhow!("not_implemented")) }
     fn ls_remote(&self, remote: &str) -> Result<Vec<(String, String)>> { Err(anyhow!("not_implemented")) }
-    fn fetch(&self, repo_path: &str, remote: &str, reference: &str) -> Result<()> { Err(anyhow!("not_implemented")) }
-    fn push(&self, repo_path: &str, remote: &str, reference: &str) -> Result<()> { Err(anyhow!("not_implemented")) }
+    /// Fetch `reference` (e.g. refs/heads/main) from the remote into the local repo.
+    /// Returns the remote commit id, None when the remote has no such reference.
+    fn fetch(&self, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials) -> Result<Option<String>> { Err(anyhow!("not_implemented")) }
+    /// Push the local `reference` to the same name on the remote; `force` allows non-fast-forward updates.
+    fn push(&self, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials, force: bool) -> Result<()> { Err(anyhow!("not_implemented")) }
+}
+
+/// Split a '/'-separated path into its directory ("" at the root) and last segment.
+pub(crate) fn split_dir(path: &str) -> (&str, &str) {
+    match path.rfind('/') { Some(i) => (&path[..i], &path[i + 1..]), None => ("", path) }
+}
+
+/// Group flat `(path, id)` entries by directory ("" is the root), deepest directories first so
+/// subtrees can be written before the trees that contain them. Every ancestor directory is listed.
+pub(crate) fn tree_levels(entries: &[(String, String)]) -> Vec<(String, Vec<(String, String)>)> {
+    use std::collections::BTreeMap;
+    let mut dirs: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    dirs.entry(String::new()).or_default();
+    for (path, id) in entries {
+        let (dir, name) = split_dir(path.trim_matches('/'));
+        dirs.entry(dir.to_string()).or_default().push((name.to_string(), id.clone()));
+        let mut d = dir;
+        while !d.is_empty() {
+            let (parent, _) = split_dir(d);
+            dirs.entry(parent.to_string()).or_default();
+            d = parent;
+        }
+    }
+    let depth = |d: &str| if d.is_empty() { 0 } else { d.matches('/').count() + 1 };
+    let mut out: Vec<(String, Vec<(String, String)>)> = dirs.into_iter().collect();
+    out.sort_by(|a, b| depth(&b.0).cmp(&depth(&a.0)).then(a.0.cmp(&b.0)));
+    out
+}
+
+/// Local remote-tracking ref a fetched `refs/heads/<b>` is stored under.
+pub(crate) fn tracking_ref(reference: &str) -> String {
+    format!("refs/remotes/origin/{}", reference.strip_prefix("refs/heads/").unwrap_or(reference))
+}
+
+/// Split a "Name <email>" author string; a bare value is used as both.
+pub(crate) fn split_author(author: &str) -> (String, String) {
+    match (author.find('<'), author.rfind('>')) {
+        (Some(a), Some(b)) if a < b => (author[..a].trim().to_string(), author[a + 1..b].trim().to_string()),
+        _ => (author.trim().to_string(), author.trim().to_string()),
+    }
 }
@@ -1,12 +1,13 @@
-//! Composite backend that prefers gitoxide and can fallback to libgit2 for push.
+//! Composite backend that prefers gitoxide and falls back to libgit2 for whatever gitoxide
+//! reports as unsupported (push always, everything when built without feature `gitoxide`).
 
-use super::backend::{GitBackend, GitCommitIds, GitRefUpdate};
+use super::backend::{GitBackend, GitCommitIds, GitCredentials, GitRefUpdate};
 use anyhow::Result;
 
 pub struct CompositeGitBackend {
     gitoxide: Box<dyn GitBackend>,
     #[cfg(feature = "libgit2-push")]
-    libgit2: Option<Box<dyn GitBackend>>, // optional fallback used for push
+    libgit2: Option<Box<dyn GitBackend>>, // optional fallback for unsupported operations
 }
 
 impl CompositeGitBackend {
@@ -19,44 +20,64 @@ impl CompositeGitBackend {
     pub fn new(gitoxide: Box<dyn GitBackend>) -> Self {
         Self { gitoxide }
     }
+
+    /// Run `op` on gitoxide; when it answers `*_unsupported` and libgit2 is available, rerun on libgit2.
+    fn with_fallback<T>(&self, name: &str, op: impl Fn(&dyn GitBackend) -> Result<T>) -> Result<T> {
+        match op(self.gitoxide.as_ref()) {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                #[cfg(feature = "libgit2-push")]
+                {
+                    if e.to_string().contains("_unsupported") {
+                        if let Some(ref l2) = self.libgit2 {
+                            crate::tprintln!("gitoxide {} unsupported, falling back to libgit2: {}", name, e);
+                            return op(l2.as_ref());
+                        }
+                    }
+                }
+                let _ = name;
+                Err(e)
+            }
+        }
+    }
 }
 
 impl GitBackend for CompositeGitBackend {
     fn ensure_repo(&self, repo_path: &str, remote: Option<&str>) -> Result<()> {
-        self.gitoxide.ensure_repo(repo_path, remote)
+        self.gitoxide.ensure_repo(repo_path, remote)?;
+        // Both backends work on the same bare repository; make sure it exists for the fallback too
+        #[cfg(feature = "libgit2-push")]
+        if let Some(ref l2) = self.libgit2 { l2.ensure_repo(repo_path, remote)?; }
+        Ok(())
     }
     fn write_blob(&self, repo_path: &str, path: &str, data: &[u8]) -> Result<String> {
-        self.gitoxide.write_blob(repo_path, path, data)
+        self.with_fallback("write_blob", |b| b.write_blob(repo_path, path, data))
     }
     fn write_tree(&self, repo_path: &str, entries: &[(String, String)]) -> Result<String> {
-        self.gitoxide.write_tree(repo_path, entries)
+        self.with_fallback("write_tree", |b| b.write_tree(repo_path, entries))
     }
     fn write_commit(&self, repo_path: &str, message: &str, author: &str, parents: &[String], tree_id: &str) -> Result<GitCommitIds> {
-        self.gitoxide.write_commit(repo_path, message, author, parents, tree_id)
+        self.with_fallback("write_commit", |b| b.write_commit(repo_path, message, author, parents, tree_id))
     }
     fn update_ref(&self, repo_path: &str, update: &GitRefUpdate) -> Result<()> {
-        self.gitoxide.update_ref(repo_path, update)
+        self.with_fallback("update_ref", |b| b.update_ref(repo_path, update))
+    }
+    fn resolve_ref(&self, repo_path: &str, reference: &str) -> Result<Option<String>> {
+        self.with_fallback("resolve_ref", |b| b.resolve_ref(repo_path, reference))
+    }
+    fn read_tree(&self, repo_path: &str, commit_id: &str) -> Result<Vec<(String, String)>> {
+        self.with_fallback("read_tree", |b| b.read_tree(repo_path, commit_id))
+    }
+    fn read_blob(&self, repo_path: &str, blob_id: &str) -> Result<Vec<u8>> {
+        self.with_fallback("read_blob", |b| b.read_blob(repo_path, blob_id))
     }
     fn ls_remote(&self, remote: &str) -> Result<Vec<(String, String)>> {
-        self.gitoxide.ls_remote(remote)
+        self.with_fallback("ls_remote", |b| b.ls_remote(remote))
     }
-    fn fetch(&self, repo_path: &str, remote: &str, reference: &str) -> Result<()> {
-        self.gitoxide.fetch(repo_path, remote, reference)
+    fn fetch(&self, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials) -> Result<Option<String>> {
+        self.with_fallback("fetch", |b| b.fetch(repo_path, remote, reference, creds))
     }
-    fn push(&self, repo_path: &str, remote: &str, reference: &str) -> Result<()> {
-        // For now just call gitoxide; later we can detect Unsupported and fallback.
-        #[cfg(feature = "libgit2-push")]
-        {
-            if let Err(e) = self.gitoxide.push(repo_path, remote, reference) {
-                crate::tprintln!("gitoxide push error, attempting libgit2 fallback: {}", e);
-                if let Some(ref l2) = self.libgit2 { return l2.push(repo_path, remote, reference); }
-                return Err(e);
-            }
-            return Ok(());
-        }
-        #[cfg(not(feature = "libgit2-push"))]
-        {
-            self.gitoxide.push(repo_path, remote, reference)
-        }
+    fn push(&self, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials, force: bool) -> Result<()> {
+        self.with_fallback("push", |b| b.push(repo_path, remote, reference, creds, force))
     }
 }
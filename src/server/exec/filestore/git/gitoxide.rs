@@ -1,11 +1,12 @@
 //! Gitoxide-backed implementation of `GitBackend`.
-//! Minimal plumbing now to keep builds green and provide clear Unsupported errors
-//! where functionality isn't wired yet. Full implementation will follow the plan
-//! in `plans/filestore_git_backend_plan.md`.
+//! With feature `gitoxide` this does all local plumbing (objects, trees, commits, refs) and
+//! fetches over file://, ssh:// and git:// remotes. gix has no push and no HTTP transport in
+//! this build; those return `*_unsupported` errors so `composite` can fall back to libgit2.
+//! Without the feature every operation but `ensure_repo` is unsupported.
 
 use anyhow::{anyhow, Result};
 
-use super::backend::{GitBackend, GitCommitIds, GitRefUpdate};
+use super::backend::{GitBackend, GitCommitIds, GitCredentials, GitRefUpdate};
 
 #[derive(Debug, Default, Clone)]
 pub struct GitoxideBackend;
@@ -14,10 +15,180 @@ impl GitoxideBackend {
     pub fn new() -> Self { Self }
 }
 
+#[cfg(feature = "gitoxide")]
+mod imp {
+    use std::collections::BTreeMap;
+    use std::sync::atomic::AtomicBool;
+
+    use anyhow::{anyhow, bail, Result};
+    use gix::ObjectId;
+
+    use super::super::backend::{split_author, split_dir, tracking_ref, tree_levels, GitCommitIds, GitCredentials};
+
+    pub fn open(repo_path: &str) -> Result<gix::Repository> {
+        gix::open(repo_path).map_err(|e| anyhow!("gitoxide_open_failed: {}", e))
+    }
+
+    pub fn ensure_repo(repo_path: &str) -> Result<()> {
+        if gix::open(repo_path).is_ok() { return Ok(()); }
+        std::fs::create_dir_all(repo_path)?;
+        gix::init_bare(repo_path).map_err(|e| anyhow!("gitoxide_init_failed: {}", e))?;
+        crate::tprintln!("gitoxide.ensure_repo initialized bare repo {}", repo_path);
+        Ok(())
+    }
+
+    fn oid(id: &str) -> Result<ObjectId> {
+        ObjectId::from_hex(id.as_bytes()).map_err(|_| anyhow!("gitoxide_invalid_oid: {}", id))
+    }
+
+    /// Serialize tree entries in git order: names compare bytewise, directories as if followed by '/'.
+    fn encode_tree(entries: &mut Vec<(String, ObjectId, bool)>) -> Vec<u8> {
+        let sort_key = |name: &str, is_tree: bool| { let mut k = name.as_bytes().to_vec(); if is_tree { k.push(b'/'); } k };
+        entries.sort_by(|a, b| sort_key(&a.0, a.2).cmp(&sort_key(&b.0, b.2)));
+        let mut buf = Vec::new();
+        for (name, id, is_tree) in entries.iter() {
+            buf.extend_from_slice(if *is_tree { b"40000 " } else { b"100644 " });
+            buf.extend_from_slice(name.as_bytes());
+            buf.push(0);
+            buf.extend_from_slice(id.as_bytes());
+        }
+        buf
+    }
+
+    pub fn write_blob(repo_path: &str, data: &[u8]) -> Result<String> {
+        Ok(open(repo_path)?.write_blob(data)?.to_string())
+    }
+
+    pub fn write_tree(repo_path: &str, entries: &[(String, String)]) -> Result<String> {
+        let repo = open(repo_path)?;
+        let mut written: BTreeMap<String, ObjectId> = BTreeMap::new();
+        for (dir, files) in tree_levels(entries) {
+            let mut items: Vec<(String, ObjectId, bool)> = Vec::new();
+            for (name, id) in files { items.push((name, oid(&id)?, false)); }
+            for (sub, sub_id) in written.iter() {
+                let (parent, name) = split_dir(sub);
+                if parent == dir && !sub.is_empty() { items.push((name.to_string(), *sub_id, true)); }
+            }
+            let id = repo.write_buf(gix::object::Kind::Tree, &encode_tree(&mut items))?.detach();
+            written.insert(dir, id);
+        }
+        Ok(written.get("").map(|o| o.to_string()).unwrap_or_default())
+    }
+
+    pub fn write_commit(repo_path: &str, message: &str, author: &str, parents: &[String], tree_id: &str) -> Result<GitCommitIds> {
+        let repo = open(repo_path)?;
+        let (name, email) = split_author(author);
+        let now = chrono::Utc::now().timestamp();
+        let mut text = format!("tree {}\n", oid(tree_id)?);
+        for p in parents { text.push_str(&format!("parent {}\n", oid(p)?)); }
+        text.push_str(&format!("author {} <{}> {} +0000\ncommitter {} <{}> {} +0000\n\n", name, email, now, name, email, now));
+        text.push_str(message);
+        if !message.ends_with('\n') { text.push('\n'); }
+        let id = repo.write_buf(gix::object::Kind::Commit, text.as_bytes())?;
+        Ok(GitCommitIds { tree_id: tree_id.to_string(), commit_id: id.to_string() })
+    }
+
+    pub fn update_ref(repo_path: &str, reference: &str, target: &str) -> Result<()> {
+        let repo = open(repo_path)?;
+        repo.reference(reference, oid(target)?, gix::refs::transaction::PreviousValue::Any, "clarium filestore sync")?;
+        Ok(())
+    }
+
+    pub fn resolve_ref(repo_path: &str, reference: &str) -> Result<Option<String>> {
+        let repo = open(repo_path)?;
+        Ok(repo.try_find_reference(reference)?.and_then(|r| r.target().try_id().map(|id| id.to_string())))
+    }
+
+    pub fn read_tree(repo_path: &str, commit_id: &str) -> Result<Vec<(String, String)>> {
+        let repo = open(repo_path)?;
+        let tree_id = repo.find_object(oid(commit_id)?)?.try_into_commit()?.tree_id()?.detach();
+        let mut out: Vec<(String, String)> = Vec::new();
+        let mut pending: Vec<(String, ObjectId)> = vec![(String::new(), tree_id)];
+        while let Some((prefix, id)) = pending.pop() {
+            let obj = repo.find_object(id)?;
+            for entry in gix::objs::TreeRefIter::from_bytes(&obj.data) {
+                let entry = entry?;
+                let path = format!("{}{}", prefix, entry.filename);
+                if entry.mode.is_tree() { pending.push((format!("{}/", path), entry.oid.to_owned())); }
+                else if entry.mode.is_blob() { out.push((path, entry.oid.to_string())); }
+            }
+        }
+        out.sort();
+        Ok(out)
+    }
+
+    pub fn read_blob(repo_path: &str, blob_id: &str) -> Result<Vec<u8>> {
+        let repo = open(repo_path)?;
+        Ok(repo.find_object(oid(blob_id)?)?.detach().data)
+    }
+
+    fn check_transport(remote: &str, op: &str) -> Result<()> {
+        let lower = remote.to_ascii_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") { bail!("gitoxide_{}_unsupported: no HTTP transport in this build", op); }
+        Ok(())
+    }
+
+    pub fn fetch(repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials) -> Result<Option<String>> {
+        check_transport(remote, "fetch")?;
+        let repo = open(repo_path)?;
+        let tracking = tracking_ref(reference);
+        let spec = format!("+{}:{}", reference, tracking);
+        let r = repo.remote_at(remote)?.with_refspecs(Some(spec.as_str()), gix::remote::Direction::Fetch)?;
+        let (username, token) = (creds.username.clone(), creds.token.clone());
+        let conn = r.connect(gix::remote::Direction::Fetch)?.with_credentials(move |action| match action {
+            gix::credentials::helper::Action::Get(ctx) => match (username.clone(), token.clone()) {
+                (Some(user), Some(password)) => Ok(Some(gix::credentials::protocol::Outcome {
+                    identity: gix::sec::identity::Account { username: user, password },
+                    next: ctx.into(),
+                })),
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        });
+        let outcome = conn
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .receive(gix::progress::Discard, &AtomicBool::new(false))
+            .map_err(|e| anyhow!("git_fetch_failed: {}", e))?;
+        let head = outcome.ref_map.remote_refs.iter().find_map(|r| {
+            let (name, target, peeled) = r.unpack();
+            if name == reference.as_bytes() { peeled.or(target).map(|id| id.to_owned()) } else { None }
+        });
+        match head {
+            Some(id) => {
+                repo.reference(tracking.as_str(), id, gix::refs::transaction::PreviousValue::Any, "clarium filestore fetch")?;
+                Ok(Some(id.to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "gitoxide")]
+impl GitBackend for GitoxideBackend {
+    fn ensure_repo(&self, repo_path: &str, _remote: Option<&str>) -> Result<()> { imp::ensure_repo(repo_path) }
+    fn write_blob(&self, repo_path: &str, _path: &str, data: &[u8]) -> Result<String> { imp::write_blob(repo_path, data) }
+    fn write_tree(&self, repo_path: &str, entries: &[(String, String)]) -> Result<String> { imp::write_tree(repo_path, entries) }
+    fn write_commit(&self, repo_path: &str, message: &str, author: &str, parents: &[String], tree_id: &str) -> Result<GitCommitIds> {
+        imp::write_commit(repo_path, message, author, parents, tree_id)
+    }
+    fn update_ref(&self, repo_path: &str, update: &GitRefUpdate) -> Result<()> { imp::update_ref(repo_path, &update.reference, &update.new_target) }
+    fn resolve_ref(&self, repo_path: &str, reference: &str) -> Result<Option<String>> { imp::resolve_ref(repo_path, reference) }
+    fn read_tree(&self, repo_path: &str, commit_id: &str) -> Result<Vec<(String, String)>> { imp::read_tree(repo_path, commit_id) }
+    fn read_blob(&self, repo_path: &str, blob_id: &str) -> Result<Vec<u8>> { imp::read_blob(repo_path, blob_id) }
+    fn ls_remote(&self, _remote: &str) -> Result<Vec<(String, String)>> { Err(anyhow!("gitoxide_ls_remote_unsupported")) }
+    fn fetch(&self, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials) -> Result<Option<String>> {
+        imp::fetch(repo_path, remote, reference, creds)
+    }
+    fn push(&self, _repo_path: &str, _remote: &str, _reference: &str, _creds: &GitCredentials, _force: bool) -> Result<()> {
+        // Explicit unsupported so `composite` can fallback to libgit2 when enabled.
+        Err(anyhow!("gitoxide_push_unsupported"))
+    }
+}
+
+#[cfg(not(feature = "gitoxide"))]
 impl GitBackend for GitoxideBackend {
     fn ensure_repo(&self, _repo_path: &str, _remote: Option<&str>) -> Result<()> {
-        // TODO: integrate gix repository initialization/open when feature is enabled
-        crate::tprintln!("gitoxide.ensure_repo placeholder ok");
+        crate::tprintln!("gitoxide.ensure_repo placeholder ok (feature 'gitoxide' disabled)");
         Ok(())
     }
 
@@ -37,15 +208,27 @@ impl GitBackend for GitoxideBackend {
         Err(anyhow!("gitoxide_update_ref_unsupported"))
     }
 
+    fn resolve_ref(&self, _repo_path: &str, _reference: &str) -> Result<Option<String>> {
+        Err(anyhow!("gitoxide_resolve_ref_unsupported"))
+    }
+
+    fn read_tree(&self, _repo_path: &str, _commit_id: &str) -> Result<Vec<(String, String)>> {
+        Err(anyhow!("gitoxide_read_tree_unsupported"))
+    }
+
+    fn read_blob(&self, _repo_path: &str, _blob_id: &str) -> Result<Vec<u8>> {
+        Err(anyhow!("gitoxide_read_blob_unsupported"))
+    }
+
     fn ls_remote(&self, _remote: &str) -> Result<Vec<(String, String)>> {
         Err(anyhow!("gitoxide_ls_remote_unsupported"))
     }
 
-    fn fetch(&self, _repo_path: &str, _remote: &str, _reference: &str) -> Result<()> {
+    fn fetch(&self, _repo_path: &str, _remote: &str, _reference: &str, _creds: &GitCredentials) -> Result<Option<String>> {
         Err(anyhow!("gitoxide_fetch_unsupported"))
     }
 
-    fn push(&self, _repo_path: &str, _remote: &str, _reference: &str) -> Result<()> {
+    fn push(&self, _repo_path: &str, _remote: &str, _reference: &str, _creds: &GitCredentials, _force: bool) -> Result<()> {
         // Explicit unsupported so `composite` can fallback to libgit2 when enabled.
         Err(anyhow!("gitoxide_push_unsupported"))
     }
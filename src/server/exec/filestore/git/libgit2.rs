@@ -1,25 +1,171 @@
-//! libgit2-backed implementation of `GitBackend` used only for push fallback.
+//! libgit2-backed implementation of `GitBackend`: full plumbing plus fetch/push over any
+//! transport libgit2 supports. Used as the push fallback for gitoxide and selectable on its own.
 //! Compiled when feature `libgit2-push` is enabled.
 
-use anyhow::{anyhow, Result};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::path::Path;
 
-use super::backend::{GitBackend, GitCommitIds, GitRefUpdate};
+use anyhow::{anyhow, bail, Result};
+use git2::{Cred, CredentialType, ErrorCode, ObjectType, Oid, RemoteCallbacks, Repository, TreeWalkMode, TreeWalkResult};
+
+use super::backend::{split_author, split_dir, tracking_ref, tree_levels, GitBackend, GitCommitIds, GitCredentials, GitRefUpdate};
+
+/// Credential callbacks are retried by libgit2 on auth failure; give up after this many.
+const MAX_AUTH_ATTEMPTS: usize = 3;
 
 #[derive(Debug, Default, Clone)]
 pub struct Libgit2Backend;
 
 impl Libgit2Backend { pub fn new() -> Self { Self } }
 
+fn open(repo_path: &str) -> Result<Repository> {
+    Repository::open_bare(repo_path).map_err(|e| anyhow!("libgit2_open_failed: {}", e.message()))
+}
+
+fn oid(id: &str) -> Result<Oid> {
+    Oid::from_str(id).map_err(|_| anyhow!("libgit2_invalid_oid: {}", id))
+}
+
+/// Callbacks answering credential requests from `creds`: SSH key file or agent, then
+/// user/token for HTTPS. Secrets never reach the log.
+fn callbacks<'a>(creds: &'a GitCredentials, attempts: &'a Cell<usize>) -> RemoteCallbacks<'a> {
+    let mut cb = RemoteCallbacks::new();
+    cb.credentials(move |_url, username_from_url, allowed| {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() > MAX_AUTH_ATTEMPTS { return Err(git2::Error::from_str("git_auth_failed")); }
+        let user = creds.username.as_deref().or(username_from_url).unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) { return Cred::username(user); }
+        if allowed.contains(CredentialType::SSH_KEY) {
+            return match creds.ssh_key_path.as_deref() {
+                Some(key) => Cred::ssh_key(user, None, Path::new(key), None),
+                None => Cred::ssh_key_from_agent(user),
+            };
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(tok) = creds.token.as_deref() { return Cred::userpass_plaintext(user, tok); }
+        }
+        Cred::default()
+    });
+    cb
+}
+
 impl GitBackend for Libgit2Backend {
-    fn ensure_repo(&self, _repo_path: &str, _remote: Option<&str>) -> Result<()> { Ok(()) }
-    fn write_blob(&self, _repo_path: &str, _path: &str, _data: &[u8]) -> Result<String> { Err(anyhow!("libgit2_write_blob_unsupported")) }
-    fn write_tree(&self, _repo_path: &str, _entries: &[(String, String)]) -> Result<String> { Err(anyhow!("libgit2_write_tree_unsupported")) }
-    fn write_commit(&self, _repo_path: &str, _message: &str, _author: &str, _parents: &[String], _tree_id: &str) -> Result<GitCommitIds> { Err(anyhow!("libgit2_write_commit_unsupported")) }
-    fn update_ref(&self, _repo_path: &str, _update: &GitRefUpdate) -> Result<()> { Err(anyhow!("libgit2_update_ref_unsupported")) }
-    fn ls_remote(&self, _remote: &str) -> Result<Vec<(String, String)>> { Err(anyhow!("libgit2_ls_remote_unsupported")) }
-    fn fetch(&self, _repo_path: &str, _remote: &str, _reference: &str) -> Result<()> { Err(anyhow!("libgit2_fetch_unsupported")) }
-    fn push(&self, _repo_path: &str, _remote: &str, _reference: &str) -> Result<()> {
-        // Minimal placeholder; real push implementation can be added later.
-        Err(anyhow!("libgit2_push_unsupported"))
+    fn ensure_repo(&self, repo_path: &str, _remote: Option<&str>) -> Result<()> {
+        if Repository::open_bare(repo_path).is_ok() { return Ok(()); }
+        std::fs::create_dir_all(repo_path)?;
+        Repository::init_bare(repo_path).map_err(|e| anyhow!("libgit2_init_failed: {}", e.message()))?;
+        crate::tprintln!("libgit2.ensure_repo initialized bare repo {}", repo_path);
+        Ok(())
+    }
+
+    fn write_blob(&self, repo_path: &str, _path: &str, data: &[u8]) -> Result<String> {
+        Ok(open(repo_path)?.blob(data)?.to_string())
+    }
+
+    fn write_tree(&self, repo_path: &str, entries: &[(String, String)]) -> Result<String> {
+        let repo = open(repo_path)?;
+        let mut written: BTreeMap<String, Oid> = BTreeMap::new();
+        for (dir, files) in tree_levels(entries) {
+            let mut tb = repo.treebuilder(None)?;
+            for (name, id) in files { tb.insert(name.as_str(), oid(&id)?, 0o100644)?; }
+            for (sub, sub_id) in written.iter() {
+                let (parent, name) = split_dir(sub);
+                if parent == dir && !sub.is_empty() { tb.insert(name, *sub_id, 0o040000)?; }
+            }
+            written.insert(dir, tb.write()?);
+        }
+        Ok(written.get("").map(|o| o.to_string()).unwrap_or_default())
+    }
+
+    fn write_commit(&self, repo_path: &str, message: &str, author: &str, parents: &[String], tree_id: &str) -> Result<GitCommitIds> {
+        let repo = open(repo_path)?;
+        let (name, email) = split_author(author);
+        let sig = git2::Signature::now(&name, &email)?;
+        let tree = repo.find_tree(oid(tree_id)?)?;
+        let parent_commits = parents.iter().map(|p| repo.find_commit(oid(p)?).map_err(anyhow::Error::from)).collect::<Result<Vec<_>>>()?;
+        let parent_refs: Vec<&git2::Commit> = parent_commits.iter().collect();
+        let id = repo.commit(None, &sig, &sig, message, &tree, &parent_refs)?;
+        Ok(GitCommitIds { tree_id: tree_id.to_string(), commit_id: id.to_string() })
+    }
+
+    fn update_ref(&self, repo_path: &str, update: &GitRefUpdate) -> Result<()> {
+        open(repo_path)?.reference(&update.reference, oid(&update.new_target)?, true, "clarium filestore sync")?;
+        Ok(())
+    }
+
+    fn resolve_ref(&self, repo_path: &str, reference: &str) -> Result<Option<String>> {
+        match open(repo_path)?.find_reference(reference) {
+            Ok(r) => Ok(r.target().map(|o| o.to_string())),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn read_tree(&self, repo_path: &str, commit_id: &str) -> Result<Vec<(String, String)>> {
+        let repo = open(repo_path)?;
+        let tree = repo.find_commit(oid(commit_id)?)?.tree()?;
+        let mut out: Vec<(String, String)> = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(ObjectType::Blob) {
+                if let Some(name) = entry.name() { out.push((format!("{}{}", root, name), entry.id().to_string())); }
+            }
+            TreeWalkResult::Ok
+        })?;
+        out.sort();
+        Ok(out)
+    }
+
+    fn read_blob(&self, repo_path: &str, blob_id: &str) -> Result<Vec<u8>> {
+        Ok(open(repo_path)?.find_blob(oid(blob_id)?)?.content().to_vec())
+    }
+
+    fn ls_remote(&self, remote: &str) -> Result<Vec<(String, String)>> {
+        let mut r = git2::Remote::create_detached(remote)?;
+        r.connect(git2::Direction::Fetch)?;
+        Ok(r.list()?.iter().map(|h| (h.name().to_string(), h.oid().to_string())).collect())
+    }
+
+    fn fetch(&self, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials) -> Result<Option<String>> {
+        let repo = open(repo_path)?;
+        let mut r = repo.remote_anonymous(remote)?;
+        let attempts = Cell::new(0);
+        let mut fo = git2::FetchOptions::new();
+        fo.remote_callbacks(callbacks(creds, &attempts));
+        let tracking = tracking_ref(reference);
+        let spec = format!("+{}:{}", reference, tracking);
+        r.fetch(&[spec.as_str()], Some(&mut fo), None).map_err(|e| anyhow!("git_fetch_failed: {}", e.message()))?;
+        // The advertised refs stay readable after the fetch disconnects
+        let head = r.list()?.iter().find(|h| h.name() == reference).map(|h| h.oid());
+        match head {
+            Some(id) => {
+                repo.reference(&tracking, id, true, "clarium filestore fetch")?;
+                Ok(Some(id.to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn push(&self, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials, force: bool) -> Result<()> {
+        let repo = open(repo_path)?;
+        let mut r = repo.remote_anonymous(remote)?;
+        let attempts = Cell::new(0);
+        let rejected: RefCell<Option<String>> = RefCell::new(None);
+        let mut cb = callbacks(creds, &attempts);
+        cb.push_update_reference(|refname, status| {
+            if let Some(msg) = status { *rejected.borrow_mut() = Some(format!("{}: {}", refname, msg)); }
+            Ok(())
+        });
+        let mut po = git2::PushOptions::new();
+        po.remote_callbacks(cb);
+        let spec = format!("{}{}:{}", if force { "+" } else { "" }, reference, reference);
+        r.push(&[spec.as_str()], Some(&mut po)).map_err(|e| anyhow!("git_push_failed: {}", e.message()))?;
+        drop(po);
+        if let Some(msg) = rejected.into_inner() { bail!("git_push_rejected: {}", msg); }
+        // Keep the tracking ref in step so the next fetch sees no divergence
+        if let Ok(Some(head)) = self.resolve_ref(repo_path, reference) {
+            repo.reference(&tracking_ref(reference), oid(&head)?, true, "clarium filestore push")?;
+        }
+        Ok(())
     }
 }
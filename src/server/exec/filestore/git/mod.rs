@@ -1,5 +1,5 @@
 //! Git backends for FILESTORE: gitoxide primary, optional libgit2 fallback for push.
-//! This is a scaffold to keep the crate buildable while we implement details.
+//! `sync` drives SYNC FILESTORE ... PUSH/PULL against the configured remote.
 
 pub mod backend;
 pub mod gitoxide;
@@ -7,5 +7,7 @@ pub mod gitoxide;
 pub mod libgit2;
 pub mod composite;
 pub mod ops;
+pub mod sync;
 
-pub use backend::{GitBackend, GitCommitIds, GitRefUpdate};
\ No newline at end of file
+pub use backend::{GitBackend, GitCommitIds, GitCredentials, GitRefUpdate};
+pub use sync::{ConflictPolicy, SyncOptions, SyncRun, sync_push, sync_pull};
\ No newline at end of file
@@ -2,7 +2,7 @@
 
 use anyhow::Result;
 
-use super::backend::GitBackend;
+use super::backend::{GitBackend, GitCredentials};
 use super::composite::CompositeGitBackend;
 use super::gitoxide::GitoxideBackend;
 #[cfg(feature = "libgit2-push")]
@@ -51,6 +51,6 @@ pub fn select_backend(eff: &EffectiveConfig) -> Box<dyn GitBackend> {
 
 /// Push a reference using the selected backend.
 #[allow(unused_variables)]
-pub fn push_ref<B: GitBackend>(backend: &B, repo_path: &str, remote: &str, reference: &str) -> Result<()> {
-    backend.push(repo_path, remote, reference)
+pub fn push_ref<B: GitBackend + ?Sized>(backend: &B, repo_path: &str, remote: &str, reference: &str, creds: &GitCredentials, force: bool) -> Result<()> {
+    backend.push(repo_path, remote, reference, creds, force)
 }
@@ -0,0 +1,446 @@
+//! SYNC FILESTORE <name> PUSH|PULL: mirror the live files of a filestore to a branch of the
+//! configured Git remote, and bring remote changes back.
+//!
+//! Git objects live in a bare repository at `<db root>/<db>/.filestore_git/<fs>.git`. Per branch
+//! the last synchronized remote commit and the blob/etag of each file it held are kept under
+//! `Keys::git_sync`; that snapshot is the merge base of the next run:
+//! - PUSH commits the live files on top of the base and pushes. When the remote moved since,
+//!   the push is rejected as non-fast-forward unless FORCE.
+//! - PULL applies remote changes since the base through ingest/update/delete (ACL checked and
+//!   indexed like any write). Files changed on both sides are conflicts, resolved by the policy.
+//!
+//! Each run records its stages under its correlation id (`Keys::git_sync_run`, SHOW SYNC) and
+//! logs them with [corr=...].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::storage::{KvValue, SharedStore};
+
+use super::backend::{GitBackend, GitCredentials, GitRefUpdate};
+use super::ops::{push_ref, select_backend};
+use super::super::config::EffectiveConfig;
+use super::super::correlation::CorrelationId;
+use super::super::kv::Keys;
+use super::super::ops::{commit_tree, create_tree_from_prefix, delete_file, get_file_bytes, ingest_from_bytes, list_files_by_prefix, update_from_bytes};
+use super::super::security::{check_acl, ACLAction, AclContext, AclUser, GitCtx};
+use super::super::types::{CommitAuthor, FileMeta};
+
+/// How PULL treats a file changed both locally and on the remote since the last sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Abort the pull and list the conflicting paths; nothing is applied
+    Fail,
+    /// Keep the local file; the next PUSH sends it
+    Ours,
+    /// Take the remote file
+    Theirs,
+}
+
+impl ConflictPolicy {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fail" => Ok(Self::Fail),
+            "ours" => Ok(Self::Ours),
+            "theirs" => Ok(Self::Theirs),
+            other => bail!("invalid conflict policy '{}': expected fail, ours or theirs", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    /// Defaults to the filestore's git_branch
+    pub branch: Option<String>,
+    /// PUSH: overwrite a remote branch that moved since the last sync
+    pub force: bool,
+    /// PULL: overrides the filestore's git_conflict_policy
+    pub policy: Option<ConflictPolicy>,
+    /// Caller-chosen id for the run; a new one is generated when absent
+    pub correlation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncStage {
+    pub at: i64,
+    pub stage: String,
+    pub detail: String,
+}
+
+/// Progress and outcome of one SYNC run, persisted under its correlation id.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncRun {
+    pub correlation_id: String,
+    /// "push" | "pull"
+    pub direction: String,
+    pub remote: String,
+    pub branch: String,
+    /// "running" | "ok" | "up_to_date" | "rejected" | "conflict" | "failed"
+    pub status: String,
+    /// Remote commit after the run
+    pub git_sha: Option<String>,
+    /// Filestore commit recording the synchronized tree
+    pub commit_id: Option<String>,
+    pub files_added: u64,
+    pub files_updated: u64,
+    pub files_deleted: u64,
+    pub conflicts: Vec<String>,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub stages: Vec<SyncStage>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+struct SyncedFile {
+    blob: String,
+    /// Local etag matching `blob`; empty when the local file was kept over a remote change
+    etag: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+struct SyncState {
+    remote: String,
+    git_sha: String,
+    files: BTreeMap<String, SyncedFile>,
+    updated_at: i64,
+}
+
+/// Bare repository holding a filestore's Git objects. Dot-prefixed so catalog scans skip it.
+pub fn repo_path(store: &SharedStore, database: &str, filestore: &str) -> String {
+    store.root_path().join(database).join(".filestore_git").join(format!("{}.git", filestore)).to_string_lossy().to_string()
+}
+
+/// Remote credentials from the effective config; the token is read from `git_token_env` now.
+pub fn credentials(eff: &EffectiveConfig) -> GitCredentials {
+    GitCredentials {
+        username: eff.git_username.clone(),
+        token: std::env::var(&eff.git_token_env).ok().filter(|t| !t.is_empty()),
+        ssh_key_path: eff.git_ssh_key_path.clone(),
+    }
+}
+
+fn load_state(store: &SharedStore, database: &str, filestore: &str, branch: &str, remote: &str) -> Option<SyncState> {
+    match store.kv_store(database, filestore).get(&Keys::git_sync(database, filestore, branch)) {
+        // A state recorded against another remote is no base for this one
+        Some(KvValue::Json(j)) => serde_json::from_value::<SyncState>(j).ok().filter(|s| s.remote == remote),
+        _ => None,
+    }
+}
+
+fn save_state(store: &SharedStore, database: &str, filestore: &str, branch: &str, state: &SyncState) -> Result<()> {
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::git_sync(database, filestore, branch), KvValue::Json(serde_json::to_value(state)?), None, None);
+    Ok(())
+}
+
+/// List recorded runs, most recent first.
+pub fn list_sync_runs(store: &SharedStore, database: &str, filestore: &str) -> Vec<SyncRun> {
+    let kv = store.kv_store(database, filestore);
+    let prefix = Keys::git_sync_run_prefix(database, filestore);
+    let mut out: Vec<SyncRun> = kv.keys().into_iter()
+        .filter(|k| k.starts_with(&prefix))
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value(j).ok(), _ => None })
+        .collect();
+    out.sort_by(|a: &SyncRun, b: &SyncRun| b.started_at.cmp(&a.started_at).then(a.correlation_id.cmp(&b.correlation_id)));
+    out
+}
+
+/// Persists the run record after every stage so progress is visible while the run is going.
+struct Progress<'a> {
+    store: &'a SharedStore,
+    database: &'a str,
+    filestore: &'a str,
+    run: SyncRun,
+}
+
+impl<'a> Progress<'a> {
+    fn start(store: &'a SharedStore, database: &'a str, filestore: &'a str, direction: &str, remote: &str, branch: &str, corr: String) -> Self {
+        let run = SyncRun {
+            correlation_id: corr,
+            direction: direction.to_string(),
+            remote: remote.to_string(),
+            branch: branch.to_string(),
+            status: "running".to_string(),
+            git_sha: None,
+            commit_id: None,
+            files_added: 0,
+            files_updated: 0,
+            files_deleted: 0,
+            conflicts: Vec::new(),
+            error: None,
+            started_at: Utc::now().timestamp(),
+            finished_at: None,
+            stages: Vec::new(),
+        };
+        let mut p = Self { store, database, filestore, run };
+        p.stage("start", format!("remote={} branch={}", remote, branch));
+        p
+    }
+
+    fn stage(&mut self, stage: &str, detail: String) {
+        crate::tprintln!("FILESTORE sync {} fs={} stage={} {} [corr={}]", self.run.direction, self.filestore, stage, detail, self.run.correlation_id);
+        self.run.stages.push(SyncStage { at: Utc::now().timestamp(), stage: stage.to_string(), detail });
+        self.save();
+    }
+
+    fn save(&self) {
+        let kv = self.store.kv_store(self.database, self.filestore);
+        if let Ok(j) = serde_json::to_value(&self.run) {
+            kv.set(Keys::git_sync_run(self.database, self.filestore, &self.run.correlation_id), KvValue::Json(j), None, None);
+        }
+    }
+
+    fn finish(mut self, status: &str, error: Option<String>) -> SyncRun {
+        self.run.status = status.to_string();
+        self.run.error = error;
+        self.run.finished_at = Some(Utc::now().timestamp());
+        let detail = self.run.error.clone().unwrap_or_else(|| format!("added={} updated={} deleted={} conflicts={}",
+            self.run.files_added, self.run.files_updated, self.run.files_deleted, self.run.conflicts.len()));
+        self.stage(status, detail);
+        self.run
+    }
+
+    /// Close a failed run and return its error tagged with the correlation id.
+    fn fail(self, e: anyhow::Error) -> anyhow::Error {
+        let msg = e.to_string();
+        let status = if msg.starts_with("non_fast_forward") { "rejected" } else if msg.starts_with("sync_conflict") { "conflict" } else { "failed" };
+        let run = self.finish(status, Some(msg.clone()));
+        anyhow!("{} [corr={}]", msg, run.correlation_id)
+    }
+}
+
+struct Target {
+    remote: String,
+    branch: String,
+    reference: String,
+    repo: String,
+    ctx: AclContext,
+    corr: String,
+}
+
+async fn prepare(store: &SharedStore, database: &str, filestore: &str, opts: &SyncOptions, action: ACLAction, user: &AclUser, eff: &EffectiveConfig, ctx: &AclContext) -> Result<Target> {
+    let remote = eff.git_remote.clone().filter(|r| !r.trim().is_empty())
+        .ok_or_else(|| anyhow!("git_remote_not_configured: set git_remote with ALTER FILESTORE {} SET {{\"git_remote\": \"...\"}}", filestore))?;
+    let branch = opts.branch.clone().or_else(|| eff.git_branch.clone()).unwrap_or_else(|| "main".to_string());
+    let corr = CorrelationId::from_opt_str(opts.correlation_id.as_deref().or(ctx.request_id.as_deref())).to_string();
+    let mut ctx = ctx.clone();
+    ctx.request_id = Some(corr.clone());
+    ctx.git = Some(GitCtx { remote: Some(remote.clone()), branch: Some(branch.clone()) });
+    let decision = check_acl(eff, user, action, "", None, &ctx, filestore).await;
+    if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+    Ok(Target { reference: format!("refs/heads/{}", branch), repo: repo_path(store, database, filestore), remote, branch, ctx, corr })
+}
+
+/// Record the synchronized live files as a filestore commit carrying the Git commit id.
+fn record_commit(store: &SharedStore, database: &str, filestore: &str, branch: &str, git_sha: &str, user: &AclUser, message: &str) -> Result<String> {
+    let tree = create_tree_from_prefix(store, database, filestore, None)?;
+    let author = CommitAuthor { name: user.id.clone(), email: format!("{}@clarium", user.id), time_unix: Utc::now().timestamp() };
+    let mut commit = commit_tree(store, database, filestore, &tree.id, &[], &author, message, &[], branch)?;
+    commit.git_sha = Some(git_sha.to_string());
+    let id = Uuid::parse_str(&commit.id).unwrap_or_else(|_| Uuid::nil());
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::commit(database, filestore, &id), KvValue::Json(serde_json::to_value(&commit)?), None, None);
+    kv.set(Keys::git_map_commit_to_sha(database, filestore, &id), KvValue::Str(git_sha.to_string()), None, None);
+    Ok(commit.id)
+}
+
+/// SYNC FILESTORE ... PUSH using the backend chosen by `git_push_backend`.
+pub async fn sync_push(store: &SharedStore, database: &str, filestore: &str, opts: &SyncOptions, user: &AclUser, eff: &EffectiveConfig, ctx: &AclContext) -> Result<SyncRun> {
+    let backend = select_backend(eff);
+    push_with(backend.as_ref(), store, database, filestore, opts, user, eff, ctx).await
+}
+
+/// SYNC FILESTORE ... PULL using the backend chosen by `git_push_backend`.
+pub async fn sync_pull(store: &SharedStore, database: &str, filestore: &str, opts: &SyncOptions, user: &AclUser, eff: &EffectiveConfig, ctx: &AclContext) -> Result<SyncRun> {
+    let backend = select_backend(eff);
+    pull_with(backend.as_ref(), store, database, filestore, opts, user, eff, ctx).await
+}
+
+pub async fn push_with(backend: &dyn GitBackend, store: &SharedStore, database: &str, filestore: &str, opts: &SyncOptions, user: &AclUser, eff: &EffectiveConfig, ctx: &AclContext) -> Result<SyncRun> {
+    let t = prepare(store, database, filestore, opts, ACLAction::Push, user, eff, ctx).await?;
+    let mut p = Progress::start(store, database, filestore, "push", &t.remote, &t.branch, t.corr.clone());
+    match push_inner(backend, &mut p, &t, opts, user, eff) {
+        Ok(status) => Ok(p.finish(status, None)),
+        Err(e) => Err(p.fail(e)),
+    }
+}
+
+fn push_inner(backend: &dyn GitBackend, p: &mut Progress<'_>, t: &Target, opts: &SyncOptions, user: &AclUser, eff: &EffectiveConfig) -> Result<&'static str> {
+    let (store, database, filestore) = (p.store, p.database, p.filestore);
+    let creds = credentials(eff);
+    backend.ensure_repo(&t.repo, Some(&t.remote))?;
+
+    p.stage("fetch", t.reference.clone());
+    let remote_head = backend.fetch(&t.repo, &t.remote, &t.reference, &creds)?;
+    let state = load_state(store, database, filestore, &t.branch, &t.remote);
+    let base_sha = state.as_ref().map(|s| s.git_sha.clone());
+    if let Some(head) = remote_head.as_ref() {
+        if Some(head) != base_sha.as_ref() && !opts.force {
+            bail!("non_fast_forward: branch '{}' on the remote has commits this filestore has not pulled; run SYNC FILESTORE {} PULL first or push with FORCE", t.branch, filestore);
+        }
+    }
+    let base_files = state.map(|s| s.files).unwrap_or_default();
+
+    let live: Vec<FileMeta> = list_files_by_prefix(store, database, filestore, None)?.into_iter().filter(|m| !m.deleted).collect();
+    p.stage("write_objects", format!("files={}", live.len()));
+    let mut entries: Vec<(String, String)> = Vec::with_capacity(live.len());
+    let mut files: BTreeMap<String, SyncedFile> = BTreeMap::new();
+    for m in live {
+        let bytes = get_file_bytes(store, database, filestore, &m)?
+            .ok_or_else(|| anyhow!("content_missing: {}", m.logical_path))?;
+        let blob = backend.write_blob(&t.repo, &m.logical_path, &bytes)?;
+        match base_files.get(&m.logical_path) {
+            None => p.run.files_added += 1,
+            Some(b) if b.blob != blob => p.run.files_updated += 1,
+            _ => {}
+        }
+        entries.push((m.logical_path.clone(), blob.clone()));
+        files.insert(m.logical_path, SyncedFile { blob, etag: m.etag });
+    }
+    p.run.files_deleted = base_files.keys().filter(|k| !files.contains_key(*k)).count() as u64;
+    let changed = p.run.files_added + p.run.files_updated + p.run.files_deleted;
+    if changed == 0 && remote_head.is_some() && remote_head == base_sha {
+        p.run.git_sha = remote_head;
+        return Ok("up_to_date");
+    }
+
+    let tree_id = backend.write_tree(&t.repo, &entries)?;
+    // Our last synced commit is the parent; with FORCE a remote that moved is overwritten
+    let parents: Vec<String> = base_sha.into_iter().collect();
+    let message = format!("Sync filestore {}: {} added, {} updated, {} deleted\n\nclarium-correlation-id: {}",
+        filestore, p.run.files_added, p.run.files_updated, p.run.files_deleted, t.corr);
+    let author = format!("{} <{}@clarium>", user.id, user.id);
+    let ids = backend.write_commit(&t.repo, &message, &author, &parents, &tree_id)?;
+    backend.update_ref(&t.repo, &GitRefUpdate { reference: t.reference.clone(), new_target: ids.commit_id.clone() })?;
+    p.stage("commit", ids.commit_id.clone());
+
+    p.stage("push", format!("{}{}", t.reference, if opts.force { " (force)" } else { "" }));
+    push_ref(backend, &t.repo, &t.remote, &t.reference, &creds, opts.force)?;
+
+    let commit_id = record_commit(store, database, filestore, &t.branch, &ids.commit_id, user, &message)?;
+    save_state(store, database, filestore, &t.branch, &SyncState { remote: t.remote.clone(), git_sha: ids.commit_id.clone(), files, updated_at: Utc::now().timestamp() })?;
+    p.run.git_sha = Some(ids.commit_id);
+    p.run.commit_id = Some(commit_id);
+    Ok("ok")
+}
+
+pub async fn pull_with(backend: &dyn GitBackend, store: &SharedStore, database: &str, filestore: &str, opts: &SyncOptions, user: &AclUser, eff: &EffectiveConfig, ctx: &AclContext) -> Result<SyncRun> {
+    let policy = match opts.policy { Some(p) => p, None => ConflictPolicy::parse(&eff.git_conflict_policy)? };
+    let t = prepare(store, database, filestore, opts, ACLAction::Pull, user, eff, ctx).await?;
+    let mut p = Progress::start(store, database, filestore, "pull", &t.remote, &t.branch, t.corr.clone());
+    match pull_inner(backend, &mut p, &t, policy, user, eff).await {
+        Ok(status) => Ok(p.finish(status, None)),
+        Err(e) => Err(p.fail(e)),
+    }
+}
+
+/// A remote change to apply locally: the new blob, or None to delete.
+type Change = (String, Option<String>);
+
+async fn pull_inner(backend: &dyn GitBackend, p: &mut Progress<'_>, t: &Target, policy: ConflictPolicy, user: &AclUser, eff: &EffectiveConfig) -> Result<&'static str> {
+    let (store, database, filestore) = (p.store, p.database, p.filestore);
+    let creds = credentials(eff);
+    backend.ensure_repo(&t.repo, Some(&t.remote))?;
+
+    p.stage("fetch", t.reference.clone());
+    let remote_head = backend.fetch(&t.repo, &t.remote, &t.reference, &creds)?
+        .ok_or_else(|| anyhow!("remote_branch_not_found: the remote has no branch '{}'", t.branch))?;
+    let state = load_state(store, database, filestore, &t.branch, &t.remote);
+    if state.as_ref().map(|s| s.git_sha == remote_head).unwrap_or(false) {
+        p.run.git_sha = Some(remote_head);
+        return Ok("up_to_date");
+    }
+    let base = state.map(|s| s.files).unwrap_or_default();
+
+    p.stage("read_tree", remote_head.clone());
+    let theirs: BTreeMap<String, String> = backend.read_tree(&t.repo, &remote_head)?.into_iter().collect();
+    let local: BTreeMap<String, FileMeta> = list_files_by_prefix(store, database, filestore, None)?
+        .into_iter().filter(|m| !m.deleted).map(|m| (m.logical_path.clone(), m)).collect();
+
+    // Three-way compare against the last synced snapshot
+    let mut changes: Vec<Change> = Vec::new();
+    let mut next: BTreeMap<String, SyncedFile> = BTreeMap::new();
+    let paths: BTreeSet<&String> = base.keys().chain(theirs.keys()).chain(local.keys()).collect();
+    for path in paths {
+        let (b, r, l) = (base.get(path), theirs.get(path), local.get(path));
+        if r != b.map(|x| &x.blob) {
+            let local_changed = match (l, b) { (Some(m), Some(b)) => m.etag != b.etag, (None, None) => false, _ => true };
+            if local_changed {
+                // The same edit made on both sides is no conflict
+                let same = match (l, r) {
+                    (None, None) => true,
+                    (Some(m), Some(rb)) => {
+                        let bytes = get_file_bytes(store, database, filestore, m)?.unwrap_or_default();
+                        &backend.write_blob(&t.repo, path, &bytes)? == rb
+                    }
+                    _ => false,
+                };
+                if same {
+                    if let (Some(m), Some(rb)) = (l, r) { next.insert(path.clone(), SyncedFile { blob: rb.clone(), etag: m.etag.clone() }); }
+                    continue;
+                }
+                p.run.conflicts.push(path.clone());
+                match policy {
+                    ConflictPolicy::Fail => continue,
+                    ConflictPolicy::Ours => {
+                        // Treat the remote version as seen; the local file then differs from it and is pushed next
+                        if let Some(rb) = r { next.insert(path.clone(), SyncedFile { blob: rb.clone(), etag: String::new() }); }
+                        continue;
+                    }
+                    ConflictPolicy::Theirs => {}
+                }
+            }
+            changes.push((path.clone(), r.cloned()));
+        } else if let Some(b) = b {
+            // Unchanged remotely: local edits, if any, wait for the next push
+            next.insert(path.clone(), b.clone());
+        }
+    }
+    if policy == ConflictPolicy::Fail && !p.run.conflicts.is_empty() {
+        let shown: Vec<&str> = p.run.conflicts.iter().take(10).map(String::as_str).collect();
+        bail!("sync_conflict: {} file(s) changed both locally and on the remote ({}{}); pull with POLICY 'ours' or 'theirs'",
+            p.run.conflicts.len(), shown.join(", "), if p.run.conflicts.len() > shown.len() { ", ..." } else { "" });
+    }
+
+    p.stage("apply", format!("changes={} conflicts={}", changes.len(), p.run.conflicts.len()));
+    for (path, blob) in changes {
+        match blob {
+            Some(rb) => {
+                let bytes = backend.read_blob(&t.repo, &rb)?;
+                let meta = match local.get(&path) {
+                    Some(m) => {
+                        p.run.files_updated += 1;
+                        update_from_bytes(store, database, filestore, &path, &m.etag, &bytes, None, None, user, eff, &t.ctx).await?
+                    }
+                    None => {
+                        p.run.files_added += 1;
+                        ingest_from_bytes(store, database, filestore, &path, &bytes, None, None, user, eff, &t.ctx).await?
+                    }
+                };
+                next.insert(path, SyncedFile { blob: rb, etag: meta.etag });
+            }
+            None => {
+                if local.contains_key(&path) {
+                    delete_file(store, database, filestore, &path, user, eff, &t.ctx).await?;
+                    p.run.files_deleted += 1;
+                }
+            }
+        }
+    }
+
+    backend.update_ref(&t.repo, &GitRefUpdate { reference: t.reference.clone(), new_target: remote_head.clone() })?;
+    let message = format!("Pull {} from {}: {} added, {} updated, {} deleted\n\nclarium-correlation-id: {}",
+        t.branch, t.remote, p.run.files_added, p.run.files_updated, p.run.files_deleted, t.corr);
+    let commit_id = record_commit(store, database, filestore, &t.branch, &remote_head, user, &message)?;
+    save_state(store, database, filestore, &t.branch, &SyncState { remote: t.remote.clone(), git_sha: remote_head.clone(), files: next, updated_at: Utc::now().timestamp() })?;
+    p.stage("record", commit_id.clone());
+    p.run.git_sha = Some(remote_head);
+    p.run.commit_id = Some(commit_id);
+    Ok("ok")
+}
@@ -34,6 +34,10 @@ impl Keys {
     }
     #[inline]
     pub fn chunk_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".chunk::") }
+    /// Content-addressed chunk, named by the hex SHA-256 of its bytes (see chunker.rs).
+    pub fn chunk_oid(db: &str, fs: &str, oid: &str) -> String {
+        format!("{}{}", Self::chunk_prefix(db, fs), oid)
+    }
     pub fn path(db: &str, fs: &str, logical_path_nfc: &str) -> String {
         format!("{}{}{}", ns(db, fs), ".path::", logical_path_nfc)
     }
@@ -61,9 +65,84 @@ impl Keys {
     pub fn git_ref_prefix(db: &str, fs: &str, remote: &str) -> String {
         format!("{}{}{}::", ns(db, fs), ".git::", remote)
     }
+    /// Branch checked out in the live files (see branch.rs).
+    pub fn head(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".head") }
     pub fn git_map_commit_to_sha(db: &str, fs: &str, commit_guid: &Uuid) -> String {
         format!("{}{}{}", ns(db, fs), ".map.git_sha::", commit_guid)
     }
+    /// Last synchronized remote commit of a branch and the files it held (see git/sync.rs).
+    pub fn git_sync(db: &str, fs: &str, branch: &str) -> String {
+        format!("{}{}{}", ns(db, fs), ".git.sync::", branch)
+    }
+    /// Progress record of one SYNC run, keyed by its correlation id.
+    pub fn git_sync_run(db: &str, fs: &str, correlation_id: &str) -> String {
+        format!("{}{}", Self::git_sync_run_prefix(db, fs), correlation_id)
+    }
+    #[inline]
+    pub fn git_sync_run_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".git.run::") }
+
+    // Full-text index (see fulltext.rs) ---------------------------------------
+    /// Postings of one term: `{logical_path: frequency}`.
+    pub fn fts_term(db: &str, fs: &str, term: &str) -> String {
+        format!("{}{}{}", ns(db, fs), ".fts.term::", term)
+    }
+    /// Indexed terms of the live file at a logical path.
+    pub fn fts_doc(db: &str, fs: &str, logical_path_nfc: &str) -> String {
+        format!("{}{}", Self::fts_doc_prefix(db, fs), logical_path_nfc)
+    }
+    #[inline]
+    pub fn fts_doc_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".fts.doc::") }
+    /// Document count and total length of the index.
+    pub fn fts_stats(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".fts.stats") }
+
+    // Quotas (see quota.rs) ---------------------------------------------------
+    /// One recorded quota event; ids sort by time.
+    pub fn quota_event(db: &str, fs: &str, id: &str) -> String {
+        format!("{}{}", Self::quota_event_prefix(db, fs), id)
+    }
+    #[inline]
+    pub fn quota_event_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".quota.event::") }
+
+    // Retention (see retention.rs) --------------------------------------------
+    /// A retained earlier version of a file; versions of a file sort in order.
+    pub fn version(db: &str, fs: &str, file_id: &str, version: u64) -> String {
+        format!("{}{}::{:020}", Self::version_prefix(db, fs), file_id, version)
+    }
+    #[inline]
+    pub fn version_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".version::") }
+    /// Legal hold on a logical path.
+    pub fn legal_hold(db: &str, fs: &str, logical_path_nfc: &str) -> String {
+        format!("{}{}", Self::legal_hold_prefix(db, fs), logical_path_nfc)
+    }
+    #[inline]
+    pub fn legal_hold_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".hold::") }
+
+    // File locks (see locks.rs) ----------------------------------------------
+    /// Advisory lock on a logical path.
+    pub fn file_lock(db: &str, fs: &str, logical_path_nfc: &str) -> String {
+        format!("{}{}", Self::file_lock_prefix(db, fs), logical_path_nfc)
+    }
+    #[inline]
+    pub fn file_lock_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".lock::") }
+
+    // Server-side encryption (see sse.rs) --------------------------------------
+    /// The filestore's data key, wrapped by a master key.
+    pub fn data_key(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".sse.data_key") }
+
+    // GC (see gc.rs, gc_scheduler.rs) -----------------------------------------
+    /// When a run first found the chunk or blob under `entry_key` unreferenced.
+    pub fn gc_mark(db: &str, fs: &str, entry_key: &str) -> String {
+        let rel = entry_key.strip_prefix(&ns(db, fs)).unwrap_or(entry_key);
+        format!("{}{}", Self::gc_mark_prefix(db, fs), rel.trim_start_matches('.'))
+    }
+    #[inline]
+    pub fn gc_mark_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".gc.mark::") }
+    /// One recorded GC run; ids sort by time.
+    pub fn gc_run(db: &str, fs: &str, id: &str) -> String {
+        format!("{}{}", Self::gc_run_prefix(db, fs), id)
+    }
+    #[inline]
+    pub fn gc_run_prefix(db: &str, fs: &str) -> String { format!("{}{}", ns(db, fs), ".gc.run::") }
 
     // Information schema / registry ---------------------------------------
     pub fn info_global(db: &str) -> String { format!("{}.info.fs.global", db) }
@@ -0,0 +1,145 @@
+//! Advisory file locks (check-out / check-in).
+//!
+//! LOCK FILE takes a lock on a live file for the calling user, kept under `Keys::file_lock`
+//! until UNLOCK FILE or its expiry (FOR <seconds>, else `lock_default_ttl_seconds`; 0 = none).
+//! While it is active, update, rename and delete of the file by anyone but the owner fail with
+//! `file_locked`, and so does an ingest over it. The owner keeps working as usual: a rename
+//! takes the lock along, a delete releases it. Another user's lock can only be broken with
+//! UNLOCK FILE ... FORCE, which the SQL layer reserves for admins.
+//! Reads are never blocked.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::storage::{KvValue, SharedStore};
+
+use super::config::EffectiveConfig;
+use super::kv::Keys;
+use super::ops::get_file_meta;
+use super::paths::{normalize_nfc, validate_logical_path};
+use super::security::{check_acl, ACLAction, AclContext, AclUser};
+use super::types::FileLock;
+
+/// Serializes lock changes, so two users cannot both take a free lock.
+static LOCKING: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// The active lock on a path, if any; expired locks are ignored.
+pub fn file_lock(store: &SharedStore, database: &str, filestore: &str, logical_path: &str) -> Option<FileLock> {
+    let kv = store.kv_store(database, filestore);
+    match kv.get(&Keys::file_lock(database, filestore, &normalize_nfc(logical_path))) {
+        Some(KvValue::Json(j)) => serde_json::from_value::<FileLock>(j).ok().filter(|l| l.is_active(Utc::now().timestamp())),
+        _ => None,
+    }
+}
+
+/// Active locks of the filestore, sorted by path.
+pub fn list_file_locks(store: &SharedStore, database: &str, filestore: &str) -> Vec<FileLock> {
+    let kv = store.kv_store(database, filestore);
+    let prefix = Keys::file_lock_prefix(database, filestore);
+    let now = Utc::now().timestamp();
+    let mut out: Vec<FileLock> = kv.keys().into_iter()
+        .filter(|k| k.starts_with(&prefix))
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value::<FileLock>(j).ok(), _ => None })
+        .filter(|l| l.is_active(now))
+        .collect();
+    out.sort_by(|a, b| a.logical_path.cmp(&b.logical_path));
+    out
+}
+
+/// Fail with `file_locked` when another user holds an active lock on the path.
+pub fn check_unlocked(store: &SharedStore, database: &str, filestore: &str, logical_path: &str, user: &AclUser) -> Result<()> {
+    if let Some(l) = file_lock(store, database, filestore, logical_path) {
+        if l.owner != user.id {
+            match l.expires_at {
+                Some(t) => bail!("file_locked: {} is locked by {} until {}", l.logical_path, l.owner, t),
+                None => bail!("file_locked: {} is locked by {}", l.logical_path, l.owner),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lock a live file for `user` (checked as a Write). Locking a file again as its owner renews
+/// the lock with the new expiry and note.
+pub async fn lock_file(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    logical_path: &str,
+    ttl_seconds: Option<u64>,
+    note: Option<&str>,
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<FileLock> {
+    validate_logical_path(logical_path)?;
+    let path_nfc = normalize_nfc(logical_path);
+    let decision = check_acl(eff, user, ACLAction::Write, &path_nfc, None, ctx, filestore).await;
+    if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+    if !get_file_meta(store, database, filestore, &path_nfc)?.is_some_and(|m| !m.deleted) { bail!("not_found"); }
+
+    let _guard = LOCKING.lock();
+    check_unlocked(store, database, filestore, &path_nfc, user)?;
+    let now = Utc::now().timestamp();
+    let ttl = ttl_seconds.unwrap_or(eff.lock_default_ttl_seconds);
+    let lock = FileLock {
+        logical_path: path_nfc.clone(),
+        owner: user.id.clone(),
+        note: note.map(str::to_string),
+        locked_at: now,
+        expires_at: (ttl > 0).then(|| now.saturating_add(ttl as i64)),
+    };
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::file_lock(database, filestore, &path_nfc), KvValue::Json(serde_json::to_value(&lock)?), None, None);
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    crate::tprintln!("FILESTORE lock fs={} path={} owner={} expires_at={:?} [corr={}]", filestore, path_nfc, user.id, lock.expires_at, corr);
+    Ok(lock)
+}
+
+/// Release the lock on a path (checked as a Write). Only the owner may release an active lock
+/// unless `force` breaks it. Returns whether an active lock was released.
+pub async fn unlock_file(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    logical_path: &str,
+    force: bool,
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<bool> {
+    let path_nfc = normalize_nfc(logical_path);
+    let decision = check_acl(eff, user, ACLAction::Write, &path_nfc, None, ctx, filestore).await;
+    if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+
+    let _guard = LOCKING.lock();
+    let held = file_lock(store, database, filestore, &path_nfc);
+    if let Some(l) = &held {
+        if l.owner != user.id && !force { bail!("lock_owner: {} is locked by {}; only the owner or an admin (FORCE) can unlock it", path_nfc, l.owner); }
+    }
+    store.kv_store(database, filestore).delete(&Keys::file_lock(database, filestore, &path_nfc));
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    let owner = held.as_ref().map(|l| l.owner.as_str()).unwrap_or("-");
+    crate::tprintln!("FILESTORE unlock fs={} path={} owner={} by={} force={} [corr={}]", filestore, path_nfc, owner, user.id, force, corr);
+    Ok(held.is_some())
+}
+
+/// Drop the lock on a path (its file was deleted).
+pub(crate) fn release(store: &SharedStore, database: &str, filestore: &str, logical_path_nfc: &str) {
+    store.kv_store(database, filestore).delete(&Keys::file_lock(database, filestore, logical_path_nfc));
+}
+
+/// Carry the lock on `old_nfc` over to `new_nfc` (its file was renamed).
+pub(crate) fn carry_over(store: &SharedStore, database: &str, filestore: &str, old_nfc: &str, new_nfc: &str) -> Result<()> {
+    let Some(mut l) = file_lock(store, database, filestore, old_nfc) else {
+        release(store, database, filestore, old_nfc);
+        return Ok(());
+    };
+    l.logical_path = new_nfc.to_string();
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::file_lock(database, filestore, new_nfc), KvValue::Json(serde_json::to_value(&l)?), None, None);
+    kv.delete(&Keys::file_lock(database, filestore, old_nfc));
+    Ok(())
+}
@@ -15,23 +15,47 @@ pub mod registry;
 pub mod show;
 pub mod ddl;
 pub mod gc;
+pub mod gc_scheduler;
+pub mod chunker;
+pub mod blob;
+pub mod fulltext;
+pub mod branch;
+pub mod quota;
+pub mod retention;
+pub mod events;
+pub mod scan;
+pub mod sse;
+pub mod locks;
 
 // Re-export common types for early adopters
-pub use config::{GlobalFilestoreConfig, FilestoreConfig, FolderGitOverride, EffectiveConfig};
+pub use config::{GlobalFilestoreConfig, FilestoreConfig, FolderGitOverride, EffectiveConfig, PrefixQuota, WebhookConfig};
 pub use paths::{normalize_nfc, validate_logical_path, split_normalized_segments};
-pub use security::{ACLAction, AclUser, AclContext, AclDecision, check_acl};
+pub use security::{ACLAction, AclUser, AclContext, AclDecision, AccessExplanation, check_acl, decide_acl, explain_acl};
 // Expose new security API surface for incremental adoption
 pub use sec::{authorize as authorize_v2, explain as explain_v2};
 pub use host_path::{is_host_path_allowed, normalize_abs_path};
 pub use correlation::{CorrelationId, correlation_id_opt_str};
-pub use types::{FileMeta, Chunking, ChunkRef, Tree, Commit, CommitAuthor, RefInfo, Alias};
-pub use ops::{ingest_from_bytes, get_file_meta, get_file_bytes, update_from_bytes, rename_file, delete_file, ingest_from_host_path, head_file_meta, list_files_by_prefix};
+pub use types::{FileMeta, Chunking, ChunkRef, Tree, Commit, CommitAuthor, RefInfo, Alias, FileVersion, LegalHold, FileLock, ScanInfo};
+pub use ops::{ingest_from_bytes, get_file_meta, get_file_bytes, read_file_checked, update_from_bytes, rename_file, delete_file, ingest_from_host_path, head_file_meta, list_files_by_prefix, set_file_metadata};
 pub use ops::current_branch_head;
 pub use registry::{FilestoreRegistryEntry, save_filestore_entry, load_filestore_entry, list_filestore_entries, drop_filestore_entry, alter_filestore_entry};
-pub use show::{show_filestores_df, show_filestore_config_df, show_files_df, show_trees_df, show_commits_df, show_diff_df, show_chunks_df, show_aliases_df, show_admin_counts_df, show_files_df_paged, show_health_df};
+pub use show::{show_filestores_df, show_filestore_config_df, show_files_df, show_trees_df, show_commits_df, show_diff_df, show_chunks_df, show_aliases_df, show_admin_counts_df, show_files_df_paged, show_health_df, show_sync_runs_df, show_branches_df, show_merge_conflicts_df, show_legal_holds_df, show_versions_df, show_webhooks_df, show_encryption_df, show_gc_runs_df, show_file_locks_df, explain_access_df};
 pub use ops::{create_tree_from_prefix, commit_tree, load_tree, list_trees, list_commits};
 pub use ddl::{create_filestore, alter_filestore_ddl, drop_filestore};
-pub use gc::{gc_dry_run, gc_apply};
+pub use gc::{GcReport, gc_dry_run, gc_apply};
+pub use gc_scheduler::{GcRun, gc_due, list_gc_runs, run_due as run_due_gc, run_gc, spawn_gc_scheduler};
+pub use chunker::{ChunkParams, DedupStats, dedup_stats, read_chunk};
+pub use blob::{BlobBackend, KvBlobBackend, ObjectStoreBackend, backend_for};
+pub use fulltext::{SearchHit, search};
+pub use branch::{CheckoutOutcome, MergeOutcome, current_branch, create_branch, checkout_branch, merge_branch, snapshot_tree};
+pub use quota::{QuotaEvent, Usage as QuotaUsage, list_quota_events, register_quota_listener};
+pub use retention::{set_legal_hold, clear_legal_hold, legal_hold, list_legal_holds, list_versions, version_bytes};
+pub use events::{FileEvent, WebhookStats, subscribe as subscribe_events, webhook_stats};
+pub use scan::{scan_for_write, scan_mode};
+pub use locks::{lock_file, unlock_file, file_lock, list_file_locks};
+pub use sse::{DataKeyRecord, KeyRotation, data_key_record, rotate_key};
+pub use sec::hooks::{ScanHook, ScanVerdict, register_scan};
+pub use git::{ConflictPolicy, SyncOptions, SyncRun, sync_push, sync_pull};
 pub use kv::{Keys, etag_for_bytes, new_etag};
 
 #[cfg(test)]
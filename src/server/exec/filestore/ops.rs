@@ -8,10 +8,19 @@ use chrono::Utc;
 use crate::storage::{SharedStore, KvValue};
 
 use super::paths::{validate_logical_path, normalize_nfc};
-use super::security::{AclUser, AclContext, ACLAction, check_acl};
+use super::security::{AclUser, AclContext, ACLAction, check_acl, decide_acl};
 use super::config::EffectiveConfig;
 use super::kv::{Keys, etag_for_bytes};
-use super::types::{FileMeta, Tree, TreeEntry, Commit, CommitAuthor, RefInfo};
+use super::types::{FileMeta, Chunking, Tree, TreeEntry, Commit, CommitAuthor, RefInfo};
+use super::chunker::{self, ChunkParams};
+use super::blob;
+use super::fulltext;
+use super::quota;
+use super::retention;
+use super::events::{self, FileEvent};
+use super::locks;
+use super::scan;
+use super::sse;
 use super::host_path::{is_host_path_allowed, normalize_abs_path};
 
 /// Ingest file content from raw bytes. Stores bytes and writes metadata.
@@ -47,18 +56,20 @@ pub async fn ingest_from_bytes(
     if !decision.allow {
         bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string()));
     }
+    locks::check_unlocked(store, database, filestore, &path_nfc, user)?;
 
     let size = bytes.len() as u64;
+    // Ingest over a live path replaces that file
+    let replaced = get_file_meta(store, database, filestore, &path_nfc)?.filter(|m| !m.deleted);
+    let quota_check = quota::check_write(store, database, filestore, eff, replaced.as_ref().map(|m| (path_nfc.as_str(), m.size)), Some((path_nfc.as_str(), size)), ctx.request_id.as_deref())?;
+    let scan_info = scan::scan_for_write(eff, filestore, &path_nfc, bytes, ctx.request_id.as_deref())?;
+    if let Some(old) = &replaced { retention::retire_version(store, database, filestore, old, "replaced", eff)?; }
     let etag = etag_for_bytes(bytes);
     let id = Uuid::new_v4().to_string();
     let now = Utc::now().timestamp();
 
     // Persist bytes then metadata
-    let blob_key = Keys::blob(database, filestore, &Uuid::parse_str(&id).unwrap_or_else(|_| Uuid::nil()));
-    {
-        let kv = store.kv_store(database, filestore);
-        kv.set_bytes(blob_key, bytes, None, None);
-    }
+    let chunking = put_content(store, database, filestore, &id, bytes, eff)?;
 
     let meta = FileMeta {
         id: id.clone(),
@@ -72,13 +83,19 @@ pub async fn ingest_from_bytes(
         deleted: false,
         description_html: description_html.map(|s| s.to_string()),
         custom: None,
-        chunking: None,
+        chunking,
+        scan: scan_info,
     };
 
     let path_key = Keys::path(database, filestore, &path_nfc);
     let kv = store.kv_store(database, filestore);
     let meta_json = serde_json::to_value(&meta)?;
     kv.set(path_key, KvValue::Json(meta_json), None, None);
+    // Quarantined content is not indexed
+    if meta.is_quarantined() { fulltext::unindex_file(store, database, filestore, &path_nfc); }
+    else { fulltext::index_file(store, database, filestore, &meta, bytes, eff); }
+    quota::record_write(store, database, filestore, eff, quota_check, &path_nfc, ctx.request_id.as_deref());
+    events::publish(store, FileEvent::for_file("ingest", database, filestore, &meta, ctx.request_id.as_deref()));
 
     let corr = ctx.request_id.as_deref().unwrap_or("-");
     let desc_len = meta.description_html.as_ref().map(|s| s.len()).unwrap_or(0);
@@ -88,6 +105,24 @@ pub async fn ingest_from_bytes(
     Ok(meta)
 }
 
+/// Store file content: deduplicated content-defined chunks when the file reaches the chunking
+/// threshold, otherwise a single blob under the file id. Returns the chunk list when chunked.
+fn put_content(store: &SharedStore, database: &str, filestore: &str, file_id: &str, bytes: &[u8], eff: &EffectiveConfig) -> Result<Option<Chunking>> {
+    let blob_key = Keys::blob(database, filestore, &Uuid::parse_str(file_id).unwrap_or_else(|_| Uuid::nil()));
+    let kv = store.kv_store(database, filestore);
+    if eff.chunking_threshold_bytes > 0 && bytes.len() as u64 >= eff.chunking_threshold_bytes {
+        let backend = blob::backend_for(store, database, filestore, eff)?;
+        let (chunking, written) = chunker::store_chunked(store, database, filestore, bytes, &ChunkParams::default(), backend.as_ref())?;
+        // An update may move a file from a blob to chunks
+        kv.delete(&blob_key);
+        crate::tprintln!("FILESTORE chunked fs={} id={} size={} chunks={} written={} backend={}", filestore, file_id, bytes.len(), chunking.chunks.len(), written, backend.name());
+        Ok(Some(chunking))
+    } else {
+        kv.set_bytes(blob_key, &sse::seal(store, database, filestore, eff, bytes)?, None, None);
+        Ok(None)
+    }
+}
+
 /// Fetch FileMeta for a logical path, if present.
 pub fn get_file_meta(store: &SharedStore, database: &str, filestore: &str, logical_path: &str) -> Result<Option<FileMeta>> {
     validate_logical_path(logical_path)?;
@@ -102,12 +137,49 @@ pub fn get_file_meta(store: &SharedStore, database: &str, filestore: &str, logic
     }
 }
 
-/// Fetch raw bytes for a file by its metadata (id).
+/// Fetch raw bytes for a file by its metadata (id, or its chunk list when chunked).
 pub fn get_file_bytes(store: &SharedStore, database: &str, filestore: &str, meta: &FileMeta) -> Result<Option<Vec<u8>>> {
+    if let Some(ch) = &meta.chunking {
+        return chunker::read_chunked(store, database, filestore, ch);
+    }
     let uuid = Uuid::parse_str(&meta.id).unwrap_or_else(|_| Uuid::nil());
     let key = Keys::blob(database, filestore, &uuid);
     let kv = store.kv_store(database, filestore);
-    Ok(kv.get_bytes(&key))
+    kv.get_bytes(&key).map(|b| sse::open(store, database, filestore, b)).transpose()
+}
+
+/// Read a live file's content on behalf of `user`, after an ACL Read check.
+/// Synchronous so that query-time readers (table functions) can call it directly.
+pub fn read_file_checked(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    logical_path: &str,
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<(FileMeta, Vec<u8>)> {
+    let meta = match get_file_meta(store, database, filestore, logical_path)? {
+        Some(m) if !m.deleted => m,
+        _ => bail!("file not found in filestore '{}': {}", filestore, logical_path),
+    };
+    let cm = super::security::ContentMeta { size_bytes: Some(meta.size), media_type: meta.content_type.clone() };
+    let mut ctx2 = ctx.clone();
+    ctx2.content_meta = Some(cm);
+    let decision = decide_acl(eff, user, ACLAction::Read, &meta.logical_path, None, &ctx2, filestore);
+    if !decision.allow {
+        bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string()));
+    }
+    if let Some(sig) = meta.quarantine_signature() {
+        bail!("quarantined: access denied to {} in filestore '{}', flagged by content scan: {}", meta.logical_path, filestore, sig);
+    }
+    let bytes = match get_file_bytes(store, database, filestore, &meta)? {
+        Some(b) => b,
+        None => bail!("file content missing in filestore '{}': {}", filestore, logical_path),
+    };
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    crate::tprintln!("FILESTORE read ok fs={} path={} size={} [corr={}]", filestore, meta.logical_path, bytes.len(), corr);
+    Ok((meta, bytes))
 }
 
 /// Lightweight metadata fetch for preflight checks.
@@ -158,26 +230,35 @@ pub async fn update_from_bytes(
     ctx2.content_meta = Some(cm);
     let decision = check_acl(eff, user, ACLAction::Write, &cur.logical_path, None, &ctx2, filestore).await;
     if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+    locks::check_unlocked(store, database, filestore, &cur.logical_path, user)?;
 
-    // Overwrite blob and update meta
+    // Overwrite content (unchanged chunks are kept, not rewritten) and update meta
     let size = bytes.len() as u64;
+    let quota_check = quota::check_write(store, database, filestore, eff, Some((cur.logical_path.as_str(), cur.size)), Some((cur.logical_path.as_str(), size)), ctx.request_id.as_deref())?;
+    let scan_info = scan::scan_for_write(eff, filestore, &cur.logical_path, bytes, ctx.request_id.as_deref())?;
+    retention::retire_version(store, database, filestore, &cur, "replaced", eff)?;
     let etag = etag_for_bytes(bytes);
     let now = Utc::now().timestamp();
-    let uuid = Uuid::parse_str(&cur.id).unwrap_or_else(|_| Uuid::nil());
-    let blob_key = Keys::blob(database, filestore, &uuid);
+    let chunking = put_content(store, database, filestore, &cur.id, bytes, eff)?;
     let kv = store.kv_store(database, filestore);
-    kv.set_bytes(blob_key, bytes, None, None);
 
     let mut meta = cur;
+    meta.chunking = chunking;
     meta.size = size;
     meta.etag = etag.clone();
     meta.updated_at = now;
     meta.version = meta.version.saturating_add(1);
     meta.content_type = content_type.map(|s| s.to_string()).or(meta.content_type);
     if description_html.is_some() { meta.description_html = description_html.map(|s| s.to_string()); }
+    // A clean update releases a quarantined file
+    meta.scan = scan_info;
 
     let path_key = Keys::path(database, filestore, &meta.logical_path);
     kv.set(path_key, KvValue::Json(serde_json::to_value(&meta)?), None, None);
+    if meta.is_quarantined() { fulltext::unindex_file(store, database, filestore, &meta.logical_path); }
+    else { fulltext::index_file(store, database, filestore, &meta, bytes, eff); }
+    quota::record_write(store, database, filestore, eff, quota_check, &meta.logical_path, ctx.request_id.as_deref());
+    events::publish(store, FileEvent::for_file("update", database, filestore, &meta, ctx.request_id.as_deref()));
     let corr = ctx.request_id.as_deref().unwrap_or("-");
     let desc_len = meta.description_html.as_ref().map(|s| s.len()).unwrap_or(0);
     crate::tprintln!("FILESTORE update_from_bytes ok fs={} path={} size={} etag={} ct_len={} desc_len={} [corr={}]",
@@ -208,12 +289,17 @@ pub async fn rename_file(
     if !d1.allow { bail!(d1.reason.unwrap_or_else(|| "acl_denied_old".to_string())); }
     let d2 = check_acl(eff, user, ACLAction::Move, &new_nfc, Some(&old_nfc), &ctx_move, filestore).await;
     if !d2.allow { bail!(d2.reason.unwrap_or_else(|| "acl_denied_new".to_string())); }
+    locks::check_unlocked(store, database, filestore, &old_nfc, user)?;
+    locks::check_unlocked(store, database, filestore, &new_nfc, user)?;
 
     let kv = store.kv_store(database, filestore);
     let old_key = Keys::path(database, filestore, &old_nfc);
     let meta_val = kv.get(&old_key).ok_or_else(|| anyhow::anyhow!("not_found"))?;
     let mut meta: FileMeta = match meta_val { KvValue::Json(j) => serde_json::from_value(j)?, _ => bail!("corrupt_meta") };
     if meta.deleted { bail!("gone"); }
+    if retention::legal_hold(store, database, filestore, &old_nfc).is_some() { bail!("legal_hold: {}", old_nfc); }
+    // Moving into a prefix with its own quota counts against it
+    let quota_check = quota::check_write(store, database, filestore, eff, Some((old_nfc.as_str(), meta.size)), Some((new_nfc.as_str(), meta.size)), ctx.request_id.as_deref())?;
 
     // Write new meta and tombstone old
     let now = Utc::now().timestamp();
@@ -229,6 +315,10 @@ pub async fn rename_file(
     meta.updated_at = now;
     meta.version = meta.version.saturating_add(1);
     kv.set(old_key, KvValue::Json(serde_json::to_value(&meta)?), None, None);
+    locks::carry_over(store, database, filestore, &old_nfc, &new_nfc)?;
+    fulltext::rename_file(store, database, filestore, &old_nfc, &new_nfc);
+    quota::record_write(store, database, filestore, eff, quota_check, &new_nfc, ctx.request_id.as_deref());
+    events::publish(store, FileEvent { old_path: Some(old_nfc.clone()), ..FileEvent::for_file("rename", database, filestore, &new_meta, ctx.request_id.as_deref()) });
     let corr = ctx.request_id.as_deref().unwrap_or("-");
     crate::tprintln!("FILESTORE rename_file ok fs={} {} -> {} [corr={}]", filestore, old_nfc, new_nfc, corr);
     Ok(new_meta)
@@ -255,15 +345,78 @@ pub async fn delete_file(
     let decision = check_acl(eff, user, ACLAction::Delete, &path_nfc, None, &ctx_del, filestore).await;
     if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
     if meta.deleted { return Ok(()); }
+    locks::check_unlocked(store, database, filestore, &path_nfc, user)?;
+    retention::check_deletable(store, database, filestore, &meta, eff)?;
+    let quota_check = quota::check_write(store, database, filestore, eff, Some((path_nfc.as_str(), meta.size)), None, ctx.request_id.as_deref())?;
+    retention::retire_version(store, database, filestore, &meta, "deleted", eff)?;
     meta.deleted = true;
     meta.updated_at = Utc::now().timestamp();
     meta.version = meta.version.saturating_add(1);
     kv.set(key, KvValue::Json(serde_json::to_value(&meta)?), None, None);
+    locks::release(store, database, filestore, &path_nfc);
+    fulltext::unindex_file(store, database, filestore, &path_nfc);
+    quota::record_write(store, database, filestore, eff, quota_check, &path_nfc, ctx.request_id.as_deref());
+    events::publish(store, FileEvent::for_file("delete", database, filestore, &meta, ctx.request_id.as_deref()));
     let corr = ctx.request_id.as_deref().unwrap_or("-");
     crate::tprintln!("FILESTORE delete_file ok fs={} path={} [corr={}]", filestore, path_nfc, corr);
     Ok(())
 }
 
+/// Longest metadata key and value, and most keys per file.
+const METADATA_KEY_MAX: usize = 128;
+const METADATA_VALUE_MAX: usize = 4096;
+const METADATA_MAX_KEYS: usize = 64;
+
+/// Set (`Some`) or remove (`None`) user-defined metadata entries of a live file. Content and etag
+/// are unchanged; the version is bumped.
+pub async fn set_file_metadata(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    logical_path: &str,
+    changes: &[(String, Option<String>)],
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<FileMeta> {
+    validate_logical_path(logical_path)?;
+    let path_nfc = normalize_nfc(logical_path);
+    let kv = store.kv_store(database, filestore);
+    let key = Keys::path(database, filestore, &path_nfc);
+    let val = kv.get(&key).ok_or_else(|| anyhow::anyhow!("not_found"))?;
+    let mut meta: FileMeta = match val { KvValue::Json(j) => serde_json::from_value(j)?, _ => bail!("corrupt_meta") };
+    if meta.deleted { bail!("gone"); }
+    for (k, v) in changes {
+        if k.trim().is_empty() || k.len() > METADATA_KEY_MAX { bail!("metadata_key_invalid: '{}'", k); }
+        if v.as_ref().map(|v| v.len() > METADATA_VALUE_MAX).unwrap_or(false) { bail!("metadata_value_too_large: '{}'", k); }
+    }
+
+    let mut ctx2 = ctx.clone();
+    ctx2.content_meta = Some(super::security::ContentMeta { size_bytes: Some(meta.size), media_type: meta.content_type.clone() });
+    let decision = check_acl(eff, user, ACLAction::Write, &path_nfc, None, &ctx2, filestore).await;
+    if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+
+    let mut map = match meta.custom.take() {
+        Some(serde_json::Value::Object(m)) => m,
+        None | Some(serde_json::Value::Null) => serde_json::Map::new(),
+        Some(_) => bail!("corrupt_meta: custom metadata is not an object"),
+    };
+    for (k, v) in changes {
+        match v {
+            Some(v) => { map.insert(k.clone(), serde_json::Value::String(v.clone())); }
+            None => { map.remove(k); }
+        }
+    }
+    if map.len() > METADATA_MAX_KEYS { bail!("metadata_too_many_keys (max {})", METADATA_MAX_KEYS); }
+    meta.custom = (!map.is_empty()).then_some(serde_json::Value::Object(map));
+    meta.updated_at = Utc::now().timestamp();
+    meta.version = meta.version.saturating_add(1);
+    kv.set(key, KvValue::Json(serde_json::to_value(&meta)?), None, None);
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    crate::tprintln!("FILESTORE set_file_metadata ok fs={} path={} changes={} [corr={}]", filestore, path_nfc, changes.len(), corr);
+    Ok(meta)
+}
+
 /// Ingest content from a host path after allowlist validation.
 pub async fn ingest_from_host_path(
     store: &SharedStore,
@@ -341,7 +494,7 @@ pub fn create_tree_from_prefix(
     let entries: Vec<TreeEntry> = files
         .into_iter()
         .filter(|m| !m.deleted)
-        .map(|m| TreeEntry { path: m.logical_path, file_id: m.id, etag: m.etag, size: m.size })
+        .map(|m| TreeEntry { path: m.logical_path, file_id: m.id, etag: m.etag, size: m.size, content: None })
         .collect();
     let id = Uuid::new_v4().to_string();
     let now = Utc::now().timestamp();
@@ -401,6 +554,7 @@ pub fn commit_tree(
     let ref_info = RefInfo { branch: branch.to_string(), head_commit_id: id.clone(), updated_at: now };
     kv.set(ref_key, KvValue::Json(serde_json::to_value(&ref_info)?), None, None);
     crate::tprintln!("FILESTORE commit_tree ok fs={} branch={} commit_id={} tree_id={}", filestore, branch, id, tree_id);
+    events::publish(store, FileEvent { commit_id: Some(id), branch: Some(branch.to_string()), ..FileEvent::new("commit", database, filestore) });
     Ok(commit)
 }
 
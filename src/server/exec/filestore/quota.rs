@@ -0,0 +1,210 @@
+//! Storage quotas: bytes and live file count, per filestore and per logical prefix.
+//!
+//! Limits come from the filestore config (quota_bytes, quota_files, prefix_quotas). Writes call
+//! `check_write` before storing anything; a write that would take a scope over a limit fails
+//! with `quota_exceeded`. Writes that only shrink a scope always pass, so a filestore over its
+//! quota (after the limit was lowered) can still be cleaned up. Usage is summed from the live
+//! file metas, tombstones excluded, and only when a quota is configured.
+//!
+//! Crossing quota_warn_percent of a limit records a QuotaEvent: "warning" going up, "cleared"
+//! coming back down, and "exceeded" for a refused write. Events are kept under
+//! `Keys::quota_event` (the latest `EVENTS_KEPT`), logged, and passed to the listeners
+//! registered with `register_quota_listener`.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{KvValue, SharedStore};
+
+use super::config::EffectiveConfig;
+use super::kv::Keys;
+use super::ops::list_files_by_prefix;
+
+/// Events kept per filestore; older ones are dropped as new ones are recorded.
+const EVENTS_KEPT: usize = 256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// A quota scope: the whole filestore (empty prefix) or a logical prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    pub prefix: String,
+    pub max_bytes: Option<u64>,
+    pub max_files: Option<u64>,
+}
+
+impl Scope {
+    fn contains(&self, path: &str) -> bool {
+        self.prefix.is_empty() || path == self.prefix || path.strip_prefix(self.prefix.as_str()).map(|r| r.starts_with('/')).unwrap_or(false)
+    }
+
+    /// Highest share of a limit in use, in percent; `None` without limits.
+    pub fn used_percent(&self, u: &Usage) -> Option<f64> {
+        let pct = |used: u64, max: Option<u64>| max.map(|m| if m == 0 { if used == 0 { 0.0 } else { 100.0 } } else { used as f64 * 100.0 / m as f64 });
+        match (pct(u.bytes, self.max_bytes), pct(u.files, self.max_files)) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuotaEvent {
+    pub at: i64,
+    pub filestore: String,
+    /// Scope prefix; empty for the whole filestore
+    pub prefix: String,
+    /// "warning" | "cleared" | "exceeded"
+    pub kind: String,
+    /// "bytes" | "files"
+    pub metric: String,
+    /// Usage after the write; for "exceeded", the usage the write would have reached
+    pub used: u64,
+    pub limit: u64,
+    /// Path of the write that caused the event
+    pub path: String,
+    pub correlation_id: Option<String>,
+}
+
+type Listener = Box<dyn Fn(&QuotaEvent) + Send + Sync>;
+
+static LISTENERS: Lazy<RwLock<Vec<Listener>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Call `f` for every quota event recorded in this process.
+pub fn register_quota_listener(f: Listener) {
+    LISTENERS.write().push(f);
+}
+
+/// Configured scopes: the filestore first (when it has a limit), then the prefixes.
+pub fn scopes(eff: &EffectiveConfig) -> Vec<Scope> {
+    let mut out = Vec::new();
+    if eff.quota_bytes.is_some() || eff.quota_files.is_some() {
+        out.push(Scope { prefix: String::new(), max_bytes: eff.quota_bytes, max_files: eff.quota_files });
+    }
+    for q in &eff.prefix_quotas {
+        if q.max_bytes.is_none() && q.max_files.is_none() { continue; }
+        out.push(Scope { prefix: q.prefix.trim_matches('/').to_string(), max_bytes: q.max_bytes, max_files: q.max_files });
+    }
+    out
+}
+
+/// Usage of each scope, in one pass over the live files.
+pub fn usage_of(store: &SharedStore, database: &str, filestore: &str, scopes: &[Scope]) -> Result<Vec<Usage>> {
+    let mut out = vec![Usage::default(); scopes.len()];
+    if scopes.is_empty() { return Ok(out); }
+    for m in list_files_by_prefix(store, database, filestore, None)?.into_iter().filter(|m| !m.deleted) {
+        for (s, u) in scopes.iter().zip(out.iter_mut()) {
+            if s.contains(&m.logical_path) { u.bytes += m.size; u.files += 1; }
+        }
+    }
+    Ok(out)
+}
+
+/// Live bytes and files of the whole filestore.
+pub fn total_usage(store: &SharedStore, database: &str, filestore: &str) -> Result<Usage> {
+    let all = [Scope { prefix: String::new(), max_bytes: None, max_files: None }];
+    Ok(usage_of(store, database, filestore, &all)?[0])
+}
+
+/// Usage of the scopes a write touches, before and after it; hand back to `record_write`.
+#[derive(Debug, Clone, Default)]
+pub struct QuotaCheck {
+    changes: Vec<(Scope, Usage, Usage)>,
+}
+
+/// Check a write replacing `old` (path, size of the live file it removes or overwrites) with
+/// `new` (path, size of the file it leaves); either may be absent. Fails with `quota_exceeded`
+/// when a scope grows past a limit.
+pub fn check_write(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    eff: &EffectiveConfig,
+    old: Option<(&str, u64)>,
+    new: Option<(&str, u64)>,
+    corr: Option<&str>,
+) -> Result<QuotaCheck> {
+    let all = scopes(eff);
+    let touched: Vec<Scope> = all.into_iter()
+        .filter(|s| old.map(|(p, _)| s.contains(p)).unwrap_or(false) || new.map(|(p, _)| s.contains(p)).unwrap_or(false))
+        .collect();
+    let usage = usage_of(store, database, filestore, &touched)?;
+    let mut out = QuotaCheck::default();
+    for (s, before) in touched.into_iter().zip(usage) {
+        let mut after = before;
+        if let Some((_, size)) = old.filter(|(p, _)| s.contains(p)) { after.bytes = after.bytes.saturating_sub(size); after.files = after.files.saturating_sub(1); }
+        if let Some((_, size)) = new.filter(|(p, _)| s.contains(p)) { after.bytes += size; after.files += 1; }
+        let path = new.or(old).map(|(p, _)| p).unwrap_or("");
+        for (metric, used, was, max) in [("bytes", after.bytes, before.bytes, s.max_bytes), ("files", after.files, before.files, s.max_files)] {
+            let Some(max) = max else { continue };
+            if used > max && used > was {
+                record(store, database, filestore, &s.prefix, "exceeded", metric, used, max, path, corr);
+                let scope = if s.prefix.is_empty() { format!("filestore '{}'", filestore) } else { format!("prefix '{}'", s.prefix) };
+                bail!("quota_exceeded: {} {} {} > {}", scope, metric, used, max);
+            }
+        }
+        out.changes.push((s, before, after));
+    }
+    Ok(out)
+}
+
+/// After a successful write: record warning/cleared events for scopes that crossed the threshold.
+pub fn record_write(store: &SharedStore, database: &str, filestore: &str, eff: &EffectiveConfig, check: QuotaCheck, path: &str, corr: Option<&str>) {
+    let pct = eff.quota_warn_percent as u64;
+    for (s, before, after) in check.changes {
+        for (metric, was, now, max) in [("bytes", before.bytes, after.bytes, s.max_bytes), ("files", before.files, after.files, s.max_files)] {
+            let Some(max) = max else { continue };
+            // Compare in integers: used * 100 >= max * pct
+            let over = |used: u64| (used as u128) * 100 >= (max as u128) * (pct as u128);
+            match (over(was), over(now)) {
+                (false, true) => record(store, database, filestore, &s.prefix, "warning", metric, now, max, path, corr),
+                (true, false) => record(store, database, filestore, &s.prefix, "cleared", metric, now, max, path, corr),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn record(store: &SharedStore, database: &str, filestore: &str, prefix: &str, kind: &str, metric: &str, used: u64, limit: u64, path: &str, corr: Option<&str>) {
+    let ev = QuotaEvent {
+        at: Utc::now().timestamp(),
+        filestore: filestore.to_string(),
+        prefix: prefix.to_string(),
+        kind: kind.to_string(),
+        metric: metric.to_string(),
+        used,
+        limit,
+        path: path.to_string(),
+        correlation_id: corr.map(str::to_string),
+    };
+    crate::tprintln!("FILESTORE quota {} fs={} prefix={} {}={}/{} path={} [corr={}]", kind, filestore, prefix, metric, used, limit, path, corr.unwrap_or("-"));
+    let kv = store.kv_store(database, filestore);
+    // Sortable id: nanosecond timestamp, then a random suffix against collisions
+    let id = format!("{:020}-{}", Utc::now().timestamp_nanos_opt().unwrap_or_default(), &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    if let Ok(j) = serde_json::to_value(&ev) { kv.set(Keys::quota_event(database, filestore, &id), KvValue::Json(j), None, None); }
+    let prefix_key = Keys::quota_event_prefix(database, filestore);
+    let mut keys: Vec<String> = kv.keys().into_iter().filter(|k| k.starts_with(&prefix_key)).collect();
+    if keys.len() > EVENTS_KEPT {
+        keys.sort();
+        for k in &keys[..keys.len() - EVENTS_KEPT] { kv.delete(k); }
+    }
+    for l in LISTENERS.read().iter() { l(&ev); }
+}
+
+/// Recorded events, most recent first.
+pub fn list_quota_events(store: &SharedStore, database: &str, filestore: &str) -> Vec<QuotaEvent> {
+    let kv = store.kv_store(database, filestore);
+    let prefix = Keys::quota_event_prefix(database, filestore);
+    let mut keys: Vec<String> = kv.keys().into_iter().filter(|k| k.starts_with(&prefix)).collect();
+    keys.sort();
+    keys.into_iter().rev()
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value(j).ok(), _ => None })
+        .collect()
+}
@@ -6,9 +6,10 @@ use anyhow::Result;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
+use crate::storage::encryption::EncryptionSpec;
 use crate::storage::{KvValue, SharedStore};
 
-use super::config::FilestoreConfig;
+use super::config::{FilestoreConfig, PrefixQuota, WebhookConfig};
 use super::kv::Keys;
 
 /// Registry entry stored under `Keys::info_registry(db, fs)`.
@@ -40,7 +41,29 @@ pub struct FilestoreConfigUpdate {
     pub git_backend: Option<Option<String>>,
     pub git_push_backend: Option<Option<String>>,
     pub lfs_patterns: Option<Option<String>>,
+    pub git_username: Option<Option<String>>,
+    pub git_token_env: Option<Option<String>>,
+    pub git_ssh_key_path: Option<Option<String>>,
+    pub git_conflict_policy: Option<Option<String>>,
     pub html_description_max_bytes: Option<Option<usize>>,
+    pub chunking_threshold_bytes: Option<Option<u64>>,
+    pub blob_backend: Option<Option<String>>,
+    pub blob_endpoint: Option<Option<String>>,
+    pub fulltext_index: Option<Option<bool>>,
+    pub fulltext_pdf: Option<Option<bool>>,
+    pub quota_bytes: Option<Option<u64>>,
+    pub quota_files: Option<Option<u64>>,
+    pub prefix_quotas: Option<Option<Vec<PrefixQuota>>>,
+    pub quota_warn_percent: Option<Option<u8>>,
+    pub retention_min_seconds: Option<Option<u64>>,
+    pub retention_versions: Option<Option<u64>>,
+    pub webhooks: Option<Option<Vec<WebhookConfig>>>,
+    pub scanner: Option<Option<String>>,
+    pub scan_mode: Option<Option<String>>,
+    pub scan_fail_open: Option<Option<bool>>,
+    pub encryption: Option<Option<EncryptionSpec>>,
+    pub gc_interval_seconds: Option<Option<u64>>,
+    pub gc_grace_seconds: Option<Option<u64>>,
 }
 
 /// Save (create or overwrite) a registry entry for a filestore.
@@ -132,7 +155,29 @@ pub fn alter_filestore_entry(store: &SharedStore, database: &str, fs: &str, upda
         if let Some(v) = update.git_backend { ent.config.git_backend = v; }
         if let Some(v) = update.git_push_backend { ent.config.git_push_backend = v; }
         if let Some(v) = update.lfs_patterns { ent.config.lfs_patterns = v; }
+        if let Some(v) = update.git_username { ent.config.git_username = v; }
+        if let Some(v) = update.git_token_env { ent.config.git_token_env = v; }
+        if let Some(v) = update.git_ssh_key_path { ent.config.git_ssh_key_path = v; }
+        if let Some(v) = update.git_conflict_policy { ent.config.git_conflict_policy = v; }
         if let Some(v) = update.html_description_max_bytes { ent.config.html_description_max_bytes = v; }
+        if let Some(v) = update.chunking_threshold_bytes { ent.config.chunking_threshold_bytes = v; }
+        if let Some(v) = update.blob_backend { ent.config.blob_backend = v; }
+        if let Some(v) = update.blob_endpoint { ent.config.blob_endpoint = v; }
+        if let Some(v) = update.fulltext_index { ent.config.fulltext_index = v; }
+        if let Some(v) = update.fulltext_pdf { ent.config.fulltext_pdf = v; }
+        if let Some(v) = update.quota_bytes { ent.config.quota_bytes = v; }
+        if let Some(v) = update.quota_files { ent.config.quota_files = v; }
+        if let Some(v) = update.prefix_quotas { ent.config.prefix_quotas = v; }
+        if let Some(v) = update.quota_warn_percent { ent.config.quota_warn_percent = v; }
+        if let Some(v) = update.retention_min_seconds { ent.config.retention_min_seconds = v; }
+        if let Some(v) = update.retention_versions { ent.config.retention_versions = v; }
+        if let Some(v) = update.webhooks { ent.config.webhooks = v; }
+        if let Some(v) = update.scanner { ent.config.scanner = v; }
+        if let Some(v) = update.scan_mode { ent.config.scan_mode = v; }
+        if let Some(v) = update.scan_fail_open { ent.config.scan_fail_open = v; }
+        if let Some(v) = update.encryption { ent.config.encryption = v; }
+        if let Some(v) = update.gc_interval_seconds { ent.config.gc_interval_seconds = v; }
+        if let Some(v) = update.gc_grace_seconds { ent.config.gc_grace_seconds = v; }
 
         ent.config_version = ent.config_version.saturating_add(1);
         ent.updated_at = Utc::now().timestamp();
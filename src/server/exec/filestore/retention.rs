@@ -0,0 +1,157 @@
+//! Retention policies and legal holds.
+//!
+//! With `retention_min_seconds` a file cannot be deleted until that long after it was created.
+//! When retention is configured, or the path is under legal hold, the content an update
+//! replaces or a delete removes is kept as a FileVersion under `Keys::version`, pinned in the
+//! chunk store. GC (gc.rs) prunes a version once it is older than `retention_min_seconds` and
+//! not among the latest `retention_versions` of its file, and purges tombstones only after the
+//! same minimum.
+//!
+//! A legal hold on a logical path blocks delete and rename of the file there and keeps its
+//! tombstone and versions from GC until the hold is cleared, whatever the retention settings.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+
+use crate::storage::{KvValue, SharedStore};
+
+use super::blob;
+use super::chunker::{self, ChunkParams};
+use super::config::EffectiveConfig;
+use super::kv::Keys;
+use super::ops::get_file_bytes;
+use super::paths::{normalize_nfc, validate_logical_path};
+use super::security::{check_acl, ACLAction, AclContext, AclUser};
+use super::types::{FileMeta, FileVersion, LegalHold};
+
+/// Whether updates and deletes keep the content they replace.
+pub fn keeps_versions(eff: &EffectiveConfig) -> bool {
+    eff.retention_min_seconds > 0 || eff.retention_versions > 0
+}
+
+/// The legal hold on a path, if any.
+pub fn legal_hold(store: &SharedStore, database: &str, filestore: &str, logical_path: &str) -> Option<LegalHold> {
+    let kv = store.kv_store(database, filestore);
+    match kv.get(&Keys::legal_hold(database, filestore, &normalize_nfc(logical_path))) {
+        Some(KvValue::Json(j)) => serde_json::from_value(j).ok(),
+        _ => None,
+    }
+}
+
+/// Legal holds of the filestore, sorted by path.
+pub fn list_legal_holds(store: &SharedStore, database: &str, filestore: &str) -> Vec<LegalHold> {
+    let kv = store.kv_store(database, filestore);
+    let prefix = Keys::legal_hold_prefix(database, filestore);
+    let mut out: Vec<LegalHold> = kv.keys().into_iter()
+        .filter(|k| k.starts_with(&prefix))
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value(j).ok(), _ => None })
+        .collect();
+    out.sort_by(|a, b| a.logical_path.cmp(&b.logical_path));
+    out
+}
+
+/// Place a legal hold on a path; the file need not exist yet. Setting it again replaces the reason.
+pub async fn set_legal_hold(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    logical_path: &str,
+    reason: Option<&str>,
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<LegalHold> {
+    validate_logical_path(logical_path)?;
+    let path_nfc = normalize_nfc(logical_path);
+    let decision = check_acl(eff, user, ACLAction::Write, &path_nfc, None, ctx, filestore).await;
+    if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+    let hold = LegalHold { logical_path: path_nfc.clone(), reason: reason.map(str::to_string), set_by: user.id.clone(), set_at: Utc::now().timestamp() };
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::legal_hold(database, filestore, &path_nfc), KvValue::Json(serde_json::to_value(&hold)?), None, None);
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    crate::tprintln!("FILESTORE legal_hold set fs={} path={} by={} [corr={}]", filestore, path_nfc, user.id, corr);
+    Ok(hold)
+}
+
+/// Release the legal hold on a path (checked as a Delete, since it makes the file deletable).
+/// Returns whether there was one.
+pub async fn clear_legal_hold(
+    store: &SharedStore,
+    database: &str,
+    filestore: &str,
+    logical_path: &str,
+    user: &AclUser,
+    eff: &EffectiveConfig,
+    ctx: &AclContext,
+) -> Result<bool> {
+    let path_nfc = normalize_nfc(logical_path);
+    let decision = check_acl(eff, user, ACLAction::Delete, &path_nfc, None, ctx, filestore).await;
+    if !decision.allow { bail!(decision.reason.unwrap_or_else(|| "acl_denied".to_string())); }
+    let kv = store.kv_store(database, filestore);
+    let had = kv.delete(&Keys::legal_hold(database, filestore, &path_nfc));
+    let corr = ctx.request_id.as_deref().unwrap_or("-");
+    crate::tprintln!("FILESTORE legal_hold clear fs={} path={} existed={} by={} [corr={}]", filestore, path_nfc, had, user.id, corr);
+    Ok(had)
+}
+
+/// Refuse to delete the live file `meta` while it is under legal hold or younger than the
+/// minimum retention.
+pub fn check_deletable(store: &SharedStore, database: &str, filestore: &str, meta: &FileMeta, eff: &EffectiveConfig) -> Result<()> {
+    if legal_hold(store, database, filestore, &meta.logical_path).is_some() {
+        bail!("legal_hold: {}", meta.logical_path);
+    }
+    let until = meta.created_at.saturating_add(eff.retention_min_seconds as i64);
+    if eff.retention_min_seconds > 0 && Utc::now().timestamp() < until {
+        bail!("retention_active: {} is retained until {}", meta.logical_path, until);
+    }
+    Ok(())
+}
+
+/// Keep the current content of `meta` as a version before an update replaces it or a delete
+/// removes it; does nothing unless retention is configured or the path is under legal hold.
+pub fn retire_version(store: &SharedStore, database: &str, filestore: &str, meta: &FileMeta, reason: &str, eff: &EffectiveConfig) -> Result<Option<FileVersion>> {
+    if !keeps_versions(eff) && legal_hold(store, database, filestore, &meta.logical_path).is_none() {
+        return Ok(None);
+    }
+    let content = match &meta.chunking {
+        Some(ch) => ch.clone(),
+        None => {
+            let Some(bytes) = get_file_bytes(store, database, filestore, meta)? else { return Ok(None) };
+            let backend = blob::backend_for(store, database, filestore, eff)?;
+            chunker::store_chunked(store, database, filestore, &bytes, &ChunkParams::default(), backend.as_ref())?.0
+        }
+    };
+    let v = FileVersion {
+        file_id: meta.id.clone(),
+        logical_path: meta.logical_path.clone(),
+        version: meta.version,
+        size: meta.size,
+        etag: meta.etag.clone(),
+        content_type: meta.content_type.clone(),
+        written_at: meta.updated_at,
+        retired_at: Utc::now().timestamp(),
+        reason: reason.to_string(),
+        content,
+    };
+    let kv = store.kv_store(database, filestore);
+    kv.set(Keys::version(database, filestore, &v.file_id, v.version), KvValue::Json(serde_json::to_value(&v)?), None, None);
+    Ok(Some(v))
+}
+
+/// Retained versions, by file then version (oldest first); optionally only those last at `logical_path`.
+pub fn list_versions(store: &SharedStore, database: &str, filestore: &str, logical_path: Option<&str>) -> Vec<FileVersion> {
+    let kv = store.kv_store(database, filestore);
+    let prefix = Keys::version_prefix(database, filestore);
+    let path_nfc = logical_path.map(normalize_nfc);
+    let mut keys: Vec<String> = kv.keys().into_iter().filter(|k| k.starts_with(&prefix)).collect();
+    keys.sort();
+    keys.into_iter()
+        .filter_map(|k| match kv.get(&k) { Some(KvValue::Json(j)) => serde_json::from_value::<FileVersion>(j).ok(), _ => None })
+        .filter(|v| path_nfc.as_deref().map(|p| v.logical_path == p).unwrap_or(true))
+        .collect()
+}
+
+/// Content of a retained version.
+pub fn version_bytes(store: &SharedStore, database: &str, filestore: &str, v: &FileVersion) -> Result<Option<Vec<u8>>> {
+    chunker::read_chunked(store, database, filestore, &v.content)
+}
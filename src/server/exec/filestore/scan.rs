@@ -0,0 +1,62 @@
+//! Content scanning on write.
+//!
+//! When a filestore sets `scan_mode` ("reject" | "quarantine"), or names a `scanner` (mode then
+//! defaults to "reject"), ingest and update pass the content through the configured scanner and
+//! then the scan hooks registered with `sec::hooks::register_scan`, before anything is stored.
+//! A flagged file is refused with `scan_rejected`, or stored with scan status "quarantined":
+//! it stays listed but its content cannot be read until a clean version replaces it. When no
+//! scanner gives a verdict the write fails with `scan_failed`, unless `scan_fail_open` lets it
+//! through with status "error".
+
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+
+use super::config::EffectiveConfig;
+use super::sec::hooks::{self, ScanVerdict};
+use super::sec::scan::scanner_from_uri;
+use super::types::ScanInfo;
+
+/// "reject" or "quarantine" when scanning is on.
+pub fn scan_mode(eff: &EffectiveConfig) -> Option<&str> {
+    match eff.scan_mode.as_deref().map(str::trim) {
+        Some(m) if m.eq_ignore_ascii_case("off") || m.is_empty() => None,
+        Some(m) if m.eq_ignore_ascii_case("quarantine") => Some("quarantine"),
+        Some(_) => Some("reject"),
+        None => eff.scanner.as_ref().map(|_| "reject"),
+    }
+}
+
+/// Scan content about to be written at `path`. Returns the ScanInfo to store with the file,
+/// None when scanning is off; fails when the file is rejected or cannot be scanned.
+pub fn scan_for_write(eff: &EffectiveConfig, filestore: &str, path: &str, bytes: &[u8], corr: Option<&str>) -> Result<Option<ScanInfo>> {
+    let Some(mode) = scan_mode(eff) else { return Ok(None) };
+    let mut results: Vec<(String, Result<ScanVerdict>)> = Vec::new();
+    if let Some(uri) = &eff.scanner {
+        let timeout = Duration::from_millis(eff.scan_timeout_ms.max(1));
+        match scanner_from_uri(uri, timeout) {
+            Ok(s) => results.push((s.name().to_string(), s.scan(path, bytes))),
+            Err(e) => results.push(("config".to_string(), Err(e))),
+        }
+    }
+    if !matches!(results.last(), Some((_, Ok(ScanVerdict::Flagged(_))))) {
+        results.extend(hooks::run_scans(path, bytes));
+    }
+    let now = Utc::now().timestamp();
+    let corr = corr.unwrap_or("-");
+    if let Some((name, sig)) = results.iter().find_map(|(n, r)| match r { Ok(ScanVerdict::Flagged(s)) => Some((n, s)), _ => None }) {
+        crate::tprintln!("FILESTORE scan flagged fs={} path={} scanner={} signature={} mode={} [corr={}]", filestore, path, name, sig, mode, corr);
+        if mode == "reject" { bail!("scan_rejected: {} flagged by {}: {}", path, name, sig); }
+        return Ok(Some(ScanInfo { status: "quarantined".into(), scanner: name.clone(), signature: Some(sig.clone()), scanned_at: now }));
+    }
+    let names = results.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(",");
+    let errors: Vec<String> = results.iter().filter_map(|(n, r)| r.as_ref().err().map(|e| format!("{}: {}", n, e))).collect();
+    if results.is_empty() || !errors.is_empty() {
+        let why = if results.is_empty() { "no scanner configured or registered".to_string() } else { errors.join("; ") };
+        crate::tprintln!("FILESTORE scan failed fs={} path={} fail_open={} error={} [corr={}]", filestore, path, eff.scan_fail_open, why, corr);
+        if !eff.scan_fail_open { bail!("scan_failed: {}: {}", path, why); }
+        return Ok(Some(ScanInfo { status: "error".into(), scanner: names, signature: Some(why), scanned_at: now }));
+    }
+    Ok(Some(ScanInfo { status: "clean".into(), scanner: names, signature: None, scanned_at: now }))
+}
@@ -1,5 +1,3 @@
-use anyhow::Result;
-
 use super::evaluator;
 use super::model::{Action, Context, ResourceId, User};
 
@@ -16,11 +14,21 @@ pub struct Decision {
     pub reason: Option<String>,
 }
 
+/// A decision with how it was reached (EXPLAIN ACCESS).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub decision: Decision,
+    /// Roles the user was evaluated with: its own plus memberships valid now
+    pub roles: Vec<String>,
+    /// The policy that decided; None for the admin and fallback role gates
+    pub policy_id: Option<String>,
+    pub resource: ResourceId,
+}
+
 pub fn authorize(user: &User, action: Action, resource: &ResourceId, ctx: &Context) -> Decision {
     evaluator::evaluate(user, action, resource, ctx)
 }
 
-pub fn explain(user: &User, action: Action, resource: &ResourceId) -> Result<String> {
-    // Placeholder for detailed explain; wire to evaluator traces later.
-    Ok(format!("explain: user={} action={:?} resource={}", user.id, action, resource.0))
+pub fn explain(user: &User, action: Action, resource: &ResourceId, ctx: &Context) -> Explanation {
+    evaluator::explain(user, action, resource, ctx)
 }
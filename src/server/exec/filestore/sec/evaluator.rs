@@ -1,4 +1,7 @@
 //! In-process authorization evaluator (storage-backed RBAC with deny precedence).
+//! Policies and role memberships are evaluated from an in-memory snapshot of security.policies
+//! and security.role_memberships. Every write through `sec::storage` bumps the global epoch;
+//! [`ensure_fresh`] reloads the snapshot when it is behind, so evaluation itself never queries.
 //! Keep logic small and fast; avoid large match statements by delegating to helpers.
 
 use std::collections::HashMap;
@@ -7,42 +10,37 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 
 use super::model::{Action, Context, ResourceId, User};
-use super::api::Decision;
+use super::api::{Decision, Explanation};
+use super::epochs;
+use super::storage::policies::PolicyRecord;
+use super::storage::role_memberships::MembershipRecord;
 
 // Global store for loading roles/policies on demand (set once by host)
 static STORE: Lazy<RwLock<Option<crate::storage::SharedStore>>> = Lazy::new(|| RwLock::new(None));
 
-// Cache compiled policies per role with a simple global epoch tag
 #[derive(Clone)]
 struct CompiledPolicy {
+    id: String,
+    role: String,         // lowercase
     actions: Vec<String>, // lowercase; "*" matches all
     res_regex: Regex,
     allow: bool,          // true=allow, false=deny
-    priority: i32,
 }
 
-static ROLE_CACHE: Lazy<RwLock<HashMap<String, (u64, Vec<CompiledPolicy>)>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+/// Policies (highest priority first) and memberships as of `epoch`.
+#[derive(Default)]
+struct Snapshot {
+    epoch: u64,
+    policies: Vec<CompiledPolicy>,
+    memberships: Vec<MembershipRecord>,
+}
+
+static SNAPSHOT: Lazy<RwLock<Snapshot>> = Lazy::new(|| RwLock::new(Snapshot::default()));
 
 fn allow_all() -> Decision { Decision { allow: true, reason: Some("role_admin".into()) } }
 fn deny(reason: &str) -> Decision { Decision { allow: false, reason: Some(reason.into()) } }
 
-fn has_role(user: &User, role: &str) -> bool { user.roles.iter().any(|r| r.eq_ignore_ascii_case(role)) }
-
-fn action_str(a: Action) -> &'static str {
-    match a {
-        Action::Read => "read",
-        Action::Write => "write",
-        Action::Delete => "delete",
-        Action::Move => "move",
-        Action::Copy => "copy",
-        Action::Rename => "rename",
-        Action::List => "list",
-        Action::Commit => "commit",
-        Action::Push => "push",
-        Action::Pull => "pull",
-        Action::Clone => "clone",
-    }
-}
+fn has_role(roles: &[String], role: &str) -> bool { roles.iter().any(|r| r.eq_ignore_ascii_case(role)) }
 
 fn glob_to_regex(pattern: &str) -> Regex {
     // Convert simple glob (with * and **) to a Rust regex anchored at both ends
@@ -55,74 +53,53 @@ fn glob_to_regex(pattern: &str) -> Regex {
     Regex::new(&full).unwrap_or_else(|_| Regex::new("^$" ).unwrap())
 }
 
-fn compile_policy(p: &crate::server::exec::filestore::sec::storage::policies::PolicyRecord) -> CompiledPolicy {
+fn compile_policy(p: &PolicyRecord) -> CompiledPolicy {
     let rx = glob_to_regex(&p.resource_selector);
     let allow = p.effect == "allow";
-    CompiledPolicy { actions: p.actions.clone(), res_regex: rx, allow, priority: p.priority }
+    CompiledPolicy { id: p.policy_id.clone(), role: p.role_id.to_ascii_lowercase(), actions: p.actions.clone(), res_regex: rx, allow }
 }
 
-fn fetch_policies_for_roles_sync(store: &crate::storage::SharedStore, role_ids: &[String]) -> Vec<CompiledPolicy> {
-    // Attempt to call async storage using the current Tokio runtime; if unavailable, return empty
-    match tokio::runtime::Handle::try_current() {
-        Ok(h) => {
-            if let Ok(policies) = h.block_on(crate::server::exec::filestore::sec::storage::policies::list_policies_for_roles(store, role_ids)) {
-                return policies.into_iter().map(|p| compile_policy(&p)).collect();
-            }
-        }
-        Err(_) => { /* no runtime; fall through */ }
-    }
-    Vec::new()
+fn compile_all(policies: &[PolicyRecord]) -> Vec<CompiledPolicy> {
+    let mut compiled: Vec<(i32, CompiledPolicy)> = policies.iter().map(|p| (p.priority, compile_policy(p))).collect();
+    // Sort by priority (desc) to make evaluation consistent
+    compiled.sort_by(|a, b| b.0.cmp(&a.0));
+    compiled.into_iter().map(|(_, p)| p).collect()
 }
 
-fn load_policies_for_roles(role_ids: &[String]) -> Vec<CompiledPolicy> {
-    if role_ids.is_empty() { return Vec::new(); }
-    // Check cache per role; refetch when epoch changes
-    let epoch = crate::server::exec::filestore::sec::epochs::epoch_global();
-    let store_opt = STORE.read().clone();
-    let Some(store) = store_opt else { return Vec::new(); };
-
-    let mut out: Vec<CompiledPolicy> = Vec::new();
-    let mut miss_roles: Vec<String> = Vec::new();
-    {
-        let cache = ROLE_CACHE.read();
-        for r in role_ids {
-            if let Some((ep, pols)) = cache.get(r) {
-                if *ep == epoch { out.extend_from_slice(pols); continue; }
-            }
-            miss_roles.push(r.clone());
-        }
-    }
-    if !miss_roles.is_empty() {
-        let policies = fetch_policies_for_roles_sync(&store, &miss_roles);
-        let mut grouped: HashMap<String, Vec<CompiledPolicy>> = HashMap::new();
-        for p in policies.into_iter() {
-            // We lost role_id in compile step; fetch raw again to preserve
-        }
-        // Fetch raw to preserve role mapping
-        match tokio::runtime::Handle::try_current() {
-            Ok(h) => {
-                if let Ok(raw) = h.block_on(crate::server::exec::filestore::sec::storage::policies::list_policies_for_roles(&store, &miss_roles)) {
-                    let mut grouped: HashMap<String, Vec<CompiledPolicy>> = HashMap::new();
-                    for rp in raw.iter() {
-                        grouped.entry(rp.role_id.clone()).or_default().push(compile_policy(rp));
-                    }
-                    let mut w = ROLE_CACHE.write();
-                    for (role, pols) in grouped.into_iter() {
-                        w.insert(role.clone(), (epoch, pols.clone()));
-                    }
-                }
-            }
-            Err(_) => { /* no runtime; skip cache fill */ }
-        }
-    }
-    // Collect final list
-    let cache = ROLE_CACHE.read();
-    for r in role_ids {
-        if let Some((_ep, pols)) = cache.get(r) { out.extend_from_slice(pols); }
+/// Replace the policy snapshot and bump the global epoch, so cached decisions are dropped.
+pub fn install_snapshot(policies: &[PolicyRecord], memberships: &[MembershipRecord]) {
+    let compiled = compile_all(policies);
+    let mut w = SNAPSHOT.write();
+    w.policies = compiled;
+    w.memberships = memberships.to_vec();
+    w.epoch = epochs::bump_global();
+}
+
+/// Reload the snapshot from the security tables.
+pub async fn refresh(store: &crate::storage::SharedStore) -> anyhow::Result<()> {
+    let started = epochs::epoch_global();
+    let policies = super::storage::policies::list_policies(store).await?;
+    let memberships = super::storage::role_memberships::list_memberships(store).await?;
+    let compiled = compile_all(&policies);
+    let mut w = SNAPSHOT.write();
+    // Something changed while loading: this load may miss it, so leave it to the next check
+    if epochs::epoch_global() != started { return Ok(()); }
+    w.policies = compiled;
+    w.memberships = memberships;
+    w.epoch = epochs::bump_global();
+    crate::tprintln!("[sec] policy snapshot reloaded: policies={} memberships={}", policies.len(), w.memberships.len());
+    Ok(())
+}
+
+/// Reload the snapshot if the security tables changed since it was taken. When they cannot be
+/// read (e.g. not installed) the current snapshot is kept and not retried until the next change.
+pub async fn ensure_fresh() {
+    if SNAPSHOT.read().epoch == epochs::epoch_global() { return; }
+    let Some(store) = STORE.read().clone() else { return; };
+    if let Err(e) = refresh(&store).await {
+        tracing::debug!("security policy snapshot not reloaded: {}", e);
+        SNAPSHOT.write().epoch = epochs::epoch_global();
     }
-    // Sort by priority (desc) to make evaluation consistent
-    out.sort_by(|a, b| b.priority.cmp(&a.priority));
-    out
 }
 
 /// Host can set the global store to enable storage-backed RBAC
@@ -137,7 +114,7 @@ thread_local! {
 
 #[inline]
 fn l1_key(user: &User, action: Action, res: &ResourceId) -> String {
-    format!("{}|{}|{}", user.id, action_str(action), res.0)
+    format!("{}|{}|{}|{}", user.id, user.roles.join(","), action.as_str(), res.0)
 }
 
 fn l1_get(epoch: u64, key: &str) -> Option<Decision> {
@@ -158,55 +135,58 @@ fn l1_put(epoch: u64, key: String, value: Decision) {
     });
 }
 
-pub fn evaluate(user: &User, action: Action, res: &ResourceId, _ctx: &Context) -> Decision {
-    let epoch = crate::server::exec::filestore::sec::epochs::epoch_global();
+pub fn evaluate(user: &User, action: Action, res: &ResourceId, ctx: &Context) -> Decision {
+    let epoch = epochs::epoch_global();
     let key = l1_key(user, action, res);
     if let Some(hit) = l1_get(epoch, &key) { return hit; }
-    // Admin fast-path
-    if has_role(user, "admin") { return allow_all(); }
+    let out = explain(user, action, res, ctx).decision;
+    l1_put(epoch, key, out.clone());
+    out
+}
 
-    // Resolve roles: explicit roles from principal/user
+/// Evaluate without caching and report which roles and policy decided.
+pub fn explain(user: &User, action: Action, res: &ResourceId, _ctx: &Context) -> Explanation {
+    let snap = SNAPSHOT.read();
+    // Resolve roles: explicit roles from principal/user, then memberships valid now
+    let now_ms = chrono::Utc::now().timestamp_millis();
     let mut roles: Vec<String> = user.roles.clone();
-
-    // Optionally augment with dynamic memberships from storage
-    if let Some(store) = STORE.read().clone() {
-        // Try to fetch dynamic roles using current Tokio runtime; ignore on failure
-        if let Ok(handle) = tokio::runtime::Handle::try_current() {
-            if let Ok(mut dyn_roles) = handle.block_on(crate::server::exec::filestore::sec::storage::role_memberships::list_roles_for_user(&store, &user.id)) {
-                roles.append(&mut dyn_roles);
-            }
+    for m in snap.memberships.iter() {
+        if m.user_id.eq_ignore_ascii_case(&user.id) && m.is_valid_at(now_ms) && !has_role(&roles, &m.role_id) {
+            roles.push(m.role_id.clone());
         }
     }
+    let explained = |decision: Decision, policy_id: Option<&str>, roles: Vec<String>| Explanation {
+        decision,
+        roles,
+        policy_id: policy_id.map(str::to_string),
+        resource: res.clone(),
+    };
+    // Admin fast-path
+    if has_role(&roles, "admin") { return explained(allow_all(), None, roles); }
 
-    // Load compiled policies for these roles
-    let compiled = load_policies_for_roles(&roles);
-    let a = action_str(action);
+    let a = action.as_str();
     let r = &res.0;
-
+    let matching: Vec<&CompiledPolicy> = snap.policies.iter()
+        .filter(|p| has_role(&roles, &p.role))
+        .filter(|p| p.actions.iter().any(|x| x == "*" || x == a) && p.res_regex.is_match(r))
+        .collect();
     // Deny precedence: any deny match blocks immediately
-    for p in compiled.iter() {
-        let act_match = p.actions.iter().any(|x| x == "*" || x == a);
-        if act_match && p.res_regex.is_match(r) {
-            if !p.allow { return deny("policy_deny"); }
-        }
+    if let Some(p) = matching.iter().find(|p| !p.allow) {
+        return explained(deny("policy_deny"), Some(&p.id), roles);
     }
     // Allow if any allow policy matches
-    for p in compiled.iter() {
-        let act_match = p.actions.iter().any(|x| x == "*" || x == a);
-        if act_match && p.res_regex.is_match(r) {
-            if p.allow { return Decision { allow: true, reason: Some("policy_allow".into()) }; }
-        }
+    if let Some(p) = matching.iter().find(|p| p.allow) {
+        return explained(Decision { allow: true, reason: Some("policy_allow".into()) }, Some(&p.id), roles);
     }
 
     // Fallback: minimal role-based gates to keep behavior sensible if no policies loaded
     let out = match action {
         Action::Read | Action::List | Action::Pull | Action::Clone => {
-            if has_role(user, "db_reader") || has_role(user, "fs_reader") { Decision { allow: true, reason: Some("role_reader".into()) } } else { deny("no_read_policy") }
+            if has_role(&roles, "db_reader") || has_role(&roles, "fs_reader") { Decision { allow: true, reason: Some("role_reader".into()) } } else { deny("no_read_policy") }
         }
         _ => {
-            if has_role(user, "db_writer") || has_role(user, "fs_writer") { Decision { allow: true, reason: Some("role_writer".into()) } } else { deny("no_write_policy") }
+            if has_role(&roles, "db_writer") || has_role(&roles, "fs_writer") { Decision { allow: true, reason: Some("role_writer".into()) } } else { deny("no_write_policy") }
         }
     };
-    l1_put(epoch, key, out.clone());
-    out
+    explained(out, None, roles)
 }
@@ -37,6 +37,21 @@ pub trait PostListHook: Send + Sync {
     fn on_post_list(&self, _ev: &HookEvent) {}
 }
 
+/// What a content scanner found in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// Flagged, with the signature or policy the scanner reported
+    Flagged(String),
+}
+
+/// Content scan stage, run on file bytes before a write is stored. An Err means the scanner
+/// could not give a verdict (unreachable, timed out), not that the content is bad.
+pub trait ScanHook: Send + Sync {
+    fn name(&self) -> &str;
+    fn scan(&self, path: &str, bytes: &[u8]) -> anyhow::Result<ScanVerdict>;
+}
+
 pub struct HookRegistry {
     pub pre_auth: Vec<Box<dyn PreAuthHook>>,    
     pub post_auth: Vec<Box<dyn PostAuthHook>>,  
@@ -44,11 +59,12 @@ pub struct HookRegistry {
     pub post_mut: Vec<Box<dyn PostMutationHook>>,
     pub post_read: Vec<Box<dyn PostReadHook>>,  
     pub post_list: Vec<Box<dyn PostListHook>>,  
+    pub scan: Vec<Box<dyn ScanHook>>,
 }
 
 impl Default for HookRegistry {
     fn default() -> Self {
-        Self { pre_auth: vec![], post_auth: vec![], pre_mut: vec![], post_mut: vec![], post_read: vec![], post_list: vec![] }
+        Self { pre_auth: vec![], post_auth: vec![], pre_mut: vec![], post_mut: vec![], post_read: vec![], post_list: vec![], scan: vec![] }
     }
 }
 
@@ -66,6 +82,23 @@ pub fn emit_post_auth(ev: &HookEvent) {
     }
 }
 
+pub fn register_scan(h: Box<dyn ScanHook>) {
+    REG.write().scan.push(h);
+}
+
+/// Run the registered scan hooks in order, stopping at the first that flags the content.
+/// Returns each hook's name with its verdict (or error) up to there.
+pub fn run_scans(path: &str, bytes: &[u8]) -> Vec<(String, anyhow::Result<ScanVerdict>)> {
+    let mut out = Vec::new();
+    for h in REG.read().scan.iter() {
+        let r = h.scan(path, bytes);
+        let flagged = matches!(r, Ok(ScanVerdict::Flagged(_)));
+        out.push((h.name().to_string(), r));
+        if flagged { break; }
+    }
+    out
+}
+
 // --- Simple file logger sink for audit events ---
 
 struct FileLogger { path: String }
@@ -10,12 +10,13 @@ pub mod rbac;
 pub mod abac;
 pub mod evaluator;
 pub mod hooks;
+pub mod scan;
 pub mod resources;
 pub mod published;
 pub mod epochs;
 pub mod storage;
 
 // Re‑exports for thin public surface
-pub use api::{authorize, explain, SecurityMode, Decision};
+pub use api::{authorize, explain, SecurityMode, Decision, Explanation};
 pub use model::{Action, User, ResourceId, Context};
-pub use hooks::{HookRegistry, HookEvent, HookOutcome};
+pub use hooks::{HookRegistry, HookEvent, HookOutcome, ScanHook, ScanVerdict};
@@ -28,6 +28,35 @@ pub enum Action {
     Clone,
 }
 
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Read, Action::Write, Action::Delete, Action::Move, Action::Copy, Action::Rename,
+        Action::List, Action::Commit, Action::Push, Action::Pull, Action::Clone,
+    ];
+
+    /// Lowercase name, as written in policies.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Read => "read",
+            Action::Write => "write",
+            Action::Delete => "delete",
+            Action::Move => "move",
+            Action::Copy => "copy",
+            Action::Rename => "rename",
+            Action::List => "list",
+            Action::Commit => "commit",
+            Action::Push => "push",
+            Action::Pull => "pull",
+            Action::Clone => "clone",
+        }
+    }
+
+    /// Parse an action name (case-insensitive).
+    pub fn parse(s: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.as_str().eq_ignore_ascii_case(s.trim()))
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Context {
     #[serde(default)]
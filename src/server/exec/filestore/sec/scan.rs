@@ -0,0 +1,200 @@
+//! Built-in content scanners for the scan hook stage: clamd, ICAP and an external command.
+//!
+//! `scanner_from_uri` builds one from a filestore's `scanner` setting:
+//! - `clamd://host:port` streams the content with clamd's INSTREAM command
+//! - `icap://host:port/service` sends it as an ICAP RESPMOD request
+//! - `command:<program> [args]` runs a program with the content on stdin; exit 0 is clean,
+//!   1 is flagged (the first line of stdout is the signature), anything else is a failure
+//!
+//! All scanners are blocking and bounded by the given timeout.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+
+use super::hooks::{ScanHook, ScanVerdict};
+
+/// clamd accepts INSTREAM chunks up to its StreamMaxLength; keep each one small.
+const CLAMD_CHUNK: usize = 64 * 1024;
+
+/// Build the scanner named by a `scanner` URI.
+pub fn scanner_from_uri(uri: &str, timeout: Duration) -> Result<Box<dyn ScanHook>> {
+    let uri = uri.trim();
+    if let Some(rest) = uri.strip_prefix("clamd://") {
+        let addr = rest.trim_end_matches('/');
+        if addr.is_empty() { bail!("invalid_scanner: missing clamd address in '{}'", uri); }
+        return Ok(Box::new(ClamdScanner { addr: addr.to_string(), timeout }));
+    }
+    if let Some(rest) = uri.strip_prefix("icap://") {
+        let (addr, service) = rest.split_once('/').unwrap_or((rest, ""));
+        if addr.is_empty() { bail!("invalid_scanner: missing ICAP address in '{}'", uri); }
+        return Ok(Box::new(IcapScanner { addr: addr.to_string(), service: service.to_string(), timeout }));
+    }
+    if let Some(rest) = uri.strip_prefix("command:") {
+        let mut parts = rest.split_whitespace().map(str::to_string);
+        let program = parts.next().ok_or_else(|| anyhow!("invalid_scanner: missing program in '{}'", uri))?;
+        return Ok(Box::new(CommandScanner { program, args: parts.collect(), timeout }));
+    }
+    bail!("invalid_scanner: expected clamd://, icap:// or command:, got '{}'", uri)
+}
+
+fn connect(addr: &str, timeout: Duration) -> Result<TcpStream> {
+    let sa = addr.to_socket_addrs().with_context(|| format!("resolve {}", addr))?
+        .next().ok_or_else(|| anyhow!("no address for {}", addr))?;
+    let s = TcpStream::connect_timeout(&sa, timeout).with_context(|| format!("connect {}", addr))?;
+    s.set_read_timeout(Some(timeout))?;
+    s.set_write_timeout(Some(timeout))?;
+    Ok(s)
+}
+
+/// clamd over TCP (INSTREAM).
+pub struct ClamdScanner {
+    pub addr: String,
+    pub timeout: Duration,
+}
+
+impl ScanHook for ClamdScanner {
+    fn name(&self) -> &str { "clamd" }
+
+    fn scan(&self, _path: &str, bytes: &[u8]) -> Result<ScanVerdict> {
+        let mut s = connect(&self.addr, self.timeout)?;
+        s.write_all(b"zINSTREAM\0")?;
+        for chunk in bytes.chunks(CLAMD_CHUNK) {
+            s.write_all(&(chunk.len() as u32).to_be_bytes())?;
+            s.write_all(chunk)?;
+        }
+        s.write_all(&0u32.to_be_bytes())?;
+        s.flush()?;
+        let mut reply = Vec::new();
+        BufReader::new(s).read_until(0, &mut reply)?;
+        let reply = String::from_utf8_lossy(&reply).trim_end_matches('\0').trim().to_string();
+        parse_clamd_reply(&reply)
+    }
+}
+
+/// `stream: OK`, `stream: <signature> FOUND` or `... ERROR`.
+pub fn parse_clamd_reply(reply: &str) -> Result<ScanVerdict> {
+    let body = reply.split_once(": ").map(|(_, b)| b).unwrap_or(reply);
+    if body == "OK" { return Ok(ScanVerdict::Clean); }
+    if let Some(sig) = body.strip_suffix(" FOUND") { return Ok(ScanVerdict::Flagged(sig.trim().to_string())); }
+    bail!("clamd: {}", if reply.is_empty() { "empty reply" } else { reply })
+}
+
+/// ICAP RESPMOD (RFC 3507), the content sent as the body of an HTTP response.
+pub struct IcapScanner {
+    pub addr: String,
+    pub service: String,
+    pub timeout: Duration,
+}
+
+impl ScanHook for IcapScanner {
+    fn name(&self) -> &str { "icap" }
+
+    fn scan(&self, _path: &str, bytes: &[u8]) -> Result<ScanVerdict> {
+        let mut s = connect(&self.addr, self.timeout)?;
+        let res_hdr = format!("HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\n\r\n", bytes.len());
+        let head = format!(
+            "RESPMOD icap://{}/{} ICAP/1.0\r\nHost: {}\r\nAllow: 204\r\nEncapsulated: res-hdr=0, res-body={}\r\n\r\n",
+            self.addr, self.service, self.addr, res_hdr.len()
+        );
+        s.write_all(head.as_bytes())?;
+        s.write_all(res_hdr.as_bytes())?;
+        if !bytes.is_empty() {
+            s.write_all(format!("{:x}\r\n", bytes.len()).as_bytes())?;
+            s.write_all(bytes)?;
+            s.write_all(b"\r\n")?;
+        }
+        s.write_all(b"0\r\n\r\n")?;
+        s.flush()?;
+        // Only the ICAP status and headers matter; a modified body is not read
+        let mut reader = BufReader::new(s);
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 { break; }
+            let line = line.trim_end().to_string();
+            if line.is_empty() { break; }
+            lines.push(line);
+        }
+        parse_icap_reply(&lines)
+    }
+}
+
+/// Status line and headers of an ICAP answer: 204 is clean; 200 is flagged when it carries
+/// X-Infection-Found or X-Violations-Found (their threat name as signature), clean otherwise.
+pub fn parse_icap_reply(lines: &[String]) -> Result<ScanVerdict> {
+    let status = lines.first().ok_or_else(|| anyhow!("icap: empty reply"))?;
+    let code: u16 = status.split_whitespace().nth(1).and_then(|c| c.parse().ok())
+        .ok_or_else(|| anyhow!("icap: bad status line '{}'", status))?;
+    match code {
+        204 => Ok(ScanVerdict::Clean),
+        200 => {
+            for l in &lines[1..] {
+                let Some((name, value)) = l.split_once(':') else { continue };
+                let name = name.trim();
+                if name.eq_ignore_ascii_case("X-Infection-Found") {
+                    // Type=0; Resolution=2; Threat=<name>;
+                    let threat = value.split(';').filter_map(|p| p.trim().strip_prefix("Threat=")).next().unwrap_or(value.trim());
+                    return Ok(ScanVerdict::Flagged(threat.trim().to_string()));
+                }
+                if name.eq_ignore_ascii_case("X-Violations-Found") {
+                    return Ok(ScanVerdict::Flagged(value.trim().to_string()));
+                }
+            }
+            Ok(ScanVerdict::Clean)
+        }
+        _ => bail!("icap: {}", status),
+    }
+}
+
+/// An external program reading the content on stdin; `CLARIUM_SCAN_PATH` holds the logical path.
+pub struct CommandScanner {
+    pub program: String,
+    pub args: Vec<String>,
+    pub timeout: Duration,
+}
+
+impl ScanHook for CommandScanner {
+    fn name(&self) -> &str { "command" }
+
+    fn scan(&self, path: &str, bytes: &[u8]) -> Result<ScanVerdict> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env("CLARIUM_SCAN_PATH", path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("spawn {}", self.program))?;
+        // Feed stdin from a thread so a scanner that answers early cannot block us; it may
+        // close stdin before reading everything
+        let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("no stdin"))?;
+        let input = bytes.to_vec();
+        let writer = std::thread::spawn(move || { let _ = stdin.write_all(&input); });
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(st) = child.try_wait()? { break st; }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                bail!("{}: timed out after {} ms", self.program, self.timeout.as_millis());
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        let _ = writer.join();
+        let mut out = String::new();
+        if let Some(mut o) = child.stdout.take() { let _ = o.read_to_string(&mut out); }
+        match status.code() {
+            Some(0) => Ok(ScanVerdict::Clean),
+            Some(1) => Ok(ScanVerdict::Flagged(out.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("flagged").to_string())),
+            _ => {
+                let mut err = String::new();
+                if let Some(mut e) = child.stderr.take() { let _ = e.read_to_string(&mut err); }
+                bail!("{}: {} {}", self.program, status, err.trim())
+            }
+        }
+    }
+}
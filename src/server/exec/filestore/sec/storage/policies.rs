@@ -11,6 +11,45 @@ pub struct PolicyRecord {
     pub priority: i32,
 }
 
+const POLICY_COLUMNS: &str = "policy_id, role_id, actions, resource_selector, predicate_json, effect, priority";
+
+fn record_from_row(m: &serde_json::Map<String, serde_json::Value>) -> Option<PolicyRecord> {
+    let policy_id = m.get("policy_id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    if policy_id.is_empty() { return None; }
+    let role_id = m.get("role_id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let actions_str = m.get("actions").and_then(|v| v.as_str()).unwrap_or("");
+    let actions = actions_str.split(',').map(|s| s.trim().to_ascii_lowercase()).filter(|s| !s.is_empty()).collect();
+    let resource_selector = m.get("resource_selector").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let predicate_json = m.get("predicate_json").and_then(|v| v.as_str()).map(|s| s.to_string());
+    let effect = m.get("effect").and_then(|v| v.as_str()).unwrap_or("deny").to_ascii_lowercase();
+    let priority = m.get("priority").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
+    Some(PolicyRecord { policy_id, role_id, actions, resource_selector, predicate_json, effect, priority })
+}
+
+async fn query_policies(store: &crate::storage::SharedStore, sql: &str) -> Result<Vec<PolicyRecord>> {
+    let val = crate::server::exec::execute_query_safe(store, sql).await?;
+    let mut out = Vec::new();
+    if let serde_json::Value::Array(rows) = val {
+        for r in rows {
+            if let serde_json::Value::Object(m) = r {
+                out.extend(record_from_row(&m));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Fetch all policies, highest priority first.
+pub async fn list_policies(store: &crate::storage::SharedStore) -> Result<Vec<PolicyRecord>> {
+    query_policies(store, &format!("SELECT {} FROM security.policies ORDER BY priority DESC", POLICY_COLUMNS)).await
+}
+
+/// Get a single policy by id
+pub async fn get_policy(store: &crate::storage::SharedStore, policy_id: &str) -> Result<Option<PolicyRecord>> {
+    let sql = format!("SELECT {} FROM security.policies WHERE policy_id = '{}'", POLICY_COLUMNS, policy_id.replace("'", "''"));
+    Ok(query_policies(store, &sql).await?.into_iter().next())
+}
+
 /// Fetch policies for a set of role IDs. Empty input returns empty.
 pub async fn list_policies_for_roles(store: &crate::storage::SharedStore, role_ids: &[String]) -> Result<Vec<PolicyRecord>> {
     if role_ids.is_empty() { return Ok(Vec::new()); }
@@ -23,28 +62,10 @@ pub async fn list_policies_for_roles(store: &crate::storage::SharedStore, role_i
         inlist.push('\'');
     }
     let sql = format!(
-        "SELECT policy_id, role_id, actions, resource_selector, predicate_json, effect, priority FROM security.policies WHERE role_id IN ({}) ORDER BY priority DESC",
-        inlist
+        "SELECT {} FROM security.policies WHERE role_id IN ({}) ORDER BY priority DESC",
+        POLICY_COLUMNS, inlist
     );
-    let val = crate::server::exec::execute_query_safe(store, &sql).await?;
-    let mut out = Vec::new();
-    if let serde_json::Value::Array(rows) = val {
-        for r in rows {
-            if let serde_json::Value::Object(m) = r {
-                let policy_id = m.get("policy_id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
-                if policy_id.is_empty() { continue; }
-                let role_id = m.get("role_id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
-                let actions_str = m.get("actions").and_then(|v| v.as_str()).unwrap_or("");
-                let actions = actions_str.split(',').map(|s| s.trim().to_ascii_lowercase()).filter(|s| !s.is_empty()).collect();
-                let resource_selector = m.get("resource_selector").and_then(|v| v.as_str()).unwrap_or_default().to_string();
-                let predicate_json = m.get("predicate_json").and_then(|v| v.as_str()).map(|s| s.to_string());
-                let effect = m.get("effect").and_then(|v| v.as_str()).unwrap_or("deny").to_ascii_lowercase();
-                let priority = m.get("priority").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
-                out.push(PolicyRecord { policy_id, role_id, actions, resource_selector, predicate_json, effect, priority });
-            }
-        }
-    }
-    Ok(out)
+    query_policies(store, &sql).await
 }
 
 /// Create a policy
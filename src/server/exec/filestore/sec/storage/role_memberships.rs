@@ -22,6 +22,40 @@ pub async fn list_roles_for_user(store: &crate::storage::SharedStore, user_id: &
     Ok(out)
 }
 
+/// A user's membership of a role, valid between the optional bounds (epoch ms).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MembershipRecord {
+    pub user_id: String,
+    pub role_id: String,
+    pub valid_from: Option<i64>,
+    pub valid_to: Option<i64>,
+}
+
+impl MembershipRecord {
+    pub fn is_valid_at(&self, now_ms: i64) -> bool {
+        self.valid_from.is_none_or(|t| t <= now_ms) && self.valid_to.is_none_or(|t| t >= now_ms)
+    }
+}
+
+/// Fetch all role memberships, including those outside their validity window.
+pub async fn list_memberships(store: &crate::storage::SharedStore) -> Result<Vec<MembershipRecord>> {
+    let val = crate::server::exec::execute_query_safe(store, "SELECT user_id, role_id, valid_from, valid_to FROM security.role_memberships").await?;
+    let mut out = Vec::new();
+    if let serde_json::Value::Array(rows) = val {
+        for r in rows {
+            if let serde_json::Value::Object(m) = r {
+                let user_id = m.get("user_id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                let role_id = m.get("role_id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                if user_id.is_empty() || role_id.is_empty() { continue; }
+                let valid_from = m.get("valid_from").and_then(|v| v.as_i64());
+                let valid_to = m.get("valid_to").and_then(|v| v.as_i64());
+                out.push(MembershipRecord { user_id, role_id, valid_from, valid_to });
+            }
+        }
+    }
+    Ok(out)
+}
+
 /// Grant a role to a user with optional validity window (epoch ms).
 pub async fn grant_role(
     store: &crate::storage::SharedStore,
@@ -43,6 +43,9 @@ pub struct AclContext {
     pub git: Option<GitCtx>,
     #[serde(default)]
     pub request_id: Option<String>,
+    /// Database of the filestore, for policy resource ids (res://<db>/<fs>/path/...)
+    #[serde(default)]
+    pub database: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -83,6 +86,8 @@ impl AclDecision {
 /// In-memory TTL cache for ACL decisions.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct CacheKey {
+    epoch: u64,
+    database: String,
     filestore: String,
     user: String,
     action: String,
@@ -135,6 +140,84 @@ struct AclError {
     message: Option<String>,
 }
 
+/// Access explanation for EXPLAIN ACCESS: the decision with the roles and policy behind it.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AccessExplanation {
+    pub user: String,
+    pub roles: Vec<String>,
+    pub action: ACLAction,
+    pub resource: String,
+    pub allow: bool,
+    pub reason: Option<String>,
+    pub policy_id: Option<String>,
+}
+
+fn v2_action(action: &ACLAction) -> sec::model::Action {
+    match action {
+        ACLAction::Read => sec::model::Action::Read,
+        ACLAction::Write => sec::model::Action::Write,
+        ACLAction::Delete => sec::model::Action::Delete,
+        ACLAction::Move => sec::model::Action::Move,
+        ACLAction::Copy => sec::model::Action::Copy,
+        ACLAction::Rename => sec::model::Action::Rename,
+        ACLAction::List => sec::model::Action::List,
+        ACLAction::Commit => sec::model::Action::Commit,
+        ACLAction::Push => sec::model::Action::Push,
+        ACLAction::Pull => sec::model::Action::Pull,
+        ACLAction::Clone => sec::model::Action::Clone,
+    }
+}
+
+fn v2_context(ctx: &AclContext) -> sec::model::Context {
+    sec::model::Context {
+        filestore_config_version: ctx.filestore_config_version,
+        media_type: ctx.content_meta.as_ref().and_then(|m| m.media_type.clone()),
+        size_bytes: ctx.content_meta.as_ref().and_then(|m| m.size_bytes),
+        git_remote: ctx.git.as_ref().and_then(|g| g.remote.clone()),
+        git_branch: ctx.git.as_ref().and_then(|g| g.branch.clone()),
+        request_id: ctx.request_id.clone(),
+    }
+}
+
+/// Policy resource of a path ("" = the filestore root).
+fn v2_resource(ctx: &AclContext, filestore_name: &str, logical_path: &str) -> sec::model::ResourceId {
+    sec::resources::res_path(ctx.database.as_deref().unwrap_or("unknown_db"), filestore_name, logical_path)
+}
+
+fn v2_user(user: &AclUser) -> sec::model::User {
+    sec::model::User { id: user.id.clone(), roles: user.roles.clone(), ip: user.ip.clone() }
+}
+
+/// Explain the decision `check_acl` would make, without caching it.
+pub async fn explain_acl(
+    eff: &EffectiveConfig,
+    user: &AclUser,
+    action: ACLAction,
+    logical_path: &str,
+    ctx: &AclContext,
+    filestore_name: &str,
+) -> AccessExplanation {
+    sec::evaluator::ensure_fresh().await;
+    let res = v2_resource(ctx, filestore_name, logical_path);
+    let mut out = AccessExplanation {
+        user: user.id.clone(),
+        roles: user.roles.clone(),
+        action: action.clone(),
+        resource: res.0.clone(),
+        allow: true,
+        reason: Some("security_check_disabled".into()),
+        policy_id: None,
+    };
+    if eff.security_check_enabled {
+        let ex = sec::explain(&v2_user(user), v2_action(&action), &res, &v2_context(ctx));
+        out.roles = ex.roles;
+        out.allow = ex.decision.allow;
+        out.reason = ex.decision.reason;
+        out.policy_id = ex.policy_id;
+    }
+    out
+}
+
 /// Check ACL decisions with cache and HTTP POST per configured contract.
 /// Behavior:
 /// - If `security_check_enabled == false`, allow.
@@ -147,6 +230,23 @@ pub async fn check_acl(
     old_path: Option<&str>,
     ctx: &AclContext,
     filestore_name: &str,
+) -> AclDecision {
+    // Pick up policy changes before deciding; decide_acl itself never queries
+    sec::evaluator::ensure_fresh().await;
+    decide_acl(eff, user, action, logical_path, old_path, ctx, filestore_name)
+}
+
+/// Synchronous form of [`check_acl`] for callers outside an async context (e.g. table functions).
+/// Evaluation is local, so both return the same decision and share the decision cache; only
+/// `check_acl` reloads the policy snapshot first.
+pub fn decide_acl(
+    eff: &EffectiveConfig,
+    user: &AclUser,
+    action: ACLAction,
+    logical_path: &str,
+    old_path: Option<&str>,
+    ctx: &AclContext,
+    filestore_name: &str,
 ) -> AclDecision {
     let corr = ctx
         .request_id
@@ -161,6 +261,8 @@ pub async fn check_acl(
 
     // Build cache key
     let key = CacheKey {
+        epoch: sec::epochs::epoch_global(),
+        database: ctx.database.clone().unwrap_or_default(),
         filestore: filestore_name.to_string(),
         user: user.id.clone(),
         action: format!("{:?}", action).to_lowercase(),
@@ -188,29 +290,10 @@ pub async fn check_acl(
     );
 
     // Evaluate authorization locally via Security v2 (RBAC/ABAC) — replaces remote HTTP path
-    let v2_user = sec::model::User { id: user.id.clone(), roles: user.roles.clone(), ip: user.ip.clone() };
-    let v2_action = match action {
-        ACLAction::Read => sec::model::Action::Read,
-        ACLAction::Write => sec::model::Action::Write,
-        ACLAction::Delete => sec::model::Action::Delete,
-        ACLAction::Move => sec::model::Action::Move,
-        ACLAction::Copy => sec::model::Action::Copy,
-        ACLAction::Rename => sec::model::Action::Rename,
-        ACLAction::List => sec::model::Action::List,
-        ACLAction::Commit => sec::model::Action::Commit,
-        ACLAction::Push => sec::model::Action::Push,
-        ACLAction::Pull => sec::model::Action::Pull,
-        ACLAction::Clone => sec::model::Action::Clone,
-    };
-    let v2_ctx = sec::model::Context {
-        filestore_config_version: ctx.filestore_config_version,
-        media_type: ctx.content_meta.as_ref().and_then(|m| m.media_type.clone()),
-        size_bytes: ctx.content_meta.as_ref().and_then(|m| m.size_bytes),
-        git_remote: ctx.git.as_ref().and_then(|g| g.remote.clone()),
-        git_branch: ctx.git.as_ref().and_then(|g| g.branch.clone()),
-        request_id: ctx.request_id.clone(),
-    };
-    let v2_res = sec::resources::res_path("unknown_db", filestore_name, logical_path);
+    let v2_user = v2_user(user);
+    let v2_action = v2_action(&action);
+    let v2_ctx = v2_context(ctx);
+    let v2_res = v2_resource(ctx, filestore_name, logical_path);
     let v2_dec = sec::authorize(&v2_user, v2_action, &v2_res, &v2_ctx);
     crate::tprintln!(
         "ACL local eval: user={} action={:?} path={} allow={}{}",
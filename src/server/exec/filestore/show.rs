@@ -6,7 +6,7 @@ use polars::prelude::*;
 
 use crate::storage::SharedStore;
 
-use super::config::{EffectiveConfig, GlobalFilestoreConfig};
+use super::config::EffectiveConfig;
 use super::registry::{list_filestore_entries, load_filestore_entry};
 use super::types::{FileMeta, Tree, Commit, Alias};
 use super::ops::{list_files_by_prefix, list_trees, list_commits, load_tree, diff_trees};
@@ -23,6 +23,9 @@ pub(crate) fn empty_files_df() -> Result<DataFrame> {
         Series::new("updated_at".into(), Vec::<i64>::new()).into(),
         Series::new("deleted".into(), Vec::<bool>::new()).into(),
         Series::new("content_type".into(), Vec::<String>::new()).into(),
+        Series::new("metadata".into(), Vec::<Option<String>>::new()).into(),
+        Series::new("scan_status".into(), Vec::<Option<String>>::new()).into(),
+        Series::new("scan_signature".into(), Vec::<Option<String>>::new()).into(),
     ])?)
 }
 
@@ -74,7 +77,8 @@ pub fn show_filestore_config_df(
     filestore: &str,
     folder_prefix: Option<&str>,
 ) -> Result<DataFrame> {
-    let global = GlobalFilestoreConfig::default();
+    // Global layer comes from the [filestore] section of the server configuration
+    let global = crate::config::current().filestore.clone();
     let fs_cfg = if let Some(ent) = load_filestore_entry(store, database, filestore)? { ent.config } else { super::config::FilestoreConfig::default() };
     let folder = folder_prefix.map(|_| super::config::FolderGitOverride::default());
     let eff = EffectiveConfig::from_layers(&global, &fs_cfg, folder.as_ref());
@@ -87,6 +91,7 @@ pub fn show_filestore_config_df(
         ("effective_git_branch", eff.git_branch.clone().unwrap_or_default()),
         ("effective_git_mode", eff.git_mode.clone()),
         ("effective_git_push_backend", eff.git_push_backend.clone()),
+        ("effective_git_conflict_policy", eff.git_conflict_policy.clone()),
         ("effective_lfs_patterns", eff.lfs_patterns.clone().unwrap_or_default()),
     ];
 
@@ -99,7 +104,7 @@ pub fn show_filestore_config_df(
     }
 
     // Additional scalar fields
-    let bools = vec![("security_check_enabled", eff.security_check_enabled)];
+    let bools = vec![("security_check_enabled", eff.security_check_enabled), ("effective_fulltext_index", eff.fulltext_index)];
     let mut bool_names: Vec<String> = Vec::with_capacity(bools.len());
     let mut bool_vals: Vec<bool> = Vec::with_capacity(bools.len());
     for (k, v) in bools { bool_names.push(k.to_string()); bool_vals.push(v); }
@@ -138,7 +143,16 @@ pub fn show_files_df(store: &SharedStore, database: &str, filestore: &str, prefi
     let mut updated_at: Vec<i64> = Vec::with_capacity(n);
     let mut deleted: Vec<bool> = Vec::with_capacity(n);
     let mut content_type: Vec<String> = Vec::with_capacity(n);
+    // User-defined metadata as a JSON object, NULL when there is none
+    let mut metadata: Vec<Option<String>> = Vec::with_capacity(n);
+    // Content scan result of the last write; NULL when the filestore does not scan
+    let mut scan_status: Vec<Option<String>> = Vec::with_capacity(n);
+    let mut scan_signature: Vec<Option<String>> = Vec::with_capacity(n);
     for m in list.into_iter() {
+        let md = m.metadata();
+        metadata.push((!md.is_empty()).then(|| serde_json::to_string(&md).unwrap_or_default()));
+        scan_status.push(m.scan.as_ref().map(|s| s.status.clone()));
+        scan_signature.push(m.scan.as_ref().and_then(|s| s.signature.clone()));
         logical_path.push(m.logical_path);
         size.push(m.size as i64);
         etag.push(m.etag);
@@ -155,6 +169,9 @@ pub fn show_files_df(store: &SharedStore, database: &str, filestore: &str, prefi
         Series::new("updated_at".into(), updated_at).into(),
         Series::new("deleted".into(), deleted).into(),
         Series::new("content_type".into(), content_type).into(),
+        Series::new("metadata".into(), metadata).into(),
+        Series::new("scan_status".into(), scan_status).into(),
+        Series::new("scan_signature".into(), scan_signature).into(),
     ])?;
     Ok(df)
 }
@@ -214,6 +231,59 @@ pub fn show_commits_df(store: &SharedStore, database: &str, filestore: &str) ->
     Ok(df)
 }
 
+/// Show SYNC runs (most recent first) with their last stage; `error` and `finished_at` are null while unset.
+pub fn show_sync_runs_df(store: &SharedStore, database: &str, filestore: &str) -> Result<DataFrame> {
+    let runs = super::git::sync::list_sync_runs(store, database, filestore);
+    let n = runs.len();
+    let mut correlation_id: Vec<String> = Vec::with_capacity(n);
+    let mut direction: Vec<String> = Vec::with_capacity(n);
+    let mut remote: Vec<String> = Vec::with_capacity(n);
+    let mut branch: Vec<String> = Vec::with_capacity(n);
+    let mut status: Vec<String> = Vec::with_capacity(n);
+    let mut stage: Vec<String> = Vec::with_capacity(n);
+    let mut git_sha: Vec<String> = Vec::with_capacity(n);
+    let mut files_added: Vec<i64> = Vec::with_capacity(n);
+    let mut files_updated: Vec<i64> = Vec::with_capacity(n);
+    let mut files_deleted: Vec<i64> = Vec::with_capacity(n);
+    let mut conflicts: Vec<String> = Vec::with_capacity(n);
+    let mut error: Vec<Option<String>> = Vec::with_capacity(n);
+    let mut started_at: Vec<i64> = Vec::with_capacity(n);
+    let mut finished_at: Vec<Option<i64>> = Vec::with_capacity(n);
+    for r in runs.into_iter() {
+        stage.push(r.stages.last().map(|s| s.stage.clone()).unwrap_or_default());
+        correlation_id.push(r.correlation_id);
+        direction.push(r.direction);
+        remote.push(r.remote);
+        branch.push(r.branch);
+        status.push(r.status);
+        git_sha.push(r.git_sha.unwrap_or_default());
+        files_added.push(r.files_added as i64);
+        files_updated.push(r.files_updated as i64);
+        files_deleted.push(r.files_deleted as i64);
+        conflicts.push(r.conflicts.join(","));
+        error.push(r.error);
+        started_at.push(r.started_at);
+        finished_at.push(r.finished_at);
+    }
+    let df = DataFrame::new(vec![
+        Series::new("correlation_id".into(), correlation_id).into(),
+        Series::new("direction".into(), direction).into(),
+        Series::new("remote".into(), remote).into(),
+        Series::new("branch".into(), branch).into(),
+        Series::new("status".into(), status).into(),
+        Series::new("stage".into(), stage).into(),
+        Series::new("git_sha".into(), git_sha).into(),
+        Series::new("files_added".into(), files_added).into(),
+        Series::new("files_updated".into(), files_updated).into(),
+        Series::new("files_deleted".into(), files_deleted).into(),
+        Series::new("conflicts".into(), conflicts).into(),
+        Series::new("error".into(), error).into(),
+        Series::new("started_at".into(), started_at).into(),
+        Series::new("finished_at".into(), finished_at).into(),
+    ])?;
+    Ok(df)
+}
+
 /// Show diff between two tree IDs, or between a tree and the current live prefix if `right_tree_id` is None and `live_prefix` provided.
 pub fn show_diff_df(
     store: &SharedStore,
@@ -232,7 +302,7 @@ pub fn show_diff_df(
         // Build a temporary live snapshot for the given prefix
         let files = list_files_by_prefix(store, database, filestore, live_prefix)?;
         let entries: Vec<super::types::TreeEntry> = files.into_iter().filter(|m| !m.deleted)
-            .map(|m| super::types::TreeEntry { path: m.logical_path, file_id: m.id, etag: m.etag, size: m.size }).collect();
+            .map(|m| super::types::TreeEntry { path: m.logical_path, file_id: m.id, etag: m.etag, size: m.size, content: None }).collect();
         super::types::Tree { id: "__live__".to_string(), created_at: 0, entries }
     };
     let diff = diff_trees(&left, &right);
@@ -262,6 +332,220 @@ pub fn show_diff_df(
     Ok(df)
 }
 
+/// MERGE conflicts: the SHOW DIFF rows between the two branch tips, restricted to the conflicting paths.
+pub fn show_merge_conflicts_df(store: &SharedStore, database: &str, filestore: &str, outcome: &super::branch::MergeOutcome) -> Result<DataFrame> {
+    let ours = outcome.ours_tree_id.as_deref().ok_or_else(|| anyhow::anyhow!("merge has no tree on the target branch"))?;
+    let df = show_diff_df(store, database, filestore, ours, Some(&outcome.theirs_tree_id), None)?;
+    let conflicts: std::collections::HashSet<&str> = outcome.conflicts.iter().map(String::as_str).collect();
+    let mask: BooleanChunked = df.column("path")?.str()?.into_iter()
+        .map(|p| p.map(|p| conflicts.contains(p)).unwrap_or(false))
+        .collect();
+    Ok(df.filter(&mask)?)
+}
+
+/// Show local branches with their head commit; `current` marks the checked-out one.
+pub fn show_branches_df(store: &SharedStore, database: &str, filestore: &str, eff: &EffectiveConfig) -> Result<DataFrame> {
+    let branches = super::branch::list_branches(store, database, filestore);
+    let cur = super::branch::current_branch(store, database, filestore, eff);
+    let n = branches.len();
+    let mut branch: Vec<String> = Vec::with_capacity(n);
+    let mut head_commit_id: Vec<String> = Vec::with_capacity(n);
+    let mut current: Vec<bool> = Vec::with_capacity(n);
+    let mut updated_at: Vec<i64> = Vec::with_capacity(n);
+    for r in branches.into_iter() {
+        current.push(r.branch == cur);
+        branch.push(r.branch);
+        head_commit_id.push(r.head_commit_id);
+        updated_at.push(r.updated_at);
+    }
+    let df = DataFrame::new(vec![
+        Series::new("branch".into(), branch).into(),
+        Series::new("head_commit_id".into(), head_commit_id).into(),
+        Series::new("current".into(), current).into(),
+        Series::new("updated_at".into(), updated_at).into(),
+    ])?;
+    Ok(df)
+}
+
+/// Show the filestore's webhooks with their delivery counters since startup.
+pub fn show_webhooks_df(store: &SharedStore, database: &str, filestore: &str) -> Result<DataFrame> {
+    let hooks = load_filestore_entry(store, database, filestore)?.and_then(|e| e.config.webhooks).unwrap_or_default();
+    let n = hooks.len();
+    let mut url: Vec<String> = Vec::with_capacity(n);
+    let mut events: Vec<Option<String>> = Vec::with_capacity(n);
+    let mut prefix: Vec<Option<String>> = Vec::with_capacity(n);
+    let mut signed: Vec<bool> = Vec::with_capacity(n);
+    let mut delivered: Vec<i64> = Vec::with_capacity(n);
+    let mut failed: Vec<i64> = Vec::with_capacity(n);
+    let mut last_status: Vec<Option<i64>> = Vec::with_capacity(n);
+    let mut last_error: Vec<Option<String>> = Vec::with_capacity(n);
+    for h in hooks.into_iter() {
+        let st = super::events::webhook_stats(database, filestore, &h.url);
+        events.push(h.events.map(|e| e.join(",")));
+        prefix.push(h.prefix);
+        signed.push(h.secret_env.is_some());
+        delivered.push(st.delivered as i64);
+        failed.push(st.failed as i64);
+        last_status.push(st.last_status.map(|c| c as i64));
+        last_error.push(st.last_error);
+        url.push(h.url);
+    }
+    let df = DataFrame::new(vec![
+        Series::new("url".into(), url).into(),
+        Series::new("events".into(), events).into(),
+        Series::new("prefix".into(), prefix).into(),
+        Series::new("signed".into(), signed).into(),
+        Series::new("delivered".into(), delivered).into(),
+        Series::new("failed".into(), failed).into(),
+        Series::new("last_status".into(), last_status).into(),
+        Series::new("last_error".into(), last_error).into(),
+    ])?;
+    Ok(df)
+}
+
+/// Show legal holds, sorted by path.
+pub fn show_legal_holds_df(store: &SharedStore, database: &str, filestore: &str) -> Result<DataFrame> {
+    let holds = super::retention::list_legal_holds(store, database, filestore);
+    let n = holds.len();
+    let mut logical_path: Vec<String> = Vec::with_capacity(n);
+    let mut reason: Vec<Option<String>> = Vec::with_capacity(n);
+    let mut set_by: Vec<String> = Vec::with_capacity(n);
+    let mut set_at: Vec<i64> = Vec::with_capacity(n);
+    for h in holds.into_iter() {
+        logical_path.push(h.logical_path);
+        reason.push(h.reason);
+        set_by.push(h.set_by);
+        set_at.push(h.set_at);
+    }
+    let df = DataFrame::new(vec![
+        Series::new("logical_path".into(), logical_path).into(),
+        Series::new("reason".into(), reason).into(),
+        Series::new("set_by".into(), set_by).into(),
+        Series::new("set_at".into(), set_at).into(),
+    ])?;
+    Ok(df)
+}
+
+/// Show the active file locks, sorted by path.
+pub fn show_file_locks_df(store: &SharedStore, database: &str, filestore: &str) -> Result<DataFrame> {
+    let locks = super::locks::list_file_locks(store, database, filestore);
+    let n = locks.len();
+    let mut logical_path: Vec<String> = Vec::with_capacity(n);
+    let mut owner: Vec<String> = Vec::with_capacity(n);
+    let mut note: Vec<Option<String>> = Vec::with_capacity(n);
+    let mut locked_at: Vec<i64> = Vec::with_capacity(n);
+    let mut expires_at: Vec<Option<i64>> = Vec::with_capacity(n);
+    for l in locks.into_iter() {
+        logical_path.push(l.logical_path);
+        owner.push(l.owner);
+        note.push(l.note);
+        locked_at.push(l.locked_at);
+        expires_at.push(l.expires_at);
+    }
+    let df = DataFrame::new(vec![
+        Series::new("logical_path".into(), logical_path).into(),
+        Series::new("owner".into(), owner).into(),
+        Series::new("note".into(), note).into(),
+        Series::new("locked_at".into(), locked_at).into(),
+        Series::new("expires_at".into(), expires_at).into(),
+    ])?;
+    Ok(df)
+}
+
+/// Show the filestore's encryption setting and its data key (one row; key columns NULL until
+/// something has been encrypted).
+pub fn show_encryption_df(store: &SharedStore, database: &str, filestore: &str, eff: &EffectiveConfig) -> Result<DataFrame> {
+    let rec = super::sse::data_key_record(store, database, filestore);
+    let df = DataFrame::new(vec![
+        Series::new("encrypted".into(), vec![eff.encryption.is_some()]).into(),
+        Series::new("configured_master_key".into(), vec![eff.encryption.as_ref().and_then(|e| e.key_id.clone())]).into(),
+        Series::new("data_key".into(), vec![rec.as_ref().map(|r| r.id.clone())]).into(),
+        Series::new("master_key".into(), vec![rec.as_ref().map(|r| r.master_key.clone())]).into(),
+        Series::new("created_at".into(), vec![rec.as_ref().map(|r| r.created_at)]).into(),
+        Series::new("rotated_at".into(), vec![rec.as_ref().and_then(|r| r.rotated_at)]).into(),
+    ])?;
+    Ok(df)
+}
+
+/// Show recorded GC runs, most recent first, with the bytes each reclaimed.
+pub fn show_gc_runs_df(store: &SharedStore, database: &str, filestore: &str) -> Result<DataFrame> {
+    let runs = super::gc_scheduler::list_gc_runs(store, database, filestore);
+    let n = runs.len();
+    let mut id: Vec<String> = Vec::with_capacity(n);
+    let mut trigger: Vec<String> = Vec::with_capacity(n);
+    let mut started_at: Vec<i64> = Vec::with_capacity(n);
+    let mut duration_ms: Vec<i64> = Vec::with_capacity(n);
+    let mut files_deleted: Vec<i64> = Vec::with_capacity(n);
+    let mut versions_deleted: Vec<i64> = Vec::with_capacity(n);
+    let mut chunks_deleted: Vec<i64> = Vec::with_capacity(n);
+    let mut blobs_deleted: Vec<i64> = Vec::with_capacity(n);
+    let mut retained: Vec<i64> = Vec::with_capacity(n);
+    let mut bytes_reclaimed: Vec<i64> = Vec::with_capacity(n);
+    let mut error: Vec<Option<String>> = Vec::with_capacity(n);
+    for r in runs.into_iter() {
+        id.push(r.id);
+        trigger.push(r.trigger);
+        started_at.push(r.started_at);
+        duration_ms.push(r.duration_ms);
+        files_deleted.push(r.files_deleted);
+        versions_deleted.push(r.versions_deleted);
+        chunks_deleted.push(r.chunks_deleted);
+        blobs_deleted.push(r.blobs_deleted);
+        retained.push(r.retained);
+        bytes_reclaimed.push(r.bytes_reclaimed as i64);
+        error.push(r.error);
+    }
+    let df = DataFrame::new(vec![
+        Series::new("id".into(), id).into(),
+        Series::new("trigger".into(), trigger).into(),
+        Series::new("started_at".into(), started_at).into(),
+        Series::new("duration_ms".into(), duration_ms).into(),
+        Series::new("files_deleted".into(), files_deleted).into(),
+        Series::new("versions_deleted".into(), versions_deleted).into(),
+        Series::new("chunks_deleted".into(), chunks_deleted).into(),
+        Series::new("blobs_deleted".into(), blobs_deleted).into(),
+        Series::new("retained".into(), retained).into(),
+        Series::new("bytes_reclaimed".into(), bytes_reclaimed).into(),
+        Series::new("error".into(), error).into(),
+    ])?;
+    Ok(df)
+}
+
+/// Show retained file versions, optionally only those last at a logical path.
+pub fn show_versions_df(store: &SharedStore, database: &str, filestore: &str, logical_path: Option<&str>) -> Result<DataFrame> {
+    let versions = super::retention::list_versions(store, database, filestore, logical_path);
+    let n = versions.len();
+    let mut path: Vec<String> = Vec::with_capacity(n);
+    let mut file_id: Vec<String> = Vec::with_capacity(n);
+    let mut version: Vec<i64> = Vec::with_capacity(n);
+    let mut size: Vec<i64> = Vec::with_capacity(n);
+    let mut etag: Vec<String> = Vec::with_capacity(n);
+    let mut reason: Vec<String> = Vec::with_capacity(n);
+    let mut written_at: Vec<i64> = Vec::with_capacity(n);
+    let mut retired_at: Vec<i64> = Vec::with_capacity(n);
+    for v in versions.into_iter() {
+        path.push(v.logical_path);
+        file_id.push(v.file_id);
+        version.push(v.version as i64);
+        size.push(v.size as i64);
+        etag.push(v.etag);
+        reason.push(v.reason);
+        written_at.push(v.written_at);
+        retired_at.push(v.retired_at);
+    }
+    let df = DataFrame::new(vec![
+        Series::new("logical_path".into(), path).into(),
+        Series::new("file_id".into(), file_id).into(),
+        Series::new("version".into(), version).into(),
+        Series::new("size".into(), size).into(),
+        Series::new("etag".into(), etag).into(),
+        Series::new("reason".into(), reason).into(),
+        Series::new("written_at".into(), written_at).into(),
+        Series::new("retired_at".into(), retired_at).into(),
+    ])?;
+    Ok(df)
+}
+
 /// Show chunks present in a filestore by scanning the chunk namespace.
 pub fn show_chunks_df(store: &SharedStore, database: &str, filestore: &str) -> Result<DataFrame> {
     let kv = store.kv_store(database, filestore);
@@ -271,16 +555,17 @@ pub fn show_chunks_df(store: &SharedStore, database: &str, filestore: &str) -> R
     let mut oid: Vec<String> = Vec::new();
     let mut size: Vec<i64> = Vec::new();
     let mut ref_count: Vec<i64> = Vec::new();
+    let refs = super::chunker::chunk_ref_counts(store, database, filestore);
     for k in kv.keys() {
         if !k.starts_with(prefix) { continue; }
         // Key ends with UUID; use it as oid
         if let Some(id_part) = k.split("::").last() {
             oid.push(id_part.to_string());
-            // Size unknown without loading bytes; attempt to read Bytes value if present
-            let sz = match kv.get(&k) { Some(KvValue::Bytes(b)) => b.len() as i64, _ => 0 };
+            // Payload size: local bytes, or the length recorded for a remote chunk
+            let sz = kv.get(&k).map(|v| super::blob::stored_len(&v) as i64).unwrap_or(0);
             size.push(sz);
-            // ref_count not tracked yet; default 0
-            ref_count.push(0);
+            // References from file metas (live and tombstoned)
+            ref_count.push(refs.get(id_part).copied().unwrap_or(0));
         }
     }
     let df = DataFrame::new(vec![
@@ -328,14 +613,21 @@ pub fn show_admin_counts_df(store: &SharedStore, database: &str, filestore: &str
     let path_prefix = Keys::path(database, filestore, "");
     let mut files_live = 0i64;
     let mut files_tomb = 0i64;
+    let mut live_bytes = 0u64;
     for k in kv.keys() {
         if !k.starts_with(&path_prefix) { continue; }
         if let Some(KvValue::Json(j)) = kv.get(&k) {
             if let Ok(m) = serde_json::from_value::<FileMeta>(j) {
-                if m.deleted { files_tomb += 1; } else { files_live += 1; }
+                if m.deleted { files_tomb += 1; } else { files_live += 1; live_bytes += m.size; }
             }
         }
     }
+    // Filestore-wide quota (prefix quotas are checked per write, see quota.rs)
+    let global = crate::config::current().filestore.clone();
+    let fs_cfg = load_filestore_entry(store, database, filestore)?.map(|e| e.config).unwrap_or_default();
+    let eff = EffectiveConfig::from_layers(&global, &fs_cfg, None);
+    let root = super::quota::Scope { prefix: String::new(), max_bytes: eff.quota_bytes, max_files: eff.quota_files };
+    let quota_used_pct = root.used_percent(&super::quota::Usage { bytes: live_bytes, files: files_live as u64 });
     // Chunks
     let chunk_prefix_sample = Keys::chunk(database, filestore, &uuid::Uuid::nil());
     let chunk_prefix = &chunk_prefix_sample[..chunk_prefix_sample.len() - uuid::Uuid::nil().to_string().len()];
@@ -349,6 +641,8 @@ pub fn show_admin_counts_df(store: &SharedStore, database: &str, filestore: &str
     let com_prefix_sample = Keys::commit(database, filestore, &uuid::Uuid::nil());
     let com_prefix = &com_prefix_sample[..com_prefix_sample.len() - uuid::Uuid::nil().to_string().len()];
     let mut commits = 0i64; for k in kv.keys() { if k.starts_with(com_prefix) { commits += 1; } }
+    // Chunk store deduplication
+    let dedup = super::chunker::dedup_stats(store, database, filestore);
 
     let df = DataFrame::new(vec![
         Series::new("files_live".into(), vec![files_live]).into(),
@@ -356,6 +650,13 @@ pub fn show_admin_counts_df(store: &SharedStore, database: &str, filestore: &str
         Series::new("chunks".into(), vec![chunks]).into(),
         Series::new("trees".into(), vec![trees]).into(),
         Series::new("commits".into(), vec![commits]).into(),
+        Series::new("logical_bytes".into(), vec![dedup.logical_bytes as i64]).into(),
+        Series::new("stored_bytes".into(), vec![dedup.stored_bytes as i64]).into(),
+        Series::new("dedup_ratio".into(), vec![dedup.ratio()]).into(),
+        Series::new("live_bytes".into(), vec![live_bytes as i64]).into(),
+        Series::new("quota_bytes".into(), vec![eff.quota_bytes.map(|v| v as i64)]).into(),
+        Series::new("quota_files".into(), vec![eff.quota_files.map(|v| v as i64)]).into(),
+        Series::new("quota_used_pct".into(), vec![quota_used_pct]).into(),
     ])?;
     Ok(df)
 }
@@ -383,8 +684,8 @@ pub fn show_files_df_paged(
 /// Health summary: orphaned chunks, stale refs, and config mismatches.
 /// Current implementation provides conservative counts; deeper checks will be added later.
 pub fn show_health_df(store: &SharedStore, database: &str, filestore: &str) -> Result<DataFrame> {
-    // Orphaned chunks: unknown without reverse index — return 0 for now
-    let orphaned_chunks: i64 = 0;
+    // Orphaned chunks: stored chunks no file meta references
+    let orphaned_chunks = super::chunker::dedup_stats(store, database, filestore).orphan_chunks as i64;
     // Stale refs: if a local ref points to non-existent commit
     let kv = store.kv_store(database, filestore);
     let mut stale_refs: i64 = 0;
@@ -407,3 +708,18 @@ pub fn show_health_df(store: &SharedStore, database: &str, filestore: &str) -> R
     ])?;
     Ok(df)
 }
+
+/// EXPLAIN ACCESS row: who asked, with which roles, for what, and the decision with the policy behind it.
+pub fn explain_access_df(ex: &super::security::AccessExplanation) -> Result<DataFrame> {
+    let action = serde_json::to_value(&ex.action)?.as_str().unwrap_or_default().to_string();
+    let df = DataFrame::new(vec![
+        Series::new("user".into(), vec![ex.user.clone()]).into(),
+        Series::new("roles".into(), vec![ex.roles.join(",")]).into(),
+        Series::new("action".into(), vec![action]).into(),
+        Series::new("resource".into(), vec![ex.resource.clone()]).into(),
+        Series::new("allow".into(), vec![ex.allow]).into(),
+        Series::new("reason".into(), vec![ex.reason.clone()]).into(),
+        Series::new("policy_id".into(), vec![ex.policy_id.clone()]).into(),
+    ])?;
+    Ok(df)
+}
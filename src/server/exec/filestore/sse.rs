@@ -0,0 +1,224 @@
+//! Server-side encryption of file content (envelope encryption).
+//!
+//! A filestore with `encryption` set gets a random 256-bit data key on its first encrypted
+//! write. The data key is kept under `Keys::data_key`, wrapped (sealed) by a master key from the
+//! storage keyring (`storage::encryption`: CLARIUM_ENCRYPTION_KEYS / CLARIUM_KMS_KEY_COMMAND),
+//! and never stored in the clear. Chunk payloads and single-blob contents are sealed with the
+//! data key using AES-256-GCM:
+//!
+//! `CLRMFSE1 | id len (u8) | data key id | nonce (12) | ciphertext | tag (16)`
+//!
+//! Reads open sealed payloads transparently and pass plaintext through, so a filestore can hold
+//! content written before encryption was switched on (or after it was switched off). Chunk ids
+//! stay the SHA-256 of the plaintext, so deduplication is unaffected.
+//!
+//! `ROTATE FILESTORE KEY` re-wraps the data key with another master key; content is not
+//! rewritten. Full-text index terms and extracted text are not encrypted.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::encryption::{self, EncryptionSpec};
+use crate::storage::{KvValue, SharedStore};
+
+use super::blob::BlobBackend;
+use super::config::EffectiveConfig;
+use super::kv::Keys;
+
+const MAGIC: &[u8; 8] = b"CLRMFSE1";
+const TAG_LEN: usize = 16;
+
+/// A filestore's data key as stored: wrapped by a master key.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DataKeyRecord {
+    pub id: String,
+    pub algorithm: String,
+    /// Master key the data key is wrapped with
+    pub master_key: String,
+    /// base64 of the wrapped key (a `storage::encryption` sealed buffer)
+    pub wrapped: String,
+    pub created_at: i64,
+    #[serde(default)]
+    pub rotated_at: Option<i64>,
+}
+
+/// Outcome of a key rotation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeyRotation {
+    pub data_key: String,
+    pub old_master_key: String,
+    pub new_master_key: String,
+    pub rotated_at: i64,
+}
+
+/// Unwrapped data keys by id; ids are unique, so entries never go stale.
+static DATA_KEYS: Lazy<RwLock<HashMap<String, [u8; 32]>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+/// Serializes data key creation, so concurrent first writes agree on one key.
+static CREATE: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// The filestore's data key record, if it has one.
+pub fn data_key_record(store: &SharedStore, database: &str, filestore: &str) -> Option<DataKeyRecord> {
+    match store.kv_store(database, filestore).get(&Keys::data_key(database, filestore)) {
+        Some(KvValue::Json(j)) => serde_json::from_value(j).ok(),
+        _ => None,
+    }
+}
+
+fn save_record(store: &SharedStore, database: &str, filestore: &str, rec: &DataKeyRecord) -> Result<()> {
+    store.kv_store(database, filestore).set(Keys::data_key(database, filestore), KvValue::Json(serde_json::to_value(rec)?), None, None);
+    Ok(())
+}
+
+fn wrap(key: &[u8; 32], spec: &EncryptionSpec) -> Result<(String, String)> {
+    let sealed = encryption::encrypt(key, spec)?;
+    let master = encryption::key_id_of(&sealed).unwrap_or_default();
+    Ok((base64::engine::general_purpose::STANDARD.encode(sealed), master))
+}
+
+fn unwrap_key(rec: &DataKeyRecord) -> Result<[u8; 32]> {
+    if let Some(k) = DATA_KEYS.read().get(&rec.id) { return Ok(*k); }
+    let sealed = base64::engine::general_purpose::STANDARD.decode(&rec.wrapped).context("data key is not valid base64")?;
+    let plain = encryption::decrypt(sealed).with_context(|| format!("unwrapping data key {} with master key '{}'", rec.id, rec.master_key))?;
+    let key: [u8; 32] = plain.try_into().map_err(|_| anyhow!("data key {} has the wrong length", rec.id))?;
+    DATA_KEYS.write().insert(rec.id.clone(), key);
+    Ok(key)
+}
+
+/// The filestore's data key, created and wrapped with `spec` on first use.
+fn data_key(store: &SharedStore, database: &str, filestore: &str, spec: &EncryptionSpec) -> Result<(String, [u8; 32])> {
+    if let Some(rec) = data_key_record(store, database, filestore) { return Ok((rec.id.clone(), unwrap_key(&rec)?)); }
+    let _guard = CREATE.lock();
+    if let Some(rec) = data_key_record(store, database, filestore) { return Ok((rec.id.clone(), unwrap_key(&rec)?)); }
+    let mut key = [0u8; 32];
+    SystemRandom::new().fill(&mut key).map_err(|_| anyhow!("failed to generate data key"))?;
+    let (wrapped, master_key) = wrap(&key, spec)?;
+    let rec = DataKeyRecord {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        algorithm: "aes-256-gcm".into(),
+        master_key,
+        wrapped,
+        created_at: Utc::now().timestamp(),
+        rotated_at: None,
+    };
+    save_record(store, database, filestore, &rec)?;
+    DATA_KEYS.write().insert(rec.id.clone(), key);
+    crate::tprintln!("FILESTORE sse data key created fs={} id={} master={}", filestore, rec.id, rec.master_key);
+    Ok((rec.id, key))
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey> {
+    Ok(LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow!("invalid data key"))?))
+}
+
+fn header(key_id: &str) -> Vec<u8> {
+    let mut h = Vec::with_capacity(MAGIC.len() + 1 + key_id.len());
+    h.extend_from_slice(MAGIC);
+    h.push(key_id.len() as u8);
+    h.extend_from_slice(key_id.as_bytes());
+    h
+}
+
+fn seal_with(key_id: &str, key: &[u8; 32], plain: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).map_err(|_| anyhow!("failed to generate nonce"))?;
+    let mut out = header(key_id);
+    let mut buf = plain.to_vec();
+    cipher(key)?.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&out), &mut buf)
+        .map_err(|_| anyhow!("encryption failed"))?;
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&buf);
+    Ok(out)
+}
+
+pub fn is_sealed(bytes: &[u8]) -> bool { bytes.starts_with(MAGIC) }
+
+/// Seal `plain` with the filestore's data key when encryption is configured; otherwise pass it through.
+pub fn seal<'a>(store: &SharedStore, database: &str, filestore: &str, eff: &EffectiveConfig, plain: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    let Some(spec) = &eff.encryption else { return Ok(Cow::Borrowed(plain)) };
+    let (id, key) = data_key(store, database, filestore, spec)?;
+    Ok(Cow::Owned(seal_with(&id, &key, plain)?))
+}
+
+/// Open a payload written by [`seal`]; plaintext payloads are returned unchanged.
+pub fn open(store: &SharedStore, database: &str, filestore: &str, bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_sealed(&bytes) { return Ok(bytes); }
+    let n = *bytes.get(MAGIC.len()).ok_or_else(|| anyhow!("truncated encryption header"))? as usize;
+    let hlen = MAGIC.len() + 1 + n;
+    if bytes.len() < hlen + NONCE_LEN + TAG_LEN { bail!("truncated encrypted payload"); }
+    let id = std::str::from_utf8(&bytes[MAGIC.len() + 1..hlen]).map_err(|_| anyhow!("bad data key id"))?;
+    let rec = data_key_record(store, database, filestore)
+        .filter(|r| r.id == id)
+        .ok_or_else(|| anyhow!("data key {} of filestore '{}' not found", id, filestore))?;
+    let key = unwrap_key(&rec)?;
+    let nonce: [u8; NONCE_LEN] = bytes[hlen..hlen + NONCE_LEN].try_into()?;
+    let mut buf = bytes[hlen + NONCE_LEN..].to_vec();
+    let plain = cipher(&key)?.open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(&bytes[..hlen]), &mut buf)
+        .map_err(|_| anyhow!("decryption failed (wrong key or damaged content)"))?;
+    Ok(plain.to_vec())
+}
+
+/// Re-wrap the filestore's data key with `to_key` (default: the configured master key, else
+/// the keyring's active key). Content is untouched.
+pub fn rotate_key(store: &SharedStore, database: &str, filestore: &str, to_key: Option<&str>, eff: &EffectiveConfig) -> Result<KeyRotation> {
+    let _guard = CREATE.lock();
+    let mut rec = data_key_record(store, database, filestore)
+        .ok_or_else(|| anyhow!("no data key in filestore '{}': nothing has been encrypted yet", filestore))?;
+    let key = unwrap_key(&rec)?;
+    let spec = match to_key {
+        Some(k) => EncryptionSpec::new(Some(k.to_string())),
+        None => eff.encryption.clone().unwrap_or_else(|| EncryptionSpec::new(None)),
+    };
+    let (wrapped, master_key) = wrap(&key, &spec)?;
+    let now = Utc::now().timestamp();
+    let out = KeyRotation { data_key: rec.id.clone(), old_master_key: rec.master_key.clone(), new_master_key: master_key.clone(), rotated_at: now };
+    rec.wrapped = wrapped;
+    rec.master_key = master_key;
+    rec.rotated_at = Some(now);
+    save_record(store, database, filestore, &rec)?;
+    crate::tprintln!("FILESTORE sse key rotated fs={} data_key={} master {} -> {}", filestore, out.data_key, out.old_master_key, out.new_master_key);
+    Ok(out)
+}
+
+/// A blob backend that seals payloads with the filestore's data key before storing them.
+pub struct SealedBackend {
+    inner: Box<dyn BlobBackend>,
+    store: SharedStore,
+    database: String,
+    filestore: String,
+    key_id: String,
+    key: [u8; 32],
+}
+
+impl SealedBackend {
+    pub fn new(inner: Box<dyn BlobBackend>, store: &SharedStore, database: &str, filestore: &str, spec: &EncryptionSpec) -> Result<Self> {
+        let (key_id, key) = data_key(store, database, filestore, spec)?;
+        Ok(Self { inner, store: store.clone(), database: database.to_string(), filestore: filestore.to_string(), key_id, key })
+    }
+
+    /// Bytes sealing adds to a payload.
+    fn overhead(&self) -> u64 { (MAGIC.len() + 1 + self.key_id.len() + NONCE_LEN + TAG_LEN) as u64 }
+}
+
+impl BlobBackend for SealedBackend {
+    fn name(&self) -> String { format!("{}+sse", self.inner.name()) }
+    fn index_entry(&self, len: u64) -> Option<KvValue> { self.inner.index_entry(len + self.overhead()) }
+    fn put(&self, items: &[(&str, &[u8])]) -> Result<()> {
+        let sealed: Vec<(&str, Vec<u8>)> = items.iter()
+            .map(|(oid, b)| seal_with(&self.key_id, &self.key, b).map(|s| (*oid, s)))
+            .collect::<Result<_>>()?;
+        let refs: Vec<(&str, &[u8])> = sealed.iter().map(|(o, b)| (*o, b.as_slice())).collect();
+        self.inner.put(&refs)
+    }
+    fn get(&self, oids: &[&str]) -> Result<Vec<Vec<u8>>> {
+        self.inner.get(oids)?.into_iter().map(|b| open(&self.store, &self.database, &self.filestore, b)).collect()
+    }
+}
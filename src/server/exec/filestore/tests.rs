@@ -1,9 +1,21 @@
-
+mod blob_tests;
+mod branch_tests;
+mod chunker_tests;
 mod config_tests;
+mod events_tests;
+mod fulltext_tests;
+mod gc_scheduler_tests;
 mod gc_tests;
+mod git_sync_tests;
 mod host_path_tests;
 mod kv_tests;
+mod locks_tests;
 mod ops_tests;
 mod paths_tests;
+mod policy_tests;
+mod quota_tests;
+mod retention_tests;
+mod scan_tests;
 mod security_tests;
-mod show_tests;
\ No newline at end of file
+mod show_tests;
+mod sse_tests;
\ No newline at end of file
@@ -0,0 +1,152 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+const DB: &str = "clarium";
+const FS: &str = "docs";
+
+fn setup() -> (tempfile::TempDir, SharedStore, EffectiveConfig) {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let cfg = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    let eff = EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None);
+    (tmp, store, eff)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+fn author() -> CommitAuthor { CommitAuthor { name: "t".into(), email: "t@local".into(), time_unix: 0 } }
+
+async fn put(store: &SharedStore, eff: &EffectiveConfig, path: &str, body: &[u8]) {
+    let (u, ctx) = (user(), AclContext::default());
+    match get_file_meta(store, DB, FS, path).unwrap() {
+        Some(m) if !m.deleted => { update_from_bytes(store, DB, FS, path, &m.etag, body, None, None, &u, eff, &ctx).await.unwrap(); }
+        _ => { ingest_from_bytes(store, DB, FS, path, body, None, None, &u, eff, &ctx).await.unwrap(); }
+    }
+}
+
+fn read(store: &SharedStore, path: &str) -> Option<Vec<u8>> {
+    let m = get_file_meta(store, DB, FS, path).unwrap().filter(|m| !m.deleted)?;
+    get_file_bytes(store, DB, FS, &m).unwrap()
+}
+
+async fn checkout(store: &SharedStore, eff: &EffectiveConfig, branch: &str) -> CheckoutOutcome {
+    checkout_branch(store, DB, FS, branch, &author(), &user(), eff, &AclContext::default()).await.unwrap()
+}
+
+async fn merge(store: &SharedStore, eff: &EffectiveConfig, source: &str) -> MergeOutcome {
+    merge_branch(store, DB, FS, source, &author(), None, &user(), eff, &AclContext::default()).await.unwrap()
+}
+
+#[tokio::test]
+async fn checkout_restores_branch_content_after_live_updates() {
+    let (_tmp, store, eff) = setup();
+    put(&store, &eff, "spec.md", b"draft 1").await;
+    put(&store, &eff, "notes.txt", b"shared").await;
+
+    let info = create_branch(&store, DB, FS, "review", None, &author(), &eff).unwrap();
+    assert_eq!(current_branch_head(&store, DB, FS, "main"), Some(info.head_commit_id.clone()));
+    assert!(create_branch(&store, DB, FS, "review", None, &author(), &eff).unwrap_err().to_string().starts_with("branch_exists"));
+
+    let out = checkout(&store, &eff, "review").await;
+    assert_eq!((out.previous_branch.as_str(), out.files_written, out.files_deleted), ("main", 0, 0));
+    assert_eq!(current_branch(&store, DB, FS, &eff), "review");
+
+    // Edit on the branch: the blob of spec.md is overwritten in place
+    put(&store, &eff, "spec.md", b"draft 2").await;
+    put(&store, &eff, "review.txt", b"comments").await;
+
+    // Back on main: the working set is saved to review, main's content comes back from its tree
+    let out = checkout(&store, &eff, "main").await;
+    assert!(out.saved_commit_id.is_some());
+    assert_eq!((out.files_written, out.files_deleted), (1, 1));
+    assert_eq!(read(&store, "spec.md").as_deref(), Some(&b"draft 1"[..]));
+    assert!(read(&store, "review.txt").is_none());
+
+    checkout(&store, &eff, "review").await;
+    assert_eq!(read(&store, "spec.md").as_deref(), Some(&b"draft 2"[..]));
+    assert_eq!(read(&store, "review.txt").as_deref(), Some(&b"comments"[..]));
+
+    let df = show_branches_df(&store, DB, FS, &eff).unwrap();
+    let names: Vec<&str> = df.column("branch").unwrap().str().unwrap().into_iter().flatten().collect();
+    assert_eq!(names, vec!["main", "review"]);
+    let current: Vec<bool> = df.column("current").unwrap().bool().unwrap().into_iter().flatten().collect();
+    assert_eq!(current, vec![false, true]);
+    // Pinned snapshot chunks are not orphans
+    assert_eq!(dedup_stats(&store, DB, FS).orphan_chunks, 0);
+}
+
+#[tokio::test]
+async fn merge_fast_forwards_then_merges_non_conflicting_paths() {
+    let (_tmp, store, eff) = setup();
+    put(&store, &eff, "a.txt", b"a1").await;
+    put(&store, &eff, "b.txt", b"b1").await;
+    create_branch(&store, DB, FS, "feature", None, &author(), &eff).unwrap();
+
+    checkout(&store, &eff, "feature").await;
+    put(&store, &eff, "a.txt", b"a2").await;
+    checkout(&store, &eff, "main").await;
+
+    let out = merge(&store, &eff, "feature").await;
+    assert_eq!(out.status, "fast_forward");
+    assert_eq!(out.applied, vec!["a.txt".to_string()]);
+    assert_eq!(current_branch_head(&store, DB, FS, "main"), current_branch_head(&store, DB, FS, "feature"));
+    assert_eq!(read(&store, "a.txt").as_deref(), Some(&b"a2"[..]));
+    assert_eq!(merge(&store, &eff, "feature").await.status, "up_to_date");
+
+    // Diverge: main edits b.txt, feature adds c.txt and deletes a.txt
+    put(&store, &eff, "b.txt", b"b2").await;
+    checkout(&store, &eff, "feature").await;
+    put(&store, &eff, "c.txt", b"c1").await;
+    delete_file(&store, DB, FS, "a.txt", &user(), &eff, &AclContext::default()).await.unwrap();
+    checkout(&store, &eff, "main").await;
+
+    let out = merge(&store, &eff, "feature").await;
+    assert_eq!(out.status, "merged");
+    assert_eq!(out.applied, vec!["a.txt".to_string(), "c.txt".to_string()]);
+    assert!(read(&store, "a.txt").is_none());
+    assert_eq!(read(&store, "b.txt").as_deref(), Some(&b"b2"[..]));
+    assert_eq!(read(&store, "c.txt").as_deref(), Some(&b"c1"[..]));
+    let commit = ops::load_commit(&store, DB, FS, out.commit_id.as_deref().unwrap()).unwrap().unwrap();
+    assert_eq!(commit.parents.len(), 2);
+    assert_eq!(commit.message, "Merge branch 'feature' into 'main'");
+}
+
+#[tokio::test]
+async fn conflicting_merge_applies_nothing_and_lists_conflicts() {
+    let (_tmp, store, eff) = setup();
+    put(&store, &eff, "plan.txt", b"v1").await;
+    put(&store, &eff, "other.txt", b"o1").await;
+    create_branch(&store, DB, FS, "edit", None, &author(), &eff).unwrap();
+
+    put(&store, &eff, "plan.txt", b"ours").await;
+    checkout(&store, &eff, "edit").await;
+    put(&store, &eff, "plan.txt", b"theirs").await;
+    put(&store, &eff, "other.txt", b"o2").await;
+    checkout(&store, &eff, "main").await;
+
+    let head_before = current_branch_head(&store, DB, FS, "main");
+    let out = merge(&store, &eff, "edit").await;
+    assert_eq!(out.status, "conflict");
+    assert_eq!(out.conflicts, vec!["plan.txt".to_string()]);
+    assert!(out.commit_id.is_none() && out.applied.is_empty());
+    assert_eq!(current_branch_head(&store, DB, FS, "main"), head_before);
+    assert_eq!(read(&store, "plan.txt").as_deref(), Some(&b"ours"[..]));
+    assert_eq!(read(&store, "other.txt").as_deref(), Some(&b"o1"[..]));
+
+    let df = show_merge_conflicts_df(&store, DB, FS, &out).unwrap();
+    assert_eq!(df.height(), 1);
+    assert_eq!(df.column("path").unwrap().str().unwrap().get(0), Some("plan.txt"));
+    assert_eq!(df.column("change").unwrap().str().unwrap().get(0), Some("modified"));
+}
+
+#[test]
+fn create_branch_validates_source_and_name() {
+    let (_tmp, store, eff) = setup();
+    let err = create_branch(&store, DB, FS, "x", None, &author(), &eff).unwrap_err().to_string();
+    assert!(err.starts_with("nothing_to_branch"), "{}", err);
+    let err = create_branch(&store, DB, FS, "x", Some("nope"), &author(), &eff).unwrap_err().to_string();
+    assert!(err.starts_with("branch_not_found"), "{}", err);
+    assert!(create_branch(&store, DB, FS, "bad name", Some("main"), &author(), &eff).is_err());
+}
@@ -0,0 +1,80 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use crate::server::exec::filestore::chunker::{chunk_oid, chunk_ranges};
+use std::collections::HashSet;
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+/// Deterministic pseudo-random bytes (xorshift64).
+fn noise(len: usize, seed: u64) -> Vec<u8> {
+    let mut x = seed;
+    (0..len).map(|_| { x ^= x << 13; x ^= x >> 7; x ^= x << 17; x as u8 }).collect()
+}
+
+/// `data` with a few bytes inserted in the middle.
+fn edited(data: &[u8]) -> Vec<u8> {
+    let mid = data.len() / 2;
+    [&data[..mid], b"-edit-", &data[mid..]].concat()
+}
+
+fn oids(data: &[u8], p: &ChunkParams) -> Vec<String> {
+    chunk_ranges(data, p).into_iter().map(|(off, len)| chunk_oid(&data[off..off + len])).collect()
+}
+
+#[test]
+fn boundaries_are_content_defined() {
+    let p = ChunkParams::default();
+    let data = noise(1_500_000, 7);
+    let ranges = chunk_ranges(&data, &p);
+    assert_eq!(ranges.iter().map(|r| r.1).sum::<usize>(), data.len());
+    for (i, (_, len)) in ranges.iter().enumerate() {
+        assert!(*len <= p.max);
+        if i + 1 < ranges.len() { assert!(*len >= p.min); }
+    }
+    assert!(ranges.len() > 5, "{} chunks", ranges.len());
+
+    // An insertion only changes the chunks around it
+    let before: HashSet<String> = oids(&data, &p).into_iter().collect();
+    let after = oids(&edited(&data), &p);
+    let changed = after.iter().filter(|o| !before.contains(*o)).count();
+    assert!(changed <= 2, "{} of {} chunks changed", changed, after.len());
+}
+
+#[tokio::test]
+async fn versions_share_chunks() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let (db, fs) = ("clarium", "media");
+    let mut cfg = FilestoreConfig::default();
+    cfg.security_check_enabled = false;
+    let eff = EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None);
+    let user = AclUser { id: "u".into(), roles: vec![], ip: None };
+    let ctx = AclContext::default();
+
+    let v1 = noise(1_500_000, 11);
+    let v2 = edited(&v1);
+    let m1 = ingest_from_bytes(&store, db, fs, "big.bin", &v1, None, None, &user, &eff, &ctx).await.unwrap();
+    assert!(m1.chunking.is_some());
+    assert_eq!(get_file_bytes(&store, db, fs, &m1).unwrap().unwrap(), v1);
+    let chunks_v1 = dedup_stats(&store, db, fs).chunks;
+
+    // A second version stored under another path only adds the changed chunks
+    let m2 = ingest_from_bytes(&store, db, fs, "big.v2.bin", &v2, None, None, &user, &eff, &ctx).await.unwrap();
+    assert_eq!(get_file_bytes(&store, db, fs, &m2).unwrap().unwrap(), v2);
+    let st = dedup_stats(&store, db, fs);
+    assert!(st.chunks - chunks_v1 <= 2, "{:?}", st);
+    assert_eq!(st.logical_bytes, (v1.len() + v2.len()) as u64);
+    assert!(st.ratio().unwrap() > 1.4, "{:?}", st);
+    let admin = show_admin_counts_df(&store, db, fs).unwrap();
+    assert_eq!(admin.column("dedup_ratio").unwrap().f64().unwrap().get(0), st.ratio());
+
+    // Updating in place keeps the unchanged chunks; the replaced ones become orphans
+    let m3 = update_from_bytes(&store, db, fs, "big.bin", &m1.etag, &noise(1_200_000, 12), None, None, &user, &eff, &ctx).await.unwrap();
+    assert_eq!(get_file_bytes(&store, db, fs, &m3).unwrap().unwrap(), noise(1_200_000, 12));
+    assert!(gc_dry_run(&store, db, fs).unwrap().orphan_chunks > 0);
+
+    // Small files stay single blobs
+    let small = ingest_from_bytes(&store, db, fs, "small.txt", b"hello", None, None, &user, &eff, &ctx).await.unwrap();
+    assert!(small.chunking.is_none());
+    assert_eq!(get_file_bytes(&store, db, fs, &small).unwrap().unwrap(), b"hello");
+}
@@ -0,0 +1,141 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+const DB: &str = "clarium";
+
+fn eff() -> EffectiveConfig {
+    let cfg = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+/// Next event of filestore `fs` from the process-wide bus.
+async fn next_for(rx: &mut tokio::sync::broadcast::Receiver<FileEvent>, fs: &str) -> FileEvent {
+    loop {
+        let ev = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.expect("event").expect("bus open");
+        if ev.filestore == fs { return ev; }
+    }
+}
+
+/// Read one HTTP request (head and body) from a connection.
+fn read_request(conn: &mut std::net::TcpStream) -> (String, Vec<u8>) {
+    let mut buf: Vec<u8> = Vec::new();
+    let mut tmp = [0u8; 4096];
+    let head_end = loop {
+        let n = conn.read(&mut tmp).unwrap();
+        buf.extend_from_slice(&tmp[..n]);
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") { break i + 4; }
+        assert!(n > 0, "connection closed mid-request");
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let len: usize = head.lines()
+        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+        .unwrap_or(0);
+    while buf.len() < head_end + len {
+        let n = conn.read(&mut tmp).unwrap();
+        buf.extend_from_slice(&tmp[..n]);
+    }
+    (head, buf[head_end..head_end + len].to_vec())
+}
+
+#[test]
+fn signs_bodies_with_hmac_sha256() {
+    assert_eq!(
+        events::sign(b"key", b"The quick brown fox jumps over the lazy dog"),
+        "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+    );
+}
+
+#[test]
+fn webhooks_filter_by_kind_and_prefix() {
+    let hook = WebhookConfig { url: "http://x".into(), events: Some(vec!["DELETE".into()]), prefix: Some("/legal/".into()), secret_env: None };
+    let ev = |kind: &str, path: Option<&str>| FileEvent { path: path.map(str::to_string), ..FileEvent::new(kind, DB, "fs") };
+    assert!(hook.matches(&ev("delete", Some("legal/a.pdf"))));
+    assert!(!hook.matches(&ev("delete", Some("legalese/a.pdf"))));
+    assert!(!hook.matches(&ev("ingest", Some("legal/a.pdf"))));
+    let all = WebhookConfig { url: "http://x".into(), ..Default::default() };
+    assert!(all.matches(&ev("commit", None)));
+}
+
+#[tokio::test]
+async fn mutations_publish_events_on_the_bus() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let (eff, u) = (eff(), user());
+    let ctx = AclContext { request_id: Some("req-1".into()), ..Default::default() };
+    let mut rx = subscribe_events();
+
+    let m = ingest_from_bytes(&store, DB, "ev1", "a.txt", b"one", None, None, &u, &eff, &ctx).await.unwrap();
+    update_from_bytes(&store, DB, "ev1", "a.txt", &m.etag, b"two", None, None, &u, &eff, &ctx).await.unwrap();
+    rename_file(&store, DB, "ev1", "a.txt", "b.txt", &u, &eff, &ctx).await.unwrap();
+    delete_file(&store, DB, "ev1", "b.txt", &u, &eff, &ctx).await.unwrap();
+    let tree = create_tree_from_prefix(&store, DB, "ev1", None).unwrap();
+    let author = CommitAuthor { name: "t".into(), email: "t@local".into(), time_unix: 0 };
+    let commit = commit_tree(&store, DB, "ev1", &tree.id, &[], &author, "m", &[], "main").unwrap();
+
+    let ev = next_for(&mut rx, "ev1").await;
+    assert_eq!((ev.kind.as_str(), ev.path.as_deref(), ev.size, ev.correlation_id.as_deref()), ("ingest", Some("a.txt"), Some(3), Some("req-1")));
+    let ev = next_for(&mut rx, "ev1").await;
+    assert_eq!((ev.kind.as_str(), ev.version), ("update", Some(2)));
+    let ev = next_for(&mut rx, "ev1").await;
+    assert_eq!((ev.kind.as_str(), ev.path.as_deref(), ev.old_path.as_deref()), ("rename", Some("b.txt"), Some("a.txt")));
+    let ev = next_for(&mut rx, "ev1").await;
+    assert_eq!((ev.kind.as_str(), ev.path.as_deref()), ("delete", Some("b.txt")));
+    let ev = next_for(&mut rx, "ev1").await;
+    assert_eq!((ev.kind.as_str(), ev.commit_id, ev.branch.as_deref()), ("commit", Some(commit.id), Some("main")));
+}
+
+#[tokio::test]
+async fn webhook_delivery_is_signed_and_retried() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    // First attempt fails with 500, the retry succeeds
+    let server = std::thread::spawn(move || {
+        let mut seen = Vec::new();
+        for status in ["500 Internal Server Error", "200 OK"] {
+            let (mut conn, _) = listener.accept().unwrap();
+            seen.push(read_request(&mut conn));
+            write!(conn, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+        }
+        seen
+    });
+    std::env::set_var("CLARIUM_TEST_WEBHOOK_SECRET", "s3cret");
+
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let hook = WebhookConfig { url: url.clone(), events: Some(vec!["ingest".into()]), prefix: None, secret_env: Some("CLARIUM_TEST_WEBHOOK_SECRET".into()) };
+    let cfg = FilestoreConfig { security_check_enabled: false, webhooks: Some(vec![hook]), ..Default::default() };
+    save_filestore_entry(&store, DB, "ev2", &FilestoreRegistryEntry::new("ev2", cfg)).unwrap();
+
+    let m = ingest_from_bytes(&store, DB, "ev2", "doc.md", b"# hi", None, None, &user(), &eff(), &AclContext::default()).await.unwrap();
+    // Not subscribed to updates: nothing more is sent
+    update_from_bytes(&store, DB, "ev2", "doc.md", &m.etag, b"# hello", None, None, &user(), &eff(), &AclContext::default()).await.unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while webhook_stats(DB, "ev2", &url).delivered == 0 {
+        assert!(Instant::now() < deadline, "webhook not delivered");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let st = webhook_stats(DB, "ev2", &url);
+    assert_eq!((st.delivered, st.failed, st.last_status), (1, 0, Some(200)));
+
+    let seen = server.join().unwrap();
+    let (head, body) = &seen[1];
+    assert_eq!(body, &seen[0].1, "the retry resends the same body");
+    let lower = head.to_ascii_lowercase();
+    assert!(lower.starts_with("post /hook "), "{}", head);
+    assert!(lower.contains("x-clarium-event: ingest"), "{}", head);
+    assert!(lower.contains(&format!("x-clarium-signature: {}", events::sign(b"s3cret", body))), "{}", head);
+    let ev: FileEvent = serde_json::from_slice(body).unwrap();
+    assert_eq!((ev.kind.as_str(), ev.path.as_deref(), ev.etag.as_deref()), ("ingest", Some("doc.md"), Some(m.etag.as_str())));
+
+    let df = show_webhooks_df(&store, DB, "ev2").unwrap();
+    assert_eq!(df.column("delivered").unwrap().i64().unwrap().get(0), Some(1));
+    assert_eq!(df.column("signed").unwrap().bool().unwrap().get(0), Some(true));
+}
@@ -0,0 +1,104 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use crate::server::exec::filestore::fulltext::{extract_text, tokenize};
+use std::io::Write;
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+fn eff() -> EffectiveConfig {
+    let cfg = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+fn paths(hits: &[SearchHit]) -> Vec<&str> { hits.iter().map(|h| h.path.as_str()).collect() }
+
+#[test]
+fn tokenizes_lowercase_alphanumeric_runs() {
+    assert_eq!(tokenize("Hello, World! x86_64 Café-au-lait"), vec!["hello", "world", "x86", "64", "café", "au", "lait"]);
+    assert!(tokenize(&"a".repeat(65)).is_empty());
+    assert!(tokenize(" -- ").is_empty());
+}
+
+#[test]
+fn extracts_text_by_type() {
+    assert_eq!(extract_text("notes.md", None, b"# Title", false).as_deref(), Some("# Title"));
+    assert_eq!(extract_text("blob", Some("text/csv; charset=utf-8"), b"a,b", false).as_deref(), Some("a,b"));
+    assert!(extract_text("image.png", Some("image/png"), b"\x89PNG", false).is_none());
+
+    let json = extract_text("d.json", None, br#"{"name": "alpha", "n": 3, "tags": ["beta"]}"#, false).unwrap();
+    let mut terms = tokenize(&json);
+    terms.sort();
+    assert_eq!(terms, vec!["alpha", "beta", "n", "name", "tags"]);
+    let ndjson = extract_text("d.ndjson", None, b"{\"k\": \"one\"}\n{\"k\": \"two\"}\n", false).unwrap();
+    assert_eq!(tokenize(&ndjson), vec!["k", "one", "k", "two"]);
+}
+
+#[test]
+fn extracts_pdf_text_only_when_enabled() {
+    let content = b"BT /F1 12 Tf 72 712 Td (Quarterly) Tj [(rev) -30 (enue) -250 (grew)] TJ ET";
+    let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    z.write_all(b"BT (Deflated\\040words) Tj ET").unwrap();
+    let deflated = z.finish().unwrap();
+    let mut pdf = b"%PDF-1.4\n4 0 obj << /Length 80 >>\nstream\n".to_vec();
+    pdf.extend_from_slice(content);
+    pdf.extend_from_slice(b"\nendstream\nendobj\n5 0 obj << /Filter /FlateDecode >>\nstream\n");
+    pdf.extend_from_slice(&deflated);
+    pdf.extend_from_slice(b"\nendstream\nendobj\n%%EOF");
+
+    assert!(extract_text("r.pdf", None, &pdf, false).is_none());
+    let text = extract_text("r.pdf", None, &pdf, true).unwrap();
+    assert_eq!(tokenize(&text), vec!["quarterly", "revenue", "grew", "deflated", "words"]);
+    assert!(extract_text("r.pdf", None, b"not a pdf", true).is_none());
+}
+
+#[tokio::test]
+async fn index_follows_update_rename_and_delete() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let (db, fs) = ("clarium", "kb");
+    let (eff, ctx) = (eff(), AclContext::default());
+
+    let a = ingest_from_bytes(&store, db, fs, "a.txt", b"red apple", None, None, &user(), &eff, &ctx).await.unwrap();
+    ingest_from_bytes(&store, db, fs, "b.txt", b"green apple", None, None, &user(), &eff, &ctx).await.unwrap();
+    assert_eq!(paths(&search(&store, db, fs, "apple").unwrap()), vec!["a.txt", "b.txt"]);
+    assert_eq!(paths(&search(&store, db, fs, "RED apple").unwrap()), vec!["a.txt"]);
+    assert!(search(&store, db, fs, "red green").unwrap().is_empty());
+
+    update_from_bytes(&store, db, fs, "a.txt", &a.etag, b"blue pear", None, None, &user(), &eff, &ctx).await.unwrap();
+    assert_eq!(paths(&search(&store, db, fs, "apple").unwrap()), vec!["b.txt"]);
+    assert_eq!(paths(&search(&store, db, fs, "pear").unwrap()), vec!["a.txt"]);
+
+    rename_file(&store, db, fs, "a.txt", "fruit/a.txt", &user(), &eff, &ctx).await.unwrap();
+    let hits = search(&store, db, fs, "pear").unwrap();
+    assert_eq!(paths(&hits), vec!["fruit/a.txt"]);
+    assert_eq!(hits[0].snippet, "blue pear");
+
+    delete_file(&store, db, fs, "fruit/a.txt", &user(), &eff, &ctx).await.unwrap();
+    assert!(search(&store, db, fs, "pear").unwrap().is_empty());
+    let kv = store.kv_store(db, fs);
+    assert!(kv.keys().iter().all(|k| !k.contains(".fts.term::pear")));
+    assert!(search(&store, db, fs, "!!").is_err());
+}
+
+#[tokio::test]
+async fn ranks_by_bm25_and_respects_config() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let (db, fs) = ("clarium", "kb");
+    let ctx = AclContext::default();
+    let eff = eff();
+
+    ingest_from_bytes(&store, db, fs, "once.txt", b"rust is a language among many other languages and tools", None, None, &user(), &eff, &ctx).await.unwrap();
+    ingest_from_bytes(&store, db, fs, "often.txt", b"rust rust rust", None, None, &user(), &eff, &ctx).await.unwrap();
+    let hits = search(&store, db, fs, "rust").unwrap();
+    assert_eq!(paths(&hits), vec!["often.txt", "once.txt"]);
+    assert!(hits[0].score > hits[1].score && hits[1].score > 0.0);
+
+    let off = EffectiveConfig { fulltext_index: false, ..eff.clone() };
+    ingest_from_bytes(&store, db, fs, "hidden.txt", b"rust", None, None, &user(), &off, &ctx).await.unwrap();
+    let small = EffectiveConfig { fulltext_max_bytes: 3, ..eff.clone() };
+    ingest_from_bytes(&store, db, fs, "large.txt", b"rust", None, None, &user(), &small, &ctx).await.unwrap();
+    assert_eq!(search(&store, db, fs, "rust").unwrap().len(), 2);
+}
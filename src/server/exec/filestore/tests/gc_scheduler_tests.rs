@@ -0,0 +1,115 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use chrono::Utc;
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+const DB: &str = "clarium";
+const FS: &str = "scratch";
+
+/// Save the config in the registry (GC and the scheduler read it from there) and return its effective form.
+fn configure(store: &SharedStore, cfg: FilestoreConfig) -> EffectiveConfig {
+    let cfg = FilestoreConfig { security_check_enabled: false, chunking_threshold_bytes: Some(64 * 1024), ..cfg };
+    save_filestore_entry(store, DB, FS, &FilestoreRegistryEntry::new(FS, cfg.clone())).unwrap();
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+fn big(seed: u8) -> Vec<u8> { (0..300_000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8 ^ seed).collect() }
+
+#[tokio::test]
+async fn unreferenced_content_is_reclaimed_on_the_run_after_it_is_marked() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = configure(&store, FilestoreConfig { gc_grace_seconds: Some(0), ..Default::default() });
+    let (u, ctx) = (user(), AclContext::default());
+
+    let meta = ingest_from_bytes(&store, DB, FS, "data.bin", &big(1), None, None, &u, &eff, &ctx).await.unwrap();
+    update_from_bytes(&store, DB, FS, "data.bin", &meta.etag, &big(2), None, None, &u, &eff, &ctx).await.unwrap();
+    ingest_from_bytes(&store, DB, FS, "note.txt", b"short lived", None, None, &u, &eff, &ctx).await.unwrap();
+    delete_file(&store, DB, FS, "note.txt", &u, &eff, &ctx).await.unwrap();
+    let orphans = dedup_stats(&store, DB, FS).orphan_chunks as i64;
+    assert!(orphans > 0);
+
+    // First run drops the tombstone and only marks what nothing refers to
+    let rep = gc_apply(&store, DB, FS).unwrap();
+    assert_eq!((rep.files_deleted, rep.chunks_deleted, rep.blobs_deleted, rep.bytes_reclaimed), (1, 0, 0, 0));
+
+    let rep = gc_apply(&store, DB, FS).unwrap();
+    assert_eq!((rep.chunks_deleted, rep.blobs_deleted), (orphans, 1));
+    assert!(rep.bytes_reclaimed >= 300_000);
+    assert_eq!(dedup_stats(&store, DB, FS).orphan_chunks, 0);
+    let meta = get_file_meta(&store, DB, FS, "data.bin").unwrap().unwrap();
+    assert_eq!(get_file_bytes(&store, DB, FS, &meta).unwrap().unwrap(), big(2));
+
+    // Nothing left to reclaim, and no marks left behind
+    let rep = gc_apply(&store, DB, FS).unwrap();
+    assert_eq!(rep.bytes_reclaimed, 0);
+    let marks = Keys::gc_mark_prefix(DB, FS);
+    assert!(!store.kv_store(DB, FS).keys().iter().any(|k| k.starts_with(&marks)));
+}
+
+#[tokio::test]
+async fn content_referenced_again_keeps_it_and_loses_its_mark() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = configure(&store, FilestoreConfig { gc_grace_seconds: Some(0), ..Default::default() });
+    let (u, ctx) = (user(), AclContext::default());
+
+    let meta = ingest_from_bytes(&store, DB, FS, "a.bin", &big(1), None, None, &u, &eff, &ctx).await.unwrap();
+    update_from_bytes(&store, DB, FS, "a.bin", &meta.etag, &big(2), None, None, &u, &eff, &ctx).await.unwrap();
+    assert_eq!(gc_apply(&store, DB, FS).unwrap().chunks_deleted, 0);
+
+    // The old content comes back under another path before the next run
+    ingest_from_bytes(&store, DB, FS, "b.bin", &big(1), None, None, &u, &eff, &ctx).await.unwrap();
+    let rep = gc_apply(&store, DB, FS).unwrap();
+    assert_eq!((rep.chunks_deleted, rep.bytes_reclaimed), (0, 0));
+    let meta = get_file_meta(&store, DB, FS, "b.bin").unwrap().unwrap();
+    assert_eq!(get_file_bytes(&store, DB, FS, &meta).unwrap().unwrap(), big(1));
+}
+
+#[tokio::test]
+async fn grace_period_holds_marked_content_back() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = configure(&store, FilestoreConfig { gc_grace_seconds: Some(3600), ..Default::default() });
+    let (u, ctx) = (user(), AclContext::default());
+
+    let meta = ingest_from_bytes(&store, DB, FS, "a.bin", &big(1), None, None, &u, &eff, &ctx).await.unwrap();
+    update_from_bytes(&store, DB, FS, "a.bin", &meta.etag, &big(2), None, None, &u, &eff, &ctx).await.unwrap();
+    for _ in 0..2 { assert_eq!(gc_apply(&store, DB, FS).unwrap().chunks_deleted, 0); }
+    assert!(dedup_stats(&store, DB, FS).orphan_chunks > 0);
+}
+
+#[tokio::test]
+async fn scheduler_runs_due_filestores_and_records_history() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = configure(&store, FilestoreConfig { gc_interval_seconds: Some(3600), gc_grace_seconds: Some(0), ..Default::default() });
+    assert_eq!(eff.gc_interval_seconds, 3600);
+    assert!(!gc_due(&EffectiveConfig { gc_interval_seconds: 0, ..eff.clone() }, None, 0));
+    assert!(gc_due(&eff, None, 0));
+    assert!(!gc_due(&eff, Some(100), 3699));
+    assert!(gc_due(&eff, Some(100), 3700));
+
+    let now = Utc::now().timestamp();
+    let runs = run_due_gc(&store, DB, now);
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].trigger, "scheduled");
+    // Not due again until an interval has passed
+    assert!(run_due_gc(&store, DB, now + 60).is_empty());
+    assert_eq!(run_due_gc(&store, DB, now + 7200).len(), 1);
+
+    let manual = run_gc(&store, DB, FS, "manual").unwrap();
+    let runs = list_gc_runs(&store, DB, FS);
+    assert_eq!(runs.len(), 3);
+    assert_eq!(runs[0], manual);
+
+    let df = show_gc_runs_df(&store, DB, FS).unwrap();
+    assert_eq!(df.height(), 3);
+    assert_eq!(df.column("trigger").unwrap().str().unwrap().get(0), Some("manual"));
+    assert_eq!(df.column("trigger").unwrap().str().unwrap().get(2), Some("scheduled"));
+    assert_eq!(df.column("bytes_reclaimed").unwrap().i64().unwrap().get(0), Some(0));
+    assert_eq!(df.column("error").unwrap().str().unwrap().get(0), None);
+}
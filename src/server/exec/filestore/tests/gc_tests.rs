@@ -27,6 +27,7 @@ fn dry_run_counts_tombstones_only() {
         description_html: None,
         custom: None,
         chunking: None,
+        scan: None,
     };
     let k1 = Keys::path(db, fs, &live.logical_path);
     kv.set(k1, KvValue::Json(serde_json::to_value(&live).unwrap()), None, None);
@@ -68,6 +69,7 @@ fn apply_respects_grace_period() {
         description_html: None,
         custom: None,
         chunking: None,
+        scan: None,
     };
     let k1 = Keys::path(db, fs, &m1.logical_path);
     kv.set(k1.clone(), KvValue::Json(serde_json::to_value(&m1).unwrap()), None, None);
@@ -0,0 +1,222 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use crate::server::exec::filestore::git::sync::{push_with, pull_with};
+use crate::server::exec::filestore::git::{GitBackend, GitCommitIds, GitCredentials, GitRefUpdate};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use sha2::{Digest, Sha256};
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+/// In-memory stand-in for a Git repository and its remote. Objects are shared; `remote`
+/// holds the branches of the "server", `local` the refs of the clone (keyed by repo path).
+#[derive(Default)]
+struct MockState {
+    blobs: HashMap<String, Vec<u8>>,
+    trees: HashMap<String, Vec<(String, String)>>,
+    commits: HashMap<String, (String, Vec<String>)>,
+    local: HashMap<(String, String), String>,
+    remote: HashMap<String, String>,
+}
+
+#[derive(Clone, Default)]
+struct MockGit(Arc<Mutex<MockState>>);
+
+fn oid(kind: &str, data: &[u8]) -> String {
+    let mut h = Sha256::new();
+    h.update(kind.as_bytes());
+    h.update(data);
+    h.finalize().iter().take(20).map(|b| format!("{b:02x}")).collect()
+}
+
+impl GitBackend for MockGit {
+    fn ensure_repo(&self, _repo_path: &str, _remote: Option<&str>) -> anyhow::Result<()> { Ok(()) }
+    fn write_blob(&self, _repo_path: &str, _path: &str, data: &[u8]) -> anyhow::Result<String> {
+        let id = oid("blob", data);
+        self.0.lock().unwrap().blobs.insert(id.clone(), data.to_vec());
+        Ok(id)
+    }
+    fn write_tree(&self, _repo_path: &str, entries: &[(String, String)]) -> anyhow::Result<String> {
+        let mut entries = entries.to_vec();
+        entries.sort();
+        let id = oid("tree", format!("{:?}", entries).as_bytes());
+        self.0.lock().unwrap().trees.insert(id.clone(), entries);
+        Ok(id)
+    }
+    fn write_commit(&self, _repo_path: &str, message: &str, _author: &str, parents: &[String], tree_id: &str) -> anyhow::Result<GitCommitIds> {
+        let id = oid("commit", format!("{}{:?}{}", tree_id, parents, message).as_bytes());
+        self.0.lock().unwrap().commits.insert(id.clone(), (tree_id.to_string(), parents.to_vec()));
+        Ok(GitCommitIds { tree_id: tree_id.to_string(), commit_id: id })
+    }
+    fn update_ref(&self, repo_path: &str, update: &GitRefUpdate) -> anyhow::Result<()> {
+        self.0.lock().unwrap().local.insert((repo_path.to_string(), update.reference.clone()), update.new_target.clone());
+        Ok(())
+    }
+    fn resolve_ref(&self, repo_path: &str, reference: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.0.lock().unwrap().local.get(&(repo_path.to_string(), reference.to_string())).cloned())
+    }
+    fn read_tree(&self, _repo_path: &str, commit_id: &str) -> anyhow::Result<Vec<(String, String)>> {
+        let st = self.0.lock().unwrap();
+        let (tree, _) = st.commits.get(commit_id).cloned().ok_or_else(|| anyhow::anyhow!("no commit"))?;
+        Ok(st.trees.get(&tree).cloned().unwrap_or_default())
+    }
+    fn read_blob(&self, _repo_path: &str, blob_id: &str) -> anyhow::Result<Vec<u8>> {
+        self.0.lock().unwrap().blobs.get(blob_id).cloned().ok_or_else(|| anyhow::anyhow!("no blob"))
+    }
+    fn fetch(&self, _repo_path: &str, _remote: &str, reference: &str, _creds: &GitCredentials) -> anyhow::Result<Option<String>> {
+        Ok(self.0.lock().unwrap().remote.get(reference).cloned())
+    }
+    fn push(&self, repo_path: &str, _remote: &str, reference: &str, _creds: &GitCredentials, force: bool) -> anyhow::Result<()> {
+        let mut st = self.0.lock().unwrap();
+        let new = st.local.get(&(repo_path.to_string(), reference.to_string())).cloned().ok_or_else(|| anyhow::anyhow!("no local ref"))?;
+        if let Some(cur) = st.remote.get(reference) {
+            let parents = st.commits.get(&new).map(|c| c.1.clone()).unwrap_or_default();
+            if !force && !parents.contains(cur) { anyhow::bail!("non-fast-forward"); }
+        }
+        st.remote.insert(reference.to_string(), new);
+        Ok(())
+    }
+}
+
+struct Side {
+    store: SharedStore,
+    fs: &'static str,
+    eff: EffectiveConfig,
+}
+
+fn side(fs: &'static str) -> (tempfile::TempDir, Side) {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let cfg = FilestoreConfig { security_check_enabled: false, git_remote: Some("ssh://git@example.com/docs.git".into()), ..Default::default() };
+    let eff = EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None);
+    (tmp, Side { store, fs, eff })
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+impl Side {
+    async fn put(&self, path: &str, body: &[u8]) {
+        let (u, ctx) = (user(), AclContext::default());
+        match get_file_meta(&self.store, "clarium", self.fs, path).unwrap() {
+            Some(m) if !m.deleted => { update_from_bytes(&self.store, "clarium", self.fs, path, &m.etag, body, None, None, &u, &self.eff, &ctx).await.unwrap(); }
+            _ => { ingest_from_bytes(&self.store, "clarium", self.fs, path, body, None, None, &u, &self.eff, &ctx).await.unwrap(); }
+        }
+    }
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let m = get_file_meta(&self.store, "clarium", self.fs, path).unwrap().filter(|m| !m.deleted)?;
+        get_file_bytes(&self.store, "clarium", self.fs, &m).unwrap()
+    }
+    async fn push(&self, git: &MockGit, force: bool) -> anyhow::Result<SyncRun> {
+        let opts = SyncOptions { force, ..Default::default() };
+        push_with(git, &self.store, "clarium", self.fs, &opts, &user(), &self.eff, &AclContext::default()).await
+    }
+    async fn pull(&self, git: &MockGit, policy: Option<ConflictPolicy>) -> anyhow::Result<SyncRun> {
+        let opts = SyncOptions { policy, correlation_id: Some("pull-run".into()), ..Default::default() };
+        pull_with(git, &self.store, "clarium", self.fs, &opts, &user(), &self.eff, &AclContext::default()).await
+    }
+}
+
+#[tokio::test]
+async fn push_then_pull_round_trips_files() {
+    let git = MockGit::default();
+    let (_ta, a) = side("docs");
+    let (_tb, b) = side("mirror");
+    a.put("readme.md", b"hello").await;
+    a.put("guides/intro.txt", b"intro").await;
+
+    let run = a.push(&git, false).await.unwrap();
+    assert_eq!((run.status.as_str(), run.files_added, run.direction.as_str(), run.branch.as_str()), ("ok", 2, "push", "main"));
+    let sha = run.git_sha.clone().unwrap();
+    assert_eq!(git.0.lock().unwrap().remote.get("refs/heads/main"), Some(&sha));
+    let commit = ops::load_commit(&a.store, "clarium", "docs", run.commit_id.as_deref().unwrap()).unwrap().unwrap();
+    assert_eq!(commit.git_sha.as_deref(), Some(sha.as_str()));
+    assert_eq!(a.push(&git, false).await.unwrap().status, "up_to_date");
+
+    let run = b.pull(&git, None).await.unwrap();
+    assert_eq!((run.status.as_str(), run.files_added), ("ok", 2));
+    assert_eq!(b.read("guides/intro.txt").as_deref(), Some(&b"intro"[..]));
+
+    // Progress is recorded under the correlation id
+    let runs = git::sync::list_sync_runs(&b.store, "clarium", "mirror");
+    let stages: Vec<&str> = runs[0].stages.iter().map(|s| s.stage.as_str()).collect();
+    assert_eq!(stages, vec!["start", "fetch", "read_tree", "apply", "record", "ok"]);
+    assert_eq!(b.pull(&git, None).await.unwrap().status, "up_to_date");
+    let df = show_sync_runs_df(&b.store, "clarium", "mirror").unwrap();
+    assert_eq!(df.height(), 1, "both pulls share the correlation id");
+    assert_eq!(df.column("correlation_id").unwrap().str().unwrap().get(0), Some("pull-run"));
+
+    // Edits and deletes flow back the other way
+    b.put("readme.md", b"hello again").await;
+    delete_file(&b.store, "clarium", "mirror", "guides/intro.txt", &user(), &b.eff, &AclContext::default()).await.unwrap();
+    let run = b.push(&git, false).await.unwrap();
+    assert_eq!((run.files_updated, run.files_deleted), (1, 1));
+    let run = a.pull(&git, None).await.unwrap();
+    assert_eq!((run.files_updated, run.files_deleted), (1, 1));
+    assert_eq!(a.read("readme.md").as_deref(), Some(&b"hello again"[..]));
+    assert!(a.read("guides/intro.txt").is_none());
+}
+
+#[tokio::test]
+async fn diverged_push_is_rejected_and_pull_resolves_conflicts_by_policy() {
+    let git = MockGit::default();
+    let (_ta, a) = side("docs");
+    let (_tb, b) = side("mirror");
+    a.put("plan.txt", b"v1").await;
+    a.push(&git, false).await.unwrap();
+    b.pull(&git, None).await.unwrap();
+
+    b.put("plan.txt", b"theirs").await;
+    b.push(&git, false).await.unwrap();
+    a.put("plan.txt", b"ours").await;
+    a.put("notes.txt", b"local only").await;
+
+    let err = a.push(&git, false).await.unwrap_err().to_string();
+    assert!(err.starts_with("non_fast_forward"), "{}", err);
+    assert!(err.contains("[corr="), "{}", err);
+    let runs = git::sync::list_sync_runs(&a.store, "clarium", "docs");
+    assert!(runs.iter().any(|r| r.status == "rejected"));
+
+    // Default policy fails and applies nothing
+    let err = a.pull(&git, None).await.unwrap_err().to_string();
+    assert!(err.starts_with("sync_conflict: 1 file(s)") && err.contains("plan.txt"), "{}", err);
+    assert_eq!(a.read("plan.txt").as_deref(), Some(&b"ours"[..]));
+
+    // Ours keeps the local file, which the next push sends
+    let run = a.pull(&git, Some(ConflictPolicy::Ours)).await.unwrap();
+    assert_eq!(run.conflicts, vec!["plan.txt".to_string()]);
+    assert_eq!(a.read("plan.txt").as_deref(), Some(&b"ours"[..]));
+    let run = a.push(&git, false).await.unwrap();
+    assert_eq!((run.files_added, run.files_updated), (1, 1));
+
+    // Theirs takes the remote file
+    b.put("plan.txt", b"b wins").await;
+    let err = b.push(&git, false).await.unwrap_err().to_string();
+    assert!(err.starts_with("non_fast_forward"), "{}", err);
+    let run = b.pull(&git, Some(ConflictPolicy::Theirs)).await.unwrap();
+    assert_eq!(run.conflicts, vec!["plan.txt".to_string()]);
+    assert_eq!(b.read("plan.txt").as_deref(), Some(&b"ours"[..]));
+    assert_eq!(b.read("notes.txt").as_deref(), Some(&b"local only"[..]));
+
+    // FORCE overwrites a remote that moved
+    a.put("plan.txt", b"forced").await;
+    b.put("other.txt", b"x").await;
+    b.push(&git, false).await.unwrap();
+    assert!(a.push(&git, false).await.is_err());
+    a.push(&git, true).await.unwrap();
+    let files: BTreeMap<String, String> = {
+        let head = git.0.lock().unwrap().remote.get("refs/heads/main").cloned().unwrap();
+        git.read_tree("", &head).unwrap().into_iter().collect()
+    };
+    assert!(!files.contains_key("other.txt"));
+}
+
+#[tokio::test]
+async fn sync_requires_a_remote() {
+    let git = MockGit::default();
+    let (_t, mut a) = side("docs");
+    a.eff.git_remote = None;
+    let err = a.push(&git, false).await.unwrap_err().to_string();
+    assert!(err.starts_with("git_remote_not_configured"), "{}", err);
+    assert!(ConflictPolicy::parse("Theirs").is_ok());
+    assert!(ConflictPolicy::parse("merge").is_err());
+}
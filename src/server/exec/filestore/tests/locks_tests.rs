@@ -0,0 +1,102 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+const DB: &str = "clarium";
+const FS: &str = "designs";
+
+fn config() -> EffectiveConfig {
+    let cfg = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user(id: &str) -> AclUser { AclUser { id: id.into(), roles: vec![], ip: None } }
+
+async fn put(store: &SharedStore, eff: &EffectiveConfig, path: &str, body: &[u8]) -> FileMeta {
+    ingest_from_bytes(store, DB, FS, path, body, None, None, &user("alice"), eff, &AclContext::default()).await.unwrap()
+}
+
+#[tokio::test]
+async fn lock_blocks_other_users_until_unlocked() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config();
+    let ctx = AclContext::default();
+    let (alice, bob) = (user("alice"), user("bob"));
+    let meta = put(&store, &eff, "cad/part.dwg", b"v1").await;
+
+    let lock = lock_file(&store, DB, FS, "cad/part.dwg", None, Some("editing"), &alice, &eff, &ctx).await.unwrap();
+    assert_eq!((lock.owner.as_str(), lock.note.as_deref()), ("alice", Some("editing")));
+    assert_eq!(lock.expires_at, Some(lock.locked_at + GlobalFilestoreConfig::default().lock_default_ttl_seconds as i64));
+
+    let err = update_from_bytes(&store, DB, FS, "cad/part.dwg", &meta.etag, b"bob", None, None, &bob, &eff, &ctx).await.unwrap_err().to_string();
+    assert!(err.starts_with("file_locked: cad/part.dwg is locked by alice"), "{}", err);
+    let err = ingest_from_bytes(&store, DB, FS, "cad/part.dwg", b"bob", None, None, &bob, &eff, &ctx).await.unwrap_err().to_string();
+    assert!(err.starts_with("file_locked"), "{}", err);
+    assert!(rename_file(&store, DB, FS, "cad/part.dwg", "cad/x.dwg", &bob, &eff, &ctx).await.unwrap_err().to_string().starts_with("file_locked"));
+    assert!(delete_file(&store, DB, FS, "cad/part.dwg", &bob, &eff, &ctx).await.unwrap_err().to_string().starts_with("file_locked"));
+    assert!(lock_file(&store, DB, FS, "cad/part.dwg", None, None, &bob, &eff, &ctx).await.unwrap_err().to_string().starts_with("file_locked"));
+    let err = unlock_file(&store, DB, FS, "cad/part.dwg", false, &bob, &eff, &ctx).await.unwrap_err().to_string();
+    assert!(err.starts_with("lock_owner: cad/part.dwg is locked by alice"), "{}", err);
+
+    // Reads are not blocked; the owner works as usual
+    assert!(read_file_checked(&store, DB, FS, "cad/part.dwg", &bob, &eff, &ctx).is_ok());
+    let meta = update_from_bytes(&store, DB, FS, "cad/part.dwg", &meta.etag, b"v2", None, None, &alice, &eff, &ctx).await.unwrap();
+    assert_eq!(meta.version, 2);
+
+    let df = show_file_locks_df(&store, DB, FS).unwrap();
+    assert_eq!(df.column("owner").unwrap().str().unwrap().get(0), Some("alice"));
+
+    assert!(unlock_file(&store, DB, FS, "cad/part.dwg", false, &alice, &eff, &ctx).await.unwrap());
+    assert!(!unlock_file(&store, DB, FS, "cad/part.dwg", false, &alice, &eff, &ctx).await.unwrap());
+    update_from_bytes(&store, DB, FS, "cad/part.dwg", &meta.etag, b"bob", None, None, &bob, &eff, &ctx).await.unwrap();
+}
+
+#[tokio::test]
+async fn force_breaks_and_expiry_lapses_a_lock() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config();
+    let ctx = AclContext::default();
+    let (alice, admin) = (user("alice"), user("admin"));
+    put(&store, &eff, "a.pptx", b"a").await;
+    put(&store, &eff, "b.pptx", b"b").await;
+
+    lock_file(&store, DB, FS, "a.pptx", Some(0), None, &alice, &eff, &ctx).await.unwrap();
+    assert_eq!(file_lock(&store, DB, FS, "a.pptx").unwrap().expires_at, None);
+    assert!(unlock_file(&store, DB, FS, "a.pptx", true, &admin, &eff, &ctx).await.unwrap());
+    assert!(file_lock(&store, DB, FS, "a.pptx").is_none());
+
+    // An expired lock no longer holds and is not listed
+    let mut lapsed = lock_file(&store, DB, FS, "b.pptx", Some(60), None, &alice, &eff, &ctx).await.unwrap();
+    lapsed.expires_at = Some(lapsed.locked_at - 1);
+    store.kv_store(DB, FS).set(Keys::file_lock(DB, FS, "b.pptx"), crate::storage::KvValue::Json(serde_json::to_value(&lapsed).unwrap()), None, None);
+    assert!(file_lock(&store, DB, FS, "b.pptx").is_none());
+    assert!(list_file_locks(&store, DB, FS).is_empty());
+    delete_file(&store, DB, FS, "b.pptx", &admin, &eff, &ctx).await.unwrap();
+
+    let err = lock_file(&store, DB, FS, "missing.pptx", None, None, &alice, &eff, &ctx).await.unwrap_err().to_string();
+    assert_eq!(err, "not_found");
+}
+
+#[tokio::test]
+async fn rename_moves_the_lock_and_delete_releases_it() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config();
+    let ctx = AclContext::default();
+    let (alice, bob) = (user("alice"), user("bob"));
+    put(&store, &eff, "draft.docx", b"d").await;
+
+    lock_file(&store, DB, FS, "draft.docx", None, None, &alice, &eff, &ctx).await.unwrap();
+    rename_file(&store, DB, FS, "draft.docx", "final.docx", &alice, &eff, &ctx).await.unwrap();
+    assert!(file_lock(&store, DB, FS, "draft.docx").is_none());
+    assert_eq!(file_lock(&store, DB, FS, "final.docx").unwrap().logical_path, "final.docx");
+    assert!(delete_file(&store, DB, FS, "final.docx", &bob, &eff, &ctx).await.is_err());
+
+    delete_file(&store, DB, FS, "final.docx", &alice, &eff, &ctx).await.unwrap();
+    assert!(list_file_locks(&store, DB, FS).is_empty());
+    // The path is free for anyone once the file is gone
+    ingest_from_bytes(&store, DB, FS, "final.docx", b"new", None, None, &bob, &eff, &ctx).await.unwrap();
+}
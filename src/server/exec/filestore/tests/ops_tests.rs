@@ -27,6 +27,7 @@ async fn commit_parent_inference_works() {
             description_html: None,
             custom: None,
             chunking: None,
+            scan: None,
         };
         let key = Keys::path(db, fs, &meta.logical_path);
         kv.set(key, KvValue::Json(serde_json::to_value(&meta).unwrap()), None, None);
@@ -54,6 +55,7 @@ async fn commit_parent_inference_works() {
         description_html: None,
         custom: None,
         chunking: None,
+        scan: None,
     };
     let k3 = Keys::path(db, fs, &meta_new.logical_path);
     kv.set(k3, KvValue::Json(serde_json::to_value(&meta_new).unwrap()), None, None);
@@ -65,3 +67,37 @@ async fn commit_parent_inference_works() {
     assert_eq!(c2.parents.len(), 1);
     assert_eq!(c2.parents[0], c1.id);
 }
+
+#[tokio::test]
+async fn file_metadata_set_remove_and_survives_content_changes() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let (db, fs) = ("clarium", "docs");
+    let cfg = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    let eff = EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None);
+    let user = AclUser { id: "u".into(), roles: vec![], ip: None };
+    let ctx = AclContext::default();
+    let set = |k: &str, v: Option<&str>| (k.to_string(), v.map(str::to_string));
+
+    let m0 = ingest_from_bytes(&store, db, fs, "a.txt", b"one", None, None, &user, &eff, &ctx).await.unwrap();
+    let m1 = set_file_metadata(&store, db, fs, "a.txt", &[set("stage", Some("draft")), set("owner", Some("alice"))], &user, &eff, &ctx).await.unwrap();
+    assert_eq!(m1.etag, m0.etag);
+    assert_eq!(m1.version, m0.version + 1);
+    assert_eq!(m1.metadata().into_iter().collect::<Vec<_>>(), vec![("owner".to_string(), "alice".to_string()), ("stage".to_string(), "draft".to_string())]);
+
+    let m2 = update_from_bytes(&store, db, fs, "a.txt", &m1.etag, b"two", None, None, &user, &eff, &ctx).await.unwrap();
+    assert_eq!(m2.metadata().get("stage").map(String::as_str), Some("draft"));
+    let m3 = rename_file(&store, db, fs, "a.txt", "b.txt", &user, &eff, &ctx).await.unwrap();
+    assert_eq!(m3.metadata().len(), 2);
+
+    let m4 = set_file_metadata(&store, db, fs, "b.txt", &[set("stage", None), set("owner", None)], &user, &eff, &ctx).await.unwrap();
+    assert!(m4.custom.is_none());
+
+    let err = set_file_metadata(&store, db, fs, "a.txt", &[set("k", Some("v"))], &user, &eff, &ctx).await.unwrap_err();
+    assert_eq!(err.to_string(), "gone");
+    let err = set_file_metadata(&store, db, fs, "b.txt", &[set(" ", Some("v"))], &user, &eff, &ctx).await.unwrap_err();
+    assert!(err.to_string().starts_with("metadata_key_invalid"), "{}", err);
+    let big = "x".repeat(5000);
+    let err = set_file_metadata(&store, db, fs, "b.txt", &[set("k", Some(big.as_str()))], &user, &eff, &ctx).await.unwrap_err();
+    assert!(err.to_string().starts_with("metadata_value_too_large"), "{}", err);
+}
@@ -0,0 +1,108 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use crate::server::exec::filestore::sec::storage::policies::PolicyRecord;
+use crate::server::exec::filestore::sec::storage::role_memberships::MembershipRecord;
+
+// The policy snapshot is process-wide: everything that installs one lives in a single test,
+// and roles/users are unique to this file so other tests' fallback decisions are unaffected.
+
+fn policy(id: &str, role: &str, effect: &str, actions: &[&str], selector: &str, priority: i32) -> PolicyRecord {
+    PolicyRecord {
+        policy_id: id.into(),
+        role_id: role.into(),
+        actions: actions.iter().map(|a| a.to_string()).collect(),
+        resource_selector: selector.into(),
+        predicate_json: None,
+        effect: effect.into(),
+        priority,
+    }
+}
+
+fn membership(user: &str, role: &str, valid_from: Option<i64>, valid_to: Option<i64>) -> MembershipRecord {
+    MembershipRecord { user_id: user.into(), role_id: role.into(), valid_from, valid_to }
+}
+
+fn enforced() -> EffectiveConfig {
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &FilestoreConfig::default(), None)
+}
+
+fn ctx(db: &str) -> AclContext { AclContext { database: Some(db.into()), ..Default::default() } }
+
+fn user(id: &str) -> AclUser { AclUser { id: id.into(), roles: vec![], ip: None } }
+
+#[tokio::test]
+async fn snapshot_policies_decide_with_deny_precedence_and_memberships() {
+    let now = chrono::Utc::now().timestamp_millis();
+    sec::evaluator::install_snapshot(
+        &[
+            policy("pt_cad_rw", "pt_cad_editors", "allow", &["read", "write", "list"], "res://pt_db/designs/path/cad/**", 10),
+            policy("pt_cad_nodelete", "pt_cad_editors", "deny", &["*"], "res://pt_db/designs/path/cad/locked/**", 20),
+            policy("pt_all_read", "pt_viewers", "allow", &["read"], "res://pt_db/designs/path/**", 0),
+        ],
+        &[
+            membership("pt_dana", "pt_cad_editors", None, None),
+            membership("pt_erin", "pt_cad_editors", Some(now - 60_000), Some(now - 1_000)),
+        ],
+    );
+    let eff = enforced();
+    let ctx = ctx("pt_db");
+    let dana = user("pt_dana");
+
+    let dec = check_acl(&eff, &dana, ACLAction::Write, "cad/part.dwg", None, &ctx, "designs").await;
+    assert!(dec.allow, "{:?}", dec.reason);
+    assert_eq!(dec.reason.as_deref(), Some("policy_allow"));
+    // Deny wins over a matching allow
+    let dec = check_acl(&eff, &dana, ACLAction::Read, "cad/locked/spec.dwg", None, &ctx, "designs").await;
+    assert_eq!((dec.allow, dec.reason.as_deref()), (false, Some("policy_deny")));
+    // Outside the selector, or another database: fall back to the role gates
+    let dec = check_acl(&eff, &dana, ACLAction::Write, "docs/readme.md", None, &ctx, "designs").await;
+    assert_eq!((dec.allow, dec.reason.as_deref()), (false, Some("no_write_policy")));
+    let dec = check_acl(&eff, &dana, ACLAction::Write, "cad/part.dwg", None, &AclContext::default(), "designs").await;
+    assert!(!dec.allow);
+
+    // A lapsed membership grants nothing; roles on the user still apply
+    let dec = check_acl(&eff, &user("pt_erin"), ACLAction::Read, "cad/part.dwg", None, &ctx, "designs").await;
+    assert!(!dec.allow);
+    let viewer = AclUser { id: "pt_frank".into(), roles: vec!["pt_viewers".into()], ip: None };
+    assert!(check_acl(&eff, &viewer, ACLAction::Read, "docs/readme.md", None, &ctx, "designs").await.allow);
+
+    let ex = explain_acl(&eff, &dana, ACLAction::Delete, "cad/locked/spec.dwg", &ctx, "designs").await;
+    assert_eq!(ex.resource, "res://pt_db/designs/path/cad/locked/spec.dwg");
+    assert_eq!(ex.roles, vec!["pt_cad_editors".to_string()]);
+    assert_eq!((ex.allow, ex.policy_id.as_deref()), (false, Some("pt_cad_nodelete")));
+    let ex = explain_acl(&eff, &dana, ACLAction::List, "cad/", &ctx, "designs").await;
+    assert_eq!((ex.allow, ex.policy_id.as_deref()), (true, Some("pt_cad_rw")));
+
+    let df = explain_access_df(&ex).unwrap();
+    assert_eq!(df.height(), 1);
+    assert_eq!(df.column("policy_id").unwrap().str().unwrap().get(0), Some("pt_cad_rw"));
+    assert_eq!(df.column("roles").unwrap().str().unwrap().get(0), Some("pt_cad_editors"));
+}
+
+#[tokio::test]
+async fn explain_reports_disabled_security() {
+    let cfg = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    let eff = EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None);
+    let ex = explain_acl(&eff, &user("pt_nobody"), ACLAction::Delete, "a/b.txt", &ctx("pt_db"), "designs").await;
+    assert!(ex.allow);
+    assert_eq!(ex.reason.as_deref(), Some("security_check_disabled"));
+    assert_eq!(ex.policy_id, None);
+    assert_eq!(ex.resource, "res://pt_db/designs/path/a/b.txt");
+}
+
+#[tokio::test]
+async fn policy_ddl_requires_an_admin_session() {
+    use crate::server::activity::{self, BackendGuard, Frontend};
+    use crate::server::exec::exec_security_policy::{handle_create_policy, handle_drop_policy};
+    let tmp = tempfile::tempdir().unwrap();
+    let store = crate::storage::SharedStore::new(tmp.path()).unwrap();
+    let pid = activity::register(Frontend::Pgwire, "pt_gina", "clarium", "", None);
+    let _guard = BackendGuard(pid);
+    let actions = vec!["*".to_string()];
+    let err = activity::run_statement(Some(pid), "CREATE SECURITY POLICY", handle_create_policy(&store, "pt_gina_all", "pt_gina_role", "allow", &actions, "res://**", 100))
+        .await
+        .unwrap_err();
+    assert!(matches!(err.downcast_ref::<crate::error::AppError>(), Some(crate::error::AppError::Permission { .. })), "{}", err);
+    let err = activity::run_statement(Some(pid), "DROP SECURITY POLICY", handle_drop_policy(&store, "pt_cad_rw", true)).await.unwrap_err();
+    assert!(err.to_string().contains("permission denied"), "{}", err);
+}
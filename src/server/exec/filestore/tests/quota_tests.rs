@@ -0,0 +1,93 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use std::sync::{Arc, Mutex};
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+const DB: &str = "clarium";
+
+fn config(cfg: FilestoreConfig) -> EffectiveConfig {
+    let cfg = FilestoreConfig { security_check_enabled: false, ..cfg };
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+async fn put(store: &SharedStore, fs: &str, eff: &EffectiveConfig, path: &str, body: &[u8]) -> anyhow::Result<FileMeta> {
+    ingest_from_bytes(store, DB, fs, path, body, None, None, &user(), eff, &AclContext::default()).await
+}
+
+#[tokio::test]
+async fn filestore_quota_refuses_growth_but_allows_shrinking() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config(FilestoreConfig { quota_bytes: Some(10), quota_files: Some(2), ..Default::default() });
+
+    put(&store, "q1", &eff, "a.txt", b"12345").await.unwrap();
+    let m = put(&store, "q1", &eff, "b.txt", b"1234").await.unwrap();
+    let err = put(&store, "q1", &eff, "c.txt", b"1").await.unwrap_err().to_string();
+    assert!(err.starts_with("quota_exceeded: filestore 'q1' files"), "{}", err);
+    let err = update_from_bytes(&store, DB, "q1", "b.txt", &m.etag, b"123456", None, None, &user(), &eff, &AclContext::default()).await.unwrap_err().to_string();
+    assert!(err.starts_with("quota_exceeded: filestore 'q1' bytes 11 > 10"), "{}", err);
+    // Replacing a file only counts the difference
+    put(&store, "q1", &eff, "b.txt", b"12345").await.unwrap();
+    assert!(get_file_meta(&store, DB, "q1", "c.txt").unwrap().is_none());
+
+    // Over quota after lowering the limit: shrinking writes and deletes still go through
+    let tight = config(FilestoreConfig { quota_bytes: Some(4), ..Default::default() });
+    let m = get_file_meta(&store, DB, "q1", "a.txt").unwrap().unwrap();
+    update_from_bytes(&store, DB, "q1", "a.txt", &m.etag, b"1", None, None, &user(), &tight, &AclContext::default()).await.unwrap();
+    delete_file(&store, DB, "q1", "b.txt", &user(), &tight, &AclContext::default()).await.unwrap();
+    let usage = quota::total_usage(&store, DB, "q1").unwrap();
+    assert_eq!((usage.bytes, usage.files), (1, 1));
+}
+
+#[tokio::test]
+async fn prefix_quotas_apply_to_writes_and_moves_into_the_prefix() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config(FilestoreConfig {
+        prefix_quotas: Some(vec![PrefixQuota { prefix: "/uploads/".into(), max_bytes: Some(6), max_files: None }]),
+        ..Default::default()
+    });
+
+    put(&store, "q2", &eff, "uploads/a.bin", b"1234").await.unwrap();
+    let err = put(&store, "q2", &eff, "uploads/b.bin", b"123").await.unwrap_err().to_string();
+    assert!(err.starts_with("quota_exceeded: prefix 'uploads' bytes 7 > 6"), "{}", err);
+    // Outside the prefix (including a sibling sharing the name) is unlimited
+    put(&store, "q2", &eff, "uploads2/b.bin", b"123").await.unwrap();
+    put(&store, "q2", &eff, "other/big.bin", &[0u8; 64]).await.unwrap();
+    let err = rename_file(&store, DB, "q2", "uploads2/b.bin", "uploads/b.bin", &user(), &eff, &AclContext::default()).await.unwrap_err().to_string();
+    assert!(err.starts_with("quota_exceeded"), "{}", err);
+    rename_file(&store, DB, "q2", "uploads/a.bin", "archive/a.bin", &user(), &eff, &AclContext::default()).await.unwrap();
+    rename_file(&store, DB, "q2", "uploads2/b.bin", "uploads/b.bin", &user(), &eff, &AclContext::default()).await.unwrap();
+}
+
+#[tokio::test]
+async fn threshold_crossings_record_events_and_notify_listeners() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config(FilestoreConfig { quota_bytes: Some(10), quota_warn_percent: Some(80), ..Default::default() });
+    let seen: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    register_quota_listener(Box::new(move |ev: &QuotaEvent| if ev.filestore == "q3" { sink.lock().unwrap().push(ev.kind.clone()); }));
+
+    put(&store, "q3", &eff, "a.txt", b"1234567").await.unwrap();
+    assert!(list_quota_events(&store, DB, "q3").is_empty());
+    put(&store, "q3", &eff, "b.txt", b"1").await.unwrap();
+    put(&store, "q3", &eff, "c.txt", b"1").await.unwrap();
+    assert!(put(&store, "q3", &eff, "d.txt", b"12").await.is_err());
+    delete_file(&store, DB, "q3", "a.txt", &user(), &eff, &AclContext::default()).await.unwrap();
+
+    let events = list_quota_events(&store, DB, "q3");
+    let kinds: Vec<&str> = events.iter().map(|e| e.kind.as_str()).collect();
+    assert_eq!(kinds, vec!["cleared", "exceeded", "warning"]);
+    assert_eq!((events[2].metric.as_str(), events[2].used, events[2].limit, events[2].path.as_str()), ("bytes", 8, 10, "b.txt"));
+    assert_eq!(events[1].used, 11);
+    assert_eq!(*seen.lock().unwrap(), vec!["warning", "exceeded", "cleared"]);
+
+    let df = show_admin_counts_df(&store, DB, "q3").unwrap();
+    assert_eq!(df.column("live_bytes").unwrap().i64().unwrap().get(0), Some(2));
+    // No registry entry: the quota columns are empty
+    assert_eq!(df.column("quota_bytes").unwrap().i64().unwrap().get(0), None);
+}
@@ -0,0 +1,97 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+const DB: &str = "clarium";
+const FS: &str = "records";
+
+/// Save the config in the registry (GC reads it from there) and return its effective form.
+fn configure(store: &SharedStore, cfg: FilestoreConfig) -> EffectiveConfig {
+    let cfg = FilestoreConfig { security_check_enabled: false, ..cfg };
+    save_filestore_entry(store, DB, FS, &FilestoreRegistryEntry::new(FS, cfg.clone())).unwrap();
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+async fn put(store: &SharedStore, eff: &EffectiveConfig, path: &str, body: &[u8]) {
+    let (u, ctx) = (user(), AclContext::default());
+    match get_file_meta(store, DB, FS, path).unwrap() {
+        Some(m) if !m.deleted => { update_from_bytes(store, DB, FS, path, &m.etag, body, None, None, &u, eff, &ctx).await.unwrap(); }
+        _ => { ingest_from_bytes(store, DB, FS, path, body, None, None, &u, eff, &ctx).await.unwrap(); }
+    }
+}
+
+#[tokio::test]
+async fn minimum_retention_blocks_delete_and_keeps_versions() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = configure(&store, FilestoreConfig { retention_min_seconds: Some(3600), ..Default::default() });
+
+    put(&store, &eff, "ledger.csv", b"v1").await;
+    put(&store, &eff, "ledger.csv", b"v2").await;
+    let err = delete_file(&store, DB, FS, "ledger.csv", &user(), &eff, &AclContext::default()).await.unwrap_err().to_string();
+    assert!(err.starts_with("retention_active: ledger.csv"), "{}", err);
+
+    let versions = list_versions(&store, DB, FS, Some("ledger.csv"));
+    assert_eq!(versions.len(), 1);
+    assert_eq!((versions[0].version, versions[0].reason.as_str()), (1, "replaced"));
+    assert_eq!(version_bytes(&store, DB, FS, &versions[0]).unwrap().as_deref(), Some(&b"v1"[..]));
+
+    // Younger than the minimum: GC keeps the version
+    let rep = gc_apply(&store, DB, FS).unwrap();
+    assert_eq!((rep.versions_deleted, rep.retained), (0, 1));
+    // Retained content is pinned, not an orphan
+    assert_eq!(dedup_stats(&store, DB, FS).orphan_chunks, 0);
+}
+
+#[tokio::test]
+async fn gc_prunes_versions_beyond_the_retained_count() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = configure(&store, FilestoreConfig { retention_versions: Some(1), ..Default::default() });
+
+    for body in [&b"a"[..], b"b", b"c"] { put(&store, &eff, "notes.txt", body).await; }
+    delete_file(&store, DB, FS, "notes.txt", &user(), &eff, &AclContext::default()).await.unwrap();
+    let versions = show_versions_df(&store, DB, FS, None).unwrap();
+    assert_eq!(versions.height(), 3);
+
+    let rep = gc_apply(&store, DB, FS).unwrap();
+    assert_eq!(rep.versions_deleted, 2);
+    let left = list_versions(&store, DB, FS, None);
+    assert_eq!(left.len(), 1);
+    assert_eq!((left[0].version, left[0].reason.as_str()), (3, "deleted"));
+    assert_eq!(version_bytes(&store, DB, FS, &left[0]).unwrap().as_deref(), Some(&b"c"[..]));
+}
+
+#[tokio::test]
+async fn legal_hold_blocks_delete_rename_and_gc_until_cleared() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    // No retention configured: only the hold keeps anything
+    let eff = configure(&store, FilestoreConfig::default());
+    let ctx = AclContext::default();
+
+    put(&store, &eff, "case/evidence.pdf", b"original").await;
+    let hold = set_legal_hold(&store, DB, FS, "case/evidence.pdf", Some("case 42"), &user(), &eff, &ctx).await.unwrap();
+    assert_eq!((hold.set_by.as_str(), hold.reason.as_deref()), ("u", Some("case 42")));
+
+    let err = delete_file(&store, DB, FS, "case/evidence.pdf", &user(), &eff, &ctx).await.unwrap_err().to_string();
+    assert_eq!(err, "legal_hold: case/evidence.pdf");
+    let err = rename_file(&store, DB, FS, "case/evidence.pdf", "tmp/x.pdf", &user(), &eff, &ctx).await.unwrap_err().to_string();
+    assert!(err.starts_with("legal_hold"), "{}", err);
+
+    // Updates go through, but the replaced content is kept while held
+    put(&store, &eff, "case/evidence.pdf", b"edited").await;
+    assert_eq!(gc_apply(&store, DB, FS).unwrap().retained, 1);
+    assert_eq!(list_versions(&store, DB, FS, Some("case/evidence.pdf")).len(), 1);
+
+    let df = show_legal_holds_df(&store, DB, FS).unwrap();
+    assert_eq!(df.column("logical_path").unwrap().str().unwrap().get(0), Some("case/evidence.pdf"));
+
+    assert!(clear_legal_hold(&store, DB, FS, "case/evidence.pdf", &user(), &eff, &ctx).await.unwrap());
+    assert!(list_legal_holds(&store, DB, FS).is_empty());
+    assert_eq!(gc_apply(&store, DB, FS).unwrap().versions_deleted, 1);
+    delete_file(&store, DB, FS, "case/evidence.pdf", &user(), &eff, &ctx).await.unwrap();
+}
@@ -0,0 +1,170 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use crate::server::exec::filestore::sec::scan::{parse_clamd_reply, parse_icap_reply};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use tempfile::tempdir;
+use crate::storage::SharedStore;
+
+const DB: &str = "clarium";
+const FS: &str = "uploads";
+/// Content the test hook flags; nothing else in the test suite writes it
+const MARKER: &[u8] = b"CLARIUM-SCAN-TEST-MARKER";
+
+struct MarkerHook;
+
+impl ScanHook for MarkerHook {
+    fn name(&self) -> &str { "marker" }
+    fn scan(&self, _path: &str, bytes: &[u8]) -> anyhow::Result<ScanVerdict> {
+        let hit = bytes.windows(MARKER.len()).any(|w| w == MARKER);
+        Ok(if hit { ScanVerdict::Flagged("Test.Marker".into()) } else { ScanVerdict::Clean })
+    }
+}
+
+/// Scan hooks are process-wide; register the test hook once.
+fn register_marker_hook() {
+    static ONCE: std::sync::Once = std::sync::Once::new();
+    ONCE.call_once(|| register_scan(Box::new(MarkerHook)));
+}
+
+fn config(cfg: FilestoreConfig) -> EffectiveConfig {
+    let cfg = FilestoreConfig { security_check_enabled: false, ..cfg };
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+async fn ingest(store: &SharedStore, eff: &EffectiveConfig, path: &str, body: &[u8]) -> anyhow::Result<FileMeta> {
+    ingest_from_bytes(store, DB, FS, path, body, None, None, &user(), eff, &AclContext::default()).await
+}
+
+/// A clamd stand-in answering INSTREAM requests: FOUND when the stream holds MARKER.
+fn fake_clamd() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    std::thread::spawn(move || {
+        for conn in listener.incoming() {
+            let Ok(mut s) = conn else { continue };
+            let mut cmd = [0u8; 10];
+            if s.read_exact(&mut cmd).is_err() || &cmd != b"zINSTREAM\0" { continue; }
+            let mut data = Vec::new();
+            loop {
+                let mut len = [0u8; 4];
+                if s.read_exact(&mut len).is_err() { break; }
+                let n = u32::from_be_bytes(len) as usize;
+                if n == 0 { break; }
+                let mut chunk = vec![0u8; n];
+                if s.read_exact(&mut chunk).is_err() { break; }
+                data.extend(chunk);
+            }
+            let hit = data.windows(MARKER.len()).any(|w| w == MARKER);
+            let _ = s.write_all(if hit { b"stream: Test.Clamd FOUND\0" as &[u8] } else { b"stream: OK\0" });
+        }
+    });
+    addr
+}
+
+#[tokio::test]
+async fn reject_mode_refuses_flagged_files_and_records_clean_scans() {
+    register_marker_hook();
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config(FilestoreConfig { scan_mode: Some("reject".into()), ..Default::default() });
+
+    let err = ingest(&store, &eff, "in/bad.bin", &[&b"xx "[..], MARKER].concat()).await.unwrap_err().to_string();
+    assert!(err.starts_with("scan_rejected: in/bad.bin flagged by marker: Test.Marker"), "{}", err);
+    assert!(get_file_meta(&store, DB, FS, "in/bad.bin").unwrap().is_none());
+
+    let meta = ingest(&store, &eff, "in/good.txt", b"hello").await.unwrap();
+    let scan = meta.scan.unwrap();
+    assert_eq!(scan.status, "clean");
+    assert!(scan.scanner.contains("marker"));
+
+    // Without a scan mode nothing is scanned
+    let meta = ingest(&store, &config(FilestoreConfig::default()), "plain.bin", MARKER).await.unwrap();
+    assert!(meta.scan.is_none());
+}
+
+#[tokio::test]
+async fn quarantined_files_are_listed_but_unreadable_until_replaced() {
+    register_marker_hook();
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config(FilestoreConfig { scan_mode: Some("quarantine".into()), ..Default::default() });
+
+    let meta = ingest(&store, &eff, "drop/payload.exe", MARKER).await.unwrap();
+    assert!(meta.is_quarantined());
+    assert_eq!(meta.quarantine_signature(), Some("Test.Marker"));
+
+    let df = show_files_df(&store, DB, FS, None).unwrap();
+    assert_eq!(df.column("scan_status").unwrap().str().unwrap().get(0), Some("quarantined"));
+    assert_eq!(df.column("scan_signature").unwrap().str().unwrap().get(0), Some("Test.Marker"));
+
+    let err = read_file_checked(&store, DB, FS, "drop/payload.exe", &user(), &eff, &AclContext::default()).unwrap_err().to_string();
+    assert!(err.starts_with("quarantined: access denied"), "{}", err);
+
+    // A clean update releases the file
+    update_from_bytes(&store, DB, FS, "drop/payload.exe", &meta.etag, b"fixed", None, None, &user(), &eff, &AclContext::default()).await.unwrap();
+    let (meta, bytes) = read_file_checked(&store, DB, FS, "drop/payload.exe", &user(), &eff, &AclContext::default()).unwrap();
+    assert_eq!(bytes, b"fixed");
+    assert_eq!(meta.scan.map(|s| s.status).as_deref(), Some("clean"));
+}
+
+#[tokio::test]
+async fn clamd_scanner_flags_content_and_failures_respect_fail_open() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let clamd = fake_clamd();
+    let eff_clamd = config(FilestoreConfig { scanner: Some(format!("clamd://{}", clamd)), ..Default::default() });
+    assert_eq!(scan::scan_mode(&eff_clamd), Some("reject"));
+
+    let meta = ingest(&store, &eff_clamd, "ok.txt", &vec![b'a'; 200_000]).await.unwrap();
+    assert!(meta.scan.unwrap().scanner.starts_with("clamd"));
+    let err = ingest(&store, &eff_clamd, "bad.txt", &[&vec![b'a'; 100_000][..], MARKER].concat()).await.unwrap_err().to_string();
+    assert!(err.starts_with("scan_rejected: bad.txt flagged by clamd: Test.Clamd"), "{}", err);
+
+    // Nothing listens on a port just released
+    let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let down = FilestoreConfig { scanner: Some(format!("clamd://{}", closed)), ..Default::default() };
+    let err = ingest(&store, &config(down.clone()), "x.txt", b"x").await.unwrap_err().to_string();
+    assert!(err.starts_with("scan_failed: x.txt: clamd"), "{}", err);
+    let meta = ingest(&store, &config(FilestoreConfig { scan_fail_open: Some(true), ..down }), "x.txt", b"x").await.unwrap();
+    assert_eq!(meta.scan.map(|s| s.status).as_deref(), Some("error"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn command_scanner_uses_exit_status() {
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let script = tmp.path().join("scan.sh");
+    std::fs::write(&script, format!("if grep -q '{}'; then echo Test.Command; exit 1; fi\nexit 0\n", std::str::from_utf8(MARKER).unwrap())).unwrap();
+    let eff = config(FilestoreConfig {
+        scanner: Some(format!("command:sh {}", script.display())),
+        scan_mode: Some("quarantine".into()),
+        ..Default::default()
+    });
+
+    let meta = ingest(&store, &eff, "a.txt", b"fine").await.unwrap();
+    assert_eq!(meta.scan.map(|s| s.status).as_deref(), Some("clean"));
+    let meta = ingest(&store, &eff, "b.txt", MARKER).await.unwrap();
+    assert_eq!(meta.quarantine_signature(), Some("Test.Command"));
+}
+
+#[test]
+fn scanner_replies_are_parsed() {
+    assert_eq!(parse_clamd_reply("stream: OK").unwrap(), ScanVerdict::Clean);
+    assert_eq!(parse_clamd_reply("stream: Win.Test.EICAR_HDB-1 FOUND").unwrap(), ScanVerdict::Flagged("Win.Test.EICAR_HDB-1".into()));
+    assert!(parse_clamd_reply("INSTREAM size limit exceeded. ERROR").is_err());
+
+    let lines = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(parse_icap_reply(&lines(&["ICAP/1.0 204 No Content"])).unwrap(), ScanVerdict::Clean);
+    assert_eq!(
+        parse_icap_reply(&lines(&["ICAP/1.0 200 OK", "X-Infection-Found: Type=0; Resolution=2; Threat=EICAR-Test;"])).unwrap(),
+        ScanVerdict::Flagged("EICAR-Test".into())
+    );
+    assert!(parse_icap_reply(&lines(&["ICAP/1.0 500 Server Error"])).is_err());
+
+    assert!(sec::scan::scanner_from_uri("ftp://x", std::time::Duration::from_secs(1)).is_err());
+    assert_eq!(sec::scan::scanner_from_uri("icap://av:1344/avscan", std::time::Duration::from_secs(1)).unwrap().name(), "icap");
+}
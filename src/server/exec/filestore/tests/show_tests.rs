@@ -11,7 +11,8 @@ fn empty_files_frame_schema() {
     // Avoid importing DataType variants that clash with `String` type
     let cols: Vec<std::string::String> = df.get_columns().iter().map(|s| s.name().to_string()).collect();
     assert_eq!(cols, vec![
-        "logical_path", "size", "etag", "version", "updated_at", "deleted", "content_type"
+        "logical_path", "size", "etag", "version", "updated_at", "deleted", "content_type", "metadata",
+        "scan_status", "scan_signature"
     ]);
     // Basic dtype checks (support either Utf8 or String by Polars version)
     use polars::prelude::DataType;
@@ -23,6 +24,9 @@ fn empty_files_frame_schema() {
     assert!(matches!(dts[4], DataType::Int64));
     assert!(matches!(dts[5], DataType::Boolean));
     assert!(is_str_dtype(&dts[6]));
+    assert!(is_str_dtype(&dts[7]));
+    assert!(is_str_dtype(&dts[8]));
+    assert!(is_str_dtype(&dts[9]));
     assert_eq!(df.height(), 0);
 }
 
@@ -55,6 +59,7 @@ fn paging_boundaries() {
             description_html: None,
             custom: None,
             chunking: None,
+            scan: None,
         };
         let key = Keys::path(db, fs_name, &meta.logical_path);
         kv.set(key, KvValue::Json(serde_json::to_value(&meta).unwrap()), None, None);
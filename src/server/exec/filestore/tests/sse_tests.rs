@@ -0,0 +1,124 @@
+use super::*;
+use crate::server::exec::filestore::*;
+use crate::storage::encryption::EncryptionSpec;
+use crate::storage::{KvValue, SharedStore};
+use base64::Engine;
+use tempfile::tempdir;
+
+const DB: &str = "clarium";
+const FS: &str = "vault";
+
+/// Same key set as the storage encryption tests, so running them in parallel is harmless.
+fn install_test_keys() {
+    let b64 = |b: u8| base64::engine::general_purpose::STANDARD.encode([b; 32]);
+    std::env::set_var("CLARIUM_ENCRYPTION_KEYS", format!("k1:{},k2:{}", b64(1), b64(2)));
+    crate::storage::encryption::reload_keys().unwrap();
+}
+
+fn config(encryption: Option<&str>) -> EffectiveConfig {
+    let cfg = FilestoreConfig {
+        security_check_enabled: false,
+        chunking_threshold_bytes: Some(64 * 1024),
+        encryption: encryption.map(|k| EncryptionSpec::new(Some(k.to_string()))),
+        ..Default::default()
+    };
+    EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None)
+}
+
+fn user() -> AclUser { AclUser { id: "u".into(), roles: vec![], ip: None } }
+
+async fn put(store: &SharedStore, eff: &EffectiveConfig, path: &str, body: &[u8]) -> FileMeta {
+    ingest_from_bytes(store, DB, FS, path, body, None, None, &user(), eff, &AclContext::default()).await.unwrap()
+}
+
+fn read(store: &SharedStore, path: &str) -> Vec<u8> {
+    let m = get_file_meta(store, DB, FS, path).unwrap().unwrap();
+    get_file_bytes(store, DB, FS, &m).unwrap().unwrap()
+}
+
+/// Stored payloads: the single blob of `meta`, or its chunks.
+fn raw_payloads(store: &SharedStore, meta: &FileMeta) -> Vec<Vec<u8>> {
+    let kv = store.kv_store(DB, FS);
+    match &meta.chunking {
+        Some(ch) => ch.chunks.iter().map(|c| match kv.get(&Keys::chunk_oid(DB, FS, &c.oid)) {
+            Some(KvValue::Bytes(b)) => b,
+            other => panic!("unexpected chunk entry {:?}", other),
+        }).collect(),
+        None => vec![kv.get_bytes(&Keys::blob(DB, FS, &uuid::Uuid::parse_str(&meta.id).unwrap())).unwrap()],
+    }
+}
+
+fn big(seed: u8) -> Vec<u8> { (0..300_000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8 ^ seed).collect() }
+
+#[tokio::test]
+async fn content_is_sealed_at_rest_and_read_transparently() {
+    install_test_keys();
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config(Some("k1"));
+
+    let small = put(&store, &eff, "notes.txt", b"top secret").await;
+    let large = put(&store, &eff, "dump.bin", &big(7)).await;
+    assert!(small.chunking.is_none() && large.chunking.is_some());
+    for payload in raw_payloads(&store, &small).into_iter().chain(raw_payloads(&store, &large)) {
+        assert!(sse::is_sealed(&payload));
+        assert!(!payload.windows(10).any(|w| w == b"top secret"));
+    }
+    assert_eq!(read(&store, "notes.txt"), b"top secret");
+    assert_eq!(read(&store, "dump.bin"), big(7));
+
+    // Chunk ids are over the plaintext: a copy adds no chunks
+    let chunks = dedup_stats(&store, DB, FS).chunks;
+    put(&store, &eff, "copy.bin", &big(7)).await;
+    assert_eq!(dedup_stats(&store, DB, FS).chunks, chunks);
+
+    let rec = data_key_record(&store, DB, FS).unwrap();
+    assert_eq!(rec.master_key, "k1");
+    assert!(rec.rotated_at.is_none());
+}
+
+#[tokio::test]
+async fn rotation_rewraps_the_data_key_without_rewriting_content() {
+    install_test_keys();
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+    let eff = config(Some("k1"));
+    let err = rotate_key(&store, DB, FS, Some("k2"), &eff).unwrap_err().to_string();
+    assert!(err.starts_with("no data key"), "{}", err);
+
+    let meta = put(&store, &eff, "a.bin", &big(1)).await;
+    let before = raw_payloads(&store, &meta);
+    let id = data_key_record(&store, DB, FS).unwrap().id;
+
+    let out = rotate_key(&store, DB, FS, Some("k2"), &eff).unwrap();
+    assert_eq!((out.data_key.as_str(), out.old_master_key.as_str(), out.new_master_key.as_str()), (id.as_str(), "k1", "k2"));
+    let rec = data_key_record(&store, DB, FS).unwrap();
+    let wrapped = base64::engine::general_purpose::STANDARD.decode(&rec.wrapped).unwrap();
+    assert_eq!(crate::storage::encryption::key_id_of(&wrapped).as_deref(), Some("k2"));
+    assert!(rec.rotated_at.is_some());
+
+    assert_eq!(raw_payloads(&store, &meta), before);
+    assert_eq!(read(&store, "a.bin"), big(1));
+    assert!(rotate_key(&store, DB, FS, Some("missing"), &eff).is_err());
+}
+
+#[tokio::test]
+async fn plaintext_written_before_encryption_stays_readable() {
+    install_test_keys();
+    let tmp = tempdir().unwrap();
+    let store = SharedStore::new(tmp.path()).unwrap();
+
+    let plain = put(&store, &config(None), "old.txt", b"before").await;
+    assert!(!sse::is_sealed(&raw_payloads(&store, &plain)[0]));
+    let df = show_encryption_df(&store, DB, FS, &config(None)).unwrap();
+    assert_eq!(df.column("encrypted").unwrap().bool().unwrap().get(0), Some(false));
+    assert_eq!(df.column("data_key").unwrap().str().unwrap().get(0), None);
+
+    let eff = config(Some("k2"));
+    put(&store, &eff, "new.txt", b"after").await;
+    assert_eq!(read(&store, "old.txt"), b"before");
+    assert_eq!(read(&store, "new.txt"), b"after");
+    let df = show_encryption_df(&store, DB, FS, &eff).unwrap();
+    assert_eq!(df.column("configured_master_key").unwrap().str().unwrap().get(0), Some("k2"));
+    assert_eq!(df.column("master_key").unwrap().str().unwrap().get(0), Some("k2"));
+}
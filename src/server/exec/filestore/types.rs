@@ -1,6 +1,8 @@
 //! Core FILESTORE data contracts (metadata objects persisted in KV)
 //! Keep this module purely about types/serde and light helpers.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -32,10 +34,94 @@ pub struct FileMeta {
     pub deleted: bool,
     #[serde(default)]
     pub description_html: Option<String>,
+    /// User-defined key/value metadata (`SET FILE METADATA`), a JSON object of strings
     #[serde(default)]
     pub custom: Option<serde_json::Value>,
     #[serde(default)]
     pub chunking: Option<Chunking>,
+    /// Result of the content scan on the last write; None when no scanner is configured
+    #[serde(default)]
+    pub scan: Option<ScanInfo>,
+}
+
+/// Outcome of scanning a file's content (see scan.rs).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScanInfo {
+    /// "clean" | "quarantined" | "error" (scanner failed and the filestore is fail-open)
+    pub status: String,
+    pub scanner: String,
+    /// What the scanner reported: the signature of a flagged file, or the failure
+    #[serde(default)]
+    pub signature: Option<String>,
+    pub scanned_at: i64,
+}
+
+impl FileMeta {
+    /// Whether the last write was flagged by a content scan and quarantined.
+    pub fn is_quarantined(&self) -> bool {
+        self.scan.as_ref().map(|s| s.status == "quarantined").unwrap_or(false)
+    }
+
+    /// The scan signature of a quarantined file.
+    pub fn quarantine_signature(&self) -> Option<&str> {
+        self.scan.as_ref().filter(|s| s.status == "quarantined").map(|s| s.signature.as_deref().unwrap_or("flagged"))
+    }
+
+    /// Entries of `custom`, sorted by key; non-string values as JSON text.
+    pub fn metadata(&self) -> BTreeMap<String, String> {
+        match &self.custom {
+            Some(serde_json::Value::Object(m)) => m.iter()
+                .map(|(k, v)| (k.clone(), v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string())))
+                .collect(),
+            _ => BTreeMap::new(),
+        }
+    }
+}
+
+/// An earlier version of a file kept by retention (see retention.rs), its content pinned in the
+/// chunk store.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileVersion {
+    pub file_id: String,
+    pub logical_path: String,
+    pub version: u64,
+    pub size: u64,
+    pub etag: String,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// When this version was written
+    pub written_at: i64,
+    /// When an update replaced it, or a delete removed it
+    pub retired_at: i64,
+    /// "replaced" | "deleted"
+    pub reason: String,
+    pub content: Chunking,
+}
+
+/// Advisory lock on a file (LOCK FILE): only its owner may update, rename or delete the file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileLock {
+    pub logical_path: String,
+    pub owner: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    pub locked_at: i64,
+    /// The lock lapses at this time; None holds until unlocked
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+impl FileLock {
+    pub fn is_active(&self, now: i64) -> bool { self.expires_at.is_none_or(|t| now < t) }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LegalHold {
+    pub logical_path: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub set_by: String,
+    pub set_at: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -44,6 +130,10 @@ pub struct TreeEntry {
     pub file_id: String,
     pub etag: String,
     pub size: u64,
+    /// Content pinned in the chunk store when the tree was taken (branch snapshots). Without it
+    /// the entry refers to the live file by id, whose content moves on with updates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Chunking>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
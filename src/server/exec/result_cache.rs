@@ -0,0 +1,215 @@
+//! Opt-in result cache for repeated SELECTs, e.g. dashboards polling the same queries.
+//!
+//! Sessions turn it on with `SET enable_result_cache = on`. A result is keyed on the
+//! normalized statement text (pgwire parameters are already bound into it) and the
+//! session state that changes its meaning: database, schema, user and vector search
+//! settings. Each entry also records the data version of every table the statement
+//! read (see `storage::versions`). A lookup whose tables have been written to since
+//! misses and drops the entry; DDL and other catalog changes clear the whole cache.
+//!
+//! Not cached: statements reading system tables, table functions, KV stores or volatile
+//! functions (`now()`, `random()`, ...), `SELECT ... INTO`, and results larger than a
+//! quarter of the cache.
+//!
+//! - `limits.result_cache_mb`: total size of cached results; 0 disables the cache
+//! - `limits.result_cache_ttl_secs`: lifetime of an entry
+//!
+//! `SHOW CACHE STATS` reports entries, size, hits, misses, invalidations and evictions.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use polars::prelude::*;
+use serde_json::Value;
+
+use crate::server::query::Command;
+use crate::storage::SharedStore;
+
+/// Functions whose result changes between calls with the same arguments.
+const VOLATILE_FUNCTIONS: &[&str] = &[
+    "now", "random", "setseed", "clock_timestamp", "statement_timestamp", "transaction_timestamp",
+    "current_timestamp", "current_date", "current_time", "localtimestamp", "localtime",
+    "timeofday", "age", "gen_random_uuid", "uuid",
+];
+
+struct Entry {
+    value: Arc<Value>,
+    /// Table -> data version the result was computed from
+    tables: Vec<(String, u64)>,
+    bytes: usize,
+    created: Instant,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<String, Entry>,
+    bytes: usize,
+}
+
+static CACHE: Lazy<Mutex<Cache>> = Lazy::new(|| Mutex::new(Cache::default()));
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+static INVALIDATIONS: AtomicU64 = AtomicU64::new(0);
+static EVICTIONS: AtomicU64 = AtomicU64::new(0);
+
+fn capacity() -> usize { (crate::config::current().limits.result_cache_mb as usize).saturating_mul(1024 * 1024) }
+
+fn ttl() -> Duration { Duration::from_secs(crate::config::current().limits.result_cache_ttl_secs) }
+
+/// Collapse whitespace outside quotes and drop a trailing `;`.
+fn normalize_sql(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut quote: Option<char> = None;
+    let mut pending_space = false;
+    for ch in text.trim().trim_end_matches(';').trim_end().chars() {
+        match quote {
+            Some(q) => { out.push(ch); if ch == q { quote = None; } }
+            None if ch.is_whitespace() => pending_space = true,
+            None => {
+                if pending_space && !out.is_empty() { out.push(' '); }
+                pending_space = false;
+                if ch == '\'' || ch == '"' { quote = Some(ch); }
+                out.push(ch);
+            }
+        }
+    }
+    out
+}
+
+fn has_volatile_function(text: &str) -> bool {
+    text.to_ascii_lowercase()
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .any(|tok| VOLATILE_FUNCTIONS.contains(&tok))
+}
+
+/// Cache key for `cmd`, or None when this statement must not be cached.
+pub fn cache_key(cmd: &Command, text: &str) -> Option<String> {
+    if !crate::system::result_cache_enabled() || capacity() == 0 { return None; }
+    match cmd {
+        Command::Select(q) if q.into_table.is_none() => {}
+        Command::SelectUnion { .. } => {}
+        _ => return None,
+    }
+    if has_volatile_function(text) { return None; }
+    Some(format!(
+        "{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}",
+        crate::system::get_current_database(),
+        crate::system::get_current_schema(),
+        crate::server::activity::current_user().unwrap_or_default(),
+        crate::system::get_vector_ef_search(),
+        crate::system::get_vector_preselect_alpha(),
+        crate::system::get_vector_ivf_nprobe(),
+        crate::system::get_vector_ivf_rerank(),
+        crate::system::get_vector_prefilter_percent(),
+        crate::system::get_strict_projection(),
+        normalize_sql(text),
+    ))
+}
+
+/// Whether `cmd` changes the catalog in ways table data versions do not capture
+/// (views, scripts, schemas, drops, ...), so every cached result must go.
+pub fn clears_cache(cmd: &Command) -> bool {
+    if crate::server::replication::is_read_only_command(cmd) { return false; }
+    !matches!(cmd,
+        Command::Insert { .. } | Command::InsertSelect { .. } | Command::CopyFrom { .. }
+        | Command::Update { .. } | Command::DeleteRows { .. }
+        | Command::WriteKey { .. } | Command::DropKey { .. } | Command::RenameKey { .. } | Command::KeyOp { .. } | Command::KvBatch { .. }
+        | Command::Set { .. } | Command::Reset { .. })
+}
+
+/// Drop every cached result.
+pub fn clear() {
+    let mut cache = CACHE.lock();
+    if cache.entries.is_empty() { return; }
+    INVALIDATIONS.fetch_add(cache.entries.len() as u64, Ordering::Relaxed);
+    cache.entries.clear();
+    cache.bytes = 0;
+}
+
+fn remove(cache: &mut Cache, key: &str) {
+    if let Some(e) = cache.entries.remove(key) { cache.bytes -= e.bytes; }
+}
+
+fn lookup(store: &SharedStore, key: &str) -> Option<Value> {
+    let (value, tables) = {
+        let mut cache = CACHE.lock();
+        let e = cache.entries.get(key)?;
+        if e.created.elapsed() > ttl() {
+            remove(&mut cache, key);
+            INVALIDATIONS.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        (e.value.clone(), e.tables.clone())
+    };
+    let fresh = {
+        let guard = store.0.lock();
+        tables.iter().all(|(t, v)| guard.data_version(t) == *v)
+    };
+    let mut cache = CACHE.lock();
+    if !fresh {
+        remove(&mut cache, key);
+        INVALIDATIONS.fetch_add(1, Ordering::Relaxed);
+        crate::tprintln!("[result_cache] invalidated entry: a table was written");
+        return None;
+    }
+    if let Some(e) = cache.entries.get_mut(key) { e.last_used = Instant::now(); }
+    Some((*value).clone())
+}
+
+fn insert(key: String, value: &Value, tables: Vec<(String, u64)>) {
+    let cap = capacity();
+    let bytes = key.len() + value.to_string().len();
+    if bytes > cap / 4 { return; }
+    let mut cache = CACHE.lock();
+    remove(&mut cache, &key);
+    while cache.bytes + bytes > cap {
+        let Some(oldest) = cache.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone()) else { break };
+        remove(&mut cache, &oldest);
+        EVICTIONS.fetch_add(1, Ordering::Relaxed);
+    }
+    let now = Instant::now();
+    cache.bytes += bytes;
+    cache.entries.insert(key, Entry { value: Arc::new(value.clone()), tables, bytes, created: now, last_used: now });
+}
+
+/// Answer the statement keyed by `key` from the cache, or run `fut` and cache its result
+/// when every source it read has a data version.
+pub async fn cached<F>(store: &SharedStore, key: String, fut: F) -> Result<Value>
+where
+    F: Future<Output = Result<Value>>,
+{
+    if let Some(v) = lookup(store, &key) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(v);
+    }
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let (out, reads) = crate::storage::versions::track_reads(fut).await;
+    let value = out?;
+    if !reads.uncacheable && !reads.tables.is_empty() {
+        insert(key, &value, reads.tables.into_iter().collect());
+    }
+    Ok(value)
+}
+
+/// Single-row `SHOW CACHE STATS` result.
+pub fn stats_df() -> Result<DataFrame> {
+    let (entries, bytes) = { let c = CACHE.lock(); (c.entries.len() as i64, c.bytes as i64) };
+    Ok(DataFrame::new(vec![
+        Series::new("enabled".into(), vec![crate::system::result_cache_enabled()]).into(),
+        Series::new("entries".into(), vec![entries]).into(),
+        Series::new("bytes".into(), vec![bytes]).into(),
+        Series::new("capacity_bytes".into(), vec![capacity() as i64]).into(),
+        Series::new("ttl_secs".into(), vec![ttl().as_secs() as i64]).into(),
+        Series::new("hits".into(), vec![HITS.load(Ordering::Relaxed) as i64]).into(),
+        Series::new("misses".into(), vec![MISSES.load(Ordering::Relaxed) as i64]).into(),
+        Series::new("invalidations".into(), vec![INVALIDATIONS.load(Ordering::Relaxed) as i64]).into(),
+        Series::new("evictions".into(), vec![EVICTIONS.load(Ordering::Relaxed) as i64]).into(),
+    ])?)
+}
@@ -0,0 +1,122 @@
+//! Admission control for analytical statements.
+//!
+//! SELECT, SLICE, CALCULATE, MATCH and EXPLAIN take a slot before they run. At most
+//! `limits.max_concurrent_queries` hold a slot at once, and at most
+//! `limits.max_queries_per_user` for one user (0 = unlimited). Excess statements queue
+//! for up to `limits.queue_timeout_ms` and then fail. Writes (INSERT, COPY, `/write`
+//! ingest, DDL) never queue, so a burst of heavy queries does not hold up ingest.
+//!
+//! Running and queued statements per user are exposed as `pg_catalog.clarium_scheduler`.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+use crate::config::LimitSettings;
+use crate::server::query::Command;
+
+/// Scheduler counters for one user.
+#[derive(Debug, Clone, Default)]
+pub struct UserSlots {
+    pub running: u64,
+    /// Current queue depth
+    pub queued: u64,
+    pub admitted_total: u64,
+    pub timed_out_total: u64,
+}
+
+#[derive(Default)]
+struct Scheduler {
+    users: Mutex<BTreeMap<String, UserSlots>>,
+    released: Notify,
+}
+
+static SCHEDULER: Lazy<Scheduler> = Lazy::new(Scheduler::default);
+
+/// Statements that go through admission control.
+pub fn is_analytical(cmd: &Command) -> bool {
+    matches!(cmd,
+        Command::Select(_) | Command::SelectUnion { .. } | Command::Slice(_)
+        | Command::Calculate { .. } | Command::MatchRewrite { .. } | Command::Explain { .. })
+}
+
+/// Counters per user, ordered by user name.
+pub fn snapshot() -> Vec<(String, UserSlots)> {
+    SCHEDULER.users.lock().iter().map(|(u, s)| (u.clone(), s.clone())).collect()
+}
+
+/// Statements waiting for a slot across all users.
+pub fn queue_depth() -> u64 { SCHEDULER.users.lock().values().map(|s| s.queued).sum() }
+
+/// A running statement's slot; released on drop.
+pub struct Slot { user: String }
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(s) = SCHEDULER.users.lock().get_mut(&self.user) { s.running = s.running.saturating_sub(1); }
+        SCHEDULER.released.notify_waiters();
+    }
+}
+
+/// Counts a statement in its user's queue until dropped (admitted, timed out or cancelled).
+struct Waiting<'a> { user: &'a str }
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(s) = SCHEDULER.users.lock().get_mut(self.user) { s.queued = s.queued.saturating_sub(1); }
+    }
+}
+
+fn try_take(users: &mut BTreeMap<String, UserSlots>, user: &str, max_total: u64, max_user: u64) -> bool {
+    let total: u64 = users.values().map(|s| s.running).sum();
+    if max_total > 0 && total >= max_total { return false; }
+    let slots = users.entry(user.to_string()).or_default();
+    if max_user > 0 && slots.running >= max_user { return false; }
+    slots.running += 1;
+    slots.admitted_total += 1;
+    true
+}
+
+/// Take a slot for the statement running on this thread, waiting up to `limits.queue_timeout_ms`.
+pub async fn acquire() -> Result<Slot> {
+    let user = crate::server::activity::current_user().unwrap_or_default();
+    acquire_as(user, &crate::config::current().limits).await
+}
+
+pub(crate) async fn acquire_as(user: String, limits: &LimitSettings) -> Result<Slot> {
+    let wait = Duration::from_millis(limits.queue_timeout_ms);
+    let deadline = Instant::now() + wait;
+    let mut waiting: Option<Waiting> = None;
+    loop {
+        let notified = SCHEDULER.released.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        {
+            let mut users = SCHEDULER.users.lock();
+            if try_take(&mut users, &user, limits.max_concurrent_queries, limits.max_queries_per_user) {
+                drop(users);
+                drop(waiting);
+                return Ok(Slot { user });
+            }
+            if waiting.is_none() {
+                users.entry(user.clone()).or_default().queued += 1;
+                waiting = Some(Waiting { user: &user });
+                crate::tprintln!("[scheduler] queued statement for user='{}'", user);
+            }
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            drop(waiting);
+            if let Some(s) = SCHEDULER.users.lock().get_mut(&user) { s.timed_out_total += 1; }
+            anyhow::bail!(
+                "too many concurrent queries: no slot free after waiting {} ms (limits: {} total, {} per user; 0 = unlimited)",
+                wait.as_millis(), limits.max_concurrent_queries, limits.max_queries_per_user
+            );
+        }
+        let _ = tokio::time::timeout(deadline - now, notified).await;
+    }
+}
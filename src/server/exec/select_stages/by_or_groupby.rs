@@ -19,8 +19,28 @@ use crate::server::query::query_common::ArithTerm as AT;
 use crate::server::exec::exec_common::build_arith_expr;
 use crate::scripts::get_script_registry;
 use crate::server::exec::select_stages::having::apply_having_with_validation;
+use crate::server::exec::select_stages::counters::{counter_agg_expr, counter_value};
 use crate::storage::SharedStore;
 
+// Bucket sizes BY AUTO rounds up to, so buckets line up with readable boundaries
+const AUTO_WINDOWS_MS: &[i64] = &[
+    1, 2, 5, 10, 20, 50, 100, 200, 500,
+    1_000, 2_000, 5_000, 10_000, 15_000, 30_000,
+    60_000, 120_000, 300_000, 600_000, 900_000, 1_800_000,
+    3_600_000, 7_200_000, 10_800_000, 21_600_000, 43_200_000,
+    86_400_000, 172_800_000, 604_800_000, 2_592_000_000,
+];
+
+/// Smallest readable bucket size that splits the `_time` range into at most `points` buckets.
+pub fn auto_window_ms(t: &Int64Chunked, points: usize) -> i64 {
+    let span = match (t.min(), t.max()) {
+        (Some(lo), Some(hi)) => hi - lo + 1,
+        _ => return AUTO_WINDOWS_MS[0],
+    };
+    let target = (span + points as i64 - 1) / points.max(1) as i64;
+    AUTO_WINDOWS_MS.iter().copied().find(|w| *w >= target).unwrap_or(target.max(1))
+}
+
 pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result<DataFrame> {
     debug!("[BY_OR_GROUPBY] Entering: by_window={:?}, group_by_cols={:?}, by_slices={:?}", q.by_window_ms.is_some(), q.group_by_cols.as_ref().map(|v| v.len()), q.by_slices.is_some());
     // If no BY/GROUP BY/SLICE requested, passthrough
@@ -101,6 +121,7 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
                                     AE::Cast { expr, ty } => {
                                         AE::Cast { expr: Box::new(qualify(df, ctx, expr)?), ty: ty.clone() }
                                     }
+                                    AE::Collate { expr, collation } => AE::Collate { expr: Box::new(qualify(df, ctx, expr)?), collation: collation.clone() },
                                     AE::BinOp { left, op, right } => AE::BinOp { left: Box::new(qualify(df, ctx, left)?), op: op.clone(), right: Box::new(qualify(df, ctx, right)?) },
                                     AE::Concat(parts) => AE::Concat(parts.iter().map(|p| qualify(df, ctx, p)).collect::<anyhow::Result<Vec<_>>>()?),
                                     AE::Call { name, args } => AE::Call { name: name.clone(), args: args.iter().map(|p| qualify(df, ctx, p)).collect::<anyhow::Result<Vec<_>>>()? },
@@ -146,6 +167,11 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
                         AggFunc::Gradient => format!("GRADIENT({})", item.column),
                         AggFunc::Quantile(qp) => format!("_{}_QUANTILE({})", qp, item.column),
                         AggFunc::ArrayAgg => format!("ARRAY_AGG({})", item.column),
+                        AggFunc::Ema(_) => format!("EMA({})", item.column),
+                        AggFunc::Wma => format!("WMA({})", item.column),
+                        AggFunc::Rate => format!("RATE({})", item.column),
+                        AggFunc::Increase => format!("INCREASE({})", item.column),
+                        AggFunc::Irate => format!("IRATE({})", item.column),
                     };
                     let vec = agg_columns.entry(out_name.clone()).or_default();
                     // Compute aggregation value (as f64 where applicable)
@@ -214,6 +240,14 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
                             // For now, store as NaN as placeholder (will handle string output separately)
                             None
                         }
+                        AggFunc::Rate | AggFunc::Increase | AggFunc::Irate => {
+                            let times = part.column(&time_col)?.i64()?;
+                            f64ca_opt.as_ref().and_then(|ca| {
+                                let pts: Vec<(i64, f64)> = times.into_iter().zip(ca.into_iter()).filter_map(|(t, v)| Some((t?, v?))).collect();
+                                counter_value(func, &pts)
+                            })
+                        }
+                        AggFunc::Ema(_) | AggFunc::Wma => anyhow::bail!("EMA and WMA are only supported with ROLLING BY"),
                     };
                     vec.push(val);
                 }
@@ -255,6 +289,7 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
                 AE::Cast { expr, ty } => {
                     AE::Cast { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), ty: ty.clone() }
                 }
+                AE::Collate { expr, collation } => AE::Collate { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), collation: collation.clone() },
                 AE::BinOp { left, op, right } => AE::BinOp { left: Box::new(qualify_arith_ctx(df, ctx, left, clause)?), op: op.clone(), right: Box::new(qualify_arith_ctx(df, ctx, right, clause)?) },
                 AE::Concat(parts) => AE::Concat(parts.iter().map(|p| qualify_arith_ctx(df, ctx, p, clause)).collect::<anyhow::Result<Vec<_>>>()?),
                 AE::Call { name, args } => AE::Call { name: name.clone(), args: args.iter().map(|p| qualify_arith_ctx(df, ctx, p, clause)).collect::<anyhow::Result<Vec<_>>>()? },
@@ -316,13 +351,28 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
         // bucket column using resolved _time
         let time_col = resolve_col_name_ctx(&df, ctx, "_time").unwrap_or_else(|_| "_time".to_string());
         let t = df.column(&time_col)?.i64()?;
+        let win = match q.by_auto_points {
+            Some(points) => auto_window_ms(t, points),
+            None => win,
+        };
         let buckets: Vec<i64> = t.into_iter().map(|opt| opt.map(|v| (v / win) * win).unwrap_or_default()).collect();
+        let row_buckets = buckets.clone();
         let bucket_s = Series::new("_bucket".into(), buckets);
         let df = df.hstack(&[bucket_s.into()])?;
 
         // build groupby via lazy API for compatibility
         let mut agg_cols: Vec<Expr> = Vec::new();
+        // Aggregate UDF items: (output name, function, qualified args), evaluated per bucket below
+        let mut udf_aggs: Vec<(String, String, Vec<ArithExpr>)> = Vec::new();
         for item in &q.select {
+            if let (None, Some(ArithExpr::Call { name, args })) = (&item.func, &item.expr) {
+                let is_agg = get_script_registry().and_then(|r| r.get_meta(name)).is_some_and(|m| matches!(m.kind, crate::scripts::ScriptKind::Aggregate));
+                if is_agg {
+                    let qargs = args.iter().map(|a| qualify_arith_ctx(&df, ctx, a, "BY")).collect::<anyhow::Result<Vec<_>>>()?;
+                    udf_aggs.push((item.alias.clone().unwrap_or_else(|| name.clone()), name.clone(), qargs));
+                    continue;
+                }
+            }
             if let Some(func) = &item.func {
                 let base = if let Some(ex) = &item.expr { build_arith_expr(&qualify_arith_ctx(&df, ctx, ex, "BY")?, ctx) } else if matches!(func, AggFunc::Count) && item.column == "*" { lit(1) } else {
                     let qn = resolve_col_name_ctx(&df, ctx, &item.column).unwrap_or_else(|_| item.column.clone());
@@ -364,6 +414,11 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
                         // Collect values into PostgreSQL array format: {val1,val2,val3}
                         base.cast(DataType::String).implode().alias(format!("ARRAY_AGG({})", item.column))
                     }
+                    AggFunc::Rate | AggFunc::Increase | AggFunc::Irate => {
+                        let fname = match func { AggFunc::Rate => "RATE", AggFunc::Increase => "INCREASE", _ => "IRATE" };
+                        counter_agg_expr(func, base, col(&time_col)).alias(format!("{}({})", fname, item.column))
+                    }
+                    AggFunc::Ema(_) | AggFunc::Wma => anyhow::bail!("EMA and WMA are only supported with ROLLING BY"),
                 };
                 if let Some(a) = &item.alias { e = e.alias(a); }
                 agg_cols.push(e);
@@ -385,10 +440,14 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
             }
         }
         let mut out = df
+            .clone()
             .lazy()
             .group_by([col("_bucket")])
             .agg(agg_cols)
             .collect()?;
+        if !udf_aggs.is_empty() {
+            out = append_by_udf_aggregates(&df, out, &row_buckets, &udf_aggs, ctx)?;
+        }
         // Rename bucket key to _time
         if out.get_column_names().iter().any(|c| c.as_str()=="_bucket") {
             let s = out.column("_bucket")?.clone();
@@ -502,6 +561,7 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
                 AE::Cast { expr, ty } => {
                     AE::Cast { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), ty: ty.clone() }
                 }
+                AE::Collate { expr, collation } => AE::Collate { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), collation: collation.clone() },
                 AE::BinOp { left, op, right } => AE::BinOp { left: Box::new(qualify_arith_ctx(df, ctx, left, clause)?), op: op.clone(), right: Box::new(qualify_arith_ctx(df, ctx, right, clause)?) },
                 AE::Concat(parts) => AE::Concat(parts.iter().map(|p| qualify_arith_ctx(df, ctx, p, clause)).collect::<anyhow::Result<Vec<_>>>()?),
                 AE::Call { name, args } => AE::Call { name: name.clone(), args: args.iter().map(|p| qualify_arith_ctx(df, ctx, p, clause)).collect::<anyhow::Result<Vec<_>>>()? },
@@ -606,6 +666,11 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
                         // Collect values into PostgreSQL array format
                         base.cast(DataType::String).implode().alias(format!("ARRAY_AGG({})", item.column))
                     }
+                    AggFunc::Rate | AggFunc::Increase | AggFunc::Irate => {
+                        let fname = match func { AggFunc::Rate => "RATE", AggFunc::Increase => "INCREASE", _ => "IRATE" };
+                        counter_agg_expr(func, base, col(&time_col)).alias(format!("{}({})", fname, item.column))
+                    }
+                    AggFunc::Ema(_) | AggFunc::Wma => anyhow::bail!("EMA and WMA are only supported with ROLLING BY"),
                 };
                 if let Some(a) = &item.alias { e = e.alias(a); }
                 agg_cols.push(e);
@@ -1029,3 +1094,44 @@ pub fn by_or_groupby(store: &SharedStore, mut df: DataFrame, q: &Query, ctx: &mu
     ctx.register_df_columns_for_stage(SelectStage::ByOrGroupBy, &df);
     Ok(df)
 }
+
+/// Evaluate aggregate UDFs of a BY window over the rows of each `_bucket` in `out`. Single
+/// returns become a column named after the alias (or function), multiple returns `<name>_<i>`,
+/// typed by the declared `returns` and inferred otherwise.
+fn append_by_udf_aggregates(df: &DataFrame, mut out: DataFrame, row_buckets: &[i64], udf_aggs: &[(String, String, Vec<ArithExpr>)], ctx: &mut DataContext) -> Result<DataFrame> {
+    let mut bucket_rows: std::collections::HashMap<i64, Vec<usize>> = std::collections::HashMap::new();
+    for (i, b) in row_buckets.iter().enumerate() { bucket_rows.entry(*b).or_default().push(i); }
+    let out_buckets: Vec<Option<i64>> = out.column("_bucket")?.i64()?.into_iter().collect();
+    let reg = get_script_registry().and_then(|r| r.snapshot().ok()).ok_or_else(|| anyhow::anyhow!("Lua registry not initialized"))?;
+    let null_on_err = crate::system::get_null_on_error();
+    let mut appended: Vec<String> = Vec::new();
+    for (base_name, func_name, args) in udf_aggs {
+        let exprs: Vec<Expr> = args.iter().enumerate().map(|(ai, a)| build_arith_expr(a, ctx).alias(format!("{}{}", ARG_PREFIX, ai))).collect();
+        let arg_df = df.clone().lazy().select(exprs).collect()?;
+        let returns = reg.get_meta(func_name).map(|m| m.returns).unwrap_or_default();
+        let mut results: Vec<serde_json::Value> = Vec::with_capacity(out.height());
+        for b in &out_buckets {
+            let rows = b.and_then(|b| bucket_rows.get(&b)).map(|r| r.as_slice()).unwrap_or(&[]);
+            let jargs: Vec<serde_json::Value> = arg_df.get_columns().iter().map(|c| {
+                serde_json::Value::Array(rows.iter().map(|&r| crate::storage::native::anyvalue_to_json(c.get(r).unwrap_or(AnyValue::Null))).collect())
+            }).collect();
+            match reg.call_function_json_aggregate(func_name, &jargs) {
+                Ok(v) => results.push(v),
+                Err(e) if null_on_err => { tracing::debug!(target: "clarium::udf", "aggregate UDF '{}' failed: {}", func_name, e); results.push(serde_json::Value::Null); }
+                Err(e) => anyhow::bail!("UDF '{}' error: {}", func_name, e),
+            }
+        }
+        let mut cols: Vec<Column> = Vec::new();
+        if returns.len() > 1 {
+            for (ri, dt) in returns.iter().enumerate() {
+                let vals: Vec<serde_json::Value> = results.iter().map(|v| v.get(ri).cloned().unwrap_or(serde_json::Value::Null)).collect();
+                cols.push(crate::scripts::ScriptRegistry::json_values_to_series(&format!("{}_{}", base_name, ri), &vals, Some(dt.clone()))?.into());
+            }
+        } else {
+            cols.push(crate::scripts::ScriptRegistry::json_values_to_series(base_name, &results, returns.first().cloned())?.into());
+        }
+        for c in cols { appended.push(c.name().to_string()); out = out.hstack(&[c])?; }
+    }
+    ctx.register_user_columns_for_stage(SelectStage::ByOrGroupBy, appended);
+    Ok(out)
+}
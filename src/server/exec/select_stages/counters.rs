@@ -0,0 +1,56 @@
+//! Counter aggregates: INCREASE, RATE and IRATE
+//! A monitoring counter only grows until its process restarts. A drop between two
+//! consecutive samples is treated as a reset to zero, so the new value itself is the
+//! increase for that step. Rates are per second of `_time` (epoch milliseconds).
+
+use polars::prelude::*;
+
+use crate::server::query::query_common::AggFunc;
+
+// Reset-aware step between consecutive values; null for the first row
+fn step_expr(v: Expr) -> Expr {
+    let d = v.clone() - v.clone().shift(lit(1));
+    when(d.clone().lt(lit(0.0))).then(v).otherwise(d)
+}
+
+fn per_second_expr(num: Expr, dt_ms: Expr) -> Expr {
+    when(dt_ms.clone().gt(lit(0)))
+        .then(num / (dt_ms.cast(DataType::Float64) / lit(1000.0)))
+        .otherwise(lit(polars::prelude::Null {}).cast(DataType::Float64))
+}
+
+/// Aggregation expression for a counter function over rows ordered by `time`.
+pub fn counter_agg_expr(func: &AggFunc, value: Expr, time: Expr) -> Expr {
+    let v = value.cast(DataType::Float64);
+    match func {
+        AggFunc::Increase => step_expr(v).sum(),
+        AggFunc::Rate => per_second_expr(step_expr(v).sum(), time.clone().max() - time.min()),
+        // IRATE: the last two samples only
+        _ => {
+            let t2 = time.tail(Some(2));
+            per_second_expr(step_expr(v.tail(Some(2))).last(), t2.clone().last() - t2.first())
+        }
+    }
+}
+
+fn increase(pts: &[(i64, f64)]) -> f64 {
+    pts.windows(2).map(|w| if w[1].1 < w[0].1 { w[1].1 } else { w[1].1 - w[0].1 }).sum()
+}
+
+fn per_second(num: f64, dt_ms: i64) -> Option<f64> {
+    if dt_ms > 0 { Some(num / (dt_ms as f64 / 1000.0)) } else { None }
+}
+
+/// Counter function over non-null (time, value) samples in time order.
+pub fn counter_value(func: &AggFunc, pts: &[(i64, f64)]) -> Option<f64> {
+    let (first, last) = (pts.first()?, pts.last()?);
+    match func {
+        AggFunc::Increase => Some(increase(pts)),
+        AggFunc::Rate => per_second(increase(pts), last.0 - first.0),
+        _ => {
+            let w = &pts[pts.len().saturating_sub(2)..];
+            if w.len() < 2 { return None; }
+            per_second(increase(w), w[1].0 - w[0].0)
+        }
+    }
+}
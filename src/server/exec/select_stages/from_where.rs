@@ -19,6 +19,7 @@ use crate::storage::SharedStore;
 use crate::server::exec::exec_common::{build_where_expr};
 use crate::server::exec::where_subquery::{eval_where_mask};
 use crate::tprintln;
+use crate::telemetry::QUERY_SPAN_TARGET;
 use crate::server::exec::internal::constants::{UNIT, LEFT_ROW_ID};
 
 fn extract_simple_equi_with_remainder(on: &WhereExpr) -> Option<((String, String), Option<WhereExpr>)> {
@@ -44,6 +45,51 @@ fn extract_simple_equi_with_remainder(on: &WhereExpr) -> Option<((String, String
     }
 }
 
+/// Collect top-level AND-ed `col = 'literal'` predicates usable for bloom-filter chunk pruning.
+/// Column qualifiers are dropped since hints only apply to single-table queries.
+fn collect_bloom_hints(w: &WhereExpr, out: &mut Vec<(String, String)>) {
+    match w {
+        WhereExpr::Comp { left, op: CompOp::Eq, right } => {
+            let pair = match (left, right) {
+                (ArithExpr::Term(ArithTerm::Col { name, previous: false }), ArithExpr::Term(ArithTerm::Str(v)))
+                | (ArithExpr::Term(ArithTerm::Str(v)), ArithExpr::Term(ArithTerm::Col { name, previous: false })) => Some((name, v)),
+                _ => None,
+            };
+            if let Some((name, v)) = pair {
+                let base = name.rsplit('.').next().unwrap_or(name);
+                out.push((base.to_string(), v.clone()));
+            }
+        }
+        WhereExpr::And(a, b) => { collect_bloom_hints(a, out); collect_bloom_hints(b, out); }
+        _ => {}
+    }
+}
+
+/// Collect top-level AND-ed `col = literal` predicates (strings and numbers) usable for
+/// partition pruning. Integral numbers render without a fraction, matching partition folders.
+fn collect_partition_hints(w: &WhereExpr, out: &mut Vec<(String, String)>) {
+    match w {
+        WhereExpr::Comp { left, op: CompOp::Eq, right } => {
+            let pair = match (left, right) {
+                (ArithExpr::Term(ArithTerm::Col { name, previous: false }), ArithExpr::Term(lit))
+                | (ArithExpr::Term(lit), ArithExpr::Term(ArithTerm::Col { name, previous: false })) => match lit {
+                    ArithTerm::Str(v) => Some((name, v.clone())),
+                    ArithTerm::Number(n) if n.fract() == 0.0 && n.abs() < 9.0e15 => Some((name, (*n as i64).to_string())),
+                    ArithTerm::Number(n) => Some((name, n.to_string())),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some((name, v)) = pair {
+                let base = name.rsplit('.').next().unwrap_or(name);
+                out.push((base.to_string(), v));
+            }
+        }
+        WhereExpr::And(a, b) => { collect_partition_hints(a, out); collect_partition_hints(b, out); }
+        _ => {}
+    }
+}
+
 fn join_how(t: &JoinType) -> polars::prelude::JoinType {
     match t {
         JoinType::Inner => polars::prelude::JoinType::Inner,
@@ -58,7 +104,30 @@ pub fn from_where(store: &SharedStore, q: &Query, ctx: &mut DataContext) -> Resu
     let mut df = if let Some(tref) = &q.base_table {
         ctx.add_source(tref);
         tprintln!("Defaulting to {:?} dataframe", tref);
-        ctx.load_source_df(store, tref)?
+        let mut hints: Vec<(String, String)> = Vec::new();
+        let mut part_hints: Vec<(String, String)> = Vec::new();
+        if q.joins.as_ref().map(|j| j.is_empty()).unwrap_or(true) {
+            if let Some(w) = &q.where_clause {
+                // NO_PRUNE hint and planner toggles can switch either kind of pruning off
+                let (bloom, partition) = super::hints::pruning_enabled(q);
+                if bloom { collect_bloom_hints(w, &mut hints); }
+                if partition { collect_partition_hints(w, &mut part_hints); }
+                // Equality under a column collation is not byte equality, so it cannot skip chunks
+                hints.retain(|(c, _)| ctx.column_collation(c).is_none());
+                part_hints.retain(|(c, _)| ctx.column_collation(c).is_none());
+            }
+        }
+        ctx.chunk_prune_eq = hints;
+        ctx.partition_prune_eq = part_hints;
+        // `chunks` is filled in by the storage read
+        let scan = tracing::debug_span!(target: QUERY_SPAN_TARGET, "scan",
+            table = tref.effective_name(), rows = tracing::field::Empty, chunks = tracing::field::Empty).entered();
+        let loaded = ctx.load_source_df(store, tref);
+        if let Ok(df) = &loaded { scan.record("rows", df.height()); }
+        drop(scan);
+        ctx.chunk_prune_eq.clear();
+        ctx.partition_prune_eq.clear();
+        loaded?
     } else {
         tprintln!("Defaulting to blank dataframe");
         // Support queries without a FROM source by starting with a single-row dummy DataFrame.
@@ -84,6 +153,7 @@ pub fn from_where(store: &SharedStore, q: &Query, ctx: &mut DataContext) -> Resu
             AE::Cast { expr, ty } => {
                 AE::Cast { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), ty: ty.clone() }
             }
+            AE::Collate { expr, collation } => AE::Collate { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), collation: collation.clone() },
             AE::BinOp { left, op, right } => AE::BinOp {
                 left: Box::new(qualify_arith_ctx(df, ctx, left, clause)?),
                 op: op.clone(),
@@ -140,6 +210,9 @@ pub fn from_where(store: &SharedStore, q: &Query, ctx: &mut DataContext) -> Resu
         for jc in joins {
             // Load right side with alias-prefixed columns
             ctx.add_source(&jc.right);
+            let join_span = tracing::debug_span!(target: QUERY_SPAN_TARGET, "join",
+                table = jc.right.effective_name(), kind = ?jc.join_type,
+                rows = tracing::field::Empty, chunks = tracing::field::Empty).entered();
             let right_df = ctx.load_source_df(store, &jc.right)?;
             
             // Try to extract equi-join condition with remainder
@@ -355,6 +428,7 @@ pub fn from_where(store: &SharedStore, q: &Query, ctx: &mut DataContext) -> Resu
                     _ => anyhow::bail!("RIGHT/FULL JOIN with pure non-equi conditions requires at least one equality in ON clause"),
                 }
             };
+            join_span.record("rows", joined.height());
             df = joined;
         }
     }
@@ -46,6 +46,7 @@ fn qualify_having_arith(df: &DataFrame, a: &ArithExpr) -> ArithExpr {
         AE::Cast { expr, ty } => {
             AE::Cast { expr: Box::new(qualify_having_arith(df, expr)), ty: ty.clone() }
         }
+        AE::Collate { expr, collation } => AE::Collate { expr: Box::new(qualify_having_arith(df, expr)), collation: collation.clone() },
         AE::BinOp { left, op, right } => AE::BinOp { left: Box::new(qualify_having_arith(df, left)), op: op.clone(), right: Box::new(qualify_having_arith(df, right)) },
         AE::Concat(parts) => AE::Concat(parts.iter().map(|p| qualify_having_arith(df, p)).collect()),
         AE::Call { name, args } => AE::Call { name: name.clone(), args: args.iter().map(|p| qualify_having_arith(df, p)).collect() },
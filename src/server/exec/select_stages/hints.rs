@@ -0,0 +1,57 @@
+//! Apply optimizer hints and planner toggles to a SELECT before its stages run.
+
+use crate::server::query::{JoinClause, JoinType, Query, QueryHints, TableRef};
+
+/// Hints of `q`, or none when `SET planner.enable_hints = off`.
+pub fn effective_hints(q: &Query) -> QueryHints {
+    if crate::system::planner_toggle("enable_hints") { q.hints.clone() } else { QueryHints::default() }
+}
+
+/// Whether `(bloom, partition)` pruning may be used for `q`.
+pub fn pruning_enabled(q: &Query) -> (bool, bool) {
+    let no_prune = effective_hints(q).no_prune;
+    (
+        !no_prune && crate::system::planner_toggle("enable_bloom_pruning"),
+        !no_prune && crate::system::planner_toggle("enable_partition_pruning"),
+    )
+}
+
+/// Names a table in the FROM clause can be referred to by in JOIN_ORDER: alias,
+/// written name, and the bare table name without path or `.time` suffix.
+fn matches_name(t: &TableRef, hint: &str) -> bool {
+    if t.effective_name().eq_ignore_ascii_case(hint) { return true; }
+    let Some(name) = t.table_name() else { return false };
+    if name.eq_ignore_ascii_case(hint) { return true; }
+    let last = name.rsplit(['/', '.']).find(|p| !p.eq_ignore_ascii_case("time")).unwrap_or(name);
+    last.eq_ignore_ascii_case(hint)
+}
+
+/// `q` with its joins reordered per a JOIN_ORDER hint, or `None` when there is no
+/// hint, it is already satisfied, or it cannot be applied (outer joins, or names
+/// that do not match exactly one table each).
+///
+/// The first named table becomes the base; the ON condition that joined it moves
+/// to the original base table, and every other join keeps its own condition.
+pub fn apply_join_order(q: &Query) -> Option<Query> {
+    let order = effective_hints(q).join_order?;
+    let base = q.base_table.as_ref()?;
+    let joins = q.joins.as_ref().filter(|j| !j.is_empty())?;
+    if joins.iter().any(|j| j.join_type != JoinType::Inner) { return None; }
+    let tables: Vec<&TableRef> = std::iter::once(base).chain(joins.iter().map(|j| &j.right)).collect();
+    let mut picked: Vec<usize> = Vec::with_capacity(tables.len());
+    for name in &order {
+        let hits: Vec<usize> = (0..tables.len()).filter(|i| matches_name(tables[*i], name)).collect();
+        if hits.len() != 1 || picked.contains(&hits[0]) { return None; }
+        picked.push(hits[0]);
+    }
+    for i in 0..tables.len() { if !picked.contains(&i) { picked.push(i); } }
+    if picked.iter().enumerate().all(|(pos, i)| pos == *i) { return None; }
+
+    let new_base = picked[0];
+    // ON condition per table index; the original base inherits the new base's condition
+    let on_of = |i: usize| if i == 0 { joins[new_base - 1].on.clone() } else { joins[i - 1].on.clone() };
+    let mut out = q.clone();
+    out.base_table = Some(tables[new_base].clone());
+    out.joins = Some(picked[1..].iter().map(|i| JoinClause { join_type: JoinType::Inner, right: tables[*i].clone(), on: on_of(*i) }).collect());
+    Some(out)
+}
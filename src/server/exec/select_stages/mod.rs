@@ -5,5 +5,7 @@ pub mod project_select;
 pub mod from_where;
 pub mod by_or_groupby;
 pub mod rolling;
+pub mod counters;
 pub mod order_limit;
+pub mod hints;
 
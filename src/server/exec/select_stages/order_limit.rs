@@ -189,8 +189,10 @@ pub fn order_limit(mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Resul
                     }
                 }
                 let mut exprs: Vec<Expr> = Vec::new();
+                let mut sort_names: Vec<String> = Vec::new();
                 let mut descending: Vec<bool> = Vec::new();
-                for (name, asc) in ob.iter() {
+                let mut collate_keys: Vec<String> = Vec::new();
+                for (i, (name, asc)) in ob.iter().enumerate() {
                     // Apply any override established during strict temp validation
                     let effective_name: &str = if let Some(n) = ob_overrides.get(name) { n.as_str() } else { name.as_str() };
                     // If the ORDER BY key looks like an expression (e.g., a function call),
@@ -203,7 +205,23 @@ pub fn order_limit(mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Resul
                     // Try to resolve the ORDER BY column against current DF/Context
                     match ctx.resolve_column_at_stage(&df, effective_name, SelectStage::OrderLimit) {
                         Ok(resolved) => {
+                            // Text under a collation sorts by its collation sort key, held in a temporary column
+                            let explicit = q.order_by_collations.as_ref().and_then(|v| v.get(i).cloned().flatten());
+                            let collation = match explicit {
+                                Some(n) => crate::server::collation::Collation::lookup(&n),
+                                None => ctx.column_collation(effective_name),
+                            }.filter(|c| !c.is_bytewise());
+                            let resolved = match collation {
+                                Some(c) => {
+                                    let key = format!("__collate_key_{}", i);
+                                    df = df.lazy().with_column(c.key_expr(col(resolved.as_str())).alias(&key)).collect()?;
+                                    collate_keys.push(key.clone());
+                                    key
+                                }
+                                None => resolved,
+                            };
                             exprs.push(col(resolved.as_str()));
+                            sort_names.push(resolved);
                             descending.push(!asc);
                         }
                         Err(_) => {
@@ -235,8 +253,15 @@ pub fn order_limit(mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Resul
                         "[ORDER_LIMIT] exact sort: exprs={} descending={:?} nulls_last={:?}",
                         exprs.len(), descending, nulls_last
                     );
-                    df = df.lazy().sort_by_exprs(exprs, opts).collect()?;
+                    if let Some(work_mem) = crate::memory::spill_threshold(df.estimated_size()) {
+                        // Input over work_mem: external merge sort, stopping early for a positive LIMIT
+                        let top = q.limit.filter(|n| *n > 0).map(|n| n as usize);
+                        df = crate::server::exec::spill::external_sort(df, &sort_names, &descending, top, work_mem)?;
+                    } else {
+                        df = df.lazy().sort_by_exprs(exprs, opts).collect()?;
+                    }
                 }
+                if !collate_keys.is_empty() { df = df.drop_many(collate_keys.iter().map(|k| k.as_str())); }
             }
             // In loose mode, drop temporary ORDER BY columns that were added for sorting
             if !strict && !ctx.temp_order_by_columns.is_empty() {
@@ -293,7 +318,7 @@ fn ann_diag_for_order_by(ctx: &DataContext, order_key: &str) -> Option<AnnDiag>
     // Walk the db/schema tree and search for any .vindex whose column matches the ORDER BY key
     // This is a heuristic when we cannot perfectly resolve the source table at this stage.
     let mut best: Option<AnnDiag> = None;
-    if let Ok(dbs) = fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let dbp = db_ent.path(); if !dbp.is_dir() { continue; }
             if let Ok(schemas) = fs::read_dir(&dbp) {
@@ -327,7 +352,7 @@ fn ann_diag_for_order_by(ctx: &DataContext, order_key: &str) -> Option<AnnDiag>
 fn ann_find_index_for(ctx: &DataContext, table: &str, column: &str) -> Option<AnnDiag> {
     let store = ctx.store.as_ref()?;
     let root = store.0.lock().root_path().clone();
-    if let Ok(dbs) = fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let dbp = db_ent.path(); if !dbp.is_dir() { continue; }
             if let Ok(schemas) = fs::read_dir(&dbp) {
@@ -432,6 +457,23 @@ fn parse_vec_literal_f64(s: &str) -> Option<Vec<f64>> {
     vector_utils::parse_vec_literal(s).map(|v| v.into_iter().map(|x| x as f64).collect())
 }
 
+// Map each stable row id in `rid_col` to its row index
+fn row_id_positions(rid_col: &Column) -> HashMap<u64, u32> {
+    let mut pos: HashMap<u64, u32> = HashMap::with_capacity(rid_col.len());
+    for i in 0..rid_col.len() {
+        if let Ok(av) = rid_col.get(i) {
+            if let Ok(id) = av.try_extract::<u64>() {
+                pos.insert(id, i as u32);
+            } else if let Ok(id32) = av.try_extract::<u32>() {
+                pos.insert(id32 as u64, i as u32);
+            } else if let Ok(id64) = av.try_extract::<i64>() {
+                pos.insert(id64 as u64, i as u32);
+            }
+        }
+    }
+    pos
+}
+
 // Compute ANN ordering using exact scoring on the provided column containing vector-encoded strings
 fn ann_order_dataframe(
     ctx: &DataContext,
@@ -510,7 +552,7 @@ fn ann_order_dataframe(
                 if let Some(ann) = ann_diag_for_order_by(ctx, &cname) {
                     // Find the exact .vindex file matching table+column
                     let root = store.0.lock().root_path().clone();
-                    'outer: for db_ent in std::fs::read_dir(&root).unwrap_or_else(|_| std::fs::read_dir(".").unwrap()) {
+                    'outer: for db_ent in crate::storage::attach::read_database_dirs(&root).unwrap_or_else(|_| Vec::new().into_iter()) {
                         if let Ok(db_e) = db_ent {
                             let dbp = db_e.path(); if !dbp.is_dir() { continue; }
                             if let Ok(schemas) = std::fs::read_dir(&dbp) {
@@ -529,25 +571,20 @@ fn ann_order_dataframe(
                                                             // Preselect W = alpha * k candidates using ANN engine (or flat as baseline)
                                                             let alpha = crate::system::get_vector_preselect_alpha().max(1) as usize;
                                                             let w = k.saturating_mul(alpha);
-                                                            if let Ok(cands) = crate::server::exec::exec_vector_runtime::search_vector_index(store, &vf, &qf, w) {
+                                                            // Only rows that passed WHERE may come back: their row ids filter the index search
+                                                            let rid_pos = rid_col_name.as_deref().and_then(|rn| df.column(rn).ok()).map(row_id_positions);
+                                                            let searched = match rid_pos.as_ref() {
+                                                                Some(pos) => {
+                                                                    let allowed: std::collections::HashSet<u64> = pos.keys().copied().collect();
+                                                                    crate::server::exec::exec_vector_runtime::search_vector_index_filtered(store, &vf, &qf, w, &allowed)
+                                                                }
+                                                                None => crate::server::exec::exec_vector_runtime::search_vector_index(store, &vf, &qf, w),
+                                                            };
+                                                            if let Ok(cands) = searched {
                                                                 if !cands.is_empty() {
                                                                     // If a stable __row_id column exists, map candidates by row_id; otherwise treat ids as positional indices
-                                                                    if let Some(rn) = rid_col_name.as_deref() {
-                                                                        if let Ok(rid_col) = df.column(rn) {
-                                                                            // Build map from row_id -> row_index
-                                                                            use std::collections::HashMap;
-                                                                            let mut pos: HashMap<u64, u32> = HashMap::with_capacity(df.height());
-                                                                            for i in 0..rid_col.len() {
-                                                                                if let Ok(av) = rid_col.get(i) {
-                                                                                    if let Ok(id) = av.try_extract::<u64>() {
-                                                                                        pos.insert(id, i as u32);
-                                                                                    } else if let Ok(id32) = av.try_extract::<u32>() {
-                                                                                        pos.insert(id32 as u64, i as u32);
-                                                                                    } else if let Ok(id64) = av.try_extract::<i64>() {
-                                                                                        pos.insert(id64 as u64, i as u32);
-                                                                                    }
-                                                                                }
-                                                                            }
+                                                                    if rid_col_name.is_some() {
+                                                                        if let Some(pos) = rid_pos.as_ref() {
                                                                             // Collect DF indices for preselected candidates, preserving ANN order
                                                                             let mut idx: Vec<u32> = Vec::with_capacity(cands.len());
                                                                             for (rid, _s) in cands.iter() {
@@ -763,7 +800,7 @@ fn ann_order_dataframe(
             let mut preselect_count: usize = 0;
             // Locate vindex matching table+column
             let root = store.0.lock().root_path().clone();
-            'outer2: for db_ent in std::fs::read_dir(&root).unwrap_or_else(|_| std::fs::read_dir(".").unwrap()) {
+            'outer2: for db_ent in crate::storage::attach::read_database_dirs(&root).unwrap_or_else(|_| Vec::new().into_iter()) {
                 if let Ok(db_e) = db_ent {
                     let dbp = db_e.path(); if !dbp.is_dir() { continue; }
                     if let Ok(schemas) = std::fs::read_dir(&dbp) {
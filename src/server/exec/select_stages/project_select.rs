@@ -19,6 +19,7 @@ use crate::server::query::query_common::WindowFunc;
 use crate::server::query::query_common::TableRef;
 use crate::server::query::query_common::StrSliceBound;
 use crate::server::exec::exec_common::build_arith_expr;
+use crate::server::exec::select_stages::counters::counter_agg_expr;
 use crate::server::exec::internal::constants::{ARG_PREFIX, WINDOW_ORDER_PREFIX};
 use crate::scripts::get_script_registry;
 
@@ -30,6 +31,7 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
         match a {
             AE::Term(AT::Col { name, previous }) => { if !*previous { out.push(name.clone()); } },
             AE::Cast { expr, .. } => { collect_cols_arith(expr, out); },
+            AE::Collate { expr, .. } => { collect_cols_arith(expr, out); },
             AE::BinOp { left, right, .. } => { collect_cols_arith(left, out); collect_cols_arith(right, out); },
             AE::Concat(parts) => { for p in parts { collect_cols_arith(p, out); } },
             AE::Func(_) => {},
@@ -50,6 +52,7 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
         match a {
             AE::Call { name, args } => { out.push(name.clone()); for x in args { collect_udf_names_arith(x, out); } },
             AE::Cast { expr, .. } => { collect_udf_names_arith(expr, out); },
+            AE::Collate { expr, .. } => { collect_udf_names_arith(expr, out); },
             AE::BinOp { left, right, .. } => { collect_udf_names_arith(left, out); collect_udf_names_arith(right, out); },
             AE::Concat(parts) => { for p in parts { collect_udf_names_arith(p, out); } },
             AE::Slice { base, .. } => { collect_udf_names_arith(base, out); },
@@ -78,6 +81,7 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
             AE::Cast { expr, ty } => {
                 AE::Cast { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), ty: ty.clone() }
             }
+            AE::Collate { expr, collation } => AE::Collate { expr: Box::new(qualify_arith_ctx(df, ctx, expr, clause)?), collation: collation.clone() },
             AE::BinOp { left, op, right } => AE::BinOp { left: Box::new(qualify_arith_ctx(df, ctx, left, clause)?), op: op.clone(), right: Box::new(qualify_arith_ctx(df, ctx, right, clause)?) },
             AE::Concat(parts) => AE::Concat(parts.iter().map(|p| qualify_arith_ctx(df, ctx, p, clause)).collect::<anyhow::Result<Vec<_>>>()?),
             AE::Call { name, args } => AE::Call { name: name.clone(), args: args.iter().map(|p| qualify_arith_ctx(df, ctx, p, clause)).collect::<anyhow::Result<Vec<_>>>()? },
@@ -127,7 +131,7 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
 
     // If BY/GROUP BY/SLICE or ROLLING already computed aggregations, don't recompute.
     // Instead, apply aliases to existing aggregate columns (function-form names) and passthrough.
-    if q.by_window_ms.is_some() || q.group_by_cols.is_some() || q.rolling_window_ms.is_some() || q.by_slices.is_some() {
+    if q.by_window_ms.is_some() || q.group_by_cols.is_some() || q.rolling_window_ms.is_some() || q.rolling_rows.is_some() || q.by_slices.is_some() {
         // Build a mutable copy to apply renames for aliases
         let mut out = df.clone();
         for item in &q.select {
@@ -149,6 +153,11 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
                         AggFunc::Gradient => format!("GRADIENT({})", item.column),
                         AggFunc::Quantile(qp) => format!("_{}_QUANTILE({})", qp, item.column),
                         AggFunc::ArrayAgg => format!("ARRAY_AGG({})", item.column),
+                        AggFunc::Ema(_) => format!("EMA({})", item.column),
+                        AggFunc::Wma => format!("WMA({})", item.column),
+                        AggFunc::Rate => format!("RATE({})", item.column),
+                        AggFunc::Increase => format!("INCREASE({})", item.column),
+                        AggFunc::Irate => format!("IRATE({})", item.column),
                     };
                     if out.get_column_names().iter().any(|c| c.as_str() == src_name) {
                         // Attempt to rename; if DataFrame::rename is unavailable, rebuild column
@@ -271,7 +280,7 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
         }
     }
     // If BY/GROUP BY or ROLLING already computed aggregations, pass-through
-    if q.by_window_ms.is_some() || q.group_by_cols.is_some() || q.rolling_window_ms.is_some() {
+    if q.by_window_ms.is_some() || q.group_by_cols.is_some() || q.rolling_window_ms.is_some() || q.rolling_rows.is_some() {
         ctx.register_df_columns_for_stage(SelectStage::ProjectSelect, &df);
         return Ok(df);
     }
@@ -317,6 +326,12 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
                         // Collect values into PostgreSQL array format
                         base.cast(DataType::String).implode().alias(format!("ARRAY_AGG({})", item.column))
                     }
+                    AggFunc::Rate | AggFunc::Increase | AggFunc::Irate => {
+                        let fname = match func { AggFunc::Rate => "RATE", AggFunc::Increase => "INCREASE", _ => "IRATE" };
+                        let time_col = resolve_col_name_ctx(&df, ctx, "_time").unwrap_or_else(|_| "_time".to_string());
+                        counter_agg_expr(func, base, col(&time_col)).alias(format!("{}({})", fname, item.column))
+                    }
+                    AggFunc::Ema(_) | AggFunc::Wma => anyhow::bail!("EMA and WMA are only supported with ROLLING BY"),
                 };
                 if let Some(a) = &item.alias { e = e.alias(a); }
                 agg_cols.push(e);
@@ -689,7 +704,7 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
                                         let fields = sc.fields_as_series();
                                         let len = sc.len();
                                         if let Some(r) = &reg_snapshot {
-                                            let out_col: Column = r
+                                            let out_col: Column = crate::script_stats::timed(&name_eval, len as u64, |_| r
                                                 .with_lua_function(&name_eval, |lua, func| {
                                                     use mlua::Value as LVal;
                                                     use mlua::MultiValue;
@@ -826,7 +841,7 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
                                                         Ok(s.into_column())
                                                     }
                                                 }
-                                            })
+                                            }))
                                             .map_err(|e| polars::error::PolarsError::ComputeError(e.to_string().into()))?;
                                         Ok(out_col)
                                     } else {
@@ -864,6 +879,10 @@ pub fn project_select(df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result
                     ArithExpr::Predicate(_) => None,
                     ArithExpr::Case { .. } => None,
                     ArithExpr::Cast { .. } => None,
+                    ArithExpr::Collate { expr, .. } => match expr.as_ref() {
+                        ArithExpr::Term(AT::Col { name, .. }) => Some(name.rsplit('.').next().unwrap_or(name).to_string()),
+                        _ => None,
+                    },
                     ArithExpr::Term(_) => None,
                 };
                 let base_name = item.alias.clone().or(derived_name);
@@ -9,9 +9,15 @@ use crate::server::query::query_common::Query;
 use crate::server::query::query_common::ArithTerm;
 use crate::server::query::query_common::AggFunc;
 use crate::server::query::query_common::ArithExpr;
+use crate::server::query::query_common::RollingFrame;
+use crate::server::exec::select_stages::counters::counter_value;
 
 pub fn rolling(mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result<DataFrame> {
-    let win = q.rolling_window_ms.ok_or_else(|| anyhow::anyhow!("ROLLING BY requires a window"))?;
+    let default_frame = match (q.rolling_window_ms, q.rolling_rows) {
+        (Some(ms), _) => RollingFrame::Range(ms),
+        (None, Some(n)) => RollingFrame::Rows(n),
+        (None, None) => anyhow::bail!("ROLLING BY requires a window"),
+    };
     if q.group_by_cols.is_some() { anyhow::bail!("ROLLING BY cannot be used with GROUP BY"); }
     if q.select.iter().any(|i| i.str_func.is_some()) {
         anyhow::bail!("String functions are not supported with ROLLING BY window");
@@ -50,43 +56,8 @@ pub fn rolling(mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result<Da
                 }
             };
 
-            let n = times.len();
-            let mut res: Vec<Option<f64>> = Vec::with_capacity(n);
-            let mut j: usize = 0; // window start index (inclusive)
-            let mut sum: f64 = 0.0;
-            let mut cnt: usize = 0;
-            let mut sumsq: f64 = 0.0; // for STDEV
-
-            for i in 0..n {
-                let cur_t = times[i];
-                let cutoff = cur_t - win + 1;
-                // slide window start forward
-                while j < i {
-                    if times[j] >= cutoff { break; }
-                    if let Some(v) = val_opt[j] { sum -= v; sumsq -= v * v; cnt -= 1; }
-                    j += 1;
-                }
-                // include current i
-                if let Some(v) = val_opt[i] { sum += v; sumsq += v * v; cnt += 1; }
-
-                // compute aggregate for this i
-                let v_opt = match func {
-                    AggFunc::Avg => { if cnt > 0 { Some(sum / cnt as f64) } else { None } }
-                    AggFunc::Sum => { if cnt > 0 { Some(sum) } else { None } }
-                    AggFunc::Count => { Some(cnt as f64) }
-                    AggFunc::Stdev => {
-                        if cnt >= 2 {
-                            let mean = sum / cnt as f64;
-                            let var = (sumsq - mean * mean * cnt as f64) / (cnt as f64 - 1.0);
-                            Some(var.sqrt())
-                        } else { None }
-                    }
-                    AggFunc::Max | AggFunc::Min | AggFunc::First | AggFunc::Last | AggFunc::Delta | AggFunc::Height | AggFunc::Gradient | AggFunc::Quantile(_) | AggFunc::ArrayAgg => {
-                        anyhow::bail!("ROLLING BY currently supports AVG, SUM, COUNT, and STDEV only");
-                    }
-                };
-                res.push(v_opt);
-            }
+            let frame = item.frame.unwrap_or(default_frame);
+            let res = rolling_values(func, frame, &times, &val_opt)?;
 
             let name = match func {
                 AggFunc::Avg => format!("AVG({})", item.column),
@@ -102,6 +73,11 @@ pub fn rolling(mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result<Da
                 AggFunc::Gradient => format!("GRADIENT({})", item.column),
                 AggFunc::Quantile(cutoff) => format!("_{}_QUANTILE({})", cutoff, item.column),
                 AggFunc::ArrayAgg => format!("ARRAY_AGG({})", item.column),
+                AggFunc::Ema(_) => format!("EMA({})", item.column),
+                AggFunc::Wma => format!("WMA({})", item.column),
+                AggFunc::Rate => format!("RATE({})", item.column),
+                AggFunc::Increase => format!("INCREASE({})", item.column),
+                AggFunc::Irate => format!("IRATE({})", item.column),
             };
             out_cols.push(Series::new((&name).into(), res).into());
         }
@@ -114,3 +90,99 @@ pub fn rolling(mut df: DataFrame, q: &Query, ctx: &mut DataContext) -> Result<Da
     ctx.register_df_columns_for_stage(SelectStage::Rolling, &out);
     Ok(out)
 }
+
+// First row of the frame ending at each row; non-decreasing, so sums can slide
+fn frame_starts(frame: RollingFrame, times: &[i64]) -> Vec<usize> {
+    let mut starts = Vec::with_capacity(times.len());
+    let mut j: usize = 0;
+    for (i, cur_t) in times.iter().enumerate() {
+        match frame {
+            RollingFrame::Range(win) => {
+                let cutoff = cur_t.saturating_sub(win).saturating_add(1);
+                while j < i && times[j] < cutoff { j += 1; }
+            }
+            RollingFrame::Rows(n) => j = i.saturating_sub(n),
+        }
+        starts.push(j);
+    }
+    starts
+}
+
+fn rolling_values(func: &AggFunc, frame: RollingFrame, times: &[i64], vals: &[Option<f64>]) -> Result<Vec<Option<f64>>> {
+    let n = times.len();
+    let mut res: Vec<Option<f64>> = Vec::with_capacity(n);
+    match func {
+        AggFunc::Avg | AggFunc::Sum | AggFunc::Count | AggFunc::Stdev => {
+            let mut j: usize = 0; // window start index (inclusive)
+            let mut sum: f64 = 0.0;
+            let mut cnt: usize = 0;
+            let mut sumsq: f64 = 0.0; // for STDEV
+            for (i, start) in frame_starts(frame, times).into_iter().enumerate() {
+                // slide window start forward
+                while j < start {
+                    if let Some(v) = vals[j] { sum -= v; sumsq -= v * v; cnt -= 1; }
+                    j += 1;
+                }
+                // include current i
+                if let Some(v) = vals[i] { sum += v; sumsq += v * v; cnt += 1; }
+                res.push(match func {
+                    AggFunc::Avg => if cnt > 0 { Some(sum / cnt as f64) } else { None },
+                    AggFunc::Sum => if cnt > 0 { Some(sum) } else { None },
+                    AggFunc::Count => Some(cnt as f64),
+                    _ => if cnt >= 2 {
+                        let mean = sum / cnt as f64;
+                        let var = (sumsq - mean * mean * cnt as f64) / (cnt as f64 - 1.0);
+                        Some(var.max(0.0).sqrt())
+                    } else { None },
+                });
+            }
+        }
+        AggFunc::Ema(alpha) => {
+            // Recursive over the whole series; NULLs carry the previous average forward
+            let mut ema: Option<f64> = None;
+            for v in vals {
+                if let Some(v) = v { ema = Some(ema.map(|e| alpha * v + (1.0 - alpha) * e).unwrap_or(*v)); }
+                res.push(ema);
+            }
+        }
+        AggFunc::ArrayAgg => anyhow::bail!("ROLLING BY does not support ARRAY_AGG"),
+        _ => {
+            // Remaining aggregates look at the non-null (time, value) pairs of each frame
+            for (i, start) in frame_starts(frame, times).into_iter().enumerate() {
+                let pts: Vec<(i64, f64)> = (start..=i).filter_map(|k| vals[k].map(|v| (times[k], v))).collect();
+                let first = pts.first().copied();
+                let last = pts.last().copied();
+                let min = pts.iter().map(|p| p.1).reduce(f64::min);
+                let max = pts.iter().map(|p| p.1).reduce(f64::max);
+                res.push(match func {
+                    AggFunc::Max => max,
+                    AggFunc::Min => min,
+                    AggFunc::First => first.map(|p| p.1),
+                    AggFunc::Last => last.map(|p| p.1),
+                    AggFunc::Delta => if pts.len() >= 2 { Some(last.unwrap().1 - first.unwrap().1) } else { None },
+                    AggFunc::Height => min.zip(max).map(|(lo, hi)| hi - lo),
+                    AggFunc::Gradient => match (first, last) {
+                        (Some(a), Some(b)) if b.0 != a.0 => Some((b.1 - a.1) / (b.0 - a.0) as f64),
+                        _ => None,
+                    },
+                    AggFunc::Quantile(qp) => {
+                        let mut v: Vec<f64> = pts.iter().map(|p| p.1).collect();
+                        v.sort_by(|a, b| a.total_cmp(b));
+                        let pos = ((*qp as f64) / 100.0 * (v.len() as f64 - 1.0)).round() as usize;
+                        v.get(pos).copied()
+                    }
+                    AggFunc::Rate | AggFunc::Increase | AggFunc::Irate => counter_value(func, &pts),
+                    // Weights 1..k with the most recent value weighted highest
+                    _ => {
+                        let k = pts.len() as f64;
+                        if pts.is_empty() { None } else {
+                            let num: f64 = pts.iter().enumerate().map(|(w, p)| (w + 1) as f64 * p.1).sum();
+                            Some(num / (k * (k + 1.0) / 2.0))
+                        }
+                    }
+                });
+            }
+        }
+    }
+    Ok(res)
+}
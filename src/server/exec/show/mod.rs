@@ -16,7 +16,7 @@ pub fn df_show_tables(store: &SharedStore) -> Result<DataFrame> {
     let mut dbs_vec: Vec<String> = Vec::new();
     let mut schemas_vec: Vec<String> = Vec::new();
     let mut names_vec: Vec<String> = Vec::new();
-    if let Ok(dbs) = fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path();
             if !db_path.is_dir() { continue; }
@@ -58,7 +58,7 @@ pub fn df_show_schemas(store: &SharedStore) -> Result<DataFrame> {
     let root = root_path(store);
     let mut dbs_vec: Vec<String> = Vec::new();
     let mut schemas_vec: Vec<String> = Vec::new();
-    if let Ok(dbs) = fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path();
             if !db_path.is_dir() { continue; }
@@ -94,7 +94,7 @@ pub fn df_show_objects(store: &SharedStore) -> Result<DataFrame> {
     let mut schemas_vec: Vec<String> = Vec::new();
     let mut names_vec: Vec<String> = Vec::new();
     let mut types_vec: Vec<String> = Vec::new();
-    if let Ok(dbs) = fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
             let dbname = db_ent.file_name().to_string_lossy().to_string();
@@ -138,7 +138,7 @@ pub fn df_show_scripts(store: &SharedStore) -> Result<DataFrame> {
     let mut names: Vec<String> = Vec::new();
     let mut kinds: Vec<String> = Vec::new();
     let mut folders: Vec<String> = Vec::new();
-    if let Ok(dbs_iter) = fs::read_dir(&root) {
+    if let Ok(dbs_iter) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs_iter.flatten() {
             let dbname = db_ent.file_name().to_string_lossy().to_string();
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
@@ -147,19 +147,22 @@ pub fn df_show_scripts(store: &SharedStore) -> Result<DataFrame> {
                     let sname = sch_ent.file_name().to_string_lossy().to_string();
                     let sdir = scripts_dir_for(std::path::Path::new(&root), &dbname, &sname);
                     if sdir.exists() {
-                        for sub in ["scalars", "aggregates", "constraints", "tvfs", "packages"] {
+                        for sub in ["scalars", "aggregates", "constraints", "tvfs", "packages", "tests"] {
                             let subd = sdir.join(sub);
                             if subd.exists() {
                                 if let Ok(listing) = fs::read_dir(&subd) {
                                     for f in listing.flatten() {
                                         let p = f.path();
-                                        if p.extension().and_then(|e| e.to_str()).unwrap_or("").eq_ignore_ascii_case("lua") {
+                                        let ext = p.extension().and_then(|e| e.to_str()).unwrap_or("");
+                                        // Script tests may also be SQL
+                                        if ext.eq_ignore_ascii_case("lua") || (sub == "tests" && ext.eq_ignore_ascii_case("sql")) {
                                             let name = p.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_string();
                                             let kind = match sub {
                                                 "aggregates" => "aggregate",
                                                 "constraints" => "constraint",
                                                 "tvfs" => "tvf",
                                                 "packages" => "package",
+                                                "tests" => "test",
                                                 _ => "scalar",
                                             };
                                             dbs.push(dbname.clone());
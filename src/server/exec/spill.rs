@@ -0,0 +1,205 @@
+//! Spill-to-disk for ORDER BY and GROUP BY inputs larger than `work_mem` (see `crate::memory`).
+//!
+//! - Sorts become an external merge sort: the input is cut into runs of about half of
+//!   `work_mem`, each run is sorted and written to disk in small batches, and the
+//!   batches are merged back a few at a time. With a positive LIMIT the merge stops as
+//!   soon as enough rows are out.
+//! - GROUP BY sorts the input by the group keys, writes it to disk in partitions that
+//!   never split a group, and aggregates one partition at a time. Every group lives in
+//!   exactly one partition, so any aggregate gives the same result as in memory.
+//!
+//! Spill files go to a per-operation directory under the system temp dir, removed when
+//! the operation ends. They are sealed with the active encryption key when keys are configured.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use polars::prelude::*;
+
+use crate::server::data_context::DataContext;
+use crate::server::query::query_common::Query;
+use crate::storage::encryption::{self, EncryptionSpec};
+
+const SEQ: &str = "__spill_seq";
+const RUN: &str = "__spill_run";
+const LAST: &str = "__spill_last";
+
+/// Directory holding one operation's spill files; deleted on drop.
+struct SpillDir {
+    path: PathBuf,
+    seal: Option<EncryptionSpec>,
+    files: usize,
+}
+
+impl SpillDir {
+    fn new() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("clarium-spill-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path)?;
+        let spec = EncryptionSpec::new(None);
+        let seal = encryption::resolve_key_id(&spec).ok().map(|_| spec);
+        Ok(Self { path, seal, files: 0 })
+    }
+
+    fn write(&mut self, df: &mut DataFrame) -> Result<PathBuf> {
+        let p = self.path.join(format!("{:06}.parquet", self.files));
+        self.files += 1;
+        encryption::write_parquet(&p, df, self.seal.as_ref())?;
+        crate::memory::note_spill(fs::metadata(&p).map(|m| m.len()).unwrap_or(0));
+        Ok(p)
+    }
+
+    fn take(&self, p: &Path) -> Result<DataFrame> {
+        let df = encryption::read_parquet(p)?;
+        let _ = fs::remove_file(p);
+        Ok(df)
+    }
+}
+
+impl Drop for SpillDir {
+    fn drop(&mut self) { let _ = fs::remove_dir_all(&self.path); }
+}
+
+/// Rows of `df` that take about `bytes` of memory (at least one).
+fn rows_for(df: &DataFrame, bytes: u64) -> usize {
+    let size = df.estimated_size().max(1) as u64;
+    ((df.height() as u64).saturating_mul(bytes) / size).max(1) as usize
+}
+
+fn sort_opts(descending: Vec<bool>) -> SortMultipleOptions {
+    let n = descending.len();
+    SortMultipleOptions { descending, nulls_last: vec![true; n], maintain_order: true, multithreaded: true, limit: None }
+}
+
+/// Sort `df` by `by` (nulls last) within a `work_mem` budget, keeping the first `limit` rows when given.
+pub(crate) fn external_sort(df: DataFrame, by: &[String], descending: &[bool], limit: Option<usize>, work_mem: u64) -> Result<DataFrame> {
+    let height = df.height();
+    if height == 0 || limit == Some(0) { return Ok(df.slice(0, 0)); }
+    let run_rows = rows_for(&df, work_mem / 2);
+    let runs = height.div_ceil(run_rows);
+    let batch_rows = (run_rows / (2 * runs)).max(1);
+    crate::tprintln!("[spill] external sort rows={} runs={} batch_rows={}", height, runs, batch_rows);
+
+    // The original row position breaks ties, so the result matches a stable in-memory sort
+    let mut keys: Vec<&str> = by.iter().map(|s| s.as_str()).collect();
+    keys.push(SEQ);
+    let mut desc = descending.to_vec();
+    desc.push(false);
+
+    let mut dir = SpillDir::new()?;
+    let df = df.with_row_index(SEQ.into(), None)?;
+    let mut pending: Vec<VecDeque<PathBuf>> = Vec::with_capacity(runs);
+    for r in 0..runs {
+        let run = df.slice((r * run_rows) as i64, run_rows).sort(keys.clone(), sort_opts(desc.clone()))?;
+        let mut files = VecDeque::new();
+        let mut off = 0usize;
+        while off < run.height() {
+            files.push_back(dir.write(&mut run.slice(off as i64, batch_rows))?);
+            off += batch_rows;
+        }
+        pending.push(files);
+    }
+    drop(df);
+
+    let mut out: Option<DataFrame> = None;
+    let mut emitted = 0usize;
+    let mut carry: Option<DataFrame> = None;
+    let mut to_load: Vec<usize> = (0..runs).collect();
+    loop {
+        let mut window = carry.take();
+        for &r in &to_load {
+            let Some(path) = pending[r].pop_front() else { continue };
+            let mut batch = dir.take(&path)?;
+            let n = batch.height();
+            let more = !pending[r].is_empty();
+            batch.with_column(Series::new(RUN.into(), vec![r as u32; n]))?;
+            batch.with_column(Series::new(LAST.into(), (0..n).map(|i| more && i + 1 == n).collect::<Vec<bool>>()))?;
+            match window.as_mut() {
+                Some(w) => { w.vstack_mut(&batch)?; }
+                None => window = Some(batch),
+            }
+        }
+        let Some(w) = window else { break };
+        let w = w.sort(keys.clone(), sort_opts(desc.clone()))?;
+        // Every run with batches left has the last row of its loaded batch in the window.
+        // Rows up to the first such row sort before anything still on disk.
+        let cut = w.column(LAST)?.bool()?.into_iter().position(|v| v == Some(true));
+        let (ready, rest) = match cut {
+            Some(p) => (w.slice(0, p + 1), Some(w.slice((p + 1) as i64, w.height() - p - 1))),
+            None => (w, None),
+        };
+        to_load = match cut {
+            Some(p) => vec![ready.column(RUN)?.u32()?.get(p).unwrap_or(0) as usize],
+            None => Vec::new(),
+        };
+        emitted += ready.height();
+        let ready = ready.drop_many([SEQ, RUN, LAST]);
+        match out.as_mut() {
+            Some(o) => { o.vstack_mut(&ready)?; }
+            None => out = Some(ready),
+        }
+        if cut.is_none() || limit.is_some_and(|l| emitted >= l) { break; }
+        carry = rest;
+    }
+    let mut out = out.unwrap_or_else(DataFrame::empty);
+    if let Some(l) = limit { if out.height() > l { out = out.slice(0, l); } }
+    out.rechunk_mut();
+    Ok(out)
+}
+
+/// Group keys to partition on when a GROUP BY input must spill, with the `work_mem` to respect.
+/// Only plain GROUP BY (no BY window, slices or NOTNULL) spills.
+pub(crate) fn group_spill_keys(q: &Query, ctx: &DataContext, df: &DataFrame) -> Option<(Vec<String>, u64)> {
+    let group_cols = q.group_by_cols.as_ref()?;
+    if group_cols.is_empty() || q.by_window_ms.is_some() || q.by_slices.is_some() { return None; }
+    if q.group_by_notnull_cols.as_ref().is_some_and(|v| !v.is_empty()) { return None; }
+    let work_mem = crate::memory::spill_threshold(df.estimated_size())?;
+    let keys = group_cols.iter().map(|c| ctx.resolve_column(df, c)).collect::<Result<Vec<_>>>().ok()?;
+    Some((keys, work_mem))
+}
+
+/// Run `agg` over partitions of `df` that keep each `keys` group together, spilling the
+/// partitions to disk first; outputs are stacked in key order.
+pub(crate) fn partitioned_aggregate(df: DataFrame, keys: &[String], work_mem: u64, mut agg: impl FnMut(DataFrame) -> Result<DataFrame>) -> Result<DataFrame> {
+    let part_rows = rows_for(&df, work_mem / 2);
+    if df.height() <= part_rows { return agg(df); }
+    let key_refs: Vec<&str> = keys.iter().map(|s| s.as_str()).collect();
+    let sorted = df.sort(key_refs, SortMultipleOptions::default().with_maintain_order(true))?;
+    drop(df);
+
+    // Rows where a new group starts
+    let height = sorted.height();
+    let mut starts = vec![false; height];
+    for k in keys {
+        let s = sorted.column(k)?.as_materialized_series().clone();
+        let changed = s.not_equal_missing(&s.shift(1))?;
+        for (i, v) in changed.into_iter().enumerate() {
+            if v == Some(true) { starts[i] = true; }
+        }
+    }
+    let mut cuts: Vec<usize> = vec![0];
+    for (i, is_start) in starts.iter().enumerate() {
+        if *is_start && i >= cuts[cuts.len() - 1] + part_rows { cuts.push(i); }
+    }
+    cuts.push(height);
+    if cuts.len() <= 2 { return agg(sorted); }
+    crate::tprintln!("[spill] partitioned aggregate rows={} partitions={}", height, cuts.len() - 1);
+
+    let mut dir = SpillDir::new()?;
+    let mut parts: Vec<PathBuf> = Vec::with_capacity(cuts.len() - 1);
+    for w in cuts.windows(2) {
+        parts.push(dir.write(&mut sorted.slice(w[0] as i64, w[1] - w[0]))?);
+    }
+    drop(sorted);
+
+    let mut out: Option<DataFrame> = None;
+    for p in &parts {
+        let part = agg(dir.take(p)?)?;
+        match out.as_mut() {
+            Some(o) => { o.vstack_mut(&part)?; }
+            None => out = Some(part),
+        }
+    }
+    Ok(out.unwrap_or_else(DataFrame::empty))
+}
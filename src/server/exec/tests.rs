@@ -88,23 +88,40 @@ pub(super) mod udf_common {
     }
 }
 
+mod activity_tests;
 mod ambiguous_names_tests;
+mod analyze_tests;
 mod ann_no_limit_parity_tests;
 mod ann_order_by_tests;
 mod ann_topk_heap_tests;
+mod attach_tests;
+mod backup_tests;
+mod bloom_filter_tests;
+mod bool_timestamp_tests;
 mod cast_and_regclass_tests;
 mod cast_followups_tests;
+mod cdc_tests;
 mod clause_errors_tests; // File not found
+mod collation_tests;
+mod computed_tests;
+mod config_tests;
+mod copy_tests;
 mod cte_tests;
 mod dbeaver_tests;
+mod decimal_tests;
 mod deadlock_tests;
+mod dedup_tests;
 mod delete_tests;
+mod encryption_tests;
 mod end_to_end_planning_tests;
 mod exists_tests;
+mod explain_tests;
 mod expressive_exec_tests;
 mod exec_show_tests;
+mod file_tvf_tests;
 mod fixtures;
 mod graph_catalog_tests;
+mod graph_dml_tests;
 mod graph_tvf_neighbors_tests;
 mod graph_tvf_paths_tests;
 mod graphstore_gc_tests;
@@ -115,30 +132,59 @@ mod column_name_resolution_tests;
 mod exec_helpers_qualify_tests;
 mod from_where_defaults_tests;
 mod group_by_tests;
+mod guc_tests;
 mod having_tests;
 mod having_tests2;
+mod hints_tests;
+mod http_auth_tests;
+mod http_layers_tests;
+mod influx_tests;
+mod ingest_mode_tests;
+mod ingest_tests;
 mod insert_tests;
 mod intermittent_failure_test;
+mod jobs_tests;
 mod join_inner_tests;
 mod join_outer_tests;
+mod kv_batch_tests;
+mod kv_collection_tests;
+mod kv_expiry_tests;
+mod kv_watch_tests;
+mod late_data_tests;
 mod like_tests;
+mod lua_aggregate_tests;
+mod lua_tvf_tests;
 mod match_rewrite_tests;
 mod match_view_tests;
+mod memory_tests;
 mod metric_semantics_tests;
 mod nested_exists_tests;
 mod normalize_tests;
+mod null_ingest_tests;
+mod openapi_tests;
 mod order_mode_tests;
+mod partition_tests;
 mod perf_tests;
 mod perf_tests_month;
 mod pg_catalog_tests;
 mod primary_key_tests;
+mod prometheus_tests;
+mod procedures_tests;
+mod quota_tests;
 mod quick_checks_udf;
 mod raw_tests;
+mod replication_tests;
+mod result_cache_tests;
 mod row_id_mapping_tests;
+mod scheduler_tests;
+mod script_test_harness_tests;
+mod select_into_tests;
 mod rolling_tests;
 mod session_defaults_tests;
 mod show_describe_tests;
+mod slice_adjust_tests;
 mod slice_blend_tests;
+mod slice_catalog_tests;
 mod slice_manual_tests;
 mod slice_tests;
 mod slice_tests_more;
@@ -149,23 +195,30 @@ mod stress_concurrency;
 mod stress_concurrency_no_udf;
 mod string_slice_tests;
 mod system_table_tests;
+mod telemetry_tests;
 mod calculation_tests;
 mod test_views;
 mod tests_udf;
 mod time_table_by_tests;
+mod triggers_tests;
+mod ts_tvf_tests;
 mod udf_lua_direct_tests;
 mod udf_startup_tests;
 mod udf_vectors_tests;
 mod udf_vectors_simple_tests;
 mod union_select_tests;
 mod unnamed_and_join_tests;
+mod update_tests;
 mod vector_column_type_tests;
 mod vector_hnsw_smoke;
+mod vector_hybrid_search_tests;
 mod vector_index_ddl_tests;
 mod vector_index_modes_tests;
 mod vector_index_runtime_tests;
+mod vector_ivfpq_tests;
 mod vector_tvf_tests;
 mod vector_utils_tests;
+mod verify_tests;
 mod views_with_tvfs_tests;
 mod where_tests;
 mod windowing_tests;
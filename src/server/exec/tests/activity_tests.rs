@@ -0,0 +1,138 @@
+use super::super::execute_query;
+use crate::server::activity::{self, BackendGuard, Frontend};
+use crate::server::query::{parse, Command};
+use crate::storage::SharedStore;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_pg_stat_activity_shows_running_statement_and_cancel_stops_it() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let pid = activity::register(Frontend::Pgwire, "alice", "clarium", "127.0.0.1:5555", None);
+    let _guard = BackendGuard(pid);
+
+    let (started_tx, started_rx) = tokio::sync::oneshot::channel::<()>();
+    let running = tokio::spawn(async move {
+        activity::run_statement(Some(pid), "SELECT slow()", async move {
+            let _ = started_tx.send(());
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            Ok(())
+        }).await
+    });
+    started_rx.await.unwrap();
+
+    let q = format!("SELECT usename, datname, state, query, application_name FROM pg_catalog.pg_stat_activity WHERE pid = {}", pid);
+    let rows = execute_query(&shared, &q).await.unwrap();
+    let row = &rows.as_array().unwrap()[0];
+    assert_eq!(row["usename"], json!("alice"));
+    assert_eq!(row["datname"], json!("clarium"));
+    assert_eq!(row["state"], json!("active"));
+    assert_eq!(row["query"], json!("SELECT slow()"));
+    assert_eq!(row["application_name"], json!("pgwire"));
+
+    let out = execute_query(&shared, &format!("KILL QUERY {}", pid)).await.unwrap();
+    assert_eq!(out["action"], json!("cancel"));
+    let err = tokio::time::timeout(std::time::Duration::from_secs(5), running).await.unwrap().unwrap().unwrap_err();
+    assert!(activity::is_cancelled_error(&err), "{}", err);
+    assert_eq!(err.to_string(), "canceling statement due to user request");
+
+    let rows = execute_query(&shared, &q).await.unwrap();
+    assert_eq!(rows.as_array().unwrap()[0]["state"], json!("idle"));
+}
+
+#[tokio::test]
+async fn test_terminate_backend_and_kill_syntax() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let pid = activity::register(Frontend::Http, "bob", "clarium", "10.0.0.1", Some("sid-terminate-test".into()));
+    let _guard = BackendGuard(pid);
+    assert_eq!(activity::session_pid("sid-terminate-test"), Some(pid));
+
+    let rows = execute_query(&shared, &format!("SELECT pg_terminate_backend({}) AS t", pid)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap()[0]["t"], json!(true));
+    assert!(activity::is_terminated(pid));
+    // The termination is observable by the session's frontend even when it was idle
+    tokio::time::timeout(std::time::Duration::from_secs(5), activity::terminated(pid)).await.unwrap();
+
+    assert!(execute_query(&shared, "KILL 2147483000").await.is_err());
+    match parse("KILL CONNECTION 42").unwrap() {
+        Command::Kill { pid, query_only } => { assert_eq!(pid, 42); assert!(!query_only); }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(parse("KILL abc").is_err());
+}
+
+#[tokio::test]
+async fn test_pg_backend_pid_inside_statement() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let pid = activity::register(Frontend::Pgwire, "carol", "clarium", "", None);
+    let _guard = BackendGuard(pid);
+    let rows = activity::run_statement(Some(pid), "SELECT pg_backend_pid()", execute_query(&shared, "SELECT pg_backend_pid() AS p")).await.unwrap();
+    assert_eq!(rows.as_array().unwrap()[0]["p"], json!(pid));
+}
+
+#[tokio::test]
+async fn test_pg_stat_database_counts_statements_rows_and_blocks() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "CREATE TABLE statsdb/public/t (a BIGINT)").await.unwrap();
+    execute_query(&shared, "INSERT INTO statsdb/public/t (a) VALUES (1), (2), (3)").await.unwrap();
+    let pid = activity::register(Frontend::Pgwire, "dave", "statsdb", "", None);
+    let _guard = BackendGuard(pid);
+
+    let sql = "SELECT a FROM statsdb/public/t";
+    activity::run_statement(Some(pid), sql, execute_query(&shared, sql)).await.unwrap();
+    let bad = "SELECT a FROM statsdb/public/missing";
+    assert!(activity::run_statement(Some(pid), bad, execute_query(&shared, bad)).await.is_err());
+
+    let rows = execute_query(&shared, "SELECT numbackends, xact_commit, xact_rollback, tup_returned, blks_read FROM pg_catalog.pg_stat_database WHERE datname = 'statsdb'").await.unwrap();
+    let row = &rows.as_array().unwrap()[0];
+    assert_eq!(row["numbackends"], json!(1));
+    assert_eq!(row["xact_commit"], json!(1));
+    assert_eq!(row["xact_rollback"], json!(1));
+    assert_eq!(row["tup_returned"], json!(3));
+    assert!(row["blks_read"].as_i64().unwrap() >= 1, "{}", row);
+}
+
+#[tokio::test]
+async fn test_session_cap_idle_expiry_and_kill_session() {
+    use crate::identity::{Principal, SessionManager};
+    use std::time::Duration;
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let who = || Principal { user_id: "sess_cap".into(), ..Default::default() };
+    let sm = SessionManager { ttl: Duration::from_secs(3600), idle: Duration::from_secs(3600), max_per_user: 2 };
+
+    // A third login ends the oldest session and closes its connection
+    let first = sm.issue(who());
+    let pid = activity::register(Frontend::Pgwire, "sess_cap", "clarium", "", Some(first.session_id.clone()));
+    let _guard = BackendGuard(pid);
+    let second = sm.issue(who());
+    let third = sm.issue(who());
+    assert!(sm.validate(&first.token).is_none());
+    assert!(activity::is_terminated(pid));
+    let rows = execute_query(&shared, "SELECT session_id FROM pg_catalog.clarium_sessions WHERE usename = 'sess_cap' ORDER BY issued_at").await.unwrap();
+    let ids: Vec<&str> = rows.as_array().unwrap().iter().map(|r| r["session_id"].as_str().unwrap()).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&second.session_id.as_str()) && ids.contains(&third.session_id.as_str()));
+
+    // Admin revocation
+    execute_query(&shared, &format!("KILL SESSION '{}'", second.session_id)).await.unwrap();
+    assert!(sm.validate(&second.token).is_none());
+    assert!(execute_query(&shared, &format!("KILL SESSION '{}'", second.session_id)).await.is_err());
+    let out = execute_query(&shared, "KILL SESSIONS FOR USER sess_cap").await.unwrap();
+    assert_eq!(out["sessions"], json!(1));
+    assert!(sm.validate(&third.token).is_none());
+
+    // Idle expiry
+    let s = sm.issue(who());
+    std::thread::sleep(Duration::from_millis(5));
+    let strict = SessionManager { idle: Duration::from_millis(1), ..sm };
+    assert!(strict.validate(&s.token).is_none());
+
+    match parse("KILL SESSION 'abc'").unwrap() {
+        Command::KillSession { session_id } => assert_eq!(session_id, "abc"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
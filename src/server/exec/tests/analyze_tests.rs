@@ -0,0 +1,68 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use serde_json::json;
+
+/// 100 readings: v = 0..99, grp cycles through 4 values, label is null for every fifth row.
+fn seed(shared: &SharedStore, table: &str) {
+    let recs: Vec<Record> = (0..100i64).map(|i| {
+        let mut m = serde_json::Map::new();
+        m.insert("v".into(), json!(i));
+        m.insert("grp".into(), json!(i % 4));
+        m.insert("label".into(), if i % 5 == 0 { json!(null) } else { json!(format!("l{}", i % 3)) });
+        Record { _time: 1_000 + i * 1_000, sensors: m }
+    }).collect();
+    shared.0.lock().write_records(table, &recs).unwrap();
+}
+
+async fn estimate(shared: &SharedStore, sql: &str, stage: &str) -> serde_json::Value {
+    let plan = execute_query(shared, &format!("EXPLAIN (FORMAT JSON) {}", sql)).await.unwrap();
+    let stages = plan["explain"]["stages"].as_array().unwrap().clone();
+    stages.iter().find(|s| s["name"] == json!(stage)).unwrap()["estimated_rows"].clone()
+}
+
+#[tokio::test]
+async fn test_analyze_column_statistics() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/an_stats.time";
+    seed(&shared, table);
+
+    let res = execute_query(&shared, &format!("ANALYZE {}", table)).await.unwrap();
+    assert_eq!(res[0]["rows"], json!(100));
+    let stats = shared.0.lock().table_stats(table).unwrap();
+    let v = &stats.columns["v"];
+    assert_eq!((v.min.clone(), v.max.clone()), (Some(json!(0)), Some(json!(99))));
+    assert_eq!(v.n_distinct, 100.0);
+    assert_eq!(stats.columns["grp"].n_distinct, 4.0);
+    assert_eq!(stats.columns["label"].null_frac, 0.2);
+    assert_eq!(stats.columns["label"].n_distinct, 3.0);
+
+    let rows = execute_query(&shared, "SELECT attname, null_frac, n_distinct, histogram_bounds FROM pg_catalog.pg_stats WHERE tablename = 'an_stats.time'").await.unwrap();
+    let row = |name: &str| rows.as_array().unwrap().iter().find(|r| r["attname"] == json!(name)).cloned().unwrap();
+    // Distinct counts that grow with the table are reported as a negative fraction
+    assert_eq!(row("v")["n_distinct"], json!(-1.0));
+    assert_eq!(row("v")["histogram_bounds"], json!("{0,9,19,29,39,49,59,69,79,89,99}"));
+    assert_eq!(row("grp")["n_distinct"], json!(4.0));
+    assert_eq!(row("label")["null_frac"], json!(0.2));
+
+    let stat = execute_query(&shared, "SELECT staattnum, stakind1 FROM pg_catalog.pg_statistic WHERE stakind1 = 2").await.unwrap();
+    assert_eq!(stat.as_array().unwrap().len(), 4, "{}", stat);
+}
+
+#[tokio::test]
+async fn test_explain_uses_analyzed_statistics() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/an_explain.time";
+    seed(&shared, table);
+
+    // Without statistics a filter keeps a third
+    assert_eq!(estimate(&shared, &format!("SELECT v FROM {} WHERE grp = 1", table), "Scan").await, json!(33));
+
+    execute_query(&shared, "ANALYZE").await.unwrap();
+    assert_eq!(estimate(&shared, &format!("SELECT v FROM {} WHERE grp = 1", table), "Scan").await, json!(25));
+    assert_eq!(estimate(&shared, &format!("SELECT v FROM {} WHERE v < 25", table), "Scan").await, json!(26));
+    assert_eq!(estimate(&shared, &format!("SELECT v FROM {} WHERE label IS NULL", table), "Scan").await, json!(20));
+    assert_eq!(estimate(&shared, &format!("SELECT v FROM {} WHERE 1 = grp AND label IS NOT NULL", table), "Scan").await, json!(20));
+    assert_eq!(estimate(&shared, &format!("SELECT grp, COUNT(v) FROM {} GROUP BY grp", table), "GroupBy").await, json!(4));
+}
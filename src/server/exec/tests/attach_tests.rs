@@ -0,0 +1,74 @@
+use super::super::execute_query;
+use crate::server::exec::tests::fixtures::rec;
+use crate::storage::SharedStore;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_attach_routes_database_to_external_folder() {
+    let tmp = tempfile::tempdir().unwrap();
+    let ext = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let ext_db = ext.path().join("archive");
+
+    let res = execute_query(&shared, &format!("ATTACH DATABASE '{}' AS archive", ext_db.display())).await.unwrap();
+    assert_eq!(res["status"], json!("ok"));
+    assert!(ext_db.is_dir());
+
+    let table = "archive/public/metrics.time";
+    shared.0.lock().write_records(table, &[rec(1_000, 1.0), rec(2_000, 2.0)]).unwrap();
+    assert!(ext_db.join("public").join("metrics.time").join("schema.json").exists());
+    assert!(!tmp.path().join("archive").exists());
+    let rows = execute_query(&shared, "SELECT _time, v FROM archive/public/metrics.time").await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+
+    // The attached name is taken and cannot be dropped or renamed in place
+    assert!(execute_query(&shared, "CREATE DATABASE archive").await.is_err());
+    assert!(execute_query(&shared, "DROP DATABASE archive").await.is_err());
+    assert!(execute_query(&shared, &format!("ATTACH DATABASE '{}' AS archive", ext.path().join("other").display())).await.is_err());
+
+    // Detach forgets the mapping but keeps the files; re-attaching brings the data back
+    execute_query(&shared, "DETACH DATABASE archive").await.unwrap();
+    assert!(!crate::storage::attach::attached(tmp.path()).contains_key("archive"));
+    assert!(ext_db.join("public").join("metrics.time").join("schema.json").exists());
+    execute_query(&shared, &format!("ATTACH DATABASE '{}' AS archive2", ext_db.display())).await.unwrap();
+    let rows = execute_query(&shared, "SELECT _time, v FROM archive2/public/metrics.time").await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_attach_rejects_relative_and_nested_paths() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    assert!(execute_query(&shared, "ATTACH DATABASE 'relative/dir' AS rel").await.is_err());
+    let inside = tmp.path().join("nested");
+    assert!(execute_query(&shared, &format!("ATTACH DATABASE '{}' AS nested", inside.display())).await.is_err());
+    assert!(execute_query(&shared, "DETACH DATABASE missing").await.is_err());
+}
+
+#[tokio::test]
+async fn test_attach_migrates_legacy_folders_and_refuses_newer_ones() {
+    let tmp = tempfile::tempdir().unwrap();
+    let ext = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let version = crate::storage::migrate::FORMAT_VERSION;
+
+    // A folder from an older build is upgraded on attach, like the root at startup
+    let old = ext.path().join("old").join("public").join("m.time");
+    std::fs::create_dir_all(&old).unwrap();
+    std::fs::write(old.join("schema.json"), r#"{"v":"float64"}"#).unwrap();
+    execute_query(&shared, &format!("ATTACH DATABASE '{}' AS old", ext.path().join("old").display())).await.unwrap();
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(old.join("schema.json")).unwrap()).unwrap();
+    assert_eq!(v["formatVersion"], json!(version));
+    assert_eq!(v["columns"]["v"], json!("float64"));
+    assert!(crate::storage::migrate::load_history(tmp.path()).iter().any(|h| h["table"] == json!("old/public/m.time")));
+
+    // A folder from a newer build is refused and left as it was
+    let newer = ext.path().join("newer").join("public").join("t");
+    std::fs::create_dir_all(&newer).unwrap();
+    let text = serde_json::to_string(&json!({"formatVersion": version + 1, "columns": {}})).unwrap();
+    std::fs::write(newer.join("schema.json"), &text).unwrap();
+    let err = execute_query(&shared, &format!("ATTACH DATABASE '{}' AS newer", ext.path().join("newer").display())).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("refusing to open"), "unexpected error: {:#}", err);
+    assert!(!crate::storage::attach::attached(tmp.path()).contains_key("newer"));
+    assert_eq!(std::fs::read_to_string(newer.join("schema.json")).unwrap(), text);
+}
@@ -0,0 +1,124 @@
+use super::super::execute_query;
+use crate::server::exec::tests::fixtures::rec;
+use crate::storage::{Record, SharedStore};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_backup_and_point_in_time_restore() {
+    let tmp = tempfile::tempdir().unwrap();
+    let bk = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "bkdb/public/metrics.time";
+    shared.0.lock().write_records(table, &[rec(1_000, 1.0), rec(2_000, 2.0)]).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let cut = chrono::Utc::now().timestamp_millis();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    shared.0.lock().write_records(table, &[rec(3_000, 3.0)]).unwrap();
+
+    let target = bk.path().join("snap");
+    let res = execute_query(&shared, &format!("BACKUP DATABASE bkdb TO '{}'", target.display())).await.unwrap();
+    assert_eq!(res["status"], json!("ok"));
+    assert!(target.join("backup.json").exists());
+    let manifest = crate::storage::backup::read_manifest(&target).unwrap();
+    assert_eq!(manifest.files.iter().filter(|f| f.written_at.is_some()).count(), 2);
+
+    // Full restore under a new name
+    execute_query(&shared, &format!("RESTORE DATABASE bkfull FROM '{}'", target.display())).await.unwrap();
+    let rows = execute_query(&shared, "SELECT _time, v FROM bkfull/public/metrics.time").await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+
+    // Point-in-time restore drops the chunk written after the cut
+    let res = execute_query(&shared, &format!("RESTORE DATABASE bkpit FROM '{}' AS OF {}", target.display(), cut)).await.unwrap();
+    assert_eq!(res["skipped_chunks"], json!(1));
+    assert_eq!(res["rewritten_chunks"], json!([]));
+    let rows = execute_query(&shared, "SELECT _time, v FROM bkpit/public/metrics.time").await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+
+    // Existing databases are never overwritten
+    let err = execute_query(&shared, &format!("RESTORE DATABASE bkdb FROM '{}'", target.display())).await.unwrap_err();
+    assert!(err.to_string().contains("already exists"));
+}
+
+#[test]
+fn test_backup_restore_parse() {
+    use crate::server::query::{parse, Command};
+    match parse("BACKUP DATABASE clarium TO 's3://bucket/nightly'").unwrap() {
+        Command::BackupDatabase { database, target } => { assert_eq!(database, "clarium"); assert_eq!(target, "s3://bucket/nightly"); }
+        other => panic!("unexpected: {:?}", other),
+    }
+    match parse("RESTORE DATABASE c2 FROM '/tmp/b' AS OF '1970-01-01T00:00:01Z'").unwrap() {
+        Command::RestoreDatabase { as_of, .. } => assert_eq!(as_of, Some(1_000)),
+        other => panic!("unexpected: {:?}", other),
+    }
+    assert!(parse("BACKUP DATABASE clarium").is_err());
+}
+
+#[tokio::test]
+async fn test_point_in_time_restore_replays_tombstones_and_sidecars() {
+    let tmp = tempfile::tempdir().unwrap();
+    let bk = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "bktomb/public/metrics.time";
+    let rows: Vec<Record> = (1..=8).map(|i| rec(i * 1_000, i as f64)).collect();
+    shared.0.lock().write_records(table, &rows).unwrap();
+    execute_query(&shared, &format!("DELETE FROM {} WHERE v = 1", table)).await.unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let cut = chrono::Utc::now().timestamp_millis();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    // Two of eight rows stay under the rewrite threshold, so both deletes are tombstones
+    execute_query(&shared, &format!("DELETE FROM {} WHERE v = 2", table)).await.unwrap();
+    shared.0.lock().write_records(table, &[rec(9_000, 9.0)]).unwrap();
+
+    let target = bk.path().join("snap");
+    execute_query(&shared, &format!("BACKUP DATABASE bktomb TO '{}'", target.display())).await.unwrap();
+    let res = execute_query(&shared, &format!("RESTORE DATABASE bktombpit FROM '{}' AS OF {}", target.display(), cut)).await.unwrap();
+    assert_eq!(res["skipped_chunks"], json!(1));
+
+    // The delete before the cut holds, the one after it is undone, the late chunk is gone
+    let rows = execute_query(&shared, "SELECT v FROM bktombpit/public/metrics.time ORDER BY v").await.unwrap();
+    let vs: Vec<f64> = rows.as_array().unwrap().iter().map(|r| r["v"].as_f64().unwrap()).collect();
+    assert_eq!(vs, vec![2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+
+    // No sidecar is restored without its chunk
+    let chunks = shared.0.lock().chunk_paths("bktombpit/public/metrics.time").unwrap();
+    let dir = chunks[0].parent().unwrap();
+    for ent in std::fs::read_dir(&dir).unwrap().flatten() {
+        let name = ent.file_name().to_string_lossy().to_string();
+        if let Some(chunk) = [".bloom", ".crc", ".tomb"].iter().find_map(|s| name.strip_suffix(s)) {
+            assert!(dir.join(chunk).exists(), "orphan sidecar {}", name);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_point_in_time_restore_reports_chunks_rewritten_after_the_cut() {
+    let tmp = tempfile::tempdir().unwrap();
+    let bk = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "bkcomp/public/metrics.time";
+    shared.0.lock().write_records(table, &[rec(1_000, 1.0)]).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    shared.0.lock().write_records(table, &[rec(2_000, 2.0)]).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let cut = chrono::Utc::now().timestamp_millis();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    execute_query(&shared, &format!("COMPACT TABLE {}", table)).await.unwrap();
+
+    let target = bk.path().join("snap");
+    execute_query(&shared, &format!("BACKUP DATABASE bkcomp TO '{}'", target.display())).await.unwrap();
+    let manifest = crate::storage::backup::read_manifest(&target).unwrap();
+    let compacted: Vec<_> = manifest.files.iter().filter(|f| f.written_at.is_some()).collect();
+    assert_eq!(compacted.len(), 1);
+    assert!(compacted[0].replaced_since.is_some_and(|s| s <= cut));
+
+    // The compacted chunk is left out and named, since the rows it holds predate the cut
+    let res = execute_query(&shared, &format!("RESTORE DATABASE bkcomppit FROM '{}' AS OF {}", target.display(), cut)).await.unwrap();
+    assert_eq!(res["skipped_chunks"], json!(1));
+    assert_eq!(res["rewritten_chunks"], json!([compacted[0].path.clone()]));
+
+    // A full restore keeps the chunk and reports nothing
+    let res = execute_query(&shared, &format!("RESTORE DATABASE bkcompnow FROM '{}'", target.display())).await.unwrap();
+    assert_eq!(res["rewritten_chunks"], json!([]));
+    let rows = execute_query(&shared, "SELECT v FROM bkcompnow/public/metrics.time").await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,75 @@
+use super::super::execute_query;
+use crate::storage::{Store, SharedStore, Record};
+use serde_json::json;
+
+fn device_batch(start_ms: i64, device: &str, n: i64) -> Vec<Record> {
+    let mut recs: Vec<Record> = Vec::with_capacity(n as usize);
+    for i in 0..n {
+        let mut m = serde_json::Map::new();
+        m.insert("device_id".into(), json!(device));
+        m.insert("value".into(), json!(i as f64));
+        recs.push(Record { _time: start_ms + i * 1_000, sensors: m });
+    }
+    recs
+}
+
+fn sidecar_count(store: &Store, table: &str) -> usize {
+    let dir = store.root_path().join(table.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+    std::fs::read_dir(&dir).unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().ends_with(".parquet.bloom"))
+        .count()
+}
+
+#[tokio::test]
+async fn test_set_bloom_builds_sidecars_and_prunes_chunks() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let table = "clarium/public/bloom_dev.time";
+    store.write_records(table, &device_batch(1_700_000_000_000, "dev-a", 5)).unwrap();
+    store.write_records(table, &device_batch(1_700_000_100_000, "dev-b", 5)).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    assert_eq!(sidecar_count(&store, table), 0);
+
+    // Enabling bloom filters backfills sidecars for existing chunks
+    let res = execute_query(&shared, &format!("ALTER TABLE {} SET BLOOM (device_id)", table)).await.unwrap();
+    assert_eq!(res["status"], json!("ok"));
+    assert_eq!(store.get_bloom_columns(table), vec!["device_id".to_string()]);
+    assert_eq!(sidecar_count(&store, table), 2);
+
+    // New chunks get a sidecar at write time
+    store.write_records(table, &device_batch(1_700_000_200_000, "dev-c", 5)).unwrap();
+    assert_eq!(sidecar_count(&store, table), 3);
+
+    // Storage-level pruning only reads chunks that may contain the value
+    let cols = vec!["device_id".to_string(), "value".to_string()];
+    let pruned = store
+        .filter_df_pruned(table, &cols, None, None, &[("device_id".to_string(), "dev-b".to_string())])
+        .unwrap();
+    assert_eq!(pruned.height(), 5);
+    let none = store
+        .filter_df_pruned(table, &cols, None, None, &[("device_id".to_string(), "missing".to_string())])
+        .unwrap();
+    assert_eq!(none.height(), 0);
+
+    // Query results are unaffected by pruning
+    let res = execute_query(&shared, &format!("SELECT value FROM {} WHERE device_id = 'dev-c'", table)).await.unwrap();
+    assert_eq!(res.as_array().unwrap().len(), 5);
+    let res = execute_query(&shared, &format!("SELECT value FROM {} WHERE device_id = 'nope'", table)).await.unwrap();
+    assert!(res.as_array().unwrap().is_empty());
+
+    // Clearing the setting removes sidecars
+    execute_query(&shared, &format!("ALTER TABLE {} SET BLOOM ()", table)).await.unwrap();
+    assert!(store.get_bloom_columns(table).is_empty());
+    assert_eq!(sidecar_count(&store, table), 0);
+}
+
+#[test]
+fn test_bloom_filter_membership() {
+    use crate::storage::bloom::BloomFilter;
+    let mut bf = BloomFilter::with_capacity(1000);
+    for i in 0..1000 { bf.insert(&format!("dev-{}", i)); }
+    for i in 0..1000 { assert!(bf.may_contain(&format!("dev-{}", i))); }
+    let fp = (0..1000).filter(|i| bf.may_contain(&format!("other-{}", i))).count();
+    assert!(fp < 50, "false positive count too high: {}", fp);
+}
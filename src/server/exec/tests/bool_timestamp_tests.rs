@@ -0,0 +1,51 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use polars::prelude::*;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_boolean_and_timestamp_columns_are_stored_natively() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/native_types";
+    execute_query(&shared, &format!("CREATE TABLE {} (id int, flag boolean, at timestamp)", table)).await.unwrap();
+    execute_query(&shared, &format!(
+        "INSERT INTO {} (id, flag, at) VALUES (1, true, '2024-05-01 12:00:00'), (2, 'f', 1714564800123), (3, NULL, '2024-05-01T12:00:00+02:00')",
+        table
+    )).await.unwrap();
+
+    let df = shared.0.lock().read_df(table).unwrap();
+    assert_eq!(df.column("flag").unwrap().dtype(), &DataType::Boolean);
+    assert_eq!(df.column("at").unwrap().dtype(), &DataType::Datetime(TimeUnit::Milliseconds, None));
+
+    let res = execute_query(&shared, &format!("SELECT id, flag, at FROM {} ORDER BY id", table)).await.unwrap();
+    let rows = res.as_array().unwrap();
+    assert_eq!(rows[0]["flag"], json!(true));
+    assert_eq!(rows[1]["flag"], json!(false));
+    assert_eq!(rows[2]["flag"], serde_json::Value::Null);
+    assert_eq!(rows[0]["at"], json!("2024-05-01 12:00:00"));
+    assert_eq!(rows[1]["at"], json!("2024-05-01 12:00:00.123"));
+    // Offsets are converted to UTC
+    assert_eq!(rows[2]["at"], json!("2024-05-01 10:00:00"));
+}
+
+#[tokio::test]
+async fn test_json_booleans_infer_a_boolean_column() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/flags.time";
+    let rec = |t: i64, v: serde_json::Value| {
+        let mut sensors = serde_json::Map::new();
+        sensors.insert("ok".into(), v);
+        Record { _time: t, sensors }
+    };
+    shared.0.lock().write_records(table, &[rec(1_000, json!(true)), rec(2_000, json!(false))]).unwrap();
+    // A later batch sending 0/1 keeps the column boolean
+    shared.0.lock().write_records(table, &[rec(3_000, json!(1))]).unwrap();
+
+    let df = shared.0.lock().read_df(table).unwrap();
+    let ok = df.column("ok").unwrap();
+    assert_eq!(ok.dtype(), &DataType::Boolean);
+    let vals: Vec<Option<bool>> = ok.as_materialized_series().bool().unwrap().iter().collect();
+    assert_eq!(vals, vec![Some(true), Some(false), Some(true)]);
+}
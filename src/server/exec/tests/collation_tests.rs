@@ -0,0 +1,96 @@
+use super::super::execute_query;
+use crate::storage::SharedStore;
+use serde_json::json;
+
+async fn fruit_table(shared: &SharedStore, table: &str) {
+    execute_query(shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+    execute_query(shared, &format!(
+        "INSERT INTO {} (id, name) VALUES (1, 'banana'), (2, 'Apple'), (3, 'Zebra'), (4, 'apple'), (5, 'Éclair'), (6, 'eclair')",
+        table
+    )).await.unwrap();
+}
+
+fn names(res: &serde_json::Value) -> Vec<String> {
+    res.as_array().unwrap().iter().map(|r| r["name"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_order_by_collate_orders_by_letters_then_accents_then_case() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/coll_order";
+    fruit_table(&shared, table).await;
+
+    let res = execute_query(&shared, &format!("SELECT name FROM {} ORDER BY name", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["Apple", "Zebra", "apple", "banana", "eclair", "Éclair"]);
+
+    let res = execute_query(&shared, &format!("SELECT name FROM {} ORDER BY name COLLATE \"codepoint\"", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["apple", "Apple", "banana", "eclair", "Éclair", "Zebra"]);
+
+    let res = execute_query(&shared, &format!("SELECT name FROM {} ORDER BY name COLLATE \"codepoint\" DESC LIMIT 2", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["Zebra", "Éclair"]);
+
+    let err = execute_query(&shared, &format!("SELECT name FROM {} ORDER BY name COLLATE \"nope\"", table)).await.unwrap_err();
+    assert!(err.to_string().contains("collation \"nope\" does not exist"), "{}", err);
+    // No language tailoring is available, so locale collations are refused rather than approximated
+    for locale in ["de-DE", "sv-SE", "en-US-ci"] {
+        let err = execute_query(&shared, &format!("SELECT name FROM {} ORDER BY name COLLATE \"{}\"", table, locale)).await.unwrap_err();
+        assert!(err.to_string().contains(&format!("collation \"{}\" is not supported", locale)), "{}", err);
+    }
+}
+
+#[tokio::test]
+async fn test_where_collate_compares_case_and_accent_insensitively() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/coll_where";
+    fruit_table(&shared, table).await;
+
+    let res = execute_query(&shared, &format!("SELECT name FROM {} WHERE name COLLATE \"codepoint-ci\" = 'APPLE' ORDER BY id", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["Apple", "apple"]);
+    // -ci still tells accents apart; -ai does not
+    let res = execute_query(&shared, &format!("SELECT name FROM {} WHERE name COLLATE \"codepoint-ci\" = 'ECLAIR' ORDER BY id", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["eclair"]);
+    let res = execute_query(&shared, &format!("SELECT name FROM {} WHERE name COLLATE \"codepoint-ai\" = 'ECLAIR' ORDER BY id", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["Éclair", "eclair"]);
+    // Ordered comparisons follow the collation too: 'Zebra' sorts after 'b'
+    let res = execute_query(&shared, &format!("SELECT name FROM {} WHERE name COLLATE \"codepoint-ci\" < 'b' ORDER BY id", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["Apple", "apple"]);
+}
+
+#[tokio::test]
+async fn test_column_collation_applies_until_dropped() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/coll_column";
+    fruit_table(&shared, table).await;
+    execute_query(&shared, &format!("ALTER TABLE {} ALTER COLUMN name SET COLLATION \"codepoint-ci\"", table)).await.unwrap();
+
+    let res = execute_query(&shared, &format!("SELECT name FROM {} WHERE name = 'APPLE' ORDER BY id", table)).await.unwrap();
+    assert_eq!(names(&res), vec!["Apple", "apple"]);
+    let res = execute_query(&shared, &format!("SELECT name FROM {} ORDER BY name LIMIT 3", table)).await.unwrap();
+    assert_eq!(names(&res)[2], "banana");
+    // An explicit COLLATE wins over the column's collation
+    let res = execute_query(&shared, &format!("SELECT name FROM {} WHERE name COLLATE \"C\" = 'APPLE'", table)).await.unwrap();
+    assert!(res.as_array().unwrap().is_empty());
+
+    execute_query(&shared, &format!("ALTER TABLE {} ALTER COLUMN name DROP COLLATION", table)).await.unwrap();
+    let res = execute_query(&shared, &format!("SELECT name FROM {} WHERE name = 'APPLE'", table)).await.unwrap();
+    assert!(res.as_array().unwrap().is_empty());
+
+    let err = execute_query(&shared, &format!("ALTER TABLE {} ALTER COLUMN missing SET COLLATION \"codepoint\"", table)).await.unwrap_err();
+    assert!(err.to_string().contains("column not found"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_pg_collation_lists_available_collations() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let res = execute_query(&shared, "SELECT collname, collprovider, collisdeterministic FROM pg_catalog.pg_collation WHERE collname = 'codepoint-ci'").await.unwrap();
+    let arr = res.as_array().unwrap();
+    assert_eq!(arr.len(), 1);
+    assert_eq!(arr[0]["collprovider"], json!("b"));
+    assert_eq!(arr[0]["collisdeterministic"], json!(false));
+    let res = execute_query(&shared, "SELECT oid FROM pg_catalog.pg_collation WHERE collname = 'C'").await.unwrap();
+    assert_eq!(res[0]["oid"], json!(950));
+}
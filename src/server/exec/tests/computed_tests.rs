@@ -0,0 +1,69 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use serde_json::json;
+
+fn reading(t: i64, c: f64) -> Record {
+    let mut sensors = serde_json::Map::new();
+    sensors.insert("temp_c".into(), json!(c));
+    Record { _time: t, sensors }
+}
+
+fn floats(res: &serde_json::Value, name: &str) -> Vec<Option<f64>> {
+    res.as_array().unwrap().iter().map(|r| r[name].as_f64()).collect()
+}
+
+#[tokio::test]
+async fn test_computed_column_on_ingest_and_backfill() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/comp_temps.time";
+    shared.0.lock().write_records(table, &[reading(1_000, 10.0)]).unwrap();
+
+    // Rows already stored get the column when it is added
+    execute_query(&shared, &format!("ALTER TABLE {} ADD COMPUTED COLUMN temp_f AS temp_c * 9 / 5 + 32", table)).await.unwrap();
+    let res = execute_query(&shared, &format!("SELECT temp_f FROM {}", table)).await.unwrap();
+    assert_eq!(floats(&res, "temp_f"), vec![Some(50.0)]);
+
+    // New batches are computed on write; a value sent for the column is replaced
+    let mut sent = reading(3_000, 100.0);
+    sent.sensors.insert("temp_f".into(), json!(0.0));
+    shared.0.lock().write_records(table, &[reading(2_000, -40.0), sent]).unwrap();
+    let res = execute_query(&shared, &format!("SELECT temp_f FROM {} ORDER BY _time", table)).await.unwrap();
+    assert_eq!(floats(&res, "temp_f"), vec![Some(50.0), Some(-40.0), Some(212.0)]);
+
+    // Dropping the definition keeps the stored values but stops computing new ones
+    execute_query(&shared, &format!("ALTER TABLE {} DROP COMPUTED COLUMN temp_f", table)).await.unwrap();
+    shared.0.lock().write_records(table, &[reading(4_000, 0.0)]).unwrap();
+    let res = execute_query(&shared, &format!("SELECT temp_f FROM {} ORDER BY _time", table)).await.unwrap();
+    assert_eq!(floats(&res, "temp_f"), vec![Some(50.0), Some(-40.0), Some(212.0), None]);
+}
+
+#[tokio::test]
+async fn test_computed_column_on_insert() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/comp_orders";
+    execute_query(&shared, &format!("CREATE TABLE {} (id int, qty int, price double)", table)).await.unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} ADD COMPUTED COLUMN total AS qty * price", table)).await.unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} ADD COMPUTED COLUMN gross AS total * 2", table)).await.unwrap();
+
+    execute_query(&shared, &format!("INSERT INTO {} (id, qty, price) VALUES (1, 2, 2.5), (2, 10, 20)", table)).await.unwrap();
+    // Columns left out of the INSERT read as null
+    execute_query(&shared, &format!("INSERT INTO {} (id, qty) VALUES (3, 4)", table)).await.unwrap();
+    let res = execute_query(&shared, &format!("SELECT total, gross FROM {} ORDER BY id", table)).await.unwrap();
+    assert_eq!(floats(&res, "total"), vec![Some(5.0), Some(200.0), None]);
+    // A definition sees the computed columns declared before it
+    assert_eq!(floats(&res, "gross"), vec![Some(10.0), Some(400.0), None]);
+}
+
+#[tokio::test]
+async fn test_computed_column_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/comp_err";
+    execute_query(&shared, &format!("CREATE TABLE {} (a int)", table)).await.unwrap();
+    let err = execute_query(&shared, &format!("ALTER TABLE {} ADD COMPUTED COLUMN b", table)).await.unwrap_err();
+    assert!(err.to_string().contains("ADD COMPUTED COLUMN expects <name> AS <expr>"), "{}", err);
+    let err = execute_query(&shared, &format!("ALTER TABLE {} DROP COMPUTED COLUMN b", table)).await.unwrap_err();
+    assert!(err.to_string().contains("computed column not found: b"), "{}", err);
+}
@@ -0,0 +1,70 @@
+use super::super::execute_query;
+use crate::config::{self, ConfigSource};
+use crate::server::query::{parse, Command};
+use crate::storage::SharedStore;
+use serde_json::json;
+
+fn entry(entries: &[config::ConfigEntry], key: &str) -> config::ConfigEntry {
+    entries.iter().find(|e| e.key == key).cloned().unwrap_or_else(|| panic!("missing option {}", key))
+}
+
+#[test]
+fn test_config_layers_file_then_cli() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("clarium.toml");
+    std::fs::write(&path, "[server]\nhttp_port = 9000\npg_port = 6543\n\n[limits]\nsession_idle_secs = 60\n\n[filestore]\ngit_branch = \"trunk\"\n").unwrap();
+
+    let cli = vec![("server.http_port".to_string(), "9100".to_string())];
+    let (cfg, entries) = config::build(Some(&path), &cli).unwrap();
+    assert_eq!(cfg.server.http_port, 9100);
+    assert_eq!(cfg.server.pg_port, 6543);
+    assert_eq!(cfg.limits.session_idle_secs, 60);
+    assert_eq!(cfg.filestore.git_branch.as_deref(), Some("trunk"));
+    // Untouched options keep their defaults
+    assert_eq!(cfg.limits.session_abs_secs, 24 * 60 * 60);
+
+    assert_eq!(entry(&entries, "server.http_port").source, ConfigSource::Cli);
+    assert_eq!(entry(&entries, "server.pg_port").source, ConfigSource::File);
+    assert_eq!(entry(&entries, "limits.session_abs_secs").source, ConfigSource::Default);
+    assert!(!entry(&entries, "server.http_port").reloadable);
+    assert!(entry(&entries, "limits.session_idle_secs").reloadable);
+
+    // Typos and wrong types are rejected rather than ignored
+    std::fs::write(&path, "[server]\nhttp_prot = 9000\n").unwrap();
+    assert!(config::build(Some(&path), &[]).unwrap_err().to_string().contains("server.http_prot"));
+    std::fs::write(&path, "[server]\nhttp_port = \"high\"\n").unwrap();
+    assert!(config::build(Some(&path), &[]).is_err());
+    let bad_cli = vec![("server.pgwire".to_string(), "maybe".to_string())];
+    assert!(config::build(None, &bad_cli).is_err());
+}
+
+#[tokio::test]
+async fn test_admin_reload_config_applies_reloadable_and_flags_restart_options() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let path = tmp.path().join("reload.toml");
+    std::fs::write(&path, "[server]\nhttp_port = 9200\n\n[limits]\nsession_abs_secs = 7200\n").unwrap();
+    config::init(Some(&path), Vec::new()).unwrap();
+    assert_eq!(config::current().limits.session_abs_secs, 7200);
+
+    assert!(matches!(parse("ADMIN RELOAD CONFIG").unwrap(), Command::ReloadConfig));
+    assert!(parse("ADMIN RELOAD EVERYTHING").is_err());
+
+    std::fs::write(&path, "[server]\nhttp_port = 9300\n\n[limits]\nsession_abs_secs = 3600\n").unwrap();
+    let out = execute_query(&shared, "ADMIN RELOAD CONFIG").await.unwrap();
+    assert_eq!(out["applied"], json!(["limits.session_abs_secs"]));
+    assert_eq!(out["pending_restart"], json!(["server.http_port"]));
+    let cfg = config::current();
+    assert_eq!(cfg.limits.session_abs_secs, 3600);
+    // Ports only change on restart
+    assert_eq!(cfg.server.http_port, 9200);
+
+    let rows = execute_query(&shared, "SELECT name, setting, source, reloadable, pending_restart FROM pg_catalog.clarium_config WHERE name = 'server.http_port' OR name = 'limits.session_abs_secs' ORDER BY name").await.unwrap();
+    assert_eq!(rows, json!([
+        {"name": "limits.session_abs_secs", "setting": "3600", "source": "file", "reloadable": true, "pending_restart": false},
+        {"name": "server.http_port", "setting": "9200", "source": "file", "reloadable": false, "pending_restart": true},
+    ]));
+
+    // Restore the defaults for other tests sharing the process
+    config::init(None, Vec::new()).unwrap();
+}
@@ -0,0 +1,235 @@
+use super::super::execute_query;
+use crate::server::exec::exec_copy::{CopyExport, CopyIngest};
+use crate::server::query::{self, Command, CopyFormat, CopyOptions, CopyRelation, CopySource};
+use crate::storage::{Store, SharedStore};
+use polars::prelude::*;
+use serde_json::json;
+
+fn chunk_count(store: &Store, table: &str) -> usize {
+    let dir = store.root_path().join(table.replace('/', std::path::MAIN_SEPARATOR.to_string().as_str()));
+    std::fs::read_dir(&dir).unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| { let n = e.file_name().to_string_lossy().to_string(); n.starts_with("data-") && n.ends_with(".parquet") })
+        .count()
+}
+
+#[test]
+fn test_parse_copy_variants() {
+    match query::parse("COPY t (a, b) FROM STDIN WITH (FORMAT csv, HEADER true, DELIMITER ';')").unwrap() {
+        Command::CopyFrom { table, columns, source, options } => {
+            assert_eq!(table, "t");
+            assert_eq!(columns, vec!["a".to_string(), "b".to_string()]);
+            assert_eq!(source, CopySource::Stdin);
+            assert_eq!(options.format, CopyFormat::Csv);
+            assert!(options.header);
+            assert_eq!(options.delimiter, ';');
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match query::parse("COPY t FROM '/tmp/x.parquet' WITH (BATCH_SIZE 10)").unwrap() {
+        Command::CopyFrom { source, options, .. } => {
+            assert_eq!(source, CopySource::File("/tmp/x.parquet".into()));
+            assert_eq!(options.format, CopyFormat::Parquet);
+            assert_eq!(options.batch_size, 10);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match query::parse("COPY t FROM 'https://example.com/d.ndjson?sig=1'").unwrap() {
+        Command::CopyFrom { source, options, .. } => {
+            assert!(matches!(source, CopySource::Url(_)));
+            assert_eq!(options.format, CopyFormat::Ndjson);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    // Legacy unparenthesized options, including a comma delimiter
+    match query::parse("COPY t FROM STDIN CSV HEADER DELIMITER ','").unwrap() {
+        Command::CopyFrom { options, .. } => {
+            assert_eq!(options.format, CopyFormat::Csv);
+            assert_eq!(options.delimiter, ',');
+            assert!(options.header);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(query::parse("COPY t FROM STDIN WITH (BATCH_SIZE 0)").is_err());
+}
+
+#[test]
+fn test_parse_copy_to_stdout() {
+    match query::parse("COPY t (a, b) TO STDOUT WITH (FORMAT csv, HEADER)").unwrap() {
+        Command::CopyTo { relation, columns, options } => {
+            assert_eq!(relation, CopyRelation::Table("t".into()));
+            assert_eq!(columns, vec!["a".to_string(), "b".to_string()]);
+            assert_eq!(options.format, CopyFormat::Csv);
+            assert!(options.header);
+            assert_eq!(options.delimiter, ',');
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match query::parse("COPY (SELECT a FROM t WHERE b > 1) TO STDOUT BINARY").unwrap() {
+        Command::CopyTo { relation, options, .. } => {
+            assert_eq!(relation, CopyRelation::Query("SELECT a FROM t WHERE b > 1".into()));
+            assert_eq!(options.format, CopyFormat::Binary);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match query::parse("COPY t TO STDOUT").unwrap() {
+        Command::CopyTo { options, .. } => assert_eq!((options.format, options.null_str.as_str()), (CopyFormat::Text, "\\N")),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(query::parse("COPY t TO '/tmp/out.csv'").is_err());
+    assert!(query::parse("COPY t TO STDOUT WITH (FORMAT parquet)").is_err());
+}
+
+#[tokio::test]
+async fn test_copy_csv_file_into_time_table_in_batches() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let csv = tmp.path().join("readings.csv");
+    std::fs::write(&csv, "_time,device,value\n\
+        1700000000000,a,1.5\n\
+        1700000001000,b,2.5\n\
+        1700000002000,\"c, quoted\",3.5\n\
+        2023-11-14T22:13:23Z,d,4.5\n\
+        1700000004000,e,5.5\n").unwrap();
+    let table = "clarium/public/copy_readings.time";
+    execute_query(&shared, &format!("CREATE TIME TABLE {}", table)).await.unwrap();
+    let sql = format!("COPY {} FROM '{}' WITH (FORMAT csv, HEADER, BATCH_SIZE 2)", table, csv.display());
+    let res = execute_query(&shared, &sql).await.unwrap();
+    assert_eq!(res["copied"], json!(5));
+    let store = Store::new(tmp.path()).unwrap();
+    // 5 rows at batch size 2 -> three appended chunks
+    assert_eq!(chunk_count(&store, table), 3);
+    let rows = execute_query(&shared, &format!("SELECT device, value FROM {} WHERE device = 'c, quoted'", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    let all = execute_query(&shared, &format!("SELECT device FROM {}", table)).await.unwrap();
+    assert_eq!(all.as_array().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn test_copy_ndjson_and_parquet_append_to_regular_table() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/copy_people";
+    execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+    let nd = tmp.path().join("people.ndjson");
+    std::fs::write(&nd, "{\"name\":\"ann\",\"age\":31}\n{\"name\":\"bob\",\"age\":42}\n").unwrap();
+    let res = execute_query(&shared, &format!("COPY {} FROM '{}'", table, nd.display())).await.unwrap();
+    assert_eq!(res["copied"], json!(2));
+
+    let pq = tmp.path().join("people.parquet");
+    let mut df = df!("name" => &["cat", "dan", "eve"], "age" => &[25i64, 37, 29]).unwrap();
+    ParquetWriter::new(std::fs::File::create(&pq).unwrap()).finish(&mut df).unwrap();
+    let res = execute_query(&shared, &format!("COPY {} FROM '{}' WITH (BATCH_SIZE 2)", table, pq.display())).await.unwrap();
+    assert_eq!(res["copied"], json!(3));
+
+    let rows = execute_query(&shared, &format!("SELECT name, age FROM {} ORDER BY age", table)).await.unwrap();
+    let arr = rows.as_array().unwrap();
+    assert_eq!(arr.len(), 5);
+    assert_eq!(arr[0]["name"], json!("cat"));
+    assert_eq!(arr[4]["age"], json!(42));
+}
+
+#[tokio::test]
+async fn test_copy_ingest_handles_split_frames_and_requires_pgwire_for_stdin() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/copy_frames";
+    execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+    let opts = CopyOptions::for_format(CopyFormat::Csv);
+    let mut ingest = CopyIngest::new(&shared, table, vec!["id".into(), "note".into()], opts).unwrap();
+    // CopyData frames may split rows and quoted fields arbitrarily
+    for frame in ["1,\"multi", "\nline\"\r\n2,pl", "ain\n3,\"say \"\"hi\"\"\"\n"] {
+        ingest.push(frame.as_bytes()).unwrap();
+    }
+    assert_eq!(ingest.finish().unwrap(), 3);
+    let rows = execute_query(&shared, &format!("SELECT note FROM {} ORDER BY id", table)).await.unwrap();
+    let arr = rows.as_array().unwrap();
+    assert_eq!(arr[0]["note"], json!("multi\nline"));
+    assert_eq!(arr[2]["note"], json!("say \"hi\""));
+
+    let err = execute_query(&shared, &format!("COPY {} FROM STDIN", table)).await.unwrap_err();
+    assert!(err.to_string().contains("wire protocol"));
+}
+
+#[tokio::test]
+async fn test_copy_needs_existing_table_and_rejects_bad_values() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let csv = tmp.path().join("typed.csv");
+    std::fs::write(&csv, "id,qty\n1,10\n2,oops\n3,30\n").unwrap();
+
+    // No implicit CREATE TABLE: a missing target is an error and nothing is created
+    let table = "clarium/public/copy_typed";
+    let err = execute_query(&shared, &format!("COPY {} FROM '{}' WITH (FORMAT csv, HEADER)", table, csv.display())).await.unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "unexpected error: {}", err);
+    assert!(!shared.0.lock().schema_path(table).exists());
+
+    // A value that does not parse as its declared type fails with its line number
+    execute_query(&shared, &format!("CREATE TABLE {} (id BIGINT, qty BIGINT)", table)).await.unwrap();
+    let err = execute_query(&shared, &format!("COPY {} FROM '{}' WITH (FORMAT csv, HEADER)", table, csv.display())).await.unwrap_err();
+    let msg = format!("{:#}", err);
+    assert!(msg.contains("line 3") && msg.contains("qty") && msg.contains("oops"), "unexpected error: {}", msg);
+}
+
+#[tokio::test]
+async fn test_copy_binary_decodes_tuples_split_across_frames() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/copy_binary";
+    execute_query(&shared, &format!("CREATE TABLE {} (id BIGINT, note TEXT)", table)).await.unwrap();
+
+    let mut payload = crate::server::exec::exec_copy::PGCOPY_SIGNATURE.to_vec();
+    payload.extend_from_slice(&[0u8; 8]); // flags + header extension length
+    for (id, note) in [(1i64, "one"), (2, "two"), (3, "three")] {
+        payload.extend_from_slice(&2i16.to_be_bytes());
+        payload.extend_from_slice(&8i32.to_be_bytes());
+        payload.extend_from_slice(&id.to_be_bytes());
+        payload.extend_from_slice(&(note.len() as i32).to_be_bytes());
+        payload.extend_from_slice(note.as_bytes());
+    }
+    payload.extend_from_slice(&(-1i16).to_be_bytes());
+
+    let opts = CopyOptions { batch_size: 1, ..CopyOptions::for_format(CopyFormat::Binary) };
+    let mut ingest = CopyIngest::new(&shared, table, vec!["id".into(), "note".into()], opts).unwrap();
+    // Tuples are flushed as soon as they are complete, not at the end of the input
+    for frame in payload.chunks(7) { ingest.push(frame).unwrap(); }
+    let rows = execute_query(&shared, &format!("SELECT id FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+    assert_eq!(ingest.finish().unwrap(), 3);
+
+    // A payload cut off mid-tuple is an error at the end of the input
+    let opts = CopyOptions::for_format(CopyFormat::Binary);
+    let mut ingest = CopyIngest::new(&shared, table, vec!["id".into(), "note".into()], opts).unwrap();
+    ingest.push(&payload[..payload.len() - 6]).unwrap();
+    assert!(ingest.finish().unwrap_err().to_string().contains("unexpected end of data"));
+}
+
+#[tokio::test]
+async fn test_copy_export_reads_one_chunk_per_batch() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/copy_export";
+    execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+    execute_query(&shared, &format!("INSERT INTO {} (id, note) VALUES (1, 'a'), (2, 'b'), (3, 'c')", table)).await.unwrap();
+    execute_query(&shared, &format!("INSERT INTO {} (id) VALUES (4)", table)).await.unwrap();
+
+    let opts = CopyOptions { batch_size: 2, ..CopyOptions::for_format(CopyFormat::Text) };
+    let mut export = CopyExport::for_table(&shared, table, vec!["note".into(), "id".into()], &opts).unwrap();
+    assert_eq!(export.columns(), &["note".to_string(), "id".to_string()]);
+    let mut heights = Vec::new();
+    let mut ids = Vec::new();
+    while let Some(df) = export.next_batch().unwrap() {
+        assert_eq!(df.get_column_names().iter().map(|c| c.as_str()).collect::<Vec<_>>(), vec!["note", "id"]);
+        heights.push(df.height());
+        ids.extend(df.column("id").unwrap().cast(&DataType::Int64).unwrap().i64().unwrap().into_no_null_iter());
+    }
+    ids.sort();
+    assert_eq!(ids, vec![1, 2, 3, 4]);
+    assert_eq!(heights.iter().sum::<usize>(), 4);
+    assert!(heights.iter().all(|h| *h <= 2));
+
+    assert!(CopyExport::for_table(&shared, table, vec!["missing".into()], &opts).is_err());
+    assert!(CopyExport::for_table(&shared, "clarium/public/no_such_table", vec![], &opts).is_err());
+    let err = execute_query(&shared, &format!("COPY {} TO STDOUT", table)).await.unwrap_err();
+    assert!(err.to_string().contains("wire protocol"));
+}
@@ -0,0 +1,80 @@
+use super::super::execute_query;
+use crate::storage::SharedStore;
+
+async fn price_table(shared: &SharedStore, table: &str) {
+    execute_query(shared, &format!("CREATE TABLE {} (id int, price numeric(10,2))", table)).await.unwrap();
+    execute_query(shared, &format!(
+        "INSERT INTO {} (id, price) VALUES (1, 12.345), (2, 0.1), (3, '7')",
+        table
+    )).await.unwrap();
+}
+
+fn col(res: &serde_json::Value, name: &str) -> Vec<String> {
+    res.as_array().unwrap().iter().map(|r| r[name].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_numeric_column_rounds_to_declared_scale() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/dec_round";
+    price_table(&shared, table).await;
+
+    let res = execute_query(&shared, &format!("SELECT price FROM {} ORDER BY id", table)).await.unwrap();
+    assert_eq!(col(&res, "price"), vec!["12.35", "0.10", "7.00"]);
+
+    // Appending keeps the column decimal
+    execute_query(&shared, &format!("INSERT INTO {} (id, price) VALUES (4, 0.2)", table)).await.unwrap();
+    let res = execute_query(&shared, &format!("SELECT price FROM {} WHERE id = 4", table)).await.unwrap();
+    assert_eq!(col(&res, "price"), vec!["0.20"]);
+}
+
+#[tokio::test]
+async fn test_numeric_arithmetic_is_exact() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/dec_arith";
+    price_table(&shared, table).await;
+
+    let res = execute_query(&shared, &format!(
+        "SELECT price + 0.2 AS s, price * 3 AS m, price / 3 AS d FROM {} WHERE id = 2",
+        table
+    )).await.unwrap();
+    assert_eq!(col(&res, "s"), vec!["0.30"]);
+    assert_eq!(col(&res, "m"), vec!["0.30"]);
+    // Division keeps at least six fractional digits
+    assert_eq!(col(&res, "d"), vec!["0.033333"]);
+
+    let res = execute_query(&shared, &format!("SELECT price * 1.1 AS p FROM {} WHERE id = 1", table)).await.unwrap();
+    assert_eq!(col(&res, "p"), vec!["13.585"]);
+
+    let err = execute_query(&shared, &format!("SELECT price / 0 AS z FROM {}", table)).await.unwrap_err();
+    assert!(err.to_string().contains("division by zero"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_numeric_overflow_and_bad_modifiers_are_rejected() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/dec_overflow";
+    price_table(&shared, table).await;
+
+    let err = execute_query(&shared, &format!("INSERT INTO {} (id, price) VALUES (5, 123456789.5)", table)).await.unwrap_err();
+    assert!(err.to_string().contains("numeric field overflow"), "{}", err);
+
+    let err = execute_query(&shared, "CREATE TABLE clarium/public/dec_bad (v numeric(50,2))").await.unwrap_err();
+    assert!(err.to_string().contains("NUMERIC precision 50 must be between 1 and 38"), "{}", err);
+    let err = execute_query(&shared, "CREATE TABLE clarium/public/dec_bad (v numeric(4,6))").await.unwrap_err();
+    assert!(err.to_string().contains("NUMERIC scale 6 must be between 0 and precision 4"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_cast_to_numeric_rounds_half_away_from_zero() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    // 2.675 is 2.67499... as a double; the decimal cast parses the text exactly
+    let res = execute_query(&shared, "SELECT CAST('2.675' AS numeric(5,2)) AS v, CAST('-2.675' AS numeric(5,2)) AS n").await.unwrap();
+    assert_eq!(col(&res, "v"), vec!["2.68"]);
+    assert_eq!(col(&res, "n"), vec!["-2.68"]);
+}
@@ -0,0 +1,57 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use serde_json::json;
+
+fn rec(t: i64, device: &str, v: f64) -> Record {
+    let mut m = serde_json::Map::new();
+    m.insert("device".into(), json!(device));
+    m.insert("v".into(), json!(v));
+    Record { _time: t, sensors: m }
+}
+
+fn batch() -> Vec<Record> {
+    vec![rec(1_000, "a", 1.0), rec(1_000, "b", 2.0), rec(2_000, "a", 3.0)]
+}
+
+#[tokio::test]
+async fn test_dedup_on_write_drops_resent_rows() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/dedup_w.time";
+    shared.0.lock().write_records(table, &batch()).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} ADD PRIMARY KEY (device)", table)).await.unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET DEDUP ON WRITE", table)).await.unwrap();
+
+    // Full re-send is dropped entirely; a partially new batch keeps only the new row
+    shared.0.lock().write_records(table, &batch()).unwrap();
+    shared.0.lock().write_records(table, &[rec(2_000, "a", 9.0), rec(2_000, "b", 4.0), rec(2_000, "b", 4.0)]).unwrap();
+    let rows = execute_query(&shared, &format!("SELECT _time, device, v FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 4);
+    assert_eq!(shared.0.lock().ingest_stats(table).duplicates_dropped, 5);
+
+    let stats = execute_query(&shared, "SELECT relname, duplicates_dropped FROM pg_catalog.pg_stat_ingest WHERE relname = 'dedup_w.time'").await.unwrap();
+    assert_eq!(stats.as_array().unwrap()[0]["duplicates_dropped"], json!(5));
+}
+
+#[tokio::test]
+async fn test_dedup_on_compact_merges_chunks() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/dedup_c.time";
+    shared.0.lock().write_records(table, &batch()).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} ADD PRIMARY KEY (device)", table)).await.unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET DEDUP ON COMPACT", table)).await.unwrap();
+    shared.0.lock().write_records(table, &batch()).unwrap();
+    let rows = execute_query(&shared, &format!("SELECT _time, device, v FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 6);
+
+    let res = execute_query(&shared, &format!("COMPACT TABLE {}", table)).await.unwrap();
+    assert_eq!(res["chunks_before"], json!(2));
+    assert_eq!(res["chunks_after"], json!(1));
+    assert_eq!(res["duplicates_dropped"], json!(3));
+    let rows = execute_query(&shared, &format!("SELECT _time, device, v FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+
+    execute_query(&shared, "CREATE TABLE clarium/public/dedup_plain (id INT)").await.unwrap();
+    assert!(execute_query(&shared, "ALTER TABLE clarium/public/dedup_plain SET DEDUP ON WRITE").await.is_err());
+}
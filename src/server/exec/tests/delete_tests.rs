@@ -55,3 +55,133 @@ async fn test_delete_columns_where_and_drop() {
 }
 
 
+fn write_two_chunks(store: &Store, db: &str, base: i64) {
+    for batch in 0..2i64 {
+        let recs: Vec<Record> = (0..5i64).map(|i| {
+            let mut m = serde_json::Map::new();
+            m.insert("v".into(), json!(batch * 10 + i));
+            Record { _time: base + batch * 60_000 + i * 1000, sensors: m }
+        }).collect();
+        store.write_records(db, &recs).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_delete_skips_chunks_outside_time_bounds() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let db = "clarium/public/db_del_chunks.time";
+    let base: i64 = 1_806_100_000_000;
+    write_two_chunks(&store, db, base);
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let before = shared.0.lock().chunk_paths(db).unwrap();
+    assert_eq!(before.len(), 2);
+
+    // Every row of the second chunk goes; the first chunk is never read
+    let out = execute_query(&shared, &format!("DELETE FROM {} WHERE _time >= {}", db, base + 60_000)).await.unwrap();
+    assert_eq!(out["deleted"], json!(5));
+    assert_eq!(out["chunks_skipped"], json!(1));
+    assert_eq!(out["chunks_scanned"], json!(1));
+    assert_eq!(out["chunks_dropped"], json!(1));
+    let after = shared.0.lock().chunk_paths(db).unwrap();
+    assert_eq!(after, vec![before[0].clone()]);
+    assert_eq!(shared.0.lock().ingest_stats(db).chunks_dropped, 1);
+}
+
+#[tokio::test]
+async fn test_delete_partial_chunk_tombstones_then_compacts() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let db = "clarium/public/db_del_tomb.time";
+    let base: i64 = 1_806_200_000_000;
+    write_two_chunks(&store, db, base);
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let chunks = shared.0.lock().chunk_paths(db).unwrap();
+
+    // One row of five stays under the rewrite threshold: tombstoned, not rewritten
+    let out = execute_query(&shared, &format!("DELETE FROM {} WHERE v = 1", db)).await.unwrap();
+    assert_eq!(out["deleted"], json!(1));
+    assert_eq!(out["chunks_tombstoned"], json!(1));
+    assert_eq!(out["chunks_rewritten"], json!(0));
+    assert!(crate::storage::tombstone::sidecar_path(&chunks[0]).exists());
+    let df = { let g = shared.0.lock(); g.read_df(db).unwrap() };
+    assert_eq!(df.height(), 9);
+    assert!(!df.column("v").unwrap().i64().unwrap().into_no_null_iter().any(|v| v == 1));
+
+    // A second delete in the same chunk crosses the threshold and rewrites it
+    let out = execute_query(&shared, &format!("DELETE FROM {} WHERE v = 3", db)).await.unwrap();
+    assert_eq!(out["chunks_rewritten"], json!(1));
+    let after = shared.0.lock().chunk_paths(db).unwrap();
+    assert!(after.iter().all(|p| !crate::storage::tombstone::sidecar_path(p).exists()));
+    let stats = shared.0.lock().ingest_stats(db);
+    assert_eq!((stats.chunks_rewritten, stats.rows_tombstoned), (1, 1));
+
+    // Tombstones left behind are folded in by compaction
+    execute_query(&shared, &format!("DELETE FROM {} WHERE v = 12", db)).await.unwrap();
+    execute_query(&shared, &format!("COMPACT TABLE {}", db)).await.unwrap();
+    let after = shared.0.lock().chunk_paths(db).unwrap();
+    assert!(after.iter().all(|p| !crate::storage::tombstone::sidecar_path(p).exists()));
+    let out = execute_query(&shared, &format!("SELECT COUNT(*) AS n FROM {}", db)).await.unwrap();
+    assert_eq!(out[0]["n"].as_i64(), Some(7));
+}
+
+#[tokio::test]
+async fn test_corrupt_tombstone_sidecar_fails_the_read() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let db = "clarium/public/db_del_tomb_bad.time";
+    write_two_chunks(&store, db, 1_806_300_000_000);
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let chunks = shared.0.lock().chunk_paths(db).unwrap();
+    execute_query(&shared, &format!("DELETE FROM {} WHERE v = 1", db)).await.unwrap();
+    let sidecar = crate::storage::tombstone::sidecar_path(&chunks[0]);
+    assert!(!sidecar.with_extension("tomb.tmp").exists());
+
+    // A torn sidecar must not bring the deleted row back
+    std::fs::write(&sidecar, b"{\"rows\": [0,").unwrap();
+    let err = { let g = shared.0.lock(); g.read_df(db).unwrap_err() };
+    assert!(err.to_string().contains("corrupt tombstone sidecar"), "{}", err);
+}
+
+#[test]
+fn test_concurrent_deletes_remove_exactly_their_rows() {
+    use crate::server::exec::exec_delete::handle_delete_rows;
+    use crate::server::query::{self, Command};
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let db = "clarium/public/db_del_race.time";
+    let base: i64 = 1_806_400_000_000;
+    let recs: Vec<Record> = (0..20i64).map(|i| {
+        let mut m = serde_json::Map::new();
+        m.insert("v".into(), json!(i));
+        Record { _time: base + i * 1000, sensors: m }
+    }).collect();
+    store.write_records(db, &recs).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let chunk = shared.0.lock().chunk_paths(db).unwrap().remove(0);
+    let before = crate::storage::tombstone::chunk_generation(&chunk).unwrap();
+
+    // Each delete tombstones or rewrites the same chunk, shifting the positions of the others
+    let doomed = [1i64, 3, 4, 8, 11, 15, 16, 19];
+    std::thread::scope(|scope| {
+        for v in doomed {
+            let shared = shared.clone();
+            scope.spawn(move || {
+                let Ok(Command::DeleteRows { database, where_clause }) = query::parse(&format!("DELETE FROM {} WHERE v = {}", db, v)) else { unreachable!() };
+                let out = loop {
+                    match handle_delete_rows(&shared, database.clone(), where_clause.clone()) {
+                        Err(e) if e.to_string().contains("kept changing") => continue,
+                        other => break other.unwrap(),
+                    }
+                };
+                assert_eq!(out["deleted"], json!(1));
+            });
+        }
+    });
+
+    assert_ne!(crate::storage::tombstone::chunk_generation(&chunk).unwrap(), before);
+    let df = { let g = shared.0.lock(); g.read_df(db).unwrap() };
+    let mut left: Vec<i64> = df.column("v").unwrap().i64().unwrap().into_no_null_iter().collect();
+    left.sort();
+    assert_eq!(left, (0..20i64).filter(|v| !doomed.contains(v)).collect::<Vec<_>>());
+}
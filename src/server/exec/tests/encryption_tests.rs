@@ -0,0 +1,128 @@
+use super::super::execute_query;
+use crate::server::exec::tests::fixtures::rec;
+use crate::storage::{SharedStore, Store};
+use base64::Engine;
+use serde_json::json;
+
+/// Every test installs the same key set, so running them in parallel is harmless.
+fn install_test_keys() {
+    let b64 = |b: u8| base64::engine::general_purpose::STANDARD.encode([b; 32]);
+    std::env::set_var("CLARIUM_ENCRYPTION_KEYS", format!("k1:{},k2:{}", b64(1), b64(2)));
+    crate::storage::encryption::reload_keys().unwrap();
+}
+
+fn chunk_key_ids(store: &Store, table: &str) -> Vec<Option<String>> {
+    let dir = crate::ident::to_local_path(store.root_path(), table);
+    let mut out: Vec<Option<String>> = std::fs::read_dir(&dir).unwrap()
+        .flatten()
+        .filter(|e| { let n = e.file_name().to_string_lossy().to_string(); n.starts_with("data") && n.ends_with(".parquet") })
+        .map(|e| crate::storage::encryption::key_id_of(&std::fs::read(e.path()).unwrap()))
+        .collect();
+    out.sort();
+    out
+}
+
+#[tokio::test]
+async fn test_table_encryption_and_key_rotation() {
+    install_test_keys();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "encdb/public/secrets.time";
+    shared.0.lock().write_records(table, &[rec(1_000, 1.0), rec(2_000, 2.0)]).unwrap();
+    assert_eq!(chunk_key_ids(&shared.0.lock(), table), vec![None]);
+
+    // Turning encryption on seals existing chunks; new chunks are sealed at write time
+    execute_query(&shared, &format!("ALTER TABLE {} SET ENCRYPTION ON KEY 'k1'", table)).await.unwrap();
+    shared.0.lock().write_records(table, &[rec(3_000, 3.0)]).unwrap();
+    assert_eq!(chunk_key_ids(&shared.0.lock(), table), vec![Some("k1".to_string()), Some("k1".to_string())]);
+    let rows = execute_query(&shared, &format!("SELECT _time, v FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+    let reports = shared.0.lock().verify_table(table, false).unwrap();
+    assert!(reports.iter().all(|r| !r.status.is_bad()));
+
+    // Rotation: point the table at k2 and rewrite
+    execute_query(&shared, &format!("ALTER TABLE {} SET ENCRYPTION ON KEY 'k2'", table)).await.unwrap();
+    let res = execute_query(&shared, &format!("REENCRYPT TABLE {}", table)).await.unwrap();
+    assert_eq!(res["chunks"], json!(2));
+    assert_eq!(chunk_key_ids(&shared.0.lock(), table), vec![Some("k2".to_string()), Some("k2".to_string())]);
+    let rows = execute_query(&shared, &format!("SELECT _time, v FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+
+    // Unknown keys are rejected before the setting changes; OFF restores plaintext
+    assert!(execute_query(&shared, &format!("ALTER TABLE {} SET ENCRYPTION ON KEY 'nope'", table)).await.is_err());
+    execute_query(&shared, &format!("ALTER TABLE {} SET ENCRYPTION OFF", table)).await.unwrap();
+    assert_eq!(chunk_key_ids(&shared.0.lock(), table), vec![None, None]);
+}
+
+#[test]
+fn test_decrypt_rejects_tampered_data() {
+    install_test_keys();
+    let spec = crate::storage::encryption::EncryptionSpec::new(Some("k1".into()));
+    let mut sealed = crate::storage::encryption::encrypt(b"hello", &spec).unwrap();
+    assert_eq!(crate::storage::encryption::decrypt(sealed.clone()).unwrap(), b"hello");
+    let last = sealed.len() - 1;
+    sealed[last] ^= 0xff;
+    assert!(crate::storage::encryption::decrypt(sealed).is_err());
+}
+
+#[test]
+fn test_invalid_encryption_setting_refuses_writes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let table = "encdb/public/badspec.time";
+    store.write_records(table, &[rec(1_000, 1.0)]).unwrap();
+    // A per-column spec is not a supported setting: writes fail instead of storing plaintext
+    let sp = store.schema_path(table);
+    let mut v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&sp).unwrap()).unwrap();
+    v["encryption"] = json!({"algorithm": "aes-256-gcm", "columns": ["v"]});
+    std::fs::write(&sp, serde_json::to_vec(&v).unwrap()).unwrap();
+    let err = store.write_records(table, &[rec(2_000, 2.0)]).unwrap_err();
+    assert!(format!("{:#}", err).contains("invalid encryption setting"));
+    assert_eq!(chunk_key_ids(&store, table), vec![None]);
+}
+
+#[tokio::test]
+async fn test_cdc_changelog_of_encrypted_table_is_sealed() {
+    install_test_keys();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "encdb/public/cdc_secrets.time";
+    shared.0.lock().write_records(table, &[rec(1_000, 1.0)]).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET CDC ON", table)).await.unwrap();
+    shared.0.lock().write_records(table, &[rec(2_000, 4242.5)]).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET ENCRYPTION ON KEY 'k1'", table)).await.unwrap();
+    shared.0.lock().write_records(table, &[rec(3_000, 9191.5)]).unwrap();
+
+    // The line written after encryption was switched on holds no plaintext values
+    let log = shared.0.lock().db_dir(table).join("_cdc").join("changes.ndjson");
+    let text = std::fs::read_to_string(&log).unwrap();
+    assert!(text.contains("4242.5"));
+    assert!(!text.contains("9191.5"));
+
+    // Both plain and sealed lines read back in order
+    let evs = shared.0.lock().read_changes(table, 0).unwrap();
+    assert_eq!(evs.len(), 2);
+    assert!(evs[0].seq < evs[1].seq);
+    assert_eq!(evs[1].row["v"], json!(9191.5));
+}
+
+#[tokio::test]
+async fn test_analyze_stats_of_encrypted_table_are_sealed() {
+    install_test_keys();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "encdb/public/stats_secrets.time";
+    shared.0.lock().write_records(table, &[rec(1_000, 5151.5), rec(2_000, 6161.5)]).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET ENCRYPTION ON KEY 'k1'", table)).await.unwrap();
+    execute_query(&shared, &format!("ANALYZE {}", table)).await.unwrap();
+
+    let path = crate::storage::analyze::stats_path(&shared.0.lock().db_dir(table));
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(crate::storage::encryption::key_id_of(&bytes), Some("k1".to_string()));
+    let stats = shared.0.lock().table_stats(table).unwrap();
+    assert_eq!(stats.columns["v"].max, Some(json!(6161.5)));
+
+    // Switching encryption off rewrites the statistics in plaintext as well
+    execute_query(&shared, &format!("ALTER TABLE {} SET ENCRYPTION OFF", table)).await.unwrap();
+    assert!(String::from_utf8(std::fs::read(&path).unwrap()).unwrap().contains("6161.5"));
+}
@@ -0,0 +1,120 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use serde_json::json;
+
+fn seed(shared: &SharedStore, table: &str, n: i64) {
+    let recs: Vec<Record> = (0..n).map(|i| {
+        let mut m = serde_json::Map::new();
+        m.insert("v".into(), json!(i as f64));
+        Record { _time: 1_000 + i * 1_000, sensors: m }
+    }).collect();
+    shared.0.lock().write_records(table, &recs).unwrap();
+}
+
+#[tokio::test]
+async fn test_explain_json_estimates_and_analyze_actuals() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed(&shared, "clarium/public/exp_rows.time", 5);
+
+    let plan = execute_query(&shared, "EXPLAIN (FORMAT JSON) SELECT v FROM clarium/public/exp_rows.time").await.unwrap();
+    let stages = plan["explain"]["stages"].as_array().unwrap().clone();
+    assert_eq!(stages[0]["id"], json!(1));
+    assert_eq!(stages[0]["name"], json!("Scan"));
+    assert_eq!(stages[0]["estimated_rows"], json!(5));
+    assert!(stages[0]["actual_rows"].is_null());
+    assert_eq!(stages[1]["input"], json!(1));
+
+    let plan = execute_query(&shared, "EXPLAIN ANALYZE FORMAT JSON SELECT v FROM clarium/public/exp_rows.time LIMIT 2").await.unwrap();
+    assert_eq!(plan["explain"]["analyze"], json!(true));
+    let stages = plan["explain"]["stages"].as_array().unwrap().clone();
+    assert_eq!(stages[0]["actual_rows"], json!(5));
+    let ol = stages.iter().find(|s| s["name"] == json!("OrderLimit")).unwrap();
+    assert_eq!(ol["estimated_rows"], json!(2));
+    assert_eq!(ol["actual_rows"], json!(2));
+}
+
+#[tokio::test]
+async fn test_explain_yaml_and_dot_formats() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed(&shared, "clarium/public/exp_fmt.time", 3);
+
+    let yaml = execute_query(&shared, "EXPLAIN (ANALYZE, FORMAT YAML) SELECT v FROM clarium/public/exp_fmt.time").await.unwrap();
+    let yaml = yaml["explain"].as_str().unwrap().to_string();
+    assert!(yaml.starts_with("plan:\n"), "{}", yaml);
+    assert!(yaml.contains("    - id: 1\n      name: \"Scan\""), "{}", yaml);
+    assert!(yaml.contains("estimated_rows: 3"), "{}", yaml);
+    assert!(yaml.contains("actual_rows: 3"), "{}", yaml);
+
+    let dot = execute_query(&shared, "EXPLAIN FORMAT DOT SELECT v FROM clarium/public/exp_fmt.time").await.unwrap();
+    let dot = dot["explain"].as_str().unwrap().to_string();
+    assert!(dot.starts_with("digraph plan {"), "{}", dot);
+    assert!(dot.contains("n1 [label=\"#1 Scan"), "{}", dot);
+    assert!(dot.contains("n1 -> n2;"), "{}", dot);
+    assert!(dot.trim_end().ends_with('}'));
+
+    let err = execute_query(&shared, "EXPLAIN (FORMAT XML) SELECT 1").await;
+    assert!(err.is_err());
+    let err = execute_query(&shared, "EXPLAIN ANALYZE DELETE FROM clarium/public/exp_fmt.time").await;
+    assert!(err.is_err());
+}
+
+#[tokio::test]
+async fn test_explain_analyze_attributes_script_time() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed(&shared, "clarium/public/exp_udf.time", 4);
+
+    let plan = execute_query(&shared, "EXPLAIN ANALYZE FORMAT JSON SELECT dbl(v) AS d FROM clarium/public/exp_udf.time").await.unwrap();
+    let scripts = plan["explain"]["scripts"].as_array().unwrap().clone();
+    let dbl = scripts.iter().find(|s| s["name"] == json!("dbl")).expect("dbl attributed");
+    // Other tests may call dbl concurrently; this statement alone makes 4 calls
+    assert!(dbl["calls"].as_u64().unwrap() >= 4, "{}", dbl);
+    assert!(dbl["ms"].as_f64().unwrap() >= 0.0);
+
+    let text = execute_query(&shared, "EXPLAIN ANALYZE SELECT dbl(v) AS d FROM clarium/public/exp_udf.time").await.unwrap();
+    assert!(text["explain"].as_str().unwrap().contains("- script dbl: calls="), "{}", text);
+
+    // Counters since startup
+    let rows = execute_query(&shared, "SELECT kind, calls, errors FROM clarium_catalog.script_stats WHERE name = 'dbl'").await.unwrap();
+    let row = &rows.as_array().unwrap()[0];
+    assert_eq!(row["kind"], json!("scalar"));
+    assert!(row["calls"].as_i64().unwrap() >= 8, "{}", row);
+
+    // Plain EXPLAIN does not run the statement
+    let plan = execute_query(&shared, "EXPLAIN FORMAT JSON SELECT dbl(v) AS d FROM clarium/public/exp_udf.time").await.unwrap();
+    assert_eq!(plan["explain"]["scripts"], json!([]));
+}
+
+#[tokio::test]
+async fn test_explain_analyze_lists_pruned_chunks() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "CREATE TABLE clarium/public/exp_part (region TEXT, amount INT) PARTITION BY (region)").await.unwrap();
+    execute_query(&shared, "INSERT INTO clarium/public/exp_part (region, amount) VALUES ('eu', 1), ('us', 2), ('eu', 3), ('ap', 4)").await.unwrap();
+
+    let plan = execute_query(&shared, "EXPLAIN ANALYZE FORMAT JSON SELECT amount FROM clarium/public/exp_part WHERE region = 'eu'").await.unwrap();
+    let chunks = plan["explain"]["chunks"].as_array().unwrap().clone();
+    assert_eq!(chunks.len(), 3, "{:?}", chunks);
+    let read: Vec<&serde_json::Value> = chunks.iter().filter(|c| c["pruned"] == json!(false)).collect();
+    assert_eq!(read.len(), 1);
+    assert!(read[0]["chunk"].as_str().unwrap().starts_with("region=eu/"), "{:?}", read);
+    for c in chunks.iter().filter(|c| c["pruned"] == json!(true)) {
+        assert!(c["reason"].as_str().unwrap().starts_with("partition excluded by region"), "{}", c);
+    }
+
+    let text = execute_query(&shared, "EXPLAIN ANALYZE SELECT amount FROM clarium/public/exp_part WHERE region = 'eu'").await.unwrap();
+    let text = text["explain"].as_str().unwrap().to_string();
+    assert!(text.contains("- chunks: read=1 pruned=2"), "{}", text);
+
+    // Plain EXPLAIN does not scan
+    let plan = execute_query(&shared, "EXPLAIN FORMAT JSON SELECT amount FROM clarium/public/exp_part WHERE region = 'eu'").await.unwrap();
+    assert_eq!(plan["explain"]["chunks"], json!([]));
+
+    let rows = execute_query(&shared, "SELECT chunk, rows, compression FROM clarium_catalog.chunks WHERE table_name = 'exp_part'").await.unwrap();
+    let rows = rows.as_array().unwrap().clone();
+    assert_eq!(rows.iter().map(|r| r["rows"].as_i64().unwrap()).sum::<i64>(), 4);
+    assert!(rows.iter().all(|r| r["compression"] == json!("zstd")), "{:?}", rows);
+}
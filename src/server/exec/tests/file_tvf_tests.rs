@@ -0,0 +1,159 @@
+use super::super::execute_query;
+use crate::storage::SharedStore;
+use polars::prelude::*;
+use serde_json::json;
+use std::io::Write;
+
+#[tokio::test]
+async fn test_read_csv_infers_types_and_honors_options() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let p = tmp.path().join("m.csv");
+    std::fs::write(&p, "id;name;score;ok\n1;\"a;b\";1.5;true\n2;bee;;false\n3;cee;3;true\n").unwrap();
+    let sql = format!("SELECT id, name, score FROM read_csv('{}', delimiter => ';') WHERE id > 1 ORDER BY id", p.display());
+    let res = execute_query(&shared, &sql).await.unwrap();
+    let arr = res.as_array().unwrap();
+    assert_eq!(arr.len(), 2);
+    assert_eq!(arr[0]["name"], json!("bee"));
+    assert!(arr[0]["score"].is_null());
+    assert_eq!(arr[1]["score"], json!(3.0));
+
+    // Quoted delimiter survives; header => false yields positional column names
+    let sql = format!("SELECT column1 FROM read_csv('{}', delimiter => ';', header => false) WHERE column0 = '1'", p.display());
+    let res = execute_query(&shared, &sql).await.unwrap();
+    assert_eq!(res.as_array().unwrap()[0]["column1"], json!("a;b"));
+}
+
+#[tokio::test]
+async fn test_read_json_gzip_and_parquet() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    let gz = tmp.path().join("events.ndjson.gz");
+    let mut enc = flate2::write::GzEncoder::new(std::fs::File::create(&gz).unwrap(), flate2::Compression::default());
+    enc.write_all(b"{\"k\":\"x\",\"v\":1}\n{\"k\":\"y\",\"v\":2}\n{\"k\":\"z\",\"v\":3}\n").unwrap();
+    enc.finish().unwrap();
+    let res = execute_query(&shared, &format!("SELECT k FROM read_json('{}') WHERE v >= 2 ORDER BY v", gz.display())).await.unwrap();
+    let arr = res.as_array().unwrap();
+    assert_eq!(arr.len(), 2);
+    assert_eq!(arr[0]["k"], json!("y"));
+
+    let pq = tmp.path().join("t.parquet");
+    let mut df = df!("a" => &[10i64, 20, 30], "b" => &["p", "q", "r"]).unwrap();
+    ParquetWriter::new(std::fs::File::create(&pq).unwrap()).finish(&mut df).unwrap();
+    let res = execute_query(&shared, &format!("SELECT f.b FROM read_parquet('{}') f WHERE f.a = 20", pq.display())).await.unwrap();
+    assert_eq!(res.as_array().unwrap().len(), 1);
+
+    let err = execute_query(&shared, "SELECT * FROM read_csv('/no/such/file.csv')").await.unwrap_err();
+    assert!(err.to_string().contains("cannot read"));
+}
+
+#[tokio::test]
+async fn test_local_paths_need_an_admin_session() {
+    use crate::server::activity::{self, BackendGuard, Frontend};
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let p = tmp.path().join("m.csv");
+    std::fs::write(&p, "k\n1\n").unwrap();
+    let pid = activity::register(Frontend::Pgwire, "ft_olga", "clarium", "", None);
+    let _guard = BackendGuard(pid);
+    for f in ["read_csv", "read_json", "read_parquet"] {
+        let sql = format!("SELECT * FROM {}('{}')", f, p.display());
+        let err = activity::run_statement(Some(pid), &sql, execute_query(&shared, &sql)).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<crate::error::AppError>(), Some(crate::error::AppError::Permission { .. })), "{}: {}", f, err);
+    }
+}
+
+/// Create filestore `name` in the current database and store `files` in it with ACL checks bypassed.
+async fn seed_filestore(shared: &SharedStore, name: &str, security: bool, files: &[(&str, Vec<u8>)]) {
+    use crate::server::exec::filestore::*;
+    let (db, fs_name) = crate::server::exec::filestore_target(name);
+    let cfg = FilestoreConfig { security_check_enabled: security, ..Default::default() };
+    create_filestore(shared, &db, &fs_name, cfg, None).unwrap();
+    let bypass = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    let eff = EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &bypass, None);
+    let user = AclUser { id: "loader".into(), roles: vec![], ip: None };
+    for (path, bytes) in files {
+        ingest_from_bytes(shared, &db, &fs_name, path, bytes, None, None, &user, &eff, &AclContext::default()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_filestore_read_tvfs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let mut pq = Vec::new();
+    let mut df = df!("a" => &[1i64, 2, 3], "b" => &["x", "y", "z"]).unwrap();
+    ParquetWriter::new(&mut pq).finish(&mut df).unwrap();
+    seed_filestore(&shared, "docs", false, &[
+        ("data/m.csv", b"id,name\n1,one\n2,two\n3,three\n".to_vec()),
+        ("notes.txt", b"first\r\nsecond\n\nfourth\n".to_vec()),
+        ("t.parquet", pq),
+    ]).await;
+
+    let res = execute_query(&shared, "SELECT name FROM filestore_read_csv('docs:/data/m.csv') WHERE id >= 2 ORDER BY id").await.unwrap();
+    assert_eq!(res, json!([{"name": "two"}, {"name": "three"}]));
+
+    let res = execute_query(&shared, "SELECT line_no, line FROM filestore_read_lines('docs:notes.txt') ORDER BY line_no").await.unwrap();
+    let arr = res.as_array().unwrap();
+    assert_eq!(arr.len(), 4);
+    assert_eq!(arr[0], json!({"line_no": 1, "line": "first"}));
+    assert_eq!(arr[2]["line"], json!(""));
+
+    let res = execute_query(&shared, "SELECT p.b FROM filestore_read_parquet('docs:t.parquet') p WHERE p.a = 3").await.unwrap();
+    assert_eq!(res, json!([{"b": "z"}]));
+
+    let err = execute_query(&shared, "SELECT * FROM filestore_read_lines('docs:missing.txt')").await.unwrap_err();
+    assert!(err.to_string().contains("file not found"), "{}", err);
+    let err = execute_query(&shared, "SELECT * FROM filestore_read_lines('docs')").await.unwrap_err();
+    assert!(err.to_string().contains("<filestore>:<path>"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_filestore_reads_respect_acl() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed_filestore(&shared, "locked", true, &[("secret.csv", b"k\n1\n".to_vec())]).await;
+
+    // Security is on and the session user holds no reader role
+    let err = execute_query(&shared, "SELECT * FROM filestore_read_csv('locked:secret.csv')").await.unwrap_err();
+    assert!(err.to_string().contains("no_read_policy"), "{}", err);
+    let err = execute_query(&shared, "SELECT * FROM read_csv('filestore://locked/secret.csv')").await.unwrap_err();
+    assert!(err.to_string().contains("no_read_policy"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_filestore_search() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed_filestore(&shared, "kb", false, &[
+        ("guides/backup.md", b"# Backups\nRun the backup nightly and verify the backup archive.".to_vec()),
+        ("guides/restore.txt", b"Restore a backup into a fresh database.".to_vec()),
+        ("data/items.json", br#"{"title": "Nightly report", "tags": ["archive"]}"#.to_vec()),
+        ("image.bin", b"backup archive".to_vec()),
+    ]).await;
+
+    let res = execute_query(&shared, "SELECT path FROM filestore_search('kb', 'Backup') ORDER BY score DESC").await.unwrap();
+    assert_eq!(res, json!([{"path": "guides/backup.md"}, {"path": "guides/restore.txt"}]));
+
+    let res = execute_query(&shared, "SELECT path, snippet FROM filestore_search('kb', 'nightly archive')").await.unwrap();
+    let arr = res.as_array().unwrap();
+    assert_eq!(arr.len(), 2);
+    assert!(arr.iter().any(|r| r["path"] == json!("data/items.json")));
+    assert!(arr.iter().all(|r| r["snippet"].as_str().unwrap().to_lowercase().contains("nightly")));
+
+    let err = execute_query(&shared, "SELECT * FROM filestore_search('missing', 'x')").await.unwrap_err();
+    assert!(err.to_string().contains("filestore not found"), "{}", err);
+    let err = execute_query(&shared, "SELECT * FROM filestore_search('kb')").await.unwrap_err();
+    assert!(err.to_string().contains("two arguments"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_filestore_search_respects_acl() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed_filestore(&shared, "locked", true, &[("secret.txt", b"launch codes".to_vec())]).await;
+
+    let res = execute_query(&shared, "SELECT * FROM filestore_search('locked', 'launch')").await.unwrap();
+    assert_eq!(res.as_array().map(|a| a.len()).unwrap_or(0), 0);
+}
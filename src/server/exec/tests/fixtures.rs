@@ -5,6 +5,13 @@ pub fn new_store(tmp: &tempfile::TempDir) -> SharedStore {
     SharedStore::new(tmp.path()).unwrap()
 }
 
+/// One time-table row with a single float column `v`.
+pub fn rec(t: i64, v: f64) -> Record {
+    let mut m = serde_json::Map::new();
+    m.insert("v".into(), json!(v));
+    Record { _time: t, sensors: m }
+}
+
 pub fn write_rows(store: &SharedStore, table: &str, rows: Vec<serde_json::Map<String, serde_json::Value>>) {
     let mut recs: Vec<Record> = Vec::new();
     for (i, m) in rows.into_iter().enumerate() {
@@ -0,0 +1,63 @@
+use crate::server::query::{self, Command, GraphElement};
+use crate::storage::SharedStore;
+use futures::executor::block_on;
+use serde_json::json;
+
+#[test]
+fn parse_graph_dml_statements() {
+    match query::parse("INSERT INTO GRAPH know EDGES Calls (src, dst) VALUES ('a','b'), ('b','c')").unwrap() {
+        Command::InsertGraph { graph, element, name, columns, values } => {
+            assert_eq!((graph.as_str(), element, name.as_str()), ("know", GraphElement::Edges, "Calls"));
+            assert_eq!(columns, vec!["src".to_string(), "dst".to_string()]);
+            assert_eq!(values.len(), 2);
+        }
+        _ => panic!("expected INSERT INTO GRAPH"),
+    }
+    match query::parse("DELETE FROM GRAPH know DETACH NODES Tool VALUES ('a'), ('b')").unwrap() {
+        Command::DeleteGraph { element, keys, detach, .. } => {
+            assert_eq!((element, keys.len(), detach), (GraphElement::Nodes, 2, true));
+        }
+        _ => panic!("expected DELETE FROM GRAPH"),
+    }
+    assert!(query::parse("INSERT INTO GRAPH know NODES Tool VALUES ('a')").is_err());
+    assert!(query::parse("DELETE FROM GRAPH know EDGES Calls VALUES ('a')").is_err());
+    assert!(query::parse("DELETE FROM GRAPH know DETACH EDGES Calls VALUES ('a','b')").is_err());
+}
+
+#[test]
+fn insert_and_delete_graph_nodes_and_edges() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let run = |sql: &str| block_on(crate::server::exec::execute_query(&shared, sql));
+    run("CREATE GRAPH know NODES (Tool KEY(id)) EDGES (Calls FROM Tool TO Tool) \
+         USING TABLES (nodes=clarium/public/know_nodes, edges=clarium/public/know_edges)").unwrap();
+
+    let res = run("INSERT INTO GRAPH know NODES Tool (id, name) VALUES ('planner', 'Planner'), ('toolA', 'Tool A')").unwrap();
+    assert_eq!(res["inserted"], json!(2));
+    // Keys are unique per label and the key column is required
+    assert!(run("INSERT INTO GRAPH know NODES Tool (id) VALUES ('planner')").is_err());
+    assert!(run("INSERT INTO GRAPH know NODES Tool (id) VALUES ('x'), ('x')").is_err());
+    assert!(run("INSERT INTO GRAPH know NODES Tool (name) VALUES ('nameless')").is_err());
+    assert!(run("INSERT INTO GRAPH know NODES Doc (id) VALUES ('d1')").is_err());
+
+    run("INSERT INTO GRAPH know EDGES Calls (src, dst) VALUES ('planner', 'toolA')").unwrap();
+    // Both endpoints must already exist
+    assert!(run("INSERT INTO GRAPH know EDGES Calls (src, dst) VALUES ('planner', 'ghost')").is_err());
+
+    let df = run("MATCH USING GRAPH know (a:Tool)-[:Calls]->(b:Tool) RETURN a.name AS first, b.name AS last").unwrap();
+    assert_eq!(df, json!([{"first": "Planner", "last": "Tool A"}]));
+
+    // A node with edges needs DETACH
+    assert!(run("DELETE FROM GRAPH know NODES Tool VALUES ('toolA')").is_err());
+    let res = run("DELETE FROM GRAPH know EDGES Calls VALUES ('planner', 'toolA')").unwrap();
+    assert_eq!(res["deleted"], json!(1));
+    let res = run("DELETE FROM GRAPH know NODES Tool VALUES ('toolA')").unwrap();
+    assert_eq!((res["deleted"].clone(), res["edges_deleted"].clone()), (json!(1), json!(0)));
+
+    run("INSERT INTO GRAPH know NODES Tool (id, name) VALUES ('toolA', 'Tool A')").unwrap();
+    run("INSERT INTO GRAPH know EDGES Calls (src, dst) VALUES ('planner', 'toolA'), ('toolA', 'planner')").unwrap();
+    let res = run("DELETE FROM GRAPH know DETACH NODES Tool VALUES ('planner')").unwrap();
+    assert_eq!((res["deleted"].clone(), res["edges_deleted"].clone()), (json!(1), json!(2)));
+    let df = run("MATCH USING GRAPH know (a:Tool)-[:Calls]->(b) RETURN b.key AS k").unwrap();
+    assert_eq!(df.as_array().map(|a| a.len()), Some(0));
+}
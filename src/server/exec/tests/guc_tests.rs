@@ -0,0 +1,85 @@
+use super::super::execute_query;
+use crate::server::query::{parse, Command};
+use crate::storage::SharedStore;
+use serde_json::json;
+
+#[test]
+fn test_parse_set_reset_and_alter_database() {
+    assert!(matches!(parse("SET LOCAL work_mem = '8MB'").unwrap(), Command::Set { variable, value, local: true } if variable == "work_mem" && value == "8MB"));
+    assert!(matches!(parse("SET SESSION TIME ZONE 'Europe/Paris'").unwrap(), Command::Set { variable, value, local: false } if variable == "TimeZone" && value == "Europe/Paris"));
+    assert!(matches!(parse("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE").unwrap(), Command::Set { variable, local: true, .. } if variable == "transaction_isolation"));
+    assert!(matches!(parse("RESET ALL").unwrap(), Command::Reset { variable: None }));
+    assert!(matches!(parse("SHOW TIME ZONE").unwrap(), Command::ShowVariable { name } if name == "TimeZone"));
+    assert!(matches!(parse("SHOW server_version_num;").unwrap(), Command::ShowVariable { name } if name == "server_version_num"));
+    assert!(matches!(parse("ALTER DATABASE Sales SET DateStyle TO 'German'").unwrap(),
+        Command::AlterDatabaseSet { database, variable: Some(v), value: Some(val) } if database == "sales" && v == "DateStyle" && val == "German"));
+    assert!(matches!(parse("ALTER DATABASE sales RESET ALL").unwrap(), Command::AlterDatabaseSet { variable: None, value: None, .. }));
+}
+
+#[tokio::test]
+async fn test_set_show_reset_through_registry() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    assert_eq!(execute_query(&shared, "SHOW DateStyle").await.unwrap(), json!([{ "DateStyle": "ISO, MDY" }]));
+    assert_eq!(execute_query(&shared, "SHOW transaction isolation level").await.unwrap(), json!([{ "transaction_isolation": "read committed" }]));
+
+    execute_query(&shared, "SET TIME ZONE 'Europe/Paris'").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW timezone").await.unwrap(), json!([{ "TimeZone": "Europe/Paris" }]));
+    execute_query(&shared, "SET standard_conforming_strings = false").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW standard_conforming_strings").await.unwrap()[0]["standard_conforming_strings"], json!("off"));
+    execute_query(&shared, "RESET TimeZone").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW TimeZone").await.unwrap()[0]["TimeZone"], json!("UTC"));
+    execute_query(&shared, "RESET ALL").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW standard_conforming_strings").await.unwrap()[0]["standard_conforming_strings"], json!("on"));
+
+    // Values are checked against the parameter's type and context
+    assert!(execute_query(&shared, "SET extra_float_digits = 9").await.is_err());
+    assert!(execute_query(&shared, "SET client_min_messages = chatty").await.is_err());
+    assert!(execute_query(&shared, "SET server_version = '15.0'").await.is_err());
+    assert!(execute_query(&shared, "SHOW no_such_parameter").await.is_err());
+    // Unknown names are still accepted by SET for client compatibility
+    assert_eq!(execute_query(&shared, "SET lock_timeout = 0").await.unwrap()["status"], json!("ignored"));
+
+    let all = execute_query(&shared, "SHOW ALL").await.unwrap();
+    assert!(all.as_array().unwrap().iter().any(|r| r["name"] == json!("work_mem")));
+}
+
+#[tokio::test]
+async fn test_set_local_ends_with_transaction() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "SET work_mem = '4MB'").await.unwrap();
+    execute_query(&shared, "SET LOCAL work_mem = '64kB'").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW work_mem").await.unwrap()[0]["work_mem"], json!("64kB"));
+    assert_eq!(crate::system::work_mem_override(), Some(64 * 1024));
+    crate::server::guc::end_transaction(&shared);
+    assert_eq!(execute_query(&shared, "SHOW work_mem").await.unwrap()[0]["work_mem"], json!("4MB"));
+    assert_eq!(crate::system::work_mem_override(), Some(4 * 1024 * 1024));
+    execute_query(&shared, "RESET work_mem").await.unwrap();
+    assert!(crate::system::work_mem_override().is_none());
+}
+
+#[tokio::test]
+async fn test_database_defaults_back_pg_settings() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "CREATE TABLE clarium/public/guc_db").await.unwrap();
+    assert!(execute_query(&shared, "ALTER DATABASE nowhere SET DateStyle = 'German'").await.is_err());
+    execute_query(&shared, "ALTER DATABASE clarium SET DateStyle = 'German'").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW DateStyle").await.unwrap()[0]["DateStyle"], json!("German"));
+
+    let rows = execute_query(&shared, "SELECT setting, source, reset_val FROM pg_catalog.pg_settings WHERE name = 'DateStyle'").await.unwrap();
+    assert_eq!(rows, json!([{ "setting": "German", "source": "database", "reset_val": "German" }]));
+    let rows = execute_query(&shared, "SELECT setconfig FROM pg_catalog.pg_db_role_setting").await.unwrap();
+    assert_eq!(rows, json!([{ "setconfig": "{\"DateStyle=German\"}" }]));
+
+    // A session value wins over the database default until RESET
+    execute_query(&shared, "SET DateStyle = 'SQL, DMY'").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW DateStyle").await.unwrap()[0]["DateStyle"], json!("SQL, DMY"));
+    execute_query(&shared, "RESET DateStyle").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW DateStyle").await.unwrap()[0]["DateStyle"], json!("German"));
+
+    execute_query(&shared, "ALTER DATABASE clarium RESET ALL").await.unwrap();
+    assert_eq!(execute_query(&shared, "SHOW DateStyle").await.unwrap()[0]["DateStyle"], json!("ISO, MDY"));
+    assert_eq!(execute_query(&shared, "SELECT * FROM pg_catalog.pg_db_role_setting").await.unwrap(), json!([]));
+}
@@ -0,0 +1,89 @@
+use super::super::execute_query;
+use crate::server::query::{extract_hints, parse, Command};
+use crate::storage::{SharedStore, Store};
+use polars::prelude::*;
+use serde_json::json;
+
+fn seed_ab(root: &std::path::Path) {
+    let store = Store::new(root).unwrap();
+    let df_a = DataFrame::new(vec![
+        Series::new("id".into(), &[1i64, 2i64]).into(),
+        Series::new("aval".into(), &[10i64, 20i64]).into(),
+    ]).unwrap();
+    store.rewrite_table_df("ha", df_a).unwrap();
+    let df_b = DataFrame::new(vec![
+        Series::new("id".into(), &[2i64, 3i64]).into(),
+        Series::new("bval".into(), &[200i64, 300i64]).into(),
+    ]).unwrap();
+    store.rewrite_table_df("hb", df_b).unwrap();
+}
+
+#[test]
+fn test_extract_hints_from_comments() {
+    let h = extract_hints("SELECT /*+ NO_PRUNE, JOIN_ORDER(b, a) FANCY */ * FROM a -- /*+ IGNORED */");
+    assert!(h.no_prune);
+    assert_eq!(h.join_order, Some(vec!["b".to_string(), "a".to_string()]));
+    assert_eq!(h.ignored, vec!["FANCY".to_string()]);
+
+    // Plain comments and hint-like text inside literals are not hints
+    assert!(extract_hints("SELECT /* NO_PRUNE */ '/*+ NO_PRUNE */' AS s").is_empty());
+
+    match parse("SELECT /*+ NO_PRUNE */ 1 AS one").unwrap() {
+        Command::Select(q) => assert!(q.hints.no_prune),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_join_order_hint_reorders_and_keeps_results() {
+    let tmp = tempfile::tempdir().unwrap();
+    seed_ab(tmp.path());
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    let q = "SELECT /*+ JOIN_ORDER(b, a) */ a.id, b.bval FROM ha AS a INNER JOIN hb AS b ON a.id = b.id";
+    let v = execute_query(&shared, q).await.unwrap();
+    let arr = v.as_array().unwrap();
+    assert_eq!(arr.len(), 1);
+    assert_eq!(arr[0]["a.id"], json!(2));
+    assert_eq!(arr[0]["b.bval"], json!(200));
+
+    let plan = execute_query(&shared, &format!("EXPLAIN (FORMAT JSON) {}", q)).await.unwrap();
+    assert_eq!(plan["explain"]["hints"], json!(["JOIN_ORDER(b, a)"]));
+    let scan = plan["explain"]["stages"][0]["details"].as_str().unwrap().to_string();
+    assert!(scan.contains("hb") && scan.ends_with("join a"), "{}", scan);
+
+    // Outer joins are left alone and EXPLAIN says so
+    let q = "EXPLAIN (FORMAT JSON) SELECT /*+ JOIN_ORDER(b, a) */ a.id FROM ha AS a LEFT JOIN hb AS b ON a.id = b.id";
+    let plan = execute_query(&shared, q).await.unwrap();
+    assert_eq!(plan["explain"]["hints"], json!(["JOIN_ORDER(b, a) (not applicable)"]));
+}
+
+#[tokio::test]
+async fn test_no_prune_hint_and_planner_toggles_in_explain() {
+    let tmp = tempfile::tempdir().unwrap();
+    seed_ab(tmp.path());
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    let v = execute_query(&shared, "SELECT /*+ NO_PRUNE */ aval FROM ha WHERE id = 2").await.unwrap();
+    assert_eq!(v.as_array().unwrap()[0]["aval"], json!(20));
+    let plan = execute_query(&shared, "EXPLAIN SELECT /*+ NO_PRUNE */ aval FROM ha WHERE id = 2").await.unwrap();
+    let text = plan["explain"].as_str().unwrap().to_string();
+    assert!(text.contains("hints: NO_PRUNE"), "{}", text);
+    assert!(text.contains("(no pruning)"), "{}", text);
+
+    let st = execute_query(&shared, "SET planner.enable_bloom_pruning = off").await.unwrap();
+    assert_eq!(st["status"], json!("ok"));
+    let plan = execute_query(&shared, "EXPLAIN (FORMAT JSON) SELECT aval FROM ha WHERE id = 2").await.unwrap();
+    assert_eq!(plan["explain"]["settings"], json!(["planner.enable_bloom_pruning=off"]));
+
+    execute_query(&shared, "SET planner.enable_hints = off").await.unwrap();
+    let plan = execute_query(&shared, "EXPLAIN (FORMAT JSON) SELECT /*+ NO_PRUNE */ aval FROM ha").await.unwrap();
+    assert_eq!(plan["explain"]["hints"], json!(["NO_PRUNE (hints disabled)"]));
+
+    execute_query(&shared, "SET planner.enable_bloom_pruning = default").await.unwrap();
+    execute_query(&shared, "SET planner.enable_hints = default").await.unwrap();
+    let plan = execute_query(&shared, "EXPLAIN (FORMAT JSON) SELECT aval FROM ha").await.unwrap();
+    assert_eq!(plan["explain"]["settings"], json!([]));
+    let st = execute_query(&shared, "SET planner.no_such_toggle = off").await.unwrap();
+    assert_eq!(st["status"], json!("ignored"));
+}
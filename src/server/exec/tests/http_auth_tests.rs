@@ -0,0 +1,98 @@
+use crate::identity::{create_api_key, list_api_keys, resolve_api_key, revoke_api_key, API_KEY_PREFIX};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::routing::get;
+use tokio::sync::RwLock;
+
+use crate::security::CommandKind;
+use crate::server::{http_auth, http_router, AppState, HTTP_ROUTES};
+use crate::storage::SharedStore;
+
+#[test]
+fn test_api_key_lifecycle() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    let (record, key) = create_api_key(&root, "alice", "telegraf").unwrap();
+    assert!(key.starts_with(API_KEY_PREFIX));
+    assert!(record.hash.is_empty());
+    // Only the hash is stored
+    let stored = std::fs::read_to_string(tmp.path().join("api_keys.json")).unwrap();
+    assert!(!stored.contains(&key));
+
+    let principal = resolve_api_key(&root, &key).expect("key resolves");
+    assert_eq!(principal.user_id, "alice");
+    assert!(principal.roles.is_empty());
+    // Same id, wrong secret
+    let forged = format!("{}{}_{}", API_KEY_PREFIX, record.id, "x".repeat(43));
+    assert!(resolve_api_key(&root, &forged).is_none());
+
+    let (_, other) = create_api_key(&root, "bob", "ci").unwrap();
+    assert_eq!(list_api_keys(&root, Some("alice")).unwrap().len(), 1);
+    assert_eq!(list_api_keys(&root, None).unwrap().len(), 2);
+    assert!(list_api_keys(&root, None).unwrap().iter().all(|k| k.hash.is_empty()));
+
+    // bob cannot revoke alice's key
+    assert!(!revoke_api_key(&root, &record.id, Some("bob")).unwrap());
+    assert!(revoke_api_key(&root, &record.id, Some("alice")).unwrap());
+    assert!(resolve_api_key(&root, &key).is_none());
+    assert!(resolve_api_key(&root, &other).is_some());
+}
+
+#[test]
+fn test_routes_declare_authentication() {
+    let public: Vec<&str> = HTTP_ROUTES.iter().filter(|r| !r.auth).map(|r| r.path).collect();
+    assert_eq!(public, vec!["/", "/login", "/ping", "/replication/manifest", "/replication/file/{*path}"]);
+    let route = |method: &str, path: &str| HTTP_ROUTES.iter().find(|r| r.method == method && r.path == path).unwrap();
+    assert_eq!(route("post", "/write/{database}").privilege, Some((CommandKind::Insert, "database")));
+    assert_eq!(route("post", "/write").privilege, Some((CommandKind::Insert, "db")));
+    assert!(route("post", "/write").agent && route("post", "/api/v1/write").agent);
+    assert!(!route("post", "/query").agent);
+    assert_eq!(route("get", "/cdc/{database}/{schema}/{table}").privilege, Some((CommandKind::Select, "database")));
+    // State-changing routes need CSRF from cookie sessions
+    assert!(HTTP_ROUTES.iter().filter(|r| r.auth && r.method != "get").all(|r| r.csrf));
+    assert!(route("get", "/ws").csrf && !route("get", "/csrf").csrf);
+}
+
+fn app_state(root: &std::path::Path) -> AppState {
+    AppState {
+        store: SharedStore::new(root).unwrap(),
+        db_root: root.to_string_lossy().to_string(),
+        scripts: crate::scripts::ScriptRegistry::new().unwrap(),
+        sessions: Arc::new(RwLock::new(HashMap::new())),
+        csrf_tokens: Arc::new(RwLock::new(HashMap::new())),
+        session_defaults: Arc::new(RwLock::new(HashMap::new())),
+        session_meta: Arc::new(RwLock::new(HashMap::new())),
+        login_attempts: Arc::new(RwLock::new(HashMap::new())),
+        oidc: None,
+    }
+}
+
+#[tokio::test]
+async fn test_every_mounted_route_has_a_spec() {
+    let tmp = tempfile::tempdir().unwrap();
+    let state = app_state(tmp.path());
+    // A route mounted without a RouteSpec, as a wiring mistake would
+    let app = http_router()
+        .route("/unlisted", get(|| async { "served" }))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), http_auth::require_auth))
+        .with_state(state);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let client = reqwest::Client::new();
+
+    for r in HTTP_ROUTES {
+        let path: Vec<&str> = r.path.split('/').map(|seg| if seg.starts_with('{') { "x" } else { seg }).collect();
+        let method = reqwest::Method::from_bytes(r.method.to_ascii_uppercase().as_bytes()).unwrap();
+        let resp = client.request(method, format!("{}{}", base, path.join("/"))).send().await.unwrap();
+        let body = resp.text().await.unwrap();
+        assert!(!body.contains("route_not_registered"), "{} {} has no RouteSpec", r.method, r.path);
+    }
+    // HEAD is answered by the GET route and its spec
+    assert_eq!(client.head(format!("{}/", base)).send().await.unwrap().status(), 200);
+
+    let resp = client.get(format!("{}/unlisted", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 500);
+    assert!(resp.text().await.unwrap().contains("route_not_registered"));
+}
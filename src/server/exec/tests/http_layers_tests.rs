@@ -0,0 +1,42 @@
+use crate::config::HttpSettings;
+use crate::server::{http_layers, HTTP_ROUTES};
+
+const MB: usize = 1024 * 1024;
+
+fn route(method: &str, path: &str) -> &'static crate::server::openapi::RouteSpec {
+    HTTP_ROUTES.iter().find(|r| r.method == method && r.path == path).unwrap()
+}
+
+#[test]
+fn test_body_limits_per_route() {
+    let http = HttpSettings::default();
+    assert_eq!(http_layers::body_limit(route("post", "/query"), &http, 256), 2 * MB);
+    assert_eq!(http_layers::body_limit(route("post", "/v1/ingest/{*table}"), &http, 256), 256 * MB);
+
+    // Either path syntax names a route
+    let http = HttpSettings { max_body_mb: 4, body_limits: "/query=8, /v1/ingest/{table}=16".to_string(), ..Default::default() };
+    assert_eq!(http_layers::body_limit(route("post", "/query"), &http, 256), 8 * MB);
+    assert_eq!(http_layers::body_limit(route("post", "/v1/ingest/{*table}"), &http, 256), 16 * MB);
+    assert_eq!(http_layers::body_limit(route("post", "/v2/query"), &http, 256), 4 * MB);
+    assert!(http_layers::check(&http, HTTP_ROUTES).is_ok());
+
+    let unknown = HttpSettings { body_limits: "/nope=1".to_string(), ..Default::default() };
+    assert!(http_layers::check(&unknown, HTTP_ROUTES).unwrap_err().to_string().contains("/nope"));
+    assert!(http_layers::parse_body_limits("/query").is_err());
+    assert!(http_layers::parse_body_limits("/query=big").is_err());
+}
+
+#[test]
+fn test_cors_and_compression_settings() {
+    let http = HttpSettings::default();
+    assert!(http_layers::cors_layer(&http).unwrap().is_none());
+    assert!(http_layers::compression_layer(&http).is_some());
+
+    let http = HttpSettings { cors_origins: "https://app.example.com, https://admin.example.com".to_string(), cors_allow_credentials: true, compression: false, ..Default::default() };
+    assert!(http_layers::cors_layer(&http).unwrap().is_some());
+    assert!(http_layers::compression_layer(&http).is_none());
+    let any = HttpSettings { cors_origins: "*".to_string(), cors_allow_credentials: true, ..Default::default() };
+    assert!(http_layers::cors_layer(&any).unwrap().is_some());
+    let bad = HttpSettings { cors_origins: "bad\u{7f}origin".to_string(), ..Default::default() };
+    assert!(http_layers::check(&bad, HTTP_ROUTES).is_err());
+}
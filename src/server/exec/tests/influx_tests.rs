@@ -0,0 +1,39 @@
+use super::super::execute_query;
+use crate::server::influx::parse_records;
+use crate::storage::SharedStore;
+
+#[tokio::test]
+async fn test_line_protocol_maps_measurements_to_time_tables() {
+    let body = "cpu,host=a usage_idle=97.5,cores=4i,online=true 1700000000000000000\n\
+        mem,host=a used=1024i 1700000000000000000\n\
+        cpu,host=b usage_idle=12 1700000001000000000\n\
+        cpu,host=c\n";
+    let (tables, errors) = parse_records(body, "ns");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, 4);
+    assert_eq!(tables.keys().collect::<Vec<_>>(), vec!["cpu", "mem"]);
+    let cpu = &tables["cpu"];
+    assert_eq!(cpu[0]._time, 1_700_000_000_000);
+    assert_eq!(cpu[0].sensors["host"], "a");
+    assert_eq!(cpu[0].sensors["online"], 1);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    {
+        let guard = shared.0.lock();
+        for (measurement, records) in tables.iter() {
+            let path = format!("clarium/public/{}.time", measurement);
+            guard.create_table(&path).ok();
+            guard.write_records(&path, records).unwrap();
+        }
+    }
+    let rows = execute_query(&shared, "SELECT host, usage_idle FROM clarium/public/cpu.time").await.unwrap();
+    assert_eq!(rows, serde_json::json!([{"host": "a", "usage_idle": 97.5}, {"host": "b", "usage_idle": 12.0}]));
+}
+
+#[test]
+fn test_line_protocol_precision() {
+    let (tables, errors) = parse_records("m v=1 1700000000\nm v=2 1700000001", "s");
+    assert!(errors.is_empty());
+    assert_eq!(tables["m"].iter().map(|r| r._time).collect::<Vec<_>>(), vec![1_700_000_000_000, 1_700_000_001_000]);
+}
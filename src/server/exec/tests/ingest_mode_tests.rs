@@ -0,0 +1,89 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use polars::prelude::*;
+use serde_json::json;
+
+fn rec(t: i64, v: serde_json::Value, ok: serde_json::Value) -> Record {
+    let mut sensors = serde_json::Map::new();
+    sensors.insert("v".into(), v);
+    sensors.insert("ok".into(), ok);
+    Record { _time: t, sensors }
+}
+
+fn schema_of(shared: &SharedStore, table: &str) -> std::collections::HashMap<String, DataType> {
+    shared.0.lock().load_schema_with_locks(table).unwrap().0
+}
+
+#[tokio::test]
+async fn test_widen_is_the_default() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/im_widen.time";
+    shared.0.lock().write_records(table, &[rec(1_000, json!(1), json!(true))]).unwrap();
+    shared.0.lock().write_records(table, &[rec(2_000, json!("n/a"), json!(true))]).unwrap();
+    assert_eq!(schema_of(&shared, table).get("v"), Some(&DataType::String));
+}
+
+#[tokio::test]
+async fn test_strict_rejects_mismatched_batch_with_report() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/im_strict.time";
+    shared.0.lock().write_records(table, &[rec(1_000, json!(1), json!(true))]).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET INGEST MODE STRICT", table)).await.unwrap();
+
+    let batch = [
+        rec(2_000, json!(2), json!(true)),
+        rec(3_000, json!(2.5), json!("maybe")),
+        rec(4_000, json!("n/a"), json!(false)),
+        rec(5_000, json!(null), json!(null)),
+    ];
+    let err = shared.0.lock().write_records(table, &batch).unwrap_err().to_string();
+    assert!(err.contains("batch rejected by strict ingest mode"), "{}", err);
+    assert!(err.contains("column 'v' (int64): 2 of 4 value(s) do not fit, first at row 1: 2.5"), "{}", err);
+    assert!(err.contains("column 'ok' (bool): 1 of 4 value(s) do not fit, first at row 1: \"maybe\""), "{}", err);
+
+    // Nothing of the batch was written and the schema is unchanged
+    assert_eq!(schema_of(&shared, table).get("v"), Some(&DataType::Int64));
+    let rows = execute_query(&shared, &format!("SELECT v FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+
+    // Fitting values (numeric text included) and new columns are accepted
+    let mut fits = rec(6_000, json!("7"), json!("t"));
+    fits.sensors.insert("extra".into(), json!("x"));
+    shared.0.lock().write_records(table, &[fits]).unwrap();
+    let rows = execute_query(&shared, &format!("SELECT v, extra FROM {} WHERE _time = 6000", table)).await.unwrap();
+    assert_eq!(rows[0]["v"].as_i64(), Some(7));
+    assert_eq!(rows[0]["extra"], json!("x"));
+}
+
+#[tokio::test]
+async fn test_coerce_keeps_types_and_counts_nulls() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/im_coerce.time";
+    shared.0.lock().write_records(table, &[rec(1_000, json!(1.5), json!(true))]).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET INGEST MODE COERCE", table)).await.unwrap();
+
+    shared.0.lock().write_records(table, &[rec(2_000, json!("n/a"), json!(false)), rec(3_000, json!(3), json!(1))]).unwrap();
+    assert_eq!(schema_of(&shared, table).get("v"), Some(&DataType::Float64));
+    let rows = execute_query(&shared, &format!("SELECT _time FROM {} WHERE v IS NULL", table)).await.unwrap();
+    assert_eq!(rows[0]["_time"].as_i64(), Some(2_000));
+
+    let stats = execute_query(&shared, "SELECT values_coerced FROM pg_catalog.pg_stat_ingest WHERE relname = 'im_coerce.time'").await.unwrap();
+    assert_eq!(stats[0]["values_coerced"], json!(1));
+
+    // Back to widen: the column may change type again
+    execute_query(&shared, &format!("ALTER TABLE {} SET INGEST MODE WIDEN", table)).await.unwrap();
+    shared.0.lock().write_records(table, &[rec(4_000, json!("n/a"), json!(true))]).unwrap();
+    assert_eq!(schema_of(&shared, table).get("v"), Some(&DataType::String));
+}
+
+#[tokio::test]
+async fn test_set_ingest_mode_rejects_unknown_mode() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "CREATE TABLE clarium/public/im_bad (a int)").await.unwrap();
+    let err = execute_query(&shared, "ALTER TABLE clarium/public/im_bad SET INGEST MODE LOOSE").await.unwrap_err();
+    assert!(err.to_string().contains("SET INGEST MODE expects STRICT, WIDEN or COERCE"), "{}", err);
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use super::super::execute_query;
+use crate::server::ingest::{self, ColumnType, IngestFormat, IngestOptions};
+use crate::storage::SharedStore;
+
+fn opts(format: IngestFormat, batch_rows: usize) -> IngestOptions {
+    IngestOptions { format, types: HashMap::new(), precision: "ms".into(), batch_rows }
+}
+
+#[tokio::test]
+async fn test_ingest_ndjson_with_type_hints_counts_bad_lines() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let body = b"{\"id\": 1, \"code\": 7}\n\n{\"id\": \"2\", \"code\": 8}\nnot json\n{\"id\": \"x\", \"code\": 9}\n";
+    let mut o = opts(IngestFormat::Ndjson, 1_000);
+    o.types = ingest::parse_type_hints("id:int64, code:string").unwrap();
+    assert_eq!(o.types.get("code"), Some(&ColumnType::String));
+    let report = ingest::ingest(&shared, "clarium/public/ingest_ndjson", body, &o);
+    assert_eq!((report.rows, report.errors, report.batches), (2, 2, 1));
+    assert_eq!(report.samples.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![4, 5]);
+    assert_eq!(report.to_json()["status"], "partial");
+
+    let rows = execute_query(&shared, "SELECT id, code FROM clarium/public/ingest_ndjson ORDER BY id").await.unwrap();
+    assert_eq!(rows, serde_json::json!([{"id": 1, "code": "7"}, {"id": 2, "code": "8"}]));
+    assert!(ingest::parse_type_hints("id:uuid").is_err());
+}
+
+#[tokio::test]
+async fn test_ingest_gzip_line_protocol_into_time_table_in_batches() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let text = "# comment\n\
+        cpu,host=a usage=0.5,cores=4i 1700000000000000000\n\
+        cpu,host=b usage=0.75,cores=8i,ok=t 1700000001000000000\n\
+        cpu,host=c usage=oops 1700000002000000000\n\
+        cpu,host=d\\ e note=\"x y\",usage=1 1700000003000000000\n";
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    enc.write_all(text.as_bytes()).unwrap();
+    let body = ingest::decode_body(Some("gzip"), &enc.finish().unwrap(), 1 << 20).unwrap();
+    let mut o = opts(IngestFormat::LineProtocol, 2);
+    o.precision = "ns".into();
+    let report = ingest::ingest(&shared, "clarium/public/ingest_lp.time", &body, &o);
+    assert_eq!((report.rows, report.errors, report.batches), (3, 1, 2));
+    assert!(report.samples[0].1.contains("oops"), "{:?}", report.samples);
+
+    let rows = execute_query(&shared, "SELECT _time, host, usage FROM clarium/public/ingest_lp.time").await.unwrap();
+    assert_eq!(rows, serde_json::json!([
+        {"_time": 1700000000000i64, "host": "a", "usage": 0.5},
+        {"_time": 1700000001000i64, "host": "b", "usage": 0.75},
+        {"_time": 1700000003000i64, "host": "d e", "usage": 1.0},
+    ]));
+}
+
+#[test]
+fn test_decode_body_zstd_and_size_limit() {
+    let packed = zstd::encode_all(&b"{\"a\": 1}\n"[..], 3).unwrap();
+    assert_eq!(ingest::decode_body(Some("zstd"), &packed, 1024).unwrap(), b"{\"a\": 1}\n");
+    let err = ingest::decode_body(Some("zstd"), &packed, 4).unwrap_err();
+    assert_eq!(err.sqlstate(), "53400");
+    assert!(ingest::decode_body(Some("br"), b"", 1024).is_err());
+    let (measurement, row) = ingest::parse_line_protocol("m f=\"a,b\",n=2u", "ms").unwrap();
+    assert_eq!(measurement, "m");
+    assert_eq!(serde_json::Value::Object(row), serde_json::json!({"f": "a,b", "n": 2}));
+}
@@ -204,3 +204,21 @@ async fn test_insert_select_with_column_list_and_pk_enforced() {
     let err = execute_query(&shared, &sql_dup).await.err().expect("expected PK violation");
     assert!(err.to_string().contains("PRIMARY KEY"));
 }
+
+#[tokio::test]
+async fn test_insert_into_empty_table_with_declared_columns() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/test_insert_declared";
+    execute_query(&shared, &format!("CREATE TABLE {} (id int, name text)", table)).await.unwrap();
+
+    // The empty table reads back with the declared int64 id; float literals must still stack onto it
+    let result = execute_query(&shared, &format!("INSERT INTO {} (id, name) VALUES (1, 'a'), (2, 'b')", table)).await.unwrap();
+    assert_eq!(result["inserted"], 2);
+    let result = execute_query(&shared, &format!("INSERT INTO {} (name) VALUES ('c')", table)).await.unwrap();
+    assert_eq!(result["inserted"], 1);
+
+    let df = { let g = shared.0.lock(); g.read_df(table).unwrap() };
+    assert_eq!(df.height(), 3);
+    assert_eq!(df.column("id").unwrap().null_count(), 1);
+}
@@ -0,0 +1,101 @@
+use super::super::execute_query;
+use crate::server::jobs::{self, CronSchedule, Job};
+use crate::server::query::JobBody;
+use crate::storage::SharedStore;
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+
+#[test]
+fn cron_schedule_fields() {
+    let at = |d: u32, h: u32, m: u32| Utc.with_ymd_and_hms(2026, 10, d, h, m, 0).unwrap();
+    // 2026-10-16 is a Friday
+    let s = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+    assert!(s.matches(&at(16, 9, 30)));
+    assert!(!s.matches(&at(16, 9, 31)));
+    assert!(!s.matches(&at(16, 18, 0)));
+    assert!(!s.matches(&at(17, 9, 30)));
+    // Both day fields restricted: the 13th or any Friday
+    let s = CronSchedule::parse("0 0 13 * 5").unwrap();
+    assert!(s.matches(&at(13, 0, 0)));
+    assert!(s.matches(&at(16, 0, 0)));
+    assert!(!s.matches(&at(14, 0, 0)));
+    // Lists, n/step, and 7 as Sunday
+    let s = CronSchedule::parse("5/20 0,12 * * 7").unwrap();
+    assert!(s.matches(&at(18, 12, 45)));
+    assert!(!s.matches(&at(18, 12, 40)));
+    assert!(!s.matches(&at(18, 6, 45)));
+    for bad in ["60 * * * *", "* * *", "*/0 * * * *", "5-1 * * * *", "* * * 13 *", "a * * * *"] {
+        assert!(CronSchedule::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[tokio::test]
+async fn test_job_lifecycle_runs_as_owner() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    // Jobs created without a session belong to the default admin
+    crate::security::ensure_default_admin(&root).unwrap();
+    crate::security::add_user(&root, crate::security::Scope::Global, "bob", "pw", crate::security::Perms::default()).unwrap();
+    let exec = |sql: &'static str| execute_query(&shared, sql);
+    exec("CREATE ROLE ops WITH SUPERUSER").await.unwrap();
+    exec("GRANT ops TO clarium").await.unwrap();
+    exec("CREATE TABLE clarium/public/job_log (n BIGINT)").await.unwrap();
+
+    exec("CREATE JOB fill SCHEDULE '*/5 * * * *' AS 'INSERT INTO clarium/public/job_log (n) VALUES (1); INSERT INTO clarium/public/job_log (n) VALUES (2)'").await.unwrap();
+    assert!(exec("CREATE JOB fill SCHEDULE '* * * * *' AS SELECT 1").await.is_err());
+    assert!(exec("CREATE JOB bad SCHEDULE '61 * * * *' AS SELECT 1").await.is_err());
+    let rows = exec("SHOW JOBS").await.unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["owner"], json!("clarium"));
+    assert_eq!(rows[0]["kind"], json!("sql"));
+    assert_eq!(rows[0]["enabled"], json!(true));
+
+    // Disabled jobs are not started
+    exec("ALTER JOB fill DISABLE").await.unwrap();
+    assert!(tick_at(&shared, 5).is_empty());
+    exec("ALTER JOB fill ENABLE").await.unwrap();
+    assert!(tick_at(&shared, 6).is_empty());
+    assert!(exec("ALTER JOB missing ENABLE").await.is_err());
+
+    let job = jobs::list_jobs(&root).unwrap().remove(0);
+    let run = jobs::run_job(&shared, &job).await;
+    assert_eq!(run.status, "succeeded", "{:?}", run.error);
+    assert_eq!(run.statements, 2);
+
+    // A script job runs the statements its function returns
+    crate::scripts::get_script_registry().unwrap().load_script_text("job_rollup",
+        "function job_rollup() return {'INSERT INTO clarium/public/job_log (n) VALUES (10)'} end").unwrap();
+    exec("CREATE JOB rollup SCHEDULE '0 * * * *' AS SCRIPT clarium/public/job_rollup").await.unwrap();
+    let job = jobs::list_jobs(&root).unwrap().into_iter().find(|j| j.name == "rollup").unwrap();
+    assert_eq!(jobs::run_job(&shared, &job).await.status, "succeeded");
+
+    // The owner's privileges apply: bob may not write the table
+    let mut denied = Job { name: "as_bob".into(), owner: "bob".into(), enabled: false, ..job };
+    denied.body = JobBody::Sql("INSERT INTO clarium/public/job_log (n) VALUES (99)".into());
+    jobs::create_job(&root, denied.clone()).unwrap();
+    let run = jobs::run_job(&shared, &denied).await;
+    assert_eq!(run.status, "failed");
+    assert_eq!(run.statements, 0);
+    assert!(run.error.unwrap().contains("permission denied"));
+
+    let total = exec("SELECT SUM(n) AS s FROM clarium/public/job_log").await.unwrap();
+    assert_eq!(total.as_array().unwrap()[0]["s"], json!(13));
+    let runs = exec("SHOW JOB RUNS").await.unwrap();
+    let runs = runs.as_array().unwrap();
+    assert_eq!(runs.len(), 3);
+    let bob = runs.iter().find(|r| r["job"] == json!("as_bob")).unwrap();
+    assert_eq!(bob["owner"], json!("bob"));
+    assert_eq!(bob["status"], json!("failed"));
+
+    exec("DROP JOB fill").await.unwrap();
+    assert!(exec("DROP JOB fill").await.is_err());
+    exec("DROP JOB IF EXISTS fill").await.unwrap();
+    assert_eq!(jobs::list_jobs(&root).unwrap().len(), 2);
+}
+
+fn tick_at(shared: &SharedStore, minute: u32) -> Vec<String> {
+    jobs::tick(shared, Utc.with_ymd_and_hms(2026, 10, 16, 0, minute, 0).unwrap())
+}
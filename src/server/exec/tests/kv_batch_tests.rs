@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use super::super::execute_query;
+use crate::server::query::{self, Command};
+use crate::server::query::query_common::KvBatchWrite;
+use crate::storage::{KvStore, KvValue, KvWrite, PersistenceSettings, SharedStore, StoreSettings};
+
+#[test]
+fn test_parse_multi() {
+    let sql = "MULTI IN kvb.store.s EXPECT 'acct:1' VERSION 4, acct:2 VERSION 0 EXEC $$
+        WRITE KEY acct:1 = 90;
+        WRITE KEY acct:2 IN kvb.store.s = 10 TTL 5s;
+        WRITE KEY log RPUSH 'moved 10';
+        DROP KEY pending
+    $$;";
+    match query::parse(sql).unwrap() {
+        Command::KvBatch { database, store, expect, writes } => {
+            assert_eq!((database.as_str(), store.as_str()), ("kvb", "s"));
+            assert_eq!(expect, vec![("acct:1".to_string(), 4), ("acct:2".to_string(), 0)]);
+            assert_eq!(writes.len(), 4);
+            assert!(matches!(&writes[0], KvBatchWrite::Set { key, value, .. } if key == "acct:1" && value == "90"));
+            assert!(matches!(&writes[1], KvBatchWrite::Set { ttl_ms: Some(5000), .. }));
+            assert!(matches!(&writes[2], KvBatchWrite::Op { key, .. } if key == "log"));
+            assert!(matches!(&writes[3], KvBatchWrite::Delete { key } if key == "pending"));
+        }
+        other => panic!("{:?}", other),
+    }
+    // One store per batch, writes only, and a closed body
+    assert!(query::parse("MULTI IN kvb.store.s EXEC $$ WRITE KEY a IN kvb.store.t = 1 $$").is_err());
+    assert!(query::parse("MULTI IN kvb.store.s EXEC $$ READ KEY a $$").is_err());
+    assert!(query::parse("MULTI IN kvb.store.s EXEC $$ $$").is_err());
+    assert!(query::parse("MULTI IN kvb.store.s EXPECT a VERSION x EXEC $$ WRITE KEY a = 1 $$").is_err());
+    assert!(query::parse("MULTI IN kvb.store.s EXEC WRITE KEY a = 1").is_err());
+}
+
+#[tokio::test]
+async fn test_kv_batch_applies_all_writes_with_versions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "WRITE KEY acct:1 IN kvb.store.s = 100").await.unwrap();
+    execute_query(&shared, "WRITE KEY pending IN kvb.store.s = 'x'").await.unwrap();
+    let v1 = execute_query(&shared, "READ KEY acct:1 IN kvb.store.s").await.unwrap()["version"].as_u64().unwrap();
+    assert!(v1 > 0);
+
+    let sql = format!("MULTI IN kvb.store.s EXPECT acct:1 VERSION {}, acct:2 VERSION 0 EXEC $$
+        WRITE KEY acct:1 = 90; WRITE KEY acct:2 = 10; WRITE KEY log RPUSH 'moved 10'; DROP KEY pending $$", v1);
+    let out = execute_query(&shared, &sql).await.unwrap();
+    assert_eq!(out["results"][0]["written"], 1);
+    assert_eq!(out["results"][0]["type"], "int");
+    assert_eq!(out["results"][2]["length"], 1);
+    assert_eq!(out["results"][3]["dropped"], true);
+    assert_eq!(out["versions"]["pending"], 0);
+    let v1_after = out["versions"]["acct:1"].as_u64().unwrap();
+    assert!(v1_after > v1);
+    let read = execute_query(&shared, "READ KEY acct:1 IN kvb.store.s").await.unwrap();
+    assert_eq!((read["value"].clone(), read["version"].as_u64()), (serde_json::json!(90), Some(v1_after)));
+    assert_eq!(execute_query(&shared, "READ KEY acct:2 IN kvb.store.s").await.unwrap()["value"], 10);
+    assert!(execute_query(&shared, "READ KEY pending IN kvb.store.s").await.is_err());
+}
+
+#[tokio::test]
+async fn test_kv_batch_conflict_and_failure_apply_nothing() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "WRITE KEY acct:1 IN kvb.store.c = 100").await.unwrap();
+    let seen = execute_query(&shared, "READ KEY acct:1 IN kvb.store.c").await.unwrap()["version"].as_u64().unwrap();
+    // Another writer gets in between the read and the batch
+    execute_query(&shared, "WRITE KEY acct:1 IN kvb.store.c = 50").await.unwrap();
+    let sql = format!("MULTI IN kvb.store.c EXPECT acct:1 VERSION {} EXEC $$ WRITE KEY acct:1 = 90; WRITE KEY acct:2 = 10 $$", seen);
+    let err = execute_query(&shared, &sql).await.unwrap_err();
+    assert!(err.to_string().contains("kv_conflict"), "{}", err);
+    let app = crate::error::AppError::classify(&err);
+    assert_eq!((app.http_status(), app.sqlstate()), (409, "40001"));
+    assert_eq!(execute_query(&shared, "READ KEY acct:1 IN kvb.store.c").await.unwrap()["value"], 50);
+    assert!(execute_query(&shared, "READ KEY acct:2 IN kvb.store.c").await.is_err());
+
+    // A failing write undoes the writes before it
+    let err = execute_query(&shared, "MULTI IN kvb.store.c EXEC $$ WRITE KEY acct:2 = 10; WRITE KEY acct:1 LPUSH 'x' $$").await.unwrap_err();
+    assert!(err.to_string().contains("wrong_type"), "{}", err);
+    assert!(execute_query(&shared, "READ KEY acct:2 IN kvb.store.c").await.is_err());
+    assert_eq!(execute_query(&shared, "READ KEY acct:1 IN kvb.store.c").await.unwrap()["value"], 50);
+}
+
+#[test]
+fn test_kv_batch_is_persisted_before_it_returns() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("p");
+    std::fs::create_dir_all(&dir).unwrap();
+    // A long interval: only the batch itself writes the snapshot
+    let persistence = PersistenceSettings { enabled: true, interval_ms: 3_600_000, ..PersistenceSettings::default() };
+    let settings = StoreSettings { name: "p".into(), persistence: Some(persistence), ..StoreSettings::default() };
+    std::fs::write(dir.join("store.json"), serde_json::to_vec(&settings).unwrap()).unwrap();
+
+    let kv = KvStore::load_or_default(dir.clone(), "kvb", "p");
+    let writes = vec![
+        KvWrite::Set { key: "a".into(), value: KvValue::Int(1), ttl: Some(Duration::from_secs(600)), reset_on_access: None },
+        KvWrite::HashSet { key: "h".into(), fields: vec![("f".into(), "v".into())] },
+    ];
+    let res = kv.apply_batch(&[("a".into(), 0)], &writes).unwrap();
+    assert_eq!(res.versions.get("a").copied(), Some(kv.version("a")));
+
+    let reloaded = KvStore::load_or_default(dir, "kvb", "p");
+    reloaded.load_snapshot().unwrap();
+    assert!(matches!(reloaded.get("a"), Some(KvValue::Int(1))));
+    assert_eq!(reloaded.hash_get("h", "f").unwrap().as_deref(), Some("v"));
+}
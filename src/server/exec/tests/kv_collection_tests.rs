@@ -0,0 +1,82 @@
+use super::super::execute_query;
+use crate::storage::{KvValue, SharedStore};
+
+#[tokio::test]
+async fn test_kv_list_set_hash_ops() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    // Lists: LPUSH a, b leaves b first; pops come from either end
+    let out = execute_query(&shared, "WRITE KEY q IN kvc.store.s LPUSH 'a', 'b'").await.unwrap();
+    assert_eq!(out["length"], 2);
+    execute_query(&shared, "WRITE KEY q IN kvc.store.s RPUSH 'c, with comma'").await.unwrap();
+    let out = execute_query(&shared, "READ KEY q IN kvc.store.s").await.unwrap();
+    assert_eq!(out["type"], "list");
+    assert_eq!(out["value"], serde_json::json!(["b", "a", "c, with comma"]));
+    assert_eq!(execute_query(&shared, "WRITE KEY q IN kvc.store.s LPOP").await.unwrap()["value"], "b");
+    assert_eq!(execute_query(&shared, "WRITE KEY q IN kvc.store.s RPOP").await.unwrap()["value"], "c, with comma");
+    assert_eq!(execute_query(&shared, "WRITE KEY q IN kvc.store.s RPOP").await.unwrap()["value"], "a");
+    // The emptied list is gone
+    assert!(execute_query(&shared, "READ KEY q IN kvc.store.s").await.is_err());
+    assert!(execute_query(&shared, "WRITE KEY q IN kvc.store.s LPOP").await.unwrap()["value"].is_null());
+
+    // Sets count only new / present members
+    assert_eq!(execute_query(&shared, "WRITE KEY tags IN kvc.store.s SADD x, y, x").await.unwrap()["added"], 2);
+    assert_eq!(execute_query(&shared, "WRITE KEY tags IN kvc.store.s SADD 'y', 'z'").await.unwrap()["added"], 1);
+    assert_eq!(execute_query(&shared, "WRITE KEY tags IN kvc.store.s SREM x, w").await.unwrap()["removed"], 1);
+    assert_eq!(execute_query(&shared, "READ KEY tags IN kvc.store.s").await.unwrap()["value"], serde_json::json!(["y", "z"]));
+
+    // Hashes
+    let out = execute_query(&shared, "WRITE KEY user:1 IN kvc.store.s HSET name = 'Ada', 'role' = 'admin=yes'").await.unwrap();
+    assert_eq!(out["added"], 2);
+    assert_eq!(execute_query(&shared, "WRITE KEY user:1 IN kvc.store.s HSET name = 'Grace'").await.unwrap()["added"], 0);
+    assert_eq!(execute_query(&shared, "READ KEY user:1 IN kvc.store.s HGET name").await.unwrap()["value"], "Grace");
+    assert_eq!(execute_query(&shared, "READ KEY user:1 IN kvc.store.s HGET 'role'").await.unwrap()["value"], "admin=yes");
+    assert!(execute_query(&shared, "READ KEY user:1 IN kvc.store.s HGET missing").await.unwrap()["value"].is_null());
+    assert_eq!(execute_query(&shared, "WRITE KEY user:1 IN kvc.store.s HDEL role, missing").await.unwrap()["removed"], 1);
+    assert_eq!(execute_query(&shared, "READ KEY user:1 IN kvc.store.s").await.unwrap()["value"], serde_json::json!({"name": "Grace"}));
+    assert_eq!(execute_query(&shared, "DESCRIBE KEY user:1 IN kvc.store.s").await.unwrap()["len"], 1);
+
+    // Scalar writes still parse as before
+    execute_query(&shared, "WRITE KEY n IN kvc.store.s = 5").await.unwrap();
+    assert_eq!(execute_query(&shared, "READ KEY n IN kvc.store.s").await.unwrap()["value"], 5);
+}
+
+#[tokio::test]
+async fn test_kv_collection_wrong_type_and_syntax_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "WRITE KEY n IN kvc.store.s = 5").await.unwrap();
+    execute_query(&shared, "WRITE KEY tags IN kvc.store.s SADD a").await.unwrap();
+
+    let err = execute_query(&shared, "WRITE KEY n IN kvc.store.s LPUSH a").await.unwrap_err().to_string();
+    assert!(err.contains("wrong_type: key 'n' holds int, expected list"), "{}", err);
+    let err = execute_query(&shared, "READ KEY tags IN kvc.store.s HGET a").await.unwrap_err().to_string();
+    assert!(err.contains("wrong_type"), "{}", err);
+    // A failed op leaves the value alone
+    assert_eq!(execute_query(&shared, "READ KEY n IN kvc.store.s").await.unwrap()["value"], 5);
+
+    assert!(execute_query(&shared, "WRITE KEY l IN kvc.store.s LPUSH").await.is_err());
+    assert!(execute_query(&shared, "WRITE KEY l IN kvc.store.s LPOP x").await.is_err());
+    assert!(execute_query(&shared, "WRITE KEY h IN kvc.store.s HSET f").await.is_err());
+    assert!(execute_query(&shared, "WRITE KEY h IN kvc.store.s HGET f").await.is_err());
+    assert!(execute_query(&shared, "READ KEY h IN kvc.store.s SADD f").await.is_err());
+}
+
+#[test]
+fn test_kv_collections_survive_snapshot() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let kv = shared.kv_store("kvc", "persist");
+    kv.list_push("l", &["1".into(), "2".into()], false).unwrap();
+    kv.set_add("s", &["b".into(), "a".into()]).unwrap();
+    kv.hash_set("h", &[("f".into(), "v".into())]).unwrap();
+
+    let reg = shared.kv_registry();
+    reg.snapshot_database("kvc").unwrap();
+    reg.reload_database("kvc");
+    let kv = shared.kv_store("kvc", "persist");
+    match kv.get("l") { Some(KvValue::List(l)) => assert_eq!(l, ["1", "2"]), _ => panic!("list not restored") }
+    match kv.get("s") { Some(KvValue::Set(m)) => assert_eq!(m.into_iter().collect::<Vec<_>>(), ["a", "b"]), _ => panic!("set not restored") }
+    assert_eq!(kv.hash_get("h", "f").unwrap().as_deref(), Some("v"));
+}
@@ -0,0 +1,95 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use crate::server::exec::filestore::config::WebhookConfig;
+use crate::server::exec::filestore::events::webhook_stats;
+use crate::storage::kv_events::{subscribe, KvEvent};
+use crate::storage::{KvValue, SharedStore, StoreSettings};
+
+/// Next "expired" event of store `store` from the process-wide bus.
+async fn next_for(rx: &mut tokio::sync::broadcast::Receiver<KvEvent>, store: &str) -> KvEvent {
+    loop {
+        let ev = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.expect("event").expect("bus open");
+        if ev.store == store && ev.kind == "expired" { return ev; }
+    }
+}
+
+#[tokio::test]
+async fn test_kv_expired_keys_are_hidden_swept_and_published() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let mut rx = subscribe();
+    let kv = shared.kv_store("kvx", "exp1");
+    kv.set("short", KvValue::Int(1), Some(Duration::from_millis(1)), Some(false));
+    kv.set("lazy", KvValue::Int(2), Some(Duration::from_millis(1)), Some(false));
+    kv.set("keep", KvValue::Int(3), None, None);
+    std::thread::sleep(Duration::from_millis(20));
+
+    // Expired keys are neither listed nor counted before the sweep
+    assert_eq!(kv.keys(), vec!["keep".to_string()]);
+    assert_eq!(kv.len(), 1);
+
+    // Read expires lazily
+    assert!(kv.get("lazy").is_none());
+    let ev = next_for(&mut rx, "exp1").await;
+    assert_eq!((ev.kind.as_str(), ev.database.as_str(), ev.key.as_str()), ("expired", "kvx", "lazy"));
+
+    assert_eq!(shared.kv_registry().sweep_all(), 1);
+    let ev = next_for(&mut rx, "exp1").await;
+    assert_eq!((ev.kind.as_str(), ev.key.as_str()), ("expired", "short"));
+    assert_eq!(kv.sweep(), 0);
+    assert!(matches!(kv.get("keep"), Some(KvValue::Int(3))));
+}
+
+#[tokio::test]
+async fn test_kv_expiry_webhook_delivery() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/kv", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (mut conn, _) = listener.accept().unwrap();
+        let mut buf = Vec::new();
+        let mut tmp = [0u8; 4096];
+        // Read the head, then the body up to Content-Length
+        let head_end = loop {
+            let n = conn.read(&mut tmp).unwrap();
+            assert!(n > 0, "connection closed mid-request");
+            buf.extend_from_slice(&tmp[..n]);
+            if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") { break i + 4; }
+        };
+        let len: usize = String::from_utf8_lossy(&buf[..head_end]).lines()
+            .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+            .unwrap_or(0);
+        while buf.len() < head_end + len {
+            let n = conn.read(&mut tmp).unwrap();
+            buf.extend_from_slice(&tmp[..n]);
+        }
+        write!(conn, "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+        String::from_utf8_lossy(&buf).to_ascii_lowercase()
+    });
+
+    // Deliveries reach the worker through the server's storage hooks
+    crate::server::storage_hooks::install();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    // Webhooks are configured in the store's store.json
+    let dir = crate::storage::attach::database_dir(tmp.path(), "kvx").join("stores").join("exp2");
+    std::fs::create_dir_all(&dir).unwrap();
+    let hook = WebhookConfig { url: url.clone(), events: Some(vec!["expired".into()]), prefix: Some("session:".into()), secret_env: None };
+    let settings = StoreSettings { name: "exp2".into(), webhooks: Some(vec![hook]), ..StoreSettings::default() };
+    std::fs::write(dir.join("store.json"), serde_json::to_vec(&settings).unwrap()).unwrap();
+
+    let kv = shared.kv_store("kvx", "exp2");
+    kv.set("session:1", KvValue::Str("u".into()), Some(Duration::from_millis(1)), None);
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(kv.sweep(), 1);
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while webhook_stats("kvx", "store.exp2", &url).delivered == 0 {
+        assert!(Instant::now() < deadline, "webhook not delivered");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let head = server.join().unwrap();
+    assert!(head.contains("x-clarium-event: expired"), "{}", head);
+    assert!(head.contains("\"key\":\"session:1\""), "{}", head);
+}
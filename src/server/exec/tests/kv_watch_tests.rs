@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use super::super::execute_query;
+use crate::server::exec::exec_keys::{handle_watch_command, watch_message_json};
+use crate::server::query::{self, Command};
+use crate::storage::kv_events::{self, WatchMessage, WatchTarget, WATCH_CAPACITY};
+use crate::storage::SharedStore;
+
+async fn next(rx: &mut tokio::sync::mpsc::Receiver<WatchMessage>) -> WatchMessage {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.expect("watch message").expect("channel open")
+}
+
+fn event(m: WatchMessage) -> (u64, String, String) {
+    match m {
+        WatchMessage::Event { watch, event } => (watch, event.kind, event.key),
+        other => panic!("expected an event, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_watch_statements() {
+    match query::parse("WATCH KEY cfg:mode IN kvw.store.s").unwrap() {
+        Command::Watch { database, store, target } => assert_eq!((database.as_str(), store.as_str(), target), ("kvw", "s", WatchTarget::Key("cfg:mode".into()))),
+        other => panic!("{:?}", other),
+    }
+    match query::parse("WATCH PREFIX 'cfg:' IN kvw.store.s;").unwrap() {
+        Command::Watch { target, .. } => assert_eq!(target, WatchTarget::Prefix("cfg:".into())),
+        other => panic!("{:?}", other),
+    }
+    assert!(matches!(query::parse("UNWATCH 7").unwrap(), Command::Unwatch { id: Some(7) }));
+    assert!(matches!(query::parse("UNWATCH ALL").unwrap(), Command::Unwatch { id: None }));
+    assert!(query::parse("UNWATCH x").is_err());
+    assert!(query::parse("WATCH PREFIX 'cfg:'").is_err());
+}
+
+#[tokio::test]
+async fn test_kv_watch_key_and_prefix_deliver_changes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let (tx, mut rx) = kv_events::watch_channel();
+    let mut own = Vec::new();
+    let out = handle_watch_command(&query::parse("WATCH KEY cfg:mode IN kvw.store.w1").unwrap(), &tx, &mut own).unwrap();
+    let key_watch = out["watch"].as_u64().unwrap();
+    assert_eq!(out["key"], "cfg:mode");
+    let out = handle_watch_command(&query::parse("WATCH PREFIX 'feature:' IN kvw.store.w1").unwrap(), &tx, &mut own).unwrap();
+    let prefix_watch = out["watch"].as_u64().unwrap();
+
+    // Unwatched keys and other stores are not delivered
+    execute_query(&shared, "WRITE KEY other IN kvw.store.w1 = 1").await.unwrap();
+    execute_query(&shared, "WRITE KEY cfg:mode IN kvw.store.w2 = 'x'").await.unwrap();
+    execute_query(&shared, "WRITE KEY cfg:mode IN kvw.store.w1 = 'dark'").await.unwrap();
+    assert_eq!(event(next(&mut rx).await), (key_watch, "set".into(), "cfg:mode".into()));
+    execute_query(&shared, "WRITE KEY feature:beta IN kvw.store.w1 SADD a").await.unwrap();
+    execute_query(&shared, "WRITE KEY feature:beta IN kvw.store.w1 SREM a").await.unwrap();
+    assert_eq!(event(next(&mut rx).await), (prefix_watch, "set".into(), "feature:beta".into()));
+    // The last member removed drops the set
+    assert_eq!(event(next(&mut rx).await), (prefix_watch, "delete".into(), "feature:beta".into()));
+    assert!(shared.kv_store("kvw", "w1").delete("cfg:mode"));
+    let m = next(&mut rx).await;
+    assert_eq!(watch_message_json(&m)["event"]["kind"], "delete");
+
+    // A connection removes only its own watches
+    assert!(handle_watch_command(&Command::Unwatch { id: Some(u64::MAX) }, &tx, &mut own).is_err());
+    let out = handle_watch_command(&Command::Unwatch { id: Some(key_watch) }, &tx, &mut own).unwrap();
+    assert_eq!(out["unwatched"], 1);
+    execute_query(&shared, "WRITE KEY cfg:mode IN kvw.store.w1 = 'light'").await.unwrap();
+    execute_query(&shared, "WRITE KEY feature:x IN kvw.store.w1 = 1").await.unwrap();
+    assert_eq!(event(next(&mut rx).await), (prefix_watch, "set".into(), "feature:x".into()));
+    let out = handle_watch_command(&Command::Unwatch { id: None }, &tx, &mut own).unwrap();
+    assert_eq!(out["unwatched"], 1);
+    assert!(!kv_events::unwatch(prefix_watch));
+}
+
+#[tokio::test]
+async fn test_kv_watch_lagging_receiver_is_dropped() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let (tx, mut rx) = kv_events::watch_channel();
+    let id = kv_events::watch("kvw", "lag", WatchTarget::Prefix(String::new()), tx);
+    let kv = shared.kv_store("kvw", "lag");
+    for i in 0..WATCH_CAPACITY + 10 { kv.set(format!("k{}", i), crate::storage::KvValue::Int(i as i64), None, None); }
+    let mut events = 0;
+    loop {
+        match next(&mut rx).await {
+            WatchMessage::Event { .. } => events += 1,
+            WatchMessage::Lagged => break,
+        }
+    }
+    assert_eq!(events, WATCH_CAPACITY - 1);
+    assert!(!kv_events::unwatch(id));
+}
+
+#[tokio::test]
+async fn test_watch_outside_websocket_is_rejected() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let err = execute_query(&shared, "WATCH KEY k IN kvw.store.s").await.unwrap_err();
+    assert!(err.to_string().contains("watch_requires_websocket"), "{}", err);
+}
@@ -0,0 +1,60 @@
+use super::super::execute_query;
+use crate::server::exec::tests::fixtures::rec;
+use crate::storage::SharedStore;
+use serde_json::json;
+
+fn chunk_ranges(shared: &SharedStore, table: &str) -> Vec<(i64, i64)> {
+    let dir = shared.0.lock().db_dir(table);
+    let mut out: Vec<(i64, i64)> = std::fs::read_dir(dir).unwrap()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let base = name.strip_prefix("data-")?.strip_suffix(".parquet")?.to_string();
+            let parts: Vec<i64> = base.split('-').filter_map(|p| p.parse().ok()).collect();
+            Some((parts[0], parts[1]))
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+#[tokio::test]
+async fn test_late_data_separate_keeps_on_time_chunk_tight() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/late_sep.time";
+    shared.0.lock().write_records(table, &[rec(10_000, 1.0), rec(12_000, 2.0)]).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET LATE DATA SEPARATE", table)).await.unwrap();
+
+    shared.0.lock().write_records(table, &[rec(13_000, 3.0), rec(1_000, 0.5)]).unwrap();
+    assert_eq!(chunk_ranges(&shared, table), vec![(1_000, 1_000), (10_000, 12_000), (13_000, 13_000)]);
+    assert_eq!(shared.0.lock().ingest_stats(table).late_records, 1);
+
+    let stats = execute_query(&shared, "SELECT late_records FROM pg_catalog.pg_stat_ingest WHERE relname = 'late_sep.time'").await.unwrap();
+    assert_eq!(stats.as_array().unwrap()[0]["late_records"], json!(1));
+
+    // Compaction folds the late chunk back in
+    let res = execute_query(&shared, &format!("COMPACT TABLE {}", table)).await.unwrap();
+    assert_eq!(res["chunks_after"], json!(1));
+    assert_eq!(chunk_ranges(&shared, table), vec![(1_000, 13_000)]);
+}
+
+#[tokio::test]
+async fn test_late_data_merge_respects_reorder_window() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/late_merge.time";
+    shared.0.lock().write_records(table, &[rec(10_000, 1.0), rec(12_000, 2.0)]).unwrap();
+    execute_query(&shared, &format!("ALTER TABLE {} SET LATE DATA MERGE WINDOW 1000", table)).await.unwrap();
+
+    // 11_500 is within the window; 10_500 is late and lands in the existing chunk
+    shared.0.lock().write_records(table, &[rec(11_500, 3.0), rec(10_500, 4.0)]).unwrap();
+    assert_eq!(chunk_ranges(&shared, table), vec![(10_000, 12_000), (11_500, 11_500)]);
+    assert_eq!(shared.0.lock().ingest_stats(table).late_records, 1);
+
+    let rows = execute_query(&shared, &format!("SELECT _time, v FROM {} ORDER BY _time", table)).await.unwrap();
+    let times: Vec<i64> = rows.as_array().unwrap().iter().map(|r| r["_time"].as_i64().unwrap()).collect();
+    assert_eq!(times, vec![10_000, 10_500, 11_500, 12_000]);
+
+    assert!(execute_query(&shared, &format!("ALTER TABLE {} SET LATE DATA MERGE WINDOW soon", table)).await.is_err());
+}
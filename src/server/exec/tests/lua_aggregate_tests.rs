@@ -0,0 +1,79 @@
+use super::super::run_select;
+use crate::scripts::{get_script_registry, ScriptKind, ScriptRegistry, AGGREGATE_PARTITION_ROWS};
+use crate::server::query::{self, Command};
+use crate::storage::{Record, SharedStore, Store};
+use crate::system;
+use polars::prelude::*;
+use serde_json::json;
+
+// Mean with a state table; `parts` counts the partial states merged into the result
+const STAT_MEAN: &str = r#"--[[ {"kind": "aggregate", "returns": ["float64"]} ]]
+function stat_mean__init() return {n = 0, sum = 0, parts = 1} end
+function stat_mean__accumulate(s, v)
+  if v ~= nil then s.n = s.n + 1; s.sum = s.sum + v end
+  return s
+end
+function stat_mean__merge(a, b) return {n = a.n + b.n, sum = a.sum + b.sum, parts = a.parts + b.parts} end
+function stat_mean__finalize(s) if s.n == 0 then return nil end return s.sum / s.n end
+function stat_mean_parts__init() return stat_mean__init() end
+function stat_mean_parts__accumulate(s, v) return stat_mean__accumulate(s, v) end
+function stat_mean_parts__merge(a, b) return stat_mean__merge(a, b) end
+function stat_mean_parts__finalize(s) return s.parts end
+"#;
+
+#[test]
+fn stateful_aggregate_merges_partial_states() {
+    let reg = ScriptRegistry::new().unwrap();
+    reg.register_script("stat_mean", STAT_MEAN, ScriptKind::Aggregate).unwrap();
+    let meta = reg.get_meta("stat_mean").unwrap();
+    assert!(matches!(meta.kind, ScriptKind::Aggregate));
+    assert_eq!(meta.returns, vec![DataType::Float64]);
+
+    let n = AGGREGATE_PARTITION_ROWS * 2 + 10;
+    let mut vals: Vec<serde_json::Value> = (1..=n).map(|i| json!(i)).collect();
+    vals.push(serde_json::Value::Null);
+    let mean = reg.call_function_json_aggregate("stat_mean", &[json!(vals.clone())]).unwrap();
+    assert_eq!(mean.as_f64(), Some((n as f64 + 1.0) / 2.0));
+    let parts = reg.call_function_json_aggregate("stat_mean_parts", &[json!(vals)]).unwrap();
+    assert_eq!(parts.as_i64(), Some(3));
+
+    // Empty groups still go through init and finalize
+    assert!(reg.call_function_json_aggregate("stat_mean", &[json!([])]).unwrap().is_null());
+}
+
+#[test]
+fn stateful_aggregate_in_group_by_and_by_window() {
+    super::udf_common::init_all_test_udfs();
+    get_script_registry().unwrap().register_script("stat_mean", STAT_MEAN, ScriptKind::Aggregate).unwrap();
+    let prev = system::get_strict_projection();
+    system::set_strict_projection(false);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let db = "udf_stateful.time";
+    store.create_table(db).unwrap();
+    // group a in the first second, group b in the next
+    let rows = [("a", 0i64, 1i64), ("a", 1, 2), ("a", 2, 3), ("b", 1000, 10), ("b", 1001, 20)];
+    let recs: Vec<Record> = rows.iter().map(|(k, t, v)| {
+        let mut m = serde_json::Map::new();
+        m.insert("k".into(), json!(k));
+        m.insert("v".into(), json!(v));
+        Record { _time: 1_700_200_000_000 + t, sensors: m }
+    }).collect();
+    store.write_records(db, &recs).unwrap();
+
+    let q = match query::parse(&format!("SELECT k, stat_mean(v) AS m FROM {} GROUP BY k ORDER BY k", db)).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    let df = run_select(&shared, &q).unwrap();
+    let m = df.column("m").unwrap();
+    assert_eq!(m.dtype(), &DataType::Float64);
+    assert_eq!(m.f64().unwrap().into_no_null_iter().collect::<Vec<_>>(), vec![2.0, 15.0]);
+
+    let q = match query::parse(&format!("SELECT stat_mean(v) AS m FROM {} BY 1s ORDER BY _time", db)).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    let df = run_select(&shared, &q).unwrap();
+    let m = df.column("m").unwrap();
+    assert_eq!(m.dtype(), &DataType::Float64);
+    assert_eq!(m.f64().unwrap().into_no_null_iter().collect::<Vec<_>>(), vec![2.0, 15.0]);
+
+    system::set_strict_projection(prev);
+}
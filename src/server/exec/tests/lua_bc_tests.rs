@@ -138,3 +138,61 @@ async fn test_clear_script_cache_all_nonpersistent_keeps_kv() {
     assert!(!kv_keys_with_prefix(&shared, &prefix1).is_empty());
     assert!(!kv_keys_with_prefix(&shared, &prefix2).is_empty());
 }
+
+#[test]
+fn test_lua_bc_sidecar_reuse_and_invalidation() {
+    let tmp = tempfile::tempdir().unwrap();
+    let script = tmp.path().join("bc_sidecar_fn.lua");
+    let name = "bc_sidecar_fn";
+    let source = "function bc_sidecar_fn(x) return x + 1 end";
+    std::fs::write(&script, source).unwrap();
+    let cache = LuaBytecodeCache::global();
+
+    let b1 = cache.load_or_compile_file(name, &script, source).unwrap();
+    let sidecar = crate::lua_bc::sidecar_path(&script);
+    let hash = LuaBytecodeCache::source_hash(&LuaBytecodeCache::abi_salt(), false, source);
+    let data = std::fs::read(&sidecar).expect("sidecar written");
+    assert!(data.starts_with(format!("CLBC{}\n", hash).as_bytes()));
+    assert!(data.ends_with(&b1));
+
+    // A cold L1 is served from the sidecar
+    cache.invalidate_name(name);
+    assert_eq!(&*cache.load_or_compile_file(name, &script, source).unwrap(), &*b1);
+
+    // Edited source: the stale sidecar is ignored and rewritten
+    let edited = "function bc_sidecar_fn(x) return x + 2 end";
+    std::fs::write(&script, edited).unwrap();
+    let b2 = cache.load_or_compile_file(name, &script, edited).unwrap();
+    assert_ne!(&*b1, &*b2);
+    assert!(std::fs::read(&sidecar).unwrap().ends_with(&b2));
+
+    // Garbage sidecars are recompiled over
+    std::fs::write(&sidecar, b"not bytecode").unwrap();
+    cache.invalidate_name(name);
+    assert_eq!(&*cache.load_or_compile_file(name, &script, edited).unwrap(), &*b2);
+    assert!(std::fs::read(&sidecar).unwrap().ends_with(&b2));
+}
+
+#[tokio::test]
+async fn test_load_script_all_uses_sidecars_and_skips_unchanged() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "CREATE SCRIPT clarium/public/bc_double AS 'function bc_double(x) return x * 2 end'").await.unwrap();
+    let dir = crate::scripts::scripts_dir_for(tmp.path(), "clarium", "public").join("scalars");
+    assert!(crate::lua_bc::sidecar_path(&dir.join("bc_double.lua")).exists());
+
+    let reg = crate::scripts::get_script_registry().unwrap();
+    let version = reg.get_meta("bc_double").unwrap().version;
+    execute_query(&shared, "LOAD SCRIPT ALL").await.unwrap();
+    assert_eq!(reg.get_meta("bc_double").unwrap().version, version);
+    let res = execute_query(&shared, "SELECT bc_double(21) AS v").await.unwrap();
+    assert_eq!(res.as_array().unwrap()[0]["v"], serde_json::json!(42));
+
+    // A script edited on disk is reloaded
+    std::fs::write(dir.join("bc_double.lua"), "function bc_double(x) return x * 3 end").unwrap();
+    execute_query(&shared, "LOAD SCRIPT ALL").await.unwrap();
+    assert!(reg.is_loaded("bc_double", "function bc_double(x) return x * 3 end"));
+    let res = execute_query(&shared, "SELECT bc_double(2) AS v").await.unwrap();
+    assert_eq!(res.as_array().unwrap()[0]["v"], serde_json::json!(6));
+}
@@ -0,0 +1,60 @@
+use super::super::execute_query;
+use super::udf_common::init_all_test_udfs;
+use crate::scripts::{ScriptKind, ScriptRegistry};
+use crate::storage::SharedStore;
+use polars::prelude::*;
+use serde_json::json;
+
+const PAIRS_TVF: &str = r#"--[[ {"columns": [{"name": "n", "type": "int64"}, {"name": "label", "type": "string"}]} ]]
+function lua_pairs(lo, hi, prefix)
+  local i = lo - 1
+  return function()
+    i = i + 1
+    if i > hi then return nil end
+    return i, prefix .. i
+  end
+end
+"#;
+
+#[test]
+fn lua_tvf_iterator_uses_declared_columns() {
+    let reg = ScriptRegistry::new().unwrap();
+    reg.register_script("lua_pairs", PAIRS_TVF, ScriptKind::Tvf).unwrap();
+    let meta = reg.get_meta("lua_pairs").unwrap();
+    assert!(matches!(meta.kind, ScriptKind::Tvf));
+    assert_eq!(meta.tvf_columns.len(), 2);
+
+    // Arguments arrive in call order; each iterator step is one row
+    let df = reg.try_eval_tvf_call("lua_pairs(2, 4, 'x')", None).unwrap().unwrap();
+    assert_eq!(df.get_column_names().iter().map(|c| c.as_str()).collect::<Vec<_>>(), vec!["n", "label"]);
+    assert_eq!(df.column("n").unwrap().dtype(), &DataType::Int64);
+    assert_eq!(df.column("n").unwrap().i64().unwrap().into_no_null_iter().collect::<Vec<_>>(), vec![2, 3, 4]);
+    assert_eq!(df.column("label").unwrap().str().unwrap().get(0), Some("x2"));
+
+    // No rows still yields the declared schema
+    let empty = reg.try_eval_tvf_call("lua_pairs(5, 1, 'x')", None).unwrap().unwrap();
+    assert_eq!(empty.height(), 0);
+    assert_eq!(empty.get_column_names().iter().map(|c| c.as_str()).collect::<Vec<_>>(), vec!["n", "label"]);
+    assert_eq!(empty.column("label").unwrap().dtype(), &DataType::String);
+
+    // Functions of other kinds are not TVFs
+    reg.register_script("not_tvf", "function not_tvf() return 1 end", ScriptKind::Scalar).unwrap();
+    assert!(reg.try_eval_tvf_call("not_tvf()", None).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_select_from_lua_tvf_created_with_sql() {
+    init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, r#"CREATE SCRIPT TVF clarium/public/lua_squares AS 'function lua_squares__meta() return {kind = "tvf", columns = {{name = "x", type = "int64"}, {name = "sq", type = "float64"}}} end function lua_squares(n) local rows = {} for i = 1, n do rows[i] = {x = i, sq = i * i} end return rows end'"#).await.unwrap();
+
+    let res = execute_query(&shared, "SELECT x, sq FROM lua_squares(4) WHERE x > 2 ORDER BY x").await.unwrap();
+    let arr = res.as_array().unwrap();
+    assert_eq!(arr.len(), 2);
+    assert_eq!(arr[0]["x"], json!(3));
+    assert_eq!(arr[1]["sq"], json!(16.0));
+
+    let res = execute_query(&shared, "SELECT s.x FROM lua_squares(2) s ORDER BY s.x DESC").await.unwrap();
+    assert_eq!(res.as_array().unwrap()[0]["x"], json!(2));
+}
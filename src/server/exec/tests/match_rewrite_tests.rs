@@ -66,3 +66,46 @@ fn match_shortest_paths_weighted() {
     };
     assert_eq!(mid.as_str(), "toolA");
 }
+
+#[test]
+fn match_pattern_rewrites_to_graph_match() {
+    let sql = "MATCH USING GRAPH 'clarium/public/g' (a:Tool { name: 'planner' })-[r:Calls]->(b)<-[:Calls]-(c) \
+               WHERE c.name <> 'x' RETURN a.name, c.key LIMIT 5";
+    let rewritten = match query::parse(sql).unwrap() { Command::MatchRewrite { sql } => sql, _ => panic!("expected match rewrite") };
+    assert_eq!(
+        rewritten,
+        "SELECT a.name, c.key FROM graph_match('clarium/public/g','(a:Tool)-[r:Calls]->(b)<-[_r1:Calls]-(c)') \
+         WHERE a.name = 'planner' AND (c.name <> 'x') LIMIT 5"
+    );
+    assert!(matches!(query::parse(&rewritten).unwrap(), Command::Select(_)));
+    // Variable-length and undirected hops are rejected by the fixed-length form
+    assert!(query::parse("MATCH (a:Tool)-[:Calls*1..2]->(b) RETURN b.key").is_err());
+    assert!(query::parse("MATCH (a:Tool)-[:Calls]-(b) RETURN b.key").is_err());
+}
+
+#[test]
+fn match_pattern_hash_join_traversal() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = new_store(&tmp);
+    seed_tools_graph(&store, "clarium/public/nodes_p", "clarium/public/edges_p");
+    write_graph_sidecar(&store, "clarium/public/g_pat", "clarium/public/nodes_p", "clarium/public/edges_p");
+    let run = |sql: &str| futures::executor::block_on(crate::server::exec::execute_query(&store, sql)).unwrap();
+
+    // Two outgoing hops; relationship columns come from the edge table
+    let rows = run("MATCH USING GRAPH 'clarium/public/g_pat' (a:Tool)-[r:Calls]->(b:Tool)-[:Calls]->(c) \
+                    RETURN a.name AS src_name, b.name AS via, c.key AS dst_key, r.cost AS cost");
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 1, "{:?}", rows);
+    assert_eq!((rows[0]["src_name"].clone(), rows[0]["via"].clone(), rows[0]["dst_key"].clone()), (json!("planner"), json!("toolA"), json!("executor")));
+    assert_eq!(rows[0]["cost"].as_f64(), Some(1.0));
+
+    // Incoming hop with an inline property map
+    let rows = run("MATCH USING GRAPH 'clarium/public/g_pat' (b:Tool { name: 'toolA' })<-[:Calls]-(a) RETURN a.key AS caller");
+    assert_eq!(rows.as_array().unwrap().iter().map(|r| r["caller"].clone()).collect::<Vec<_>>(), vec![json!("planner")]);
+
+    // WHERE on the far node, ordered over every single-hop binding
+    let rows = run("MATCH USING GRAPH 'clarium/public/g_pat' (a:Tool)-[:Calls]->(b) WHERE b.name <> 'nobody' \
+                    RETURN a.name AS caller, b.name AS callee ORDER BY caller");
+    let pairs: Vec<(serde_json::Value, serde_json::Value)> = rows.as_array().unwrap().iter().map(|r| (r["caller"].clone(), r["callee"].clone())).collect();
+    assert_eq!(pairs, vec![(json!("planner"), json!("toolA")), (json!("toolA"), json!("executor"))]);
+}
@@ -0,0 +1,68 @@
+use super::super::execute_query;
+use crate::server::exec::spill;
+use crate::storage::{SharedStore, Store};
+use crate::system::parse_memory_size;
+use polars::prelude::*;
+
+#[test]
+fn test_parse_memory_size_units() {
+    assert_eq!(parse_memory_size("64MB"), Some(64 * 1024 * 1024));
+    assert_eq!(parse_memory_size("'512kB'"), Some(512 * 1024));
+    assert_eq!(parse_memory_size("1 GB"), Some(1024 * 1024 * 1024));
+    // Plain numbers are kB
+    assert_eq!(parse_memory_size("8192"), Some(8192 * 1024));
+    assert_eq!(parse_memory_size("100b"), Some(100));
+    assert_eq!(parse_memory_size("lots"), None);
+    assert_eq!(parse_memory_size("10 TB"), None);
+}
+
+#[test]
+fn test_external_sort_matches_in_memory_sort() {
+    let n = 5_000i64;
+    let k: Vec<Option<i64>> = (0..n).map(|i| if i % 97 == 0 { None } else { Some((i * 7919) % 101) }).collect();
+    let v: Vec<i64> = (0..n).collect();
+    let df = DataFrame::new(vec![Series::new("k".into(), k).into(), Series::new("v".into(), v).into()]).unwrap();
+    let opts = SortMultipleOptions::default().with_order_descending(true).with_nulls_last(true).with_maintain_order(true);
+    let expected = df.sort(["k"], opts).unwrap();
+
+    let sorted = spill::external_sort(df.clone(), &["k".to_string()], &[true], None, 4 * 1024).unwrap();
+    assert!(sorted.equals_missing(&expected));
+
+    // LIMIT stops the merge early but keeps the same leading rows
+    let top = spill::external_sort(df, &["k".to_string()], &[true], Some(250), 4 * 1024).unwrap();
+    assert!(top.equals_missing(&expected.slice(0, 250)));
+}
+
+#[tokio::test]
+async fn test_work_mem_spills_order_by_and_group_by() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let table = "clarium/public/mem_spill";
+    store.create_table(table).unwrap();
+    let n = 20_000i64;
+    let grp: Vec<i64> = (0..n).map(|i| (i * 31) % 500).collect();
+    let val: Vec<i64> = (0..n).map(|i| (i * 7) % 1000).collect();
+    let id: Vec<i64> = (0..n).collect();
+    let df = DataFrame::new(vec![
+        Series::new("id".into(), id).into(),
+        Series::new("grp".into(), grp).into(),
+        Series::new("val".into(), val).into(),
+    ]).unwrap();
+    store.rewrite_table_df(table, df).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    let sort_sql = format!("SELECT id, val FROM {} ORDER BY val DESC, id LIMIT 300", table);
+    let group_sql = format!("SELECT grp, SUM(val) AS total, COUNT(*) AS cnt FROM {} GROUP BY grp ORDER BY grp", table);
+    let sorted = execute_query(&shared, &sort_sql).await.unwrap();
+    let grouped = execute_query(&shared, &group_sql).await.unwrap();
+
+    let spilled_before = crate::memory::stats().spilled_bytes_total;
+    execute_query(&shared, "SET work_mem = '64kB'").await.unwrap();
+    assert_eq!(execute_query(&shared, &sort_sql).await.unwrap(), sorted);
+    assert_eq!(execute_query(&shared, &group_sql).await.unwrap(), grouped);
+    assert!(crate::memory::stats().spilled_bytes_total > spilled_before);
+    execute_query(&shared, "SET work_mem = DEFAULT").await.unwrap();
+    assert!(crate::system::work_mem_override().is_none());
+
+    assert!(execute_query(&shared, "SET work_mem = 'plenty'").await.is_err());
+}
@@ -269,3 +269,15 @@ async fn test_drop_table_if_exists_on_nonexistent_table() {
     let result2 = execute_query(&store, "DROP TABLE clarium/public/another_nonexistent").await;
     assert!(result2.is_err(), "DROP TABLE without IF EXISTS should fail for non-existent table");
 }
+
+#[test]
+fn test_normalization_skips_comments() {
+    // A leading statement tag no longer hides the DDL keyword from normalization
+    let result = normalize_query_with_defaults("/* migration 42 */ DROP TABLE my_table", "clarium", "public");
+    assert_eq!(result, "DROP TABLE clarium/public/my_table");
+    let result2 = normalize_query_with_defaults("-- seed\nINSERT INTO my_table VALUES (1)", "clarium", "public");
+    assert_eq!(result2, "INSERT INTO clarium/public/my_table VALUES (1)");
+    // SELECT keeps its comments so optimizer hints still reach the parser
+    let sql = "SELECT /*+ NO_CACHE */ a FROM t";
+    assert_eq!(normalize_query_with_defaults(sql, "clarium", "public"), sql);
+}
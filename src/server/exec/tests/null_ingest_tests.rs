@@ -0,0 +1,62 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use polars::prelude::*;
+use serde_json::json;
+
+fn rec(t: i64, a: serde_json::Value, b: i64) -> Record {
+    let mut sensors = serde_json::Map::new();
+    sensors.insert("a".into(), a);
+    sensors.insert("b".into(), json!(b));
+    Record { _time: t, sensors }
+}
+
+#[tokio::test]
+async fn test_null_only_batch_keeps_column_until_first_value() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/nulls.time";
+
+    shared.0.lock().write_records(table, &[rec(1_000, json!(null), 1), rec(2_000, json!(null), 2)]).unwrap();
+    {
+        let g = shared.0.lock();
+        let (schema, _) = g.load_schema_with_locks(table).unwrap();
+        assert_eq!(schema.get("a"), Some(&DataType::Null));
+        assert_eq!(g.read_df(table).unwrap().column("a").unwrap().null_count(), 2);
+    }
+
+    // The first typed value decides the column type; the earlier chunk still reads back as nulls
+    shared.0.lock().write_records(table, &[rec(3_000, json!(5), 3)]).unwrap();
+    {
+        let g = shared.0.lock();
+        let (schema, _) = g.load_schema_with_locks(table).unwrap();
+        assert_eq!(schema.get("a"), Some(&DataType::Int64));
+        let nullable = g.get_nullability(table);
+        assert_eq!(nullable.get("a"), Some(&true));
+        assert_eq!(nullable.get("b"), Some(&false));
+    }
+
+    let res = execute_query(&shared, &format!("SELECT b FROM {} WHERE a IS NULL ORDER BY b", table)).await.unwrap();
+    let bs: Vec<i64> = res.as_array().unwrap().iter().map(|r| r["b"].as_i64().unwrap()).collect();
+    assert_eq!(bs, vec![1, 2]);
+
+    let res = execute_query(&shared, &format!("SELECT COUNT(a) AS n, SUM(a) AS s, MAX(a) AS m FROM {}", table)).await.unwrap();
+    let row = &res.as_array().unwrap()[0];
+    assert_eq!(row["n"].as_i64(), Some(1));
+    assert_eq!(row["s"].as_f64(), Some(5.0));
+    assert_eq!(row["m"].as_f64(), Some(5.0));
+}
+
+#[tokio::test]
+async fn test_missing_keys_are_nulls_not_defaults() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/sparse.time";
+    let mut only_b = serde_json::Map::new();
+    only_b.insert("b".into(), json!(7));
+    shared.0.lock().write_records(table, &[rec(1_000, json!("x"), 1), Record { _time: 2_000, sensors: only_b }]).unwrap();
+
+    let res = execute_query(&shared, &format!("SELECT b FROM {} WHERE a IS NULL", table)).await.unwrap();
+    assert_eq!(res.as_array().unwrap().len(), 1);
+    assert_eq!(res[0]["b"].as_i64(), Some(7));
+    assert_eq!(shared.0.lock().get_nullability(table).get("a"), Some(&true));
+}
@@ -0,0 +1,23 @@
+use crate::server::{openapi, HTTP_ROUTES};
+
+#[test]
+fn test_openapi_document_covers_every_route() {
+    let doc = openapi::spec(HTTP_ROUTES);
+    assert_eq!(doc["openapi"], "3.0.3");
+    let mut ids = std::collections::HashSet::new();
+    for route in HTTP_ROUTES {
+        let op = &doc["paths"][route.openapi_path()][route.method];
+        assert!(op.is_object(), "{} {} missing from the document", route.method, route.path);
+        assert!(ids.insert(route.operation_id()), "duplicate operationId {}", route.operation_id());
+        // Every referenced schema exists
+        for body in [route.request, route.response].into_iter().flatten() {
+            if !body.schema.is_empty() { assert!(doc["components"]["schemas"][body.schema].is_object(), "{}", body.schema); }
+        }
+    }
+    let ingest = &doc["paths"]["/v1/ingest/{table}"]["post"];
+    assert_eq!(ingest["operationId"], "post_v1_ingest_table");
+    assert_eq!(ingest["parameters"][0], serde_json::json!({"name": "table", "in": "path", "required": true, "schema": {"type": "string"}}));
+    assert!(HTTP_ROUTES.iter().any(|r| r.path == "/v1/ingest/{*table}" && r.bulk));
+    assert_eq!(doc["paths"]["/login"]["post"]["security"], serde_json::json!([]));
+    assert!(doc["paths"]["/openapi"]["get"].is_object());
+}
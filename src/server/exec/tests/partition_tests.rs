@@ -0,0 +1,73 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use serde_json::json;
+
+fn device_batch(start_ms: i64, n: i64) -> Vec<Record> {
+    (0..n).map(|i| {
+        let mut m = serde_json::Map::new();
+        m.insert("device".into(), json!(format!("dev-{}", i % 4)));
+        m.insert("v".into(), json!(i as f64));
+        Record { _time: start_ms + i * 1_000, sensors: m }
+    }).collect()
+}
+
+fn partition_dirs(shared: &SharedStore, table: &str) -> Vec<String> {
+    let dir = shared.0.lock().db_dir(table);
+    let mut out: Vec<String> = std::fs::read_dir(dir).unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n.contains('='))
+        .collect();
+    out.sort();
+    out
+}
+
+#[tokio::test]
+async fn test_list_partitioned_regular_table_prunes_by_equality() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/part_sales";
+    execute_query(&shared, &format!("CREATE TABLE {} (region TEXT, amount INT) PARTITION BY (region)", table)).await.unwrap();
+    execute_query(&shared, &format!("INSERT INTO {} (region, amount) VALUES ('eu', 1), ('us', 2), ('eu', 3), ('ap/se', 4)", table)).await.unwrap();
+    assert_eq!(partition_dirs(&shared, table), vec!["region=ap%2Fse", "region=eu", "region=us"]);
+
+    let rows = execute_query(&shared, &format!("SELECT region, amount FROM {} WHERE region = 'eu'", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+    let rows = execute_query(&shared, &format!("SELECT amount FROM {} WHERE region = 'ap/se'", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+
+    // Only the matching partition folder is read
+    let cols = vec!["region".to_string(), "amount".to_string()];
+    let df = shared.0.lock().filter_df_partitioned(table, &cols, None, None, &[], &[("region".into(), "us".into())]).unwrap();
+    assert_eq!(df.height(), 1);
+    assert_eq!(shared.0.lock().read_df(table).unwrap().height(), 4);
+}
+
+#[tokio::test]
+async fn test_hash_partitioned_time_table_repartition_and_prune() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/part_dev.time";
+    shared.0.lock().write_records(table, &device_batch(1_700_000_000_000, 8)).unwrap();
+    assert!(partition_dirs(&shared, table).is_empty());
+
+    execute_query(&shared, &format!("ALTER TABLE {} SET PARTITION BY HASH (device) BUCKETS 4", table)).await.unwrap();
+    assert_eq!(partition_dirs(&shared, table), vec!["bucket=0", "bucket=1", "bucket=2", "bucket=3"]);
+    // New writes are routed into the bucket folders too
+    shared.0.lock().write_records(table, &device_batch(1_700_000_100_000, 4)).unwrap();
+    assert_eq!(shared.0.lock().read_df(table).unwrap().height(), 12);
+
+    let cols = vec!["device".to_string(), "v".to_string()];
+    let df = shared.0.lock().filter_df_partitioned(table, &cols, None, None, &[], &[("device".into(), "dev-1".into())]).unwrap();
+    assert_eq!(df.height(), 3);
+    let rows = execute_query(&shared, &format!("SELECT _time, v FROM {} WHERE device = 'dev-1'", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 3);
+
+    // Dropping the partitioning moves rows back into the table folder
+    execute_query(&shared, &format!("ALTER TABLE {} SET PARTITION BY NONE", table)).await.unwrap();
+    assert!(partition_dirs(&shared, table).is_empty());
+    assert_eq!(shared.0.lock().read_df(table).unwrap().height(), 12);
+
+    assert!(execute_query(&shared, &format!("ALTER TABLE {} SET PARTITION BY HASH (device)", table)).await.is_err());
+}
@@ -0,0 +1,81 @@
+use super::super::execute_query;
+use crate::storage::SharedStore;
+use serde_json::{json, Value};
+
+async fn ns(shared: &SharedStore) -> Vec<i64> {
+    let res = execute_query(shared, "SELECT n FROM clarium/public/proc_items ORDER BY n").await.unwrap();
+    res.as_array().unwrap().iter().map(|r| r["n"].as_i64().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_procedures_control_flow_and_transactions() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let exec = |sql: &'static str| execute_query(&shared, sql);
+    exec("CREATE TABLE clarium/public/proc_items (n BIGINT, label TEXT)").await.unwrap();
+
+    // Variables, WHILE with CONTINUE / EXIT, IF / ELSIF
+    exec("CREATE PROCEDURE clarium/public/fill(cnt BIGINT, tag TEXT) AS $$
+        DECLARE i BIGINT := 0;
+        BEGIN
+            WHILE i < cnt LOOP
+                i := i + 1;
+                IF i = 2 THEN CONTINUE; ELSIF i > 4 THEN EXIT; END IF;
+                INSERT INTO proc_items (n, label) VALUES (i, tag);
+            END LOOP;
+        END
+    $$").await.unwrap();
+    assert!(exec("CREATE PROCEDURE fill(cnt BIGINT) AS $$ SELECT 1 $$").await.is_err());
+    assert_eq!(exec("CALL fill(10, 'a')").await.unwrap(), json!({"status": "ok"}));
+    assert_eq!(ns(&shared).await, vec![1, 3, 4]);
+    let labels = exec("SELECT DISTINCT label FROM clarium/public/proc_items").await.unwrap();
+    assert_eq!(labels, json!([{"label": "a"}]));
+    assert!(exec("CALL fill(1)").await.unwrap_err().to_string().contains("takes 2 argument(s)"));
+
+    // A query assigned to a variable; nested CALL
+    exec("CREATE PROCEDURE count_items() AS $$
+        DECLARE total BIGINT;
+        total := (SELECT COUNT(*) AS c FROM proc_items);
+        INSERT INTO proc_items (n, label) VALUES (total * 10, 'count');
+        CALL fill(1, 'nested')
+    $$").await.unwrap();
+    exec("CALL count_items()").await.unwrap();
+    assert_eq!(ns(&shared).await, vec![1, 1, 3, 4, 30]);
+
+    // RAISE EXCEPTION rolls back everything since the last COMMIT
+    exec("CREATE PROCEDURE guarded(v BIGINT) AS $$
+        INSERT INTO proc_items (n, label) VALUES (v, 'g');
+        COMMIT;
+        INSERT INTO proc_items (n, label) VALUES (v + 1, 'g');
+        IF v > 100 THEN RAISE EXCEPTION 'value % too large', v; END IF;
+    $$").await.unwrap();
+    exec("CALL guarded(50)").await.unwrap();
+    let err = exec("CALL guarded(500)").await.unwrap_err();
+    assert!(err.to_string().contains("value 500 too large"), "{}", err);
+    assert_eq!(ns(&shared).await, vec![1, 1, 3, 4, 30, 50, 51, 500]);
+
+    // ROLLBACK discards the writes and the procedure carries on
+    exec("CREATE PROCEDURE undo_one() AS $$
+        DELETE FROM proc_items WHERE label = 'g';
+        ROLLBACK;
+        INSERT INTO proc_items (n, label) VALUES (600, 'kept')
+    $$").await.unwrap();
+    exec("CALL undo_one()").await.unwrap();
+    assert_eq!(ns(&shared).await, vec![1, 1, 3, 4, 30, 50, 51, 500, 600]);
+
+    // Runaway recursion stops at the nesting limit
+    exec("CREATE PROCEDURE recurse() AS $$ CALL recurse() $$").await.unwrap();
+    assert!(exec("CALL recurse()").await.unwrap_err().to_string().contains("nested more than"));
+
+    let rows = exec("SELECT proname, prokind, pronargs, proargnames FROM pg_catalog.pg_proc WHERE proname = 'fill'").await.unwrap();
+    assert_eq!(rows, json!([{"proname": "fill", "prokind": "p", "pronargs": 2, "proargnames": "{cnt,tag}"}]));
+
+    exec("CREATE OR REPLACE PROCEDURE fill() AS $$ SELECT 1 $$").await.unwrap();
+    exec("DROP PROCEDURE fill").await.unwrap();
+    assert!(exec("CALL fill()").await.is_err());
+    assert!(exec("DROP PROCEDURE fill").await.is_err());
+    exec("DROP PROCEDURE IF EXISTS fill").await.unwrap();
+    let left: Value = exec("SELECT COUNT(*) AS c FROM pg_catalog.pg_proc").await.unwrap();
+    assert_eq!(left.as_array().unwrap()[0]["c"], json!(4));
+}
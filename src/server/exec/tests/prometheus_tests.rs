@@ -0,0 +1,83 @@
+use super::super::execute_query;
+use crate::server::prometheus::{self, Layout};
+use crate::storage::SharedStore;
+
+// Protobuf encoding helpers for a WriteRequest
+fn varint(mut v: u64, out: &mut Vec<u8>) {
+    while v >= 0x80 { out.push((v as u8) | 0x80); v >>= 7; }
+    out.push(v as u8);
+}
+
+fn bytes_field(num: u64, data: &[u8], out: &mut Vec<u8>) {
+    varint(num << 3 | 2, out);
+    varint(data.len() as u64, out);
+    out.extend_from_slice(data);
+}
+
+fn series(labels: &[(&str, &str)], samples: &[(f64, i64)]) -> Vec<u8> {
+    let mut ts = Vec::new();
+    for (k, v) in labels {
+        let mut l = Vec::new();
+        bytes_field(1, k.as_bytes(), &mut l);
+        bytes_field(2, v.as_bytes(), &mut l);
+        bytes_field(1, &l, &mut ts);
+    }
+    for (value, t) in samples {
+        let mut s = Vec::new();
+        varint(1 << 3 | 1, &mut s);
+        s.extend_from_slice(&value.to_bits().to_le_bytes());
+        varint(2 << 3, &mut s);
+        varint(*t as u64, &mut s);
+        bytes_field(2, &s, &mut ts);
+    }
+    ts
+}
+
+fn write_request() -> Vec<u8> {
+    let mut req = Vec::new();
+    bytes_field(1, &series(&[("__name__", "http_requests_total"), ("job", "api"), ("code", "200")], &[(10.0, 1_000), (12.0, 2_000), (f64::NAN, 3_000)]), &mut req);
+    bytes_field(1, &series(&[("__name__", "node:cpu_seconds"), ("job", "api"), ("code", "200")], &[(0.5, 1_000)]), &mut req);
+    req
+}
+
+#[tokio::test]
+async fn test_remote_write_per_metric_tables() {
+    let body = snap::raw::Encoder::new().compress_vec(&write_request()).unwrap();
+    let decoded = prometheus::decompress(&body, 1 << 20).unwrap();
+    let series = prometheus::decode_write_request(&decoded).unwrap();
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].labels[0], ("__name__".to_string(), "http_requests_total".to_string()));
+
+    let (tables, skipped) = prometheus::to_records(&series, &Layout::PerMetric);
+    assert_eq!(skipped, 1);
+    assert_eq!(tables.keys().collect::<Vec<_>>(), vec!["http_requests_total", "node_cpu_seconds"]);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    {
+        let guard = shared.0.lock();
+        for (table, records) in tables.iter() {
+            let path = format!("clarium/public/{}.time", table);
+            guard.create_table(&path).ok();
+            guard.write_records(&path, records).unwrap();
+        }
+    }
+    let rows = execute_query(&shared, "SELECT _time, job, code, value FROM clarium/public/http_requests_total.time").await.unwrap();
+    assert_eq!(rows, serde_json::json!([
+        {"_time": 1000, "job": "api", "code": "200", "value": 10.0},
+        {"_time": 2000, "job": "api", "code": "200", "value": 12.0},
+    ]));
+}
+
+#[test]
+fn test_remote_write_wide_layout_merges_series_rows() {
+    let series = prometheus::decode_write_request(&write_request()).unwrap();
+    let (tables, _) = prometheus::to_records(&series, &Layout::Wide("prometheus".into()));
+    let rows = &tables["prometheus"];
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]._time, 1_000);
+    assert_eq!(rows[0].sensors["http_requests_total"], 10.0);
+    assert_eq!(rows[0].sensors["node_cpu_seconds"], 0.5);
+    assert!(prometheus::decode_write_request(&[0x0a, 0x05, 0x01]).is_err());
+    assert!(prometheus::decompress(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff], 1 << 20).is_err());
+}
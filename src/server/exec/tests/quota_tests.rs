@@ -0,0 +1,53 @@
+use super::super::execute_query;
+use crate::config::LimitSettings;
+use crate::server::activity::{self, BackendGuard, Frontend};
+use crate::server::quota::{self, admit_query_as, charge_as};
+use crate::storage::SharedStore;
+
+fn usage(user: &str) -> quota::QuotaUsage {
+    quota::snapshot().into_iter().find(|(u, _)| u == user).map(|(_, q)| q).unwrap_or_default()
+}
+
+#[tokio::test]
+async fn test_queries_per_minute_throttles_then_rejects() {
+    // Unique user names keep this independent of statements run by other tests
+    let limits = LimitSettings { queries_per_minute: 2, queue_timeout_ms: 20, ..Default::default() };
+    admit_query_as("quota_alice", &limits).await.unwrap();
+    admit_query_as("quota_alice", &limits).await.unwrap();
+    let err = admit_query_as("quota_alice", &limits).await.unwrap_err();
+    assert!(err.to_string().contains("rate limit exceeded"), "{}", err);
+    let u = usage("quota_alice");
+    assert_eq!((u.queries_last_minute, u.rejected_total), (2, 1));
+    // Other users have their own window
+    admit_query_as("quota_bob", &limits).await.unwrap();
+}
+
+#[test]
+fn test_daily_quotas_reject_the_crossing_statement() {
+    let limits = LimitSettings { rows_scanned_per_day: 100, ingest_bytes_per_day: 1_000, ..Default::default() };
+    charge_as("quota_carol", 60, 0, &limits).unwrap();
+    let err = charge_as("quota_carol", 60, 0, &limits).unwrap_err();
+    assert!(err.to_string().contains("limits.rows_scanned_per_day = 100"), "{}", err);
+    charge_as("quota_carol", 0, 900, &limits).unwrap();
+    assert!(charge_as("quota_carol", 0, 200, &limits).is_err());
+    let u = usage("quota_carol");
+    assert_eq!((u.rows_scanned_today, u.ingest_bytes_today, u.rejected_total), (120, 1_100, 2));
+}
+
+#[tokio::test]
+async fn test_usage_is_charged_to_the_running_backend() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "CREATE TABLE quota_t (a INT)").await.unwrap();
+    let pid = activity::register(Frontend::Pgwire, "quota_dave", "clarium", "", None);
+    let _guard = BackendGuard(pid);
+    activity::run_statement(Some(pid), "INSERT", execute_query(&shared, "INSERT INTO quota_t (a) VALUES (1), (2), (3)")).await.unwrap();
+    activity::run_statement(Some(pid), "SELECT", execute_query(&shared, "SELECT a FROM quota_t")).await.unwrap();
+    let u = usage("quota_dave");
+    assert_eq!(u.queries_last_minute, 2);
+    assert!(u.ingest_bytes_today > 0);
+    assert!(u.rows_scanned_today >= 3);
+
+    let rows = execute_query(&shared, "SELECT queries_last_minute, rows_scanned_per_day FROM pg_catalog.clarium_quotas WHERE usename = 'quota_dave'").await.unwrap();
+    assert_eq!(rows, serde_json::json!([{"queries_last_minute": 2, "rows_scanned_per_day": 0}]));
+}
@@ -0,0 +1,51 @@
+use super::super::execute_query;
+use crate::server::query::parse;
+use crate::server::replication::{build_manifest, diff, is_read_only_command, resolve_rel_path, sync_once_with};
+use crate::storage::SharedStore;
+
+#[tokio::test]
+async fn test_replication_sync_copies_tables_and_removes_stale_files() {
+    let primary = tempfile::tempdir().unwrap();
+    let replica = tempfile::tempdir().unwrap();
+    let pstore = SharedStore::new(primary.path()).unwrap();
+    let table = "clarium/public/repl_items";
+    execute_query(&pstore, &format!("CREATE TABLE {}", table)).await.unwrap();
+    execute_query(&pstore, &format!("INSERT INTO {} (id, name) VALUES (1, 'a'), (2, 'b')", table)).await.unwrap();
+
+    // A file the primary does not have must be removed on the replica
+    std::fs::create_dir_all(replica.path().join("clarium/public")).unwrap();
+    std::fs::write(replica.path().join("clarium/public/stale.txt"), b"x").unwrap();
+
+    let manifest = build_manifest(primary.path()).unwrap();
+    assert!(manifest.files.iter().any(|f| f.path.ends_with("repl_items/schema.json")));
+    assert!(manifest.position > 0);
+    let proot = primary.path().to_path_buf();
+    let fetch = |rel: String| { let p = proot.join(rel); async move { std::fs::read(p).map_err(anyhow::Error::from) } };
+    let (written, removed) = sync_once_with(replica.path(), &manifest, fetch).await.unwrap();
+    assert!(written >= 2);
+    assert_eq!(removed, 1);
+    assert!(!replica.path().join("clarium/public/stale.txt").exists());
+
+    let rstore = SharedStore::new(replica.path()).unwrap();
+    let res = execute_query(&rstore, &format!("SELECT id, name FROM {} ORDER BY id", table)).await.unwrap();
+    let arr = res.as_array().unwrap();
+    assert_eq!(arr.len(), 2);
+    assert_eq!(arr[1]["name"], serde_json::json!("b"));
+
+    // Converged: a second pass has nothing to do
+    let (fetch_list, remove_list) = diff(&build_manifest(replica.path()).unwrap(), &build_manifest(primary.path()).unwrap());
+    assert!(fetch_list.is_empty() && remove_list.is_empty());
+}
+
+#[test]
+fn test_replication_read_only_classification_and_path_checks() {
+    assert!(is_read_only_command(&parse("SELECT 1").unwrap()));
+    assert!(is_read_only_command(&parse("SHOW TABLES").unwrap()));
+    assert!(!is_read_only_command(&parse("INSERT INTO t (a) VALUES (1)").unwrap()));
+    assert!(!is_read_only_command(&parse("DROP TABLE t").unwrap()));
+
+    let root = std::path::Path::new("/data");
+    assert!(resolve_rel_path(root, "clarium/public/t/data.parquet").is_ok());
+    assert!(resolve_rel_path(root, "../etc/passwd").is_err());
+    assert!(resolve_rel_path(root, "/etc/passwd").is_err());
+}
@@ -0,0 +1,51 @@
+use super::super::execute_query;
+use crate::storage::SharedStore;
+use serde_json::Value;
+
+async fn cache_stat(shared: &SharedStore, name: &str) -> i64 {
+    let stats = execute_query(shared, "SHOW CACHE STATS").await.unwrap();
+    stats[0][name].as_i64().unwrap()
+}
+
+#[tokio::test]
+async fn test_result_cache_hits_until_table_is_written() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/cache_dash";
+    execute_query(&shared, &format!("CREATE TABLE {}", table)).await.unwrap();
+    execute_query(&shared, &format!("INSERT INTO {} (id, v) VALUES (1, 10), (2, 20)", table)).await.unwrap();
+    let sql = format!("SELECT SUM(v) AS total FROM {}", table);
+
+    // Off by default: nothing is cached
+    let hits0 = cache_stat(&shared, "hits").await;
+    execute_query(&shared, &sql).await.unwrap();
+    execute_query(&shared, &sql).await.unwrap();
+    assert_eq!(cache_stat(&shared, "hits").await, hits0);
+
+    execute_query(&shared, "SET enable_result_cache = on").await.unwrap();
+    let stats = execute_query(&shared, "SHOW CACHE STATS").await.unwrap();
+    assert_eq!(stats[0]["enabled"], Value::Bool(true));
+
+    let first = execute_query(&shared, &sql).await.unwrap();
+    assert_eq!(first[0]["total"].as_f64(), Some(30.0));
+    // Same statement modulo whitespace and trailing semicolon is a hit
+    let again = execute_query(&shared, &format!("SELECT  SUM(v) AS total\n FROM {} ;", table)).await.unwrap();
+    assert_eq!(again, first);
+    assert_eq!(cache_stat(&shared, "hits").await, hits0 + 1);
+
+    // A write to the table invalidates the cached result
+    let inv0 = cache_stat(&shared, "invalidations").await;
+    execute_query(&shared, &format!("INSERT INTO {} (id, v) VALUES (3, 5)", table)).await.unwrap();
+    assert_eq!(execute_query(&shared, &sql).await.unwrap()[0]["total"].as_f64(), Some(35.0));
+    assert!(cache_stat(&shared, "invalidations").await > inv0);
+    assert_eq!(cache_stat(&shared, "hits").await, hits0 + 1);
+
+    // Volatile functions are never cached
+    let volatile = format!("SELECT SUM(v) AS total, now() AS at FROM {}", table);
+    execute_query(&shared, &volatile).await.unwrap();
+    execute_query(&shared, &volatile).await.unwrap();
+    assert_eq!(cache_stat(&shared, "hits").await, hits0 + 1);
+
+    assert!(execute_query(&shared, "SET enable_result_cache = sometimes").await.is_err());
+    execute_query(&shared, "SET enable_result_cache = off").await.unwrap();
+}
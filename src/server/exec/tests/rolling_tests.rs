@@ -33,3 +33,55 @@ fn test_rolling_by_avg_simple() {
 }
 
 
+
+fn seed_gappy(tmp: &tempfile::TempDir, db: &str) -> SharedStore {
+    // Samples at 0s, 1s, 2s then 10s, 11s
+    let store = Store::new(tmp.path()).unwrap();
+    let base: i64 = 1_700_200_000_000;
+    let recs: Vec<Record> = [(0i64, 5.0), (1, 1.0), (2, 2.0), (10, 4.0), (11, 3.0)].iter().map(|(s, v)| {
+        let mut m = serde_json::Map::new();
+        m.insert("v".into(), json!(*v));
+        Record { _time: base + s * 1000, sensors: m }
+    }).collect();
+    store.write_records(db, &recs).unwrap();
+    SharedStore::new(tmp.path()).unwrap()
+}
+
+fn assert_col(df: &polars::prelude::DataFrame, name: &str, expected: &[f64]) {
+    let col = df.column(name).unwrap().f64().unwrap();
+    for (i, e) in expected.iter().enumerate() {
+        let got = col.get(i).unwrap();
+        assert!((got - e).abs() < 1e-9, "{} idx {} expected {} got {}", name, i, e, got);
+    }
+}
+
+#[test]
+fn test_rolling_by_rows_frame() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_gappy(&tmp, "db_roll_rows.time");
+    let qtext = "SELECT SUM(v) AS s, COUNT(*) AS n FROM db_roll_rows.time ROLLING BY ROWS BETWEEN 1 PRECEDING AND CURRENT ROW";
+    let q = match query::parse(qtext).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    assert_eq!((q.rolling_window_ms, q.rolling_rows), (None, Some(1)));
+    let df = run_select(&shared, &q).unwrap();
+    assert_col(&df, "s", &[5.0, 6.0, 3.0, 6.0, 7.0]);
+    assert_col(&df, "n", &[1.0, 2.0, 2.0, 2.0, 2.0]);
+}
+
+#[test]
+fn test_rolling_frame_overrides_and_moving_averages() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_gappy(&tmp, "db_roll_ma.time");
+    let qtext = "SELECT MAX(v) OVER (RANGE BETWEEN 2s PRECEDING AND CURRENT ROW) AS m, EMA(v, 0.5) AS e, WMA(v) AS w \
+                 FROM db_roll_ma.time ROLLING BY ROWS BETWEEN 2 PRECEDING AND CURRENT ROW";
+    let q = match query::parse(qtext).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    let df = run_select(&shared, &q).unwrap();
+    assert_col(&df, "m", &[5.0, 5.0, 2.0, 4.0, 4.0]);
+    assert_col(&df, "e", &[5.0, 3.0, 2.5, 3.25, 3.125]);
+    assert_col(&df, "w", &[5.0, 7.0 / 3.0, 13.0 / 6.0, 17.0 / 6.0, 19.0 / 6.0]);
+
+    // Moving averages need ROLLING BY; EMA needs a valid smoothing factor
+    let q = match query::parse("SELECT EMA(v, 0.5) FROM db_roll_ma.time BY 5s").unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    assert!(run_select(&shared, &q).is_err());
+    assert!(query::parse("SELECT EMA(v, 1.5) FROM db_roll_ma.time ROLLING BY 5s").is_err());
+    assert!(query::parse("SELECT AVG(v) FROM db_roll_ma.time ROLLING BY ROWS BETWEEN x PRECEDING AND CURRENT ROW").is_err());
+}
@@ -0,0 +1,42 @@
+use crate::config::LimitSettings;
+use crate::server::exec::scheduler::{self, acquire_as};
+use crate::server::query::parse;
+
+fn slots(user: &str) -> scheduler::UserSlots {
+    scheduler::snapshot().into_iter().find(|(u, _)| u == user).map(|(_, s)| s).unwrap_or_default()
+}
+
+#[test]
+fn test_only_analytical_statements_are_scheduled() {
+    assert!(scheduler::is_analytical(&parse("SELECT 1").unwrap()));
+    assert!(scheduler::is_analytical(&parse("EXPLAIN SELECT 1").unwrap()));
+    assert!(!scheduler::is_analytical(&parse("INSERT INTO t (a) VALUES (1)").unwrap()));
+    assert!(!scheduler::is_analytical(&parse("SET work_mem = '4MB'").unwrap()));
+}
+
+#[tokio::test]
+async fn test_per_user_limit_queues_then_times_out() {
+    // Unique user names keep this independent of statements run by other tests
+    let limits = LimitSettings { max_queries_per_user: 1, queue_timeout_ms: 50, ..Default::default() };
+    let first = acquire_as("sched_alice".into(), &limits).await.unwrap();
+    assert_eq!(slots("sched_alice").running, 1);
+
+    let err = acquire_as("sched_alice".into(), &limits).await.err().unwrap();
+    assert!(err.to_string().contains("too many concurrent queries"));
+    assert_eq!(slots("sched_alice").timed_out_total, 1);
+    assert_eq!(slots("sched_alice").queued, 0);
+
+    // Other users are not held up
+    let other = acquire_as("sched_bob".into(), &limits).await.unwrap();
+    drop(other);
+
+    // A queued statement starts as soon as the slot is released
+    let patient = LimitSettings { queue_timeout_ms: 5_000, ..limits };
+    let waiter = tokio::spawn(async move { acquire_as("sched_alice".into(), &patient).await.map(|_| ()) });
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert_eq!(slots("sched_alice").queued, 1);
+    drop(first);
+    waiter.await.unwrap().unwrap();
+    let s = slots("sched_alice");
+    assert_eq!((s.running, s.queued, s.admitted_total), (0, 0, 2));
+}
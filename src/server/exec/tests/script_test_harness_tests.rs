@@ -0,0 +1,61 @@
+use super::super::execute_query;
+use crate::storage::SharedStore;
+use serde_json::{json, Value};
+
+fn statuses(rows: &Value) -> Vec<(String, String)> {
+    rows.as_array().unwrap().iter()
+        .map(|r| (r["test"].as_str().unwrap().to_string(), r["status"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_script_tests_and_assert() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let exec = |sql: &'static str| execute_query(&shared, sql);
+    exec("CREATE TABLE clarium/public/st_items (n BIGINT)").await.unwrap();
+    exec("INSERT INTO clarium/public/st_items (n) VALUES (1), (2)").await.unwrap();
+
+    // ASSERT as a plain statement
+    exec("ASSERT (SELECT COUNT(*) AS c FROM clarium/public/st_items) = 2").await.unwrap();
+    let err = exec("ASSERT (SELECT MAX(n) AS m FROM clarium/public/st_items) > 5, 'max too small'").await.unwrap_err();
+    assert!(err.to_string().contains("max too small"), "{}", err);
+    assert!(exec("ASSERT 1 = 2").await.unwrap_err().to_string().contains("assertion failed: 1 = 2"));
+
+    // Lua: each test_* function is a case and can call registered UDFs
+    exec("CREATE SCRIPT TEST clarium/public/dbl_checks AS $$
+        function test_doubles() assert_eq(dbl(21), 42) end
+        function test_wrong() assert_eq(dbl(1), 3, 'dbl(1)') end
+    $$").await.unwrap();
+    // SQL: unqualified tables live in a scratch schema for the run
+    exec("CREATE SCRIPT TEST SQL clarium/public/scratch_rows AS $$
+        CREATE TABLE tmp_rows (v BIGINT);
+        INSERT INTO tmp_rows (v) VALUES (10), (20);
+        ASSERT (SELECT SUM(v) AS s FROM tmp_rows) = 30;
+        ASSERT (SELECT COUNT(*) AS c FROM clarium/public/st_items) = 3, 'expected three items';
+    $$").await.unwrap();
+    assert!(exec("CREATE SCRIPT TEST bad_lua AS $$ function test_x( $$").await.is_err());
+    assert!(exec("CREATE SCRIPT TEST SQL bad_sql AS $$ SELEC 1 $$").await.is_err());
+
+    let rows = exec("RUN TESTS clarium/public").await.unwrap();
+    assert_eq!(statuses(&rows), vec![
+        ("clarium/public/dbl_checks.test_doubles".to_string(), "pass".to_string()),
+        ("clarium/public/dbl_checks.test_wrong".to_string(), "fail".to_string()),
+        ("clarium/public/scratch_rows".to_string(), "fail".to_string()),
+    ]);
+    let messages: Vec<Value> = rows.as_array().unwrap().iter().map(|r| r["message"].clone()).collect();
+    assert_eq!(messages[0], Value::Null);
+    assert!(messages[1].as_str().unwrap().contains("dbl(1): expected 3, got 2"), "{}", messages[1]);
+    assert_eq!(messages[2], json!("expected three items"));
+    // The scratch schema is gone after the run
+    assert!(exec("SELECT v FROM clarium/public/tmp_rows").await.is_err());
+
+    let one = exec("RUN TESTS clarium/public/scratch_rows").await.unwrap();
+    assert_eq!(one.as_array().unwrap().len(), 1);
+
+    exec("DROP SCRIPT TEST clarium/public/dbl_checks").await.unwrap();
+    assert!(exec("DROP SCRIPT TEST clarium/public/dbl_checks").await.is_err());
+    exec("DROP SCRIPT TEST IF EXISTS clarium/public/dbl_checks").await.unwrap();
+    assert!(exec("RUN TESTS clarium/public/dbl_checks").await.is_err());
+}
@@ -0,0 +1,53 @@
+use crate::server::query::{self, Command, query_common::{IntoMode, IntoOptions}};
+use crate::storage::{Store, SharedStore, Record};
+use serde_json::json;
+
+fn seed_table(tmp: &tempfile::TempDir, name: &str) -> SharedStore {
+    let store = Store::new(tmp.path()).unwrap();
+    let recs: Vec<Record> = (1..=4i64).map(|i| {
+        let mut m = serde_json::Map::new();
+        m.insert("id".into(), json!(i));
+        m.insert("region".into(), json!(if i % 2 == 0 { "east" } else { "west" }));
+        m.insert("v".into(), json!(i * 10));
+        Record { _time: 1_700_000_000_000 + i, sensors: m }
+    }).collect();
+    store.write_records(name, &recs).unwrap();
+    SharedStore::new(tmp.path()).unwrap()
+}
+
+#[test]
+fn parse_into_options_and_merge_mode() {
+    let q = match query::parse("SELECT id, v FROM clarium/public/src WHERE v > 1 INTO clarium/public/dst (PRIMARY KEY(id), PARTITION BY region) MERGE").unwrap() {
+        Command::Select(q) => q, _ => unreachable!()
+    };
+    assert!(q.where_clause.is_some());
+    assert_eq!(q.into_table.as_deref(), Some("clarium/public/dst"));
+    assert_eq!(q.into_mode, Some(IntoMode::Merge));
+    assert_eq!(q.into_options, Some(IntoOptions { primary_key: Some(vec!["id".into()]), partitions: Some(vec!["region".into()]), time_table: false }));
+    assert!(query::parse("SELECT id FROM clarium/public/src INTO clarium/public/dst (TIME TABLE)").is_err());
+    assert!(query::parse("SELECT id FROM clarium/public/src INTO clarium/public/dst (CLUSTER BY id)").is_err());
+    assert!(query::parse("SELECT id FROM clarium/public/src INTO clarium/public/dst UPSERT").is_err());
+}
+
+#[test]
+fn into_merge_upserts_by_primary_key() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_table(&tmp, "clarium/public/src.time");
+    let run = |sql: &str| futures::executor::block_on(crate::server::exec::execute_query(&shared, sql));
+
+    run("SELECT id, region, v FROM clarium/public/src.time WHERE id <= 2 INTO clarium/public/dst (PRIMARY KEY(id), PARTITION BY(region))").unwrap();
+    assert_eq!(shared.0.lock().get_primary_key("clarium/public/dst"), Some(vec!["id".to_string()]));
+
+    // ids 2..4: 2 is replaced, 3 and 4 are new
+    run("SELECT id, region, v + 1 AS v FROM clarium/public/src.time WHERE id >= 2 INTO clarium/public/dst MERGE").unwrap();
+    let out = run("SELECT id, v FROM clarium/public/dst ORDER BY id").unwrap();
+    let rows: Vec<(i64, f64)> = out.as_array().unwrap().iter().map(|r| (r["id"].as_i64().unwrap(), r["v"].as_f64().unwrap())).collect();
+    assert_eq!(rows, vec![(1, 10.0), (2, 21.0), (3, 31.0), (4, 41.0)]);
+
+    // APPEND may not duplicate a key
+    assert!(run("SELECT id, region, v FROM clarium/public/src.time WHERE id = 1 INTO clarium/public/dst APPEND").is_err());
+
+    // MERGE needs a key on the destination
+    run("SELECT id, v FROM clarium/public/src.time INTO clarium/public/nokey").unwrap();
+    assert!(run("SELECT id, v FROM clarium/public/src.time INTO clarium/public/nokey MERGE").is_err());
+}
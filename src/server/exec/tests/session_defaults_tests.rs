@@ -70,3 +70,58 @@ fn test_use_affects_table_ddl() {
     rt.block_on(exec::execute_query(&shared, "DROP TABLE t1")).unwrap();
     assert!(!dir.exists(), "expected table directory removed at {}", dir.display());
 }
+
+#[test]
+fn test_search_path_resolves_unqualified_names() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let run = |sql: &str| rt.block_on(exec::execute_query(&shared, sql));
+
+    run("USE DATABASE db3").unwrap();
+    run("CREATE SCHEMA db3.s1").unwrap();
+    run("CREATE SCHEMA s2").unwrap();
+    assert!(tmp.path().join("db3").join("s1").is_dir());
+    assert!(tmp.path().join("db3").join("s2").is_dir());
+    run("CREATE TABLE db3.s1.only_s1").unwrap();
+    run("INSERT INTO db3.s1.only_s1 (v) VALUES (1), (2)").unwrap();
+
+    // The first schema on the path takes new objects; later ones are searched for existing ones
+    run("SET search_path TO s2, s1").unwrap();
+    let show = run("SHOW search_path").unwrap();
+    assert_eq!(show[0]["search_path"].as_str(), Some("s2, s1"));
+    let out = run("SELECT COUNT(*) AS n FROM only_s1").unwrap();
+    assert_eq!(out[0]["n"].as_i64(), Some(2));
+    run("CREATE TABLE fresh").unwrap();
+    assert!(tmp.path().join("db3").join("s2").join("fresh").is_dir());
+
+    // Three-part names bypass the path
+    let out = run("SELECT COUNT(*) AS n FROM db3.s1.only_s1").unwrap();
+    assert_eq!(out[0]["n"].as_i64(), Some(2));
+
+    // USE SCHEMA replaces the path
+    run("USE SCHEMA s1").unwrap();
+    assert_eq!(run("SHOW search_path").unwrap()[0]["search_path"].as_str(), Some("s1"));
+    assert_eq!(run("SELECT COUNT(*) AS n FROM only_s1").unwrap()[0]["n"].as_i64(), Some(2));
+    run("SET SCHEMA 's2'").unwrap();
+    assert_eq!(crate::system::get_current_schema(), "s2");
+}
+
+#[test]
+fn test_kv_and_filestore_names_use_current_database() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let run = |sql: &str| rt.block_on(exec::execute_query(&shared, sql));
+
+    run("USE DATABASE db4").unwrap();
+    run("WRITE KEY k IN store.cfg = 7").unwrap();
+    let out = run("READ KEY k IN db4.store.cfg").unwrap();
+    assert_eq!(out["value"], serde_json::json!(7));
+
+    run("CREATE FILESTORE docs").unwrap();
+    let listed = crate::server::exec::filestore::show_filestores_df(&shared, "db4").unwrap();
+    assert_eq!(listed.height(), 1);
+    let via_qualified = run("SHOW FILESTORE CONFIG db4.docs");
+    assert!(via_qualified.is_ok(), "{:?}", via_qualified);
+}
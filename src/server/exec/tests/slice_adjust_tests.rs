@@ -0,0 +1,69 @@
+use super::super::run_slice;
+use crate::server::query::{self, Command, query_common::{SliceAdjust, SliceSource}};
+use crate::storage::SharedStore;
+use crate::server::data_context::DataContext;
+
+fn eval(sql: &str) -> polars::prelude::DataFrame {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let plan = match query::parse(sql).unwrap() { Command::Slice(p) => p, _ => unreachable!() };
+    run_slice(&shared, &plan, &DataContext::with_defaults("clarium", "public")).unwrap()
+}
+
+fn spans(sql: &str) -> Vec<(i64, i64)> {
+    let df = eval(sql);
+    let s = df.column("_start_date").unwrap().i64().unwrap();
+    let e = df.column("_end_date").unwrap().i64().unwrap();
+    s.into_no_null_iter().zip(e.into_no_null_iter()).collect()
+}
+
+#[test]
+fn parse_except_and_adjustments() {
+    let plan = match query::parse("SLICE USING runs WHERE state = 'on' MERGE WITHIN 5s PAD BEFORE 1s AFTER 2m EXCEPT (0, 10) COMPLEMENT").unwrap() {
+        Command::Slice(p) => p, _ => unreachable!()
+    };
+    assert!(matches!(&plan.base, SliceSource::Table { where_clause: Some(_), .. }));
+    assert_eq!(plan.adjust, vec![SliceAdjust::MergeWithin(5_000), SliceAdjust::Pad { before: 1_000, after: 120_000 }]);
+    assert_eq!(plan.clauses.len(), 1);
+    assert_eq!(plan.clauses[0].adjust, vec![SliceAdjust::Complement]);
+    assert!(query::parse("SLICE USING (0, 10) MERGE 5s").is_err());
+}
+
+#[test]
+fn except_removes_covered_time() {
+    assert_eq!(spans("SLICE USING ((0, 100), (200, 300)) EXCEPT (50, 250)"), vec![(0, 50), (250, 300)]);
+    assert_eq!(spans("SLICE USING (0, 100) EXCEPT (0, 100)"), vec![]);
+    // Grouped right-hand side: padding applies inside SLICE(...) only
+    assert_eq!(
+        spans("SLICE USING (0, 1000) EXCEPT SLICE(USING (100, 200) UNION (400, 500) PAD 50ms)"),
+        vec![(0, 50), (250, 350), (550, 1000)]
+    );
+}
+
+#[test]
+fn except_keeps_left_labels() {
+    let df = eval("SLICE USING ((0, 100, run:='A'), (200, 300, run:='B')) EXCEPT (90, 210)");
+    let s: Vec<i64> = df.column("_start_date").unwrap().i64().unwrap().into_no_null_iter().collect();
+    let run: Vec<&str> = df.column("run").unwrap().str().unwrap().into_no_null_iter().collect();
+    assert_eq!(s, vec![0, 210]);
+    assert_eq!(run, vec!["A", "B"]);
+}
+
+#[test]
+fn complement_merge_and_pad() {
+    assert_eq!(spans("SLICE USING ((0, 100), (200, 300), (250, 400), (500, 600)) COMPLEMENT"), vec![(100, 200), (400, 500)]);
+    // Adjustments apply to everything to their left
+    assert_eq!(spans("SLICE USING (0, 100) UNION (200, 300) COMPLEMENT"), vec![(100, 200)]);
+    assert_eq!(spans("SLICE USING ((0, 100), (104, 200), (300, 400)) MERGE WITHIN 5ms"), vec![(0, 200), (300, 400)]);
+    assert_eq!(spans("SLICE USING ((0, 100), (105, 200)) MERGE WITHIN 5ms"), vec![(0, 100), (105, 200)]);
+    assert_eq!(spans("SLICE USING ((1000, 2000), (5000, 6000)) PAD 1s"), vec![(0, 3000), (4000, 7000)]);
+    assert_eq!(spans("SLICE USING ((1000, 2000), (5000, 6000)) PAD AFTER 3s"), vec![(1000, 9000)]);
+}
+
+#[test]
+fn merge_within_respects_labels() {
+    let df = eval("SLICE USING ((0, 100, run:='A'), (102, 200, run:='B'), (203, 300, run:='B')) MERGE WITHIN 5ms");
+    let s: Vec<i64> = df.column("_start_date").unwrap().i64().unwrap().into_no_null_iter().collect();
+    let e: Vec<i64> = df.column("_end_date").unwrap().i64().unwrap().into_no_null_iter().collect();
+    assert_eq!((s, e), (vec![0, 102], vec![100, 300]));
+}
@@ -0,0 +1,65 @@
+use crate::storage::{Store, SharedStore, Record};
+use serde_json::json;
+
+const T0: i64 = 1_805_000_000_000;
+
+// Two minutes of readings: 1.0 for the first minute, 2.0 for the second
+fn seed_table(tmp: &tempfile::TempDir, name: &str) -> SharedStore {
+    let store = Store::new(tmp.path()).unwrap();
+    let recs: Vec<Record> = (0..120i64).map(|i| {
+        let mut m = serde_json::Map::new();
+        m.insert("v".into(), json!(if i < 60 { 1.0 } else { 2.0 }));
+        Record { _time: T0 + i * 1000, sensors: m }
+    }).collect();
+    store.write_records(name, &recs).unwrap();
+    SharedStore::new(tmp.path()).unwrap()
+}
+
+#[test]
+fn parse_named_slice_references() {
+    use crate::server::query::{self, Command, query_common::SliceSource};
+    let plan = match query::parse("SLICE shifts").unwrap() { Command::Slice(p) => p, _ => unreachable!() };
+    assert_eq!(plan.base, SliceSource::Named("shifts".into()));
+    let plan = match query::parse("SLICE USING (0, 10) UNION SLICE(slice_b)").unwrap() { Command::Slice(p) => p, _ => unreachable!() };
+    assert_eq!(plan.clauses[0].source, SliceSource::Plan(Box::new(query::parse_slice("slice_b").unwrap())));
+    assert!(matches!(query::parse("CREATE OR REPLACE SLICE s AS SLICE USING (0, 10)").unwrap(), Command::CreateSlice { or_replace: true, .. }));
+    assert!(query::parse("CREATE SLICE s AS SELECT 1").is_err());
+    assert!(matches!(query::parse("DROP SLICE IF EXISTS s").unwrap(), Command::DropSlice { if_exists: true, .. }));
+    assert!(matches!(query::parse("SHOW SLICES").unwrap(), Command::ShowSlices));
+}
+
+#[test]
+fn saved_slices_are_listed_and_reused_by_name() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_table(&tmp, "clarium/public/readings.time");
+    let run = |sql: &str| futures::executor::block_on(crate::server::exec::execute_query(&shared, sql));
+
+    run(&format!("CREATE SLICE shifts AS SLICE USING LABELS(shift) (({}, {}, shift:='A'), ({}, {}, shift:='B'))",
+        T0, T0 + 60_000, T0 + 60_000, T0 + 120_000)).unwrap();
+    assert!(run(&format!("CREATE SLICE shifts AS SLICE USING ({}, {})", T0, T0 + 1000)).is_err());
+    run(&format!("CREATE SLICE IF NOT EXISTS shifts AS SLICE USING ({}, {})", T0, T0 + 1000)).unwrap();
+    // Saved slices compose with inline sources and with each other
+    run(&format!("CREATE SLICE first_half AS SLICE USING ({}, {}) INTERSECT SLICE(shifts)", T0, T0 + 30_000)).unwrap();
+    assert!(run("CREATE SLICE broken AS SLICE SLICE(missing)").is_err());
+    assert!(run("CREATE OR REPLACE SLICE shifts AS SLICE SLICE(first_half)").is_err());
+
+    let listed = run("SHOW SLICES").unwrap();
+    let names: Vec<&str> = listed.as_array().unwrap().iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["clarium/public/first_half", "clarium/public/shifts"]);
+    assert_eq!(listed[1]["labels"], json!("shift"));
+
+    let rows = run("SELECT AVG(v) FROM clarium/public/readings.time BY SLICE(shifts)").unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 2, "{:?}", rows);
+    assert_eq!((rows[0]["shift"].clone(), rows[1]["shift"].clone()), (json!("A"), json!("B")));
+    assert_eq!((rows[0]["AVG(v)"].clone(), rows[1]["AVG(v)"].clone()), (json!(1.0), json!(2.0)));
+
+    let windows = run("SLICE first_half").unwrap();
+    assert_eq!(windows.as_array().unwrap().len(), 1, "{}", windows);
+    assert_eq!(windows[0]["_end_date"], json!(T0 + 30_000));
+
+    run("DROP SLICE shifts").unwrap();
+    assert!(run("SELECT AVG(v) FROM clarium/public/readings.time BY SLICE(shifts)").is_err());
+    assert!(run("DROP SLICE shifts").is_err());
+    run("DROP SLICE IF EXISTS shifts").unwrap();
+}
@@ -55,5 +55,265 @@ fn test_pg_catalog_pg_class_alias() {
     assert!(df.get_column_names().iter().any(|c| c.as_str() == "cnt" || c.as_str() == "COUNT(relname)"));
 }
 
+fn text_col(df: &polars::prelude::DataFrame, name: &str) -> Vec<String> {
+    df.column(name).unwrap().as_materialized_series().str().unwrap().into_iter().map(|v| v.unwrap_or("").to_string()).collect()
+}
+
+fn select(shared: &SharedStore, sql: &str) -> polars::prelude::DataFrame {
+    let q = match query::parse(sql).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    run_select(shared, &q).unwrap()
+}
+
+#[tokio::test]
+async fn test_information_schema_constraints_from_metadata() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    super::super::execute_query(&shared, "CREATE TABLE clarium/public/customers (region TEXT, code TEXT, name TEXT)").await.unwrap();
+    super::super::execute_query(&shared, "CREATE TABLE clarium/public/orders (id BIGINT, cust_region TEXT, cust_code TEXT)").await.unwrap();
+    store.set_table_metadata("clarium/public/customers", Some(vec!["region".to_string(), "code".to_string()]), None).unwrap();
+    store.set_table_metadata("clarium/public/orders", Some(vec!["id".to_string()]), None).unwrap();
+    let fk = serde_json::json!([{
+        "name": "orders_customer_fkey", "type": "foreign_key",
+        "columns": ["cust_code", "cust_region"], "ref_schema": "public", "ref_table": "customers",
+        "ref_columns": ["code", "region"], "on_delete": "cascade"
+    }]);
+    std::fs::write(tmp.path().join("clarium/public/orders/constraints.json"), fk.to_string()).unwrap();
+
+    let df = select(&shared, "SELECT constraint_name, constraint_type FROM information_schema.table_constraints WHERE table_name = 'orders'");
+    let mut rows: Vec<(String, String)> = text_col(&df, "constraint_name").into_iter().zip(text_col(&df, "constraint_type")).collect();
+    rows.sort();
+    assert_eq!(rows, vec![("orders_customer_fkey".to_string(), "FOREIGN KEY".to_string()), ("orders_pkey".to_string(), "PRIMARY KEY".to_string())]);
+
+    // Composite key columns keep their declared order
+    let df = select(&shared, "SELECT column_name, ordinal_position FROM information_schema.key_column_usage WHERE constraint_name = 'customers_pkey'");
+    assert_eq!(text_col(&df, "column_name"), vec!["region", "code"]);
+
+    // FK columns point at their position within the referenced primary key
+    let df = select(&shared, "SELECT column_name, position_in_unique_constraint FROM information_schema.key_column_usage WHERE constraint_name = 'orders_customer_fkey'");
+    assert_eq!(text_col(&df, "column_name"), vec!["cust_code", "cust_region"]);
+    let pos: Vec<Option<i32>> = df.column("position_in_unique_constraint").unwrap().as_materialized_series().i32().unwrap().into_iter().collect();
+    assert_eq!(pos, vec![Some(2), Some(1)]);
+
+    let df = select(&shared, "SELECT unique_constraint_name, update_rule, delete_rule, match_option FROM information_schema.referential_constraints");
+    assert_eq!(text_col(&df, "unique_constraint_name"), vec!["customers_pkey"]);
+    assert_eq!(text_col(&df, "update_rule"), vec!["NO ACTION"]);
+    assert_eq!(text_col(&df, "delete_rule"), vec!["CASCADE"]);
+    assert_eq!(text_col(&df, "match_option"), vec!["NONE"]);
+}
+
+#[test]
+fn test_information_schema_routines_lists_udfs() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let df = select(&shared, "SELECT routine_name, data_type, external_language FROM information_schema.routines");
+    let names = text_col(&df, "routine_name");
+    let types = text_col(&df, "data_type");
+    let type_of = |n: &str| names.iter().position(|x| x == n).map(|i| types[i].clone());
+    assert_eq!(type_of("is_pos").as_deref(), Some("boolean"));
+    assert_eq!(type_of("split2").as_deref(), Some("record"));
+    assert!(text_col(&df, "external_language").iter().all(|l| l == "LUA"));
+}
+
+#[tokio::test]
+async fn test_comment_on_feeds_pg_description() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let exec = |sql: &'static str| super::super::execute_query(&shared, sql);
+    exec("CREATE TABLE clarium/public/orders (id BIGINT, total BIGINT)").await.unwrap();
+    exec("INSERT INTO clarium/public/orders (id, total) VALUES (1, 10)").await.unwrap();
+    exec("CREATE VIEW big_orders AS SELECT id FROM orders").await.unwrap();
+    exec("COMMENT ON TABLE orders IS 'Customer orders'").await.unwrap();
+    exec("COMMENT ON COLUMN orders.total IS 'Gross, in cents'").await.unwrap();
+    exec("COMMENT ON VIEW big_orders IS 'Orders over the limit'").await.unwrap();
+    exec("COMMENT ON FUNCTION is_pos(bigint) IS 'True when positive'").await.unwrap();
+    assert!(exec("COMMENT ON COLUMN orders.missing IS 'x'").await.is_err());
+    assert!(exec("COMMENT ON TABLE no_such_table IS 'x'").await.is_err());
+
+    let df = select(&shared, "SELECT classoid, objsubid, description FROM pg_catalog.pg_description ORDER BY description");
+    assert_eq!(text_col(&df, "description"), vec!["Customer orders", "Gross, in cents", "Orders over the limit", "True when positive"]);
+    let subids: Vec<Option<i32>> = df.column("objsubid").unwrap().as_materialized_series().i32().unwrap().into_iter().collect();
+    assert_eq!(subids, vec![Some(0), Some(2), Some(0), Some(0)]);
+    let classoids: Vec<Option<i32>> = df.column("classoid").unwrap().as_materialized_series().i32().unwrap().into_iter().collect();
+    assert_eq!(classoids, vec![Some(1259), Some(1259), Some(1259), Some(1255)]);
+
+    let df = select(&shared, "SELECT column_name, column_comment FROM information_schema.columns WHERE table_name = 'orders' ORDER BY column_name");
+    assert_eq!(text_col(&df, "column_comment"), vec!["", "Gross, in cents"]);
+
+    // IS NULL removes the comment
+    exec("COMMENT ON COLUMN orders.total IS NULL").await.unwrap();
+    exec("COMMENT ON TABLE orders IS ''").await.unwrap();
+    let df = select(&shared, "SELECT description FROM pg_catalog.pg_description ORDER BY description");
+    assert_eq!(text_col(&df, "description"), vec!["Orders over the limit", "True when positive"]);
+}
+
+#[tokio::test]
+async fn test_clarium_catalog_lists_native_objects() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let mut recs: Vec<Record> = Vec::new();
+    for i in 0..3i64 {
+        let mut m = serde_json::Map::new();
+        m.insert("id".into(), serde_json::json!(i));
+        m.insert("body_embed".into(), serde_json::json!("0.1,0.0,0.0"));
+        recs.push(Record { _time: 1_000 + i, sensors: m });
+    }
+    store.write_records("clarium/public/docs", &recs).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    shared.kv_store("clarium", "cache").set("k1", crate::storage::KvValue::Int(1), None, None);
+    super::super::execute_query(&shared, "CREATE VECTOR INDEX idx_docs ON clarium/public/docs(body_embed) USING hnsw WITH (metric='l2', dim=3)").await.unwrap();
+
+    let df = select(&shared, "SELECT store_name, key_count FROM clarium_catalog.kv_stores WHERE store_name = 'cache'");
+    let counts: Vec<Option<i64>> = df.column("key_count").unwrap().as_materialized_series().i64().unwrap().into_iter().collect();
+    assert_eq!(counts, vec![Some(1)]);
 
+    let df = select(&shared, "SELECT name, table_name, column_name, metric, dim FROM clarium_catalog.vector_indexes");
+    assert_eq!(text_col(&df, "name"), vec!["idx_docs"]);
+    assert_eq!(text_col(&df, "column_name"), vec!["body_embed"]);
+    assert_eq!(text_col(&df, "metric"), vec!["l2"]);
 
+    let df = select(&shared, "SELECT table_name, chunk, bytes FROM clarium_catalog.chunks WHERE table_name = 'docs'");
+    assert!(df.height() >= 1);
+    let bytes: Vec<Option<i64>> = df.column("bytes").unwrap().as_materialized_series().i64().unwrap().into_iter().collect();
+    assert!(bytes.iter().all(|b| b.unwrap_or(0) > 0));
+
+    let df = select(&shared, "SELECT name, kind FROM clarium_catalog.scripts WHERE name = 'is_pos'");
+    assert_eq!(text_col(&df, "kind"), vec!["scalar"]);
+
+    let df = select(&shared, "SELECT table_name, table_type FROM information_schema.tables WHERE table_schema = 'clarium_catalog' ORDER BY table_name");
+    assert_eq!(text_col(&df, "table_name"), vec!["chunks", "filestore_file_metadata", "filestore_files", "filestores", "graphs", "kv_stores", "script_stats", "scripts", "vector_indexes"]);
+    assert!(text_col(&df, "table_type").iter().all(|t| t == "VIEW"));
+}
+
+#[tokio::test]
+async fn test_filestore_file_metadata_views() {
+    use crate::server::exec::filestore::*;
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let cfg = FilestoreConfig { security_check_enabled: false, ..Default::default() };
+    create_filestore(&shared, "clarium", "docs", cfg.clone(), None).unwrap();
+    let eff = EffectiveConfig::from_layers(&GlobalFilestoreConfig::default(), &cfg, None);
+    let user = AclUser { id: "loader".into(), roles: vec![], ip: None };
+    for p in ["a.txt", "b.txt", "c.txt"] {
+        ingest_from_bytes(&shared, "clarium", "docs", p, b"x", Some("text/plain"), None, &user, &eff, &AclContext::default()).await.unwrap();
+    }
+    let exec = |sql: &'static str| super::super::execute_query(&shared, sql);
+    exec("SET FILE METADATA IN FILESTORE docs PATH 'a.txt' ('stage' = 'draft', 'owner' = 'o''brien')").await.unwrap();
+    exec("SET FILE METADATA IN FILESTORE docs PATH 'b.txt' ('stage' = 'final')").await.unwrap();
+    exec("SET FILE METADATA IN FILESTORE docs PATH 'c.txt' ('stage' = 'draft')").await.unwrap();
+    exec("SET FILE METADATA IN FILESTORE docs PATH 'c.txt' ('stage' = NULL)").await.unwrap();
+    assert!(exec("SET FILE METADATA IN FILESTORE docs PATH 'missing.txt' ('k' = 'v')").await.is_err());
+
+    let df = select(&shared, "SELECT path FROM clarium_catalog.filestore_file_metadata WHERE filestore = 'docs' AND key = 'stage' AND value = 'draft'");
+    assert_eq!(text_col(&df, "path"), vec!["a.txt"]);
+    let df = select(&shared, "SELECT value FROM clarium_catalog.filestore_file_metadata WHERE path = 'a.txt' AND key = 'owner'");
+    assert_eq!(text_col(&df, "value"), vec!["o'brien"]);
+
+    let df = select(&shared, "SELECT path, metadata FROM clarium_catalog.filestore_files WHERE filestore = 'docs' ORDER BY path");
+    assert_eq!(text_col(&df, "path"), vec!["a.txt", "b.txt", "c.txt"]);
+    let md: Vec<Option<&str>> = df.column("metadata").unwrap().as_materialized_series().str().unwrap().into_iter().collect();
+    assert_eq!(md, vec![Some(r#"{"owner":"o'brien","stage":"draft"}"#), Some(r#"{"stage":"final"}"#), None]);
+
+    let files = exec("SHOW FILES IN FILESTORE docs").await.unwrap();
+    assert_eq!(files[1]["metadata"], serde_json::json!(r#"{"stage":"final"}"#));
+}
+
+#[tokio::test]
+async fn test_grant_revoke_feed_table_privileges() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    let exec = |sql: &'static str| super::super::execute_query(&shared, sql);
+    exec("CREATE TABLE clarium/public/orders (id BIGINT)").await.unwrap();
+    exec("CREATE TABLE clarium/public/items (id BIGINT)").await.unwrap();
+    exec("GRANT SELECT, UPDATE ON TABLE orders TO analyst").await.unwrap();
+    exec("GRANT INSERT ON SCHEMA clarium/public TO loader").await.unwrap();
+    assert!(exec("GRANT SELECT ON TABLE missing TO analyst").await.is_err());
+
+    use crate::security::{has_privilege, Privilege};
+    assert!(has_privilege(&root, "analyst", Privilege::Select, "clarium/public/orders"));
+    assert!(!has_privilege(&root, "analyst", Privilege::Select, "clarium/public/items"));
+    assert!(has_privilege(&root, "loader", Privilege::Insert, "clarium/public/items"));
+
+    let df = select(&shared, "SELECT grantee, table_name, privilege_type FROM information_schema.table_privileges ORDER BY grantee, table_name, privilege_type");
+    assert_eq!(text_col(&df, "grantee"), vec!["analyst", "analyst", "loader", "loader"]);
+    assert_eq!(text_col(&df, "table_name"), vec!["orders", "orders", "items", "orders"]);
+    assert_eq!(text_col(&df, "privilege_type"), vec!["SELECT", "UPDATE", "INSERT", "INSERT"]);
+
+    exec("REVOKE ALL ON TABLE orders FROM analyst").await.unwrap();
+    assert!(!has_privilege(&root, "analyst", Privilege::Select, "clarium/public/orders"));
+    let df = select(&shared, "SELECT grantee FROM information_schema.table_privileges");
+    assert_eq!(text_col(&df, "grantee"), vec!["loader", "loader"]);
+}
+
+#[tokio::test]
+async fn test_select_needs_privilege_on_every_relation() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    let exec = |sql: &'static str| super::super::execute_query(&shared, sql);
+    exec("CREATE TABLE clarium/public/orders (id BIGINT)").await.unwrap();
+    exec("CREATE TABLE clarium/public/secret (id BIGINT)").await.unwrap();
+    exec("GRANT SELECT ON TABLE orders TO analyst").await.unwrap();
+    let defaults = crate::system::current_query_defaults();
+    let allowed = |sql: &str| {
+        let cmd = query::parse(sql).unwrap();
+        let (shared, root, defaults) = (shared.clone(), root.clone(), defaults.clone());
+        async move { crate::server::exec::exec_authorize::statement_allowed(&shared, &root, "analyst", None, &[], &cmd, &defaults).await }
+    };
+
+    assert!(allowed("SELECT id FROM orders").await);
+    assert!(allowed("WITH o AS (SELECT id FROM orders) SELECT id FROM o").await);
+    for sql in [
+        "SELECT o.id FROM orders o JOIN secret s ON o.id = s.id",
+        "SELECT t.id FROM (SELECT id FROM secret) t",
+        "SELECT id FROM orders WHERE EXISTS (SELECT id FROM secret)",
+        "WITH s AS (SELECT id FROM secret) SELECT id FROM orders WHERE EXISTS (SELECT id FROM s)",
+        "SELECT id FROM orders UNION SELECT id FROM secret",
+    ] {
+        assert!(!allowed(sql).await, "{} must need SELECT on secret", sql);
+    }
+    exec("GRANT SELECT ON TABLE secret TO analyst").await.unwrap();
+    assert!(allowed("SELECT o.id FROM orders o JOIN secret s ON o.id = s.id").await);
+}
+
+#[tokio::test]
+async fn test_roles_membership_and_catalogs() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    crate::security::add_user(&root, crate::security::Scope::Global, "alice", "pw", crate::security::Perms::default()).unwrap();
+    let exec = |sql: &'static str| super::super::execute_query(&shared, sql);
+    exec("CREATE TABLE clarium/public/orders (id BIGINT)").await.unwrap();
+    exec("CREATE ROLE readers").await.unwrap();
+    exec("CREATE ROLE analyst WITH NOSUPERUSER INHERIT").await.unwrap();
+    assert!(exec("CREATE ROLE analyst").await.is_err());
+    exec("GRANT SELECT ON TABLE orders TO readers").await.unwrap();
+    exec("GRANT readers TO analyst").await.unwrap();
+    exec("GRANT analyst TO alice WITH ADMIN OPTION").await.unwrap();
+    // membership cycles and unknown members are rejected
+    assert!(exec("GRANT analyst TO readers").await.is_err());
+    assert!(exec("GRANT analyst TO nobody").await.is_err());
+
+    use crate::security::{has_privilege, Privilege};
+    assert!(has_privilege(&root, "alice", Privilege::Select, "clarium/public/orders"));
+    assert_eq!(crate::security::effective_roles(&root, "alice"), vec!["alice", "analyst", "readers"]);
+    exec("SET ROLE analyst").await.unwrap();
+    assert!(exec("SET ROLE nobody").await.is_err());
+    exec("RESET ROLE").await.unwrap();
+
+    let df = select(&shared, "SELECT rolname FROM pg_catalog.pg_roles ORDER BY rolname");
+    assert_eq!(text_col(&df, "rolname"), vec!["alice", "analyst", "postgres", "public", "readers"]);
+    let df = select(&shared, "SELECT roleid, member, admin_option FROM pg_catalog.pg_auth_members");
+    assert_eq!(df.height(), 2);
+
+    exec("DROP ROLE readers").await.unwrap();
+    assert!(exec("DROP ROLE readers").await.is_err());
+    exec("DROP ROLE IF EXISTS readers").await.unwrap();
+    assert!(!has_privilege(&root, "alice", Privilege::Select, "clarium/public/orders"));
+    let df = select(&shared, "SELECT member FROM pg_catalog.pg_auth_members");
+    assert_eq!(df.height(), 1);
+}
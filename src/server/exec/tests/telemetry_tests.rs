@@ -0,0 +1,84 @@
+use super::super::execute_query;
+use crate::storage::{SharedStore, Store};
+use parking_lot::Mutex;
+use polars::prelude::*;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+/// Collects `(span name, "field=value" pairs)` for every span, including later `record` calls.
+#[derive(Clone, Default)]
+struct SpanCollector(Arc<Mutex<Vec<(Id, String, Vec<String>)>>>);
+
+struct FieldVisitor<'a>(&'a mut Vec<String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.push(format!("{}={:?}", field.name(), value));
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push(format!("{}={}", field.name(), value));
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for SpanCollector {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = Vec::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        self.0.lock().push((id.clone(), attrs.metadata().name().to_string(), fields));
+    }
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut spans = self.0.lock();
+        if let Some((_, _, fields)) = spans.iter_mut().rev().find(|(sid, _, _)| sid == id) {
+            values.record(&mut FieldVisitor(fields));
+        }
+    }
+}
+
+impl SpanCollector {
+    fn span(&self, name: &str) -> Option<Vec<String>> {
+        self.0.lock().iter().find(|(_, n, _)| n == name).map(|(_, _, f)| f.clone())
+    }
+}
+
+#[tokio::test]
+async fn test_query_produces_span_tree_with_scan_attributes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let orders = DataFrame::new(vec![
+        Series::new("id".into(), &[1i64, 2, 3]).into(),
+        Series::new("cust".into(), &[10i64, 10, 20]).into(),
+    ]).unwrap();
+    store.rewrite_table_df("otel_orders", orders).unwrap();
+    let custs = DataFrame::new(vec![
+        Series::new("cid".into(), &[10i64, 20]).into(),
+        Series::new("name".into(), &["a", "b"]).into(),
+    ]).unwrap();
+    store.rewrite_table_df("otel_custs", custs).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    let collector = SpanCollector::default();
+    let subscriber = tracing_subscriber::registry().with(collector.clone());
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    execute_query(&shared, "SELECT c.name, COUNT(*) AS n FROM otel_orders o JOIN otel_custs c ON o.cust = c.cid GROUP BY c.name").await.unwrap();
+
+    for name in ["query", "parse", "plan", "scan", "join", "aggregate"] {
+        assert!(collector.span(name).is_some(), "missing span '{}'", name);
+    }
+    let scan = collector.span("scan").unwrap();
+    assert!(scan.contains(&"table=o".to_string()), "{:?}", scan);
+    assert!(scan.contains(&"rows=3".to_string()), "{:?}", scan);
+    assert!(scan.iter().any(|f| f.starts_with("chunks=")), "{:?}", scan);
+    let join = collector.span("join").unwrap();
+    assert!(join.contains(&"rows=3".to_string()), "{:?}", join);
+    let agg = collector.span("aggregate").unwrap();
+    assert!(agg.contains(&"rows=2".to_string()), "{:?}", agg);
+
+    // Failed statements mark the root span as an error
+    assert!(execute_query(&shared, "SELECT * FROM otel_missing").await.is_err());
+    let spans = collector.0.lock();
+    let (_, _, root) = spans.iter().rev().find(|(_, n, _)| n == "query").unwrap();
+    assert!(root.contains(&"otel.status_code=ERROR".to_string()), "{:?}", root);
+}
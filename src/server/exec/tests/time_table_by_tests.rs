@@ -79,3 +79,35 @@ fn test_time_by_5m_user_example_executes() {
 
 
 
+
+#[test]
+fn test_time_by_counter_aggregates_handle_resets() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let db = "demo/public/tt_counter.time";
+    let base: i64 = 1_700_000_040_000; // minute-aligned
+    // Counter restarts between the third and fourth sample
+    let recs: Vec<Record> = [0.0, 10.0, 20.0, 5.0, 15.0, 25.0].iter().enumerate().map(|(i, v)| {
+        let mut m = serde_json::Map::new();
+        m.insert("requests".into(), json!(*v));
+        Record { _time: base + i as i64 * 10_000, sensors: m }
+    }).collect();
+    store.write_records(db, &recs).unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+
+    let qtext = format!("SELECT INCREASE(requests) AS inc, RATE(requests) AS r, IRATE(requests) AS ir, DELTA(requests) AS d FROM {} BY 1m", db);
+    let q = match query::parse(&qtext).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    let df = run_select(&shared, &q).unwrap();
+    assert_eq!(df.height(), 1);
+    let get = |c: &str| df.column(c).unwrap().f64().unwrap().get(0).unwrap();
+    assert!((get("inc") - 45.0).abs() < 1e-9);
+    assert!((get("r") - 0.9).abs() < 1e-9);
+    assert!((get("ir") - 1.0).abs() < 1e-9);
+    // DELTA ignores the reset
+    assert!((get("d") - 25.0).abs() < 1e-9);
+
+    // Without BY the whole table is one group
+    let q = match query::parse(&format!("SELECT INCREASE(requests) AS inc FROM {}", db)).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+    let df = run_select(&shared, &q).unwrap();
+    assert!((df.column("inc").unwrap().f64().unwrap().get(0).unwrap() - 45.0).abs() < 1e-9);
+}
@@ -0,0 +1,70 @@
+use super::super::execute_query;
+use crate::storage::SharedStore;
+use serde_json::{json, Value};
+
+async fn trigger_row(shared: &SharedStore, name: &str) -> Value {
+    let res = execute_query(shared, &format!("SELECT * FROM pg_catalog.clarium_triggers WHERE trigger_name = '{}'", name)).await.unwrap();
+    res.as_array().unwrap()[0].clone()
+}
+
+#[tokio::test]
+async fn test_lua_triggers_batches_and_failure_policies() {
+    super::udf_common::init_all_test_udfs();
+    crate::scripts::get_script_registry().unwrap().load_script_text("trg_check",
+        "function trg_check(rows, ctx) for _, r in ipairs(rows) do if r.n < 0 then error('negative n in ' .. ctx.op) end end return #rows end").unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let exec = |sql: &'static str| execute_query(&shared, sql);
+    exec("CREATE TABLE clarium/public/trg_items (n BIGINT)").await.unwrap();
+
+    assert!(exec("CREATE TRIGGER chk AFTER INSERT ON clarium/public/trg_items EXECUTE FUNCTION no_such_fn").await.is_err());
+    assert!(exec("CREATE TRIGGER chk AFTER INSERT ON clarium/public/missing EXECUTE FUNCTION trg_check").await.is_err());
+    exec("CREATE TRIGGER chk AFTER INSERT OR UPDATE ON clarium/public/trg_items EXECUTE FUNCTION trg_check WITH (batch_size = 2)").await.unwrap();
+    assert!(exec("CREATE TRIGGER chk AFTER DELETE ON clarium/public/trg_items EXECUTE FUNCTION trg_check").await.is_err());
+
+    // Three rows in batches of two
+    exec("INSERT INTO clarium/public/trg_items (n) VALUES (1), (2), (3)").await.unwrap();
+    let row = trigger_row(&shared, "chk").await;
+    assert_eq!(row["events"], json!("insert,update"));
+    assert_eq!(row["mode"], json!("sync"));
+    assert_eq!(row["batches"], json!(2));
+    assert_eq!(row["rows"], json!(3));
+
+    // A failing sync trigger fails the statement; the rows are already written
+    let err = exec("INSERT INTO clarium/public/trg_items (n) VALUES (-1)").await.unwrap_err();
+    assert!(err.to_string().contains("trigger \"chk\""), "{}", err);
+    let row = trigger_row(&shared, "chk").await;
+    assert_eq!(row["failures"], json!(1));
+    assert!(row["last_error"].as_str().unwrap().contains("negative n in insert"));
+    // Deletes are not among the trigger's events
+    exec("DELETE FROM clarium/public/trg_items WHERE n = -1").await.unwrap();
+
+    let rows = exec("SELECT tgname, tgtype, tgenabled FROM pg_catalog.pg_trigger").await.unwrap();
+    assert_eq!(rows, json!([{"tgname": "chk", "tgtype": 20, "tgenabled": "O"}]));
+
+    // on_error = 'ignore' only counts the failure
+    exec("DROP TRIGGER chk ON clarium/public/trg_items").await.unwrap();
+    exec("CREATE TRIGGER chk_soft AFTER INSERT ON clarium/public/trg_items EXECUTE FUNCTION trg_check WITH (on_error = 'ignore')").await.unwrap();
+    exec("INSERT INTO clarium/public/trg_items (n) VALUES (-2)").await.unwrap();
+    assert_eq!(trigger_row(&shared, "chk_soft").await["failures"], json!(1));
+
+    // Async triggers never fail the statement and run on the background worker
+    exec("CREATE TRIGGER chk_async AFTER INSERT ON clarium/public/trg_items EXECUTE FUNCTION trg_check WITH (mode = 'async')").await.unwrap();
+    exec("INSERT INTO clarium/public/trg_items (n) VALUES (5), (6)").await.unwrap();
+    let mut batches = json!(0);
+    for _ in 0..50 {
+        batches = trigger_row(&shared, "chk_async").await["batches"].clone();
+        if batches == json!(1) { break; }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(batches, json!(1));
+
+    let total = exec("SELECT COUNT(*) AS c FROM clarium/public/trg_items").await.unwrap();
+    assert_eq!(total.as_array().unwrap()[0]["c"], json!(6));
+
+    exec("DROP TRIGGER chk_soft ON clarium/public/trg_items").await.unwrap();
+    assert!(exec("DROP TRIGGER chk_soft ON clarium/public/trg_items").await.is_err());
+    exec("DROP TRIGGER IF EXISTS chk_soft ON clarium/public/trg_items").await.unwrap();
+    let left = exec("SELECT trigger_name FROM pg_catalog.clarium_triggers").await.unwrap();
+    assert_eq!(left, json!([{"trigger_name": "chk_async"}]));
+}
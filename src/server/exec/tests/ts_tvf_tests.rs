@@ -0,0 +1,104 @@
+use super::super::execute_query;
+use crate::storage::{Record, SharedStore};
+use serde_json::json;
+
+// Trend of 0.5 per sample plus a period-4 season
+fn seed_series(shared: &SharedStore, table: &str, spike_at: Option<usize>) {
+    let recs: Vec<Record> = (0..40usize).map(|i| {
+        let mut v = 10.0 + 0.5 * i as f64 + [0.0, 3.0, 0.0, -3.0][i % 4];
+        if spike_at == Some(i) { v += 40.0; }
+        let mut m = serde_json::Map::new();
+        m.insert("v".into(), json!(v));
+        Record { _time: 1_000 * i as i64, sensors: m }
+    }).collect();
+    shared.0.lock().write_records(table, &recs).unwrap();
+}
+
+#[tokio::test]
+async fn test_ts_forecast_follows_trend_and_season() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed_series(&shared, "clarium/public/ts_load.time", None);
+
+    let res = execute_query(&shared, "SELECT _time, step, forecast, lower, upper FROM ts_forecast('clarium/public/ts_load.time', v, 4) ORDER BY step").await.unwrap();
+    let rows = res.as_array().unwrap();
+    assert_eq!(rows.len(), 4);
+    for (h, row) in rows.iter().enumerate() {
+        let i = 40 + h;
+        let truth = 10.0 + 0.5 * i as f64 + [0.0, 3.0, 0.0, -3.0][i % 4];
+        let f = row["forecast"].as_f64().unwrap();
+        assert_eq!(row["_time"], json!(1_000 * i as i64));
+        assert!((f - truth).abs() < 0.25, "step {} forecast {} expected {}", h + 1, f, truth);
+        assert!(row["lower"].as_f64().unwrap() <= f && f <= row["upper"].as_f64().unwrap());
+    }
+
+    assert!(execute_query(&shared, "SELECT * FROM ts_forecast('clarium/public/ts_load.time', v, 0)").await.is_err());
+    assert!(execute_query(&shared, "SELECT * FROM ts_forecast('clarium/public/ts_load.time', missing, 3)").await.is_err());
+}
+
+#[tokio::test]
+async fn test_ts_anomalies_flags_spike() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed_series(&shared, "clarium/public/ts_spiky.time", Some(17));
+
+    let res = execute_query(&shared, "SELECT _time, value, score FROM ts_anomalies('clarium/public/ts_spiky.time', v)").await.unwrap();
+    let rows = res.as_array().unwrap();
+    assert_eq!(rows.len(), 1, "{:?}", rows);
+    assert_eq!(rows[0]["_time"], json!(17_000));
+    assert!(rows[0]["score"].as_f64().unwrap() > 3.5);
+
+    // A very high threshold flags nothing
+    let res = execute_query(&shared, "SELECT * FROM ts_anomalies('clarium/public/ts_spiky.time', v, 1000)").await.unwrap();
+    assert_eq!(res.as_array().map(|a| a.len()), Some(0));
+}
+
+#[tokio::test]
+async fn test_downsample_lttb_and_largest_gap() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    // 100 samples one second apart with a spike, then a 50s hole before the last 20
+    let recs: Vec<Record> = (0..120i64).map(|i| {
+        let t = if i < 100 { i * 1_000 } else { 150_000 + (i - 100) * 1_000 };
+        let mut m = serde_json::Map::new();
+        m.insert("v".into(), json!(if i == 42 { 100.0 } else { (i % 7) as f64 }));
+        Record { _time: t, sensors: m }
+    }).collect();
+    let table = "clarium/public/ts_dense.time";
+    shared.0.lock().write_records(table, &recs).unwrap();
+
+    let res = execute_query(&shared, &format!("SELECT _time, v FROM downsample('{}', v, 12) ORDER BY _time", table)).await.unwrap();
+    let rows = res.as_array().unwrap();
+    assert_eq!(rows.len(), 12);
+    assert_eq!(rows[0]["_time"], json!(0));
+    assert_eq!(rows[11]["_time"], json!(169_000));
+    assert!(rows.iter().any(|r| r["v"] == json!(100.0)), "LTTB keeps the spike: {:?}", rows);
+
+    let res = execute_query(&shared, &format!("SELECT _time FROM downsample('{}', v, 6, 'gap') ORDER BY _time", table)).await.unwrap();
+    let times: Vec<i64> = res.as_array().unwrap().iter().map(|r| r["_time"].as_i64().unwrap()).collect();
+    assert!(times.len() <= 6 && times.contains(&99_000) && times.contains(&150_000), "{:?}", times);
+
+    assert!(execute_query(&shared, &format!("SELECT * FROM downsample('{}', v, 1)", table)).await.is_err());
+    assert!(execute_query(&shared, &format!("SELECT * FROM downsample('{}', v, 10, 'median')", table)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_by_auto_targets_row_count() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    seed_series(&shared, "clarium/public/ts_auto.time", None);
+
+    // 40 samples over 39s: 4s target rounds up to 5s buckets
+    let res = execute_query(&shared, "SELECT COUNT(v) AS n FROM clarium/public/ts_auto.time BY AUTO(10)").await.unwrap();
+    let rows = res.as_array().unwrap();
+    assert_eq!(rows.len(), 8, "{:?}", rows);
+    assert_eq!(rows.iter().map(|r| r["n"].as_i64().unwrap()).sum::<i64>(), 40);
+
+    let q = match crate::server::query::parse("SELECT COUNT(v) FROM t.time BY AUTO WHERE v > 0").unwrap() {
+        crate::server::query::Command::Select(q) => q,
+        _ => panic!("expected select"),
+    };
+    assert_eq!(q.by_auto_points, Some(1000));
+    assert!(q.where_clause.is_some());
+    assert!(crate::server::query::parse("SELECT COUNT(v) FROM t.time BY AUTO(0)").is_err());
+}
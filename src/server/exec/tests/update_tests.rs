@@ -0,0 +1,73 @@
+use super::super::execute_query;
+use crate::server::query::{self, Command, query_common::{ArithExpr, UpdateFrom}};
+use crate::storage::{Store, SharedStore, Record};
+use serde_json::json;
+
+const T0: i64 = 1_806_000_000_000;
+
+#[test]
+fn parse_update_expressions_and_from() {
+    let cmd = query::parse("UPDATE clarium/public/orders SET total = qty * price, note = 'a, b' FROM clarium/public/prices AS p WHERE orders.sku = p.sku").unwrap();
+    let Command::Update { table, assignments, from, where_clause } = cmd else { unreachable!() };
+    assert_eq!(table, "clarium/public/orders");
+    assert_eq!(assignments.len(), 2);
+    assert!(matches!(assignments[0].1, ArithExpr::BinOp { .. }));
+    assert_eq!(from, Some(UpdateFrom { table: "clarium/public/prices".into(), alias: Some("p".into()) }));
+    assert!(where_clause.is_some());
+    assert!(query::parse("UPDATE t SET a = 1 FROM s x y WHERE t.id = x.id").is_err());
+}
+
+#[tokio::test]
+async fn update_rewrites_only_chunks_with_matching_rows() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    let table = "clarium/public/readings.time";
+    // Two batches, so two chunks
+    for batch in 0..2i64 {
+        let recs: Vec<Record> = (0..5i64).map(|i| {
+            let mut m = serde_json::Map::new();
+            m.insert("v".into(), json!(1.0 + i as f64));
+            m.insert("w".into(), json!(10.0));
+            Record { _time: T0 + batch * 60_000 + i * 1000, sensors: m }
+        }).collect();
+        store.write_records(table, &recs).unwrap();
+    }
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let before = shared.0.lock().chunk_paths(table).unwrap();
+    assert_eq!(before.len(), 2);
+
+    execute_query(&shared, &format!("UPDATE {} SET v = v * 2 + w, w = v WHERE _time >= {}", table, T0 + 60_000)).await.unwrap();
+
+    let after = shared.0.lock().chunk_paths(table).unwrap();
+    assert_eq!(after.len(), 2);
+    assert!(after.contains(&before[0]), "untouched chunk was rewritten: {:?}", after);
+    let out = execute_query(&shared, &format!("SELECT v, w FROM {} WHERE _time >= {} ORDER BY _time LIMIT 2", table, T0 + 60_000)).await.unwrap();
+    let rows: Vec<(f64, f64)> = out.as_array().unwrap().iter().map(|r| (r["v"].as_f64().unwrap(), r["w"].as_f64().unwrap())).collect();
+    // SET expressions read the old row: w takes the old v
+    assert_eq!(rows, vec![(12.0, 1.0), (14.0, 2.0)]);
+    let first = execute_query(&shared, &format!("SELECT v FROM {} WHERE _time < {} ORDER BY _time LIMIT 1", table, T0 + 60_000)).await.unwrap();
+    assert_eq!(first[0]["v"].as_f64(), Some(1.0));
+}
+
+#[tokio::test]
+async fn update_from_joins_on_where_equalities() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    execute_query(&shared, "CREATE TABLE clarium/public/orders").await.unwrap();
+    execute_query(&shared, "INSERT INTO clarium/public/orders (id, sku, qty, price) VALUES (1, 'a', 2, 0), (2, 'b', 3, 0), (3, 'c', 4, 0)").await.unwrap();
+    execute_query(&shared, "CREATE TABLE clarium/public/prices").await.unwrap();
+    execute_query(&shared, "INSERT INTO clarium/public/prices (sku, price, active) VALUES ('a', 1.5, 1), ('b', 2.5, 0), ('c', 3.5, 1)").await.unwrap();
+
+    execute_query(&shared, "UPDATE orders SET price = p.price * qty FROM prices p WHERE orders.sku = p.sku AND p.active = 1").await.unwrap();
+    let out = execute_query(&shared, "SELECT id, price FROM clarium/public/orders ORDER BY id").await.unwrap();
+    let prices: Vec<f64> = out.as_array().unwrap().iter().map(|r| r["price"].as_f64().unwrap()).collect();
+    assert_eq!(prices, vec![3.0, 0.0, 14.0]);
+
+    // Without a join equality, or with a target row matching two source rows, nothing is written
+    assert!(execute_query(&shared, "UPDATE orders SET price = 1 FROM prices p WHERE p.active = 1").await.is_err());
+    execute_query(&shared, "INSERT INTO clarium/public/prices (sku, price, active) VALUES ('a', 9.0, 1)").await.unwrap();
+    let err = execute_query(&shared, "UPDATE orders SET price = p.price FROM prices p WHERE orders.sku = p.sku").await.unwrap_err();
+    assert!(err.to_string().contains("more than one row"), "{}", err);
+    let out = execute_query(&shared, "SELECT price FROM clarium/public/orders WHERE id = 1").await.unwrap();
+    assert_eq!(out[0]["price"].as_f64(), Some(3.0));
+}
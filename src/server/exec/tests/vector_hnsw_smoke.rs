@@ -46,4 +46,21 @@ mod hnsw_smoke {
         // Ensure cosine scores are within [-1, 1]
         for (_id, s) in res_cos { assert!(s <= 1.0 + 1e-5 && s >= -1.0 - 1e-5); }
     }
+
+    #[test]
+    fn hnsw_graph_matches_exact_and_round_trips() {
+        use crate::server::exec::vector_hnsw::{HnswGraph, Metric};
+        // 2000 points on a 40x50 grid; queries sit next to one grid point
+        let dim = 2usize;
+        let data: Vec<f32> = (0..2000).flat_map(|i| [(i % 40) as f32, (i / 40) as f32]).collect();
+        let graph = HnswGraph::build(&data, dim, Metric::L2, 8, 64);
+        let loaded = HnswGraph::from_bytes(&graph.to_bytes()).unwrap();
+        assert_eq!(loaded.len(), 2000);
+        for q in [[3.2f32, 7.1], [20.4, 33.3], [38.9, 0.2], [0.1, 49.2]] {
+            let nearest = (q[0].round() as usize) + (q[1].round() as usize) * 40;
+            assert_eq!(loaded.search(&data, dim, &q, 1, 32), vec![nearest as u32], "query {:?}", q);
+            assert_eq!(graph.search(&data, dim, &q, 5, 32), loaded.search(&data, dim, &q, 5, 32));
+        }
+        assert!(HnswGraph::from_bytes(&[0u8; 12]).is_err());
+    }
 }
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use crate::server::exec::exec_vector_index::read_vindex_file;
+use crate::server::exec::exec_vector_runtime;
+use crate::storage::{Store, SharedStore, Record};
+use serde_json::json;
+
+// 400 points on a 20x20 grid; `cat` splits them into ten interleaved groups
+fn seed_table(tmp: &tempfile::TempDir, name: &str) -> SharedStore {
+    let store = Store::new(tmp.path()).unwrap();
+    let recs: Vec<Record> = (0..400i64).map(|i| {
+        let mut m = serde_json::Map::new();
+        m.insert("id".into(), json!(i));
+        m.insert("cat".into(), json!(i % 10));
+        m.insert("vec".into(), json!(format!("{},{},0", i % 20, i / 20)));
+        Record { _time: 1_700_000_000_000 + i, sensors: m }
+    }).collect();
+    store.write_records(name, &recs).unwrap();
+    SharedStore::new(tmp.path()).unwrap()
+}
+
+#[test]
+fn filtered_search_returns_only_allowed_rows_and_reports_recall() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_table(&tmp, "clarium/public/t");
+    let run = |sql: &str| futures::executor::block_on(crate::server::exec::execute_query(&shared, sql)).unwrap();
+    run("CREATE VECTOR INDEX idx_t ON clarium/public/t(vec) USING hnsw WITH (metric='l2', dim=3, M=8, ef_build=64)");
+    run("BUILD VECTOR INDEX clarium/public/idx_t");
+    let vf = read_vindex_file(&shared, "clarium/public/idx_t").unwrap().unwrap();
+
+    // Rows of cat 3, a tenth of the table, are left by the filter
+    let allowed: HashSet<u64> = (0..400u64).filter(|i| i % 10 == 3).collect();
+    let q = vec![9.2f32, 9.9, 0.0];
+    let exact_opts = exec_vector_runtime::SearchOptions { engine_hint: Some("flat".into()), ..Default::default() };
+    let exact: Vec<u64> = exec_vector_runtime::search_vector_index_with_opts(&shared, &vf, &q, 400, &exact_opts).unwrap()
+        .into_iter().map(|(id, _)| id as u64).filter(|id| allowed.contains(id)).take(5).collect();
+
+    exec_vector_runtime::collect_search_metrics();
+    let ann = exec_vector_runtime::search_vector_index_filtered(&shared, &vf, &q, 5, &allowed).unwrap();
+    crate::system::set_vector_prefilter_percent(20);
+    let pre = exec_vector_runtime::search_vector_index_filtered(&shared, &vf, &q, 5, &allowed).unwrap();
+    crate::system::set_vector_prefilter_percent(5);
+    let metrics = exec_vector_runtime::take_search_metrics();
+
+    assert_eq!(ann.len(), 5);
+    assert!(ann.iter().all(|(id, _)| allowed.contains(id)), "{:?}", ann);
+    assert_eq!(pre.iter().map(|(id, _)| *id).collect::<Vec<u64>>(), exact);
+    assert_eq!(metrics.len(), 2);
+    assert_eq!((metrics[0].strategy, metrics[0].allowed_rows, metrics[0].indexed_rows), ("filtered_ann", 40, 400));
+    let ann_ids: HashSet<u64> = ann.iter().map(|(id, _)| *id).collect();
+    let expected_recall = exact.iter().filter(|id| ann_ids.contains(id)).count() as f64 / exact.len() as f64;
+    assert!((metrics[0].recall - expected_recall).abs() < 1e-9);
+    assert_eq!((metrics[1].strategy, metrics[1].rounds, metrics[1].recall), ("prefilter", 0, 1.0));
+    assert!(exec_vector_runtime::take_search_metrics().is_empty());
+}
+
+#[test]
+fn explain_analyze_reports_filtered_ann_search() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_table(&tmp, "clarium/public/t");
+    let run = |sql: &str| futures::executor::block_on(crate::server::exec::execute_query(&shared, sql)).unwrap();
+    run("CREATE VECTOR INDEX idx_t ON clarium/public/t(vec) USING hnsw WITH (metric='l2', dim=3, M=8, ef_build=64)");
+    run("BUILD VECTOR INDEX clarium/public/idx_t");
+
+    let plan = run("EXPLAIN ANALYZE FORMAT JSON SELECT id, cat FROM clarium/public/t WHERE cat = 3 \
+                    ORDER BY vec_l2(clarium/public/t.vec, '[9.2,9.9,0]') USING ANN LIMIT 5");
+    let searches = plan["explain"]["vector_searches"].as_array().unwrap().clone();
+    assert_eq!(searches.len(), 1, "{}", plan);
+    assert_eq!(searches[0]["index"], json!("clarium/public/idx_t"));
+    assert_eq!(searches[0]["allowed_rows"], json!(40));
+    assert!(searches[0]["recall"].as_f64().unwrap() > 0.0);
+
+    let df = run("SELECT id, cat FROM clarium/public/t WHERE cat = 3 \
+                  ORDER BY vec_l2(clarium/public/t.vec, '[9.2,9.9,0]') USING ANN LIMIT 5");
+    let rows = df.as_array().unwrap();
+    assert_eq!(rows.len(), 5);
+    assert!(rows.iter().all(|r| r["cat"] == json!(3)), "{:?}", rows);
+}
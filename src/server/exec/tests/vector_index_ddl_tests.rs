@@ -89,3 +89,42 @@ fn show_vector_indexes_enumerates() {
     let arr = json.as_array().cloned().unwrap();
     assert!(arr.len() >= 2);
 }
+
+#[test]
+fn vector_index_listed_in_pg_index_pg_class_and_pg_attribute() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_simple_table(&tmp, "clarium/public/docs");
+    block_on(crate::server::exec::execute_query(&shared, "CREATE VECTOR INDEX idx_docs_body ON clarium/public/docs(body_embed) USING hnsw WITH (metric='l2', dim=3)")).unwrap();
+    let select = |sql: &str| {
+        let q = match query::parse(sql).unwrap() { Command::Select(q) => q, _ => unreachable!() };
+        run_select(&shared, &q).unwrap()
+    };
+    let int = |df: &polars::prelude::DataFrame, col: &str| -> Vec<i32> {
+        df.column(col).unwrap().as_materialized_series().i32().unwrap().into_iter().flatten().collect()
+    };
+    let text = |df: &polars::prelude::DataFrame, col: &str| -> Vec<String> {
+        df.column(col).unwrap().as_materialized_series().str().unwrap().into_iter().flatten().map(|s| s.to_string()).collect()
+    };
+
+    let idx = select("SELECT oid, relnatts FROM pg_catalog.pg_class WHERE relkind = 'i' AND relname = 'idx_docs_body'");
+    let idx_oid = int(&idx, "oid")[0];
+    assert_eq!(int(&idx, "relnatts"), vec![1]);
+    let tbl = select("SELECT oid, relhasindex FROM pg_catalog.pg_class WHERE relname = 'docs'");
+    let tbl_oid = int(&tbl, "oid")[0];
+    assert_eq!(tbl.column("relhasindex").unwrap().as_materialized_series().bool().unwrap().get(0), Some(true));
+
+    let pgi = select(&format!("SELECT indrelid, indnatts, indkey, indisvalid FROM pg_catalog.pg_index WHERE indexrelid = {}", idx_oid));
+    assert_eq!(int(&pgi, "indrelid"), vec![tbl_oid]);
+    assert_eq!(int(&pgi, "indnatts"), vec![1]);
+    // indkey points at the indexed column's attnum in the table
+    let col = select(&format!("SELECT attnum FROM pg_catalog.pg_attribute WHERE attrelid = {} AND attname = 'body_embed'", tbl_oid));
+    assert_eq!(text(&pgi, "indkey"), vec![int(&col, "attnum")[0].to_string()]);
+
+    let attrs = select(&format!("SELECT attname, attnum FROM pg_catalog.pg_attribute WHERE attrelid = {}", idx_oid));
+    assert_eq!(text(&attrs, "attname"), vec!["body_embed"]);
+    assert_eq!(int(&attrs, "attnum"), vec![1]);
+
+    // Dropping the index removes it from pg_index
+    block_on(crate::server::exec::execute_query(&shared, "DROP VECTOR INDEX idx_docs_body")).unwrap();
+    assert_eq!(select("SELECT indexrelid FROM pg_catalog.pg_index").height(), 0);
+}
@@ -31,3 +31,37 @@ fn create_show_alter_modes_and_status_contains_mode() {
     let row = arr[0].as_object().unwrap();
     if let Some(m) = row.get("mode").and_then(|v| v.as_str()) { assert_eq!(m, "IMMEDIATE"); }
 }
+
+#[test]
+fn immediate_mode_applies_inserts_through_the_delta() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let store = new_store(&tmp);
+    seed_docs_with_embeddings(&store, "clarium/public/docs");
+    let exec = |sql: &str| block_on(crate::server::exec::execute_query(&store, sql)).unwrap();
+    let status = || exec("SHOW VECTOR INDEX STATUS clarium/public/idx_docs_imm")[0].clone();
+    exec("CREATE VECTOR INDEX idx_docs_imm ON clarium/public/docs(body_embed) USING HNSW WITH (metric='l2', dim=3, mode='immediate')");
+    exec("BUILD VECTOR INDEX clarium/public/idx_docs_imm");
+
+    // Inserted rows are searchable without a rebuild and don't make the index stale
+    exec("INSERT INTO clarium/public/docs (id, body, body_embed) VALUES (4, 'delta', '0.9,0.0,0.0')");
+    assert_eq!(status()["delta_rows"], serde_json::json!(1));
+    assert_eq!(status()["stale"], serde_json::json!(false));
+    let vf = crate::server::exec::exec_vector_index::read_vindex_file(&store, "clarium/public/idx_docs_imm").unwrap().unwrap();
+    let res = crate::server::exec::exec_vector_runtime::search_vector_index(&store, &vf, &[0.9, 0.0, 0.0], 2).unwrap();
+    assert_eq!(res.len(), 2);
+    assert!(res[0].1 < 1e-6, "nearest should be the inserted row, got {:?}", res);
+
+    // COMPACT TABLE folds the delta into the build
+    let out = exec("COMPACT TABLE clarium/public/docs");
+    assert_eq!(out["vector_indexes_rebuilt"], serde_json::json!(["clarium/public/idx_docs_imm"]));
+    assert_eq!(status()["delta_rows"], serde_json::json!(0));
+    assert_eq!(status()["rows_indexed"], serde_json::json!(4));
+    assert_eq!(status()["stale"], serde_json::json!(false));
+
+    // REBUILD_ONLY leaves new rows to the next build
+    exec("ALTER VECTOR INDEX clarium/public/idx_docs_imm SET MODE REBUILD_ONLY");
+    exec("INSERT INTO clarium/public/docs (id, body, body_embed) VALUES (5, 'later', '0.5,0.0,0.0')");
+    assert_eq!(status()["delta_rows"], serde_json::json!(0));
+    assert_eq!(status()["stale"], serde_json::json!(true));
+}
@@ -110,3 +110,57 @@ fn query_time_dimension_mismatch_returns_error() {
     let err = exec_vector_runtime::search_vector_index(&shared, &vf, &q, 3).err();
     assert!(err.is_some());
 }
+
+#[test]
+fn built_index_persists_under_table_and_tracks_staleness() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let table = "clarium/public/t3";
+    let shared = seed_table(&tmp, table);
+    let exec = |sql: &str| futures::executor::block_on(crate::server::exec::execute_query(&shared, sql)).unwrap();
+    exec("CREATE VECTOR INDEX idx_t3_vec ON clarium/public/t3(vec) USING HNSW WITH (metric='l2', dim=3, M=8, ef_build=32)");
+    exec("BUILD VECTOR INDEX clarium/public/idx_t3_vec");
+
+    // Artifacts live in the table folder
+    let vf = read_vindex_file(&shared, "clarium/public/idx_t3_vec").unwrap().unwrap();
+    let vdata = exec_vector_runtime::artifact_path(&shared, &vf, "vdata");
+    assert!(vdata.exists());
+    assert!(vdata.starts_with(shared.0.lock().db_dir(table).join("_vindex")));
+    #[cfg(feature = "ann_hnsw")]
+    assert!(exec_vector_runtime::artifact_path(&shared, &vf, "hnsw").exists());
+    assert_eq!(exec_vector_runtime::is_stale(&shared, &vf), Some(false));
+
+    // A store reopened over the same folder searches the saved index
+    let reopened = SharedStore::new(tmp.path()).unwrap();
+    let res = exec_vector_runtime::search_vector_index(&reopened, &vf, &[0.55f32, 0.0, 0.0], 3).unwrap();
+    assert_eq!(res.len(), 3);
+    assert!(res[0].1 <= 0.05 + 1e-5, "nearest distance {}", res[0].1);
+
+    // New rows make it stale
+    exec("INSERT INTO clarium/public/t3 (id, vec) VALUES (100, '2,0,0'), (101, '2,0,0')");
+    let vf = read_vindex_file(&shared, "clarium/public/idx_t3_vec").unwrap().unwrap();
+    assert_eq!(exec_vector_runtime::is_stale(&shared, &vf), Some(true));
+    let status = exec("SHOW VECTOR INDEX STATUS clarium/public/idx_t3_vec");
+    assert_eq!(status[0]["stale"], json!(true));
+
+    // REBUILD VECTOR INDEXES rebuilds it, then finds nothing left to do
+    let rebuilt = exec("REBUILD VECTOR INDEXES");
+    assert_eq!(rebuilt.as_array().unwrap().len(), 1);
+    assert_eq!(rebuilt[0]["rows_indexed"], json!(12));
+    let vf = read_vindex_file(&shared, "clarium/public/idx_t3_vec").unwrap().unwrap();
+    assert_eq!(exec_vector_runtime::is_stale(&shared, &vf), Some(false));
+    assert_eq!(exec("REBUILD VECTOR INDEXES").as_array().unwrap().len(), 0);
+    exec("REBUILD VECTOR INDEX clarium/public/idx_t3_vec");
+
+    // DROP removes the artifacts
+    exec("DROP VECTOR INDEX clarium/public/idx_t3_vec");
+    assert!(!vdata.exists());
+}
+
+#[test]
+fn parse_rebuild_vector_index() {
+    let Command::RebuildVectorIndex { name } = query::parse("REBUILD VECTOR INDEX clarium/public/idx").unwrap() else { panic!("expected REBUILD VECTOR INDEX") };
+    assert_eq!(name.as_deref(), Some("clarium/public/idx"));
+    let Command::RebuildVectorIndex { name } = query::parse("rebuild vector indexes").unwrap() else { panic!("expected REBUILD VECTOR INDEXES") };
+    assert!(name.is_none());
+}
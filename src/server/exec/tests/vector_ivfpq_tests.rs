@@ -0,0 +1,80 @@
+use crate::server::exec::exec_vector_index::read_vindex_file;
+use crate::server::exec::exec_vector_runtime;
+use crate::server::exec::vector_ivfpq::IvfPq;
+use crate::storage::{Store, SharedStore, Record};
+use serde_json::json;
+
+fn seed_table(tmp: &tempfile::TempDir, name: &str) -> SharedStore {
+    let store = Store::new(tmp.path()).unwrap();
+    let mut recs: Vec<Record> = Vec::new();
+    // 200 points on a 20x10 grid in the z=1 plane
+    for i in 0..200i64 {
+        let mut m = serde_json::Map::new();
+        m.insert("id".into(), json!(i + 1));
+        m.insert("vec".into(), json!(format!("{},{},1", i % 20, i / 20)));
+        recs.push(Record { _time: 1_700_000_000_000 + i, sensors: m });
+    }
+    store.write_records(name, &recs).unwrap();
+    SharedStore::new(tmp.path()).unwrap()
+}
+
+#[test]
+fn ivfpq_candidates_hold_nearest_and_round_trip() {
+    // 2000 points on a 40x50 grid; queries sit next to one grid point
+    let dim = 2usize;
+    let data: Vec<f32> = (0..2000).flat_map(|i| [(i % 40) as f32, (i / 40) as f32]).collect();
+    let ivf = IvfPq::build(&data, dim, "l2", 16, 2, 6).unwrap();
+    let loaded = IvfPq::from_bytes(&ivf.to_bytes()).unwrap();
+    assert_eq!((loaded.len(), loaded.nlist()), (2000, 16));
+    for q in [[3.2f32, 7.1], [20.4, 33.3], [38.9, 0.2], [0.1, 49.2]] {
+        let nearest = ((q[0].round() as usize) + (q[1].round() as usize) * 40) as u32;
+        let found = loaded.search(&q, 20, 4);
+        assert!(found.iter().any(|&(pos, _)| pos == nearest), "query {:?} missed {} in {:?}", q, nearest, found);
+        assert_eq!(ivf.search(&q, 20, 4), found);
+    }
+    assert!(IvfPq::build(&data, dim, "l2", 16, 3, 8).is_err());
+    assert!(IvfPq::from_bytes(&[0u8; 12]).is_err());
+}
+
+#[test]
+fn ivfpq_index_builds_and_searches() {
+    super::udf_common::init_all_test_udfs();
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = seed_table(&tmp, "clarium/public/t");
+    let run = |sql: &str| futures::executor::block_on(crate::server::exec::execute_query(&shared, sql));
+
+    // m must divide dim
+    assert!(run("CREATE VECTOR INDEX idx_bad ON clarium/public/t(vec) USING ivfpq WITH (metric='l2', dim=3, m=2)").is_err());
+    assert!(run("CREATE VECTOR INDEX idx_bad ON clarium/public/t(vec) USING ivfpq WITH (metric='l2', dim=3, nbits=9)").is_err());
+    assert!(run("CREATE VECTOR INDEX idx_bad ON clarium/public/t(vec) USING annoy WITH (metric='l2', dim=3)").is_err());
+
+    run("CREATE VECTOR INDEX idx_pq ON clarium/public/t(vec) USING ivfpq WITH (metric='l2', dim=3, nlist=8, m=3, nbits=4, nprobe=2)").unwrap();
+    run("BUILD VECTOR INDEX clarium/public/idx_pq").unwrap();
+    let vf = read_vindex_file(&shared, "clarium/public/idx_pq").unwrap().unwrap();
+    assert_eq!(vf.algo, "ivfpq");
+    let st = vf.status.as_ref().unwrap();
+    assert_eq!(st.get("engine").and_then(|x| x.as_str()), Some("ivfpq"));
+    assert_eq!(exec_vector_runtime::ann_engine(&shared, &vf), "ivfpq");
+    assert!(exec_vector_runtime::load_vdata(&shared, &vf).unwrap().has_ivf());
+
+    // Scores are exact after re-ranking: the nearest grid point is 0.1 away
+    let q = vec![7.1f32, 4.0, 1.0];
+    let res = exec_vector_runtime::search_vector_index(&shared, &vf, &q, 3).unwrap();
+    assert_eq!(res.len(), 3);
+    assert!((res[0].1 - 0.1).abs() < 1e-4, "{:?}", res);
+    assert!(res.windows(2).all(|w| w[0].1 <= w[1].1));
+
+    // Probing every list with a wide re-rank matches the exact scan
+    assert!(crate::system::apply_vector_setting("vector.ivf.nprobe", "8"));
+    assert!(crate::system::apply_vector_setting("vector.ivf.rerank", "50"));
+    let wide = exec_vector_runtime::search_vector_index(&shared, &vf, &q, 5).unwrap();
+    let opts = exec_vector_runtime::SearchOptions { engine_hint: Some("flat".into()), ..Default::default() };
+    let exact = exec_vector_runtime::search_vector_index_with_opts(&shared, &vf, &q, 5, &opts).unwrap();
+    let scores = |v: &[(u64, f32)]| v.iter().map(|x| x.1).collect::<Vec<f32>>();
+    assert_eq!(scores(&wide), exact.iter().map(|x| x.1).collect::<Vec<f32>>());
+    crate::system::set_vector_ivf_nprobe(0);
+    crate::system::set_vector_ivf_rerank(0);
+
+    run("DROP VECTOR INDEX clarium/public/idx_pq").unwrap();
+    assert!(!exec_vector_runtime::artifact_path(&shared, &vf, "ivfpq").exists());
+}
@@ -0,0 +1,42 @@
+use super::super::execute_query;
+use crate::server::exec::tests::fixtures::rec;
+use crate::storage::SharedStore;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_verify_table_detects_and_quarantines_corrupt_chunk() {
+    let tmp = tempfile::tempdir().unwrap();
+    let shared = SharedStore::new(tmp.path()).unwrap();
+    let table = "clarium/public/verify_m.time";
+    shared.0.lock().write_records(table, &[rec(1_000, 1.0), rec(2_000, 2.0)]).unwrap();
+    shared.0.lock().write_records(table, &[rec(5_000, 5.0)]).unwrap();
+
+    let res = execute_query(&shared, &format!("VERIFY TABLE {}", table)).await.unwrap();
+    let rows = res.as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|r| r["status"] == json!("ok")));
+
+    // Flip bytes in the middle of the first chunk to simulate bitrot
+    let dir = tmp.path().join("clarium/public/verify_m.time");
+    let chunk = dir.join(rows[0]["file"].as_str().unwrap());
+    let mut bytes = std::fs::read(&chunk).unwrap();
+    let mid = bytes.len() / 2;
+    bytes[mid] ^= 0xff;
+    std::fs::write(&chunk, &bytes).unwrap();
+
+    let res = execute_query(&shared, &format!("VERIFY TABLE {}", table)).await.unwrap();
+    let bad: Vec<&serde_json::Value> = res.as_array().unwrap().iter().filter(|r| r["status"] != json!("ok")).collect();
+    assert_eq!(bad.len(), 1);
+    assert_eq!(bad[0]["status"], json!("checksum_mismatch"));
+    assert_eq!(bad[0]["min_time"], json!(1000));
+    assert_eq!(bad[0]["max_time"], json!(2000));
+
+    let res = execute_query(&shared, &format!("VERIFY TABLE {} QUARANTINE", table)).await.unwrap();
+    assert!(res.as_array().unwrap().iter().any(|r| r["quarantined"] == json!(true)));
+    assert!(!chunk.exists());
+    assert!(dir.join("_quarantine").join(chunk.file_name().unwrap()).exists());
+
+    // The remaining chunk is still queryable
+    let rows = execute_query(&shared, &format!("SELECT _time, v FROM {}", table)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,272 @@
+//! vector_delta
+//! ------------
+//! Incremental maintenance of built vector indexes. Rows inserted into an indexed table after
+//! the build are appended to a delta next to the index payload, `<table>/_vindex/<q>.vdelta`,
+//! which searches scan exactly and merge with the results of the built index. COMPACT TABLE
+//! rebuilds indexes that have a delta, folding it into the payload and graph.
+//!
+//! magic:u32 | version:u32 | dim:u32 | flags:u32 | fingerprint:u64 | records: (row_id:u64 | dim*f32)*
+//!
+//! `fingerprint` is the table's chunk fingerprint after the last append, so the rows the delta
+//! holds don't make the index stale. Updates and deletes can't be followed by a delta: they set
+//! [`FLAG_STALE`], which keeps the index stale until it is rebuilt.
+//!
+//! The index mode decides when inserts are applied: IMMEDIATE appends within the writing
+//! statement, BATCHED and ASYNC hand the rows to a background writer, REBUILD_ONLY leaves the
+//! index stale until the next build.
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use polars::prelude::DataFrame;
+
+use crate::server::exec::exec_vector_index::VIndexFile;
+use crate::server::exec::exec_vector_runtime::{artifact_file, fingerprint_dir, RowIdSource};
+use crate::storage::cdc::ChangeOp;
+use crate::storage::Store;
+
+const MAGIC: u32 = 0x4C444456; // 'VDDL'
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 24;
+/// Rows were updated or deleted since the build
+pub const FLAG_STALE: u32 = 1;
+
+/// Rows appended to an index since its last build.
+pub struct VectorDelta {
+    pub dim: u32,
+    pub flags: u32,
+    /// Chunk fingerprint of the table after the last append, as recorded by builds
+    pub fingerprint: String,
+    pub row_ids: Vec<u64>,
+    pub data: Vec<f32>,
+}
+
+impl VectorDelta {
+    pub fn rows(&self) -> usize { self.row_ids.len() }
+
+    pub fn vector(&self, i: usize) -> &[f32] { &self.data[i * self.dim as usize..(i + 1) * self.dim as usize] }
+}
+
+pub(crate) fn delta_path(table_dir: &Path, qualified: &str) -> PathBuf { artifact_file(table_dir, qualified, "vdelta") }
+
+fn read_delta(path: &Path) -> Result<VectorDelta> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < HEADER_LEN { bail!("corrupt vdelta: too small"); }
+    let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    if word(0) != MAGIC { bail!("corrupt vdelta: bad magic"); }
+    if word(4) != VERSION { bail!("unsupported vdelta version {}", word(4)); }
+    let (dim, flags) = (word(8), word(12));
+    let fingerprint = format!("{:016x}", u64::from_le_bytes(bytes[16..24].try_into().unwrap()));
+    let record = 8 + dim as usize * 4;
+    // A torn trailing record (crash during an append) is ignored
+    let rows = (bytes.len() - HEADER_LEN) / record;
+    let mut row_ids: Vec<u64> = Vec::with_capacity(rows);
+    let mut data: Vec<f32> = Vec::with_capacity(rows * dim as usize);
+    for r in bytes[HEADER_LEN..HEADER_LEN + rows * record].chunks_exact(record) {
+        row_ids.push(u64::from_le_bytes(r[..8].try_into().unwrap()));
+        data.extend(r[8..].chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())));
+    }
+    Ok(VectorDelta { dim, flags, fingerprint, row_ids, data })
+}
+
+type FileStamp = (u64, Option<std::time::SystemTime>);
+
+static LOADED: Lazy<Mutex<HashMap<PathBuf, (FileStamp, Arc<VectorDelta>)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Delta stored at `path`, from the cache while the file is unchanged; None without one.
+pub fn load_delta(path: &Path) -> Option<Arc<VectorDelta>> {
+    let md = std::fs::metadata(path).ok()?;
+    let stamp: FileStamp = (md.len(), md.modified().ok());
+    if let Some((s, d)) = LOADED.lock().get(path) {
+        if *s == stamp { return Some(d.clone()); }
+    }
+    match read_delta(path) {
+        Ok(d) => {
+            let d = Arc::new(d);
+            LOADED.lock().insert(path.to_path_buf(), (stamp, d.clone()));
+            Some(d)
+        }
+        Err(e) => {
+            tracing::warn!(target: "clarium::vector", "ignoring vector delta {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Remove the delta at `path` (after a build folded it in, or with its index).
+pub fn delete_delta(path: &Path) {
+    LOADED.lock().remove(path);
+    let _ = std::fs::remove_file(path);
+}
+
+/// Open the delta at `path`, writing its header when it is new.
+fn open_delta(path: &Path, dim: u32) -> Result<std::fs::File> {
+    if let Some(parent) = path.parent() { std::fs::create_dir_all(parent)?; }
+    let mut f = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    if f.metadata()?.len() < HEADER_LEN as u64 {
+        let mut header: Vec<u8> = Vec::with_capacity(HEADER_LEN);
+        for w in [MAGIC, VERSION, dim, 0] { header.extend_from_slice(&w.to_le_bytes()); }
+        header.extend_from_slice(&0u64.to_le_bytes());
+        f.set_len(0)?;
+        f.write_all(&header)?;
+    } else {
+        let mut header = [0u8; 12];
+        f.read_exact(&mut header)?;
+        let existing = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if existing != dim { bail!("vector delta {} has dim {}, not {}", path.display(), existing, dim); }
+    }
+    Ok(f)
+}
+
+/// Append rows and record the table's fingerprint after them.
+fn append(path: &Path, dim: u32, row_ids: &[u64], data: &[f32], fingerprint: &str) -> Result<()> {
+    let mut f = open_delta(path, dim)?;
+    let mut buf: Vec<u8> = Vec::with_capacity(row_ids.len() * (8 + dim as usize * 4));
+    for (i, rid) in row_ids.iter().enumerate() {
+        buf.extend_from_slice(&rid.to_le_bytes());
+        for x in &data[i * dim as usize..(i + 1) * dim as usize] { buf.extend_from_slice(&x.to_le_bytes()); }
+    }
+    // Drop a torn trailing record before appending after it
+    let record = 8 + dim as u64 * 4;
+    let len = f.metadata()?.len();
+    let whole = HEADER_LEN as u64 + (len - HEADER_LEN as u64) / record * record;
+    if whole != len { f.set_len(whole)?; }
+    f.seek(SeekFrom::Start(whole))?;
+    f.write_all(&buf)?;
+    let fp = u64::from_str_radix(fingerprint, 16).unwrap_or(0);
+    f.seek(SeekFrom::Start(16))?;
+    f.write_all(&fp.to_le_bytes())?;
+    f.sync_data()?;
+    LOADED.lock().remove(path);
+    Ok(())
+}
+
+fn mark_stale(path: &Path, dim: u32) -> Result<()> {
+    let mut f = open_delta(path, dim)?;
+    let mut flags = [0u8; 4];
+    f.seek(SeekFrom::Start(12))?;
+    f.read_exact(&mut flags)?;
+    let flags = u32::from_le_bytes(flags) | FLAG_STALE;
+    f.seek(SeekFrom::Start(12))?;
+    f.write_all(&flags.to_le_bytes())?;
+    // The header changes in place, possibly within the same mtime tick
+    LOADED.lock().remove(path);
+    Ok(())
+}
+
+/// Rows waiting for the background writer (BATCHED / ASYNC indexes).
+struct PendingRows {
+    path: PathBuf,
+    table_dir: PathBuf,
+    index: String,
+    dim: u32,
+    row_ids: Vec<u64>,
+    data: Vec<f32>,
+}
+
+/// Background writer for BATCHED and ASYNC indexes, started on first use. Batches queued
+/// together for the same index are written with one append.
+static QUEUE: Lazy<Mutex<mpsc::Sender<PendingRows>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<PendingRows>();
+    std::thread::Builder::new()
+        .name("clarium-vector-delta".into())
+        .spawn(move || {
+            while let Ok(first) = rx.recv() {
+                let mut batch: Vec<PendingRows> = vec![first];
+                batch.extend(rx.try_iter());
+                let mut merged: Vec<PendingRows> = Vec::new();
+                for p in batch {
+                    match merged.iter_mut().find(|m| m.path == p.path && m.dim == p.dim) {
+                        Some(m) => { m.row_ids.extend(p.row_ids); m.data.extend(p.data); }
+                        None => merged.push(p),
+                    }
+                }
+                for p in merged {
+                    let (_, fp) = fingerprint_dir(&p.table_dir);
+                    if let Err(e) = append(&p.path, p.dim, &p.row_ids, &p.data, &fp) {
+                        tracing::warn!(target: "clarium::vector", "vector index {}: {} queued row(s) not applied: {}", p.index, p.row_ids.len(), e);
+                        let _ = mark_stale(&p.path, p.dim);
+                    }
+                }
+            }
+        })
+        .expect("spawn vector delta worker");
+    Mutex::new(tx)
+});
+
+/// Built indexes over the table stored in `table_dir` that are maintained incrementally (mode
+/// other than REBUILD_ONLY).
+fn incremental_indexes(store: &Store, table_dir: &Path) -> Vec<VIndexFile> {
+    crate::system_catalog::shared::enumerate_vector_indexes_in(store.root_path())
+        .into_iter()
+        .filter_map(|m| std::fs::read_to_string(&m.file).ok().and_then(|t| serde_json::from_str::<VIndexFile>(&t).ok()))
+        .filter(|v| store.db_dir(&v.table) == table_dir)
+        .filter(|v| !v.mode.as_deref().unwrap_or("REBUILD_ONLY").eq_ignore_ascii_case("REBUILD_ONLY"))
+        .filter(|v| v.status.as_ref().and_then(|m| m.get("state")).and_then(|x| x.as_str()) == Some("built"))
+        .collect()
+}
+
+/// Whether writes to `table` feed an incrementally maintained vector index.
+pub fn has_incremental_indexes(store: &Store, table: &str) -> bool {
+    let table_dir = store.db_dir(table);
+    table_dir.join("_vindex").is_dir() && !incremental_indexes(store, &table_dir).is_empty()
+}
+
+/// Vectors and row ids of the rows of `df` an index over `v.column` takes. Rows without a
+/// vector of the index dimension are skipped, as a `dim_policy='skip'` build would.
+fn extract_rows(store: &Store, table: &str, v: &VIndexFile, df: &DataFrame, dim: u32, first_ordinal: u64) -> (Vec<u64>, Vec<f32>) {
+    let mut row_ids: Vec<u64> = Vec::new();
+    let mut data: Vec<f32> = Vec::new();
+    let Some(col) = df.get_column_names().into_iter().find(|c| c.eq_ignore_ascii_case(&v.column)).cloned() else { return (row_ids, data) };
+    let Ok(series) = df.column(&col) else { return (row_ids, data) };
+    let pk_cols = store.get_primary_key(table);
+    let ids = RowIdSource::new(df, pk_cols.as_ref());
+    for i in 0..df.height() {
+        let Some(vv) = crate::server::exec::vector_utils::extract_vec_f32_col(series, i) else { continue };
+        if vv.len() as u32 != dim { continue; }
+        row_ids.push(ids.row_id(i, first_ordinal + row_ids.len() as u64).0);
+        data.extend_from_slice(&vv);
+    }
+    (row_ids, data)
+}
+
+/// Row-change hook (`StorageHooks::rows_changed`, see `server::storage_hooks`) called from
+/// `Store::record_changes` after `df` was written to `table`.
+/// Failures never fail the write: the index is marked stale instead.
+pub fn on_rows_changed(store: &Store, table: &str, op: ChangeOp, df: &DataFrame) {
+    let table_dir = store.db_dir(table);
+    // Only built indexes have artifacts under the table; most tables have none
+    if !table_dir.join("_vindex").is_dir() { return; }
+    for v in incremental_indexes(store, &table_dir) {
+        let st = v.status.as_ref();
+        let Some(dim) = st.and_then(|m| m.get("dim")).and_then(|x| x.as_u64()).map(|d| d as u32).filter(|d| *d > 0) else { continue };
+        let path = delta_path(&table_dir, &v.qualified);
+        if op != ChangeOp::Insert {
+            if let Err(e) = mark_stale(&path, dim) {
+                tracing::warn!(target: "clarium::vector", "vector index {}: could not mark stale: {}", v.qualified, e);
+            }
+            continue;
+        }
+        let base_rows = st.and_then(|m| m.get("rows_indexed")).and_then(|x| x.as_u64()).unwrap_or(0);
+        let delta_rows = load_delta(&path).map(|d| d.rows() as u64).unwrap_or(0);
+        let (row_ids, data) = extract_rows(store, table, &v, df, dim, base_rows + delta_rows);
+        if row_ids.is_empty() { continue; }
+        crate::tprintln!("[vector.delta] name={} op=insert rows={} mode={:?}", v.qualified, row_ids.len(), v.mode);
+        if v.mode.as_deref().is_some_and(|m| m.eq_ignore_ascii_case("IMMEDIATE")) {
+            let (_, fp) = fingerprint_dir(&table_dir);
+            if let Err(e) = append(&path, dim, &row_ids, &data, &fp) {
+                tracing::warn!(target: "clarium::vector", "vector index {}: {} row(s) not applied: {}", v.qualified, row_ids.len(), e);
+                let _ = mark_stale(&path, dim);
+            }
+        } else {
+            let pending = PendingRows { path, table_dir: table_dir.clone(), index: v.qualified.clone(), dim, row_ids, data };
+            let _ = QUEUE.lock().send(pending);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! vector_hnsw
+//! -----------
+//! HNSW graph over the vectors of a `.vdata` payload, with a compact binary form kept in the
+//! `.hnsw` file next to it so a built index is reloaded rather than rebuilt:
+//!
+//! magic:u32 | version:u32 | metric:u32 | m:u32 | rows:u32 | entry:u32 | max_level:u32 |
+//! per node: level:u32, then per layer 0..=level: count:u32 | neighbours: count*u32
+//!
+//! Nodes are positions in the payload. A node's level is drawn from a hash of its position,
+//! so the same vectors always give the same graph.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use anyhow::{bail, Result};
+
+const MAGIC: u32 = 0x564E5348; // 'HSNV'
+const VERSION: u32 = 1;
+const MAX_LEVEL: usize = 16;
+/// Nodes a filtered search may visit per unit of `ef` before it gives up on finding more
+/// allowed nodes; callers widen `ef` or scan exactly when that leaves too few.
+const FILTER_VISITS_PER_EF: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric { L2, Cosine, Ip }
+
+impl Metric {
+    pub fn parse(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "cosine" => Metric::Cosine,
+            "ip" | "dot" => Metric::Ip,
+            _ => Metric::L2,
+        }
+    }
+
+    fn code(self) -> u32 { match self { Metric::L2 => 0, Metric::Cosine => 1, Metric::Ip => 2 } }
+
+    fn from_code(code: u32) -> Result<Self> {
+        match code {
+            0 => Ok(Metric::L2),
+            1 => Ok(Metric::Cosine),
+            2 => Ok(Metric::Ip),
+            other => bail!("corrupt hnsw: unknown metric {}", other),
+        }
+    }
+
+    /// Distance used to walk the graph; smaller is closer for every metric.
+    fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        let d = match self {
+            Metric::L2 => super::exec_vector_runtime::l2(a, b),
+            Metric::Cosine => 1.0 - super::exec_vector_runtime::cosine(a, b),
+            Metric::Ip => -super::exec_vector_runtime::dot(a, b),
+        };
+        if d.is_nan() { f32::INFINITY } else { d }
+    }
+}
+
+/// Order-preserving map of an f32 onto u32, so distances can live in integer heaps.
+#[inline]
+fn key(v: f32) -> u32 { let b = v.to_bits(); if b & (1u32 << 31) != 0 { !b } else { b | (1u32 << 31) } }
+
+#[inline]
+fn vector(data: &[f32], dim: usize, i: u32) -> &[f32] { &data[i as usize * dim..(i as usize + 1) * dim] }
+
+#[derive(Debug, Clone)]
+pub struct HnswGraph {
+    metric: Metric,
+    /// Links per node on upper layers; layer 0 keeps twice as many
+    m: usize,
+    entry: u32,
+    max_level: usize,
+    /// links[node][layer]
+    links: Vec<Vec<Vec<u32>>>,
+}
+
+impl HnswGraph {
+    pub fn metric(&self) -> Metric { self.metric }
+
+    pub fn len(&self) -> usize { self.links.len() }
+
+    pub fn is_empty(&self) -> bool { self.links.is_empty() }
+
+    fn level_for(pos: u32, m: usize) -> usize {
+        let h = xxhash_rust::xxh3::xxh3_64(&pos.to_le_bytes());
+        // Uniform in (0, 1]
+        let u = ((h >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        ((-u.ln() / (m as f64).ln()).floor() as usize).min(MAX_LEVEL)
+    }
+
+    fn max_links(&self, layer: usize) -> usize { if layer == 0 { self.m * 2 } else { self.m } }
+
+    fn dist(&self, data: &[f32], dim: usize, q: &[f32], n: u32) -> u32 { key(self.metric.distance(q, vector(data, dim, n))) }
+
+    /// Build the graph over `data` (rows of `dim` values), inserting rows in order.
+    pub fn build(data: &[f32], dim: usize, metric: Metric, m: usize, ef_build: usize) -> Self {
+        let rows = if dim == 0 { 0 } else { data.len() / dim };
+        let m = m.max(2);
+        let mut g = HnswGraph { metric, m, entry: 0, max_level: 0, links: Vec::with_capacity(rows) };
+        for i in 0..rows as u32 {
+            let level = Self::level_for(i, m);
+            g.links.push(vec![Vec::new(); level + 1]);
+            if i == 0 { g.max_level = level; continue; }
+            let q = vector(data, dim, i);
+            let mut cur = g.entry;
+            for layer in (level + 1..=g.max_level).rev() {
+                cur = g.greedy(data, dim, q, cur, layer);
+            }
+            let mut eps = vec![cur];
+            for layer in (0..=level.min(g.max_level)).rev() {
+                let found = g.search_layer(data, dim, q, &eps, ef_build.max(m), layer, None);
+                let keep = g.max_links(layer);
+                let neighbours: Vec<u32> = found.iter().take(keep).map(|&(_, n)| n).collect();
+                for &n in &neighbours {
+                    let metric = g.metric;
+                    let links = &mut g.links[n as usize][layer];
+                    links.push(i);
+                    if links.len() > keep {
+                        // Keep the neighbour's closest links
+                        let base = vector(data, dim, n);
+                        let mut scored: Vec<(u32, u32)> = links.iter().map(|&x| (key(metric.distance(base, vector(data, dim, x))), x)).collect();
+                        scored.sort_unstable();
+                        *links = scored.into_iter().take(keep).map(|(_, x)| x).collect();
+                    }
+                }
+                g.links[i as usize][layer] = neighbours;
+                eps = found.into_iter().map(|(_, n)| n).collect();
+            }
+            if level > g.max_level { g.max_level = level; g.entry = i; }
+        }
+        g
+    }
+
+    /// Walk `layer` from `start` towards `q` while a neighbour is closer.
+    fn greedy(&self, data: &[f32], dim: usize, q: &[f32], start: u32, layer: usize) -> u32 {
+        let mut cur = start;
+        let mut best = self.dist(data, dim, q, cur);
+        loop {
+            let mut moved = false;
+            for &n in self.links[cur as usize].get(layer).map(|v| v.as_slice()).unwrap_or(&[]) {
+                let d = self.dist(data, dim, q, n);
+                if d < best { best = d; cur = n; moved = true; }
+            }
+            if !moved { return cur; }
+        }
+    }
+
+    /// The `ef` nodes of `layer` closest to `q` reachable from `eps`, as (distance key, node),
+    /// closest first. With `allow`, only allowed nodes are returned; the others are still walked
+    /// through, until `ef` allowed nodes are found or the visit budget runs out.
+    fn search_layer(&self, data: &[f32], dim: usize, q: &[f32], eps: &[u32], ef: usize, layer: usize, allow: Option<&dyn Fn(u32) -> bool>) -> Vec<(u32, u32)> {
+        let allowed = |n: u32| allow.is_none_or(|f| f(n));
+        let mut visited: HashSet<u32> = eps.iter().copied().collect();
+        let mut candidates: BinaryHeap<Reverse<(u32, u32)>> = BinaryHeap::new();
+        let mut found: BinaryHeap<(u32, u32)> = BinaryHeap::new();
+        for &e in &visited {
+            let d = self.dist(data, dim, q, e);
+            candidates.push(Reverse((d, e)));
+            if allowed(e) {
+                found.push((d, e));
+                if found.len() > ef { found.pop(); }
+            }
+        }
+        let budget = if allow.is_some() { ef.saturating_mul(FILTER_VISITS_PER_EF) } else { usize::MAX };
+        while let Some(Reverse((d, c))) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|&(worst, _)| d > worst) { break; }
+            if visited.len() > budget { break; }
+            for &n in self.links[c as usize].get(layer).map(|v| v.as_slice()).unwrap_or(&[]) {
+                if !visited.insert(n) { continue; }
+                let dn = self.dist(data, dim, q, n);
+                if found.len() < ef || found.peek().is_some_and(|&(worst, _)| dn < worst) {
+                    candidates.push(Reverse((dn, n)));
+                    if allowed(n) {
+                        found.push((dn, n));
+                        if found.len() > ef { found.pop(); }
+                    }
+                }
+            }
+        }
+        let mut out = found.into_vec();
+        out.sort_unstable();
+        out
+    }
+
+    /// Positions of (about) the `k` rows closest to `q`, closest first; `ef` bounds the
+    /// candidate list on the bottom layer.
+    pub fn search(&self, data: &[f32], dim: usize, q: &[f32], k: usize, ef: usize) -> Vec<u32> {
+        self.search_filtered(data, dim, q, k, ef, None)
+    }
+
+    /// Like [`search`](Self::search), returning only nodes `allow` accepts. The filter applies
+    /// during the bottom-layer walk, which may return fewer than `k` nodes when allowed ones are
+    /// rare; a larger `ef` walks further.
+    pub fn search_filtered(&self, data: &[f32], dim: usize, q: &[f32], k: usize, ef: usize, allow: Option<&dyn Fn(u32) -> bool>) -> Vec<u32> {
+        if self.links.is_empty() || k == 0 { return Vec::new(); }
+        let mut cur = self.entry;
+        for layer in (1..=self.max_level).rev() {
+            cur = self.greedy(data, dim, q, cur, layer);
+        }
+        self.search_layer(data, dim, q, &[cur], ef.max(k), 0, allow).into_iter().take(k).map(|(_, n)| n).collect()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        for v in [MAGIC, VERSION, self.metric.code(), self.m as u32, self.links.len() as u32, self.entry, self.max_level as u32] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for node in &self.links {
+            out.extend_from_slice(&((node.len() - 1) as u32).to_le_bytes());
+            for layer in node {
+                out.extend_from_slice(&(layer.len() as u32).to_le_bytes());
+                for n in layer { out.extend_from_slice(&n.to_le_bytes()); }
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut pos = 0usize;
+        let mut next = || -> Result<u32> {
+            let Some(b) = bytes.get(pos..pos + 4) else { bail!("corrupt hnsw: truncated") };
+            pos += 4;
+            Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        if next()? != MAGIC { bail!("corrupt hnsw: bad magic"); }
+        let version = next()?;
+        if version != VERSION { bail!("unsupported hnsw version {}", version); }
+        let metric = Metric::from_code(next()?)?;
+        let m = next()? as usize;
+        let rows = next()? as usize;
+        let entry = next()?;
+        let max_level = next()? as usize;
+        if max_level > MAX_LEVEL || (rows > 0 && entry as usize >= rows) { bail!("corrupt hnsw: bad header"); }
+        let mut links: Vec<Vec<Vec<u32>>> = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let level = next()? as usize;
+            if level > max_level { bail!("corrupt hnsw: level {} above {}", level, max_level); }
+            let mut node: Vec<Vec<u32>> = Vec::with_capacity(level + 1);
+            for _ in 0..=level {
+                let count = next()? as usize;
+                let mut layer: Vec<u32> = Vec::with_capacity(count.min(rows));
+                for _ in 0..count {
+                    let n = next()?;
+                    if n as usize >= rows { bail!("corrupt hnsw: link to {} of {} rows", n, rows); }
+                    layer.push(n);
+                }
+                node.push(layer);
+            }
+            links.push(node);
+        }
+        Ok(HnswGraph { metric, m, entry, max_level, links })
+    }
+}
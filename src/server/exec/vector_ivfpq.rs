@@ -0,0 +1,244 @@
+//! vector_ivfpq
+//! ------------
+//! IVF-PQ index over the vectors of a `.vdata` payload: rows are grouped into `nlist` lists
+//! around k-means centroids, and each row is stored in its list as `m` bytes, the codes of
+//! its residual (row minus centroid) in `m` sub-quantizers of `2^nbits` centroids each.
+//! A search scans the `nprobe` lists closest to the query and ranks their rows by the
+//! distance estimated from the codes; callers re-rank the best candidates exactly against
+//! the payload. The index takes `m` bytes per row instead of HNSW's links, which is what
+//! makes it usable when a graph would not fit in memory.
+//!
+//! Candidates are ranked by L2 distance, over unit vectors for cosine. Inner product has no
+//! such mapping, so ip indexes rely on the exact re-ranking.
+//!
+//! `.ivfpq` format:
+//! magic:u32 | version:u32 | metric:u32 | dim:u32 | nlist:u32 | m:u32 | nbits:u32 | rows:u32 |
+//! centroids: nlist*dim*f32 | codebooks: m*2^nbits*(dim/m)*f32 |
+//! per list: count:u32 | positions: count*u32 | codes: count*m*u8
+
+use anyhow::{bail, Result};
+
+const MAGIC: u32 = 0x46564956; // 'VIVF'
+const VERSION: u32 = 1;
+/// Rows sampled to train the centroids and codebooks
+const TRAIN_SAMPLE: usize = 65_536;
+const KMEANS_ITERS: usize = 12;
+
+const METRIC_L2: u32 = 0;
+const METRIC_COSINE: u32 = 1;
+const METRIC_IP: u32 = 2;
+
+fn metric_code(metric: &str) -> u32 {
+    match metric.to_ascii_lowercase().as_str() {
+        "cosine" => METRIC_COSINE,
+        "ip" | "dot" => METRIC_IP,
+        _ => METRIC_L2,
+    }
+}
+
+#[inline]
+fn l2_sq(a: &[f32], b: &[f32]) -> f32 { a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum() }
+
+/// `v` scaled to unit length; zero vectors are left as they are.
+fn normalized(v: &[f32]) -> Vec<f32> {
+    let n = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if n == 0.0 { v.to_vec() } else { v.iter().map(|x| x / n).collect() }
+}
+
+/// Index of the row of `centroids` (rows of `dim`) closest to `v`.
+fn nearest(centroids: &[f32], dim: usize, v: &[f32]) -> usize {
+    let mut best = (f32::INFINITY, 0usize);
+    for (c, cv) in centroids.chunks_exact(dim).enumerate() {
+        let d = l2_sq(cv, v);
+        if d < best.0 { best = (d, c); }
+    }
+    best.1
+}
+
+/// Lloyd's k-means over `points` (rows of `dim`), seeded with evenly spaced points so the
+/// same input always trains the same centroids. Centroids left without points keep their
+/// previous position. With fewer points than `k`, points are repeated.
+fn kmeans(points: &[f32], dim: usize, k: usize) -> Vec<f32> {
+    let n = points.len() / dim;
+    if n == 0 { return vec![0.0; k * dim]; }
+    let mut centroids: Vec<f32> = Vec::with_capacity(k * dim);
+    for c in 0..k {
+        let i = c * n / k;
+        centroids.extend_from_slice(&points[i * dim..(i + 1) * dim]);
+    }
+    if n <= k { return centroids; }
+    let mut assign = vec![0usize; n];
+    for _ in 0..KMEANS_ITERS {
+        let mut moved = false;
+        for (i, p) in points.chunks_exact(dim).enumerate() {
+            let c = nearest(&centroids, dim, p);
+            if assign[i] != c { assign[i] = c; moved = true; }
+        }
+        let mut sums = vec![0f32; k * dim];
+        let mut counts = vec![0usize; k];
+        for (i, p) in points.chunks_exact(dim).enumerate() {
+            let c = assign[i];
+            counts[c] += 1;
+            for (s, x) in sums[c * dim..(c + 1) * dim].iter_mut().zip(p) { *s += x; }
+        }
+        for c in 0..k {
+            if counts[c] == 0 { continue; }
+            for j in 0..dim { centroids[c * dim + j] = sums[c * dim + j] / counts[c] as f32; }
+        }
+        if !moved { break; }
+    }
+    centroids
+}
+
+#[derive(Debug, Clone)]
+pub struct IvfPq {
+    metric: u32,
+    dim: usize,
+    /// Sub-quantizers per row, each covering `dim / m` values
+    m: usize,
+    nbits: u32,
+    /// nlist rows of dim
+    centroids: Vec<f32>,
+    /// m blocks of 2^nbits rows of dim/m
+    codebooks: Vec<f32>,
+    /// Payload positions in each list
+    lists: Vec<Vec<u32>>,
+    /// m codes per position, in list order
+    codes: Vec<Vec<u8>>,
+    rows: usize,
+}
+
+impl IvfPq {
+    pub fn len(&self) -> usize { self.rows }
+
+    pub fn is_empty(&self) -> bool { self.rows == 0 }
+
+    pub fn nlist(&self) -> usize { self.lists.len() }
+
+    fn ksub(&self) -> usize { 1usize << self.nbits }
+
+    fn dsub(&self) -> usize { self.dim / self.m }
+
+    /// Train and fill the index over `data` (rows of `dim` values). `nlist` is capped at the
+    /// row count; `m` must divide `dim` and `nbits` be within 1..=8.
+    pub fn build(data: &[f32], dim: usize, metric: &str, nlist: usize, m: usize, nbits: u32) -> Result<Self> {
+        if dim == 0 { bail!("ivfpq: dimension is 0"); }
+        if m == 0 || dim % m != 0 { bail!("ivfpq: m={} must divide dim={}", m, dim); }
+        if !(1..=8).contains(&nbits) { bail!("ivfpq: nbits={} must be between 1 and 8", nbits); }
+        let metric = metric_code(metric);
+        let rows = data.len() / dim;
+        let points: Vec<f32> = if metric == METRIC_COSINE { data.chunks_exact(dim).flat_map(normalized).collect() } else { data.to_vec() };
+        let nlist = nlist.clamp(1, rows.max(1));
+        // Train on an evenly strided sample of the rows
+        let stride = rows.div_ceil(TRAIN_SAMPLE).max(1);
+        let sample: Vec<f32> = points.chunks_exact(dim).step_by(stride).flatten().copied().collect();
+        let centroids = kmeans(&sample, dim, nlist);
+        let mut ivf = IvfPq { metric, dim, m, nbits, centroids, codebooks: Vec::new(), lists: vec![Vec::new(); nlist], codes: vec![Vec::new(); nlist], rows };
+        // Sub-quantizers are trained on the sample's residuals
+        let (ksub, dsub) = (ivf.ksub(), ivf.dsub());
+        let residuals: Vec<f32> = sample.chunks_exact(dim).flat_map(|p| ivf.residual(p, nearest(&ivf.centroids, dim, p))).collect();
+        for j in 0..m {
+            let sub: Vec<f32> = residuals.chunks_exact(dim).flat_map(|r| r[j * dsub..(j + 1) * dsub].iter().copied()).collect();
+            ivf.codebooks.extend(kmeans(&sub, dsub, ksub));
+        }
+        for (pos, p) in points.chunks_exact(dim).enumerate() {
+            let list = nearest(&ivf.centroids, dim, p);
+            let r = ivf.residual(p, list);
+            for j in 0..m {
+                let book = &ivf.codebooks[j * ksub * dsub..(j + 1) * ksub * dsub];
+                ivf.codes[list].push(nearest(book, dsub, &r[j * dsub..(j + 1) * dsub]) as u8);
+            }
+            ivf.lists[list].push(pos as u32);
+        }
+        Ok(ivf)
+    }
+
+    fn residual(&self, v: &[f32], list: usize) -> Vec<f32> {
+        v.iter().zip(&self.centroids[list * self.dim..(list + 1) * self.dim]).map(|(x, c)| x - c).collect()
+    }
+
+    /// Positions of (about) the `k` rows closest to `q` among the `nprobe` lists nearest to
+    /// it, with their estimated squared L2 distance, closest first.
+    pub fn search(&self, q: &[f32], k: usize, nprobe: usize) -> Vec<(u32, f32)> {
+        self.search_filtered(q, k, nprobe, None)
+    }
+
+    /// Like [`search`](Self::search), skipping positions `allow` rejects while the lists are
+    /// scanned. Fewer than `k` come back when the probed lists hold fewer allowed rows.
+    pub fn search_filtered(&self, q: &[f32], k: usize, nprobe: usize, allow: Option<&dyn Fn(u32) -> bool>) -> Vec<(u32, f32)> {
+        if self.rows == 0 || k == 0 || q.len() != self.dim { return Vec::new(); }
+        let q = if self.metric == METRIC_COSINE { normalized(q) } else { q.to_vec() };
+        let mut lists: Vec<(f32, usize)> = self.centroids.chunks_exact(self.dim).map(|c| l2_sq(c, &q)).zip(0..).collect();
+        lists.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (ksub, dsub) = (self.ksub(), self.dsub());
+        let mut table = vec![0f32; self.m * ksub];
+        let mut found: Vec<(u32, f32)> = Vec::new();
+        for &(_, list) in lists.iter().take(nprobe.max(1)) {
+            // Distance from the query's residual to every codeword, per sub-quantizer
+            let r = self.residual(&q, list);
+            for j in 0..self.m {
+                let rs = &r[j * dsub..(j + 1) * dsub];
+                let book = &self.codebooks[j * ksub * dsub..(j + 1) * ksub * dsub];
+                for (c, cw) in book.chunks_exact(dsub).enumerate() { table[j * ksub + c] = l2_sq(cw, rs); }
+            }
+            for (pos, codes) in self.lists[list].iter().zip(self.codes[list].chunks_exact(self.m)) {
+                if allow.is_some_and(|f| !f(*pos)) { continue; }
+                let d: f32 = codes.iter().enumerate().map(|(j, &c)| table[j * ksub + c as usize]).sum();
+                found.push((*pos, d));
+            }
+        }
+        if found.len() > k {
+            found.select_nth_unstable_by(k - 1, |a, b| a.1.total_cmp(&b.1));
+            found.truncate(k);
+        }
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::with_capacity(32 + (self.centroids.len() + self.codebooks.len()) * 4 + self.rows * (4 + self.m) + self.lists.len() * 4);
+        for v in [MAGIC, VERSION, self.metric, self.dim as u32, self.lists.len() as u32, self.m as u32, self.nbits, self.rows as u32] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        for x in self.centroids.iter().chain(&self.codebooks) { out.extend_from_slice(&x.to_le_bytes()); }
+        for (list, codes) in self.lists.iter().zip(&self.codes) {
+            out.extend_from_slice(&(list.len() as u32).to_le_bytes());
+            for pos in list { out.extend_from_slice(&pos.to_le_bytes()); }
+            out.extend_from_slice(codes);
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        fn slice_at<'a>(bytes: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8]> {
+            let Some(b) = bytes.get(*pos..*pos + n) else { bail!("corrupt ivfpq: truncated") };
+            *pos += n;
+            Ok(b)
+        }
+        let mut pos = 0usize;
+        let mut take = |n: usize| slice_at(bytes, &mut pos, n);
+        let mut header = [0u32; 8];
+        for h in header.iter_mut() { *h = u32::from_le_bytes(take(4)?.try_into().unwrap()); }
+        let [magic, version, metric, dim, nlist, m, nbits, rows] = header.map(|x| x as usize);
+        if magic as u32 != MAGIC { bail!("corrupt ivfpq: bad magic"); }
+        if version as u32 != VERSION { bail!("unsupported ivfpq version {}", version); }
+        if metric as u32 > METRIC_IP || dim == 0 || nlist == 0 || m == 0 || dim % m != 0 || !(1..=8).contains(&nbits) {
+            bail!("corrupt ivfpq: bad header");
+        }
+        let floats = |b: &[u8]| -> Vec<f32> { b.chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())).collect() };
+        let centroids = floats(take(nlist * dim * 4)?);
+        let codebooks = floats(take(m * (1 << nbits) * (dim / m) * 4)?);
+        let (mut lists, mut codes) = (Vec::with_capacity(nlist), Vec::with_capacity(nlist));
+        let mut total = 0usize;
+        for _ in 0..nlist {
+            let count = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let list: Vec<u32> = take(count * 4)?.chunks_exact(4).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect();
+            if list.iter().any(|&p| p as usize >= rows) { bail!("corrupt ivfpq: position out of {} rows", rows); }
+            codes.push(take(count * m)?.to_vec());
+            lists.push(list);
+            total += count;
+        }
+        if total != rows { bail!("corrupt ivfpq: lists hold {} of {} rows", total, rows); }
+        Ok(IvfPq { metric: metric as u32, dim, m, nbits: nbits as u32, centroids, codebooks, lists, codes, rows })
+    }
+}
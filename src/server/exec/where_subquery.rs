@@ -282,6 +282,7 @@ pub(crate) fn substitute_outer_refs_in_query(df: &DataFrame, row_idx: usize, sub
             }
             AE::Term(_) => a.clone(),
             AE::Cast { expr, ty } => AE::Cast { expr: Box::new(subst_arith(df, row_idx, expr, inner_aliases, outer_aliases)), ty: ty.clone() },
+            AE::Collate { expr, collation } => AE::Collate { expr: Box::new(subst_arith(df, row_idx, expr, inner_aliases, outer_aliases)), collation: collation.clone() },
             AE::BinOp { left, op, right } => AE::BinOp { left: Box::new(subst_arith(df, row_idx, left, inner_aliases, outer_aliases)), op: op.clone(), right: Box::new(subst_arith(df, row_idx, right, inner_aliases, outer_aliases)) },
             AE::Func(f) => AE::Func(f.clone()),
             AE::Slice { base, start, stop, step } => AE::Slice { base: Box::new(subst_arith(df, row_idx, base, inner_aliases, outer_aliases)), start: start.clone(), stop: stop.clone(), step: *step },
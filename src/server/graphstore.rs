@@ -220,8 +220,7 @@ impl GraphHandle {
 /// Resolve `<db>/<schema>/<graph>.gstore` directory for a qualified name like `clarium/public/know`.
 fn gstore_root(store: &SharedStore, qualified: &str) -> PathBuf {
     let mut p = store.0.lock().root_path().clone();
-    let local = qualified.replace('/', std::path::MAIN_SEPARATOR_STR);
-    p.push(local);
+    p = crate::ident::to_local_path(&p, &qualified);
     p.set_extension("gstore");
     p
 }
@@ -465,6 +464,18 @@ impl GraphHandle {
     #[allow(dead_code)]
     fn dict(&self) -> Option<&NodeDict> { self.dict.as_ref() }
 
+    /// Node id of `(label, key)` in the dictionary overlaid with the node delta log.
+    pub fn node_id(&mut self, label: &str, key: &str) -> Result<Option<u64>> {
+        self.ensure_loaded()?;
+        Ok(self.dict.as_ref().and_then(|d| d.lookup(label, key)))
+    }
+
+    /// First node id not yet assigned by the dictionary or the node delta log.
+    pub fn next_node_id(&mut self) -> Result<u64> {
+        self.ensure_loaded()?;
+        Ok(self.dict.as_ref().map(|d| d.next_id()).unwrap_or(0))
+    }
+
     #[allow(dead_code)]
     fn part(&self, p: u32) -> Option<&PartitionState> {
         self.parts.as_ref()?.get(p as usize)
@@ -612,7 +623,7 @@ pub fn gc_scan_all_graphs(store: &SharedStore) {
     // Walk db_root and find any path ending with .gstore/meta/manifest.json
     let root = store.0.lock().root_path().clone();
     let mut graphs: Vec<String> = Vec::new();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for dbe in dbs.flatten() {
             if !dbe.path().is_dir() { continue; }
             if let Ok(schemas) = std::fs::read_dir(dbe.path()) {
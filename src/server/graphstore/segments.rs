@@ -171,6 +171,10 @@ impl NodeDict {
         None
     }
 
+    /// Smallest node id above every id assigned so far.
+    #[inline]
+    pub fn next_id(&self) -> u64 { self.rev.len() as u64 }
+
     /// Apply an upsert of a node mapping. If `node_id` is None, this call is a no-op.
     pub fn upsert(&mut self, label: &str, key: &str, node_id: Option<u64>) {
         if let Some(id) = node_id {
@@ -0,0 +1,347 @@
+//! Session configuration parameters (GUCs).
+//!
+//! Every parameter a client can SHOW, SET or RESET is described once in [`GUCS`] with
+//! its type, built-in default and context. A session value is looked up in layers:
+//! `SET LOCAL` (until the end of the transaction), `SET` (until RESET or the end of
+//! the session), the database default from `ALTER DATABASE <db> SET ...`, then the
+//! built-in default. Values are kept per thread like the other session settings in
+//! `crate::system`; database defaults are stored in `<root>/.system/db_settings.json`.
+//!
+//! Parameters with an `apply` hook (work_mem, enable_result_cache, search_path) also drive engine
+//! state; the hook sees the effective value whenever a layer changes. The same registry
+//! backs SHOW ALL, `pg_catalog.pg_settings` and `pg_catalog.pg_db_role_setting`.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use polars::prelude::*;
+use serde_json::Value;
+
+use crate::storage::SharedStore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GucKind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    Enum(&'static [&'static str]),
+    /// A size such as `64MB` (plain numbers are kB)
+    Memory,
+    Text,
+}
+
+impl GucKind {
+    /// pg_settings.vartype
+    pub fn vartype(&self) -> &'static str {
+        match self {
+            GucKind::Bool => "bool",
+            GucKind::Integer { .. } | GucKind::Memory => "integer",
+            GucKind::Enum(_) => "enum",
+            GucKind::Text => "string",
+        }
+    }
+}
+
+/// Where a parameter can be changed: `Internal` ones are read-only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GucContext { Internal, User }
+
+impl GucContext {
+    pub fn as_str(&self) -> &'static str {
+        match self { GucContext::Internal => "internal", GucContext::User => "user" }
+    }
+}
+
+pub struct GucDef {
+    pub name: &'static str,
+    pub kind: GucKind,
+    default: &'static str,
+    pub context: GucContext,
+    pub category: &'static str,
+    pub description: &'static str,
+    /// Sent to pgwire clients as ParameterStatus at startup and when it changes
+    pub report: bool,
+    boot: Option<fn() -> String>,
+    apply: Option<fn(&str, &str) -> Result<bool>>,
+}
+
+impl GucDef {
+    const fn new(name: &'static str, kind: GucKind, default: &'static str, context: GucContext, category: &'static str, description: &'static str) -> Self {
+        Self { name, kind, default, context, category, description, report: false, boot: None, apply: None }
+    }
+    const fn report(mut self) -> Self { self.report = true; self }
+    const fn boot(mut self, f: fn() -> String) -> Self { self.boot = Some(f); self }
+    const fn apply(mut self, f: fn(&str, &str) -> Result<bool>) -> Self { self.apply = Some(f); self }
+
+    /// Built-in default value.
+    pub fn boot_value(&self) -> String { self.boot.map(|f| f()).unwrap_or_else(|| self.default.to_string()) }
+}
+
+const ISOLATION_LEVELS: &[&str] = &["serializable", "repeatable read", "read committed", "read uncommitted"];
+const MESSAGE_LEVELS: &[&str] = &["debug5", "debug4", "debug3", "debug2", "debug1", "log", "notice", "warning", "error"];
+const INTERVAL_STYLES: &[&str] = &["postgres", "postgres_verbose", "sql_standard", "iso_8601"];
+
+fn boot_work_mem() -> String { format!("{}MB", crate::config::current().limits.work_mem_mb) }
+
+use GucContext::{Internal, User};
+
+pub static GUCS: &[GucDef] = &[
+    GucDef::new("application_name", GucKind::Text, "", User, "Reporting and Logging", "Sets the application name to be reported in statistics and logs.").report(),
+    GucDef::new("client_encoding", GucKind::Text, "UTF8", User, "Client Connection Defaults", "Sets the client's character set encoding.").report(),
+    GucDef::new("client_min_messages", GucKind::Enum(MESSAGE_LEVELS), "notice", User, "Client Connection Defaults", "Sets the message levels that are sent to the client."),
+    GucDef::new("DateStyle", GucKind::Text, "ISO, MDY", User, "Client Connection Defaults", "Sets the display format for date and time values.").report(),
+    GucDef::new("default_transaction_isolation", GucKind::Enum(ISOLATION_LEVELS), "read committed", User, "Client Connection Defaults", "Sets the transaction isolation level of each new transaction."),
+    GucDef::new("default_transaction_read_only", GucKind::Bool, "off", User, "Client Connection Defaults", "Sets the default read-only status of new transactions.").report(),
+    GucDef::new("enable_result_cache", GucKind::Bool, "off", User, "Query Tuning", "Caches SELECT results until a table they read is written.").apply(crate::system::apply_result_cache_setting),
+    GucDef::new("extra_float_digits", GucKind::Integer { min: -15, max: 3 }, "3", User, "Client Connection Defaults", "Sets the number of digits displayed for floating-point values."),
+    GucDef::new("integer_datetimes", GucKind::Bool, "on", Internal, "Preset Options", "Shows whether datetimes are integer based.").report(),
+    GucDef::new("IntervalStyle", GucKind::Enum(INTERVAL_STYLES), "postgres", User, "Client Connection Defaults", "Sets the display format for interval values.").report(),
+    GucDef::new("is_superuser", GucKind::Bool, "off", Internal, "Preset Options", "Shows whether the current user is a superuser.").report(),
+    GucDef::new("max_identifier_length", GucKind::Integer { min: 63, max: 63 }, "63", Internal, "Preset Options", "Shows the maximum identifier length."),
+    GucDef::new("role", GucKind::Text, "none", User, "Client Connection Defaults", "Sets the current role (SET ROLE).").apply(crate::server::exec::exec_role::apply_role_setting),
+    GucDef::new("search_path", GucKind::Text, "\"$user\", public", User, "Client Connection Defaults", "Sets the schema search order for names that are not schema-qualified.").report().apply(crate::system::apply_search_path_setting),
+    GucDef::new("server_encoding", GucKind::Text, "UTF8", Internal, "Preset Options", "Shows the server (database) character set encoding.").report(),
+    GucDef::new("server_version", GucKind::Text, "14.0", Internal, "Preset Options", "Shows the server version.").report(),
+    GucDef::new("server_version_num", GucKind::Integer { min: 140000, max: 140000 }, "140000", Internal, "Preset Options", "Shows the server version as an integer.").report(),
+    GucDef::new("standard_conforming_strings", GucKind::Bool, "on", User, "Version and Platform Compatibility", "Causes '...' strings to treat backslashes literally.").report(),
+    GucDef::new("TimeZone", GucKind::Text, "UTC", User, "Client Connection Defaults", "Sets the time zone for displaying and interpreting time stamps.").report(),
+    GucDef::new("transaction_isolation", GucKind::Enum(ISOLATION_LEVELS), "read committed", User, "Client Connection Defaults", "Sets the current transaction's isolation level."),
+    GucDef::new("transaction_read_only", GucKind::Bool, "off", User, "Client Connection Defaults", "Sets the current transaction's read-only status."),
+    GucDef::new("work_mem", GucKind::Memory, "", User, "Resource Usage", "Sets the memory a sort or aggregation may use before spilling to disk.").boot(boot_work_mem).apply(crate::system::apply_memory_setting),
+];
+
+/// Registry entry for `name` (case-insensitive).
+pub fn lookup(name: &str) -> Option<&'static GucDef> {
+    let n = name.trim().trim_matches('"');
+    GUCS.iter().find(|g| g.name.eq_ignore_ascii_case(n))
+}
+
+fn unrecognized(name: &str) -> anyhow::Error { anyhow::anyhow!("unrecognized configuration parameter \"{}\"", name) }
+
+/// Validate `raw` for `def` and return its canonical form (`on`/`off`, lower-case enum labels, ...).
+pub fn check_value(def: &GucDef, raw: &str) -> Result<String> {
+    let v = raw.trim().trim_matches('\'').trim();
+    let invalid = || anyhow::anyhow!("invalid value for parameter \"{}\": \"{}\"", def.name, v);
+    match def.kind {
+        GucKind::Bool => match v.to_ascii_lowercase().as_str() {
+            "on" | "true" | "yes" | "1" => Ok("on".into()),
+            "off" | "false" | "no" | "0" => Ok("off".into()),
+            _ => Err(invalid()),
+        },
+        GucKind::Integer { min, max } => {
+            let n: i64 = v.parse().map_err(|_| invalid())?;
+            if n < min || n > max { bail!("{} is outside the valid range for parameter \"{}\" ({} .. {})", n, def.name, min, max); }
+            Ok(n.to_string())
+        }
+        GucKind::Enum(labels) => labels.iter().find(|l| l.eq_ignore_ascii_case(v)).map(|l| l.to_string()).ok_or_else(invalid),
+        GucKind::Memory => crate::system::parse_memory_size(v).map(|_| v.to_string()).ok_or_else(invalid),
+        GucKind::Text => Ok(v.to_string()),
+    }
+}
+
+// ----------------------------
+// Session layers
+// ----------------------------
+#[derive(Default)]
+struct Session {
+    values: HashMap<&'static str, String>,
+    local: HashMap<&'static str, String>,
+}
+
+thread_local! {
+    static TLS_GUC: RefCell<Session> = RefCell::new(Session::default());
+}
+
+/// Effective value of `def` for a session on `db`, with its pg_settings source.
+pub fn current_value(store: &SharedStore, db: &str, def: &'static GucDef) -> (String, &'static str) {
+    let set = TLS_GUC.with(|s| { let s = s.borrow(); s.local.get(def.name).or_else(|| s.values.get(def.name)).cloned() });
+    if let Some(v) = set { return (v, "session"); }
+    if let Some(v) = database_defaults(store, db).get(def.name) { return (v.clone(), "database"); }
+    (def.boot_value(), "default")
+}
+
+// Push the effective value of a hooked parameter into the engine setting it drives
+fn sync_hook(store: &SharedStore, db: &str, def: &'static GucDef) -> Result<()> {
+    let Some(apply) = def.apply else { return Ok(()) };
+    let (value, source) = current_value(store, db, def);
+    apply(def.name, if source == "default" { "default" } else { &value })?;
+    Ok(())
+}
+
+/// `SET [LOCAL] name = value`. Returns false when `name` is not a registered parameter;
+/// `value` DEFAULT resets the parameter.
+pub fn set(store: &SharedStore, name: &str, value: &str, local: bool) -> Result<bool> {
+    let Some(def) = lookup(name) else { return Ok(false) };
+    if value.trim().eq_ignore_ascii_case("default") {
+        reset(store, Some(name))?;
+        return Ok(true);
+    }
+    if def.context == GucContext::Internal { bail!("parameter \"{}\" cannot be changed", def.name); }
+    let canonical = check_value(def, value)?;
+    TLS_GUC.with(|s| {
+        let mut s = s.borrow_mut();
+        if local { s.local.insert(def.name, canonical); } else { s.local.remove(def.name); s.values.insert(def.name, canonical); }
+    });
+    sync_hook(store, &crate::system::get_current_database(), def)?;
+    Ok(true)
+}
+
+/// `RESET name` or, with None, `RESET ALL`: drop session values so database and built-in defaults apply again.
+pub fn reset(store: &SharedStore, name: Option<&str>) -> Result<()> {
+    let defs: Vec<&'static GucDef> = match name {
+        Some(n) => vec![lookup(n).ok_or_else(|| unrecognized(n))?],
+        None => GUCS.iter().filter(|g| g.context == GucContext::User).collect(),
+    };
+    let db = crate::system::get_current_database();
+    for def in defs {
+        if def.context == GucContext::Internal { bail!("parameter \"{}\" cannot be changed", def.name); }
+        TLS_GUC.with(|s| { let mut s = s.borrow_mut(); s.local.remove(def.name); s.values.remove(def.name); });
+        sync_hook(store, &db, def)?;
+    }
+    Ok(())
+}
+
+/// End of a transaction: `SET LOCAL` values are discarded.
+pub fn end_transaction(store: &SharedStore) {
+    let names: Vec<&'static str> = TLS_GUC.with(|s| s.borrow_mut().local.drain().map(|(k, _)| k).collect());
+    if names.is_empty() { return; }
+    let db = crate::system::get_current_database();
+    for def in names.into_iter().filter_map(lookup) { let _ = sync_hook(store, &db, def); }
+}
+
+/// Start a pgwire session on `db`: clear values left by an earlier session on this thread,
+/// apply the database defaults and the startup parameters (including `options=-c name=value`).
+/// Returns the reported parameters to send as ParameterStatus.
+pub fn start_session(store: &SharedStore, db: &str, startup: &HashMap<String, String>) -> Vec<(&'static str, String)> {
+    TLS_GUC.with(|s| *s.borrow_mut() = Session::default());
+    let mut pairs: Vec<(String, String)> = startup.iter()
+        .filter(|(k, _)| !matches!(k.as_str(), "user" | "database" | "options" | "replication"))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    if let Some(opts) = startup.get("options") {
+        let mut words = opts.split_whitespace();
+        while let Some(w) = words.next() {
+            let kv = if w == "-c" { words.next().unwrap_or("") } else { w.strip_prefix("-c").or_else(|| w.strip_prefix("--")).unwrap_or("") };
+            if let Some((k, v)) = kv.split_once('=') { pairs.push((k.replace('-', "_"), v.to_string())); }
+        }
+    }
+    for (k, v) in pairs {
+        let Some(def) = lookup(&k) else { continue };
+        if def.context == GucContext::Internal { continue; }
+        // A bad startup value is ignored rather than refusing the connection
+        if let Ok(canonical) = check_value(def, &v) { TLS_GUC.with(|s| s.borrow_mut().values.insert(def.name, canonical)); }
+    }
+    for def in GUCS { let _ = sync_hook(store, db, def); }
+    GUCS.iter().filter(|g| g.report).map(|g| (g.name, current_value(store, db, g).0)).collect()
+}
+
+/// SHOW name
+pub fn show(store: &SharedStore, name: &str) -> Result<Value> {
+    let def = lookup(name).ok_or_else(|| unrecognized(name))?;
+    let (value, _) = current_value(store, &crate::system::get_current_database(), def);
+    Ok(serde_json::json!([{ def.name: value }]))
+}
+
+/// SHOW ALL: name, setting and description of every parameter.
+pub fn show_all(store: &SharedStore) -> Value {
+    let db = crate::system::get_current_database();
+    let rows = GUCS.iter().map(|g| serde_json::json!({ "name": g.name, "setting": current_value(store, &db, g).0, "description": g.description })).collect();
+    Value::Array(rows)
+}
+
+/// pg_catalog.pg_settings for the current session.
+pub fn settings_df(store: &SharedStore) -> Result<DataFrame> {
+    let db = crate::system::get_current_database();
+    let values: Vec<(String, &str)> = GUCS.iter().map(|g| current_value(store, &db, g)).collect();
+    let range = |f: fn(i64, i64) -> i64| -> Vec<Option<String>> {
+        GUCS.iter().map(|g| match g.kind { GucKind::Integer { min, max } => Some(f(min, max).to_string()), _ => None }).collect()
+    };
+    let enumvals: Vec<Option<String>> = GUCS.iter().map(|g| match g.kind { GucKind::Enum(l) => Some(format!("{{{}}}", l.iter().map(|x| if x.contains(' ') { format!("\"{}\"", x) } else { x.to_string() }).collect::<Vec<_>>().join(","))), _ => None }).collect();
+    let db_defaults = database_defaults(store, &db);
+    let reset_val: Vec<String> = GUCS.iter().map(|g| db_defaults.get(g.name).cloned().unwrap_or_else(|| g.boot_value())).collect();
+    Ok(DataFrame::new(vec![
+        Series::new("name".into(), GUCS.iter().map(|g| g.name).collect::<Vec<_>>()).into(),
+        Series::new("setting".into(), values.iter().map(|(v, _)| v.as_str()).collect::<Vec<_>>()).into(),
+        Series::new("unit".into(), GUCS.iter().map(|g| if g.kind == GucKind::Memory { Some("kB") } else { None }).collect::<Vec<_>>()).into(),
+        Series::new("category".into(), GUCS.iter().map(|g| g.category).collect::<Vec<_>>()).into(),
+        Series::new("short_desc".into(), GUCS.iter().map(|g| g.description).collect::<Vec<_>>()).into(),
+        Series::new("context".into(), GUCS.iter().map(|g| g.context.as_str()).collect::<Vec<_>>()).into(),
+        Series::new("vartype".into(), GUCS.iter().map(|g| g.kind.vartype()).collect::<Vec<_>>()).into(),
+        Series::new("source".into(), values.iter().map(|(_, s)| *s).collect::<Vec<_>>()).into(),
+        Series::new("min_val".into(), range(|lo, _| lo)).into(),
+        Series::new("max_val".into(), range(|_, hi| hi)).into(),
+        Series::new("enumvals".into(), enumvals).into(),
+        Series::new("boot_val".into(), GUCS.iter().map(|g| g.boot_value()).collect::<Vec<_>>()).into(),
+        Series::new("reset_val".into(), reset_val).into(),
+        Series::new("pending_restart".into(), vec![false; GUCS.len()]).into(),
+    ])?)
+}
+
+// ----------------------------
+// Per-database defaults
+// ----------------------------
+type DbSettings = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Loaded defaults keyed by store root; read on first use, updated by ALTER DATABASE.
+static DB_SETTINGS: Lazy<RwLock<HashMap<PathBuf, DbSettings>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn settings_path(root: &Path) -> PathBuf { crate::system_paths::system_root(root).join("db_settings.json") }
+
+fn all_database_defaults(root: &Path) -> DbSettings {
+    if let Some(m) = DB_SETTINGS.read().get(root) { return m.clone(); }
+    let m: DbSettings = fs::read(settings_path(root)).ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default();
+    DB_SETTINGS.write().insert(root.to_path_buf(), m.clone());
+    m
+}
+
+/// Defaults set with `ALTER DATABASE db SET ...`, by parameter name.
+pub fn database_defaults(store: &SharedStore, db: &str) -> BTreeMap<String, String> {
+    all_database_defaults(&store.root_path()).get(db).cloned().unwrap_or_default()
+}
+
+/// `ALTER DATABASE db SET name = value` (Some) or `RESET name` (None); with no name, `RESET ALL`.
+pub fn set_database_default(store: &SharedStore, db: &str, name: Option<&str>, value: Option<&str>) -> Result<()> {
+    let root = store.root_path();
+    if !crate::storage::attach::database_dir(&root, db).is_dir() { bail!("database \"{}\" does not exist", db); }
+    let mut all = all_database_defaults(&root);
+    let entry = all.entry(db.to_string()).or_default();
+    match (name, value) {
+        (Some(n), v) => {
+            let def = lookup(n).ok_or_else(|| unrecognized(n))?;
+            if def.context == GucContext::Internal { bail!("parameter \"{}\" cannot be changed", def.name); }
+            match v {
+                Some(v) if !v.trim().eq_ignore_ascii_case("default") => { entry.insert(def.name.to_string(), check_value(def, v)?); }
+                _ => { entry.remove(def.name); }
+            }
+        }
+        (None, _) => entry.clear(),
+    }
+    if entry.is_empty() { all.remove(db); }
+    let p = settings_path(&root);
+    if let Some(parent) = p.parent() { fs::create_dir_all(parent)?; }
+    let tmp = p.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(&all)?)?;
+    fs::rename(&tmp, &p)?;
+    DB_SETTINGS.write().insert(root, all);
+    Ok(())
+}
+
+/// pg_catalog.pg_db_role_setting: one row per database with defaults (setrole 0 = all roles).
+pub fn db_role_setting_df(store: &SharedStore) -> Result<DataFrame> {
+    let all = all_database_defaults(&store.root_path());
+    let oids: Vec<i32> = all.keys().map(|db| crate::system_catalog::pg_catalog::pg_database::database_oid(db)).collect();
+    let config: Vec<String> = all.values()
+        .map(|m| format!("{{{}}}", m.iter().map(|(k, v)| format!("\"{}={}\"", k, v.replace('\\', "\\\\").replace('"', "\\\""))).collect::<Vec<_>>().join(",")))
+        .collect();
+    Ok(DataFrame::new(vec![
+        Series::new("setdatabase".into(), oids).into(),
+        Series::new("setrole".into(), vec![0i32; all.len()]).into(),
+        Series::new("setconfig".into(), config).into(),
+    ])?)
+}
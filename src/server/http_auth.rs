@@ -0,0 +1,214 @@
+//! Authentication and route permissions for every HTTP endpoint.
+//!
+//! `require_auth` runs in front of each handler and looks the request up in `HTTP_ROUTES` (a
+//! mounted route missing from it answers 500 instead of running unauthenticated):
+//! - public routes (`/`, `/login`, `/ping`, the replication routes, which check their own
+//!   token, and the filestore file routes, which accept presigned links) pass through;
+//! - otherwise the caller is resolved to a [`Principal`]: `Authorization: Bearer` with an API key
+//!   (`clk_...`) or an OIDC token, user/password credentials on agent routes (Basic,
+//!   `Token user:password`, `u`/`p` parameters), else the session cookie;
+//! - cookie sessions send `X-CSRF-Token` unless the route is declared without CSRF;
+//! - routes declaring a privilege check it against the database named by a path or query
+//!   parameter.
+//!
+//! Failures answer 401 (no or bad credentials) or 403 (CSRF, permission) with the v1 error body
+//! and are written as audit events (target `clarium::audit`). Handlers get the caller as an
+//! [`AuthContext`] request extension.
+
+use std::collections::HashMap;
+
+use axum::extract::{FromRequestParts, MatchedPath, Path, Query, RawPathParams, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use base64::Engine;
+use serde::Deserialize;
+
+use super::{openapi::RouteSpec, AppState, HTTP_ROUTES};
+use crate::error::AppError;
+use crate::identity::Principal;
+
+/// How a request proved who it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// Login session cookie
+    Session,
+    /// OIDC bearer token
+    Bearer,
+    /// API key sent as a bearer token
+    ApiKey,
+    /// User and password sent with the request (agent routes)
+    Password,
+}
+
+/// Caller of an authenticated route, inserted as a request extension.
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub principal: Principal,
+    /// Session id of cookie-authenticated requests
+    pub session_id: Option<String>,
+    pub method: AuthMethod,
+}
+
+impl AuthContext {
+    pub fn username(&self) -> &str { &self.principal.user_id }
+
+    /// Current (database, schema) of the session; server defaults without one.
+    pub async fn current_defaults(&self, state: &AppState) -> (String, String) {
+        let fallback = || (super::env_default_db(), super::env_default_schema());
+        match &self.session_id {
+            Some(sid) => state.session_defaults.read().await.get(sid).cloned().unwrap_or_else(fallback),
+            None => fallback(),
+        }
+    }
+}
+
+/// User/password of an agent request: `u`/`p` parameters, Basic or `Token user:password`.
+fn agent_credentials(headers: &HeaderMap, u: Option<&str>, p: Option<&str>) -> Option<(String, String)> {
+    if let (Some(u), Some(p)) = (u, p) { return Some((u.to_string(), p.to_string())); }
+    let v = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, rest) = v.trim().split_once(' ')?;
+    let pair = if scheme.eq_ignore_ascii_case("basic") {
+        String::from_utf8(base64::engine::general_purpose::STANDARD.decode(rest.trim()).ok()?).ok()?
+    } else if scheme.eq_ignore_ascii_case("token") {
+        rest.trim().to_string()
+    } else {
+        return None;
+    };
+    let (u, p) = pair.split_once(':')?;
+    Some((u.to_string(), p.to_string()))
+}
+
+/// Check agent credentials against local users, with the same lockout as interactive logins.
+fn verify_password(db_root: &str, username: &str, password: &str) -> bool {
+    if crate::identity::check_lockout(username).is_err() { return false; }
+    match crate::security::authenticate(db_root, username, password) {
+        Ok(true) => { crate::identity::record_login_success(username); true }
+        _ => { crate::identity::record_login_failure(username); false }
+    }
+}
+
+/// Resolve the caller. `Ok(None)` when the request carries no credentials, `Err` with the
+/// reason when it carries credentials that are not valid.
+pub(super) async fn authenticate(state: &AppState, headers: &HeaderMap, query: &HashMap<String, String>, agent: bool) -> Result<Option<AuthContext>, &'static str> {
+    let with = |principal: Principal, method: AuthMethod| Some(AuthContext { principal, session_id: None, method });
+    // A bearer token authenticates the request on its own; it never falls back to the cookie
+    if let Some(token) = super::bearer_token(headers) {
+        if token.starts_with(crate::identity::API_KEY_PREFIX) {
+            return crate::identity::resolve_api_key(&state.db_root, token).map(|p| with(p, AuthMethod::ApiKey)).ok_or("invalid api key");
+        }
+        if state.oidc.is_none() { return Err("bearer tokens are not enabled"); }
+        return super::bearer_principal(state, headers).await.map(|p| with(p, AuthMethod::Bearer)).ok_or("invalid bearer token");
+    }
+    if agent {
+        if let Some((user, password)) = agent_credentials(headers, query.get("u").map(|s| s.as_str()), query.get("p").map(|s| s.as_str())) {
+            if !verify_password(&state.db_root, &user, &password) { return Err("invalid credentials"); }
+            return Ok(with(Principal { user_id: user, ..Default::default() }, AuthMethod::Password));
+        }
+    }
+    let Some(sid) = super::get_sid_from_headers(headers) else { return Ok(None) };
+    match super::get_username_from_headers(state, headers).await {
+        Some(user) => Ok(Some(AuthContext { principal: Principal { user_id: user, ..Default::default() }, session_id: Some(sid), method: AuthMethod::Session })),
+        None => Err("session expired or unknown"),
+    }
+}
+
+/// Spec of a matched route. `get` routes also answer HEAD.
+pub(super) fn route_spec(method: &axum::http::Method, path: &str) -> Option<&'static RouteSpec> {
+    let method = if method == axum::http::Method::HEAD { "get" } else { method.as_str() };
+    HTTP_ROUTES.iter().find(|r| r.path == path && method.eq_ignore_ascii_case(r.method))
+}
+
+fn deny(app: AppError, spec: &RouteSpec, headers: &HeaderMap, user: Option<&str>) -> Response {
+    tracing::warn!(target: "clarium::audit", "http deny status={} route=\"{} {}\" user={} ip={} reason={}",
+        app.http_status(), spec.method.to_ascii_uppercase(), spec.path, user.unwrap_or("-"), super::extract_client_ip(Some(headers)), app.message());
+    (StatusCode::from_u16(app.http_status()).unwrap_or(StatusCode::FORBIDDEN), Json(app.to_json())).into_response()
+}
+
+/// Middleware in front of every route; see the module docs.
+pub(super) async fn require_auth(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let (mut parts, body) = req.into_parts();
+    let matched = parts.extensions.get::<MatchedPath>().map(|m| m.as_str().to_string());
+    let Some(spec) = matched.as_deref().and_then(|p| route_spec(&parts.method, p)) else {
+        // Every mounted route comes with a RouteSpec; one without is a wiring bug and is refused
+        // rather than served without authentication
+        tracing::error!(target: "clarium::audit", "http route has no RouteSpec: {} {}", parts.method, matched.as_deref().unwrap_or("-"));
+        let app = AppError::internal("route_not_registered", "route is not registered");
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(app.to_json())).into_response();
+    };
+    if !spec.auth { return next.run(Request::from_parts(parts, body)).await; }
+    let query: HashMap<String, String> = Query::try_from_uri(&parts.uri).map(|Query(q)| q).unwrap_or_default();
+    let auth = match authenticate(&state, &parts.headers, &query, spec.agent).await {
+        Ok(Some(auth)) => auth,
+        Ok(None) => return deny(AppError::auth("unauthorized", "authentication required"), spec, &parts.headers, None),
+        Err(reason) => return deny(AppError::auth("unauthorized", reason), spec, &parts.headers, None),
+    };
+    if spec.csrf && auth.method == AuthMethod::Session && !super::validate_csrf(&state, &parts.headers).await {
+        return deny(AppError::csrf("invalid_csrf", "invalid csrf token"), spec, &parts.headers, Some(auth.username()));
+    }
+    if let Some((kind, param)) = spec.privilege {
+        let path_params = RawPathParams::from_request_parts(&mut parts, &state).await.ok();
+        let database = path_params.as_ref()
+            .and_then(|ps| ps.iter().find(|(k, _)| *k == param).map(|(_, v)| v.to_string()))
+            .or_else(|| query.get(param).cloned());
+        if let Some(database) = database {
+            let database = crate::ident::normalize_identifier(&database);
+            if !crate::identity::check_command_allowed_async(&state.store, auth.username(), kind, Some(&database)).await {
+                return deny(AppError::permission("insufficient_privilege", "permission denied"), spec, &parts.headers, Some(auth.username()));
+            }
+        }
+    }
+    parts.extensions.insert(auth);
+    next.run(Request::from_parts(parts, body)).await
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ApiKeyPayload { name: String }
+
+/// Admins manage every user's keys.
+async fn is_admin(state: &AppState, auth: &AuthContext) -> bool {
+    auth.principal.is_admin()
+        || crate::identity::check_command_allowed_async(&state.store, auth.username(), crate::security::CommandKind::Other, None).await
+}
+
+fn api_key_error(e: anyhow::Error) -> Response {
+    tracing::error!("api key store failed: {e}");
+    let app = AppError::classify(&e);
+    (StatusCode::from_u16(app.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR), Json(app.to_json())).into_response()
+}
+
+/// POST /auth/api-keys {"name"} -> the new key, shown only in this response
+pub(super) async fn create_api_key(State(state): State<AppState>, Extension(auth): Extension<AuthContext>, Json(payload): Json<ApiKeyPayload>) -> Response {
+    // A key cannot mint further keys
+    if auth.method == AuthMethod::ApiKey {
+        return (StatusCode::FORBIDDEN, Json(AppError::permission("insufficient_privilege", "api keys cannot create api keys").to_json())).into_response();
+    }
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(AppError::user("invalid_parameter_value", "name is required").to_json())).into_response();
+    }
+    match crate::identity::create_api_key(&state.db_root, auth.username(), name) {
+        Ok((record, key)) => (StatusCode::CREATED, Json(serde_json::json!({"status":"ok","id": record.id,"name": record.name,"user": record.user,"key": key}))).into_response(),
+        Err(e) => api_key_error(e),
+    }
+}
+
+/// GET /auth/api-keys -> the caller's keys (every key for admins)
+pub(super) async fn list_api_keys(State(state): State<AppState>, Extension(auth): Extension<AuthContext>) -> Response {
+    let user = if is_admin(&state, &auth).await { None } else { Some(auth.username()) };
+    match crate::identity::list_api_keys(&state.db_root, user) {
+        Ok(keys) => (StatusCode::OK, Json(serde_json::json!({"status":"ok","keys": keys}))).into_response(),
+        Err(e) => api_key_error(e),
+    }
+}
+
+/// DELETE /auth/api-keys/{id}
+pub(super) async fn revoke_api_key(State(state): State<AppState>, Extension(auth): Extension<AuthContext>, Path(id): Path<String>) -> Response {
+    let user = if is_admin(&state, &auth).await { None } else { Some(auth.username()) };
+    match crate::identity::revoke_api_key(&state.db_root, &id, user) {
+        Ok(true) => (StatusCode::OK, Json(serde_json::json!({"status":"ok"}))).into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(AppError::not_found("undefined_object".to_string(), format!("api key \"{}\" does not exist", id)).to_json())).into_response(),
+        Err(e) => api_key_error(e),
+    }
+}
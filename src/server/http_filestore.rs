@@ -0,0 +1,553 @@
+//! `/v1/filestore/...`: filestore files over HTTP.
+//!
+//! - `GET /v1/filestore/{database}/{filestore}/{*path}` streams a file, a chunked file one chunk at
+//!   a time from the chunk holding the first requested byte. One `Range: bytes=` range
+//!   answers 206 with `Content-Range` (416 when it starts past the end); other units and
+//!   multi-range requests get the whole file. The `ETag` is the file's etag: `If-None-Match`
+//!   answers 304, and `If-Range` drops the range once the file has changed.
+//! - `PUT` on the same path creates the file (201) or replaces its content (200). `If-Match`
+//!   makes the replace conditional and `If-None-Match: *` the create; a failed condition is 412.
+//!   The body is read as a stream up to the route's body limit (`limits.ingest_max_body_mb`).
+//! - `POST /v1/filestore/presign` returns a link to GET or PUT one file without credentials until
+//!   it expires (at most `filestore.presign_max_ttl_secs`). Links are HMAC-SHA256 signed with
+//!   `CLARIUM_FILESTORE_SIGNING_KEY`, or a random key per process when unset (links then stop
+//!   working on restart), and act as the user who signed them.
+//!
+//! Each request needs SELECT (GET) or INSERT (PUT) on the database and passes the filestore's
+//! ACL (`security::check_acl`, backed by `sec::authorize`) as the acting user. The file routes
+//! are public in `HTTP_ROUTES` because presigned links carry no credentials; without a signature
+//! they authenticate like any other route, and cookie sessions send `X-CSRF-Token` on PUT.
+
+use std::collections::HashMap;
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use sha2::Sha256;
+
+use super::exec::filestore::{self as fs, security::ContentMeta, ACLAction, AclContext, AclUser, EffectiveConfig, FileMeta};
+use super::exec::{effective_for, make_acl_ctx};
+use super::http_auth::{self, AuthContext, AuthMethod};
+use super::{quota, replication, AppState};
+use crate::error::AppError;
+use crate::security::CommandKind;
+use crate::storage::SharedStore;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Query parameters of a presigned link.
+pub const LINK_PARAMS: &[(&str, &str)] = &[
+    ("expires", "presigned link: expiry, unix seconds"),
+    ("user", "presigned link: user the link acts as"),
+    ("sig", "presigned link: signature"),
+];
+
+/// Environment variable holding the key presigned links are signed with.
+pub const SIGNING_KEY_ENV: &str = "CLARIUM_FILESTORE_SIGNING_KEY";
+
+/// Lifetime of a presigned link when the request does not ask for one.
+const DEFAULT_LINK_SECS: u64 = 900;
+
+/// Size of the body slices a download is streamed in.
+const STREAM_CHUNK: usize = 64 * 1024;
+
+static SIGNING_KEY: Lazy<Vec<u8>> = Lazy::new(|| match std::env::var(SIGNING_KEY_ENV) {
+    Ok(k) if !k.trim().is_empty() => k.trim().as_bytes().to_vec(),
+    _ => {
+        let mut k = vec![0u8; 32];
+        if getrandom::getrandom(&mut k).is_err() { tracing::error!("no randomness for the filestore signing key"); }
+        k
+    }
+});
+
+/// File named by a request path.
+struct FileTarget {
+    database: String,
+    filestore: String,
+    path: String,
+}
+
+impl FileTarget {
+    fn new(database: &str, filestore: &str, path: &str) -> Self {
+        Self {
+            database: crate::ident::normalize_identifier(database),
+            filestore: crate::ident::normalize_identifier(filestore),
+            path: path.trim_start_matches('/').to_string(),
+        }
+    }
+}
+
+fn error(app: AppError) -> Response {
+    let status = match app.code_str() {
+        "configuration_limit_exceeded" => StatusCode::PAYLOAD_TOO_LARGE,
+        _ => StatusCode::from_u16(app.http_status()).unwrap_or(StatusCode::BAD_REQUEST),
+    };
+    (status, Json(app.to_json())).into_response()
+}
+
+/// 401/403 written as an audit event, like the denials of `http_auth`.
+fn deny(app: AppError, method: &str, t: &FileTarget, headers: &HeaderMap, user: Option<&str>) -> Response {
+    tracing::warn!(target: "clarium::audit", "http deny status={} route=\"{} /v1/filestore/{}/{}/{}\" user={} ip={} reason={}",
+        app.http_status(), method, t.database, t.filestore, t.path, user.unwrap_or("-"), super::extract_client_ip(Some(headers)), app.message());
+    error(app)
+}
+
+fn precondition_failed() -> Response {
+    (StatusCode::PRECONDITION_FAILED, Json(serde_json::json!({"status":"error","code":"precondition_failed","message":"precondition failed"}))).into_response()
+}
+
+fn acl_user(auth: &AuthContext, headers: &HeaderMap) -> AclUser {
+    AclUser { id: auth.username().to_string(), roles: auth.principal.roles.clone(), ip: Some(super::extract_client_ip(Some(headers))) }
+}
+
+/// Signature of a link letting `user` run `method` on one file until `expires` (unix seconds).
+/// Fields are length-prefixed so that no two links share a message.
+fn signature(key: &[u8], method: &str, t: &FileTarget, user: &str, expires: i64) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("hmac accepts any key length");
+    let expires = expires.to_string();
+    for field in [method, t.database.as_str(), t.filestore.as_str(), t.path.as_str(), user, expires.as_str()] {
+        mac.update(&(field.len() as u64).to_be_bytes());
+        mac.update(field.as_bytes());
+    }
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Check the presigned-link parameters of a request at time `now`; the user the link acts as.
+fn verify_link(key: &[u8], method: &str, t: &FileTarget, query: &HashMap<String, String>, now: i64) -> Result<String, &'static str> {
+    let (Some(user), Some(expires), Some(sig)) = (query.get("user"), query.get("expires"), query.get("sig")) else {
+        return Err("incomplete presigned link");
+    };
+    let expires: i64 = expires.parse().map_err(|_| "invalid presigned link")?;
+    if expires < now { return Err("presigned link expired"); }
+    let expected = signature(key, method, t, user, expires);
+    // Constant-time comparison
+    let same = expected.len() == sig.len() && expected.bytes().zip(sig.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+    if !same { return Err("invalid presigned link signature"); }
+    Ok(user.clone())
+}
+
+/// The user a file request acts as: the signer of a presigned link, else the authenticated caller.
+async fn caller(state: &AppState, headers: &HeaderMap, query: &HashMap<String, String>, method: &str, t: &FileTarget) -> Result<AclUser, Response> {
+    if query.contains_key("sig") {
+        return verify_link(&SIGNING_KEY, method, t, query, chrono::Utc::now().timestamp())
+            .map(|user| AclUser { id: user, roles: vec![], ip: Some(super::extract_client_ip(Some(headers))) })
+            .map_err(|reason| deny(AppError::auth("unauthorized", reason), method, t, headers, None));
+    }
+    let auth = match http_auth::authenticate(state, headers, query, false).await {
+        Ok(Some(auth)) => auth,
+        Ok(None) => return Err(deny(AppError::auth("unauthorized", "authentication required"), method, t, headers, None)),
+        Err(reason) => return Err(deny(AppError::auth("unauthorized", reason), method, t, headers, None)),
+    };
+    if method == "PUT" && auth.method == AuthMethod::Session && !super::validate_csrf(state, headers).await {
+        return Err(deny(AppError::csrf("invalid_csrf", "invalid csrf token"), method, t, headers, Some(auth.username())));
+    }
+    Ok(acl_user(&auth, headers))
+}
+
+/// Database privilege, existing filestore, then the filestore ACL for `action` on the file.
+/// Returns the filestore config and ACL context the operation runs with.
+async fn authorize(
+    state: &AppState,
+    headers: &HeaderMap,
+    method: &str,
+    t: &FileTarget,
+    user: &AclUser,
+    action: ACLAction,
+    content: ContentMeta,
+) -> Result<(EffectiveConfig, AclContext), Response> {
+    let kind = if action == ACLAction::Read { CommandKind::Select } else { CommandKind::Insert };
+    if !crate::identity::check_command_allowed_async(&state.store, &user.id, kind, Some(&t.database)).await {
+        return Err(deny(AppError::permission("insufficient_privilege", "permission denied"), method, t, headers, Some(&user.id)));
+    }
+    if let Err(e) = fs::validate_logical_path(&t.path) {
+        return Err(error(AppError::user("invalid_parameter_value".to_string(), e.to_string())));
+    }
+    match fs::load_filestore_entry(&state.store, &t.database, &t.filestore) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(error(AppError::not_found("undefined_object".to_string(), format!("filestore \"{}\" does not exist in database \"{}\"", t.filestore, t.database)))),
+        Err(e) => return Err(error(AppError::classify(&e))),
+    }
+    let eff = effective_for(&state.store, &t.database, &t.filestore).map_err(|e| error(AppError::classify(&e)))?;
+    let mut ctx = make_acl_ctx(&state.store, &t.database, &t.filestore);
+    ctx.content_meta = Some(content);
+    let decision = fs::check_acl(&eff, user, action, &fs::normalize_nfc(&t.path), None, &ctx, &t.filestore).await;
+    if !decision.allow {
+        let reason = decision.reason.unwrap_or_else(|| "acl_denied".to_string());
+        return Err(deny(AppError::permission("insufficient_privilege".to_string(), format!("filestore access denied: {}", reason)), method, t, headers, Some(&user.id)));
+    }
+    ctx.content_meta = None;
+    Ok((eff, ctx))
+}
+
+/// Part of a file a GET answers with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteRange {
+    Full,
+    /// First and last byte offsets, inclusive
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Interpret a `Range` header against a file of `len` bytes. Only a single `bytes=` range is
+/// served; anything else (other units, several ranges, bad syntax) gets the whole file.
+fn parse_range(header: &str, len: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else { return ByteRange::Full };
+    if spec.contains(',') { return ByteRange::Full; }
+    let Some((start, end)) = spec.split_once('-') else { return ByteRange::Full };
+    let (start, end) = (start.trim(), end.trim());
+    match (start.parse::<u64>().ok(), end.parse::<u64>().ok()) {
+        // bytes=-N: the last N bytes
+        (None, Some(n)) if start.is_empty() => {
+            if n == 0 || len == 0 { ByteRange::Unsatisfiable } else { ByteRange::Partial(len.saturating_sub(n), len - 1) }
+        }
+        (Some(s), None) if end.is_empty() => if s < len { ByteRange::Partial(s, len - 1) } else { ByteRange::Unsatisfiable },
+        (Some(s), Some(e)) if s <= e => if s < len { ByteRange::Partial(s, e.min(len - 1)) } else { ByteRange::Unsatisfiable },
+        _ => ByteRange::Full,
+    }
+}
+
+/// Whether an `If-Match` / `If-None-Match` / `If-Range` value (`*`, `"etag"`, `W/"etag"`, comma
+/// separated) names `etag`.
+fn etag_listed(value: &str, etag: &str) -> bool {
+    value.split(',').map(str::trim).any(|t| t == "*" || t.trim_start_matches("W/").trim_matches('"') == etag)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: header::HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn quoted(etag: &str) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", etag)).unwrap_or_else(|_| HeaderValue::from_static("\"\""))
+}
+
+fn http_date(unix_secs: i64) -> Option<HeaderValue> {
+    let t = chrono::DateTime::from_timestamp(unix_secs, 0)?;
+    HeaderValue::from_str(&t.format("%a, %d %b %Y %H:%M:%S GMT").to_string()).ok()
+}
+
+/// Bytes `start..=end` of a file as a body stream. A chunked file is read one chunk at a time,
+/// starting at the chunk holding `start`, so a download never holds the whole file; a file
+/// stored in one piece is held once and sent in slices.
+fn content_stream(store: &SharedStore, t: &FileTarget, meta: &FileMeta, start: u64, end: u64) -> Result<Body, AppError> {
+    if let Some(chunking) = &meta.chunking {
+        let wanted: Vec<fs::ChunkRef> = chunking.chunks.iter().filter(|c| c.off + c.len as u64 > start && c.off <= end).cloned().collect();
+        let (store, database, filestore) = (store.clone(), t.database.clone(), t.filestore.clone());
+        let chunks = futures_util::stream::iter(wanted).then(move |c| {
+            let (store, database, filestore) = (store.clone(), database.clone(), filestore.clone());
+            async move {
+                // Window of this chunk inside the requested range
+                let from = start.saturating_sub(c.off) as usize;
+                let to = (end + 1 - c.off).min(c.len as u64) as usize;
+                let oid = c.oid.clone();
+                let bytes = tokio::task::spawn_blocking(move || fs::read_chunk(&store, &database, &filestore, &c))
+                    .await
+                    .map_err(std::io::Error::other)?
+                    .map_err(std::io::Error::other)?
+                    .ok_or_else(|| std::io::Error::other(format!("chunk missing: {}", oid)))?;
+                if bytes.len() < to { return Err(std::io::Error::other(format!("chunk truncated: {}", oid))); }
+                Ok::<_, std::io::Error>(Bytes::from(bytes).slice(from..to))
+            }
+        });
+        return Ok(Body::from_stream(chunks));
+    }
+    let data = match fs::get_file_bytes(store, &t.database, &t.filestore, meta) {
+        Ok(Some(b)) => Bytes::from(b),
+        Ok(None) => return Err(AppError::internal("data_corrupted".to_string(), format!("file content missing in filestore '{}': {}", t.filestore, t.path))),
+        Err(e) => return Err(AppError::classify(&e)),
+    };
+    if data.len() as u64 != meta.size {
+        return Err(AppError::internal("data_corrupted".to_string(), format!("file content of {} bytes in filestore '{}' does not match its size {}: {}", data.len(), t.filestore, meta.size, t.path)));
+    }
+    let data = data.slice(start as usize..=end as usize);
+    let len = data.len();
+    let slices = futures_util::stream::iter((0..len).step_by(STREAM_CHUNK).map(move |off| {
+        Ok::<_, std::io::Error>(data.slice(off..(off + STREAM_CHUNK).min(len)))
+    }));
+    Ok(Body::from_stream(slices))
+}
+
+/// Response with a `len`-byte body and the file's validators.
+fn file_response(status: StatusCode, meta: &FileMeta, body: Body, len: u64) -> Response {
+    let mut resp = Response::new(body);
+    *resp.status_mut() = status;
+    let h = resp.headers_mut();
+    let content_type = meta.content_type.as_deref().and_then(|ct| HeaderValue::from_str(ct).ok());
+    h.insert(header::CONTENT_TYPE, content_type.unwrap_or_else(|| HeaderValue::from_static("application/octet-stream")));
+    h.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+    h.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    h.insert(header::ETAG, quoted(&meta.etag));
+    if let Some(date) = http_date(meta.updated_at) { h.insert(header::LAST_MODIFIED, date); }
+    resp
+}
+
+/// GET /v1/filestore/{database}/{filestore}/{*path} -> file content
+pub(super) async fn download(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((database, filestore, path)): Path<(String, String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let t = FileTarget::new(&database, &filestore, &path);
+    let user = match caller(&state, &headers, &query, "GET", &t).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    let meta = match fs::get_file_meta(&state.store, &t.database, &t.filestore, &t.path) {
+        Ok(Some(m)) if !m.deleted => Some(m),
+        Ok(_) => None,
+        Err(e) => return error(AppError::user("invalid_parameter_value".to_string(), e.to_string())),
+    };
+    let content = ContentMeta { size_bytes: meta.as_ref().map(|m| m.size), media_type: meta.as_ref().and_then(|m| m.content_type.clone()) };
+    if let Err(resp) = authorize(&state, &headers, "GET", &t, &user, ACLAction::Read, content).await { return resp; }
+    let Some(meta) = meta else {
+        return error(AppError::not_found("undefined_file".to_string(), format!("file not found in filestore '{}': {}", t.filestore, t.path)));
+    };
+    if let Some(sig) = meta.quarantine_signature() {
+        return error(AppError::permission("file_quarantined".to_string(), format!("file in filestore '{}' is quarantined by content scan: {} ({})", t.filestore, t.path, sig)));
+    }
+    if header_str(&headers, header::IF_NONE_MATCH).is_some_and(|v| etag_listed(v, &meta.etag)) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, quoted(&meta.etag))]).into_response();
+    }
+    let len = meta.size;
+    // If-Range: serve the range only while the file still has the given etag
+    let range = match header_str(&headers, header::RANGE) {
+        Some(r) if header_str(&headers, header::IF_RANGE).is_none_or(|v| etag_listed(v, &meta.etag)) => parse_range(r, len),
+        _ => ByteRange::Full,
+    };
+    let (status, start, end) = match range {
+        ByteRange::Full => (StatusCode::OK, 0, len.saturating_sub(1)),
+        ByteRange::Partial(start, end) => (StatusCode::PARTIAL_CONTENT, start, end),
+        ByteRange::Unsatisfiable => {
+            let range = HeaderValue::from_str(&format!("bytes */{}", len)).unwrap_or_else(|_| HeaderValue::from_static("bytes */0"));
+            return (StatusCode::RANGE_NOT_SATISFIABLE, [(header::CONTENT_RANGE, range)]).into_response();
+        }
+    };
+    // An empty file has no byte range to read
+    let body = if len == 0 { Ok(Body::empty()) } else { content_stream(&state.store, &t, &meta, start, end) };
+    let body = match body {
+        Ok(b) => b,
+        Err(app) => return error(app),
+    };
+    let mut resp = file_response(status, &meta, body, if len == 0 { 0 } else { end - start + 1 });
+    if status == StatusCode::PARTIAL_CONTENT {
+        if let Ok(v) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, len)) {
+            resp.headers_mut().insert(header::CONTENT_RANGE, v);
+        }
+    }
+    resp
+}
+
+/// Largest upload the PUT route accepts, in bytes (`http.body_limits`, else `limits.ingest_max_body_mb`).
+fn upload_limit() -> usize {
+    let cfg = crate::config::current();
+    super::HTTP_ROUTES.iter()
+        .find(|r| r.method == "put" && r.path.starts_with("/v1/filestore/"))
+        .map(|spec| super::http_layers::body_limit(spec, &cfg.http, cfg.limits.ingest_max_body_mb))
+        .unwrap_or_else(|| (cfg.limits.ingest_max_body_mb as usize).saturating_mul(1024 * 1024))
+}
+
+/// Collect a streamed request body, failing once it passes `limit` bytes.
+async fn read_body(body: Body, limit: usize) -> Result<Vec<u8>, AppError> {
+    let mut stream = body.into_data_stream();
+    let mut out = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| AppError::io("io_error".to_string(), format!("failed to read request body: {}", e)))?;
+        if out.len() + chunk.len() > limit {
+            return Err(AppError::user("configuration_limit_exceeded".to_string(), format!("request body exceeds the upload limit ({} bytes)", limit)));
+        }
+        out.extend_from_slice(&chunk);
+    }
+    Ok(out)
+}
+
+/// PUT /v1/filestore/{database}/{filestore}/{*path} -> create (201) or replace (200) a file
+pub(super) async fn upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((database, filestore, path)): Path<(String, String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+    body: Body,
+) -> Response {
+    let t = FileTarget::new(&database, &filestore, &path);
+    let user = match caller(&state, &headers, &query, "PUT", &t).await {
+        Ok(u) => u,
+        Err(resp) => return resp,
+    };
+    if let Err(e) = replication::ensure_writable() {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response();
+    }
+    let bytes = match read_body(body, upload_limit()).await {
+        Ok(b) => b,
+        Err(app) => return error(app),
+    };
+    let content_type = header_str(&headers, header::CONTENT_TYPE).map(str::to_string);
+    let content = ContentMeta { size_bytes: Some(bytes.len() as u64), media_type: content_type.clone() };
+    let (eff, ctx) = match authorize(&state, &headers, "PUT", &t, &user, ACLAction::Write, content).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let current = match fs::get_file_meta(&state.store, &t.database, &t.filestore, &t.path) {
+        Ok(m) => m.filter(|m| !m.deleted),
+        Err(e) => return error(AppError::user("invalid_parameter_value".to_string(), e.to_string())),
+    };
+    let if_match = header_str(&headers, header::IF_MATCH);
+    let if_none_match = header_str(&headers, header::IF_NONE_MATCH);
+    let cur_etag = current.as_ref().map(|m| m.etag.as_str());
+    if if_match.is_some_and(|v| !cur_etag.is_some_and(|e| etag_listed(v, e)))
+        || if_none_match.is_some_and(|v| cur_etag.is_some_and(|e| etag_listed(v, e))) {
+        return precondition_failed();
+    }
+    if let Err(e) = quota::charge_ingest_as(&user.id, bytes.len()) {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(AppError::classify(&e).to_json())).into_response();
+    }
+    let store = &state.store;
+    let written = match &current {
+        Some(cur) => fs::update_from_bytes(store, &t.database, &t.filestore, &t.path, &cur.etag, &bytes, content_type.as_deref(), None, &user, &eff, &ctx).await
+            .map(|m| (StatusCode::OK, m)),
+        None => fs::ingest_from_bytes(store, &t.database, &t.filestore, &t.path, &bytes, content_type.as_deref(), None, &user, &eff, &ctx).await
+            .map(|m| (StatusCode::CREATED, m)),
+    };
+    match written {
+        Ok((status, meta)) => (status, [(header::ETAG, quoted(&meta.etag))], Json(serde_json::json!({
+            "status": "ok", "path": meta.logical_path, "etag": meta.etag, "version": meta.version, "size": meta.size,
+        }))).into_response(),
+        // Another writer replaced the file between the check and the update
+        Err(e) if e.to_string() == "precondition_failed" => precondition_failed(),
+        // Another user has the file checked out (LOCK FILE)
+        Err(e) if e.to_string().starts_with("file_locked:") => (StatusCode::LOCKED, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response(),
+        Err(e) => error(AppError::user("invalid_parameter_value".to_string(), e.to_string())),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct PresignPayload {
+    /// Defaults to the session's current database
+    database: Option<String>,
+    filestore: String,
+    path: String,
+    /// GET (default) or PUT
+    method: Option<String>,
+    /// Seconds the link stays valid
+    expires_in: Option<u64>,
+}
+
+/// Relative URL of a file, each path segment percent-encoded.
+fn file_url(t: &FileTarget) -> String {
+    let path: Vec<String> = t.path.split('/').map(|s| urlencoding::encode(s).into_owned()).collect();
+    format!("/v1/filestore/{}/{}/{}", urlencoding::encode(&t.database), urlencoding::encode(&t.filestore), path.join("/"))
+}
+
+/// POST /v1/filestore/presign {"filestore","path","method","expires_in"} -> a link acting as the caller
+pub(super) async fn presign(State(state): State<AppState>, Extension(auth): Extension<AuthContext>, headers: HeaderMap, Json(payload): Json<PresignPayload>) -> Response {
+    let method = payload.method.as_deref().unwrap_or("GET").trim().to_ascii_uppercase();
+    let action = match method.as_str() {
+        "GET" => ACLAction::Read,
+        "PUT" => ACLAction::Write,
+        _ => return error(AppError::user("invalid_parameter_value".to_string(), format!("method must be GET or PUT, got '{}'", method))),
+    };
+    let max_ttl = crate::config::current().filestore.presign_max_ttl_secs;
+    let ttl = payload.expires_in.unwrap_or_else(|| DEFAULT_LINK_SECS.min(max_ttl));
+    if ttl == 0 || ttl > max_ttl {
+        return error(AppError::user("invalid_parameter_value".to_string(), format!("expires_in must be between 1 and {} seconds", max_ttl)));
+    }
+    let database = match payload.database {
+        Some(db) => db,
+        None => auth.current_defaults(&state).await.0,
+    };
+    let t = FileTarget::new(&database, &payload.filestore, &payload.path);
+    let user = acl_user(&auth, &headers);
+    // The signer must be allowed to do what the link allows
+    if let Err(resp) = authorize(&state, &headers, &method, &t, &user, action, ContentMeta::default()).await { return resp; }
+    let expires = chrono::Utc::now().timestamp() + ttl as i64;
+    let sig = signature(&SIGNING_KEY, &method, &t, &user.id, expires);
+    let url = format!("{}?expires={}&user={}&sig={}", file_url(&t), expires, urlencoding::encode(&user.id), sig);
+    (StatusCode::OK, Json(serde_json::json!({"status":"ok","method": method,"url": url,"expires": expires}))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(parse_range("bytes=900-", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(parse_range("bytes=-100", 1000), ByteRange::Partial(900, 999));
+        assert_eq!(parse_range("bytes=-5000", 1000), ByteRange::Partial(0, 999));
+        assert_eq!(parse_range("bytes=990-2000", 1000), ByteRange::Partial(990, 999));
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), ByteRange::Unsatisfiable);
+        // Ignored: multi-range, other units, malformed
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=9-3", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=x-3", 1000), ByteRange::Full);
+    }
+
+    #[tokio::test]
+    async fn streams_ranges_chunk_by_chunk() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = SharedStore::new(tmp.path()).unwrap();
+        let cfg = fs::FilestoreConfig { security_check_enabled: false, ..Default::default() };
+        let eff = EffectiveConfig::from_layers(&fs::GlobalFilestoreConfig::default(), &cfg, None);
+        let user = AclUser { id: "u".into(), roles: vec![], ip: None };
+        let mut x = 7u64;
+        let data: Vec<u8> = (0..1_500_000).map(|_| { x ^= x << 13; x ^= x >> 7; x ^= x << 17; x as u8 }).collect();
+        let meta = fs::ingest_from_bytes(&store, "clarium", "media", "big.bin", &data, None, None, &user, &eff, &AclContext::default()).await.unwrap();
+        let chunks = meta.chunking.as_ref().expect("chunked").chunks.clone();
+        assert!(chunks.len() > 2);
+        let t = FileTarget::new("clarium", "media", "big.bin");
+        let read = |start: u64, end: u64| {
+            let body = content_stream(&store, &t, &meta, start, end).unwrap();
+            async move { axum::body::to_bytes(body, usize::MAX).await.unwrap() }
+        };
+        assert_eq!(read(0, data.len() as u64 - 1).await, data);
+        // A range across a chunk boundary, and one ending at the last byte
+        let boundary = chunks[1].off;
+        assert_eq!(read(boundary - 10, boundary + 9).await, data[boundary as usize - 10..boundary as usize + 10]);
+        let tail = chunks.last().unwrap().off + 1;
+        assert_eq!(read(tail, data.len() as u64 - 1).await, data[tail as usize..]);
+
+        // Chunks outside the range are never read: dropping the first one leaves a later range intact
+        store.kv_store("clarium", "media").delete(&fs::Keys::chunk_oid("clarium", "media", &chunks[0].oid));
+        assert_eq!(read(tail, data.len() as u64 - 1).await, data[tail as usize..]);
+        let body = content_stream(&store, &t, &meta, 0, 99).unwrap();
+        assert!(axum::body::to_bytes(body, usize::MAX).await.is_err());
+    }
+
+    #[test]
+    fn matches_etag_lists() {
+        assert!(etag_listed("\"abc\"", "abc"));
+        assert!(etag_listed("\"x\", W/\"abc\"", "abc"));
+        assert!(etag_listed("*", "abc"));
+        assert!(!etag_listed("\"abd\"", "abc"));
+    }
+
+    #[test]
+    fn presigned_links_are_bound_to_file_method_and_time() {
+        let key = b"test-key";
+        let t = FileTarget::new("clarium", "docs", "a/b.txt");
+        let sig = signature(key, "GET", &t, "alice", 2_000);
+        let q = |user: &str, expires: i64, sig: &str| HashMap::from([
+            ("user".to_string(), user.to_string()), ("expires".to_string(), expires.to_string()), ("sig".to_string(), sig.to_string()),
+        ]);
+        assert_eq!(verify_link(key, "GET", &t, &q("alice", 2_000, &sig), 1_000), Ok("alice".to_string()));
+        assert_eq!(verify_link(key, "GET", &t, &q("alice", 2_000, &sig), 2_001), Err("presigned link expired"));
+        assert!(verify_link(key, "PUT", &t, &q("alice", 2_000, &sig), 1_000).is_err());
+        assert!(verify_link(key, "GET", &t, &q("bob", 2_000, &sig), 1_000).is_err());
+        assert!(verify_link(key, "GET", &t, &q("alice", 3_000, &sig), 1_000).is_err());
+        assert!(verify_link(key, "GET", &FileTarget::new("clarium", "docs", "a/c.txt"), &q("alice", 2_000, &sig), 1_000).is_err());
+        assert!(verify_link(b"other-key", "GET", &t, &q("alice", 2_000, &sig), 1_000).is_err());
+        // Moving text between fields changes the signature
+        let shifted = FileTarget::new("clarium", "docsa", "b.txt");
+        assert_ne!(signature(key, "GET", &shifted, "alice", 2_000), sig);
+        assert_eq!(file_url(&FileTarget::new("clarium", "docs", "a b/ü.txt")), "/v1/filestore/clarium/docs/a%20b/%C3%BC.txt");
+    }
+}
@@ -0,0 +1,129 @@
+//! `POST /v1/kv/{database}/{store}/batch`: the HTTP form of `MULTI ... EXEC`.
+//!
+//! The body lists writes to keys of one store and, optionally, the versions (from READ KEY) the
+//! caller expects keys to be at. The batch applies all together or not at all: a key at another
+//! version answers 409 (`serialization_failure`) and a write that fails (e.g. LPUSH on a string)
+//! leaves every key as it was. `value` of a set is stored as a string, an integer or JSON after
+//! its JSON type.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::exec::exec_keys::write_outcome_json;
+use super::{http_auth::AuthContext, quota, replication, AppState};
+use crate::error::AppError;
+use crate::storage::{KvValue, KvWrite};
+
+#[derive(Debug, Deserialize)]
+pub(super) struct KvBatchPayload {
+    #[serde(default)]
+    expect: BTreeMap<String, u64>,
+    writes: Vec<KvBatchOp>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum KvBatchOp {
+    Set { key: String, value: Value, ttl_ms: Option<u64>, reset_on_access: Option<bool> },
+    Delete { key: String },
+    Lpush { key: String, values: Vec<String> },
+    Rpush { key: String, values: Vec<String> },
+    Lpop { key: String },
+    Rpop { key: String },
+    Sadd { key: String, members: Vec<String> },
+    Srem { key: String, members: Vec<String> },
+    Hset { key: String, fields: BTreeMap<String, String> },
+    Hdel { key: String, fields: Vec<String> },
+}
+
+impl KvBatchOp {
+    // The store write and, for sets, the type reported back
+    fn into_write(self) -> (KvWrite, Option<&'static str>) {
+        match self {
+            KvBatchOp::Set { key, value, ttl_ms, reset_on_access } => {
+                let (value, kind) = match value {
+                    Value::String(s) => (KvValue::Str(s), "string"),
+                    Value::Number(n) if n.is_i64() => (KvValue::Int(n.as_i64().unwrap_or_default()), "int"),
+                    other => (KvValue::Json(other), "json"),
+                };
+                let ttl = ttl_ms.filter(|ms| *ms > 0).map(Duration::from_millis);
+                (KvWrite::Set { key, value, ttl, reset_on_access: Some(reset_on_access.unwrap_or(false)) }, Some(kind))
+            }
+            KvBatchOp::Delete { key } => (KvWrite::Delete { key }, None),
+            KvBatchOp::Lpush { key, values } => (KvWrite::Push { key, values, front: true }, None),
+            KvBatchOp::Rpush { key, values } => (KvWrite::Push { key, values, front: false }, None),
+            KvBatchOp::Lpop { key } => (KvWrite::Pop { key, front: true }, None),
+            KvBatchOp::Rpop { key } => (KvWrite::Pop { key, front: false }, None),
+            KvBatchOp::Sadd { key, members } => (KvWrite::SetAdd { key, members }, None),
+            KvBatchOp::Srem { key, members } => (KvWrite::SetRemove { key, members }, None),
+            KvBatchOp::Hset { key, fields } => (KvWrite::HashSet { key, fields: fields.into_iter().collect() }, None),
+            KvBatchOp::Hdel { key, fields } => (KvWrite::HashDelete { key, fields }, None),
+        }
+    }
+}
+
+fn error_response(e: &anyhow::Error) -> Response {
+    let app = AppError::classify(e);
+    (StatusCode::from_u16(app.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR), Json(app.to_json())).into_response()
+}
+
+/// POST /v1/kv/{database}/{store}/batch {"expect": {key: version}, "writes": [{"op", "key", ...}]}
+pub(super) async fn batch(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path((database, store)): Path<(String, String)>,
+    Json(payload): Json<KvBatchPayload>,
+) -> Response {
+    if let Err(e) = replication::ensure_writable() {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response();
+    }
+    if let Err(e) = quota::admit_query(auth.username()).await {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(AppError::classify(&e).to_json())).into_response();
+    }
+    let database = crate::ident::normalize_identifier(&database);
+    let (writes, kinds): (Vec<KvWrite>, Vec<Option<&'static str>>) = payload.writes.into_iter().map(KvBatchOp::into_write).unzip();
+    let expect: Vec<(String, u64)> = payload.expect.into_iter().collect();
+    match state.store.kv_store(&database, &store).apply_batch(&expect, &writes) {
+        Ok(res) => {
+            let results: Vec<Value> = res.outcomes.iter().zip(kinds).map(|(o, k)| write_outcome_json(o, k)).collect();
+            (StatusCode::OK, Json(serde_json::json!({"status":"ok","results": results,"versions": res.versions}))).into_response()
+        }
+        Err(e) => error_response(&e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_body_maps_ops_and_value_types() {
+        let body = serde_json::json!({
+            "expect": {"a": 3},
+            "writes": [
+                {"op": "set", "key": "a", "value": "x"},
+                {"op": "set", "key": "n", "value": 7, "ttl_ms": 1000},
+                {"op": "set", "key": "j", "value": {"k": [1, 2]}},
+                {"op": "lpush", "key": "l", "values": ["1", "2"]},
+                {"op": "hset", "key": "h", "fields": {"f": "v"}},
+                {"op": "delete", "key": "old"}
+            ]
+        });
+        let payload: KvBatchPayload = serde_json::from_value(body).unwrap();
+        assert_eq!(payload.expect.get("a"), Some(&3));
+        let (writes, kinds): (Vec<KvWrite>, Vec<_>) = payload.writes.into_iter().map(KvBatchOp::into_write).unzip();
+        assert_eq!(kinds, vec![Some("string"), Some("int"), Some("json"), None, None, None]);
+        assert!(matches!(&writes[1], KvWrite::Set { value: KvValue::Int(7), ttl: Some(t), .. } if *t == Duration::from_millis(1000)));
+        assert!(matches!(&writes[3], KvWrite::Push { front: true, values, .. } if values.len() == 2));
+        assert!(matches!(&writes[4], KvWrite::HashSet { fields, .. } if fields == &vec![("f".to_string(), "v".to_string())]));
+        assert_eq!(writes[5].key(), "old");
+        assert!(serde_json::from_value::<KvBatchPayload>(serde_json::json!({"writes": [{"op": "incr", "key": "a"}]})).is_err());
+    }
+}
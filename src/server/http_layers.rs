@@ -0,0 +1,83 @@
+//! Browser-facing HTTP policy from the `[http]` section: CORS, request body limits per route and
+//! gzip/brotli response compression negotiated with `Accept-Encoding`.
+//!
+//! Everything here is fixed when the router is built, so changes apply after a restart. CORS is
+//! off until `http.cors_origins` lists origins; preflight requests are answered before
+//! authentication. The session cookie is `SameSite=Strict`, so cross-origin browser clients
+//! authenticate with an API key or bearer token.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use axum::http::{header, Extensions, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Version};
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use super::openapi::RouteSpec;
+use crate::config::HttpSettings;
+
+/// `http.body_limits` as (route path, MB) pairs.
+pub fn parse_body_limits(spec: &str) -> Result<Vec<(String, u64)>> {
+    spec.split(',').map(str::trim).filter(|r| !r.is_empty()).map(|rule| {
+        let (path, mb) = rule.rsplit_once('=')
+            .ok_or_else(|| anyhow!("invalid http.body_limits rule '{}': expected '<route path>=<MB>'", rule))?;
+        let mb = mb.trim().parse::<u64>().map_err(|_| anyhow!("invalid size '{}' in http.body_limits", mb.trim()))?;
+        Ok((path.trim().to_string(), mb))
+    }).collect()
+}
+
+/// Largest request body `route` accepts, in bytes: its `http.body_limits` entry, else
+/// `limits.ingest_max_body_mb` for bulk routes and `http.max_body_mb` for the rest.
+pub fn body_limit(route: &RouteSpec, http: &HttpSettings, ingest_max_body_mb: u64) -> usize {
+    let configured = parse_body_limits(&http.body_limits).unwrap_or_default().into_iter()
+        .find(|(path, _)| path == route.path || *path == route.openapi_path())
+        .map(|(_, mb)| mb);
+    let mb = configured.unwrap_or(if route.bulk { ingest_max_body_mb } else { http.max_body_mb });
+    (mb as usize).saturating_mul(1024 * 1024)
+}
+
+/// Reject `[http]` settings that would be silently ignored: malformed or unknown-route body
+/// limits and origins that are not valid header values.
+pub fn check(http: &HttpSettings, routes: &[RouteSpec]) -> Result<()> {
+    for (path, _) in parse_body_limits(&http.body_limits)? {
+        if !routes.iter().any(|r| r.path == path || r.openapi_path() == path) {
+            bail!("http.body_limits names unknown route '{}'", path);
+        }
+    }
+    cors_layer(http)?;
+    Ok(())
+}
+
+/// CORS for `http.cors_origins`; None when CORS is off.
+pub fn cors_layer(http: &HttpSettings) -> Result<Option<CorsLayer>> {
+    let origins: Vec<&str> = http.cors_origins.split(',').map(str::trim).filter(|o| !o.is_empty()).collect();
+    if origins.is_empty() { return Ok(None); }
+    let layer = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
+        .allow_headers([header::ACCEPT, header::AUTHORIZATION, header::CONTENT_ENCODING, header::CONTENT_TYPE, HeaderName::from_static("x-csrf-token"),
+            header::RANGE, header::IF_MATCH, header::IF_NONE_MATCH, header::IF_RANGE])
+        .expose_headers([header::ETAG, header::CONTENT_RANGE, header::ACCEPT_RANGES])
+        .max_age(Duration::from_secs(http.cors_max_age_secs));
+    // Credentials cannot be combined with a wildcard origin
+    if origins.contains(&"*") { return Ok(Some(layer.allow_origin(Any))); }
+    let list = origins.iter()
+        .map(|o| HeaderValue::from_str(o).map_err(|_| anyhow!("invalid origin '{}' in http.cors_origins", o)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(layer.allow_origin(AllowOrigin::list(list)).allow_credentials(http.cors_allow_credentials)))
+}
+
+/// gzip/brotli compression of responses above `http.compression_min_bytes`; None when
+/// `http.compression` is off. Images, event streams, WebSocket upgrades and partial content
+/// (byte ranges of filestore downloads) pass through.
+pub fn compression_layer(http: &HttpSettings) -> Option<CompressionLayer<impl Predicate>> {
+    if !http.compression { return None; }
+    let not_upgrade = |status: StatusCode, _: Version, _: &HeaderMap, _: &Extensions| status != StatusCode::SWITCHING_PROTOCOLS;
+    let not_partial = |status: StatusCode, _: Version, _: &HeaderMap, _: &Extensions| status != StatusCode::PARTIAL_CONTENT;
+    let predicate = SizeAbove::new(http.compression_min_bytes.min(u16::MAX as u64) as u16)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(not_upgrade)
+        .and(not_partial);
+    Some(CompressionLayer::new().compress_when(predicate))
+}
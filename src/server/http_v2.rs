@@ -0,0 +1,369 @@
+//! `POST /v2/query`: streamed results over HTTP.
+//!
+//! Body: `{"query": "...", "params": {...} | [...], "format": "ndjson" | "json-seq"}`. Named
+//! parameters are written `:name` and bound from an object; positional ones are `$1`, `$2`, ...
+//! and bound from an array. The format can also come from the `Accept` header
+//! (`application/x-ndjson`, `application/json-seq`); NDJSON is the default.
+//!
+//! The response is a sequence of JSON records: a header `{"columns": [...]}`, one array per row,
+//! and a trailer `{"status":"ok","rows":N}`. Rows are encoded in batches into a bounded channel,
+//! so a slow client holds back the encoder instead of buffering the whole result. Errors answer
+//! with the HTTP status of the error and a body `{"error": {...}}` carrying the fields of the v1
+//! error body (`code`, `sqlstate`, `message`, `position`).
+
+use std::panic::AssertUnwindSafe;
+
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use futures_util::FutureExt;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use polars::prelude::DataFrame;
+
+use super::{activity, http_auth::AuthContext, query, AppState};
+use crate::error::AppError;
+use crate::server::exec::exec_helpers::dataframe_to_json;
+
+/// Rows encoded per chunk sent to the client.
+const BATCH_ROWS: usize = 512;
+/// Encoded chunks buffered ahead of the client.
+const CHANNEL_CHUNKS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Newline-delimited JSON (`application/x-ndjson`)
+    Ndjson,
+    /// RFC 7464 JSON text sequences (`application/json-seq`)
+    JsonSeq,
+}
+
+impl StreamFormat {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ndjson" | "application/x-ndjson" | "application/ndjson" => Some(Self::Ndjson),
+            "json-seq" | "application/json-seq" => Some(Self::JsonSeq),
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Ndjson => "application/x-ndjson",
+            Self::JsonSeq => "application/json-seq",
+        }
+    }
+
+    /// One record in this format.
+    pub fn record(self, value: &serde_json::Value) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.push(&mut out, value);
+        out
+    }
+
+    fn push(self, out: &mut Vec<u8>, value: &serde_json::Value) {
+        if self == Self::JsonSeq { out.push(0x1E); }
+        // Serializing a Value into a Vec cannot fail
+        let _ = serde_json::to_writer(&mut *out, value);
+        out.push(b'\n');
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct QueryV2Payload {
+    query: String,
+    #[serde(default)]
+    params: Option<serde_json::Value>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+fn undefined_parameter(message: String) -> AppError {
+    AppError::user("undefined_parameter".to_string(), message)
+}
+
+// SQL text for a bound value
+fn param_literal(v: &serde_json::Value) -> Result<String, AppError> {
+    Ok(match v {
+        serde_json::Value::Null => "NULL".to_string(),
+        serde_json::Value::Bool(b) => if *b { "TRUE".to_string() } else { "FALSE".to_string() },
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+        other => return Err(AppError::user(
+            "invalid_parameter_value".to_string(),
+            format!("parameter values must be scalars, got {}", other),
+        )),
+    })
+}
+
+/// Substitute `:name` (from an object) or `$n` (from an array) placeholders in `sql`.
+/// Quoted strings, quoted identifiers, comments and `::` casts are left alone.
+pub fn bind_params(sql: &str, params: Option<&serde_json::Value>) -> Result<String, AppError> {
+    let params = match params {
+        None | Some(serde_json::Value::Null) => return Ok(sql.to_string()),
+        Some(p @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => p,
+        Some(other) => return Err(AppError::user(
+            "invalid_parameter_value".to_string(),
+            format!("params must be an object or an array, got {}", other),
+        )),
+    };
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' | '"' => {
+                // Copy through the closing quote; doubled quotes stay inside the literal
+                out.push(c);
+                i += 1;
+                while i < chars.len() {
+                    out.push(chars[i]);
+                    if chars[i] == c {
+                        if chars.get(i + 1) == Some(&c) { out.push(c); i += 2; continue; }
+                        break;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' { out.push(chars[i]); i += 1; }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) { out.push(chars[i]); i += 1; }
+                if i < chars.len() { out.push_str("*/"); i += 2; }
+            }
+            ':' if chars.get(i + 1) == Some(&':') => { out.push_str("::"); i += 2; }
+            ':' if chars.get(i + 1).is_some_and(|n| n.is_ascii_alphabetic() || *n == '_') => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') { end += 1; }
+                let name: String = chars[start..end].iter().collect();
+                let serde_json::Value::Object(map) = params else {
+                    return Err(undefined_parameter(format!("named parameter :{} needs params as an object", name)));
+                };
+                let v = map.get(&name).ok_or_else(|| undefined_parameter(format!("no value for parameter :{}", name)))?;
+                out.push_str(&param_literal(v)?);
+                i = end;
+            }
+            '$' if chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()) => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end].is_ascii_digit() { end += 1; }
+                let digits: String = chars[start..end].iter().collect();
+                let serde_json::Value::Array(list) = params else {
+                    return Err(undefined_parameter(format!("positional parameter ${} needs params as an array", digits)));
+                };
+                let v = digits.parse::<usize>().ok().filter(|n| *n >= 1).and_then(|n| list.get(n - 1))
+                    .ok_or_else(|| undefined_parameter(format!("no value for parameter ${}", digits)))?;
+                out.push_str(&param_literal(v)?);
+                i = end;
+            }
+            _ => { out.push(c); i += 1; }
+        }
+    }
+    Ok(out)
+}
+
+/// Rows of a statement result, encoded batch by batch as the client reads them.
+pub enum ResultRows {
+    /// A SELECT result, sliced per batch
+    Frame(DataFrame),
+    /// Row objects of any other result
+    Values(Vec<serde_json::Value>),
+}
+
+// Values of a row object in column order
+fn row_array(columns: &[String], row: serde_json::Value) -> serde_json::Value {
+    let serde_json::Value::Object(mut m) = row else { return serde_json::Value::Array(vec![row]) };
+    serde_json::Value::Array(columns.iter().map(|c| m.remove(c).unwrap_or(serde_json::Value::Null)).collect())
+}
+
+/// Column names and row objects of a JSON statement result; non-tabular results become one `result` column.
+pub fn result_rows(value: serde_json::Value) -> (Vec<String>, Vec<serde_json::Value>) {
+    match value {
+        serde_json::Value::Array(rows) if rows.iter().all(|r| r.is_object()) => {
+            let columns: Vec<String> = rows.first()
+                .and_then(|r| r.as_object())
+                .map(|m| m.keys().cloned().collect())
+                .unwrap_or_default();
+            (columns, rows)
+        }
+        other => (vec!["result".to_string()], vec![serde_json::json!({ "result": other })]),
+    }
+}
+
+fn error_response(app: &AppError, fallback: StatusCode) -> Response {
+    (StatusCode::from_u16(app.http_status()).unwrap_or(fallback), Json(serde_json::json!({ "error": error_object(app) }))).into_response()
+}
+
+fn error_object(app: &AppError) -> serde_json::Value {
+    let mut body = app.to_json();
+    if let Some(m) = body.as_object_mut() { m.remove("status"); }
+    body
+}
+
+/// Encode `rows` as a stream of records: header, rows in batches, trailer.
+pub fn encode_stream(format: StreamFormat, columns: Vec<String>, rows: ResultRows) -> mpsc::Receiver<Bytes> {
+    let (tx, rx) = mpsc::channel::<Bytes>(CHANNEL_CHUNKS);
+    tokio::spawn(async move {
+        if tx.send(Bytes::from(format.record(&serde_json::json!({ "columns": columns })))).await.is_err() { return; }
+        let mut total = 0usize;
+        let mut send_batch = |batch: Vec<serde_json::Value>| {
+            total += batch.len();
+            let mut buf = Vec::new();
+            for row in batch { format.push(&mut buf, &row_array(&columns, row)); }
+            Bytes::from(buf)
+        };
+        match rows {
+            ResultRows::Frame(df) => {
+                let mut offset = 0usize;
+                while offset < df.height() {
+                    let part = df.slice(offset as i64, BATCH_ROWS);
+                    offset += part.height();
+                    let serde_json::Value::Array(batch) = dataframe_to_json(&part) else { break };
+                    // Waits while the client is behind; stops once it disconnects
+                    if tx.send(send_batch(batch)).await.is_err() { return; }
+                }
+            }
+            ResultRows::Values(values) => {
+                let mut values = values.into_iter().peekable();
+                while values.peek().is_some() {
+                    let batch: Vec<serde_json::Value> = values.by_ref().take(BATCH_ROWS).collect();
+                    if tx.send(send_batch(batch)).await.is_err() { return; }
+                }
+            }
+        }
+        let _ = tx.send(Bytes::from(format.record(&serde_json::json!({ "status": "ok", "rows": total })))).await;
+    });
+    rx
+}
+
+fn stream_response(format: StreamFormat, columns: Vec<String>, rows: ResultRows) -> Response {
+    let rx = encode_stream(format, columns, rows);
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (Ok::<_, std::io::Error>(chunk), rx))
+    });
+    let mut resp = Response::new(Body::from_stream(stream));
+    resp.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+    resp
+}
+
+// Run a statement: plain SELECTs keep their DataFrame (column order, batch slicing), the rest go
+// through the regular executor.
+async fn run(state: &AppState, sql: &str, defaults: &crate::ident::QueryDefaults) -> anyhow::Result<(Vec<String>, ResultRows)> {
+    let effective = crate::server::exec::normalize_query_with_defaults(sql, &defaults.current_database, &defaults.current_schema);
+    if let Ok(query::Command::Select(q)) = query::parse(&effective) {
+        if q.into_table.is_none() {
+            let (df, _) = crate::memory::run_query(async { crate::server::exec::exec_select::handle_select(&state.store, &q) }).await?;
+            let columns = df.get_column_names().iter().map(|c| c.to_string()).collect();
+            return Ok((columns, ResultRows::Frame(df)));
+        }
+    }
+    let value = crate::server::exec::execute_query_safe(&state.store, &effective).await?;
+    let (columns, rows) = result_rows(value);
+    Ok((columns, ResultRows::Values(rows)))
+}
+
+pub(super) async fn query_v2_handler(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    headers: HeaderMap,
+    Json(payload): Json<QueryV2Payload>,
+) -> Response {
+    let requested = payload.format.clone()
+        .or_else(|| headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()).map(|s| s.to_string()));
+    let format = match requested {
+        None => StreamFormat::Ndjson,
+        Some(f) => match f.split(',').find_map(StreamFormat::parse) {
+            Some(fmt) => fmt,
+            // Generic Accept values fall back to the default
+            None if payload.format.is_none() => StreamFormat::Ndjson,
+            None => return error_response(&AppError::user("invalid_parameter_value".to_string(), format!("unknown format '{}' (expected ndjson or json-seq)", f)), StatusCode::BAD_REQUEST),
+        },
+    };
+    let sql = match bind_params(&payload.query, payload.params.as_ref()) {
+        Ok(s) => s,
+        Err(app) => return error_response(&app, StatusCode::BAD_REQUEST),
+    };
+    if super::detect_transaction_cmd(&sql).is_some() {
+        return stream_response(format, vec!["transaction".to_string()], ResultRows::Values(vec![serde_json::json!({"transaction": "ok"})]));
+    }
+    let cmd = match query::parse(&sql) {
+        Ok(c) => c,
+        Err(e) => return error_response(&AppError::from_parse_error(&e, &sql), StatusCode::BAD_REQUEST),
+    };
+    let (cur_db, cur_schema) = auth.current_defaults(&state).await;
+    let defaults = crate::ident::QueryDefaults::new(cur_db.clone(), cur_schema);
+    let backend_pid = auth.session_id.as_deref().and_then(activity::session_pid);
+    let role = backend_pid.and_then(activity::role_of);
+    if !super::command_allowed(&state, auth.username(), role.as_deref(), &auth.principal.roles, &cmd, &defaults).await {
+        return error_response(&AppError::Permission { code: "insufficient_privilege".to_string(), message: "permission denied".to_string() }, StatusCode::FORBIDDEN);
+    }
+    if let Some(pid) = backend_pid { activity::set_database(pid, &cur_db); }
+    let exec_fut = activity::run_statement(backend_pid, &sql, run(&state, &sql, &defaults));
+    match AssertUnwindSafe(exec_fut).catch_unwind().await {
+        Ok(Ok((columns, rows))) => stream_response(format, columns, rows),
+        Ok(Err(e)) => {
+            let app = AppError::classify(&e);
+            if matches!(app, AppError::Exec { .. } | AppError::Internal { .. }) { tracing::error!("v2 query failed: {e}"); }
+            error_response(&app, StatusCode::UNPROCESSABLE_ENTITY)
+        }
+        Err(_) => {
+            tracing::error!(target: "panic", "HTTP query_v2_handler panic");
+            error_response(&AppError::Internal { code: "internal_panic".to_string(), message: "internal server error".to_string() }, StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn binds_named_and_positional_params() {
+        let sql = "SELECT * FROM t WHERE a = :a AND b = :b AND c = ':a' AND d::text = :d -- :zz";
+        let out = bind_params(sql, Some(&json!({"a": 1, "b": "it's", "d": null}))).unwrap();
+        assert_eq!(out, "SELECT * FROM t WHERE a = 1 AND b = 'it''s' AND c = ':a' AND d::text = NULL -- :zz");
+        let out = bind_params("SELECT $1, $2", Some(&json!([true, 2.5]))).unwrap();
+        assert_eq!(out, "SELECT TRUE, 2.5");
+        let err = bind_params("SELECT :missing", Some(&json!({}))).unwrap_err();
+        assert_eq!(err.sqlstate(), "42P02");
+        assert!(bind_params("SELECT $3", Some(&json!([1]))).is_err());
+        assert!(bind_params("SELECT :x", Some(&json!({"x": [1]}))).is_err());
+    }
+
+    #[test]
+    fn encodes_rows_and_records() {
+        let (cols, rows) = result_rows(json!([{"a": 1, "b": "x"}, {"a": 2}]));
+        assert_eq!(cols, vec!["a", "b"]);
+        let arrays: Vec<_> = rows.into_iter().map(|r| row_array(&cols, r)).collect();
+        assert_eq!(arrays, vec![json!([1, "x"]), json!([2, null])]);
+        let (cols, rows) = result_rows(json!({"status": "ok"}));
+        assert_eq!(cols, vec!["result"]);
+        assert_eq!(row_array(&cols, rows[0].clone()), json!([{"status": "ok"}]));
+        assert_eq!(StreamFormat::Ndjson.record(&json!({"rows": 1})), b"{\"rows\":1}\n".to_vec());
+        assert_eq!(StreamFormat::JsonSeq.record(&json!([1])), b"\x1e[1]\n".to_vec());
+    }
+
+    #[tokio::test]
+    async fn streams_frames_in_batches() {
+        let df = polars::df!("z" => (0..1000i64).collect::<Vec<_>>(), "a" => vec!["x"; 1000]).unwrap();
+        let mut rx = encode_stream(StreamFormat::Ndjson, vec!["z".into(), "a".into()], ResultRows::Frame(df));
+        let mut chunks = Vec::new();
+        while let Some(c) = rx.recv().await { chunks.push(c); }
+        // header, two batches, trailer
+        assert_eq!(chunks.len(), 4);
+        let text: String = chunks.iter().map(|c| String::from_utf8_lossy(c).to_string()).collect();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], r#"{"columns":["z","a"]}"#);
+        assert_eq!(lines[1], r#"[0,"x"]"#);
+        assert_eq!(lines[1000], r#"[999,"x"]"#);
+        assert_eq!(lines[1001], r#"{"rows":1000,"status":"ok"}"#);
+    }
+}
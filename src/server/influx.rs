@@ -0,0 +1,126 @@
+//! InfluxDB 1.x write compatibility: `POST /write?db=<database>[&precision=ns|u|ms|s|m|h]` and
+//! `GET /ping`, enough for Telegraf and other line-protocol agents.
+//!
+//! Each measurement is written to the time table `<db>/<default schema>/<measurement>.time`
+//! through the regular record path. Tags become string columns; numeric fields stay numbers,
+//! booleans become 1/0 and string fields are kept as strings. Timestamps default to
+//! nanoseconds, as in InfluxDB.
+//!
+//! Agents authenticate per request with `u`/`p` query parameters, `Authorization: Basic` or
+//! `Authorization: Token <user>:<password>`; a login session, API key or bearer token works too
+//! (see `http_auth`, which also checks INSERT on `db`). Success is `204 No Content`; bad lines make the request answer `400 {"error": "partial write: ..."}`
+//! after the valid points are written.
+
+use std::collections::BTreeMap;
+
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{http_auth::AuthContext, ingest, quota, replication, AppState};
+use crate::storage::Record;
+
+#[derive(Debug, Deserialize)]
+pub(super) struct InfluxWriteParams {
+    db: Option<String>,
+    precision: Option<String>,
+}
+
+/// Line-protocol body grouped into records per measurement, plus (1-based line, message) errors.
+pub fn parse_records(body: &str, precision: &str) -> (BTreeMap<String, Vec<Record>>, Vec<(usize, String)>) {
+    let mut out: BTreeMap<String, Vec<Record>> = BTreeMap::new();
+    let mut errors = Vec::new();
+    let now = chrono::Utc::now().timestamp_millis();
+    for (idx, raw) in body.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        let (measurement, mut row) = match ingest::parse_line_protocol(line, precision) {
+            Ok(parsed) => parsed,
+            Err(message) => { errors.push((idx + 1, message)); continue; }
+        };
+        let time = row.remove("_time").and_then(|t| t.as_i64()).unwrap_or(now);
+        for v in row.values_mut() {
+            if let Value::Bool(b) = v { *v = Value::from(*b as i64); }
+        }
+        out.entry(measurement).or_default().push(Record { _time: time, sensors: row });
+    }
+    (out, errors)
+}
+
+fn influx_error(status: StatusCode, message: String) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+pub(super) async fn ping() -> Response {
+    let mut resp = StatusCode::NO_CONTENT.into_response();
+    resp.headers_mut().insert("X-Influxdb-Version", header::HeaderValue::from_static("1.8-clarium"));
+    resp
+}
+
+pub(super) async fn write_handler(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    headers: HeaderMap,
+    Query(params): Query<InfluxWriteParams>,
+    body: Bytes,
+) -> Response {
+    let username = auth.username();
+    let Some(db) = params.db.clone().filter(|d| !d.trim().is_empty()) else {
+        return influx_error(StatusCode::BAD_REQUEST, "database is required".to_string());
+    };
+    let database = crate::ident::normalize_identifier(&db);
+    let precision = params.precision.clone().unwrap_or_else(|| "ns".to_string()).to_ascii_lowercase();
+    if !matches!(precision.as_str(), "n" | "ns" | "u" | "us" | "ms" | "s" | "m" | "h") {
+        return influx_error(StatusCode::BAD_REQUEST, format!("invalid precision '{}'", precision));
+    }
+    if let Err(e) = replication::ensure_writable() {
+        return influx_error(StatusCode::FORBIDDEN, e.to_string());
+    }
+    let encoding = headers.get(header::CONTENT_ENCODING).and_then(|v| v.to_str().ok());
+    let max = (crate::config::current().limits.ingest_max_body_mb as usize).saturating_mul(1024 * 1024);
+    let decoded = match ingest::decode_body(encoding, &body, max) {
+        Ok(b) => b,
+        Err(app) => return influx_error(StatusCode::BAD_REQUEST, app.message().to_string()),
+    };
+    let (tables, errors) = parse_records(&String::from_utf8_lossy(&decoded), &precision);
+    // Per-user quotas: the request counts as a statement and its records as ingested bytes
+    let admitted = match quota::admit_query(username).await {
+        Ok(()) => quota::charge_ingest_as(username, tables.values().map(|r| quota::records_size(r)).sum()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = admitted {
+        return influx_error(StatusCode::TOO_MANY_REQUESTS, e.to_string());
+    }
+    let schema = super::env_default_schema();
+    let store = state.store.clone();
+    let written = tokio::task::spawn_blocking(move || -> anyhow::Result<usize> {
+        let guard = store.0.lock();
+        let mut points = 0usize;
+        for (measurement, records) in tables.iter() {
+            let path = format!("{}/{}/{}.time", database, schema, crate::ident::normalize_identifier(measurement));
+            guard.create_table(&path).ok();
+            guard.write_records(&path, records)?;
+            points += records.len();
+        }
+        Ok(points)
+    }).await;
+    match written {
+        Ok(Ok(points)) => crate::tprintln!("[influx] user={} db={} points={} errors={}", username, db, points, errors.len()),
+        Ok(Err(e)) => {
+            tracing::error!("influx write failed: {e}");
+            return influx_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+        Err(e) => {
+            tracing::error!("influx write task failed: {e}");
+            return influx_error(StatusCode::INTERNAL_SERVER_ERROR, "internal server error".to_string());
+        }
+    }
+    if let Some((line, message)) = errors.first() {
+        return influx_error(StatusCode::BAD_REQUEST, format!("partial write: unable to parse line {}: {} (dropped={})", line, message, errors.len()));
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
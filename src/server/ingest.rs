@@ -0,0 +1,421 @@
+//! `POST /v1/ingest/{table}`: bulk loading over HTTP.
+//!
+//! The body is NDJSON (one object per line) or line protocol
+//! (`measurement[,tag=v...] field=v[,field=v...] [timestamp]`; the measurement is ignored, tags
+//! become string columns). The format comes from `?format=ndjson|lp` or the `Content-Type`
+//! (`application/x-ndjson`, `text/plain`). `Content-Encoding: gzip` and `zstd` bodies are
+//! decompressed up to `limits.ingest_max_body_mb`.
+//!
+//! `?types=col:int64,col2:string` fixes column types instead of inferring them from the batch;
+//! a value that does not convert rejects its line. Line-protocol timestamps are read in
+//! `?precision=ns|us|ms|s` (default ms). Rows are written in batches of
+//! `limits.ingest_batch_rows`; a bad line or a failed batch counts as errors without stopping the
+//! rest of the request, and the response reports rows written and errors with a few samples.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use anyhow::Result;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use polars::prelude::*;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::{http_auth::AuthContext, quota, replication, AppState};
+use crate::error::AppError;
+use crate::storage::SharedStore;
+
+/// Error lines echoed back in the response.
+const MAX_ERROR_SAMPLES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestFormat {
+    Ndjson,
+    LineProtocol,
+}
+
+impl IngestFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ndjson" | "jsonl" | "application/x-ndjson" | "application/ndjson" | "application/jsonl" => Some(Self::Ndjson),
+            "lp" | "line" | "line-protocol" | "text/plain" => Some(Self::LineProtocol),
+            _ => None,
+        }
+    }
+}
+
+/// Column type given by a `types` hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType { Int64, Float64, Boolean, String }
+
+impl ColumnType {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "int" | "int64" | "bigint" | "integer" => Some(Self::Int64),
+            "float" | "float64" | "double" | "real" => Some(Self::Float64),
+            "bool" | "boolean" => Some(Self::Boolean),
+            "string" | "str" | "text" | "utf8" | "varchar" => Some(Self::String),
+            _ => None,
+        }
+    }
+
+    // Convert a parsed value; None when it does not fit the type
+    fn coerce(self, v: Value) -> Option<Value> {
+        if v.is_null() { return Some(v); }
+        Some(match self {
+            Self::Int64 => match &v {
+                Value::Number(n) => Value::from(n.as_i64().or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64))?),
+                Value::String(s) => Value::from(s.trim().parse::<i64>().ok()?),
+                Value::Bool(b) => Value::from(*b as i64),
+                _ => return None,
+            },
+            Self::Float64 => match &v {
+                Value::Number(n) => Value::from(n.as_f64()?),
+                Value::String(s) => Value::from(s.trim().parse::<f64>().ok()?),
+                _ => return None,
+            },
+            Self::Boolean => match &v {
+                Value::Bool(_) => v,
+                Value::String(s) => Value::Bool(parse_bool(s)?),
+                Value::Number(n) => Value::Bool(n.as_i64()? != 0),
+                _ => return None,
+            },
+            Self::String => match v {
+                Value::String(_) => v,
+                other => Value::String(other.to_string()),
+            },
+        })
+    }
+}
+
+/// `col:type,col:type` from the `types` query parameter.
+pub fn parse_type_hints(spec: &str) -> Result<HashMap<String, ColumnType>, AppError> {
+    let mut out = HashMap::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (name, ty) = part.split_once(':').ok_or_else(|| bad_request(format!("type hint '{}' must be column:type", part)))?;
+        let ty = ColumnType::parse(ty).ok_or_else(|| bad_request(format!("unknown type '{}' for column {} (expected int64, float64, bool or string)", ty.trim(), name.trim())))?;
+        out.insert(name.trim().to_string(), ty);
+    }
+    Ok(out)
+}
+
+fn bad_request(message: String) -> AppError {
+    AppError::user("invalid_parameter_value".to_string(), message)
+}
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s {
+        "t" | "T" | "true" | "True" | "TRUE" => Some(true),
+        "f" | "F" | "false" | "False" | "FALSE" => Some(false),
+        _ => None,
+    }
+}
+
+/// Decompress a request body per its `Content-Encoding`, failing past `max_bytes`.
+pub fn decode_body(encoding: Option<&str>, body: &[u8], max_bytes: usize) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    let limit = max_bytes as u64 + 1;
+    let read = match encoding.map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("identity") => { out.extend_from_slice(body); Ok(out.len()) }
+        Some("gzip") | Some("x-gzip") => flate2::read::MultiGzDecoder::new(body).take(limit).read_to_end(&mut out),
+        Some("zstd") => zstd::stream::read::Decoder::new(body).and_then(|d| d.take(limit).read_to_end(&mut out)),
+        Some(other) => return Err(AppError::user("feature_not_supported".to_string(), format!("unsupported Content-Encoding '{}' (expected gzip or zstd)", other))),
+    };
+    read.map_err(|e| bad_request(format!("cannot decompress request body: {}", e)))?;
+    if out.len() > max_bytes {
+        return Err(AppError::user("configuration_limit_exceeded".to_string(), format!("request body exceeds limits.ingest_max_body_mb ({} bytes)", max_bytes)));
+    }
+    Ok(out)
+}
+
+// Split on `sep` outside double quotes, honouring backslash escapes
+fn split_unquoted(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut in_q, mut escaped) = (0usize, false, false);
+    for (i, c) in s.char_indices() {
+        if escaped { escaped = false; continue; }
+        match c {
+            '\\' => escaped = true,
+            '"' => in_q = !in_q,
+            c if c == sep && !in_q => { parts.push(&s[start..i]); start = i + c.len_utf8(); }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' { if let Some(n) = chars.next() { out.push(n); } } else { out.push(c); }
+    }
+    out
+}
+
+fn lp_field_value(raw: &str) -> Result<Value, String> {
+    if let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+        return Ok(Value::String(unescape(inner)));
+    }
+    if let Some(b) = parse_bool(raw) { return Ok(Value::Bool(b)); }
+    if let Some(int) = raw.strip_suffix('i').or_else(|| raw.strip_suffix('u')) {
+        return int.parse::<i64>().map(Value::from).map_err(|_| format!("invalid integer field value '{}'", raw));
+    }
+    raw.parse::<f64>().ok().filter(|f| f.is_finite()).map(Value::from).ok_or_else(|| format!("invalid field value '{}'", raw))
+}
+
+/// One line-protocol line as its measurement and a row; the timestamp becomes `_time` in
+/// milliseconds.
+pub fn parse_line_protocol(line: &str, precision: &str) -> Result<(String, Map<String, Value>), String> {
+    let parts: Vec<&str> = split_unquoted(line, ' ').into_iter().filter(|p| !p.is_empty()).collect();
+    if parts.len() < 2 || parts.len() > 3 { return Err("expected 'measurement[,tags] fields [timestamp]'".to_string()); }
+    let mut series = split_unquoted(parts[0], ',').into_iter();
+    let measurement = unescape(series.next().unwrap_or_default());
+    if measurement.is_empty() { return Err("missing measurement".to_string()); }
+    let mut row = Map::new();
+    for tag in series {
+        let (k, v) = tag.split_once('=').ok_or_else(|| format!("invalid tag '{}'", tag))?;
+        row.insert(unescape(k), Value::String(unescape(v)));
+    }
+    for field in split_unquoted(parts[1], ',') {
+        let (k, v) = field.split_once('=').ok_or_else(|| format!("invalid field '{}'", field))?;
+        row.insert(unescape(k), lp_field_value(v)?);
+    }
+    if let Some(ts) = parts.get(2) {
+        let raw: i64 = ts.parse().map_err(|_| format!("invalid timestamp '{}'", ts))?;
+        let ms = match precision {
+            "ns" | "n" => raw / 1_000_000,
+            "us" | "u" => raw / 1_000,
+            "ms" => raw,
+            "s" => raw.saturating_mul(1_000),
+            "m" => raw.saturating_mul(60_000),
+            "h" => raw.saturating_mul(3_600_000),
+            other => return Err(format!("unknown precision '{}'", other)),
+        };
+        row.insert("_time".to_string(), Value::from(ms));
+    }
+    Ok((measurement, row))
+}
+
+/// Outcome of one ingest request.
+#[derive(Debug, Default, Clone)]
+pub struct IngestReport {
+    pub rows: usize,
+    pub errors: usize,
+    pub batches: usize,
+    /// (1-based line, message), at most `MAX_ERROR_SAMPLES`
+    pub samples: Vec<(usize, String)>,
+}
+
+impl IngestReport {
+    fn error(&mut self, line: usize, message: String) {
+        self.errors += 1;
+        if self.samples.len() < MAX_ERROR_SAMPLES { self.samples.push((line, message)); }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let status = if self.errors == 0 { "ok" } else if self.rows > 0 { "partial" } else { "error" };
+        serde_json::json!({
+            "status": status,
+            "rows": self.rows,
+            "errors": self.errors,
+            "batches": self.batches,
+            "error_samples": self.samples.iter().map(|(line, message)| serde_json::json!({"line": line, "message": message})).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Options of one ingest request.
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+    pub format: IngestFormat,
+    pub types: HashMap<String, ColumnType>,
+    pub precision: String,
+    pub batch_rows: usize,
+}
+
+// Build a batch frame; hinted columns use their type, the rest are inferred from the values
+fn batch_frame(rows: &[(usize, Map<String, Value>)], types: &HashMap<String, ColumnType>) -> Result<DataFrame> {
+    let mut names: Vec<String> = Vec::new();
+    for (_, row) in rows {
+        for k in row.keys() { if !names.contains(k) { names.push(k.clone()); } }
+    }
+    let mut columns: Vec<Column> = Vec::with_capacity(names.len());
+    for name in &names {
+        let values: Vec<&Value> = rows.iter().map(|(_, r)| r.get(name).unwrap_or(&Value::Null)).collect();
+        let ty = types.get(name).copied().unwrap_or_else(|| {
+            let present = || values.iter().filter(|v| !v.is_null());
+            if name == "_time" || (present().count() > 0 && present().all(|v| v.is_i64())) { ColumnType::Int64 }
+            else if present().count() > 0 && present().all(|v| v.is_number()) { ColumnType::Float64 }
+            else if present().count() > 0 && present().all(|v| v.is_boolean()) { ColumnType::Boolean }
+            else { ColumnType::String }
+        });
+        let col = match ty {
+            ColumnType::Int64 => Series::new(name.as_str().into(), values.iter().map(|v| v.as_i64()).collect::<Vec<_>>()),
+            ColumnType::Float64 => Series::new(name.as_str().into(), values.iter().map(|v| v.as_f64()).collect::<Vec<_>>()),
+            ColumnType::Boolean => Series::new(name.as_str().into(), values.iter().map(|v| v.as_bool()).collect::<Vec<_>>()),
+            ColumnType::String => Series::new(name.as_str().into(), values.iter().map(|v| match v {
+                Value::Null => None,
+                Value::String(s) => Some(s.clone()),
+                other => Some(other.to_string()),
+            }).collect::<Vec<_>>()),
+        };
+        columns.push(col.into());
+    }
+    Ok(DataFrame::new(columns)?)
+}
+
+/// Parse `body` and write it to `table_path` (fully qualified) in batches.
+pub fn ingest(store: &SharedStore, table_path: &str, body: &[u8], opts: &IngestOptions) -> IngestReport {
+    let mut report = IngestReport::default();
+    let is_time_table = {
+        let guard = store.0.lock();
+        guard.create_table(table_path).ok();
+        guard.is_time_table(table_path)
+    };
+    let text = String::from_utf8_lossy(body);
+    let batch_rows = opts.batch_rows.max(1);
+    let mut batch: Vec<(usize, Map<String, Value>)> = Vec::with_capacity(batch_rows.min(65_536));
+    let flush = |batch: &mut Vec<(usize, Map<String, Value>)>, report: &mut IngestReport| {
+        if batch.is_empty() { return; }
+        let first_line = batch[0].0;
+        let written = batch_frame(batch, &opts.types)
+            .and_then(|df| crate::server::exec::exec_insert::handle_insert_from_df(store, table_path.to_string(), Vec::new(), df));
+        match written {
+            Ok(_) => { report.rows += batch.len(); report.batches += 1; }
+            Err(e) => {
+                let message = format!("batch starting at line {} failed: {}", first_line, e);
+                for (line, _) in batch.iter() { report.error(*line, message.clone()); }
+            }
+        }
+        batch.clear();
+    };
+    for (idx, raw) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw.trim();
+        if line.is_empty() || (opts.format == IngestFormat::LineProtocol && line.starts_with('#')) { continue; }
+        let parsed = match opts.format {
+            IngestFormat::Ndjson => match serde_json::from_str::<Value>(line) {
+                Ok(Value::Object(m)) => Ok(m),
+                Ok(_) => Err("expected a JSON object".to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+            IngestFormat::LineProtocol => parse_line_protocol(line, &opts.precision).map(|(_, row)| row),
+        };
+        let mut row = match parsed {
+            Ok(r) => r,
+            Err(message) => { report.error(line_no, message); continue; }
+        };
+        if is_time_table && !row.contains_key("_time") {
+            if opts.format == IngestFormat::LineProtocol {
+                row.insert("_time".to_string(), Value::from(chrono::Utc::now().timestamp_millis()));
+            } else {
+                report.error(line_no, "time table rows need a _time value".to_string());
+                continue;
+            }
+        }
+        let mut bad = None;
+        for (name, ty) in opts.types.iter() {
+            let Some(v) = row.remove(name) else { continue };
+            match ty.coerce(v.clone()) {
+                Some(c) => { row.insert(name.clone(), c); }
+                None => { bad = Some(format!("value {} of column {} is not {:?}", v, name, ty)); break; }
+            }
+        }
+        if let Some(message) = bad { report.error(line_no, message); continue; }
+        batch.push((line_no, row));
+        if batch.len() >= batch_rows { flush(&mut batch, &mut report); }
+    }
+    flush(&mut batch, &mut report);
+    crate::tprintln!("[ingest] table='{}' rows={} errors={} batches={}", table_path, report.rows, report.errors, report.batches);
+    report
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct IngestParams {
+    format: Option<String>,
+    types: Option<String>,
+    precision: Option<String>,
+}
+
+fn error_response(app: &AppError) -> Response {
+    let status = match app.code_str() {
+        "configuration_limit_exceeded" => StatusCode::PAYLOAD_TOO_LARGE,
+        "feature_not_supported" => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        _ => StatusCode::from_u16(app.http_status()).unwrap_or(StatusCode::BAD_REQUEST),
+    };
+    (status, Json(app.to_json())).into_response()
+}
+
+pub(super) async fn ingest_handler(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    headers: HeaderMap,
+    Path(table): Path<String>,
+    Query(params): Query<IngestParams>,
+    body: Bytes,
+) -> Response {
+    let username = auth.username();
+    let content_type = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(|s| s.split(';').next().unwrap_or("").to_string());
+    let format = match params.format.as_deref().or(content_type.as_deref()) {
+        None => IngestFormat::Ndjson,
+        Some(f) => match IngestFormat::parse(f) {
+            Some(fmt) => fmt,
+            None if params.format.is_none() => IngestFormat::Ndjson,
+            None => return error_response(&bad_request(format!("unknown format '{}' (expected ndjson or lp)", f))),
+        },
+    };
+    let types = match params.types.as_deref().map(parse_type_hints).transpose() {
+        Ok(t) => t.unwrap_or_default(),
+        Err(app) => return error_response(&app),
+    };
+    let precision = params.precision.unwrap_or_else(|| "ms".to_string()).to_ascii_lowercase();
+    if !matches!(precision.as_str(), "ns" | "us" | "ms" | "s") {
+        return error_response(&bad_request(format!("unknown precision '{}' (expected ns, us, ms or s)", precision)));
+    }
+    // Qualify the target with the session's current database and schema
+    let (cur_db, cur_schema) = auth.current_defaults(&state).await;
+    let defaults = crate::ident::QueryDefaults::new(cur_db, cur_schema);
+    let table_path = if table.to_ascii_lowercase().ends_with(".time") {
+        crate::ident::qualify_time_ident(&table, &defaults)
+    } else {
+        crate::ident::qualify_regular_ident(&table, &defaults)
+    };
+    let database = table_path.split('/').next().unwrap_or_default().to_string();
+    if !crate::identity::check_command_allowed_async(&state.store, username, crate::security::CommandKind::Insert, Some(&database)).await {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"forbidden"}))).into_response();
+    }
+    if let Err(e) = replication::ensure_writable() {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"status":"error","error": e.to_string()}))).into_response();
+    }
+    let limits = crate::config::current().limits.clone();
+    let encoding = headers.get(header::CONTENT_ENCODING).and_then(|v| v.to_str().ok());
+    let decoded = match decode_body(encoding, &body, (limits.ingest_max_body_mb as usize).saturating_mul(1024 * 1024)) {
+        Ok(b) => b,
+        Err(app) => return error_response(&app),
+    };
+    // Per-user quotas: the request counts as a statement and its decoded body as ingested bytes
+    let admitted = match quota::admit_query(username).await {
+        Ok(()) => quota::charge_ingest_as(username, decoded.len()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = admitted {
+        return (StatusCode::TOO_MANY_REQUESTS, Json(AppError::classify(&e).to_json())).into_response();
+    }
+    let opts = IngestOptions { format, types, precision, batch_rows: limits.ingest_batch_rows as usize };
+    let store = state.store.clone();
+    let report = match tokio::task::spawn_blocking(move || ingest(&store, &table_path, &decoded, &opts)).await {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("ingest task failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"status":"error","code":"internal_panic","message":"internal server error"}))).into_response();
+        }
+    };
+    let status = if report.errors > 0 && report.rows == 0 { StatusCode::BAD_REQUEST } else { StatusCode::OK };
+    (status, Json(report.to_json())).into_response()
+}
@@ -0,0 +1,339 @@
+//!
+//! clarium scheduled jobs
+//! ----------------------
+//! `CREATE JOB <name> SCHEDULE '<cron>' AS {SCRIPT <db>/<schema>/<name> | <sql>}` runs SQL on a
+//! five-field cron schedule (UTC). Jobs are kept in `<root>/jobs.json` together with their owner
+//! (the user who created them) and the database/schema they resolve names against.
+//!
+//! The scheduler ([`spawn_scheduler`]) wakes at every minute boundary and starts the enabled jobs
+//! whose schedule matches; a job still running from an earlier minute is skipped. Replicas do not
+//! run jobs. Each run:
+//! - registers a `job` backend for the owner, so it shows in `pg_stat_activity` and can be
+//!   cancelled with `KILL QUERY <pid>`;
+//! - passes every statement through the same command gate as HTTP statements, so a job can do
+//!   nothing its owner could not;
+//! - for `AS SCRIPT`, calls the loaded Lua function of that name with no arguments; it returns
+//!   the SQL to run (a string, or an array of statements) or nil to skip the run.
+//!
+//! Runs are appended to `<root>/job_runs.json` (the last [`JOB_RUN_HISTORY`]) and reported by
+//! `pg_catalog.clarium_job_runs`; jobs by `pg_catalog.clarium_jobs` (SHOW JOBS).
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::error::AppError;
+use crate::ident::QueryDefaults;
+use crate::server::activity::{self, Frontend};
+use crate::server::query::{self, JobBody};
+use crate::storage::SharedStore;
+
+/// Runs kept in `job_runs.json`, across all jobs.
+pub const JOB_RUN_HISTORY: usize = 1000;
+
+/// One scheduled job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub name: String,
+    /// User the job runs as
+    pub owner: String,
+    /// Cron expression, see [`CronSchedule`]
+    pub schedule: String,
+    pub body: JobBody,
+    pub enabled: bool,
+    /// Defaults for unqualified names in the job's statements
+    pub database: String,
+    pub schema: String,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// One finished run of a job. Times are epoch ms.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobRun {
+    pub job: String,
+    pub owner: String,
+    pub pid: i32,
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// `succeeded` or `failed`
+    pub status: String,
+    /// Statements that completed
+    pub statements: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Five-field cron expression: minute, hour, day of month, month, day of week (0-7, 0 and 7
+/// are Sunday). Fields take `*`, `n`, `a-b`, `*/n`, `a-b/n`, `n/step` and comma lists. When
+/// both day fields are restricted a day matching either one fires, as in cron.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn cron_field(spec: &str, lo: u32, hi: u32, expr: &str) -> Result<u64> {
+    let bad = || -> anyhow::Error {
+        AppError::user("invalid_parameter_value".to_string(), format!("invalid cron field '{}' in '{}' (allowed {}-{})", spec, expr, lo, hi)).into()
+    };
+    let mut mask = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(bad)?),
+            None => (part, 1),
+        };
+        let (a, b) = if range == "*" {
+            (lo, hi)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (a.parse().map_err(|_| bad())?, b.parse().map_err(|_| bad())?)
+        } else {
+            let v: u32 = range.parse().map_err(|_| bad())?;
+            // `n/step` runs from n to the end of the range
+            (v, if part.contains('/') { hi } else { v })
+        };
+        if a < lo || b > hi || a > b { return Err(bad()); }
+        for v in (a..=b).step_by(step as usize) { mask |= 1u64 << v; }
+    }
+    Ok(mask)
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(AppError::user("invalid_parameter_value".to_string(), format!("invalid cron schedule '{}': expected 5 fields (minute hour day month weekday)", expr)).into());
+        };
+        let mut weekdays = cron_field(weekday, 0, 7, expr)?;
+        if weekdays & (1 << 7) != 0 { weekdays |= 1; }
+        Ok(Self {
+            minutes: cron_field(minute, 0, 59, expr)?,
+            hours: cron_field(hour, 0, 23, expr)?,
+            days: cron_field(day, 1, 31, expr)?,
+            months: cron_field(month, 1, 12, expr)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule fires in the minute containing `t`.
+    pub fn matches(&self, t: &DateTime<Utc>) -> bool {
+        let bit = |mask: u64, v: u32| mask & (1u64 << v) != 0;
+        let day = bit(self.days, t.day());
+        let weekday = bit(self.weekdays, t.weekday().num_days_from_sunday());
+        let day_ok = if self.any_day || self.any_weekday { day && weekday } else { day || weekday };
+        day_ok && bit(self.minutes, t.minute()) && bit(self.hours, t.hour()) && bit(self.months, t.month())
+    }
+}
+
+// Serializes read-modify-write of jobs.json and job_runs.json
+static FILE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+// Jobs with a run in progress, as "<root>\0<name>"
+static RUNNING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn now_ms() -> i64 { Utc::now().timestamp_millis() }
+
+fn root_of(store: &SharedStore) -> String { store.root_path().to_string_lossy().to_string() }
+
+fn jobs_path(db_root: &str) -> PathBuf { Path::new(db_root).join("jobs.json") }
+
+fn runs_path(db_root: &str) -> PathBuf { Path::new(db_root).join("job_runs.json") }
+
+fn read_list<T: DeserializeOwned>(p: &Path) -> Result<Vec<T>> {
+    if !p.exists() { return Ok(Vec::new()); }
+    Ok(serde_json::from_slice(&std::fs::read(p)?)?)
+}
+
+fn write_list<T: Serialize>(p: &Path, items: &[T]) -> Result<()> {
+    if let Some(dir) = p.parent() { std::fs::create_dir_all(dir).ok(); }
+    let tmp = p.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(items)?)?;
+    std::fs::rename(&tmp, p)?;
+    Ok(())
+}
+
+fn undefined_job(name: &str) -> anyhow::Error {
+    AppError::not_found("undefined_object".to_string(), format!("job \"{}\" does not exist", name)).into()
+}
+
+/// All jobs, ordered by name.
+pub fn list_jobs(db_root: &str) -> Result<Vec<Job>> {
+    let mut jobs: Vec<Job> = read_list(&jobs_path(db_root))?;
+    jobs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(jobs)
+}
+
+/// Recorded runs, oldest first.
+pub fn list_runs(db_root: &str) -> Result<Vec<JobRun>> { read_list(&runs_path(db_root)) }
+
+/// Add a job; the schedule is validated here so a bad one never reaches the scheduler.
+pub fn create_job(db_root: &str, job: Job) -> Result<()> {
+    CronSchedule::parse(&job.schedule)?;
+    let _lock = FILE_LOCK.lock();
+    let mut jobs: Vec<Job> = read_list(&jobs_path(db_root))?;
+    if jobs.iter().any(|j| j.name == job.name) {
+        return Err(AppError::conflict("duplicate_object".to_string(), format!("job \"{}\" already exists", job.name)).into());
+    }
+    jobs.push(job);
+    write_list(&jobs_path(db_root), &jobs)
+}
+
+/// Remove a job. Returns false when it does not exist; its run history is kept.
+pub fn drop_job(db_root: &str, name: &str) -> Result<bool> {
+    let _lock = FILE_LOCK.lock();
+    let mut jobs: Vec<Job> = read_list(&jobs_path(db_root))?;
+    let before = jobs.len();
+    jobs.retain(|j| j.name != name);
+    if jobs.len() == before { return Ok(false); }
+    write_list(&jobs_path(db_root), &jobs)?;
+    Ok(true)
+}
+
+/// ALTER JOB ... ENABLE | DISABLE; a disabled job keeps its definition but is not scheduled.
+pub fn set_enabled(db_root: &str, name: &str, enabled: bool) -> Result<()> {
+    let _lock = FILE_LOCK.lock();
+    let mut jobs: Vec<Job> = read_list(&jobs_path(db_root))?;
+    let job = jobs.iter_mut().find(|j| j.name == name).ok_or_else(|| undefined_job(name))?;
+    job.enabled = enabled;
+    write_list(&jobs_path(db_root), &jobs)
+}
+
+fn record_run(db_root: &str, run: &JobRun) -> Result<()> {
+    let _lock = FILE_LOCK.lock();
+    let mut runs: Vec<JobRun> = read_list(&runs_path(db_root))?;
+    runs.push(run.clone());
+    if runs.len() > JOB_RUN_HISTORY { runs.drain(..runs.len() - JOB_RUN_HISTORY); }
+    write_list(&runs_path(db_root), &runs)
+}
+
+/// Marks a job as running until dropped.
+struct RunningGuard(String);
+
+impl RunningGuard {
+    fn acquire(db_root: &str, name: &str) -> Option<Self> {
+        let key = format!("{}\0{}", db_root, name);
+        RUNNING.lock().insert(key.clone()).then_some(Self(key))
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) { RUNNING.lock().remove(&self.0); }
+}
+
+/// SQL produced by a job script: its function returns a statement, an array of statements or nil.
+fn script_sql(path: &str) -> Result<String> {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let name = file.strip_suffix(".lua").unwrap_or(file);
+    let reg = crate::scripts::get_script_registry().ok_or_else(|| anyhow!("script registry is not initialized"))?;
+    if !reg.has_function(name) {
+        return Err(AppError::not_found("undefined_function".to_string(), format!("job script \"{}\" is not loaded", path)).into());
+    }
+    let statement = |v: serde_json::Value| match v {
+        serde_json::Value::String(s) => Ok(s),
+        other => Err(anyhow!("job script \"{}\" must return SQL text, got {}", path, other)),
+    };
+    match reg.call_function_json(name, &[])? {
+        serde_json::Value::Null => Ok(String::new()),
+        serde_json::Value::Array(items) => Ok(items.into_iter().map(statement).collect::<Result<Vec<_>>>()?.join(";\n")),
+        other => statement(other),
+    }
+}
+
+async fn execute(store: &SharedStore, db_root: &str, job: &Job, pid: i32, done: &mut i64) -> Result<()> {
+    let defaults = QueryDefaults::new(job.database.clone(), job.schema.clone());
+    let text = match &job.body {
+        JobBody::Sql(sql) => sql.clone(),
+        JobBody::Script(path) => activity::run_statement(Some(pid), &format!("SCRIPT {}", path), async { script_sql(path) }).await?,
+    };
+    for sql in query::split_sql_statements(&text) {
+        let cmd = query::parse(sql).map_err(|e| AppError::from_parse_error(&e, sql))?;
+        if !super::exec::exec_authorize::statement_allowed(store, db_root, &job.owner, None, &[], &cmd, &defaults).await {
+            return Err(AppError::permission("insufficient_privilege".to_string(), format!("permission denied for job owner \"{}\": {}", job.owner, sql)).into());
+        }
+        activity::run_statement(Some(pid), sql, crate::server::exec::execute_query_with_defaults(store, sql, &defaults)).await?;
+        *done += 1;
+    }
+    Ok(())
+}
+
+/// Run `job` now as its owner and record the run. Statements stop at the first failure.
+pub async fn run_job(store: &SharedStore, job: &Job) -> JobRun {
+    let root = root_of(store);
+    let started_at = now_ms();
+    let pid = activity::register(Frontend::Job, &job.owner, &job.database, "", None);
+    let _backend = activity::BackendGuard(pid);
+    let mut statements = 0;
+    let outcome = execute(store, &root, job, pid, &mut statements).await;
+    let run = JobRun {
+        job: job.name.clone(),
+        owner: job.owner.clone(),
+        pid,
+        started_at,
+        finished_at: now_ms(),
+        status: if outcome.is_ok() { "succeeded" } else { "failed" }.to_string(),
+        statements,
+        error: outcome.err().map(|e| e.to_string()),
+    };
+    match &run.error {
+        None => tracing::info!("job {} succeeded: {} statement(s) as {}", run.job, run.statements, run.owner),
+        Some(e) => tracing::warn!("job {} failed after {} statement(s): {}", run.job, run.statements, e),
+    }
+    if let Err(e) = record_run(&root, &run) { tracing::warn!("failed to record run of job {}: {}", run.job, e); }
+    run
+}
+
+/// Start every enabled job due in the minute of `now`; returns the names started.
+pub fn tick(store: &SharedStore, now: DateTime<Utc>) -> Vec<String> {
+    let root = root_of(store);
+    let jobs = match list_jobs(&root) {
+        Ok(jobs) => jobs,
+        Err(e) => { tracing::warn!("failed to read jobs: {}", e); return Vec::new(); }
+    };
+    let mut started = Vec::new();
+    for job in jobs.into_iter().filter(|j| j.enabled) {
+        if !CronSchedule::parse(&job.schedule).map(|s| s.matches(&now)).unwrap_or(false) { continue; }
+        let Some(running) = RunningGuard::acquire(&root, &job.name) else {
+            tracing::warn!("job {} is still running; skipping its {} run", job.name, now.format("%H:%M"));
+            continue;
+        };
+        started.push(job.name.clone());
+        let store = store.clone();
+        tokio::spawn(async move {
+            let _running = running;
+            run_job(&store, &job).await;
+        });
+    }
+    started
+}
+
+/// Start the scheduler task; it exits when `shutdown` turns true.
+pub fn spawn_scheduler(store: SharedStore, mut shutdown: watch::Receiver<bool>) {
+    tokio::spawn(async move {
+        loop {
+            // Wake just after the next minute boundary
+            let wait_ms = 60_000 - now_ms().rem_euclid(60_000) + 50;
+            tokio::select! {
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() { crate::tprintln!("[shutdown] job_scheduler exiting on shutdown signal"); break; }
+                }
+                _ = tokio::time::sleep(Duration::from_millis(wait_ms as u64)) => {
+                    if crate::server::replication::is_replica() { continue; }
+                    tick(&store, Utc::now());
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,322 @@
+//! OpenAPI 3.0 description of the HTTP API, served at `/openapi`.
+//!
+//! Every route is declared once in the `http_routes!` table in `server.rs`, which builds both the
+//! axum router and the [`RouteSpec`] list this module turns into the document, so a route cannot
+//! be added without its description. SQL-level features (filestore DDL and commands, admin
+//! commands, DDL) go through `/query` and `/v2/query`; filestore file content also has its own
+//! download, upload and presign routes.
+
+use serde_json::{json, Map, Value};
+
+use crate::security::CommandKind;
+
+pub const JSON: &str = "application/json";
+pub const NDJSON: &str = "application/x-ndjson";
+pub const TEXT: &str = "text/plain";
+
+/// Request or response body: media type and component schema (empty for free-form).
+#[derive(Debug, Clone, Copy)]
+pub struct Body {
+    pub content_type: &'static str,
+    pub schema: &'static str,
+}
+
+/// Description of one HTTP route.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteSpec {
+    /// Lower-case method (`get`, `post`), filled in by `http_routes!`
+    pub method: &'static str,
+    /// Route path in axum syntax (`{name}`, `{*rest}`), filled in by `http_routes!`
+    pub path: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+    /// Query parameters: (name, description)
+    pub query: &'static [(&'static str, &'static str)],
+    pub request: Option<Body>,
+    pub response: Option<Body>,
+    /// Status of a successful call
+    pub status: u16,
+    /// Accepts bodies up to `limits.ingest_max_body_mb` instead of `http.max_body_mb`
+    pub bulk: bool,
+    /// Requires a session, bearer token or API key
+    pub auth: bool,
+    /// Cookie sessions must send `X-CSRF-Token`
+    pub csrf: bool,
+    /// Also accepts user/password credentials (Basic, `Token user:password`, `u`/`p` parameters)
+    pub agent: bool,
+    /// Permission checked before the handler runs, on the database named by this path or
+    /// query parameter
+    pub privilege: Option<(CommandKind, &'static str)>,
+}
+
+impl RouteSpec {
+    pub const fn new(tag: &'static str, summary: &'static str) -> Self {
+        Self { method: "", path: "", tag, summary, query: &[], request: None, response: None, status: 200, bulk: false, auth: true, csrf: true, agent: false, privilege: None }
+    }
+
+    pub const fn body(mut self, content_type: &'static str, schema: &'static str) -> Self {
+        self.request = Some(Body { content_type, schema });
+        self
+    }
+
+    pub const fn returns(mut self, content_type: &'static str, schema: &'static str) -> Self {
+        self.response = Some(Body { content_type, schema });
+        self
+    }
+
+    pub const fn query(mut self, params: &'static [(&'static str, &'static str)]) -> Self {
+        self.query = params;
+        self
+    }
+
+    pub const fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub const fn bulk(mut self) -> Self {
+        self.bulk = true;
+        self
+    }
+
+    pub const fn public(mut self) -> Self {
+        self.auth = false;
+        self
+    }
+
+    pub const fn no_csrf(mut self) -> Self {
+        self.csrf = false;
+        self
+    }
+
+    pub const fn agent(mut self) -> Self {
+        self.agent = true;
+        self
+    }
+
+    pub const fn requires(mut self, kind: CommandKind, database_param: &'static str) -> Self {
+        self.privilege = Some((kind, database_param));
+        self
+    }
+
+    /// Path in OpenAPI syntax (`{*rest}` becomes `{rest}`).
+    pub fn openapi_path(&self) -> String {
+        self.path.replace("{*", "{")
+    }
+
+    fn path_params(&self) -> Vec<String> {
+        let path = self.openapi_path();
+        path.split('{').skip(1).filter_map(|s| s.split_once('}').map(|(name, _)| name.to_string())).collect()
+    }
+
+    /// `post_v2_query` style operation id.
+    pub fn operation_id(&self) -> String {
+        let mut id = self.method.to_string();
+        for part in self.openapi_path().split(|c: char| !c.is_ascii_alphanumeric()).filter(|p| !p.is_empty()) {
+            id.push('_');
+            id.push_str(&part.to_ascii_lowercase());
+        }
+        if self.path == "/" { id.push_str("_root"); }
+        id
+    }
+}
+
+fn content(body: &Body) -> Value {
+    let schema = if body.schema.is_empty() {
+        if body.content_type == JSON { json!({"type": "object"}) } else { json!({"type": "string"}) }
+    } else {
+        json!({"$ref": format!("#/components/schemas/{}", body.schema)})
+    };
+    json!({ body.content_type: { "schema": schema } })
+}
+
+fn operation(route: &RouteSpec) -> Value {
+    let mut op = json!({
+        "operationId": route.operation_id(),
+        "tags": [route.tag],
+        "summary": route.summary,
+    });
+    let mut params: Vec<Value> = route.path_params().into_iter()
+        .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+        .collect();
+    params.extend(route.query.iter().map(|(name, description)| json!({"name": name, "in": "query", "required": false, "description": description, "schema": {"type": "string"}})));
+    if !params.is_empty() { op["parameters"] = Value::Array(params); }
+    if let Some(body) = &route.request {
+        op["requestBody"] = json!({"required": true, "content": content(body)});
+    }
+    let mut ok = json!({"description": "success"});
+    if let Some(body) = &route.response { ok["content"] = content(body); }
+    let mut responses = Map::new();
+    responses.insert(route.status.to_string(), ok);
+    if route.auth {
+        responses.insert("401".to_string(), json!({"description": "not authenticated"}));
+        responses.insert("403".to_string(), json!({"description": "forbidden or invalid CSRF token"}));
+    }
+    responses.insert("default".to_string(), json!({"description": "error", "content": {JSON: {"schema": {"$ref": "#/components/schemas/Error"}}}}));
+    op["responses"] = Value::Object(responses);
+    if !route.auth { op["security"] = json!([]); }
+    if route.agent { op["security"] = json!([{"session": [], "csrf": []}, {"bearer": []}, {"basic": []}]); }
+    op
+}
+
+fn schemas() -> Value {
+    json!({
+        "Error": {
+            "type": "object",
+            "properties": {
+                "status": {"type": "string", "example": "error"},
+                "code": {"type": "string"},
+                "sqlstate": {"type": "string", "example": "42P01"},
+                "message": {"type": "string"},
+                "position": {"type": "integer", "description": "1-based offset of a syntax error"}
+            },
+            "required": ["code", "message"]
+        },
+        "Status": {"type": "object", "properties": {"status": {"type": "string", "example": "ok"}}},
+        "LoginRequest": {
+            "type": "object",
+            "properties": {"username": {"type": "string"}, "password": {"type": "string", "format": "password"}},
+            "required": ["username", "password"]
+        },
+        "CsrfToken": {"type": "object", "properties": {"status": {"type": "string"}, "csrf": {"type": "string"}}},
+        "UseRequest": {"type": "object", "properties": {"name": {"type": "string"}}, "required": ["name"]},
+        "QueryRequest": {"type": "object", "properties": {"query": {"type": "string"}}, "required": ["query"]},
+        "QueryResult": {
+            "type": "object",
+            "properties": {"status": {"type": "string"}, "results": {"description": "rows as objects, or the statement result"}}
+        },
+        "QueryV2Request": {
+            "type": "object",
+            "properties": {
+                "query": {"type": "string"},
+                "params": {"description": "object for :name parameters, array for $n", "oneOf": [{"type": "object"}, {"type": "array"}]},
+                "format": {"type": "string", "enum": ["ndjson", "json-seq"]}
+            },
+            "required": ["query"]
+        },
+        "QueryV2Stream": {
+            "type": "string",
+            "description": "{\"columns\":[...]} header, one JSON array per row, then {\"status\":\"ok\",\"rows\":N}"
+        },
+        "WriteRequest": {
+            "type": "object",
+            "properties": {"records": {"type": "array", "items": {
+                "type": "object",
+                "properties": {"_time": {"type": "integer", "description": "epoch milliseconds"}},
+                "required": ["_time"],
+                "additionalProperties": true
+            }}},
+            "required": ["records"]
+        },
+        "WriteResult": {"type": "object", "properties": {"status": {"type": "string"}, "written": {"type": "integer"}}},
+        "IngestReport": {
+            "type": "object",
+            "properties": {
+                "status": {"type": "string", "enum": ["ok", "partial", "error"]},
+                "rows": {"type": "integer"},
+                "errors": {"type": "integer"},
+                "batches": {"type": "integer"},
+                "error_samples": {"type": "array", "items": {"type": "object", "properties": {"line": {"type": "integer"}, "message": {"type": "string"}}}}
+            }
+        },
+        "ChangeEvents": {"type": "string", "description": "one change event per line"},
+        "ApiKeyRequest": {"type": "object", "properties": {"name": {"type": "string"}}, "required": ["name"]},
+        "ApiKeyCreated": {
+            "type": "object",
+            "properties": {
+                "status": {"type": "string"},
+                "id": {"type": "string"},
+                "name": {"type": "string"},
+                "user": {"type": "string"},
+                "key": {"type": "string", "description": "send as Authorization: Bearer <key>; shown only once"}
+            }
+        },
+        "ApiKeyList": {
+            "type": "object",
+            "properties": {"status": {"type": "string"}, "keys": {"type": "array", "items": {
+                "type": "object",
+                "properties": {"id": {"type": "string"}, "user": {"type": "string"}, "name": {"type": "string"}, "created_at": {"type": "integer"}}
+            }}}
+        },
+        "FileWritten": {
+            "type": "object",
+            "properties": {"status": {"type": "string"}, "path": {"type": "string"}, "etag": {"type": "string"}, "version": {"type": "integer"}, "size": {"type": "integer"}}
+        },
+        "PresignRequest": {
+            "type": "object",
+            "properties": {
+                "database": {"type": "string", "description": "defaults to the session's current database"},
+                "filestore": {"type": "string"},
+                "path": {"type": "string"},
+                "method": {"type": "string", "enum": ["GET", "PUT"]},
+                "expires_in": {"type": "integer", "description": "seconds, at most filestore.presign_max_ttl_secs (default 900)"}
+            },
+            "required": ["filestore", "path"]
+        },
+        "PresignedLink": {
+            "type": "object",
+            "properties": {"status": {"type": "string"}, "method": {"type": "string"}, "url": {"type": "string", "description": "path and query, relative to the server"}, "expires": {"type": "integer", "description": "unix seconds"}}
+        },
+        "KvBatchRequest": {
+            "type": "object",
+            "properties": {
+                "expect": {"type": "object", "additionalProperties": {"type": "integer"}, "description": "key -> version (from READ KEY) it must still be at; 0 for absent"},
+                "writes": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "op": {"type": "string", "enum": ["set", "delete", "lpush", "rpush", "lpop", "rpop", "sadd", "srem", "hset", "hdel"]},
+                            "key": {"type": "string"},
+                            "value": {"description": "set: stored as a string, an integer or JSON"},
+                            "ttl_ms": {"type": "integer"},
+                            "reset_on_access": {"type": "boolean"},
+                            "values": {"type": "array", "items": {"type": "string"}},
+                            "members": {"type": "array", "items": {"type": "string"}},
+                            "fields": {"description": "hset: object of field -> value; hdel: array of fields"}
+                        },
+                        "required": ["op", "key"]
+                    }
+                }
+            },
+            "required": ["writes"]
+        },
+        "KvBatchResult": {
+            "type": "object",
+            "properties": {
+                "status": {"type": "string"},
+                "results": {"type": "array", "items": {"type": "object"}, "description": "one per write, as the single-key statement reports it"},
+                "versions": {"type": "object", "additionalProperties": {"type": "integer"}, "description": "key -> version after the batch; 0 once removed"}
+            }
+        },
+        "ReplicationManifest": {"type": "object"}
+    })
+}
+
+/// The OpenAPI document for `routes`.
+pub fn spec(routes: &[RouteSpec]) -> Value {
+    let mut paths = Map::new();
+    for route in routes {
+        let entry = paths.entry(route.openapi_path()).or_insert_with(|| json!({}));
+        entry[route.method] = operation(route);
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Clarium HTTP API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Log in with POST /login (session cookie) and send the token from GET /csrf as X-CSRF-Token, or send an API key (POST /auth/api-keys) or OIDC token as a bearer token."
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "session": {"type": "apiKey", "in": "cookie", "name": "clarium_session"},
+                "csrf": {"type": "apiKey", "in": "header", "name": "X-CSRF-Token"},
+                "bearer": {"type": "http", "scheme": "bearer", "description": "API key (clk_...) or OIDC JWT"},
+                "basic": {"type": "http", "scheme": "basic"}
+            }
+        },
+        "security": [{"session": [], "csrf": []}, {"bearer": []}]
+    })
+}
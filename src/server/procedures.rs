@@ -0,0 +1,546 @@
+//!
+//! clarium stored procedures
+//! -------------------------
+//! `CREATE PROCEDURE <name>(<param> <type>, ...) AS $$ <body> $$` stores a body of SQL statements
+//! with variables (DECLARE, `:=`), IF / ELSIF / ELSE, LOOP / WHILE with EXIT and CONTINUE,
+//! RAISE, COMMIT / ROLLBACK and nested CALL (see `query::ProcStmt`). Procedures are kept in
+//! `<root>/procedures.json` under their qualified `<db>/<schema>/<name>`, and unqualified names
+//! in the body resolve against that database and schema.
+//!
+//! `CALL <name>(<args>)` runs the body in the server:
+//! - as the calling session's user and role: every statement passes the same command gate as
+//!   an HTTP statement, so a procedure can do nothing its caller could not;
+//! - variables are replaced by SQL literals before a statement runs, and expressions and
+//!   conditions are evaluated by the engine as `SELECT <expr>`;
+//! - the body runs as a transaction over table data. Before a statement first writes a table
+//!   (INSERT, UPDATE, DELETE, COPY FROM) the table's files are copied aside. COMMIT keeps the
+//!   writes and starts a new transaction; ROLLBACK, an error, RAISE EXCEPTION or cancelling the
+//!   CALL restores every table written since. Both end `SET LOCAL` values, as a session
+//!   COMMIT does. DDL, KV writes, CDC events and trigger calls are not undone, and a rollback
+//!   also discards writes other sessions made to those tables meanwhile.
+
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::ident::{qualify_regular_ident, QueryDefaults};
+use crate::server::activity;
+use crate::server::query::{self, mask_sql_literals, Command, ProcParam, ProcStmt};
+use crate::storage::SharedStore;
+
+/// Nesting limit for CALL inside procedures.
+pub const MAX_CALL_DEPTH: usize = 32;
+
+/// One stored procedure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Procedure {
+    /// Qualified `<db>/<schema>/<name>`
+    pub name: String,
+    pub params: Vec<ProcParam>,
+    pub body: String,
+    /// User who created (or last replaced) the procedure
+    pub owner: String,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+impl Procedure {
+    /// Defaults for unqualified names in the body: the procedure's own database and schema.
+    fn defaults(&self) -> QueryDefaults {
+        let mut parts = self.name.splitn(3, '/');
+        let db = parts.next().unwrap_or(crate::ident::DEFAULT_DB);
+        let schema = parts.next().unwrap_or(crate::ident::DEFAULT_SCHEMA);
+        QueryDefaults::new(db, schema)
+    }
+}
+
+static FILE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn root_of(store: &SharedStore) -> String { store.root_path().to_string_lossy().to_string() }
+
+fn procedures_path(db_root: &str) -> PathBuf { Path::new(db_root).join("procedures.json") }
+
+fn read_procedures(db_root: &str) -> Result<Vec<Procedure>> {
+    let p = procedures_path(db_root);
+    if !p.exists() { return Ok(Vec::new()); }
+    Ok(serde_json::from_slice(&fs::read(p)?)?)
+}
+
+fn write_procedures(db_root: &str, items: &[Procedure]) -> Result<()> {
+    let p = procedures_path(db_root);
+    let tmp = p.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(items)?)?;
+    fs::rename(&tmp, &p)?;
+    Ok(())
+}
+
+fn undefined_procedure(name: &str) -> anyhow::Error {
+    AppError::not_found("undefined_function".to_string(), format!("procedure {} does not exist", name)).into()
+}
+
+/// All procedures, ordered by name.
+pub fn list_procedures(db_root: &str) -> Result<Vec<Procedure>> {
+    let mut procs = read_procedures(db_root)?;
+    procs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(procs)
+}
+
+pub fn get_procedure(db_root: &str, name: &str) -> Result<Option<Procedure>> {
+    Ok(read_procedures(db_root)?.into_iter().find(|p| p.name == name))
+}
+
+/// Add a procedure, or replace one of the same name when `or_replace`.
+pub fn create_procedure(db_root: &str, proc: Procedure, or_replace: bool) -> Result<()> {
+    let _lock = FILE_LOCK.lock();
+    let mut procs = read_procedures(db_root)?;
+    match procs.iter_mut().find(|p| p.name == proc.name) {
+        Some(existing) if or_replace => *existing = proc,
+        Some(_) => return Err(AppError::conflict("duplicate_function".to_string(), format!("procedure {} already exists", proc.name)).into()),
+        None => procs.push(proc),
+    }
+    write_procedures(db_root, &procs)
+}
+
+/// Remove a procedure. Returns false when it does not exist.
+pub fn drop_procedure(db_root: &str, name: &str) -> Result<bool> {
+    let _lock = FILE_LOCK.lock();
+    let mut procs = read_procedures(db_root)?;
+    let before = procs.len();
+    procs.retain(|p| p.name != name);
+    if procs.len() == before { return Ok(false); }
+    write_procedures(db_root, &procs)?;
+    Ok(true)
+}
+
+// ---- Transactions ----
+
+fn copy_tree(src: &Path, dst: &Path, skip: Option<&str>) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if skip.is_some_and(|s| entry.file_name() == s) { continue; }
+        let to = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() { copy_tree(&entry.path(), &to, None)?; } else { fs::copy(entry.path(), to)?; }
+    }
+    Ok(())
+}
+
+/// Remove a table's files, keeping its CDC changelog.
+fn clear_table(dir: &Path) -> Result<()> {
+    if !dir.exists() { return Ok(()); }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name() == crate::storage::cdc::CDC_DIR { continue; }
+        if entry.file_type()?.is_dir() { fs::remove_dir_all(entry.path())?; } else { fs::remove_file(entry.path())?; }
+    }
+    Ok(())
+}
+
+/// Tables written by the open transaction, as they were before its first write to each.
+#[derive(Default)]
+struct Undo {
+    /// `<root>/.system/procedure-undo-<id>`, created on the first write
+    dir: Option<PathBuf>,
+    /// Table directory and whether it existed, in the order first written
+    saved: Vec<(PathBuf, bool)>,
+}
+
+impl Undo {
+    fn save(&mut self, store: &SharedStore, table_dir: &Path) -> Result<()> {
+        if self.saved.iter().any(|(d, _)| d == table_dir) { return Ok(()); }
+        let dir = match &self.dir {
+            Some(d) => d.clone(),
+            None => crate::system_paths::system_root(&store.root_path()).join(format!("procedure-undo-{}", uuid::Uuid::new_v4())),
+        };
+        self.dir = Some(dir.clone());
+        let existed = table_dir.exists();
+        if existed {
+            let _guard = store.0.lock();
+            copy_tree(table_dir, &dir.join(self.saved.len().to_string()), Some(crate::storage::cdc::CDC_DIR))?;
+        }
+        self.saved.push((table_dir.to_path_buf(), existed));
+        Ok(())
+    }
+
+    fn commit(&mut self) {
+        self.saved.clear();
+        if let Some(dir) = self.dir.take() { let _ = fs::remove_dir_all(dir); }
+    }
+
+    fn rollback(&mut self, store: &SharedStore) -> Result<()> {
+        let saved = std::mem::take(&mut self.saved);
+        let dir = self.dir.take();
+        let mut result = Ok(());
+        {
+            let _guard = store.0.lock();
+            for (i, (table_dir, existed)) in saved.iter().enumerate() {
+                let restored = clear_table(table_dir).and_then(|_| match (existed, &dir) {
+                    (true, Some(d)) => copy_tree(&d.join(i.to_string()), table_dir, None),
+                    _ => { let _ = fs::remove_dir(table_dir); Ok(()) }
+                });
+                if let Err(e) = restored {
+                    tracing::warn!(target: "clarium::procedures", "failed to roll back {}: {}", table_dir.display(), e);
+                    if result.is_ok() { result = Err(e); }
+                }
+            }
+        }
+        if let Some(dir) = dir { let _ = fs::remove_dir_all(dir); }
+        crate::server::exec::result_cache::clear();
+        result
+    }
+}
+
+/// Table a DML statement writes.
+fn written_table(cmd: &Command) -> Option<&str> {
+    match cmd {
+        Command::Insert { table, .. }
+        | Command::InsertSelect { table, .. }
+        | Command::Update { table, .. }
+        | Command::CopyFrom { table, .. } => Some(table),
+        Command::DeleteRows { database, .. } => Some(database),
+        _ => None,
+    }
+}
+
+// ---- Values ----
+
+struct Var {
+    type_name: Option<String>,
+    value: Value,
+}
+
+/// Variables of one procedure invocation.
+struct Scope {
+    vars: HashMap<String, Var>,
+    defaults: QueryDefaults,
+}
+
+pub(crate) fn sql_literal(v: &Value) -> String {
+    match v {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(b) => (if *b { "TRUE" } else { "FALSE" }).to_string(),
+        Value::Number(n) if n.as_f64().is_some_and(|f| f < 0.0) => format!("({})", n),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+        other => format!("'{}'", other.to_string().replace('\'', "''")),
+    }
+}
+
+/// Replace variable references in `sql` by literals. Names inside literals and quoted
+/// identifiers, qualified names, function names and `AS` aliases are left alone.
+fn substitute(sql: &str, vars: &HashMap<String, Var>) -> String {
+    if vars.is_empty() { return sql.to_string(); }
+    let masked = mask_sql_literals(sql);
+    let b = masked.as_bytes();
+    let word = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+    let mut out = String::with_capacity(sql.len());
+    let (mut last, mut i) = (0usize, 0usize);
+    let mut after_as = false;
+    while i < b.len() {
+        let c = b[i];
+        if c.is_ascii_digit() {
+            while i < b.len() && word(b[i]) { i += 1; }
+            continue;
+        }
+        if !(c.is_ascii_alphabetic() || c == b'_') {
+            if !c.is_ascii_whitespace() { after_as = false; }
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < b.len() && word(b[i]) { i += 1; }
+        let before = masked[..start].trim_end().bytes().last();
+        let next = masked[i..].trim_start().bytes().next();
+        let qualified = matches!(before, Some(b'.' | b'/' | b':' | b'$')) || matches!(next, Some(b'(' | b'.' | b'/'));
+        if !qualified && !after_as {
+            if let Some(v) = vars.get(&sql[start..i].to_ascii_lowercase()) {
+                out.push_str(&sql[last..start]);
+                out.push_str(&sql_literal(&v.value));
+                last = i;
+            }
+        }
+        after_as = sql[start..i].eq_ignore_ascii_case("AS");
+    }
+    out.push_str(&sql[last..]);
+    out
+}
+
+/// Value of `text` when it is a single literal, without asking the engine.
+fn literal_value(text: &str) -> Option<Value> {
+    let t = text.trim();
+    if let Some(inner) = t.strip_prefix('(').and_then(|x| x.strip_suffix(')')).filter(|x| !x.contains(['(', ')'])) {
+        return literal_value(inner);
+    }
+    let up = t.to_ascii_uppercase();
+    match up.as_str() {
+        "NULL" => return Some(Value::Null),
+        "TRUE" => return Some(Value::Bool(true)),
+        "FALSE" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    if let Some(rest) = up.strip_prefix("NOT ") {
+        return match literal_value(rest)? { Value::Bool(v) => Some(Value::Bool(!v)), _ => None };
+    }
+    if let Ok(i) = t.parse::<i64>() { return Some(Value::from(i)); }
+    if t.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+        if let Some(n) = t.parse::<f64>().ok().and_then(serde_json::Number::from_f64) { return Some(Value::Number(n)); }
+    }
+    let inner = t.strip_prefix('\'')?.strip_suffix('\'')?;
+    (!inner.replace("''", "").contains('\'')).then(|| Value::String(inner.replace("''", "'")))
+}
+
+/// Integral floats from the engine become integers, so counters stay integers.
+pub(crate) fn tidy(v: Value) -> Value {
+    match v.as_f64() {
+        Some(f) if v.is_f64() && f.fract() == 0.0 && f.abs() < 9.0e15 => Value::from(f as i64),
+        _ => v,
+    }
+}
+
+pub(crate) fn truthy(v: &Value) -> bool {
+    match v {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        Value::String(s) => matches!(s.to_ascii_lowercase().as_str(), "t" | "true" | "1" | "yes" | "on"),
+        _ => false,
+    }
+}
+
+/// Whether `v` already has the SQL type `type_name`, so no cast is needed.
+fn has_type(v: &Value, type_name: &str) -> bool {
+    let t = type_name.trim().to_ascii_lowercase();
+    match v {
+        Value::Number(n) if n.is_i64() && matches!(t.as_str(), "smallint" | "int2" | "int" | "integer" | "int4" | "bigint" | "int8") => true,
+        Value::Number(_) => matches!(t.as_str(), "real" | "float4" | "double precision" | "float8") || t.starts_with("numeric") || t.starts_with("decimal"),
+        Value::String(_) => t == "text" || t.starts_with("varchar") || t.starts_with("character varying"),
+        Value::Bool(_) => t == "boolean" || t == "bool",
+        _ => false,
+    }
+}
+
+/// A query (rather than an expression) on the right of `:=`, without enclosing parentheses.
+fn as_query(text: &str) -> Option<&str> {
+    let t = text.trim();
+    let t = t.strip_prefix('(').and_then(|x| x.strip_suffix(')')).map(str::trim).unwrap_or(t);
+    let first = t.split(|c: char| !c.is_ascii_alphanumeric()).next().unwrap_or("");
+    (first.eq_ignore_ascii_case("SELECT") || first.eq_ignore_ascii_case("WITH")).then_some(t)
+}
+
+// ---- Execution ----
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// How a block finished.
+enum Flow {
+    Next,
+    Exit,
+    Continue,
+    Return,
+}
+
+/// One CALL, including the procedures it calls.
+struct Run<'a> {
+    store: &'a SharedStore,
+    root: String,
+    /// Session user and role statements are checked against; None without a session
+    user: Option<String>,
+    role: Option<String>,
+    undo: Undo,
+}
+
+impl Drop for Run<'_> {
+    // A cancelled CALL is dropped mid-statement: roll back what it wrote
+    fn drop(&mut self) {
+        if self.undo.saved.is_empty() { self.undo.commit(); } else { let _ = self.undo.rollback(self.store); }
+    }
+}
+
+impl Run<'_> {
+    /// Run one statement (variables already substituted) through the command gate and the engine.
+    async fn execute(&mut self, defaults: &QueryDefaults, sql: &str) -> Result<Value> {
+        let cmd = query::parse(sql).map_err(|e| AppError::from_parse_error(&e, sql))?;
+        if let Some(user) = &self.user {
+            if !super::exec::exec_authorize::statement_allowed(self.store, &self.root, user, self.role.as_deref(), &[], &cmd, defaults).await {
+                return Err(AppError::permission("insufficient_privilege".to_string(), format!("permission denied for \"{}\": {}", user, sql)).into());
+            }
+        }
+        if let Some(table) = written_table(&cmd) {
+            let dir = self.store.0.lock().db_dir(&qualify_regular_ident(table, defaults));
+            self.undo.save(self.store, &dir)?;
+        }
+        crate::server::exec::execute_query_with_defaults(self.store, sql, defaults).await
+    }
+
+    async fn eval(&mut self, scope: &Scope, expr: &str) -> Result<Value> {
+        let text = substitute(expr, &scope.vars);
+        if let Some(v) = literal_value(&text) { return Ok(v); }
+        let query = as_query(&text);
+        let sql = match query { Some(q) => q.to_string(), None => format!("SELECT {} AS v", text.trim()) };
+        let rows = self.execute(&scope.defaults, &sql).await?;
+        let Some(row) = rows.as_array().and_then(|r| r.first()).and_then(|r| r.as_object()) else { return Ok(Value::Null) };
+        if query.is_some() && row.len() != 1 { bail!("query assigned to a variable must return one column: {}", sql); }
+        Ok(tidy(row.values().next().cloned().unwrap_or(Value::Null)))
+    }
+
+    async fn cond(&mut self, scope: &Scope, cond: &str) -> Result<bool> {
+        let text = substitute(cond, &scope.vars);
+        if let Some(v) = literal_value(&text) { return Ok(truthy(&v)); }
+        let rows = self.execute(&scope.defaults, &format!("SELECT CASE WHEN {} THEN 1 ELSE 0 END AS v", text.trim())).await?;
+        Ok(rows.as_array().and_then(|r| r.first()).and_then(|r| r.get("v")).is_some_and(truthy))
+    }
+
+    async fn when(&mut self, scope: &Scope, when: &Option<String>) -> Result<bool> {
+        match when { Some(c) => self.cond(scope, c).await, None => Ok(true) }
+    }
+
+    /// Cast `v` to the variable's declared type.
+    async fn coerce(&mut self, scope: &Scope, v: Value, type_name: Option<&str>) -> Result<Value> {
+        match type_name {
+            Some(t) if !v.is_null() && !has_type(&v, t) => {
+                let rows = self.execute(&scope.defaults, &format!("SELECT {}::{} AS v", sql_literal(&v), t)).await?;
+                Ok(tidy(rows.as_array().and_then(|r| r.first()).and_then(|r| r.get("v")).cloned().unwrap_or(Value::Null)))
+            }
+            _ => Ok(v),
+        }
+    }
+
+    async fn raise(&mut self, scope: &Scope, level: &str, message: &str, args: &[String]) -> Result<()> {
+        let mut values = Vec::with_capacity(args.len());
+        for a in args { values.push(self.eval(scope, a).await?); }
+        let mut values = values.into_iter();
+        let mut text = String::with_capacity(message.len());
+        let mut chars = message.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' { text.push(c); continue; }
+            if chars.peek() == Some(&'%') { chars.next(); text.push('%'); continue; }
+            match values.next() {
+                Some(Value::String(s)) => text.push_str(&s),
+                Some(Value::Null) => text.push_str("<NULL>"),
+                Some(v) => text.push_str(&v.to_string()),
+                None => bail!("too few parameters specified for RAISE"),
+            }
+        }
+        match level {
+            "EXCEPTION" => return Err(AppError::exec("raise_exception".to_string(), text).into()),
+            "WARNING" => tracing::warn!(target: "clarium::procedures", "{}", text),
+            "DEBUG" => tracing::debug!(target: "clarium::procedures", "{}", text),
+            _ => tracing::info!(target: "clarium::procedures", "{}", text),
+        }
+        Ok(())
+    }
+
+    fn block<'s>(&'s mut self, scope: &'s mut Scope, stmts: &'s [ProcStmt], depth: usize) -> BoxFuture<'s, Result<Flow>> {
+        Box::pin(async move {
+            for stmt in stmts {
+                match stmt {
+                    ProcStmt::Sql(sql) => {
+                        let sql = substitute(sql, &scope.vars);
+                        self.execute(&scope.defaults, &sql).await?;
+                    }
+                    ProcStmt::Declare { name, type_name, expr } => {
+                        let value = match expr {
+                            Some(e) => { let v = self.eval(scope, e).await?; self.coerce(scope, v, type_name.as_deref()).await? }
+                            None => Value::Null,
+                        };
+                        scope.vars.insert(name.clone(), Var { type_name: type_name.clone(), value });
+                    }
+                    ProcStmt::Assign { name, expr } => {
+                        let type_name = scope.vars.get(name).ok_or_else(|| anyhow!("variable \"{}\" is not declared", name))?.type_name.clone();
+                        let v = self.eval(scope, expr).await?;
+                        let value = self.coerce(scope, v, type_name.as_deref()).await?;
+                        if let Some(var) = scope.vars.get_mut(name) { var.value = value; }
+                    }
+                    ProcStmt::If { branches, otherwise } => {
+                        let mut chosen = otherwise.as_slice();
+                        for (cond, body) in branches {
+                            if self.cond(scope, cond).await? { chosen = body.as_slice(); break; }
+                        }
+                        match self.block(scope, chosen, depth).await? {
+                            Flow::Next => {}
+                            flow => return Ok(flow),
+                        }
+                    }
+                    ProcStmt::Loop { cond, body } => loop {
+                        // Yield so a runaway loop can still be cancelled with KILL QUERY
+                        tokio::task::yield_now().await;
+                        if let Some(c) = cond {
+                            if !self.cond(scope, c).await? { break; }
+                        }
+                        match self.block(scope, body, depth).await? {
+                            Flow::Exit => break,
+                            Flow::Return => return Ok(Flow::Return),
+                            Flow::Next | Flow::Continue => {}
+                        }
+                    },
+                    ProcStmt::Exit { when } => if self.when(scope, when).await? { return Ok(Flow::Exit); },
+                    ProcStmt::Continue { when } => if self.when(scope, when).await? { return Ok(Flow::Continue); },
+                    ProcStmt::Return => return Ok(Flow::Return),
+                    ProcStmt::Raise { level, message, args } => self.raise(scope, level, message, args).await?,
+                    ProcStmt::Commit => {
+                        self.undo.commit();
+                        crate::server::guc::end_transaction(self.store);
+                    }
+                    ProcStmt::Rollback => {
+                        self.undo.rollback(self.store)?;
+                        crate::server::guc::end_transaction(self.store);
+                    }
+                    ProcStmt::Call { name, args } => self.call(scope, name, args, depth + 1).await?,
+                }
+            }
+            Ok(Flow::Next)
+        })
+    }
+
+    /// Evaluate `args` in the caller's scope and run the procedure `name`.
+    fn call<'s>(&'s mut self, caller: &'s Scope, name: &'s str, args: &'s [String], depth: usize) -> BoxFuture<'s, Result<()>> {
+        Box::pin(async move {
+            if depth >= MAX_CALL_DEPTH {
+                return Err(AppError::exec("statement_too_complex".to_string(), format!("procedure calls nested more than {} deep", MAX_CALL_DEPTH)).into());
+            }
+            let qualified = qualify_regular_ident(name, &caller.defaults);
+            let proc = get_procedure(&self.root, &qualified)?.ok_or_else(|| undefined_procedure(&qualified))?;
+            if args.len() != proc.params.len() {
+                return Err(AppError::user("undefined_function".to_string(),
+                    format!("procedure {} takes {} argument(s), got {}", qualified, proc.params.len(), args.len())).into());
+            }
+            let stmts = query::parse_procedure_body(&proc.body)?;
+            let mut scope = Scope { vars: HashMap::new(), defaults: proc.defaults() };
+            for (param, arg) in proc.params.iter().zip(args) {
+                let v = self.eval(caller, arg).await?;
+                let value = self.coerce(&scope, v, Some(param.type_name.as_str())).await?;
+                scope.vars.insert(param.name.clone(), Var { type_name: Some(param.type_name.clone()), value });
+            }
+            self.block(&mut scope, &stmts, depth).await?;
+            Ok(())
+        })
+    }
+}
+
+/// CALL `name(args)` as the session user. Whatever the body leaves uncommitted is committed
+/// when it finishes and rolled back when it fails.
+pub async fn call(store: &SharedStore, name: &str, args: &[String]) -> Result<Value> {
+    let mut run = Run { store, root: root_of(store), user: activity::current_user(), role: activity::current_role(), undo: Undo::default() };
+    let caller = Scope { vars: HashMap::new(), defaults: crate::system::current_query_defaults() };
+    match run.call(&caller, name, args, 0).await {
+        Ok(()) => {
+            run.undo.commit();
+            Ok(serde_json::json!({"status": "ok"}))
+        }
+        Err(e) => {
+            if let Err(re) = run.undo.rollback(store) {
+                tracing::warn!(target: "clarium::procedures", "rollback after failed CALL {} was incomplete: {}", name, re);
+            }
+            Err(e)
+        }
+    }
+}
+
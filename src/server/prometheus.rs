@@ -0,0 +1,273 @@
+//! Prometheus remote-write ingestion: `POST /api/v1/write`.
+//!
+//! The body is a snappy-compressed (block format) protobuf `WriteRequest`. Query parameters pick
+//! where samples go:
+//! - `db`, `schema`: target database and schema (session/server defaults otherwise);
+//! - `mode=per_metric` (default): one time table per metric name, with a column per label and
+//!   the sample in `value`;
+//! - `mode=wide&table=<name>`: one time table (default `prometheus`) with a column per metric;
+//!   samples of the same series and timestamp share a row.
+//!
+//! Metric names are normalized like identifiers (`:` becomes `_`). Stale markers and other
+//! non-finite samples are skipped. Agents authenticate like the InfluxDB endpoint (Basic auth,
+//! an API key, a bearer token or a session; see `http_auth`). The response is `204 No Content`; payloads that do not decode
+//! get `400` so Prometheus drops them instead of retrying, write failures get `500`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::{http_auth::AuthContext, quota, replication, AppState};
+use crate::storage::Record;
+
+/// One sample of a series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub value: f64,
+    pub timestamp_ms: i64,
+}
+
+/// One series of a `WriteRequest`: its labels (`__name__` included) and samples.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeSeries {
+    pub labels: Vec<(String, String)>,
+    pub samples: Vec<Sample>,
+}
+
+// Minimal protobuf reader for the WriteRequest messages
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self { Self { buf, pos: 0 } }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut out = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = *self.buf.get(self.pos).ok_or_else(|| anyhow!("truncated varint"))?;
+            self.pos += 1;
+            out |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 { return Ok(out); }
+        }
+        bail!("varint too long")
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).filter(|e| *e <= self.buf.len()).ok_or_else(|| anyhow!("truncated field"))?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    // Next (field number, value); None at the end of the message
+    fn next(&mut self) -> Result<Option<(u64, Field<'a>)>> {
+        if self.pos >= self.buf.len() { return Ok(None); }
+        let key = self.varint()?;
+        let field = match key & 7 {
+            0 => Field::Varint(self.varint()?),
+            1 => Field::Fixed64(u64::from_le_bytes(self.take(8)?.try_into()?)),
+            2 => { let n = self.varint()? as usize; Field::Bytes(self.take(n)?) }
+            5 => { self.take(4)?; Field::Fixed32 }
+            w => bail!("unsupported wire type {}", w),
+        };
+        Ok(Some((key >> 3, field)))
+    }
+}
+
+fn decode_label(buf: &[u8]) -> Result<(String, String)> {
+    let (mut name, mut value) = (String::new(), String::new());
+    let mut r = Reader::new(buf);
+    while let Some((num, f)) = r.next()? {
+        match (num, f) {
+            (1, Field::Bytes(b)) => name = String::from_utf8(b.to_vec())?,
+            (2, Field::Bytes(b)) => value = String::from_utf8(b.to_vec())?,
+            _ => {}
+        }
+    }
+    Ok((name, value))
+}
+
+fn decode_sample(buf: &[u8]) -> Result<Sample> {
+    let mut s = Sample { value: 0.0, timestamp_ms: 0 };
+    let mut r = Reader::new(buf);
+    while let Some((num, f)) = r.next()? {
+        match (num, f) {
+            (1, Field::Fixed64(bits)) => s.value = f64::from_bits(bits),
+            (2, Field::Varint(v)) => s.timestamp_ms = v as i64,
+            _ => {}
+        }
+    }
+    Ok(s)
+}
+
+/// Decode an uncompressed `WriteRequest` (metadata and exemplars are ignored).
+pub fn decode_write_request(buf: &[u8]) -> Result<Vec<TimeSeries>> {
+    let mut out = Vec::new();
+    let mut r = Reader::new(buf);
+    while let Some((num, f)) = r.next()? {
+        let (1, Field::Bytes(ts_buf)) = (num, f) else { continue };
+        let mut ts = TimeSeries::default();
+        let mut tr = Reader::new(ts_buf);
+        while let Some((tnum, tf)) = tr.next()? {
+            match (tnum, tf) {
+                (1, Field::Bytes(b)) => ts.labels.push(decode_label(b)?),
+                (2, Field::Bytes(b)) => ts.samples.push(decode_sample(b)?),
+                _ => {}
+            }
+        }
+        out.push(ts);
+    }
+    Ok(out)
+}
+
+/// Snappy block decompression of a remote-write body.
+pub fn decompress(body: &[u8], max_bytes: usize) -> Result<Vec<u8>> {
+    let len = snap::raw::decompress_len(body).map_err(|e| anyhow!("invalid snappy payload: {}", e))?;
+    if len > max_bytes { bail!("decompressed payload of {} bytes exceeds limits.ingest_max_body_mb", len); }
+    snap::raw::Decoder::new().decompress_vec(body).map_err(|e| anyhow!("invalid snappy payload: {}", e))
+}
+
+/// How samples map to tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+    /// One table per metric, samples in `value`
+    PerMetric,
+    /// One table with a column per metric
+    Wide(String),
+}
+
+fn metric_ident(name: &str) -> String {
+    crate::ident::normalize_identifier(&name.replace(':', "_"))
+}
+
+/// Records per table name; returns them with the number of skipped samples.
+pub fn to_records(series: &[TimeSeries], layout: &Layout) -> (BTreeMap<String, Vec<Record>>, usize) {
+    let mut out: BTreeMap<String, Vec<Record>> = BTreeMap::new();
+    let mut skipped = 0usize;
+    // wide layout: (labels, timestamp) -> row index in the table
+    let mut wide_rows: BTreeMap<(Vec<(String, String)>, i64), usize> = BTreeMap::new();
+    for ts in series {
+        let Some(name) = ts.labels.iter().find(|(k, _)| k == "__name__").map(|(_, v)| metric_ident(v)) else {
+            skipped += ts.samples.len();
+            continue;
+        };
+        let mut labels: Vec<(String, String)> = ts.labels.iter().filter(|(k, _)| k != "__name__").cloned().collect();
+        labels.sort();
+        for s in &ts.samples {
+            if !s.value.is_finite() { skipped += 1; continue; }
+            match layout {
+                Layout::PerMetric => {
+                    let mut row: Map<String, Value> = labels.iter().map(|(k, v)| (k.clone(), Value::String(v.clone()))).collect();
+                    row.insert("value".to_string(), Value::from(s.value));
+                    out.entry(name.clone()).or_default().push(Record { _time: s.timestamp_ms, sensors: row });
+                }
+                Layout::Wide(table) => {
+                    let rows = out.entry(table.clone()).or_default();
+                    let idx = *wide_rows.entry((labels.clone(), s.timestamp_ms)).or_insert_with(|| {
+                        let row: Map<String, Value> = labels.iter().map(|(k, v)| (k.clone(), Value::String(v.clone()))).collect();
+                        rows.push(Record { _time: s.timestamp_ms, sensors: row });
+                        rows.len() - 1
+                    });
+                    rows[idx].sensors.insert(name.clone(), Value::from(s.value));
+                }
+            }
+        }
+    }
+    (out, skipped)
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct RemoteWriteParams {
+    db: Option<String>,
+    schema: Option<String>,
+    mode: Option<String>,
+    table: Option<String>,
+}
+
+fn plain(status: StatusCode, message: String) -> Response {
+    (status, message).into_response()
+}
+
+pub(super) async fn remote_write_handler(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    headers: HeaderMap,
+    Query(params): Query<RemoteWriteParams>,
+    body: Bytes,
+) -> Response {
+    let username = auth.username();
+    let layout = match params.mode.as_deref().map(|m| m.to_ascii_lowercase()) {
+        None => Layout::PerMetric,
+        Some(m) if m == "per_metric" || m == "metric" => Layout::PerMetric,
+        Some(m) if m == "wide" => Layout::Wide(crate::ident::normalize_identifier(params.table.as_deref().unwrap_or("prometheus"))),
+        Some(other) => return plain(StatusCode::BAD_REQUEST, format!("unknown mode '{}' (expected per_metric or wide)", other)),
+    };
+    let (cur_db, cur_schema) = auth.current_defaults(&state).await;
+    let database = crate::ident::normalize_identifier(&params.db.clone().unwrap_or(cur_db));
+    let schema = crate::ident::normalize_identifier(&params.schema.clone().unwrap_or(cur_schema));
+    if !crate::identity::check_command_allowed_async(&state.store, username, crate::security::CommandKind::Insert, Some(&database)).await {
+        return plain(StatusCode::FORBIDDEN, "forbidden".to_string());
+    }
+    if let Err(e) = replication::ensure_writable() {
+        return plain(StatusCode::FORBIDDEN, e.to_string());
+    }
+    let max = (crate::config::current().limits.ingest_max_body_mb as usize).saturating_mul(1024 * 1024);
+    // Remote-write bodies are always snappy; only an explicit identity encoding is read as is
+    let identity = headers.get(header::CONTENT_ENCODING).and_then(|v| v.to_str().ok()).is_some_and(|v| v.eq_ignore_ascii_case("identity"));
+    let decoded = if identity { Ok(body.to_vec()) } else { decompress(&body, max) };
+    let series = match decoded.and_then(|b| decode_write_request(&b)) {
+        Ok(s) => s,
+        Err(e) => return plain(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    let (tables, skipped) = to_records(&series, &layout);
+    // Per-user quotas: the request counts as a statement and its records as ingested bytes
+    let admitted = match quota::admit_query(username).await {
+        Ok(()) => quota::charge_ingest_as(username, tables.values().map(|r| quota::records_size(r)).sum()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = admitted {
+        return plain(StatusCode::TOO_MANY_REQUESTS, e.to_string());
+    }
+    let store = state.store.clone();
+    let written = tokio::task::spawn_blocking(move || -> Result<usize> {
+        let guard = store.0.lock();
+        let mut samples = 0usize;
+        for (table, records) in tables.iter() {
+            let path = format!("{}/{}/{}.time", database, schema, table);
+            guard.create_table(&path).ok();
+            guard.write_records(&path, records)?;
+            samples += records.len();
+        }
+        Ok(samples)
+    }).await;
+    match written {
+        Ok(Ok(rows)) => {
+            crate::tprintln!("[prometheus] user={} series={} rows={} skipped={}", username, series.len(), rows, skipped);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(Err(e)) => {
+            tracing::error!("remote write failed: {e}");
+            plain(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        }
+        Err(e) => {
+            tracing::error!("remote write task failed: {e}");
+            plain(StatusCode::INTERNAL_SERVER_ERROR, "internal server error".to_string())
+        }
+    }
+}
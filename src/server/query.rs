@@ -20,9 +20,15 @@ pub mod query_parse_user;
 pub mod query_parse_where_tokens;
 pub mod query_parse_where;
 pub mod query_parse_txn;
+pub mod query_parse_graph_dml;
 pub mod query_parse_alter;
 pub mod query_parse_vector;
 pub mod query_parse_filestore;
+pub mod query_parse_copy;
+pub mod query_parse_backup;
+pub mod query_parse_hints;
+pub mod query_parse_procedure;
+pub mod query_parse_script_test;
 
 // Import MATCH parser entrypoint for top-level dispatch
 use crate::server::query::query_parse_match::parse_match;
@@ -49,6 +55,11 @@ pub use query_parse_where::*;
 pub use query_parse_alter::*;
 pub use query_parse_vector::*;
 pub use query_parse_filestore::*;
+pub use query_parse_copy::*;
+pub use query_parse_backup::*;
+pub use query_parse_hints::*;
+pub use query_parse_procedure::*;
+pub use query_parse_script_test::*;
 
 
 
@@ -64,9 +75,16 @@ pub enum Command {
     DropView { name: String, if_exists: bool },
     // SHOW VIEW <name>
     ShowView { name: String },
+    // Named slice catalog
+    // CREATE [OR REPLACE] SLICE [IF NOT EXISTS] <name> AS SLICE ...
+    CreateSlice { name: String, or_replace: bool, if_not_exists: bool, definition_sql: String },
+    // DROP SLICE [IF EXISTS] <name>
+    DropSlice { name: String, if_exists: bool },
+    // SHOW SLICES
+    ShowSlices,
     Calculate { target_sensor: String, query: Query },
-    // UPDATE <table> SET col = value[, ...] [WHERE ...]
-    Update { table: String, assignments: Vec<(String, ArithTerm)>, where_clause: Option<WhereExpr> },
+    // UPDATE <table> SET col = <expr>[, ...] [FROM <table> [[AS] alias]] [WHERE ...]
+    Update { table: String, assignments: Vec<(String, ArithExpr)>, from: Option<UpdateFrom>, where_clause: Option<WhereExpr> },
     DeleteRows { database: String, where_clause: Option<WhereExpr> },
     DeleteColumns { database: String, columns: Vec<String>, where_clause: Option<WhereExpr> },
     SchemaShow { database: String },
@@ -105,11 +123,20 @@ pub enum Command {
     ReadKey { database: String, store: String, key: String },
     DropKey { database: String, store: String, key: String },
     RenameKey { database: String, store: String, from: String, to: String },
+    // List/set/hash operations: WRITE KEY <key> IN <db>.store.<store> LPUSH|RPUSH|LPOP|RPOP|SADD|SREM|HSET|HDEL ...,
+    // READ KEY <key> IN <db>.store.<store> HGET <field>
+    KeyOp { database: String, store: String, key: String, op: KvOp },
+    // MULTI IN <db>.store.<store> [EXPECT <key> VERSION <n>, ...] EXEC $$ <write>; ... $$
+    KvBatch { database: String, store: String, expect: Vec<(String, u64)>, writes: Vec<KvBatchWrite> },
+    // WATCH KEY <key> | PREFIX '<prefix>' IN <db>.store.<store>; UNWATCH <id> | ALL (WebSocket only)
+    Watch { database: String, store: String, target: crate::storage::kv_events::WatchTarget },
+    Unwatch { id: Option<u64> },
     // Scripts/bytecode cache maintenance
     ClearScriptCache { scope: ScriptCacheScope, persistent: bool },
     UserAdd { username: String, password: String, is_admin: bool, perms: Vec<String>, scope_db: Option<String> },
     UserDelete { username: String, scope_db: Option<String> },
-    UserAlter { username: String, new_password: Option<String>, is_admin: Option<bool>, perms: Option<Vec<String>>, scope_db: Option<String> },
+    // EXPIRE PASSWORD forces a password change at next login; UNLOCK lifts a failed-login lockout
+    UserAlter { username: String, new_password: Option<String>, is_admin: Option<bool>, perms: Option<Vec<String>>, scope_db: Option<String>, expire_password: bool, unlock: bool },
     // Scripts
     CreateScript { kind: Option<ScriptCreateKind>, path: String, code: String },
     DropScript { path: String },
@@ -118,26 +145,22 @@ pub enum Command {
     // Global session-affecting commands
     UseDatabase { name: String },
     UseSchema { name: String },
-    Set { variable: String, value: String },
+    /// SET [SESSION | LOCAL] variable {TO | =} value; LOCAL lasts until the end of the transaction
+    Set { variable: String, value: String, local: bool },
+    /// RESET variable | RESET ALL (None)
+    Reset { variable: Option<String> },
+    /// ALTER DATABASE db SET variable = value | RESET variable (value None) | RESET ALL (variable None)
+    AlterDatabaseSet { database: String, variable: Option<String>, value: Option<String> },
     // SHOW commands
-    ShowTransactionIsolation,
-    ShowStandardConformingStrings,
-    ShowServerVersion,
-    ShowClientEncoding,
-    ShowServerEncoding,
-    ShowDateStyle,
-    ShowIntegerDateTimes,
-    ShowTimeZone,
-    ShowSearchPath,
-    ShowDefaultTransactionIsolation,
-    ShowTransactionReadOnly,
-    ShowApplicationName,
-    ShowExtraFloatDigits,
+    /// SHOW <parameter> from the session GUC registry
+    ShowVariable { name: String },
     ShowAll,
     ShowSchemas,
     ShowTables,
     ShowObjects,
     ShowScripts,
+    /// SHOW CACHE STATS: result cache counters
+    ShowCacheStats,
     // Vector index catalog
     CreateVectorIndex { name: String, table: String, column: String, algo: String, options: Vec<(String, String)> },
     DropVectorIndex { name: String },
@@ -147,6 +170,8 @@ pub enum Command {
     // Vector index lifecycle
     BuildVectorIndex { name: String, options: Vec<(String, String)> },
     ReindexVectorIndex { name: String },
+    /// REBUILD VECTOR INDEX <name> (None: REBUILD VECTOR INDEXES, every stale index)
+    RebuildVectorIndex { name: Option<String> },
     ShowVectorIndexStatus { name: Option<String> },
     // Graph catalog
     // Optional integration with GraphStore engine via `USING GRAPHSTORE [CONFIG <name>] [WITH (k=v, ...)]`.
@@ -180,10 +205,16 @@ pub enum Command {
     InsertNodeTxn { graph: Option<String>, label: String, key: String, node_id: Option<u64> },
     // INSERT EDGE <src_id> -> <dst_id> [ETYPE <etype_id>] [PART <n>] [GRAPH <name>]
     InsertEdgeTxn { graph: Option<String>, src: u64, dst: u64, etype_id: Option<u16>, part: Option<u32> },
+    // INSERT INTO GRAPH <g> {NODES <Label> | EDGES <Type>} (col, ...) VALUES (...), ...
+    InsertGraph { graph: String, element: GraphElement, name: String, columns: Vec<String>, values: Vec<Vec<ArithTerm>> },
+    // DELETE FROM GRAPH <g> [DETACH] NODES <Label> VALUES (key), ... | EDGES <Type> VALUES (src, dst), ...
+    DeleteGraph { graph: String, element: GraphElement, name: String, keys: Vec<Vec<ArithTerm>>, detach: bool },
     // MATCH (rewritten to SELECT)
     MatchRewrite { sql: String },
     // GC DDL
     GcGraph { name: Option<String> },
+    // GC FILESTORE <name>
+    GcFilestore { filestore: String },
     // DESCRIBE <object> (table/view) and DESCRIBE KEY ... (existing)
     // For backward compatibility, DESCRIBE KEY is parsed specially; otherwise
     // we treat DESCRIBE <object> as DescribeObject with a possibly unqualified name.
@@ -192,8 +223,74 @@ pub enum Command {
     Insert { table: String, columns: Vec<String>, values: Vec<Vec<ArithTerm>> },
     // INSERT INTO <table> [(col1, col2, ...)] SELECT ...
     InsertSelect { table: String, columns: Vec<String>, query: Query },
+    // COPY <table> [(col, ...)] FROM {STDIN | '<path>' | '<url>'} [WITH (...)]
+    CopyFrom { table: String, columns: Vec<String>, source: CopySource, options: CopyOptions },
+    // COPY {<table> [(col, ...)] | (<select>)} TO STDOUT [WITH (...)]
+    CopyTo { relation: CopyRelation, columns: Vec<String>, options: CopyOptions },
+    // BACKUP DATABASE <db> TO '<path|s3://...>'
+    BackupDatabase { database: String, target: String },
+    // RESTORE DATABASE <db> FROM '<path|s3://...>' [AS OF <ts>] (as_of in epoch ms)
+    RestoreDatabase { database: String, source: String, as_of: Option<i64> },
+    // VERIFY TABLE <table> [QUARANTINE]
+    VerifyTable { table: String, quarantine: bool },
+    // COMPACT TABLE <table>
+    CompactTable { table: String },
+    // ANALYZE [<table>]; without a table, every table of the current database
+    Analyze { table: Option<String> },
+    // REENCRYPT TABLE <table>
+    ReencryptTable { table: String },
+    // KILL [CONNECTION | QUERY] <pid>; QUERY cancels the running statement only
+    Kill { pid: i32, query_only: bool },
+    // KILL SESSION '<session_id>': revoke a login session and close its connection
+    KillSession { session_id: String },
+    // KILL SESSIONS FOR USER <name>
+    KillUserSessions { user: String },
+    // ADMIN RELOAD CONFIG: re-read clarium.toml and the environment
+    ReloadConfig,
+    // ATTACH DATABASE '<path>' AS <name>
+    AttachDatabase { path: String, name: String },
+    // DETACH DATABASE <name>
+    DetachDatabase { name: String },
     // EXPLAIN <stmt>
     Explain { sql: String },
+    // COMMENT ON {TABLE | COLUMN | VIEW | FUNCTION} <name> IS '<text>' | NULL; None removes the comment
+    CommentOn { object: CommentObject, comment: Option<String> },
+    // GRANT {<privilege>[, ...] | ALL [PRIVILEGES]} ON [TABLE | SCHEMA | DATABASE] <name> TO <grantee>[, ...]
+    Grant { privileges: Vec<crate::security::Privilege>, object: GrantTarget, grantees: Vec<String> },
+    // REVOKE {<privilege>[, ...] | ALL [PRIVILEGES]} ON [TABLE | SCHEMA | DATABASE] <name> FROM <grantee>[, ...]
+    Revoke { privileges: Vec<crate::security::Privilege>, object: GrantTarget, grantees: Vec<String> },
+    // CREATE ROLE <name> [[WITH] {SUPERUSER | NOSUPERUSER | INHERIT | NOINHERIT} ...]
+    CreateRole { name: String, superuser: bool, inherit: bool },
+    // DROP ROLE [IF EXISTS] <name>
+    DropRole { name: String, if_exists: bool },
+    // GRANT <role>[, ...] TO <member>[, ...] [WITH ADMIN OPTION]
+    GrantRole { roles: Vec<String>, members: Vec<String>, admin_option: bool },
+    // REVOKE <role>[, ...] FROM <member>[, ...]
+    RevokeRole { roles: Vec<String>, members: Vec<String> },
+    // CREATE JOB <name> SCHEDULE '<cron>' AS {SCRIPT <db>/<schema>/<name> | <sql>}
+    CreateJob { name: String, schedule: String, body: JobBody },
+    // DROP JOB [IF EXISTS] <name>
+    DropJob { name: String, if_exists: bool },
+    // ALTER JOB <name> {ENABLE | DISABLE}
+    AlterJob { name: String, enabled: bool },
+    // CREATE TRIGGER <name> AFTER <event> [OR <event>...] ON <table> EXECUTE FUNCTION <fn> [WITH (...)]
+    CreateTrigger { table: String, trigger: crate::storage::triggers::Trigger },
+    // DROP TRIGGER [IF EXISTS] <name> ON <table>
+    DropTrigger { name: String, table: String, if_exists: bool },
+    // CREATE [OR REPLACE] PROCEDURE <name>([<param> <type>, ...]) AS $$ <body> $$
+    CreateProcedure { name: String, params: Vec<ProcParam>, body: String, or_replace: bool },
+    // DROP PROCEDURE [IF EXISTS] <name>
+    DropProcedure { name: String, if_exists: bool },
+    // CALL <procedure>([<expr>, ...])
+    Call { name: String, args: Vec<String> },
+    // CREATE SCRIPT TEST [LUA | SQL] <db>/<schema>/<name> AS $$ <code> $$
+    CreateScriptTest { path: String, language: ScriptTestLanguage, code: String },
+    // DROP SCRIPT TEST [IF EXISTS] <db>/<schema>/<name>
+    DropScriptTest { path: String, if_exists: bool },
+    // RUN TESTS [<db>/<schema>[/<name>]]; None runs the current schema's tests
+    RunTests { target: Option<String> },
+    // ASSERT <condition> [, '<message>']
+    Assert { condition: String, message: Option<String> },
     // FILESTORE SHOW variants
     ShowFilestores { database: Option<String> },
     ShowFilestoreConfig { filestore: String, folder_prefix: Option<String> },
@@ -205,6 +302,16 @@ pub enum Command {
     ShowAliasesInFilestore { filestore: String },
     ShowAdminInFilestore { filestore: String },
     ShowHealthInFilestore { filestore: String },
+    ShowSyncInFilestore { filestore: String },
+    ShowBranchesInFilestore { filestore: String },
+    ShowLegalHoldsInFilestore { filestore: String },
+    ShowLocksInFilestore { filestore: String },
+    ShowWebhooksInFilestore { filestore: String },
+    ShowEncryptionInFilestore { filestore: String },
+    // SHOW FILESTORE GC <name>
+    ShowFilestoreGc { filestore: String },
+    // SHOW VERSIONS IN FILESTORE <name> [PATH '<logical>']
+    ShowVersionsInFilestore { filestore: String, logical_path: Option<String> },
     // FILESTORE DDL/mutations/versioning
     CreateFilestoreCmd { filestore: String, cfg_json: Option<String> },
     AlterFilestoreCmd { filestore: String, update_json: String },
@@ -214,8 +321,37 @@ pub enum Command {
     UpdateFileFromBytesCmd { filestore: String, logical_path: String, if_match: String, payload: String, content_type: Option<String> },
     RenameFilePathCmd { filestore: String, from: String, to: String },
     DeleteFilePathCmd { filestore: String, logical_path: String },
+    // SET FILE METADATA IN FILESTORE <name> PATH '<logical>' ('key' = 'value' | NULL, ...)
+    SetFileMetadataCmd { filestore: String, logical_path: String, changes: Vec<(String, Option<String>)> },
+    // SET LEGAL HOLD IN FILESTORE <name> PATH '<logical>' [REASON '<text>']
+    SetLegalHoldCmd { filestore: String, logical_path: String, reason: Option<String> },
+    // CLEAR LEGAL HOLD IN FILESTORE <name> PATH '<logical>'
+    ClearLegalHoldCmd { filestore: String, logical_path: String },
+    // ROTATE FILESTORE KEY <name> [TO '<master key id>']
+    RotateFilestoreKeyCmd { filestore: String, to_key: Option<String> },
+    // LOCK FILE IN FILESTORE <name> PATH '<logical>' [FOR <seconds>] [NOTE '<text>']
+    LockFileCmd { filestore: String, logical_path: String, ttl_seconds: Option<u64>, note: Option<String> },
+    // UNLOCK FILE IN FILESTORE <name> PATH '<logical>' [FORCE]
+    UnlockFileCmd { filestore: String, logical_path: String, force: bool },
     CreateTreeCmd { filestore: String, prefix: Option<String> },
     CommitTreeCmd { filestore: String, tree_id: String, parents: Vec<String>, branch: Option<String>, author_name: Option<String>, author_email: Option<String>, message: Option<String>, tags: Vec<String> },
+    // SYNC FILESTORE <name> PUSH|PULL [BRANCH '<b>'] [FORCE] [POLICY '<fail|ours|theirs>'] [CORRELATION '<id>']; direction is "push" or "pull"
+    SyncFilestoreCmd { filestore: String, direction: String, branch: Option<String>, force: bool, policy: Option<String>, correlation_id: Option<String> },
+    // CREATE BRANCH '<b>' IN FILESTORE <name> [FROM '<branch>']
+    CreateBranchCmd { filestore: String, branch: String, from: Option<String> },
+    // CHECKOUT BRANCH '<b>' IN FILESTORE <name>
+    CheckoutBranchCmd { filestore: String, branch: String },
+    // MERGE BRANCH '<b>' INTO FILESTORE <name> [MESSAGE '<msg>'] [AUTHOR_NAME '<name>'] [AUTHOR_EMAIL '<email>']
+    MergeBranchCmd { filestore: String, source: String, message: Option<String>, author_name: Option<String>, author_email: Option<String> },
+    // FILESTORE security policies (security.policies); actions are lowercase, "*" for ALL
+    // CREATE SECURITY POLICY <id> FOR ROLE <role> {ALLOW | DENY} {<action>[, ...] | ALL} ON '<selector>' [PRIORITY <n>]
+    CreateSecurityPolicyCmd { policy_id: String, role: String, effect: String, actions: Vec<String>, selector: String, priority: i32 },
+    // ALTER SECURITY POLICY <id> [FOR ROLE <role>] [{ALLOW | DENY} {<action>[, ...] | ALL}] [ON '<selector>'] [PRIORITY <n>]
+    AlterSecurityPolicyCmd { policy_id: String, role: Option<String>, effect: Option<String>, actions: Option<Vec<String>>, selector: Option<String>, priority: Option<i32> },
+    // DROP SECURITY POLICY [IF EXISTS] <id>
+    DropSecurityPolicyCmd { policy_id: String, if_exists: bool },
+    // EXPLAIN ACCESS <action> ON FILESTORE <name> [PATH '<logical>'] [FOR USER '<user>']
+    ExplainAccessCmd { filestore: String, action: String, logical_path: Option<String>, user: Option<String> },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -233,22 +369,64 @@ pub enum ScriptCacheScope {
     Name(String),
 }
 
+/// Which side of a graph catalog INSERT INTO GRAPH / DELETE FROM GRAPH touches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphElement {
+    Nodes,
+    Edges,
+}
+
+/// Target of COMMENT ON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommentObject {
+    Table(String),
+    Column { table: String, column: String },
+    View(String),
+    Function(String),
+}
+
+/// What a scheduled job runs.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "text", rename_all = "lowercase")]
+pub enum JobBody {
+    /// One or more SQL statements
+    Sql(String),
+    /// Lua script `<db>/<schema>/<name>`; its function returns the SQL to run
+    Script(String),
+}
+
+/// Object named in GRANT / REVOKE, as written; resolved against the session defaults when executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrantTarget {
+    Database(String),
+    Schema(String),
+    Table(String),
+}
+
 
 
 
 
 
 pub fn parse(input: &str) -> Result<Command> {
-    // Preprocess: strip SQL comments while preserving content within quotes
+    // Pre-lex: blank out comments (same byte offsets as the input) before dispatching on keywords
     let cleaned = strip_sql_comments(input);
     let s = cleaned.trim();
     let sup = s.to_uppercase();
+    if sup.starts_with("EXPLAIN ACCESS ") {
+        return query_parse_filestore::parse_filestore(s);
+    }
     if sup.starts_with("EXPLAIN ") {
         let rest = s[7..].trim();
         if rest.is_empty() { bail!("EXPLAIN requires a statement"); }
+        // Keep the raw text (with comments) so optimizer hints reach the explained statement
+        let raw = input.trim();
+        if raw.get(..8).is_some_and(|h| h.eq_ignore_ascii_case("EXPLAIN ")) {
+            return Ok(Command::Explain { sql: raw[8..].trim().to_string() });
+        }
         return Ok(Command::Explain { sql: rest.to_string() });
     }
-    // Vector lifecycle commands (BUILD/REINDEX/SHOW STATUS)
+    // Vector lifecycle commands (BUILD/REINDEX/REBUILD/SHOW STATUS)
     if let Some(res) = parse_vector_ddl(s) { return res; }
     if sup.starts_with("SLICE ") || sup == "SLICE" {
         let plan = parse_slice(s)?;
@@ -270,12 +448,18 @@ pub fn parse(input: &str) -> Result<Command> {
     if sup.starts_with("WITH ") || sup.starts_with("SELECT") {
         // Detect UNION / UNION ALL at top-level using a parser that respects nesting
         let (parts, all) = split_union_queries(s)?;
+        let hints = extract_hints(input);
         if parts.len() > 1 {
             let mut queries: Vec<Query> = Vec::new();
-            for part in parts { queries.push(parse_select(part)?); }
+            for part in parts {
+                let mut q = parse_select(part)?;
+                q.hints = hints.clone();
+                queries.push(q);
+            }
             return Ok(Command::SelectUnion { queries, all });
         } else {
-            let q = parse_select(s)?;
+            let mut q = parse_select(s)?;
+            q.hints = hints;
             return Ok(Command::Select(q));
         }
     }
@@ -296,8 +480,21 @@ pub fn parse(input: &str) -> Result<Command> {
         || sup.starts_with("UPDATE FILESTORE")
         || sup.starts_with("RENAME FILESTORE")
         || sup.starts_with("DELETE FILESTORE")
+        || sup.starts_with("SET FILE METADATA")
+        || sup.starts_with("SET LEGAL HOLD")
+        || sup.starts_with("CLEAR LEGAL HOLD")
+        || sup.starts_with("ROTATE FILESTORE KEY")
+        || sup.starts_with("LOCK FILE ")
+        || sup.starts_with("UNLOCK FILE ")
         || sup.starts_with("CREATE TREE IN FILESTORE")
         || sup.starts_with("COMMIT TREE IN FILESTORE")
+        || sup.starts_with("SYNC FILESTORE")
+        || sup.starts_with("CREATE BRANCH ")
+        || sup.starts_with("CHECKOUT BRANCH ")
+        || sup.starts_with("MERGE BRANCH ")
+        || sup.starts_with("CREATE SECURITY POLICY ")
+        || sup.starts_with("ALTER SECURITY POLICY ")
+        || sup.starts_with("DROP SECURITY POLICY ")
     {
         return query_parse_filestore::parse_filestore(s);
     }
@@ -307,6 +504,9 @@ pub fn parse(input: &str) -> Result<Command> {
     if sup.starts_with("LOAD ") {
         return parse_load(s);
     }
+    if sup.starts_with("INSERT INTO GRAPH ") || sup.starts_with("DELETE FROM GRAPH ") {
+        return crate::server::query::query_parse_graph_dml::parse_graph_dml(s);
+    }
     if sup.starts_with("DELETE ") {
         return parse_delete(s);
     }
@@ -319,6 +519,21 @@ pub fn parse(input: &str) -> Result<Command> {
     if sup.starts_with("DATABASE ") {
         return parse_database(s);
     }
+    if sup.starts_with("CREATE ROLE ") || sup.starts_with("DROP ROLE ") {
+        return parse_role(s);
+    }
+    if sup.starts_with("CREATE JOB ") || sup.starts_with("DROP JOB ") || sup.starts_with("ALTER JOB ") {
+        return parse_job(s);
+    }
+    if sup.starts_with("CREATE TRIGGER ") || sup.starts_with("DROP TRIGGER ") {
+        return parse_trigger(s);
+    }
+    if sup.starts_with("CREATE PROCEDURE ") || sup.starts_with("CREATE OR REPLACE PROCEDURE ") || sup.starts_with("DROP PROCEDURE ") || sup.starts_with("CALL ") {
+        return parse_procedure(s);
+    }
+    if sup.starts_with("CREATE SCRIPT TEST ") || sup.starts_with("DROP SCRIPT TEST ") || sup.trim_end_matches(';').trim_end() == "RUN TESTS" || sup.starts_with("RUN TESTS ") || sup.starts_with("ASSERT ") {
+        return parse_script_test(s);
+    }
     if sup.starts_with("CREATE ") {
         return parse_create(s);
     }
@@ -337,6 +552,12 @@ pub fn parse(input: &str) -> Result<Command> {
     if sup.starts_with("READ ") {
         return parse_read(s);
     }
+    if sup.starts_with("MULTI ") {
+        return parse_multi(s);
+    }
+    if sup.starts_with("WATCH ") || sup.starts_with("UNWATCH") {
+        return parse_watch(s);
+    }
     if sup.starts_with("LIST ") {
         return parse_list(s);
     }
@@ -349,6 +570,9 @@ pub fn parse(input: &str) -> Result<Command> {
     if sup.starts_with("SET ") {
         return parse_set(s);
     }
+    if sup.starts_with("RESET ") {
+        return parse_reset(s);
+    }
     if sup.starts_with("CLEAR ") {
         return parse_clear(s);
     }
@@ -359,6 +583,45 @@ pub fn parse(input: &str) -> Result<Command> {
     if sup.starts_with("INSERT ") {
         return parse_insert(s);
     }
+    if sup.starts_with("COPY ") {
+        return parse_copy(s);
+    }
+    if sup.starts_with("BACKUP ") {
+        return parse_backup(s);
+    }
+    if sup.starts_with("RESTORE ") {
+        return parse_restore(s);
+    }
+    if sup.starts_with("VERIFY ") {
+        return parse_verify(s);
+    }
+    if sup.starts_with("COMPACT ") {
+        return parse_compact(s);
+    }
+    if matches!(sup.trim_end_matches(';').trim_end(), "ANALYZE" | "ANALYSE") || sup.starts_with("ANALYZE ") || sup.starts_with("ANALYSE ") {
+        return parse_analyze(s);
+    }
+    if sup.starts_with("REENCRYPT ") {
+        return parse_reencrypt(s);
+    }
+    if sup.starts_with("KILL ") {
+        return parse_kill(s);
+    }
+    if sup.starts_with("COMMENT ON ") {
+        return parse_comment(s);
+    }
+    if sup.starts_with("GRANT ") || sup.starts_with("REVOKE ") {
+        return parse_grant(s);
+    }
+    if sup.starts_with("ADMIN ") {
+        return parse_admin(s);
+    }
+    if sup.starts_with("ATTACH ") {
+        return parse_attach(s);
+    }
+    if sup.starts_with("DETACH ") {
+        return parse_detach(s);
+    }
     bail!("Unsupported DDL-SQL command: {} ", sup)
 }
 
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use super::query_parse_hints::QueryHints;
 
 /// Build an uppercase "shadow" string used only for keyword scanning.
 /// - Converts ASCII letters to uppercase
@@ -15,95 +16,158 @@ pub fn upper_shadow(s: &str) -> String {
     out
 }
 
-/// Strip SQL comments from the input while preserving content inside string literals.
-/// Supported comment styles:
-/// - Line comments starting with `--` until end of line
-/// - Block comments delimited by `/* ... */` (nesting is supported defensively)
-/// Newlines inside comments are preserved to keep line numbers stable; other
-/// commented characters are removed. Quotes inside strings are respected and
-/// not treated as comment delimiters.
-pub fn strip_sql_comments(input: &str) -> String {
-    let bytes = input.as_bytes();
-    let mut out = String::with_capacity(input.len());
-    let mut i = 0usize;
-    let mut in_squote = false;
-    let mut in_dquote = false;
-    let mut block_depth: i32 = 0;
-    let mut line_comment = false;
-
-    while i < bytes.len() {
-        let ch = bytes[i] as char;
-
-        // Handle inside line comment
-        if line_comment {
-            if ch == '\n' {
-                out.push('\n');
-                line_comment = false;
-            } else if ch == '\r' {
-                out.push('\r');
-                // Do not reset here; Windows CRLF will reset on next \n
-            }
-            i += 1;
-            continue;
-        }
+/// Lexical region of a character in SQL text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SqlRegion { Code, Literal, Comment }
 
-        // Handle inside block comment
-        if block_depth > 0 {
-            // Preserve newlines, skip other chars
-            if ch == '\n' || ch == '\r' {
-                out.push(ch);
-                i += 1;
+fn is_ident_char(c: char) -> bool { c.is_alphanumeric() || c == '_' }
+
+/// Walk `input` and report the byte offset and region of every character.
+/// Recognises '...' strings ('' doubling, plus backslash escapes in E'...'), "..." identifiers,
+/// $tag$...$tag$ dollar quotes, `--` line comments and nested `/* */` block comments.
+/// Unterminated quotes or comments run to the end of the input.
+fn scan_sql(input: &str, mut visit: impl FnMut(usize, char, SqlRegion)) {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let at = |k: usize| chars.get(k).map(|c| c.1);
+    let mut i = 0usize;
+    while i < chars.len() {
+        let (pos, ch) = chars[i];
+        match ch {
+            '-' if at(i + 1) == Some('-') => {
+                // The terminating newline is ordinary code
+                while i < chars.len() && chars[i].1 != '\n' {
+                    visit(chars[i].0, chars[i].1, SqlRegion::Comment);
+                    i += 1;
+                }
                 continue;
             }
-            // Detect nested /* */
-            if ch == '/' && i + 1 < bytes.len() && bytes[i + 1] as char == '*' {
-                block_depth += 1;
-                i += 2;
+            '/' if at(i + 1) == Some('*') => {
+                let mut depth = 0usize;
+                while i < chars.len() {
+                    let (c, n) = (chars[i].1, at(i + 1));
+                    let delim = (c == '/' && n == Some('*')) || (c == '*' && n == Some('/'));
+                    if delim {
+                        visit(chars[i].0, c, SqlRegion::Comment);
+                        visit(chars[i + 1].0, chars[i + 1].1, SqlRegion::Comment);
+                        i += 2;
+                        if c == '/' { depth += 1; } else { depth -= 1; if depth == 0 { break; } }
+                        continue;
+                    }
+                    visit(chars[i].0, c, SqlRegion::Comment);
+                    i += 1;
+                }
                 continue;
             }
-            if ch == '*' && i + 1 < bytes.len() && bytes[i + 1] as char == '/' {
-                block_depth -= 1;
-                i += 2;
+            '\'' | '"' => {
+                let escapes = ch == '\'' && i > 0 && matches!(chars[i - 1].1, 'e' | 'E') && !(i > 1 && is_ident_char(chars[i - 2].1));
+                visit(pos, ch, SqlRegion::Literal);
+                i += 1;
+                while i < chars.len() {
+                    let (p, c) = chars[i];
+                    visit(p, c, SqlRegion::Literal);
+                    i += 1;
+                    if escapes && c == '\\' {
+                        if let Some(&(p2, c2)) = chars.get(i) { visit(p2, c2, SqlRegion::Literal); i += 1; }
+                        continue;
+                    }
+                    if c == ch {
+                        // A doubled quote is an escaped quote, not the end of the literal
+                        if at(i) == Some(ch) { visit(chars[i].0, ch, SqlRegion::Literal); i += 1; continue; }
+                        break;
+                    }
+                }
                 continue;
             }
-            i += 1;
-            continue;
+            // $tag$ opens a dollar quote unless it is part of an identifier or a $n parameter
+            '$' if !(i > 0 && is_ident_char(chars[i - 1].1)) && !at(i + 1).is_some_and(|c| c.is_ascii_digit()) => {
+                let mut j = i + 1;
+                while at(j).is_some_and(is_ident_char) { j += 1; }
+                if at(j) == Some('$') {
+                    let after_open = chars[j].0 + 1;
+                    let delim = &input[pos..after_open];
+                    let end = input[after_open..].find(delim).map(|k| after_open + k + delim.len()).unwrap_or(input.len());
+                    while i < chars.len() && chars[i].0 < end {
+                        visit(chars[i].0, chars[i].1, SqlRegion::Literal);
+                        i += 1;
+                    }
+                    continue;
+                }
+            }
+            _ => {}
         }
+        visit(pos, ch, SqlRegion::Code);
+        i += 1;
+    }
+}
 
-        // Not inside any comment
-        if !in_dquote && ch == '\'' {
-            in_squote = !in_squote;
-            out.push(ch);
-            i += 1;
-            continue;
-        }
-        if !in_squote && ch == '"' {
-            in_dquote = !in_dquote;
-            out.push(ch);
-            i += 1;
-            continue;
+/// Blank out SQL comments (`--` to end of line and nested `/* ... */`) while leaving string
+/// literals, quoted identifiers and dollar quotes untouched. Commented characters become
+/// spaces (newlines are kept), so the result has the same byte length as the input and an
+/// offset found in it points at the same place in the original text, e.g. for error carets.
+/// A comment between two tokens still separates them, as in PostgreSQL.
+pub fn strip_sql_comments(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    scan_sql(input, |_, ch, region| match region {
+        SqlRegion::Comment if ch != '\n' && ch != '\r' => {
+            for _ in 0..ch.len_utf8() { out.push(' '); }
         }
+        _ => out.push(ch),
+    });
+    out
+}
 
-        if !in_squote && !in_dquote {
-            // Start of line comment?
-            if ch == '-' && i + 1 < bytes.len() && bytes[i + 1] as char == '-' {
-                line_comment = true;
-                i += 2;
-                continue;
-            }
-            // Start of block comment?
-            if ch == '/' && i + 1 < bytes.len() && bytes[i + 1] as char == '*' {
-                block_depth = 1;
-                i += 2;
-                continue;
-            }
+/// Blank out literals, quoted identifiers, dollar quotes and comments, leaving only code.
+/// The result has the same byte length as the input, so keyword and punctuation offsets
+/// found in it can be used to slice the original text.
+pub fn mask_sql_literals(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    scan_sql(input, |_, ch, region| match region {
+        SqlRegion::Code => out.push(ch),
+        _ => {
+            for _ in 0..ch.len_utf8() { out.push(' '); }
         }
+    });
+    out
+}
 
-        // Default: copy through
-        out.push(ch);
-        i += 1;
+/// Split a trailing `COLLATE <name>` off an expression. The keyword must sit outside
+/// literals and parentheses; the name may be double-quoted.
+pub fn split_collate(expr: &str) -> Option<(&str, String)> {
+    let masked = mask_sql_literals(expr).to_ascii_uppercase();
+    let b = masked.as_bytes();
+    let mut depth = 0i32;
+    let mut at: Option<usize> = None;
+    for i in 0..b.len() {
+        match b[i] {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            _ if depth == 0 && b[i..].starts_with(b"COLLATE") && i > 0 && b[i - 1].is_ascii_whitespace()
+                && b.get(i + 7).is_some_and(|c| c.is_ascii_whitespace()) => at = Some(i),
+            _ => {}
+        }
     }
+    let i = at?;
+    let name = expr[i + 7..].trim();
+    let quoted = name.len() >= 2 && name.starts_with('"') && name.ends_with('"') && !name[1..name.len() - 1].contains('"');
+    if name.is_empty() || (!quoted && name.contains(|c: char| c.is_whitespace() || c == '"')) { return None; }
+    Some((expr[..i].trim_end(), name.trim_matches('"').to_string()))
+}
 
+/// Split a multi-statement string at top-level semicolons, ignoring those inside literals,
+/// quoted identifiers, dollar quotes and comments. Statements are trimmed and empty or
+/// comment-only ones dropped; comments inside a statement are kept so optimizer hints
+/// still reach the parser.
+pub fn split_sql_statements(input: &str) -> Vec<&str> {
+    let mut cuts = Vec::new();
+    scan_sql(input, |pos, ch, region| if ch == ';' && region == SqlRegion::Code { cuts.push(pos); });
+    cuts.push(input.len());
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    for end in cuts {
+        let stmt = input[start..end].trim();
+        if !strip_sql_comments(stmt).trim().is_empty() { out.push(stmt); }
+        start = end + 1;
+    }
     out
 }
 
@@ -125,6 +189,8 @@ pub enum ArithTerm {
 pub struct Query {
     pub select: Vec<SelectItem>,
     pub by_window_ms: Option<i64>,
+    // BY AUTO(n): by_window_ms is a placeholder, the bucket size is picked from the data to yield about n rows
+    pub by_auto_points: Option<usize>,
     pub by_slices: Option<SlicePlan>,
     pub group_by_cols: Option<Vec<String>>,
     // Columns within group_by that use NOTNULL run-based grouping semantics
@@ -132,15 +198,20 @@ pub struct Query {
     pub where_clause: Option<WhereExpr>,
     pub having_clause: Option<WhereExpr>,
     pub rolling_window_ms: Option<i64>,
+    // ROLLING BY ROWS BETWEEN n PRECEDING AND CURRENT ROW
+    pub rolling_rows: Option<usize>,
     pub order_by: Option<Vec<(String, bool)>>, // (column/alias, asc=true/desc=false)
     // Optional ANN/EXACT hint attached to ORDER BY clause: "ANN" | "EXACT"
     pub order_by_hint: Option<String>,
     // Raw ORDER BY items as written (per item text), preserved for advanced planners (e.g., ANN)
     pub order_by_raw: Option<Vec<(String, bool)>>,
+    // Explicit COLLATE per ORDER BY item, parallel to order_by; None when no item has one
+    pub order_by_collations: Option<Vec<Option<String>>>,
     pub limit: Option<i64>,
     // Optional INTO destination for persisting SELECT results
     pub into_table: Option<String>,
     pub into_mode: Option<IntoMode>,
+    pub into_options: Option<IntoOptions>,
     // JOIN support (optional). When present, JOINs take precedence over `base_table`.
     pub base_table: Option<TableRef>,
     pub joins: Option<Vec<JoinClause>>,
@@ -148,6 +219,8 @@ pub struct Query {
     pub with_ctes: Option<Vec<CTE>>,
     // Full original SQL text for this query, preserved for diagnostics/debugging/reference
     pub original_sql: String,
+    /// Optimizer hints from `/*+ ... */` comments (top-level statements only)
+    pub hints: QueryHints,
 }
 
 
@@ -185,6 +258,8 @@ pub enum ArithExpr {
     Case { when_clauses: Vec<(WhereExpr, ArithExpr)>, else_expr: Option<Box<ArithExpr>> },
     // PostgreSQL-style type cast: expr::typename (with optional parameters)
     Cast { expr: Box<ArithExpr>, ty: SqlType },
+    // expr COLLATE "name": ordering and comparisons of expr follow the named collation
+    Collate { expr: Box<ArithExpr>, collation: String },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -218,10 +293,44 @@ pub enum SqlType {
     Array(Box<SqlType>),
 }
 
+/// Operation on a list, set or hash key (WRITE KEY ... <op> / READ KEY ... HGET).
+#[derive(Debug, Clone, PartialEq)]
+pub enum KvOp {
+    // LPUSH | RPUSH <value>[, ...]
+    Push { front: bool, values: Vec<String> },
+    // LPOP | RPOP
+    Pop { front: bool },
+    // SADD <member>[, ...]
+    SetAdd { members: Vec<String> },
+    // SREM <member>[, ...]
+    SetRemove { members: Vec<String> },
+    // HSET <field> = <value>[, ...]
+    HashSet { fields: Vec<(String, String)> },
+    // HDEL <field>[, ...]
+    HashDelete { fields: Vec<String> },
+    // HGET <field>
+    HashGet { field: String },
+}
+
+/// One statement of a MULTI ... EXEC batch; keys belong to the batch's store.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KvBatchWrite {
+    // WRITE KEY <key> = <value> [TTL <duration>] [RESET ON ACCESS | NO RESET]
+    Set { key: String, value: String, ttl_ms: Option<i64>, reset_on_access: Option<bool> },
+    // DROP KEY <key>
+    Delete { key: String },
+    // WRITE KEY <key> LPUSH | RPUSH | LPOP | RPOP | SADD | SREM | HSET | HDEL ...
+    Op { key: String, op: KvOp },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AlterOp {
     // ADD COLUMN <name> <type> [NULL|NOT NULL] [DEFAULT <expr>]
     AddColumn { name: String, type_key: String, nullable: bool, default_expr: Option<String> },
+    // ADD COMPUTED COLUMN <name> AS <expr>; evaluated for every row written
+    AddComputedColumn { name: String, expr: String },
+    // DROP COMPUTED COLUMN <name>; the column and its stored values stay
+    DropComputedColumn { name: String },
     // RENAME COLUMN <old> TO <new>
     RenameColumn { from: String, to: String },
     // ALTER COLUMN <name> TYPE <type>
@@ -234,6 +343,69 @@ pub enum AlterOp {
     AddConstraint { name: String, udf: String },
     // DROP CONSTRAINT <name>
     DropConstraint { name: String },
+    // SET BLOOM (col[, ...]); an empty list disables bloom filters
+    SetBloom { columns: Vec<String> },
+    // SET CDC ON|OFF; toggles the per-table changelog
+    SetCdc { enabled: bool },
+    // SET ENCRYPTION ON [KEY '<id>'] | OFF; existing chunks are rewritten to match
+    SetEncryption { enabled: bool, key_id: Option<String> },
+    // ALTER COLUMN <name> SET COLLATION "<collation>" | DROP COLLATION
+    SetCollation { column: String, collation: Option<String> },
+    // SET DEDUP ON WRITE | ON COMPACT | OFF; duplicate (_time + primary key) suppression
+    SetDedup { mode: crate::storage::dedup::DedupMode },
+    // SET INGEST MODE STRICT | WIDEN | COERCE; what a batch that does not fit the column types does
+    SetIngestMode { mode: crate::storage::ingest_mode::IngestMode },
+    // SET LATE DATA MERGE | SEPARATE | OFF [WINDOW <ms>]; handling of rows behind the high-water mark
+    SetLateData { setting: crate::storage::late::LateSetting },
+    // SET PARTITION BY [LIST | HASH] (cols) [BUCKETS n] | NONE; rewrites existing chunks into the new layout
+    SetPartitioning { spec: crate::storage::partition::PartitionSpec },
+}
+
+/// Where COPY ... FROM reads its payload.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopySource {
+    // COPY ... FROM STDIN (pgwire CopyIn sub-protocol)
+    Stdin,
+    // COPY ... FROM '/server/side/path'
+    File(String),
+    // COPY ... FROM 'http(s)://...'
+    Url(String),
+}
+
+/// What COPY ... TO STDOUT exports.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopyRelation {
+    // COPY <table> [(col, ...)] TO STDOUT
+    Table(String),
+    // COPY (<select>) TO STDOUT; the query text as written
+    Query(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat { Text, Csv, Ndjson, Parquet, Binary }
+
+/// Options accepted in the COPY WITH (...) list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyOptions {
+    pub format: CopyFormat,
+    pub header: bool,
+    pub delimiter: char,
+    // Literal that denotes NULL in text/CSV input
+    pub null_str: String,
+    // Records buffered before each write_records call (one Parquet chunk per batch)
+    pub batch_size: usize,
+}
+
+impl CopyOptions {
+    pub const DEFAULT_BATCH_SIZE: usize = 50_000;
+
+    pub fn for_format(format: CopyFormat) -> Self {
+        let (delimiter, null_str) = match format {
+            CopyFormat::Text => ('\t', "\\N".to_string()),
+            _ => (',', String::new()),
+        };
+        Self { format, header: false, delimiter, null_str, batch_size: Self::DEFAULT_BATCH_SIZE }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -252,7 +424,20 @@ pub enum WhereExpr {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum AggFunc { Avg, Max, Min, Sum, Count, First, Last, Stdev, Delta, Height, Gradient, Quantile(i64), ArrayAgg }
+pub enum AggFunc { Avg, Max, Min, Sum, Count, First, Last, Stdev, Delta, Height, Gradient, Quantile(i64), ArrayAgg,
+    // Moving averages, ROLLING BY only: EMA(col, alpha) and linearly weighted WMA(col)
+    Ema(f64), Wma,
+    // Reset-aware counter aggregates (see select_stages::counters)
+    Rate, Increase, Irate }
+
+/// Frame of a ROLLING BY aggregate ending at the current row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollingFrame {
+    /// Rows whose `_time` lies within this many milliseconds of the current row
+    Range(i64),
+    /// The current row and up to this many preceding rows
+    Rows(usize),
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum StrFunc { Upper, Lower }
@@ -272,13 +457,30 @@ pub struct SelectItem {
     pub str_func: Option<StrFunc>,
     pub window_func: Option<WindowFunc>,
     pub window_spec: Option<WindowSpec>,
+    // Per-aggregate ROLLING BY frame from `agg(col) OVER (ROWS|RANGE ...)`
+    pub frame: Option<RollingFrame>,
     pub column: String,
     pub expr: Option<ArithExpr>,
     pub alias: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum IntoMode { Append, Replace }
+pub enum IntoMode { Append, Replace, Merge }
+
+/// Options from `INTO <table> (PRIMARY KEY(..), PARTITION BY(..), TIME TABLE)`, applied when the destination is created
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntoOptions {
+    pub primary_key: Option<Vec<String>>,
+    pub partitions: Option<Vec<String>>,
+    pub time_table: bool,
+}
+
+/// `UPDATE ... FROM <table> [[AS] alias]`: rows joined to the target through equalities in WHERE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateFrom {
+    pub table: String,
+    pub alias: Option<String>,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JoinType { Inner, Left, Right, Full }
@@ -345,6 +547,7 @@ pub struct SlicePlan {
     pub base: SliceSource, // from USING
     pub clauses: Vec<SliceClause>,
     pub labels: Option<Vec<String>>, // optional LABELS declared after USING
+    pub adjust: Vec<SliceAdjust>, // MERGE/PAD/COMPLEMENT written after the base, applied before the first clause
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -352,13 +555,26 @@ pub enum SliceSource {
     Table { database: String, start_col: Option<String>, end_col: Option<String>, where_clause: Option<WhereExpr>, label_values: Option<Vec<String>> },
     Manual { rows: Vec<ManualRow> },
     Plan(Box<SlicePlan>),
+    // Saved plan from CREATE SLICE, referenced by name; resolved against the catalog at run time
+    Named(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SliceOp { Intersect, Union }
+pub enum SliceOp { Intersect, Union, Except }
+
+// Single-operand steps on the slices evaluated so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceAdjust {
+    // Gaps between the slices, from the first slice's end to the last slice's start
+    Complement,
+    // Join slices with matching labels whose gap is shorter than the window (ms)
+    MergeWithin(i64),
+    // Widen every slice by `before`/`after` ms, then merge overlaps
+    Pad { before: i64, after: i64 },
+}
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct SliceClause { pub op: SliceOp, pub source: SliceSource }
+pub struct SliceClause { pub op: SliceOp, pub source: SliceSource, pub adjust: Vec<SliceAdjust> }
 
 // Module-level helper: skip ASCII whitespace from index and return the next position
 #[inline]
@@ -406,44 +622,31 @@ pub fn find_token_ci(tokens: &[String], needle: &str) -> Option<usize> {
     None
 }
 
-pub fn parse_iso8601_to_ms(tok: &str) -> Option<i64> {
-    // Accept bare or single-quoted ISO 8601/RFC3339 timestamps and common variants without timezone (assume UTC)
-    let mut s = tok.trim();
-    if s.len() >= 2 {
-        let first = s.as_bytes()[0] as char;
-        let last = s.as_bytes()[s.len()-1] as char;
-        if first == '\'' && last == '\'' {
-            s = &s[1..s.len()-1];
-        }
-    }
-    // Try RFC3339 (e.g., 2025-01-01T00:00:00Z or with offset)
-    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
-        return Some(dt.timestamp_millis());
-    }
-    // Try NaiveDateTime with T separator, assume UTC
-    if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f") {
-        let dt = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(ndt, chrono::Utc);
-        return Some(dt.timestamp_millis());
-    }
-    // Try NaiveDateTime with space separator
-    if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f") {
-        let dt = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(ndt, chrono::Utc);
-        return Some(dt.timestamp_millis());
-    }
-    // Try date-only (YYYY-MM-DD) at midnight UTC
-    if let Ok(nd) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-        if let Some(ndt) = nd.and_hms_opt(0, 0, 0) {
-            let dt = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(ndt, chrono::Utc);
-            return Some(dt.timestamp_millis());
-        }
-    }
-    None
-}
+// Timestamp literal parsing is shared with storage, which cannot depend on the query layer
+pub use crate::storage::native::parse_iso8601_to_ms;
 
 pub fn prec(op: &ArithOp) -> i32 { match op { ArithOp::Add|ArithOp::Sub => 1, ArithOp::Mul|ArithOp::Div => 2 } }
 
 
 
+/// Offset of keyword `kw` (given in uppercase, with its surrounding spaces) outside
+/// literals and parentheses.
+pub fn find_top_level_keyword(s: &str, kw: &str) -> Option<usize> {
+    let masked = upper_shadow(&mask_sql_literals(s));
+    let bytes = masked.as_bytes();
+    let kw = kw.as_bytes();
+    let mut depth = 0i32;
+    for i in 0..bytes.len() {
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            _ if depth == 0 && bytes[i..].starts_with(kw) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
 pub fn find_next_keyword(s: &str, kws: &[&str]) -> Option<usize> {
     let up = s.to_uppercase();
     let mut best: Option<usize> = None;
@@ -7,13 +7,48 @@ fn normalize_ident(name: &str) -> String {
     crate::ident::qualify_regular_ident(name, &qd)
 }
 
-fn sql_type_to_key(ty: &str) -> String {
+fn sql_type_to_key(ty: &str) -> Result<String> {
     let t = ty.to_ascii_lowercase();
-    if t.contains("char") || t.contains("text") || t.contains("json") || t.contains("bool") { "string".to_string() }
+    if let Some((p, s)) = crate::storage::decimal::parse_type(&t)? { return Ok(crate::storage::decimal::type_key(p, s)); }
+    Ok(if t.contains("bool") { "bool".to_string() }
+    else if t.contains("char") || t.contains("text") || t.contains("json") { "string".to_string() }
     else if t.contains("int") { "int64".to_string() }
-    else if t.contains("double") || t.contains("real") || t.contains("float") || t.contains("numeric") || t.contains("decimal") { "float64".to_string() }
+    else if t.contains("double") || t.contains("real") || t.contains("float") { "float64".to_string() }
+    else if t.contains("timestamp") || t.contains("datetime") { "timestamp".to_string() }
     else if t.contains("time") || t.contains("date") { "int64".to_string() }
-    else { "string".to_string() }
+    else { "string".to_string() })
+}
+
+/// Parse the tail of a `PARTITION BY` clause: `[LIST | HASH] (col[, ...]) [BUCKETS n]`.
+/// Empty parentheses or `NONE` remove partitioning. Text after the clause is ignored.
+pub(crate) fn parse_partition_spec(s: &str) -> Result<crate::storage::partition::PartitionSpec> {
+    use crate::storage::partition::{PartitionMethod, PartitionSpec};
+    let s = s.trim();
+    let up = s.to_ascii_uppercase();
+    if up == "NONE" { return Ok(PartitionSpec::default()); }
+    let (method, rest) = if up.starts_with("HASH") {
+        (PartitionMethod::Hash, &s[4..])
+    } else if up.starts_with("LIST") {
+        (PartitionMethod::List, &s[4..])
+    } else {
+        (PartitionMethod::List, s)
+    };
+    let rest = rest.trim_start();
+    if !rest.starts_with('(') { return Err(anyhow!("PARTITION BY expects [LIST | HASH] (col[, ...]) [BUCKETS n]")); }
+    let end = rest.find(')').ok_or_else(|| anyhow!("PARTITION BY expects closing )"))?;
+    let columns: Vec<String> = rest[1..end].split(',').map(|x| x.trim().trim_matches('"').to_string()).filter(|x| !x.is_empty()).collect();
+    let tail: Vec<String> = rest[end + 1..].split_whitespace().map(|t| t.to_ascii_uppercase()).collect();
+    let buckets = match method {
+        PartitionMethod::Hash => {
+            if tail.first().map(|t| t.as_str()) != Some("BUCKETS") {
+                return Err(anyhow!("PARTITION BY HASH expects BUCKETS n"));
+            }
+            tail.get(1).and_then(|n| n.parse::<u32>().ok()).filter(|n| *n > 0)
+                .ok_or_else(|| anyhow!("BUCKETS expects a positive integer"))?
+        }
+        PartitionMethod::List => 0,
+    };
+    Ok(PartitionSpec { method, columns, buckets })
 }
 
 /// Parse comma-separated operations inside an ALTER TABLE statement tail
@@ -34,9 +69,30 @@ fn parse_ops(s: &str) -> Result<Vec<AlterOp>> {
     Ok(ops)
 }
 
+/// Parse the text of a computed column expression (see `storage::computed`).
+pub fn parse_computed_expr(text: &str) -> Result<crate::server::query::ArithExpr> {
+    let tokens: Vec<String> = text.split_whitespace().map(|t| t.to_string()).collect();
+    if tokens.is_empty() { return Err(anyhow!("computed column expects an expression after AS")); }
+    crate::server::query::query_parse_arith_expr::parse_arith_expr(&tokens)
+}
+
 fn parse_one_op(s: &str) -> Result<AlterOp> {
     let up = s.to_ascii_uppercase();
     if up.starts_with("ADD COLUMN ") { return parse_add_column(&s["ADD COLUMN ".len()..]); }
+    if up.starts_with("ADD COMPUTED COLUMN ") {
+        // ADD COMPUTED COLUMN <name> AS <expr>
+        let tail = &s["ADD COMPUTED COLUMN ".len()..];
+        let i = tail.to_ascii_uppercase().find(" AS ").ok_or_else(|| anyhow!("ADD COMPUTED COLUMN expects <name> AS <expr>"))?;
+        let name = tail[..i].trim().trim_matches('"').to_string();
+        let expr = tail[i + 4..].trim().to_string();
+        if name.is_empty() { return Err(anyhow!("ADD COMPUTED COLUMN expects <name> AS <expr>")); }
+        parse_computed_expr(&expr)?;
+        return Ok(AlterOp::AddComputedColumn { name, expr });
+    }
+    if up.starts_with("DROP COMPUTED COLUMN ") {
+        let name = s["DROP COMPUTED COLUMN ".len()..].trim().trim_matches('"').to_string();
+        return Ok(AlterOp::DropComputedColumn { name });
+    }
     if up.starts_with("ADD PRIMARY KEY") {
         // ADD PRIMARY KEY (col[, ...])
         let start = s.find('(').ok_or_else(|| anyhow!("ADD PRIMARY KEY expects column list"))?;
@@ -56,15 +112,24 @@ fn parse_one_op(s: &str) -> Result<AlterOp> {
         return Ok(AlterOp::RenameColumn { from, to });
     }
     if up.starts_with("ALTER COLUMN ") {
-        // ALTER COLUMN <name> TYPE <type>
+        // ALTER COLUMN <name> TYPE <type> | SET COLLATION "<collation>" | DROP COLLATION
         let rest = &s["ALTER COLUMN ".len()..];
         let rup = rest.to_ascii_uppercase();
+        if let Some(pos) = rup.find(" SET COLLATION ") {
+            let column = rest[..pos].trim().trim_matches('"').to_string();
+            let collation = crate::server::collation::Collation::resolve(&rest[pos + " SET COLLATION ".len()..])?;
+            return Ok(AlterOp::SetCollation { column, collation: Some(collation.name) });
+        }
+        if rup.trim_end().ends_with(" DROP COLLATION") {
+            let column = rest[..rup.trim_end().len() - " DROP COLLATION".len()].trim().trim_matches('"').to_string();
+            return Ok(AlterOp::SetCollation { column, collation: None });
+        }
         if let Some(pos) = rup.find(" TYPE ") {
             let name = rest[..pos].trim().trim_matches('"').to_string();
             let ty = rest[pos+" TYPE ".len()..].trim();
-            return Ok(AlterOp::AlterColumnType { name, type_key: sql_type_to_key(ty) });
+            return Ok(AlterOp::AlterColumnType { name, type_key: sql_type_to_key(ty)? });
         }
-        return Err(anyhow!("Invalid ALTER COLUMN syntax; expected TYPE"));
+        return Err(anyhow!("Invalid ALTER COLUMN syntax; expected TYPE, SET COLLATION or DROP COLLATION"));
     }
     if up.starts_with("ADD CONSTRAINT ") {
         // ADD CONSTRAINT <name> USING <udf>
@@ -77,6 +142,83 @@ fn parse_one_op(s: &str) -> Result<AlterOp> {
         }
         return Err(anyhow!("Invalid ADD CONSTRAINT syntax; expected USING <udf>"));
     }
+    if up.starts_with("SET BLOOM") {
+        // SET BLOOM (col[, ...]) -- empty parentheses clear the setting
+        let start = s.find('(').ok_or_else(|| anyhow!("SET BLOOM expects column list"))?;
+        let end = s.rfind(')').ok_or_else(|| anyhow!("SET BLOOM expects closing )"))?;
+        let inside = &s[start+1..end];
+        let cols: Vec<String> = inside.split(',').map(|x| x.trim().trim_matches('"').to_string()).filter(|x| !x.is_empty()).collect();
+        return Ok(AlterOp::SetBloom { columns: cols });
+    }
+    if up.starts_with("SET CDC") {
+        let v = up["SET CDC".len()..].trim();
+        let enabled = match v {
+            "ON" | "TRUE" | "1" => true,
+            "OFF" | "FALSE" | "0" => false,
+            _ => return Err(anyhow!("SET CDC expects ON or OFF")),
+        };
+        return Ok(AlterOp::SetCdc { enabled });
+    }
+    if up.starts_with("SET DEDUP") {
+        use crate::storage::dedup::DedupMode;
+        let mode = match up["SET DEDUP".len()..].split_whitespace().collect::<Vec<_>>().join(" ").as_str() {
+            "ON WRITE" | "ON" => DedupMode::Write,
+            "ON COMPACT" => DedupMode::Compact,
+            "OFF" => DedupMode::Off,
+            _ => return Err(anyhow!("SET DEDUP expects ON WRITE, ON COMPACT or OFF")),
+        };
+        return Ok(AlterOp::SetDedup { mode });
+    }
+    if up.starts_with("SET INGEST MODE") {
+        use crate::storage::ingest_mode::IngestMode;
+        let mode = match up["SET INGEST MODE".len()..].trim() {
+            "STRICT" => IngestMode::Strict,
+            "WIDEN" => IngestMode::Widen,
+            "COERCE" => IngestMode::Coerce,
+            _ => return Err(anyhow!("SET INGEST MODE expects STRICT, WIDEN or COERCE")),
+        };
+        return Ok(AlterOp::SetIngestMode { mode });
+    }
+    if up.starts_with("SET PARTITION BY") {
+        // SET PARTITION BY [LIST | HASH] (col[, ...]) [BUCKETS n] | SET PARTITION BY NONE
+        let spec = parse_partition_spec(&s["SET PARTITION BY".len()..])?;
+        return Ok(AlterOp::SetPartitioning { spec });
+    }
+    if up.starts_with("SET LATE DATA") {
+        use crate::storage::late::{LatePolicy, LateSetting};
+        let toks: Vec<&str> = up["SET LATE DATA".len()..].split_whitespace().collect();
+        let policy = match toks.first().copied() {
+            Some("MERGE") => LatePolicy::Merge,
+            Some("SEPARATE") => LatePolicy::Separate,
+            Some("OFF") => LatePolicy::Off,
+            _ => return Err(anyhow!("SET LATE DATA expects MERGE, SEPARATE or OFF [WINDOW <ms>]")),
+        };
+        let window_ms = match &toks[1..] {
+            [] => 0,
+            ["WINDOW", ms] => ms.parse::<i64>().ok().filter(|v| *v >= 0)
+                .ok_or_else(|| anyhow!("SET LATE DATA WINDOW expects a non-negative number of milliseconds"))?,
+            _ => return Err(anyhow!("SET LATE DATA expects MERGE, SEPARATE or OFF [WINDOW <ms>]")),
+        };
+        return Ok(AlterOp::SetLateData { setting: LateSetting { policy, window_ms } });
+    }
+    if up.starts_with("SET ENCRYPTION") {
+        // SET ENCRYPTION ON [KEY '<id>'] | OFF
+        let rest = s["SET ENCRYPTION".len()..].trim();
+        let rup = rest.to_ascii_uppercase();
+        if rup == "OFF" { return Ok(AlterOp::SetEncryption { enabled: false, key_id: None }); }
+        if !rup.starts_with("ON") { return Err(anyhow!("SET ENCRYPTION expects ON [KEY '<id>'] or OFF")); }
+        let tail = rest[2..].trim();
+        let key_id = if tail.is_empty() {
+            None
+        } else if tail.to_ascii_uppercase().starts_with("KEY ") {
+            let k = tail[4..].trim().trim_matches('\'').trim_matches('"').to_string();
+            if k.is_empty() { return Err(anyhow!("SET ENCRYPTION ON KEY expects a key id")); }
+            Some(k)
+        } else {
+            return Err(anyhow!("SET ENCRYPTION expects ON [KEY '<id>'] or OFF"));
+        };
+        return Ok(AlterOp::SetEncryption { enabled: true, key_id });
+    }
     if up.starts_with("DROP CONSTRAINT ") {
         let name = s["DROP CONSTRAINT ".len()..].trim().trim_matches('"').to_string();
         return Ok(AlterOp::DropConstraint { name });
@@ -112,14 +254,15 @@ fn parse_add_column(s: &str) -> Result<AlterOp> {
         }
         i += 1;
     }
-    Ok(AlterOp::AddColumn { name, type_key: sql_type_to_key(&ty_parts.join(" ")), nullable, default_expr })
+    Ok(AlterOp::AddColumn { name, type_key: sql_type_to_key(&ty_parts.join(" "))?, nullable, default_expr })
 }
 
 pub fn parse_alter(s: &str) -> Result<Command> {
     // ALTER TABLE <ident> <ops>
     let rest = s["ALTER ".len()..].trim();
     let up = rest.to_ascii_uppercase();
-    if !up.starts_with("TABLE ") { return Err(anyhow!("Only ALTER TABLE is supported")); }
+    if up.starts_with("DATABASE ") { return parse_alter_database(&rest["DATABASE ".len()..]); }
+    if !up.starts_with("TABLE ") { return Err(anyhow!("Only ALTER TABLE and ALTER DATABASE ... SET/RESET are supported")); }
     let tail = &rest["TABLE ".len()..];
     // split first space to get table ident
     let mut parts = tail.splitn(2, ' ');
@@ -131,3 +274,24 @@ pub fn parse_alter(s: &str) -> Result<Command> {
     let ops = parse_ops(ops_str)?;
     Ok(Command::AlterTable { table, ops })
 }
+
+/// ALTER DATABASE <db> SET <param> {TO | =} <value> | RESET <param> | RESET ALL
+fn parse_alter_database(s: &str) -> Result<Command> {
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let (name, tail) = s.split_once(char::is_whitespace).ok_or_else(|| anyhow!("ALTER DATABASE expects SET or RESET"))?;
+    let database = crate::ident::normalize_identifier(name);
+    let tail = tail.trim();
+    let up = tail.to_ascii_uppercase();
+    if up.starts_with("SET ") {
+        match crate::server::query::parse_set(tail)? {
+            Command::Set { variable, value, local: false } => Ok(Command::AlterDatabaseSet { database, variable: Some(variable), value: Some(value) }),
+            _ => Err(anyhow!("ALTER DATABASE SET does not accept LOCAL")),
+        }
+    } else if up.starts_with("RESET ") {
+        let variable = tail["RESET ".len()..].trim();
+        let variable = if variable.eq_ignore_ascii_case("ALL") { None } else { Some(variable.to_string()) };
+        Ok(Command::AlterDatabaseSet { database, variable, value: None })
+    } else {
+        Err(anyhow!("ALTER DATABASE expects SET or RESET"))
+    }
+}
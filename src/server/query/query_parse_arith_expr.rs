@@ -223,6 +223,13 @@ pub fn parse_arith_expr(tokens: &[String]) -> Result<ArithExpr> {
         }
     }
 
+    // Trailing COLLATE applies to the whole expression before it
+    if let Some((inner, name)) = split_collate(&src) {
+        let collation = crate::server::collation::Collation::resolve(&name)?.name;
+        let expr = super_parse_arith(inner).ok_or_else(|| anyhow::anyhow!("Invalid expression before COLLATE: {}", inner))?;
+        return Ok(ArithExpr::Collate { expr: Box::new(expr), collation });
+    }
+
     let bytes = src.as_bytes();
     let mut i = 0usize;
 
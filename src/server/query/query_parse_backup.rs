@@ -0,0 +1,70 @@
+use anyhow::{anyhow, bail, Result};
+
+use crate::server::query::Command;
+
+/// Split a leading single-quoted literal from `s`, returning (literal, remainder).
+fn quoted_literal(s: &str) -> Option<(String, &str)> {
+    let rest = s.strip_prefix('\'')?;
+    let end = rest.find('\'')?;
+    Some((rest[..end].to_string(), rest[end + 1..].trim()))
+}
+
+/// Parse `BACKUP DATABASE <db> TO '<path|s3://bucket/prefix>'`.
+pub fn parse_backup(s: &str) -> Result<Command> {
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let rest = s["BACKUP".len()..].trim();
+    if !rest.to_ascii_uppercase().starts_with("DATABASE ") { bail!("Invalid BACKUP syntax: expected BACKUP DATABASE <db> TO '<target>'"); }
+    let rest = rest["DATABASE".len()..].trim();
+    let up = rest.to_ascii_uppercase();
+    let to_pos = up.find(" TO ").ok_or_else(|| anyhow!("Invalid BACKUP: missing TO '<target>'"))?;
+    let database = rest[..to_pos].trim().to_string();
+    if database.is_empty() { bail!("Invalid BACKUP: missing database"); }
+    let (target, tail) = quoted_literal(rest[to_pos + 4..].trim()).ok_or_else(|| anyhow!("Invalid BACKUP: target must be a quoted path"))?;
+    if target.is_empty() { bail!("Invalid BACKUP: empty target"); }
+    if !tail.is_empty() { bail!("Unexpected text after BACKUP target: {}", tail); }
+    Ok(Command::BackupDatabase { database, target })
+}
+
+/// Parse `RESTORE DATABASE <db> FROM '<path|s3://...>' [AS OF <epoch_ms> | AS OF '<rfc3339>']`.
+pub fn parse_restore(s: &str) -> Result<Command> {
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let rest = s["RESTORE".len()..].trim();
+    if !rest.to_ascii_uppercase().starts_with("DATABASE ") { bail!("Invalid RESTORE syntax: expected RESTORE DATABASE <db> FROM '<source>'"); }
+    let rest = rest["DATABASE".len()..].trim();
+    let up = rest.to_ascii_uppercase();
+    let from_pos = up.find(" FROM ").ok_or_else(|| anyhow!("Invalid RESTORE: missing FROM '<source>'"))?;
+    let database = rest[..from_pos].trim().to_string();
+    if database.is_empty() { bail!("Invalid RESTORE: missing database"); }
+    let (source, tail) = quoted_literal(rest[from_pos + 6..].trim()).ok_or_else(|| anyhow!("Invalid RESTORE: source must be a quoted path"))?;
+    let as_of = if tail.is_empty() {
+        None
+    } else {
+        let tup = tail.to_ascii_uppercase();
+        if !tup.starts_with("AS OF ") { bail!("Unexpected text after RESTORE source: {}", tail); }
+        Some(crate::storage::backup::parse_as_of(&tail["AS OF ".len()..])?)
+    };
+    Ok(Command::RestoreDatabase { database, source, as_of })
+}
+
+/// Parse `ATTACH DATABASE '<path>' AS <name>`.
+pub fn parse_attach(s: &str) -> Result<Command> {
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let rest = s["ATTACH".len()..].trim();
+    if !rest.to_ascii_uppercase().starts_with("DATABASE ") { bail!("Invalid ATTACH syntax: expected ATTACH DATABASE '<path>' AS <name>"); }
+    let (path, tail) = quoted_literal(rest["DATABASE".len()..].trim()).ok_or_else(|| anyhow!("Invalid ATTACH: path must be a quoted string"))?;
+    if path.is_empty() { bail!("Invalid ATTACH: empty path"); }
+    if !tail.to_ascii_uppercase().starts_with("AS ") { bail!("Invalid ATTACH: missing AS <name>"); }
+    let name = tail[3..].trim();
+    if name.is_empty() || name.contains(char::is_whitespace) { bail!("Invalid ATTACH: expected a single database name after AS"); }
+    Ok(Command::AttachDatabase { path, name: crate::ident::normalize_identifier(name) })
+}
+
+/// Parse `DETACH DATABASE <name>`.
+pub fn parse_detach(s: &str) -> Result<Command> {
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let rest = s["DETACH".len()..].trim();
+    if !rest.to_ascii_uppercase().starts_with("DATABASE ") { bail!("Invalid DETACH syntax: expected DETACH DATABASE <name>"); }
+    let name = rest["DATABASE".len()..].trim();
+    if name.is_empty() || name.contains(char::is_whitespace) { bail!("Invalid DETACH: expected a single database name"); }
+    Ok(Command::DetachDatabase { name: crate::ident::normalize_identifier(name) })
+}
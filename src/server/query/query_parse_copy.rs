@@ -0,0 +1,220 @@
+/// Parse `COPY <table> [(col, ...)] FROM {STDIN | '<path>' | '<url>'} [[WITH] (opt [value], ...)]`
+/// and `COPY {<table> [(col, ...)] | (<select>)} TO STDOUT [[WITH] (opt [value], ...)]`.
+///
+/// Options: FORMAT text|csv|ndjson|json|parquet|binary, HEADER [true|false],
+/// DELIMITER '<c>', NULL '<s>', BATCH_SIZE <n>. The bare PostgreSQL forms
+/// `CSV`, `BINARY` and `HEADER` are accepted as well. When FORMAT is omitted,
+/// file/URL sources infer it from the extension; STDIN and STDOUT default to text.
+/// COPY TO writes text, csv or binary only.
+pub fn parse_copy(s: &str) -> Result<Command> {
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let rest = s["COPY".len()..].trim();
+    let up = rest.to_ascii_uppercase();
+    let Some(from_pos) = find_keyword(&up, "FROM") else {
+        let to_pos = find_keyword(&up, "TO").ok_or_else(|| anyhow!("COPY syntax error: expected FROM or TO"))?;
+        return parse_copy_to(rest[..to_pos].trim(), rest[to_pos + "TO".len()..].trim());
+    };
+    let target = rest[..from_pos].trim();
+    let after_from = rest[from_pos + "FROM".len()..].trim();
+    let (table, columns) = parse_copy_target(target)?;
+
+    // Source
+    let (source, tail) = if after_from.to_ascii_uppercase().starts_with("STDIN") {
+        (CopySource::Stdin, after_from["STDIN".len()..].trim())
+    } else if let Some(stripped) = after_from.strip_prefix('\'') {
+        let end = stripped.find('\'').ok_or_else(|| anyhow!("COPY source literal missing closing quote"))?;
+        let lit = stripped[..end].to_string();
+        let src = if lit.to_ascii_lowercase().starts_with("http://") || lit.to_ascii_lowercase().starts_with("https://") {
+            CopySource::Url(lit)
+        } else {
+            CopySource::File(lit)
+        };
+        (src, stripped[end + 1..].trim())
+    } else {
+        bail!("COPY FROM expects STDIN or a quoted path/URL");
+    };
+
+    let options = parse_copy_options(tail, default_format_for(&source))?;
+    Ok(Command::CopyFrom { table, columns, source, options })
+}
+
+fn parse_copy_to(target: &str, after_to: &str) -> Result<Command> {
+    if !after_to.to_ascii_uppercase().starts_with("STDOUT") {
+        bail!("COPY TO supports only STDOUT");
+    }
+    let (relation, columns) = if let Some(inner) = target.strip_prefix('(') {
+        let inner = inner.strip_suffix(')').ok_or_else(|| anyhow!("COPY query missing closing )"))?.trim();
+        if inner.is_empty() { bail!("COPY requires a query inside ( )"); }
+        (CopyRelation::Query(inner.to_string()), Vec::new())
+    } else {
+        let (table, columns) = parse_copy_target(target)?;
+        (CopyRelation::Table(table), columns)
+    };
+    let options = parse_copy_options(after_to["STDOUT".len()..].trim(), CopyFormat::Text)?;
+    if !matches!(options.format, CopyFormat::Text | CopyFormat::Csv | CopyFormat::Binary) {
+        bail!("COPY TO STDOUT supports FORMAT text, csv or binary");
+    }
+    Ok(Command::CopyTo { relation, columns, options })
+}
+
+/// Table name with an optional column list: `t` or `t (a, b)`.
+fn parse_copy_target(target: &str) -> Result<(String, Vec<String>)> {
+    let (table, columns) = match target.find('(') {
+        Some(p) => {
+            let end = target.rfind(')').ok_or_else(|| anyhow!("COPY column list missing closing )"))?;
+            let cols: Vec<String> = target[p + 1..end]
+                .split(',')
+                .map(|c| c.trim().trim_matches('"').to_string())
+                .filter(|c| !c.is_empty())
+                .collect();
+            (target[..p].trim().to_string(), cols)
+        }
+        None => (target.to_string(), Vec::new()),
+    };
+    if table.is_empty() { bail!("COPY requires a table name"); }
+    Ok((table, columns))
+}
+
+/// Parse the option list after the source/target, starting from the defaults of `default_format`.
+fn parse_copy_options(tail: &str, default_format: CopyFormat) -> Result<CopyOptions> {
+    let mut options = CopyOptions::for_format(default_format);
+    let mut format_set = false;
+    let mut delimiter_set = false;
+    let mut null_set = false;
+    let mut tail = tail;
+    if tail.to_ascii_uppercase().starts_with("WITH") { tail = tail["WITH".len()..].trim(); }
+    let body = tail.strip_prefix('(').map(|t| t.strip_suffix(')').unwrap_or(t)).unwrap_or(tail);
+    let items: Vec<String> = if tail.starts_with('(') {
+        split_quoted_commas(body)
+    } else {
+        // Legacy unparenthesized options are whitespace separated: CSV HEADER DELIMITER ';'
+        split_legacy_options(body)
+    };
+    for item in items {
+        let mut parts = item.splitn(2, char::is_whitespace);
+        let key = parts.next().unwrap_or("").to_ascii_uppercase();
+        let val = parts.next().map(|v| v.trim().trim_matches('\'').to_string());
+        match key.as_str() {
+            "FORMAT" => {
+                let v = val.ok_or_else(|| anyhow!("COPY FORMAT requires a value"))?;
+                options.format = parse_format(&v)?;
+                format_set = true;
+            }
+            "CSV" | "BINARY" | "TEXT" | "NDJSON" | "PARQUET" => { options.format = parse_format(&key)?; format_set = true; }
+            "HEADER" => {
+                options.header = match val.as_deref().map(|v| v.to_ascii_lowercase()) {
+                    None => true,
+                    Some(v) => matches!(v.as_str(), "true" | "on" | "1" | "match"),
+                };
+            }
+            "DELIMITER" => {
+                let v = val.ok_or_else(|| anyhow!("COPY DELIMITER requires a value"))?;
+                let v = if v == "\\t" { "\t".to_string() } else { v };
+                let mut chars = v.chars();
+                options.delimiter = chars.next().ok_or_else(|| anyhow!("COPY DELIMITER must be a single character"))?;
+                if chars.next().is_some() { bail!("COPY DELIMITER must be a single character"); }
+                delimiter_set = true;
+            }
+            "NULL" => { options.null_str = val.unwrap_or_default(); null_set = true; }
+            "BATCH_SIZE" => {
+                let v = val.ok_or_else(|| anyhow!("COPY BATCH_SIZE requires a value"))?;
+                options.batch_size = v.parse::<usize>().map_err(|_| anyhow!("COPY BATCH_SIZE must be a positive integer"))?;
+                if options.batch_size == 0 { bail!("COPY BATCH_SIZE must be a positive integer"); }
+            }
+            other => bail!("Unsupported COPY option: {}", other),
+        }
+    }
+    // Re-derive format defaults for delimiter/NULL unless explicitly provided
+    if format_set {
+        let d = CopyOptions::for_format(options.format);
+        if !delimiter_set { options.delimiter = d.delimiter; }
+        if !null_set { options.null_str = d.null_str; }
+    }
+    Ok(options)
+}
+
+fn parse_format(v: &str) -> Result<CopyFormat> {
+    Ok(match v.to_ascii_lowercase().as_str() {
+        "text" => CopyFormat::Text,
+        "csv" => CopyFormat::Csv,
+        "ndjson" | "json" | "jsonl" => CopyFormat::Ndjson,
+        "parquet" => CopyFormat::Parquet,
+        "binary" => CopyFormat::Binary,
+        other => bail!("Unsupported COPY format: {}", other),
+    })
+}
+
+fn default_format_for(source: &CopySource) -> CopyFormat {
+    let path = match source {
+        CopySource::Stdin => return CopyFormat::Text,
+        CopySource::File(p) => p.as_str(),
+        CopySource::Url(u) => u.split(['?', '#']).next().unwrap_or(u),
+    };
+    let low = path.to_ascii_lowercase();
+    if low.ends_with(".parquet") { CopyFormat::Parquet }
+    else if low.ends_with(".ndjson") || low.ends_with(".jsonl") || low.ends_with(".json") { CopyFormat::Ndjson }
+    else { CopyFormat::Csv }
+}
+
+/// Find a standalone keyword (surrounded by whitespace) outside parentheses.
+fn find_keyword(up: &str, kw: &str) -> Option<usize> {
+    let bytes = up.as_bytes();
+    let mut depth = 0i32;
+    for (i, ch) in up.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 && up[i..].starts_with(kw) => {
+                let before_ok = i == 0 || bytes[i - 1].is_ascii_whitespace() || bytes[i - 1] == b')';
+                let after = i + kw.len();
+                let after_ok = after >= bytes.len() || bytes[after].is_ascii_whitespace();
+                if before_ok && after_ok { return Some(i); }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split an option list on commas that are not inside single quotes.
+fn split_quoted_commas(s: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut cur = String::new();
+    let mut in_q = false;
+    for ch in s.chars() {
+        match ch {
+            '\'' => { in_q = !in_q; cur.push(ch); }
+            ',' if !in_q => { if !cur.trim().is_empty() { out.push(cur.trim().to_string()); } cur.clear(); }
+            _ => cur.push(ch),
+        }
+    }
+    if !cur.trim().is_empty() { out.push(cur.trim().to_string()); }
+    out
+}
+
+/// Split `CSV HEADER DELIMITER ';' NULL ''` into option items, pairing keys that take values.
+fn split_legacy_options(s: &str) -> Vec<String> {
+    let mut toks: Vec<String> = Vec::new();
+    let mut cur = String::new();
+    let mut in_q = false;
+    for ch in s.chars() {
+        if ch == '\'' { in_q = !in_q; cur.push(ch); continue; }
+        if ch.is_whitespace() && !in_q {
+            if !cur.is_empty() { toks.push(std::mem::take(&mut cur)); }
+        } else { cur.push(ch); }
+    }
+    if !cur.is_empty() { toks.push(cur); }
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < toks.len() {
+        let k = toks[i].to_ascii_uppercase();
+        if matches!(k.as_str(), "DELIMITER" | "NULL" | "FORMAT" | "BATCH_SIZE") && i + 1 < toks.len() {
+            out.push(format!("{} {}", toks[i], toks[i + 1]));
+            i += 2;
+        } else {
+            out.push(toks[i].clone());
+            i += 1;
+        }
+    }
+    out
+}
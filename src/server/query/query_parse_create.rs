@@ -109,6 +109,25 @@ pub fn parse_create(s: &str) -> Result<Command> {
         let normalized_name = crate::ident::normalize_identifier(name);
         return Ok(Command::CreateView { name: normalized_name, or_alter, if_not_exists, definition_sql: def_sql.to_string() });
     }
+    if up.starts_with("SLICE ") || up.starts_with("OR REPLACE SLICE ") {
+        // CREATE [OR REPLACE] SLICE [IF NOT EXISTS] <name> AS SLICE ...
+        let or_replace = up.starts_with("OR REPLACE SLICE ");
+        let after = if or_replace { &rest["OR REPLACE SLICE ".len()..] } else { &rest["SLICE ".len()..] };
+        let mut if_not_exists = false;
+        let mut a = after.trim();
+        if a.to_uppercase().starts_with("IF NOT EXISTS ") { if_not_exists = true; a = a["IF NOT EXISTS ".len()..].trim(); }
+        let as_pos = find_as_token(a).ok_or_else(|| anyhow::anyhow!("Invalid CREATE SLICE: expected AS"))?;
+        let name = a[..as_pos].trim();
+        let mut k = as_pos + 2;
+        while k < a.len() && is_ws(a.as_bytes()[k] as char) { k += 1; }
+        let def_sql = a[k..].trim().trim_end_matches(';').trim_end();
+        if name.is_empty() { anyhow::bail!("Invalid CREATE SLICE: missing slice name"); }
+        if !def_sql.to_uppercase().starts_with("SLICE") { anyhow::bail!("Invalid CREATE SLICE: expected SLICE definition after AS"); }
+        // Reject a malformed definition now rather than when the slice is first used
+        parse_slice(def_sql)?;
+        let normalized_name = crate::ident::normalize_identifier(name);
+        return Ok(Command::CreateSlice { name: normalized_name, or_replace, if_not_exists, definition_sql: def_sql.to_string() });
+    }
     if up.starts_with("VECTOR INDEX ") {
         // CREATE VECTOR INDEX <name> ON <table>(<column>) USING hnsw [WITH (k=v, ...)]
         let after = &rest["VECTOR INDEX ".len()..];
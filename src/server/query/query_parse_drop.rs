@@ -22,6 +22,18 @@ pub fn parse_drop(s: &str) -> Result<Command> {
         let normalized_name = crate::ident::normalize_identifier(tail);
         return Ok(Command::DropView { name: normalized_name, if_exists });
     }
+    if up.starts_with("SLICE ") {
+        // DROP SLICE [IF EXISTS] <name>
+        let mut tail = rest["SLICE ".len()..].trim();
+        let mut if_exists = false;
+        if tail.to_uppercase().starts_with("IF EXISTS ") {
+            if_exists = true;
+            tail = tail["IF EXISTS ".len()..].trim();
+        }
+        if tail.is_empty() { anyhow::bail!("Invalid DROP SLICE: missing slice name"); }
+        let normalized_name = crate::ident::normalize_identifier(tail);
+        return Ok(Command::DropSlice { name: normalized_name, if_exists });
+    }
     if up.starts_with("VECTOR INDEX ") {
         // DROP VECTOR INDEX <name>
         let name = rest["VECTOR INDEX ".len()..].trim();
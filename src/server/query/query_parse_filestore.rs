@@ -112,6 +112,70 @@ pub fn parse_filestore(s: &str) -> Result<Command> {
         let (logical, _) = parse_quoted_first(&tail[10..].trim())?;
         return Ok(Command::DeleteFilePathCmd { filestore: fs, logical_path: logical });
     }
+    if up.starts_with("SET FILE METADATA IN FILESTORE ") {
+        // SET FILE METADATA IN FILESTORE <name> PATH '<logical>' ('<key>' = '<value>' | NULL, ...)
+        let mut tail = s.trim()["SET FILE METADATA IN FILESTORE ".len()..].trim().trim_end_matches(';').trim().to_string();
+        let sp = tail.find(' ').unwrap_or(tail.len());
+        let fs = crate::ident::normalize_identifier(&tail[..sp]);
+        tail = tail[sp..].trim().to_string();
+        let up2 = tail.to_uppercase();
+        if !up2.starts_with("PATH ") { bail!("SET FILE METADATA: expected PATH '<logical>'"); }
+        let (logical, rest) = parse_quoted_first(&tail[5..].trim())?;
+        let changes = parse_metadata_pairs(&rest)?;
+        return Ok(Command::SetFileMetadataCmd { filestore: fs, logical_path: logical, changes });
+    }
+    if up.starts_with("SET LEGAL HOLD ") {
+        // SET LEGAL HOLD IN FILESTORE <name> PATH '<logical>' [REASON '<text>']
+        let (fs, rest) = parse_branch_target(&s.trim()["SET LEGAL HOLD ".len()..], "IN FILESTORE ", "SET LEGAL HOLD")?;
+        if !rest.to_uppercase().starts_with("PATH ") { bail!("SET LEGAL HOLD: expected PATH '<logical>'"); }
+        let (logical, rest) = parse_quoted_first(&rest[5..])?;
+        let (reason, rem) = parse_optional_kv_str(&rest, "REASON")?;
+        if !rem.is_empty() { bail!("SET LEGAL HOLD: unexpected '{}'", rem); }
+        return Ok(Command::SetLegalHoldCmd { filestore: fs, logical_path: logical, reason });
+    }
+    if up.starts_with("CLEAR LEGAL HOLD ") {
+        // CLEAR LEGAL HOLD IN FILESTORE <name> PATH '<logical>'
+        let (fs, rest) = parse_branch_target(&s.trim()["CLEAR LEGAL HOLD ".len()..], "IN FILESTORE ", "CLEAR LEGAL HOLD")?;
+        if !rest.to_uppercase().starts_with("PATH ") { bail!("CLEAR LEGAL HOLD: expected PATH '<logical>'"); }
+        let (logical, rem) = parse_quoted_first(&rest[5..])?;
+        if !rem.is_empty() { bail!("CLEAR LEGAL HOLD: unexpected '{}'", rem); }
+        return Ok(Command::ClearLegalHoldCmd { filestore: fs, logical_path: logical });
+    }
+    if up.starts_with("LOCK FILE ") {
+        // LOCK FILE IN FILESTORE <name> PATH '<logical>' [FOR <seconds>] [NOTE '<text>']
+        let (fs, rest) = parse_branch_target(&s.trim()["LOCK FILE ".len()..], "IN FILESTORE ", "LOCK FILE")?;
+        if !rest.to_uppercase().starts_with("PATH ") { bail!("LOCK FILE: expected PATH '<logical>'"); }
+        let (logical, mut rest) = parse_quoted_first(&rest[5..])?;
+        let mut ttl_seconds = None;
+        if rest.to_uppercase().starts_with("FOR ") {
+            let tail = rest[4..].trim();
+            let end = tail.find(' ').unwrap_or(tail.len());
+            ttl_seconds = Some(tail[..end].parse::<u64>().map_err(|_| anyhow::anyhow!("LOCK FILE: FOR expects a number of seconds"))?);
+            rest = tail[end..].trim().to_string();
+        }
+        let (note, rem) = parse_optional_kv_str(&rest, "NOTE")?;
+        if !rem.is_empty() { bail!("LOCK FILE: unexpected '{}'", rem); }
+        return Ok(Command::LockFileCmd { filestore: fs, logical_path: logical, ttl_seconds, note });
+    }
+    if up.starts_with("UNLOCK FILE ") {
+        // UNLOCK FILE IN FILESTORE <name> PATH '<logical>' [FORCE]
+        let (fs, rest) = parse_branch_target(&s.trim()["UNLOCK FILE ".len()..], "IN FILESTORE ", "UNLOCK FILE")?;
+        if !rest.to_uppercase().starts_with("PATH ") { bail!("UNLOCK FILE: expected PATH '<logical>'"); }
+        let (logical, rem) = parse_quoted_first(&rest[5..])?;
+        let force = rem.eq_ignore_ascii_case("FORCE");
+        if !force && !rem.is_empty() { bail!("UNLOCK FILE: unexpected '{}'", rem); }
+        return Ok(Command::UnlockFileCmd { filestore: fs, logical_path: logical, force });
+    }
+    if up.starts_with("ROTATE FILESTORE KEY ") {
+        // ROTATE FILESTORE KEY <name> [TO '<master key id>']
+        let tail = s.trim()["ROTATE FILESTORE KEY ".len()..].trim().trim_end_matches(';').trim();
+        let sp = tail.find(' ').unwrap_or(tail.len());
+        if sp == 0 { bail!("ROTATE FILESTORE KEY: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(&tail[..sp]);
+        let (to_key, rem) = parse_optional_kv_str(&tail[sp..], "TO")?;
+        if !rem.is_empty() { bail!("ROTATE FILESTORE KEY: unexpected '{}'", rem); }
+        return Ok(Command::RotateFilestoreKeyCmd { filestore: fs, to_key });
+    }
     // Versioning -----------------------------------------
     if up.starts_with("CREATE TREE IN FILESTORE ") {
         // CREATE TREE IN FILESTORE <name> [LIKE '<prefix>']
@@ -159,9 +223,183 @@ pub fn parse_filestore(s: &str) -> Result<Command> {
         }
         return Ok(Command::CommitTreeCmd { filestore: fs, tree_id, parents, branch, author_name, author_email, message, tags });
     }
+    // Git remote sync ------------------------------------
+    if up.starts_with("SYNC FILESTORE ") {
+        // SYNC FILESTORE <name> PUSH [BRANCH '<b>'] [FORCE] [CORRELATION '<id>']
+        // SYNC FILESTORE <name> PULL [BRANCH '<b>'] [POLICY '<fail|ours|theirs>'] [CORRELATION '<id>']
+        let mut tail = s.trim()["SYNC FILESTORE ".len()..].trim().trim_end_matches(';').trim().to_string();
+        let sp = tail.find(' ').unwrap_or(tail.len());
+        let fs = crate::ident::normalize_identifier(&tail[..sp]);
+        tail = tail[sp..].trim().to_string();
+        let dir_end = tail.find(' ').unwrap_or(tail.len());
+        let direction = tail[..dir_end].to_ascii_lowercase();
+        if direction != "push" && direction != "pull" { bail!("SYNC FILESTORE: expected PUSH or PULL"); }
+        let mut rest = tail[dir_end..].trim().to_string();
+        let mut branch: Option<String> = None;
+        let mut force = false;
+        let mut policy: Option<String> = None;
+        let mut correlation_id: Option<String> = None;
+        loop {
+            let upr = rest.to_uppercase();
+            if upr.is_empty() { break; }
+            if upr.starts_with("BRANCH ") { let (v, r2) = parse_quoted_first(&rest[7..].trim())?; branch = Some(v); rest = r2; continue; }
+            if upr.starts_with("POLICY ") {
+                let (v, r2) = parse_quoted_first(&rest[7..].trim())?;
+                crate::server::exec::filestore::ConflictPolicy::parse(&v)?;
+                policy = Some(v.to_ascii_lowercase()); rest = r2; continue;
+            }
+            if upr.starts_with("CORRELATION ") { let (v, r2) = parse_quoted_first(&rest[12..].trim())?; correlation_id = Some(v); rest = r2; continue; }
+            if upr == "FORCE" || upr.starts_with("FORCE ") { force = true; rest = rest[5..].trim().to_string(); continue; }
+            bail!("SYNC FILESTORE: unexpected '{}'", rest);
+        }
+        if force && direction == "pull" { bail!("SYNC FILESTORE: FORCE applies to PUSH only"); }
+        if policy.is_some() && direction == "push" { bail!("SYNC FILESTORE: POLICY applies to PULL only"); }
+        return Ok(Command::SyncFilestoreCmd { filestore: fs, direction, branch, force, policy, correlation_id });
+    }
+    // Branches --------------------------------------------
+    if up.starts_with("CREATE BRANCH ") {
+        // CREATE BRANCH '<b>' IN FILESTORE <name> [FROM '<branch>']
+        let (branch, rest) = parse_quoted_first(&s.trim()["CREATE BRANCH ".len()..])?;
+        let (fs, rest) = parse_branch_target(&rest, "IN FILESTORE ", "CREATE BRANCH")?;
+        let (from, rem) = parse_optional_kv_str(&rest, "FROM")?;
+        if !rem.is_empty() { bail!("CREATE BRANCH: unexpected '{}'", rem); }
+        return Ok(Command::CreateBranchCmd { filestore: fs, branch, from });
+    }
+    if up.starts_with("CHECKOUT BRANCH ") {
+        // CHECKOUT BRANCH '<b>' IN FILESTORE <name>
+        let (branch, rest) = parse_quoted_first(&s.trim()["CHECKOUT BRANCH ".len()..])?;
+        let (fs, rem) = parse_branch_target(&rest, "IN FILESTORE ", "CHECKOUT BRANCH")?;
+        if !rem.is_empty() { bail!("CHECKOUT BRANCH: unexpected '{}'", rem); }
+        return Ok(Command::CheckoutBranchCmd { filestore: fs, branch });
+    }
+    if up.starts_with("MERGE BRANCH ") {
+        // MERGE BRANCH '<b>' INTO FILESTORE <name> [MESSAGE '<msg>'] [AUTHOR_NAME '<name>'] [AUTHOR_EMAIL '<email>']
+        let (source, rest) = parse_quoted_first(&s.trim()["MERGE BRANCH ".len()..])?;
+        let (fs, mut rest) = parse_branch_target(&rest, "INTO FILESTORE ", "MERGE BRANCH")?;
+        let mut message: Option<String> = None;
+        let mut author_name: Option<String> = None;
+        let mut author_email: Option<String> = None;
+        loop {
+            let upr = rest.to_uppercase();
+            if upr.is_empty() { break; }
+            if upr.starts_with("MESSAGE ") { let (v, r2) = parse_quoted_first(&rest[8..].trim())?; message = Some(v); rest = r2; continue; }
+            if upr.starts_with("AUTHOR_NAME ") { let (v, r2) = parse_quoted_first(&rest[12..].trim())?; author_name = Some(v); rest = r2; continue; }
+            if upr.starts_with("AUTHOR_EMAIL ") { let (v, r2) = parse_quoted_first(&rest[13..].trim())?; author_email = Some(v); rest = r2; continue; }
+            bail!("MERGE BRANCH: unexpected '{}'", rest);
+        }
+        return Ok(Command::MergeBranchCmd { filestore: fs, source, message, author_name, author_email });
+    }
+    // Security policies ----------------------------------
+    if up.starts_with("CREATE SECURITY POLICY ") {
+        // CREATE SECURITY POLICY <id> FOR ROLE <role> {ALLOW | DENY} {<action>[, ...] | ALL} ON '<selector>' [PRIORITY <n>]
+        let (policy_id, rest) = parse_policy_id(&s.trim()["CREATE SECURITY POLICY ".len()..], "CREATE SECURITY POLICY")?;
+        let c = parse_policy_clauses(&rest, "CREATE SECURITY POLICY")?;
+        let (Some(role), Some(effect), Some(actions), Some(selector)) = (c.role, c.effect, c.actions, c.selector) else {
+            bail!("CREATE SECURITY POLICY: expected FOR ROLE <role> {{ALLOW | DENY}} <actions> ON '<selector>'");
+        };
+        return Ok(Command::CreateSecurityPolicyCmd { policy_id, role, effect, actions, selector, priority: c.priority.unwrap_or(0) });
+    }
+    if up.starts_with("ALTER SECURITY POLICY ") {
+        // ALTER SECURITY POLICY <id> [FOR ROLE <role>] [{ALLOW | DENY} {<action>[, ...] | ALL}] [ON '<selector>'] [PRIORITY <n>]
+        let (policy_id, rest) = parse_policy_id(&s.trim()["ALTER SECURITY POLICY ".len()..], "ALTER SECURITY POLICY")?;
+        let c = parse_policy_clauses(&rest, "ALTER SECURITY POLICY")?;
+        if c.role.is_none() && c.actions.is_none() && c.selector.is_none() && c.priority.is_none() {
+            bail!("ALTER SECURITY POLICY: nothing to change");
+        }
+        return Ok(Command::AlterSecurityPolicyCmd { policy_id, role: c.role, effect: c.effect, actions: c.actions, selector: c.selector, priority: c.priority });
+    }
+    if up.starts_with("DROP SECURITY POLICY ") {
+        // DROP SECURITY POLICY [IF EXISTS] <id>
+        let mut tail = s.trim()["DROP SECURITY POLICY ".len()..].trim();
+        let if_exists = tail.to_uppercase().starts_with("IF EXISTS ");
+        if if_exists { tail = &tail["IF EXISTS ".len()..]; }
+        let (policy_id, rem) = parse_policy_id(tail, "DROP SECURITY POLICY")?;
+        if !rem.is_empty() { bail!("DROP SECURITY POLICY: unexpected '{}'", rem); }
+        return Ok(Command::DropSecurityPolicyCmd { policy_id, if_exists });
+    }
+    if up.starts_with("EXPLAIN ACCESS ") {
+        // EXPLAIN ACCESS <action> ON FILESTORE <name> [PATH '<logical>'] [FOR USER '<user>']
+        let tail = s.trim()["EXPLAIN ACCESS ".len()..].trim();
+        let sp = tail.find(' ').unwrap_or(tail.len());
+        let action = tail[..sp].to_ascii_lowercase();
+        let (fs, rest) = parse_branch_target(&tail[sp..], "ON FILESTORE ", "EXPLAIN ACCESS")?;
+        let (logical_path, rest) = parse_optional_kv_str(&rest, "PATH")?;
+        let (user, rem) = parse_optional_kv_str(&rest, "FOR USER")?;
+        if !rem.is_empty() { bail!("EXPLAIN ACCESS: unexpected '{}'", rem); }
+        return Ok(Command::ExplainAccessCmd { filestore: fs, action, logical_path, user });
+    }
     anyhow::bail!("Unsupported FILESTORE command")
 }
 
+/// Policy id at the start of `s`; returns (id, rest).
+fn parse_policy_id(s: &str, cmd: &str) -> Result<(String, String)> {
+    let st = s.trim().trim_end_matches(';').trim();
+    let sp = st.find(' ').unwrap_or(st.len());
+    if sp == 0 { bail!("{}: missing policy name", cmd); }
+    Ok((crate::ident::normalize_identifier(&st[..sp]), st[sp..].trim().to_string()))
+}
+
+/// Clauses of CREATE / ALTER SECURITY POLICY; all optional here, CREATE checks what it needs.
+#[derive(Default)]
+struct PolicyClauses {
+    role: Option<String>,
+    effect: Option<String>,
+    actions: Option<Vec<String>>,
+    selector: Option<String>,
+    priority: Option<i32>,
+}
+
+fn parse_policy_clauses(s: &str, cmd: &str) -> Result<PolicyClauses> {
+    let mut out = PolicyClauses::default();
+    let mut rest = s.trim().to_string();
+    while !rest.is_empty() {
+        let upr = rest.to_uppercase();
+        if upr.starts_with("FOR ROLE ") {
+            let tail = rest[9..].trim();
+            let end = tail.find(' ').unwrap_or(tail.len());
+            out.role = Some(crate::ident::normalize_identifier(&tail[..end]));
+            rest = tail[end..].trim().to_string();
+        } else if upr.starts_with("ALLOW ") || upr.starts_with("DENY ") {
+            let effect = if upr.starts_with("ALLOW ") { "allow" } else { "deny" };
+            let tail = rest[effect.len()..].trim();
+            // The action list runs up to the next clause
+            let upt = tail.to_uppercase();
+            let end = [" ON ", " PRIORITY ", " FOR ROLE "].iter().filter_map(|k| upt.find(k)).min().unwrap_or(tail.len());
+            let actions: Vec<String> = tail[..end].split(',')
+                .map(|a| a.trim().to_ascii_lowercase())
+                .filter(|a| !a.is_empty())
+                .map(|a| if a == "all" { "*".to_string() } else { a })
+                .collect();
+            if actions.is_empty() { bail!("{}: expected actions after {}", cmd, effect.to_uppercase()); }
+            out.effect = Some(effect.to_string());
+            out.actions = Some(actions);
+            rest = tail[end..].trim().to_string();
+        } else if upr.starts_with("ON ") {
+            let (selector, r) = parse_quoted_first(&rest[3..])?;
+            out.selector = Some(selector);
+            rest = r;
+        } else if upr.starts_with("PRIORITY ") {
+            let tail = rest[9..].trim();
+            let end = tail.find(' ').unwrap_or(tail.len());
+            out.priority = Some(tail[..end].parse::<i32>().map_err(|_| anyhow::anyhow!("{}: PRIORITY expects an integer", cmd))?);
+            rest = tail[end..].trim().to_string();
+        } else {
+            bail!("{}: unexpected '{}'", cmd, rest);
+        }
+    }
+    Ok(out)
+}
+
+/// `<keyword> <filestore> ...` after a branch name; returns (filestore, rest).
+fn parse_branch_target(s: &str, keyword: &str, cmd: &str) -> Result<(String, String)> {
+    let st = s.trim().trim_end_matches(';').trim();
+    if !st.to_uppercase().starts_with(keyword) { bail!("{}: expected {}<name>", cmd, keyword); }
+    let tail = st[keyword.len()..].trim();
+    let sp = tail.find(' ').unwrap_or(tail.len());
+    if sp == 0 { bail!("{}: missing filestore name", cmd); }
+    Ok((crate::ident::normalize_identifier(&tail[..sp]), tail[sp..].trim().to_string()))
+}
+
 // Helpers: parse a single quoted string (') from start of `s`; returns (value, rest)
 fn parse_quoted_first(s: &str) -> Result<(String, String)> {
     let st = s.trim();
@@ -181,3 +419,41 @@ fn parse_optional_kv_str(s: &str, key: &str) -> Result<(Option<String>, String)>
         Ok((None, st.to_string()))
     }
 }
+
+/// Parse `('<key>' = '<value>' | NULL, ...)`; quotes inside values are doubled ('').
+fn parse_metadata_pairs(s: &str) -> Result<Vec<(String, Option<String>)>> {
+    let st = s.trim();
+    let inner = st.strip_prefix('(').and_then(|r| r.strip_suffix(')'))
+        .ok_or_else(|| anyhow::anyhow!("SET FILE METADATA: expected ('<key>' = '<value>', ...)"))?;
+    let mut out = Vec::new();
+    let mut rest = inner.trim();
+    while !rest.is_empty() {
+        let (key, r) = parse_sql_string(rest)?;
+        let r = r.trim_start().strip_prefix('=').ok_or_else(|| anyhow::anyhow!("SET FILE METADATA: expected = after '{}'", key))?.trim_start();
+        let (value, r) = if r.get(..4).map(|w| w.eq_ignore_ascii_case("NULL")).unwrap_or(false) {
+            (None, &r[4..])
+        } else {
+            let (v, r) = parse_sql_string(r)?;
+            (Some(v), r)
+        };
+        out.push((key, value));
+        rest = r.trim_start();
+        if let Some(r) = rest.strip_prefix(',') { rest = r.trim_start(); }
+        else if !rest.is_empty() { bail!("SET FILE METADATA: expected , between entries"); }
+    }
+    if out.is_empty() { bail!("SET FILE METADATA: no entries given"); }
+    Ok(out)
+}
+
+/// Single-quoted SQL string literal at the start of `s` ('' is a quote); returns (value, rest).
+fn parse_sql_string(s: &str) -> Result<(String, &str)> {
+    let body = s.strip_prefix('\'').ok_or_else(|| anyhow::anyhow!("expected quoted string"))?;
+    let mut val = String::new();
+    let mut chars = body.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != '\'' { val.push(c); continue; }
+        if matches!(chars.peek(), Some((_, '\''))) { chars.next(); val.push('\''); continue; }
+        return Ok((val, &body[i + 1..]));
+    }
+    bail!("unterminated quoted string")
+}
@@ -1,8 +1,8 @@
 use anyhow::Result;
 use crate::server::query::Command;
 
-// Syntax: GC GRAPH [<qualified_graph_name>]
-// When name is omitted, will use session default graph if set, else applies to all graphs.
+// Syntax: GC GRAPH [<qualified_graph_name>] | GC FILESTORE <name>
+// When the graph name is omitted, will use session default graph if set, else applies to all graphs.
 pub fn parse_gc(s: &str) -> Result<Command> {
     let rest = s.trim();
     let up = rest.to_ascii_uppercase();
@@ -18,5 +18,10 @@ pub fn parse_gc(s: &str) -> Result<Command> {
         let normalized = crate::ident::normalize_identifier(name);
         return Ok(Command::GcGraph { name: Some(normalized) });
     }
-    anyhow::bail!("Unsupported GC command; use: GC GRAPH [<db/schema/graph>]")
+    if up_tail.starts_with("FILESTORE") {
+        let name = tail[9..].trim().trim_end_matches(';').trim();
+        if name.is_empty() { anyhow::bail!("GC FILESTORE: missing filestore name"); }
+        return Ok(Command::GcFilestore { filestore: crate::ident::normalize_identifier(name) });
+    }
+    anyhow::bail!("Unsupported GC command; use: GC GRAPH [<db/schema/graph>] | GC FILESTORE <name>")
 }
@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use crate::server::query::{parse_insert, ArithTerm, Command, GraphElement};
+
+// Graph mutation statements over a graph catalog:
+// INSERT INTO GRAPH <graph> {NODES <Label> | EDGES <Type>} (col, ...) VALUES (v, ...), ...
+// DELETE FROM GRAPH <graph> [DETACH] NODES <Label> VALUES (key), ...
+// DELETE FROM GRAPH <graph> EDGES <Type> VALUES (src, dst), ...
+// Value tuples are parsed by the regular INSERT parser.
+
+pub fn parse_graph_dml(s: &str) -> Result<Command> {
+    let t = s.trim();
+    let up = t.to_ascii_uppercase();
+    let (is_insert, rest) = if up.starts_with("INSERT INTO GRAPH ") {
+        (true, &t["INSERT INTO GRAPH ".len()..])
+    } else if up.starts_with("DELETE FROM GRAPH ") {
+        (false, &t["DELETE FROM GRAPH ".len()..])
+    } else {
+        anyhow::bail!("Expected INSERT INTO GRAPH or DELETE FROM GRAPH");
+    };
+    let (graph, rest) = next_word(rest);
+    let graph = graph.trim_matches(['"', '\'']).to_string();
+    if graph.is_empty() { anyhow::bail!("Graph DML: missing graph name"); }
+
+    let (mut kw, mut rest) = next_word(rest);
+    let mut detach = false;
+    if !is_insert && kw.eq_ignore_ascii_case("DETACH") {
+        detach = true;
+        (kw, rest) = next_word(rest);
+    }
+    let element = if kw.eq_ignore_ascii_case("NODES") {
+        GraphElement::Nodes
+    } else if kw.eq_ignore_ascii_case("EDGES") {
+        if detach { anyhow::bail!("DELETE FROM GRAPH: DETACH applies to NODES only"); }
+        GraphElement::Edges
+    } else {
+        anyhow::bail!("Graph DML: expected NODES or EDGES after graph name");
+    };
+    let (name, tail) = take_name(rest);
+    if name.is_empty() { anyhow::bail!("Graph DML: missing {} name", if element == GraphElement::Nodes { "node label" } else { "edge type" }); }
+
+    // Reuse INSERT's column list and VALUES tuple parsing on the remaining text
+    let tail = tail.trim();
+    if is_insert {
+        let (columns, values) = insert_tuples(&name, tail)?;
+        if columns.is_empty() { anyhow::bail!("INSERT INTO GRAPH: a column list is required"); }
+        return Ok(Command::InsertGraph { graph, element, name, columns, values });
+    }
+    if !tail.to_ascii_uppercase().starts_with("VALUES") {
+        anyhow::bail!("DELETE FROM GRAPH: expected VALUES (...) after the {}", if element == GraphElement::Nodes { "label" } else { "type" });
+    }
+    let (_, keys) = insert_tuples(&name, &format!("(k) {}", tail))?;
+    let width = if element == GraphElement::Nodes { 1 } else { 2 };
+    if let Some(bad) = keys.iter().find(|v| v.len() != width || v.iter().any(|x| matches!(x, ArithTerm::Null))) {
+        anyhow::bail!("DELETE FROM GRAPH: each tuple must hold {} non-null value(s), got {:?}", width, bad);
+    }
+    Ok(Command::DeleteGraph { graph, element, name, keys, detach })
+}
+
+fn insert_tuples(name: &str, tail: &str) -> Result<(Vec<String>, Vec<Vec<ArithTerm>>)> {
+    match parse_insert(&format!("INSERT INTO {} {}", name, tail))? {
+        Command::Insert { columns, values, .. } => Ok((columns, values)),
+        _ => anyhow::bail!("Graph DML: expected VALUES (...)"),
+    }
+}
+
+fn next_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    (&s[..end], &s[end..])
+}
+
+// Label or type name: stops at whitespace or the column list's '('
+fn take_name(s: &str) -> (String, &str) {
+    let s = s.trim_start();
+    let end = s.find(|c: char| c.is_whitespace() || c == '(').unwrap_or(s.len());
+    (s[..end].trim_matches('"').to_string(), &s[end..])
+}
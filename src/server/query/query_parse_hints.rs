@@ -0,0 +1,91 @@
+//! Optimizer hints embedded in `/*+ ... */` comments.
+//!
+//! Hints are collected from the raw statement text before comments are stripped,
+//! e.g. `SELECT /*+ NO_PRUNE JOIN_ORDER(b, a) */ * FROM a JOIN b ON ...`. Several
+//! hints may share a comment, separated by spaces or commas. Recognised hints:
+//!
+//! - `NO_PRUNE`: load every chunk and partition, ignoring bloom and partition pruning.
+//! - `JOIN_ORDER(t1, t2, ...)`: join the named tables (aliases or table names) in
+//!   this order; unnamed tables follow in their written order. Only applied when
+//!   every join is INNER.
+//!
+//! Unknown hints are kept in `ignored` so EXPLAIN can report them.
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryHints {
+    pub no_prune: bool,
+    pub join_order: Option<Vec<String>>,
+    pub ignored: Vec<String>,
+}
+
+impl QueryHints {
+    pub fn is_empty(&self) -> bool { !self.no_prune && self.join_order.is_none() && self.ignored.is_empty() }
+
+    /// Human-readable hint list, in a stable order.
+    pub fn describe(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.no_prune { out.push("NO_PRUNE".to_string()); }
+        if let Some(order) = &self.join_order { out.push(format!("JOIN_ORDER({})", order.join(", "))); }
+        for h in &self.ignored { out.push(format!("{} (ignored)", h)); }
+        out
+    }
+
+    fn apply(&mut self, name: &str, args: Option<Vec<String>>) {
+        match (name.to_ascii_uppercase().as_str(), args) {
+            ("NO_PRUNE", None) => self.no_prune = true,
+            ("JOIN_ORDER", Some(a)) if !a.is_empty() => self.join_order = Some(a),
+            (_, None) => self.ignored.push(name.to_string()),
+            (_, Some(a)) => self.ignored.push(format!("{}({})", name, a.join(", "))),
+        }
+    }
+}
+
+/// Parse the body of one hint comment (between `/*+` and `*/`).
+fn parse_hint_body(body: &str, hints: &mut QueryHints) {
+    let chars: Vec<char> = body.chars().collect();
+    let mut i = 0usize;
+    while i < chars.len() {
+        if chars[i].is_whitespace() || chars[i] == ',' { i += 1; continue; }
+        let start = i;
+        while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') { i += 1; }
+        if i == start { i += 1; continue; }
+        let name: String = chars[start..i].iter().collect();
+        let mut j = i;
+        while j < chars.len() && chars[j].is_whitespace() { j += 1; }
+        let args = if j < chars.len() && chars[j] == '(' {
+            let close = chars[j..].iter().position(|c| *c == ')').map(|p| j + p).unwrap_or(chars.len());
+            let inner: String = chars[j + 1..close].iter().collect();
+            i = (close + 1).min(chars.len());
+            Some(inner.split([',', ' ', '\t', '\n']).map(|s| s.trim().trim_matches('"').to_string()).filter(|s| !s.is_empty()).collect())
+        } else { None };
+        hints.apply(&name, args);
+    }
+}
+
+/// Collect hints from every `/*+ ... */` comment outside string literals.
+pub fn extract_hints(input: &str) -> QueryHints {
+    let mut hints = QueryHints::default();
+    let bytes = input.as_bytes();
+    let mut i = 0usize;
+    let mut in_squote = false;
+    let mut in_dquote = false;
+    while i < bytes.len() {
+        let ch = bytes[i];
+        if !in_dquote && ch == b'\'' { in_squote = !in_squote; i += 1; continue; }
+        if !in_squote && ch == b'"' { in_dquote = !in_dquote; i += 1; continue; }
+        if in_squote || in_dquote { i += 1; continue; }
+        if bytes[i..].starts_with(b"--") {
+            i = input[i..].find('\n').map(|p| i + p).unwrap_or(bytes.len());
+            continue;
+        }
+        if bytes[i..].starts_with(b"/*") {
+            let body_start = i + 2;
+            let end = input[body_start..].find("*/").map(|p| body_start + p).unwrap_or(bytes.len());
+            if let Some(body) = input[body_start..end].strip_prefix('+') { parse_hint_body(body, &mut hints); }
+            i = (end + 2).min(bytes.len());
+            continue;
+        }
+        i += 1;
+    }
+    hints
+}
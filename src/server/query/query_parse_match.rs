@@ -7,6 +7,52 @@ use crate::server::query::Command;
 // MATCH [USING GRAPH <graph>] (s:Label { key: <start_expr> })-[:<etype>*L..U]->(t:Label)
 // [WHERE <expr>] RETURN <proj_list> [ORDER BY <expr_list>] [LIMIT n]
 // We rewrite it into a SELECT over TVF graph_neighbors with optional WHERE/ORDER/LIMIT.
+//
+// Fixed-length patterns are the general form:
+// MATCH [USING GRAPH <graph>] (a[:Label] [{prop: value, ...}])-[[r][:TYPE]]->(b) <-[:TYPE]-(c) ...
+// [WHERE <expr>] RETURN <proj_list> [ORDER BY <expr_list>] [LIMIT n]
+// They rewrite into a SELECT over TVF graph_match, whose columns are named `<var>.<column>`
+// so `a.name` in WHERE/RETURN/ORDER BY resolves directly. Inline property maps become WHERE terms.
+
+/// A fixed-length MATCH pattern: `rels[i]` joins `nodes[i]` to `nodes[i + 1]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphPattern {
+    pub nodes: Vec<PatternNode>,
+    pub rels: Vec<PatternRel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternNode {
+    pub var: String,
+    pub label: Option<String>,
+    /// Inline `{prop: value}` terms, kept as SQL literal text
+    pub props: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternRel {
+    pub var: String,
+    pub etype: Option<String>,
+    /// `<-[...]-`: the edge runs from `nodes[i + 1]` to `nodes[i]`
+    pub incoming: bool,
+}
+
+impl GraphPattern {
+    /// Canonical text without inline property maps; `parse_graph_pattern` reads it back unchanged.
+    pub fn to_pattern_string(&self) -> String {
+        let node = |n: &PatternNode| match &n.label {
+            Some(l) => format!("({}:{})", n.var, l),
+            None => format!("({})", n.var),
+        };
+        let mut out = node(&self.nodes[0]);
+        for (r, n) in self.rels.iter().zip(self.nodes.iter().skip(1)) {
+            let inner = match &r.etype { Some(t) => format!("{}:{}", r.var, t), None => r.var.clone() };
+            if r.incoming { out.push_str(&format!("<-[{}]-", inner)); } else { out.push_str(&format!("-[{}]->", inner)); }
+            out.push_str(&node(n));
+        }
+        out
+    }
+}
 
 pub fn parse_match(s: &str) -> Result<Command> {
     let text = s.trim();
@@ -23,7 +69,11 @@ pub fn parse_match(s: &str) -> Result<Command> {
     // Extract pattern core: (s:Label { key: <start> })-[:Type*L..U]->(t:Label [{ key: <dst> }])
     // This is deliberately permissive; we only need start key, optional dst key (required for SHORTEST), edge type, and hops upper bound.
     let pat_re = Regex::new(r"\(\s*s\s*:\s*([A-Za-z_][A-Za-z0-9_]*)[^\)]*?\{[^}]*key\s*:\s*([^}]+)\}[^\)]*\)\s*-\s*\[\s*:\s*([A-Za-z_][A-Za-z0-9_]*)\s*\*\s*([0-9]+)\s*(?:\.\.\s*([0-9]+))?\s*\]\s*->\s*\(\s*t\s*:\s*([A-Za-z_][A-Za-z0-9_]*)\s*(?:\{[^}]*key\s*:\s*([^}]+)\}[^\)]*)?\)").unwrap();
-    let caps = pat_re.captures(text).ok_or_else(|| anyhow::anyhow!("Unsupported MATCH pattern. Expect (s:Label {{ key: ... }})-[:Type*L..U]->(t:Label)"))?;
+    let caps = match pat_re.captures(text) {
+        Some(c) => c,
+        None if is_shortest => anyhow::bail!("Unsupported MATCH SHORTEST pattern. Expect (s:Label {{ key: ... }})-[:Type*L..U]->(t:Label {{ key: ... }})"),
+        None => return parse_match_pattern(text, graph),
+    };
     let _s_label = caps.get(1).unwrap().as_str();
     let start_expr_raw = caps.get(2).unwrap().as_str().trim();
     let etype = caps.get(3).unwrap().as_str();
@@ -48,13 +98,7 @@ pub fn parse_match(s: &str) -> Result<Command> {
     let where_sql = where_part.map(|w| w.replace("t.key", "node_id").replace("s.key", &start_sql).replace("prev.key", "prev_id"));
     let order_sql = order_part.map(|o| o.replace("t.key", "node_id").replace("s.key", &start_sql).replace("prev.key", "prev_id"));
 
-    // Determine graph name: explicit USING GRAPH wins; else defer to session default at execution time
-    let mut gf = graph.unwrap_or_else(|| "__SESSION_DEFAULT__".to_string());
-    if gf == "__SESSION_DEFAULT__" {
-        if let Some(sess) = crate::system::get_current_graph_opt() {
-            gf = sess;
-        }
-    }
+    let gf = resolve_graph(graph);
     let etype_sql = format!("'{}'", etype);
     let u_sql = u;
 
@@ -86,6 +130,185 @@ pub fn parse_match(s: &str) -> Result<Command> {
     Ok(Command::MatchRewrite { sql: select_sql })
 }
 
+// Determine graph name: explicit USING GRAPH wins; else defer to session default at execution time
+fn resolve_graph(graph: Option<String>) -> String {
+    let mut gf = graph.unwrap_or_else(|| "__SESSION_DEFAULT__".to_string());
+    if gf == "__SESSION_DEFAULT__" {
+        if let Some(sess) = crate::system::get_current_graph_opt() {
+            gf = sess;
+        }
+    }
+    gf
+}
+
+// Fixed-length pattern form: rewrite into SELECT ... FROM graph_match(<graph>, '<pattern>')
+fn parse_match_pattern(text: &str, graph: Option<String>) -> Result<Command> {
+    // Pattern text starts after MATCH and the optional USING GRAPH <graph>
+    let mut body = text["MATCH".len()..].trim_start();
+    let using_re = Regex::new(r"(?i)^USING\s+GRAPH\s+[^\s]+").unwrap();
+    if let Some(m) = using_re.find(body) { body = body[m.end()..].trim_start(); }
+    let end = pattern_end(body);
+    let pattern = parse_graph_pattern(&body[..end])?;
+    let rest = &body[end..];
+
+    let where_part = extract_clause(rest, "WHERE", &["RETURN", "ORDER BY", "LIMIT"]);
+    let return_part = extract_clause(rest, "RETURN", &["ORDER BY", "LIMIT"]).ok_or_else(|| anyhow::anyhow!("MATCH requires a RETURN clause"))?;
+    let order_part = extract_clause(rest, "ORDER BY", &["LIMIT"]);
+    let limit_part = extract_clause(rest, "LIMIT", &[]);
+
+    let mut terms: Vec<String> = Vec::new();
+    for n in &pattern.nodes {
+        for (k, v) in &n.props { terms.push(format!("{}.{} = {}", n.var, k, v)); }
+    }
+    if let Some(w) = where_part { if !w.is_empty() { terms.push(format!("({})", w)); } }
+
+    let gf = resolve_graph(graph);
+    let mut select_sql = format!(
+        "SELECT {} FROM graph_match({},'{}')",
+        return_part,
+        quote_graph_if_needed(&gf),
+        pattern.to_pattern_string()
+    );
+    if !terms.is_empty() { select_sql.push_str(" WHERE "); select_sql.push_str(&terms.join(" AND ")); }
+    if let Some(os) = order_part { select_sql.push_str(" ORDER BY "); select_sql.push_str(os.trim()); }
+    if let Some(ls) = limit_part { select_sql.push_str(" LIMIT "); select_sql.push_str(ls.trim()); }
+    Ok(Command::MatchRewrite { sql: select_sql })
+}
+
+// Byte offset of the first top-level WHERE/RETURN keyword, i.e. where the pattern stops
+fn pattern_end(s: &str) -> usize {
+    let b = s.as_bytes();
+    let up = s.to_ascii_uppercase();
+    let mut depth = 0i32;
+    let mut in_sq = false;
+    let mut i = 0usize;
+    while i < b.len() {
+        let ch = b[i] as char;
+        if in_sq { if ch == '\'' { in_sq = false; } i += 1; continue; }
+        match ch {
+            '\'' => in_sq = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ if ch.is_ascii_alphabetic() && depth == 0 => {
+                let at_word = i == 0 || !(b[i - 1].is_ascii_alphanumeric() || b[i - 1] == b'_');
+                if at_word {
+                    for kw in ["WHERE", "RETURN"] {
+                        let after = i + kw.len();
+                        if up[i..].starts_with(kw) && (after >= b.len() || !b[after].is_ascii_alphanumeric()) {
+                            return i;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    b.len()
+}
+
+/// Parse `(a:Label {k: v})-[r:TYPE]->(b)<-[:TYPE]-(c)...`. Anonymous nodes and relationships
+/// are named `_n<i>` / `_r<i>` so every binding column has a stable prefix.
+pub fn parse_graph_pattern(text: &str) -> Result<GraphPattern> {
+    let chars: Vec<char> = text.trim().chars().collect();
+    let mut i = 0usize;
+    let mut pattern = GraphPattern { nodes: Vec::new(), rels: Vec::new() };
+    loop {
+        skip_ws(&chars, &mut i);
+        let idx = pattern.nodes.len();
+        pattern.nodes.push(parse_pattern_node(&chars, &mut i, idx)?);
+        skip_ws(&chars, &mut i);
+        if i >= chars.len() { break; }
+        let idx = pattern.rels.len();
+        pattern.rels.push(parse_pattern_rel(&chars, &mut i, idx)?);
+    }
+    Ok(pattern)
+}
+
+fn skip_ws(c: &[char], i: &mut usize) { while *i < c.len() && c[*i].is_whitespace() { *i += 1; } }
+
+fn read_ident(c: &[char], i: &mut usize) -> Option<String> {
+    if *i >= c.len() || !(c[*i].is_ascii_alphabetic() || c[*i] == '_') { return None; }
+    let start = *i;
+    while *i < c.len() && (c[*i].is_ascii_alphanumeric() || c[*i] == '_') { *i += 1; }
+    Some(c[start..*i].iter().collect())
+}
+
+fn expect(c: &[char], i: &mut usize, lit: &str) -> Result<()> {
+    skip_ws(c, i);
+    for ch in lit.chars() {
+        if *i >= c.len() || c[*i] != ch { anyhow::bail!("Invalid MATCH pattern: expected '{}' at position {}", lit, *i); }
+        *i += 1;
+    }
+    Ok(())
+}
+
+// `var[:Label]` or `[var][:TYPE]` inside the pattern's parentheses/brackets
+fn read_var_and_label(c: &[char], i: &mut usize) -> Result<(Option<String>, Option<String>)> {
+    skip_ws(c, i);
+    let var = read_ident(c, i);
+    skip_ws(c, i);
+    let label = if *i < c.len() && c[*i] == ':' {
+        *i += 1;
+        skip_ws(c, i);
+        Some(read_ident(c, i).ok_or_else(|| anyhow::anyhow!("Invalid MATCH pattern: expected a label after ':'"))?)
+    } else { None };
+    skip_ws(c, i);
+    Ok((var, label))
+}
+
+fn parse_pattern_node(c: &[char], i: &mut usize, idx: usize) -> Result<PatternNode> {
+    expect(c, i, "(")?;
+    let (var, label) = read_var_and_label(c, i)?;
+    let mut props: Vec<(String, String)> = Vec::new();
+    if *i < c.len() && c[*i] == '{' {
+        *i += 1;
+        loop {
+            skip_ws(c, i);
+            if *i < c.len() && c[*i] == '}' { *i += 1; break; }
+            let key = read_ident(c, i).ok_or_else(|| anyhow::anyhow!("Invalid MATCH pattern: expected a property name"))?;
+            expect(c, i, ":")?;
+            skip_ws(c, i);
+            // Value runs to the next top-level ',' or '}' (quote-aware)
+            let start = *i;
+            let mut in_sq = false;
+            while *i < c.len() && (in_sq || (c[*i] != ',' && c[*i] != '}')) {
+                if c[*i] == '\'' { in_sq = !in_sq; }
+                *i += 1;
+            }
+            if *i >= c.len() { anyhow::bail!("Invalid MATCH pattern: unterminated property map"); }
+            let value: String = c[start..*i].iter().collect::<String>().trim().to_string();
+            if value.is_empty() { anyhow::bail!("Invalid MATCH pattern: missing value for property '{}'", key); }
+            props.push((key, prop_literal(&value)));
+            if c[*i] == ',' { *i += 1; }
+        }
+        skip_ws(c, i);
+    }
+    expect(c, i, ")")?;
+    Ok(PatternNode { var: var.unwrap_or_else(|| format!("_n{}", idx)), label, props })
+}
+
+fn parse_pattern_rel(c: &[char], i: &mut usize, idx: usize) -> Result<PatternRel> {
+    skip_ws(c, i);
+    let incoming = *i < c.len() && c[*i] == '<';
+    if incoming { *i += 1; }
+    expect(c, i, "-")?;
+    let (var, etype) = if *i < c.len() && c[*i] == '[' {
+        *i += 1;
+        let vl = read_var_and_label(c, i)?;
+        if *i < c.len() && c[*i] == '*' {
+            anyhow::bail!("Variable-length hops need the (s:Label {{ key: ... }})-[:Type*L..U]->(t:Label) form");
+        }
+        expect(c, i, "]")?;
+        vl
+    } else { (None, None) };
+    expect(c, i, "-")?;
+    let outgoing = *i < c.len() && c[*i] == '>';
+    if outgoing { *i += 1; }
+    if incoming == outgoing { anyhow::bail!("Invalid MATCH pattern: relationships must have exactly one direction, -[...]-> or <-[...]-"); }
+    Ok(PatternRel { var: var.unwrap_or_else(|| format!("_r{}", idx)), etype, incoming })
+}
+
 fn extract_clause<'a>(text: &'a str, kw: &str, stops: &[&str]) -> Option<&'a str> {
     let up = text.to_ascii_uppercase();
     let kwu = kw.to_ascii_uppercase();
@@ -106,6 +329,13 @@ fn normalize_start_expr(expr: &str) -> String {
     format!("'{}'", t.replace('\'', "''"))
 }
 
+// Inline property values: numbers and booleans stay as-is, everything else becomes a string literal
+fn prop_literal(value: &str) -> String {
+    let t = value.trim();
+    if t.parse::<f64>().is_ok() || t.eq_ignore_ascii_case("true") || t.eq_ignore_ascii_case("false") { return t.to_string(); }
+    normalize_start_expr(t)
+}
+
 fn quote_graph_if_needed(name: &str) -> String {
     if name == "__SESSION_DEFAULT__" { return name.to_string(); }
     // Ensure it's a single-quoted string literal for the TVF argument
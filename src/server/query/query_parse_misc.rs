@@ -1,5 +1,6 @@
 use crate::server::query::query_common::*;
 use crate::server::query::*;
+use crate::storage::kv_events::WatchTarget;
 use regex::Regex;
 
 
@@ -33,10 +34,34 @@ pub fn parse_use(s: &str) -> Result<Command> {
 }
 
 pub fn parse_set(s: &str) -> Result<Command> {
-    // SET variable TO value | SET variable = value
-    let rest = s[3..].trim(); // after SET
-    // Split by TO or = (case-insensitive for TO)
+    // SET [SESSION | LOCAL] variable {TO | =} value
+    let mut rest = s[3..].trim().trim_end_matches(';').trim_end(); // after SET
+    let mut local = false;
+    let first = rest.split_whitespace().next().unwrap_or("").to_uppercase();
+    if first == "LOCAL" || first == "SESSION" {
+        local = first == "LOCAL";
+        rest = rest[first.len()..].trim_start();
+    }
     let up = rest.to_uppercase();
+    // Spellings with their own grammar: TIME ZONE, ROLE, SCHEMA, TRANSACTION / SESSION CHARACTERISTICS AS TRANSACTION
+    if let Some(v) = strip_words(rest, &up, "TIME ZONE") {
+        return Ok(Command::Set { variable: "TimeZone".to_string(), value: unquote(v).to_string(), local });
+    }
+    if let Some(v) = strip_words(rest, &up, "ROLE").filter(|v| !v.to_uppercase().starts_with("TO ") && !v.starts_with('=')) {
+        if v.is_empty() { anyhow::bail!("SET ROLE: missing role name"); }
+        return Ok(Command::Set { variable: "role".to_string(), value: unquote(v).trim_matches('"').to_string(), local });
+    }
+    // SET SCHEMA 'name' is SET search_path TO name
+    if let Some(v) = strip_words(rest, &up, "SCHEMA").filter(|v| !v.is_empty() && !v.to_uppercase().starts_with("TO ") && !v.starts_with('=')) {
+        return Ok(Command::Set { variable: "search_path".to_string(), value: unquote(v).to_string(), local });
+    }
+    if let Some(v) = strip_words(rest, &up, "TRANSACTION ISOLATION LEVEL") {
+        return Ok(Command::Set { variable: "transaction_isolation".to_string(), value: v.to_string(), local: true });
+    }
+    if let Some(v) = strip_words(rest, &up, "CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL") {
+        return Ok(Command::Set { variable: "default_transaction_isolation".to_string(), value: v.to_string(), local: false });
+    }
+    // Split by TO or = (case-insensitive for TO)
     let (variable, value) = if let Some(pos) = up.find(" TO ") {
         let var = rest[..pos].trim();
         let val = rest[pos + 4..].trim();
@@ -52,19 +77,38 @@ pub fn parse_set(s: &str) -> Result<Command> {
     if variable.is_empty() { anyhow::bail!("SET: missing variable name"); }
     if value.is_empty() { anyhow::bail!("SET: missing value"); }
     
-    // Strip quotes from value if present
-    let value_clean = if (value.starts_with('\'') && value.ends_with('\'')) || (value.starts_with('"') && value.ends_with('"')) {
-        if value.len() >= 2 { &value[1..value.len()-1] } else { value }
-    } else {
-        value
-    };
-    
     Ok(Command::Set { 
         variable: variable.to_string(), 
-        value: value_clean.to_string() 
+        value: unquote(value).to_string(),
+        local,
     })
 }
 
+/// RESET variable | RESET ALL
+pub fn parse_reset(s: &str) -> Result<Command> {
+    let rest = s[5..].trim().trim_end_matches(';').trim_end(); // after RESET
+    if rest.is_empty() { anyhow::bail!("RESET: missing variable name"); }
+    if rest.eq_ignore_ascii_case("ALL") { return Ok(Command::Reset { variable: None }); }
+    let variable = if rest.eq_ignore_ascii_case("TIME ZONE") { "TimeZone" } else { rest };
+    Ok(Command::Reset { variable: Some(variable.to_string()) })
+}
+
+// Text after the leading `words` (matched on the upper-cased copy), or None
+fn strip_words<'a>(rest: &'a str, up: &str, words: &str) -> Option<&'a str> {
+    let tail = up.strip_prefix(words)?;
+    if !tail.is_empty() && !tail.starts_with(char::is_whitespace) { return None; }
+    Some(rest[words.len()..].trim())
+}
+
+// Strip quotes from a value if present
+fn unquote(value: &str) -> &str {
+    if value.len() >= 2 && ((value.starts_with('\'') && value.ends_with('\'')) || (value.starts_with('"') && value.ends_with('"'))) {
+        &value[1..value.len()-1]
+    } else {
+        value
+    }
+}
+
 
 
 
@@ -95,12 +139,17 @@ pub fn parse_date_token_to_ms(tok: &str) -> Result<i64> {
 
 // --- KV STORE/KEY parsing helpers ---
 pub fn parse_store_addr(addr: &str) -> Result<(String, String)> {
-    // Expect <database>.store.<store>
+    // Expect <database>.store.<store>; store.<store> or <store> use the current database
     let parts: Vec<&str> = addr.split('.').collect();
-    if parts.len() != 3 { anyhow::bail!(format!("Invalid store address '{}'. Expected <database>.store.<store>", addr)); }
-    if parts[1].to_lowercase() != "store" { anyhow::bail!("Invalid store address: missing literal 'store' segment"); }
-    let db = parts[0].trim();
-    let store = parts[2].trim();
+    let current_db = || crate::system::get_current_database();
+    let (db, store) = match parts.as_slice() {
+        [db, kw, store] if kw.eq_ignore_ascii_case("store") => (db.trim().to_string(), store.trim()),
+        [_, _, _] => anyhow::bail!("Invalid store address: missing literal 'store' segment"),
+        [kw, store] if kw.eq_ignore_ascii_case("store") => (current_db(), store.trim()),
+        [store] => (current_db(), store.trim()),
+        _ => anyhow::bail!(format!("Invalid store address '{}'. Expected [<database>.]store.<store>", addr)),
+    };
+    let db = db.as_str();
     if db.is_empty() || store.is_empty() { anyhow::bail!("Invalid store address: empty database or store name"); }
     Ok((db.to_string(), store.to_string()))
 }
@@ -119,17 +168,464 @@ pub fn parse_key_in_clause(rest: &str) -> Result<(String, String, String)> {
     Ok((db, store, key.to_string()))
 }
 
+// KEY <key> IN <database>.store.<store> <op> <args>, or None when no list/set/hash op follows the store
+fn parse_key_op(rest: &str) -> Result<Option<(String, String, String, KvOp)>> {
+    let Some(i) = rest.to_uppercase().find(" IN ") else { return Ok(None) };
+    let Some((addr, tail)) = rest[i + 4..].trim().split_once(char::is_whitespace) else { return Ok(None) };
+    let tail = tail.trim();
+    let (word, args) = tail.split_once(char::is_whitespace).unwrap_or((tail, ""));
+    let word = word.to_uppercase();
+    let args = args.trim();
+    let items = || -> Result<Vec<String>> {
+        let items: Vec<String> = split_csv_ignoring_quotes(args).iter().map(|v| unquote(v).to_string()).collect();
+        if args.is_empty() || items.iter().any(|v| v.is_empty()) { anyhow::bail!("Invalid {}: expected one or more values", word); }
+        Ok(items)
+    };
+    let op = match word.as_str() {
+        "LPUSH" | "RPUSH" => KvOp::Push { front: word == "LPUSH", values: items()? },
+        "LPOP" | "RPOP" => {
+            if !args.is_empty() { anyhow::bail!("Invalid {}: takes no arguments", word); }
+            KvOp::Pop { front: word == "LPOP" }
+        }
+        "SADD" => KvOp::SetAdd { members: items()? },
+        "SREM" => KvOp::SetRemove { members: items()? },
+        "HDEL" => KvOp::HashDelete { fields: items()? },
+        "HGET" => {
+            let mut fields = items()?;
+            if fields.len() != 1 { anyhow::bail!("Invalid HGET: expected a single field"); }
+            KvOp::HashGet { field: fields.remove(0) }
+        }
+        "HSET" => {
+            let mut fields = Vec::new();
+            for item in split_csv_ignoring_quotes(args) {
+                let Some(eq) = find_unquoted(&item, '=') else { anyhow::bail!("Invalid HSET: expected <field> = <value>[, ...]") };
+                let field = unquote(item[..eq].trim());
+                if field.is_empty() { anyhow::bail!("Invalid HSET: missing field name"); }
+                fields.push((field.to_string(), unquote(item[eq + 1..].trim()).to_string()));
+            }
+            if fields.is_empty() { anyhow::bail!("Invalid HSET: expected <field> = <value>[, ...]"); }
+            KvOp::HashSet { fields }
+        }
+        _ => return Ok(None),
+    };
+    let (db, store, key) = parse_key_in_clause(&format!("{} IN {}", &rest[..i], addr))?;
+    Ok(Some((db, store, key, op)))
+}
+
+// Byte offset of the first `ch` outside single or double quotes
+fn find_unquoted(s: &str, ch: char) -> Option<usize> {
+    let (mut in_s, mut in_d) = (false, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' if !in_d => in_s = !in_s,
+            '"' if !in_s => in_d = !in_d,
+            c if c == ch && !in_s && !in_d => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
 pub fn parse_read(s: &str) -> Result<Command> {
-    // READ KEY <key> IN <database>.store.<store>
+    // READ KEY <key> IN <database>.store.<store> [HGET <field>]
     let rest = s[4..].trim();
     let up = rest.to_uppercase();
     if up.starts_with("KEY ") {
+        if let Some((db, store, key, op)) = parse_key_op(rest)? {
+            if !matches!(op, KvOp::HashGet { .. }) { anyhow::bail!("Invalid READ KEY: only HGET reads a field; use WRITE KEY for changes"); }
+            return Ok(Command::KeyOp { database: db, store, key, op });
+        }
         let (db, store, key) = parse_key_in_clause(rest)?;
         return Ok(Command::ReadKey { database: db, store, key });
     }
     anyhow::bail!("Invalid READ syntax")
 }
 
+pub fn parse_watch(s: &str) -> Result<Command> {
+    // WATCH KEY <key> IN <database>.store.<store> | WATCH PREFIX '<prefix>' IN <database>.store.<store>
+    // UNWATCH <id> | UNWATCH [ALL]
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let up = s.to_uppercase();
+    if up.starts_with("UNWATCH") {
+        let arg = s[7..].trim();
+        if arg.is_empty() || arg.eq_ignore_ascii_case("ALL") { return Ok(Command::Unwatch { id: None }); }
+        let id = arg.parse::<u64>().map_err(|_| anyhow::anyhow!("Invalid UNWATCH: expected a watch id or ALL"))?;
+        return Ok(Command::Unwatch { id: Some(id) });
+    }
+    let rest = s[6..].trim();
+    if rest.to_uppercase().starts_with("PREFIX ") {
+        let Some(i) = rest.to_uppercase().rfind(" IN ") else { anyhow::bail!("Invalid WATCH PREFIX: expected IN <database>.store.<store>") };
+        let prefix = unquote(rest[6..i].trim());
+        let (db, store) = parse_store_addr(rest[i + 4..].trim())?;
+        return Ok(Command::Watch { database: db, store, target: WatchTarget::Prefix(prefix.to_string()) });
+    }
+    let (db, store, key) = parse_key_in_clause(rest)?;
+    Ok(Command::Watch { database: db, store, target: WatchTarget::Key(key) })
+}
+
+pub fn parse_multi(s: &str) -> Result<Command> {
+    // MULTI IN <database>.store.<store> [EXPECT <key> VERSION <n>[, ...]] EXEC $$
+    //   WRITE KEY <key> = <value> ... | WRITE KEY <key> <op> ... | DROP KEY <key>; ...
+    // $$
+    let s = s.trim().trim_end_matches(';').trim_end();
+    let (Some(open), Some(close)) = (s.find("$$"), s.rfind("$$")) else { anyhow::bail!("Invalid MULTI: expected EXEC $$ <writes> $$") };
+    if close == open || !s[close + 2..].trim().is_empty() { anyhow::bail!("Invalid MULTI: expected EXEC $$ <writes> $$"); }
+    let head = s[5..open].trim();
+    let up = head.to_uppercase();
+    if head.len() < 9 || !up.starts_with("IN ") || !up.ends_with(" EXEC") { anyhow::bail!("Invalid MULTI: expected MULTI IN <database>.store.<store> ... EXEC $$"); }
+    let head = head[3..head.len() - 5].trim();
+    let (addr, expect_list) = match head.to_uppercase().find(" EXPECT ") {
+        Some(i) => (&head[..i], Some(head[i + 8..].trim())),
+        None => (head, None),
+    };
+    let (db, store) = parse_store_addr(addr.trim())?;
+    let mut expect = Vec::new();
+    for item in expect_list.map(split_csv_ignoring_quotes).unwrap_or_default() {
+        let parts: Vec<&str> = item.split_whitespace().collect();
+        match parts.as_slice() {
+            [key, kw, n] if kw.eq_ignore_ascii_case("VERSION") => {
+                let n = n.parse::<u64>().map_err(|_| anyhow::anyhow!("Invalid MULTI EXPECT: version of '{}' must be a number", key))?;
+                expect.push((unquote(key).to_string(), n));
+            }
+            _ => anyhow::bail!("Invalid MULTI EXPECT: expected <key> VERSION <n>[, ...]"),
+        }
+    }
+    let mut writes = Vec::new();
+    for stmt in split_sql_statements(&s[open + 2..close]) {
+        writes.push(parse_batch_write(stmt, &db, &store)?);
+    }
+    if writes.is_empty() { anyhow::bail!("Invalid MULTI: no writes between $$"); }
+    Ok(Command::KvBatch { database: db, store, expect, writes })
+}
+
+// One write of a MULTI batch; the key may omit `IN <store>`, or name the batch's store
+fn parse_batch_write(stmt: &str, db: &str, store: &str) -> Result<KvBatchWrite> {
+    let up = stmt.to_uppercase();
+    let (verb, rest) = if up.starts_with("WRITE KEY ") { ("WRITE", stmt[10..].trim()) }
+        else if up.starts_with("DROP KEY ") { ("DROP", stmt[9..].trim()) }
+        else { anyhow::bail!("Invalid MULTI: only WRITE KEY and DROP KEY statements are allowed, got '{}'", stmt) };
+    let (key, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let tail = tail.trim();
+    let full = if tail.to_uppercase().starts_with("IN ") {
+        format!("{} KEY {} {}", verb, key, tail)
+    } else {
+        format!("{} KEY {} IN {}.store.{} {}", verb, key, db, store, tail)
+    };
+    let (in_db, in_store, key, cmd) = if verb == "DROP" {
+        let (in_db, in_store, key) = parse_key_in_clause(full[5..].trim())?;
+        (in_db, in_store, key.clone(), KvBatchWrite::Delete { key })
+    } else {
+        match parse_write(full.trim())? {
+            Command::WriteKey { database, store, key, value, ttl_ms, reset_on_access } => (database, store, key.clone(), KvBatchWrite::Set { key, value, ttl_ms, reset_on_access }),
+            Command::KeyOp { database, store, key, op } => (database, store, key.clone(), KvBatchWrite::Op { key, op }),
+            _ => anyhow::bail!("Invalid MULTI: unsupported write '{}'", stmt),
+        }
+    };
+    if in_db != db || in_store != store { anyhow::bail!("Invalid MULTI: key '{}' is not in {}.store.{}; a batch writes one store", key, db, store); }
+    Ok(cmd)
+}
+
+pub fn parse_verify(s: &str) -> Result<Command> {
+    // VERIFY TABLE <table> [QUARANTINE]
+    let rest = s.trim().trim_end_matches(';')[6..].trim();
+    let up = rest.to_uppercase();
+    if !up.starts_with("TABLE ") { anyhow::bail!("Invalid VERIFY syntax: expected VERIFY TABLE <table> [QUARANTINE]"); }
+    let mut table = rest[6..].trim();
+    let mut quarantine = false;
+    if table.to_uppercase().ends_with(" QUARANTINE") {
+        quarantine = true;
+        table = table[..table.len() - " QUARANTINE".len()].trim();
+    }
+    if table.is_empty() { anyhow::bail!("Invalid VERIFY TABLE: missing table name"); }
+    Ok(Command::VerifyTable { table: table.to_string(), quarantine })
+}
+
+pub fn parse_compact(s: &str) -> Result<Command> {
+    // COMPACT TABLE <table>
+    let rest = s.trim().trim_end_matches(';')["COMPACT".len()..].trim();
+    if !rest.to_uppercase().starts_with("TABLE ") { anyhow::bail!("Invalid COMPACT syntax: expected COMPACT TABLE <table>"); }
+    let table = rest[6..].trim();
+    if table.is_empty() { anyhow::bail!("Invalid COMPACT TABLE: missing table name"); }
+    Ok(Command::CompactTable { table: table.to_string() })
+}
+
+pub fn parse_analyze(s: &str) -> Result<Command> {
+    // ANALYZE [VERBOSE] [<table>] (ANALYSE is accepted as in PostgreSQL)
+    let mut rest = s.trim().trim_end_matches(';')["ANALYZE".len()..].trim();
+    if rest.to_uppercase().starts_with("VERBOSE") { rest = rest["VERBOSE".len()..].trim(); }
+    if rest.contains(char::is_whitespace) { anyhow::bail!("Invalid ANALYZE syntax: expected ANALYZE [<table>]"); }
+    Ok(Command::Analyze { table: if rest.is_empty() { None } else { Some(rest.to_string()) } })
+}
+
+pub fn parse_kill(s: &str) -> Result<Command> {
+    // KILL [CONNECTION | QUERY] <pid> | KILL SESSION '<id>' | KILL SESSIONS FOR USER <name>
+    let rest = s.trim().trim_end_matches(';')["KILL".len()..].trim();
+    let up = rest.to_uppercase();
+    if up.starts_with("SESSIONS FOR USER ") {
+        let user = rest["SESSIONS FOR USER ".len()..].trim().trim_matches('\'').trim_matches('"');
+        if user.is_empty() { anyhow::bail!("Invalid KILL syntax: expected KILL SESSIONS FOR USER <name>"); }
+        return Ok(Command::KillUserSessions { user: user.to_string() });
+    }
+    if up.starts_with("SESSION ") {
+        let id = rest["SESSION ".len()..].trim().trim_matches('\'');
+        if id.is_empty() { anyhow::bail!("Invalid KILL syntax: expected KILL SESSION '<session_id>'"); }
+        return Ok(Command::KillSession { session_id: id.to_string() });
+    }
+    let (query_only, pid_txt) = if up.starts_with("QUERY ") { (true, rest[6..].trim()) }
+        else if up.starts_with("CONNECTION ") { (false, rest[11..].trim()) }
+        else { (false, rest) };
+    let pid: i32 = pid_txt.parse().map_err(|_| anyhow::anyhow!("Invalid KILL syntax: expected KILL [CONNECTION | QUERY] <pid>"))?;
+    Ok(Command::Kill { pid, query_only })
+}
+
+pub fn parse_comment(s: &str) -> Result<Command> {
+    // COMMENT ON {TABLE | COLUMN | VIEW | FUNCTION} <name> IS '<text>' | NULL
+    const USAGE: &str = "Invalid COMMENT syntax: expected COMMENT ON {TABLE | COLUMN | VIEW | FUNCTION} <name> IS '<text>' | NULL";
+    let rest = s.trim().trim_end_matches(';')["COMMENT ON".len()..].trim();
+    let (kind, rest) = rest.split_once(char::is_whitespace).ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    let is_pos = rest.to_uppercase().find(" IS ").ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    let name = rest[..is_pos].trim();
+    let value = rest[is_pos + 4..].trim();
+    if name.is_empty() { anyhow::bail!(USAGE); }
+    let comment = if value.eq_ignore_ascii_case("NULL") {
+        None
+    } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        // An empty comment removes it, as in PostgreSQL
+        Some(value[1..value.len() - 1].replace("''", "'")).filter(|c| !c.is_empty())
+    } else {
+        anyhow::bail!(USAGE);
+    };
+    let object = match kind.to_uppercase().as_str() {
+        "TABLE" => CommentObject::Table(name.to_string()),
+        "VIEW" => CommentObject::View(name.to_string()),
+        "COLUMN" => {
+            let (table, column) = name.rsplit_once('.').ok_or_else(|| anyhow::anyhow!("COMMENT ON COLUMN expects <table>.<column>"))?;
+            CommentObject::Column { table: table.to_string(), column: column.trim_matches('"').to_string() }
+        }
+        // Argument lists are accepted and ignored: functions are not overloaded
+        "FUNCTION" => CommentObject::Function(name.split('(').next().unwrap_or(name).trim().to_string()),
+        _ => anyhow::bail!(USAGE),
+    };
+    Ok(Command::CommentOn { object, comment })
+}
+
+pub fn parse_grant(s: &str) -> Result<Command> {
+    // GRANT {<privilege>[, ...] | ALL [PRIVILEGES]} ON [TABLE | SCHEMA | DATABASE] <name> TO <grantee>[, ...]
+    // REVOKE ... FROM <grantee>[, ...]
+    let body = s.trim().trim_end_matches(';');
+    let (verb, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    let revoke = verb.eq_ignore_ascii_case("REVOKE");
+    let to_kw = if revoke { "FROM" } else { "TO" };
+    let usage = format!("Invalid {} syntax: expected {} {{<privilege>[, ...] | ALL}} ON [TABLE | SCHEMA | DATABASE] <name> {} <grantee>[, ...]", verb.to_uppercase(), verb.to_uppercase(), to_kw);
+    let up = rest.to_uppercase();
+    if !up.contains(" ON ") { return parse_grant_role(verb, rest, revoke); }
+    let on_pos = up.find(" ON ").ok_or_else(|| anyhow::anyhow!(usage.clone()))?;
+    let to_pos = up.rfind(&format!(" {} ", to_kw)).filter(|p| *p > on_pos + 3).ok_or_else(|| anyhow::anyhow!(usage.clone()))?;
+    let privs_txt = rest[..on_pos].trim();
+    let privileges: Vec<crate::security::Privilege> = if privs_txt.eq_ignore_ascii_case("ALL") || privs_txt.eq_ignore_ascii_case("ALL PRIVILEGES") {
+        crate::security::Privilege::ALL.to_vec()
+    } else {
+        privs_txt.split(',').map(|p| crate::security::Privilege::parse(p).ok_or_else(|| anyhow::anyhow!("{}: unknown privilege '{}' (expected SELECT, INSERT, UPDATE, DELETE, DDL or ALL)", verb.to_uppercase(), p.trim()))).collect::<Result<_>>()?
+    };
+    let target = rest[on_pos + 4..to_pos].trim();
+    let (kind, name) = match target.split_once(char::is_whitespace) {
+        Some((k, n)) if ["TABLE", "SCHEMA", "DATABASE"].contains(&k.to_uppercase().as_str()) => (k.to_uppercase(), n.trim()),
+        _ => ("TABLE".to_string(), target),
+    };
+    if name.is_empty() { anyhow::bail!(usage); }
+    let name = name.to_string();
+    let object = match kind.as_str() {
+        "SCHEMA" => GrantTarget::Schema(name),
+        "DATABASE" => GrantTarget::Database(name),
+        _ => GrantTarget::Table(name),
+    };
+    let grantees: Vec<String> = rest[to_pos + to_kw.len() + 2..].split(',').map(|g| g.trim().trim_matches('"').to_string()).filter(|g| !g.is_empty()).collect();
+    if grantees.is_empty() { anyhow::bail!(usage); }
+    Ok(if revoke { Command::Revoke { privileges, object, grantees } } else { Command::Grant { privileges, object, grantees } })
+}
+
+// GRANT <role>[, ...] TO <member>[, ...] [WITH ADMIN OPTION] / REVOKE <role>[, ...] FROM <member>[, ...]
+fn parse_grant_role(verb: &str, rest: &str, revoke: bool) -> Result<Command> {
+    let to_kw = if revoke { " FROM " } else { " TO " };
+    let usage = || anyhow::anyhow!("Invalid {} syntax: expected {} <role>[, ...] {} <member>[, ...]", verb.to_uppercase(), verb.to_uppercase(), to_kw.trim());
+    let mut body = rest.trim();
+    let mut admin_option = false;
+    if !revoke && body.to_uppercase().ends_with(" WITH ADMIN OPTION") {
+        admin_option = true;
+        body = body[..body.len() - " WITH ADMIN OPTION".len()].trim_end();
+    }
+    let pos = body.to_uppercase().find(to_kw).ok_or_else(usage)?;
+    let names = |t: &str| t.split(',').map(|g| g.trim().trim_matches('"').to_string()).filter(|g| !g.is_empty()).collect::<Vec<String>>();
+    let roles = names(&body[..pos]);
+    let members = names(&body[pos + to_kw.len()..]);
+    if roles.is_empty() || members.is_empty() { return Err(usage()); }
+    Ok(if revoke { Command::RevokeRole { roles, members } } else { Command::GrantRole { roles, members, admin_option } })
+}
+
+pub fn parse_role(s: &str) -> Result<Command> {
+    // CREATE ROLE <name> [[WITH] option ...] | DROP ROLE [IF EXISTS] <name>
+    let words: Vec<&str> = s.trim().trim_end_matches(';').split_whitespace().collect();
+    if words[0].eq_ignore_ascii_case("DROP") {
+        let (if_exists, rest) = match words.get(2..4) {
+            Some([a, b]) if a.eq_ignore_ascii_case("IF") && b.eq_ignore_ascii_case("EXISTS") => (true, &words[4..]),
+            _ => (false, &words[2..]),
+        };
+        let [name] = rest else { anyhow::bail!("Invalid DROP ROLE syntax: expected DROP ROLE [IF EXISTS] <name>") };
+        return Ok(Command::DropRole { name: name.trim_matches('"').to_string(), if_exists });
+    }
+    let Some(name) = words.get(2) else { anyhow::bail!("Invalid CREATE ROLE syntax: missing role name") };
+    let (mut superuser, mut inherit) = (false, true);
+    for opt in words[3..].iter().map(|w| w.to_uppercase()) {
+        match opt.as_str() {
+            "WITH" => {}
+            "SUPERUSER" => superuser = true,
+            "NOSUPERUSER" => superuser = false,
+            "INHERIT" => inherit = true,
+            "NOINHERIT" => inherit = false,
+            "NOLOGIN" => {}
+            other => anyhow::bail!("CREATE ROLE: unsupported option '{}' (expected SUPERUSER, NOSUPERUSER, INHERIT, NOINHERIT or NOLOGIN)", other),
+        }
+    }
+    Ok(Command::CreateRole { name: name.trim_matches('"').to_string(), superuser, inherit })
+}
+
+pub fn parse_job(s: &str) -> Result<Command> {
+    // CREATE JOB <name> SCHEDULE '<cron>' AS {SCRIPT <path> | '<sql>' | <sql>}
+    // DROP JOB [IF EXISTS] <name> | ALTER JOB <name> {ENABLE | DISABLE}
+    let body = s.trim().trim_end_matches(';').trim_end();
+    let words: Vec<&str> = body.split_whitespace().collect();
+    let name_of = crate::ident::normalize_identifier;
+    if words[0].eq_ignore_ascii_case("DROP") {
+        let (if_exists, rest) = match words.get(2..4) {
+            Some([a, b]) if a.eq_ignore_ascii_case("IF") && b.eq_ignore_ascii_case("EXISTS") => (true, &words[4..]),
+            _ => (false, &words[2..]),
+        };
+        let [name] = rest else { anyhow::bail!("Invalid DROP JOB syntax: expected DROP JOB [IF EXISTS] <name>") };
+        return Ok(Command::DropJob { name: name_of(name), if_exists });
+    }
+    if words[0].eq_ignore_ascii_case("ALTER") {
+        let [_, _, name, action] = words.as_slice() else { anyhow::bail!("Invalid ALTER JOB syntax: expected ALTER JOB <name> ENABLE | DISABLE") };
+        let enabled = match action.to_uppercase().as_str() {
+            "ENABLE" => true,
+            "DISABLE" => false,
+            _ => anyhow::bail!("Invalid ALTER JOB syntax: expected ALTER JOB <name> ENABLE | DISABLE"),
+        };
+        return Ok(Command::AlterJob { name: name_of(name), enabled });
+    }
+    const USAGE: &str = "Invalid CREATE JOB syntax: expected CREATE JOB <name> SCHEDULE '<cron>' AS {SCRIPT <db>/<schema>/<name> | <sql>}";
+    let rest = body["CREATE JOB".len()..].trim_start();
+    let (name, rest) = rest.split_once(char::is_whitespace).ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    let rest = rest.trim_start();
+    if !rest.to_uppercase().starts_with("SCHEDULE ") { anyhow::bail!(USAGE); }
+    let rest = rest["SCHEDULE".len()..].trim_start();
+    let Some(quoted) = rest.strip_prefix('\'') else { anyhow::bail!("CREATE JOB: the schedule must be a quoted cron expression") };
+    let (schedule, rest) = quoted.split_once('\'').ok_or_else(|| anyhow::anyhow!("CREATE JOB: unterminated schedule literal"))?;
+    let rest = rest.trim_start();
+    if !rest.to_uppercase().starts_with("AS ") { anyhow::bail!(USAGE); }
+    let target = rest[3..].trim();
+    let body = if target.to_uppercase().starts_with("SCRIPT ") {
+        let path = target["SCRIPT ".len()..].trim().trim_matches('\'');
+        if path.is_empty() || path.contains(char::is_whitespace) { anyhow::bail!("CREATE JOB: expected AS SCRIPT <db>/<schema>/<name>"); }
+        JobBody::Script(path.to_string())
+    } else if target.len() >= 2 && target.starts_with('\'') && target.ends_with('\'') {
+        JobBody::Sql(target[1..target.len() - 1].replace("''", "'"))
+    } else {
+        JobBody::Sql(target.to_string())
+    };
+    if matches!(&body, JobBody::Sql(sql) if sql.trim().is_empty()) { anyhow::bail!("CREATE JOB: missing statement after AS"); }
+    Ok(Command::CreateJob { name: name_of(name), schedule: schedule.trim().to_string(), body })
+}
+
+pub fn parse_trigger(s: &str) -> Result<Command> {
+    // CREATE TRIGGER <name> AFTER {INSERT | UPDATE | DELETE} [OR ...] ON <table> [FOR EACH {ROW | STATEMENT}]
+    //   EXECUTE {FUNCTION | PROCEDURE} <fn>[()] [WITH (mode = 'async', on_error = 'retry', retries = 3, batch_size = 500)]
+    // DROP TRIGGER [IF EXISTS] <name> ON <table>
+    use crate::storage::cdc::ChangeOp;
+    use crate::storage::triggers::{Trigger, DEFAULT_BATCH_SIZE};
+    let body = s.trim().trim_end_matches(';').trim_end();
+    if body.to_uppercase().starts_with("DROP") {
+        let words: Vec<&str> = body.split_whitespace().collect();
+        let (if_exists, rest) = match words.get(2..4) {
+            Some([a, b]) if a.eq_ignore_ascii_case("IF") && b.eq_ignore_ascii_case("EXISTS") => (true, &words[4..]),
+            _ => (false, &words[2..]),
+        };
+        let [name, on, table] = rest else { anyhow::bail!("Invalid DROP TRIGGER syntax: expected DROP TRIGGER [IF EXISTS] <name> ON <table>") };
+        if !on.eq_ignore_ascii_case("ON") { anyhow::bail!("Invalid DROP TRIGGER syntax: expected DROP TRIGGER [IF EXISTS] <name> ON <table>"); }
+        return Ok(Command::DropTrigger { name: crate::ident::normalize_identifier(name), table: table.to_string(), if_exists });
+    }
+    const USAGE: &str = "Invalid CREATE TRIGGER syntax: expected CREATE TRIGGER <name> AFTER <event> [OR <event>...] ON <table> EXECUTE FUNCTION <fn> [WITH (...)]";
+    let up = body.to_uppercase();
+    let (head, options) = match up.find(" WITH ") {
+        Some(i) => {
+            let opts = body[i + 6..].trim();
+            let inner = opts.strip_prefix('(').and_then(|o| o.strip_suffix(')'))
+                .ok_or_else(|| anyhow::anyhow!("CREATE TRIGGER: expected WITH (<option> = <value>, ...)"))?;
+            (&body[..i], Some(inner))
+        }
+        None => (body, None),
+    };
+    let words: Vec<&str> = head.split_whitespace().collect();
+    let [_, _, name, timing, rest @ ..] = words.as_slice() else { anyhow::bail!(USAGE) };
+    if !timing.eq_ignore_ascii_case("AFTER") {
+        return Err(crate::error::AppError::user("feature_not_supported".to_string(), format!("{} triggers are not supported; use AFTER", timing.to_uppercase())).into());
+    }
+    let mut events: Vec<ChangeOp> = Vec::new();
+    let mut i = 0;
+    while i < rest.len() && !rest[i].eq_ignore_ascii_case("ON") {
+        let op = match rest[i].to_uppercase().as_str() {
+            "OR" => None,
+            "INSERT" => Some(ChangeOp::Insert),
+            "UPDATE" => Some(ChangeOp::Update),
+            "DELETE" => Some(ChangeOp::Delete),
+            _ => anyhow::bail!("CREATE TRIGGER: unsupported event '{}' (expected INSERT, UPDATE or DELETE)", rest[i]),
+        };
+        if let Some(op) = op { if !events.contains(&op) { events.push(op); } }
+        i += 1;
+    }
+    if events.is_empty() || i + 1 >= rest.len() { anyhow::bail!(USAGE); }
+    let table = rest[i + 1];
+    let mut tail = &rest[i + 2..];
+    if tail.len() >= 3 && tail[0].eq_ignore_ascii_case("FOR") && tail[1].eq_ignore_ascii_case("EACH") { tail = &tail[3..]; }
+    let function = match tail {
+        [exec, kind, f] if exec.eq_ignore_ascii_case("EXECUTE") && (kind.eq_ignore_ascii_case("FUNCTION") || kind.eq_ignore_ascii_case("PROCEDURE")) => {
+            f.trim_end_matches("()").to_string()
+        }
+        _ => anyhow::bail!(USAGE),
+    };
+    let mut trigger = Trigger {
+        name: crate::ident::normalize_identifier(name),
+        events,
+        function,
+        mode: Default::default(),
+        on_error: Default::default(),
+        retries: 0,
+        batch_size: DEFAULT_BATCH_SIZE,
+    };
+    for opt in options.into_iter().flat_map(|o| o.split(',')).filter(|o| !o.trim().is_empty()) {
+        let (k, v) = opt.split_once('=').ok_or_else(|| anyhow::anyhow!("CREATE TRIGGER: expected <option> = <value>, got '{}'", opt.trim()))?;
+        trigger.set_option(k.trim(), v)?;
+    }
+    Ok(Command::CreateTrigger { table: table.to_string(), trigger })
+}
+
+pub fn parse_admin(s: &str) -> Result<Command> {
+    // ADMIN RELOAD CONFIG[URATION]
+    let words: Vec<String> = s.trim().trim_end_matches(';').split_whitespace().skip(1).map(|w| w.to_uppercase()).collect();
+    match words.iter().map(|w| w.as_str()).collect::<Vec<_>>().as_slice() {
+        ["RELOAD", "CONFIG" | "CONFIGURATION"] => Ok(Command::ReloadConfig),
+        _ => anyhow::bail!("Invalid ADMIN syntax: expected ADMIN RELOAD CONFIG"),
+    }
+}
+
+pub fn parse_reencrypt(s: &str) -> Result<Command> {
+    // REENCRYPT TABLE <table>
+    let rest = s.trim().trim_end_matches(';')["REENCRYPT".len()..].trim();
+    if !rest.to_uppercase().starts_with("TABLE ") { anyhow::bail!("Invalid REENCRYPT syntax: expected REENCRYPT TABLE <table>"); }
+    let table = rest[6..].trim();
+    if table.is_empty() { anyhow::bail!("Invalid REENCRYPT TABLE: missing table name"); }
+    Ok(Command::ReencryptTable { table: table.to_string() })
+}
+
 pub fn parse_list(s: &str) -> Result<Command> {
     // LIST STORES <db>
     // LIST KEYS IN <database>.store.<store>
@@ -174,9 +670,14 @@ pub fn parse_describe(s: &str) -> Result<Command> {
 
 pub fn parse_write(s: &str) -> Result<Command> {
     // WRITE KEY <key> IN <database>.store.<store> = <value_or_address> [TTL <duration>] [RESET ON ACCESS|NO RESET]
+    // WRITE KEY <key> IN <database>.store.<store> LPUSH|RPUSH|SADD|SREM|HDEL <value>[, ...] | LPOP|RPOP | HSET <field> = <value>[, ...]
     let rest = s[5..].trim();
     let up = rest.to_uppercase();
     if up.starts_with("KEY ") {
+        if let Some((db, store, key, op)) = parse_key_op(rest)? {
+            if matches!(op, KvOp::HashGet { .. }) { anyhow::bail!("Invalid WRITE KEY: HGET is a read; use READ KEY ... HGET"); }
+            return Ok(Command::KeyOp { database: db, store, key, op });
+        }
         // split around '=' first
         let eq_pos = rest.find('=');
         if eq_pos.is_none() { anyhow::bail!("Invalid WRITE KEY: missing '=' assignment"); }
@@ -353,3 +854,23 @@ pub fn parse_type_name(s: &str) -> Option<(SqlType, usize)> {
     Some((ty, consumed_kw))
 }
 
+
+pub fn parse_rolling_frame(s: &str) -> Result<RollingFrame> {
+    // ROWS BETWEEN <n|UNBOUNDED> PRECEDING AND CURRENT ROW
+    // RANGE BETWEEN <window|UNBOUNDED> PRECEDING AND CURRENT ROW
+    // <window> (e.g. 10m), shorthand for the RANGE form
+    let t = s.trim();
+    let re = Regex::new(r"^(?i)(ROWS|RANGE)\s+BETWEEN\s+(\S+)\s+PRECEDING\s+AND\s+CURRENT\s+ROW$")?;
+    let Some(caps) = re.captures(t) else { return Ok(RollingFrame::Range(parse_window(t)?)); };
+    let bound = caps.get(2).unwrap().as_str();
+    let unbounded = bound.eq_ignore_ascii_case("UNBOUNDED");
+    if caps.get(1).unwrap().as_str().eq_ignore_ascii_case("ROWS") {
+        if unbounded { return Ok(RollingFrame::Rows(usize::MAX)); }
+        let n = bound.parse::<usize>().map_err(|_| anyhow::anyhow!("Invalid ROWS frame: expected a row count, got {}", bound))?;
+        Ok(RollingFrame::Rows(n))
+    } else if unbounded {
+        Ok(RollingFrame::Range(i64::MAX))
+    } else {
+        Ok(RollingFrame::Range(parse_window(bound)?))
+    }
+}
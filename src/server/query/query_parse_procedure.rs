@@ -0,0 +1,437 @@
+//! CREATE PROCEDURE / DROP PROCEDURE / CALL and the statement language of procedure bodies.
+//! Bodies are parsed here when the procedure is created, so syntax errors surface at CREATE,
+//! and again when it is called; `server::procedures` runs them.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::server::query::query_common::{mask_sql_literals, split_sql_statements};
+use crate::server::query::query_parse_misc::parse_type_name;
+use crate::server::query::Command;
+
+/// One procedure parameter; arguments are cast to its type when the procedure is called.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+/// Statement of a procedure body. Expressions and conditions are kept as SQL text and
+/// evaluated when they run, after variables are replaced by their values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcStmt {
+    /// Any other statement, run through the engine
+    Sql(String),
+    /// DECLARE <name> [<type>] [{:= | DEFAULT} <expr>]
+    Declare { name: String, type_name: Option<String>, expr: Option<String> },
+    /// <name> := {<expr> | <query>}
+    Assign { name: String, expr: String },
+    /// IF <cond> THEN ... [ELSIF <cond> THEN ...] [ELSE ...] END IF
+    If { branches: Vec<(String, Vec<ProcStmt>)>, otherwise: Vec<ProcStmt> },
+    /// [WHILE <cond>] LOOP ... END LOOP
+    Loop { cond: Option<String>, body: Vec<ProcStmt> },
+    /// EXIT [WHEN <cond>]
+    Exit { when: Option<String> },
+    /// CONTINUE [WHEN <cond>]
+    Continue { when: Option<String> },
+    Return,
+    /// RAISE [<level>] '<format>' [, <expr> ...]; `%` in the format takes the next argument
+    Raise { level: String, message: String, args: Vec<String> },
+    /// COMMIT | END
+    Commit,
+    Rollback,
+    /// CALL <name>([<expr>, ...])
+    Call { name: String, args: Vec<String> },
+}
+
+const RAISE_LEVELS: &[&str] = &["EXCEPTION", "WARNING", "NOTICE", "INFO", "LOG", "DEBUG"];
+
+fn is_word_byte(c: u8) -> bool { c.is_ascii_alphanumeric() || c == b'_' }
+
+/// Offset of `word` as a whole word in `up` (uppercase, literals masked).
+fn find_word(up: &str, word: &str) -> Option<usize> {
+    let b = up.as_bytes();
+    let mut from = 0;
+    while let Some(i) = up[from..].find(word).map(|i| i + from) {
+        let end = i + word.len();
+        if (i == 0 || !is_word_byte(b[i - 1])) && b.get(end).map_or(true, |c| !is_word_byte(*c)) { return Some(i); }
+        from = end;
+    }
+    None
+}
+
+/// End of `word` when it is the next word at or after `from` in `up`.
+fn next_word(up: &str, from: usize, word: &str) -> Option<usize> {
+    let at = from + (up[from..].len() - up[from..].trim_start().len());
+    (find_word(&up[at..], word) == Some(0)).then(|| at + word.len())
+}
+
+/// Leading word of `up` as (start, end) offsets.
+fn first_word(up: &str) -> (usize, usize) {
+    let start = up.len() - up.trim_start().len();
+    let end = up[start..].bytes().position(|c| !is_word_byte(c)).map_or(up.len(), |i| start + i);
+    (start, end)
+}
+
+fn check_type(type_name: &str) -> Result<()> {
+    match parse_type_name(type_name) {
+        Some((_, n)) if type_name[n..].trim().is_empty() => Ok(()),
+        _ => bail!("type \"{}\" does not exist", type_name),
+    }
+}
+
+fn check_variable(name: &str) -> Result<String> {
+    let ok = !name.is_empty() && !name.as_bytes()[0].is_ascii_digit() && name.bytes().all(is_word_byte);
+    if !ok { bail!("invalid variable name '{}'", name); }
+    Ok(name.to_ascii_lowercase())
+}
+
+/// Split at commas outside parentheses, literals and quoted identifiers.
+pub(crate) fn split_args(s: &str) -> Vec<String> {
+    let masked = mask_sql_literals(s);
+    let mut out = Vec::new();
+    let (mut depth, mut start) = (0i32, 0usize);
+    for (i, c) in masked.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => { out.push(s[start..i].trim().to_string()); start = i + 1; }
+            _ => {}
+        }
+    }
+    let last = s[start..].trim();
+    if !last.is_empty() || !out.is_empty() { out.push(last.to_string()); }
+    out
+}
+
+/// `<name>[(<item>, ...)]` with the parenthesised list split at top-level commas.
+fn name_and_list(s: &str, what: &str) -> Result<(String, Vec<String>)> {
+    let t = s.trim();
+    let masked = mask_sql_literals(t);
+    let Some(open) = masked.find('(') else { return Ok((t.to_string(), Vec::new())) };
+    let mut depth = 0i32;
+    let close = masked[open..].char_indices().find_map(|(i, c)| {
+        match c { '(' => depth += 1, ')' => { depth -= 1; if depth == 0 { return Some(open + i); } } _ => {} }
+        None
+    }).ok_or_else(|| anyhow!("{}: unbalanced parentheses", what))?;
+    if !t[close + 1..].trim().is_empty() { bail!("{}: unexpected text after ')'", what); }
+    let items = split_args(&t[open + 1..close]);
+    if items.iter().any(|a| a.is_empty()) { bail!("{}: empty item in list", what); }
+    Ok((t[..open].trim().to_string(), items))
+}
+
+fn parse_call_target(s: &str) -> Result<(String, Vec<String>)> {
+    let (name, args) = name_and_list(s, "CALL")?;
+    if name.is_empty() || name.contains(char::is_whitespace) { bail!("Invalid CALL syntax: expected CALL <procedure>([<arg>, ...])"); }
+    Ok((name, args))
+}
+
+/// `DECLARE` text after the keyword (or one line of a DECLARE section).
+fn parse_declare(text: &str) -> Result<ProcStmt> {
+    let t = text.trim();
+    let up = mask_sql_literals(t).to_ascii_uppercase();
+    let (_, name_end) = first_word(&up);
+    let name = check_variable(&t[..name_end])?;
+    let (type_end, expr) = match up.find(":=") {
+        Some(i) => (i, Some(t[i + 2..].trim())),
+        None => match find_word(&up, "DEFAULT") {
+            Some(i) => (i, Some(t[i + "DEFAULT".len()..].trim())),
+            None => (t.len(), None),
+        },
+    };
+    if type_end < name_end { bail!("DECLARE: expected a variable name before :="); }
+    if expr == Some("") { bail!("DECLARE {}: missing value", name); }
+    let type_name = t[name_end..type_end].trim();
+    let type_name = if type_name.is_empty() { None } else { check_type(type_name)?; Some(type_name.to_string()) };
+    Ok(ProcStmt::Declare { name, type_name, expr: expr.map(str::to_string) })
+}
+
+fn parse_raise(s: &str, up: &str, from: usize) -> Result<ProcStmt> {
+    let mut at = from;
+    let mut level = "EXCEPTION".to_string();
+    if let Some((l, after)) = RAISE_LEVELS.iter().find_map(|l| next_word(up, from, l).map(|e| (l, e))) {
+        level = l.to_string();
+        at = after;
+    }
+    let parts = split_args(&s[at..]);
+    let Some(fmt) = parts.first().filter(|f| f.len() >= 2 && f.starts_with('\'') && f.ends_with('\'')) else {
+        bail!("RAISE: expected a quoted message, as in RAISE EXCEPTION 'value % out of range', v");
+    };
+    let message = fmt[1..fmt.len() - 1].replace("''", "'");
+    Ok(ProcStmt::Raise { level, message, args: parts[1..].to_vec() })
+}
+
+/// A statement that does not open or close a block.
+fn parse_simple(chunk: &str, up: &str, word: (usize, usize)) -> Result<ProcStmt> {
+    let (start, end) = word;
+    let rest = chunk[end..].trim();
+    let optional_when = |kw: &str| -> Result<Option<String>> {
+        if rest.is_empty() { return Ok(None); }
+        match next_word(up, end, "WHEN") {
+            Some(at) if !chunk[at..].trim().is_empty() => Ok(Some(chunk[at..].trim().to_string())),
+            _ => bail!("{}: expected {} [WHEN <condition>]", kw, kw),
+        }
+    };
+    let only = |words: &[&str]| up[end..].split_whitespace().all(|w| words.contains(&w));
+    Ok(match &up[start..end] {
+        "DECLARE" => parse_declare(rest)?,
+        "RETURN" if rest.is_empty() => ProcStmt::Return,
+        "RETURN" => bail!("RETURN cannot have a value in a procedure"),
+        "EXIT" => ProcStmt::Exit { when: optional_when("EXIT")? },
+        "CONTINUE" => ProcStmt::Continue { when: optional_when("CONTINUE")? },
+        "RAISE" => parse_raise(chunk, up, end)?,
+        "BEGIN" | "START" if only(&["WORK", "TRANSACTION"]) => bail!("BEGIN is implicit in a procedure: a new transaction starts after each COMMIT or ROLLBACK"),
+        "COMMIT" if only(&["WORK", "TRANSACTION"]) => ProcStmt::Commit,
+        "ROLLBACK" if only(&["WORK", "TRANSACTION"]) => ProcStmt::Rollback,
+        "CALL" => {
+            let (name, args) = parse_call_target(rest)?;
+            ProcStmt::Call { name, args }
+        }
+        _ => {
+            let at = end + (up[end..].len() - up[end..].trim_start().len());
+            if end > start && up[at..].starts_with(":=") {
+                let expr = chunk[at + 2..].trim();
+                if expr.is_empty() { bail!("missing value after :="); }
+                ProcStmt::Assign { name: check_variable(&chunk[start..end])?, expr: expr.to_string() }
+            } else {
+                ProcStmt::Sql(chunk.trim().to_string())
+            }
+        }
+    })
+}
+
+/// Body statement with block headers (IF ... THEN, ELSE, LOOP, ...) split off.
+#[derive(Debug)]
+enum Item {
+    If(String),
+    Elsif(String),
+    Else,
+    Loop(Option<String>),
+    EndIf,
+    EndLoop,
+    Stmt(ProcStmt),
+}
+
+/// Condition between the header keyword and `kw`, and the text after `kw`.
+fn header<'a>(chunk: &'a str, up: &str, from: usize, kw: &str, what: &str) -> Result<(String, &'a str)> {
+    let at = find_word(&up[from..], kw).map(|i| from + i).ok_or_else(|| anyhow!("{} without {}", what, kw))?;
+    let cond = chunk[from..at].trim();
+    if cond.is_empty() { bail!("{}: missing condition", what); }
+    Ok((cond.to_string(), &chunk[at + kw.len()..]))
+}
+
+/// Split one `;`-terminated chunk into items. A header and the first statement of its block
+/// share a chunk (`IF x THEN INSERT ...`), so the text after a header is split again.
+fn flatten(chunk: &str, out: &mut Vec<Item>) -> Result<()> {
+    let up = mask_sql_literals(chunk).to_ascii_uppercase();
+    let (start, end) = first_word(&up);
+    let rest = match &up[start..end] {
+        "IF" => { let (c, r) = header(chunk, &up, end, "THEN", "IF")?; out.push(Item::If(c)); r }
+        "ELSIF" | "ELSEIF" => { let (c, r) = header(chunk, &up, end, "THEN", "ELSIF")?; out.push(Item::Elsif(c)); r }
+        "WHILE" => { let (c, r) = header(chunk, &up, end, "LOOP", "WHILE")?; out.push(Item::Loop(Some(c))); r }
+        "ELSE" => { out.push(Item::Else); &chunk[end..] }
+        "LOOP" => { out.push(Item::Loop(None)); &chunk[end..] }
+        "END" => {
+            match up.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["END", "IF"] => out.push(Item::EndIf),
+                ["END", "LOOP"] => out.push(Item::EndLoop),
+                ["END"] | ["END", "WORK" | "TRANSACTION"] => out.push(Item::Stmt(ProcStmt::Commit)),
+                _ => bail!("expected END IF or END LOOP, got '{}'", chunk.trim()),
+            }
+            return Ok(());
+        }
+        _ => { out.push(Item::Stmt(parse_simple(chunk, &up, (start, end))?)); return Ok(()); }
+    };
+    if !rest.trim().is_empty() { flatten(rest.trim(), out)?; }
+    Ok(())
+}
+
+/// Statements up to the next ELSIF / ELSE / END IF / END LOOP.
+fn build_block(items: &[Item], pos: &mut usize) -> Result<Vec<ProcStmt>> {
+    let mut out = Vec::new();
+    while let Some(item) = items.get(*pos) {
+        match item {
+            Item::Elsif(_) | Item::Else | Item::EndIf | Item::EndLoop => break,
+            Item::Stmt(s) => { out.push(s.clone()); *pos += 1; }
+            Item::If(cond) => {
+                *pos += 1;
+                let mut branches = vec![(cond.clone(), build_block(items, pos)?)];
+                let mut otherwise = Vec::new();
+                loop {
+                    match items.get(*pos) {
+                        Some(Item::Elsif(c)) => { *pos += 1; branches.push((c.clone(), build_block(items, pos)?)); }
+                        Some(Item::Else) => {
+                            *pos += 1;
+                            otherwise = build_block(items, pos)?;
+                            if !matches!(items.get(*pos), Some(Item::EndIf)) { bail!("expected END IF after ELSE"); }
+                        }
+                        Some(Item::EndIf) => { *pos += 1; break; }
+                        _ => bail!("IF without END IF"),
+                    }
+                }
+                out.push(ProcStmt::If { branches, otherwise });
+            }
+            Item::Loop(cond) => {
+                *pos += 1;
+                let body = build_block(items, pos)?;
+                if !matches!(items.get(*pos), Some(Item::EndLoop)) { bail!("LOOP without END LOOP"); }
+                *pos += 1;
+                out.push(ProcStmt::Loop { cond: cond.clone(), body });
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// EXIT and CONTINUE only make sense inside a loop.
+fn check_loops(stmts: &[ProcStmt], in_loop: bool) -> Result<()> {
+    for s in stmts {
+        match s {
+            ProcStmt::Exit { .. } | ProcStmt::Continue { .. } if !in_loop => bail!("EXIT and CONTINUE cannot be used outside a loop"),
+            ProcStmt::If { branches, otherwise } => {
+                for (_, b) in branches { check_loops(b, in_loop)?; }
+                check_loops(otherwise, in_loop)?;
+            }
+            ProcStmt::Loop { body, .. } => check_loops(body, true)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Parse a procedure body: `;`-separated statements, optionally written as a PL/pgSQL block
+/// (`[DECLARE <var> <type> [:= <expr>]; ...] BEGIN ... END`).
+pub fn parse_procedure_body(body: &str) -> Result<Vec<ProcStmt>> {
+    let mut chunks: Vec<String> = split_sql_statements(body).into_iter().map(str::to_string).collect();
+    let lead = |c: &str| {
+        let up = mask_sql_literals(c).to_ascii_uppercase();
+        let (s, e) = first_word(&up);
+        (up[s..e].to_string(), e)
+    };
+    let ends_block = chunks.last().is_some_and(|c| mask_sql_literals(c).trim().eq_ignore_ascii_case("END"));
+    let begin_at = chunks.iter().position(|c| lead(c).0 == "BEGIN");
+    let mut stmts = Vec::new();
+    if let Some(b) = begin_at.filter(|b| ends_block && (*b == 0 || lead(&chunks[0]).0 == "DECLARE")) {
+        let section = &chunks[..b];
+        for (i, decl) in section.iter().enumerate() {
+            let text = if i == 0 { &decl[lead(decl).1..] } else { decl.as_str() };
+            stmts.push(parse_declare(text)?);
+        }
+        let first = chunks[b][lead(&chunks[b]).1..].trim().to_string();
+        chunks.pop();
+        chunks.drain(..=b);
+        if !first.is_empty() { chunks.insert(0, first); }
+    }
+    let mut items = Vec::new();
+    for c in &chunks { flatten(c, &mut items)?; }
+    let mut pos = 0;
+    stmts.extend(build_block(&items, &mut pos)?);
+    if let Some(stray) = items.get(pos) {
+        bail!("{} without a matching {}", match stray {
+            Item::Elsif(_) => "ELSIF", Item::Else => "ELSE", Item::EndIf => "END IF", _ => "END LOOP",
+        }, if matches!(stray, Item::EndLoop) { "LOOP" } else { "IF" });
+    }
+    check_loops(&stmts, false)?;
+    Ok(stmts)
+}
+
+/// Text of a `$tag$ ... $tag$` or `'...'` body and the text after it; `what` names the
+/// statement in errors.
+pub(crate) fn body_literal<'a>(s: &'a str, what: &str) -> Result<(String, &'a str)> {
+    let t = s.trim_start();
+    if t.starts_with('$') {
+        let tag_end = t[1..].find('$').map(|i| i + 2).ok_or_else(|| anyhow!("{}: unterminated dollar quote", what))?;
+        let tag = &t[..tag_end];
+        let close = t[tag_end..].find(tag).map(|i| tag_end + i).ok_or_else(|| anyhow!("{}: unterminated dollar quote", what))?;
+        return Ok((t[tag_end..close].to_string(), &t[close + tag.len()..]));
+    }
+    if let Some(inner) = t.strip_prefix('\'') {
+        let b = inner.as_bytes();
+        let mut i = 0;
+        while i < b.len() {
+            if b[i] == b'\'' {
+                if b.get(i + 1) == Some(&b'\'') { i += 2; continue; }
+                return Ok((inner[..i].replace("''", "'"), &inner[i + 1..]));
+            }
+            i += 1;
+        }
+        bail!("{}: unterminated body literal", what);
+    }
+    bail!("{}: the body must be quoted, as in AS $$ ... $$", what)
+}
+
+/// Drop a `LANGUAGE <name>` clause from `s`; only SQL-like languages are accepted.
+fn strip_language(s: &str) -> Result<&str> {
+    let t = s.trim();
+    let words: Vec<&str> = t.split_whitespace().collect();
+    match words.as_slice() {
+        [] => Ok(t),
+        [kw, lang, ..] if kw.eq_ignore_ascii_case("LANGUAGE") => {
+            if !["sql", "plpgsql"].contains(&lang.trim_matches('\'').to_ascii_lowercase().as_str()) {
+                return Err(crate::error::AppError::user("feature_not_supported".to_string(), format!("procedures in language {} are not supported; use SQL", lang)).into());
+            }
+            let after = t[kw.len()..].trim_start()[lang.len()..].trim_start();
+            Ok(after)
+        }
+        _ => Ok(t),
+    }
+}
+
+pub fn parse_procedure(s: &str) -> Result<Command> {
+    // CREATE [OR REPLACE] PROCEDURE <name>([<param> <type>, ...]) [LANGUAGE sql] AS $$ <body> $$
+    // DROP PROCEDURE [IF EXISTS] <name>[(...)]
+    // CALL <name>([<expr>, ...])
+    let text = s.trim().trim_end_matches(';').trim_end();
+    let up = mask_sql_literals(text).to_ascii_uppercase();
+    let (_, first_end) = first_word(&up);
+    match &up[..first_end] {
+        "CALL" => {
+            let (name, args) = parse_call_target(&text[first_end..])?;
+            Ok(Command::Call { name, args })
+        }
+        "DROP" => {
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let (if_exists, rest) = match words.get(2..4) {
+                Some([a, b]) if a.eq_ignore_ascii_case("IF") && b.eq_ignore_ascii_case("EXISTS") => (true, &words[4..]),
+                _ => (false, &words[2..]),
+            };
+            let name = rest.join(" ");
+            let name = name.split('(').next().unwrap_or("").trim();
+            if name.is_empty() || name.contains(char::is_whitespace) { bail!("Invalid DROP PROCEDURE syntax: expected DROP PROCEDURE [IF EXISTS] <name>"); }
+            Ok(Command::DropProcedure { name: name.to_string(), if_exists })
+        }
+        _ => {
+            const USAGE: &str = "Invalid CREATE PROCEDURE syntax: expected CREATE [OR REPLACE] PROCEDURE <name>([<param> <type>, ...]) AS $$ <body> $$";
+            let or_replace = next_word(&up, first_end, "OR").and_then(|e| next_word(&up, e, "REPLACE"));
+            let after_kw = next_word(&up, or_replace.unwrap_or(first_end), "PROCEDURE").ok_or_else(|| anyhow!(USAGE))?;
+            let as_at = find_word(&up[after_kw..], "AS").map(|i| after_kw + i).ok_or_else(|| anyhow!(USAGE))?;
+            let mut head = text[after_kw..as_at].trim();
+            if let Some(i) = find_word(&up[after_kw..as_at], "LANGUAGE") {
+                if !strip_language(&text[after_kw + i..as_at])?.is_empty() { bail!(USAGE); }
+                head = text[after_kw..after_kw + i].trim();
+            }
+            let (name, raw_params) = name_and_list(head, "CREATE PROCEDURE")?;
+            if name.is_empty() || name.contains(char::is_whitespace) { bail!(USAGE); }
+            let mut params: Vec<ProcParam> = Vec::with_capacity(raw_params.len());
+            for p in raw_params {
+                let mut p = p.as_str();
+                let (w, rest) = p.split_once(char::is_whitespace).unwrap_or((p, ""));
+                if w.eq_ignore_ascii_case("IN") { p = rest.trim_start(); }
+                else if w.eq_ignore_ascii_case("OUT") || w.eq_ignore_ascii_case("INOUT") {
+                    return Err(crate::error::AppError::user("feature_not_supported".to_string(), "OUT and INOUT procedure parameters are not supported".to_string()).into());
+                }
+                let (pname, ptype) = p.split_once(char::is_whitespace).ok_or_else(|| anyhow!("CREATE PROCEDURE: parameter '{}' needs a type", p))?;
+                let pname = check_variable(pname)?;
+                let ptype = ptype.trim();
+                check_type(ptype)?;
+                if params.iter().any(|q| q.name == pname) { bail!("CREATE PROCEDURE: parameter \"{}\" is used more than once", pname); }
+                params.push(ProcParam { name: pname, type_name: ptype.to_string() });
+            }
+            let (body, tail) = body_literal(&text[as_at + 2..], "CREATE PROCEDURE")?;
+            if !strip_language(tail)?.is_empty() { bail!(USAGE); }
+            parse_procedure_body(&body)?;
+            Ok(Command::CreateProcedure { name, params, body: body.trim().to_string(), or_replace: or_replace.is_some() })
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! CREATE / DROP SCRIPT TEST, RUN TESTS and ASSERT; `server::script_tests` stores and runs
+//! the tests.
+
+use anyhow::{bail, Result};
+
+use crate::server::query::query_parse_procedure::{body_literal, split_args};
+use crate::server::query::Command;
+
+/// Language of a script test: Lua `test_*` functions, or SQL statements checked with ASSERT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptTestLanguage {
+    Lua,
+    Sql,
+}
+
+impl ScriptTestLanguage {
+    pub fn as_str(&self) -> &'static str {
+        match self { ScriptTestLanguage::Lua => "lua", ScriptTestLanguage::Sql => "sql" }
+    }
+
+    /// File extension of stored tests in this language.
+    pub fn extension(&self) -> &'static str { self.as_str() }
+}
+
+fn check_path(path: &str, what: &str) -> Result<String> {
+    if path.is_empty() || path.contains(char::is_whitespace) { bail!("Invalid {} syntax: expected a test name such as <db>/<schema>/<name>", what); }
+    Ok(path.to_string())
+}
+
+pub fn parse_script_test(s: &str) -> Result<Command> {
+    // CREATE SCRIPT TEST [LUA | SQL] <name> AS {$$ <code> $$ | '<code>'}
+    // DROP SCRIPT TEST [IF EXISTS] <name>
+    // RUN TESTS [<db>/<schema>[/<name>]]
+    // ASSERT <condition> [, '<message>']
+    let text = s.trim().trim_end_matches(';').trim_end();
+    let up = text.to_ascii_uppercase();
+    if let Some(rest) = up.strip_prefix("CREATE SCRIPT TEST ") {
+        const USAGE: &str = "Invalid CREATE SCRIPT TEST syntax. Use: CREATE SCRIPT TEST [LUA | SQL] <db>/<schema>/<name> AS $$ <code> $$";
+        let mut at = text.len() - rest.len();
+        let mut language = ScriptTestLanguage::Lua;
+        for (kw, lang) in [("LUA ", ScriptTestLanguage::Lua), ("SQL ", ScriptTestLanguage::Sql)] {
+            if up[at..].starts_with(kw) { language = lang; at += kw.len(); break; }
+        }
+        let Some(as_at) = up[at..].find(" AS ").map(|i| at + i) else { bail!(USAGE) };
+        let path = check_path(text[at..as_at].trim(), "CREATE SCRIPT TEST")?;
+        let (code, tail) = body_literal(&text[as_at + 4..], "CREATE SCRIPT TEST")?;
+        if !tail.trim().is_empty() { bail!(USAGE); }
+        return Ok(Command::CreateScriptTest { path, language, code });
+    }
+    if let Some(rest) = up.strip_prefix("DROP SCRIPT TEST ") {
+        let mut path = text[text.len() - rest.len()..].trim();
+        let if_exists = rest.trim_start().starts_with("IF EXISTS ");
+        if if_exists { path = path["IF EXISTS ".len()..].trim(); }
+        return Ok(Command::DropScriptTest { path: check_path(path, "DROP SCRIPT TEST")?, if_exists });
+    }
+    if up == "RUN TESTS" || up.starts_with("RUN TESTS ") {
+        let target = text["RUN TESTS".len()..].trim();
+        if target.is_empty() { return Ok(Command::RunTests { target: None }); }
+        let parts = target.split('/').count();
+        if !(2..=3).contains(&parts) || target.contains(char::is_whitespace) {
+            bail!("Invalid RUN TESTS syntax. Use: RUN TESTS [<db>/<schema>[/<name>]]");
+        }
+        return Ok(Command::RunTests { target: Some(target.to_string()) });
+    }
+    // ASSERT
+    let parts = split_args(&text["ASSERT".len()..]);
+    let (condition, message) = match parts.as_slice() {
+        [c] => (c.clone(), None),
+        [c, m] if m.len() >= 2 && m.starts_with('\'') && m.ends_with('\'') => (c.clone(), Some(m[1..m.len() - 1].replace("''", "'"))),
+        _ => bail!("Invalid ASSERT syntax. Use: ASSERT <condition> [, '<message>']"),
+    };
+    if condition.is_empty() { bail!("Invalid ASSERT syntax: missing condition"); }
+    Ok(Command::Assert { condition, message })
+}
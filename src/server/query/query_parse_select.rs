@@ -8,6 +8,9 @@ use crate::tprintln;
 
 
 
+// Target row count for BY AUTO without an explicit (n)
+const BY_AUTO_DEFAULT_POINTS: usize = 1000;
+
 pub fn split_union_queries(input: &str) -> Result<(Vec<&str>, bool)> {
     // Split top-level SELECT statements by UNION or UNION ALL, respecting parentheses and quotes.
     let mut parts: Vec<&str> = Vec::new();
@@ -402,22 +405,27 @@ pub fn parse_select(s: &str) -> Result<Query> {
         return Ok(Query {
             select,
             by_window_ms: None,
+            by_auto_points: None,
             by_slices: None,
             group_by_cols: None,
             group_by_notnull_cols: None,
             where_clause: None,
             having_clause: None,
             rolling_window_ms: None,
+            rolling_rows: None,
             order_by: None,
             order_by_hint: None,
             order_by_raw: None,
+            order_by_collations: None,
             limit: None,
             into_table: None,
             into_mode: None,
+            into_options: None,
             base_table: None,
             joins: None,
             with_ctes,
             original_sql: s.trim().to_string(),
+            hints: QueryHints::default(),
         });
     }
 
@@ -432,19 +440,23 @@ pub fn parse_select(s: &str) -> Result<Query> {
     // Parse database name until BY/GROUP BY/WHERE/HAVING or end
     let mut database = rest.trim();
     let mut by_window_ms: Option<i64> = None;
+    let mut by_auto_points: Option<usize> = None;
     let mut by_slices: Option<SlicePlan> = None;
     let mut group_by_cols: Option<Vec<String>> = None;
     let mut group_by_notnull_cols: Option<Vec<String>> = None;
     let mut where_clause: Option<WhereExpr> = None;
     let mut having_clause: Option<WhereExpr> = None;
     let mut rolling_window_ms: Option<i64> = None;
+    let mut rolling_rows: Option<usize> = None;
     let mut order_by: Option<Vec<(String, bool)>> = None;
     let mut limit: Option<i64> = None;
     let mut order_by_hint: Option<String> = None;
     let mut order_by_raw: Option<Vec<(String, bool)>> = None;
+    let mut order_by_collations: Option<Vec<Option<String>>> = None;
     // Optional INTO target and mode
     let mut into_table: Option<String> = None;
     let mut into_mode: Option<IntoMode> = None;
+    let mut into_options: Option<IntoOptions> = None;
 
     // Determine cut for database token
     let up_db = upper_shadow(database);
@@ -486,7 +498,10 @@ pub fn parse_select(s: &str) -> Result<Query> {
             if let Some(i) = after_up.find(" ORDER BY ") { win_end = win_end.min(i); }
             if let Some(i) = after_up.find(" LIMIT ") { win_end = win_end.min(i); }
             if let Some(i) = after_up.find(" INTO ") { win_end = win_end.min(i); }
-            rolling_window_ms = Some(parse_window(after[..win_end].trim())?);
+            match parse_rolling_frame(after[..win_end].trim())? {
+                RollingFrame::Range(ms) => rolling_window_ms = Some(ms),
+                RollingFrame::Rows(n) => rolling_rows = Some(n),
+            }
             t = after[win_end..].trim_start();
             continue;
         } else if t_up.starts_with("BY ") {
@@ -506,6 +521,21 @@ pub fn parse_select(s: &str) -> Result<Query> {
                 t = t[adv..].trim_start();
                 continue;
             }
+            // BY AUTO [(n)]: bucket size chosen at execution time to target n rows
+            if after_up.starts_with("AUTO") && after_trim[4..].chars().next().map(|c| c == '(' || c.is_whitespace()).unwrap_or(true) {
+                let mut rest = after_trim[4..].trim_start();
+                let mut points = BY_AUTO_DEFAULT_POINTS;
+                if rest.starts_with('(') {
+                    let close = rest.find(')').ok_or_else(|| anyhow::anyhow!("BY AUTO: missing ')'"))?;
+                    points = rest[1..close].trim().parse::<usize>().ok().filter(|n| *n > 0)
+                        .ok_or_else(|| anyhow::anyhow!("BY AUTO(n): n must be a positive integer"))?;
+                    rest = rest[close + 1..].trim_start();
+                }
+                by_window_ms = Some(0);
+                by_auto_points = Some(points);
+                t = rest;
+                continue;
+            }
             // numeric window e.g. 1s, 5m — only if the next non-space token looks numeric
             let next_tok = after_trim.split_whitespace().next().unwrap_or("");
             if next_tok.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false) {
@@ -530,6 +560,7 @@ pub fn parse_select(s: &str) -> Result<Query> {
             if let Some(i) = after_up.find(" HAVING ") { end = end.min(i); }
             if let Some(i) = after_up.find(" ORDER BY ") { end = end.min(i); }
             if let Some(i) = after_up.find(" LIMIT ") { end = end.min(i); }
+            if let Some(i) = after_up.find(" INTO ") { end = end.min(i); }
             debug!("[PARSE GROUP BY] Raw GROUP BY text: '{}'", &after[..end]);
             // parse columns list between start..end comma-separated, supporting optional NOTNULL modifier per column
             let mut cols: Vec<String> = Vec::new();
@@ -587,6 +618,7 @@ pub fn parse_select(s: &str) -> Result<Query> {
             if let Some(i) = find_at_depth_zero(&after_up, " HAVING ") { end = end.min(i); }
             if let Some(i) = find_at_depth_zero(&after_up, " ORDER BY ") { end = end.min(i); }
             if let Some(i) = find_at_depth_zero(&after_up, " LIMIT ") { end = end.min(i); }
+            if let Some(i) = find_at_depth_zero(&after_up, " INTO ") { end = end.min(i); }
             let w_txt = after[..end].trim();
             debug!("[PARSE WHERE] Raw WHERE text: '{}'", w_txt);
             match parse_where_expr(w_txt) {
@@ -609,6 +641,7 @@ pub fn parse_select(s: &str) -> Result<Query> {
             let mut end = after.len();
             if let Some(i) = after_up.find(" ORDER BY ") { end = end.min(i); }
             if let Some(i) = after_up.find(" LIMIT ") { end = end.min(i); }
+            if let Some(i) = after_up.find(" INTO ") { end = end.min(i); }
             // Extract only the HAVING predicate text
             let h_txt = after[..end].trim();
             having_clause = parse_where_expr(h_txt).ok();
@@ -621,6 +654,7 @@ pub fn parse_select(s: &str) -> Result<Query> {
             let after_up = upper_shadow(after);
             let mut end = after.len();
             if let Some(i) = after_up.find(" LIMIT ") { end = end.min(i); }
+            if let Some(i) = after_up.find(" INTO ") { end = end.min(i); }
             // Allow ORDER BY to be the last clause, so no further trims
             let mut inside = after[..end].trim().to_string();
             // Optional trailing USING ANN|EXACT hint
@@ -639,6 +673,7 @@ pub fn parse_select(s: &str) -> Result<Query> {
             }
             let mut list: Vec<(String, bool)> = Vec::new();
             let mut raw_list: Vec<(String, bool)> = Vec::new();
+            let mut collations: Vec<Option<String>> = Vec::new();
             // Split by comma respecting parenthesis depth and quotes to handle function calls correctly
             let mut parts: Vec<String> = Vec::new();
             let mut buf = String::new();
@@ -705,7 +740,12 @@ pub fn parse_select(s: &str) -> Result<Query> {
                         }
                     }
                 }
-                let expr_txt = p.trim().to_string();
+                // Optional COLLATE "<name>" between the key and ASC/DESC
+                let (expr_txt, collation) = match split_collate(p.trim()) {
+                    Some((key, name)) => (key.to_string(), Some(crate::server::collation::Collation::resolve(&name)?.name)),
+                    None => (p.trim().to_string(), None),
+                };
+                collations.push(collation);
                 // Preserve raw expression for advanced planners (e.g., ANN)
                 raw_list.push((expr_txt.clone(), asc));
                 // Determine if this is a bare identifier (no parens, spaces, or quotes)
@@ -727,6 +767,7 @@ pub fn parse_select(s: &str) -> Result<Query> {
             if order_by.is_some() { anyhow::bail!("Duplicate ORDER BY clause"); }
             order_by = Some(list);
             order_by_raw = Some(raw_list);
+            if collations.iter().any(|c| c.is_some()) { order_by_collations = Some(collations); }
             t = after[end..].trim_start();
             continue;
         } else if t_up.starts_with("LIMIT ") {
@@ -762,21 +803,27 @@ pub fn parse_select(s: &str) -> Result<Query> {
             t = &t[1..];
             continue;
         } else if t_up.starts_with(" INTO ") || t_up.starts_with("INTO ") {
-            // Parse: INTO <table> [APPEND|REPLACE]
+            // Parse: INTO <table> [(PRIMARY KEY(..), PARTITION BY(..), TIME TABLE)] [APPEND|REPLACE|MERGE]
             // Accept both with/without leading space
             let after = if t_up.starts_with(" INTO ") { &t[6..] } else { &t[5..] };
             let after = after.trim_start();
-            // split once on whitespace to separate table and optional mode
-            let mut parts = after.splitn(2, char::is_whitespace);
-            let tbl = parts.next().unwrap_or("").trim();
+            // the table name ends at whitespace or at the options list
+            let tbl_end = after.find(|c: char| c.is_whitespace() || c == '(').unwrap_or(after.len());
+            let tbl = after[..tbl_end].trim();
             if tbl.is_empty() { anyhow::bail!("Invalid INTO: missing table name"); }
             into_table = Some(tbl.to_string());
-            if let Some(rest) = parts.next() {
-                let mode_tok = rest.split_whitespace().next().unwrap_or("").to_uppercase();
-                if !mode_tok.is_empty() {
-                    into_mode = Some(match mode_tok.as_str() { "APPEND" => IntoMode::Append, "REPLACE" => IntoMode::Replace, other => { anyhow::bail!("Invalid INTO mode: {} (expected APPEND or REPLACE)", other); } });
-                    // consume the mode token (rest of string is ignored)
-                }
+            let mut rest = after[tbl_end..].trim_start();
+            if rest.starts_with('(') {
+                let (inner, used) = extract_paren_block(rest).ok_or_else(|| anyhow::anyhow!("Invalid INTO options: missing ')'"))?;
+                let opts = parse_into_options(inner)?;
+                if opts.time_table && !tbl.ends_with(".time") { anyhow::bail!("INTO ... (TIME TABLE) target must end with .time"); }
+                into_options = Some(opts);
+                rest = rest[used..].trim_start();
+            }
+            let mode_tok = rest.split_whitespace().next().unwrap_or("").to_uppercase();
+            if !mode_tok.is_empty() {
+                into_mode = Some(match mode_tok.as_str() { "APPEND" => IntoMode::Append, "REPLACE" => IntoMode::Replace, "MERGE" => IntoMode::Merge, other => { anyhow::bail!("Invalid INTO mode: {} (expected APPEND, REPLACE or MERGE)", other); } });
+                // consume the mode token (rest of string is ignored)
             }
             // nothing else should follow INTO; break
             t = "";
@@ -820,5 +867,48 @@ pub fn parse_select(s: &str) -> Result<Query> {
         anyhow::bail!("BY and GROUP BY cannot be used together");
     }
 
-    Ok(Query { select, by_window_ms, by_slices, group_by_cols, group_by_notnull_cols, where_clause, having_clause, rolling_window_ms, order_by, order_by_hint, order_by_raw, limit, into_table, into_mode, base_table, joins, with_ctes, original_sql: s.trim().to_string() })
-}
\ No newline at end of file
+    Ok(Query { select, by_window_ms, by_auto_points, by_slices, group_by_cols, group_by_notnull_cols, where_clause, having_clause, rolling_window_ms, rolling_rows, order_by, order_by_hint, order_by_raw, order_by_collations, limit, into_table, into_mode, into_options, base_table, joins, with_ctes, original_sql: s.trim().to_string(), hints: QueryHints::default() })
+}
+
+// Column list of PRIMARY KEY(...) / PARTITION BY(...) in INTO options
+fn into_option_columns(s: &str, what: &str) -> Result<(Vec<String>, usize)> {
+    let t = s.trim_start();
+    let lead = s.len() - t.len();
+    let (cols_txt, used) = if t.starts_with('(') {
+        extract_paren_block(t).ok_or_else(|| anyhow::anyhow!("Invalid INTO options: unterminated {}(...)", what))?
+    } else {
+        // single unparenthesized column
+        let end = t.find(|c: char| c == ',' || c.is_whitespace()).unwrap_or(t.len());
+        (&t[..end], end)
+    };
+    let cols: Vec<String> = cols_txt.split(',').map(|c| c.trim().trim_matches('"').to_string()).filter(|c| !c.is_empty()).collect();
+    if cols.is_empty() { anyhow::bail!("Invalid INTO options: {} needs at least one column", what); }
+    Ok((cols, lead + used))
+}
+
+fn parse_into_options(inner: &str) -> Result<IntoOptions> {
+    let mut opts = IntoOptions::default();
+    let mut rest = inner.trim();
+    while !rest.is_empty() {
+        let up = rest.to_uppercase();
+        if up.starts_with("PRIMARY KEY") {
+            let (cols, used) = into_option_columns(&rest["PRIMARY KEY".len()..], "PRIMARY KEY")?;
+            opts.primary_key = Some(cols);
+            rest = &rest["PRIMARY KEY".len() + used..];
+        } else if up.starts_with("PARTITION BY") {
+            let (cols, used) = into_option_columns(&rest["PARTITION BY".len()..], "PARTITION BY")?;
+            opts.partitions = Some(cols);
+            rest = &rest["PARTITION BY".len() + used..];
+        } else if up.starts_with("TIME TABLE") {
+            opts.time_table = true;
+            rest = &rest["TIME TABLE".len()..];
+        } else {
+            anyhow::bail!("Invalid INTO option near '{}' (expected PRIMARY KEY(...), PARTITION BY(...) or TIME TABLE)", rest);
+        }
+        rest = rest.trim_start();
+        if let Some(r) = rest.strip_prefix(',') { rest = r.trim_start(); } else if !rest.is_empty() {
+            anyhow::bail!("Invalid INTO options: expected ',' before '{}'", rest);
+        }
+    }
+    Ok(opts)
+}
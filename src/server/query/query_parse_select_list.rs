@@ -107,12 +107,12 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
         }
         if t == "_time" {
             if alias.is_some() { anyhow::bail!("Alias is not allowed on _time"); }
-            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: "_time".into(), expr: None, alias: None});
+            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: "_time".into(), expr: None, alias: None});
             continue;
         }
         if t == "*" {
             if alias.is_some() { anyhow::bail!("Alias is not allowed on *"); }
-            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: "*".into(), expr: None, alias: None});
+            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: "*".into(), expr: None, alias: None});
             continue;
         }
         // Qualified wildcard like t.* (or schema-qualified alias like t/* not expected here)
@@ -124,12 +124,21 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
                 anyhow::bail!("Syntax error: expected qualifier before .* in SELECT list");
             }
             // Keep original text for qualifier (may include dots or quotes), executor will expand based on alias mapping
-            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: format!("{}.*", qual), expr: None, alias: None});
+            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: format!("{}.*", qual), expr: None, alias: None});
             continue;
         }
         if (t == "_start_time" || t == "_end_time") && alias.is_some() {
             anyhow::bail!("Alias is not allowed on _start_time or _end_time");
         }
+        // Per-aggregate ROLLING BY frame: agg(col) OVER (ROWS|RANGE BETWEEN ... PRECEDING AND CURRENT ROW)
+        let mut frame: Option<RollingFrame> = None;
+        let t_up = t.to_uppercase();
+        if !t_up.starts_with("ROW_NUMBER") && t.ends_with(')') {
+            if let Some(i) = t_up.find(") OVER (") {
+                frame = Some(parse_rolling_frame(&t[i + 8..t.len() - 1])?);
+                t = t[..=i].trim();
+            }
+        }
         // Try function form FUNC(expr)
         if let Some(p1) = t.find('(') {
             if t.ends_with(')') {
@@ -147,7 +156,17 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
                         (a.trim(), Some(p))
                     } else { (inner, None) };
                     let ar = parse_arith_expr(&expr_txt.split_whitespace().map(|s| s.to_string()).collect::<Vec<String>>())?;
-                    items.push(SelectItem{ func: Some(AggFunc::Quantile(cutoff)), str_func: None, window_func: None, window_spec: None, column: expr_txt.into(), expr: Some(ar), alias });
+                    items.push(SelectItem{ func: Some(AggFunc::Quantile(cutoff)), str_func: None, window_func: None, window_spec: None, frame, column: expr_txt.into(), expr: Some(ar), alias });
+                    continue;
+                }
+                // EMA(expr, alpha): exponential moving average with smoothing factor 0 < alpha <= 1
+                if func_name == "EMA" {
+                    let idx = inner.rfind(',').ok_or_else(|| anyhow::anyhow!("EMA requires a smoothing factor: EMA(expr, alpha)"))?;
+                    let (expr_txt, p) = (inner[..idx].trim(), inner[idx + 1..].trim());
+                    let alpha = p.parse::<f64>().ok().filter(|a| *a > 0.0 && *a <= 1.0)
+                        .ok_or_else(|| anyhow::anyhow!(format!("Invalid EMA alpha (expected 0 < alpha <= 1): {}", p)))?;
+                    let ar = parse_arith_expr(&expr_txt.split_whitespace().map(|s| s.to_string()).collect::<Vec<String>>())?;
+                    items.push(SelectItem{ func: Some(AggFunc::Ema(alpha)), str_func: None, window_func: None, window_spec: None, frame, column: expr_txt.into(), expr: Some(ar), alias });
                     continue;
                 }
                 // Recognize numeric aggs and string funcs
@@ -164,19 +183,24 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
                     "HEIGHT" => Some(AggFunc::Height),
                     "GRADIENT" => Some(AggFunc::Gradient),
                     "ARRAY_AGG" => Some(AggFunc::ArrayAgg),
+                    "WMA" => Some(AggFunc::Wma),
+                    "RATE" => Some(AggFunc::Rate),
+                    "INCREASE" => Some(AggFunc::Increase),
+                    "IRATE" => Some(AggFunc::Irate),
                     _ => None,
                 };
                 if let Some(a) = agg {
                     // Special-case COUNT(*) to support row counting semantics
                     if a == AggFunc::Count && inner.trim() == "*" {
-                        items.push(SelectItem{ func: Some(AggFunc::Count), str_func: None, window_func: None, window_spec: None, column: "*".into(), expr: None, alias });
+                        items.push(SelectItem{ func: Some(AggFunc::Count), str_func: None, window_func: None, window_spec: None, frame, column: "*".into(), expr: None, alias });
                         continue;
                     }
                     // Parse inner as arithmetic expression allowing sensor-1 etc.
                     let ar = parse_arith_expr(&inner.split_whitespace().map(|s| s.to_string()).collect::<Vec<String>>())?;
-                    items.push(SelectItem{ func: Some(a), str_func: None, window_func: None, window_spec: None, column: inner.into(), expr: Some(ar), alias });
+                    items.push(SelectItem{ func: Some(a), str_func: None, window_func: None, window_spec: None, frame, column: inner.into(), expr: Some(ar), alias });
                     continue;
                 }
+                if frame.is_some() { anyhow::bail!("OVER (ROWS|RANGE ...) frames apply only to aggregate functions"); }
                 let sfunc = match func_name.as_str() {
                     "UPPER" => Some(StrFunc::Upper),
                     "LOWER" => Some(StrFunc::Lower),
@@ -184,7 +208,7 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
                 };
                 if let Some(sf) = sfunc {
                     // For string funcs, keep legacy column parsing
-                    items.push(SelectItem{ func: None, str_func: Some(sf), window_func: None, window_spec: None, column: inner.into(), expr: None, alias });
+                    items.push(SelectItem{ func: None, str_func: Some(sf), window_func: None, window_spec: None, frame: None, column: inner.into(), expr: None, alias });
                     continue;
                 }
                 // Recognize window functions: ROW_NUMBER() OVER (...)
@@ -286,6 +310,7 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
                                         str_func: None, 
                                         window_func: Some(wf), 
                                         window_spec: Some(window_spec), 
+                                        frame: None,
                                         column: t.into(), 
                                         expr: None, 
                                         alias 
@@ -300,12 +325,12 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
                 // Support date functions as arithmetic expressions
                 if matches!(func_name.as_str(), "DATEPART" | "DATEADD" | "DATEDIFF") {
                     let ar = parse_arith_expr(&[t.to_string()])?;
-                    items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: t.into(), expr: Some(ar), alias });
+                    items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: t.into(), expr: Some(ar), alias });
                     continue;
                 }
                 // Unknown functions: allow as arithmetic expression (may resolve to Lua UDF at execution)
                 let ar = parse_arith_expr(&[t.to_string()])?;
-                items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: t.into(), expr: Some(ar), alias });
+                items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: t.into(), expr: Some(ar), alias });
                 continue;
             }
         }
@@ -324,14 +349,14 @@ pub fn parse_select_list(s: &str) -> Result<Vec<SelectItem>> {
             if contains_pg_cast || is_numeric || is_datetime || looks_like_slice || tok.starts_with("f'") || is_single_quoted_literal || is_null_literal {
                 // Defer to arithmetic expression parser to correctly build literal/expr nodes
                 let ar = parse_arith_expr(&tokens)?;
-                items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: t.into(), expr: Some(ar), alias });
+                items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: t.into(), expr: Some(ar), alias });
             } else {
                 // simple column name
-                items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: t.into(), expr: None, alias });
+                items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: t.into(), expr: None, alias });
             }
         } else {
             let ar = parse_arith_expr(&tokens)?;
-            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, column: t.into(), expr: Some(ar), alias });
+            items.push(SelectItem{ func: None, str_func: None, window_func: None, window_spec: None, frame: None, column: t.into(), expr: Some(ar), alias });
         }
     }
     Ok(items)
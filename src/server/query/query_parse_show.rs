@@ -16,20 +16,20 @@ pub fn parse_show(s: &str) -> Result<Command> {
             return Ok(Command::ShowGraphStatus { name: Some(normalized_name) });
         }
     }
-    if up == "SHOW TRANSACTION ISOLATION LEVEL" { return Ok(Command::ShowTransactionIsolation); }
-    if up == "SHOW STANDARD_CONFORMING_STRINGS" { return Ok(Command::ShowStandardConformingStrings); }
-    if up.starts_with("SHOW SERVER_VERSION") { return Ok(Command::ShowServerVersion); }
-    if up == "SHOW CLIENT_ENCODING" { return Ok(Command::ShowClientEncoding); }
-    if up == "SHOW SERVER_ENCODING" { return Ok(Command::ShowServerEncoding); }
-    if up == "SHOW DATESTYLE" { return Ok(Command::ShowDateStyle); }
-    if up == "SHOW INTEGER_DATETIMES" { return Ok(Command::ShowIntegerDateTimes); }
-    if up == "SHOW TIME ZONE" || up == "SHOW TIMEZONE" { return Ok(Command::ShowTimeZone); }
-    if up == "SHOW SEARCH_PATH" { return Ok(Command::ShowSearchPath); }
-    if up == "SHOW DEFAULT_TRANSACTION_ISOLATION" { return Ok(Command::ShowDefaultTransactionIsolation); }
-    if up == "SHOW TRANSACTION_READ_ONLY" { return Ok(Command::ShowTransactionReadOnly); }
-    if up == "SHOW APPLICATION_NAME" { return Ok(Command::ShowApplicationName); }
-    if up == "SHOW EXTRA_FLOAT_DIGITS" { return Ok(Command::ShowExtraFloatDigits); }
-    if up == "SHOW ALL" { return Ok(Command::ShowAll); }
+    // Parameters whose SHOW spelling differs from the parameter name
+    if up == "SHOW TRANSACTION ISOLATION LEVEL" { return Ok(Command::ShowVariable { name: "transaction_isolation".to_string() }); }
+    if up == "SHOW TIME ZONE" { return Ok(Command::ShowVariable { name: "TimeZone".to_string() }); }
+    if up.trim_end_matches(';').trim_end() == "SHOW ALL" { return Ok(Command::ShowAll); }
+    if up.trim_end_matches(';').trim_end() == "SHOW CACHE STATS" { return Ok(Command::ShowCacheStats); }
+    if up.trim_end_matches(';').trim_end() == "SHOW SESSIONS" {
+        return Ok(Command::Select(parse_select("SELECT * FROM pg_catalog.clarium_sessions ORDER BY issued_at")?));
+    }
+    if up.trim_end_matches(';').trim_end() == "SHOW JOBS" {
+        return Ok(Command::Select(parse_select("SELECT * FROM pg_catalog.clarium_jobs ORDER BY name")?));
+    }
+    if up.trim_end_matches(';').trim_end() == "SHOW JOB RUNS" {
+        return Ok(Command::Select(parse_select("SELECT * FROM pg_catalog.clarium_job_runs ORDER BY started_at DESC")?));
+    }
     // SHOW SCHEMAS / SCHEMA [WHERE ...] [ORDER BY ...]
     if up.starts_with("SHOW SCHEMAS") || up.starts_with("SHOW SCHEMA") {
         let tail = s.trim()["SHOW SCHEMAS".len().min(s.len())..].trim();
@@ -98,6 +98,12 @@ pub fn parse_show(s: &str) -> Result<Command> {
         return Ok(Command::ShowFilestoreConfig { filestore: fs, folder_prefix });
     }
 
+    if up.starts_with("SHOW FILESTORE GC ") {
+        let tail = s.trim()["SHOW FILESTORE GC ".len()..].trim().trim_end_matches(';').trim();
+        if tail.is_empty() { anyhow::bail!("SHOW FILESTORE GC: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(tail);
+        return Ok(Command::ShowFilestoreGc { filestore: fs });
+    }
     if up.starts_with("SHOW FILES IN FILESTORE ") {
         // SHOW FILES IN FILESTORE <name> [LIKE '<prefix>'] [LIMIT n] [OFFSET k]
         let tail = s.trim()["SHOW FILES IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
@@ -172,6 +178,55 @@ pub fn parse_show(s: &str) -> Result<Command> {
         let fs = crate::ident::normalize_identifier(tail);
         return Ok(Command::ShowHealthInFilestore { filestore: fs });
     }
+    if up.starts_with("SHOW BRANCHES IN FILESTORE ") {
+        let tail = s.trim()["SHOW BRANCHES IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
+        if tail.is_empty() { anyhow::bail!("SHOW BRANCHES IN FILESTORE: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(tail);
+        return Ok(Command::ShowBranchesInFilestore { filestore: fs });
+    }
+    if up.starts_with("SHOW SYNC IN FILESTORE ") {
+        let tail = s.trim()["SHOW SYNC IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
+        if tail.is_empty() { anyhow::bail!("SHOW SYNC IN FILESTORE: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(tail);
+        return Ok(Command::ShowSyncInFilestore { filestore: fs });
+    }
+    if up.starts_with("SHOW LEGAL HOLDS IN FILESTORE ") {
+        let tail = s.trim()["SHOW LEGAL HOLDS IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
+        if tail.is_empty() { anyhow::bail!("SHOW LEGAL HOLDS IN FILESTORE: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(tail);
+        return Ok(Command::ShowLegalHoldsInFilestore { filestore: fs });
+    }
+    if up.starts_with("SHOW LOCKS IN FILESTORE ") {
+        let tail = s.trim()["SHOW LOCKS IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
+        if tail.is_empty() { anyhow::bail!("SHOW LOCKS IN FILESTORE: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(tail);
+        return Ok(Command::ShowLocksInFilestore { filestore: fs });
+    }
+    if up.starts_with("SHOW WEBHOOKS IN FILESTORE ") {
+        let tail = s.trim()["SHOW WEBHOOKS IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
+        if tail.is_empty() { anyhow::bail!("SHOW WEBHOOKS IN FILESTORE: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(tail);
+        return Ok(Command::ShowWebhooksInFilestore { filestore: fs });
+    }
+    if up.starts_with("SHOW ENCRYPTION IN FILESTORE ") {
+        let tail = s.trim()["SHOW ENCRYPTION IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
+        if tail.is_empty() { anyhow::bail!("SHOW ENCRYPTION IN FILESTORE: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(tail);
+        return Ok(Command::ShowEncryptionInFilestore { filestore: fs });
+    }
+    if up.starts_with("SHOW VERSIONS IN FILESTORE ") {
+        // SHOW VERSIONS IN FILESTORE <name> [PATH '<logical>']
+        let tail = s.trim()["SHOW VERSIONS IN FILESTORE ".len()..].trim().trim_end_matches(';').trim();
+        let sp = tail.find(' ').unwrap_or(tail.len());
+        if sp == 0 { anyhow::bail!("SHOW VERSIONS IN FILESTORE: missing filestore name"); }
+        let fs = crate::ident::normalize_identifier(&tail[..sp]);
+        let rest = tail[sp..].trim();
+        let logical_path = if rest.is_empty() { None } else {
+            if !rest.to_uppercase().starts_with("PATH ") { anyhow::bail!("SHOW VERSIONS: expected PATH '<logical>'"); }
+            Some(rest[5..].trim().trim_matches('\'').to_string())
+        };
+        return Ok(Command::ShowVersionsInFilestore { filestore: fs, logical_path });
+    }
 
     if up.starts_with("SHOW DIFF IN FILESTORE ") {
         // SHOW DIFF IN FILESTORE <name> LEFT <tree_id> [RIGHT <tree_id> | LIVE LIKE '<prefix>']
@@ -218,11 +273,17 @@ pub fn parse_show(s: &str) -> Result<Command> {
         return Ok(Command::ShowGraph { name: normalized_name });
     }
     if up.starts_with("SHOW GRAPHS") { return Ok(Command::ShowGraphs); }
+    if up.trim_end_matches(';').trim_end() == "SHOW SLICES" { return Ok(Command::ShowSlices); }
     if up.starts_with("SHOW VIEW ") {
         let name = s.trim()["SHOW VIEW ".len()..].trim();
         if name.is_empty() { anyhow::bail!("SHOW VIEW: missing name"); }
         let normalized_name = crate::ident::normalize_identifier(name);
         return Ok(Command::ShowView { name: normalized_name });
     }
+    // SHOW <parameter>: any single name is looked up in the GUC registry when executed
+    let tail = s.trim()["SHOW".len()..].trim().trim_end_matches(';').trim_end();
+    if !tail.is_empty() && !tail.contains(char::is_whitespace) {
+        return Ok(Command::ShowVariable { name: tail.to_string() });
+    }
     anyhow::bail!("Unsupported SHOW command")
 }
\ No newline at end of file
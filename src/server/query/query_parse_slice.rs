@@ -2,9 +2,81 @@ use crate::server::query::query_common::*;
 use crate::server::query::*;
 
 
+// A bare identifier names a slice saved with CREATE SLICE: BY SLICE(shifts), UNION SLICE(shifts), SLICE shifts
+fn named_slice_ref(s: &str) -> Option<SlicePlan> {
+    let t = s.trim();
+    if t.is_empty() || t.contains(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == '{' || c == '}') { return None; }
+    if t.eq_ignore_ascii_case("SLICE") || t.eq_ignore_ascii_case("USING") { return None; }
+    let name = crate::ident::normalize_identifier(t);
+    Some(SlicePlan { base: SliceSource::Named(name), clauses: Vec::new(), labels: None, adjust: Vec::new() })
+}
+
+// Keywords that end a slice source (WHERE text, LABEL(...)) and start the next clause or adjustment
+const SLICE_SOURCE_END: [&str; 6] = [" INTERSECT ", " UNION ", " EXCEPT ", " MERGE ", " PAD ", " COMPLEMENT"];
+
+// Byte offset just past the n-th whitespace-separated token of s
+fn tokens_end(s: &str, n: usize) -> usize {
+    let mut end = 0usize;
+    for _ in 0..n {
+        let rest = &s[end..];
+        let start = end + (rest.len() - rest.trim_start().len());
+        end = start + s[start..].find(char::is_whitespace).unwrap_or(s.len() - start);
+    }
+    end
+}
+
+/// Parse MERGE WITHIN <window>, PAD <window> | PAD [BEFORE <window>] [AFTER <window>] and COMPLEMENT
+/// steps at the start of s. Returns the steps in written order and the bytes consumed.
+pub fn parse_slice_adjust(s: &str) -> Result<(Vec<SliceAdjust>, usize)> {
+    let mut out: Vec<SliceAdjust> = Vec::new();
+    let mut pos = 0usize;
+    loop {
+        let rest = &s[pos..];
+        let t = rest.trim_start();
+        let lead = rest.len() - t.len();
+        let words: Vec<&str> = t.split_whitespace().take(5).collect();
+        let kw = words.first().map(|w| w.to_uppercase()).unwrap_or_default();
+        match kw.as_str() {
+            "COMPLEMENT" => {
+                out.push(SliceAdjust::Complement);
+                pos += lead + tokens_end(t, 1);
+            }
+            "MERGE" => {
+                if !words.get(1).is_some_and(|w| w.eq_ignore_ascii_case("WITHIN")) || words.len() < 3 {
+                    anyhow::bail!("MERGE expects WITHIN <window>, e.g. MERGE WITHIN 5s");
+                }
+                out.push(SliceAdjust::MergeWithin(parse_window(words[2])?));
+                pos += lead + tokens_end(t, 3);
+            }
+            "PAD" => {
+                let (mut before, mut after) = (0i64, 0i64);
+                let mut used = 1usize;
+                while used + 1 < words.len() {
+                    let side = words[used].to_uppercase();
+                    if side != "BEFORE" && side != "AFTER" { break; }
+                    let ms = parse_window(words[used + 1])?;
+                    if side == "BEFORE" { before = ms; } else { after = ms; }
+                    used += 2;
+                }
+                if used == 1 {
+                    let w = words.get(1).ok_or_else(|| anyhow::anyhow!("PAD expects <window> or BEFORE/AFTER <window>"))?;
+                    let ms = parse_window(w)?;
+                    before = ms; after = ms;
+                    used = 2;
+                }
+                out.push(SliceAdjust::Pad { before, after });
+                pos += lead + tokens_end(t, used);
+            }
+            _ => break,
+        }
+    }
+    Ok((out, pos))
+}
+
 // --- SLICE parser ---
 pub fn parse_slice(input: &str) -> Result<SlicePlan> {
     let s = input.trim();
+    if let Some(plan) = named_slice_ref(s) { return Ok(plan); }
     let up = s.to_uppercase();
     let mut pos = 0usize;
     // Expect leading SLICE
@@ -16,6 +88,7 @@ pub fn parse_slice(input: &str) -> Result<SlicePlan> {
     }
     // Expect USING or nested SLICE
     let rest = &s[pos..].trim_start();
+    if pos > 0 { if let Some(plan) = named_slice_ref(rest) { return Ok(plan); } }
     let rest_up = rest.to_uppercase();
     let mut cursor = 0usize;
     if rest_up.starts_with("USING ") {
@@ -29,13 +102,19 @@ pub fn parse_slice(input: &str) -> Result<SlicePlan> {
         let inner_plan = parse_slice(inner)?;
         let mut clauses: Vec<SliceClause> = Vec::new();
         let mut tail = &rest[kw_len + consumed..];
+        let (adjust, used_adj) = parse_slice_adjust(tail)?;
+        tail = &tail[used_adj..];
         // parse subsequent clauses
         while !tail.trim().is_empty() {
-            let (cl, used) = parse_slice_clause(tail.trim())?;
+            let lead_ws = tail.len() - tail.trim_start().len();
+            let (mut cl, used) = parse_slice_clause(tail.trim_start())?;
+            tail = &tail[lead_ws + used..];
+            let (adj, used_adj) = parse_slice_adjust(tail)?;
+            cl.adjust = adj;
+            tail = &tail[used_adj..];
             clauses.push(cl);
-            tail = &tail[used..];
         }
-        return Ok(SlicePlan { base: SliceSource::Plan(Box::new(inner_plan)), clauses, labels: None });
+        return Ok(SlicePlan { base: SliceSource::Plan(Box::new(inner_plan)), clauses, labels: None, adjust });
     } else {
         anyhow::bail!("SLICE expects USING or SLICE(...)");
     }
@@ -77,22 +156,28 @@ pub fn parse_slice(input: &str) -> Result<SlicePlan> {
     let (base_src, used) = parse_slice_source(t0)?;
     let lead_ws0 = tail.len() - t0.len();
     tail = &tail[lead_ws0 + used..];
+    let (adjust, used_adj) = parse_slice_adjust(tail)?;
+    tail = &tail[used_adj..];
     let mut clauses: Vec<SliceClause> = Vec::new();
     loop {
         let t = tail.trim_start();
         if t.is_empty() { break; }
         let up = t.to_uppercase();
-        if !(up.starts_with("INTERSECT") || up.starts_with("UNION")) {
+        if !(up.starts_with("INTERSECT") || up.starts_with("UNION") || up.starts_with("EXCEPT")) {
             break;
         }
-        let (cl, used2) = parse_slice_clause(t)?;
-        clauses.push(cl);
+        let (mut cl, used2) = parse_slice_clause(t)?;
         // Map used2 (relative to trimmed t) back to original tail by accounting for leading whitespace
         let lead_ws = tail.len() - tail.trim_start().len();
         let adv = lead_ws + used2;
         tail = &tail[adv..];
+        // Adjustments after a clause apply to everything to their left
+        let (adj, used_adj) = parse_slice_adjust(tail)?;
+        cl.adjust = adj;
+        tail = &tail[used_adj..];
+        clauses.push(cl);
     }
-    Ok(SlicePlan { base: base_src, clauses, labels })
+    Ok(SlicePlan { base: base_src, clauses, labels, adjust })
 }
 
 pub fn parse_slice_clause(s: &str) -> Result<(SliceClause, usize)> {
@@ -103,7 +188,9 @@ pub fn parse_slice_clause(s: &str) -> Result<(SliceClause, usize)> {
     else if up.starts_with("INTERSECT") { op = Some(SliceOp::Intersect); offset = 9; }
     else if up.starts_with("UNION ") { op = Some(SliceOp::Union); offset = 6; }
     else if up.starts_with("UNION") { op = Some(SliceOp::Union); offset = 5; }
-    else { anyhow::bail!("Expected INTERSECT or UNION"); }
+    else if up.starts_with("EXCEPT ") { op = Some(SliceOp::Except); offset = 7; }
+    else if up.starts_with("EXCEPT") { op = Some(SliceOp::Except); offset = 6; }
+    else { anyhow::bail!("Expected INTERSECT, UNION or EXCEPT"); }
     let rest = s[offset..].trim_start();
     // Nested grouped plan? Accept only SLICE(...)
     let rest_up = rest.to_uppercase();
@@ -112,11 +199,11 @@ pub fn parse_slice_clause(s: &str) -> Result<(SliceClause, usize)> {
         let (inner, consumed) = extract_slice_block(&rest[kw_len..])?;
         let plan = parse_slice(inner)?;
         let used = offset + (rest.len() - rest[kw_len+consumed..].len());
-        return Ok((SliceClause{ op: op.unwrap(), source: SliceSource::Plan(Box::new(plan)) }, used));
+        return Ok((SliceClause{ op: op.unwrap(), source: SliceSource::Plan(Box::new(plan)), adjust: Vec::new() }, used));
     }
     let (src, used2) = parse_slice_source(rest)?;
     let used = offset + (rest.len() - rest[used2..].len());
-    Ok((SliceClause{ op: op.unwrap(), source: src }, used))
+    Ok((SliceClause{ op: op.unwrap(), source: src, adjust: Vec::new() }, used))
 }
 
 pub fn parse_slice_source(s: &str) -> Result<(SliceSource, usize)> {
@@ -181,7 +268,7 @@ pub fn parse_slice_source(s: &str) -> Result<(SliceSource, usize)> {
     let mut t2 = tail;
     // Optional WHERE/FILTER for this source; capture only if it appears before the next UNION/INTERSECT
     let t2_up = t2.to_uppercase();
-    let next_clause_pos = find_next_keyword(&t2, [" INTERSECT ", " UNION "].as_slice());
+    let next_clause_pos = find_next_keyword(&t2, SLICE_SOURCE_END.as_slice());
     let mut found_filter = None;
     if let Some(iw) = t2_up.find("WHERE ") { found_filter = Some((iw, 5)); }
     else if let Some(iflt) = t2_up.find("FILTER ") { found_filter = Some((iflt, 6)); }
@@ -190,7 +277,7 @@ pub fn parse_slice_source(s: &str) -> Result<(SliceSource, usize)> {
         if next_clause_pos.map(|p| pos_kw < p).unwrap_or(true) {
             let after = &t2[pos_kw + kw_len + 1..]; // skip keyword and following space
             // find end marker starting from 'after'
-            let end_idx_rel = find_next_keyword(after, [" INTERSECT ", " UNION "].as_slice()).unwrap_or(after.len());
+            let end_idx_rel = find_next_keyword(after, SLICE_SOURCE_END.as_slice()).unwrap_or(after.len());
             let expr_txt = after[..end_idx_rel].trim();
             where_clause = Some(parse_where_expr(expr_txt)?);
             // Reconstruct remaining tail of this slice source (everything after the WHERE expression)
@@ -203,7 +290,7 @@ pub fn parse_slice_source(s: &str) -> Result<(SliceSource, usize)> {
     let rem_all = t2.trim_start();
     if !rem_all.is_empty() {
         // Determine boundary to next clause based on the original (untrimmed) tail to avoid missing leading-space keywords
-        let next_pos_full = find_next_keyword(&t2, [" INTERSECT ", " UNION "].as_slice());
+        let next_pos_full = find_next_keyword(&t2, SLICE_SOURCE_END.as_slice());
         let lead_ws = t2.len() - rem_all.len();
         let next_pos = next_pos_full.map(|p| p.saturating_sub(lead_ws)).unwrap_or(rem_all.len());
         let cutoff = next_pos.min(rem_all.len());
@@ -1,8 +1,55 @@
 use crate::server::query::query_common::*;
+use crate::server::query::query_parse_arith_expr::parse_arith_expr;
 use crate::server::query::*;
 
+// Split SET assignments at commas outside literals and parentheses
+fn split_assignments(s: &str) -> Vec<&str> {
+    let masked = mask_sql_literals(s);
+    let mut out: Vec<&str> = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0usize;
+    for (i, b) in masked.bytes().enumerate() {
+        match b {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b',' if depth == 0 => { out.push(&s[start..i]); start = i + 1; }
+            _ => {}
+        }
+    }
+    out.push(&s[start..]);
+    out
+}
+
+fn parse_assignment_value(right: &str) -> Result<ArithExpr> {
+    let term = if right.eq_ignore_ascii_case("NULL") {
+        ArithTerm::Null
+    } else if right.starts_with('\'') && right.ends_with('\'') && right.len() >= 2 && !right[1..right.len()-1].contains('\'') {
+        ArithTerm::Str(right[1..right.len()-1].to_string())
+    } else if let Ok(num) = right.parse::<f64>() {
+        ArithTerm::Number(num)
+    } else if parse_iso8601_to_ms(right).is_some() {
+        ArithTerm::Str(right.to_string())
+    } else {
+        // Expression over columns; a bare word that names no column is still taken as text at execution
+        let tokens: Vec<String> = right.split_whitespace().map(|s| s.to_string()).collect();
+        return Ok(parse_arith_expr(&tokens).unwrap_or_else(|_| ArithExpr::Term(ArithTerm::Str(right.to_string()))));
+    };
+    Ok(ArithExpr::Term(term))
+}
+
+fn parse_update_from(s: &str) -> Result<UpdateFrom> {
+    let mut parts = s.split_whitespace();
+    let table = parts.next().ok_or_else(|| anyhow::anyhow!("Invalid UPDATE syntax: missing table after FROM"))?.to_string();
+    let mut alias = parts.next().map(|a| a.to_string());
+    if alias.as_deref().map(|a| a.eq_ignore_ascii_case("AS")).unwrap_or(false) {
+        alias = Some(parts.next().ok_or_else(|| anyhow::anyhow!("Invalid UPDATE syntax: missing alias after AS"))?.to_string());
+    }
+    if let Some(extra) = parts.next() { anyhow::bail!("Invalid UPDATE syntax near '{}': FROM takes a single table", extra); }
+    Ok(UpdateFrom { table, alias })
+}
+
 pub fn parse_update(s: &str) -> Result<Command> {
-    // UPDATE <table> SET col = value[, ...] [WHERE ...]
+    // UPDATE <table> SET col = <expr>[, ...] [FROM <table> [[AS] alias]] [WHERE ...]
     let rest = s[6..].trim(); // after UPDATE
     if rest.is_empty() { anyhow::bail!("Invalid UPDATE syntax: missing table name"); }
     // Split at SET (case-insensitive)
@@ -14,13 +61,20 @@ pub fn parse_update(s: &str) -> Result<Command> {
         if table.len() >= 2 { table = table[1..table.len()-1].to_string(); }
     }
     let after_set = &rest[pos_set + 5..];
-    // Optional WHERE: split once on WHERE
-    let (assign_part, where_part_opt) = split_once_any(after_set, &[" WHERE "]);
+    // Optional WHERE, then optional FROM before it; both only outside literals and subqueries
+    let (head, where_part_opt) = match find_top_level_keyword(after_set, " WHERE ") {
+        Some(i) => (&after_set[..i], Some(&after_set[i + 7..])),
+        None => (after_set, None),
+    };
+    let (assign_part, from) = match find_top_level_keyword(head, " FROM ") {
+        Some(i) => (&head[..i], Some(parse_update_from(&head[i + 6..])?)),
+        None => (head, None),
+    };
     let assign_part = assign_part.trim();
     if assign_part.is_empty() { anyhow::bail!("Invalid UPDATE syntax: empty SET assignments"); }
-    // Parse assignments: comma-separated col = value
-    let mut assignments: Vec<(String, ArithTerm)> = Vec::new();
-    for chunk in split_csv_ignoring_quotes(assign_part) {
+    // Parse assignments: comma-separated col = expr
+    let mut assignments: Vec<(String, ArithExpr)> = Vec::new();
+    for chunk in split_assignments(assign_part) {
         let t = chunk.trim();
         if t.is_empty() { continue; }
         // split on first '='
@@ -29,28 +83,13 @@ pub fn parse_update(s: &str) -> Result<Command> {
         let left = t[..eq].trim().trim_matches('"').to_string();
         let right = t[eq+1..].trim();
         if left.is_empty() { anyhow::bail!("Invalid assignment: missing column name"); }
-        let term = if right.eq_ignore_ascii_case("NULL") {
-            ArithTerm::Null
-        } else if right.starts_with('\'') && right.ends_with('\'') && right.len() >= 2 {
-            ArithTerm::Str(right[1..right.len()-1].to_string())
-        } else if let Ok(num) = right.parse::<f64>() {
-            ArithTerm::Number(num)
-        } else {
-            // treat as bare identifier string literal for now
-            ArithTerm::Str(right.to_string())
-        };
-        assignments.push((left, term));
+        if right.is_empty() { anyhow::bail!("Invalid assignment: missing value for {}", left); }
+        assignments.push((left, parse_assignment_value(right)?));
     }
     if assignments.is_empty() { anyhow::bail!("UPDATE: no assignments parsed"); }
     let where_clause = where_part_opt
         .map(|w| w.trim())
         .filter(|w| !w.is_empty())
         .and_then(|w| parse_where_expr(w).ok());
-    Ok(Command::Update { table, assignments, where_clause })
+    Ok(Command::Update { table, assignments, from, where_clause })
 }
-
-
-
-
-
-
@@ -52,7 +52,7 @@ pub fn parse_user(s: &str) -> Result<Command> {
         }
         return Ok(Command::UserAdd { username: username.to_string(), password: pw.to_string(), is_admin, perms, scope_db });
     } else if up.starts_with("ALTER ") {
-        // USER ALTER <username> [PASSWORD '<pw>'] [ADMIN true|false] [PERMISSIONS (<list>)] [GLOBAL | (IN|FROM|TO) <db>]
+        // USER ALTER <username> [PASSWORD '<pw>'] [ADMIN true|false] [PERMISSIONS (<list>)] [EXPIRE PASSWORD] [UNLOCK] [GLOBAL | (IN|FROM|TO) <db>]
         let mut tail = &rest[6..];
         // username up to space or end
         let mut parts = tail.trim().splitn(2, ' ');
@@ -63,10 +63,14 @@ pub fn parse_user(s: &str) -> Result<Command> {
         let mut is_admin: Option<bool> = None;
         let mut perms: Option<Vec<String>> = None;
         let mut scope_db: Option<String> = None;
+        let mut expire_password = false;
+        let mut unlock = false;
         let mut t = tail;
         loop {
             if t.is_empty() { break; }
             let t_up = t.to_uppercase();
+            if t_up.starts_with("EXPIRE PASSWORD") { expire_password = true; t = t[15..].trim_start(); continue; }
+            if t_up.starts_with("UNLOCK") { unlock = true; t = t[6..].trim_start(); continue; }
             if t_up.starts_with("PASSWORD ") {
                 let after_pw = &t[9..].trim();
                 let pw = if after_pw.starts_with('\'') {
@@ -105,7 +109,7 @@ pub fn parse_user(s: &str) -> Result<Command> {
             }
             break;
         }
-        return Ok(Command::UserAlter { username: username.to_string(), new_password, is_admin, perms, scope_db });
+        return Ok(Command::UserAlter { username: username.to_string(), new_password, is_admin, perms, scope_db, expire_password, unlock });
     } else if up.starts_with("DELETE ") {
         let tail = &rest[7..].trim();
         let mut scope_db: Option<String> = None;
@@ -78,6 +78,15 @@ pub fn parse_vector_ddl(s: &str) -> Option<Result<Command>> {
         let normalized = crate::ident::normalize_identifier(name);
         return Some(Ok(Command::ReindexVectorIndex { name: normalized }));
     }
+    if up == "REBUILD VECTOR INDEXES" {
+        return Some(Ok(Command::RebuildVectorIndex { name: None }));
+    }
+    if up.starts_with("REBUILD VECTOR INDEX ") {
+        let name = s.trim()["REBUILD VECTOR INDEX ".len()..].trim();
+        if name.is_empty() { return Some(Err(anyhow::anyhow!("REBUILD VECTOR INDEX: missing name"))); }
+        let normalized = crate::ident::normalize_identifier(name);
+        return Some(Ok(Command::RebuildVectorIndex { name: Some(normalized) }));
+    }
     if up.starts_with("SHOW VECTOR INDEX STATUS") {
         let tail = s.trim()["SHOW VECTOR INDEX STATUS".len()..].trim();
         if tail.is_empty() { return Some(Ok(Command::ShowVectorIndexStatus { name: None })); }
@@ -30,6 +30,8 @@ pub fn parse_where_expr(s: &str) -> Result<WhereExpr> {
         And, Or, Not, Is, Null,
         Like, Between, In, Exists, Any, All,
         True, False,
+        // COLLATE together with the collation name that follows it
+        Collate(String),
     }
     #[derive(Clone, Debug)]
     struct Tok { kind: TKind, pos: usize }
@@ -92,6 +94,24 @@ pub fn parse_where_expr(s: &str) -> Result<WhereExpr> {
                 let start = i; i += 1; while i < bytes.len() { let ch = bytes[i] as char; if is_ident_part(ch) { i += 1; } else { break; } }
                 let raw = input[start..i].to_string();
                 let up = raw.to_uppercase();
+                if up == "COLLATE" {
+                    // The name may be quoted ("codepoint-ci") and contain '-', which identifiers cannot
+                    while i < bytes.len() && bytes[i].is_ascii_whitespace() { i += 1; }
+                    let name_start = i;
+                    let name = if i < bytes.len() && bytes[i] == b'"' {
+                        let close = input[i + 1..].find('"').map(|k| i + 1 + k)
+                            .ok_or_else(|| anyhow::anyhow!("Syntax error at position {}: unterminated collation name.\n{}", name_start, caret_snippet(input, name_start)))?;
+                        i = close + 1;
+                        &input[name_start + 1..close]
+                    } else {
+                        while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'-') { i += 1; }
+                        &input[name_start..i]
+                    };
+                    if name.is_empty() { anyhow::bail!("Syntax error at position {}: expected collation name after COLLATE.\n{}", name_start, caret_snippet(input, name_start)); }
+                    let c = crate::server::collation::Collation::resolve(name)?;
+                    toks.push(Tok{ kind: TKind::Collate(c.name), pos: start });
+                    continue;
+                }
                 let kind = match up.as_str() {
                     "AND" => TKind::And,
                     "OR" => TKind::Or,
@@ -146,6 +166,15 @@ pub fn parse_where_expr(s: &str) -> Result<WhereExpr> {
 
     // precedence: OR=1, AND=2, comparisons/IS=3
     fn parse_primary(cur: &mut Cursor, src: &str) -> Result<ArithExpr> {
+        let expr = parse_operand(cur, src)?;
+        if let Some(TKind::Collate(collation)) = cur.peek_kind() {
+            cur.next();
+            return Ok(ArithExpr::Collate { expr: Box::new(expr), collation });
+        }
+        Ok(expr)
+    }
+
+    fn parse_operand(cur: &mut Cursor, src: &str) -> Result<ArithExpr> {
         if let Some(t) = cur.peek() {
             match &t.kind {
                 TKind::LParen => { cur.next(); let expr = parse_bool_expr(cur, src, 1)?; // parse inner as boolean, wrap as predicate=1 for arithmetic context
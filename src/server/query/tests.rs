@@ -224,3 +224,237 @@ fn test_from_table_still_works() {
         }
     }
 }
+
+#[test]
+fn strip_sql_comments_preserves_offsets() {
+    let sql = "SELECT /* café */ a, -- naïve\n'-- kept' AS s, $$ /* kept */ $$ AS d FROM t";
+    let out = strip_sql_comments(sql);
+    assert_eq!(out.len(), sql.len());
+    assert_eq!(out.find("FROM"), sql.find("FROM"));
+    assert!(out.contains("'-- kept'"));
+    assert!(out.contains("$$ /* kept */ $$"));
+    assert!(!out.contains("café") && !out.contains("naïve"));
+    // Nested block comments, and a comment still separates tokens
+    assert_eq!(strip_sql_comments("a/* x /* y */ z */b").replace(' ', "_"), "a_________________b");
+    // Escaped quotes do not end a literal early
+    assert_eq!(strip_sql_comments("E'it\\'s -- x' /*c*/"), "E'it\\'s -- x'      ");
+    assert_eq!(strip_sql_comments("'it''s -- x'"), "'it''s -- x'");
+    assert_eq!(strip_sql_comments("\"a--b\" --c"), "\"a--b\"    ");
+}
+
+#[test]
+fn split_sql_statements_ignores_quoted_and_commented_semicolons() {
+    let sql = "SELECT ';' AS x; -- one; two\nSELECT 2 /* ; */; $f$ ; $f$; ; -- trailing";
+    let parts = split_sql_statements(sql);
+    assert_eq!(parts, vec!["SELECT ';' AS x", "-- one; two\nSELECT 2 /* ; */", "$f$ ; $f$"]);
+    // $1 parameters are not dollar quotes
+    assert_eq!(split_sql_statements("SELECT $1; SELECT $2"), vec!["SELECT $1", "SELECT $2"]);
+}
+
+#[test]
+fn parse_with_leading_and_inline_comments() {
+    for sql in [
+        "/* app=orders */ SELECT a FROM t",
+        "-- generated by IDE\nSELECT a FROM t",
+        "SELECT a -- trailing\nFROM t /* end */",
+        "SELECT/**/a FROM t",
+    ] {
+        match parse(sql) {
+            Ok(Command::Select(q)) => {
+                assert!(matches!(&q.base_table, Some(TableRef::Table { name, .. }) if name == "t"), "{}", sql);
+            }
+            other => panic!("{}: expected Select, got {:?}", sql, other),
+        }
+    }
+}
+
+#[test]
+fn parse_comment_on_targets_and_values() {
+    let on = |sql: &str| match parse(sql) {
+        Ok(Command::CommentOn { object, comment }) => (object, comment),
+        other => panic!("{}: expected CommentOn, got {:?}", sql, other),
+    };
+    assert_eq!(on("COMMENT ON TABLE public.orders IS 'Customer orders';"), (CommentObject::Table("public.orders".into()), Some("Customer orders".into())));
+    assert_eq!(on("comment on column orders.total is 'it''s gross'"), (CommentObject::Column { table: "orders".into(), column: "total".into() }, Some("it's gross".into())));
+    assert_eq!(on("COMMENT ON VIEW v_recent IS NULL"), (CommentObject::View("v_recent".into()), None));
+    assert_eq!(on("COMMENT ON FUNCTION add_one(bigint) IS ''"), (CommentObject::Function("add_one".into()), None));
+    assert!(parse("COMMENT ON COLUMN total IS 'x'").is_err());
+    assert!(parse("COMMENT ON INDEX i IS 'x'").is_err());
+    assert!(parse("COMMENT ON TABLE t IS bare").is_err());
+}
+
+#[test]
+fn parse_grant_and_revoke() {
+    use crate::security::Privilege;
+    match parse("GRANT SELECT, insert ON TABLE public.orders TO analyst, \"Bob\";").unwrap() {
+        Command::Grant { privileges, object, grantees } => {
+            assert_eq!(privileges, vec![Privilege::Select, Privilege::Insert]);
+            assert_eq!(object, GrantTarget::Table("public.orders".into()));
+            assert_eq!(grantees, vec!["analyst".to_string(), "Bob".to_string()]);
+        }
+        other => panic!("expected Grant, got {:?}", other),
+    }
+    match parse("REVOKE ALL PRIVILEGES ON SCHEMA clarium/public FROM analyst").unwrap() {
+        Command::Revoke { privileges, object, grantees } => {
+            assert_eq!(privileges, Privilege::ALL.to_vec());
+            assert_eq!(object, GrantTarget::Schema("clarium/public".into()));
+            assert_eq!(grantees, vec!["analyst".to_string()]);
+        }
+        other => panic!("expected Revoke, got {:?}", other),
+    }
+    assert!(matches!(parse("GRANT DDL ON DATABASE clarium TO ops").unwrap(), Command::Grant { object: GrantTarget::Database(_), .. }));
+    assert!(parse("GRANT TRUNCATE ON orders TO analyst").is_err());
+    assert!(parse("GRANT SELECT ON orders").is_err());
+    assert!(parse("REVOKE SELECT ON orders TO analyst").is_err());
+}
+
+#[test]
+fn parse_roles_and_set_role() {
+    assert!(matches!(parse("CREATE ROLE ops WITH SUPERUSER NOINHERIT").unwrap(), Command::CreateRole { ref name, superuser: true, inherit: false } if name == "ops"));
+    assert!(matches!(parse("CREATE ROLE readers;").unwrap(), Command::CreateRole { superuser: false, inherit: true, .. }));
+    assert!(parse("CREATE ROLE readers LOGIN").is_err());
+    assert!(matches!(parse("DROP ROLE IF EXISTS ops").unwrap(), Command::DropRole { ref name, if_exists: true } if name == "ops"));
+    match parse("GRANT readers, writers TO alice WITH ADMIN OPTION").unwrap() {
+        Command::GrantRole { roles, members, admin_option } => {
+            assert_eq!(roles, vec!["readers".to_string(), "writers".to_string()]);
+            assert_eq!(members, vec!["alice".to_string()]);
+            assert!(admin_option);
+        }
+        other => panic!("expected GrantRole, got {:?}", other),
+    }
+    assert!(matches!(parse("REVOKE readers FROM alice, bob").unwrap(), Command::RevokeRole { ref members, .. } if members.len() == 2));
+    assert!(parse("GRANT readers").is_err());
+    for sql in ["SET ROLE analyst", "SET role TO analyst", "SET SESSION ROLE 'analyst'"] {
+        match parse(sql).unwrap() {
+            Command::Set { variable, value, .. } => { assert_eq!(variable.to_lowercase(), "role"); assert_eq!(value, "analyst"); }
+            other => panic!("expected Set for {}, got {:?}", sql, other),
+        }
+    }
+    assert!(matches!(parse("RESET ROLE").unwrap(), Command::Reset { variable: Some(_) }));
+}
+
+#[test]
+fn parse_jobs() {
+    match parse("CREATE JOB Nightly SCHEDULE '*/5 * * * *' AS DELETE FROM clarium/public/events WHERE age > 30;").unwrap() {
+        Command::CreateJob { name, schedule, body } => {
+            assert_eq!(name, "nightly");
+            assert_eq!(schedule, "*/5 * * * *");
+            assert_eq!(body, JobBody::Sql("DELETE FROM clarium/public/events WHERE age > 30".into()));
+        }
+        other => panic!("expected CreateJob, got {:?}", other),
+    }
+    assert!(matches!(parse("CREATE JOB rollup SCHEDULE '0 3 * * 1-5' AS SCRIPT clarium/public/rollup").unwrap(),
+        Command::CreateJob { body: JobBody::Script(ref p), .. } if p == "clarium/public/rollup"));
+    assert!(matches!(parse("CREATE JOB q SCHEDULE '0 * * * *' AS 'INSERT INTO t (a) VALUES (''x''); SELECT 1'").unwrap(),
+        Command::CreateJob { body: JobBody::Sql(ref s), .. } if s == "INSERT INTO t (a) VALUES ('x'); SELECT 1"));
+    assert!(parse("CREATE JOB q SCHEDULE */5 * * * * AS SELECT 1").is_err());
+    assert!(parse("CREATE JOB q AS SELECT 1").is_err());
+    assert!(matches!(parse("ALTER JOB nightly DISABLE").unwrap(), Command::AlterJob { ref name, enabled: false } if name == "nightly"));
+    assert!(matches!(parse("ALTER JOB nightly ENABLE;").unwrap(), Command::AlterJob { enabled: true, .. }));
+    assert!(parse("ALTER JOB nightly PAUSE").is_err());
+    assert!(matches!(parse("DROP JOB IF EXISTS nightly").unwrap(), Command::DropJob { if_exists: true, .. }));
+    assert!(matches!(parse("SHOW JOBS").unwrap(), Command::Select(_)));
+}
+
+#[test]
+fn parse_triggers() {
+    use crate::storage::cdc::ChangeOp;
+    use crate::storage::triggers::{OnError, TriggerMode};
+    match parse("CREATE TRIGGER Audit AFTER INSERT OR DELETE ON clarium/public/orders FOR EACH ROW EXECUTE FUNCTION audit_rows() WITH (mode = 'async', on_error = 'retry', batch_size = 50);").unwrap() {
+        Command::CreateTrigger { table, trigger } => {
+            assert_eq!(table, "clarium/public/orders");
+            assert_eq!(trigger.name, "audit");
+            assert_eq!(trigger.events, vec![ChangeOp::Insert, ChangeOp::Delete]);
+            assert_eq!(trigger.function, "audit_rows");
+            assert_eq!(trigger.mode, TriggerMode::Async);
+            assert_eq!(trigger.on_error, OnError::Retry);
+            assert_eq!(trigger.retries, 3);
+            assert_eq!(trigger.batch_size, 50);
+        }
+        other => panic!("expected CreateTrigger, got {:?}", other),
+    }
+    assert!(matches!(parse("CREATE TRIGGER t AFTER UPDATE ON orders EXECUTE PROCEDURE f").unwrap(),
+        Command::CreateTrigger { ref trigger, .. } if trigger.mode == TriggerMode::Sync && trigger.on_error == OnError::Fail));
+    assert!(parse("CREATE TRIGGER t BEFORE INSERT ON orders EXECUTE FUNCTION f").is_err());
+    assert!(parse("CREATE TRIGGER t AFTER TRUNCATE ON orders EXECUTE FUNCTION f").is_err());
+    assert!(parse("CREATE TRIGGER t AFTER INSERT ON orders EXECUTE FUNCTION f WITH (mode = 'later')").is_err());
+    assert!(parse("CREATE TRIGGER t AFTER INSERT ON orders EXECUTE FUNCTION f WITH (batch_size = 0)").is_err());
+    assert!(parse("CREATE TRIGGER t AFTER INSERT ON orders").is_err());
+    assert!(matches!(parse("DROP TRIGGER IF EXISTS audit ON orders").unwrap(),
+        Command::DropTrigger { ref name, ref table, if_exists: true } if name == "audit" && table == "orders"));
+    assert!(parse("DROP TRIGGER audit").is_err());
+}
+
+#[test]
+fn parse_procedures() {
+    let sql = "CREATE OR REPLACE PROCEDURE clarium/public/Refill(n BIGINT, IN tag TEXT) LANGUAGE plpgsql AS $$
+        DECLARE i BIGINT := 0;
+        BEGIN
+            WHILE i < n LOOP
+                i := i + 1;
+                IF i % 2 = 0 THEN CONTINUE; ELSIF i > 7 THEN EXIT; END IF;
+                INSERT INTO stock (label, n) VALUES (tag, i);
+            END LOOP;
+            RAISE NOTICE 'refilled % rows for %', i, tag;
+        END
+    $$;";
+    let Command::CreateProcedure { name, params, body, or_replace } = parse(sql).unwrap() else { panic!("expected CreateProcedure") };
+    assert_eq!(name, "clarium/public/Refill");
+    assert!(or_replace);
+    assert_eq!(params, vec![ProcParam { name: "n".into(), type_name: "BIGINT".into() }, ProcParam { name: "tag".into(), type_name: "TEXT".into() }]);
+    let stmts = parse_procedure_body(&body).unwrap();
+    assert_eq!(stmts[0], ProcStmt::Declare { name: "i".into(), type_name: Some("BIGINT".into()), expr: Some("0".into()) });
+    let ProcStmt::Loop { cond: Some(cond), body: loop_body } = &stmts[1] else { panic!("expected WHILE loop, got {:?}", stmts[1]) };
+    assert_eq!(cond, "i < n");
+    assert_eq!(loop_body[0], ProcStmt::Assign { name: "i".into(), expr: "i + 1".into() });
+    assert_eq!(loop_body[1], ProcStmt::If {
+        branches: vec![("i % 2 = 0".into(), vec![ProcStmt::Continue { when: None }]), ("i > 7".into(), vec![ProcStmt::Exit { when: None }])],
+        otherwise: vec![],
+    });
+    assert!(matches!(&loop_body[2], ProcStmt::Sql(s) if s.starts_with("INSERT INTO stock")));
+    assert_eq!(stmts[2], ProcStmt::Raise { level: "NOTICE".into(), message: "refilled % rows for %".into(), args: vec!["i".into(), "tag".into()] });
+    // END closes the block, so nothing follows the RAISE
+    assert_eq!(stmts.len(), 3);
+
+    // Plain statement lists, with COMMIT / ROLLBACK between them
+    let body = parse_procedure_body("INSERT INTO t VALUES (1); COMMIT; LOOP EXIT WHEN TRUE; END LOOP; ROLLBACK WORK; CALL other(1, 'a,b')").unwrap();
+    assert_eq!(body[1], ProcStmt::Commit);
+    assert_eq!(body[2], ProcStmt::Loop { cond: None, body: vec![ProcStmt::Exit { when: Some("TRUE".into()) }] });
+    assert_eq!(body[3], ProcStmt::Rollback);
+    assert_eq!(body[4], ProcStmt::Call { name: "other".into(), args: vec!["1".into(), "'a,b'".into()] });
+
+    assert!(matches!(parse("CALL refill(3, 'x' || 'y');").unwrap(), Command::Call { ref name, ref args } if name == "refill" && args.len() == 2));
+    assert!(matches!(parse("CALL noop()").unwrap(), Command::Call { ref args, .. } if args.is_empty()));
+    assert!(matches!(parse("DROP PROCEDURE IF EXISTS refill(bigint, text)").unwrap(),
+        Command::DropProcedure { ref name, if_exists: true } if name == "refill"));
+
+    // Errors surface at CREATE
+    assert!(parse("CREATE PROCEDURE p() AS $$ IF x THEN SELECT 1; $$").is_err());
+    assert!(parse("CREATE PROCEDURE p() AS $$ EXIT; $$").is_err());
+    assert!(parse("CREATE PROCEDURE p() AS $$ BEGIN; INSERT INTO t VALUES (1) $$").is_err());
+    assert!(parse("CREATE PROCEDURE p(a BIGINT, a TEXT) AS $$ SELECT 1 $$").is_err());
+    assert!(parse("CREATE PROCEDURE p(OUT a BIGINT) AS $$ SELECT 1 $$").is_err());
+    assert!(parse("CREATE PROCEDURE p() LANGUAGE lua AS $$ SELECT 1 $$").is_err());
+    assert!(parse("CREATE PROCEDURE p() AS SELECT 1").is_err());
+    assert!(parse("CREATE PROCEDURE p() AS $$ RAISE EXCEPTION oops $$").is_err());
+}
+
+#[test]
+fn parse_script_tests() {
+    let Command::CreateScriptTest { path, language, code } = parse("CREATE SCRIPT TEST SQL clarium/public/totals AS $$ ASSERT 1 = 1 $$;").unwrap()
+        else { panic!("expected CreateScriptTest") };
+    assert_eq!((path.as_str(), language, code.trim()), ("clarium/public/totals", ScriptTestLanguage::Sql, "ASSERT 1 = 1"));
+    assert!(matches!(parse("create script test t1 as 'function test_a() end'").unwrap(),
+        Command::CreateScriptTest { language: ScriptTestLanguage::Lua, ref path, .. } if path == "t1"));
+    assert!(matches!(parse("DROP SCRIPT TEST IF EXISTS t1").unwrap(), Command::DropScriptTest { ref path, if_exists: true } if path == "t1"));
+    assert!(matches!(parse("RUN TESTS").unwrap(), Command::RunTests { target: None }));
+    assert!(matches!(parse("RUN TESTS clarium/public;").unwrap(), Command::RunTests { target: Some(ref t) } if t == "clarium/public"));
+    let Command::Assert { condition, message } = parse("ASSERT (SELECT COUNT(*) FROM t) = 2, 'two rows, it''s expected'").unwrap()
+        else { panic!("expected Assert") };
+    assert_eq!(condition, "(SELECT COUNT(*) FROM t) = 2");
+    assert_eq!(message.as_deref(), Some("two rows, it's expected"));
+    assert!(parse("ASSERT").is_err());
+    assert!(parse("ASSERT x = 1, no_quotes").is_err());
+    assert!(parse("RUN TESTS clarium").is_err());
+    assert!(parse("CREATE SCRIPT TEST t1 AS function").is_err());
+}
@@ -0,0 +1,157 @@
+//! Per-user quotas for statements run by a frontend backend (HTTP, WebSocket, pgwire).
+//!
+//! - `limits.queries_per_minute`: statements started in the last 60 seconds. Over the limit a
+//!   statement is throttled until the window frees a slot, for up to `limits.queue_timeout_ms`,
+//!   and then rejected.
+//! - `limits.rows_scanned_per_day`: rows read from table storage.
+//! - `limits.ingest_bytes_per_day`: in-memory size of rows written by INSERT, COPY and `/write`.
+//!
+//! 0 = unlimited. Daily counters restart at midnight UTC; the statement that crosses a daily
+//! limit fails with SQLSTATE 53400. Statements run without a backend (startup, internal
+//! lookups) are not counted. Usage lives in memory and is exposed as `pg_catalog.clarium_quotas`.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::config::LimitSettings;
+use crate::error::AppError;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Usage counters of one user.
+#[derive(Debug, Clone, Default)]
+pub struct QuotaUsage {
+    pub queries_last_minute: u64,
+    pub rows_scanned_today: u64,
+    pub ingest_bytes_today: u64,
+    /// Statements that had to wait for the per-minute window
+    pub throttled_total: u64,
+    /// Statements that failed on a quota
+    pub rejected_total: u64,
+}
+
+#[derive(Default)]
+struct Usage {
+    recent: VecDeque<Instant>,
+    day: i64,
+    rows_scanned: u64,
+    ingest_bytes: u64,
+    throttled_total: u64,
+    rejected_total: u64,
+}
+
+impl Usage {
+    // Restart daily counters on a new UTC day and forget statements older than the window
+    fn roll(&mut self, now: Instant) {
+        let today = chrono::Utc::now().timestamp().div_euclid(86_400);
+        if self.day != today {
+            self.day = today;
+            self.rows_scanned = 0;
+            self.ingest_bytes = 0;
+        }
+        while self.recent.front().is_some_and(|t| now.duration_since(*t) >= WINDOW) { self.recent.pop_front(); }
+    }
+}
+
+static USAGE: Lazy<Mutex<BTreeMap<String, Usage>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+fn limit_error(message: String) -> anyhow::Error {
+    AppError::exec("configuration_limit_exceeded".to_string(), message).into()
+}
+
+/// Counters per user, ordered by user name.
+pub fn snapshot() -> Vec<(String, QuotaUsage)> {
+    let now = Instant::now();
+    let mut map = USAGE.lock();
+    map.iter_mut().map(|(user, u)| {
+        u.roll(now);
+        (user.clone(), QuotaUsage {
+            queries_last_minute: u.recent.len() as u64,
+            rows_scanned_today: u.rows_scanned,
+            ingest_bytes_today: u.ingest_bytes,
+            throttled_total: u.throttled_total,
+            rejected_total: u.rejected_total,
+        })
+    }).collect()
+}
+
+/// Count a statement of `user` against `limits.queries_per_minute`, waiting while the window is full.
+pub async fn admit_query(user: &str) -> Result<()> {
+    admit_query_as(user, &crate::config::current().limits).await
+}
+
+pub(crate) async fn admit_query_as(user: &str, limits: &LimitSettings) -> Result<()> {
+    let deadline = Instant::now() + Duration::from_millis(limits.queue_timeout_ms);
+    let mut throttled = false;
+    loop {
+        let now = Instant::now();
+        let wait = {
+            let mut map = USAGE.lock();
+            let u = map.entry(user.to_string()).or_default();
+            u.roll(now);
+            if limits.queries_per_minute == 0 || (u.recent.len() as u64) < limits.queries_per_minute {
+                u.recent.push_back(now);
+                return Ok(());
+            }
+            let wait = u.recent.front().map(|t| WINDOW.saturating_sub(now.duration_since(*t))).unwrap_or_default();
+            if now + wait > deadline {
+                u.rejected_total += 1;
+                return Err(limit_error(format!(
+                    "rate limit exceeded: user \"{}\" ran {} statements in the last minute (limits.queries_per_minute = {})",
+                    user, u.recent.len(), limits.queries_per_minute
+                )));
+            }
+            if !throttled { u.throttled_total += 1; throttled = true; }
+            wait
+        };
+        crate::tprintln!("[quota] throttling user='{}' for {:?}", user, wait);
+        tokio::time::sleep(wait.max(Duration::from_millis(1))).await;
+    }
+}
+
+enum Daily { RowsScanned, IngestBytes }
+
+fn charge(user: &str, kind: Daily, amount: u64, limits: &LimitSettings) -> Result<()> {
+    if amount == 0 { return Ok(()); }
+    let mut map = USAGE.lock();
+    let u = map.entry(user.to_string()).or_default();
+    u.roll(Instant::now());
+    let (used, limit, what, key) = match kind {
+        Daily::RowsScanned => { u.rows_scanned += amount; (u.rows_scanned, limits.rows_scanned_per_day, "rows scanned", "rows_scanned_per_day") }
+        Daily::IngestBytes => { u.ingest_bytes += amount; (u.ingest_bytes, limits.ingest_bytes_per_day, "bytes ingested", "ingest_bytes_per_day") }
+    };
+    if limit == 0 || used <= limit { return Ok(()); }
+    u.rejected_total += 1;
+    Err(limit_error(format!("daily quota exceeded: user \"{}\" has {} {} today (limits.{} = {})", user, used, what, key, limit)))
+}
+
+/// Charge rows read from storage to the user of the running statement.
+pub fn charge_rows_scanned(rows: usize) -> Result<()> {
+    let Some(user) = crate::server::activity::current_user() else { return Ok(()) };
+    charge(&user, Daily::RowsScanned, rows as u64, &crate::config::current().limits)
+}
+
+/// Charge ingested bytes to the user of the running statement.
+pub fn charge_ingest(bytes: usize) -> Result<()> {
+    let Some(user) = crate::server::activity::current_user() else { return Ok(()) };
+    charge_ingest_as(&user, bytes)
+}
+
+/// Charge ingested bytes to `user` (for frontends that write outside a statement, like `/write`).
+pub fn charge_ingest_as(user: &str, bytes: usize) -> Result<()> {
+    charge(user, Daily::IngestBytes, bytes as u64, &crate::config::current().limits)
+}
+
+pub(crate) fn charge_as(user: &str, rows: u64, bytes: u64, limits: &LimitSettings) -> Result<()> {
+    charge(user, Daily::RowsScanned, rows, limits)?;
+    charge(user, Daily::IngestBytes, bytes, limits)
+}
+
+/// Approximate size of time-table records, for ingest accounting.
+pub fn records_size(records: &[crate::storage::Record]) -> usize {
+    records.iter().map(|r| 8 + serde_json::to_vec(&r.sensors).map(|v| v.len()).unwrap_or(0)).sum()
+}
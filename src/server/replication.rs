@@ -0,0 +1,338 @@
+//!
+//! clarium replication
+//! -------------------
+//! Primary → replica replication over HTTP using file manifests.
+//!
+//! Everything Clarium persists (parquet chunks, schema.json, CDC logs, catalogs under
+//! `.system`) lives in immutable-or-rewritten files under the database root, so the
+//! primary publishes a manifest of `{path, size, mtime, hash}` and a replica converges
+//! by downloading files whose hash differs and removing files the primary no longer has.
+//!
+//! Primary endpoints (enabled when `CLARIUM_REPLICATION_TOKEN` is set; requests must send
+//! the same value in `X-Replication-Token`):
+//! - GET /replication/manifest      -> manifest JSON; `X-Replica-Id`/`X-Replica-Position` record the poller
+//! - GET /replication/file/{*path}  -> raw bytes of one manifest entry
+//!
+//! Replica mode is enabled with `CLARIUM_REPLICA_OF=<primary http url>`; the server then
+//! polls every `CLARIUM_REPLICATION_INTERVAL_MS` (default 1000) and rejects writes.
+//! Progress is exposed in `pg_catalog.pg_stat_replication` (primary) and
+//! `pg_catalog.pg_stat_wal_receiver` (replica).
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::server::query::{Command, KvOp};
+
+pub const TOKEN_HEADER: &str = "X-Replication-Token";
+pub const REPLICA_ID_HEADER: &str = "X-Replica-Id";
+pub const REPLICA_POSITION_HEADER: &str = "X-Replica-Position";
+
+/// Replica-local bookkeeping under `.system`, never shipped in manifests.
+const REPLICATION_DIR: &str = "replication";
+const TMP_SUFFIX: &str = ".repl-tmp";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path relative to the database root, always `/`-separated.
+    pub path: String,
+    pub size: u64,
+    pub mtime: i64,
+    pub hash: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub generated_at: i64,
+    /// Newest file modification time (ms) on the primary; replicas report it back as their position.
+    pub position: i64,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Replica progress, surfaced through pg_stat_wal_receiver.
+#[derive(Debug, Clone, Default)]
+pub struct ReceiverStatus {
+    pub status: String,
+    pub sender: String,
+    pub position: i64,
+    /// Primary manifest time of the last fully applied sync.
+    pub latest_end_time: i64,
+    pub pending_files: i64,
+    pub last_error: Option<String>,
+}
+
+impl ReceiverStatus {
+    /// How far the replica's data trails the primary, growing between polls; -1 before the first sync.
+    pub fn lag_ms(&self) -> i64 {
+        if self.latest_end_time == 0 { return -1; }
+        (now_ms() - self.latest_end_time).max(0)
+    }
+}
+
+/// A replica as seen by the primary, surfaced through pg_stat_replication.
+#[derive(Debug, Clone)]
+pub struct PeerStatus {
+    pub id: String,
+    pub addr: String,
+    pub sent_position: i64,
+    pub replay_position: i64,
+    pub last_seen: i64,
+}
+
+static REPLICA_MODE: AtomicBool = AtomicBool::new(false);
+static RECEIVER: Lazy<Mutex<Option<ReceiverStatus>>> = Lazy::new(|| Mutex::new(None));
+static PEERS: Lazy<Mutex<HashMap<String, PeerStatus>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Content hashes keyed by path and invalidated by (size, mtime).
+static HASH_CACHE: Lazy<Mutex<HashMap<PathBuf, (u64, i64, u64)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// Whether this server is a read-only replica.
+pub fn is_replica() -> bool { REPLICA_MODE.load(Ordering::Relaxed) }
+
+pub fn set_replica_mode(on: bool) { REPLICA_MODE.store(on, Ordering::Relaxed); }
+
+/// Commands a replica may run: reads, session settings and metadata inspection.
+pub fn is_read_only_command(cmd: &Command) -> bool {
+    matches!(cmd,
+        Command::Select { .. } | Command::SelectUnion { .. } | Command::Slice { .. } | Command::Explain { .. }
+        | Command::ShowView { .. } | Command::ShowSlices | Command::SchemaShow { .. } | Command::DescribeObject { .. }
+        | Command::ListStores { .. } | Command::ListKeys { .. } | Command::DescribeKey { .. } | Command::ReadKey { .. } | Command::KeyOp { op: KvOp::HashGet { .. }, .. }
+        | Command::Watch { .. } | Command::Unwatch { .. }
+        | Command::UseDatabase { .. } | Command::UseSchema { .. } | Command::Set { .. } | Command::Reset { .. } | Command::ClearScriptCache { .. } | Command::Assert { .. } | Command::Kill { .. } | Command::KillSession { .. } | Command::KillUserSessions { .. } | Command::ReloadConfig
+        | Command::ShowVariable { .. } | Command::ShowAll { .. } | Command::ShowSchemas { .. }
+        | Command::ShowTables { .. } | Command::ShowObjects { .. } | Command::ShowScripts { .. }
+        | Command::ShowCacheStats
+        | Command::ShowVectorIndex { .. } | Command::ShowVectorIndexes { .. } | Command::ShowVectorIndexStatus { .. }
+        | Command::ShowGraph { .. } | Command::ShowGraphs { .. } | Command::ShowGraphStatus { .. }
+        | Command::UseGraph { .. } | Command::UnsetGraph { .. } | Command::ShowCurrentGraph { .. }
+        | Command::MatchRewrite { .. } | Command::BackupDatabase { .. } | Command::CopyTo { .. } | Command::VerifyTable { quarantine: false, .. }
+        | Command::ShowFilestores { .. } | Command::ShowFilestoreConfig { .. } | Command::ShowFilesInFilestore { .. }
+        | Command::ShowTreesInFilestore { .. } | Command::ShowCommitsInFilestore { .. } | Command::ShowDiffInFilestore { .. }
+        | Command::ShowChunksInFilestore { .. } | Command::ShowAliasesInFilestore { .. } | Command::ShowAdminInFilestore { .. }
+        | Command::ShowHealthInFilestore { .. } | Command::ShowSyncInFilestore { .. }
+        | Command::ShowBranchesInFilestore { .. } | Command::ShowLegalHoldsInFilestore { .. } | Command::ShowLocksInFilestore { .. }
+        | Command::ShowVersionsInFilestore { .. } | Command::ShowWebhooksInFilestore { .. }
+        | Command::ShowEncryptionInFilestore { .. } | Command::ShowFilestoreGc { .. } | Command::ExplainAccessCmd { .. })
+}
+
+/// Error out when a write reaches a replica.
+pub fn ensure_writable() -> Result<()> {
+    if is_replica() { bail!("cannot execute write statement on a read-only replica"); }
+    Ok(())
+}
+
+// ---- Manifest (primary side) ----
+
+fn excluded(rel: &str) -> bool {
+    rel.starts_with(&format!(".system/{}", REPLICATION_DIR)) || rel.ends_with(TMP_SUFFIX) || rel.ends_with(".tmp")
+}
+
+fn file_hash(path: &Path, size: u64, mtime: i64) -> Result<u64> {
+    if let Some((s, m, h)) = HASH_CACHE.lock().get(path).copied() {
+        if s == size && m == mtime { return Ok(h); }
+    }
+    let h = xxh3_64(&std::fs::read(path)?);
+    HASH_CACHE.lock().insert(path.to_path_buf(), (size, mtime, h));
+    Ok(h)
+}
+
+fn walk(root: &Path, dir: &Path, out: &mut Vec<ManifestEntry>) -> Result<()> {
+    let Ok(rd) = std::fs::read_dir(dir) else { return Ok(()) };
+    for ent in rd.flatten() {
+        let p = ent.path();
+        let ft = ent.file_type()?;
+        if ft.is_dir() { walk(root, &p, out)?; continue; }
+        if !ft.is_file() { continue; }
+        let rel = p.strip_prefix(root)?.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>().join("/");
+        if excluded(&rel) { continue; }
+        let md = ent.metadata()?;
+        let mtime = md.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64).unwrap_or(0);
+        // A file removed between listing and hashing is simply left out of this manifest
+        let Ok(hash) = file_hash(&p, md.len(), mtime) else { continue };
+        out.push(ManifestEntry { path: rel, size: md.len(), mtime, hash });
+    }
+    Ok(())
+}
+
+/// Snapshot every replicated file under `root`.
+pub fn build_manifest(root: &Path) -> Result<Manifest> {
+    let mut files = Vec::new();
+    walk(root, root, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let position = files.iter().map(|f| f.mtime).max().unwrap_or(0);
+    Ok(Manifest { generated_at: now_ms(), position, files })
+}
+
+/// Resolve a manifest path under `root`, rejecting anything that could escape it.
+pub fn resolve_rel_path(root: &Path, rel: &str) -> Result<PathBuf> {
+    let rel_p = Path::new(rel);
+    if rel.is_empty() || !rel_p.components().all(|c| matches!(c, Component::Normal(_))) {
+        bail!("invalid replication path: {}", rel);
+    }
+    if excluded(rel) { bail!("path is not replicated: {}", rel); }
+    Ok(root.join(rel_p))
+}
+
+/// Check the shared replication token; replication is off when no token is configured.
+pub fn authorize(token: Option<&str>) -> bool {
+    match std::env::var("CLARIUM_REPLICATION_TOKEN") {
+        Ok(expected) if !expected.is_empty() => {
+            let Some(token) = token else { return false };
+            // Constant-time comparison
+            token.len() == expected.len() && token.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+        }
+        _ => false,
+    }
+}
+
+/// Record a manifest poll from a replica.
+pub fn note_peer(id: &str, addr: &str, sent_position: i64, replay_position: i64) {
+    PEERS.lock().insert(id.to_string(), PeerStatus {
+        id: id.to_string(),
+        addr: addr.to_string(),
+        sent_position,
+        replay_position,
+        last_seen: now_ms(),
+    });
+}
+
+pub fn peers() -> Vec<PeerStatus> {
+    let mut v: Vec<PeerStatus> = PEERS.lock().values().cloned().collect();
+    v.sort_by(|a, b| a.id.cmp(&b.id));
+    v
+}
+
+pub fn receiver_status() -> Option<ReceiverStatus> { RECEIVER.lock().clone() }
+
+// ---- Apply (replica side) ----
+
+/// Files to download and files to delete so that `local` matches `remote`.
+pub fn diff(local: &Manifest, remote: &Manifest) -> (Vec<ManifestEntry>, Vec<String>) {
+    let have: HashMap<&str, u64> = local.files.iter().map(|f| (f.path.as_str(), f.hash)).collect();
+    let want: std::collections::HashSet<&str> = remote.files.iter().map(|f| f.path.as_str()).collect();
+    let mut fetch: Vec<ManifestEntry> = remote.files.iter()
+        .filter(|f| have.get(f.path.as_str()) != Some(&f.hash))
+        .cloned().collect();
+    // Data before metadata: readers never see a schema.json that points at missing chunks
+    fetch.sort_by_key(|f| (f.path.ends_with("schema.json"), f.path.clone()));
+    let remove = local.files.iter().filter(|f| !want.contains(f.path.as_str())).map(|f| f.path.clone()).collect();
+    (fetch, remove)
+}
+
+/// Bring `root` in line with `remote`, downloading changed files through `fetch`.
+/// Returns (files written, files removed).
+pub async fn sync_once_with<F, Fut>(root: &Path, remote: &Manifest, fetch: F) -> Result<(usize, usize)>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<u8>>>,
+{
+    let local = build_manifest(root)?;
+    let (to_fetch, to_remove) = diff(&local, remote);
+    update_receiver(|s| s.pending_files = (to_fetch.len() + to_remove.len()) as i64);
+    let mut written = 0usize;
+    for ent in &to_fetch {
+        let bytes = fetch(ent.path.clone()).await?;
+        if xxh3_64(&bytes) != ent.hash {
+            // The primary rewrote the file after publishing the manifest; pick it up next poll
+            continue;
+        }
+        let dest = resolve_rel_path(root, &ent.path)?;
+        if let Some(parent) = dest.parent() { std::fs::create_dir_all(parent)?; }
+        let tmp = dest.with_file_name(format!("{}{}", dest.file_name().and_then(|s| s.to_str()).unwrap_or("file"), TMP_SUFFIX));
+        std::fs::write(&tmp, &bytes)?;
+        std::fs::rename(&tmp, &dest)?;
+        written += 1;
+        update_receiver(|s| s.pending_files = (s.pending_files - 1).max(0));
+    }
+    let mut removed = 0usize;
+    for rel in &to_remove {
+        let p = resolve_rel_path(root, rel)?;
+        if std::fs::remove_file(&p).is_ok() { removed += 1; }
+    }
+    crate::tprintln!("[replication] applied manifest position={} written={} removed={}", remote.position, written, removed);
+    Ok((written, removed))
+}
+
+fn update_receiver(f: impl FnOnce(&mut ReceiverStatus)) {
+    let mut g = RECEIVER.lock();
+    f(g.get_or_insert_with(ReceiverStatus::default));
+}
+
+/// Replica configuration read from the environment.
+pub struct ReplicaConfig {
+    pub primary_url: String,
+    pub interval_ms: u64,
+    pub replica_id: String,
+    pub token: Option<String>,
+}
+
+impl ReplicaConfig {
+    pub fn from_env() -> Option<Self> {
+        let primary_url = std::env::var("CLARIUM_REPLICA_OF").ok().filter(|s| !s.trim().is_empty())?;
+        let interval_ms = std::env::var("CLARIUM_REPLICATION_INTERVAL_MS").ok().and_then(|s| s.parse::<u64>().ok()).unwrap_or(1000).max(50);
+        let replica_id = std::env::var("CLARIUM_REPLICA_ID").unwrap_or_else(|_| format!("replica-{}", whoami::devicename()));
+        let token = std::env::var("CLARIUM_REPLICATION_TOKEN").ok();
+        Some(Self { primary_url: primary_url.trim_end_matches('/').to_string(), interval_ms, replica_id, token })
+    }
+}
+
+/// One poll: fetch the primary manifest and apply it.
+pub async fn poll_primary(client: &reqwest::Client, cfg: &ReplicaConfig, root: &Path) -> Result<()> {
+    let position = receiver_status().map(|s| s.position).unwrap_or(0);
+    let with_headers = |rb: reqwest::RequestBuilder| {
+        let rb = rb.header(REPLICA_ID_HEADER, &cfg.replica_id).header(REPLICA_POSITION_HEADER, position.to_string());
+        match &cfg.token { Some(t) => rb.header(TOKEN_HEADER, t), None => rb }
+    };
+    let manifest: Manifest = with_headers(client.get(format!("{}/replication/manifest", cfg.primary_url)))
+        .send().await?.error_for_status()?.json().await?;
+    let fetch = |rel: String| {
+        let req = with_headers(client.get(format!("{}/replication/file/{}", cfg.primary_url, rel)));
+        async move {
+            let resp = req.send().await?.error_for_status().map_err(|e| anyhow!("fetch {}: {}", rel, e))?;
+            Ok(resp.bytes().await?.to_vec())
+        }
+    };
+    sync_once_with(root, &manifest, fetch).await?;
+    update_receiver(|s| {
+        s.status = "streaming".into();
+        s.position = manifest.position;
+        s.latest_end_time = manifest.generated_at;
+        s.last_error = None;
+    });
+    Ok(())
+}
+
+/// Background loop for replica mode; exits when `shutdown` flips to true.
+pub async fn run_replica(cfg: ReplicaConfig, root: PathBuf, mut shutdown: tokio::sync::watch::Receiver<bool>) {
+    set_replica_mode(true);
+    update_receiver(|s| { s.status = "starting".into(); s.sender = cfg.primary_url.clone(); });
+    let client = reqwest::Client::new();
+    loop {
+        if let Err(e) = poll_primary(&client, &cfg, &root).await {
+            tracing::warn!(target = "replication", "replication poll failed: {}", e);
+            update_receiver(|s| {
+                s.status = "retrying".into();
+                s.last_error = Some(e.to_string());
+            });
+        }
+        tokio::select! {
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() { crate::tprintln!("[shutdown] replication receiver exiting on shutdown signal"); break; }
+            }
+            _ = tokio::time::sleep(std::time::Duration::from_millis(cfg.interval_ms)) => {}
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//!
+//! clarium script tests
+//! --------------------
+//! `CREATE SCRIPT TEST` keeps a test next to a schema's scripts, as
+//! `<schema>/scripts/tests/<name>.{lua,sql}`, and `RUN TESTS` runs them, one result row per case:
+//! - Lua tests run in a fresh Lua state holding every registered script plus
+//!   `assert_eq(actual, expected [, message])`. Each global `test_*` function the test defines
+//!   is a case, called in name order; an error raised in it (`assert`, `assert_eq`, `error`)
+//!   fails the case.
+//! - A SQL test is one case. Its statements run in order against a scratch schema
+//!   `_test_<id>` created in the test's database and removed afterwards, so unqualified tables
+//!   are private to the run while qualified names still reach real tables. A false ASSERT fails
+//!   the case and any other error reports it as `error`; either stops the test.
+//!
+//! `ASSERT <condition> [, '<message>']` is also an ordinary statement: parenthesised
+//! `(SELECT ...)` subqueries in the condition are replaced by their single value, and the
+//! condition is then evaluated like a WHERE condition.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::ident::{qualify_regular_ident, QueryDefaults};
+use crate::server::exec::execute_query_with_defaults;
+use crate::server::procedures::{sql_literal, tidy, truthy};
+use crate::server::query::{self, mask_sql_literals, split_sql_statements, Command, ScriptTestLanguage};
+use crate::storage::SharedStore;
+
+/// Helpers defined in every Lua test state before the test itself.
+const LUA_PRELUDE: &str = r#"
+function assert_eq(actual, expected, message)
+    if actual ~= expected then
+        local prefix = message and (tostring(message) .. ': ') or ''
+        error(prefix .. 'expected ' .. tostring(expected) .. ', got ' .. tostring(actual), 2)
+    end
+end
+"#;
+
+/// Outcome of one test case.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// `<db>/<schema>/<name>`, plus `.<function>` for Lua cases
+    pub test: String,
+    pub language: ScriptTestLanguage,
+    /// `pass`, `fail` or `error`
+    pub status: &'static str,
+    pub message: Option<String>,
+    pub duration_ms: f64,
+}
+
+/// Folder holding the tests of one schema.
+pub fn tests_dir(root: &Path, db: &str, schema: &str) -> PathBuf {
+    crate::scripts::scripts_dir_for(root, db, schema).join("tests")
+}
+
+fn split_qualified(qualified: &str) -> Result<(&str, &str, &str)> {
+    let mut parts = qualified.splitn(3, '/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(db), Some(schema), Some(name)) if !name.is_empty() && !name.contains('/') => Ok((db, schema, name)),
+        _ => bail!("script test name must be <db>/<schema>/<name>"),
+    }
+}
+
+fn test_file(root: &Path, qualified: &str, language: ScriptTestLanguage) -> Result<PathBuf> {
+    let (db, schema, name) = split_qualified(qualified)?;
+    Ok(tests_dir(root, db, schema).join(format!("{}.{}", name, language.extension())))
+}
+
+/// Store a test, replacing one of the same name in either language.
+pub fn create_test(root: &Path, qualified: &str, language: ScriptTestLanguage, code: &str) -> Result<()> {
+    let path = test_file(root, qualified, language)?;
+    if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+    drop_test(root, qualified)?;
+    fs::write(&path, code.as_bytes())?;
+    Ok(())
+}
+
+/// Remove a test. Returns false when it does not exist.
+pub fn drop_test(root: &Path, qualified: &str) -> Result<bool> {
+    let mut found = false;
+    for language in [ScriptTestLanguage::Lua, ScriptTestLanguage::Sql] {
+        let path = test_file(root, qualified, language)?;
+        if path.exists() { fs::remove_file(path)?; found = true; }
+    }
+    Ok(found)
+}
+
+/// Tests of one schema as (name, language, file), ordered by name.
+pub fn list_tests(root: &Path, db: &str, schema: &str) -> Result<Vec<(String, ScriptTestLanguage, PathBuf)>> {
+    let dir = tests_dir(root, db, schema);
+    if !dir.exists() { return Ok(Vec::new()); }
+    let mut out = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let language = match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+            Some("lua") => ScriptTestLanguage::Lua,
+            Some("sql") => ScriptTestLanguage::Sql,
+            _ => continue,
+        };
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_string();
+        out.push((name, language, path));
+    }
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(out)
+}
+
+/// Run the tests of `<db>/<schema>` (or only `<db>/<schema>/<name>`); the current schema when
+/// `target` is None.
+pub async fn run_tests(store: &SharedStore, target: Option<&str>) -> Result<Vec<TestResult>> {
+    let root = store.root_path();
+    let defaults = crate::system::current_query_defaults();
+    let (db, schema, only) = match target {
+        None => (defaults.current_database.clone(), defaults.current_schema.clone(), None),
+        Some(t) if t.split('/').count() == 2 => {
+            let (db, schema) = t.split_once('/').unwrap_or((t, ""));
+            (crate::ident::normalize_identifier(db), crate::ident::normalize_identifier(schema), None)
+        }
+        Some(t) => {
+            let qualified = qualify_regular_ident(t, &defaults);
+            let (db, schema, name) = split_qualified(&qualified)?;
+            (db.to_string(), schema.to_string(), Some(name.to_string()))
+        }
+    };
+    let mut tests = list_tests(&root, &db, &schema)?;
+    if let Some(name) = &only {
+        tests.retain(|(n, _, _)| n == name);
+        if tests.is_empty() {
+            return Err(AppError::not_found("undefined_object".to_string(), format!("script test {}/{}/{} does not exist", db, schema, name)).into());
+        }
+    }
+    let mut results = Vec::new();
+    for (name, language, path) in tests {
+        let qualified = format!("{}/{}/{}", db, schema, name);
+        let code = fs::read_to_string(&path)?;
+        match language {
+            ScriptTestLanguage::Lua => results.extend(run_lua_test(&qualified, &code)),
+            ScriptTestLanguage::Sql => results.push(run_sql_test(store, &db, &qualified, &code).await),
+        }
+    }
+    tracing::info!(target: "clarium::scripts", "RUN TESTS {}/{}: {} case(s), {} failed",
+        db, schema, results.len(), results.iter().filter(|r| r.status != "pass").count());
+    Ok(results)
+}
+
+/// Message of an error without the `code:` prefix AppError displays with.
+fn error_message(e: &anyhow::Error) -> String {
+    match e.downcast_ref::<AppError>() {
+        Some(app) => app.message().to_string(),
+        None => e.to_string(),
+    }
+}
+
+fn elapsed_ms(start: Instant) -> f64 { start.elapsed().as_secs_f64() * 1000.0 }
+
+/// Lua errors span several lines (message, then a traceback); the first is the useful one.
+fn lua_error_message(e: &mlua::Error) -> String {
+    let text = e.to_string();
+    let first = text.lines().next().unwrap_or("").trim();
+    first.strip_prefix("runtime error: ").unwrap_or(first).to_string()
+}
+
+fn run_lua_test(qualified: &str, code: &str) -> Vec<TestResult> {
+    let start = Instant::now();
+    let result = |test: String, status, message: Option<String>, start: Instant| TestResult {
+        test, language: ScriptTestLanguage::Lua, status, message, duration_ms: elapsed_ms(start),
+    };
+    let setup = || -> Result<(mlua::Lua, Vec<String>)> {
+        let reg = crate::scripts::get_script_registry().ok_or_else(|| anyhow!("script registry is not initialized"))?;
+        let lua = reg.new_lua_state()?;
+        lua.load(LUA_PRELUDE).exec()?;
+        let before = test_functions(&lua)?;
+        lua.load(code).set_name(qualified).exec().map_err(|e| anyhow!(lua_error_message(&e)))?;
+        let mut cases: Vec<String> = test_functions(&lua)?.into_iter().filter(|n| !before.contains(n)).collect();
+        cases.sort();
+        Ok((lua, cases))
+    };
+    let (lua, cases) = match setup() {
+        Ok(v) => v,
+        Err(e) => return vec![result(qualified.to_string(), "error", Some(e.to_string()), start)],
+    };
+    if cases.is_empty() {
+        return vec![result(qualified.to_string(), "error", Some("no test_* functions defined".to_string()), start)];
+    }
+    cases.into_iter().map(|case| {
+        let start = Instant::now();
+        let outcome = lua.globals().get::<_, mlua::Function>(case.as_str()).and_then(|f| f.call::<_, ()>(()));
+        match outcome {
+            Ok(()) => result(format!("{}.{}", qualified, case), "pass", None, start),
+            Err(e) => result(format!("{}.{}", qualified, case), "fail", Some(lua_error_message(&e)), start),
+        }
+    }).collect()
+}
+
+/// Names of global functions starting with `test_`.
+fn test_functions(lua: &mlua::Lua) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    for pair in lua.globals().pairs::<mlua::Value, mlua::Value>() {
+        if let (mlua::Value::String(k), mlua::Value::Function(_)) = pair? {
+            let name = k.to_str()?;
+            if name.starts_with("test_") { out.push(name.to_string()); }
+        }
+    }
+    Ok(out)
+}
+
+/// Points this thread's current database and schema at a test's scratch schema, restoring the
+/// previous values on drop. SELECT resolves unqualified tables through these rather than the
+/// defaults passed with the statement.
+struct SessionSchema {
+    db: Option<String>,
+    schema: Option<String>,
+}
+
+impl SessionSchema {
+    fn enter(defaults: &QueryDefaults) -> Self {
+        let saved = SessionSchema { db: crate::system::get_current_database_opt(), schema: crate::system::get_current_schema_opt() };
+        crate::system::set_current_database(&defaults.current_database);
+        crate::system::set_current_schema(&defaults.current_schema);
+        saved
+    }
+}
+
+impl Drop for SessionSchema {
+    fn drop(&mut self) {
+        match &self.db { Some(db) => crate::system::set_current_database(db), None => crate::system::unset_current_database() }
+        match &self.schema { Some(schema) => crate::system::set_current_schema(schema), None => crate::system::unset_current_schema() }
+    }
+}
+
+async fn run_sql_test(store: &SharedStore, db: &str, qualified: &str, code: &str) -> TestResult {
+    let start = Instant::now();
+    let scratch = format!("_test_{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let scratch_dir = crate::ident::to_local_path(&store.root_path(), &format!("{}/{}", db, scratch));
+    let (mut status, mut message) = ("pass", None);
+    if let Err(e) = fs::create_dir_all(&scratch_dir) {
+        (status, message) = ("error", Some(format!("cannot create scratch schema: {}", e)));
+    } else {
+        let defaults = QueryDefaults::new(db, scratch.as_str());
+        for stmt in split_sql_statements(code) {
+            let _session = SessionSchema::enter(&defaults);
+            let outcome = match query::parse(stmt) {
+                Ok(Command::Assert { condition, message: text }) => assert(store, &defaults, &condition, text.as_deref()).await,
+                Ok(_) => execute_query_with_defaults(store, stmt, &defaults).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = outcome {
+                let failed = e.downcast_ref::<AppError>().is_some_and(|a| a.code_str() == "assert_failure");
+                (status, message) = (if failed { "fail" } else { "error" }, Some(error_message(&e)));
+                break;
+            }
+        }
+        let _ = fs::remove_dir_all(&scratch_dir);
+    }
+    TestResult { test: qualified.to_string(), language: ScriptTestLanguage::Sql, status, message, duration_ms: elapsed_ms(start) }
+}
+
+/// Replace each `(SELECT ...)` / `(WITH ...)` in `condition` by the literal value it returns.
+async fn inline_subqueries(store: &SharedStore, defaults: &QueryDefaults, condition: &str) -> Result<String> {
+    let masked = mask_sql_literals(condition);
+    let b = masked.as_bytes();
+    let mut out = String::with_capacity(condition.len());
+    let (mut last, mut i) = (0usize, 0usize);
+    while i < b.len() {
+        if b[i] != b'(' { i += 1; continue; }
+        let word: String = masked[i + 1..].trim_start().chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        if !word.eq_ignore_ascii_case("SELECT") && !word.eq_ignore_ascii_case("WITH") { i += 1; continue; }
+        let mut depth = 0i32;
+        let close = masked[i..].char_indices().find_map(|(j, c)| {
+            match c { '(' => depth += 1, ')' => { depth -= 1; if depth == 0 { return Some(i + j); } } _ => {} }
+            None
+        }).ok_or_else(|| anyhow!("ASSERT: unbalanced parentheses"))?;
+        let sql = condition[i + 1..close].trim();
+        let rows = execute_query_with_defaults(store, sql, defaults).await?;
+        let rows = rows.as_array().cloned().unwrap_or_default();
+        if rows.len() > 1 { bail!("ASSERT: subquery returned {} rows, expected at most one: {}", rows.len(), sql); }
+        let value = match rows.first().and_then(|r| r.as_object()) {
+            Some(row) if row.len() != 1 => bail!("ASSERT: subquery must return one column: {}", sql),
+            Some(row) => tidy(row.values().next().cloned().unwrap_or(Value::Null)),
+            None => Value::Null,
+        };
+        out.push_str(&condition[last..i]);
+        out.push_str(&sql_literal(&value));
+        last = close + 1;
+        i = close + 1;
+    }
+    out.push_str(&condition[last..]);
+    Ok(out)
+}
+
+/// Evaluate an ASSERT condition; false or NULL fails with `assert_failure` (SQLSTATE P0004).
+pub async fn assert(store: &SharedStore, defaults: &QueryDefaults, condition: &str, message: Option<&str>) -> Result<()> {
+    let cond = inline_subqueries(store, defaults, condition).await?;
+    let rows = execute_query_with_defaults(store, &format!("SELECT CASE WHEN {} THEN 1 ELSE 0 END AS v", cond.trim()), defaults).await?;
+    let holds = rows.as_array().and_then(|r| r.first()).and_then(|r| r.get("v")).is_some_and(truthy);
+    if holds { return Ok(()); }
+    let message = message.map(str::to_string).unwrap_or_else(|| format!("assertion failed: {}", condition.trim()));
+    Err(AppError::exec("assert_failure".to_string(), message).into())
+}
@@ -0,0 +1,47 @@
+//! The server's `StorageHooks` (see `storage::hooks`): storage events that drive
+//! engine-level work. Installed at startup and, for embedded use and tests, on the
+//! first statement executed.
+
+use std::sync::{Arc, Once};
+
+use anyhow::Result;
+use polars::prelude::*;
+
+use crate::storage::cdc::ChangeOp;
+use crate::storage::hooks::StorageHooks;
+use crate::storage::webhooks::Delivery;
+use crate::storage::Store;
+
+struct ServerHooks;
+
+impl StorageHooks for ServerHooks {
+    fn rows_changed(&self, store: &Store, table: &str, op: ChangeOp, df: &DataFrame) {
+        crate::server::exec::vector_delta::on_rows_changed(store, table, op, df);
+    }
+
+    fn blocks_read(&self, table: &str, chunks: usize) {
+        crate::server::db_stats::count_blocks_read(table, chunks);
+    }
+
+    fn rows_scanned(&self, rows: usize) -> Result<()> {
+        crate::server::quota::charge_rows_scanned(rows)
+    }
+
+    fn computed_expr(&self, text: &str) -> Result<Expr> {
+        let expr = crate::server::query::query_parse_alter::parse_computed_expr(text)?;
+        let mut ctx = crate::server::data_context::DataContext::new();
+        if let Some(reg) = crate::scripts::get_script_registry().and_then(|r| r.snapshot().ok()) { ctx.script_registry = Some(reg); }
+        Ok(crate::server::exec::exec_common::build_arith_expr(&expr, &ctx))
+    }
+
+    fn deliver_webhook(&self, delivery: Delivery) {
+        crate::server::exec::filestore::events::enqueue(delivery);
+    }
+}
+
+static INSTALL: Once = Once::new();
+
+/// Register the server's hooks with storage; later calls do nothing.
+pub fn install() {
+    INSTALL.call_once(|| crate::storage::hooks::register(Arc::new(ServerHooks)));
+}
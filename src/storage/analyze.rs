@@ -0,0 +1,224 @@
+//! Column statistics gathered by `ANALYZE`.
+//!
+//! `ANALYZE <table>` reads the table once and keeps per-column statistics in
+//! `<table_dir>/stats.json`, next to schema.json:
+//!
+//! - `null_frac` and `min`/`max` over every row;
+//! - `n_distinct`, `avg_width` and an equi-depth `histogram` (bucket bounds) over a sample
+//!   of at most `SAMPLE_ROWS` evenly spaced rows. The distinct count of a sample is scaled
+//!   to the table with the Haas–Stokes estimator PostgreSQL uses.
+//!
+//! Numeric, decimal and timestamp columns keep `min`, `max` and bounds as numbers
+//! (timestamps as epoch ms), text columns as strings. `pg_catalog.pg_statistic` and
+//! `pg_stats` report them, and EXPLAIN uses them for its row estimates. The statistics are
+//! not updated by writes; run ANALYZE again after large changes.
+//!
+//! Bounds and histograms are column values, so for tables with an `encryption` setting
+//! stats.json is sealed with the table's key like their chunks (see `storage::encryption`).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::Store;
+
+const STATS_FILE: &str = "stats.json";
+/// Rows sampled for distinct counts, widths and histograms.
+pub const SAMPLE_ROWS: usize = 30_000;
+/// Buckets in a histogram; it keeps one more bound than this.
+pub const HISTOGRAM_BUCKETS: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnStats {
+    /// Fraction of rows that are null.
+    pub null_frac: f64,
+    /// Average stored width of a non-null value in bytes.
+    pub avg_width: i32,
+    /// Estimated number of distinct non-null values (0 when unknown).
+    pub n_distinct: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+    /// Ascending bucket bounds; each bucket holds about the same number of sampled values.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub histogram: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableStats {
+    pub rows: u64,
+    pub sampled_rows: u64,
+    /// Epoch ms of the ANALYZE that produced these statistics.
+    pub analyzed_at: i64,
+    pub columns: BTreeMap<String, ColumnStats>,
+}
+
+impl ColumnStats {
+    /// Fraction of rows equal to a non-null value, assuming values are evenly spread.
+    pub fn eq_fraction(&self) -> f64 {
+        if self.n_distinct >= 1.0 { (1.0 - self.null_frac) / self.n_distinct } else { 0.005 }
+    }
+
+    /// Fraction of rows below `v` according to the histogram, or `None` without one.
+    /// Numbers are interpolated within their bucket; text only counts whole buckets.
+    pub fn below_fraction(&self, v: &Value) -> Option<f64> {
+        let n = self.histogram.len();
+        if n < 2 { return None; }
+        let buckets = (n - 1) as f64;
+        let pos = match v {
+            Value::Number(x) => {
+                let x = x.as_f64()?;
+                let bounds: Vec<f64> = self.histogram.iter().map(|b| b.as_f64()).collect::<Option<_>>()?;
+                if x <= bounds[0] { 0.0 }
+                else if x >= bounds[n - 1] { buckets }
+                else {
+                    let i = bounds.windows(2).position(|w| x < w[1]).unwrap_or(n - 2);
+                    let (lo, hi) = (bounds[i], bounds[i + 1]);
+                    i as f64 + if hi > lo { (x - lo) / (hi - lo) } else { 0.5 }
+                }
+            }
+            Value::String(x) => {
+                let below = self.histogram.iter().map(|b| b.as_str()).collect::<Option<Vec<_>>>()?
+                    .iter().filter(|b| **b < x.as_str()).count();
+                below.saturating_sub(1) as f64
+            }
+            _ => return None,
+        };
+        Some(pos / buckets * (1.0 - self.null_frac))
+    }
+}
+
+pub(crate) fn stats_path(table_dir: &Path) -> PathBuf { table_dir.join(STATS_FILE) }
+
+/// Statistics of the table stored in `table_dir`, if it has been analyzed (and, when
+/// sealed, its key is loaded).
+pub fn read_stats(table_dir: &Path) -> Option<TableStats> {
+    let bytes = fs::read(stats_path(table_dir)).ok()?;
+    let plain = super::encryption::decrypt(bytes).ok()?;
+    serde_json::from_slice(&plain).ok()
+}
+
+/// Rows at evenly spaced positions, at most `SAMPLE_ROWS` of them.
+fn sample(df: &DataFrame) -> Result<DataFrame> {
+    let n = df.height();
+    if n <= SAMPLE_ROWS { return Ok(df.clone()); }
+    let idx: Vec<IdxSize> = (0..SAMPLE_ROWS).map(|i| (i * n / SAMPLE_ROWS) as IdxSize).collect();
+    Ok(df.take(&IdxCa::from_vec("idx".into(), idx))?)
+}
+
+/// Haas–Stokes (Duj1) estimate of the distinct values among `total` rows from a sample of
+/// `n` values holding `d` distinct values, `f1` of them seen once.
+fn estimate_distinct(n: usize, d: usize, f1: usize, total: f64) -> f64 {
+    if n == 0 { return 0.0; }
+    if (n as f64) >= total || f1 == 0 { return d as f64; }
+    let n = n as f64;
+    let est = n * d as f64 / (n - f1 as f64 + f1 as f64 * n / total);
+    est.clamp(d as f64, total)
+}
+
+/// Values of `c` as f64 when the column is numeric, decimal or a timestamp (epoch ms).
+fn numeric_values(c: &Column) -> Result<Option<Float64Chunked>> {
+    let dt = c.dtype();
+    let s = c.as_materialized_series();
+    let f = if dt.is_primitive_numeric() || matches!(dt, DataType::Decimal(..)) {
+        s.cast(&DataType::Float64)?
+    } else if let DataType::Datetime(unit, _) = dt {
+        let unit = *unit;
+        let ms: Int64Chunked = s.cast(&DataType::Int64)?.i64()?.iter().map(|v| v.map(|x| super::native::to_ms(x, unit))).collect();
+        ms.into_series().cast(&DataType::Float64)?
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(f.f64()?.clone()))
+}
+
+fn number(v: f64) -> Value {
+    if v.fract() == 0.0 && v.abs() < 9.0e15 { Value::from(v as i64) } else { serde_json::Number::from_f64(v).map(Value::Number).unwrap_or(Value::Null) }
+}
+
+/// Bounds splitting ascending `sorted` into `HISTOGRAM_BUCKETS` buckets of equal size.
+fn histogram_bounds<T: Clone>(sorted: &[T]) -> Vec<T> {
+    if sorted.len() < 2 { return Vec::new(); }
+    let buckets = HISTOGRAM_BUCKETS.min(sorted.len() - 1);
+    (0..=buckets).map(|i| sorted[i * (sorted.len() - 1) / buckets].clone()).collect()
+}
+
+fn column_stats(full: &Column, sampled: &Column, rows: usize) -> Result<ColumnStats> {
+    let mut st = ColumnStats { null_frac: if rows == 0 { 0.0 } else { full.null_count() as f64 / rows as f64 }, ..Default::default() };
+    let non_null = sampled.drop_nulls();
+    if matches!(full.dtype(), DataType::List(_) | DataType::Null) || non_null.is_empty() { return Ok(st); }
+
+    // Distinct values and widths over the sample
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut width = 0usize;
+    for i in 0..non_null.len() {
+        let key = match non_null.get(i)? {
+            AnyValue::String(s) => { width += s.len(); s.to_string() }
+            AnyValue::StringOwned(s) => { width += s.len(); s.to_string() }
+            v => v.to_string(),
+        };
+        *counts.entry(key).or_default() += 1;
+    }
+    let f1 = counts.values().filter(|c| **c == 1).count();
+    let total_non_null = rows as f64 * (1.0 - st.null_frac);
+    st.n_distinct = estimate_distinct(non_null.len(), counts.len(), f1, total_non_null).round();
+    st.avg_width = match full.dtype() {
+        DataType::String => (width / non_null.len()) as i32,
+        DataType::Boolean => 1,
+        DataType::Decimal(..) => 16,
+        _ => 8,
+    };
+
+    if let Some(all) = numeric_values(full)? {
+        st.min = all.min().map(number);
+        st.max = all.max().map(number);
+        let mut vals: Vec<f64> = numeric_values(&non_null)?.map(|s| s.into_no_null_iter().collect()).unwrap_or_default();
+        vals.sort_by(|a, b| a.total_cmp(b));
+        st.histogram = histogram_bounds(&vals).into_iter().map(number).collect();
+    } else if let Ok(all) = full.str() {
+        st.min = all.iter().flatten().min().map(|s| Value::from(s.to_string()));
+        st.max = all.iter().flatten().max().map(|s| Value::from(s.to_string()));
+        let mut vals: Vec<&str> = non_null.str()?.iter().flatten().collect();
+        vals.sort_unstable();
+        st.histogram = histogram_bounds(&vals).into_iter().map(|s| Value::from(s.to_string())).collect();
+    }
+    Ok(st)
+}
+
+impl Store {
+    /// Gather statistics for `table` and store them in its stats.json.
+    pub fn analyze_table(&self, table: &str) -> Result<TableStats> {
+        let dir = self.db_dir(table);
+        if !dir.is_dir() { anyhow::bail!("Table not found: {}", table); }
+        let df = self.read_df(table)?;
+        let rows = df.height();
+        let sampled = sample(&df)?;
+        let mut columns = BTreeMap::new();
+        for c in df.get_columns() {
+            let st = column_stats(c, sampled.column(c.name().as_str())?, rows)?;
+            columns.insert(c.name().to_string(), st);
+        }
+        let stats = TableStats {
+            rows: rows as u64,
+            sampled_rows: sampled.height() as u64,
+            analyzed_at: chrono::Utc::now().timestamp_millis(),
+            columns,
+        };
+        let json = serde_json::to_vec_pretty(&stats)?;
+        match self.get_table_encryption(table)? {
+            Some(spec) => fs::write(stats_path(&dir), super::encryption::encrypt(&json, &spec)?)?,
+            None => fs::write(stats_path(&dir), json)?,
+        }
+        crate::tprintln!("[storage.analyze] '{}' rows={} sampled={}", table, stats.rows, stats.sampled_rows);
+        Ok(stats)
+    }
+
+    /// Statistics from the last ANALYZE of `table`.
+    pub fn table_stats(&self, table: &str) -> Option<TableStats> { read_stats(&self.db_dir(table)) }
+}
@@ -0,0 +1,125 @@
+//! Attached databases: database folders that live outside the store root.
+//!
+//! `ATTACH DATABASE '<path>' AS name` records `name -> path` in
+//! `<root>/.system/attached.json`; from then on every path that starts with that
+//! database (`name/<schema>/<table>`) resolves under `path` instead of
+//! `<root>/name`, so a database can sit on a separate disk or mount. The folder
+//! layout below the database is unchanged. `DETACH DATABASE` only forgets the
+//! mapping; the files stay where they are and can be attached again later.
+//!
+//! Attached databases are not part of replication manifests, which cover the
+//! store root only.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+/// Loaded registries keyed by store root; read on first use, updated on attach/detach.
+static REGISTRY: Lazy<RwLock<HashMap<PathBuf, BTreeMap<String, PathBuf>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn registry_path(root: &Path) -> PathBuf { crate::system_paths::system_root(root).join("attached.json") }
+
+fn load(root: &Path) -> BTreeMap<String, PathBuf> {
+    fs::read(registry_path(root)).ok()
+        .and_then(|b| serde_json::from_slice::<BTreeMap<String, PathBuf>>(&b).ok())
+        .unwrap_or_default()
+}
+
+fn save(root: &Path, map: &BTreeMap<String, PathBuf>) -> Result<()> {
+    let p = registry_path(root);
+    if let Some(parent) = p.parent() { fs::create_dir_all(parent)?; }
+    let tmp = p.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(map)?)?;
+    fs::rename(&tmp, &p)?;
+    Ok(())
+}
+
+/// All attached databases of `root` as `name -> folder`.
+pub fn attached(root: &Path) -> BTreeMap<String, PathBuf> {
+    if let Some(m) = REGISTRY.read().get(root) { return m.clone(); }
+    let m = load(root);
+    REGISTRY.write().insert(root.to_path_buf(), m.clone());
+    m
+}
+
+/// Folder of an attached database, if `db` is one.
+pub fn attached_dir(root: &Path, db: &str) -> Option<PathBuf> {
+    if let Some(m) = REGISTRY.read().get(root) { return m.get(db).cloned(); }
+    attached(root).get(db).cloned()
+}
+
+/// Folder holding database `db`: its attached location or `<root>/<db>`.
+pub fn database_dir(root: &Path, db: &str) -> PathBuf {
+    attached_dir(root, db).unwrap_or_else(|| root.join(db))
+}
+
+/// Attach the folder at `path` as database `name`. The folder is created when missing.
+/// Its tables are migrated to the current on-disk format first; a folder written by a
+/// newer build is refused (see `storage::migrate`).
+pub fn attach(root: &Path, name: &str, path: &Path) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("Invalid database name: {}", name);
+    }
+    if attached_dir(root, name).is_some() || root.join(name).exists() {
+        bail!("Database already exists: {}", name);
+    }
+    if !path.is_absolute() { bail!("ATTACH DATABASE needs an absolute path: {}", path.display()); }
+    if path.exists() && !path.is_dir() { bail!("Not a directory: {}", path.display()); }
+    let root_abs = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let inside_root = |p: &Path| p.starts_with(&root_abs) || p.starts_with(root);
+    if inside_root(path) { bail!("Cannot attach a folder inside the store root: {}", path.display()); }
+    fs::create_dir_all(path)?;
+    let path = path.canonicalize()?;
+    if inside_root(&path) { bail!("Cannot attach a folder inside the store root: {}", path.display()); }
+    if let Some((other, _)) = attached(root).iter().find(|(_, p)| **p == path) {
+        bail!("Folder is already attached as database '{}'", other);
+    }
+    super::migrate::migrate_database_dir(root, name, &path)?;
+    let mut map = attached(root);
+    map.insert(name.to_string(), path.clone());
+    save(root, &map)?;
+    REGISTRY.write().insert(root.to_path_buf(), map);
+    crate::tprintln!("[storage.attach] attached '{}' -> {}", name, path.display());
+    Ok(())
+}
+
+/// Forget an attached database; its files are left untouched.
+pub fn detach(root: &Path, name: &str) -> Result<PathBuf> {
+    let mut map = attached(root);
+    let Some(path) = map.remove(name) else { bail!("Database is not attached: {}", name) };
+    save(root, &map)?;
+    REGISTRY.write().insert(root.to_path_buf(), map);
+    crate::tprintln!("[storage.attach] detached '{}' ({})", name, path.display());
+    Ok(path)
+}
+
+/// One database folder as yielded by [`read_database_dirs`]; mirrors the parts of
+/// `fs::DirEntry` that database listings use.
+#[derive(Debug, Clone)]
+pub struct DatabaseEntry {
+    name: String,
+    path: PathBuf,
+}
+
+impl DatabaseEntry {
+    pub fn path(&self) -> PathBuf { self.path.clone() }
+    pub fn file_name(&self) -> OsString { OsString::from(&self.name) }
+    pub fn file_type(&self) -> std::io::Result<fs::FileType> { fs::metadata(&self.path).map(|m| m.file_type()) }
+}
+
+/// Drop-in replacement for `fs::read_dir(root)` when enumerating databases: the
+/// entries of the root folder followed by the attached databases.
+pub fn read_database_dirs(root: &Path) -> std::io::Result<std::vec::IntoIter<std::io::Result<DatabaseEntry>>> {
+    let mut out: Vec<std::io::Result<DatabaseEntry>> = fs::read_dir(root)?
+        .map(|r| r.map(|e| DatabaseEntry { name: e.file_name().to_string_lossy().to_string(), path: e.path() }))
+        .collect();
+    for (name, path) in attached(root) {
+        out.push(Ok(DatabaseEntry { name, path }));
+    }
+    Ok(out.into_iter())
+}
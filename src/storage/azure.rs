@@ -0,0 +1,115 @@
+//! Minimal Azure Blob Storage client (Put Blob / Get Blob) with Shared Key authorization.
+//!
+//! Credentials come from the environment variables the Azure CLI uses: `AZURE_STORAGE_ACCOUNT`
+//! with `AZURE_STORAGE_KEY` (Shared Key), or `AZURE_STORAGE_SAS_TOKEN` instead of the key.
+//! Requests go to `https://<account>.blob.core.windows.net`; an endpoint on the location (e.g.
+//! Azurite's `http://127.0.0.1:10000/devstoreaccount1`) replaces it.
+
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::s3::uri_encode;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// REST API version sent with every request.
+const API_VERSION: &str = "2021-08-06";
+
+/// Parsed `az://container/prefix` (or `azure://...`) location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AzureLocation {
+    pub container: String,
+    pub prefix: String,
+    /// Blob service endpoint for this location; the account's public endpoint when `None`.
+    pub endpoint: Option<String>,
+}
+
+impl AzureLocation {
+    pub fn parse(uri: &str) -> Result<Self> {
+        let rest = uri.strip_prefix("az://").or_else(|| uri.strip_prefix("azure://"))
+            .ok_or_else(|| anyhow!("not an az:// location: {}", uri))?;
+        let (container, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if container.is_empty() { bail!("azure location is missing a container: {}", uri); }
+        Ok(Self { container: container.to_string(), prefix: prefix.trim_matches('/').to_string(), endpoint: None })
+    }
+
+    /// Blob name for `rel` under this location's prefix.
+    pub fn key(&self, rel: &str) -> String {
+        if self.prefix.is_empty() { rel.to_string() } else { format!("{}/{}", self.prefix, rel) }
+    }
+}
+
+pub fn is_azure_uri(s: &str) -> bool { s.starts_with("az://") || s.starts_with("azure://") }
+
+enum Auth { SharedKey(Vec<u8>), Sas(String) }
+
+struct Credentials { account: String, auth: Auth }
+
+fn credentials() -> Result<Credentials> {
+    let account = std::env::var("AZURE_STORAGE_ACCOUNT").map_err(|_| anyhow!("AZURE_STORAGE_ACCOUNT is not set"))?;
+    let auth = match std::env::var("AZURE_STORAGE_KEY").ok().filter(|s| !s.is_empty()) {
+        Some(key) => Auth::SharedKey(base64::engine::general_purpose::STANDARD.decode(key.trim())
+            .map_err(|_| anyhow!("AZURE_STORAGE_KEY is not valid base64"))?),
+        None => match std::env::var("AZURE_STORAGE_SAS_TOKEN").ok().filter(|s| !s.is_empty()) {
+            Some(sas) => Auth::Sas(sas.trim_start_matches('?').to_string()),
+            None => bail!("neither AZURE_STORAGE_KEY nor AZURE_STORAGE_SAS_TOKEN is set"),
+        },
+    };
+    Ok(Credentials { account, auth })
+}
+
+/// Shared Key string-to-sign for a request without query parameters. `ms_headers` are the
+/// `x-ms-*` headers, lowercase; `path` is the encoded URL path.
+pub(crate) fn string_to_sign(method: &str, content_length: usize, ms_headers: &[(&str, String)], account: &str, path: &str) -> String {
+    let mut headers: Vec<&(&str, String)> = ms_headers.iter().collect();
+    headers.sort_by(|a, b| a.0.cmp(b.0));
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v.trim())).collect();
+    // Content-Length is empty for bodiless requests; the other standard headers are not sent
+    let length = if content_length == 0 { String::new() } else { content_length.to_string() };
+    format!("{}\n\n\n{}\n\n\n\n\n\n\n\n\n{}/{}{}", method, length, canonical_headers, account, path)
+}
+
+fn signed_request(client: &reqwest::Client, method: reqwest::Method, loc: &AzureLocation, key: &str, body_len: usize, extra: &[(&str, String)]) -> Result<reqwest::RequestBuilder> {
+    let creds = credentials()?;
+    let base = loc.endpoint.clone().unwrap_or_else(|| format!("https://{}.blob.core.windows.net", creds.account));
+    let mut url = reqwest::Url::parse(&format!("{}/{}/{}", base.trim_end_matches('/'), uri_encode(&loc.container, false), uri_encode(key, true)))?;
+    let mut headers: Vec<(&str, String)> = vec![
+        ("x-ms-date", chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        ("x-ms-version", API_VERSION.to_string()),
+    ];
+    headers.extend(extra.iter().cloned());
+    let authorization = match &creds.auth {
+        Auth::SharedKey(secret) => {
+            let to_sign = string_to_sign(method.as_str(), body_len, &headers, &creds.account, url.path());
+            let mut m = HmacSha256::new_from_slice(secret).expect("hmac accepts any key length");
+            m.update(to_sign.as_bytes());
+            let signature = base64::engine::general_purpose::STANDARD.encode(m.finalize().into_bytes());
+            Some(format!("SharedKey {}:{}", creds.account, signature))
+        }
+        Auth::Sas(sas) => { url.set_query(Some(sas)); None }
+    };
+    let mut rb = client.request(method, url);
+    if let Some(a) = authorization { rb = rb.header("Authorization", a); }
+    for (k, v) in headers { rb = rb.header(k, v); }
+    Ok(rb)
+}
+
+pub async fn put_blob(client: &reqwest::Client, loc: &AzureLocation, key: &str, body: Vec<u8>) -> Result<()> {
+    let rb = signed_request(client, reqwest::Method::PUT, loc, key, body.len(), &[("x-ms-blob-type", "BlockBlob".to_string())])?;
+    let resp = rb.body(body).send().await?;
+    if !resp.status().is_success() {
+        bail!("Azure PUT az://{}/{} failed: {} {}", loc.container, key, resp.status(), resp.text().await.unwrap_or_default());
+    }
+    Ok(())
+}
+
+pub async fn get_blob(client: &reqwest::Client, loc: &AzureLocation, key: &str) -> Result<Vec<u8>> {
+    let rb = signed_request(client, reqwest::Method::GET, loc, key, 0, &[])?;
+    let resp = rb.send().await?;
+    if !resp.status().is_success() {
+        bail!("Azure GET az://{}/{} failed: {}", loc.container, key, resp.status());
+    }
+    Ok(resp.bytes().await?.to_vec())
+}
@@ -0,0 +1,247 @@
+//! Database backup and point-in-time restore.
+//!
+//! A backup is a directory holding `backup.json` (the manifest) and `files/`, a copy
+//! of everything under `<root>/<db>`: table chunks and schema.json, CDC logs, KV store
+//! snapshots and per-schema scripts. The manifest records size and xxh3 hash for
+//! every file and, for time-table chunks, the `[min, max]` time range and the write
+//! time taken from the chunk name (`data-<min>-<max>-<ts>.parquet`).
+//!
+//! Restore copies the files into a staging folder under `.system` and renames it into
+//! place, so a failed restore never leaves a half-written database behind. With
+//! `as_of`, chunks written after that instant (and their bloom, checksum and tombstone
+//! sidecars) are left out, tombstones recorded after it are dropped so rows deleted
+//! later come back, and CDC logs are truncated to events at or before it,
+//! reconstructing the append-only time tables as they were at that time.
+//!
+//! Chunks are matched by write time only: a chunk rewritten after `as_of` (`COMPACT
+//! TABLE`, a DELETE past the tombstone threshold, a late-data merge) gets a new name
+//! and write time, and the chunks it replaced are gone, so its rows are missing from
+//! a point-in-time restore before the rewrite. Rewritten chunks note the write time of
+//! the oldest chunk they replaced in their checksum sidecar, the manifest carries it as
+//! `replaced_since`, and restore reports such chunks in `rewritten_chunks` and logs a
+//! warning. Restore without `as_of`, or take the backup before compacting, when that
+//! history matters.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use super::io::{parse_chunk_min_max, parse_chunk_written_at};
+use super::tombstone::Tombstones;
+use super::Store;
+
+pub const MANIFEST_FILE: &str = "backup.json";
+pub const FILES_DIR: &str = "files";
+const BACKUP_FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// Path relative to the database folder, `/`-separated.
+    pub path: String,
+    pub size: u64,
+    pub hash: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_time: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_time: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub written_at: Option<i64>,
+    /// For a chunk that replaced older ones, the write time of the oldest of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_since: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: u32,
+    pub database: String,
+    pub created_at: i64,
+    pub clarium_version: String,
+    pub files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RestoreReport {
+    pub files: usize,
+    pub skipped_chunks: usize,
+    pub bytes: u64,
+    /// Skipped chunks that replaced chunks written at or before `as_of`; their rows are missing.
+    pub rewritten_chunks: Vec<String>,
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// Parse an `AS OF` value: epoch milliseconds or an RFC 3339 timestamp.
+pub fn parse_as_of(s: &str) -> Result<i64> {
+    let t = s.trim().trim_matches('\'');
+    if let Ok(ms) = t.parse::<i64>() { return Ok(ms); }
+    Ok(chrono::DateTime::parse_from_rfc3339(t)
+        .map_err(|_| anyhow!("AS OF expects epoch milliseconds or an RFC 3339 timestamp, got '{}'", t))?
+        .timestamp_millis())
+}
+
+fn collect_files(base: &Path, dir: &Path, out: &mut Vec<(PathBuf, String)>) -> Result<()> {
+    for ent in fs::read_dir(dir)?.flatten() {
+        let p = ent.path();
+        let ft = ent.file_type()?;
+        if ft.is_dir() { collect_files(base, &p, out)?; continue; }
+        if !ft.is_file() { continue; }
+        let name = ent.file_name().to_string_lossy().to_string();
+        // In-flight temp files from atomic writes are not part of a consistent snapshot
+        if name.ends_with(".tmp") { continue; }
+        let rel = p.strip_prefix(base)?.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>().join("/");
+        out.push((p, rel));
+    }
+    Ok(())
+}
+
+/// Path of the chunk a bloom, checksum or tombstone sidecar belongs to; None for other files.
+fn sidecar_chunk(rel: &str) -> Option<&str> {
+    [".bloom", ".crc", ".tomb"].iter().find_map(|s| rel.strip_suffix(s))
+}
+
+fn chunk_name(rel: &str) -> &str {
+    rel.rsplit('/').next().unwrap_or(rel)
+}
+
+/// Keep only tombstones recorded at or before `as_of`; None when none remain.
+fn truncate_tombstones(bytes: &[u8], path: &str, as_of: i64) -> Result<Option<Vec<u8>>> {
+    let t: Tombstones = serde_json::from_slice(bytes).with_context(|| format!("corrupt tombstone sidecar in backup: {}", path))?;
+    let kept = t.as_of(as_of);
+    if kept.rows.is_empty() { return Ok(None); }
+    Ok(Some(serde_json::to_vec(&kept)?))
+}
+
+/// Keep only changelog lines with `ts <= as_of`; sealed lines stay sealed (see `storage::cdc`).
+fn truncate_cdc_log(bytes: &[u8], as_of: i64) -> Result<Vec<u8>> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut out = String::new();
+    for line in text.lines() {
+        let keep = super::cdc::open_line(line)?.map(|ev| ev.ts <= as_of).unwrap_or(false);
+        if keep { out.push_str(line); out.push('\n'); }
+    }
+    Ok(out.into_bytes())
+}
+
+impl Store {
+    /// Copy `<root>/<database>` into `dest` (which must be empty) and write its manifest.
+    /// Callers hold the store lock so no writer can interleave with the copy.
+    pub fn backup_database(&self, database: &str, dest: &Path) -> Result<BackupManifest> {
+        let src = super::attach::database_dir(&self.root, database);
+        if !src.is_dir() { bail!("Database not found: {}", database); }
+        if dest.exists() && fs::read_dir(dest)?.next().is_some() {
+            bail!("backup target is not empty: {}", dest.display());
+        }
+        let mut files = Vec::new();
+        collect_files(&src, &src, &mut files)?;
+        let mut entries = Vec::with_capacity(files.len());
+        for (p, rel) in files {
+            let bytes = fs::read(&p).with_context(|| format!("reading {}", p.display()))?;
+            let target = dest.join(FILES_DIR).join(&rel);
+            if let Some(parent) = target.parent() { fs::create_dir_all(parent)?; }
+            fs::write(&target, &bytes)?;
+            let name = chunk_name(&rel);
+            let (min_time, max_time) = parse_chunk_min_max(name).map(|(a, b)| (Some(a), Some(b))).unwrap_or((None, None));
+            let written_at = parse_chunk_written_at(name);
+            entries.push(BackupFile {
+                path: rel.clone(),
+                size: bytes.len() as u64,
+                hash: xxh3_64(&bytes),
+                min_time,
+                max_time,
+                written_at,
+                replaced_since: super::checksum::written_since(&p).filter(|s| Some(*s) != written_at),
+            });
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let manifest = BackupManifest {
+            format: BACKUP_FORMAT,
+            database: database.to_string(),
+            created_at: now_ms(),
+            clarium_version: env!("CARGO_PKG_VERSION").to_string(),
+            files: entries,
+        };
+        fs::create_dir_all(dest)?;
+        fs::write(dest.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+        crate::tprintln!("[storage.backup] database='{}' files={} -> {}", database, manifest.files.len(), dest.display());
+        Ok(manifest)
+    }
+
+    /// Restore a backup into `database`, which must not exist yet. `read` loads a backup
+    /// file by its path relative to the backup folder; every file is hash-checked.
+    pub fn restore_database_with<F>(&self, manifest: &BackupManifest, database: &str, as_of: Option<i64>, mut read: F) -> Result<RestoreReport>
+    where
+        F: FnMut(&str) -> Result<Vec<u8>>,
+    {
+        if manifest.format > BACKUP_FORMAT {
+            bail!("backup format {} is newer than this build supports ({})", manifest.format, BACKUP_FORMAT);
+        }
+        let dest = super::attach::database_dir(&self.root, database);
+        if dest.exists() { bail!("Database already exists: {} (drop it or restore under another name)", database); }
+        let staging = crate::system_paths::system_root(&self.root).join(format!("restore-{}", uuid::Uuid::new_v4()));
+        let result = (|| -> Result<RestoreReport> {
+            let mut report = RestoreReport::default();
+            let late: HashSet<&str> = manifest.files.iter()
+                .filter(|f| matches!((as_of, f.written_at), (Some(cut), Some(w)) if w > cut))
+                .map(|f| f.path.as_str())
+                .collect();
+            for f in &manifest.files {
+                if late.contains(f.path.as_str()) {
+                    report.skipped_chunks += 1;
+                    if matches!((as_of, f.replaced_since), (Some(cut), Some(s)) if s <= cut) { report.rewritten_chunks.push(f.path.clone()); }
+                    continue;
+                }
+                // Sidecars go with their chunk
+                if sidecar_chunk(&f.path).is_some_and(|c| late.contains(c)) { continue; }
+                let mut bytes = read(&format!("{}/{}", FILES_DIR, f.path))?;
+                if xxh3_64(&bytes) != f.hash { bail!("backup file is corrupt (hash mismatch): {}", f.path); }
+                if let Some(cut) = as_of {
+                    if f.path.ends_with("_cdc/changes.ndjson") { bytes = truncate_cdc_log(&bytes, cut)?; }
+                    if f.path.ends_with(".tomb") {
+                        match truncate_tombstones(&bytes, &f.path, cut)? {
+                            Some(b) => bytes = b,
+                            None => continue,
+                        }
+                    }
+                }
+                let target = staging.join(&f.path);
+                if let Some(parent) = target.parent() { fs::create_dir_all(parent)?; }
+                fs::write(&target, &bytes)?;
+                report.files += 1;
+                report.bytes += bytes.len() as u64;
+            }
+            fs::create_dir_all(&staging)?;
+            if let Some(parent) = dest.parent() { fs::create_dir_all(parent)?; }
+            fs::rename(&staging, &dest)?;
+            Ok(report)
+        })();
+        if result.is_err() { let _ = fs::remove_dir_all(&staging); }
+        let report = result?;
+        if !report.rewritten_chunks.is_empty() {
+            tracing::warn!(target: "clarium::backup", "restore of '{}' as of {:?} left out chunks rewritten after it, rows written before it are missing: {}",
+                manifest.database, as_of, report.rewritten_chunks.join(", "));
+        }
+        crate::tprintln!("[storage.backup] restored '{}' as '{}' files={} skipped_chunks={}", manifest.database, database, report.files, report.skipped_chunks);
+        Ok(report)
+    }
+
+    /// Restore from a local backup folder.
+    pub fn restore_database(&self, src: &Path, database: &str, as_of: Option<i64>) -> Result<RestoreReport> {
+        let manifest = read_manifest(src)?;
+        self.restore_database_with(&manifest, database, as_of, |rel| Ok(fs::read(src.join(rel))?))
+    }
+}
+
+pub fn read_manifest(src: &Path) -> Result<BackupManifest> {
+    let p = src.join(MANIFEST_FILE);
+    let text = fs::read_to_string(&p).with_context(|| format!("no backup manifest at {}", p.display()))?;
+    Ok(serde_json::from_str(&text)?)
+}
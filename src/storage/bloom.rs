@@ -0,0 +1,159 @@
+//! Per-chunk bloom filters for high-cardinality string columns.
+//!
+//! Columns listed under `bloomColumns` in a table's schema.json get a compact
+//! bloom filter built for every Parquet chunk at write time. Filters for all
+//! enabled columns of a chunk live in a single sidecar file next to it
+//! (`data-<min>-<max>-<ts>.parquet.bloom`). During reads, an equality predicate
+//! on a bloom column lets us skip chunks that definitely do not contain the value
+//! without opening the Parquet file at all.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64_with_seed;
+
+use super::Store;
+
+/// Target false-positive rate used when sizing a new filter.
+const TARGET_FPP: f64 = 0.01;
+/// Upper bound on hash functions; keeps probing cheap for tiny chunks.
+const MAX_HASHES: u32 = 8;
+
+/// A classic bit-array bloom filter using double hashing over xxh3.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    k: u32,
+}
+
+impl BloomFilter {
+    /// Size a filter for `expected` distinct items at the default false-positive rate.
+    pub fn with_capacity(expected: usize) -> Self {
+        let n = expected.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let m_bits = ((-n * TARGET_FPP.ln()) / (ln2 * ln2)).ceil().max(64.0) as usize;
+        let words = m_bits.div_ceil(64);
+        let k = (((words * 64) as f64 / n) * ln2).round().clamp(1.0, MAX_HASHES as f64) as u32;
+        Self { bits: vec![0u64; words], k }
+    }
+
+    fn probes(&self, value: &str) -> impl Iterator<Item = usize> + '_ {
+        let h1 = xxh3_64_with_seed(value.as_bytes(), 0);
+        let h2 = xxh3_64_with_seed(value.as_bytes(), 0x9E37_79B9_7F4A_7C15) | 1;
+        let m = (self.bits.len() * 64) as u64;
+        (0..self.k as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+
+    pub fn insert(&mut self, value: &str) {
+        let idxs: Vec<usize> = self.probes(value).collect();
+        for i in idxs { self.bits[i / 64] |= 1u64 << (i % 64); }
+    }
+
+    /// Returns false only when `value` was definitely never inserted.
+    pub fn may_contain(&self, value: &str) -> bool {
+        if self.bits.is_empty() { return true; }
+        self.probes(value).all(|i| self.bits[i / 64] & (1u64 << (i % 64)) != 0)
+    }
+}
+
+/// All filters for one Parquet chunk, keyed by column name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChunkBlooms {
+    pub columns: HashMap<String, BloomFilter>,
+}
+
+/// Sidecar path for a given Parquet chunk.
+pub(crate) fn sidecar_path(chunk: &Path) -> PathBuf {
+    let mut s = chunk.as_os_str().to_owned();
+    s.push(".bloom");
+    PathBuf::from(s)
+}
+
+/// Build filters for the requested columns of `df`. Non-string columns are
+/// skipped; nulls are never inserted, so `col = NULL` never prunes anything.
+pub(crate) fn build_for_df(df: &DataFrame, columns: &[String]) -> ChunkBlooms {
+    let mut out = ChunkBlooms::default();
+    for name in columns {
+        let Ok(c) = df.column(name.as_str()) else { continue };
+        let Ok(ca) = c.str() else { continue };
+        let mut bf = BloomFilter::with_capacity(ca.len() - ca.null_count());
+        for v in ca.into_iter().flatten() { bf.insert(v); }
+        out.columns.insert(name.clone(), bf);
+    }
+    out
+}
+
+/// Write (or remove, when nothing applies) the sidecar for a freshly written chunk.
+pub(crate) fn write_sidecar(chunk: &Path, df: &DataFrame, columns: &[String]) -> Result<()> {
+    let side = sidecar_path(chunk);
+    if columns.is_empty() {
+        if side.exists() { let _ = fs::remove_file(&side); }
+        return Ok(());
+    }
+    let blooms = build_for_df(df, columns);
+    if blooms.columns.is_empty() {
+        if side.exists() { let _ = fs::remove_file(&side); }
+        return Ok(());
+    }
+    fs::write(&side, bincode::serialize(&blooms)?)?;
+    Ok(())
+}
+
+pub(crate) fn read_sidecar(chunk: &Path) -> Option<ChunkBlooms> {
+    let bytes = fs::read(sidecar_path(chunk)).ok()?;
+    bincode::deserialize::<ChunkBlooms>(&bytes).ok()
+}
+
+/// True when the chunk's filters prove that at least one equality predicate
+/// cannot match. Missing sidecars or unfiltered columns never prune.
+pub(crate) fn chunk_excluded(chunk: &Path, eq_preds: &[(String, String)]) -> bool {
+    if eq_preds.is_empty() { return false; }
+    let Some(blooms) = read_sidecar(chunk) else { return false };
+    eq_preds.iter().any(|(c, v)| {
+        blooms.columns.get(c).map(|bf| !bf.may_contain(v)).unwrap_or(false)
+    })
+}
+
+pub(crate) fn get_bloom_columns(store: &Store, table: &str) -> Vec<String> {
+    let p = store.schema_path(table);
+    if !p.exists() { return Vec::new(); }
+    if let Ok(text) = fs::read_to_string(&p) {
+        if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
+            if let Some(arr) = v.get("bloomColumns").and_then(|x| x.as_array()) {
+                return arr.iter().filter_map(|e| e.as_str().map(|s| s.to_string())).collect();
+            }
+        }
+    }
+    Vec::new()
+}
+
+impl Store {
+    /// Columns with per-chunk bloom filters enabled (schema.json `bloomColumns`).
+    pub fn get_bloom_columns(&self, table: &str) -> Vec<String> { get_bloom_columns(self, table) }
+
+    /// Rebuild bloom sidecars for every existing chunk of `table` using the
+    /// currently configured `bloomColumns`. Used after ALTER TABLE ... SET BLOOM.
+    /// Returns the number of chunks processed.
+    pub fn rebuild_bloom_filters(&self, table: &str) -> Result<usize> {
+        let cols = self.get_bloom_columns(table);
+        let dir = self.db_dir(table);
+        if !dir.exists() { return Ok(0); }
+        let mut n = 0usize;
+        for p in super::partition::chunk_files(&dir) {
+            if cols.is_empty() {
+                let side = sidecar_path(&p);
+                if side.exists() { let _ = fs::remove_file(&side); }
+            } else {
+                let df = super::encryption::read_parquet(&p)?;
+                write_sidecar(&p, &df, &cols)?;
+            }
+            n += 1;
+        }
+        crate::tprintln!("[storage.bloom] rebuilt filters for '{}' chunks={} cols={:?}", table, n, cols);
+        Ok(n)
+    }
+}
@@ -0,0 +1,113 @@
+//! Object comments set with `COMMENT ON`.
+//!
+//! Table and column comments live in the table's schema.json under `comments`
+//! (`{"table": "...", "columns": {"<col>": "..."}}`), so they follow the table through
+//! renames and disappear with it. View comments are kept in the `.view` file. Lua
+//! functions are registered by bare name, so their comments are kept server-wide in
+//! `<root>/.system/function_comments.json`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+use super::Store;
+
+/// schema.json key holding table and column comments.
+pub const COMMENTS_KEY: &str = "comments";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableComments {
+    pub table: Option<String>,
+    pub columns: BTreeMap<String, String>,
+}
+
+/// Comments recorded for the table stored in `table_dir`.
+pub fn table_comments(table_dir: &Path) -> TableComments {
+    let v: Option<Value> = fs::read(table_dir.join("schema.json")).ok().and_then(|b| serde_json::from_slice(&b).ok());
+    let Some(c) = v.as_ref().and_then(|v| v.get(COMMENTS_KEY)) else { return TableComments::default() };
+    TableComments {
+        table: c.get("table").and_then(|t| t.as_str()).map(|s| s.to_string()),
+        columns: c.get("columns").and_then(|m| m.as_object())
+            .map(|m| m.iter().filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string()))).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Move a column comment to its new name after `ALTER TABLE ... RENAME COLUMN`.
+pub(crate) fn rename_column_comment(schema_obj: &mut Map<String, Value>, from: &str, to: &str) {
+    let Some(cols) = schema_obj.get_mut(COMMENTS_KEY).and_then(|c| c.get_mut("columns")).and_then(|m| m.as_object_mut()) else { return };
+    if let Some(v) = cols.remove(from) { cols.insert(to.to_string(), v); }
+}
+
+impl Store {
+    /// Set (Some) or remove (None) the comment of `table`, or of one of its columns.
+    pub fn set_comment(&self, table: &str, column: Option<&str>, comment: Option<&str>) -> Result<()> {
+        let p = self.schema_path(table);
+        if !p.exists() { bail!("relation \"{}\" does not exist", table); }
+        let mut obj: Map<String, Value> = serde_json::from_slice::<Value>(&fs::read(&p)?)?.as_object().cloned().unwrap_or_default();
+        let comments = obj.entry(COMMENTS_KEY).or_insert_with(|| Value::Object(Map::new()));
+        if !comments.is_object() { *comments = Value::Object(Map::new()); }
+        let comments = comments.as_object_mut().expect("comments is an object");
+        match column {
+            None => match comment {
+                Some(c) => { comments.insert("table".into(), Value::String(c.to_string())); }
+                None => { comments.remove("table"); }
+            },
+            Some(col) => {
+                let cols = comments.entry("columns").or_insert_with(|| Value::Object(Map::new()));
+                if !cols.is_object() { *cols = Value::Object(Map::new()); }
+                let cols = cols.as_object_mut().expect("columns is an object");
+                match comment {
+                    Some(c) => { cols.insert(col.to_string(), Value::String(c.to_string())); }
+                    None => { cols.remove(col); }
+                }
+                if cols.is_empty() { comments.remove("columns"); }
+            }
+        }
+        if comments.is_empty() { obj.remove(COMMENTS_KEY); }
+        fs::write(&p, serde_json::to_string_pretty(&Value::Object(obj))?)?;
+        Ok(())
+    }
+}
+
+fn function_comments_path(root: &Path) -> PathBuf { crate::system_paths::system_root(root).join("function_comments.json") }
+
+/// Comments of Lua functions, by registered function name.
+pub fn function_comments(root: &Path) -> BTreeMap<String, String> {
+    fs::read(function_comments_path(root)).ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default()
+}
+
+/// Set (Some) or remove (None) the comment of the Lua function `name`.
+pub fn set_function_comment(root: &Path, name: &str, comment: Option<&str>) -> Result<()> {
+    let mut all = function_comments(root);
+    let key = name.to_ascii_lowercase();
+    match comment {
+        Some(c) => { all.insert(key, c.to_string()); }
+        None => {
+            if all.remove(&key).is_none() { return Ok(()); }
+        }
+    }
+    write_function_comments(root, &all)
+}
+
+/// Carry a function comment over to the new name after `RENAME SCRIPT`.
+pub fn rename_function_comment(root: &Path, from: &str, to: &str) -> Result<()> {
+    let mut all = function_comments(root);
+    let Some(c) = all.remove(&from.to_ascii_lowercase()) else { return Ok(()) };
+    all.insert(to.to_ascii_lowercase(), c);
+    write_function_comments(root, &all)
+}
+
+fn write_function_comments(root: &Path, all: &BTreeMap<String, String>) -> Result<()> {
+    let p = function_comments_path(root);
+    if let Some(parent) = p.parent() { fs::create_dir_all(parent)?; }
+    let tmp = p.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec_pretty(all)?)?;
+    fs::rename(&tmp, &p)?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Table compaction: merge all chunks of a table into one sorted chunk.
+//!
+//! `COMPACT TABLE` reads every chunk (without tombstoned rows), sorts time tables by `_time`, removes
+//! duplicate keys when the table has a dedup mode set, and rewrites the table
+//! through `rewrite_table_df` (which also rebuilds bloom and checksum sidecars, drops
+//! tombstone sidecars and honours encryption). Changes are not recorded in the CDC log: the logical
+//! contents only lose duplicates.
+
+use anyhow::{bail, Result};
+use polars::prelude::*;
+use serde::Serialize;
+
+use super::dedup::DedupMode;
+use super::Store;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompactReport {
+    pub chunks_before: usize,
+    pub chunks_after: usize,
+    pub rows: usize,
+    pub duplicates_dropped: usize,
+}
+
+fn count_chunks(dir: &std::path::Path) -> usize { super::partition::chunk_files(dir).len() }
+
+impl Store {
+    /// Merge the chunks of `table` into one, dropping duplicates when dedup is enabled.
+    pub fn compact_table(&self, table: &str) -> Result<CompactReport> {
+        let dir = self.db_dir(table);
+        if !dir.is_dir() { bail!("Table not found: {}", table); }
+        let chunks_before = count_chunks(&dir);
+        if chunks_before == 0 { return Ok(CompactReport::default()); }
+        let is_time = self.is_time_table(table);
+        let mut df = self.read_df(table)?;
+        if is_time {
+            df = df.sort(["_time"], SortMultipleOptions::default().with_maintain_order(true))?;
+        }
+        let mut dropped = 0usize;
+        if is_time && self.get_dedup_mode(table) != DedupMode::Off {
+            let (out, n) = self.dedup_frame(table, df)?;
+            df = out;
+            dropped = n;
+        }
+        let rows = df.height();
+        self.rewrite_table_df(table, df)?;
+        if dropped > 0 {
+            self.update_ingest_stats(table, |s| s.duplicates_dropped += dropped as u64)?;
+        }
+        let report = CompactReport { chunks_before, chunks_after: count_chunks(&dir), rows, duplicates_dropped: dropped };
+        crate::tprintln!("[storage.compact] table='{}' {:?}", table, report);
+        Ok(report)
+    }
+}
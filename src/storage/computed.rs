@@ -0,0 +1,111 @@
+//! Computed columns: values derived from each row when it is written.
+//!
+//! `ALTER TABLE ... ADD COMPUTED COLUMN <name> AS <expr>` keeps the definition in schema.json
+//! as `"computed": [{"name": "...", "expr": "..."}]`, in declaration order. `write_records`
+//! evaluates every definition over the incoming batch and stores the results like any other
+//! column, so queries read them instead of recomputing them. A definition may use the row's
+//! columns, earlier computed columns, built-in functions and Lua scalar UDFs; values a batch
+//! carries for a computed column are replaced.
+//!
+//! A column's type is the declared one when the table has it, otherwise the type of the first
+//! non-null result. Adding a definition computes it for the rows already stored.
+//!
+//! Expressions are compiled by the query engine through `StorageHooks::computed_expr`
+//! (see `storage::hooks`); without it registered, writes to such tables fail.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+pub const COMPUTED_KEY: &str = "computed";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComputedColumn {
+    pub name: String,
+    /// Expression text as written after `AS`.
+    pub expr: String,
+}
+
+/// Definitions of `obj` (a schema.json root) with `def` added, replacing one of the same name.
+pub fn with_definition(obj: &serde_json::Map<String, serde_json::Value>, def: ComputedColumn) -> serde_json::Value {
+    let mut defs = definitions_of(obj);
+    defs.retain(|d| d.name != def.name);
+    defs.push(def);
+    serde_json::to_value(defs).unwrap_or_default()
+}
+
+fn definitions_of(obj: &serde_json::Map<String, serde_json::Value>) -> Vec<ComputedColumn> {
+    obj.get(COMPUTED_KEY).cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn get_computed(store: &Store, table: &str) -> Vec<ComputedColumn> {
+    std::fs::read_to_string(store.schema_path(table)).ok()
+        .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+        .and_then(|v| v.as_object().map(definitions_of))
+        .unwrap_or_default()
+}
+
+/// `df` with the column `def` computes, cast to the type `schema` has for it. A column without
+/// a type yet takes the type of the results and `schema` records it.
+fn compute(df: DataFrame, def: &ComputedColumn, schema: &mut HashMap<String, DataType>) -> Result<DataFrame> {
+    let fail = |e: &dyn std::fmt::Display| anyhow!("computed column '{}' ({}): {}", def.name, def.expr, e);
+    let built = super::hooks::get().computed_expr(&def.expr).map_err(|e| fail(&e))?;
+    // Columns the table has but the batch does not carry read as null
+    let mut frame = df.clone();
+    for (name, dt) in schema.iter() {
+        if frame.column(name).is_err() { frame.with_column(Column::full_null(name.as_str().into(), df.height(), dt))?; }
+    }
+    let out = frame.lazy().select([built.alias(def.name.as_str())]).collect().map_err(|e| fail(&e))?;
+    let mut vals = out.column(&def.name)?.clone();
+    // A constant expression yields one value for the whole batch
+    if vals.len() == 1 && df.height() != 1 { vals = vals.new_from_index(0, df.height()); }
+    let target = match schema.get(&def.name) {
+        Some(dt) if *dt != DataType::Null => dt.clone(),
+        _ => super::schema::str_to_dtype(&super::schema::dtype_to_str(vals.dtype())),
+    };
+    let vals = match &target {
+        DataType::Boolean => super::native::cast_bool_column(&vals)?,
+        DataType::Datetime(..) => super::native::cast_timestamp_column(&vals)?,
+        DataType::Decimal(..) => {
+            let (p, s) = super::decimal::precision_scale(&target).ok_or_else(|| anyhow!("bad decimal type for {}", def.name))?;
+            super::decimal::cast_column(&vals, p, s)?
+        }
+        dt => vals.cast(dt)?,
+    };
+    schema.insert(def.name.clone(), target);
+    let mut df = df;
+    df.with_column(vals.take_materialized_series())?;
+    Ok(df)
+}
+
+impl Store {
+    /// Add the computed columns of `table` to a batch about to be written (see module docs).
+    pub(crate) fn apply_computed_columns(&self, table: &str, df: DataFrame, schema: &mut HashMap<String, DataType>) -> Result<DataFrame> {
+        let defs = get_computed(self, table);
+        if defs.is_empty() { return Ok(df); }
+        let mut df = df;
+        for def in &defs { df = compute(df, def, schema)?; }
+        Ok(df)
+    }
+
+    /// Compute the column `name` for the rows already stored in `table`; returns the row count.
+    pub fn backfill_computed_column(&self, table: &str, name: &str) -> Result<usize> {
+        let Some(def) = get_computed(self, table).into_iter().find(|d| d.name == name) else { return Ok(0) };
+        let df = self.read_df(table)?;
+        if df.height() == 0 { return Ok(0); }
+        let (mut schema, _) = self.load_schema_with_locks(table)?;
+        let mut df = compute(df, &def, &mut schema)?;
+        if self.is_time_table(table) {
+            df = df.sort(["_time"], SortMultipleOptions::default().with_maintain_order(true))?;
+        }
+        let n = df.height();
+        self.rewrite_table_df(table, df)?;
+        Ok(n)
+    }
+}
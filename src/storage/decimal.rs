@@ -0,0 +1,261 @@
+//! NUMERIC / DECIMAL columns.
+//!
+//! A `numeric(p, s)` column is stored as a Decimal128 (polars `Decimal(p, s)`): an i128 holding
+//! the value times 10^s. schema.json spells the type `"decimal(p,s)"`; a bare `NUMERIC` or
+//! `DECIMAL` is `decimal(38,9)`.
+//!
+//! Incoming values are parsed from their text, never through f64, and rounded half away from
+//! zero to the column scale. A value with more integer digits than `p - s` is rejected with
+//! PostgreSQL's `numeric field overflow`.
+//!
+//! Arithmetic between a decimal and a decimal or integer stays exact (`binop`): sums keep the
+//! larger scale, products add the scales, quotients keep the larger scale but at least
+//! `MIN_DIV_SCALE` digits. A float operand turns the result into a float, as before.
+
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+
+/// Most digits a Decimal128 holds.
+pub const MAX_PRECISION: usize = 38;
+
+/// Precision and scale of a bare `NUMERIC` / `DECIMAL`.
+pub const DEFAULT_PRECISION: usize = 38;
+pub const DEFAULT_SCALE: usize = 9;
+
+/// Fewest fractional digits a quotient keeps, so `1 / 3` is not 0.
+pub const MIN_DIV_SCALE: usize = 6;
+
+/// Integer operands take part in decimal arithmetic as `numeric(19, 0)`.
+const INT_DIGITS: usize = 19;
+
+pub fn dtype(precision: usize, scale: usize) -> DataType {
+    DataType::Decimal(Some(precision), Some(scale))
+}
+
+/// Precision and scale of a decimal type; None for any other type.
+pub fn precision_scale(dt: &DataType) -> Option<(usize, usize)> {
+    match dt {
+        DataType::Decimal(p, s) => Some((p.unwrap_or(MAX_PRECISION), s.unwrap_or(0))),
+        _ => None,
+    }
+}
+
+/// schema.json spelling of `numeric(p, s)`.
+pub fn type_key(precision: usize, scale: usize) -> String {
+    format!("decimal({},{})", precision, scale)
+}
+
+/// Parse the leading type of a SQL column type or schema.json entry: `numeric`, `decimal`,
+/// `numeric(p)` or `numeric(p, s)`. Ok(None) when it is not a decimal type.
+pub fn parse_type(ty: &str) -> Result<Option<(usize, usize)>> {
+    let t = ty.trim().to_ascii_lowercase();
+    let base_end = t.find(|c: char| c == '(' || c.is_whitespace()).unwrap_or(t.len());
+    if !matches!(&t[..base_end], "numeric" | "decimal") { return Ok(None); }
+    let Some(args) = t[base_end..].trim_start().strip_prefix('(') else {
+        return Ok(Some((DEFAULT_PRECISION, DEFAULT_SCALE)));
+    };
+    let args = &args[..args.find(')').ok_or_else(|| anyhow!("invalid NUMERIC type modifier: {}", ty.trim()))?];
+    let nums = args.split(',').map(|a| a.trim().parse::<usize>()).collect::<Result<Vec<_>, _>>()
+        .map_err(|_| anyhow!("invalid NUMERIC type modifier: {}", ty.trim()))?;
+    let (p, s) = match nums.as_slice() {
+        [p] => (*p, 0),
+        [p, s] => (*p, *s),
+        _ => return Err(anyhow!("invalid NUMERIC type modifier: {}", ty.trim())),
+    };
+    if p == 0 || p > MAX_PRECISION {
+        return Err(anyhow!("NUMERIC precision {} must be between 1 and {}", p, MAX_PRECISION));
+    }
+    if s > p {
+        return Err(anyhow!("NUMERIC scale {} must be between 0 and precision {}", s, p));
+    }
+    Ok(Some((p, s)))
+}
+
+/// Common type of two decimal types: room for the integer digits of either and the larger scale.
+pub fn widen(a: (usize, usize), b: (usize, usize)) -> DataType {
+    let scale = a.1.max(b.1);
+    let int_digits = (a.0 - a.1).max(b.0 - b.1);
+    dtype((int_digits + scale).min(MAX_PRECISION), scale)
+}
+
+/// Parse a number (`-12.5`, `.25`, `1e3`) into its value at `scale`, rounding half away from
+/// zero. None when the text is not a number or does not fit in an i128.
+pub fn parse_value(text: &str, scale: usize) -> Option<i128> {
+    let t = text.trim();
+    let (neg, t) = match t.as_bytes().first() {
+        Some(b'-') => (true, &t[1..]),
+        Some(b'+') => (false, &t[1..]),
+        _ => (false, t),
+    };
+    let (mantissa, exp) = match t.find(['e', 'E']) {
+        Some(i) => (&t[..i], t[i + 1..].parse::<i64>().ok()?),
+        None => (t, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() && frac.is_empty() { return None; }
+    if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) { return None; }
+    let all: String = int.chars().chain(frac.chars()).collect();
+    let digits = all.trim_start_matches('0');
+    if digits.is_empty() { return Some(0); }
+    // value = digits * 10^(exp - frac.len()), wanted at 10^-scale
+    let shift = exp + scale as i64 - frac.len() as i64;
+    let v: i128 = if shift >= 0 {
+        digits.parse::<i128>().ok()?.checked_mul(10i128.checked_pow(u32::try_from(shift).ok()?)?)?
+    } else {
+        let cut = usize::try_from(-shift).ok()?;
+        let keep = digits.len().saturating_sub(cut);
+        let kept: i128 = if keep == 0 { 0 } else { digits[..keep].parse().ok()? };
+        // The first dropped digit decides the rounding; it is an implied 0 when all digits are dropped with room to spare
+        let round_up = digits.len() >= cut && digits.as_bytes()[keep] >= b'5';
+        if round_up { kept.checked_add(1)? } else { kept }
+    };
+    Some(if neg { -v } else { v })
+}
+
+/// Text of a value stored at `scale`, with exactly `scale` fractional digits.
+pub fn format_value(v: i128, scale: usize) -> String {
+    let digits = v.unsigned_abs().to_string();
+    let sign = if v < 0 { "-" } else { "" };
+    if scale == 0 { return format!("{}{}", sign, digits); }
+    let padded = format!("{:0>width$}", digits, width = scale + 1);
+    let (int, frac) = padded.split_at(padded.len() - scale);
+    format!("{}{}.{}", sign, int, frac)
+}
+
+/// `v` at scale `from` expressed at scale `to`, rounding half away from zero when digits are dropped.
+pub fn rescale(v: i128, from: usize, to: usize) -> Option<i128> {
+    if to >= from { return v.checked_mul(10i128.checked_pow((to - from) as u32)?); }
+    div_round(v, 10i128.checked_pow((from - to) as u32)?)
+}
+
+/// `n / d` rounded half away from zero; None for a zero divisor.
+fn div_round(n: i128, d: i128) -> Option<i128> {
+    if d == 0 { return None; }
+    let (q, r) = (n / d, n % d);
+    if r.unsigned_abs() * 2 >= d.unsigned_abs() {
+        Some(if (n < 0) != (d < 0) { q - 1 } else { q + 1 })
+    } else {
+        Some(q)
+    }
+}
+
+/// True when `v` has at most `precision` digits.
+pub fn fits(v: i128, precision: usize) -> bool {
+    precision >= MAX_PRECISION || v.unsigned_abs() < 10u128.pow(precision as u32)
+}
+
+/// A JSON record value at `scale`: numbers and numeric strings are parsed from their text.
+pub fn json_value(v: &serde_json::Value, scale: usize) -> Option<i128> {
+    match v {
+        serde_json::Value::Number(n) => parse_value(&n.to_string(), scale),
+        serde_json::Value::String(s) => parse_value(s, scale),
+        _ => None,
+    }
+}
+
+/// Build a `numeric(p, s)` series from values already at scale `s`, failing when one has
+/// more digits than the precision allows.
+pub fn series(name: &str, values: Vec<Option<i128>>, precision: usize, scale: usize) -> Result<Series> {
+    if let Some(v) = values.iter().flatten().find(|v| !fits(**v, precision)) {
+        return Err(anyhow!(
+            "numeric field overflow: {} does not fit numeric({}, {}), which must round to an absolute value less than 10^{}",
+            format_value(*v, scale), precision, scale, precision - scale
+        ));
+    }
+    Ok(Int128Chunked::from_iter_options(name.into(), values.into_iter())
+        .into_decimal_unchecked(Some(precision), scale)
+        .into_series())
+}
+
+/// `c` as `numeric(p, s)` (`CAST(x AS numeric(p, s))`); see `scaled_values` for how each input
+/// type converts.
+pub fn cast_column(c: &Column, precision: usize, scale: usize) -> PolarsResult<Column> {
+    let values = scaled_values(c, scale)?;
+    series(c.name().as_str(), values, precision, scale)
+        .map(|s| s.into_column())
+        .map_err(|e| polars_err!(ComputeError: "{}", e))
+}
+
+/// Arithmetic operators with exact decimal results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op { Add, Sub, Mul, Div }
+
+fn operand(dt: &DataType) -> Option<(usize, usize)> {
+    if dt.is_integer() { Some((INT_DIGITS, 0)) } else { precision_scale(dt) }
+}
+
+/// Type of `l op r` when the result stays exact: one side decimal, the other decimal or
+/// integer. None when neither side is decimal or a float side makes the result a float.
+pub fn arith_dtype(l: &DataType, r: &DataType, op: Op) -> Option<DataType> {
+    if precision_scale(l).is_none() && precision_scale(r).is_none() { return None; }
+    let ((lp, ls), (rp, rs)) = (operand(l)?, operand(r)?);
+    Some(match op {
+        Op::Add | Op::Sub => {
+            let scale = ls.max(rs);
+            dtype(((lp - ls).max(rp - rs) + 1 + scale).min(MAX_PRECISION), scale)
+        }
+        Op::Mul => dtype((lp + rp).min(MAX_PRECISION), (ls + rs).min(MAX_PRECISION)),
+        Op::Div => dtype(MAX_PRECISION, ls.max(rs).max(MIN_DIV_SCALE)),
+    })
+}
+
+/// Values of `c` at `scale`. Decimal and integer columns convert exactly; floats and strings
+/// go through their text, so a float literal such as `1.1` means exactly 1.1.
+fn scaled_values(c: &Column, scale: usize) -> PolarsResult<Vec<Option<i128>>> {
+    let s = c.as_materialized_series();
+    let overflow = || polars_err!(ComputeError: "numeric field overflow");
+    match s.dtype() {
+        DataType::Decimal(_, from) => {
+            let from = from.unwrap_or(0);
+            s.decimal()?.physical().iter()
+                .map(|v| v.map(|v| rescale(v, from, scale).ok_or_else(overflow)).transpose())
+                .collect()
+        }
+        dt if dt.is_integer() => s.cast(&DataType::Int64)?.i64()?.iter()
+            .map(|v| v.map(|v| rescale(v as i128, 0, scale).ok_or_else(overflow)).transpose())
+            .collect(),
+        dt if dt.is_float() => Ok(s.cast(&DataType::Float64)?.f64()?.iter()
+            .map(|v| v.and_then(|f| parse_value(&f.to_string(), scale)))
+            .collect()),
+        _ => Ok(s.cast(&DataType::String)?.str()?.iter()
+            .map(|v| v.and_then(|t| parse_value(t, scale)))
+            .collect()),
+    }
+}
+
+/// Exact `l op r` for operands of type `l_dt` / `r_dt` (see `arith_dtype`). A length-1 side
+/// is broadcast. Fails on division by zero and on results beyond 38 digits.
+pub fn binop(l: &Column, r: &Column, l_dt: &DataType, r_dt: &DataType, op: Op) -> PolarsResult<Column> {
+    let out = arith_dtype(l_dt, r_dt, op).ok_or_else(|| polars_err!(ComputeError: "not a decimal operation: {} {:?} {}", l_dt, op, r_dt))?;
+    let (precision, scale) = precision_scale(&out).unwrap_or((MAX_PRECISION, 0));
+    let ((_, ls), (_, rs)) = (operand(l_dt).unwrap_or((0, 0)), operand(r_dt).unwrap_or((0, 0)));
+    let (lv, rv) = (scaled_values(l, ls)?, scaled_values(r, rs)?);
+    let n = match (lv.len(), rv.len()) {
+        (a, b) if a == b => a,
+        (1, b) => b,
+        (a, 1) => a,
+        (a, b) => polars_bail!(ShapeMismatch: "decimal operands have lengths {} and {}", a, b),
+    };
+    let at = |v: &[Option<i128>], i: usize| if v.len() == 1 { v[0] } else { v[i] };
+    let overflow = || polars_err!(ComputeError: "numeric field overflow");
+    let mut values: Vec<Option<i128>> = Vec::with_capacity(n);
+    for i in 0..n {
+        let (Some(a), Some(b)) = (at(&lv, i), at(&rv, i)) else { values.push(None); continue };
+        let v = match op {
+            Op::Add => rescale(a, ls, scale).zip(rescale(b, rs, scale)).and_then(|(a, b)| a.checked_add(b)),
+            Op::Sub => rescale(a, ls, scale).zip(rescale(b, rs, scale)).and_then(|(a, b)| a.checked_sub(b)),
+            Op::Mul => a.checked_mul(b).and_then(|p| rescale(p, ls + rs, scale)),
+            Op::Div => {
+                if b == 0 { polars_bail!(ComputeError: "division by zero"); }
+                // a / 10^ls ÷ b / 10^rs at 10^-scale = a * 10^(scale + rs - ls) / b
+                rescale(a, ls, scale + rs).and_then(|a| div_round(a, b))
+            }
+        }.ok_or_else(overflow)?;
+        if !fits(v, precision) { return Err(overflow()); }
+        values.push(Some(v));
+    }
+    Ok(Int128Chunked::from_iter_options(l.name().clone(), values.into_iter())
+        .into_decimal_unchecked(Some(precision), scale)
+        .into_series()
+        .into_column())
+}
@@ -0,0 +1,121 @@
+//! Duplicate suppression for time tables keyed on `_time` + primary key.
+//!
+//! Re-sent sensor batches otherwise produce duplicate rows. A table opts in with
+//! `"dedup": {"mode": "write"}` or `{"mode": "compact"}` in schema.json (set by
+//! `ALTER TABLE ... SET DEDUP ON WRITE | ON COMPACT | OFF`):
+//!
+//! - `write`: each incoming batch is checked against itself and against the rows
+//!   of existing chunks whose time range overlaps it; rows whose key is already
+//!   present are dropped before the chunk is written (first write wins).
+//! - `compact`: writes are untouched and duplicates are removed by `COMPACT TABLE`.
+//!
+//! The key is `_time` plus the table's primary key columns, or the whole row when
+//! no primary key is declared. Dropped rows are counted in the table's ingest stats.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+pub const DEDUP_KEY: &str = "dedup";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupMode {
+    #[default]
+    Off,
+    Write,
+    Compact,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DedupSetting {
+    mode: DedupMode,
+}
+
+/// Schema.json value for `mode` (`None` removes the setting).
+pub fn setting_value(mode: DedupMode) -> Option<serde_json::Value> {
+    match mode {
+        DedupMode::Off => None,
+        m => serde_json::to_value(DedupSetting { mode: m }).ok(),
+    }
+}
+
+pub(crate) fn get_dedup_mode(store: &Store, table: &str) -> DedupMode {
+    std::fs::read_to_string(store.schema_path(table)).ok()
+        .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+        .and_then(|v| v.get(DEDUP_KEY).cloned())
+        .and_then(|v| serde_json::from_value::<DedupSetting>(v).ok())
+        .map(|s| s.mode)
+        .unwrap_or_default()
+}
+
+/// One string per row identifying it by `cols` (unit separator between values).
+fn row_keys(df: &DataFrame, cols: &[String]) -> Result<Vec<String>> {
+    let columns: Vec<&Column> = cols.iter().map(|c| df.column(c)).collect::<PolarsResult<_>>()?;
+    let mut out = Vec::with_capacity(df.height());
+    for i in 0..df.height() {
+        let mut k = String::new();
+        for c in &columns {
+            k.push_str(&c.get(i)?.to_string());
+            k.push('\u{1f}');
+        }
+        out.push(k);
+    }
+    Ok(out)
+}
+
+/// Remove rows of `df` whose key is in `seen` or repeats earlier in `df`; returns the dropped count.
+fn drop_seen(df: DataFrame, cols: &[String], seen: &mut HashSet<String>) -> Result<(DataFrame, usize)> {
+    let keys = row_keys(&df, cols)?;
+    let mask: Vec<bool> = keys.into_iter().map(|k| seen.insert(k)).collect();
+    let dropped = mask.iter().filter(|keep| !**keep).count();
+    if dropped == 0 { return Ok((df, 0)); }
+    let out = df.filter(&BooleanChunked::from_slice("keep".into(), &mask))?;
+    Ok((out, dropped))
+}
+
+impl Store {
+    /// Dedup mode configured for `table`.
+    pub fn get_dedup_mode(&self, table: &str) -> DedupMode { get_dedup_mode(self, table) }
+
+    /// Key columns for `table`'s dedup: `_time` plus the primary key, or every column of `df`.
+    fn dedup_key_columns(&self, table: &str, df: &DataFrame) -> Vec<String> {
+        match self.get_primary_key(table) {
+            Some(pk) => {
+                let mut cols = vec!["_time".to_string()];
+                cols.extend(pk.into_iter().filter(|c| c != "_time" && df.get_column_names().iter().any(|n| n.as_str() == c)));
+                cols
+            }
+            None => df.get_column_names().iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Drop rows of an incoming time-table batch that duplicate each other or existing rows.
+    pub(crate) fn dedup_incoming(&self, table: &str, df: DataFrame) -> Result<(DataFrame, usize)> {
+        if df.height() == 0 { return Ok((df, 0)); }
+        let keys = self.dedup_key_columns(table, &df);
+        let times = df.column("_time")?.i64()?;
+        let (lo, hi) = (times.min(), times.max());
+        let mut seen: HashSet<String> = HashSet::new();
+        let data_cols: Vec<String> = keys.iter().filter(|c| c.as_str() != "_time").cloned().collect();
+        let existing = self.filter_df(table, &data_cols, lo, hi)?;
+        if existing.height() > 0 {
+            // Older chunks may lack a column the batch has; such rows cannot match
+            if keys.iter().all(|c| existing.get_column_names().iter().any(|n| n.as_str() == c)) {
+                let existing = existing.select(&keys)?;
+                seen.extend(row_keys(&existing, &keys)?);
+            }
+        }
+        drop_seen(df, &keys, &mut seen)
+    }
+
+    /// Remove duplicate rows from a whole-table frame (used by compaction).
+    pub(crate) fn dedup_frame(&self, table: &str, df: DataFrame) -> Result<(DataFrame, usize)> {
+        let keys = self.dedup_key_columns(table, &df);
+        drop_seen(df, &keys, &mut HashSet::new())
+    }
+}
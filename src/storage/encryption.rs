@@ -0,0 +1,297 @@
+//! Encryption at rest for Parquet chunks and KV store files (AES-256-GCM).
+//!
+//! A table opts in through schema.json:
+//!
+//! ```json
+//! "encryption": { "algorithm": "aes-256-gcm", "key_id": "k2" }
+//! ```
+//!
+//! `key_id` may be omitted to always use the active key. Encryption is per table:
+//! each chunk is sealed as a whole, so every column is covered and there is no
+//! per-column setting; a spec with any other field is rejected. A table whose
+//! `encryption` setting cannot be parsed refuses writes rather than storing
+//! plaintext. Encrypted files start with
+//! a small header naming the key they were sealed with, so readers pick the right
+//! key transparently and tables can hold chunks sealed with different keys while a
+//! rotation is in progress. `REENCRYPT TABLE` rewrites every chunk with the key the
+//! table is configured for (or back to plaintext when encryption was switched off).
+//! KV stores opt in through `encryption` in their store settings; their snapshots
+//! and Parquet values are sealed the same way on every save.
+//!
+//! Sidecars of an encrypted table that hold column values are sealed with the
+//! table's key too: each CDC changelog line (`storage::cdc`) and the ANALYZE
+//! statistics in stats.json (`storage::analyze`), whose bounds and histograms are
+//! sampled values.
+//!
+//! Key material is loaded on first use and on `REENCRYPT`:
+//! - `CLARIUM_ENCRYPTION_KEYS`: `id:base64key[,id:base64key...]` (32-byte keys)
+//! - `CLARIUM_KMS_KEY_COMMAND`: a shell command printing the same list (one entry
+//!   per line or comma-separated), e.g. a wrapper around a cloud KMS decrypt call
+//! - `CLARIUM_ENCRYPTION_ACTIVE_KEY`: key used for new writes (default: last listed)
+//!
+//! Checksums and bloom sidecars are computed over what is on disk; bloom filters
+//! only hold hashed values.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use polars::prelude::*;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+pub const ENCRYPTION_KEY: &str = "encryption";
+const MAGIC: &[u8; 8] = b"CLRMENC1";
+const ALGORITHM: &str = "aes-256-gcm";
+
+/// Per-table (schema.json) or per-store (store settings) encryption setting.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionSpec {
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+    /// Key to seal with; `None` uses the active key at write time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
+fn default_algorithm() -> String { ALGORITHM.to_string() }
+
+impl EncryptionSpec {
+    pub fn new(key_id: Option<String>) -> Self { Self { algorithm: ALGORITHM.to_string(), key_id } }
+}
+
+struct Keyring {
+    keys: HashMap<String, [u8; 32]>,
+    active: Option<String>,
+}
+
+static KEYRING: Lazy<RwLock<Option<Arc<Keyring>>>> = Lazy::new(|| RwLock::new(None));
+
+fn parse_key_list(text: &str, keys: &mut HashMap<String, [u8; 32]>, order: &mut Vec<String>) -> Result<()> {
+    for item in text.split([',', '\n']).map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let (id, b64) = item.split_once(':').ok_or_else(|| anyhow!("encryption key entry must be id:base64key"))?;
+        let raw = base64::engine::general_purpose::STANDARD.decode(b64.trim())
+            .with_context(|| format!("encryption key '{}' is not valid base64", id))?;
+        let key: [u8; 32] = raw.try_into().map_err(|_| anyhow!("encryption key '{}' must be 32 bytes", id))?;
+        keys.insert(id.trim().to_string(), key);
+        order.push(id.trim().to_string());
+    }
+    Ok(())
+}
+
+fn load_keyring() -> Result<Keyring> {
+    let mut keys = HashMap::new();
+    let mut order = Vec::new();
+    if let Ok(list) = std::env::var("CLARIUM_ENCRYPTION_KEYS") {
+        parse_key_list(&list, &mut keys, &mut order)?;
+    }
+    if let Some(cmd) = std::env::var("CLARIUM_KMS_KEY_COMMAND").ok().filter(|s| !s.trim().is_empty()) {
+        let out = if cfg!(windows) {
+            std::process::Command::new("cmd").args(["/C", &cmd]).output()
+        } else {
+            std::process::Command::new("sh").args(["-c", &cmd]).output()
+        }.context("running CLARIUM_KMS_KEY_COMMAND")?;
+        if !out.status.success() { bail!("CLARIUM_KMS_KEY_COMMAND failed with {}", out.status); }
+        parse_key_list(&String::from_utf8_lossy(&out.stdout), &mut keys, &mut order)?;
+    }
+    let active = std::env::var("CLARIUM_ENCRYPTION_ACTIVE_KEY").ok().filter(|s| !s.is_empty()).or_else(|| order.last().cloned());
+    if let Some(a) = &active {
+        if !keys.contains_key(a) { bail!("active encryption key '{}' is not among the loaded keys", a); }
+    }
+    Ok(Keyring { keys, active })
+}
+
+fn keyring() -> Result<Arc<Keyring>> {
+    if let Some(k) = KEYRING.read().as_ref() { return Ok(k.clone()); }
+    let k = Arc::new(load_keyring()?);
+    *KEYRING.write() = Some(k.clone());
+    Ok(k)
+}
+
+/// Drop cached key material so the next use reloads it (after adding or rotating keys).
+pub fn reload_keys() -> Result<()> {
+    let k = Arc::new(load_keyring()?);
+    crate::tprintln!("[storage.encryption] loaded {} key(s), active={:?}", k.keys.len(), k.active);
+    *KEYRING.write() = Some(k);
+    Ok(())
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey> {
+    Ok(LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow!("invalid AES-256 key"))?))
+}
+
+fn header(key_id: &str) -> Result<Vec<u8>> {
+    if key_id.is_empty() || key_id.len() > u8::MAX as usize { bail!("encryption key id must be 1..=255 bytes"); }
+    let mut h = Vec::with_capacity(MAGIC.len() + 1 + key_id.len());
+    h.extend_from_slice(MAGIC);
+    h.push(key_id.len() as u8);
+    h.extend_from_slice(key_id.as_bytes());
+    Ok(h)
+}
+
+pub fn is_encrypted(bytes: &[u8]) -> bool { bytes.starts_with(MAGIC) }
+
+/// Key id an encrypted buffer was sealed with.
+pub fn key_id_of(bytes: &[u8]) -> Option<String> {
+    if !is_encrypted(bytes) { return None; }
+    let n = *bytes.get(MAGIC.len())? as usize;
+    let id = bytes.get(MAGIC.len() + 1..MAGIC.len() + 1 + n)?;
+    Some(String::from_utf8_lossy(id).to_string())
+}
+
+/// Key id `spec` seals with right now; errors when it is not loaded.
+pub fn resolve_key_id(spec: &EncryptionSpec) -> Result<String> {
+    if !spec.algorithm.eq_ignore_ascii_case(ALGORITHM) { bail!("unsupported encryption algorithm: {}", spec.algorithm); }
+    let ring = keyring()?;
+    let key_id = spec.key_id.clone().or_else(|| ring.active.clone())
+        .ok_or_else(|| anyhow!("encryption is enabled but no keys are configured (set CLARIUM_ENCRYPTION_KEYS or CLARIUM_KMS_KEY_COMMAND)"))?;
+    if !ring.keys.contains_key(&key_id) { bail!("unknown encryption key: {}", key_id); }
+    Ok(key_id)
+}
+
+/// Seal `plain` with the key named by `spec` (or the active key).
+pub fn encrypt(plain: &[u8], spec: &EncryptionSpec) -> Result<Vec<u8>> {
+    let key_id = resolve_key_id(spec)?;
+    let ring = keyring()?;
+    let key = ring.keys.get(&key_id).ok_or_else(|| anyhow!("unknown encryption key: {}", key_id))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).map_err(|_| anyhow!("failed to generate nonce"))?;
+    let mut out = header(&key_id)?;
+    let mut buf = plain.to_vec();
+    cipher(key)?.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(&out), &mut buf)
+        .map_err(|_| anyhow!("encryption failed"))?;
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&buf);
+    Ok(out)
+}
+
+/// Open a buffer written by [`encrypt`]; plaintext buffers are returned unchanged.
+pub fn decrypt(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_encrypted(&bytes) { return Ok(bytes); }
+    let key_id = key_id_of(&bytes).ok_or_else(|| anyhow!("truncated encryption header"))?;
+    let hlen = MAGIC.len() + 1 + key_id.len();
+    if bytes.len() < hlen + NONCE_LEN { bail!("truncated encrypted file"); }
+    let ring = keyring()?;
+    let key = ring.keys.get(&key_id).ok_or_else(|| anyhow!("encryption key '{}' is not loaded", key_id))?;
+    let nonce: [u8; NONCE_LEN] = bytes[hlen..hlen + NONCE_LEN].try_into()?;
+    let mut buf = bytes[hlen + NONCE_LEN..].to_vec();
+    let plain = cipher(key)?.open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(&bytes[..hlen]), &mut buf)
+        .map_err(|_| anyhow!("decryption failed (wrong key or damaged file)"))?;
+    Ok(plain.to_vec())
+}
+
+/// Read a Parquet file, decrypting it first when it is sealed.
+pub fn read_parquet(path: &Path) -> Result<DataFrame> {
+    let mut f = fs::File::open(path)?;
+    let mut magic = [0u8; 8];
+    let sealed = std::io::Read::read_exact(&mut f, &mut magic).is_ok() && &magic == MAGIC;
+    if !sealed {
+        return Ok(ParquetReader::new(fs::File::open(path)?).finish()?);
+    }
+    let plain = decrypt(fs::read(path)?).with_context(|| format!("decrypting {}", path.display()))?;
+    Ok(ParquetReader::new(std::io::Cursor::new(plain)).finish()?)
+}
+
+/// Row count from a Parquet file's footer, without reading its columns.
+pub fn parquet_num_rows(path: &Path) -> Result<usize> {
+    let mut f = fs::File::open(path)?;
+    let mut magic = [0u8; 8];
+    let sealed = std::io::Read::read_exact(&mut f, &mut magic).is_ok() && &magic == MAGIC;
+    if !sealed {
+        return Ok(ParquetReader::new(fs::File::open(path)?).num_rows()?);
+    }
+    let plain = decrypt(fs::read(path)?).with_context(|| format!("decrypting {}", path.display()))?;
+    Ok(ParquetReader::new(std::io::Cursor::new(plain)).num_rows()?)
+}
+
+/// Row count and compression codec (of the first column chunk, e.g. `zstd`) from a Parquet
+/// file's footer.
+pub fn parquet_footer(path: &Path) -> Result<(usize, Option<String>)> {
+    fn summary(meta: &FileMetadata) -> (usize, Option<String>) {
+        let codec = meta.row_groups.first()
+            .and_then(|rg| rg.parquet_columns().first())
+            .map(|c| format!("{:?}", c.compression()).to_lowercase());
+        (meta.num_rows, codec)
+    }
+    let mut f = fs::File::open(path)?;
+    let mut magic = [0u8; 8];
+    let sealed = std::io::Read::read_exact(&mut f, &mut magic).is_ok() && &magic == MAGIC;
+    if !sealed {
+        return Ok(summary(ParquetReader::new(fs::File::open(path)?).get_metadata()?));
+    }
+    let plain = decrypt(fs::read(path)?).with_context(|| format!("decrypting {}", path.display()))?;
+    Ok(summary(ParquetReader::new(std::io::Cursor::new(plain)).get_metadata()?))
+}
+
+/// Write `df` as Parquet (with statistics), sealed when `spec` is set.
+pub fn write_parquet(path: &Path, df: &mut DataFrame, spec: Option<&EncryptionSpec>) -> Result<()> {
+    match spec {
+        None => {
+            let mut file = fs::File::create(path)?;
+            ParquetWriter::new(&mut file).with_statistics(StatisticsOptions::default()).finish(df)?;
+        }
+        Some(spec) => {
+            let mut buf: Vec<u8> = Vec::new();
+            ParquetWriter::new(&mut buf).with_statistics(StatisticsOptions::default()).finish(df)?;
+            fs::write(path, encrypt(&buf, spec)?)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn get_table_encryption(store: &Store, table: &str) -> Result<Option<EncryptionSpec>> {
+    let p = store.schema_path(table);
+    let text = match fs::read_to_string(&p) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", p.display())),
+    };
+    let v: serde_json::Value = serde_json::from_str(&text).with_context(|| format!("corrupt schema {}", p.display()))?;
+    match v.get(ENCRYPTION_KEY) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(e) => Ok(Some(serde_json::from_value(e.clone())
+            .with_context(|| format!("invalid encryption setting for table {}", table))?)),
+    }
+}
+
+impl Store {
+    /// Encryption configured for `table` (schema.json `encryption`), if any.
+    pub fn get_table_encryption(&self, table: &str) -> Result<Option<EncryptionSpec>> { get_table_encryption(self, table) }
+
+    /// Rewrite every chunk of `table` to match its current encryption setting:
+    /// sealed with the configured (or active) key, or plaintext when encryption is off.
+    /// Used for key rotation and after ALTER TABLE ... SET ENCRYPTION. stats.json is
+    /// resealed the same way. Returns the number of chunks rewritten.
+    pub fn reencrypt_table(&self, table: &str) -> Result<usize> {
+        let spec = self.get_table_encryption(table)?;
+        let dir = self.db_dir(table);
+        if !dir.is_dir() { bail!("Table not found: {}", table); }
+        let mut n = 0usize;
+        for p in super::partition::chunk_files(&dir) {
+            let bytes = fs::read(&p)?;
+            let plain = decrypt(bytes).with_context(|| format!("decrypting {}", p.display()))?;
+            let out = match &spec { Some(s) => encrypt(&plain, s)?, None => plain };
+            let tmp = p.with_extension("parquet.tmp");
+            fs::write(&tmp, &out)?;
+            fs::rename(&tmp, &p)?;
+            super::checksum::record(&p)?;
+            n += 1;
+        }
+        let stats = super::analyze::stats_path(&dir);
+        if let Ok(bytes) = fs::read(&stats) {
+            let plain = decrypt(bytes).with_context(|| format!("decrypting {}", stats.display()))?;
+            fs::write(&stats, match &spec { Some(s) => encrypt(&plain, s)?, None => plain })?;
+        }
+        crate::tprintln!("[storage.encryption] re-encrypted '{}' chunks={} key={:?}", table, n, spec.as_ref().map(|s| s.key_id.clone()));
+        Ok(n)
+    }
+}
@@ -0,0 +1,52 @@
+//! Callbacks from storage into the layers built on it.
+//!
+//! Storage does not depend on the server. Work the engine hangs off storage events
+//! (maintaining vector indexes when rows change, delivering KV store webhooks, counting
+//! blocks read per database, charging rows scanned to quotas, evaluating computed
+//! columns) goes through `StorageHooks`, which the server registers once at startup
+//! (`server::storage_hooks`). Until then, and in tools that only open a store, the hooks
+//! do nothing, except that computed columns cannot be evaluated.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use polars::prelude::*;
+
+use super::cdc::ChangeOp;
+use super::webhooks::Delivery;
+use super::Store;
+
+pub trait StorageHooks: Send + Sync {
+    /// Rows of `table` were written, updated or deleted (`df` holds them). Called before
+    /// CDC and triggers see the rows; must not fail the write.
+    fn rows_changed(&self, _store: &Store, _table: &str, _op: ChangeOp, _df: &DataFrame) {}
+
+    /// `chunks` Parquet chunks of `table` are about to be read.
+    fn blocks_read(&self, _table: &str, _chunks: usize) {}
+
+    /// `rows` rows were read from storage; an error (a quota ran out) fails the read.
+    fn rows_scanned(&self, _rows: usize) -> Result<()> { Ok(()) }
+
+    /// Compile the text of a computed column expression (see `storage::computed`) into a
+    /// polars expression over the batch's columns.
+    fn computed_expr(&self, _text: &str) -> Result<Expr> {
+        Err(anyhow!("computed columns need the query engine, which is not loaded"))
+    }
+
+    /// Queue a webhook POST for a KV store event (see `storage::kv_events`); never blocks
+    /// on the request.
+    fn deliver_webhook(&self, _delivery: Delivery) {}
+}
+
+struct NoHooks;
+impl StorageHooks for NoHooks {}
+
+static HOOKS: Lazy<RwLock<Arc<dyn StorageHooks>>> = Lazy::new(|| RwLock::new(Arc::new(NoHooks)));
+
+/// Install the hooks storage calls from now on, replacing earlier ones.
+pub fn register(hooks: Arc<dyn StorageHooks>) { *HOOKS.write() = hooks; }
+
+/// The registered hooks.
+pub(crate) fn get() -> Arc<dyn StorageHooks> { HOOKS.read().clone() }
@@ -0,0 +1,148 @@
+//! How ingestion reconciles a batch with the column types already in schema.json.
+//!
+//! Set per table with `ALTER TABLE ... SET INGEST MODE STRICT | WIDEN | COERCE` and kept
+//! as `"ingestMode": "strict"` in schema.json (no key means `widen`):
+//!
+//! - `widen`: a column widens to hold the batch (int64 → float64 → string, see
+//!   `schema::merge_dtype`). One stray string turns a numeric column into text for good.
+//! - `strict`: column types never change once set. A batch holding a value that does not
+//!   fit its column is rejected as a whole, with one line per offending column.
+//! - `coerce`: column types never change once set; values that do not fit are stored as
+//!   null and counted in the table's ingest stats (`values_coerced`).
+//!
+//! In every mode new columns, and columns that have only held nulls, take their type from
+//! the batch. Locked columns keep their type whatever the mode.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Record, Store};
+
+pub const INGEST_MODE_KEY: &str = "ingestMode";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IngestMode {
+    #[default]
+    Widen,
+    Strict,
+    Coerce,
+}
+
+/// Schema.json value for `mode` (`None` removes the setting).
+pub fn setting_value(mode: IngestMode) -> Option<Value> {
+    match mode {
+        IngestMode::Widen => None,
+        m => serde_json::to_value(m).ok(),
+    }
+}
+
+pub(crate) fn get_ingest_mode(store: &Store, table: &str) -> IngestMode {
+    std::fs::read_to_string(store.schema_path(table)).ok()
+        .and_then(|t| serde_json::from_str::<Value>(&t).ok())
+        .and_then(|v| v.get(INGEST_MODE_KEY).cloned())
+        .and_then(|v| serde_json::from_value::<IngestMode>(v).ok())
+        .unwrap_or_default()
+}
+
+/// Whether `text` reads as a list whose elements all parse with `parse`.
+fn text_list_fits<T, F: Fn(&str) -> Option<T>>(text: &str, parse: F) -> bool {
+    let parts: Vec<&str> = text.trim().trim_start_matches('[').trim_end_matches(']')
+        .split(',').map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+    !parts.is_empty() && parts.iter().all(|p| parse(p).is_some())
+}
+
+/// Whether `v` is stored as a value, not a null, in a column of type `dt`. Mirrors the
+/// conversions `write_records` applies; JSON null fits every type.
+pub fn value_fits(v: &Value, dt: &DataType) -> bool {
+    if v.is_null() { return true; }
+    match dt {
+        DataType::Null => true,
+        DataType::String => matches!(v, Value::String(_) | Value::Number(_)),
+        DataType::Int64 => match v {
+            Value::Number(n) => n.as_i64().is_some(),
+            Value::String(s) => s.parse::<i64>().is_ok(),
+            _ => false,
+        },
+        DataType::Decimal(..) => {
+            let scale = super::decimal::precision_scale(dt).map(|(_, s)| s).unwrap_or(0);
+            super::decimal::json_value(v, scale).is_some()
+        }
+        DataType::Boolean => super::native::json_bool(v).is_some(),
+        DataType::Datetime(..) => super::native::json_timestamp_ms(v).is_some(),
+        DataType::List(inner) => match (v, inner.as_ref()) {
+            (Value::Array(a), DataType::Float64) => a.iter().all(|e| e.as_f64().is_some() || e.as_str().map(|s| s.trim().parse::<f64>().is_ok()).unwrap_or(false)),
+            (Value::Array(a), DataType::Int64) => a.iter().all(|e| e.as_i64().is_some() || e.as_str().map(|s| s.trim().parse::<i64>().is_ok()).unwrap_or(false)),
+            (Value::Array(_), DataType::String) => true,
+            (Value::String(s), DataType::Float64) => text_list_fits(s, |p| p.parse::<f64>().ok()),
+            (Value::String(s), DataType::Int64) => text_list_fits(s, |p| p.parse::<i64>().ok()),
+            (Value::String(s), DataType::String) => text_list_fits(s, |p| Some(p)),
+            _ => false,
+        },
+        _ => match v {
+            Value::Number(_) => true,
+            Value::String(s) => s.parse::<f64>().is_ok(),
+            _ => false,
+        },
+    }
+}
+
+/// Values of one column that do not fit its type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMismatch {
+    pub column: String,
+    /// Schema.json type key of the column.
+    pub type_key: String,
+    pub bad: usize,
+    pub total: usize,
+    /// First offending value as JSON, and its row in the batch.
+    pub example: String,
+    pub row: usize,
+}
+
+/// Columns of `schema` that `records` hold values for which do not fit their type, in
+/// column order. Columns without a type yet (absent or `null`) are skipped.
+pub fn find_mismatches(records: &[Record], schema: &HashMap<String, DataType>, columns: &[String]) -> Vec<ColumnMismatch> {
+    let mut out = Vec::new();
+    for name in columns {
+        let Some(dt) = schema.get(name) else { continue };
+        if matches!(dt, DataType::Null) { continue; }
+        let mut found: Option<ColumnMismatch> = None;
+        for (row, r) in records.iter().enumerate() {
+            let Some(v) = r.sensors.get(name) else { continue };
+            if value_fits(v, dt) { continue; }
+            match found.as_mut() {
+                Some(m) => m.bad += 1,
+                None => found = Some(ColumnMismatch {
+                    column: name.clone(),
+                    type_key: super::schema::dtype_to_str(dt),
+                    bad: 1,
+                    total: records.len(),
+                    example: v.to_string(),
+                    row,
+                }),
+            }
+        }
+        out.extend(found);
+    }
+    out
+}
+
+/// The error a strict table returns for a batch, one line per column in `mismatches`.
+pub fn strict_error(table: &str, mismatches: &[ColumnMismatch]) -> anyhow::Error {
+    let mut msg = format!(
+        "batch rejected by strict ingest mode for table '{}': {} column(s) do not match the schema",
+        table, mismatches.len()
+    );
+    for m in mismatches {
+        msg.push_str(&format!(
+            "\n  column '{}' ({}): {} of {} value(s) do not fit, first at row {}: {}",
+            m.column, m.type_key, m.bad, m.total, m.row, m.example
+        ));
+    }
+    anyhow!(msg)
+}
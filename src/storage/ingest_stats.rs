@@ -0,0 +1,65 @@
+//! Per-table ingestion counters.
+//!
+//! Counters are kept in `<table_dir>/ingest_stats.json` so they survive restarts
+//! and are only rewritten when something noteworthy happens during a write (rows
+//! dropped as duplicates, late arrivals, coerced values, chunks touched by a DELETE).
+//! `pg_catalog.pg_stat_ingest` reports them per table.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+const STATS_FILE: &str = "ingest_stats.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IngestStats {
+    /// Rows dropped because their `_time` + key already existed.
+    #[serde(default)]
+    pub duplicates_dropped: u64,
+    /// Rows that arrived behind the table's high-water mark (see `storage::late`).
+    #[serde(default)]
+    pub late_records: u64,
+    /// Chunks a DELETE wrote again without the deleted rows.
+    #[serde(default)]
+    pub chunks_rewritten: u64,
+    /// Chunks a DELETE removed because all of their rows went.
+    #[serde(default)]
+    pub chunks_dropped: u64,
+    /// Rows a DELETE hid behind tombstones instead of rewriting their chunk (see `storage::tombstone`).
+    #[serde(default)]
+    pub rows_tombstoned: u64,
+    /// Values a `coerce` table stored as null because they did not fit their column (see `storage::ingest_mode`).
+    #[serde(default)]
+    pub values_coerced: u64,
+    /// Epoch ms of the last update.
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+pub(crate) fn stats_path(table_dir: &Path) -> PathBuf { table_dir.join(STATS_FILE) }
+
+/// Counters recorded for the table stored in `table_dir` (zero when none).
+pub fn read_stats(table_dir: &Path) -> IngestStats {
+    fs::read(stats_path(table_dir)).ok()
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default()
+}
+
+impl Store {
+    /// Ingestion counters of `table`.
+    pub fn ingest_stats(&self, table: &str) -> IngestStats { read_stats(&self.db_dir(table)) }
+
+    /// Apply `f` to the persisted counters of `table`. Callers hold the store lock.
+    pub(crate) fn update_ingest_stats<F: FnOnce(&mut IngestStats)>(&self, table: &str, f: F) -> Result<()> {
+        let dir = self.db_dir(table);
+        let mut st = read_stats(&dir);
+        f(&mut st);
+        st.updated_at = chrono::Utc::now().timestamp_millis();
+        fs::write(stats_path(&dir), serde_json::to_vec_pretty(&st)?)?;
+        Ok(())
+    }
+}
@@ -1,8 +1,7 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use anyhow::Result;
 use polars::prelude::*;
-use polars::prelude::StatisticsOptions;
 
 use super::{Record, Store};
 use crate::tprintln;
@@ -17,10 +16,65 @@ pub(crate) fn parse_chunk_min_max(name: &str) -> Option<(i64, i64)> {
     Some((min_t, max_t))
 }
 
+pub(crate) fn parse_chunk_written_at(name: &str) -> Option<i64> {
+    // Third component of data-<min>-<max>-<ts>.parquet is the write time in epoch ms
+    let base = name.strip_prefix("data-")?.strip_suffix(".parquet")?;
+    base.split('-').nth(2)?.parse::<i64>().ok()
+}
+
+/// Stack frames whose columns may differ, e.g. chunks written before a column was added or
+/// while it held only nulls or narrower values: missing columns become nulls and mismatched
+/// dtypes widen per `merge_dtype`, so IS NULL and aggregates see one column type.
+pub(crate) fn stack_aligned(mut frames: Vec<DataFrame>) -> Result<DataFrame> {
+    if frames.is_empty() { return Ok(DataFrame::empty()); }
+    let first_names = frames[0].get_column_names_owned();
+    let first_types = frames[0].dtypes();
+    if !frames.iter().all(|d| d.get_column_names_owned() == first_names && d.dtypes() == first_types) {
+        let mut fields: Vec<(PlSmallStr, DataType)> = Vec::new();
+        for df in &frames {
+            for c in df.get_columns() {
+                match fields.iter_mut().find(|(n, _)| n == c.name()) {
+                    Some((_, dt)) => *dt = super::schema::merge_dtype(dt.clone(), c.dtype().clone()),
+                    None => fields.push((c.name().clone(), c.dtype().clone())),
+                }
+            }
+        }
+        frames = frames.into_iter().map(|df| {
+            let mut cols: Vec<Column> = Vec::with_capacity(fields.len());
+            for (name, dt) in &fields {
+                cols.push(match df.column(name.as_str()) {
+                    Ok(c) if c.dtype() == dt => c.clone(),
+                    Ok(c) => c.cast(dt)?,
+                    Err(_) => Column::full_null(name.clone(), df.height(), dt),
+                });
+            }
+            Ok(DataFrame::new(cols)?)
+        }).collect::<Result<Vec<_>>>()?;
+    }
+    let mut out = frames.remove(0);
+    for df in frames.into_iter() { out.vstack_mut(&df)?; }
+    Ok(out)
+}
+
 impl Store {
     pub fn filter_df(&self, table: &str, cols: &[String], t0: Option<i64>, t1: Option<i64>) -> Result<DataFrame> {
+        self.filter_df_pruned(table, cols, t0, t1, &[])
+    }
+
+    /// Like `filter_df`, additionally skipping chunks whose bloom filters prove that
+    /// one of the `(column, value)` equality predicates cannot match. Rows are not
+    /// filtered by these predicates here; callers still apply their WHERE clause.
+    pub fn filter_df_pruned(&self, table: &str, cols: &[String], t0: Option<i64>, t1: Option<i64>, eq_preds: &[(String, String)]) -> Result<DataFrame> {
+        self.filter_df_partitioned(table, cols, t0, t1, eq_preds, eq_preds)
+    }
+
+    /// Like `filter_df_pruned`, with separate equality predicates for partition
+    /// pruning (`partition_preds` may also carry numeric literals, which bloom
+    /// filters over string columns cannot use).
+    pub fn filter_df_partitioned(&self, table: &str, cols: &[String], t0: Option<i64>, t1: Option<i64>, eq_preds: &[(String, String)], partition_preds: &[(String, String)]) -> Result<DataFrame> {
         // Opportunistic upgrade for legacy `.time` dirs
         let _ = crate::storage::schema::ensure_time_tabletype_for_legacy_dir(self, table);
+        self.note_read(table);
         let dir = self.db_dir(table);
         let mut wanted: Vec<String> = cols.iter().cloned().collect();
         // Ensure _time present only for time-series tables (metadata-first detection)
@@ -28,28 +82,42 @@ impl Store {
         if is_time_table && !wanted.iter().any(|c| c == "_time") { wanted.insert(0, "_time".into()); }
         let mut dfs: Vec<DataFrame> = Vec::new();
         if dir.exists() {
+            let spec = self.get_partition_spec(table);
             let mut files: Vec<PathBuf> = Vec::new();
-            for entry in fs::read_dir(&dir)? {
-                let p = entry?.path();
+            for p in super::partition::chunk_files(&dir) {
                 if let Some(name) = p.file_name().and_then(|s| s.to_str()) {
-                    if name == "data.parquet" || (name.starts_with("data-") && name.ends_with(".parquet")) {
-                        // If time filter provided and chunk is time-ranged, prune by filename
-                        if name.starts_with("data-") {
-                            if let Some((min_t, max_t)) = parse_chunk_min_max(name) {
-                                if let Some(lo) = t0 { if max_t < lo { continue; } }
-                                if let Some(hi) = t1 { if min_t > hi { continue; } }
+                    // If time filter provided and chunk is time-ranged, prune by filename
+                    if name.starts_with("data-") {
+                        if let Some((min_t, max_t)) = parse_chunk_min_max(name) {
+                            if t0.is_some_and(|lo| max_t < lo) || t1.is_some_and(|hi| min_t > hi) {
+                                super::pruning::record(table, &dir, &p, Some(super::pruning::describe_time_range(min_t, max_t, t0, t1)));
+                                continue;
                             }
                         }
-                        files.push(p);
                     }
+                    if super::partition::partition_excluded(&dir, &p, &spec, partition_preds) {
+                        tprintln!("[storage.filter_df] partition pruned chunk '{}'", p.display());
+                        super::pruning::record(table, &dir, &p, Some(format!("partition excluded by {}", super::pruning::describe_preds(partition_preds))));
+                        continue;
+                    }
+                    if super::bloom::chunk_excluded(&p, eq_preds) {
+                        tprintln!("[storage.filter_df] bloom pruned chunk '{}'", name);
+                        super::pruning::record(table, &dir, &p, Some(format!("bloom filter excludes {}", super::pruning::describe_preds(eq_preds))));
+                        continue;
+                    }
+                    super::pruning::record(table, &dir, &p, None);
+                    files.push(p);
                 }
             }
-            files.sort();
+            // Reported on the enclosing query `scan`/`join` span, if any
+            tracing::Span::current().record("chunks", files.len());
+            super::hooks::get().blocks_read(table, files.len());
             for p in files {
                 // Read available columns from parquet without pre-filtering. We will project
                 // and synthesize missing requested columns after stacking.
-                let reader = ParquetReader::new(std::fs::File::open(&p)?);
-                let mut df = reader.finish()?;
+                let mut df = super::tombstone::read_live_chunk(&p)?;
+                crate::memory::charge_read(df.estimated_size())?;
+                super::hooks::get().rows_scanned(df.height())?;
                 if (t0.is_some() || t1.is_some()) && is_time_table {
                     if df.get_column_names().iter().any(|c| c.as_str() == "_time") {
                         let mut lf = df.lazy();
@@ -96,8 +164,7 @@ impl Store {
             crate::tprintln!("[storage.filter_df] synthesized empty DF for '{}' with cols={:?}", table, cols_out.iter().map(|cl| cl.name().to_string()).collect::<Vec<_>>());
             return Ok(DataFrame::new(cols_out)?);
         }
-        let mut out = dfs.remove(0);
-        for df in dfs.into_iter() { out.vstack_mut(&df)?; }
+        let mut out = stack_aligned(dfs)?;
         // Ensure all requested columns exist; if missing in parquet, synthesize null columns based on schema
         let present: std::collections::HashSet<String> = out
             .get_column_names()
@@ -120,7 +187,7 @@ impl Store {
                     DataType::List(inner) if matches!(**inner, DataType::Int64) => {
                         Series::full_null(w.as_str().into(), out.height(), &DataType::List(Box::new(DataType::Int64))).into()
                     }
-                    _ => Series::new(w.as_str().into(), vec![None::<f64>; out.height()]).into(),
+                    other => Series::full_null(w.as_str().into(), out.height(), other).into(),
                 };
                 to_add.push(s);
             }
@@ -145,23 +212,17 @@ impl Store {
     pub fn read_df(&self, table: &str) -> Result<DataFrame> {
         // Opportunistic upgrade for legacy `.time` dirs
         let _ = crate::storage::schema::ensure_time_tabletype_for_legacy_dir(self, table);
+        self.note_read(table);
         let dir = self.db_dir(table);
         let mut dfs: Vec<DataFrame> = Vec::new();
         if dir.exists() {
-            let mut files: Vec<PathBuf> = Vec::new();
-            for entry in fs::read_dir(&dir)? {
-                let p = entry?.path();
-                if let Some(name) = p.file_name().and_then(|s| s.to_str()) {
-                    if (name.starts_with("data-") && name.ends_with(".parquet")) || name == "data.parquet" {
-                        files.push(p);
-                    }
-                }
-            }
-            files.sort();
+            let files = super::partition::chunk_files(&dir);
+            tracing::Span::current().record("chunks", files.len());
+            super::hooks::get().blocks_read(table, files.len());
             for p in files {
-                let f = std::fs::File::open(&p)?;
-                let reader = ParquetReader::new(f);
-                let df = reader.finish()?;
+                let df = super::tombstone::read_live_chunk(&p)?;
+                crate::memory::charge_read(df.estimated_size())?;
+                super::hooks::get().rows_scanned(df.height())?;
                 dfs.push(df);
             }
         }
@@ -187,14 +248,14 @@ impl Store {
                         let empty: Vec<Option<Series>> = Vec::new();
                         Series::new((&name).into(), empty).into()
                     }
-                    _ => Series::new((&name).into(), Vec::<Option<f64>>::new()).into(),
+                    // NUMERIC, BOOLEAN, TIMESTAMP, ...: empty column of the declared type
+                    other => Series::full_null((&name).into(), 0, &other).into(),
                 };
                 cols.push(s.into());
             }
             return Ok(DataFrame::new(cols)?);
         }
-        let mut out = dfs.remove(0);
-        for df in dfs.into_iter() { out.vstack_mut(&df)?; }
+        let out = stack_aligned(dfs)?;
         // Validate presence of _time for time tables; if missing, emit diagnostic
         if self.is_time_table(table) && !out.get_column_names().iter().any(|c| c.as_str() == "_time") {
             crate::tprintln!("[STORAGE] read_df: time table '{}' missing '_time' column in parquet; data may be legacy or corrupted", table);
@@ -203,8 +264,35 @@ impl Store {
         Ok(out)
     }
 
+    /// Chunk files of `table` in read order, or None when the table does not exist.
+    /// For readers that stream a table one chunk at a time (COPY TO) instead of `read_df`.
+    pub fn chunk_paths(&self, table: &str) -> Option<Vec<PathBuf>> {
+        let _ = crate::storage::schema::ensure_time_tabletype_for_legacy_dir(self, table);
+        let dir = self.db_dir(table);
+        if !dir.exists() { return None; }
+        self.note_read(table);
+        let files = super::partition::chunk_files(&dir);
+        super::hooks::get().blocks_read(table, files.len());
+        Some(files)
+    }
+
+    /// `_time` range encoded in the name of a chunk returned by `chunk_paths`, for time-ranged chunks.
+    pub fn chunk_time_range(&self, path: &Path) -> Option<(i64, i64)> {
+        path.file_name().and_then(|n| n.to_str()).and_then(parse_chunk_min_max)
+    }
+
+    /// Read one chunk returned by `chunk_paths`.
+    pub fn read_chunk(&self, path: &Path) -> Result<DataFrame> {
+        let df = super::tombstone::read_live_chunk(path)?;
+        super::hooks::get().rows_scanned(df.height())?;
+        Ok(df)
+    }
+
     pub fn rewrite_table_df(&self, table: &str, mut df: DataFrame) -> Result<()> {
         let __t0 = std::time::Instant::now();
+        self.conform_declared_columns(table, &mut df)?;
+        // Resolve encryption before any chunk is removed so a bad setting cannot lose data
+        let enc = self.get_table_encryption(table)?;
         // Remove existing parquet files and legacy file, then write df as a single new chunk and update schema
         let dir = self.db_dir(table);
         fs::create_dir_all(&dir).ok();
@@ -217,19 +305,17 @@ impl Store {
         }
         tprintln!("[STORAGE] rewrite_table_df: pre-scan dir='{}' took={:?}", dir.display(), __t_scan_rm0.elapsed());
 
-        // Remove all parquet files
+        // Remove all parquet files, noting how old their data is for point-in-time restore
         let __t_rm = std::time::Instant::now();
+        let replaced_since = if dir.exists() { super::checksum::oldest_write(&super::partition::chunk_files(&dir)) } else { None };
         if dir.exists() {
-            let mut to_remove: Vec<PathBuf> = Vec::new();
-            for entry in fs::read_dir(&dir)? {
-                let p = entry?.path();
-                if let Some(name) = p.file_name().and_then(|s| s.to_str()) {
-                    if (name.starts_with("data-") && name.ends_with(".parquet")) || name == "data.parquet" {
-                        to_remove.push(p);
-                    }
-                }
+            for p in super::partition::chunk_files(&dir) {
+                let _ = fs::remove_file(super::bloom::sidecar_path(&p));
+                let _ = fs::remove_file(super::checksum::sidecar_path(&p));
+                let _ = fs::remove_file(super::tombstone::sidecar_path(&p));
+                let _ = fs::remove_file(&p);
             }
-            for p in to_remove { let _ = fs::remove_file(&p); }
+            super::partition::remove_empty_partition_dirs(&dir);
         }
         tprintln!("[STORAGE] rewrite_table_df: removed old parquet files took={:?}", __t_rm.elapsed());
 
@@ -249,93 +335,28 @@ impl Store {
         for k in existing_locks { if schema.contains_key(&k) { locks.insert(k); } }
         super::schema::save_schema_with_locks(self, table, &schema, &locks)?;
         tprintln!("[STORAGE] rewrite_table_df: update schema took={:?}", __t_schema.elapsed());
-        // For regular tables: if partitions are defined, write partitioned files.
+        let bloom_cols = self.get_bloom_columns(table);
+        // For regular tables: if partitions are defined, write one file per partition folder.
         if !self.is_time_table(table) {
-            // Check for partitions in schema.json
-            let sp = self.schema_path(table);
-            let mut wrote_partitioned = false;
-            if sp.exists() {
-                if let Ok(text) = fs::read_to_string(&sp) {
-                    if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
-                        if let Some(parts_arr) = v.get("partitions").and_then(|x| x.as_array()) {
-                            let partitions: Vec<String> = parts_arr
-                                .iter()
-                                .filter_map(|e| e.as_str().map(|s| s.to_string()))
-                                .collect();
-                            if !partitions.is_empty() {
-                                // Group rows by partition key tuple
-                                use std::collections::HashMap as Map;
-                                let mut groups: Map<String, Vec<usize>> = Map::new();
-                                let n = df.height();
-                                let __t_group = std::time::Instant::now();
-                                let val_to_string = |av: AnyValue| -> String {
-                                    match av {
-                                        AnyValue::String(s) => s.to_string(),
-                                        AnyValue::StringOwned(s) => s.to_string(),
-                                        AnyValue::Int64(i) => i.to_string(),
-                                        AnyValue::Float64(f) => {
-                                            if f.fract() == 0.0 { (f as i64).to_string() } else { f.to_string() }
-                                        }
-                                        _ => av.to_string(),
-                                    }
-                                };
-                                for i in 0..n {
-                                    let mut key_parts: Vec<String> = Vec::with_capacity(partitions.len());
-                                    for pcol in &partitions {
-                                        let s = df.column(pcol).ok();
-                                        let val = s
-                                            .and_then(|ser| ser.get(i).ok())
-                                            .map(|av| val_to_string(av))
-                                            .unwrap_or_else(|| "_NULL".to_string());
-                                        key_parts.push(format!("{}={}", pcol, val));
-                                    }
-                                    let key = key_parts.join("/");
-                                    groups.entry(key).or_default().push(i);
-                                }
-                                tprintln!("[STORAGE] rewrite_table_df: group by partitions took={:?}", __t_group.elapsed());
-                                // Write each group as a parquet file under subdir
-                                let mut parts_written = 0usize;
-                                let __t_write_parts = std::time::Instant::now();
-                                for (_key, idxs) in groups.into_iter() {
-                                    // Take subset rows
-                                    let idx_vec: Vec<u32> = idxs.into_iter().map(|i| i as u32).collect();
-                                    let idx_ca = UInt32Chunked::from_vec("".into(), idx_vec);
-                                    let df_part = df.take(&idx_ca)?;
-                                    // Regular tables may not have _time; handle gracefully
-                                    let (min_t, max_t) = match df_part.column("_time") {
-                                        Ok(c) => {
-                                            let ci = c.i64();
-                                            if let Ok(ci) = ci { (ci.min().unwrap_or(0), ci.max().unwrap_or(0)) } else { (0, 0) }
-                                        }
-                                        Err(_) => (0, 0),
-                                    };
-                                    use std::time::{SystemTime, UNIX_EPOCH};
-                                    let now_ms: u128 = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
-                                    let fname = format!("data-{}-{}-{}.parquet", min_t, max_t, now_ms);
-                                    let path = dir.join(fname);
-                                    let mut file = std::fs::File::create(&path)?;
-                                    ParquetWriter::new(&mut file)
-                                        .with_statistics(StatisticsOptions::default())
-                                        .finish(&mut df_part.clone())?;
-                                    parts_written += 1;
-                                }
-                                tprintln!("[STORAGE] rewrite_table_df: wrote {} partition files took={:?}", parts_written, __t_write_parts.elapsed());
-                                wrote_partitioned = true;
-                            }
-                        }
-                    }
+            if self.get_partition_spec(table).is_partitioned() {
+                let __t_write_parts = std::time::Instant::now();
+                let groups = self.partition_frames(table, &df)?;
+                let parts_written = groups.len();
+                for (pdir, mut df_part) in groups {
+                    fs::create_dir_all(&pdir)?;
+                    let path = pdir.join("data.parquet");
+                    super::encryption::write_parquet(&path, &mut df_part, enc.as_ref())?;
+                    super::bloom::write_sidecar(&path, &df_part, &bloom_cols)?;
+                    super::checksum::record(&path)?;
                 }
-            }
-            if wrote_partitioned {
-                tprintln!("[STORAGE] rewrite_table_df: partitioned total took={:?}", __t0.elapsed());
+                tprintln!("[STORAGE] rewrite_table_df: wrote {} partition files took={:?} total={:?}", parts_written, __t_write_parts.elapsed(), __t0.elapsed());
                 return Ok(());
             } else {
                 let path = self.db_file(table);
                 let __t_write = std::time::Instant::now();
-                let mut file = std::fs::File::create(&path)?;
-                ParquetWriter::new(&mut file)
-                    .with_statistics(StatisticsOptions::default())
-                    .finish(&mut df)?;
+                super::encryption::write_parquet(&path, &mut df, enc.as_ref())?;
+                super::bloom::write_sidecar(&path, &df, &bloom_cols)?;
+                super::checksum::record(&path)?;
                 tprintln!("[STORAGE] rewrite_table_df: wrote single parquet rows={} took={:?} total={:?}", df.height(), __t_write.elapsed(), __t0.elapsed());
                 return Ok(());
             }
@@ -348,25 +369,59 @@ impl Store {
                     df.replace("_time", ser.clone())?;
                 }
             }
-        // Write one parquet chunk
-        let min_t = df.column("_time")?.i64()?.min().unwrap_or(0);
-        let max_t = df.column("_time")?.i64()?.max().unwrap_or(0);
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let now_ms: u128 = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
-        let fname = format!("data-{}-{}-{}.parquet", min_t, max_t, now_ms);
-        let path = dir.join(fname);
+        // Write one parquet chunk (per partition when partitioned)
         let __t_write_ts = std::time::Instant::now();
-        let mut file = std::fs::File::create(&path)?;
-        ParquetWriter::new(&mut file)
-            .with_statistics(StatisticsOptions::default())
-            .finish(&mut df)?;
+        let written = self.write_time_chunk(table, &mut df)?;
+        super::checksum::record_rewrite(&written, replaced_since)?;
         tprintln!("[STORAGE] rewrite_table_df: wrote time-table parquet rows={} took={:?} total={:?}", df.height(), __t_write_ts.elapsed(), __t0.elapsed());
         Ok(())
     }
 
+    /// Replace the given chunks of `table` with new contents, leaving every other chunk on disk
+    /// as it is. Regular-table chunks are rewritten in place; a time-range chunk is replaced by one
+    /// named for its new range in the same folder. Rows never move between partition folders here,
+    /// and the frames must keep the table's columns, so callers changing partition columns or
+    /// adding columns use `rewrite_table_df` instead.
+    pub fn replace_chunks(&self, table: &str, chunks: Vec<(PathBuf, DataFrame)>) -> Result<()> {
+        use std::time::UNIX_EPOCH;
+        if chunks.is_empty() { return Ok(()); }
+        let __t0 = std::time::Instant::now();
+        let enc = self.get_table_encryption(table)?;
+        let bloom_cols = self.get_bloom_columns(table);
+        let now_ms = UNIX_EPOCH.elapsed().unwrap().as_millis() as i64;
+        let replaced = chunks.len();
+        for (path, mut df) in chunks {
+            self.conform_declared_columns(table, &mut df)?;
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+            let target = if parse_chunk_min_max(&name).is_some() && df.get_column_names().iter().any(|c| c.as_str() == "_time") {
+                df = df.sort(["_time"], SortMultipleOptions::default().with_maintain_order(true))?;
+                let times = df.column("_time")?.cast(&DataType::Int64)?;
+                let ca = times.i64()?;
+                let (min_t, max_t) = (ca.min().unwrap_or(0), ca.max().unwrap_or(0));
+                // Keep chunk names unique when several chunks of a folder land on the same range
+                let mut ts = now_ms;
+                let mut p = path.with_file_name(format!("data-{}-{}-{}.parquet", min_t, max_t, ts));
+                while p != path && p.exists() { ts += 1; p = path.with_file_name(format!("data-{}-{}-{}.parquet", min_t, max_t, ts)); }
+                p
+            } else { path.clone() };
+            super::encryption::write_parquet(&target, &mut df, enc.as_ref())?;
+            super::bloom::write_sidecar(&target, &df, &bloom_cols)?;
+            super::checksum::record(&target)?;
+            // The frame holds live rows only, so the chunk's tombstones are spent
+            let _ = fs::remove_file(super::tombstone::sidecar_path(&path));
+            if target != path {
+                super::checksum::record_rewrite(std::slice::from_ref(&target), super::checksum::oldest_write(std::slice::from_ref(&path)))?;
+                let _ = fs::remove_file(super::bloom::sidecar_path(&path));
+                let _ = fs::remove_file(super::checksum::sidecar_path(&path));
+                fs::remove_file(&path)?;
+            }
+        }
+        tprintln!("[STORAGE] replace_chunks: table='{}' replaced {} chunks took={:?}", table, replaced, __t0.elapsed());
+        Ok(())
+    }
+
     pub fn write_records(&self, table: &str, records: &[Record]) -> Result<()> {
         use std::collections::HashMap;
-        use std::time::UNIX_EPOCH;
 
         fs::create_dir_all(self.db_dir(table))?;
 
@@ -393,12 +448,25 @@ impl Store {
         let (mut schema, locks) = self.load_schema_with_locks(table)
             .unwrap_or((std::collections::HashMap::new(), std::collections::HashSet::new()));
         let inferred = super::Store::infer_dtypes(records, &col_names);
-        // Merge respecting locks
+        // Strict tables reject a batch that does not fit; coerce tables store the misfits as nulls
+        let mode = super::ingest_mode::get_ingest_mode(self, table);
+        if mode != super::ingest_mode::IngestMode::Widen {
+            let mismatches = super::ingest_mode::find_mismatches(records, &schema, &col_names);
+            if !mismatches.is_empty() {
+                if mode == super::ingest_mode::IngestMode::Strict {
+                    return Err(super::ingest_mode::strict_error(table, &mismatches));
+                }
+                let coerced: usize = mismatches.iter().map(|m| m.bad).sum();
+                self.update_ingest_stats(table, |s| s.values_coerced += coerced as u64)?;
+            }
+        }
+        // Merge respecting locks; only widen mode changes the type of a typed column
         for (k, dt) in inferred {
             let merged = match schema.get(&k) {
                 None => dt,
                 Some(existing) => {
-                    if locks.contains(&k) { existing.clone() } else { super::schema::merge_dtype(existing.clone(), dt) }
+                    let keep = locks.contains(&k) || (mode != super::ingest_mode::IngestMode::Widen && *existing != DataType::Null);
+                    if keep { existing.clone() } else { super::schema::merge_dtype(existing.clone(), dt) }
                 }
             };
             schema.insert(k, merged);
@@ -419,9 +487,17 @@ impl Store {
         let mut vec_cols: HashMap<String, Vec<Option<Vec<f64>>>> = HashMap::new();
         let mut list_i64_cols: HashMap<String, Vec<Option<Vec<i64>>>> = HashMap::new();
         let mut list_str_cols: HashMap<String, Vec<Option<Vec<String>>>> = HashMap::new();
+        let mut dec_cols: HashMap<String, Vec<Option<i128>>> = HashMap::new();
+        let mut bool_cols: HashMap<String, Vec<Option<bool>>> = HashMap::new();
+        let mut ts_cols: HashMap<String, Vec<Option<i64>>> = HashMap::new();
+        let mut null_cols: Vec<String> = Vec::new();
         for name in &write_names {
             match schema.get(name) {
+                Some(DataType::Null) => null_cols.push(name.clone()),
                 Some(DataType::String) => { str_cols.insert(name.clone(), Vec::with_capacity(records.len())); },
+                Some(DataType::Decimal(..)) => { dec_cols.insert(name.clone(), Vec::with_capacity(records.len())); },
+                Some(DataType::Boolean) => { bool_cols.insert(name.clone(), Vec::with_capacity(records.len())); },
+                Some(DataType::Datetime(..)) => { ts_cols.insert(name.clone(), Vec::with_capacity(records.len())); },
                 Some(DataType::Int64) => { i64_cols.insert(name.clone(), Vec::with_capacity(records.len())); },
                 Some(DataType::List(inner)) if matches!(**inner, DataType::Float64) => {
                     vec_cols.insert(name.clone(), Vec::with_capacity(records.len()));
@@ -440,6 +516,8 @@ impl Store {
             times.push(r._time);
             for name in &write_names {
                 match schema.get(name) {
+                    // Nothing but nulls so far; the column is written as a null column below
+                    Some(DataType::Null) => {}
                     Some(DataType::String) => {
                         let entry = str_cols.get_mut(name).unwrap();
                         let v = r.sensors.get(name).and_then(|val| match val {
@@ -449,6 +527,20 @@ impl Store {
                         });
                         entry.push(v);
                     }
+                    Some(dt @ DataType::Decimal(..)) => {
+                        // Parsed from the value's text so no digits are lost through f64
+                        let scale = super::decimal::precision_scale(dt).map(|(_, sc)| sc).unwrap_or(0);
+                        let entry = dec_cols.get_mut(name).unwrap();
+                        entry.push(r.sensors.get(name).and_then(|val| super::decimal::json_value(val, scale)));
+                    }
+                    Some(DataType::Boolean) => {
+                        let entry = bool_cols.get_mut(name).unwrap();
+                        entry.push(r.sensors.get(name).and_then(super::native::json_bool));
+                    }
+                    Some(DataType::Datetime(..)) => {
+                        let entry = ts_cols.get_mut(name).unwrap();
+                        entry.push(r.sensors.get(name).and_then(super::native::json_timestamp_ms));
+                    }
                     Some(DataType::Int64) => {
                         let entry = i64_cols.get_mut(name).unwrap();
                         let v = r.sensors.get(name).and_then(|val| match val {
@@ -575,7 +667,10 @@ impl Store {
             else if let Some((_, v)) = vec_cols.iter().next() { v.len() }
             else if let Some((_, v)) = list_i64_cols.iter().next() { v.len() }
             else if let Some((_, v)) = list_str_cols.iter().next() { v.len() }
-            else { 0 }
+            else if let Some((_, v)) = dec_cols.iter().next() { v.len() }
+            else if let Some((_, v)) = bool_cols.iter().next() { v.len() }
+            else if let Some((_, v)) = ts_cols.iter().next() { v.len() }
+            else { records.len() }
         };
         let mut cols: Vec<Column> = Vec::with_capacity(write_names.len() + 1);
 
@@ -662,7 +757,24 @@ impl Store {
                 }
             }
         }
-        let mut df = DataFrame::new(cols)?;
+        for (name, vals) in dec_cols.into_iter() {
+            if name == "_time" { continue; }
+            let (p, sc) = schema.get(&name).and_then(super::decimal::precision_scale).unwrap_or((super::decimal::DEFAULT_PRECISION, super::decimal::DEFAULT_SCALE));
+            cols.push(super::decimal::series(&name, vals, p, sc)?.into());
+        }
+        for (name, vals) in bool_cols.into_iter() {
+            if name != "_time" { cols.push(Series::new(name.into(), vals).into()); }
+        }
+        for (name, vals) in ts_cols.into_iter() {
+            if name == "_time" { continue; }
+            let ms = Int64Chunked::from_iter_options(name.into(), vals.into_iter());
+            cols.push(ms.into_datetime(TimeUnit::Milliseconds, None).into_series().into());
+        }
+        for name in null_cols.into_iter() {
+            if name != "_time" { cols.push(Column::full_null(name.into(), height, &DataType::Null)); }
+        }
+        let mut df = self.apply_computed_columns(table, DataFrame::new(cols)?, &mut schema)?;
+        self.note_nullability(table, &df)?;
 
         // Sort by _time ascending for time tables only
         if is_time_table {
@@ -683,10 +795,9 @@ impl Store {
             } else { 0 };
             if parts == 0 {
                 let path = self.db_file(table);
-                let mut file = std::fs::File::create(&path)?;
-                ParquetWriter::new(&mut file)
-                    .with_statistics(StatisticsOptions::default())
-                    .finish(&mut df)?;
+                super::encryption::write_parquet(&path, &mut df, self.get_table_encryption(table)?.as_ref())?;
+                super::bloom::write_sidecar(&path, &df, &self.get_bloom_columns(table))?;
+                super::checksum::record(&path)?;
                 crate::tprintln!("[storage.write_records] regular table wrote file '{}' rows={}", path.display(), df.height());
                 // Update schema.json: merge existing declared schema with columns present in this df
                 // Do NOT drop previously declared columns (e.g., VECTOR) that may be missing in this write.
@@ -714,23 +825,76 @@ impl Store {
             return Ok(());
         }
 
-        // Write chunked file with min/max/time suffix
-        let (min_t, max_t) = if let Ok(c) = df.column("_time") {
-            let ca = c.i64();
-            if let Ok(ci) = ca { (ci.min().unwrap_or(0), ci.max().unwrap_or(0)) } else { (0, 0) }
-        } else { (0, 0) };
-        let now_ms: u128 = UNIX_EPOCH.elapsed().unwrap().as_millis();
-        let fname = format!("data-{}-{}-{}.parquet", min_t, max_t, now_ms);
-        let path = self.db_dir(table).join(fname);
-        let mut file = std::fs::File::create(&path)?;
-        ParquetWriter::new(&mut file)
-            .with_statistics(StatisticsOptions::default())
-            .finish(&mut df)?;
-        crate::tprintln!("[storage.write_records] time table wrote chunk '{}' rows={}", path.display(), df.height());
+        // Drop re-sent rows (same _time + key) before they reach a chunk
+        if self.get_dedup_mode(table) == super::dedup::DedupMode::Write {
+            let (deduped, dropped) = self.dedup_incoming(table, df)?;
+            df = deduped;
+            if dropped > 0 {
+                self.update_ingest_stats(table, |s| s.duplicates_dropped += dropped as u64)?;
+                tprintln!("[storage.write_records] dedup dropped {} duplicate row(s) for '{}'", dropped, table);
+            }
+            if df.height() == 0 {
+                super::schema::save_schema_with_locks(self, table, &schema, &locks)?;
+                return Ok(());
+            }
+        }
+
+        // Route rows older than the table's high-water mark per its late-data policy
+        let late_setting = self.get_late_setting(table);
+        let (on_time, late) = self.split_late(table, df.clone(), late_setting.window_ms)?;
+        if late.height() > 0 {
+            let n_late = late.height();
+            self.update_ingest_stats(table, |s| s.late_records += n_late as u64)?;
+            tprintln!("[storage.write_records] {} late row(s) for '{}' policy={:?}", n_late, table, late_setting.policy);
+            match late_setting.policy {
+                super::late::LatePolicy::Off => {}
+                super::late::LatePolicy::Separate => {
+                    let mut late = late;
+                    self.write_time_chunk(table, &mut late)?;
+                    self.record_changes(table, super::cdc::ChangeOp::Insert, &late)?;
+                    df = on_time;
+                }
+                super::late::LatePolicy::Merge => {
+                    self.record_changes(table, super::cdc::ChangeOp::Insert, &late)?;
+                    self.merge_late_rows(table, late)?;
+                    df = on_time;
+                }
+            }
+        }
+
+        if df.height() > 0 {
+            self.write_time_chunk(table, &mut df)?;
+            self.record_changes(table, super::cdc::ChangeOp::Insert, &df)?;
+        }
 
         // Save merged schema with locks preserved
         super::schema::save_schema_with_locks(self, table, &schema, &locks)?;
 
         Ok(())
     }
+
+    /// Write `df` (sorted by `_time`) as new chunks of a time table, one per
+    /// partition folder, with their bloom and checksum sidecars. Returns the chunk paths.
+    pub(crate) fn write_time_chunk(&self, table: &str, df: &mut DataFrame) -> Result<Vec<PathBuf>> {
+        use std::time::UNIX_EPOCH;
+        let enc = self.get_table_encryption(table)?;
+        let bloom_cols = self.get_bloom_columns(table);
+        let now_ms: u128 = UNIX_EPOCH.elapsed().unwrap().as_millis();
+        let mut written: Vec<PathBuf> = Vec::new();
+        for (pdir, mut part) in self.partition_frames(table, df)? {
+            // Write chunked file with min/max/time suffix
+            let (min_t, max_t) = if let Ok(c) = part.column("_time") {
+                let ca = c.i64();
+                if let Ok(ci) = ca { (ci.min().unwrap_or(0), ci.max().unwrap_or(0)) } else { (0, 0) }
+            } else { (0, 0) };
+            fs::create_dir_all(&pdir)?;
+            let path = pdir.join(format!("data-{}-{}-{}.parquet", min_t, max_t, now_ms));
+            super::encryption::write_parquet(&path, &mut part, enc.as_ref())?;
+            super::bloom::write_sidecar(&path, &part, &bloom_cols)?;
+            super::checksum::record(&path)?;
+            crate::tprintln!("[storage.write_records] time table wrote chunk '{}' rows={}", path.display(), part.height());
+            written.push(path);
+        }
+        Ok(written)
+    }
 }
@@ -1,6 +1,8 @@
 use std::time::{Duration, Instant};
 use std::collections::HashMap as StdHashMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
@@ -24,6 +26,41 @@ pub enum KvValue {
     ParquetDf(DataFrame),
     /// Raw binary value intended for high-performance blobs (e.g., Lua bytecode)
     Bytes(Vec<u8>),
+    /// Ordered list of strings (LPUSH/RPUSH/LPOP/RPOP)
+    List(VecDeque<String>),
+    /// Set of distinct strings (SADD/SREM)
+    Set(BTreeSet<String>),
+    /// Field -> value map (HSET/HGET/HDEL)
+    Hash(BTreeMap<String, String>),
+}
+
+impl KvValue {
+    /// Type name as reported by READ KEY / DESCRIBE KEY.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            KvValue::Str(_) => "string",
+            KvValue::Int(_) => "int",
+            KvValue::Json(_) => "json",
+            KvValue::ParquetDf(_) => "table",
+            KvValue::Bytes(_) => "bytes",
+            KvValue::List(_) => "list",
+            KvValue::Set(_) => "set",
+            KvValue::Hash(_) => "hash",
+        }
+    }
+
+    fn is_empty_collection(&self) -> bool {
+        match self {
+            KvValue::List(l) => l.is_empty(),
+            KvValue::Set(s) => s.is_empty(),
+            KvValue::Hash(h) => h.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+fn wrong_type(key: &str, value: &KvValue, expected: &str) -> anyhow::Error {
+    anyhow::anyhow!("wrong_type: key '{}' holds {}, expected {}", key, value.type_name(), expected)
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -38,11 +75,17 @@ pub struct StoreSettings {
     /// Optional persistence settings loaded from `<store dir>/store.json`.
     #[serde(default)]
     pub persistence: Option<PersistenceSettings>,
+    /// Seal snapshots and Parquet values at rest (see `storage::encryption`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<super::encryption::EncryptionSpec>,
+    /// Endpoints POSTed matching key events ("expired"; see `storage::kv_events`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhooks: Option<Vec<super::webhooks::WebhookConfig>>,
 }
 
 impl Default for StoreSettings {
     fn default() -> Self {
-        Self { name: String::new(), reset_on_access_default: true, replication: None, persistence: Some(PersistenceSettings::default()) }
+        Self { name: String::new(), reset_on_access_default: true, replication: None, persistence: Some(PersistenceSettings::default()), encryption: None, webhooks: None }
     }
 }
 
@@ -79,12 +122,124 @@ struct Entry {
     expires_at: Option<Instant>,
     /// If true for this key, accesses reset TTL
     reset_on_access: bool,
+    /// Bumped on every change of the value (`KvStore::version`)
+    version: u64,
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool { self.expires_at.is_some_and(|exp| now >= exp) }
+}
+
+/// Versions are process-wide so a key dropped and written again never repeats one.
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+fn next_version() -> u64 { NEXT_VERSION.fetch_add(1, Ordering::Relaxed) }
+
+/// One write of an atomic batch (`KvStore::apply_batch`).
+#[derive(Clone)]
+pub enum KvWrite {
+    Set { key: String, value: KvValue, ttl: Option<Duration>, reset_on_access: Option<bool> },
+    Delete { key: String },
+    Push { key: String, values: Vec<String>, front: bool },
+    Pop { key: String, front: bool },
+    SetAdd { key: String, members: Vec<String> },
+    SetRemove { key: String, members: Vec<String> },
+    HashSet { key: String, fields: Vec<(String, String)> },
+    HashDelete { key: String, fields: Vec<String> },
+}
+
+impl KvWrite {
+    pub fn key(&self) -> &str {
+        match self {
+            KvWrite::Set { key, .. } | KvWrite::Delete { key } | KvWrite::Push { key, .. } | KvWrite::Pop { key, .. }
+            | KvWrite::SetAdd { key, .. } | KvWrite::SetRemove { key, .. } | KvWrite::HashSet { key, .. } | KvWrite::HashDelete { key, .. } => key,
+        }
+    }
+}
+
+/// What one write of a batch did, as the single-key operation reports it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KvWriteOutcome {
+    Written,
+    Deleted(bool),
+    Length(usize),
+    Popped(Option<String>),
+    Added(usize),
+    Removed(usize),
+}
+
+/// Outcome of each write, in order, and the version of each key written (0 once removed).
+#[derive(Debug, Clone, PartialEq)]
+pub struct KvBatchResult {
+    pub outcomes: Vec<KvWriteOutcome>,
+    pub versions: BTreeMap<String, u64>,
+}
+
+// List/set/hash operations on a live value, shared by the single-key methods and batches
+
+fn push_values(key: &str, v: &mut KvValue, values: &[String], front: bool) -> anyhow::Result<usize> {
+    match v {
+        KvValue::List(l) => {
+            for x in values { if front { l.push_front(x.clone()) } else { l.push_back(x.clone()) } }
+            Ok(l.len())
+        }
+        other => Err(wrong_type(key, other, "list")),
+    }
+}
+
+fn pop_value(key: &str, v: &mut KvValue, front: bool) -> anyhow::Result<Option<String>> {
+    match v {
+        KvValue::List(l) => Ok(if front { l.pop_front() } else { l.pop_back() }),
+        other => Err(wrong_type(key, other, "list")),
+    }
+}
+
+fn add_members(key: &str, v: &mut KvValue, members: &[String]) -> anyhow::Result<usize> {
+    match v {
+        KvValue::Set(m) => Ok(members.iter().filter(|x| m.insert((*x).clone())).count()),
+        other => Err(wrong_type(key, other, "set")),
+    }
+}
+
+fn remove_members(key: &str, v: &mut KvValue, members: &[String]) -> anyhow::Result<usize> {
+    match v {
+        KvValue::Set(m) => Ok(members.iter().filter(|x| m.remove(x.as_str())).count()),
+        other => Err(wrong_type(key, other, "set")),
+    }
+}
+
+fn set_fields(key: &str, v: &mut KvValue, fields: &[(String, String)]) -> anyhow::Result<usize> {
+    match v {
+        KvValue::Hash(h) => Ok(fields.iter().filter(|(f, x)| h.insert(f.clone(), x.clone()).is_none()).count()),
+        other => Err(wrong_type(key, other, "hash")),
+    }
+}
+
+fn delete_fields(key: &str, v: &mut KvValue, fields: &[String]) -> anyhow::Result<usize> {
+    match v {
+        KvValue::Hash(h) => Ok(fields.iter().filter(|f| h.remove(f.as_str()).is_some()).count()),
+        other => Err(wrong_type(key, other, "hash")),
+    }
+}
+
+/// `KvStore::modify` on a staged batch entry: `create` starts an absent collection, and one
+/// left empty is removed.
+fn stage_modify(slot: &mut Option<Entry>, reset_on_access: bool, create: Option<KvValue>, f: &mut dyn FnMut(&mut KvValue) -> anyhow::Result<KvWriteOutcome>) -> anyhow::Result<Option<KvWriteOutcome>> {
+    if slot.is_none() {
+        if let Some(value) = create { *slot = Some(Entry { value, ttl: None, expires_at: None, reset_on_access, version: 0 }); }
+    }
+    let Some(ent) = slot.as_mut() else { return Ok(None) };
+    let out = f(&mut ent.value)?;
+    if ent.value.is_empty_collection() { *slot = None; }
+    Ok(Some(out))
 }
 
 /// A single named in-memory KV store.
 #[derive(Clone)]
 pub struct KvStore {
     pub(crate) settings: StoreSettings,
+    /// Database the store belongs to, for key events
+    database: String,
     dir: PathBuf,
     map: Arc<parking_lot::RwLock<StdHashMap<String, Entry>>>,
     /// Guard to ensure we only spawn one persistence thread
@@ -92,9 +247,9 @@ pub struct KvStore {
 }
 
 impl KvStore {
-    pub(crate) fn new(dir: PathBuf, settings: StoreSettings) -> Self {
+    pub(crate) fn new(dir: PathBuf, database: &str, settings: StoreSettings) -> Self {
         std::fs::create_dir_all(&dir).ok();
-        let s = Self { settings, dir, map: Arc::new(parking_lot::RwLock::new(StdHashMap::new())), persist_started: Arc::new(packing_lot_mutex()) };
+        let s = Self { settings, database: database.to_string(), dir, map: Arc::new(parking_lot::RwLock::new(StdHashMap::new())), persist_started: Arc::new(packing_lot_mutex()) };
         // Start persistence loop if enabled
         s.ensure_persistence_loop();
         s
@@ -105,7 +260,7 @@ impl KvStore {
     fn snapshot_path(&self) -> PathBuf { self.dir.join("snapshot.bin") }
     fn parquet_dir(&self) -> PathBuf { self.dir.join("parquet") }
 
-    pub fn load_or_default(dir: PathBuf, name: &str) -> Self {
+    pub fn load_or_default(dir: PathBuf, database: &str, name: &str) -> Self {
         let cfg_new = dir.join("store.json");
         let cfg_legacy = dir.join("config.json");
         let mut settings = StoreSettings::default();
@@ -121,7 +276,7 @@ impl KvStore {
                 let _ = std::fs::write(&cfg_new, serde_json::to_vec_pretty(&settings).unwrap_or_default());
             }
         }
-        Self::new(dir, settings)
+        Self::new(dir, database, settings)
     }
 
     fn ensure_persistence_loop(&self) {
@@ -149,9 +304,12 @@ impl KvStore {
         Ok(())
     }
 
-    fn save_snapshot(&self) -> anyhow::Result<()> {
+    fn save_snapshot(&self) -> anyhow::Result<()> { self.write_snapshot(&self.map.read()) }
+
+    /// Write `map` as this store's snapshot; callers hold the map's lock.
+    fn write_snapshot(&self, map: &StdHashMap<String, Entry>) -> anyhow::Result<()> {
         #[derive(Serialize, Deserialize)]
-        enum SnapVal { Str(String), Int(i64), Json(Vec<u8>), Bytes(Vec<u8>), Parquet { rel_path: String } }
+        enum SnapVal { Str(String), Int(i64), Json(Vec<u8>), Bytes(Vec<u8>), Parquet { rel_path: String }, List(Vec<String>), Set(Vec<String>), Hash(Vec<(String, String)>) }
         #[derive(Serialize, Deserialize)]
         struct SnapEntry { key: String, val: SnapVal, ttl_ms: Option<u64>, remaining_ms: Option<u64>, reset_on_access: bool }
         #[derive(Serialize, Deserialize)]
@@ -161,25 +319,29 @@ impl KvStore {
         let mut entries: Vec<SnapEntry> = Vec::new();
         let parquet_dir = self.parquet_dir();
         std::fs::create_dir_all(&parquet_dir).ok();
-        for (k, v) in self.map.read().iter() {
-            let (val, ttl_ms, remaining_ms) = match &v.value {
-                KvValue::Str(s) => (SnapVal::Str(s.clone()), v.ttl.map(|d| d.as_millis() as u64), v.expires_at.map(|e| e.saturating_duration_since(Instant::now()).as_millis() as u64)),
-                KvValue::Int(i) => (SnapVal::Int(*i), v.ttl.map(|d| d.as_millis() as u64), v.expires_at.map(|e| e.saturating_duration_since(Instant::now()).as_millis() as u64)),
-                KvValue::Json(j) => (SnapVal::Json(serde_json::to_vec(j).unwrap_or_default()), v.ttl.map(|d| d.as_millis() as u64), v.expires_at.map(|e| e.saturating_duration_since(Instant::now()).as_millis() as u64)),
-                KvValue::Bytes(b) => (SnapVal::Bytes(b.clone()), v.ttl.map(|d| d.as_millis() as u64), v.expires_at.map(|e| e.saturating_duration_since(Instant::now()).as_millis() as u64)),
+        for (k, v) in map.iter() {
+            let ttl_ms = v.ttl.map(|d| d.as_millis() as u64);
+            let remaining_ms = v.expires_at.map(|e| e.saturating_duration_since(Instant::now()).as_millis() as u64);
+            let val = match &v.value {
+                KvValue::Str(s) => SnapVal::Str(s.clone()),
+                KvValue::Int(i) => SnapVal::Int(*i),
+                KvValue::Json(j) => SnapVal::Json(serde_json::to_vec(j).unwrap_or_default()),
+                KvValue::Bytes(b) => SnapVal::Bytes(b.clone()),
                 KvValue::ParquetDf(df) => {
                     let fname = format!("{}.parquet", sanitize_filename(k));
                     let path = parquet_dir.join(&fname);
-                    if let Ok(mut f) = std::fs::File::create(&path) {
-                        let _ = ParquetWriter::new(&mut f).finish(&mut df.clone());
-                    }
-                    (SnapVal::Parquet { rel_path: format!("parquet/{}", fname) }, v.ttl.map(|d| d.as_millis() as u64), v.expires_at.map(|e| e.saturating_duration_since(Instant::now()).as_millis() as u64))
+                    let _ = super::encryption::write_parquet(&path, &mut df.clone(), self.settings.encryption.as_ref());
+                    SnapVal::Parquet { rel_path: format!("parquet/{}", fname) }
                 }
+                KvValue::List(l) => SnapVal::List(l.iter().cloned().collect()),
+                KvValue::Set(m) => SnapVal::Set(m.iter().cloned().collect()),
+                KvValue::Hash(h) => SnapVal::Hash(h.iter().map(|(f, v)| (f.clone(), v.clone())).collect()),
             };
             entries.push(SnapEntry { key: k.clone(), val, ttl_ms, remaining_ms, reset_on_access: v.reset_on_access });
         }
         let snap = Snapshot { version: 1, created_ms: now_ms, entries };
-        let bytes = bincode::serialize(&snap)?;
+        let mut bytes = bincode::serialize(&snap)?;
+        if let Some(spec) = &self.settings.encryption { bytes = super::encryption::encrypt(&bytes, spec)?; }
         let tmp = self.snapshot_path().with_extension("bin.tmp");
         std::fs::write(&tmp, bytes)?;
         std::fs::rename(tmp, self.snapshot_path())?;
@@ -190,12 +352,12 @@ impl KvStore {
     pub fn load_snapshot(&self) -> anyhow::Result<()> {
         if !self.snapshot_path().exists() { return Ok(()); }
         #[derive(Serialize, Deserialize)]
-        enum SnapVal { Str(String), Int(i64), Json(Vec<u8>), Bytes(Vec<u8>), Parquet { rel_path: String } }
+        enum SnapVal { Str(String), Int(i64), Json(Vec<u8>), Bytes(Vec<u8>), Parquet { rel_path: String }, List(Vec<String>), Set(Vec<String>), Hash(Vec<(String, String)>) }
         #[derive(Serialize, Deserialize)]
         struct SnapEntry { key: String, val: SnapVal, ttl_ms: Option<u64>, remaining_ms: Option<u64>, reset_on_access: bool }
         #[derive(Serialize, Deserialize)]
         struct Snapshot { version: u32, created_ms: i64, entries: Vec<SnapEntry> }
-        let bytes = std::fs::read(self.snapshot_path())?;
+        let bytes = super::encryption::decrypt(std::fs::read(self.snapshot_path())?)?;
         let snap: Snapshot = bincode::deserialize(&bytes)?;
         let now = Instant::now();
         let mut w = self.map.write();
@@ -211,11 +373,14 @@ impl KvStore {
                 SnapVal::Bytes(b) => KvValue::Bytes(b),
                 SnapVal::Parquet { rel_path } => {
                     let p = self.dir.join(rel_path);
-                    match polars::prelude::ParquetReader::new(std::fs::File::open(&p)?).finish() {
+                    match super::encryption::read_parquet(&p) {
                         Ok(df) => KvValue::ParquetDf(df),
                         Err(_) => KvValue::Bytes(Vec::new()),
                     }
                 }
+                SnapVal::List(l) => KvValue::List(l.into()),
+                SnapVal::Set(m) => KvValue::Set(m.into_iter().collect()),
+                SnapVal::Hash(h) => KvValue::Hash(h.into_iter().collect()),
             };
             let ttl = e.ttl_ms.map(|ms| Duration::from_millis(ms));
             let expires_at = match (ttl, e.remaining_ms) {
@@ -223,20 +388,32 @@ impl KvStore {
                 (Some(d), _) => Some(now + d), // fallback if missing remaining
                 _ => None,
             };
-            w.insert(e.key, Entry { value: kv, ttl, expires_at, reset_on_access: e.reset_on_access });
+            w.insert(e.key, Entry { value: kv, ttl, expires_at, reset_on_access: e.reset_on_access, version: next_version() });
         }
         Ok(())
     }
 
+    /// Publish a `kind` event ("set", "delete", "expired") for each of `keys`; nothing is built
+    /// when no subscriber, watch or webhook would receive it.
+    fn publish(&self, kind: &str, keys: Vec<String>) {
+        let hooks = self.settings.webhooks.as_deref().unwrap_or_default();
+        if keys.is_empty() || !super::kv_events::has_listeners(hooks) { return; }
+        for key in keys {
+            super::kv_events::publish(hooks, super::kv_events::KvEvent::new(kind, &self.database, &self.settings.name, &key));
+        }
+    }
+
+    fn publish_expired(&self, keys: Vec<String>) { self.publish("expired", keys) }
+
     /// Set a key with optional TTL and per-key reset-on-access flag (defaults from store settings).
     pub fn set(&self, key: impl Into<String>, value: KvValue, ttl: Option<Duration>, reset_on_access: Option<bool>) {
         let key = key.into();
         let now = Instant::now();
         let reset = reset_on_access.unwrap_or(self.settings.reset_on_access_default);
         let expires_at = ttl.map(|d| now + d);
-        let ent = Entry { value, ttl, expires_at, reset_on_access: reset };
-        let mut w = self.map.write();
-        w.insert(key, ent);
+        let ent = Entry { value, ttl, expires_at, reset_on_access: reset, version: next_version() };
+        self.map.write().insert(key.clone(), ent);
+        self.publish("set", vec![key]);
     }
 
     /// Convenience: store raw bytes without extra allocations by cloning once into the map.
@@ -268,7 +445,12 @@ impl KvStore {
         }
         // If expired (after potential reset), remove and return None
         if let Some(ent) = w.get(key) {
-            if let Some(exp) = ent.expires_at { if Instant::now() >= exp { w.remove(key); return None; } }
+            if ent.is_expired(Instant::now()) {
+                w.remove(key);
+                drop(w);
+                self.publish_expired(vec![key.to_string()]);
+                return None;
+            }
         } else { return None; }
         w.get(key).map(|e| e.value.clone())
     }
@@ -281,32 +463,225 @@ impl KvStore {
         }
     }
 
-    pub fn delete(&self, key: &str) -> bool { self.map.write().remove(key).is_some() }
-    pub fn clear(&self) { self.map.write().clear(); }
-    pub fn len(&self) -> usize { self.map.read().len() }
-    /// Return a snapshot of all keys in this store
-    pub fn keys(&self) -> Vec<String> { self.map.read().keys().cloned().collect() }
+    /// Apply `f` to the live value of `key` under the write lock; Ok(None) when the key is absent
+    /// (or expired) and `create` is None. A new key gets no TTL; an existing one keeps its TTL.
+    /// A collection left empty is removed, as if it had never been written. Publishes "set" when
+    /// `f` succeeds on the key, or "delete" when that removed it.
+    fn modify<R>(&self, key: &str, create: Option<KvValue>, f: impl FnOnce(&mut KvValue) -> anyhow::Result<R>) -> anyhow::Result<Option<R>> {
+        let mut w = self.map.write();
+        let expired = w.get(key).is_some_and(|e| e.is_expired(Instant::now()));
+        if expired { w.remove(key); }
+        if !w.contains_key(key) {
+            if let Some(value) = create {
+                w.insert(key.to_string(), Entry { value, ttl: None, expires_at: None, reset_on_access: self.settings.reset_on_access_default, version: next_version() });
+            }
+        }
+        let mut changed = None;
+        let out = match w.get_mut(key) {
+            Some(ent) => {
+                let out = f(&mut ent.value);
+                if ent.value.is_empty_collection() {
+                    w.remove(key);
+                    changed = Some("delete");
+                } else if out.is_ok() {
+                    ent.version = next_version();
+                    changed = Some("set");
+                }
+                out.map(Some)
+            }
+            None => Ok(None),
+        };
+        drop(w);
+        if expired { self.publish_expired(vec![key.to_string()]); }
+        if let Some(kind) = changed { self.publish(kind, vec![key.to_string()]); }
+        out
+    }
+
+    /// Push `values` onto the head (`front`) or tail of the list at `key`, one at a time, so
+    /// LPUSH a, b leaves b first. Creates the list; returns its new length.
+    pub fn list_push(&self, key: &str, values: &[String], front: bool) -> anyhow::Result<usize> {
+        let n = self.modify(key, Some(KvValue::List(VecDeque::new())), |v| push_values(key, v, values, front))?;
+        Ok(n.unwrap_or(0))
+    }
+
+    /// Pop from the head (`front`) or tail of the list at `key`; None when absent or empty.
+    pub fn list_pop(&self, key: &str, front: bool) -> anyhow::Result<Option<String>> {
+        let out = self.modify(key, None, |v| pop_value(key, v, front))?;
+        Ok(out.flatten())
+    }
+
+    /// Add `members` to the set at `key`, creating it; returns how many were not already present.
+    pub fn set_add(&self, key: &str, members: &[String]) -> anyhow::Result<usize> {
+        let n = self.modify(key, Some(KvValue::Set(BTreeSet::new())), |v| add_members(key, v, members))?;
+        Ok(n.unwrap_or(0))
+    }
+
+    /// Remove `members` from the set at `key`; returns how many were present.
+    pub fn set_remove(&self, key: &str, members: &[String]) -> anyhow::Result<usize> {
+        let n = self.modify(key, None, |v| remove_members(key, v, members))?;
+        Ok(n.unwrap_or(0))
+    }
+
+    /// Set `fields` of the hash at `key`, creating it; returns how many fields are new.
+    pub fn hash_set(&self, key: &str, fields: &[(String, String)]) -> anyhow::Result<usize> {
+        let n = self.modify(key, Some(KvValue::Hash(BTreeMap::new())), |v| set_fields(key, v, fields))?;
+        Ok(n.unwrap_or(0))
+    }
+
+    /// Value of `field` in the hash at `key`.
+    pub fn hash_get(&self, key: &str, field: &str) -> anyhow::Result<Option<String>> {
+        match self.get(key) {
+            Some(KvValue::Hash(h)) => Ok(h.get(field).cloned()),
+            Some(other) => Err(wrong_type(key, &other, "hash")),
+            None => Ok(None),
+        }
+    }
+
+    /// Remove `fields` from the hash at `key`; returns how many were present.
+    pub fn hash_delete(&self, key: &str, fields: &[String]) -> anyhow::Result<usize> {
+        let n = self.modify(key, None, |v| delete_fields(key, v, fields))?;
+        Ok(n.unwrap_or(0))
+    }
+
+    /// Version of the live value at `key`, 0 when absent or expired. Every change of the value
+    /// gives it a new one.
+    pub fn version(&self, key: &str) -> u64 {
+        let now = Instant::now();
+        self.map.read().get(key).filter(|e| !e.is_expired(now)).map(|e| e.version).unwrap_or(0)
+    }
+
+    /// Apply `writes` in order as one change: all of them or none. Each `(key, version)` of
+    /// `expect` must still match `version(key)` (0: absent), otherwise another writer got there
+    /// first and the batch fails with `kv_conflict` (SQLSTATE 40001, HTTP 409); a failing write
+    /// (e.g. `wrong_type`) fails it too. With persistence on, the snapshot is written before the
+    /// batch is visible, and a batch that cannot be saved is undone.
+    pub fn apply_batch(&self, expect: &[(String, u64)], writes: &[KvWrite]) -> anyhow::Result<KvBatchResult> {
+        let now = Instant::now();
+        let mut w = self.map.write();
+        for (key, want) in expect {
+            let have = w.get(key.as_str()).filter(|e| !e.is_expired(now)).map(|e| e.version).unwrap_or(0);
+            if have != *want {
+                return Err(crate::error::AppError::conflict("serialization_failure".to_string(), format!("kv_conflict: key '{}' is at version {}, expected {}", key, have, want)).into());
+            }
+        }
+        // Stage the keys written, None when absent; nothing touches the map until all succeed
+        let mut staged: BTreeMap<String, Option<Entry>> = BTreeMap::new();
+        let mut expired = Vec::new();
+        for wr in writes {
+            if staged.contains_key(wr.key()) { continue; }
+            let cur = match w.get(wr.key()) {
+                Some(e) if e.is_expired(now) => { expired.push(wr.key().to_string()); None }
+                cur => cur.cloned(),
+            };
+            staged.insert(wr.key().to_string(), cur);
+        }
+        let mut outcomes = Vec::with_capacity(writes.len());
+        for wr in writes {
+            let slot = staged.get_mut(wr.key()).expect("staged above");
+            outcomes.push(self.stage_write(slot, wr, now)?);
+        }
+
+        let mut undo: Vec<(String, Option<Entry>)> = Vec::with_capacity(staged.len());
+        let mut versions = BTreeMap::new();
+        let (mut written, mut deleted) = (Vec::new(), Vec::new());
+        for (key, slot) in staged {
+            let prev = match slot {
+                Some(mut ent) => {
+                    ent.version = next_version();
+                    versions.insert(key.clone(), ent.version);
+                    written.push(key.clone());
+                    w.insert(key.clone(), ent)
+                }
+                None => {
+                    versions.insert(key.clone(), 0);
+                    let prev = w.remove(&key);
+                    if prev.is_some() && !expired.contains(&key) { deleted.push(key.clone()); }
+                    prev
+                }
+            };
+            undo.push((key, prev));
+        }
+        if self.settings.persistence.as_ref().is_some_and(|p| p.enabled) {
+            if let Err(e) = self.write_snapshot(&w) {
+                for (key, prev) in undo {
+                    match prev {
+                        Some(ent) => { w.insert(key, ent); }
+                        None => { w.remove(&key); }
+                    }
+                }
+                anyhow::bail!("kv_batch_not_persisted: {}", e);
+            }
+        }
+        drop(w);
+        self.publish_expired(expired);
+        self.publish("set", written);
+        self.publish("delete", deleted);
+        Ok(KvBatchResult { outcomes, versions })
+    }
+
+    /// Apply one batch write to the staged entry of its key (None: absent).
+    fn stage_write(&self, slot: &mut Option<Entry>, wr: &KvWrite, now: Instant) -> anyhow::Result<KvWriteOutcome> {
+        let reset_default = self.settings.reset_on_access_default;
+        use KvWriteOutcome as O;
+        Ok(match wr {
+            KvWrite::Set { value, ttl, reset_on_access, .. } => {
+                let reset_on_access = reset_on_access.unwrap_or(reset_default);
+                *slot = Some(Entry { value: value.clone(), ttl: *ttl, expires_at: ttl.map(|d| now + d), reset_on_access, version: 0 });
+                O::Written
+            }
+            KvWrite::Delete { .. } => O::Deleted(slot.take().is_some()),
+            KvWrite::Push { key, values, front } => stage_modify(slot, reset_default, Some(KvValue::List(VecDeque::new())), &mut |v| push_values(key, v, values, *front).map(O::Length))?.unwrap_or(O::Length(0)),
+            KvWrite::Pop { key, front } => stage_modify(slot, reset_default, None, &mut |v| pop_value(key, v, *front).map(O::Popped))?.unwrap_or(O::Popped(None)),
+            KvWrite::SetAdd { key, members } => stage_modify(slot, reset_default, Some(KvValue::Set(BTreeSet::new())), &mut |v| add_members(key, v, members).map(O::Added))?.unwrap_or(O::Added(0)),
+            KvWrite::SetRemove { key, members } => stage_modify(slot, reset_default, None, &mut |v| remove_members(key, v, members).map(O::Removed))?.unwrap_or(O::Removed(0)),
+            KvWrite::HashSet { key, fields } => stage_modify(slot, reset_default, Some(KvValue::Hash(BTreeMap::new())), &mut |v| set_fields(key, v, fields).map(O::Added))?.unwrap_or(O::Added(0)),
+            KvWrite::HashDelete { key, fields } => stage_modify(slot, reset_default, None, &mut |v| delete_fields(key, v, fields).map(O::Removed))?.unwrap_or(O::Removed(0)),
+        })
+    }
+
+    pub fn delete(&self, key: &str) -> bool {
+        let removed = self.map.write().remove(key).is_some();
+        if removed { self.publish("delete", vec![key.to_string()]); }
+        removed
+    }
+    pub fn clear(&self) {
+        let keys: Vec<String> = self.map.write().drain().map(|(k, _)| k).collect();
+        self.publish("delete", keys);
+    }
+    /// Number of live keys; expired keys not yet swept are not counted
+    pub fn len(&self) -> usize {
+        let now = Instant::now();
+        self.map.read().values().filter(|e| !e.is_expired(now)).count()
+    }
+    /// Return a snapshot of all live keys in this store
+    pub fn keys(&self) -> Vec<String> {
+        let now = Instant::now();
+        self.map.read().iter().filter(|(_, e)| !e.is_expired(now)).map(|(k, _)| k.clone()).collect()
+    }
 
     /// Delete keys that start with the provided prefix. Returns number of removed keys.
     pub fn delete_prefix(&self, prefix: &str) -> usize {
         let mut w = self.map.write();
         let to_remove: Vec<String> = w.keys().filter(|k| k.starts_with(prefix)).cloned().collect();
         let n = to_remove.len();
-        for k in to_remove { w.remove(&k); }
+        for k in &to_remove { w.remove(k); }
+        drop(w);
+        self.publish("delete", to_remove);
         n
     }
 
-    /// Remove expired keys. Returns number removed.
+    /// Remove expired keys, publishing an "expired" event for each. Returns number removed.
     pub fn sweep(&self) -> usize {
         let now = Instant::now();
-        let mut removed = 0;
         let mut w = self.map.write();
         let keys: Vec<String> = w.iter()
-            .filter_map(|(k, v)| v.expires_at.map(|exp| (k.clone(), exp)))
-            .filter(|(_, exp)| now >= *exp)
-            .map(|(k, _)| k)
+            .filter(|(_, v)| v.is_expired(now))
+            .map(|(k, _)| k.clone())
             .collect();
-        for k in keys { if w.remove(&k).is_some() { removed += 1; } }
+        for k in &keys { w.remove(k); }
+        drop(w);
+        let removed = keys.len();
+        self.publish_expired(keys);
         removed
     }
 
@@ -314,10 +689,22 @@ impl KvStore {
     pub fn rename_key(&self, from: &str, to: &str) -> bool {
         if from == to { return true; }
         let mut w = self.map.write();
-        if let Some(entry) = w.remove(from) {
-            w.insert(to.to_string(), entry);
-            true
-        } else { false }
+        match w.remove(from) {
+            Some(entry) if entry.is_expired(Instant::now()) => {
+                drop(w);
+                self.publish_expired(vec![from.to_string()]);
+                false
+            }
+            Some(mut entry) => {
+                entry.version = next_version();
+                w.insert(to.to_string(), entry);
+                drop(w);
+                self.publish("delete", vec![from.to_string()]);
+                self.publish("set", vec![to.to_string()]);
+                true
+            }
+            None => false,
+        }
     }
 }
 
@@ -332,7 +719,7 @@ pub struct KvStoresRegistry {
 impl KvStoresRegistry {
     fn new(root: PathBuf) -> Self { Self { root, inner: Arc::new(parking_lot::RwLock::new(StdHashMap::new())) } }
 
-    fn stores_dir_for_db(&self, db: &str) -> PathBuf { self.root.join(db).join("stores") }
+    fn stores_dir_for_db(&self, db: &str) -> PathBuf { super::attach::database_dir(&self.root, db).join("stores") }
 
     /// List existing KV stores for a database by scanning the filesystem under <db>/stores
     pub fn list_stores(&self, database: &str) -> Vec<String> {
@@ -356,13 +743,26 @@ impl KvStoresRegistry {
         // Create path and load settings
         let dir = self.stores_dir_for_db(database).join(store_name);
         std::fs::create_dir_all(&dir).ok();
-        let kv = KvStore::load_or_default(dir, store_name);
+        let kv = KvStore::load_or_default(dir, database, store_name);
         let mut w = self.inner.write();
         let entry = w.entry(database.to_string()).or_default();
         entry.insert(store_name.to_string(), kv.clone());
         kv
     }
 
+    /// Settings of a store and its key count when it is loaded in memory. Unlike
+    /// `get_store`, this never creates or loads the store.
+    pub fn store_info(&self, database: &str, store_name: &str) -> (StoreSettings, Option<usize>) {
+        if let Some(st) = self.inner.read().get(database).and_then(|m| m.get(store_name)).cloned() {
+            return (st.settings.clone(), Some(st.len()));
+        }
+        let dir = self.stores_dir_for_db(database).join(store_name);
+        let settings = [dir.join("store.json"), dir.join("config.json")].iter()
+            .find_map(|p| std::fs::read(p).ok().and_then(|b| serde_json::from_slice::<StoreSettings>(&b).ok()))
+            .unwrap_or_else(|| StoreSettings { name: store_name.to_string(), ..StoreSettings::default() });
+        (settings, None)
+    }
+
     /// Drop a store: remove from registry and delete its directory. Returns true if it existed.
     pub fn drop_store(&self, database: &str, store_name: &str) -> anyhow::Result<bool> {
         let dir = self.stores_dir_for_db(database).join(store_name);
@@ -400,7 +800,7 @@ impl KvStoresRegistry {
                 // Recreate with new dir, keeping settings but updating name
                 let mut settings = kv.settings.clone();
                 settings.name = to.to_string();
-                let new_kv = KvStore::new(dst.clone(), settings);
+                let new_kv = KvStore::new(dst.clone(), database, settings);
                 // Persist settings to config.json
                 let _ = new_kv.save_settings();
                 m.insert(to.to_string(), new_kv);
@@ -409,6 +809,21 @@ impl KvStoresRegistry {
         Ok(())
     }
 
+    /// Write a fresh snapshot for every loaded store of `database`; returns the number saved.
+    pub fn snapshot_database(&self, database: &str) -> anyhow::Result<usize> {
+        let stores: Vec<KvStore> = self.inner.read().get(database).map(|m| m.values().cloned().collect()).unwrap_or_default();
+        for kv in &stores { kv.save_snapshot()?; }
+        Ok(stores.len())
+    }
+
+    /// Drop cached stores of `database` and load each store's on-disk snapshot (used by RESTORE).
+    pub fn reload_database(&self, database: &str) -> usize {
+        self.inner.write().remove(database);
+        let names = self.list_stores(database);
+        for name in &names { let _ = self.get_store(database, name).load_snapshot(); }
+        names.len()
+    }
+
     /// Sweep all stores, return total removed count
     pub fn sweep_all(&self) -> usize {
         let mut total = 0;
@@ -437,9 +852,9 @@ impl SharedStore {
         let root_path = root.as_ref().to_path_buf();
         // Create the underlying store
         let s = Self(Arc::new(parking_lot::Mutex::new(crate::storage::Store::new(&root_path)?)));
-        // One-time schema migration on startup for this root: upgrade legacy schema.json files
-        // to nested { columns: {...}, locks: [...] } and ensure explicit tableType.
-        let _ = crate::storage::schema::migrate_all_schemas_for_root(&root_path);
+        // Bring every schema.json under this root to the current on-disk format version.
+        // Fails (and refuses to open the root) if any table was written by a newer build.
+        crate::storage::migrate::run_startup_migrations(&root_path)?;
         // Seed and load system views (.view JSON format with column schemas)
         crate::system_views::load_system_views_for_root(&root_path);
         // Seed UDF scripts into <root>/.system/udf from repo scripts if missing
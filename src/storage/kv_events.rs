@@ -0,0 +1,158 @@
+//! KV key events: an in-process bus, watches and webhook delivery.
+//!
+//! Writing a key publishes a "set" KvEvent and removing one a "delete"; a key removed because
+//! its TTL lapsed, by the background sweeper or lazily when it is next touched, publishes
+//! "expired". Events go out on a broadcast channel (`subscribe`), to each registered watch on
+//! the key or a prefix of it (`watch`; WATCH KEY / WATCH PREFIX over the WebSocket endpoint),
+//! and to each webhook in the store's `store.json` whose kinds and key prefix match, through
+//! `StorageHooks::deliver_webhook` to the server's filestore webhook worker (same headers,
+//! signing and retries).
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use super::webhooks::{Delivery, WebhookConfig};
+
+const BROADCAST_CAPACITY: usize = 4096;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KvEvent {
+    /// Unique per event; sent as the delivery id
+    pub id: String,
+    /// "set", "delete" or "expired"
+    pub kind: String,
+    pub database: String,
+    pub store: String,
+    pub key: String,
+    pub at: i64,
+}
+
+impl KvEvent {
+    pub fn new(kind: &str, database: &str, store: &str, key: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            database: database.to_string(),
+            store: store.to_string(),
+            key: key.to_string(),
+            at: Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Whether `hook` wants `ev`: its kinds (all when unset) and a key prefix.
+fn matches(hook: &WebhookConfig, ev: &KvEvent) -> bool {
+    let kind_ok = hook.events.as_ref().map(|ks| ks.iter().any(|k| k.eq_ignore_ascii_case(&ev.kind))).unwrap_or(true);
+    kind_ok && hook.prefix.as_deref().map(|p| ev.key.starts_with(p)).unwrap_or(true)
+}
+
+static CHANNEL: Lazy<tokio::sync::broadcast::Sender<KvEvent>> =
+    Lazy::new(|| tokio::sync::broadcast::channel(BROADCAST_CAPACITY).0);
+
+/// Subscribe to key events of every KV store in this process.
+pub fn subscribe() -> tokio::sync::broadcast::Receiver<KvEvent> { CHANNEL.subscribe() }
+
+/// Keys a watch follows within its store.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchTarget {
+    Key(String),
+    Prefix(String),
+}
+
+impl WatchTarget {
+    pub fn matches(&self, key: &str) -> bool {
+        match self {
+            WatchTarget::Key(k) => k == key,
+            WatchTarget::Prefix(p) => key.starts_with(p.as_str()),
+        }
+    }
+}
+
+/// What a watch receiver gets: an event of a watched key, or notice that it fell
+/// `WATCH_CAPACITY` messages behind and every watch delivering to it was dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchMessage {
+    Event { watch: u64, event: KvEvent },
+    Lagged,
+}
+
+/// Queued messages per receiver before its watches are dropped as lagged.
+pub const WATCH_CAPACITY: usize = 1024;
+
+struct Watcher {
+    database: String,
+    store: String,
+    target: WatchTarget,
+    tx: mpsc::Sender<WatchMessage>,
+}
+
+static WATCHES: Lazy<RwLock<HashMap<u64, Watcher>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static NEXT_WATCH: AtomicU64 = AtomicU64::new(1);
+
+/// A channel for watches: each watch registered with the sender delivers to the receiver.
+pub fn watch_channel() -> (mpsc::Sender<WatchMessage>, mpsc::Receiver<WatchMessage>) {
+    mpsc::channel(WATCH_CAPACITY)
+}
+
+/// Register a watch on `target` in `database`.store.`store`; returns its id. It lasts until
+/// `unwatch`, its receiver is dropped, or it lags.
+pub fn watch(database: &str, store: &str, target: WatchTarget, tx: mpsc::Sender<WatchMessage>) -> u64 {
+    let id = NEXT_WATCH.fetch_add(1, Ordering::Relaxed);
+    WATCHES.write().insert(id, Watcher { database: database.to_string(), store: store.to_string(), target, tx });
+    id
+}
+
+/// Remove a watch; false when it was not registered.
+pub fn unwatch(id: u64) -> bool { WATCHES.write().remove(&id).is_some() }
+
+/// Number of registered watches.
+pub fn watch_count() -> usize { WATCHES.read().len() }
+
+/// Deliver `ev` to the watches on its key. A receiver that is gone, or full but for the slot
+/// kept for the lag notice, loses all of its watches.
+fn notify_watches(ev: &KvEvent) {
+    let mut lagging: Vec<mpsc::Sender<WatchMessage>> = Vec::new();
+    {
+        let watches = WATCHES.read();
+        for (id, w) in watches.iter() {
+            if w.database != ev.database || w.store != ev.store || !w.target.matches(&ev.key) { continue; }
+            if lagging.iter().any(|tx| tx.same_channel(&w.tx)) { continue; }
+            let sent = w.tx.capacity() > 1 && w.tx.try_send(WatchMessage::Event { watch: *id, event: ev.clone() }).is_ok();
+            if !sent {
+                let _ = w.tx.try_send(WatchMessage::Lagged);
+                lagging.push(w.tx.clone());
+            }
+        }
+    }
+    if !lagging.is_empty() {
+        WATCHES.write().retain(|_, w| !lagging.iter().any(|tx| tx.same_channel(&w.tx)));
+    }
+}
+
+/// Whether an event of a store with `webhooks` would reach anyone; lets writers skip building it.
+pub fn has_listeners(webhooks: &[WebhookConfig]) -> bool {
+    !webhooks.is_empty() || CHANNEL.receiver_count() > 0 || !WATCHES.read().is_empty()
+}
+
+/// Publish `ev` to subscribers and watches and queue it for the store's matching webhooks.
+pub fn publish(webhooks: &[WebhookConfig], ev: KvEvent) {
+    let matching: Vec<&WebhookConfig> = webhooks.iter().filter(|h| matches(h, &ev)).collect();
+    if !matching.is_empty() {
+        if let Ok(body) = serde_json::to_vec(&ev) {
+            let hooks = super::hooks::get();
+            for hook in matching {
+                hooks.deliver_webhook(Delivery { hook: hook.clone(), database: ev.database.clone(), scope: format!("store.{}", ev.store), kind: ev.kind.clone(), id: ev.id.clone(), body: body.clone() });
+            }
+        }
+    }
+    crate::tprintln!("KV event {} {}.store.{} key={} id={}", ev.kind, ev.database, ev.store, ev.key, ev.id);
+    notify_watches(&ev);
+    // No receivers is fine
+    let _ = CHANNEL.send(ev);
+}
@@ -0,0 +1,123 @@
+//! Late-arriving rows for time tables.
+//!
+//! A table's high-water mark is the largest `_time` among its chunks (taken from
+//! the chunk file names). Incoming rows older than the high-water mark minus the
+//! table's reorder window are *late*; rows inside the window are treated as merely
+//! out of order and are sorted into the batch's chunk as usual. What happens to
+//! late rows is configured with `"lateData": {"policy": ..., "windowMs": ...}` in
+//! schema.json (set by `ALTER TABLE ... SET LATE DATA MERGE | SEPARATE | OFF [WINDOW <ms>]`):
+//!
+//! - `off` (default): late rows stay in the batch's chunk, whose time range then
+//!   overlaps older chunks.
+//! - `separate`: late rows are written to a chunk of their own so the on-time
+//!   chunk keeps a tight range; `COMPACT TABLE` folds them in later.
+//! - `merge`: late rows are merged into the existing chunks their times overlap,
+//!   which are rewritten as one sorted chunk.
+//!
+//! Late rows are counted in the table's ingest stats under every policy.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::io::{parse_chunk_min_max, stack_aligned};
+use super::Store;
+
+pub const LATE_KEY: &str = "lateData";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LatePolicy {
+    #[default]
+    Off,
+    Separate,
+    Merge,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LateSetting {
+    #[serde(default)]
+    pub policy: LatePolicy,
+    /// Rows up to this many ms behind the high-water mark are not late.
+    #[serde(default)]
+    pub window_ms: i64,
+}
+
+/// Schema.json value for a late-data setting (`None` removes it).
+pub fn setting_value(setting: LateSetting) -> Option<serde_json::Value> {
+    if setting == LateSetting::default() { return None; }
+    serde_json::to_value(setting).ok()
+}
+
+pub(crate) fn get_late_setting(store: &Store, table: &str) -> LateSetting {
+    fs::read_to_string(store.schema_path(table)).ok()
+        .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+        .and_then(|v| v.get(LATE_KEY).cloned())
+        .and_then(|v| serde_json::from_value::<LateSetting>(v).ok())
+        .unwrap_or_default()
+}
+
+/// Time-ranged chunks of a table directory with their `(min, max)` times.
+fn time_chunks(dir: &std::path::Path) -> Vec<(PathBuf, i64, i64)> {
+    super::partition::chunk_files(dir).into_iter()
+        .filter_map(|p| {
+            let name = p.file_name()?.to_string_lossy().to_string();
+            parse_chunk_min_max(&name).map(|(lo, hi)| (p, lo, hi))
+        })
+        .collect()
+}
+
+impl Store {
+    /// Late-data setting of `table`.
+    pub fn get_late_setting(&self, table: &str) -> LateSetting { get_late_setting(self, table) }
+
+    /// Largest `_time` stored in `table`'s chunks, if it has any.
+    pub fn time_high_water(&self, table: &str) -> Option<i64> {
+        time_chunks(&self.db_dir(table)).into_iter().map(|(_, _, hi)| hi).max()
+    }
+
+    /// Split an incoming time-table batch into `(on_time, late)` against the table's high-water mark.
+    pub(crate) fn split_late(&self, table: &str, df: DataFrame, window_ms: i64) -> Result<(DataFrame, DataFrame)> {
+        let Some(hw) = self.time_high_water(table) else { return Ok((df, DataFrame::empty())) };
+        let cutoff = hw.saturating_sub(window_ms.max(0));
+        let times = df.column("_time")?.i64()?;
+        let late_mask: BooleanChunked = times.lt(cutoff);
+        if !late_mask.any() { return Ok((df, DataFrame::empty())); }
+        let late = df.filter(&late_mask)?;
+        let on_time = df.filter(&!&late_mask)?;
+        Ok((on_time, late))
+    }
+
+    /// Merge late rows into the chunks their time range overlaps, rewriting those
+    /// chunks as one sorted chunk. Without overlapping chunks the rows get their own chunk.
+    pub(crate) fn merge_late_rows(&self, table: &str, late: DataFrame) -> Result<()> {
+        let times = late.column("_time")?.i64()?;
+        let (Some(lo), Some(hi)) = (times.min(), times.max()) else { return Ok(()) };
+        let targets: Vec<PathBuf> = time_chunks(&self.db_dir(table)).into_iter()
+            .filter(|(_, cmin, cmax)| *cmax >= lo && *cmin <= hi)
+            .map(|(p, _, _)| p)
+            .collect();
+        let mut frames: Vec<DataFrame> = Vec::with_capacity(targets.len() + 1);
+        for p in &targets { frames.push(super::tombstone::read_live_chunk(p)?); }
+        frames.push(late);
+        let mut merged = stack_aligned(frames)?
+            .sort(["_time"], SortMultipleOptions::default().with_maintain_order(true))?;
+        let replaced_since = super::checksum::oldest_write(&targets);
+        let written = self.write_time_chunk(table, &mut merged)?;
+        super::checksum::record_rewrite(&written, replaced_since)?;
+        for p in targets {
+            // Tombstoned rows were left out of the merge
+            let _ = fs::remove_file(super::tombstone::sidecar_path(&p));
+            if written.contains(&p) { continue; }
+            let _ = fs::remove_file(super::bloom::sidecar_path(&p));
+            let _ = fs::remove_file(super::checksum::sidecar_path(&p));
+            fs::remove_file(&p)?;
+        }
+        crate::tprintln!("[storage.late] merged late rows [{}, {}] into {:?}", lo, hi, written);
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+//! On-disk format versioning and migrations.
+//!
+//! Every table's schema.json carries a `formatVersion`. Files written before the
+//! key existed are treated as version 0. At startup `run_startup_migrations` walks
+//! `<root>/<db>/<schema>/<table>/schema.json` (and the tables of attached databases)
+//! and applies the registered steps in order until each file reaches `FORMAT_VERSION`.
+//! A file declaring a version newer than this build understands aborts startup rather
+//! than risking a lossy rewrite. `ATTACH DATABASE` runs the same check and steps on
+//! the folder it attaches.
+//!
+//! Every applied step is appended to `<root>/.system/migrations.json` so operators
+//! can audit what changed and when.
+//!
+//! To add a format change: bump `FORMAT_VERSION`, append a `Migration` with
+//! `from = FORMAT_VERSION - 1`, and provide a `down` step when the change can be
+//! reversed without losing information.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use serde_json::{json, Map, Value};
+
+use crate::tprintln;
+
+/// Current schema.json format version written by this build.
+pub const FORMAT_VERSION: u64 = 2;
+
+/// Key in schema.json recording the format version.
+pub const FORMAT_VERSION_KEY: &str = "formatVersion";
+
+/// Metadata keys that are never column entries in the legacy flat layout.
+const META_KEYS: &[&str] = &[
+    "columns", "locks", "PRIMARY", "primaryKey", "partitions", "tableType",
+    "constraints", "bloomColumns", "cdc", "comments", "triggers", "computed", "ingestMode",
+    "encryption", "dedup", "lateData", "partitioning", "collations", "nullable", FORMAT_VERSION_KEY,
+];
+
+/// True for schema.json keys that hold table metadata rather than a column.
+pub(crate) fn is_meta_key(key: &str) -> bool { META_KEYS.contains(&key) }
+
+type StepFn = fn(table_dir: &Path, obj: &mut Map<String, Value>) -> Result<()>;
+
+/// One reversible (or upgrade-only) step between adjacent format versions.
+pub struct Migration {
+    pub from: u64,
+    pub name: &'static str,
+    pub up: StepFn,
+    pub down: Option<StepFn>,
+}
+
+impl Migration {
+    pub fn to(&self) -> u64 { self.from + 1 }
+}
+
+/// Registered migrations, ordered by `from`.
+pub fn migrations() -> &'static [Migration] {
+    static MIGRATIONS: &[Migration] = &[
+        Migration { from: 0, name: "explicit_table_type", up: up_explicit_table_type, down: Some(down_explicit_table_type) },
+        Migration { from: 1, name: "nested_columns", up: up_nested_columns, down: Some(down_nested_columns) },
+    ];
+    MIGRATIONS
+}
+
+fn dir_has_time_suffix(table_dir: &Path) -> bool {
+    table_dir.file_name().and_then(|s| s.to_str()).map(|s| s.ends_with(".time")).unwrap_or(false)
+}
+
+/// v0 -> v1: replace the `.time` directory-name heuristic with an explicit `tableType`.
+fn up_explicit_table_type(table_dir: &Path, obj: &mut Map<String, Value>) -> Result<()> {
+    if !obj.contains_key("tableType") {
+        let tt = if dir_has_time_suffix(table_dir) { "time" } else { "regular" };
+        obj.insert("tableType".into(), json!(tt));
+    }
+    Ok(())
+}
+
+fn down_explicit_table_type(table_dir: &Path, obj: &mut Map<String, Value>) -> Result<()> {
+    // Only reversible when the directory name still implies the same table type
+    let is_time = obj.get("tableType").and_then(|v| v.as_str()).map(|s| s.eq_ignore_ascii_case("time")).unwrap_or(false);
+    if is_time != dir_has_time_suffix(table_dir) {
+        bail!("cannot downgrade '{}': tableType does not match directory name", table_dir.display());
+    }
+    obj.remove("tableType");
+    Ok(())
+}
+
+/// v1 -> v2: move flat `{name: dtype}` entries under a nested `columns` object.
+fn up_nested_columns(_table_dir: &Path, obj: &mut Map<String, Value>) -> Result<()> {
+    if obj.get("columns").and_then(|x| x.as_object()).is_some() { return Ok(()); }
+    let mut cols = Map::new();
+    let keys: Vec<String> = obj.keys().cloned().collect();
+    for k in keys {
+        if META_KEYS.contains(&k.as_str()) { continue; }
+        if obj.get(&k).map(|v| v.is_string()).unwrap_or(false) {
+            if let Some(v) = obj.remove(&k) { cols.insert(k, v); }
+        }
+    }
+    obj.insert("columns".into(), Value::Object(cols));
+    Ok(())
+}
+
+fn down_nested_columns(_table_dir: &Path, obj: &mut Map<String, Value>) -> Result<()> {
+    if let Some(Value::Object(cols)) = obj.remove("columns") {
+        for (k, v) in cols {
+            if META_KEYS.contains(&k.as_str()) {
+                bail!("cannot downgrade: column '{}' collides with a metadata key", k);
+            }
+            obj.insert(k, v);
+        }
+    }
+    Ok(())
+}
+
+/// Read the declared format version of a schema.json object (missing = 0).
+pub fn format_version_of(obj: &Map<String, Value>) -> u64 {
+    obj.get(FORMAT_VERSION_KEY).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+/// Path of the migration history log for a root.
+pub fn history_path(root: &Path) -> PathBuf { crate::system_paths::system_root(root).join("migrations.json") }
+
+/// Load the recorded migration history (empty when none has run yet).
+pub fn load_history(root: &Path) -> Vec<Value> {
+    std::fs::read_to_string(history_path(root)).ok()
+        .and_then(|t| serde_json::from_str::<Value>(&t).ok())
+        .and_then(|v| v.as_array().cloned())
+        .unwrap_or_default()
+}
+
+fn append_history(root: &Path, entries: Vec<Value>) -> Result<()> {
+    if entries.is_empty() { return Ok(()); }
+    let mut all = load_history(root);
+    all.extend(entries);
+    let p = history_path(root);
+    if let Some(parent) = p.parent() { std::fs::create_dir_all(parent)?; }
+    std::fs::write(&p, serde_json::to_string_pretty(&Value::Array(all))?)?;
+    Ok(())
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// Move one schema.json object from its current version to `target`, applying
+/// up or down steps as needed. Returns the names of the steps applied.
+fn migrate_obj(table_dir: &Path, obj: &mut Map<String, Value>, target: u64) -> Result<Vec<(u64, u64, &'static str)>> {
+    let mut applied = Vec::new();
+    let mut v = format_version_of(obj);
+    if v > FORMAT_VERSION {
+        bail!(
+            "{} uses on-disk format version {} but this build supports up to {}; refusing to open",
+            table_dir.join("schema.json").display(), v, FORMAT_VERSION
+        );
+    }
+    while v < target {
+        let m = migrations().iter().find(|m| m.from == v)
+            .ok_or_else(|| anyhow::anyhow!("no migration registered from format version {}", v))?;
+        (m.up)(table_dir, obj)?;
+        applied.push((v, m.to(), m.name));
+        v = m.to();
+    }
+    while v > target {
+        let m = migrations().iter().find(|m| m.to() == v)
+            .ok_or_else(|| anyhow::anyhow!("no migration registered to format version {}", v))?;
+        let down = m.down.ok_or_else(|| anyhow::anyhow!("migration '{}' cannot be reversed", m.name))?;
+        down(table_dir, obj)?;
+        applied.push((v, m.from, m.name));
+        v = m.from;
+    }
+    if target == 0 { obj.remove(FORMAT_VERSION_KEY); } else { obj.insert(FORMAT_VERSION_KEY.into(), json!(target)); }
+    Ok(applied)
+}
+
+fn subdirs(p: &Path) -> Vec<PathBuf> {
+    let Ok(rd) = std::fs::read_dir(p) else { return Vec::new() };
+    rd.filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .filter(|p| !p.file_name().and_then(|s| s.to_str()).map(|s| s.starts_with('.')).unwrap_or(false))
+        .collect()
+}
+
+/// Every table directory (containing a schema.json) of the database folder `db_dir`,
+/// keyed by `<db>/<schema>/<table>`.
+fn database_table_dirs(db: &str, db_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut out = Vec::new();
+    for sch in subdirs(db_dir) {
+        for tab in subdirs(&sch) {
+            if !tab.join("schema.json").exists() { continue; }
+            let name = |p: &Path| p.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            out.push((format!("{}/{}/{}", db, name(&sch), name(&tab)), tab));
+        }
+    }
+    out
+}
+
+/// Every table directory under `root` and its attached databases (see `storage::attach`),
+/// skipping hidden folders.
+fn table_dirs(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut out = Vec::new();
+    for db in subdirs(root) {
+        let name = db.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        out.extend(database_table_dirs(&name, &db));
+    }
+    for (name, dir) in super::attach::attached(root) {
+        out.extend(database_table_dirs(&name, &dir));
+    }
+    out.sort();
+    out
+}
+
+/// Migrate the schema.json of every table in `tables` to `target`, recording the steps in
+/// `root`'s history. Fails before touching any file if one of them declares a format newer
+/// than this build supports.
+fn migrate_tables(root: &Path, tables: Vec<(String, PathBuf)>, target: u64) -> Result<usize> {
+    if target > FORMAT_VERSION { bail!("target format version {} is newer than supported {}", target, FORMAT_VERSION); }
+    let mut pending: Vec<(String, PathBuf, Map<String, Value>)> = Vec::new();
+    for (rel, dir) in tables {
+        let sj = dir.join("schema.json");
+        let Ok(text) = std::fs::read_to_string(&sj) else { continue };
+        let Ok(Value::Object(obj)) = serde_json::from_str::<Value>(&text) else { continue };
+        if format_version_of(&obj) > FORMAT_VERSION {
+            bail!(
+                "{} uses on-disk format version {} but this build supports up to {}; refusing to open",
+                sj.display(), format_version_of(&obj), FORMAT_VERSION
+            );
+        }
+        pending.push((rel, dir, obj));
+    }
+    let mut updated = 0usize;
+    let mut history: Vec<Value> = Vec::new();
+    for (rel, dir, mut obj) in pending {
+        let before = obj.clone();
+        let applied = migrate_obj(&dir, &mut obj, target)?;
+        if obj == before { continue; }
+        std::fs::write(dir.join("schema.json"), serde_json::to_string_pretty(&Value::Object(obj))?)?;
+        updated += 1;
+        let at = now_ms();
+        for (from, to, name) in applied {
+            history.push(json!({"table": rel, "from": from, "to": to, "migration": name, "at": at}));
+        }
+    }
+    append_history(root, history)?;
+    Ok(updated)
+}
+
+/// Migrate every schema.json under `root` (attached databases included) to `target`.
+/// Fails before touching any file if one of them declares a format newer than this
+/// build supports.
+pub fn migrate_root_to(root: &Path, target: u64) -> Result<usize> {
+    if !root.exists() { return Ok(0); }
+    let updated = migrate_tables(root, table_dirs(root), target)?;
+    if updated > 0 { tprintln!("[MIGRATE] migrate_root_to({}): updated {} schema.json files", target, updated); }
+    Ok(updated)
+}
+
+/// Bring the database folder `db_dir`, about to be attached to `root` as `db`, to
+/// `FORMAT_VERSION` with the same steps and checks as startup. Fails without changing
+/// anything when one of its tables was written by a newer build.
+pub fn migrate_database_dir(root: &Path, db: &str, db_dir: &Path) -> Result<usize> {
+    let updated = migrate_tables(root, database_table_dirs(db, db_dir), FORMAT_VERSION)?;
+    if updated > 0 { tprintln!("[MIGRATE] database '{}' ({}): updated {} schema.json files", db, db_dir.display(), updated); }
+    Ok(updated)
+}
+
+/// Startup entry point: upgrade every table under `root` to `FORMAT_VERSION`.
+pub fn run_startup_migrations(root: &Path) -> Result<usize> { migrate_root_to(root, FORMAT_VERSION) }
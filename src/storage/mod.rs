@@ -27,8 +27,35 @@ use tracing::debug;
 
 mod paths;
 pub mod kv;
+pub mod kv_events;
 pub mod schema;
 mod io;
+pub mod bloom;
+pub mod migrate;
+pub mod cdc;
+pub mod comments;
+pub mod analyze;
+pub mod attach;
+pub mod azure;
+pub mod backup;
+pub mod checksum;
+pub mod compaction;
+pub mod computed;
+pub mod decimal;
+pub mod dedup;
+pub mod encryption;
+pub mod hooks;
+pub mod ingest_mode;
+pub mod ingest_stats;
+pub mod late;
+pub mod native;
+pub mod partition;
+pub mod pruning;
+pub mod s3;
+pub mod tombstone;
+pub mod triggers;
+pub mod versions;
+pub mod webhooks;
 
 /// Core on-disk storage handle for a clarium table directory tree.
 ///
@@ -44,8 +71,8 @@ pub struct Store {
 
 /// A single logical row to ingest into a clarium table.
 ///
-/// Fields other than `_time` are flattened under `sensors` and may be numeric
-/// or string. During ingestion, types are inferred per-column and may be widened
+/// Fields other than `_time` are flattened under `sensors` and may be numeric,
+/// boolean or string. During ingestion, types are inferred per-column and may be widened
 /// across batches (Int64 -> Float64 -> String) unless locked in the schema.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Record {
@@ -129,6 +156,7 @@ impl Store {
             } else {
                 meta.insert("tableType".into(), serde_json::json!("regular"));
             }
+            meta.insert(migrate::FORMAT_VERSION_KEY.into(), serde_json::json!(migrate::FORMAT_VERSION));
             fs::write(&schema_path, serde_json::to_string_pretty(&serde_json::Value::Object(meta))?)?;
             debug!(target: "clarium::storage", "create_table: wrote initial schema.json for table='{}'", table);
         }
@@ -179,46 +207,7 @@ impl Store {
     }
 
     pub(crate) fn dtype_to_str(dt: &DataType) -> String {
-        match dt {
-            DataType::String => "string".into(),
-            DataType::Int64 => "int64".into(),
-            // Prefer explicit array syntax using [] and preserve inner dtype
-            DataType::List(inner) => match inner.as_ref() {
-                DataType::Float64 => "float64[]".into(),
-                DataType::Int64 => "int64[]".into(),
-                DataType::String => "string[]".into(),
-                _ => "list".into(),
-            },
-            _ => "float64".into(),
-        }
-    }
-    fn str_to_dtype(s: &str) -> DataType {
-        match s.to_ascii_lowercase().as_str() {
-            "utf8" | "string" => DataType::String,
-            "int64" => DataType::Int64,
-            // Back-compat: logical 'vector' maps to List(Float64)
-            "vector" => DataType::List(Box::new(DataType::Float64)),
-            // New explicit array syntax
-            "float64[]" | "double[]" => DataType::List(Box::new(DataType::Float64)),
-            "int64[]" | "bigint[]" => DataType::List(Box::new(DataType::Int64)),
-            "string[]" | "utf8[]" | "text[]" => DataType::List(Box::new(DataType::String)),
-            _ => DataType::Float64,
-        }
-    }
-
-    fn merge_dtype(a: DataType, b: DataType) -> DataType {
-        use DataType::*;
-        match (a, b) {
-            (String, _) | (_, String) => String,
-            // Do not implicitly widen to/from vectors. If any side is List, keep List if other side is numeric; else fall back to String.
-            (List(a), List(b)) => {
-                if *a == *b { List(a) } else { String }
-            }
-            (List(a), Float64) | (Float64, List(a)) => List(a),
-            (List(a), Int64) | (Int64, List(a)) => List(a),
-            (Float64, _) | (_, Float64) => Float64,
-            _ => Int64,
-        }
+        schema::dtype_to_str(dt)
     }
 
     fn infer_dtypes(records: &[Record], names: &[String]) -> std::collections::HashMap<String, DataType> {
@@ -231,6 +220,8 @@ impl Store {
             let mut any_list_float = false;
             let mut any_list_int = false;
             let mut any_list_string = false;
+            let mut any_bool = false;
+            let mut any_null = false;
             let mut saw_value = false;
             for r in records {
                 if let Some(val) = r.sensors.get(name) {
@@ -271,6 +262,8 @@ impl Store {
                         serde_json::Value::Number(n) => {
                             if n.as_i64().is_some() { saw_value = true; } else { any_float = true; saw_value = true; }
                         }
+                        serde_json::Value::Bool(_) => { any_bool = true; }
+                        serde_json::Value::Null => { any_null = true; }
                         _ => {}
                     }
                 }
@@ -283,6 +276,9 @@ impl Store {
                 else if any_string_label { DataType::String }
                 else if any_float { DataType::Float64 }
                 else if saw_value { DataType::Int64 }
+                else if any_bool { DataType::Boolean }
+                // Only nulls so far: the first typed value decides (see `merge_dtype`)
+                else if any_null { DataType::Null }
                 else { DataType::Float64 };
             if cfg!(debug_assertions) {
                 crate::tprintln!("[storage.infer_dtypes] name='{}' any_list={} list_int={} list_float={} list_string={} any_float={} any_string_label={} -> {:?}", name, any_list, any_list_int, any_list_float, any_list_string, any_float, any_string_label, dt);
@@ -307,7 +303,7 @@ impl Store {
 pub struct SharedStore(pub Arc<Mutex<Store>>);
 
 // Re-export KV submodule API (declared once at top of file)
-pub use kv::{KvStore, KvStoresRegistry, KvValue, StoreSettings, PersistenceSettings};
+pub use kv::{KvStore, KvStoresRegistry, KvValue, KvWrite, KvWriteOutcome, KvBatchResult, StoreSettings, PersistenceSettings};
 
 #[cfg(test)]
 #[path = "storage_tests.rs"]
@@ -0,0 +1,173 @@
+//! BOOLEAN and TIMESTAMP columns.
+//!
+//! A `boolean` column is stored as a Parquet boolean and a `timestamp` column as
+//! `Datetime(ms)` without a time zone; schema.json spells them `"bool"` and `"timestamp"`.
+//! Readers of the Parquet files therefore see true/false and timestamps rather than 0/1
+//! integers and epoch numbers.
+//!
+//! Values usually arrive as text (`true`, `'2024-05-01 12:00:00'`) or numbers, both from JSON
+//! ingestion (`write_records`) and from frames built out of SQL literals (INSERT).
+//! `conform_declared_columns` converts such frames to the declared BOOLEAN, TIMESTAMP and
+//! NUMERIC types before they are stored. Timestamps without an offset are taken as UTC and
+//! numbers as epoch milliseconds.
+
+use anyhow::Result;
+use polars::prelude::*;
+
+use super::Store;
+
+/// Type of a `timestamp` column.
+pub fn timestamp_dtype() -> DataType { DataType::Datetime(TimeUnit::Milliseconds, None) }
+
+/// PostgreSQL's boolean spellings: `true`/`false`, `t`/`f`, `yes`/`no`, `on`/`off`, `1`/`0`.
+pub fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "f" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Epoch milliseconds of an ISO 8601 / RFC 3339 timestamp or an integer.
+pub fn parse_timestamp_ms(text: &str) -> Option<i64> {
+    let t = text.trim();
+    if let Ok(ms) = t.parse::<i64>() { return Some(ms); }
+    parse_iso8601_to_ms(t)
+}
+
+pub fn parse_iso8601_to_ms(tok: &str) -> Option<i64> {
+    // Accept bare or single-quoted ISO 8601/RFC3339 timestamps and common variants without timezone (assume UTC)
+    let mut s = tok.trim();
+    if s.len() >= 2 {
+        let first = s.as_bytes()[0] as char;
+        let last = s.as_bytes()[s.len()-1] as char;
+        if first == '\'' && last == '\'' {
+            s = &s[1..s.len()-1];
+        }
+    }
+    // Try RFC3339 (e.g., 2025-01-01T00:00:00Z or with offset)
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Some(dt.timestamp_millis());
+    }
+    // Try NaiveDateTime with T separator, assume UTC
+    if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f") {
+        let dt = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(ndt, chrono::Utc);
+        return Some(dt.timestamp_millis());
+    }
+    // Try NaiveDateTime with space separator
+    if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f") {
+        let dt = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(ndt, chrono::Utc);
+        return Some(dt.timestamp_millis());
+    }
+    // Try date-only (YYYY-MM-DD) at midnight UTC
+    if let Ok(nd) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        if let Some(ndt) = nd.and_hms_opt(0, 0, 0) {
+            let dt = chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(ndt, chrono::Utc);
+            return Some(dt.timestamp_millis());
+        }
+    }
+    None
+}
+
+/// JSON form of a cell: numbers and booleans as themselves, lists as arrays, other
+/// values as their text. Used for CDC events and COPY rows.
+pub fn anyvalue_to_json(av: AnyValue) -> serde_json::Value {
+    match av {
+        AnyValue::Null => serde_json::Value::Null,
+        AnyValue::Boolean(b) => serde_json::Value::Bool(b),
+        AnyValue::Int8(v) => serde_json::Value::from(v),
+        AnyValue::Int16(v) => serde_json::Value::from(v),
+        AnyValue::Int32(v) => serde_json::Value::from(v),
+        AnyValue::Int64(v) => serde_json::Value::from(v),
+        AnyValue::UInt8(v) => serde_json::Value::from(v),
+        AnyValue::UInt16(v) => serde_json::Value::from(v),
+        AnyValue::UInt32(v) => serde_json::Value::from(v),
+        AnyValue::UInt64(v) => serde_json::Value::from(v),
+        AnyValue::Float32(v) => serde_json::Number::from_f64(v as f64).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+        AnyValue::Float64(v) => serde_json::Number::from_f64(v).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+        AnyValue::String(s) => serde_json::Value::String(s.to_string()),
+        AnyValue::StringOwned(s) => serde_json::Value::String(s.to_string()),
+        AnyValue::List(s) => serde_json::Value::Array((0..s.len()).map(|i| s.get(i).map(anyvalue_to_json).unwrap_or(serde_json::Value::Null)).collect()),
+        other => serde_json::Value::String(other.to_string()),
+    }
+}
+
+pub fn json_bool(v: &serde_json::Value) -> Option<bool> {
+    match v {
+        serde_json::Value::Bool(b) => Some(*b),
+        serde_json::Value::Number(n) => n.as_f64().map(|f| f != 0.0),
+        serde_json::Value::String(s) => parse_bool(s),
+        _ => None,
+    }
+}
+
+pub fn json_timestamp_ms(v: &serde_json::Value) -> Option<i64> {
+    match v {
+        serde_json::Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        serde_json::Value::String(s) => parse_timestamp_ms(s),
+        _ => None,
+    }
+}
+
+/// `v` in `unit` as epoch milliseconds, rounding down.
+pub fn to_ms(v: i64, unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Nanoseconds => v.div_euclid(1_000_000),
+        TimeUnit::Microseconds => v.div_euclid(1_000),
+        TimeUnit::Milliseconds => v,
+    }
+}
+
+/// PostgreSQL's text form of a timestamp: `2024-05-01 12:00:00`, with milliseconds only when
+/// they are not zero.
+pub fn format_timestamp_ms(ms: i64) -> String {
+    let Some(dt) = chrono::DateTime::<chrono::Utc>::from_timestamp_millis(ms) else { return ms.to_string() };
+    if ms.rem_euclid(1_000) == 0 { dt.format("%Y-%m-%d %H:%M:%S").to_string() } else { dt.format("%Y-%m-%d %H:%M:%S%.3f").to_string() }
+}
+
+/// `c` as a boolean column; numbers are true when not zero and unreadable text is null.
+pub fn cast_bool_column(c: &Column) -> PolarsResult<Column> {
+    let s = c.as_materialized_series();
+    let out: BooleanChunked = match s.dtype() {
+        DataType::Boolean => return Ok(c.clone()),
+        dt if dt.is_primitive_numeric() => s.cast(&DataType::Float64)?.f64()?.iter().map(|v| v.map(|f| f != 0.0)).collect(),
+        _ => s.cast(&DataType::String)?.str()?.iter().map(|v| v.and_then(parse_bool)).collect(),
+    };
+    Ok(out.with_name(c.name().clone()).into_series().into_column())
+}
+
+/// `c` as a `timestamp` column; numbers are epoch milliseconds and unreadable text is null.
+pub fn cast_timestamp_column(c: &Column) -> PolarsResult<Column> {
+    let s = c.as_materialized_series();
+    let ms: Int64Chunked = match s.dtype() {
+        DataType::Datetime(..) | DataType::Date => return Ok(s.cast(&timestamp_dtype())?.into_column()),
+        dt if dt.is_integer() => s.cast(&DataType::Int64)?.i64()?.clone(),
+        dt if dt.is_float() => s.cast(&DataType::Float64)?.f64()?.iter().map(|v| v.map(|f| f as i64)).collect(),
+        _ => s.cast(&DataType::String)?.str()?.iter().map(|v| v.and_then(parse_timestamp_ms)).collect(),
+    };
+    Ok(ms.with_name(c.name().clone()).into_datetime(TimeUnit::Milliseconds, None).into_series().into_column())
+}
+
+impl Store {
+    /// Convert the columns of `df` that `table` declares as BOOLEAN, TIMESTAMP or NUMERIC to the
+    /// declared type, so frames built from literals or computed through other types are stored
+    /// as declared.
+    pub fn conform_declared_columns(&self, table: &str, df: &mut DataFrame) -> Result<()> {
+        let Ok((schema, _)) = self.load_schema_with_locks(table) else { return Ok(()) };
+        for (name, dt) in &schema {
+            let Ok(col) = df.column(name) else { continue };
+            if col.dtype() == dt { continue; }
+            let converted = match dt {
+                DataType::Boolean => cast_bool_column(col)?,
+                DataType::Datetime(..) => cast_timestamp_column(col)?,
+                DataType::Decimal(..) => {
+                    let Some((p, s)) = super::decimal::precision_scale(dt) else { continue };
+                    super::decimal::cast_column(col, p, s)?
+                }
+                _ => continue,
+            };
+            df.replace(name, converted.take_materialized_series())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+//! Explicit table partitioning: one directory per partition under the table folder.
+//!
+//! Partition columns are the existing schema.json `partitions` array; the method
+//! is kept next to it as `"partitioning": {"method": "list" | "hash", "buckets": N}`
+//! (absent means `list`). Rows are routed at write time:
+//!
+//! - list: `<table>/<col>=<value>[/<col2>=<value2>...]/data-*.parquet`, one folder per
+//!   distinct value tuple (nulls go to `_NULL`);
+//! - hash: `<table>/bucket=<n>/data-*.parquet` with `n = fnv1a(values) % buckets`.
+//!
+//! Time tables keep their time-ranged chunk names inside each partition, so time
+//! pruning by file name still applies. Scans skip whole partitions when the WHERE
+//! clause has `col = literal` predicates on the partition columns (for hash, on all
+//! of them). Chunks written before a table was partitioned stay in the table folder
+//! and are never pruned until the table is rewritten (`SET PARTITION BY`, `COMPACT TABLE`).
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+pub const PARTITIONING_KEY: &str = "partitioning";
+const NULL_VALUE: &str = "_NULL";
+const BUCKET_DIR: &str = "bucket";
+/// Upper bound on predicate value combinations tried when pruning hash buckets.
+const MAX_HASH_PROBES: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PartitionMethod {
+    #[default]
+    List,
+    Hash,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PartitioningSetting {
+    #[serde(default)]
+    method: PartitionMethod,
+    #[serde(default)]
+    buckets: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartitionSpec {
+    pub method: PartitionMethod,
+    pub columns: Vec<String>,
+    /// Bucket count for hash partitioning (ignored for list).
+    pub buckets: u32,
+}
+
+impl PartitionSpec {
+    pub fn is_partitioned(&self) -> bool { !self.columns.is_empty() }
+
+    /// Folder (relative to the table folder) for a row with the given rendered values.
+    fn relative_dir(&self, values: &[String]) -> PathBuf {
+        match self.method {
+            PartitionMethod::List => self.columns.iter().zip(values)
+                .map(|(c, v)| format!("{}={}", escape(c), escape(v)))
+                .collect(),
+            PartitionMethod::Hash => PathBuf::from(format!("{}={}", BUCKET_DIR, bucket_of(values, self.buckets))),
+        }
+    }
+}
+
+/// Schema.json entries for a spec: `(partitions, partitioning)`; `None` removes them.
+pub fn setting_values(spec: &PartitionSpec) -> Option<(serde_json::Value, serde_json::Value)> {
+    if !spec.is_partitioned() { return None; }
+    let setting = PartitioningSetting { method: spec.method, buckets: spec.buckets };
+    Some((serde_json::json!(spec.columns), serde_json::to_value(setting).ok()?))
+}
+
+pub(crate) fn get_partition_spec(store: &Store, table: &str) -> PartitionSpec {
+    let columns = store.get_partitions(table);
+    if columns.is_empty() { return PartitionSpec::default(); }
+    let setting = fs::read_to_string(store.schema_path(table)).ok()
+        .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+        .and_then(|v| v.get(PARTITIONING_KEY).cloned())
+        .and_then(|v| serde_json::from_value::<PartitioningSetting>(v).ok())
+        .unwrap_or_default();
+    PartitionSpec { method: setting.method, columns, buckets: setting.buckets.max(1) }
+}
+
+/// Keep partition folder names portable: anything outside `[A-Za-z0-9._-]` becomes `%XX`.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-') { out.push(b as char); } else { out.push_str(&format!("%{:02X}", b)); }
+    }
+    out
+}
+
+fn unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|h| u8::from_str_radix(h, 16).ok());
+            if let Some(b) = hex { out.push(b); i += 3; continue; }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn fnv1a(s: &str) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in s.bytes() { h ^= b as u64; h = h.wrapping_mul(0x100000001b3); }
+    h
+}
+
+fn bucket_of(values: &[String], buckets: u32) -> u32 {
+    (fnv1a(&values.join("\u{1f}")) % buckets.max(1) as u64) as u32
+}
+
+/// Partition rendering of a cell; integral floats render like integers so `5.0` and `5` agree.
+fn render_value(av: AnyValue) -> Option<String> {
+    match av {
+        AnyValue::Null => None,
+        AnyValue::String(s) => Some(s.to_string()),
+        AnyValue::StringOwned(s) => Some(s.to_string()),
+        AnyValue::Float64(f) => Some(render_float(f)),
+        AnyValue::Float32(f) => Some(render_float(f as f64)),
+        other => Some(other.to_string()),
+    }
+}
+
+fn render_float(f: f64) -> String {
+    if f.fract() == 0.0 && f.abs() < 9.0e15 { (f as i64).to_string() } else { f.to_string() }
+}
+
+/// Renderings a predicate literal may match: itself, plus its integer form when numeric.
+fn pred_forms(v: &str) -> Vec<String> {
+    let mut out = vec![v.to_string()];
+    if let Ok(f) = v.trim().parse::<f64>() {
+        let r = render_float(f);
+        if r != v { out.push(r); }
+    }
+    out
+}
+
+/// Every chunk of a table folder: `data.parquet` / `data-*.parquet` in the folder
+/// itself and inside its partition folders. Sorted by path.
+pub(crate) fn chunk_files(dir: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(rd) = fs::read_dir(dir) else { return };
+        for e in rd.flatten() {
+            let p = e.path();
+            let name = e.file_name().to_string_lossy().to_string();
+            if p.is_dir() {
+                if name.contains('=') && !name.starts_with('.') { walk(&p, out); }
+            } else if name == "data.parquet" || (name.starts_with("data-") && name.ends_with(".parquet")) {
+                out.push(p);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(dir, &mut out);
+    out.sort();
+    out
+}
+
+/// One chunk of a table as listed by `clarium_catalog.chunks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkInfo {
+    /// Path relative to the table folder, partition folders included
+    pub path: String,
+    pub bytes: u64,
+    /// Time range and write time encoded in `data-<min>-<max>-<ts>.parquet` names
+    pub min_time: Option<i64>,
+    pub max_time: Option<i64>,
+    pub written_at: Option<i64>,
+    /// From the Parquet footer; `None` when it cannot be read (e.g. a sealed chunk without its key)
+    pub rows: Option<u64>,
+    pub compression: Option<String>,
+}
+
+/// Chunks of the table stored in `dir`, sorted by path.
+pub fn chunk_infos(dir: &Path) -> Vec<ChunkInfo> {
+    chunk_files(dir).into_iter().map(|p| {
+        let name = p.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let range = super::io::parse_chunk_min_max(&name);
+        let footer = super::encryption::parquet_footer(&p).ok();
+        ChunkInfo {
+            path: p.strip_prefix(dir).unwrap_or(&p).to_string_lossy().replace('\\', "/"),
+            bytes: fs::metadata(&p).map(|m| m.len()).unwrap_or(0),
+            min_time: range.map(|r| r.0),
+            max_time: range.map(|r| r.1),
+            written_at: super::io::parse_chunk_written_at(&name),
+            rows: footer.as_ref().map(|f| f.0 as u64),
+            compression: footer.and_then(|f| f.1),
+        }
+    }).collect()
+}
+
+/// Remove partition folders left empty after their chunks were deleted.
+pub(crate) fn remove_empty_partition_dirs(dir: &Path) {
+    let Ok(rd) = fs::read_dir(dir) else { return };
+    for e in rd.flatten() {
+        let p = e.path();
+        if p.is_dir() && e.file_name().to_string_lossy().contains('=') {
+            remove_empty_partition_dirs(&p);
+            let _ = fs::remove_dir(&p);
+        }
+    }
+}
+
+/// True when the partition holding `chunk` cannot contain rows matching every
+/// `(column, literal)` equality predicate.
+pub(crate) fn partition_excluded(table_dir: &Path, chunk: &Path, spec: &PartitionSpec, eq_preds: &[(String, String)]) -> bool {
+    if eq_preds.is_empty() || !spec.is_partitioned() { return false; }
+    let Some(rel) = chunk.parent().and_then(|p| p.strip_prefix(table_dir).ok()) else { return false };
+    let comps: Vec<(String, String)> = rel.components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter_map(|s| s.split_once('=').map(|(k, v)| (unescape(k), unescape(v))))
+        .collect();
+    if comps.is_empty() { return false; }
+    let preds_for = |col: &str| -> Vec<&String> {
+        eq_preds.iter().filter(|(c, _)| c.eq_ignore_ascii_case(col)).map(|(_, v)| v).collect()
+    };
+    match spec.method {
+        PartitionMethod::List => comps.iter().any(|(k, v)| {
+            preds_for(k).into_iter().any(|pv| !pred_forms(pv).contains(v))
+        }),
+        PartitionMethod::Hash => {
+            let Some(bucket) = comps.iter().find(|(k, _)| k == BUCKET_DIR).and_then(|(_, v)| v.parse::<u32>().ok()) else { return false };
+            let mut choices: Vec<Vec<String>> = Vec::with_capacity(spec.columns.len());
+            for c in &spec.columns {
+                let forms: Vec<String> = preds_for(c).into_iter().flat_map(|v| pred_forms(v)).collect();
+                if forms.is_empty() { return false; }
+                choices.push(forms);
+            }
+            if choices.iter().map(|c| c.len()).product::<usize>() > MAX_HASH_PROBES { return false; }
+            let mut combos: Vec<Vec<String>> = vec![Vec::new()];
+            for opts in &choices {
+                combos = combos.into_iter()
+                    .flat_map(|prefix| opts.iter().map(move |o| { let mut n = prefix.clone(); n.push(o.clone()); n }))
+                    .collect();
+            }
+            let possible: HashSet<u32> = combos.iter().map(|vals| bucket_of(vals, spec.buckets)).collect();
+            !possible.contains(&bucket)
+        }
+    }
+}
+
+impl Store {
+    /// Partitioning of `table` (unpartitioned when no partition columns are declared).
+    pub fn get_partition_spec(&self, table: &str) -> PartitionSpec { get_partition_spec(self, table) }
+
+    /// Record `spec` in schema.json; existing chunks are not moved (see `repartition_table`).
+    pub fn set_partition_spec(&self, table: &str, spec: &PartitionSpec) -> Result<()> {
+        let p = self.schema_path(table);
+        let mut obj: serde_json::Map<String, serde_json::Value> = fs::read_to_string(&p).ok()
+            .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+            .and_then(|v| v.as_object().cloned())
+            .unwrap_or_default();
+        match setting_values(spec) {
+            Some((cols, setting)) => {
+                obj.insert("partitions".into(), cols);
+                obj.insert(PARTITIONING_KEY.into(), setting);
+            }
+            None => { obj.remove("partitions"); obj.remove(PARTITIONING_KEY); }
+        }
+        fs::write(&p, serde_json::to_string_pretty(&serde_json::Value::Object(obj))?)?;
+        Ok(())
+    }
+
+    /// Split `df` into the rows destined for each partition folder. Unpartitioned
+    /// tables yield the table folder with the whole frame.
+    pub(crate) fn partition_frames(&self, table: &str, df: &DataFrame) -> Result<Vec<(PathBuf, DataFrame)>> {
+        let dir = self.db_dir(table);
+        let spec = self.get_partition_spec(table);
+        if !spec.is_partitioned() || df.height() == 0 { return Ok(vec![(dir, df.clone())]); }
+        let cols: Vec<Option<&Column>> = spec.columns.iter().map(|c| df.column(c).ok()).collect();
+        let mut groups: BTreeMap<PathBuf, Vec<u32>> = BTreeMap::new();
+        for i in 0..df.height() {
+            let values: Vec<String> = cols.iter()
+                .map(|c| c.and_then(|c| c.get(i).ok()).and_then(render_value).unwrap_or_else(|| NULL_VALUE.to_string()))
+                .collect();
+            groups.entry(dir.join(spec.relative_dir(&values))).or_default().push(i as u32);
+        }
+        let mut out = Vec::with_capacity(groups.len());
+        for (pdir, idx) in groups {
+            let idx_ca = UInt32Chunked::from_vec("".into(), idx);
+            out.push((pdir, df.take(&idx_ca)?));
+        }
+        Ok(out)
+    }
+
+    /// Rewrite every chunk of `table` into the layout of its current partition spec.
+    pub fn repartition_table(&self, table: &str) -> Result<usize> {
+        let dir = self.db_dir(table);
+        if !dir.is_dir() { bail!("Table not found: {}", table); }
+        let mut df = self.read_df(table)?;
+        if self.is_time_table(table) {
+            df = df.sort(["_time"], SortMultipleOptions::default().with_maintain_order(true))?;
+        }
+        self.rewrite_table_df(table, df)?;
+        let n = chunk_files(&dir).len();
+        crate::tprintln!("[storage.partition] repartitioned '{}' chunks={}", table, n);
+        Ok(n)
+    }
+}
@@ -0,0 +1,47 @@
+//! Chunk pruning decisions of table scans, collected for EXPLAIN ANALYZE.
+//!
+//! `filter_df_partitioned` reports every chunk it considers: read, or skipped because its
+//! `_time` range, its partition or its bloom filters rule out the WHERE clause. Collection is
+//! per thread and off unless [`collect_chunk_pruning`] was called.
+
+use std::cell::RefCell;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkPruning {
+    pub table: String,
+    /// Path relative to the table folder, partition folders included
+    pub chunk: String,
+    /// Why the chunk was skipped; `None` when it was read.
+    pub reason: Option<String>,
+}
+
+thread_local! {
+    static PRUNING: RefCell<Option<Vec<ChunkPruning>>> = const { RefCell::new(None) };
+}
+
+/// Start collecting the chunk decisions of scans run on this thread.
+pub fn collect_chunk_pruning() { PRUNING.with(|p| *p.borrow_mut() = Some(Vec::new())); }
+
+/// Stop collecting and return the decisions made since [`collect_chunk_pruning`].
+pub fn take_chunk_pruning() -> Vec<ChunkPruning> { PRUNING.with(|p| p.borrow_mut().take().unwrap_or_default()) }
+
+pub(crate) fn record(table: &str, table_dir: &Path, chunk: &Path, reason: Option<String>) {
+    PRUNING.with(|p| {
+        if let Some(list) = p.borrow_mut().as_mut() {
+            let chunk = chunk.strip_prefix(table_dir).unwrap_or(chunk).to_string_lossy().replace('\\', "/");
+            list.push(ChunkPruning { table: table.to_string(), chunk, reason });
+        }
+    });
+}
+
+/// `col = value` terms of equality predicates, for reasons.
+pub(crate) fn describe_preds(preds: &[(String, String)]) -> String {
+    preds.iter().map(|(c, v)| format!("{} = {}", c, v)).collect::<Vec<_>>().join(" AND ")
+}
+
+/// Reason for skipping a chunk holding `_time` in `min..=max` when the scan wants `t0..=t1`.
+pub(crate) fn describe_time_range(min: i64, max: i64, t0: Option<i64>, t1: Option<i64>) -> String {
+    let bound = |b: Option<i64>| b.map(|v| v.to_string()).unwrap_or_default();
+    format!("_time range {}..{} outside {}..{}", min, max, bound(t0), bound(t1))
+}
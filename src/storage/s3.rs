@@ -0,0 +1,141 @@
+//! Minimal S3 client (PUT/GET object) with AWS Signature Version 4.
+//!
+//! Credentials come from the standard environment variables `AWS_ACCESS_KEY_ID`,
+//! `AWS_SECRET_ACCESS_KEY`, optional `AWS_SESSION_TOKEN` and `AWS_REGION`
+//! (default `us-east-1`). Set `CLARIUM_S3_ENDPOINT` (or `S3Location::endpoint`) to target
+//! an S3-compatible service (MinIO, Ceph, GCS interoperability, ...); requests then use
+//! path-style addressing.
+
+use anyhow::{anyhow, bail, Result};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Parsed `s3://bucket/prefix` location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Location {
+    pub bucket: String,
+    pub prefix: String,
+    /// S3-compatible endpoint for this location; `CLARIUM_S3_ENDPOINT` or AWS when `None`.
+    pub endpoint: Option<String>,
+}
+
+impl S3Location {
+    pub fn parse(uri: &str) -> Result<Self> {
+        let rest = uri.strip_prefix("s3://").ok_or_else(|| anyhow!("not an s3:// location: {}", uri))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() { bail!("s3 location is missing a bucket: {}", uri); }
+        Ok(Self { bucket: bucket.to_string(), prefix: prefix.trim_matches('/').to_string(), endpoint: None })
+    }
+
+    /// Object key for `rel` under this location's prefix.
+    pub fn key(&self, rel: &str) -> String {
+        if self.prefix.is_empty() { rel.to_string() } else { format!("{}/{}", self.prefix, rel) }
+    }
+}
+
+pub fn is_s3_uri(s: &str) -> bool { s.starts_with("s3://") }
+
+struct Credentials { access_key: String, secret_key: String, session_token: Option<String>, region: String }
+
+fn credentials() -> Result<Credentials> {
+    let access_key = std::env::var("AWS_ACCESS_KEY_ID").map_err(|_| anyhow!("AWS_ACCESS_KEY_ID is not set"))?;
+    let secret_key = std::env::var("AWS_SECRET_ACCESS_KEY").map_err(|_| anyhow!("AWS_SECRET_ACCESS_KEY is not set"))?;
+    let session_token = std::env::var("AWS_SESSION_TOKEN").ok().filter(|s| !s.is_empty());
+    let region = std::env::var("AWS_REGION").or_else(|_| std::env::var("AWS_DEFAULT_REGION")).unwrap_or_else(|_| "us-east-1".into());
+    Ok(Credentials { access_key, secret_key, session_token, region })
+}
+
+fn hex(bytes: &[u8]) -> String { bytes.iter().map(|b| format!("{:02x}", b)).collect() }
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut m = HmacSha256::new_from_slice(key).expect("hmac accepts any key length");
+    m.update(data);
+    m.finalize().into_bytes().to_vec()
+}
+
+/// RFC 3986 encoding as required by SigV4 (`/` kept for object paths).
+pub(crate) fn uri_encode(s: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Build the request URL and the canonical URI for an object key.
+fn object_url(loc: &S3Location, key: &str, region: &str) -> Result<(reqwest::Url, String)> {
+    let enc_key = uri_encode(key, true);
+    let endpoint = loc.endpoint.clone().or_else(|| std::env::var("CLARIUM_S3_ENDPOINT").ok()).filter(|s| !s.is_empty());
+    let (url, path) = match endpoint {
+        Some(ep) => {
+            let path = format!("/{}/{}", uri_encode(&loc.bucket, false), enc_key);
+            (format!("{}{}", ep.trim_end_matches('/'), path), path)
+        }
+        None => {
+            let path = format!("/{}", enc_key);
+            (format!("https://{}.s3.{}.amazonaws.com{}", loc.bucket, region, path), path)
+        }
+    };
+    Ok((reqwest::Url::parse(&url)?, path))
+}
+
+fn signed_request(client: &reqwest::Client, method: reqwest::Method, loc: &S3Location, key: &str, body: &[u8]) -> Result<reqwest::RequestBuilder> {
+    let creds = credentials()?;
+    let (url, canonical_uri) = object_url(loc, key, &creds.region)?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(h), Some(p)) => format!("{}:{}", h, p),
+        (Some(h), None) => h.to_string(),
+        _ => bail!("invalid S3 endpoint URL: {}", url),
+    };
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex(&Sha256::digest(body));
+
+    let mut headers: Vec<(&str, String)> = vec![
+        ("host", host),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(tok) = &creds.session_token { headers.push(("x-amz-security-token", tok.clone())); }
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v.trim())).collect();
+    let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+    let canonical_request = format!("{}\n{}\n\n{}\n{}\n{}", method.as_str(), canonical_uri, canonical_headers, signed_headers, payload_hash);
+
+    let scope = format!("{}/{}/s3/aws4_request", date, creds.region);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex(&Sha256::digest(canonical_request.as_bytes())));
+    let k_date = hmac(format!("AWS4{}", creds.secret_key).as_bytes(), date.as_bytes());
+    let k_region = hmac(&k_date, creds.region.as_bytes());
+    let k_service = hmac(&k_region, b"s3");
+    let k_signing = hmac(&k_service, b"aws4_request");
+    let signature = hex(&hmac(&k_signing, string_to_sign.as_bytes()));
+    let authorization = format!("AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}", creds.access_key, scope, signed_headers, signature);
+
+    let mut rb = client.request(method, url).header("Authorization", authorization);
+    for (k, v) in headers.into_iter().filter(|(k, _)| *k != "host") { rb = rb.header(k, v); }
+    Ok(rb)
+}
+
+pub async fn put_object(client: &reqwest::Client, loc: &S3Location, key: &str, body: Vec<u8>) -> Result<()> {
+    let rb = signed_request(client, reqwest::Method::PUT, loc, key, &body)?;
+    let resp = rb.body(body).send().await?;
+    if !resp.status().is_success() {
+        bail!("S3 PUT s3://{}/{} failed: {} {}", loc.bucket, key, resp.status(), resp.text().await.unwrap_or_default());
+    }
+    Ok(())
+}
+
+pub async fn get_object(client: &reqwest::Client, loc: &S3Location, key: &str) -> Result<Vec<u8>> {
+    let rb = signed_request(client, reqwest::Method::GET, loc, key, &[])?;
+    let resp = rb.send().await?;
+    if !resp.status().is_success() {
+        bail!("S3 GET s3://{}/{} failed: {}", loc.bucket, key, resp.status());
+    }
+    Ok(resp.bytes().await?.to_vec())
+}
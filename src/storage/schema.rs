@@ -1,5 +1,4 @@
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
 use polars::prelude::*;
 use crate::tprintln;
 use super::Store;
@@ -46,6 +45,9 @@ pub(crate) fn get_primary_key(store: &Store, table: &str) -> Option<Vec<String>>
     None
 }
 
+/// schema.json key recording, per ingested column, whether nulls have been written to it.
+pub const NULLABLE_KEY: &str = "nullable";
+
 pub(crate) fn get_partitions(store: &Store, table: &str) -> Vec<String> {
     let p = store.schema_path(table);
     if !p.exists() { return Vec::new(); }
@@ -110,6 +112,10 @@ pub(crate) fn save_schema_with_locks(store: &Store, table: &str, schema: &HashMa
     for (k, dt) in schema.iter() { cols.insert(k.clone(), dtype_to_str(dt)); }
     root.insert("columns".into(), serde_json::json!(cols));
     root.insert("locks".into(), serde_json::json!(locks.iter().cloned().collect::<Vec<_>>()));
+    // New or rewritten files are always in the current on-disk format
+    if !root.contains_key(super::migrate::FORMAT_VERSION_KEY) {
+        root.insert(super::migrate::FORMAT_VERSION_KEY.into(), serde_json::json!(super::migrate::FORMAT_VERSION));
+    }
     // Ensure tableType is present and consistent. If missing, set based on directory suffix.
     // If present but inconsistent with `.time` suffix, correct to "time" and log.
     let ends_time = table.ends_with(".time");
@@ -140,14 +146,22 @@ pub(crate) fn dtype_to_str(dt: &DataType) -> String {
             DataType::String => "string[]".into(),
             _ => "list".into(),
         },
+        DataType::Decimal(p, sc) => super::decimal::type_key(p.unwrap_or(super::decimal::MAX_PRECISION), sc.unwrap_or(0)),
+        DataType::Boolean => "bool".into(),
+        DataType::Datetime(..) => "timestamp".into(),
+        DataType::Null => "null".into(),
         _ => "float64".into(),
     }
 }
 
 pub(crate) fn str_to_dtype(s: &str) -> DataType {
+    if let Ok(Some((p, sc))) = super::decimal::parse_type(s) { return super::decimal::dtype(p, sc); }
     match s.to_ascii_lowercase().as_str() {
         "utf8" | "string" => DataType::String,
         "int64" => DataType::Int64,
+        "bool" | "boolean" => DataType::Boolean,
+        "timestamp" | "datetime" => super::native::timestamp_dtype(),
+        "null" => DataType::Null,
         // Back-compat: logical 'vector' maps to List(Float64)
         "vector" => DataType::List(Box::new(DataType::Float64)),
         // New explicit array syntax
@@ -163,6 +177,10 @@ pub(crate) fn str_to_dtype(s: &str) -> DataType {
 pub(crate) fn merge_dtype(a: DataType, b: DataType) -> DataType {
     use DataType::*;
     match (a, b) {
+        // An all-null side takes the other side's type
+        (Null, t) | (t, Null) => t,
+        // Text written to a timestamp or boolean column is parsed into it
+        (t @ (Datetime(..) | Boolean), String) => t,
         (String, _) | (_, String) => String,
         // Do not implicitly widen to/from vectors. If any side is List, keep List if other side is numeric; else fall back to String.
         (List(a), List(b)) => {
@@ -170,12 +188,57 @@ pub(crate) fn merge_dtype(a: DataType, b: DataType) -> DataType {
         }
         (List(a), Float64) | (Float64, List(a)) => List(a),
         (List(a), Int64) | (Int64, List(a)) => List(a),
+        // Decimals widen to hold both sides. Inferred numbers are only JSON literals, which are
+        // parsed into the column's scale, so a decimal column stays decimal.
+        (a @ Decimal(..), b @ Decimal(..)) => {
+            let (Some(x), Some(y)) = (super::decimal::precision_scale(&a), super::decimal::precision_scale(&b)) else { return a };
+            super::decimal::widen(x, y)
+        }
+        (d @ Decimal(..), Float64 | Int64) | (Float64 | Int64, d @ Decimal(..)) => d,
+        // Epoch numbers and 0/1 flags keep the column a timestamp / boolean
+        (t @ Datetime(..), Datetime(..) | Float64 | Int64) => t,
+        (Boolean, Boolean | Float64 | Int64) => Boolean,
         (Float64, _) | (_, Float64) => Float64,
         _ => Int64,
     }
 }
 
 impl Store {
+    /// Whether nulls have been ingested into each column (schema.json `nullable`). Columns
+    /// never written through `write_records` are absent.
+    pub fn get_nullability(&self, table: &str) -> HashMap<String, bool> {
+        let Ok(text) = std::fs::read_to_string(self.schema_path(table)) else { return HashMap::new() };
+        let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) else { return HashMap::new() };
+        v.get(NULLABLE_KEY).and_then(|m| m.as_object())
+            .map(|m| m.iter().filter_map(|(k, v)| v.as_bool().map(|b| (k.clone(), b))).collect())
+            .unwrap_or_default()
+    }
+
+    /// Fold the nulls of a batch about to be written into schema.json `nullable`. A column
+    /// stays nullable once any null reached it.
+    pub(crate) fn note_nullability(&self, table: &str, df: &DataFrame) -> anyhow::Result<()> {
+        let p = self.schema_path(table);
+        let mut root: serde_json::Map<String, serde_json::Value> = std::fs::read_to_string(&p).ok()
+            .and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+            .and_then(|v| v.as_object().cloned())
+            .unwrap_or_default();
+        let mut map = root.get(NULLABLE_KEY).and_then(|m| m.as_object()).cloned().unwrap_or_default();
+        let mut changed = false;
+        for c in df.get_columns() {
+            if c.name().as_str() == "_time" { continue; }
+            let has_nulls = c.null_count() > 0;
+            match map.get(c.name().as_str()).and_then(|v| v.as_bool()) {
+                Some(true) => {}
+                Some(false) if !has_nulls => {}
+                _ => { map.insert(c.name().to_string(), serde_json::json!(has_nulls)); changed = true; }
+            }
+        }
+        if !changed { return Ok(()); }
+        root.insert(NULLABLE_KEY.into(), serde_json::Value::Object(map));
+        std::fs::write(&p, serde_json::to_string_pretty(&serde_json::Value::Object(root))?)?;
+        Ok(())
+    }
+
     pub fn set_table_metadata(&self, table: &str, primary_key: Option<Vec<String>>, partitions: Option<Vec<String>>) -> anyhow::Result<()> {
         use serde_json::{Value, Map};
         let p = self.schema_path(table);
@@ -196,58 +259,3 @@ impl Store {
         Ok(())
     }
 }
-
-// --- Schema migration utilities ---
-
-/// Migrate all `schema.json` files under the provided root to the new nested format
-/// with explicit `tableType`. Returns the number of files updated.
-pub(crate) fn migrate_all_schemas_for_root(root: &Path) -> anyhow::Result<usize> {
-    let mut updated = 0usize;
-    if !root.exists() { return Ok(0); }
-    // Walk: root/db/schema/table/(schema.json)
-    for db_ent in std::fs::read_dir(root).unwrap_or_else(|_| std::fs::ReadDir::from(std::fs::read_dir(root).unwrap())) {
-        if let Ok(db) = db_ent { let dbp = db.path(); if !dbp.is_dir() { continue; }
-            for sch_ent in std::fs::read_dir(&dbp).unwrap_or_else(|_| std::fs::read_dir(&dbp).unwrap()) {
-                if let Ok(sch) = sch_ent { let schp = sch.path(); if !schp.is_dir() { continue; }
-                    for tab_ent in std::fs::read_dir(&schp).unwrap_or_else(|_| std::fs::read_dir(&schp).unwrap()) {
-                        if let Ok(tab) = tab_ent { let tabp = tab.path(); if !tabp.is_dir() { continue; }
-                            let sj = tabp.join("schema.json");
-                            if !sj.exists() { continue; }
-                            if let Ok(text) = std::fs::read_to_string(&sj) {
-                                if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
-                                    let mut changed = false;
-                                    let mut obj = v.as_object().cloned().unwrap_or_default();
-                                    // Ensure tableType
-                                    if !obj.contains_key("tableType") {
-                                        let tt = if tabp.file_name().and_then(|s| s.to_str()).map(|s| s.ends_with(".time")).unwrap_or(false) { "time" } else { "regular" };
-                                        obj.insert("tableType".into(), serde_json::json!(tt));
-                                        changed = true;
-                                    }
-                                    // Ensure nested columns
-                                    let has_nested = obj.get("columns").and_then(|x| x.as_object()).is_some();
-                                    if !has_nested {
-                                        // Derive from flat entries: collect string values except known metadata keys
-                                        let mut cols: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
-                                        for (k, val) in obj.clone().into_iter() {
-                                            if matches!(k.as_str(), "columns" | "locks" | "PRIMARY" | "primaryKey" | "partitions" | "tableType") { continue; }
-                                            if let Some(s) = val.as_str() { cols.insert(k, serde_json::json!(s)); }
-                                        }
-                                        obj.insert("columns".into(), serde_json::Value::Object(cols));
-                                        changed = true;
-                                    }
-                                    if changed {
-                                        if std::fs::write(&sj, serde_json::to_string_pretty(&serde_json::Value::Object(obj))?).is_ok() {
-                                            updated += 1;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    if updated > 0 { tprintln!("[SCHEMA] migrate_all_schemas_for_root: updated {} schema.json files", updated); }
-    Ok(updated)
-}
@@ -372,3 +372,83 @@ fn test_all_column_types_roundtrip_time_table() {
     assert_eq!(s1, "y");
     assert_eq!(v1, vec![4.0, 5.0, 6.0]);
 }
+
+#[test]
+fn test_startup_migration_upgrades_legacy_layout_and_records_history() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("legacy").join("public").join("old.time");
+    std::fs::create_dir_all(&dir).unwrap();
+    // Version-0 layout: flat columns, no tableType, type implied by the `.time` suffix
+    std::fs::write(dir.join("schema.json"), r#"{"v":"float64","label":"string","locks":[]}"#).unwrap();
+
+    let n = migrate::run_startup_migrations(tmp.path()).unwrap();
+    assert_eq!(n, 1);
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("schema.json")).unwrap()).unwrap();
+    assert_eq!(v["formatVersion"], json!(migrate::FORMAT_VERSION));
+    assert_eq!(v["tableType"], json!("time"));
+    assert_eq!(v["columns"]["v"], json!("float64"));
+    assert!(v.get("label").is_none());
+
+    let hist = migrate::load_history(tmp.path());
+    assert_eq!(hist.len(), 2);
+    assert_eq!(hist[0]["migration"], json!("explicit_table_type"));
+    assert_eq!(hist[1]["to"], json!(2));
+
+    // Re-running is a no-op and adds no history
+    assert_eq!(migrate::run_startup_migrations(tmp.path()).unwrap(), 0);
+    assert_eq!(migrate::load_history(tmp.path()).len(), 2);
+
+    // Downgrade restores the flat layout
+    migrate::migrate_root_to(tmp.path(), 1).unwrap();
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("schema.json")).unwrap()).unwrap();
+    assert_eq!(v["label"], json!("string"));
+    assert!(v.get("columns").is_none());
+    assert_eq!(v["formatVersion"], json!(1));
+}
+
+#[test]
+fn test_startup_migration_refuses_newer_format() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = Store::new(tmp.path()).unwrap();
+    store.create_table("db/public/t1").unwrap();
+    let sp = tmp.path().join("db").join("public").join("t1").join("schema.json");
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&sp).unwrap()).unwrap();
+    assert_eq!(v["formatVersion"], json!(migrate::FORMAT_VERSION));
+
+    let mut obj = v.as_object().cloned().unwrap();
+    obj.insert("formatVersion".into(), json!(migrate::FORMAT_VERSION + 1));
+    std::fs::write(&sp, serde_json::to_string(&obj).unwrap()).unwrap();
+    let err = SharedStore::new(tmp.path()).err().expect("newer format must be refused");
+    assert!(err.to_string().contains("refusing to open"), "unexpected error: {}", err);
+}
+
+#[test]
+fn test_migration_round_trip_keeps_table_metadata_out_of_columns() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("meta").join("public").join("m.time");
+    std::fs::create_dir_all(&dir).unwrap();
+    // Version-1 flat layout carrying every metadata key the engine writes
+    let legacy = json!({
+        "formatVersion": 1, "tableType": "time", "v": "float64", "label": "string",
+        "ingestMode": "strict",
+        "encryption": {"algorithm": "aes-256-gcm", "key_id": "k1"},
+        "dedup": {"mode": "write"},
+        "lateData": {"windowMs": 60000, "policy": "reject"},
+        "partitioning": {"kind": "list", "column": "label"},
+        "collations": {"label": "codepoint"},
+        "nullable": {"v": true},
+        "comments": {"table": "c"}, "computed": {}, "triggers": [], "cdc": {"enabled": true},
+    });
+    std::fs::write(dir.join("schema.json"), serde_json::to_string(&legacy).unwrap()).unwrap();
+
+    assert_eq!(migrate::run_startup_migrations(tmp.path()).unwrap(), 1);
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("schema.json")).unwrap()).unwrap();
+    assert_eq!(v["columns"], json!({"v": "float64", "label": "string"}));
+    for key in ["ingestMode", "encryption", "dedup", "lateData", "partitioning", "collations", "nullable"] {
+        assert_eq!(v[key], legacy[key], "{} must stay table metadata", key);
+    }
+
+    migrate::migrate_root_to(tmp.path(), 1).unwrap();
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("schema.json")).unwrap()).unwrap();
+    assert_eq!(v, legacy);
+}
@@ -0,0 +1,161 @@
+//! Row tombstones for partially deleted chunks.
+//!
+//! A DELETE that removes a few rows of a chunk records their positions in a
+//! `<chunk>.tomb` sidecar instead of rewriting the chunk. Every table read drops
+//! those rows (`read_live_chunk`). Once the tombstoned share of a chunk passes
+//! `REWRITE_FRACTION` the chunk is rewritten without them; any full rewrite of the
+//! table (`COMPACT TABLE`, `rewrite_table_df`) folds the remaining tombstones in.
+//!
+//! Sidecars are written to a temporary file and renamed into place. A sidecar that
+//! cannot be read or parsed fails the read rather than bringing deleted rows back.
+//! Each tombstone carries its delete time so a point-in-time restore can keep only
+//! the deletes made before its cut; sidecars written before delete times were
+//! recorded count as deleted at time 0.
+//!
+//! Row positions only mean something against one state of a chunk: a delete planned
+//! outside the store lock records the chunk's `ChunkGeneration` and applies only if the
+//! chunk still has it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::Store;
+
+/// Share of a chunk's rows that may be tombstoned before the chunk is rewritten.
+pub const REWRITE_FRACTION: f64 = 0.25;
+
+/// Deleted rows of one chunk, as sorted positions in the chunk file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstones {
+    pub rows: Vec<u32>,
+    /// Delete time (epoch ms) of each entry of `rows`; empty in older sidecars.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deleted_at: Vec<i64>,
+}
+
+impl Tombstones {
+    /// Delete time of the i-th tombstone; 0 when the sidecar predates delete times.
+    fn time_of(&self, i: usize) -> i64 {
+        self.deleted_at.get(i).copied().unwrap_or(0)
+    }
+
+    /// Only the tombstones recorded at or before `as_of`.
+    pub fn as_of(&self, as_of: i64) -> Tombstones {
+        let mut out = Tombstones::default();
+        for (i, r) in self.rows.iter().enumerate() {
+            let at = self.time_of(i);
+            if at <= as_of { out.rows.push(*r); out.deleted_at.push(at); }
+        }
+        out
+    }
+}
+
+/// What a chunk-level delete did to the chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkDelete {
+    /// Every row was deleted and the chunk removed.
+    Dropped,
+    /// The chunk was written again without the deleted rows.
+    Rewritten,
+    /// The rows were recorded in the chunk's tombstone sidecar.
+    Tombstoned,
+}
+
+/// On-disk state of a chunk: its file's size and modification time, and its tombstones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkGeneration {
+    file: Option<(u64, std::time::SystemTime)>,
+    tombstones: Option<Tombstones>,
+}
+
+/// Current generation of a chunk; a removed chunk has no file.
+pub fn chunk_generation(chunk: &Path) -> Result<ChunkGeneration> {
+    let file = fs::metadata(chunk).ok().map(|m| (m.len(), m.modified().unwrap_or(std::time::UNIX_EPOCH)));
+    Ok(ChunkGeneration { file, tombstones: read_sidecar(chunk)? })
+}
+
+/// Sidecar path for a given Parquet chunk.
+pub(crate) fn sidecar_path(chunk: &Path) -> PathBuf {
+    let mut s = chunk.as_os_str().to_owned();
+    s.push(".tomb");
+    PathBuf::from(s)
+}
+
+/// Tombstones of a chunk; None when it has no sidecar.
+pub(crate) fn read_sidecar(chunk: &Path) -> Result<Option<Tombstones>> {
+    let p = sidecar_path(chunk);
+    let bytes = match fs::read(&p) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("reading tombstones {}", p.display())),
+    };
+    let t = serde_json::from_slice(&bytes).with_context(|| format!("corrupt tombstone sidecar {}", p.display()))?;
+    Ok(Some(t))
+}
+
+fn write_sidecar(chunk: &Path, tombstones: &Tombstones) -> Result<()> {
+    let p = sidecar_path(chunk);
+    let tmp = p.with_extension("tomb.tmp");
+    fs::write(&tmp, serde_json::to_vec(tombstones)?)?;
+    fs::rename(&tmp, &p)?;
+    Ok(())
+}
+
+fn drop_rows(df: DataFrame, dead: &[u32]) -> Result<DataFrame> {
+    if dead.is_empty() { return Ok(df); }
+    let mut keep = vec![true; df.height()];
+    for r in dead { if let Some(k) = keep.get_mut(*r as usize) { *k = false; } }
+    Ok(df.filter(&BooleanChunked::from_slice("__keep__".into(), &keep))?)
+}
+
+/// Read a chunk without its tombstoned rows.
+pub(crate) fn read_live_chunk(chunk: &Path) -> Result<DataFrame> {
+    let df = super::encryption::read_parquet(chunk)?;
+    match read_sidecar(chunk)? {
+        Some(t) => drop_rows(df, &t.rows),
+        None => Ok(df),
+    }
+}
+
+impl Store {
+    /// Delete rows of one chunk of `table`. `live_rows` are positions in the frame
+    /// `read_chunk` returned for the chunk, i.e. after its existing tombstones.
+    pub fn delete_chunk_rows(&self, table: &str, chunk: &Path, live_rows: &[IdxSize]) -> Result<ChunkDelete> {
+        let old = read_sidecar(chunk)?.unwrap_or_default();
+        let total = super::encryption::parquet_num_rows(chunk)?;
+        // Map positions among live rows back to positions in the file
+        let mut live_to_file: Vec<u32> = Vec::with_capacity(total.saturating_sub(old.rows.len()));
+        let mut dead_iter = old.rows.iter().peekable();
+        for r in 0..total as u32 {
+            if dead_iter.peek() == Some(&&r) { dead_iter.next(); continue; }
+            live_to_file.push(r);
+        }
+        let mut dead: BTreeMap<u32, i64> = old.rows.iter().enumerate().map(|(i, r)| (*r, old.time_of(i))).collect();
+        let now = chrono::Utc::now().timestamp_millis();
+        for r in live_rows {
+            if let Some(f) = live_to_file.get(*r as usize) { dead.entry(*f).or_insert(now); }
+        }
+
+        if dead.len() >= total {
+            let _ = fs::remove_file(super::bloom::sidecar_path(chunk));
+            let _ = fs::remove_file(super::checksum::sidecar_path(chunk));
+            let _ = fs::remove_file(sidecar_path(chunk));
+            fs::remove_file(chunk)?;
+            return Ok(ChunkDelete::Dropped);
+        }
+        if dead.len() as f64 > total as f64 * REWRITE_FRACTION {
+            let rows: Vec<u32> = dead.keys().copied().collect();
+            let live = drop_rows(super::encryption::read_parquet(chunk)?, &rows)?;
+            self.replace_chunks(table, vec![(chunk.to_path_buf(), live)])?;
+            return Ok(ChunkDelete::Rewritten);
+        }
+        let (rows, deleted_at) = dead.into_iter().unzip();
+        write_sidecar(chunk, &Tombstones { rows, deleted_at })?;
+        Ok(ChunkDelete::Tombstoned)
+    }
+}
@@ -0,0 +1,216 @@
+//! AFTER INSERT / UPDATE / DELETE table triggers backed by Lua scripts.
+//!
+//! Triggers are kept in the table's schema.json under `"triggers"` (CREATE TRIGGER /
+//! DROP TRIGGER) and fire from `Store::record_changes`, the same row-change hook CDC
+//! uses. The trigger's function is called once per batch of at most `batchSize` rows
+//! as `fn(rows, ctx)`, where `rows` is an array of row tables (the row after the change,
+//! or before it for deletes) and `ctx` is `{table, op, trigger}`.
+//!
+//! - `sync` triggers run inside the writing statement, after the rows are written and
+//!   while the table is still held, so they must not write back to the store. With
+//!   `onError: fail` a script error fails the statement; the rows stay written.
+//! - `async` triggers are queued to a background worker and never fail the statement.
+//!
+//! `onError: retry` calls the function up to `retries` more times before giving up as
+//! `fail` would; `ignore` only counts the failure. Per-trigger counters are kept in
+//! memory and shown in `pg_catalog.clarium_triggers`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::cdc::ChangeOp;
+use super::Store;
+
+/// schema.json key holding a table's trigger definitions.
+pub const TRIGGERS_KEY: &str = "triggers";
+
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+const RETRY_BACKOFF_MS: u64 = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerMode {
+    #[default]
+    Sync,
+    Async,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    #[default]
+    Fail,
+    Ignore,
+    Retry,
+}
+
+fn default_batch_size() -> usize { DEFAULT_BATCH_SIZE }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trigger {
+    pub name: String,
+    pub events: Vec<ChangeOp>,
+    /// Registered Lua function called with each batch
+    pub function: String,
+    #[serde(default)]
+    pub mode: TriggerMode,
+    #[serde(default)]
+    pub on_error: OnError,
+    /// Extra attempts for `onError: retry`
+    #[serde(default)]
+    pub retries: u32,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+}
+
+impl Trigger {
+    /// Apply a `WITH (...)` option from CREATE TRIGGER.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<()> {
+        let v = value.trim().trim_matches('\'');
+        match key.to_ascii_lowercase().as_str() {
+            "mode" => self.mode = match v.to_ascii_lowercase().as_str() {
+                "sync" => TriggerMode::Sync,
+                "async" => TriggerMode::Async,
+                _ => anyhow::bail!("trigger mode must be 'sync' or 'async'"),
+            },
+            "on_error" => self.on_error = match v.to_ascii_lowercase().as_str() {
+                "fail" => OnError::Fail,
+                "ignore" => OnError::Ignore,
+                "retry" => OnError::Retry,
+                _ => anyhow::bail!("trigger on_error must be 'fail', 'ignore' or 'retry'"),
+            },
+            "retries" => self.retries = v.parse().map_err(|_| anyhow::anyhow!("trigger retries must be a non-negative integer"))?,
+            "batch_size" => {
+                self.batch_size = v.parse().map_err(|_| anyhow::anyhow!("trigger batch_size must be a positive integer"))?;
+                if self.batch_size == 0 { anyhow::bail!("trigger batch_size must be a positive integer"); }
+            }
+            other => anyhow::bail!("unknown trigger option: {}", other),
+        }
+        if self.on_error == OnError::Retry && self.retries == 0 { self.retries = 3; }
+        Ok(())
+    }
+
+    fn attempts(&self) -> u32 { if self.on_error == OnError::Retry { self.retries + 1 } else { 1 } }
+}
+
+/// In-memory counters for one trigger since startup.
+#[derive(Debug, Clone, Default)]
+pub struct TriggerStats {
+    pub batches: u64,
+    pub rows: u64,
+    pub failures: u64,
+    pub last_error: Option<String>,
+}
+
+static STATS: Lazy<Mutex<HashMap<(PathBuf, String), TriggerStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct AsyncBatch {
+    dir: PathBuf,
+    table: String,
+    trigger: Trigger,
+    op: ChangeOp,
+    rows: Vec<Map<String, Value>>,
+}
+
+/// Background worker for async triggers, started on first use.
+static QUEUE: Lazy<Mutex<mpsc::Sender<AsyncBatch>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<AsyncBatch>();
+    std::thread::Builder::new()
+        .name("clarium-triggers".into())
+        .spawn(move || {
+            for b in rx {
+                if let Err(e) = run_batch(&b.dir, &b.table, &b.trigger, b.op, b.rows) {
+                    tracing::warn!(target: "clarium::triggers", "async trigger {} on {} failed: {}", b.trigger.name, b.table, e);
+                }
+            }
+        })
+        .expect("spawn trigger worker");
+    Mutex::new(tx)
+});
+
+/// Trigger definitions stored in a schema.json object.
+pub fn from_schema(obj: &Map<String, Value>) -> Vec<Trigger> {
+    obj.get(TRIGGERS_KEY)
+        .and_then(|v| serde_json::from_value::<Vec<Trigger>>(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Triggers of the table stored in `dir`.
+pub fn table_triggers(dir: &Path) -> Vec<Trigger> {
+    fs::read_to_string(dir.join("schema.json")).ok()
+        .and_then(|t| serde_json::from_str::<Value>(&t).ok())
+        .and_then(|v| v.as_object().map(from_schema))
+        .unwrap_or_default()
+}
+
+pub(crate) fn get_triggers(store: &Store, table: &str) -> Vec<Trigger> { table_triggers(&store.db_dir(table)) }
+
+/// Counters for the trigger `name` on the table stored in `dir`.
+pub fn stats(dir: &Path, name: &str) -> TriggerStats {
+    STATS.lock().get(&(dir.to_path_buf(), name.to_string())).cloned().unwrap_or_default()
+}
+
+fn run_batch(dir: &Path, table: &str, trigger: &Trigger, op: ChangeOp, rows: Vec<Map<String, Value>>) -> Result<()> {
+    let n = rows.len() as u64;
+    let payload = Value::Array(rows.into_iter().map(Value::Object).collect());
+    let ctx = serde_json::json!({"table": table, "op": op.as_str(), "trigger": trigger.name});
+    let mut result = Ok(());
+    for attempt in 0..trigger.attempts() {
+        if attempt > 0 && trigger.mode == TriggerMode::Async {
+            std::thread::sleep(std::time::Duration::from_millis(RETRY_BACKOFF_MS << (attempt - 1).min(6)));
+        }
+        result = match crate::scripts::get_script_registry() {
+            Some(reg) => reg.call_function_json(&trigger.function, &[payload.clone(), ctx.clone()]).map(|_| ()),
+            None => Err(anyhow::anyhow!("script registry is not initialized")),
+        };
+        if result.is_ok() { break; }
+    }
+    let mut stats = STATS.lock();
+    let s = stats.entry((dir.to_path_buf(), trigger.name.clone())).or_default();
+    s.batches += 1;
+    s.rows += n;
+    if let Err(e) = &result {
+        s.failures += 1;
+        s.last_error = Some(e.to_string());
+    }
+    result
+}
+
+impl Store {
+    /// Run the table's triggers for `op` over `rows`. Sync triggers run here, batch by batch;
+    /// async ones are queued. Only a failing sync trigger with `onError` fail/retry returns an error.
+    pub(crate) fn fire_triggers(&self, table: &str, op: ChangeOp, rows: &[Map<String, Value>]) -> Result<()> {
+        let dir = self.db_dir(table);
+        for t in get_triggers(self, table).into_iter().filter(|t| t.events.contains(&op)) {
+            for chunk in rows.chunks(t.batch_size.max(1)) {
+                match t.mode {
+                    TriggerMode::Async => {
+                        let b = AsyncBatch { dir: dir.clone(), table: table.to_string(), trigger: t.clone(), op, rows: chunk.to_vec() };
+                        let _ = QUEUE.lock().send(b);
+                    }
+                    TriggerMode::Sync => {
+                        if let Err(e) = run_batch(&dir, table, &t, op, chunk.to_vec()) {
+                            if t.on_error == OnError::Ignore {
+                                tracing::warn!(target: "clarium::triggers", "trigger {} on {} failed (ignored): {}", t.name, table, e);
+                                continue;
+                            }
+                            return Err(crate::error::AppError::exec("triggered_action_exception".to_string(),
+                                format!("trigger \"{}\" on {} failed: {}", t.name, table, e)).into());
+                        }
+                    }
+                }
+            }
+            crate::tprintln!("[storage.triggers] table='{}' trigger='{}' op={} rows={}", table, t.name, op.as_str(), rows.len());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+//! Table data versions and read tracking for the result cache.
+//!
+//! A table's data version is a fingerprint of the files under its directory (path,
+//! size and modification time), so every write changes it whichever path made it:
+//! ingest, INSERT/UPDATE/DELETE, compaction, restore or replication.
+//!
+//! While a statement runs under [`track_reads`], each table read records its version
+//! as of the read. Sources without a version (KV stores, system tables, table
+//! functions) call [`note_uncacheable`] instead.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use parking_lot::Mutex;
+
+use super::Store;
+
+/// Tables read by a tracked statement.
+#[derive(Debug, Default)]
+pub struct ReadSet {
+    /// Table -> data version when it was first read
+    pub tables: BTreeMap<String, u64>,
+    /// A source without a data version was read
+    pub uncacheable: bool,
+}
+
+thread_local! {
+    // Read set of the tracked statement being polled on this thread (set per poll by track_reads)
+    static TLS_READS: RefCell<Option<Arc<Mutex<ReadSet>>>> = const { RefCell::new(None) };
+}
+
+/// Run `fut`, recording the tables it reads.
+pub async fn track_reads<T, F>(fut: F) -> (T, ReadSet)
+where
+    F: Future<Output = T>,
+{
+    let reads = Arc::new(Mutex::new(ReadSet::default()));
+    let mut fut = Box::pin(fut);
+    let out = std::future::poll_fn(|cx| {
+        let prev = TLS_READS.with(|c| c.replace(Some(reads.clone())));
+        let r = fut.as_mut().poll(cx);
+        TLS_READS.with(|c| *c.borrow_mut() = prev);
+        r
+    }).await;
+    let set = std::mem::take(&mut *reads.lock());
+    (out, set)
+}
+
+fn tracking() -> Option<Arc<Mutex<ReadSet>>> { TLS_READS.with(|c| c.borrow().clone()) }
+
+/// Mark the tracked statement as reading a source without a data version.
+pub fn note_uncacheable() {
+    if let Some(r) = tracking() { r.lock().uncacheable = true; }
+}
+
+fn fingerprint(dir: &Path, base: &Path, h: &mut impl Hasher) {
+    let Ok(rd) = fs::read_dir(dir) else { return };
+    let mut entries: Vec<_> = rd.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for e in entries {
+        let p = e.path();
+        // The changelog only records writes already visible in the data files
+        if e.file_name() == "_cdc" { continue; }
+        let Ok(md) = e.metadata() else { continue };
+        if md.is_dir() { fingerprint(&p, base, h); continue; }
+        p.strip_prefix(base).unwrap_or(&p).hash(h);
+        md.len().hash(h);
+        md.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_nanos()).hash(h);
+    }
+}
+
+impl Store {
+    /// Data version of `table`; changes whenever its files do. 0 for a missing table.
+    pub fn data_version(&self, table: &str) -> u64 {
+        let dir = self.db_dir(table);
+        if !dir.exists() { return 0; }
+        let mut h = std::collections::hash_map::DefaultHasher::new();
+        fingerprint(&dir, &dir, &mut h);
+        h.finish().max(1)
+    }
+
+    /// Record a read of `table` for the tracked statement, if any.
+    pub(crate) fn note_read(&self, table: &str) {
+        let Some(r) = tracking() else { return };
+        if r.lock().tables.contains_key(table) { return; }
+        let v = self.data_version(table);
+        r.lock().tables.entry(table.to_string()).or_insert(v);
+    }
+}
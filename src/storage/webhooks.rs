@@ -0,0 +1,36 @@
+//! Webhook settings shared by filestores and KV stores.
+//!
+//! Both keep a list of `WebhookConfig` in their settings and hand matching events
+//! over as `Delivery` values through `StorageHooks::deliver_webhook`; the server's
+//! filestore worker POSTs them (headers, signing and retries in
+//! `server::exec::filestore::events`).
+
+use serde::{Deserialize, Serialize};
+
+/// An HTTP endpoint POSTed each matching event as JSON.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Event kinds to send (e.g. "ingest", "update", "rename", "delete", "commit", "expired"); all when unset
+    #[serde(default)]
+    pub events: Option<Vec<String>>,
+    /// Only events for paths (filestores) or keys (KV stores) under this prefix; filestore commits have no path and always match
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Environment variable holding the HMAC-SHA256 signing secret; the secret itself is never stored
+    #[serde(default)]
+    pub secret_env: Option<String>,
+}
+
+/// A queued webhook POST. Counters are kept per (database, scope, url).
+pub(crate) struct Delivery {
+    pub(crate) hook: WebhookConfig,
+    pub(crate) database: String,
+    /// The filestore, or `store.<name>` for KV store events
+    pub(crate) scope: String,
+    /// Sent as X-Clarium-Event
+    pub(crate) kind: String,
+    /// Sent as X-Clarium-Delivery
+    pub(crate) id: String,
+    pub(crate) body: Vec<u8>,
+}
@@ -35,6 +35,9 @@ thread_local! {
     static TLS_VECTOR_HNSW_M: Cell<i32> = const { Cell::new(32) };         // HNSW M (graph degree)
     static TLS_VECTOR_HNSW_EF_BUILD: Cell<i32> = const { Cell::new(200) }; // HNSW ef_build
     static TLS_VECTOR_PRESELECT_ALPHA: Cell<i32> = const { Cell::new(8) }; // ANN preselect alpha (W = alpha * k)
+    static TLS_VECTOR_IVF_NPROBE: Cell<i32> = const { Cell::new(0) };      // IVF-PQ lists probed; 0 = the index's nprobe
+    static TLS_VECTOR_IVF_RERANK: Cell<i32> = const { Cell::new(0) };      // IVF-PQ candidates re-ranked per result; 0 = the index's
+    static TLS_VECTOR_PREFILTER_PERCENT: Cell<i32> = const { Cell::new(5) }; // filtered ANN: scan exactly below this % of rows kept
 }
 
 pub fn get_vector_ef_search() -> i32 { TLS_VECTOR_EF_SEARCH.with(|c| c.get()) }
@@ -50,6 +53,18 @@ pub fn set_vector_hnsw_ef_build(v: i32) { TLS_VECTOR_HNSW_EF_BUILD.with(|c| c.se
 pub fn get_vector_preselect_alpha() -> i32 { TLS_VECTOR_PRESELECT_ALPHA.with(|c| c.get()) }
 pub fn set_vector_preselect_alpha(v: i32) { TLS_VECTOR_PRESELECT_ALPHA.with(|c| c.set(v.max(1))); }
 
+/// IVF-PQ search knobs: more lists probed or candidates re-ranked raise recall and latency.
+pub fn get_vector_ivf_nprobe() -> i32 { TLS_VECTOR_IVF_NPROBE.with(|c| c.get()) }
+pub fn set_vector_ivf_nprobe(v: i32) { TLS_VECTOR_IVF_NPROBE.with(|c| c.set(v.max(0))); }
+
+pub fn get_vector_ivf_rerank() -> i32 { TLS_VECTOR_IVF_RERANK.with(|c| c.get()) }
+pub fn set_vector_ivf_rerank(v: i32) { TLS_VECTOR_IVF_RERANK.with(|c| c.set(v.max(0))); }
+
+/// Filtered ANN searches keeping fewer than this percentage of the indexed rows score the
+/// kept rows exactly instead of walking the index.
+pub fn get_vector_prefilter_percent() -> i32 { TLS_VECTOR_PREFILTER_PERCENT.with(|c| c.get()) }
+pub fn set_vector_prefilter_percent(v: i32) { TLS_VECTOR_PREFILTER_PERCENT.with(|c| c.set(v.clamp(0, 100))); }
+
 /// Helper to accept common SET variable aliases (case-insensitive) for vector knobs
 pub fn apply_vector_setting(var: &str, val: &str) -> bool {
     let up = var.to_ascii_lowercase();
@@ -72,10 +87,128 @@ pub fn apply_vector_setting(var: &str, val: &str) -> bool {
             if let Ok(n) = val.parse::<i32>() { set_vector_preselect_alpha(n); return true; }
             return false;
         }
+        "vector.ivf.nprobe" | "vector_ivf_nprobe" | "vector.search.nprobe" => {
+            if let Ok(n) = val.parse::<i32>() { set_vector_ivf_nprobe(n); return true; }
+            return false;
+        }
+        "vector.ivf.rerank" | "vector_ivf_rerank" | "vector.search.rerank" => {
+            if let Ok(n) = val.parse::<i32>() { set_vector_ivf_rerank(n); return true; }
+            return false;
+        }
+        "vector.filter.prefilter_percent" | "vector_prefilter_percent" | "vector.prefilter_percent" => {
+            if let Ok(n) = val.parse::<i32>() { set_vector_prefilter_percent(n); return true; }
+            return false;
+        }
         _ => false,
     }
 }
 
+// ----------------------------
+// Planner toggles
+// ----------------------------
+// Switches for planner features, so a bad plan can be worked around per session
+// with `SET planner.<name> = on|off` (`SET planner.<name> = default` resets).
+pub const PLANNER_TOGGLES: &[(&str, bool)] = &[
+    ("enable_bloom_pruning", true),     // skip chunks whose bloom filter rules out WHERE col = literal
+    ("enable_partition_pruning", true), // skip partition folders excluded by WHERE col = literal
+    ("enable_hints", true),             // honour /*+ ... */ optimizer hints
+];
+
+thread_local! {
+    static TLS_PLANNER_OVERRIDES: RefCell<std::collections::HashMap<&'static str, bool>> = RefCell::new(std::collections::HashMap::new());
+}
+
+/// Current value of a planner toggle (unknown names read as enabled).
+pub fn planner_toggle(name: &str) -> bool {
+    let Some((key, default)) = PLANNER_TOGGLES.iter().find(|(k, _)| *k == name) else { return true };
+    TLS_PLANNER_OVERRIDES.with(|m| m.borrow().get(key).copied().unwrap_or(*default))
+}
+
+/// All planner toggles with their current values.
+pub fn planner_toggles() -> Vec<(&'static str, bool)> {
+    PLANNER_TOGGLES.iter().map(|(k, _)| (*k, planner_toggle(k))).collect()
+}
+
+/// Planner toggles whose current value differs from the default.
+pub fn planner_toggles_changed() -> Vec<(&'static str, bool)> {
+    PLANNER_TOGGLES.iter().filter(|(k, d)| planner_toggle(k) != *d).map(|(k, _)| (*k, planner_toggle(k))).collect()
+}
+
+/// Apply `SET planner.<name> = on|off|default`; returns false for unknown names or values.
+pub fn apply_planner_setting(var: &str, val: &str) -> bool {
+    let low = var.to_ascii_lowercase();
+    let Some(name) = low.strip_prefix("planner.").or_else(|| low.strip_prefix("planner_")) else { return false };
+    let Some((key, _)) = PLANNER_TOGGLES.iter().find(|(k, _)| *k == name) else { return false };
+    let v = val.trim().trim_matches('\'').to_ascii_lowercase();
+    let on = match v.as_str() {
+        "on" | "true" | "1" => true,
+        "off" | "false" | "0" => false,
+        "default" => { TLS_PLANNER_OVERRIDES.with(|m| m.borrow_mut().remove(key)); return true; }
+        _ => return false,
+    };
+    TLS_PLANNER_OVERRIDES.with(|m| m.borrow_mut().insert(key, on));
+    true
+}
+
+// ----------------------------
+// Per-query memory cap (work_mem)
+// ----------------------------
+thread_local! {
+    // Session override in bytes (0 = unlimited); None uses limits.work_mem_mb from the server config
+    static TLS_WORK_MEM: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+pub fn work_mem_override() -> Option<u64> { TLS_WORK_MEM.with(|c| c.get()) }
+
+/// Parse a memory size like `64MB`, `512kB`, `1GB` or `8192` (plain numbers are kB, as in PostgreSQL).
+pub fn parse_memory_size(val: &str) -> Option<u64> {
+    let v = val.trim().trim_matches('\'').trim();
+    let split = v.find(|c: char| !c.is_ascii_digit()).unwrap_or(v.len());
+    let (num, unit) = v.split_at(split);
+    let n: u64 = num.parse().ok()?;
+    let mult: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "kb" | "k" => 1024,
+        "b" => 1,
+        "mb" | "m" => 1024 * 1024,
+        "gb" | "g" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    n.checked_mul(mult)
+}
+
+/// Apply `SET work_mem = '<size>' | DEFAULT`; returns false for other names and errors on invalid sizes.
+pub fn apply_memory_setting(var: &str, val: &str) -> anyhow::Result<bool> {
+    if !var.eq_ignore_ascii_case("work_mem") { return Ok(false); }
+    if val.trim().trim_matches('\'').eq_ignore_ascii_case("default") {
+        TLS_WORK_MEM.with(|c| c.set(None));
+        return Ok(true);
+    }
+    let Some(bytes) = parse_memory_size(val) else { anyhow::bail!("invalid value for parameter \"work_mem\": {}", val) };
+    TLS_WORK_MEM.with(|c| c.set(Some(bytes)));
+    Ok(true)
+}
+
+// ----------------------------
+// Result cache opt-in (enable_result_cache)
+// ----------------------------
+thread_local! {
+    static TLS_RESULT_CACHE: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn result_cache_enabled() -> bool { TLS_RESULT_CACHE.with(|c| c.get()) }
+
+/// Apply `SET enable_result_cache = on|off|default`; returns false for other names and errors on invalid values.
+pub fn apply_result_cache_setting(var: &str, val: &str) -> anyhow::Result<bool> {
+    if !var.eq_ignore_ascii_case("enable_result_cache") { return Ok(false); }
+    let on = match val.trim().trim_matches('\'').to_ascii_lowercase().as_str() {
+        "on" | "true" | "1" => true,
+        "off" | "false" | "0" | "default" => false,
+        _ => anyhow::bail!("invalid value for parameter \"enable_result_cache\": {}", val),
+    };
+    TLS_RESULT_CACHE.with(|c| c.set(on));
+    Ok(true)
+}
+
 // Thread-local current database/schema for session-aware qualification (per-thread/session)
 thread_local! {
     static TLS_CURRENT_DB: Cell<Option<String>> = const { Cell::new(None) };
@@ -170,7 +303,60 @@ pub fn clear_graph_txn() {
 pub fn current_query_defaults() -> crate::ident::QueryDefaults {
     let db = get_current_database();
     let schema = get_current_schema();
-    crate::ident::QueryDefaults::new(db, schema)
+    with_session_search_path(crate::ident::QueryDefaults::new(db, schema))
+}
+
+// ----------------------------
+// search_path (SET search_path, SET SCHEMA, USE SCHEMA)
+// ----------------------------
+thread_local! {
+    static TLS_SEARCH_PATH: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+thread_local! {
+    // Storage root of the statement executing on this thread, for probing search_path schemas
+    static TLS_STORE_ROOT: RefCell<Option<std::path::PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Record the storage root of the statement about to run on this thread.
+pub fn set_store_root(root: &std::path::Path) { TLS_STORE_ROOT.with(|c| *c.borrow_mut() = Some(root.to_path_buf())); }
+
+/// Split a search_path value into schema names. Unquoted names are lower-cased; `$user`
+/// stands for a schema named after the session user and is dropped when there is none.
+pub fn parse_search_path(val: &str) -> Vec<String> {
+    val.split(',')
+        .map(|p| p.trim().trim_matches('\'').trim())
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| if p == "$user" || p == "\"$user\"" { crate::server::activity::current_user() } else { Some(crate::ident::normalize_identifier(p)) })
+        .collect()
+}
+
+/// Schemas searched for unqualified names: the path set with `SET search_path`, else the current schema.
+pub fn search_path() -> Vec<String> {
+    TLS_SEARCH_PATH.with(|c| c.borrow().clone()).unwrap_or_else(|| vec![get_current_schema()])
+}
+
+/// Attach the session's search_path to `d` so unqualified names resolve to the first schema holding them.
+pub fn with_session_search_path(d: crate::ident::QueryDefaults) -> crate::ident::QueryDefaults {
+    let Some(path) = TLS_SEARCH_PATH.with(|c| c.borrow().clone()) else { return d };
+    let root = TLS_STORE_ROOT.with(|c| c.borrow().clone());
+    d.with_search_path(path, root)
+}
+
+/// Apply `SET search_path = a, b, ...`: the first schema becomes the current schema (where
+/// unqualified objects are created) and the rest are searched for existing objects. DEFAULT
+/// goes back to the current schema alone.
+pub fn apply_search_path_setting(var: &str, val: &str) -> anyhow::Result<bool> {
+    if !var.eq_ignore_ascii_case("search_path") { return Ok(false); }
+    if val.trim().eq_ignore_ascii_case("default") {
+        // Only undo a path this session set; the built-in default leaves USE SCHEMA alone
+        if TLS_SEARCH_PATH.with(|c| c.borrow_mut().take()).is_some() { unset_current_schema(); }
+        return Ok(true);
+    }
+    let path = parse_search_path(val);
+    let Some(first) = path.first() else { anyhow::bail!("invalid value for parameter \"search_path\": \"{}\"", val) };
+    set_current_schema(first);
+    TLS_SEARCH_PATH.with(|c| *c.borrow_mut() = Some(path));
+    Ok(true)
 }
 
 fn strip_time_ext(name: &str) -> String {
@@ -0,0 +1,71 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::enumerate_tables;
+use crate::storage::SharedStore;
+
+pub struct Chunks;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "schema", coltype: ColType::Text },
+    ColumnDef { name: "table_name", coltype: ColType::Text },
+    // Relative to the table folder, partition folders included
+    ColumnDef { name: "chunk", coltype: ColType::Text },
+    // From the Parquet footer; NULL when it cannot be read
+    ColumnDef { name: "rows", coltype: ColType::BigInt },
+    ColumnDef { name: "bytes", coltype: ColType::BigInt },
+    // Time range of time-table chunks (epoch ms); NULL for regular tables
+    ColumnDef { name: "min_time", coltype: ColType::BigInt },
+    ColumnDef { name: "max_time", coltype: ColType::BigInt },
+    ColumnDef { name: "written_at", coltype: ColType::BigInt },
+    ColumnDef { name: "compression", coltype: ColType::Text },
+];
+
+impl SystemTable for Chunks {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "chunks" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut database: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut table: Vec<String> = Vec::new();
+        let mut chunk: Vec<String> = Vec::new();
+        let mut rows: Vec<Option<i64>> = Vec::new();
+        let mut bytes: Vec<i64> = Vec::new();
+        let mut min_time: Vec<Option<i64>> = Vec::new();
+        let mut max_time: Vec<Option<i64>> = Vec::new();
+        let mut written_at: Vec<Option<i64>> = Vec::new();
+        let mut compression: Vec<Option<String>> = Vec::new();
+
+        for m in enumerate_tables(store).iter() {
+            for c in crate::storage::partition::chunk_infos(&m.dir) {
+                database.push(m.db.clone());
+                schema.push(m.schema.clone());
+                table.push(m.display_name().to_string());
+                chunk.push(c.path);
+                rows.push(c.rows.map(|r| r as i64));
+                bytes.push(c.bytes as i64);
+                min_time.push(c.min_time);
+                max_time.push(c.max_time);
+                written_at.push(c.written_at);
+                compression.push(c.compression);
+            }
+        }
+
+        DataFrame::new(vec![
+            Series::new("database".into(), database).into(),
+            Series::new("schema".into(), schema).into(),
+            Series::new("table_name".into(), table).into(),
+            Series::new("chunk".into(), chunk).into(),
+            Series::new("rows".into(), rows).into(),
+            Series::new("bytes".into(), bytes).into(),
+            Series::new("min_time".into(), min_time).into(),
+            Series::new("max_time".into(), max_time).into(),
+            Series::new("written_at".into(), written_at).into(),
+            Series::new("compression".into(), compression).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(Chunks)); }
@@ -0,0 +1,50 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+use super::filestore_files::live_files;
+
+/// One row per metadata entry of a live file, for filtering files by tag.
+pub struct FilestoreFileMetadata;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "filestore", coltype: ColType::Text },
+    ColumnDef { name: "path", coltype: ColType::Text },
+    ColumnDef { name: "key", coltype: ColType::Text },
+    ColumnDef { name: "value", coltype: ColType::Text },
+];
+
+impl SystemTable for FilestoreFileMetadata {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "filestore_file_metadata" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut database: Vec<String> = Vec::new();
+        let mut filestore: Vec<String> = Vec::new();
+        let mut path: Vec<String> = Vec::new();
+        let mut key: Vec<String> = Vec::new();
+        let mut value: Vec<String> = Vec::new();
+
+        for (db, fs, m) in live_files(store) {
+            for (k, v) in m.metadata() {
+                database.push(db.clone());
+                filestore.push(fs.clone());
+                path.push(m.logical_path.clone());
+                key.push(k);
+                value.push(v);
+            }
+        }
+
+        DataFrame::new(vec![
+            Series::new("database".into(), database).into(),
+            Series::new("filestore".into(), filestore).into(),
+            Series::new("path".into(), path).into(),
+            Series::new("key".into(), key).into(),
+            Series::new("value".into(), value).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(FilestoreFileMetadata)); }
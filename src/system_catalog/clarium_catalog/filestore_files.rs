@@ -0,0 +1,85 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::pg_catalog::pg_database::database_names;
+use crate::server::exec::filestore::{list_filestore_entries, list_files_by_prefix, FileMeta};
+use crate::storage::SharedStore;
+
+pub struct FilestoreFiles;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "filestore", coltype: ColType::Text },
+    ColumnDef { name: "path", coltype: ColType::Text },
+    ColumnDef { name: "size", coltype: ColType::BigInt },
+    ColumnDef { name: "content_type", coltype: ColType::Text },
+    ColumnDef { name: "etag", coltype: ColType::Text },
+    ColumnDef { name: "version", coltype: ColType::BigInt },
+    ColumnDef { name: "created_at", coltype: ColType::BigInt },
+    ColumnDef { name: "updated_at", coltype: ColType::BigInt },
+    // User-defined metadata as a JSON object; NULL when there is none
+    ColumnDef { name: "metadata", coltype: ColType::Text },
+];
+
+/// Live (not deleted) files of every filestore, as (database, filestore, meta).
+pub(crate) fn live_files(store: &SharedStore) -> Vec<(String, String, FileMeta)> {
+    let reg = store.kv_registry();
+    let mut out = Vec::new();
+    for db in database_names(store) {
+        // As for clarium_catalog.filestores: the registry lives in the database's default KV store
+        if !reg.list_stores(&db).iter().any(|s| s == crate::lua_bc::DEFAULT_KV_STORE) { continue; }
+        let Ok(entries) = list_filestore_entries(store, &db) else { continue };
+        for e in entries {
+            let Ok(files) = list_files_by_prefix(store, &db, &e.name, None) else { continue };
+            out.extend(files.into_iter().filter(|m| !m.deleted).map(|m| (db.clone(), e.name.clone(), m)));
+        }
+    }
+    out
+}
+
+impl SystemTable for FilestoreFiles {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "filestore_files" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut database: Vec<String> = Vec::new();
+        let mut filestore: Vec<String> = Vec::new();
+        let mut path: Vec<String> = Vec::new();
+        let mut size: Vec<i64> = Vec::new();
+        let mut content_type: Vec<Option<String>> = Vec::new();
+        let mut etag: Vec<String> = Vec::new();
+        let mut version: Vec<i64> = Vec::new();
+        let mut created_at: Vec<i64> = Vec::new();
+        let mut updated_at: Vec<i64> = Vec::new();
+        let mut metadata: Vec<Option<String>> = Vec::new();
+
+        for (db, fs, m) in live_files(store) {
+            let md = m.metadata();
+            database.push(db);
+            filestore.push(fs);
+            path.push(m.logical_path);
+            size.push(m.size as i64);
+            content_type.push(m.content_type);
+            etag.push(m.etag);
+            version.push(m.version as i64);
+            created_at.push(m.created_at);
+            updated_at.push(m.updated_at);
+            metadata.push((!md.is_empty()).then(|| serde_json::to_string(&md).unwrap_or_default()));
+        }
+
+        DataFrame::new(vec![
+            Series::new("database".into(), database).into(),
+            Series::new("filestore".into(), filestore).into(),
+            Series::new("path".into(), path).into(),
+            Series::new("size".into(), size).into(),
+            Series::new("content_type".into(), content_type).into(),
+            Series::new("etag".into(), etag).into(),
+            Series::new("version".into(), version).into(),
+            Series::new("created_at".into(), created_at).into(),
+            Series::new("updated_at".into(), updated_at).into(),
+            Series::new("metadata".into(), metadata).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(FilestoreFiles)); }
@@ -0,0 +1,73 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::pg_catalog::pg_database::database_names;
+use crate::storage::SharedStore;
+
+pub struct Filestores;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "name", coltype: ColType::Text },
+    ColumnDef { name: "git_remote", coltype: ColType::Text },
+    ColumnDef { name: "git_branch", coltype: ColType::Text },
+    ColumnDef { name: "git_mode", coltype: ColType::Text },
+    ColumnDef { name: "git_push_backend", coltype: ColType::Text },
+    ColumnDef { name: "lfs_patterns", coltype: ColType::Text },
+    ColumnDef { name: "config_version", coltype: ColType::BigInt },
+    ColumnDef { name: "created_at", coltype: ColType::BigInt },
+    ColumnDef { name: "updated_at", coltype: ColType::BigInt },
+];
+
+impl SystemTable for Filestores {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "filestores" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut database: Vec<String> = Vec::new();
+        let mut name: Vec<String> = Vec::new();
+        let mut git_remote: Vec<Option<String>> = Vec::new();
+        let mut git_branch: Vec<Option<String>> = Vec::new();
+        let mut git_mode: Vec<String> = Vec::new();
+        let mut git_push_backend: Vec<String> = Vec::new();
+        let mut lfs_patterns: Vec<Option<String>> = Vec::new();
+        let mut config_version: Vec<i64> = Vec::new();
+        let mut created_at: Vec<i64> = Vec::new();
+        let mut updated_at: Vec<i64> = Vec::new();
+
+        let reg = store.kv_registry();
+        for db in database_names(store) {
+            // The filestore registry lives in the database's default KV store; skip databases
+            // without one rather than creating it
+            if !reg.list_stores(&db).iter().any(|s| s == crate::lua_bc::DEFAULT_KV_STORE) { continue; }
+            let Ok(entries) = crate::server::exec::filestore::list_filestore_entries(store, &db) else { continue };
+            for e in entries {
+                database.push(db.clone());
+                name.push(e.name.clone());
+                git_remote.push(e.config.git_remote.clone());
+                git_branch.push(e.config.git_branch.clone());
+                git_mode.push(e.config.git_mode.clone().unwrap_or_else(|| "plumbing_only".to_string()));
+                git_push_backend.push(e.config.git_push_backend.clone().unwrap_or_else(|| "auto".to_string()));
+                lfs_patterns.push(e.config.lfs_patterns.clone());
+                config_version.push(e.config_version as i64);
+                created_at.push(e.created_at);
+                updated_at.push(e.updated_at);
+            }
+        }
+
+        DataFrame::new(vec![
+            Series::new("database".into(), database).into(),
+            Series::new("name".into(), name).into(),
+            Series::new("git_remote".into(), git_remote).into(),
+            Series::new("git_branch".into(), git_branch).into(),
+            Series::new("git_mode".into(), git_mode).into(),
+            Series::new("git_push_backend".into(), git_push_backend).into(),
+            Series::new("lfs_patterns".into(), lfs_patterns).into(),
+            Series::new("config_version".into(), config_version).into(),
+            Series::new("created_at".into(), created_at).into(),
+            Series::new("updated_at".into(), updated_at).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(Filestores)); }
@@ -0,0 +1,61 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::enumerate_graphs;
+use crate::server::exec::exec_graph::GraphFile;
+use crate::storage::SharedStore;
+
+pub struct Graphs;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "schema", coltype: ColType::Text },
+    ColumnDef { name: "name", coltype: ColType::Text },
+    // Node labels and edge types, comma separated
+    ColumnDef { name: "node_labels", coltype: ColType::Text },
+    ColumnDef { name: "edge_types", coltype: ColType::Text },
+    ColumnDef { name: "engine", coltype: ColType::Text },
+    ColumnDef { name: "graphstore_config", coltype: ColType::Text },
+    ColumnDef { name: "created_at", coltype: ColType::Text },
+];
+
+impl SystemTable for Graphs {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "graphs" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut database: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut name: Vec<String> = Vec::new();
+        let mut node_labels: Vec<String> = Vec::new();
+        let mut edge_types: Vec<String> = Vec::new();
+        let mut engine: Vec<Option<String>> = Vec::new();
+        let mut graphstore_config: Vec<Option<String>> = Vec::new();
+        let mut created_at: Vec<Option<String>> = Vec::new();
+
+        for g in enumerate_graphs(store) {
+            let Some(gf) = std::fs::read_to_string(&g.file).ok().and_then(|t| serde_json::from_str::<GraphFile>(&t).ok()) else { continue };
+            database.push(g.db);
+            schema.push(g.schema);
+            name.push(g.name);
+            node_labels.push(gf.nodes.iter().map(|n| n.label.as_str()).collect::<Vec<_>>().join(", "));
+            edge_types.push(gf.edges.iter().map(|e| e.r#type.as_str()).collect::<Vec<_>>().join(", "));
+            engine.push(gf.engine);
+            graphstore_config.push(gf.graphstore_config);
+            created_at.push(gf.created_at);
+        }
+
+        DataFrame::new(vec![
+            Series::new("database".into(), database).into(),
+            Series::new("schema".into(), schema).into(),
+            Series::new("name".into(), name).into(),
+            Series::new("node_labels".into(), node_labels).into(),
+            Series::new("edge_types".into(), edge_types).into(),
+            Series::new("engine".into(), engine).into(),
+            Series::new("graphstore_config".into(), graphstore_config).into(),
+            Series::new("created_at".into(), created_at).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(Graphs)); }
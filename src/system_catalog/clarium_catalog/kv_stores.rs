@@ -0,0 +1,63 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::pg_catalog::pg_database::database_names;
+use crate::storage::SharedStore;
+
+pub struct KvStores;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "store_name", coltype: ColType::Text },
+    ColumnDef { name: "loaded", coltype: ColType::Boolean },
+    // NULL until the store is loaded in memory
+    ColumnDef { name: "key_count", coltype: ColType::BigInt },
+    ColumnDef { name: "reset_on_access", coltype: ColType::Boolean },
+    ColumnDef { name: "persistence_enabled", coltype: ColType::Boolean },
+    ColumnDef { name: "persistence_interval_ms", coltype: ColType::BigInt },
+    ColumnDef { name: "encrypted", coltype: ColType::Boolean },
+];
+
+impl SystemTable for KvStores {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "kv_stores" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let reg = store.kv_registry();
+        let mut database: Vec<String> = Vec::new();
+        let mut store_name: Vec<String> = Vec::new();
+        let mut loaded: Vec<bool> = Vec::new();
+        let mut key_count: Vec<Option<i64>> = Vec::new();
+        let mut reset_on_access: Vec<bool> = Vec::new();
+        let mut persist: Vec<bool> = Vec::new();
+        let mut interval: Vec<Option<i64>> = Vec::new();
+        let mut encrypted: Vec<bool> = Vec::new();
+
+        for db in database_names(store) {
+            for name in reg.list_stores(&db) {
+                let (settings, keys) = reg.store_info(&db, &name);
+                database.push(db.clone());
+                store_name.push(name);
+                loaded.push(keys.is_some());
+                key_count.push(keys.map(|n| n as i64));
+                reset_on_access.push(settings.reset_on_access_default);
+                persist.push(settings.persistence.as_ref().map(|p| p.enabled).unwrap_or(false));
+                interval.push(settings.persistence.as_ref().map(|p| p.interval_ms as i64));
+                encrypted.push(settings.encryption.is_some());
+            }
+        }
+
+        DataFrame::new(vec![
+            Series::new("database".into(), database).into(),
+            Series::new("store_name".into(), store_name).into(),
+            Series::new("loaded".into(), loaded).into(),
+            Series::new("key_count".into(), key_count).into(),
+            Series::new("reset_on_access".into(), reset_on_access).into(),
+            Series::new("persistence_enabled".into(), persist).into(),
+            Series::new("persistence_interval_ms".into(), interval).into(),
+            Series::new("encrypted".into(), encrypted).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(KvStores)); }
@@ -0,0 +1,29 @@
+//! clarium_catalog: SQL-queryable listings of Clarium-native objects (KV stores, Lua
+//! scripts and their execution counters, filestores and their files, graphs, vector indexes
+//! and table chunks), built from the same registries and sidecar files the SHOW commands read.
+
+pub mod kv_stores;
+pub mod scripts;
+pub mod script_stats;
+pub mod filestores;
+pub mod filestore_files;
+pub mod filestore_file_metadata;
+pub mod graphs;
+pub mod vector_indexes;
+pub mod chunks;
+
+pub const SCHEMA: &str = "clarium_catalog";
+/// pg_namespace oid of the schema.
+pub const NAMESPACE_OID: i32 = 13300;
+
+pub fn register_defaults() {
+    kv_stores::register();
+    scripts::register();
+    script_stats::register();
+    filestores::register();
+    filestore_files::register();
+    filestore_file_metadata::register();
+    graphs::register();
+    vector_indexes::register();
+    chunks::register();
+}
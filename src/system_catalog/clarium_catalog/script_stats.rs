@@ -0,0 +1,59 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+pub struct ScriptStats;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "name", coltype: ColType::Text },
+    // scalar, aggregate, constraint or tvf; NULL when the script is no longer registered
+    ColumnDef { name: "kind", coltype: ColType::Text },
+    ColumnDef { name: "calls", coltype: ColType::BigInt },
+    ColumnDef { name: "errors", coltype: ColType::BigInt },
+    ColumnDef { name: "total_ms", coltype: ColType::Double },
+    ColumnDef { name: "mean_ms", coltype: ColType::Double },
+];
+
+impl SystemTable for ScriptStats {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "script_stats" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        // Counters since startup, for every function called at least once
+        let reg = crate::scripts::get_script_registry();
+        let mut name: Vec<String> = Vec::new();
+        let mut kind: Vec<Option<String>> = Vec::new();
+        let mut calls: Vec<i64> = Vec::new();
+        let mut errors: Vec<i64> = Vec::new();
+        let mut total_ms: Vec<f64> = Vec::new();
+        let mut mean_ms: Vec<f64> = Vec::new();
+
+        for (fname, s) in crate::script_stats::snapshot() {
+            kind.push(reg.as_ref().filter(|r| r.has_function(&fname)).map(|r| {
+                match r.get_meta(&fname).map(|m| m.kind) {
+                    Some(crate::scripts::ScriptKind::Aggregate) => "aggregate",
+                    Some(crate::scripts::ScriptKind::Constraint) => "constraint",
+                    Some(crate::scripts::ScriptKind::Tvf) => "tvf",
+                    _ => "scalar",
+                }.to_string()
+            }));
+            calls.push(s.calls as i64);
+            errors.push(s.errors as i64);
+            total_ms.push(s.total_ms());
+            mean_ms.push(s.mean_ms());
+            name.push(fname);
+        }
+
+        DataFrame::new(vec![
+            Series::new("name".into(), name).into(),
+            Series::new("kind".into(), kind).into(),
+            Series::new("calls".into(), calls).into(),
+            Series::new("errors".into(), errors).into(),
+            Series::new("total_ms".into(), total_ms).into(),
+            Series::new("mean_ms".into(), mean_ms).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ScriptStats)); }
@@ -0,0 +1,65 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::scripts::ScriptKind;
+use crate::storage::SharedStore;
+
+pub struct Scripts;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "name", coltype: ColType::Text },
+    ColumnDef { name: "kind", coltype: ColType::Text },
+    // Declared return types, comma separated; NULL when undeclared
+    ColumnDef { name: "returns", coltype: ColType::Text },
+    ColumnDef { name: "nullable", coltype: ColType::Boolean },
+    ColumnDef { name: "version", coltype: ColType::BigInt },
+    ColumnDef { name: "comment", coltype: ColType::Text },
+    ColumnDef { name: "source", coltype: ColType::Text },
+];
+
+impl SystemTable for Scripts {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "scripts" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        // Every registered name, including the pg_catalog.<name> aliases of global scripts
+        let functions = crate::scripts::get_script_registry().map(|r| r.list_functions()).unwrap_or_default();
+        let comments = crate::storage::comments::function_comments(&store.root_path());
+        let mut name: Vec<String> = Vec::new();
+        let mut kind: Vec<String> = Vec::new();
+        let mut returns: Vec<Option<String>> = Vec::new();
+        let mut nullable: Vec<Option<bool>> = Vec::new();
+        let mut version: Vec<Option<i64>> = Vec::new();
+        let mut comment: Vec<Option<String>> = Vec::new();
+        let mut source: Vec<String> = Vec::new();
+
+        for (fname, code, meta) in functions {
+            let k = match meta.as_ref().map(|m| &m.kind) {
+                Some(ScriptKind::Aggregate) => "aggregate",
+                Some(ScriptKind::Constraint) => "constraint",
+                Some(ScriptKind::Tvf) => "tvf",
+                _ => "scalar",
+            };
+            kind.push(k.to_string());
+            returns.push(meta.as_ref().filter(|m| !m.returns.is_empty())
+                .map(|m| m.returns.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")));
+            nullable.push(meta.as_ref().map(|m| m.nullable));
+            version.push(meta.as_ref().map(|m| m.version as i64));
+            comment.push(comments.get(&fname).cloned());
+            name.push(fname);
+            source.push(code);
+        }
+
+        DataFrame::new(vec![
+            Series::new("name".into(), name).into(),
+            Series::new("kind".into(), kind).into(),
+            Series::new("returns".into(), returns).into(),
+            Series::new("nullable".into(), nullable).into(),
+            Series::new("version".into(), version).into(),
+            Series::new("comment".into(), comment).into(),
+            Series::new("source".into(), source).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(Scripts)); }
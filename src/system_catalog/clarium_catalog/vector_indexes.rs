@@ -0,0 +1,87 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::enumerate_vector_indexes;
+use crate::server::exec::exec_vector_index::VIndexFile;
+use crate::storage::SharedStore;
+
+pub struct VectorIndexes;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "schema", coltype: ColType::Text },
+    ColumnDef { name: "name", coltype: ColType::Text },
+    ColumnDef { name: "table_name", coltype: ColType::Text },
+    ColumnDef { name: "column_name", coltype: ColType::Text },
+    ColumnDef { name: "algo", coltype: ColType::Text },
+    ColumnDef { name: "metric", coltype: ColType::Text },
+    ColumnDef { name: "dim", coltype: ColType::Integer },
+    ColumnDef { name: "mode", coltype: ColType::Text },
+    // From the build status: NULL until the index was built
+    ColumnDef { name: "state", coltype: ColType::Text },
+    // Table chunks changed since the last build; NULL when unknown
+    ColumnDef { name: "stale", coltype: ColType::Boolean },
+    ColumnDef { name: "rows_indexed", coltype: ColType::BigInt },
+    ColumnDef { name: "last_built_at", coltype: ColType::Text },
+    ColumnDef { name: "created_at", coltype: ColType::Text },
+];
+
+impl SystemTable for VectorIndexes {
+    fn schema(&self) -> &'static str { super::SCHEMA }
+    fn name(&self) -> &'static str { "vector_indexes" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut database: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut name: Vec<String> = Vec::new();
+        let mut table: Vec<String> = Vec::new();
+        let mut column: Vec<String> = Vec::new();
+        let mut algo: Vec<String> = Vec::new();
+        let mut metric: Vec<Option<String>> = Vec::new();
+        let mut dim: Vec<Option<i32>> = Vec::new();
+        let mut mode: Vec<Option<String>> = Vec::new();
+        let mut state: Vec<Option<String>> = Vec::new();
+        let mut stale: Vec<Option<bool>> = Vec::new();
+        let mut rows_indexed: Vec<Option<i64>> = Vec::new();
+        let mut last_built_at: Vec<Option<String>> = Vec::new();
+        let mut created_at: Vec<Option<String>> = Vec::new();
+
+        for x in enumerate_vector_indexes(store) {
+            let Some(vf) = std::fs::read_to_string(&x.file).ok().and_then(|t| serde_json::from_str::<VIndexFile>(&t).ok()) else { continue };
+            let status = |k: &str| vf.status.as_ref().and_then(|m| m.get(k)).cloned();
+            database.push(x.db);
+            schema.push(x.schema);
+            name.push(x.name);
+            state.push(status("state").and_then(|v| v.as_str().map(|s| s.to_string())));
+            stale.push(crate::server::exec::exec_vector_runtime::is_stale(store, &vf));
+            rows_indexed.push(status("rows_indexed").and_then(|v| v.as_i64()));
+            last_built_at.push(status("last_built_at").and_then(|v| v.as_str().map(|s| s.to_string())));
+            table.push(vf.table);
+            column.push(vf.column);
+            algo.push(vf.algo);
+            metric.push(vf.metric);
+            dim.push(vf.dim);
+            mode.push(vf.mode);
+            created_at.push(vf.created_at);
+        }
+
+        DataFrame::new(vec![
+            Series::new("database".into(), database).into(),
+            Series::new("schema".into(), schema).into(),
+            Series::new("name".into(), name).into(),
+            Series::new("table_name".into(), table).into(),
+            Series::new("column_name".into(), column).into(),
+            Series::new("algo".into(), algo).into(),
+            Series::new("metric".into(), metric).into(),
+            Series::new("dim".into(), dim).into(),
+            Series::new("mode".into(), mode).into(),
+            Series::new("state".into(), state).into(),
+            Series::new("stale".into(), stale).into(),
+            Series::new("rows_indexed".into(), rows_indexed).into(),
+            Series::new("last_built_at".into(), last_built_at).into(),
+            Series::new("created_at".into(), created_at).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(VectorIndexes)); }
@@ -15,6 +15,8 @@ const COLS: &[ColumnDef] = &[
     ColumnDef { name: "data_type", coltype: ColType::Text },
     ColumnDef { name: "is_nullable", coltype: ColType::Text },
     ColumnDef { name: "udt_name", coltype: ColType::Text },
+    // clarium extension: COMMENT ON COLUMN text (also in pg_description)
+    ColumnDef { name: "column_comment", coltype: ColType::Text },
 ];
 
 impl SystemTable for IColumns {
@@ -29,10 +31,11 @@ impl SystemTable for IColumns {
         let mut data_type: Vec<String> = Vec::new();
         let mut is_null: Vec<String> = Vec::new();
         let mut udt_name: Vec<String> = Vec::new();
+        let mut comment: Vec<Option<String>> = Vec::new();
 
         // 1) Real user tables
         let root = store.root_path();
-        if let Ok(dbs) = std::fs::read_dir(&root) {
+        if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
             for db_ent in dbs.flatten() {
                 let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
                 if let Ok(sd) = std::fs::read_dir(&db_path) {
@@ -78,11 +81,13 @@ impl SystemTable for IColumns {
                                     }
                                     tprintln!("[IColumns] schema='{}' table='{}' sj_exists={} time_table={} cols={} src='{}'", schema_name, tname, sj.exists(), is_time_table, cols.len(), tp.display());
                                     if !cols.is_empty() {
+                                        let comments = crate::storage::comments::table_comments(&tp);
                                         let mut ord = 1i32;
                                         for (cname, ctype) in cols {
                                             schema_col.push(schema_name.clone());
                                             table_col.push(tname.clone());
                                             let is_time_col = is_time_table && cname == "_time";
+                                            comment.push(comments.columns.get(&cname).cloned());
                                             col_name.push(cname);
                                             ord_pos.push(ord);
                                             let (dt, udt) = map_dtype(&ctype);
@@ -113,11 +118,13 @@ impl SystemTable for IColumns {
                     ColType::BigInt => ("bigint", "int8"),
                     ColType::Integer => ("integer", "int4"),
                     ColType::Boolean => ("boolean", "bool"),
+                    ColType::Double => ("double precision", "float8"),
                     ColType::Text => ("text", "text"),
                 };
                 data_type.push(dt.to_string());
                 is_null.push("YES".to_string());
                 udt_name.push(udt.to_string());
+                comment.push(None);
                 ord += 1;
             }
         }
@@ -141,6 +148,7 @@ impl SystemTable for IColumns {
                 // View columns are nullable by default
                 is_null.push("YES".to_string());
                 udt_name.push(udt.to_string());
+                comment.push(None);
                 ord += 1;
             }
         }
@@ -153,6 +161,7 @@ impl SystemTable for IColumns {
             Series::new("data_type".into(), data_type).into(),
             Series::new("is_nullable".into(), is_null).into(),
             Series::new("udt_name".into(), udt_name).into(),
+            Series::new("column_comment".into(), comment).into(),
         ]).ok()
     }
 }
@@ -171,6 +180,8 @@ fn map_dtype(dtype: &str) -> (&'static str, &'static str) {
         }
         "bool" | "boolean" => ("boolean", "bool"),
         "timestamp" | "datetime" => ("timestamp", "timestamp"),
+        // decimal(p,s) schema keys
+        other if other.starts_with("decimal") => ("numeric", "numeric"),
         other => {
             // default to text for unknowns
             if other.eq("text") { ("text", "text") } else { ("text", "text") }
@@ -0,0 +1,78 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::{enumerate_tables, referenced_key};
+use crate::storage::SharedStore;
+
+pub struct IKeyColumnUsage;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "constraint_catalog", coltype: ColType::Text },
+    ColumnDef { name: "constraint_schema", coltype: ColType::Text },
+    ColumnDef { name: "constraint_name", coltype: ColType::Text },
+    ColumnDef { name: "table_catalog", coltype: ColType::Text },
+    ColumnDef { name: "table_schema", coltype: ColType::Text },
+    ColumnDef { name: "table_name", coltype: ColType::Text },
+    ColumnDef { name: "column_name", coltype: ColType::Text },
+    ColumnDef { name: "ordinal_position", coltype: ColType::Integer },
+    ColumnDef { name: "position_in_unique_constraint", coltype: ColType::Integer },
+];
+
+impl SystemTable for IKeyColumnUsage {
+    fn schema(&self) -> &'static str { "information_schema" }
+    fn name(&self) -> &'static str { "key_column_usage" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let metas = enumerate_tables(store);
+        let mut catalog: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut name: Vec<String> = Vec::new();
+        let mut table: Vec<String> = Vec::new();
+        let mut column: Vec<String> = Vec::new();
+        let mut ordinal: Vec<i32> = Vec::new();
+        let mut pos_in_unique: Vec<Option<i32>> = Vec::new();
+
+        for m in metas.iter() {
+            let mut push = |cname: &str, col: &str, ord: usize, unique_pos: Option<i32>| {
+                catalog.push(m.db.clone());
+                schema.push(m.schema.clone());
+                name.push(cname.to_string());
+                table.push(m.display_name().to_string());
+                column.push(col.to_string());
+                ordinal.push(ord as i32);
+                pos_in_unique.push(unique_pos);
+            };
+            let pk_name = m.primary_key_name();
+            for (i, col) in m.primary_key_columns().iter().enumerate() { push(&pk_name, col, i + 1, None); }
+            for c in &m.constraints {
+                let kind = c.contype();
+                // CHECK and EXCLUDE constraints are not key constraints
+                if kind != 'u' && kind != 'f' { continue; }
+                let cname = m.constraint_name(c);
+                // For foreign keys: position of the referenced column within the referenced key
+                let referenced = if kind == 'f' { referenced_key(&metas, m, c) } else { None };
+                for (i, col) in c.columns.iter().enumerate() {
+                    let unique_pos = referenced.as_ref().and_then(|(_, _, key)| {
+                        let ref_col = c.ref_columns.get(i).or_else(|| key.get(i))?;
+                        key.iter().position(|k| k == ref_col).map(|p| (p + 1) as i32)
+                    });
+                    push(&cname, col, i + 1, unique_pos);
+                }
+            }
+        }
+
+        DataFrame::new(vec![
+            Series::new("constraint_catalog".into(), catalog.clone()).into(),
+            Series::new("constraint_schema".into(), schema.clone()).into(),
+            Series::new("constraint_name".into(), name).into(),
+            Series::new("table_catalog".into(), catalog).into(),
+            Series::new("table_schema".into(), schema).into(),
+            Series::new("table_name".into(), table).into(),
+            Series::new("column_name".into(), column).into(),
+            Series::new("ordinal_position".into(), ordinal).into(),
+            Series::new("position_in_unique_constraint".into(), pos_in_unique).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(IKeyColumnUsage)); }
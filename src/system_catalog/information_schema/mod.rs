@@ -2,6 +2,11 @@ pub mod schemata;
 pub mod tables;
 pub mod columns;
 pub mod views;
+pub mod table_constraints;
+pub mod key_column_usage;
+pub mod referential_constraints;
+pub mod routines;
+pub mod table_privileges;
 
 use crate::system_catalog::registry::{self as reg, ColumnDef, ColType, NoOpSystemTable};
 
@@ -44,6 +49,11 @@ pub fn register_defaults() {
     tables::register();
     columns::register();
     views::register();
+    table_constraints::register();
+    key_column_usage::register();
+    referential_constraints::register();
+    routines::register();
+    table_privileges::register();
 
     // Register NoOp information_schema tables
     let regs: &[(&str, &[ColumnDef])] = &[
@@ -0,0 +1,85 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::{enumerate_tables, referenced_key};
+use crate::storage::SharedStore;
+
+pub struct IReferentialConstraints;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "constraint_catalog", coltype: ColType::Text },
+    ColumnDef { name: "constraint_schema", coltype: ColType::Text },
+    ColumnDef { name: "constraint_name", coltype: ColType::Text },
+    ColumnDef { name: "unique_constraint_catalog", coltype: ColType::Text },
+    ColumnDef { name: "unique_constraint_schema", coltype: ColType::Text },
+    ColumnDef { name: "unique_constraint_name", coltype: ColType::Text },
+    ColumnDef { name: "match_option", coltype: ColType::Text },
+    ColumnDef { name: "update_rule", coltype: ColType::Text },
+    ColumnDef { name: "delete_rule", coltype: ColType::Text },
+];
+
+fn map_rule(action: Option<&str>) -> &'static str {
+    match action.unwrap_or("").to_ascii_lowercase().replace('_', " ").as_str() {
+        "cascade" => "CASCADE",
+        "set null" | "setnull" => "SET NULL",
+        "set default" | "setdefault" => "SET DEFAULT",
+        "restrict" => "RESTRICT",
+        _ => "NO ACTION",
+    }
+}
+
+fn map_match(match_type: Option<&str>) -> &'static str {
+    match match_type.unwrap_or("").to_ascii_lowercase().as_str() {
+        "full" => "FULL",
+        "partial" => "PARTIAL",
+        _ => "NONE",
+    }
+}
+
+impl SystemTable for IReferentialConstraints {
+    fn schema(&self) -> &'static str { "information_schema" }
+    fn name(&self) -> &'static str { "referential_constraints" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let metas = enumerate_tables(store);
+        let mut catalog: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut name: Vec<String> = Vec::new();
+        let mut uq_catalog: Vec<Option<String>> = Vec::new();
+        let mut uq_schema: Vec<Option<String>> = Vec::new();
+        let mut uq_name: Vec<Option<String>> = Vec::new();
+        let mut match_option: Vec<String> = Vec::new();
+        let mut update_rule: Vec<String> = Vec::new();
+        let mut delete_rule: Vec<String> = Vec::new();
+
+        for m in metas.iter() {
+            for c in m.constraints.iter().filter(|c| c.contype() == 'f') {
+                catalog.push(m.db.clone());
+                schema.push(m.schema.clone());
+                name.push(m.constraint_name(c));
+                // NULL when the referenced table or key is missing, as for a dangling reference
+                let referenced = referenced_key(&metas, m, c);
+                uq_catalog.push(referenced.as_ref().map(|(rm, _, _)| rm.db.clone()));
+                uq_schema.push(referenced.as_ref().map(|(rm, _, _)| rm.schema.clone()));
+                uq_name.push(referenced.map(|(_, kname, _)| kname));
+                match_option.push(map_match(c.match_type.as_deref()).to_string());
+                update_rule.push(map_rule(c.on_update.as_deref()).to_string());
+                delete_rule.push(map_rule(c.on_delete.as_deref()).to_string());
+            }
+        }
+
+        DataFrame::new(vec![
+            Series::new("constraint_catalog".into(), catalog).into(),
+            Series::new("constraint_schema".into(), schema).into(),
+            Series::new("constraint_name".into(), name).into(),
+            Series::new("unique_constraint_catalog".into(), uq_catalog).into(),
+            Series::new("unique_constraint_schema".into(), uq_schema).into(),
+            Series::new("unique_constraint_name".into(), uq_name).into(),
+            Series::new("match_option".into(), match_option).into(),
+            Series::new("update_rule".into(), update_rule).into(),
+            Series::new("delete_rule".into(), delete_rule).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(IReferentialConstraints)); }
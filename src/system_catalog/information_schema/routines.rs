@@ -0,0 +1,93 @@
+use polars::prelude::{DataFrame, DataType, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::scripts::ScriptKind;
+use crate::storage::SharedStore;
+
+pub struct IRoutines;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "specific_catalog", coltype: ColType::Text },
+    ColumnDef { name: "specific_schema", coltype: ColType::Text },
+    ColumnDef { name: "specific_name", coltype: ColType::Text },
+    ColumnDef { name: "routine_catalog", coltype: ColType::Text },
+    ColumnDef { name: "routine_schema", coltype: ColType::Text },
+    ColumnDef { name: "routine_name", coltype: ColType::Text },
+    ColumnDef { name: "routine_type", coltype: ColType::Text },
+    ColumnDef { name: "data_type", coltype: ColType::Text },
+    ColumnDef { name: "routine_body", coltype: ColType::Text },
+    ColumnDef { name: "routine_definition", coltype: ColType::Text },
+    ColumnDef { name: "external_language", coltype: ColType::Text },
+    ColumnDef { name: "is_deterministic", coltype: ColType::Text },
+    ColumnDef { name: "sql_data_access", coltype: ColType::Text },
+    ColumnDef { name: "security_type", coltype: ColType::Text },
+];
+
+fn map_return_type(dt: &DataType) -> &'static str {
+    match dt {
+        DataType::Boolean => "boolean",
+        DataType::Int32 => "integer",
+        DataType::Int64 => "bigint",
+        DataType::Float32 => "real",
+        DataType::Float64 => "double precision",
+        DataType::Date => "date",
+        DataType::Time => "time without time zone",
+        DataType::Datetime(_, _) => "timestamp without time zone",
+        DataType::Duration(_) => "interval",
+        DataType::List(_) => "ARRAY",
+        _ => "text",
+    }
+}
+
+impl SystemTable for IRoutines {
+    fn schema(&self) -> &'static str { "information_schema" }
+    fn name(&self) -> &'static str { "routines" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let functions = crate::scripts::get_script_registry().map(|r| r.list_functions()).unwrap_or_default();
+        let catalog = crate::system::get_current_database();
+        let mut schema: Vec<String> = Vec::new();
+        let mut name: Vec<String> = Vec::new();
+        let mut data_type: Vec<String> = Vec::new();
+        let mut definition: Vec<String> = Vec::new();
+
+        for (fname, source, meta) in functions.iter() {
+            // Global scripts are registered both bare and as pg_catalog.<name>; list them once
+            let (sch, routine) = match fname.split_once('.') {
+                Some((s, n)) => (s.to_string(), n.to_string()),
+                None if functions.iter().any(|(o, _, _)| o == &format!("pg_catalog.{}", fname)) => continue,
+                None => ("public".to_string(), fname.clone()),
+            };
+            let dtype = match meta {
+                Some(m) if matches!(m.kind, ScriptKind::Tvf) => "record",
+                Some(m) if m.returns.len() > 1 => "record",
+                Some(m) if m.returns.len() == 1 => map_return_type(&m.returns[0]),
+                _ => "text",
+            };
+            schema.push(sch);
+            name.push(routine);
+            data_type.push(dtype.to_string());
+            definition.push(source.clone());
+        }
+
+        let n = name.len();
+        DataFrame::new(vec![
+            Series::new("specific_catalog".into(), vec![catalog.clone(); n]).into(),
+            Series::new("specific_schema".into(), schema.clone()).into(),
+            Series::new("specific_name".into(), name.clone()).into(),
+            Series::new("routine_catalog".into(), vec![catalog; n]).into(),
+            Series::new("routine_schema".into(), schema).into(),
+            Series::new("routine_name".into(), name).into(),
+            Series::new("routine_type".into(), vec!["FUNCTION"; n]).into(),
+            Series::new("data_type".into(), data_type).into(),
+            Series::new("routine_body".into(), vec!["EXTERNAL"; n]).into(),
+            Series::new("routine_definition".into(), definition).into(),
+            Series::new("external_language".into(), vec!["LUA"; n]).into(),
+            Series::new("is_deterministic".into(), vec!["NO"; n]).into(),
+            Series::new("sql_data_access".into(), vec!["NO SQL"; n]).into(),
+            Series::new("security_type".into(), vec!["INVOKER"; n]).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(IRoutines)); }
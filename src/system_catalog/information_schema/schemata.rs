@@ -16,7 +16,7 @@ impl SystemTable for ISchemata {
     fn build(&self, store: &SharedStore) -> Option<DataFrame> {
         let root = store.root_path();
         let mut schemas: Vec<String> = Vec::new();
-        if let Ok(dbs) = std::fs::read_dir(&root) {
+        if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
             for db_ent in dbs.flatten() {
                 let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
                 if let Ok(sd) = std::fs::read_dir(&db_path) {
@@ -31,6 +31,7 @@ impl SystemTable for ISchemata {
         }
         schemas.push("pg_catalog".to_string());
         schemas.push("information_schema".to_string());
+        schemas.push(crate::system_catalog::clarium_catalog::SCHEMA.to_string());
         schemas.sort();
         schemas.dedup();
         DataFrame::new(vec![Series::new("schema_name".into(), schemas).into()]).ok()
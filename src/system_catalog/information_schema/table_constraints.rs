@@ -0,0 +1,69 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::enumerate_tables;
+use crate::storage::SharedStore;
+
+pub struct ITableConstraints;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "constraint_catalog", coltype: ColType::Text },
+    ColumnDef { name: "constraint_schema", coltype: ColType::Text },
+    ColumnDef { name: "constraint_name", coltype: ColType::Text },
+    ColumnDef { name: "table_catalog", coltype: ColType::Text },
+    ColumnDef { name: "table_schema", coltype: ColType::Text },
+    ColumnDef { name: "table_name", coltype: ColType::Text },
+    ColumnDef { name: "constraint_type", coltype: ColType::Text },
+    ColumnDef { name: "is_deferrable", coltype: ColType::Text },
+    ColumnDef { name: "initially_deferred", coltype: ColType::Text },
+    ColumnDef { name: "enforced", coltype: ColType::Text },
+    ColumnDef { name: "nulls_distinct", coltype: ColType::Text },
+];
+
+impl SystemTable for ITableConstraints {
+    fn schema(&self) -> &'static str { "information_schema" }
+    fn name(&self) -> &'static str { "table_constraints" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut catalog: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut name: Vec<String> = Vec::new();
+        let mut table: Vec<String> = Vec::new();
+        let mut ctype: Vec<String> = Vec::new();
+        let mut nulls_distinct: Vec<Option<String>> = Vec::new();
+
+        for m in enumerate_tables(store).iter() {
+            let mut push = |cname: String, t: &str| {
+                catalog.push(m.db.clone());
+                schema.push(m.schema.clone());
+                name.push(cname);
+                table.push(m.display_name().to_string());
+                ctype.push(t.to_string());
+                // only meaningful for unique-style constraints
+                nulls_distinct.push(if t == "PRIMARY KEY" || t == "UNIQUE" { Some("YES".to_string()) } else { None });
+            };
+            if !m.primary_key_columns().is_empty() { push(m.primary_key_name(), "PRIMARY KEY"); }
+            for c in &m.constraints {
+                let t = match c.contype() { 'f' => "FOREIGN KEY", 'c' => "CHECK", 'x' => continue, _ => "UNIQUE" };
+                push(m.constraint_name(c), t);
+            }
+        }
+
+        let n = name.len();
+        DataFrame::new(vec![
+            Series::new("constraint_catalog".into(), catalog.clone()).into(),
+            Series::new("constraint_schema".into(), schema.clone()).into(),
+            Series::new("constraint_name".into(), name).into(),
+            Series::new("table_catalog".into(), catalog).into(),
+            Series::new("table_schema".into(), schema).into(),
+            Series::new("table_name".into(), table).into(),
+            Series::new("constraint_type".into(), ctype).into(),
+            Series::new("is_deferrable".into(), vec!["NO"; n]).into(),
+            Series::new("initially_deferred".into(), vec!["NO"; n]).into(),
+            Series::new("enforced".into(), vec!["YES"; n]).into(),
+            Series::new("nulls_distinct".into(), nulls_distinct).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ITableConstraints)); }
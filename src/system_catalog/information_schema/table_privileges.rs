@@ -0,0 +1,62 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::enumerate_tables;
+use crate::storage::SharedStore;
+
+pub struct ITablePrivileges;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "grantor", coltype: ColType::Text },
+    ColumnDef { name: "grantee", coltype: ColType::Text },
+    ColumnDef { name: "table_catalog", coltype: ColType::Text },
+    ColumnDef { name: "table_schema", coltype: ColType::Text },
+    ColumnDef { name: "table_name", coltype: ColType::Text },
+    ColumnDef { name: "privilege_type", coltype: ColType::Text },
+    ColumnDef { name: "is_grantable", coltype: ColType::Text },
+    ColumnDef { name: "with_hierarchy", coltype: ColType::Text },
+];
+
+impl SystemTable for ITablePrivileges {
+    fn schema(&self) -> &'static str { "information_schema" }
+    fn name(&self) -> &'static str { "table_privileges" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let root = store.root_path();
+        let grants = crate::security::list_grants(&root.to_string_lossy()).unwrap_or_default();
+        let metas = enumerate_tables(store);
+        let mut grantor: Vec<Option<String>> = Vec::new();
+        let mut grantee: Vec<String> = Vec::new();
+        let mut catalog: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut table: Vec<String> = Vec::new();
+        let mut privilege: Vec<String> = Vec::new();
+
+        // Database and schema grants are listed once per table they cover
+        for g in grants.iter() {
+            for m in metas.iter() {
+                if !g.object.covers(&format!("{}/{}/{}", m.db, m.schema, m.display_name())) { continue; }
+                grantor.push(g.grantor.clone());
+                grantee.push(g.grantee.clone());
+                catalog.push(m.db.clone());
+                schema.push(m.schema.clone());
+                table.push(m.display_name().to_string());
+                privilege.push(g.privilege.as_str().to_string());
+            }
+        }
+
+        let n = grantee.len();
+        DataFrame::new(vec![
+            Series::new("grantor".into(), grantor).into(),
+            Series::new("grantee".into(), grantee).into(),
+            Series::new("table_catalog".into(), catalog).into(),
+            Series::new("table_schema".into(), schema).into(),
+            Series::new("table_name".into(), table).into(),
+            Series::new("privilege_type".into(), privilege).into(),
+            Series::new("is_grantable".into(), vec!["NO"; n]).into(),
+            Series::new("with_hierarchy".into(), vec!["NO"; n]).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ITablePrivileges)); }
@@ -23,7 +23,7 @@ impl SystemTable for ITables {
 
         // 1) Real user tables on disk
         let root = store.root_path();
-        if let Ok(dbs) = std::fs::read_dir(&root) {
+        if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
             for db_ent in dbs.flatten() {
                 let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
                 if let Ok(sd) = std::fs::read_dir(&db_path) {
@@ -73,7 +73,9 @@ impl SystemTable for ITables {
             if seen.insert(key.clone()) {
                 schema_col.push(key.0);
                 table_col.push(key.1);
-                type_col.push("BASE TABLE".to_string());
+                // clarium_catalog relations are derived listings, reported as views
+                let kind = if t.schema() == crate::system_catalog::clarium_catalog::SCHEMA { "VIEW" } else { "BASE TABLE" };
+                type_col.push(kind.to_string());
             }
         }
 
@@ -26,10 +26,10 @@ impl SystemTable for IViews {
 
         // 1) Legacy user-created views: scan <db>/<schema>/*.view JSON files
         let root = store.root_path();
-        if let Ok(dbs) = std::fs::read_dir(&root) {
+        if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
             for db_ent in dbs.flatten() {
                 let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
-                let dbname = match db_path.file_name().and_then(|s| s.to_str()) { Some(n) => n.to_string(), None => continue };
+                let dbname = match db_ent.file_name().to_str() { Some(n) => n.to_string(), None => continue };
                 if dbname.starts_with('.') { continue; }
                 if let Ok(schemas_dir) = std::fs::read_dir(&db_path) {
                     for sch_ent in schemas_dir.flatten() {
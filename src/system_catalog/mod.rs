@@ -4,4 +4,5 @@
 pub mod registry;
 pub mod pg_catalog;
 pub mod information_schema;
+pub mod clarium_catalog;
 pub mod shared;
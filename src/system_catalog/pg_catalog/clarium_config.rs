@@ -0,0 +1,38 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// Effective server configuration and where each option came from (see `crate::config`).
+pub struct ClariumConfigView;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "name", coltype: ColType::Text },
+    ColumnDef { name: "setting", coltype: ColType::Text },
+    ColumnDef { name: "source", coltype: ColType::Text },
+    ColumnDef { name: "reloadable", coltype: ColType::Boolean },
+    ColumnDef { name: "pending_restart", coltype: ColType::Boolean },
+];
+
+impl SystemTable for ClariumConfigView {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "clarium_config" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let entries = crate::config::entries();
+        let name: Vec<String> = entries.iter().map(|e| e.key.clone()).collect();
+        let setting: Vec<String> = entries.iter().map(|e| e.setting()).collect();
+        let source: Vec<&str> = entries.iter().map(|e| e.source.as_str()).collect();
+        let reloadable: Vec<bool> = entries.iter().map(|e| e.reloadable).collect();
+        let pending: Vec<bool> = entries.iter().map(|e| e.pending_restart).collect();
+        DataFrame::new(vec![
+            Series::new("name".into(), name).into(),
+            Series::new("setting".into(), setting).into(),
+            Series::new("source".into(), source).into(),
+            Series::new("reloadable".into(), reloadable).into(),
+            Series::new("pending_restart".into(), pending).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ClariumConfigView)); }
@@ -0,0 +1,42 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// Run history of scheduled jobs (the last `server::jobs::JOB_RUN_HISTORY` runs); times are
+/// epoch milliseconds. Non-admins see runs of their own jobs.
+pub struct ClariumJobRuns;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "job", coltype: ColType::Text },
+    ColumnDef { name: "owner", coltype: ColType::Text },
+    ColumnDef { name: "pid", coltype: ColType::Integer },
+    ColumnDef { name: "started_at", coltype: ColType::BigInt },
+    ColumnDef { name: "finished_at", coltype: ColType::BigInt },
+    ColumnDef { name: "status", coltype: ColType::Text },
+    ColumnDef { name: "statements", coltype: ColType::BigInt },
+    ColumnDef { name: "error", coltype: ColType::Text },
+];
+
+impl SystemTable for ClariumJobRuns {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "clarium_job_runs" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let root = store.root_path().to_string_lossy().to_string();
+        let mut rows = crate::server::jobs::list_runs(&root).ok()?;
+        if let Some(user) = super::clarium_jobs::visible_owner(&root) { rows.retain(|r| r.owner.eq_ignore_ascii_case(&user)); }
+        DataFrame::new(vec![
+            Series::new("job".into(), rows.iter().map(|r| r.job.clone()).collect::<Vec<_>>()).into(),
+            Series::new("owner".into(), rows.iter().map(|r| r.owner.clone()).collect::<Vec<_>>()).into(),
+            Series::new("pid".into(), rows.iter().map(|r| r.pid).collect::<Vec<_>>()).into(),
+            Series::new("started_at".into(), rows.iter().map(|r| r.started_at).collect::<Vec<_>>()).into(),
+            Series::new("finished_at".into(), rows.iter().map(|r| r.finished_at).collect::<Vec<_>>()).into(),
+            Series::new("status".into(), rows.iter().map(|r| r.status.clone()).collect::<Vec<_>>()).into(),
+            Series::new("statements".into(), rows.iter().map(|r| r.statements).collect::<Vec<_>>()).into(),
+            Series::new("error".into(), rows.iter().map(|r| r.error.clone()).collect::<Vec<Option<String>>>()).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ClariumJobRuns)); }
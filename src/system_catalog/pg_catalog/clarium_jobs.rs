@@ -0,0 +1,56 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+use crate::server::query::JobBody;
+
+/// Scheduled jobs (see `server::jobs`), the source of SHOW JOBS. Non-admins see their own jobs.
+pub struct ClariumJobs;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "name", coltype: ColType::Text },
+    ColumnDef { name: "owner", coltype: ColType::Text },
+    ColumnDef { name: "schedule", coltype: ColType::Text },
+    ColumnDef { name: "enabled", coltype: ColType::Boolean },
+    ColumnDef { name: "kind", coltype: ColType::Text },
+    ColumnDef { name: "command", coltype: ColType::Text },
+    ColumnDef { name: "database", coltype: ColType::Text },
+    ColumnDef { name: "schema", coltype: ColType::Text },
+    ColumnDef { name: "created_at", coltype: ColType::BigInt },
+];
+
+/// Owner filter for job tables: None for admins, else the current user.
+pub(crate) fn visible_owner(root: &str) -> Option<String> {
+    let user = crate::server::activity::current_user()?;
+    let admin = crate::security::authorize(root, &user, crate::security::CommandKind::Other, None).unwrap_or(false)
+        || crate::security::has_superuser_role(root, &user);
+    (!admin).then_some(user)
+}
+
+impl SystemTable for ClariumJobs {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "clarium_jobs" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let root = store.root_path().to_string_lossy().to_string();
+        let mut rows = crate::server::jobs::list_jobs(&root).ok()?;
+        if let Some(user) = visible_owner(&root) { rows.retain(|j| j.owner.eq_ignore_ascii_case(&user)); }
+        let (kind, command): (Vec<&str>, Vec<String>) = rows.iter().map(|j| match &j.body {
+            JobBody::Sql(sql) => ("sql", sql.clone()),
+            JobBody::Script(path) => ("script", path.clone()),
+        }).unzip();
+        DataFrame::new(vec![
+            Series::new("name".into(), rows.iter().map(|j| j.name.clone()).collect::<Vec<_>>()).into(),
+            Series::new("owner".into(), rows.iter().map(|j| j.owner.clone()).collect::<Vec<_>>()).into(),
+            Series::new("schedule".into(), rows.iter().map(|j| j.schedule.clone()).collect::<Vec<_>>()).into(),
+            Series::new("enabled".into(), rows.iter().map(|j| j.enabled).collect::<Vec<_>>()).into(),
+            Series::new("kind".into(), kind).into(),
+            Series::new("command".into(), command).into(),
+            Series::new("database".into(), rows.iter().map(|j| j.database.clone()).collect::<Vec<_>>()).into(),
+            Series::new("schema".into(), rows.iter().map(|j| j.schema.clone()).collect::<Vec<_>>()).into(),
+            Series::new("created_at".into(), rows.iter().map(|j| j.created_at).collect::<Vec<_>>()).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ClariumJobs)); }
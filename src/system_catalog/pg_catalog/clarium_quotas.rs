@@ -0,0 +1,49 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// Quota usage per user next to the configured limits (see `server::quota`; 0 = unlimited).
+pub struct ClariumQuotas;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "usename", coltype: ColType::Text },
+    ColumnDef { name: "queries_last_minute", coltype: ColType::BigInt },
+    ColumnDef { name: "queries_per_minute", coltype: ColType::BigInt },
+    ColumnDef { name: "rows_scanned_today", coltype: ColType::BigInt },
+    ColumnDef { name: "rows_scanned_per_day", coltype: ColType::BigInt },
+    ColumnDef { name: "ingest_bytes_today", coltype: ColType::BigInt },
+    ColumnDef { name: "ingest_bytes_per_day", coltype: ColType::BigInt },
+    ColumnDef { name: "throttled_total", coltype: ColType::BigInt },
+    ColumnDef { name: "rejected_total", coltype: ColType::BigInt },
+];
+
+impl SystemTable for ClariumQuotas {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "clarium_quotas" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let rows = crate::server::quota::snapshot();
+        let limits = crate::config::current().limits.clone();
+        let n = rows.len();
+        let user: Vec<String> = rows.iter().map(|(u, _)| u.clone()).collect();
+        let queries: Vec<i64> = rows.iter().map(|(_, q)| q.queries_last_minute as i64).collect();
+        let scanned: Vec<i64> = rows.iter().map(|(_, q)| q.rows_scanned_today as i64).collect();
+        let ingested: Vec<i64> = rows.iter().map(|(_, q)| q.ingest_bytes_today as i64).collect();
+        let throttled: Vec<i64> = rows.iter().map(|(_, q)| q.throttled_total as i64).collect();
+        let rejected: Vec<i64> = rows.iter().map(|(_, q)| q.rejected_total as i64).collect();
+        DataFrame::new(vec![
+            Series::new("usename".into(), user).into(),
+            Series::new("queries_last_minute".into(), queries).into(),
+            Series::new("queries_per_minute".into(), vec![limits.queries_per_minute as i64; n]).into(),
+            Series::new("rows_scanned_today".into(), scanned).into(),
+            Series::new("rows_scanned_per_day".into(), vec![limits.rows_scanned_per_day as i64; n]).into(),
+            Series::new("ingest_bytes_today".into(), ingested).into(),
+            Series::new("ingest_bytes_per_day".into(), vec![limits.ingest_bytes_per_day as i64; n]).into(),
+            Series::new("throttled_total".into(), throttled).into(),
+            Series::new("rejected_total".into(), rejected).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ClariumQuotas)); }
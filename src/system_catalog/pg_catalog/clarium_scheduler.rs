@@ -0,0 +1,38 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// Admission control counters per user (see `server::exec::scheduler`).
+pub struct ClariumScheduler;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "usename", coltype: ColType::Text },
+    ColumnDef { name: "running", coltype: ColType::BigInt },
+    ColumnDef { name: "queued", coltype: ColType::BigInt },
+    ColumnDef { name: "admitted_total", coltype: ColType::BigInt },
+    ColumnDef { name: "timed_out_total", coltype: ColType::BigInt },
+];
+
+impl SystemTable for ClariumScheduler {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "clarium_scheduler" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let rows = crate::server::exec::scheduler::snapshot();
+        let user: Vec<String> = rows.iter().map(|(u, _)| u.clone()).collect();
+        let running: Vec<i64> = rows.iter().map(|(_, s)| s.running as i64).collect();
+        let queued: Vec<i64> = rows.iter().map(|(_, s)| s.queued as i64).collect();
+        let admitted: Vec<i64> = rows.iter().map(|(_, s)| s.admitted_total as i64).collect();
+        let timed_out: Vec<i64> = rows.iter().map(|(_, s)| s.timed_out_total as i64).collect();
+        DataFrame::new(vec![
+            Series::new("usename".into(), user).into(),
+            Series::new("running".into(), running).into(),
+            Series::new("queued".into(), queued).into(),
+            Series::new("admitted_total".into(), admitted).into(),
+            Series::new("timed_out_total".into(), timed_out).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ClariumScheduler)); }
@@ -0,0 +1,51 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// Live login sessions (see `identity::SessionManager`); times are epoch milliseconds.
+/// Session ids double as HTTP cookies, so non-admins only see their own sessions.
+pub struct ClariumSessions;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "session_id", coltype: ColType::Text },
+    ColumnDef { name: "usename", coltype: ColType::Text },
+    ColumnDef { name: "pid", coltype: ColType::Integer },
+    ColumnDef { name: "client_addr", coltype: ColType::Text },
+    ColumnDef { name: "issued_at", coltype: ColType::BigInt },
+    ColumnDef { name: "last_seen", coltype: ColType::BigInt },
+    ColumnDef { name: "expires_at", coltype: ColType::BigInt },
+];
+
+impl SystemTable for ClariumSessions {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "clarium_sessions" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut rows = crate::identity::SessionManager::default().list();
+        if let Some(user) = crate::server::activity::current_user() {
+            let root = store.root_path().to_string_lossy().to_string();
+            let admin = crate::security::authorize(&root, &user, crate::security::CommandKind::Other, None).unwrap_or(false)
+                || crate::security::has_superuser_role(&root, &user);
+            if !admin { rows.retain(|s| s.user_id.eq_ignore_ascii_case(&user)); }
+        }
+        let sid: Vec<String> = rows.iter().map(|s| s.session_id.clone()).collect();
+        let user: Vec<String> = rows.iter().map(|s| s.user_id.clone()).collect();
+        let pid: Vec<Option<i32>> = rows.iter().map(|s| crate::server::activity::session_pid(&s.session_id)).collect();
+        let addr: Vec<Option<String>> = rows.iter().map(|s| s.client_addr.clone()).collect();
+        let issued: Vec<i64> = rows.iter().map(|s| s.issued_at).collect();
+        let seen: Vec<i64> = rows.iter().map(|s| s.last_seen).collect();
+        let expires: Vec<i64> = rows.iter().map(|s| s.expires_at).collect();
+        DataFrame::new(vec![
+            Series::new("session_id".into(), sid).into(),
+            Series::new("usename".into(), user).into(),
+            Series::new("pid".into(), pid).into(),
+            Series::new("client_addr".into(), addr).into(),
+            Series::new("issued_at".into(), issued).into(),
+            Series::new("last_seen".into(), seen).into(),
+            Series::new("expires_at".into(), expires).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ClariumSessions)); }
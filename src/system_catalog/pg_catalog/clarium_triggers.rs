@@ -0,0 +1,59 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::enumerate_tables;
+use crate::storage::triggers::{self, OnError, TriggerMode};
+use crate::storage::SharedStore;
+
+/// Lua table triggers with their options and the in-memory counters since startup.
+pub struct ClariumTriggers;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "table_catalog", coltype: ColType::Text },
+    ColumnDef { name: "table_schema", coltype: ColType::Text },
+    ColumnDef { name: "table_name", coltype: ColType::Text },
+    ColumnDef { name: "trigger_name", coltype: ColType::Text },
+    ColumnDef { name: "events", coltype: ColType::Text },
+    ColumnDef { name: "function", coltype: ColType::Text },
+    ColumnDef { name: "mode", coltype: ColType::Text },
+    ColumnDef { name: "on_error", coltype: ColType::Text },
+    ColumnDef { name: "retries", coltype: ColType::Integer },
+    ColumnDef { name: "batch_size", coltype: ColType::BigInt },
+    ColumnDef { name: "batches", coltype: ColType::BigInt },
+    ColumnDef { name: "rows", coltype: ColType::BigInt },
+    ColumnDef { name: "failures", coltype: ColType::BigInt },
+    ColumnDef { name: "last_error", coltype: ColType::Text },
+];
+
+impl SystemTable for ClariumTriggers {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "clarium_triggers" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut rows = Vec::new();
+        for m in enumerate_tables(store).into_iter() {
+            for t in triggers::table_triggers(&m.dir) {
+                let stats = triggers::stats(&m.dir, &t.name);
+                rows.push((m.db.clone(), m.schema.clone(), m.display_name().to_string(), t, stats));
+            }
+        }
+        DataFrame::new(vec![
+            Series::new("table_catalog".into(), rows.iter().map(|r| r.0.clone()).collect::<Vec<_>>()).into(),
+            Series::new("table_schema".into(), rows.iter().map(|r| r.1.clone()).collect::<Vec<_>>()).into(),
+            Series::new("table_name".into(), rows.iter().map(|r| r.2.clone()).collect::<Vec<_>>()).into(),
+            Series::new("trigger_name".into(), rows.iter().map(|r| r.3.name.clone()).collect::<Vec<_>>()).into(),
+            Series::new("events".into(), rows.iter().map(|r| r.3.events.iter().map(|e| e.as_str()).collect::<Vec<_>>().join(",")).collect::<Vec<_>>()).into(),
+            Series::new("function".into(), rows.iter().map(|r| r.3.function.clone()).collect::<Vec<_>>()).into(),
+            Series::new("mode".into(), rows.iter().map(|r| match r.3.mode { TriggerMode::Sync => "sync", TriggerMode::Async => "async" }).collect::<Vec<_>>()).into(),
+            Series::new("on_error".into(), rows.iter().map(|r| match r.3.on_error { OnError::Fail => "fail", OnError::Ignore => "ignore", OnError::Retry => "retry" }).collect::<Vec<_>>()).into(),
+            Series::new("retries".into(), rows.iter().map(|r| r.3.retries as i32).collect::<Vec<_>>()).into(),
+            Series::new("batch_size".into(), rows.iter().map(|r| r.3.batch_size as i64).collect::<Vec<_>>()).into(),
+            Series::new("batches".into(), rows.iter().map(|r| r.4.batches as i64).collect::<Vec<_>>()).into(),
+            Series::new("rows".into(), rows.iter().map(|r| r.4.rows as i64).collect::<Vec<_>>()).into(),
+            Series::new("failures".into(), rows.iter().map(|r| r.4.failures as i64).collect::<Vec<_>>()).into(),
+            Series::new("last_error".into(), rows.iter().map(|r| r.4.last_error.clone()).collect::<Vec<Option<String>>>()).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(ClariumTriggers)); }
@@ -67,19 +67,6 @@ const COLS_PG_OPFAMILY: &[ColumnDef] = &[
     // missing columns from reconciliation
     ColumnDef { name: "opfowner", coltype: ColType::Integer },
 ];
-const COLS_PG_COLLATION: &[ColumnDef] = &[
-    ColumnDef { name: "oid", coltype: ColType::Integer },
-    ColumnDef { name: "collname", coltype: ColType::Text },
-    ColumnDef { name: "collnamespace", coltype: ColType::Integer },
-    // missing columns from reconciliation
-    ColumnDef { name: "collowner", coltype: ColType::Integer },
-    ColumnDef { name: "collprovider", coltype: ColType::Text },
-    ColumnDef { name: "collisdeterministic", coltype: ColType::Boolean },
-    ColumnDef { name: "collencoding", coltype: ColType::Integer },
-    ColumnDef { name: "collcollate", coltype: ColType::Text },
-    ColumnDef { name: "collctype", coltype: ColType::Text },
-    ColumnDef { name: "collversion", coltype: ColType::Text },
-];
 const COLS_PG_CONVERSION: &[ColumnDef] = &[
     ColumnDef { name: "oid", coltype: ColType::Integer },
     ColumnDef { name: "conname", coltype: ColType::Text },
@@ -103,29 +90,6 @@ const COLS_PG_LANGUAGE: &[ColumnDef] = &[
     ColumnDef { name: "lanvalidator", coltype: ColType::Integer },
     ColumnDef { name: "lanacl", coltype: ColType::Text },
 ];
-const COLS_PG_INDEX: &[ColumnDef] = &[
-    ColumnDef { name: "indexrelid", coltype: ColType::Integer },
-    ColumnDef { name: "indrelid", coltype: ColType::Integer },
-    ColumnDef { name: "indisunique", coltype: ColType::Boolean },
-    ColumnDef { name: "indisprimary", coltype: ColType::Boolean },
-    // missing columns from reconciliation
-    ColumnDef { name: "indnatts", coltype: ColType::Integer },
-    ColumnDef { name: "indnkeyatts", coltype: ColType::Integer },
-    ColumnDef { name: "indisexclusion", coltype: ColType::Boolean },
-    ColumnDef { name: "indimmediate", coltype: ColType::Boolean },
-    ColumnDef { name: "indisclustered", coltype: ColType::Boolean },
-    ColumnDef { name: "indisvalid", coltype: ColType::Boolean },
-    ColumnDef { name: "indcheckxmin", coltype: ColType::Boolean },
-    ColumnDef { name: "indisready", coltype: ColType::Boolean },
-    ColumnDef { name: "indislive", coltype: ColType::Boolean },
-    ColumnDef { name: "indisreplident", coltype: ColType::Boolean },
-    ColumnDef { name: "indkey", coltype: ColType::Text },
-    ColumnDef { name: "indcollation", coltype: ColType::Text },
-    ColumnDef { name: "indclass", coltype: ColType::Text },
-    ColumnDef { name: "indoption", coltype: ColType::Text },
-    ColumnDef { name: "indexprs", coltype: ColType::Text },
-    ColumnDef { name: "indpred", coltype: ColType::Text },
-];
 const COLS_PG_INHERITS: &[ColumnDef] = &[
     ColumnDef { name: "inhrelid", coltype: ColType::Integer },
     ColumnDef { name: "inhparent", coltype: ColType::Integer },
@@ -144,28 +108,6 @@ const COLS_PG_REWRITE: &[ColumnDef] = &[
     ColumnDef { name: "ev_qual", coltype: ColType::Text },
     ColumnDef { name: "ev_action", coltype: ColType::Text },
 ];
-const COLS_PG_TRIGGER: &[ColumnDef] = &[
-    ColumnDef { name: "oid", coltype: ColType::Integer },
-    ColumnDef { name: "tgrelid", coltype: ColType::Integer },
-    ColumnDef { name: "tgname", coltype: ColType::Text },
-    ColumnDef { name: "tgenabled", coltype: ColType::Text },
-    // missing columns from reconciliation
-    ColumnDef { name: "tgparentid", coltype: ColType::Integer },
-    ColumnDef { name: "tgfoid", coltype: ColType::Integer },
-    ColumnDef { name: "tgtype", coltype: ColType::Integer },
-    ColumnDef { name: "tgisinternal", coltype: ColType::Boolean },
-    ColumnDef { name: "tgconstrrelid", coltype: ColType::Integer },
-    ColumnDef { name: "tgconstrindid", coltype: ColType::Integer },
-    ColumnDef { name: "tgconstraint", coltype: ColType::Integer },
-    ColumnDef { name: "tgdeferrable", coltype: ColType::Boolean },
-    ColumnDef { name: "tginitdeferred", coltype: ColType::Boolean },
-    ColumnDef { name: "tgnargs", coltype: ColType::Integer },
-    ColumnDef { name: "tgattr", coltype: ColType::Text },
-    ColumnDef { name: "tgargs", coltype: ColType::Text },
-    ColumnDef { name: "tgqual", coltype: ColType::Text },
-    ColumnDef { name: "tgoldtable", coltype: ColType::Text },
-    ColumnDef { name: "tgnewtable", coltype: ColType::Text },
-];
 const COLS_PG_TABLESPACE: &[ColumnDef] = &[
     ColumnDef { name: "oid", coltype: ColType::Integer },
     ColumnDef { name: "spcname", coltype: ColType::Text },
@@ -252,11 +194,6 @@ const COLS_PG_LARGEOBJECT: &[ColumnDef] = &[
     ColumnDef { name: "pageno", coltype: ColType::Integer },
     ColumnDef { name: "data", coltype: ColType::Text },
 ];
-const COLS_PG_DB_ROLE_SETTING: &[ColumnDef] = &[
-    ColumnDef { name: "setdatabase", coltype: ColType::Integer },
-    ColumnDef { name: "setrole", coltype: ColType::Integer },
-    ColumnDef { name: "setconfig", coltype: ColType::Text },
-];
 const COLS_PG_TS_CONFIG_MAP: &[ColumnDef] = &[
     ColumnDef { name: "mapcfg", coltype: ColType::Integer },
     ColumnDef { name: "maptokentype", coltype: ColType::Integer },
@@ -308,39 +245,6 @@ const COLS_PG_STATISTIC_EXT_DATA: &[ColumnDef] = &[
     ColumnDef { name: "stxdmcv", coltype: ColType::Text },
     ColumnDef { name: "stxdexpr", coltype: ColType::Text },
 ];
-const COLS_PG_STATISTIC: &[ColumnDef] = &[
-    ColumnDef { name: "starelid", coltype: ColType::Integer },
-    ColumnDef { name: "staattnum", coltype: ColType::Integer },
-    ColumnDef { name: "stainherit", coltype: ColType::Boolean },
-    ColumnDef { name: "stanullfrac", coltype: ColType::Text },
-    ColumnDef { name: "stawidth", coltype: ColType::Integer },
-    ColumnDef { name: "stadistinct", coltype: ColType::Text },
-    ColumnDef { name: "stakind1", coltype: ColType::Integer },
-    ColumnDef { name: "stakind2", coltype: ColType::Integer },
-    ColumnDef { name: "stakind3", coltype: ColType::Integer },
-    ColumnDef { name: "stakind4", coltype: ColType::Integer },
-    ColumnDef { name: "stakind5", coltype: ColType::Integer },
-    ColumnDef { name: "staop1", coltype: ColType::Integer },
-    ColumnDef { name: "staop2", coltype: ColType::Integer },
-    ColumnDef { name: "staop3", coltype: ColType::Integer },
-    ColumnDef { name: "staop4", coltype: ColType::Integer },
-    ColumnDef { name: "staop5", coltype: ColType::Integer },
-    ColumnDef { name: "stacoll1", coltype: ColType::Integer },
-    ColumnDef { name: "stacoll2", coltype: ColType::Integer },
-    ColumnDef { name: "stacoll3", coltype: ColType::Integer },
-    ColumnDef { name: "stacoll4", coltype: ColType::Integer },
-    ColumnDef { name: "stacoll5", coltype: ColType::Integer },
-    ColumnDef { name: "stanumbers1", coltype: ColType::Text },
-    ColumnDef { name: "stanumbers2", coltype: ColType::Text },
-    ColumnDef { name: "stanumbers3", coltype: ColType::Text },
-    ColumnDef { name: "stanumbers4", coltype: ColType::Text },
-    ColumnDef { name: "stanumbers5", coltype: ColType::Text },
-    ColumnDef { name: "stavalues1", coltype: ColType::Text },
-    ColumnDef { name: "stavalues2", coltype: ColType::Text },
-    ColumnDef { name: "stavalues3", coltype: ColType::Text },
-    ColumnDef { name: "stavalues4", coltype: ColType::Text },
-    ColumnDef { name: "stavalues5", coltype: ColType::Text },
-];
 const COLS_PG_USER_MAPPING: &[ColumnDef] = &[
     ColumnDef { name: "oid", coltype: ColType::Integer },
     ColumnDef { name: "umuser", coltype: ColType::Integer },
@@ -512,6 +416,24 @@ pub fn register_defaults() {
     pg_constraint::register();
     pg_constraint_columns::register();
     pg_views::register();
+    pg_index::register();
+    pg_description::register();
+    pg_stat_replication::register();
+    pg_stat_wal_receiver::register();
+    pg_stat_ingest::register();
+    pg_stat_activity::register();
+    pg_stat_database::register();
+    clarium_config::register();
+    pg_settings::register();
+    clarium_scheduler::register();
+    clarium_quotas::register();
+    clarium_sessions::register();
+    clarium_jobs::register();
+    clarium_job_runs::register();
+    clarium_triggers::register();
+    pg_trigger::register();
+    pg_collation::register();
+    pg_statistic::register();
 
     // Register NoOp system tables for pg_catalog coverage
     let regs: &[(&str, &[ColumnDef])] = &[
@@ -521,13 +443,10 @@ pub fn register_defaults() {
         ("pg_operator", COLS_PG_OPERATOR),
         ("pg_opclass", COLS_PG_OPCLASS),
         ("pg_opfamily", COLS_PG_OPFAMILY),
-        ("pg_collation", COLS_PG_COLLATION),
         ("pg_conversion", COLS_PG_CONVERSION),
         ("pg_language", COLS_PG_LANGUAGE),
-        ("pg_index", COLS_PG_INDEX),
         ("pg_inherits", COLS_PG_INHERITS),
         ("pg_rewrite", COLS_PG_REWRITE),
-        ("pg_trigger", COLS_PG_TRIGGER),
         ("pg_tablespace", COLS_PG_TABLESPACE),
         ("pg_cast", COLS_PG_CAST),
         ("pg_enum", COLS_PG_ENUM),
@@ -541,7 +460,6 @@ pub fn register_defaults() {
         ("pg_ts_parser", COLS_PG_TS_PARSER),
         ("pg_ts_template", COLS_PG_TS_TEMPLATE),
         // Newly covered as NoOp to replace legacy builders
-        ("pg_description", COLS_PG_DESCRIPTION),
         ("pg_depend", COLS_PG_DEPEND),
         ("pg_shdescription", COLS_PG_SHDESCRIPTION),
         // New NoOp tables from reconciliation
@@ -553,12 +471,10 @@ pub fn register_defaults() {
         ("pg_seclabel", COLS_PG_SECLABEL),
         ("pg_largeobject_metadata", COLS_PG_LARGEOBJECT_METADATA),
         ("pg_largeobject", COLS_PG_LARGEOBJECT),
-        ("pg_db_role_setting", COLS_PG_DB_ROLE_SETTING),
         ("pg_ts_config_map", COLS_PG_TS_CONFIG_MAP),
         ("pg_transform", COLS_PG_TRANSFORM),
         ("pg_statistic_ext", COLS_PG_STATISTIC_EXT),
         ("pg_statistic_ext_data", COLS_PG_STATISTIC_EXT_DATA),
-        ("pg_statistic", COLS_PG_STATISTIC),
         ("pg_user_mapping", COLS_PG_USER_MAPPING),
         ("pg_shseclabel", COLS_PG_SHSECLABEL),
         ("pg_init_privs", COLS_PG_INIT_PRIVS),
@@ -576,12 +492,6 @@ pub fn register_defaults() {
 }
 
 // ---- Additional column definitions for newly registered NoOp tables ----
-const COLS_PG_DESCRIPTION: &[ColumnDef] = &[
-    ColumnDef { name: "objoid", coltype: ColType::Integer },
-    ColumnDef { name: "classoid", coltype: ColType::Integer },
-    ColumnDef { name: "objsubid", coltype: ColType::Integer },
-    ColumnDef { name: "description", coltype: ColType::Text },
-];
 const COLS_PG_DEPEND: &[ColumnDef] = &[
     ColumnDef { name: "classid", coltype: ColType::Integer },
     ColumnDef { name: "objid", coltype: ColType::Integer },
@@ -613,4 +523,23 @@ pub mod pg_aggregate;
 pub mod pg_class;
 pub mod pg_constraint;
 pub mod pg_constraint_columns;
-pub mod pg_views;
\ No newline at end of file
+pub mod pg_views;
+pub mod pg_index;
+pub mod pg_description;
+pub mod pg_stat_replication;
+pub mod pg_stat_wal_receiver;
+pub mod pg_stat_ingest;
+pub mod pg_stat_activity;
+pub mod pg_stat_database;
+pub mod clarium_config;
+pub mod pg_settings;
+pub mod clarium_scheduler;
+pub mod clarium_quotas;
+pub mod clarium_sessions;
+pub mod clarium_jobs;
+pub mod clarium_job_runs;
+pub mod clarium_triggers;
+pub mod pg_trigger;
+pub mod pg_collation;
+pub mod pg_statistic;
+
@@ -1,7 +1,7 @@
 use polars::prelude::{DataFrame, Series, NamedFrom};
 use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
 use crate::system_catalog::registry;
-use crate::system_catalog::shared::{enumerate_tables, enumerate_views, get_or_assign_table_oid, get_or_assign_view_oid};
+use crate::system_catalog::shared::{enumerate_indexes, enumerate_tables, enumerate_views, get_or_assign_table_oid, get_or_assign_view_oid};
 use crate::storage::SharedStore;
 use crate::tprintln;
 
@@ -84,6 +84,17 @@ impl SystemTable for PgAttribute {
             }
         }
 
+        // Index key columns, numbered within the index (pg_index.indkey maps them to the table)
+        for x in enumerate_indexes(store).iter() {
+            let index_oid = x.oid();
+            for (i, cname) in x.columns.iter().enumerate() {
+                attrelid.push(index_oid);
+                attname.push(cname.clone());
+                attnum.push(i as i32 + 1);
+                attisdropped.push(false);
+            }
+        }
+
         let rows = attrelid.len();
         tprintln!("[loader] pg_attribute built: rows={}", rows);
         // defaults for added columns
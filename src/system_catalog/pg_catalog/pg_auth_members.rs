@@ -2,6 +2,7 @@ use polars::prelude::{DataFrame, Series, NamedFrom};
 use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
 use crate::system_catalog::registry;
 use crate::storage::SharedStore;
+use crate::system_catalog::shared::role_oid;
 
 pub struct PgAuthMembers;
 
@@ -16,12 +17,19 @@ impl SystemTable for PgAuthMembers {
     fn schema(&self) -> &'static str { "pg_catalog" }
     fn name(&self) -> &'static str { "pg_auth_members" }
     fn columns(&self) -> &'static [ColumnDef] { COLS }
-    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
-        // No role memberships defined yet in RBAC surface; return empty with correct columns.
-        let roleid: Vec<i32> = Vec::new();
-        let member: Vec<i32> = Vec::new();
-        let grantor: Vec<i32> = Vec::new();
-        let admin_option: Vec<bool> = Vec::new();
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        // GRANT role TO member; grantor is the granting user (postgres when unknown)
+        let cat = crate::security::role_catalog(&store.root_path().to_string_lossy()).unwrap_or_default();
+        let mut roleid: Vec<i32> = Vec::new();
+        let mut member: Vec<i32> = Vec::new();
+        let mut grantor: Vec<i32> = Vec::new();
+        let mut admin_option: Vec<bool> = Vec::new();
+        for m in cat.members.iter() {
+            roleid.push(role_oid(&m.role));
+            member.push(role_oid(&m.member));
+            grantor.push(role_oid(m.grantor.as_deref().unwrap_or("postgres")));
+            admin_option.push(m.admin_option);
+        }
         DataFrame::new(vec![
             Series::new("roleid".into(), roleid).into(),
             Series::new("member".into(), member).into(),
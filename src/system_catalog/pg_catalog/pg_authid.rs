@@ -2,7 +2,7 @@ use polars::prelude::DataFrame;
 use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
 use crate::system_catalog::registry;
 use crate::storage::SharedStore;
-use super::role_common::synthesize_core_roles;
+use super::role_common::role_rows;
 
 pub struct PgAuthId;
 
@@ -25,8 +25,8 @@ impl SystemTable for PgAuthId {
     fn schema(&self) -> &'static str { "pg_catalog" }
     fn name(&self) -> &'static str { "pg_authid" }
     fn columns(&self) -> &'static [ColumnDef] { COLS }
-    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
-        let rows = synthesize_core_roles();
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let rows = role_rows(store);
         Some(rows.to_df())
     }
 }
@@ -61,7 +61,7 @@ fn build_pg_class_df(store: &SharedStore) -> Option<polars::prelude::DataFrame>
     use polars::prelude::{DataFrame, Series, NamedFrom};
     let metas = enumerate_tables(store);
     let vmetas = enumerate_views(store);
-    let idxs = enumerate_vector_indexes(store);
+    let idxs = enumerate_indexes(store);
     let graphs = enumerate_graphs(store);
     let mut relname: Vec<String> = Vec::new();
     let mut nspname: Vec<String> = Vec::new();
@@ -69,6 +69,8 @@ fn build_pg_class_df(store: &SharedStore) -> Option<polars::prelude::DataFrame>
     let mut oid: Vec<i32> = Vec::new();
     let mut relnamespace: Vec<i32> = Vec::new();
     let mut relpartbound: Vec<Option<String>> = Vec::new();
+    let mut relhasindex: Vec<bool> = Vec::new();
+    let mut relnatts: Vec<i32> = Vec::new();
 
     // Map schema names to namespace OIDs (matching pg_namespace)
     let pg_catalog_oid: i32 = 11;
@@ -90,6 +92,8 @@ fn build_pg_class_df(store: &SharedStore) -> Option<polars::prelude::DataFrame>
         oid.push(get_or_assign_table_oid(&m.dir, &m.db, &m.schema, &m.table));
         relnamespace.push(ns_oid_for(&m.schema));
         relpartbound.push(None);
+        relhasindex.push(idxs.iter().any(|x| x.find_table(std::slice::from_ref(m)).is_some()));
+        relnatts.push(m.cols.len() as i32);
     }
     for v in vmetas.iter() {
         relname.push(v.view.clone());
@@ -98,15 +102,19 @@ fn build_pg_class_df(store: &SharedStore) -> Option<polars::prelude::DataFrame>
         oid.push(get_or_assign_view_oid(&v.file, &v.db, &v.schema, &v.view));
         relnamespace.push(ns_oid_for(&v.schema));
         relpartbound.push(None);
+        relhasindex.push(false);
+        relnatts.push(0);
     }
     // Vector indexes as relkind 'i'
     for x in idxs.iter() {
         relname.push(x.name.clone());
         nspname.push(x.schema.clone());
         relkind.push("i".to_string());
-        oid.push(get_or_assign_vindex_oid(&x.file, &x.db, &x.schema, &x.name));
+        oid.push(x.oid());
         relnamespace.push(ns_oid_for(&x.schema));
         relpartbound.push(None);
+        relhasindex.push(false);
+        relnatts.push(x.columns.len() as i32);
     }
     // Graph catalogs – expose as views (relkind 'v') for client compatibility
     for g in graphs.iter() {
@@ -116,6 +124,8 @@ fn build_pg_class_df(store: &SharedStore) -> Option<polars::prelude::DataFrame>
         oid.push(get_or_assign_graph_oid(&g.file, &g.db, &g.schema, &g.name));
         relnamespace.push(ns_oid_for(&g.schema));
         relpartbound.push(None);
+        relhasindex.push(false);
+        relnatts.push(0);
     }
     let rows = relname.len();
     // Defaults for added columns
@@ -143,10 +153,10 @@ fn build_pg_class_df(store: &SharedStore) -> Option<polars::prelude::DataFrame>
         Series::new("reltuples".into(), empty_txt_s.clone()).into(),
         Series::new("relallvisible".into(), zeros_i32.clone()).into(),
         Series::new("reltoastrelid".into(), zeros_i32.clone()).into(),
-        Series::new("relhasindex".into(), falses.clone()).into(),
+        Series::new("relhasindex".into(), relhasindex).into(),
         Series::new("relisshared".into(), falses.clone()).into(),
         Series::new("relpersistence".into(), relpersistence).into(),
-        Series::new("relnatts".into(), zeros_i32.clone()).into(),
+        Series::new("relnatts".into(), relnatts).into(),
         Series::new("relchecks".into(), zeros_i32.clone()).into(),
         Series::new("relhasrules".into(), falses.clone()).into(),
         Series::new("relhastriggers".into(), falses.clone()).into(),
@@ -0,0 +1,48 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+use crate::tprintln;
+
+/// Collations usable with COLLATE (see `server::collation`).
+pub struct PgCollation;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "oid", coltype: ColType::Integer },
+    ColumnDef { name: "collname", coltype: ColType::Text },
+    ColumnDef { name: "collnamespace", coltype: ColType::Integer },
+    ColumnDef { name: "collowner", coltype: ColType::Integer },
+    ColumnDef { name: "collprovider", coltype: ColType::Text },
+    ColumnDef { name: "collisdeterministic", coltype: ColType::Boolean },
+    ColumnDef { name: "collencoding", coltype: ColType::Integer },
+    ColumnDef { name: "collcollate", coltype: ColType::Text },
+    ColumnDef { name: "collctype", coltype: ColType::Text },
+    ColumnDef { name: "collversion", coltype: ColType::Text },
+];
+
+impl SystemTable for PgCollation {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_collation" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let rows = crate::server::collation::available();
+        let n = rows.len();
+        tprintln!("[loader] pg_collation built: rows={}", n);
+        DataFrame::new(vec![
+            Series::new("oid".into(), rows.iter().map(|r| r.oid).collect::<Vec<i32>>()).into(),
+            Series::new("collname".into(), rows.iter().map(|r| r.name.clone()).collect::<Vec<String>>()).into(),
+            // All collations live in pg_catalog, owned by the bootstrap superuser
+            Series::new("collnamespace".into(), vec![11i32; n]).into(),
+            Series::new("collowner".into(), vec![10i32; n]).into(),
+            Series::new("collprovider".into(), rows.iter().map(|r| r.provider).collect::<Vec<&str>>()).into(),
+            Series::new("collisdeterministic".into(), rows.iter().map(|r| r.deterministic).collect::<Vec<bool>>()).into(),
+            // -1: usable with any encoding
+            Series::new("collencoding".into(), vec![-1i32; n]).into(),
+            Series::new("collcollate".into(), rows.iter().map(|r| r.locale.clone()).collect::<Vec<String>>()).into(),
+            Series::new("collctype".into(), rows.iter().map(|r| r.locale.clone()).collect::<Vec<String>>()).into(),
+            Series::new("collversion".into(), vec![None::<String>; n]).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgCollation)); }
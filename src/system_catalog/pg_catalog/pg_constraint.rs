@@ -91,79 +91,56 @@ impl SystemTable for PgConstraint {
 
         for m in metas.iter() {
             let table_oid = get_or_assign_table_oid(&m.dir, &m.db, &m.schema, &m.table);
-            if m.has_primary_marker {
-                let mut pk_columns: Vec<i32> = Vec::new();
-                for (col_idx, (cname, _dtype)) in m.cols.iter().enumerate() {
-                    if cname == "_time" || cname == "PRIMARY" { continue; }
-                    if cname == "id" || cname == "record_id" || cname.ends_with("_id") {
-                        pk_columns.push((col_idx + 1) as i32);
-                        break;
-                    }
-                }
-                if pk_columns.is_empty() {
-                    for (col_idx, (cname, _dtype)) in m.cols.iter().enumerate() {
-                        if cname != "_time" && cname != "PRIMARY" {
-                            pk_columns.push((col_idx + 1) as i32);
-                            break;
-                        }
-                    }
-                }
-                if !pk_columns.is_empty() {
-                    let conkey_str = {
-                        let nums: Vec<String> = pk_columns.iter().map(|n| n.to_string()).collect();
-                        format!("{{{}}}", nums.join(","))
-                    };
-                    conrelid.push(table_oid);
-                    conname.push(format!("{}_pkey", m.table));
-                    contype.push("p".to_string());
-                    conkey.push(conkey_str);
-                    conindid.push(0);
-                    oid.push(constraint_oid);
-                    // fill added columns for PK defaults
-                    connamespace.push(ns_oid_for(&m.schema));
-                    condeferrable.push(false);
-                    condeferred.push(false);
-                    convalidated.push(true);
-                    contypid.push(0);
-                    conparentid.push(0);
-                    confrelid.push(0);
-                    confupdtype.push(String::new());
-                    confdeltype.push(String::new());
-                    confmatchtype.push(String::new());
-                    conislocal.push(true);
-                    coninhcount.push(0);
-                    connoinherit.push(false);
-                    confkey.push("{}".to_string());
-                    conpfeqop.push("{}".to_string());
-                    conppeqop.push("{}".to_string());
-                    conffeqop.push("{}".to_string());
-                    conexclop.push("{}".to_string());
-                    conbin.push(None);
-                    constraint_oid += 1;
-                }
+            // helper: map column names to 1-based positions in this table
+            let pos_for = |name: &str| -> Option<i32> {
+                m.cols.iter().enumerate()
+                    .find(|(_i,(n,_t))| n == name)
+                    .map(|(i,_v)| (i as i32) + 1)
+            };
+            let pk_columns: Vec<i32> = m.primary_key_columns().iter().filter_map(|cn| pos_for(cn)).collect();
+            if !pk_columns.is_empty() {
+                let conkey_str = {
+                    let nums: Vec<String> = pk_columns.iter().map(|n| n.to_string()).collect();
+                    format!("{{{}}}", nums.join(","))
+                };
+                conrelid.push(table_oid);
+                conname.push(m.primary_key_name());
+                contype.push("p".to_string());
+                conkey.push(conkey_str);
+                conindid.push(0);
+                oid.push(constraint_oid);
+                // fill added columns for PK defaults
+                connamespace.push(ns_oid_for(&m.schema));
+                condeferrable.push(false);
+                condeferred.push(false);
+                convalidated.push(true);
+                contypid.push(0);
+                conparentid.push(0);
+                confrelid.push(0);
+                confupdtype.push(String::new());
+                confdeltype.push(String::new());
+                confmatchtype.push(String::new());
+                conislocal.push(true);
+                coninhcount.push(0);
+                connoinherit.push(false);
+                confkey.push("{}".to_string());
+                conpfeqop.push("{}".to_string());
+                conppeqop.push("{}".to_string());
+                conffeqop.push("{}".to_string());
+                conexclop.push("{}".to_string());
+                conbin.push(None);
+                constraint_oid += 1;
             }
 
             // Synthesize additional constraints from metadata (unique, foreign key, check, exclusion)
             for c in &m.constraints {
-                // helper: map column names to 1-based positions in this table
-                let pos_for = |name: &str| -> Option<i32> {
-                    m.cols.iter().enumerate()
-                        .find(|(_i,(n,_t))| n == name)
-                        .map(|(i,_v)| (i as i32) + 1)
-                };
                 let conkey_positions: Vec<i32> = c.columns.iter().filter_map(|cn| pos_for(cn)).collect();
                 let conkey_str = if conkey_positions.is_empty() { "{}".to_string() } else { format!("{{{}}}", conkey_positions.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(",")) };
-                let (ctype_char, is_fk, is_check, is_excl) = match c.ctype.as_str() {
-                    "unique" => ('u', false, false, false),
-                    "foreign_key" => ('f', true, false, false),
-                    "check" => ('c', false, true, false),
-                    "exclusion" => ('x', false, false, true),
-                    other if other.eq_ignore_ascii_case("fk") => ('f', true, false, false),
-                    _ => ('u', false, false, false),
-                };
+                let ctype_char = c.contype();
+                let (is_fk, is_check, is_excl) = (ctype_char == 'f', ctype_char == 'c', ctype_char == 'x');
 
                 conrelid.push(table_oid);
-                conname.push(if c.name.is_empty() { format!("{}_{}_constr", m.table, ctype_char) } else { c.name.clone() });
+                conname.push(m.constraint_name(c));
                 contype.push(ctype_char.to_string());
                 conkey.push(conkey_str);
                 conindid.push(0);
@@ -35,56 +35,31 @@ impl SystemTable for PgConstraintColumns {
 
         for m in metas.iter() {
             let table_oid = get_or_assign_table_oid(&m.dir, &m.db, &m.schema, &m.table);
-            if m.has_primary_marker {
-                let mut pk_columns: Vec<i32> = Vec::new();
-                for (col_idx, (cname, _dtype)) in m.cols.iter().enumerate() {
-                    if cname == "_time" || cname == "PRIMARY" { continue; }
-                    if cname == "id" || cname == "record_id" || cname.ends_with("_id") {
-                        pk_columns.push((col_idx + 1) as i32);
-                        break;
-                    }
-                }
-                if pk_columns.is_empty() {
-                    for (col_idx, (cname, _dtype)) in m.cols.iter().enumerate() {
-                        if cname != "_time" && cname != "PRIMARY" {
-                            pk_columns.push((col_idx + 1) as i32);
-                            break;
-                        }
-                    }
-                }
-                if !pk_columns.is_empty() {
-                    let cname_val = format!("{}_pkey", m.table);
-                    for (position, col_num) in pk_columns.iter().enumerate() {
-                        oid.push(constraint_oid);
-                        conrelid.push(table_oid);
-                        conname.push(cname_val.clone());
-                        contype.push("p".to_string());
-                        attnum.push(*col_num);
-                        ord.push((position + 1) as i32);
-                        conindid.push(0);
-                    }
-                    constraint_oid += 1;
+            // map columns to positions in table
+            let pos_for = |name: &str| -> Option<i32> {
+                m.cols.iter().enumerate()
+                    .find(|(_i,(n,_t))| n == name)
+                    .map(|(i,_v)| (i as i32) + 1)
+            };
+            let pk_columns: Vec<i32> = m.primary_key_columns().iter().filter_map(|cn| pos_for(cn)).collect();
+            if !pk_columns.is_empty() {
+                let cname_val = m.primary_key_name();
+                for (position, col_num) in pk_columns.iter().enumerate() {
+                    oid.push(constraint_oid);
+                    conrelid.push(table_oid);
+                    conname.push(cname_val.clone());
+                    contype.push("p".to_string());
+                    attnum.push(*col_num);
+                    ord.push((position + 1) as i32);
+                    conindid.push(0);
                 }
+                constraint_oid += 1;
             }
 
             // Additional constraints from metadata
             for c in &m.constraints {
-                // determine constraint type char
-                let ctype_char = match c.ctype.as_str() {
-                    "unique" => 'u',
-                    "foreign_key" => 'f',
-                    "check" => 'c',
-                    "exclusion" => 'x',
-                    other if other.eq_ignore_ascii_case("fk") => 'f',
-                    _ => 'u',
-                };
-                let cname_val = if c.name.is_empty() { format!("{}_{}_constr", m.table, ctype_char) } else { c.name.clone() };
-                // map columns to positions in table
-                let pos_for = |name: &str| -> Option<i32> {
-                    m.cols.iter().enumerate()
-                        .find(|(_i,(n,_t))| n == name)
-                        .map(|(i,_v)| (i as i32) + 1)
-                };
+                let ctype_char = c.contype();
+                let cname_val = m.constraint_name(c);
                 let mut any = false;
                 for (position, cname) in c.columns.iter().enumerate() {
                     if let Some(p) = pos_for(cname) {
@@ -6,6 +6,30 @@ use xxhash_rust::xxh3::xxh3_64;
 
 pub struct PgDatabase;
 
+/// Stable positive OID for a database, derived from its name.
+pub fn database_oid(name: &str) -> i32 {
+    let h = xxh3_64(format!("db:{}", name).as_bytes());
+    20000 + ((h as u32) % 1_000_000) as i32
+}
+
+/// Databases under the storage root (the default database when there are none yet).
+pub fn database_names(store: &SharedStore) -> Vec<String> {
+    let root = store.root_path();
+    let mut names: Vec<String> = Vec::new();
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
+        for ent in dbs.flatten() {
+            let p = ent.path();
+            if p.is_dir() {
+                if let Some(name) = ent.file_name().to_str() {
+                    if !name.starts_with('.') { names.push(name.to_string()); }
+                }
+            }
+        }
+    }
+    if names.is_empty() { names.push("clarium".to_string()); }
+    names
+}
+
 const COLS: &[ColumnDef] = &[
     ColumnDef { name: "oid", coltype: ColType::Integer },
     ColumnDef { name: "datname", coltype: ColType::Text },
@@ -29,25 +53,10 @@ impl SystemTable for PgDatabase {
     fn name(&self) -> &'static str { "pg_database" }
     fn columns(&self) -> &'static [ColumnDef] { COLS }
     fn build(&self, store: &SharedStore) -> Option<DataFrame> {
-        let root = store.root_path();
-        let mut names: Vec<String> = Vec::new();
-        if let Ok(dbs) = std::fs::read_dir(&root) {
-            for ent in dbs.flatten() {
-                let p = ent.path();
-                if p.is_dir() {
-                    if let Some(name) = p.file_name().and_then(|s| s.to_str()) {
-                        if !name.starts_with('.') { names.push(name.to_string()); }
-                    }
-                }
-            }
-        }
-        if names.is_empty() { names.push("clarium".to_string()); }
+        let names = database_names(store);
 
         // oid: stable positive OID derived from name; we can reuse a simple stable hash
-        let oids: Vec<i32> = names.iter().map(|n| {
-            let h = xxh3_64(format!("db:{}", n).as_bytes());
-            20000 + ((h as u32) % 1_000_000) as i32
-        }).collect();
+        let oids: Vec<i32> = names.iter().map(|n| database_oid(n)).collect();
         // datdba: arbitrary stable owner OID (10)
         let datdba: Vec<i32> = vec![10; names.len()];
         // encoding: 6 corresponds to UTF8 in PostgreSQL catalogs
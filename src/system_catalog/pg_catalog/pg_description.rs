@@ -0,0 +1,66 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::{enumerate_tables, enumerate_views, function_oid, get_or_assign_table_oid, get_or_assign_view_oid};
+use crate::storage::SharedStore;
+use crate::tprintln;
+
+pub struct PgDescription;
+
+// classoid values PostgreSQL uses for relations and functions
+const PG_CLASS_OID: i32 = 1259;
+const PG_PROC_OID: i32 = 1255;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "objoid", coltype: ColType::Integer },
+    ColumnDef { name: "classoid", coltype: ColType::Integer },
+    ColumnDef { name: "objsubid", coltype: ColType::Integer },
+    ColumnDef { name: "description", coltype: ColType::Text },
+];
+
+impl SystemTable for PgDescription {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_description" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut objoid: Vec<i32> = Vec::new();
+        let mut classoid: Vec<i32> = Vec::new();
+        let mut objsubid: Vec<i32> = Vec::new();
+        let mut description: Vec<String> = Vec::new();
+
+        // Tables and their columns; objsubid is the pg_attribute attnum
+        for m in enumerate_tables(store).iter() {
+            let comments = crate::storage::comments::table_comments(&m.dir);
+            if comments.table.is_none() && comments.columns.is_empty() { continue; }
+            let oid = get_or_assign_table_oid(&m.dir, &m.db, &m.schema, &m.table);
+            if let Some(c) = comments.table {
+                objoid.push(oid); classoid.push(PG_CLASS_OID); objsubid.push(0); description.push(c);
+            }
+            for (i, (cname, _)) in m.cols.iter().filter(|(n, _)| n != "PRIMARY").enumerate() {
+                if let Some(c) = comments.columns.get(cname) {
+                    objoid.push(oid); classoid.push(PG_CLASS_OID); objsubid.push(i as i32 + 1); description.push(c.clone());
+                }
+            }
+        }
+
+        for v in enumerate_views(store).into_iter() {
+            let Some(c) = v.comment.clone() else { continue };
+            objoid.push(get_or_assign_view_oid(&v.file, &v.db, &v.schema, &v.view));
+            classoid.push(PG_CLASS_OID); objsubid.push(0); description.push(c);
+        }
+
+        for (name, c) in crate::storage::comments::function_comments(&store.root_path()) {
+            objoid.push(function_oid(&name)); classoid.push(PG_PROC_OID); objsubid.push(0); description.push(c);
+        }
+
+        tprintln!("[loader] pg_description built: rows={}", objoid.len());
+        DataFrame::new(vec![
+            Series::new("objoid".into(), objoid).into(),
+            Series::new("classoid".into(), classoid).into(),
+            Series::new("objsubid".into(), objsubid).into(),
+            Series::new("description".into(), description).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgDescription)); }
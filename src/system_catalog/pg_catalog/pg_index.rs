@@ -0,0 +1,90 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::{enumerate_indexes, enumerate_tables, get_or_assign_table_oid};
+use crate::storage::SharedStore;
+use crate::tprintln;
+
+pub struct PgIndex;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "indexrelid", coltype: ColType::Integer },
+    ColumnDef { name: "indrelid", coltype: ColType::Integer },
+    ColumnDef { name: "indisunique", coltype: ColType::Boolean },
+    ColumnDef { name: "indisprimary", coltype: ColType::Boolean },
+    // missing columns from reconciliation
+    ColumnDef { name: "indnatts", coltype: ColType::Integer },
+    ColumnDef { name: "indnkeyatts", coltype: ColType::Integer },
+    ColumnDef { name: "indisexclusion", coltype: ColType::Boolean },
+    ColumnDef { name: "indimmediate", coltype: ColType::Boolean },
+    ColumnDef { name: "indisclustered", coltype: ColType::Boolean },
+    ColumnDef { name: "indisvalid", coltype: ColType::Boolean },
+    ColumnDef { name: "indcheckxmin", coltype: ColType::Boolean },
+    ColumnDef { name: "indisready", coltype: ColType::Boolean },
+    ColumnDef { name: "indislive", coltype: ColType::Boolean },
+    ColumnDef { name: "indisreplident", coltype: ColType::Boolean },
+    ColumnDef { name: "indkey", coltype: ColType::Text },
+    ColumnDef { name: "indcollation", coltype: ColType::Text },
+    ColumnDef { name: "indclass", coltype: ColType::Text },
+    ColumnDef { name: "indoption", coltype: ColType::Text },
+    ColumnDef { name: "indexprs", coltype: ColType::Text },
+    ColumnDef { name: "indpred", coltype: ColType::Text },
+];
+
+impl SystemTable for PgIndex {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_index" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let metas = enumerate_tables(store);
+        let mut indexrelid: Vec<i32> = Vec::new();
+        let mut indrelid: Vec<i32> = Vec::new();
+        let mut indnatts: Vec<i32> = Vec::new();
+        let mut indkey: Vec<String> = Vec::new();
+
+        for x in enumerate_indexes(store).iter() {
+            // Indexes whose table was dropped are left out
+            let Some(m) = x.find_table(&metas) else { continue };
+            // indkey is an int2vector of the table's attnums, space separated
+            let keys: Vec<String> = x.columns.iter()
+                .map(|c| m.cols.iter().position(|(n, _)| n == c).map(|i| i + 1).unwrap_or(0).to_string())
+                .collect();
+            indexrelid.push(x.oid());
+            indrelid.push(get_or_assign_table_oid(&m.dir, &m.db, &m.schema, &m.table));
+            indnatts.push(x.columns.len() as i32);
+            indkey.push(keys.join(" "));
+        }
+
+        let rows = indexrelid.len();
+        tprintln!("[loader] pg_index built: rows={}", rows);
+        let falses: Vec<bool> = vec![false; rows];
+        let trues: Vec<bool> = vec![true; rows];
+        let zeros_vec: Vec<String> = indnatts.iter().map(|n| vec!["0"; *n as usize].join(" ")).collect();
+        let empty_txt: Vec<Option<String>> = vec![None; rows];
+
+        DataFrame::new(vec![
+            Series::new("indexrelid".into(), indexrelid).into(),
+            Series::new("indrelid".into(), indrelid).into(),
+            Series::new("indisunique".into(), falses.clone()).into(),
+            Series::new("indisprimary".into(), falses.clone()).into(),
+            Series::new("indnatts".into(), indnatts.clone()).into(),
+            Series::new("indnkeyatts".into(), indnatts).into(),
+            Series::new("indisexclusion".into(), falses.clone()).into(),
+            Series::new("indimmediate".into(), trues.clone()).into(),
+            Series::new("indisclustered".into(), falses.clone()).into(),
+            Series::new("indisvalid".into(), trues.clone()).into(),
+            Series::new("indcheckxmin".into(), falses.clone()).into(),
+            Series::new("indisready".into(), trues.clone()).into(),
+            Series::new("indislive".into(), trues).into(),
+            Series::new("indisreplident".into(), falses).into(),
+            Series::new("indkey".into(), indkey).into(),
+            Series::new("indcollation".into(), zeros_vec.clone()).into(),
+            Series::new("indclass".into(), zeros_vec.clone()).into(),
+            Series::new("indoption".into(), zeros_vec).into(),
+            Series::new("indexprs".into(), empty_txt.clone()).into(),
+            Series::new("indpred".into(), empty_txt).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgIndex)); }
@@ -18,9 +18,9 @@ impl SystemTable for PgNamespace {
     fn name(&self) -> &'static str { "pg_namespace" }
     fn columns(&self) -> &'static [ColumnDef] { COLS }
     fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
-        // Provide minimal pg_namespace with OIDs for pg_catalog, information_schema and public
-        let nspname: Vec<String> = vec!["pg_catalog".into(), "information_schema".into(), "public".into()];
-        let oid: Vec<i32> = vec![11, 13211, 2200];
+        // Provide minimal pg_namespace with OIDs for pg_catalog, information_schema, public and clarium_catalog
+        let nspname: Vec<String> = vec!["pg_catalog".into(), "information_schema".into(), "public".into(), crate::system_catalog::clarium_catalog::SCHEMA.into()];
+        let oid: Vec<i32> = vec![11, 13211, 2200, crate::system_catalog::clarium_catalog::NAMESPACE_OID];
         let nspowner: Vec<i32> = vec![10; oid.len()];
         let nspacl: Vec<Option<String>> = vec![None; oid.len()];
         DataFrame::new(vec![
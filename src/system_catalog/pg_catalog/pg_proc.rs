@@ -1,6 +1,7 @@
 use polars::prelude::{DataFrame, Series, NamedFrom};
 use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
 use crate::system_catalog::registry;
+use crate::system_catalog::shared;
 use crate::storage::SharedStore;
 
 pub struct PgProc;
@@ -43,40 +44,45 @@ impl SystemTable for PgProc {
     fn schema(&self) -> &'static str { "pg_catalog" }
     fn name(&self) -> &'static str { "pg_proc" }
     fn columns(&self) -> &'static [ColumnDef] { COLS }
-    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
-        // Empty by default; future work may populate from UDF registry
-        // Build an empty frame with all declared columns
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        // Stored procedures (prokind 'p'); functions are not listed yet
+        let root = store.root_path().to_string_lossy().to_string();
+        let procs = crate::server::procedures::list_procedures(&root).ok()?;
+        let n = procs.len();
+        let text = |v: &str| vec![v.to_string(); n];
+        let proname: Vec<String> = procs.iter().map(|p| p.name.rsplit('/').next().unwrap_or(&p.name).to_string()).collect();
         DataFrame::new(vec![
-            Series::new("oid".into(), Vec::<i32>::new()).into(),
-            Series::new("proname".into(), Vec::<String>::new()).into(),
-            Series::new("pronamespace".into(), Vec::<i32>::new()).into(),
-            Series::new("proowner".into(), Vec::<i32>::new()).into(),
-            Series::new("prolang".into(), Vec::<i32>::new()).into(),
-            Series::new("procost".into(), Vec::<String>::new()).into(),
-            Series::new("prorows".into(), Vec::<String>::new()).into(),
-            Series::new("provariadic".into(), Vec::<i32>::new()).into(),
-            Series::new("prosupport".into(), Vec::<i32>::new()).into(),
-            Series::new("prokind".into(), Vec::<String>::new()).into(),
-            Series::new("prosecdef".into(), Vec::<bool>::new()).into(),
-            Series::new("proleakproof".into(), Vec::<bool>::new()).into(),
-            Series::new("proisstrict".into(), Vec::<bool>::new()).into(),
-            Series::new("proretset".into(), Vec::<bool>::new()).into(),
-            Series::new("provolatile".into(), Vec::<String>::new()).into(),
-            Series::new("proparallel".into(), Vec::<String>::new()).into(),
-            Series::new("pronargs".into(), Vec::<i32>::new()).into(),
-            Series::new("pronargdefaults".into(), Vec::<i32>::new()).into(),
-            Series::new("prorettype".into(), Vec::<i32>::new()).into(),
-            Series::new("proargtypes".into(), Vec::<String>::new()).into(),
-            Series::new("proallargtypes".into(), Vec::<String>::new()).into(),
-            Series::new("proargmodes".into(), Vec::<String>::new()).into(),
-            Series::new("proargnames".into(), Vec::<String>::new()).into(),
-            Series::new("proargdefaults".into(), Vec::<String>::new()).into(),
-            Series::new("protrftypes".into(), Vec::<String>::new()).into(),
-            Series::new("prosrc".into(), Vec::<String>::new()).into(),
-            Series::new("probin".into(), Vec::<String>::new()).into(),
-            Series::new("prosqlbody".into(), Vec::<String>::new()).into(),
-            Series::new("proconfig".into(), Vec::<String>::new()).into(),
-            Series::new("proacl".into(), Vec::<String>::new()).into(),
+            Series::new("oid".into(), procs.iter().map(|p| shared::function_oid(&p.name)).collect::<Vec<i32>>()).into(),
+            Series::new("proname".into(), proname).into(),
+            Series::new("pronamespace".into(), vec![2200i32; n]).into(),
+            Series::new("proowner".into(), procs.iter().map(|p| shared::role_oid(&p.owner)).collect::<Vec<i32>>()).into(),
+            Series::new("prolang".into(), vec![14i32; n]).into(),
+            Series::new("procost".into(), text("100")).into(),
+            Series::new("prorows".into(), text("0")).into(),
+            Series::new("provariadic".into(), vec![0i32; n]).into(),
+            Series::new("prosupport".into(), vec![0i32; n]).into(),
+            Series::new("prokind".into(), text("p")).into(),
+            Series::new("prosecdef".into(), vec![false; n]).into(),
+            Series::new("proleakproof".into(), vec![false; n]).into(),
+            Series::new("proisstrict".into(), vec![false; n]).into(),
+            Series::new("proretset".into(), vec![false; n]).into(),
+            Series::new("provolatile".into(), text("v")).into(),
+            Series::new("proparallel".into(), text("u")).into(),
+            Series::new("pronargs".into(), procs.iter().map(|p| p.params.len() as i32).collect::<Vec<i32>>()).into(),
+            Series::new("pronargdefaults".into(), vec![0i32; n]).into(),
+            // void
+            Series::new("prorettype".into(), vec![2278i32; n]).into(),
+            Series::new("proargtypes".into(), text("")).into(),
+            Series::new("proallargtypes".into(), text("")).into(),
+            Series::new("proargmodes".into(), text("")).into(),
+            Series::new("proargnames".into(), procs.iter().map(|p| format!("{{{}}}", p.params.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(","))).collect::<Vec<String>>()).into(),
+            Series::new("proargdefaults".into(), text("")).into(),
+            Series::new("protrftypes".into(), text("")).into(),
+            Series::new("prosrc".into(), procs.iter().map(|p| p.body.clone()).collect::<Vec<String>>()).into(),
+            Series::new("probin".into(), text("")).into(),
+            Series::new("prosqlbody".into(), text("")).into(),
+            Series::new("proconfig".into(), text("")).into(),
+            Series::new("proacl".into(), text("")).into(),
         ]).ok()
     }
 }
@@ -0,0 +1,52 @@
+use polars::prelude::DataFrame;
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// Session configuration parameters from the GUC registry (see `crate::server::guc`).
+pub struct PgSettings;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "name", coltype: ColType::Text },
+    ColumnDef { name: "setting", coltype: ColType::Text },
+    ColumnDef { name: "unit", coltype: ColType::Text },
+    ColumnDef { name: "category", coltype: ColType::Text },
+    ColumnDef { name: "short_desc", coltype: ColType::Text },
+    ColumnDef { name: "context", coltype: ColType::Text },
+    ColumnDef { name: "vartype", coltype: ColType::Text },
+    ColumnDef { name: "source", coltype: ColType::Text },
+    ColumnDef { name: "min_val", coltype: ColType::Text },
+    ColumnDef { name: "max_val", coltype: ColType::Text },
+    ColumnDef { name: "enumvals", coltype: ColType::Text },
+    ColumnDef { name: "boot_val", coltype: ColType::Text },
+    ColumnDef { name: "reset_val", coltype: ColType::Text },
+    ColumnDef { name: "pending_restart", coltype: ColType::Boolean },
+];
+
+impl SystemTable for PgSettings {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_settings" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> { crate::server::guc::settings_df(store).ok() }
+}
+
+/// Per-database parameter defaults set with ALTER DATABASE ... SET.
+pub struct PgDbRoleSetting;
+
+const COLS_DB_ROLE_SETTING: &[ColumnDef] = &[
+    ColumnDef { name: "setdatabase", coltype: ColType::Integer },
+    ColumnDef { name: "setrole", coltype: ColType::Integer },
+    ColumnDef { name: "setconfig", coltype: ColType::Text },
+];
+
+impl SystemTable for PgDbRoleSetting {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_db_role_setting" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS_DB_ROLE_SETTING }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> { crate::server::guc::db_role_setting_df(store).ok() }
+}
+
+pub fn register() {
+    registry::register(Box::new(PgSettings));
+    registry::register(Box::new(PgDbRoleSetting));
+}
@@ -0,0 +1,45 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// One row per live HTTP session or pgwire connection, with its current or last statement.
+pub struct PgStatActivity;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "pid", coltype: ColType::Integer },
+    ColumnDef { name: "datname", coltype: ColType::Text },
+    ColumnDef { name: "usename", coltype: ColType::Text },
+    ColumnDef { name: "application_name", coltype: ColType::Text },
+    ColumnDef { name: "client_addr", coltype: ColType::Text },
+    ColumnDef { name: "backend_start", coltype: ColType::BigInt },
+    ColumnDef { name: "query_start", coltype: ColType::BigInt },
+    ColumnDef { name: "state_change", coltype: ColType::BigInt },
+    ColumnDef { name: "state", coltype: ColType::Text },
+    ColumnDef { name: "query", coltype: ColType::Text },
+    ColumnDef { name: "backend_type", coltype: ColType::Text },
+];
+
+impl SystemTable for PgStatActivity {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_stat_activity" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let rows = crate::server::activity::snapshot();
+        DataFrame::new(vec![
+            Series::new("pid".into(), rows.iter().map(|a| a.pid).collect::<Vec<i32>>()).into(),
+            Series::new("datname".into(), rows.iter().map(|a| a.database.clone()).collect::<Vec<_>>()).into(),
+            Series::new("usename".into(), rows.iter().map(|a| a.user.clone()).collect::<Vec<_>>()).into(),
+            Series::new("application_name".into(), rows.iter().map(|a| a.frontend.as_str().to_string()).collect::<Vec<_>>()).into(),
+            Series::new("client_addr".into(), rows.iter().map(|a| a.client_addr.clone()).collect::<Vec<_>>()).into(),
+            Series::new("backend_start".into(), rows.iter().map(|a| a.backend_start).collect::<Vec<i64>>()).into(),
+            Series::new("query_start".into(), rows.iter().map(|a| a.query_start).collect::<Vec<Option<i64>>>()).into(),
+            Series::new("state_change".into(), rows.iter().map(|a| a.state_change).collect::<Vec<i64>>()).into(),
+            Series::new("state".into(), rows.iter().map(|a| a.state.to_string()).collect::<Vec<_>>()).into(),
+            Series::new("query".into(), rows.iter().map(|a| a.query.clone()).collect::<Vec<_>>()).into(),
+            Series::new("backend_type".into(), vec!["client backend".to_string(); rows.len()]).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgStatActivity)); }
@@ -0,0 +1,62 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::pg_catalog::pg_database::{database_names, database_oid};
+use crate::storage::SharedStore;
+
+/// Per-database activity counters (see `server::db_stats`). Counters Clarium does not
+/// track (buffer hits, temp files, conflicts, ...) are reported as 0.
+pub struct PgStatDatabase;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "datid", coltype: ColType::Integer },
+    ColumnDef { name: "datname", coltype: ColType::Text },
+    ColumnDef { name: "numbackends", coltype: ColType::Integer },
+    ColumnDef { name: "xact_commit", coltype: ColType::BigInt },
+    ColumnDef { name: "xact_rollback", coltype: ColType::BigInt },
+    ColumnDef { name: "blks_read", coltype: ColType::BigInt },
+    ColumnDef { name: "blks_hit", coltype: ColType::BigInt },
+    ColumnDef { name: "tup_returned", coltype: ColType::BigInt },
+    ColumnDef { name: "tup_fetched", coltype: ColType::BigInt },
+    ColumnDef { name: "tup_inserted", coltype: ColType::BigInt },
+    ColumnDef { name: "tup_updated", coltype: ColType::BigInt },
+    ColumnDef { name: "tup_deleted", coltype: ColType::BigInt },
+    ColumnDef { name: "conflicts", coltype: ColType::BigInt },
+    ColumnDef { name: "temp_files", coltype: ColType::BigInt },
+    ColumnDef { name: "temp_bytes", coltype: ColType::BigInt },
+    ColumnDef { name: "deadlocks", coltype: ColType::BigInt },
+    ColumnDef { name: "stats_reset", coltype: ColType::BigInt },
+];
+
+impl SystemTable for PgStatDatabase {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_stat_database" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let names = database_names(store);
+        let backends = crate::server::activity::snapshot();
+        let stats: Vec<_> = names.iter().map(|n| crate::server::db_stats::get(n)).collect();
+        let zeros = || vec![0i64; names.len()];
+        DataFrame::new(vec![
+            Series::new("datid".into(), names.iter().map(|d| database_oid(d)).collect::<Vec<i32>>()).into(),
+            Series::new("datname".into(), names.clone()).into(),
+            Series::new("numbackends".into(), names.iter().map(|d| backends.iter().filter(|a| &a.database == d).count() as i32).collect::<Vec<i32>>()).into(),
+            Series::new("xact_commit".into(), stats.iter().map(|s| s.xact_commit).collect::<Vec<i64>>()).into(),
+            Series::new("xact_rollback".into(), stats.iter().map(|s| s.xact_rollback).collect::<Vec<i64>>()).into(),
+            Series::new("blks_read".into(), stats.iter().map(|s| s.blks_read).collect::<Vec<i64>>()).into(),
+            Series::new("blks_hit".into(), zeros()).into(),
+            Series::new("tup_returned".into(), stats.iter().map(|s| s.tup_returned).collect::<Vec<i64>>()).into(),
+            Series::new("tup_fetched".into(), zeros()).into(),
+            Series::new("tup_inserted".into(), zeros()).into(),
+            Series::new("tup_updated".into(), zeros()).into(),
+            Series::new("tup_deleted".into(), zeros()).into(),
+            Series::new("conflicts".into(), zeros()).into(),
+            Series::new("temp_files".into(), zeros()).into(),
+            Series::new("temp_bytes".into(), zeros()).into(),
+            Series::new("deadlocks".into(), zeros()).into(),
+            Series::new("stats_reset".into(), vec![crate::server::db_stats::stats_reset(); names.len()]).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgStatDatabase)); }
@@ -0,0 +1,66 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::enumerate_tables;
+use crate::storage::SharedStore;
+
+/// Per-table ingestion counters (see `storage::ingest_stats`).
+pub struct PgStatIngest;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "datname", coltype: ColType::Text },
+    ColumnDef { name: "schemaname", coltype: ColType::Text },
+    ColumnDef { name: "relname", coltype: ColType::Text },
+    ColumnDef { name: "duplicates_dropped", coltype: ColType::BigInt },
+    ColumnDef { name: "late_records", coltype: ColType::BigInt },
+    ColumnDef { name: "chunks_rewritten", coltype: ColType::BigInt },
+    ColumnDef { name: "chunks_dropped", coltype: ColType::BigInt },
+    ColumnDef { name: "rows_tombstoned", coltype: ColType::BigInt },
+    ColumnDef { name: "values_coerced", coltype: ColType::BigInt },
+    ColumnDef { name: "updated_at", coltype: ColType::BigInt },
+];
+
+impl SystemTable for PgStatIngest {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_stat_ingest" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut db: Vec<String> = Vec::new();
+        let mut schema: Vec<String> = Vec::new();
+        let mut rel: Vec<String> = Vec::new();
+        let mut dups: Vec<i64> = Vec::new();
+        let mut late: Vec<i64> = Vec::new();
+        let mut rewritten: Vec<i64> = Vec::new();
+        let mut dropped: Vec<i64> = Vec::new();
+        let mut tombstoned: Vec<i64> = Vec::new();
+        let mut coerced: Vec<i64> = Vec::new();
+        let mut updated: Vec<i64> = Vec::new();
+        for t in enumerate_tables(store) {
+            let st = crate::storage::ingest_stats::read_stats(&t.dir);
+            db.push(t.db);
+            schema.push(t.schema);
+            rel.push(t.table);
+            dups.push(st.duplicates_dropped as i64);
+            late.push(st.late_records as i64);
+            rewritten.push(st.chunks_rewritten as i64);
+            dropped.push(st.chunks_dropped as i64);
+            tombstoned.push(st.rows_tombstoned as i64);
+            coerced.push(st.values_coerced as i64);
+            updated.push(st.updated_at);
+        }
+        DataFrame::new(vec![
+            Series::new("datname".into(), db).into(),
+            Series::new("schemaname".into(), schema).into(),
+            Series::new("relname".into(), rel).into(),
+            Series::new("duplicates_dropped".into(), dups).into(),
+            Series::new("late_records".into(), late).into(),
+            Series::new("chunks_rewritten".into(), rewritten).into(),
+            Series::new("chunks_dropped".into(), dropped).into(),
+            Series::new("rows_tombstoned".into(), tombstoned).into(),
+            Series::new("values_coerced".into(), coerced).into(),
+            Series::new("updated_at".into(), updated).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgStatIngest)); }
@@ -0,0 +1,48 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+use xxhash_rust::xxh3::xxh3_64;
+
+/// One row per replica that has polled this primary's replication manifest.
+pub struct PgStatReplication;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "pid", coltype: ColType::Integer },
+    ColumnDef { name: "application_name", coltype: ColType::Text },
+    ColumnDef { name: "client_addr", coltype: ColType::Text },
+    ColumnDef { name: "state", coltype: ColType::Text },
+    ColumnDef { name: "sent_lsn", coltype: ColType::BigInt },
+    ColumnDef { name: "replay_lsn", coltype: ColType::BigInt },
+    ColumnDef { name: "replay_lag_ms", coltype: ColType::BigInt },
+    ColumnDef { name: "last_seen", coltype: ColType::BigInt },
+];
+
+impl SystemTable for PgStatReplication {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_stat_replication" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let peers = crate::server::replication::peers();
+        let now = chrono::Utc::now().timestamp_millis();
+        // A replica that has not polled for 30s is reported as disconnected
+        let state: Vec<String> = peers.iter().map(|p| {
+            if now - p.last_seen > 30_000 { "disconnected".to_string() }
+            else if p.replay_position >= p.sent_position { "streaming".to_string() }
+            else { "catchup".to_string() }
+        }).collect();
+        let pid: Vec<i32> = peers.iter().map(|p| (xxh3_64(p.id.as_bytes()) as u32 % 1_000_000) as i32).collect();
+        DataFrame::new(vec![
+            Series::new("pid".into(), pid).into(),
+            Series::new("application_name".into(), peers.iter().map(|p| p.id.clone()).collect::<Vec<_>>()).into(),
+            Series::new("client_addr".into(), peers.iter().map(|p| p.addr.clone()).collect::<Vec<_>>()).into(),
+            Series::new("state".into(), state).into(),
+            Series::new("sent_lsn".into(), peers.iter().map(|p| p.sent_position).collect::<Vec<i64>>()).into(),
+            Series::new("replay_lsn".into(), peers.iter().map(|p| p.replay_position).collect::<Vec<i64>>()).into(),
+            Series::new("replay_lag_ms".into(), peers.iter().map(|p| (p.sent_position - p.replay_position).max(0)).collect::<Vec<i64>>()).into(),
+            Series::new("last_seen".into(), peers.iter().map(|p| p.last_seen).collect::<Vec<i64>>()).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgStatReplication)); }
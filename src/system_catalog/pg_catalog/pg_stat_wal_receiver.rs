@@ -0,0 +1,41 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::storage::SharedStore;
+
+/// Replica-side receiver status; empty on a primary.
+pub struct PgStatWalReceiver;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "pid", coltype: ColType::Integer },
+    ColumnDef { name: "status", coltype: ColType::Text },
+    ColumnDef { name: "sender_host", coltype: ColType::Text },
+    ColumnDef { name: "received_lsn", coltype: ColType::BigInt },
+    ColumnDef { name: "latest_end_time", coltype: ColType::BigInt },
+    ColumnDef { name: "lag_ms", coltype: ColType::BigInt },
+    ColumnDef { name: "pending_files", coltype: ColType::BigInt },
+    ColumnDef { name: "last_error", coltype: ColType::Text },
+];
+
+impl SystemTable for PgStatWalReceiver {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_stat_wal_receiver" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
+        let Some(st) = crate::server::replication::receiver_status() else {
+            return Some(registry::build_empty(COLS));
+        };
+        DataFrame::new(vec![
+            Series::new("pid".into(), vec![std::process::id() as i32]).into(),
+            Series::new("status".into(), vec![st.status.clone()]).into(),
+            Series::new("sender_host".into(), vec![st.sender.clone()]).into(),
+            Series::new("received_lsn".into(), vec![st.position]).into(),
+            Series::new("latest_end_time".into(), vec![st.latest_end_time]).into(),
+            Series::new("lag_ms".into(), vec![st.lag_ms()]).into(),
+            Series::new("pending_files".into(), vec![st.pending_files]).into(),
+            Series::new("last_error".into(), vec![st.last_error.clone()]).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgStatWalReceiver)); }
@@ -0,0 +1,198 @@
+use polars::prelude::{Column, DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::{enumerate_tables, get_or_assign_table_oid};
+use crate::storage::analyze::{read_stats, ColumnStats};
+use crate::storage::SharedStore;
+
+/// Column statistics from the last ANALYZE of each table (see `storage::analyze`).
+pub struct PgStatistic;
+
+/// The readable form of `pg_statistic`, as in PostgreSQL's `pg_stats` view.
+pub struct PgStats;
+
+/// stakind of a histogram slot.
+const STATISTIC_KIND_HISTOGRAM: i32 = 2;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "starelid", coltype: ColType::Integer },
+    ColumnDef { name: "staattnum", coltype: ColType::Integer },
+    ColumnDef { name: "stainherit", coltype: ColType::Boolean },
+    ColumnDef { name: "stanullfrac", coltype: ColType::Text },
+    ColumnDef { name: "stawidth", coltype: ColType::Integer },
+    ColumnDef { name: "stadistinct", coltype: ColType::Text },
+    ColumnDef { name: "stakind1", coltype: ColType::Integer },
+    ColumnDef { name: "stakind2", coltype: ColType::Integer },
+    ColumnDef { name: "stakind3", coltype: ColType::Integer },
+    ColumnDef { name: "stakind4", coltype: ColType::Integer },
+    ColumnDef { name: "stakind5", coltype: ColType::Integer },
+    ColumnDef { name: "staop1", coltype: ColType::Integer },
+    ColumnDef { name: "staop2", coltype: ColType::Integer },
+    ColumnDef { name: "staop3", coltype: ColType::Integer },
+    ColumnDef { name: "staop4", coltype: ColType::Integer },
+    ColumnDef { name: "staop5", coltype: ColType::Integer },
+    ColumnDef { name: "stacoll1", coltype: ColType::Integer },
+    ColumnDef { name: "stacoll2", coltype: ColType::Integer },
+    ColumnDef { name: "stacoll3", coltype: ColType::Integer },
+    ColumnDef { name: "stacoll4", coltype: ColType::Integer },
+    ColumnDef { name: "stacoll5", coltype: ColType::Integer },
+    ColumnDef { name: "stanumbers1", coltype: ColType::Text },
+    ColumnDef { name: "stanumbers2", coltype: ColType::Text },
+    ColumnDef { name: "stanumbers3", coltype: ColType::Text },
+    ColumnDef { name: "stanumbers4", coltype: ColType::Text },
+    ColumnDef { name: "stanumbers5", coltype: ColType::Text },
+    ColumnDef { name: "stavalues1", coltype: ColType::Text },
+    ColumnDef { name: "stavalues2", coltype: ColType::Text },
+    ColumnDef { name: "stavalues3", coltype: ColType::Text },
+    ColumnDef { name: "stavalues4", coltype: ColType::Text },
+    ColumnDef { name: "stavalues5", coltype: ColType::Text },
+];
+
+const COLS_STATS: &[ColumnDef] = &[
+    ColumnDef { name: "schemaname", coltype: ColType::Text },
+    ColumnDef { name: "tablename", coltype: ColType::Text },
+    ColumnDef { name: "attname", coltype: ColType::Text },
+    ColumnDef { name: "inherited", coltype: ColType::Boolean },
+    ColumnDef { name: "null_frac", coltype: ColType::Double },
+    ColumnDef { name: "avg_width", coltype: ColType::Integer },
+    ColumnDef { name: "n_distinct", coltype: ColType::Double },
+    ColumnDef { name: "most_common_vals", coltype: ColType::Text },
+    ColumnDef { name: "most_common_freqs", coltype: ColType::Text },
+    ColumnDef { name: "histogram_bounds", coltype: ColType::Text },
+    ColumnDef { name: "correlation", coltype: ColType::Double },
+];
+
+/// One analyzed column: its table, attnum (pg_attribute order) and statistics.
+struct AnalyzedColumn {
+    relid: i32,
+    schema: String,
+    table: String,
+    attnum: i32,
+    name: String,
+    stats: ColumnStats,
+    rows: u64,
+}
+
+fn analyzed_columns(store: &SharedStore) -> Vec<AnalyzedColumn> {
+    let mut out = Vec::new();
+    for m in enumerate_tables(store) {
+        let Some(mut stats) = read_stats(&m.dir) else { continue };
+        let relid = get_or_assign_table_oid(&m.dir, &m.db, &m.schema, &m.table);
+        let mut attnum = 0i32;
+        for (cname, _) in m.cols.iter() {
+            if cname == "PRIMARY" { continue; }
+            attnum += 1;
+            let Some(cs) = stats.columns.remove(cname) else { continue };
+            out.push(AnalyzedColumn { relid, schema: m.schema.clone(), table: m.table.clone(), attnum, name: cname.clone(), stats: cs, rows: stats.rows });
+        }
+    }
+    out
+}
+
+/// PostgreSQL's stadistinct: a count, or minus the fraction of rows when the number of
+/// distinct values looks like it grows with the table.
+fn stadistinct(cs: &ColumnStats, rows: u64) -> f64 {
+    if rows > 0 && cs.n_distinct > 0.1 * rows as f64 { -(cs.n_distinct / rows as f64) } else { cs.n_distinct }
+}
+
+/// Text form of an array of statistic values, e.g. `{1,5,9}` or `{a,"b c"}`.
+fn pg_array(vals: &[serde_json::Value]) -> Option<String> {
+    if vals.is_empty() { return None; }
+    let items: Vec<String> = vals.iter().map(|v| match v {
+        serde_json::Value::String(s) => {
+            let plain = !s.is_empty() && !s.eq_ignore_ascii_case("null")
+                && !s.chars().any(|c| c.is_whitespace() || matches!(c, ',' | '"' | '\\' | '{' | '}'));
+            if plain { s.clone() } else { format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")) }
+        }
+        other => other.to_string(),
+    }).collect();
+    Some(format!("{{{}}}", items.join(",")))
+}
+
+impl SystemTable for PgStatistic {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_statistic" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut starelid: Vec<i32> = Vec::new();
+        let mut staattnum: Vec<i32> = Vec::new();
+        let mut nullfrac: Vec<String> = Vec::new();
+        let mut width: Vec<i32> = Vec::new();
+        let mut distinct: Vec<String> = Vec::new();
+        let mut kind1: Vec<i32> = Vec::new();
+        let mut values1: Vec<Option<String>> = Vec::new();
+        for c in analyzed_columns(store) {
+            starelid.push(c.relid);
+            staattnum.push(c.attnum);
+            nullfrac.push(c.stats.null_frac.to_string());
+            width.push(c.stats.avg_width);
+            distinct.push(stadistinct(&c.stats, c.rows).to_string());
+            let hist = pg_array(&c.stats.histogram);
+            kind1.push(if hist.is_some() { STATISTIC_KIND_HISTOGRAM } else { 0 });
+            values1.push(hist);
+        }
+        let rows = starelid.len();
+        let zeros: Vec<i32> = vec![0; rows];
+        let nulls: Vec<Option<String>> = vec![None; rows];
+        let mut cols: Vec<Column> = vec![
+            Series::new("starelid".into(), starelid).into(),
+            Series::new("staattnum".into(), staattnum).into(),
+            Series::new("stainherit".into(), vec![false; rows]).into(),
+            Series::new("stanullfrac".into(), nullfrac).into(),
+            Series::new("stawidth".into(), width).into(),
+            Series::new("stadistinct".into(), distinct).into(),
+            Series::new("stakind1".into(), kind1).into(),
+        ];
+        for n in 2..=5 { cols.push(Series::new(format!("stakind{}", n).into(), zeros.clone()).into()); }
+        for n in 1..=5 { cols.push(Series::new(format!("staop{}", n).into(), zeros.clone()).into()); }
+        for n in 1..=5 { cols.push(Series::new(format!("stacoll{}", n).into(), zeros.clone()).into()); }
+        for n in 1..=5 { cols.push(Series::new(format!("stanumbers{}", n).into(), nulls.clone()).into()); }
+        cols.push(Series::new("stavalues1".into(), values1).into());
+        for n in 2..=5 { cols.push(Series::new(format!("stavalues{}", n).into(), nulls.clone()).into()); }
+        DataFrame::new(cols).ok()
+    }
+}
+
+impl SystemTable for PgStats {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_stats" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS_STATS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut schemaname: Vec<String> = Vec::new();
+        let mut tablename: Vec<String> = Vec::new();
+        let mut attname: Vec<String> = Vec::new();
+        let mut null_frac: Vec<f64> = Vec::new();
+        let mut avg_width: Vec<i32> = Vec::new();
+        let mut n_distinct: Vec<f64> = Vec::new();
+        let mut histogram: Vec<Option<String>> = Vec::new();
+        for c in analyzed_columns(store) {
+            n_distinct.push(stadistinct(&c.stats, c.rows));
+            null_frac.push(c.stats.null_frac);
+            avg_width.push(c.stats.avg_width);
+            histogram.push(pg_array(&c.stats.histogram));
+            schemaname.push(c.schema);
+            tablename.push(c.table);
+            attname.push(c.name);
+        }
+        let rows = schemaname.len();
+        let nulls: Vec<Option<String>> = vec![None; rows];
+        DataFrame::new(vec![
+            Series::new("schemaname".into(), schemaname).into(),
+            Series::new("tablename".into(), tablename).into(),
+            Series::new("attname".into(), attname).into(),
+            Series::new("inherited".into(), vec![false; rows]).into(),
+            Series::new("null_frac".into(), null_frac).into(),
+            Series::new("avg_width".into(), avg_width).into(),
+            Series::new("n_distinct".into(), n_distinct).into(),
+            Series::new("most_common_vals".into(), nulls.clone()).into(),
+            Series::new("most_common_freqs".into(), nulls).into(),
+            Series::new("histogram_bounds".into(), histogram).into(),
+            Series::new("correlation".into(), vec![None::<f64>; rows]).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() {
+    registry::register(Box::new(PgStatistic));
+    registry::register(Box::new(PgStats));
+}
@@ -0,0 +1,95 @@
+use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::system_catalog::registry::{SystemTable, ColumnDef, ColType};
+use crate::system_catalog::registry;
+use crate::system_catalog::shared::{enumerate_tables, function_oid, get_or_assign_table_oid, stable_hash_u32};
+use crate::storage::cdc::ChangeOp;
+use crate::storage::SharedStore;
+use crate::tprintln;
+
+/// Lua table triggers (see `storage::triggers`). All are AFTER triggers fired per batch, so
+/// `tgtype` only carries the event bits.
+pub struct PgTrigger;
+
+// tgtype bits PostgreSQL uses for the trigger events
+const TGTYPE_INSERT: i32 = 1 << 2;
+const TGTYPE_DELETE: i32 = 1 << 3;
+const TGTYPE_UPDATE: i32 = 1 << 4;
+
+const COLS: &[ColumnDef] = &[
+    ColumnDef { name: "oid", coltype: ColType::Integer },
+    ColumnDef { name: "tgrelid", coltype: ColType::Integer },
+    ColumnDef { name: "tgname", coltype: ColType::Text },
+    ColumnDef { name: "tgenabled", coltype: ColType::Text },
+    ColumnDef { name: "tgparentid", coltype: ColType::Integer },
+    ColumnDef { name: "tgfoid", coltype: ColType::Integer },
+    ColumnDef { name: "tgtype", coltype: ColType::Integer },
+    ColumnDef { name: "tgisinternal", coltype: ColType::Boolean },
+    ColumnDef { name: "tgconstrrelid", coltype: ColType::Integer },
+    ColumnDef { name: "tgconstrindid", coltype: ColType::Integer },
+    ColumnDef { name: "tgconstraint", coltype: ColType::Integer },
+    ColumnDef { name: "tgdeferrable", coltype: ColType::Boolean },
+    ColumnDef { name: "tginitdeferred", coltype: ColType::Boolean },
+    ColumnDef { name: "tgnargs", coltype: ColType::Integer },
+    ColumnDef { name: "tgattr", coltype: ColType::Text },
+    ColumnDef { name: "tgargs", coltype: ColType::Text },
+    ColumnDef { name: "tgqual", coltype: ColType::Text },
+    ColumnDef { name: "tgoldtable", coltype: ColType::Text },
+    ColumnDef { name: "tgnewtable", coltype: ColType::Text },
+];
+
+impl SystemTable for PgTrigger {
+    fn schema(&self) -> &'static str { "pg_catalog" }
+    fn name(&self) -> &'static str { "pg_trigger" }
+    fn columns(&self) -> &'static [ColumnDef] { COLS }
+    fn build(&self, store: &SharedStore) -> Option<DataFrame> {
+        let mut oid: Vec<i32> = Vec::new();
+        let mut tgrelid: Vec<i32> = Vec::new();
+        let mut tgname: Vec<String> = Vec::new();
+        let mut tgfoid: Vec<i32> = Vec::new();
+        let mut tgtype: Vec<i32> = Vec::new();
+        for m in enumerate_tables(store).iter() {
+            let triggers = crate::storage::triggers::table_triggers(&m.dir);
+            if triggers.is_empty() { continue; }
+            let relid = get_or_assign_table_oid(&m.dir, &m.db, &m.schema, &m.table);
+            for t in triggers {
+                oid.push(27000 + (stable_hash_u32(&format!("trigger:{}/{}/{}/{}", m.db, m.schema, m.table, t.name)) % 1_000_000) as i32);
+                tgrelid.push(relid);
+                tgfoid.push(function_oid(&t.function));
+                tgtype.push(t.events.iter().map(|e| match e {
+                    ChangeOp::Insert => TGTYPE_INSERT,
+                    ChangeOp::Delete => TGTYPE_DELETE,
+                    ChangeOp::Update => TGTYPE_UPDATE,
+                }).fold(0, |a, b| a | b));
+                tgname.push(t.name);
+            }
+        }
+        let n = oid.len();
+        tprintln!("[loader] pg_trigger built: rows={}", n);
+        let zeros = || vec![0i32; n];
+        let falses = || vec![false; n];
+        let nulls = || vec![None::<String>; n];
+        DataFrame::new(vec![
+            Series::new("oid".into(), oid).into(),
+            Series::new("tgrelid".into(), tgrelid).into(),
+            Series::new("tgname".into(), tgname).into(),
+            Series::new("tgenabled".into(), vec!["O"; n]).into(),
+            Series::new("tgparentid".into(), zeros()).into(),
+            Series::new("tgfoid".into(), tgfoid).into(),
+            Series::new("tgtype".into(), tgtype).into(),
+            Series::new("tgisinternal".into(), falses()).into(),
+            Series::new("tgconstrrelid".into(), zeros()).into(),
+            Series::new("tgconstrindid".into(), zeros()).into(),
+            Series::new("tgconstraint".into(), zeros()).into(),
+            Series::new("tgdeferrable".into(), falses()).into(),
+            Series::new("tginitdeferred".into(), falses()).into(),
+            Series::new("tgnargs".into(), zeros()).into(),
+            Series::new("tgattr".into(), vec![""; n]).into(),
+            Series::new("tgargs".into(), vec![""; n]).into(),
+            Series::new("tgqual".into(), nulls()).into(),
+            Series::new("tgoldtable".into(), nulls()).into(),
+            Series::new("tgnewtable".into(), nulls()).into(),
+        ]).ok()
+    }
+}
+
+pub fn register() { registry::register(Box::new(PgTrigger)); }
@@ -48,11 +48,11 @@ impl SystemTable for PgType {
     fn build(&self, _store: &SharedStore) -> Option<DataFrame> {
         // Mirror existing minimal pg_type setup from legacy implementation
         let names: Vec<String> = vec![
-            "int4".into(), "int8".into(), "float8".into(), "text".into(), "bool".into(),
+            "int4".into(), "int8".into(), "float8".into(), "numeric".into(), "text".into(), "bool".into(),
             "timestamp".into(), "timestamptz".into(), "hstore".into(), "vector".into(),
         ];
-        let oids: Vec<i32> = vec![23, 20, 701, 25, 16, 1114, 1184, 16414, 70400];
-        let arrays: Vec<i32> = vec![1007, 1016, 1022, 1009, 1000, 1115, 1185, 16415, 70401];
+        let oids: Vec<i32> = vec![23, 20, 701, 1700, 25, 16, 1114, 1184, 16414, 70400];
+        let arrays: Vec<i32> = vec![1007, 1016, 1022, 1231, 1009, 1000, 1115, 1185, 16415, 70401];
         let pg_catalog_oid: i32 = 11;
         let typnamespace: Vec<i32> = vec![pg_catalog_oid; names.len()];
         let typelem: Vec<i32> = vec![0; names.len()];
@@ -63,6 +63,7 @@ impl SystemTable for PgType {
             "N".into(), // int4 numeric
             "N".into(), // int8 numeric
             "N".into(), // float8 numeric
+            "N".into(), // numeric numeric
             "S".into(), // text string
             "B".into(), // bool boolean
             "D".into(), // timestamp datetime
@@ -1,4 +1,6 @@
 use polars::prelude::{DataFrame, Series, NamedFrom};
+use crate::storage::SharedStore;
+use crate::system_catalog::shared::role_oid;
 
 // Shared builder for role rows used by pg_roles and pg_authid
 pub struct RoleRows {
@@ -35,8 +37,39 @@ impl RoleRows {
     }
 }
 
-// For now, synthesize two core roles and map RBAC-like options onto rol* flags.
-// Future: extend from persisted RBAC once available.
+impl RoleRows {
+    fn push(&mut self, oid: i32, name: &str, superuser: bool, inherit: bool, can_login: bool) {
+        self.oid.push(oid);
+        self.rolname.push(name.to_string());
+        self.rolsuper.push(superuser);
+        self.rolinherit.push(inherit);
+        self.rolcreaterole.push(superuser);
+        self.rolcreatedb.push(superuser);
+        self.rolcanlogin.push(can_login);
+        self.rolreplication.push(false);
+        self.rolbypassrls.push(superuser);
+        self.rolconnlimit.push(-1);
+        self.rolpassword.push(String::new());
+        self.rolvaliduntil.push(String::new());
+    }
+}
+
+/// Built-in roles, then the users of user.parquet (can log in; superuser when admin) and the
+/// roles created with CREATE ROLE.
+pub fn role_rows(store: &SharedStore) -> RoleRows {
+    let mut rows = synthesize_core_roles();
+    let root = store.root_path().to_string_lossy().to_string();
+    for (user, is_admin) in crate::security::list_users(&root).unwrap_or_default() {
+        if rows.rolname.iter().any(|r| r.eq_ignore_ascii_case(&user)) { continue; }
+        rows.push(role_oid(&user), &user, is_admin, true, true);
+    }
+    for role in crate::security::role_catalog(&root).unwrap_or_default().roles {
+        rows.push(role_oid(&role.name), &role.name, role.superuser, role.inherit, false);
+    }
+    rows
+}
+
+// The two core roles, with RBAC-like options mapped onto rol* flags.
 pub fn synthesize_core_roles() -> RoleRows {
     // Stable OIDs for built-ins within this engine's lifetime
     let mut oid: Vec<i32> = Vec::new();
@@ -10,6 +10,7 @@ pub enum ColType {
     Integer,
     BigInt,
     Boolean,
+    Double,
     Text,
 }
 
@@ -54,6 +55,7 @@ pub fn build_empty(cols: &[ColumnDef]) -> DataFrame {
             ColType::Integer => series.push(Series::new(c.name.into(), Vec::<i32>::new())),
             ColType::BigInt => series.push(Series::new(c.name.into(), Vec::<i64>::new())),
             ColType::Boolean => series.push(Series::new(c.name.into(), Vec::<bool>::new())),
+            ColType::Double => series.push(Series::new(c.name.into(), Vec::<f64>::new())),
             ColType::Text => series.push(Series::new(c.name.into(), Vec::<String>::new())),
         }
     }
@@ -79,6 +81,7 @@ pub fn ensure_registered() {
     // Call default registrar
     super::pg_catalog::register_defaults();
     super::information_schema::register_defaults();
+    super::clarium_catalog::register_defaults();
 }
 
 pub fn all() -> Vec<Arc<dyn SystemTable>> {
@@ -105,6 +108,7 @@ fn coltype_to_dtype(ct: ColType) -> DataType {
         ColType::Integer => DataType::Int64,
         ColType::BigInt => DataType::Int64,
         ColType::Boolean => DataType::Boolean,
+        ColType::Double => DataType::Float64,
         ColType::Text => DataType::String,
     }
 }
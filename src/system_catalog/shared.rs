@@ -15,6 +15,10 @@ fn read_json(path: &Path) -> Option<serde_json::Value> {
         .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
 }
 
+fn str_array(v: Option<&serde_json::Value>) -> Vec<String> {
+    v.and_then(|x| x.as_array()).map(|a| a.iter().filter_map(|e| e.as_str().map(|s| s.to_string())).collect()).unwrap_or_default()
+}
+
 fn write_json(path: &Path, val: &serde_json::Value) {
     if let Ok(text) = serde_json::to_string_pretty(val) {
         let _ = std::fs::write(path, text);
@@ -44,19 +48,72 @@ pub struct TableMeta {
     pub table: String,
     pub cols: Vec<(String, String)>,
     pub has_primary_marker: bool,
+    /// Columns from schema.json `primaryKey` (empty when only the legacy PRIMARY marker is set)
+    pub primary_key: Vec<String>,
     pub dir: PathBuf,
     pub constraints: Vec<ConstraintMeta>,
 }
 
+impl TableMeta {
+    /// Table name as presented to clients (time tables without their `.time` suffix).
+    pub fn display_name(&self) -> &str { self.table.strip_suffix(".time").unwrap_or(&self.table) }
+
+    /// Primary key columns: schema.json `primaryKey`, or for a bare PRIMARY marker the first
+    /// id-like column (else the first non-`_time` column).
+    pub fn primary_key_columns(&self) -> Vec<String> {
+        if !self.primary_key.is_empty() { return self.primary_key.clone(); }
+        if !self.has_primary_marker { return Vec::new(); }
+        let candidates = || self.cols.iter().map(|(n, _)| n).filter(|n| n.as_str() != "_time");
+        candidates().find(|n| n.as_str() == "id" || n.as_str() == "record_id" || n.ends_with("_id"))
+            .or_else(|| candidates().next())
+            .map(|n| vec![n.clone()])
+            .unwrap_or_default()
+    }
+
+    pub fn primary_key_name(&self) -> String { format!("{}_pkey", self.display_name()) }
+
+    /// Name of a metadata constraint; unnamed ones get `<table>_<contype>_constr`.
+    pub fn constraint_name(&self, c: &ConstraintMeta) -> String {
+        if !c.name.is_empty() { return c.name.clone(); }
+        format!("{}_{}_constr", self.display_name(), c.contype())
+    }
+}
+
+/// The key a foreign key references: the referenced table's primary key, or its unique
+/// constraint over the same columns. Returns the referenced table, key name and key columns.
+pub fn referenced_key<'a>(metas: &'a [TableMeta], m: &TableMeta, fk: &ConstraintMeta) -> Option<(&'a TableMeta, String, Vec<String>)> {
+    let ref_schema = fk.ref_schema.as_deref().unwrap_or(&m.schema);
+    let ref_table = fk.ref_table.as_deref()?;
+    let rm = metas.iter().find(|t| t.db == m.db && t.schema == ref_schema && (t.table == ref_table || t.display_name() == ref_table))?;
+    let pk = rm.primary_key_columns();
+    let same_cols = |cols: &[String]| fk.ref_columns.is_empty() || (cols.len() == fk.ref_columns.len() && fk.ref_columns.iter().all(|c| cols.contains(c)));
+    if !pk.is_empty() && same_cols(&pk) { return Some((rm, rm.primary_key_name(), pk)); }
+    rm.constraints.iter()
+        .find(|u| u.contype() == 'u' && !fk.ref_columns.is_empty() && same_cols(&u.columns))
+        .map(|u| (rm, rm.constraint_name(u), u.columns.clone()))
+}
+
+impl ConstraintMeta {
+    /// pg_constraint.contype letter: u(nique), f(oreign key), c(heck) or (e)x(clusion).
+    pub fn contype(&self) -> char {
+        match self.ctype.to_ascii_lowercase().as_str() {
+            "foreign_key" | "fk" => 'f',
+            "check" => 'c',
+            "exclusion" => 'x',
+            _ => 'u',
+        }
+    }
+}
+
 /// Enumerate user tables on disk by scanning the store root for schema.json files.
 pub fn enumerate_tables(store: &SharedStore) -> Vec<TableMeta> {
     let mut out: Vec<TableMeta> = Vec::new();
     let root = store.root_path();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path();
             if !db_path.is_dir() { continue; }
-            let dbname = match db_path.file_name().and_then(|s| s.to_str()) {
+            let dbname = match db_ent.file_name().to_str() {
                 Some(n) => n.to_string(),
                 None => continue,
             };
@@ -75,22 +132,27 @@ pub fn enumerate_tables(store: &SharedStore) -> Vec<TableMeta> {
                                     let tname = tentry.file_name().to_string_lossy().to_string();
                                     let mut cols: Vec<(String, String)> = Vec::new();
                                     let mut has_primary_marker = false;
-                                    if let Ok(text) = std::fs::read_to_string(&sj) {
-                                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
-                                            if let serde_json::Value::Object(obj) = json {
-                                                for (k, v) in obj.into_iter() {
-                                                    if k == "PRIMARY" { has_primary_marker = true; }
-                                                    if let serde_json::Value::String(s) = v { cols.push((k, s)); }
-                                                    else if let serde_json::Value::Object(m) = v {
-                                                        if let Some(serde_json::Value::String(t)) = m.get("type") { cols.push((k, t.clone())); }
-                                                    }
-                                                }
+                                    let mut primary_key: Vec<String> = Vec::new();
+                                    let mut constraints: Vec<ConstraintMeta> = Vec::new();
+                                    if let Some(serde_json::Value::Object(obj)) = read_json(&sj) {
+                                        has_primary_marker = obj.contains_key("PRIMARY");
+                                        primary_key = str_array(obj.get("primaryKey"));
+                                        // v2 schemas nest column types under "columns"; flat ones mix them with metadata keys
+                                        let entries = obj.get("columns").and_then(|c| c.as_object()).unwrap_or(&obj);
+                                        for (k, v) in entries.iter() {
+                                            if crate::storage::migrate::is_meta_key(k) { continue; }
+                                            if let serde_json::Value::String(s) = v { cols.push((k.clone(), s.clone())); }
+                                            else if let Some(serde_json::Value::String(t)) = v.get("type") { cols.push((k.clone(), t.clone())); }
+                                        }
+                                        // UDF check constraints added by ALTER TABLE ... ADD CONSTRAINT <name> USING <udf>
+                                        for c in obj.get("constraints").and_then(|x| x.as_array()).into_iter().flatten() {
+                                            if let (Some(name), Some(udf)) = (c.get("name").and_then(|x| x.as_str()), c.get("udf").and_then(|x| x.as_str())) {
+                                                constraints.push(ConstraintMeta { name: name.to_string(), ctype: "check".into(), columns: Vec::new(), ref_schema: None, ref_table: None, ref_columns: Vec::new(), on_update: None, on_delete: None, match_type: None, check_expr: Some(format!("{}(*)", udf)), excl_operators: Vec::new() });
                                             }
                                         }
                                     }
                                     if !cols.iter().any(|(n, _)| n == "_time") { cols.insert(0, ("_time".into(), "int64".into())); }
                                     // optional constraints.json alongside schema.json
-                                    let mut constraints: Vec<ConstraintMeta> = Vec::new();
                                     let cj = tp.join("constraints.json");
                                     if cj.exists() {
                                         if let Some(serde_json::Value::Array(arr)) = read_json(&cj) {
@@ -98,21 +160,21 @@ pub fn enumerate_tables(store: &SharedStore) -> Vec<TableMeta> {
                                                 if let Some(obj) = v.as_object() {
                                                     let name = obj.get("name").and_then(|x| x.as_str()).unwrap_or("").to_string();
                                                     let ctype = obj.get("type").and_then(|x| x.as_str()).unwrap_or("").to_string();
-                                                    let columns: Vec<String> = obj.get("columns").and_then(|x| x.as_array()).map(|a| a.iter().filter_map(|e| e.as_str().map(|s| s.to_string())).collect()).unwrap_or_default();
+                                                    let columns = str_array(obj.get("columns"));
                                                     let ref_schema = obj.get("ref_schema").and_then(|x| x.as_str()).map(|s| s.to_string());
                                                     let ref_table = obj.get("ref_table").and_then(|x| x.as_str()).map(|s| s.to_string());
-                                                    let ref_columns: Vec<String> = obj.get("ref_columns").and_then(|x| x.as_array()).map(|a| a.iter().filter_map(|e| e.as_str().map(|s| s.to_string())).collect()).unwrap_or_default();
+                                                    let ref_columns = str_array(obj.get("ref_columns"));
                                                     let on_update = obj.get("on_update").and_then(|x| x.as_str()).map(|s| s.to_string());
                                                     let on_delete = obj.get("on_delete").and_then(|x| x.as_str()).map(|s| s.to_string());
                                                     let match_type = obj.get("match").and_then(|x| x.as_str()).map(|s| s.to_string());
                                                     let check_expr = obj.get("expression").and_then(|x| x.as_str()).map(|s| s.to_string());
-                                                    let excl_operators: Vec<String> = obj.get("operators").and_then(|x| x.as_array()).map(|a| a.iter().filter_map(|e| e.as_str().map(|s| s.to_string())).collect()).unwrap_or_default();
+                                                    let excl_operators = str_array(obj.get("operators"));
                                                     constraints.push(ConstraintMeta { name, ctype, columns, ref_schema, ref_table, ref_columns, on_update, on_delete, match_type, check_expr, excl_operators });
                                                 }
                                             }
                                         }
                                     }
-                                    out.push(TableMeta { db: dbname.clone(), schema: schema_name.clone(), table: tname, cols, has_primary_marker, dir: tp.clone(), constraints });
+                                    out.push(TableMeta { db: dbname.clone(), schema: schema_name.clone(), table: tname, cols, has_primary_marker, primary_key, dir: tp.clone(), constraints });
                                 }
                             }
                         }
@@ -130,16 +192,18 @@ pub struct ViewMeta {
     pub schema: String,
     pub view: String,
     pub def_sql: String,
+    /// `COMMENT ON VIEW` text stored in the `.view` file
+    pub comment: Option<String>,
     pub file: PathBuf,
 }
 
 pub fn enumerate_views(store: &SharedStore) -> Vec<ViewMeta> {
     let mut out: Vec<ViewMeta> = Vec::new();
     let root = store.root_path();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
-            let dbname = match db_path.file_name().and_then(|s| s.to_str()) { Some(n) => n.to_string(), None => continue };
+            let dbname = match db_ent.file_name().to_str() { Some(n) => n.to_string(), None => continue };
             if dbname.starts_with('.') { continue; }
             if let Ok(schemas) = std::fs::read_dir(&db_path) {
                 for sch_ent in schemas.flatten() {
@@ -154,10 +218,12 @@ pub fn enumerate_views(store: &SharedStore) -> Vec<ViewMeta> {
                                     if ext.eq_ignore_ascii_case("view") {
                                         let vname = p.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_string();
                                         let mut def = String::new();
+                                        let mut comment = None;
                                         if let Some(json) = read_json(&p) {
                                             if let Some(s) = json.get("definition_sql").and_then(|v| v.as_str()) { def = s.to_string(); }
+                                            comment = json.get("comment").and_then(|v| v.as_str()).map(|s| s.to_string());
                                         }
-                                        out.push(ViewMeta { db: dbname.clone(), schema: schema_name.clone(), view: vname, def_sql: def, file: p.clone() });
+                                        out.push(ViewMeta { db: dbname.clone(), schema: schema_name.clone(), view: vname, def_sql: def, comment, file: p.clone() });
                                     }
                                 }
                             }
@@ -179,12 +245,16 @@ pub struct SidecarMeta {
 }
 
 pub fn enumerate_vector_indexes(store: &SharedStore) -> Vec<SidecarMeta> {
+    enumerate_vector_indexes_in(&store.root_path())
+}
+
+/// [`enumerate_vector_indexes`] for the store rooted at `root`, for callers holding the store.
+pub fn enumerate_vector_indexes_in(root: &Path) -> Vec<SidecarMeta> {
     let mut out: Vec<SidecarMeta> = Vec::new();
-    let root = store.root_path();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
-            let dbname = match db_path.file_name().and_then(|s| s.to_str()) { Some(n) => n.to_string(), None => continue };
+            let dbname = match db_ent.file_name().to_str() { Some(n) => n.to_string(), None => continue };
             if let Ok(schemas) = std::fs::read_dir(&db_path) {
                 for sch_ent in schemas.flatten() {
                     let sch_path = sch_ent.path(); if !sch_path.is_dir() { continue; }
@@ -205,6 +275,41 @@ pub fn enumerate_vector_indexes(store: &SharedStore) -> Vec<SidecarMeta> {
     out
 }
 
+/// A secondary index over a table, as exposed through pg_index / pg_class (relkind 'i').
+/// Vector indexes (`.vindex` sidecars) are the only kind today.
+#[derive(Debug, Clone)]
+pub struct IndexMeta {
+    pub db: String,
+    pub schema: String,
+    pub name: String,
+    pub file: PathBuf,
+    /// Indexed table as `db/schema/table`
+    pub table: String,
+    pub columns: Vec<String>,
+    /// Access method (`hnsw`)
+    pub method: String,
+}
+
+impl IndexMeta {
+    pub fn oid(&self) -> i32 { get_or_assign_vindex_oid(&self.file, &self.db, &self.schema, &self.name) }
+
+    /// The indexed table among `metas`, if it still exists.
+    pub fn find_table<'a>(&self, metas: &'a [TableMeta]) -> Option<&'a TableMeta> {
+        let target = self.table.strip_suffix(".time").unwrap_or(&self.table);
+        metas.iter().find(|m| format!("{}/{}/{}", m.db, m.schema, m.display_name()) == target)
+    }
+}
+
+pub fn enumerate_indexes(store: &SharedStore) -> Vec<IndexMeta> {
+    enumerate_vector_indexes(store).into_iter().filter_map(|s| {
+        let json = read_json(&s.file)?;
+        let table = json.get("table").and_then(|v| v.as_str())?.replace('\\', "/");
+        let column = json.get("column").and_then(|v| v.as_str())?.to_string();
+        let method = json.get("algo").and_then(|v| v.as_str()).unwrap_or("hnsw").to_ascii_lowercase();
+        Some(IndexMeta { db: s.db, schema: s.schema, name: s.name, file: s.file, table, columns: vec![column], method })
+    }).collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct GraphTxnCtx {
     pub graph: String,
@@ -216,10 +321,10 @@ pub struct GraphTxnCtx {
 pub fn enumerate_graphs(store: &SharedStore) -> Vec<SidecarMeta> {
     let mut out: Vec<SidecarMeta> = Vec::new();
     let root = store.root_path();
-    if let Ok(dbs) = std::fs::read_dir(&root) {
+    if let Ok(dbs) = crate::storage::attach::read_database_dirs(&root) {
         for db_ent in dbs.flatten() {
             let db_path = db_ent.path(); if !db_path.is_dir() { continue; }
-            let dbname = match db_path.file_name().and_then(|s| s.to_str()) { Some(n) => n.to_string(), None => continue };
+            let dbname = match db_ent.file_name().to_str() { Some(n) => n.to_string(), None => continue };
             if let Ok(schemas) = std::fs::read_dir(&db_path) {
                 for sch_ent in schemas.flatten() {
                     let sch_path = sch_ent.path(); if !sch_path.is_dir() { continue; }
@@ -295,6 +400,22 @@ pub fn get_or_assign_view_oid(view_file: &Path, db: &str, schema: &str, view: &s
     default_oid
 }
 
+/// Stable OID for a Lua function, derived from its registered name (functions have no
+/// metadata file to persist one in).
+pub fn function_oid(name: &str) -> i32 {
+    25000 + (stable_hash_u32(&format!("func:{}", name.to_ascii_lowercase())) % 1_000_000) as i32
+}
+
+/// Stable OID for a user or role, derived from its name. The built-in `postgres` and `public`
+/// roles keep 10 and 11.
+pub fn role_oid(name: &str) -> i32 {
+    match name.to_ascii_lowercase().as_str() {
+        "postgres" => 10,
+        "public" => 11,
+        n => 26000 + (stable_hash_u32(&format!("role:{}", n)) % 1_000_000) as i32,
+    }
+}
+
 /// Obtain a stable OID for a vector index, persisted inside the `.vindex` JSON file
 pub fn get_or_assign_vindex_oid(vindex_file: &Path, db: &str, schema: &str, name: &str) -> i32 {
     // Reserve a separate range for vector indexes to avoid collision
@@ -0,0 +1,130 @@
+//!
+//! clarium tracing setup
+//! ---------------------
+//! Installs the global `tracing` subscriber for the server.
+//!
+//! Logs go to stdout through `tracing_subscriber::fmt`, filtered by `RUST_LOG`
+//! (falling back to the directives passed by the binary). Every statement runs inside
+//! a `query` span with `parse`, `plan`, `scan`, `join` and `aggregate` children carrying
+//! `table`, `rows` and `chunks` attributes. These spans use target [`QUERY_SPAN_TARGET`]
+//! at DEBUG level, so they stay out of the default log output.
+//!
+//! With the `otel` feature, the query spans are also exported over OTLP/HTTP when an
+//! endpoint is configured:
+//!
+//! - `CLARIUM_OTLP_ENDPOINT` (or `OTEL_EXPORTER_OTLP_ENDPOINT`): collector base URL,
+//!   e.g. `http://localhost:4318`
+//! - `OTEL_SERVICE_NAME`: service name reported to the collector (default `clarium`)
+//! - `CLARIUM_OTLP_FILTER`: filter directives for exported spans
+//!   (default `clarium::query=debug`)
+
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Target of the per-query span tree.
+pub const QUERY_SPAN_TARGET: &str = "clarium::query";
+
+fn env_value(keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|k| std::env::var(k).ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Configured OTLP collector endpoint, if any.
+pub fn otlp_endpoint() -> Option<String> {
+    env_value(&["CLARIUM_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_ENDPOINT"])
+}
+
+/// Install the global subscriber, using `default_directives` when `RUST_LOG` is unset.
+/// Call once at startup, from within the Tokio runtime (the OTLP batch exporter runs on it).
+/// Does nothing if a subscriber is already installed.
+pub fn init_tracing(default_directives: &str) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(default_directives))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry().with(fmt::layer().with_filter(filter));
+
+    #[cfg(feature = "otel")]
+    {
+        let (layer, status) = match otlp_endpoint() {
+            Some(endpoint) => match otel::layer(&endpoint) {
+                Ok(layer) => (Some(layer), format!("exporting query spans to {}", endpoint)),
+                Err(e) => (None, format!("OTLP export disabled: {}", e)),
+            },
+            None => (None, String::new()),
+        };
+        if registry.with(layer).try_init().is_err() { return; }
+        if !status.is_empty() {
+            tracing::info!(target: "clarium", "{}", status);
+        }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        if registry.try_init().is_err() { return; }
+        if otlp_endpoint().is_some() {
+            tracing::warn!(target: "clarium", "OTLP endpoint configured but clarium was built without the `otel` feature; spans are not exported");
+        }
+    }
+}
+
+/// Flush pending spans and stop the exporter. No-op when nothing is exported.
+pub fn shutdown_tracing() {
+    #[cfg(feature = "otel")]
+    otel::shutdown();
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use anyhow::Result;
+    use once_cell::sync::OnceCell;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::Resource;
+    use tracing::Subscriber;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::{EnvFilter, Layer};
+
+    static PROVIDER: OnceCell<TracerProvider> = OnceCell::new();
+
+    fn traces_url(endpoint: &str) -> String {
+        let base = endpoint.trim_end_matches('/');
+        if base.ends_with("/v1/traces") { base.to_string() } else { format!("{}/v1/traces", base) }
+    }
+
+    pub(super) fn layer<S>(endpoint: &str) -> Result<Box<dyn Layer<S> + Send + Sync>>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(traces_url(endpoint))
+            .build()?;
+        let service = super::env_value(&["OTEL_SERVICE_NAME"]).unwrap_or_else(|| "clarium".to_string());
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+            .with_resource(Resource::new(vec![
+                KeyValue::new("service.name", service),
+                KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+            ]))
+            .build();
+        let tracer = provider.tracer("clarium");
+        opentelemetry::global::set_tracer_provider(provider.clone());
+        let _ = PROVIDER.set(provider);
+        let directives = super::env_value(&["CLARIUM_OTLP_FILTER"])
+            .unwrap_or_else(|| format!("{}=debug", super::QUERY_SPAN_TARGET));
+        let filter = EnvFilter::try_new(directives)?;
+        Ok(tracing_opentelemetry::layer().with_tracer(tracer).with_filter(filter).boxed())
+    }
+
+    pub(super) fn shutdown() {
+        if let Some(p) = PROVIDER.get() {
+            if let Err(e) = p.shutdown() {
+                eprintln!("OTLP exporter shutdown failed: {}", e);
+            }
+        }
+    }
+}
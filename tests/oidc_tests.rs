@@ -0,0 +1,139 @@
+//! OIDC bearer-token validation: signature, issuer/audience/expiry checks and claim mapping.
+//! Keys are generated locally and handed to the provider as a JWKS document (no network).
+
+use base64::Engine;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde_json::json;
+
+use clarium::config::OidcSettings;
+use clarium::identity::{AuthProvider, LoginRequest, OidcAuthProvider, SessionManager};
+
+const ISSUER: &str = "https://login.example.com/realms/corp";
+
+fn b64(bytes: &[u8]) -> String { base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes) }
+
+fn keypair() -> (EcdsaKeyPair, String) {
+    let rng = SystemRandom::new();
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+    let kp = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+    let point = kp.public_key().as_ref().to_vec();
+    let jwks = json!({"keys": [{"kty": "EC", "crv": "P-256", "kid": "k1", "alg": "ES256", "use": "sig", "x": b64(&point[1..33]), "y": b64(&point[33..65])}]});
+    (kp, jwks.to_string())
+}
+
+fn sign(kp: &EcdsaKeyPair, claims: serde_json::Value) -> String {
+    let header = b64(json!({"alg": "ES256", "kid": "k1", "typ": "JWT"}).to_string().as_bytes());
+    let payload = b64(claims.to_string().as_bytes());
+    let message = format!("{}.{}", header, payload);
+    let sig = kp.sign(&SystemRandom::new(), message.as_bytes()).unwrap();
+    format!("{}.{}", message, b64(sig.as_ref()))
+}
+
+fn provider(jwks: &str) -> OidcAuthProvider {
+    let tmp = tempfile::tempdir().unwrap();
+    let settings = OidcSettings { issuer: ISSUER.into(), audience: "clarium".into(), roles_claim: "realm_access.roles".into(), ..Default::default() };
+    let p = OidcAuthProvider::new(settings, tmp.path().to_string_lossy().to_string(), SessionManager::default());
+    p.set_jwks(jwks).unwrap();
+    p
+}
+
+fn claims(exp_offset: i64) -> serde_json::Value {
+    let now = chrono::Utc::now().timestamp();
+    json!({
+        "iss": ISSUER, "aud": ["account", "clarium"], "sub": "f3c1", "preferred_username": "alice",
+        "exp": now + exp_offset, "iat": now, "tid": "acme",
+        "realm_access": {"roles": ["analyst", "admin"]},
+    })
+}
+
+#[tokio::test]
+async fn valid_token_maps_claims_to_principal() {
+    let (kp, jwks) = keypair();
+    let oidc = provider(&jwks);
+    let p = oidc.authenticate(&sign(&kp, claims(300)), None).await.unwrap();
+    // The user id is the issuer's `sub`, not the user-editable preferred_username
+    assert_eq!(p.user_id, "f3c1");
+    assert_eq!(p.roles, vec!["user", "analyst", "admin"]);
+    assert_eq!(p.attrs.tenant_id.as_deref(), Some("acme"));
+
+    // The same token exchanges for a session through the AuthProvider interface
+    let req = LoginRequest { username: "alice".into(), password: sign(&kp, claims(300)), db: None, ip: None };
+    assert_eq!(oidc.login(&req).unwrap().session.principal.user_id, "f3c1");
+}
+
+#[tokio::test]
+async fn admin_comes_only_from_admin_roles() {
+    let (kp, jwks) = keypair();
+    let tmp = tempfile::tempdir().unwrap();
+    let settings = OidcSettings { issuer: ISSUER.into(), audience: "clarium".into(), roles_claim: "realm_access.roles".into(), admin_roles: "clarium-admins".into(), ..Default::default() };
+    let oidc = OidcAuthProvider::new(settings, tmp.path().to_string_lossy().to_string(), SessionManager::default());
+    oidc.set_jwks(&jwks).unwrap();
+
+    // Role claims named like Clarium's built-in roles are dropped
+    let mut c = claims(300);
+    c["realm_access"]["roles"] = json!(["analyst", "admin", "db_writer"]);
+    let p = oidc.authenticate(&sign(&kp, c), None).await.unwrap();
+    assert_eq!(p.roles, vec!["user", "analyst"]);
+    assert!(!p.is_admin());
+
+    let mut c = claims(300);
+    c["realm_access"]["roles"] = json!(["clarium-admins"]);
+    let p = oidc.authenticate(&sign(&kp, c), None).await.unwrap();
+    assert_eq!(p.roles, vec!["user", "clarium-admins", "admin"]);
+    assert!(p.is_admin());
+}
+
+#[tokio::test]
+async fn local_grants_need_an_explicit_mapping() {
+    let (kp, jwks) = keypair();
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    clarium::security::create_role(&root, "alice", false, true).unwrap();
+    clarium::security::create_role(&root, "auditors", false, true).unwrap();
+    clarium::security::grant_roles(&root, &["auditors".to_string()], &["alice".to_string()], false, None).unwrap();
+    let mut c = claims(300);
+    c["realm_access"]["roles"] = json!([]);
+    let token = sign(&kp, c);
+
+    // A token whose name matches the local user gets none of its grants
+    let settings = OidcSettings { issuer: ISSUER.into(), audience: "clarium".into(), roles_claim: "realm_access.roles".into(), username_claim: "preferred_username".into(), ..Default::default() };
+    let oidc = OidcAuthProvider::new(settings.clone(), root.clone(), SessionManager::default());
+    oidc.set_jwks(&jwks).unwrap();
+    let p = oidc.authenticate(&token, None).await.unwrap();
+    assert_eq!(p.user_id, "alice");
+    assert_eq!(p.roles, vec!["user"]);
+
+    // Mapping the token user to the local user brings them in
+    let settings = OidcSettings { local_users: "alice=alice".into(), ..settings };
+    let oidc = OidcAuthProvider::new(settings, root, SessionManager::default());
+    oidc.set_jwks(&jwks).unwrap();
+    let p = oidc.authenticate(&token, None).await.unwrap();
+    assert_eq!(p.roles, vec!["user", "auditors"]);
+}
+
+#[test]
+fn rejects_bad_tokens() {
+    let (kp, jwks) = keypair();
+    let oidc = provider(&jwks);
+    assert!(oidc.validate_cached(&sign(&kp, claims(300))).is_ok());
+    // expired beyond the leeway
+    assert!(oidc.validate_cached(&sign(&kp, claims(-600))).is_err());
+    // wrong audience / issuer
+    let mut c = claims(300); c["aud"] = json!("other");
+    assert!(oidc.validate_cached(&sign(&kp, c)).is_err());
+    let mut c = claims(300); c["iss"] = json!("https://evil.example.com");
+    assert!(oidc.validate_cached(&sign(&kp, c)).is_err());
+    // tampered payload
+    let token = sign(&kp, claims(300));
+    let parts: Vec<&str> = token.split('.').collect();
+    let mut c = claims(300); c["preferred_username"] = json!("mallory");
+    let forged = format!("{}.{}.{}", parts[0], b64(c.to_string().as_bytes()), parts[2]);
+    assert!(oidc.validate_cached(&forged).is_err());
+    // signed by a key the issuer did not publish
+    let (other, _) = keypair();
+    assert!(oidc.validate_cached(&sign(&other, claims(300))).is_err());
+    // unsigned tokens
+    let none = format!("{}.{}.", b64(json!({"alg": "none"}).to_string().as_bytes()), b64(claims(300).to_string().as_bytes()));
+    assert!(oidc.validate_cached(&none).is_err());
+}
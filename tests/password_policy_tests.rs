@@ -0,0 +1,58 @@
+//! Local password handling: lockout after failed logins, rotation status on the principal and
+//! verification of both hash formats. Runs with the default `[password]` settings.
+
+use clarium::identity::{AuthProvider, LocalAuthProvider, LoginRequest, SessionManager};
+use clarium::security::{self, Perms, Scope};
+
+fn login(p: &LocalAuthProvider, user: &str, password: &str) -> anyhow::Result<clarium::identity::LoginResponse> {
+    p.login(&LoginRequest { username: user.into(), password: password.into(), db: None, ip: None })
+}
+
+#[test]
+fn failed_logins_lock_the_account_until_unlocked() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    security::add_user(&root, Scope::Global, "lock_me", "Corr3ct!", Perms::default()).unwrap();
+    let p = LocalAuthProvider::new(root.clone(), SessionManager::default());
+
+    // lockout_threshold = 5
+    for _ in 0..5 { assert!(login(&p, "lock_me", "nope").is_err()); }
+    let err = login(&p, "lock_me", "Corr3ct!").unwrap_err();
+    assert!(err.to_string().contains("locked"), "{}", err);
+
+    assert!(clarium::identity::unlock_account("lock_me"));
+    let resp = login(&p, "lock_me", "Corr3ct!").unwrap();
+    let status = resp.session.principal.password.unwrap();
+    assert!(status.changed_at.is_some());
+    assert_eq!(status.expires_at, None);
+    assert!(!status.must_change);
+}
+
+#[test]
+fn expired_password_is_flagged_until_changed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_string_lossy().to_string();
+    security::add_user(&root, Scope::Global, "rotate_me", "first", Perms::default()).unwrap();
+    let p = LocalAuthProvider::new(root.clone(), SessionManager::default());
+
+    security::expire_password(&root, Scope::Global, "rotate_me").unwrap();
+    assert!(login(&p, "rotate_me", "first").unwrap().session.principal.password.unwrap().must_change);
+
+    security::alter_user(&root, Scope::Global, "rotate_me", Some("second"), None, None).unwrap();
+    assert!(!login(&p, "rotate_me", "second").unwrap().session.principal.password.unwrap().must_change);
+    assert!(security::expire_password(&root, Scope::Global, "nobody").is_err());
+}
+
+#[test]
+fn verifies_argon2_and_bcrypt_hashes() {
+    let argon = clarium::identity::hash_password("s3cret").unwrap();
+    assert!(argon.starts_with("$argon2id$"));
+    assert!(clarium::identity::verify_password(&argon, "s3cret"));
+    assert!(!clarium::identity::verify_password(&argon, "other"));
+
+    let bcrypt = bcrypt::hash("s3cret", 4).unwrap();
+    assert!(clarium::identity::verify_password(&bcrypt, "s3cret"));
+    assert!(!clarium::identity::verify_password(&bcrypt, "other"));
+    // Empty rules accept anything
+    assert!(clarium::identity::check_password_policy("x").is_ok());
+}